@@ -0,0 +1,167 @@
+//! The index of papers already processed into an output directory
+//!
+//! `save_results` records each saved paper in `<output_dir>/processed.json`
+//! under its arXiv id and a hash of the settings that shape the output
+//! (models, languages, prompts, stage switches). `process_paper` and
+//! `process_papers` return the saved result of a paper recorded with the
+//! current settings instead of processing it again, unless forced.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::PaperProcessingResult;
+use crate::config::PaperAssistantConfig;
+
+/// The index's file name in the output directory
+pub const PROCESSED_INDEX_FILE: &str = "processed.json";
+
+/// One saved paper
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessedEntry {
+  pub paper_id: String,
+  pub config_hash: String,
+  /// The saved `*_complete_results.json`
+  pub result_path: String,
+  /// When the paper was processed
+  pub processed_at: String,
+  /// When the result was saved
+  pub saved_at: String,
+}
+
+/// `processed.json`: entries by `<arxiv id>@<config hash>`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessedIndex {
+  pub papers: BTreeMap<String, ProcessedEntry>,
+}
+
+impl ProcessedIndex {
+  fn path(output_dir: &str) -> PathBuf {
+    Path::new(output_dir).join(PROCESSED_INDEX_FILE)
+  }
+
+  /// The index of `output_dir`; empty when there is none yet
+  pub fn load(output_dir: &str) -> Result<Self> {
+    let path = Self::path(output_dir);
+    match std::fs::read_to_string(&path) {
+      Ok(content) => serde_json::from_str(&content)
+        .with_context(|| format!("Invalid processed index {}", path.display())),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+  }
+
+  pub fn save(&self, output_dir: &str) -> Result<()> {
+    std::fs::create_dir_all(output_dir)
+      .with_context(|| format!("Failed to create {}", output_dir))?;
+    let path = Self::path(output_dir);
+    std::fs::write(&path, serde_json::to_string_pretty(self)?)
+      .with_context(|| format!("Failed to write {}", path.display()))
+  }
+
+  /// Record `result`, saved at `result_path`, as processed with
+  /// `config_hash`
+  pub fn record(&mut self, result: &PaperProcessingResult, config_hash: &str, result_path: &str) {
+    self.papers.insert(
+      entry_key(&result.original_url, config_hash),
+      ProcessedEntry {
+        paper_id: result.paper_id.clone(),
+        config_hash: config_hash.to_string(),
+        result_path: result_path.to_string(),
+        processed_at: result.timestamp.clone(),
+        saved_at: chrono::Utc::now().to_rfc3339(),
+      },
+    );
+  }
+
+  /// The saved result of `arxiv_url` processed with `config_hash`, if it
+  /// is recorded and its file still reads back
+  pub fn cached_result(&self, arxiv_url: &str, config_hash: &str) -> Option<PaperProcessingResult> {
+    let entry = self.papers.get(&entry_key(arxiv_url, config_hash))?;
+    let loaded = std::fs::read_to_string(&entry.result_path)
+      .map_err(anyhow::Error::from)
+      .and_then(|content| Ok(serde_json::from_str::<PaperProcessingResult>(&content)?));
+    match loaded {
+      Ok(mut result) => {
+        result.from_cache = true;
+        Some(result)
+      }
+      Err(e) => {
+        log::warn!("Ignoring cached result {}: {}", entry.result_path, e);
+        None
+      }
+    }
+  }
+}
+
+fn entry_key(arxiv_url: &str, config_hash: &str) -> String {
+  format!("{}@{}", crate::utils::arxiv_id(arxiv_url), config_hash)
+}
+
+/// A hash of the settings that change what processing a paper produces.
+/// Where the output goes, timeouts and caching do not count.
+pub fn config_hash(config: &PaperAssistantConfig) -> String {
+  let languages: Vec<_> = config
+    .languages
+    .iter()
+    .map(|language| {
+      (
+        language,
+        config.summary_prompt(language),
+        config.translation_prompt(language),
+      )
+    })
+    .collect();
+  let settings = serde_json::json!({
+    "stage_models": config.resolved_stage_models(),
+    "languages": languages,
+    "section_prompt": config.section_prompt(),
+    "poster_prompt": config.poster_prompt(),
+    "temperature": config.temperature,
+    "max_tokens": config.max_tokens,
+    "translation_chunk_chars": config.translation_chunk_chars,
+    "glossary": config.glossary,
+    "enable_mind_maps": config.enable_mind_maps,
+    "enable_poster_generation": config.enable_poster_generation,
+    "poster_style": config.poster_style,
+    "poster_size": config.poster_size,
+    "max_sections_for_mind_maps": config.max_sections_for_mind_maps,
+    "extract_latex_files": config.extract_latex_files,
+    "expand_latex_content": config.expand_latex_content,
+  });
+  let digest = Sha256::digest(settings.to_string().as_bytes());
+  digest[..8]
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Stage;
+
+  #[test]
+  fn test_config_hash_tracks_output_settings() {
+    let config = PaperAssistantConfig::default();
+    let hash = config_hash(&config);
+    assert_eq!(hash.len(), 16);
+
+    let mut moved = config.clone();
+    moved.output_directory = "./elsewhere".to_string();
+    moved.arxiv_timeout_seconds = Some(5);
+    assert_eq!(config_hash(&moved), hash);
+
+    let mut languages = config.clone();
+    languages.languages = vec!["en".to_string()];
+    assert_ne!(config_hash(&languages), hash);
+
+    let mut model = config.clone();
+    model
+      .stage_models
+      .set(Stage::Translation, "claude-3-5-sonnet");
+    assert_ne!(config_hash(&model), hash);
+  }
+}
@@ -0,0 +1,112 @@
+//! Stage outputs saved as the pipeline runs
+//!
+//! Each completed stage's shared-state entry is written to
+//! `<output_dir>/stage_outputs/<key>.json`, so a run that fails late (say
+//! at poster generation) keeps its summary and translation, and
+//! `PaperAssistant::resume` can load them back and run only what is
+//! missing.
+
+use agentflow_core::SharedState;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The shared-state key recording which paper a checkpoint belongs to
+pub const CHECKPOINT_URL_KEY: &str = "arxiv_url";
+
+/// A directory of saved stage outputs
+#[derive(Debug, Clone)]
+pub struct StageCheckpoint {
+  dir: PathBuf,
+}
+
+impl StageCheckpoint {
+  /// The checkpoint of the run writing to `output_dir`
+  pub fn new(output_dir: impl AsRef<Path>) -> Self {
+    Self {
+      dir: output_dir.as_ref().join("stage_outputs"),
+    }
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// Save one shared-state entry
+  pub fn save(&self, key: &str, value: &Value) -> Result<()> {
+    std::fs::create_dir_all(&self.dir)
+      .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+    let path = self.dir.join(format!("{}.json", key));
+    std::fs::write(&path, serde_json::to_string_pretty(value)?)
+      .with_context(|| format!("Failed to save stage output {}", path.display()))?;
+    Ok(())
+  }
+
+  /// Load every saved entry into `shared_state` and return their keys,
+  /// sorted. A missing directory loads nothing.
+  pub fn load_into(&self, shared_state: &SharedState) -> Result<Vec<String>> {
+    let entries = match std::fs::read_dir(&self.dir) {
+      Ok(entries) => entries,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(e) => return Err(e).with_context(|| format!("Failed to read {}", self.dir.display())),
+    };
+
+    let mut keys = Vec::new();
+    for entry in entries {
+      let path = entry?.path();
+      if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+        continue;
+      }
+      let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        continue;
+      };
+      let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read stage output {}", path.display()))?;
+      let value: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid stage output {}", path.display()))?;
+      shared_state.insert(key.to_string(), value);
+      keys.push(key.to_string());
+    }
+    keys.sort();
+
+    Ok(keys)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_saved_outputs_load_back() {
+    let output_dir = std::env::temp_dir().join(format!(
+      "paper_assistant_checkpoint_{}",
+      uuid::Uuid::new_v4()
+    ));
+    let checkpoint = StageCheckpoint::new(&output_dir);
+
+    let empty = SharedState::new();
+    assert!(checkpoint.load_into(&empty).unwrap().is_empty());
+
+    checkpoint
+      .save("summary_output_zh", &json!({ "response": "摘要" }))
+      .unwrap();
+    checkpoint
+      .save(CHECKPOINT_URL_KEY, &json!("2312.07104"))
+      .unwrap();
+
+    let shared_state = SharedState::new();
+    let keys = checkpoint.load_into(&shared_state).unwrap();
+    assert_eq!(
+      keys,
+      vec!["arxiv_url".to_string(), "summary_output_zh".to_string()]
+    );
+    assert_eq!(
+      shared_state.get("summary_output_zh").unwrap()["response"],
+      "摘要"
+    );
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+}
@@ -3,8 +3,79 @@
 //! This module defines configuration structures and default values for the
 //! paper processing workflow.
 
-use serde::{Deserialize, Serialize};
+use crate::poster::{PosterStyle, parse_poster_size};
+use agentflow_agents::eval::PricingTable;
+use agentflow_agents::{CacheControl, StageCache, StageMetrics};
+use agentflow_core::value::FlowValue;
+use agentflow_llm::ModelRegistry;
+use agentflow_nodes::common::template::{RenderOptions, render};
 use anyhow;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A pipeline stage that calls a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+  Summary,
+  Translation,
+  /// Section extraction, which the mind maps are drawn from
+  MindMap,
+  Poster,
+}
+
+impl Stage {
+  pub const ALL: [Stage; 4] = [
+    Stage::Summary,
+    Stage::Translation,
+    Stage::MindMap,
+    Stage::Poster,
+  ];
+
+  /// The stage's key in `stage_models` and `stage_prompts`
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Stage::Summary => "summary",
+      Stage::Translation => "translation",
+      Stage::MindMap => "mind_map",
+      Stage::Poster => "poster",
+    }
+  }
+}
+
+/// One optional setting per [`Stage`]; an unset stage keeps the default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StageOverrides {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub summary: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub translation: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mind_map: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub poster: Option<String>,
+}
+
+impl StageOverrides {
+  pub fn get(&self, stage: Stage) -> Option<&str> {
+    match stage {
+      Stage::Summary => self.summary.as_deref(),
+      Stage::Translation => self.translation.as_deref(),
+      Stage::MindMap => self.mind_map.as_deref(),
+      Stage::Poster => self.poster.as_deref(),
+    }
+  }
+
+  pub fn set(&mut self, stage: Stage, value: impl Into<String>) {
+    let slot = match stage {
+      Stage::Summary => &mut self.summary,
+      Stage::Translation => &mut self.translation,
+      Stage::MindMap => &mut self.mind_map,
+      Stage::Poster => &mut self.poster,
+    };
+    *slot = Some(value.into());
+  }
+}
 
 /// Configuration for Paper Assistant workflow
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,30 +83,107 @@ pub struct PaperAssistantConfig {
   // Model configurations
   pub qwen_turbo_model: String,
   pub qwen_image_model: String,
-  
+  /// Models for individual stages, in place of `qwen_turbo_model` (or
+  /// `qwen_image_model` for the poster). Each must be in the model
+  /// registry.
+  #[serde(default)]
+  pub stage_models: StageOverrides,
+
   // LLM parameters
   pub temperature: Option<f32>,
   pub max_tokens: Option<u32>,
-  
+
   // Output configuration
   pub output_directory: String,
   pub save_intermediate_files: bool,
-  
+
+  /// Languages to summarize and translate into, e.g. `["en", "zh"]`.
+  /// The first is the primary language: mind maps and the poster use it.
+  #[serde(default = "default_languages")]
+  pub languages: Vec<String>,
+
   // Prompt templates
   pub chinese_summary_prompt: String,
   pub chinese_translation_prompt: String,
+  /// Summary prompts for other languages, by language code; see
+  /// [`Self::summary_prompt`].
+  #[serde(default)]
+  pub summary_prompts: HashMap<String, String>,
+  /// Translation prompts for other languages, by language code; see
+  /// [`Self::translation_prompt`].
+  #[serde(default)]
+  pub translation_prompts: HashMap<String, String>,
+  /// `{{language}}` is replaced by the primary language's name.
   pub section_extraction_prompt: String,
   pub poster_generation_prompt: String,
-  
+  /// Prompt templates for individual stages, replacing the ones above.
+  /// Placeholders: `{{paper_content}}` and `{{language}}` (the language's
+  /// name) for `summary`, `translation` and `mind_map`; those of
+  /// [`crate::poster::PosterPromptBuilder`] for `poster`. A `mind_map`
+  /// prompt must keep the `## 章节` / `### 内容摘要` output format the
+  /// sections are parsed from.
+  /// `summary_prompts` and `translation_prompts` still win for their
+  /// language.
+  #[serde(default)]
+  pub stage_prompts: StageOverrides,
+
+  /// Longest piece of the paper sent in one translation request, in
+  /// characters; the paper is split between paragraphs. `None` sends the
+  /// whole paper at once.
+  #[serde(default)]
+  pub translation_chunk_chars: Option<usize>,
+
+  /// Terms every translation must render exactly as given, e.g.
+  /// "attention" → "注意力". Each chunk's prompt lists them, and the
+  /// chunks that translate one differently are reported.
+  #[serde(default)]
+  pub glossary: BTreeMap<String, String>,
+
   // Processing options
   pub enable_mind_maps: bool,
   pub enable_poster_generation: bool,
+  /// The poster's style preset
+  #[serde(default)]
+  pub poster_style: PosterStyle,
+  /// The poster's size, `WIDTHxHEIGHT`; it also sets the aspect the
+  /// prompt asks for
+  #[serde(default = "default_poster_size")]
+  pub poster_size: String,
   pub max_sections_for_mind_maps: Option<usize>,
-  
+  /// How many sections' mind maps are drawn at once
+  #[serde(default = "default_mind_map_concurrency")]
+  pub mind_map_concurrency: usize,
+
   // ArXiv processing options
   pub extract_latex_files: bool,
   pub expand_latex_content: bool,
   pub arxiv_timeout_seconds: Option<u64>,
+  /// Where fetched papers are cached (default `~/.agentflow/cache/arxiv`).
+  /// Every paper of a batch shares it.
+  #[serde(default)]
+  pub arxiv_cache_dir: Option<String>,
+  /// Look the paper up on Semantic Scholar for its citation count,
+  /// fields of study and related papers, shown in the summary header and
+  /// the results
+  #[serde(default)]
+  pub enable_enrichment: bool,
+  /// Where Semantic Scholar lookups are cached (default
+  /// `~/.agentflow/cache/semantic_scholar`)
+  #[serde(default)]
+  pub enrichment_cache_dir: Option<String>,
+
+  /// Whether summaries and translations are read from and saved to the
+  /// stage cache, which reuses them while the paper, model and prompt are
+  /// unchanged
+  #[serde(default)]
+  pub cache: CacheControl,
+  /// Where the stage cache is kept (default `~/.agentflow/cache/agents`)
+  #[serde(default)]
+  pub cache_dir: Option<String>,
+  /// A pricing table (`pricing.yml`) that prices each stage's tokens in
+  /// the stage metrics; without one no cost is reported
+  #[serde(default)]
+  pub pricing_file: Option<String>,
 }
 
 impl Default for PaperAssistantConfig {
@@ -43,16 +191,19 @@ impl Default for PaperAssistantConfig {
     Self {
       // Use Qwen models (DashScope API)
       qwen_turbo_model: "qwen-turbo".to_string(),
-      qwen_image_model: "qwen-vl-plus".to_string(), // Use VL model for image generation
-      
+      qwen_image_model: "qwen-image".to_string(), // DashScope image synthesis
+      stage_models: StageOverrides::default(),
+
       // LLM parameters optimized for Chinese output
       temperature: Some(0.3),
       max_tokens: Some(4000),
-      
+
       // Output configuration
       output_directory: "./paper_assistant_output".to_string(),
       save_intermediate_files: true,
-      
+
+      languages: default_languages(),
+
       // Chinese summary prompt
       chinese_summary_prompt: r#"请仔细阅读以下学术论文内容，并生成一个详细的中文摘要。摘要应该包括：
 
@@ -81,88 +232,296 @@ impl Default for PaperAssistantConfig {
 
 请提供完整的中文翻译："#.to_string(),
 
+      summary_prompts: HashMap::new(),
+      translation_prompts: HashMap::new(),
+
       // Section extraction prompt
       section_extraction_prompt: r#"请分析以下学术论文内容，提取出主要章节的结构和内容。对于每个章节，请提供：
 
-1. 章节标题（中文翻译）
+1. 章节标题（{{language}}翻译）
 2. 章节编号（如果有）
-3. 章节主要内容摘要（中文，约200字）
+3. 章节主要内容摘要（{{language}}，约200字）
 
 论文内容：
 {{paper_content}}
 
-请按照以下格式输出每个章节：
+请按照以下格式输出每个章节（保留“章节”和“内容摘要”这两个标记）：
 
-## 章节 [编号]：[中文标题]
+## 章节 [编号]：[{{language}}标题]
 ### 内容摘要
-[章节内容的中文摘要]
+[章节内容的{{language}}摘要]
 
 ---"#.to_string(),
 
       // Poster generation prompt
-      poster_generation_prompt: r#"Create an academic research poster design based on this Chinese research summary. Design requirements:
+      poster_generation_prompt: r#"Create a research poster for this paper.
 
 Title: {{paper_title}}
-Summary: {{chinese_summary}}
+Field: {{domain}}
+Key findings:
+{{key_findings}}
+
+Layout: a {{aspect}} poster with the title at the top and one panel per key finding, each with a visual that supports it.
+Style: {{style}}"#.to_string(),
 
-Design a professional academic poster with:
-1. Clear, readable layout with the paper title at the top
-2. Main research highlights and key findings prominently displayed
-3. Clean, modern academic design with appropriate color scheme
-4. Visual elements that support the research content
-5. Professional typography suitable for academic presentation
+      stage_prompts: StageOverrides::default(),
 
-Style: Clean, modern academic poster design"#.to_string(),
+      translation_chunk_chars: Some(12000),
+      glossary: BTreeMap::new(),
 
       // Processing options
       enable_mind_maps: true,
       enable_poster_generation: true,
+      poster_style: PosterStyle::default(),
+      poster_size: default_poster_size(),
       max_sections_for_mind_maps: Some(10),
-      
+      mind_map_concurrency: default_mind_map_concurrency(),
+
       // ArXiv options
       extract_latex_files: true,
       expand_latex_content: true,
       arxiv_timeout_seconds: Some(120),
+      arxiv_cache_dir: None,
+      enable_enrichment: false,
+      enrichment_cache_dir: None,
+
+      cache: CacheControl::default(),
+      cache_dir: None,
+      pricing_file: None,
     }
   }
 }
 
+/// The summary prompt for a language with no built-in or configured one.
+/// `{{language}}` is replaced by the language's name.
+const SUMMARY_PROMPT_TEMPLATE: &str = r#"Read the following academic paper carefully and write a detailed summary in {{language}}. The summary should cover:
+
+1. Research background and motivation
+2. Main methods
+3. Key contributions
+4. Experimental results and findings
+5. Conclusions and significance
+
+Paper content:
+{{paper_content}}
+
+Write a precise, professional summary in {{language}} (about 500-800 words):"#;
+
+/// The translation prompt for a language with no built-in or configured
+/// one. `{{language}}` is replaced by the language's name.
+const TRANSLATION_PROMPT_TEMPLATE: &str = r#"Translate the following academic paper into {{language}}. Requirements:
+
+1. Keep the paper's technical precision
+2. Preserve the paragraph structure and formatting
+3. Use the established {{language}} terms for technical vocabulary
+4. Keep the logic clear and the language fluent
+5. For important terms, keep the original term in parentheses after the translation
+
+Original text:
+{{paper_content}}
+
+Complete {{language}} translation:"#;
+
+fn default_languages() -> Vec<String> {
+  vec!["zh".to_string()]
+}
+
+fn default_mind_map_concurrency() -> usize {
+  3
+}
+
+/// qwen-image's square size
+fn default_poster_size() -> String {
+  "1328x1328".to_string()
+}
+
+/// `template` with the placeholders named in `values` filled in one pass by
+/// the nodes' lenient renderer (`{{ name }}` spacing, `\{{` escapes); any
+/// other placeholder stays as written for a later stage to fill.
+pub fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+  let inputs = values
+    .iter()
+    .map(|(name, value)| (name.to_string(), FlowValue::Json(serde_json::json!(value))))
+    .collect();
+  render(template, &inputs, &RenderOptions::lenient()).unwrap_or_else(|_| template.to_string())
+}
+
+/// The name of a language code as written in that language, for prompts;
+/// an unknown code is used as it is.
+pub fn language_name(code: &str) -> &str {
+  match code {
+    "zh" => "中文",
+    "en" => "English",
+    "ja" => "日本語",
+    "ko" => "한국어",
+    "fr" => "Français",
+    "de" => "Deutsch",
+    "es" => "Español",
+    "ru" => "Русский",
+    _ => code,
+  }
+}
+
 impl PaperAssistantConfig {
+  /// The language mind maps and the poster use: the first of `languages`
+  pub fn primary_language(&self) -> &str {
+    self.languages.first().map(|s| s.as_str()).unwrap_or("zh")
+  }
+
+  /// The model `stage` runs on
+  pub fn stage_model(&self, stage: Stage) -> &str {
+    match (self.stage_models.get(stage), stage) {
+      (Some(model), _) => model,
+      (None, Stage::Poster) => &self.qwen_image_model,
+      (None, _) => &self.qwen_turbo_model,
+    }
+  }
+
+  /// The model of every stage, by stage name
+  pub fn resolved_stage_models(&self) -> BTreeMap<String, String> {
+    Stage::ALL
+      .iter()
+      .map(|stage| {
+        (
+          stage.as_str().to_string(),
+          self.stage_model(*stage).to_string(),
+        )
+      })
+      .collect()
+  }
+
+  /// Check that every model set in `stage_models` is in `registry`
+  pub fn check_stage_models(&self, registry: &ModelRegistry) -> Result<(), String> {
+    for stage in Stage::ALL {
+      if let Some(model) = self.stage_models.get(stage)
+        && !registry.has_model(model)
+      {
+        return Err(format!(
+          "stage_models.{}: model '{}' is not in the model registry",
+          stage.as_str(),
+          model
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// The summary prompt for `language`: its entry in `summary_prompts`,
+  /// else `stage_prompts.summary`, else `chinese_summary_prompt` for `zh`,
+  /// else a generic prompt asking for that language.
+  pub fn summary_prompt(&self, language: &str) -> String {
+    match (
+      self.summary_prompts.get(language),
+      self.stage_prompts.get(Stage::Summary),
+    ) {
+      (Some(prompt), _) => prompt.clone(),
+      (None, Some(prompt)) => fill_placeholders(prompt, &[("language", language_name(language))]),
+      (None, None) if language == "zh" => self.chinese_summary_prompt.clone(),
+      (None, None) => fill_placeholders(
+        SUMMARY_PROMPT_TEMPLATE,
+        &[("language", language_name(language))],
+      ),
+    }
+  }
+
+  /// The translation prompt for `language`, chosen like
+  /// [`Self::summary_prompt`].
+  pub fn translation_prompt(&self, language: &str) -> String {
+    match (
+      self.translation_prompts.get(language),
+      self.stage_prompts.get(Stage::Translation),
+    ) {
+      (Some(prompt), _) => prompt.clone(),
+      (None, Some(prompt)) => fill_placeholders(prompt, &[("language", language_name(language))]),
+      (None, None) if language == "zh" => self.chinese_translation_prompt.clone(),
+      (None, None) => fill_placeholders(
+        TRANSLATION_PROMPT_TEMPLATE,
+        &[("language", language_name(language))],
+      ),
+    }
+  }
+
+  /// The section extraction prompt, in the primary language
+  pub fn section_prompt(&self) -> String {
+    fill_placeholders(
+      self
+        .stage_prompts
+        .get(Stage::MindMap)
+        .unwrap_or(&self.section_extraction_prompt),
+      &[("language", language_name(self.primary_language()))],
+    )
+  }
+
+  /// The poster prompt
+  pub fn poster_prompt(&self) -> &str {
+    self
+      .stage_prompts
+      .get(Stage::Poster)
+      .unwrap_or(&self.poster_generation_prompt)
+  }
+
+  /// The stage cache `cache` and `cache_dir` describe
+  pub fn stage_cache(&self) -> StageCache {
+    match &self.cache_dir {
+      Some(dir) => StageCache::new(dir, self.cache),
+      None => StageCache::at_default_dir(self.cache),
+    }
+  }
+
+  /// The sink a run's stage metrics go to, pricing tokens with
+  /// `pricing_file` when it loads
+  pub fn stage_metrics(&self) -> StageMetrics {
+    let Some(path) = &self.pricing_file else {
+      return StageMetrics::new();
+    };
+    match PricingTable::load_from_yaml(path) {
+      Ok(pricing) => StageMetrics::new().with_pricing(pricing),
+      Err(e) => {
+        log::warn!("Reporting stage metrics without costs: {}", e);
+        StageMetrics::new()
+      }
+    }
+  }
+
   /// Create a new configuration with custom model names
   pub fn with_models(qwen_turbo: &str, qwen_image: &str) -> Self {
-    let mut config = Self::default();
-    config.qwen_turbo_model = qwen_turbo.to_string();
-    config.qwen_image_model = qwen_image.to_string();
-    config
+    Self {
+      qwen_turbo_model: qwen_turbo.to_string(),
+      qwen_image_model: qwen_image.to_string(),
+      ..Self::default()
+    }
   }
 
   /// Create a new configuration with custom output directory
   pub fn with_output_directory(output_dir: &str) -> Self {
-    let mut config = Self::default();
-    config.output_directory = output_dir.to_string();
-    config
+    Self {
+      output_directory: output_dir.to_string(),
+      ..Self::default()
+    }
   }
 
   /// Create a configuration optimized for fast processing
   pub fn fast_processing() -> Self {
-    let mut config = Self::default();
-    config.max_tokens = Some(2000);
-    config.temperature = Some(0.1);
-    config.max_sections_for_mind_maps = Some(5);
-    config.enable_poster_generation = false; // Skip image generation for speed
-    config
+    Self {
+      max_tokens: Some(2000),
+      temperature: Some(0.1),
+      max_sections_for_mind_maps: Some(5),
+      enable_poster_generation: false, // Skip image generation for speed
+      ..Self::default()
+    }
   }
 
   /// Create a configuration optimized for comprehensive analysis
   pub fn comprehensive_analysis() -> Self {
-    let mut config = Self::default();
-    config.max_tokens = Some(8000);
-    config.temperature = Some(0.3);
-    config.max_sections_for_mind_maps = Some(15);
-    config.enable_mind_maps = true;
-    config.enable_poster_generation = true;
-    config.save_intermediate_files = true;
-    config
+    Self {
+      max_tokens: Some(8000),
+      temperature: Some(0.3),
+      max_sections_for_mind_maps: Some(15),
+      enable_mind_maps: true,
+      enable_poster_generation: true,
+      save_intermediate_files: true,
+      ..Self::default()
+    }
   }
 
   /// Validate the configuration
@@ -175,22 +534,52 @@ impl PaperAssistantConfig {
       return Err("qwen_image_model cannot be empty when poster generation is enabled".to_string());
     }
 
-    if let Some(temp) = self.temperature {
-      if temp < 0.0 || temp > 2.0 {
-        return Err("temperature must be between 0.0 and 2.0".to_string());
-      }
+    if self.enable_poster_generation && parse_poster_size(&self.poster_size).is_none() {
+      return Err(format!(
+        "poster_size must be WIDTHxHEIGHT, e.g. 1024x1536, not '{}'",
+        self.poster_size
+      ));
     }
 
-    if let Some(max_tokens) = self.max_tokens {
-      if max_tokens < 100 || max_tokens > 32000 {
-        return Err("max_tokens must be between 100 and 32000".to_string());
+    for stage in Stage::ALL {
+      if self
+        .stage_models
+        .get(stage)
+        .is_some_and(|model| model.is_empty())
+      {
+        return Err(format!("stage_models.{} cannot be empty", stage.as_str()));
       }
     }
 
+    if let Some(temp) = self.temperature
+      && (!(0.0..=2.0).contains(&temp))
+    {
+      return Err("temperature must be between 0.0 and 2.0".to_string());
+    }
+
+    if let Some(max_tokens) = self.max_tokens
+      && (!(100..=32000).contains(&max_tokens))
+    {
+      return Err("max_tokens must be between 100 and 32000".to_string());
+    }
+
     if self.output_directory.is_empty() {
       return Err("output_directory cannot be empty".to_string());
     }
 
+    if self.languages.is_empty() {
+      return Err("languages cannot be empty".to_string());
+    }
+
+    for (i, language) in self.languages.iter().enumerate() {
+      if language.is_empty() {
+        return Err("languages cannot contain an empty code".to_string());
+      }
+      if self.languages[..i].contains(language) {
+        return Err(format!("language '{}' is listed twice", language));
+      }
+    }
+
     Ok(())
   }
 
@@ -198,13 +587,17 @@ impl PaperAssistantConfig {
   pub fn from_json_file(path: &str) -> anyhow::Result<Self> {
     let content = std::fs::read_to_string(path)?;
     let config: Self = serde_json::from_str(&content)?;
-    config.validate().map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
+    config
+      .validate()
+      .map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
     Ok(config)
   }
 
   /// Save configuration to JSON file
   pub fn to_json_file(&self, path: &str) -> anyhow::Result<()> {
-    self.validate().map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
+    self
+      .validate()
+      .map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
     let content = serde_json::to_string_pretty(self)?;
     std::fs::write(path, content)?;
     Ok(())
@@ -253,36 +646,60 @@ impl ConfigBuilder {
     if let Ok(turbo_model) = std::env::var("QWEN_TURBO_MODEL") {
       self.config.qwen_turbo_model = turbo_model;
     }
-    
+
     if let Ok(image_model) = std::env::var("QWEN_IMAGE_MODEL") {
       self.config.qwen_image_model = image_model;
     }
-    
+
     // Check for output directory override
     if let Ok(output_dir) = std::env::var("PAPER_ASSISTANT_OUTPUT_DIR") {
       self.config.output_directory = output_dir;
     }
-    
+
     // Check for temperature override
-    if let Ok(temp_str) = std::env::var("PAPER_ASSISTANT_TEMPERATURE") {
-      if let Ok(temp) = temp_str.parse::<f32>() {
-        self.config.temperature = Some(temp);
-      }
+    if let Ok(temp_str) = std::env::var("PAPER_ASSISTANT_TEMPERATURE")
+      && let Ok(temp) = temp_str.parse::<f32>()
+    {
+      self.config.temperature = Some(temp);
     }
-    
+
     // Check for max tokens override
-    if let Ok(tokens_str) = std::env::var("PAPER_ASSISTANT_MAX_TOKENS") {
-      if let Ok(tokens) = tokens_str.parse::<u32>() {
-        self.config.max_tokens = Some(tokens);
+    if let Ok(tokens_str) = std::env::var("PAPER_ASSISTANT_MAX_TOKENS")
+      && let Ok(tokens) = tokens_str.parse::<u32>()
+    {
+      self.config.max_tokens = Some(tokens);
+    }
+
+    // Check for per-stage models, e.g. PAPER_ASSISTANT_TRANSLATION_MODEL
+    for stage in Stage::ALL {
+      let var = format!("PAPER_ASSISTANT_{}_MODEL", stage.as_str().to_uppercase());
+      if let Ok(model) = std::env::var(&var) {
+        self.config.stage_models.set(stage, model);
       }
     }
-    
+
+    self
+  }
+
+  /// Run `stage` on `model`
+  pub fn stage_model(mut self, stage: Stage, model: &str) -> Self {
+    self.config.stage_models.set(stage, model);
+    self
+  }
+
+  /// Use `prompt` for `stage`; see `PaperAssistantConfig::stage_prompts`
+  /// for its placeholders
+  pub fn stage_prompt(mut self, stage: Stage, prompt: &str) -> Self {
+    self.config.stage_prompts.set(stage, prompt);
     self
   }
 
   /// Build the configuration
   pub fn build(self) -> anyhow::Result<PaperAssistantConfig> {
-    self.config.validate().map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
+    self
+      .config
+      .validate()
+      .map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
     Ok(self.config)
   }
 }
@@ -301,7 +718,7 @@ mod tests {
   fn test_default_config() {
     let config = PaperAssistantConfig::default();
     assert_eq!(config.qwen_turbo_model, "qwen-turbo");
-    assert_eq!(config.qwen_image_model, "qwen-vl-plus");
+    assert_eq!(config.qwen_image_model, "qwen-image");
     assert_eq!(config.temperature, Some(0.3));
     assert!(config.enable_mind_maps);
     assert!(config.enable_poster_generation);
@@ -310,17 +727,22 @@ mod tests {
   #[test]
   fn test_config_validation() {
     let mut config = PaperAssistantConfig::default();
-    
+
     // Valid config should pass
     assert!(config.validate().is_ok());
-    
+
     // Invalid temperature should fail
     config.temperature = Some(-1.0);
     assert!(config.validate().is_err());
-    
+
     config.temperature = Some(0.5);
     assert!(config.validate().is_ok());
-    
+
+    config.poster_size = "large".to_string();
+    assert!(config.validate().is_err());
+    config.poster_size = "1536x1024".to_string();
+    assert!(config.validate().is_ok());
+
     // Empty model should fail
     config.qwen_turbo_model = "".to_string();
     assert!(config.validate().is_err());
@@ -351,10 +773,109 @@ mod tests {
     assert!(config.save_intermediate_files);
   }
 
+  #[test]
+  fn test_language_prompts() {
+    let mut config = PaperAssistantConfig::default();
+    assert_eq!(config.languages, vec!["zh".to_string()]);
+    assert_eq!(config.summary_prompt("zh"), config.chinese_summary_prompt);
+
+    let english = config.summary_prompt("en");
+    assert!(english.contains("summary in English"));
+    assert!(english.contains("{{paper_content}}"));
+    assert!(config.translation_prompt("ja").contains("into 日本語"));
+
+    config
+      .translation_prompts
+      .insert("en".to_string(), "Translate: {{paper_content}}".to_string());
+    assert_eq!(
+      config.translation_prompt("en"),
+      "Translate: {{paper_content}}"
+    );
+
+    config.languages = vec!["en".to_string(), "zh".to_string(), "en".to_string()];
+    assert!(config.validate().is_err());
+    config.languages.pop();
+    assert!(config.validate().is_ok());
+    assert_eq!(config.primary_language(), "en");
+  }
+
+  #[test]
+  fn test_stage_models_and_prompts() {
+    let config = ConfigBuilder::new()
+      .stage_model(Stage::Translation, "claude-3-5-sonnet")
+      .stage_model(Stage::Summary, "qwen-turbo-cheap")
+      .stage_prompt(
+        Stage::Summary,
+        "Summarize in {{language}}: {{paper_content}}",
+      )
+      .build()
+      .unwrap();
+
+    assert_eq!(config.stage_model(Stage::Translation), "claude-3-5-sonnet");
+    assert_eq!(config.stage_model(Stage::MindMap), "qwen-turbo");
+    assert_eq!(config.stage_model(Stage::Poster), "qwen-image");
+    assert_eq!(
+      config.resolved_stage_models()["summary"],
+      "qwen-turbo-cheap"
+    );
+    assert_eq!(
+      config.summary_prompt("en"),
+      "Summarize in English: {{paper_content}}"
+    );
+    assert_eq!(
+      config.translation_prompt("zh"),
+      config.chinese_translation_prompt
+    );
+
+    let json = serde_json::to_value(&config).unwrap();
+    assert_eq!(
+      json["stage_models"],
+      serde_json::json!({
+        "summary": "qwen-turbo-cheap",
+        "translation": "claude-3-5-sonnet"
+      })
+    );
+    let unknown = json
+      .to_string()
+      .replace("\"translation\":", "\"translate\":");
+    assert!(serde_json::from_str::<PaperAssistantConfig>(&unknown).is_err());
+  }
+
+  #[tokio::test]
+  async fn test_stage_models_must_be_registered() {
+    let registry = ModelRegistry::new();
+    registry
+      .load_config_from_yaml(
+        r#"
+models:
+  qwen-turbo:
+    vendor: dashscope
+    type: chat
+providers:
+  qwen:
+    api_key_env: DASHSCOPE_API_KEY
+"#,
+      )
+      .await
+      .unwrap();
+
+    let mut config = PaperAssistantConfig::default();
+    assert!(config.check_stage_models(&registry).is_ok());
+    config.stage_models.set(Stage::Summary, "qwen-turbo");
+    assert!(config.check_stage_models(&registry).is_ok());
+    config
+      .stage_models
+      .set(Stage::Translation, "claude-unknown");
+    assert_eq!(
+      config.check_stage_models(&registry).unwrap_err(),
+      "stage_models.translation: model 'claude-unknown' is not in the model registry"
+    );
+  }
+
   #[test]
   fn test_custom_models() {
     let config = PaperAssistantConfig::with_models("custom-turbo", "custom-image");
     assert_eq!(config.qwen_turbo_model, "custom-turbo");
     assert_eq!(config.qwen_image_model, "custom-image");
   }
-}
\ No newline at end of file
+}
@@ -2,25 +2,59 @@
 //!
 //! This agent processes arXiv papers with the following capabilities:
 //! 1. Fetches paper content from arXiv URLs using ArxivNode
-//! 2. Generates Chinese summaries using qwen-turbo model
-//! 3. Translates papers to Chinese using qwen-turbo model  
-//! 4. Creates Chinese mind maps for subsections using MarkMapNode
+//! 2. Generates summaries using qwen-turbo model
+//! 3. Translates papers using qwen-turbo model
+//! 4. Creates mind maps for subsections using MarkMapNode
+//!
+//! Summaries and translations are produced in every language in
+//! `PaperAssistantConfig::languages` (Chinese by default); mind maps and
+//! the poster use the first.
 //! 5. Generates poster images using qwen-image model
+//!
+//! `PaperAssistant::process_papers` runs a whole reading list concurrently,
+//! and `save_batch_results` writes it out with an index page.
+//! `PaperAssistant::process_paper_streaming` yields each stage's output as
+//! it completes (see [`streaming`]).
 
+use agentflow_agents::{
+  ArtifactWriter, BatchProcessor, GlossaryViolation, PaperEnrichment, StageCache, StageMetric,
+  stage_metrics_table,
+};
 use agentflow_core::SharedState;
-use agentflow_nodes::{ArxivNode, LlmNode, MarkMapNode, TextToImageNode};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::collections::HashMap;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use uuid::Uuid;
 
-pub mod workflow;
+pub mod cache;
+pub mod checkpoint;
 pub mod config;
+pub mod poster;
+pub mod progress;
+pub mod report;
+pub mod streaming;
 pub mod utils;
+pub mod workflow;
 
-use workflow::PaperAssistantWorkflow;
-pub use config::{PaperAssistantConfig, ConfigBuilder};
+use cache::{ProcessedIndex, config_hash};
+use checkpoint::{CHECKPOINT_URL_KEY, StageCheckpoint};
+pub use config::{ConfigBuilder, PaperAssistantConfig, Stage, StageOverrides};
+pub use poster::{PosterPromptBuilder, PosterStyle};
+use progress::ProgressHandle;
+pub use progress::{ChannelProgressReporter, LogProgressReporter, ProgressEvent, ProgressReporter};
+pub use report::ReportFormat;
+pub use streaming::StageOutput;
+use streaming::StageOutputSink;
+use workflow::{
+  ENRICHMENT_KEY, PaperAssistantWorkflow, PaperWorkflow, STAGE_METRICS_KEY, summary_key,
+  translation_key,
+};
 
 /// Main Paper Assistant struct
 #[derive(Debug)]
@@ -28,6 +62,12 @@ pub struct PaperAssistant {
   config: PaperAssistantConfig,
   workflow: PaperAssistantWorkflow,
   shared_state: SharedState,
+  progress: ProgressHandle,
+  /// Process papers even when `processed.json` has them
+  force: bool,
+  /// Summaries and translations reused across runs; every paper of a
+  /// batch shares it
+  stage_cache: StageCache,
 }
 
 /// Result data from paper processing
@@ -35,230 +75,471 @@ pub struct PaperAssistant {
 pub struct PaperProcessingResult {
   pub paper_id: String,
   pub original_url: String,
-  pub chinese_summary: String,
-  pub chinese_translation: String,
+  /// The language the mind maps and poster are in
+  pub primary_language: String,
+  /// Summary and translation by language code
+  pub outputs: BTreeMap<String, LanguageOutput>,
+  /// The model each stage ran on, by stage name
+  #[serde(default)]
+  pub stage_models: BTreeMap<String, String>,
+  /// Citation counts, fields of study and related papers from Semantic
+  /// Scholar, with `enable_enrichment`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub enrichment: Option<PaperEnrichment>,
   pub mind_maps: Vec<MindMapResult>,
+  /// Sections whose mind map could not be generated
+  #[serde(default)]
+  pub mind_map_failures: Vec<MindMapFailure>,
   pub poster_image_path: Option<String>,
+  /// The prompt the poster was drawn from
+  #[serde(default)]
+  pub poster_prompt: Option<String>,
+  /// Why the content filter rejected the full poster prompt, when the
+  /// poster was drawn from the simplified one instead
+  #[serde(default)]
+  pub poster_filter_reason: Option<String>,
   pub processing_time_ms: u64,
+  /// How long each stage took, its model, tokens, cost and retries
+  #[serde(default)]
+  pub stage_metrics: Vec<StageMetric>,
   pub timestamp: String,
+  /// Loaded from an earlier run's saved results (see [`cache`]) instead
+  /// of processed
+  #[serde(default)]
+  pub from_cache: bool,
+}
+
+/// A paper's summary and translation in one language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageOutput {
+  pub summary: String,
+  pub translation: String,
+  /// Glossary terms the translation rendered differently
+  #[serde(default)]
+  pub glossary_violations: Vec<GlossaryViolation>,
 }
 
 /// Mind map result for a paper subsection
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MindMapResult {
+  /// The section's position in the paper (1-based)
+  #[serde(default)]
+  pub section_index: usize,
   pub section_title: String,
   pub section_number: Option<String>,
   pub mind_map_html: String,
   pub mind_map_markdown: String,
 }
 
+/// A section whose mind map could not be generated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MindMapFailure {
+  pub section_index: usize,
+  pub section_title: String,
+  pub error: String,
+}
+
 impl PaperAssistant {
   /// Create a new Paper Assistant with default configuration
   pub fn new() -> Result<Self> {
-    let config = PaperAssistantConfig::default();
-    let workflow = PaperAssistantWorkflow::new(&config)?;
-    let shared_state = SharedState::new();
-    
-    Ok(Self {
-      config,
-      workflow,
-      shared_state,
-    })
+    Self::with_config(PaperAssistantConfig::default())
   }
 
   /// Create a new Paper Assistant with custom configuration
   pub fn with_config(config: PaperAssistantConfig) -> Result<Self> {
-    let workflow = PaperAssistantWorkflow::new(&config)?;
+    let stage_cache = config.stage_cache();
+    let workflow = PaperAssistantWorkflow::new(&config)?.with_cache(stage_cache.clone());
     let shared_state = SharedState::new();
-    
+
     Ok(Self {
       config,
       workflow,
       shared_state,
+      progress: ProgressHandle::default(),
+      force: false,
+      stage_cache,
     })
   }
 
-  /// Process a paper from an arXiv URL
+  /// Report pipeline stage events to `reporter` instead of the log.
+  /// In a batch every paper reports to it.
+  pub fn with_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+    self.progress = ProgressHandle::new(reporter);
+    self.workflow.set_progress(self.progress.clone());
+    self
+  }
+
+  /// Process papers again even when the output directory's
+  /// `processed.json` records them with the current settings
+  pub fn with_force(mut self, force: bool) -> Self {
+    self.force = force;
+    self
+  }
+
+  /// The stage cache, with its hits and misses so far
+  pub fn stage_cache(&self) -> &StageCache {
+    &self.stage_cache
+  }
+
+  /// Process a paper from an arXiv URL, or return its saved result when
+  /// it was already processed into the output directory with the same
+  /// settings (see [`cache`])
   pub async fn process_paper(&mut self, arxiv_url: &str) -> Result<PaperProcessingResult> {
-    let start_time = std::time::Instant::now();
-    
-    // Set the arXiv URL in shared state
-    self.shared_state.insert("arxiv_url".to_string(), json!(arxiv_url));
-    
-    // Generate a unique processing ID
-    let processing_id = Uuid::new_v4().to_string();
-    self.shared_state.insert("processing_id".to_string(), json!(processing_id));
-    
-    log::info!("Starting paper processing for URL: {}", arxiv_url);
-    
-    // Execute the workflow
-    let result = self.workflow.execute(&self.shared_state).await?;
-    
-    let processing_time = start_time.elapsed();
-    
-    // Extract results from shared state
-    let paper_processing_result = self.extract_processing_result(
+    if let Some(result) = self.cached_result(arxiv_url) {
+      log::info!(
+        "Skipping {}: already processed with these settings",
+        arxiv_url
+      );
+      return Ok(result);
+    }
+    run_paper(
+      &mut self.workflow,
+      &self.shared_state,
       arxiv_url,
-      processing_time.as_millis() as u64,
-    )?;
-    
-    log::info!("Paper processing completed in {}ms", processing_time.as_millis());
-    
-    Ok(paper_processing_result)
-  }
-
-  /// Extract and format the processing results from shared state
-  fn extract_processing_result(
-    &self, 
-    original_url: &str,
-    processing_time_ms: u64,
+      &self.config,
+    )
+    .await
+  }
+
+  /// Process a paper like [`Self::process_paper`], on a task of its own,
+  /// streaming each stage's output as soon as it is stored. The stream
+  /// ends with a terminal item after the last stage; the handle gives the
+  /// full result. See [`streaming`] for the order items arrive in.
+  ///
+  /// The run has a `SharedState` of its own, so several can go on at
+  /// once. Dropping the stream does not stop it. Must be called from
+  /// within a tokio runtime.
+  pub fn process_paper_streaming(
+    &self,
+    arxiv_url: &str,
+  ) -> (
+    impl Stream<Item = StageOutput>,
+    JoinHandle<Result<PaperProcessingResult>>,
+  ) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let cached = self.cached_result(arxiv_url);
+    let mut workflow = self.workflow.clone();
+    workflow.set_stage_outputs(StageOutputSink::new(sender.clone()));
+    let config = self.config.clone();
+    let arxiv_url = arxiv_url.to_string();
+    let handle = tokio::spawn(async move {
+      let result = match cached {
+        Some(result) => {
+          log::info!(
+            "Skipping {}: already processed with these settings",
+            arxiv_url
+          );
+          Ok(result)
+        }
+        None => run_paper(&mut workflow, &SharedState::new(), &arxiv_url, &config).await,
+      };
+      // Every stage has sent its output by now; the stream ends when the
+      // last sender, this one, is dropped
+      drop(workflow);
+      let _ = sender.send(StageOutput::terminal(&result));
+      result
+    });
+    (UnboundedReceiverStream::new(receiver), handle)
+  }
+
+  /// Resume a run that failed part-way: load the stage outputs it saved
+  /// under `output_dir` (see [`checkpoint`]) and run only the stages that
+  /// are missing.
+  pub async fn resume(
+    &mut self,
+    output_dir: &str,
+    arxiv_url: &str,
   ) -> Result<PaperProcessingResult> {
-    // Extract ArXiv paper information
-    let arxiv_output = self.shared_state.get("arxiv_fetch_output")
-      .ok_or_else(|| anyhow::anyhow!("ArXiv fetch output not found"))?;
-    
-    let paper_id = arxiv_output["paper_id"].as_str()
-      .unwrap_or("unknown")
-      .to_string();
-
-    // Extract Chinese summary
-    let summary_output = self.shared_state.get("chinese_summary_output")
-      .ok_or_else(|| anyhow::anyhow!("Chinese summary output not found"))?;
-    
-    let chinese_summary = summary_output["response"].as_str()
-      .unwrap_or("Summary generation failed")
-      .to_string();
-
-    // Extract Chinese translation
-    let translation_output = self.shared_state.get("chinese_translation_output")
-      .ok_or_else(|| anyhow::anyhow!("Chinese translation output not found"))?;
-    
-    let chinese_translation = translation_output["response"].as_str()
-      .unwrap_or("Translation failed")
-      .to_string();
-
-    // Extract mind maps
-    let mind_maps = self.extract_mind_maps()?;
-
-    // Extract poster image path
-    let poster_image_path = self.shared_state.get("poster_image_output")
-      .and_then(|output| {
-        output.get("image_path")
-          .and_then(|path| path.as_str())
-          .map(|s| s.to_string())
-      });
-
-    Ok(PaperProcessingResult {
-      paper_id,
-      original_url: original_url.to_string(),
-      chinese_summary,
-      chinese_translation,
-      mind_maps,
-      poster_image_path,
-      processing_time_ms,
-      timestamp: chrono::Utc::now().to_rfc3339(),
-    })
+    let checkpoint = StageCheckpoint::new(output_dir);
+    let loaded = checkpoint.load_into(&self.shared_state)?;
+    if let Some(saved_url) = self.shared_state.get(CHECKPOINT_URL_KEY) {
+      let saved_url = saved_url.as_str().unwrap_or_default().to_string();
+      if saved_url != arxiv_url {
+        anyhow::bail!(
+          "{} holds partial results for {}, not {}",
+          checkpoint.dir().display(),
+          saved_url,
+          arxiv_url
+        );
+      }
+    }
+    log::info!(
+      "Resuming {} with {} saved stage output(s)",
+      arxiv_url,
+      loaded.len()
+    );
+
+    self.workflow.set_checkpoint(checkpoint);
+    run_paper(
+      &mut self.workflow,
+      &self.shared_state,
+      arxiv_url,
+      &self.config,
+    )
+    .await
   }
 
-  /// Extract mind map results from shared state
-  fn extract_mind_maps(&self) -> Result<Vec<MindMapResult>> {
-    let mut mind_maps = Vec::new();
-    
-    // Look for mind map outputs in shared state
-    for (key, value) in self.shared_state.iter() {
-      if key.contains("mind_map_") && key.ends_with("_output") {
-        // Extract section information from the key
-        // Expected format: "mind_map_section_N_output"
-        let section_info = key.replace("mind_map_", "").replace("_output", "");
-        
-        let mind_map_result = MindMapResult {
-          section_title: value["section_title"].as_str()
-            .unwrap_or(&section_info)
-            .to_string(),
-          section_number: value["section_number"].as_str()
-            .map(|s| s.to_string()),
-          mind_map_html: value["html"].as_str()
-            .unwrap_or("")
-            .to_string(),
-          mind_map_markdown: value["original_markdown"].as_str()
-            .unwrap_or("")
-            .to_string(),
-        };
-        
-        mind_maps.push(mind_map_result);
+  /// Process a reading list, up to `concurrency` papers at a time.
+  ///
+  /// Each paper gets its own workflow and `SharedState`, and writes its
+  /// intermediate files under its own subdirectory of the output
+  /// directory (see [`paper_dir_name`]). All of them share the arXiv
+  /// fetch cache, so a paper already fetched is not downloaded again.
+  /// The results are in the order of `urls`; one paper failing does not
+  /// stop the others.
+  pub async fn process_papers(
+    &self,
+    urls: &[String],
+    concurrency: usize,
+  ) -> Vec<Result<PaperProcessingResult>> {
+    let progress = self.progress.clone();
+    let stage_cache = self.stage_cache.clone();
+    self
+      .process_papers_with(urls, concurrency, move |config| {
+        let mut workflow = PaperAssistantWorkflow::new(config)?.with_cache(stage_cache.clone());
+        workflow.set_progress(progress.clone());
+        Ok(workflow)
+      })
+      .await
+  }
+
+  /// The saved result of `arxiv_url` from `processed.json`, unless forced
+  fn cached_result(&self, arxiv_url: &str) -> Option<PaperProcessingResult> {
+    if self.force {
+      return None;
+    }
+    match ProcessedIndex::load(&self.config.output_directory) {
+      Ok(index) => index.cached_result(arxiv_url, &config_hash(&self.config)),
+      Err(e) => {
+        log::warn!("Ignoring the processed paper index: {}", e);
+        None
       }
     }
-    
-    // Sort mind maps by section number if available
-    mind_maps.sort_by(|a, b| {
-      match (&a.section_number, &b.section_number) {
-        (Some(a_num), Some(b_num)) => a_num.cmp(b_num),
-        (Some(_), None) => std::cmp::Ordering::Less,
-        (None, Some(_)) => std::cmp::Ordering::Greater,
-        (None, None) => a.section_title.cmp(&b.section_title),
+  }
+
+  async fn process_papers_with<W, F>(
+    &self,
+    urls: &[String],
+    concurrency: usize,
+    make_workflow: F,
+  ) -> Vec<Result<PaperProcessingResult>>
+  where
+    W: PaperWorkflow + 'static,
+    F: Fn(&PaperAssistantConfig) -> Result<W> + Send + Sync + 'static,
+  {
+    log::info!(
+      "Processing {} papers, {} at a time",
+      urls.len(),
+      concurrency.max(1)
+    );
+    let mut results: Vec<Option<Result<PaperProcessingResult>>> = urls
+      .iter()
+      .map(|url| self.cached_result(url).map(Ok))
+      .collect();
+    let skipped = results.iter().filter(|result| result.is_some()).count();
+    if skipped > 0 {
+      log::info!("Skipping {} already processed paper(s)", skipped);
+    }
+
+    let pending: Vec<usize> = (0..urls.len())
+      .filter(|index| results[*index].is_none())
+      .collect();
+    let make_workflow = &make_workflow;
+    let report = BatchProcessor::<usize, PaperProcessingResult>::new(concurrency)
+      .process(pending, |index| {
+        let url = urls[index].clone();
+        let mut config = self.config.clone();
+        config.output_directory = format!(
+          "{}/{}",
+          self.config.output_directory,
+          paper_dir_name(index, &url)
+        );
+        async move {
+          let result = async {
+            let mut workflow = make_workflow(&config)?;
+            let shared_state = SharedState::new();
+            run_paper(&mut workflow, &shared_state, &url, &config).await
+          }
+          .await;
+          if let Err(e) = &result {
+            log::warn!("Paper processing failed for {}: {}", url, e);
+          }
+          result.map_err(Into::into)
+        }
+      })
+      .await;
+    match report {
+      Ok(report) => {
+        for (index, result) in report.results {
+          results[index] = Some(result.map_err(|e| anyhow::anyhow!(e)));
+        }
       }
-    });
-    
-    Ok(mind_maps)
+      // Only a checkpoint can fail to load, and this batch keeps none
+      Err(e) => log::error!("Batch processing failed: {}", e),
+    }
+
+    results
+      .into_iter()
+      .zip(urls)
+      .map(|(result, url)| {
+        result.unwrap_or_else(|| Err(anyhow::anyhow!("Paper {} was not processed", url)))
+      })
+      .collect()
   }
 
-  /// Save processing results to files
-  pub async fn save_results(
-    &self, 
-    result: &PaperProcessingResult, 
-    output_dir: &str,
+  /// Save processing results to files, listed in the directory's
+  /// `manifest.json`
+  pub async fn save_results(&self, result: &PaperProcessingResult, output_dir: &str) -> Result<()> {
+    let writer = ArtifactWriter::new(output_dir).await?;
+    self.write_results(result, &writer).await?;
+    log::info!("Results saved to directory: {}", output_dir);
+    Ok(())
+  }
+
+  /// Write `result`'s files with `writer`
+  async fn write_results(
+    &self,
+    result: &PaperProcessingResult,
+    writer: &ArtifactWriter,
   ) -> Result<()> {
-    // Create output directory
-    tokio::fs::create_dir_all(output_dir).await?;
-    
-    let base_filename = format!("{}_paper_assistant", result.paper_id.replace('/', "_"));
-    
-    // Save summary as markdown
-    let summary_path = format!("{}/{}_summary.md", output_dir, base_filename);
-    let summary_content = format!(
-      "# 论文摘要\n\n**论文ID:** {}\n**处理时间:** {}\n\n## 中文摘要\n\n{}\n",
-      result.paper_id,
-      result.timestamp,
-      result.chinese_summary
-    );
-    tokio::fs::write(&summary_path, summary_content).await?;
-    
-    // Save translation as markdown
-    let translation_path = format!("{}/{}_translation.md", output_dir, base_filename);
-    let translation_content = format!(
-      "# 论文中文翻译\n\n**论文ID:** {}\n**原始URL:** {}\n**处理时间:** {}\n\n## 翻译内容\n\n{}\n",
-      result.paper_id,
-      result.original_url,
-      result.timestamp,
-      result.chinese_translation
-    );
-    tokio::fs::write(&translation_path, translation_content).await?;
-    
+    let base_filename = base_filename(&result.paper_id);
+
+    // Save each language's summary and translation as markdown
+    for (language, output) in &result.outputs {
+      let labels = MarkdownLabels::for_language(language);
+
+      let summary_content = format!(
+        "# {}\n\n**{}:** {}\n**{}:** {}\n{}\n## {}\n\n{}\n",
+        labels.summary_title,
+        labels.paper_id,
+        result.paper_id,
+        labels.processed_at,
+        result.timestamp,
+        enrichment_header(result.enrichment.as_ref(), &labels),
+        labels.summary_heading,
+        output.summary
+      );
+      writer
+        .write_markdown(
+          &format!("{}_summary_{}", base_filename, language),
+          &summary_content,
+          Some(&format!("summary_{}", language)),
+        )
+        .await?;
+
+      let translation_content = format!(
+        "# {}\n\n**{}:** {}\n**{}:** {}\n**{}:** {}\n\n## {}\n\n{}\n",
+        labels.translation_title,
+        labels.paper_id,
+        result.paper_id,
+        labels.original_url,
+        result.original_url,
+        labels.processed_at,
+        result.timestamp,
+        labels.translation_heading,
+        output.translation
+      );
+      writer
+        .write_markdown(
+          &format!("{}_translation_{}", base_filename, language),
+          &translation_content,
+          Some(&format!("translation_{}", language)),
+        )
+        .await?;
+    }
+
     // Save mind maps
     for (i, mind_map) in result.mind_maps.iter().enumerate() {
-      let mind_map_html_path = format!(
-        "{}/{}_mindmap_{:02}__{}.html", 
-        output_dir, 
-        base_filename, 
-        i + 1,
-        mind_map.section_title.chars().take(20).collect::<String>()
-          .replace(' ', "_")
-          .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
-      );
-      
-      tokio::fs::write(&mind_map_html_path, &mind_map.mind_map_html).await?;
+      let title = utils::sanitize_filename(&mind_map.section_title);
+      writer
+        .write_html(
+          &format!("{}_mindmap_{:02}_{}", base_filename, i + 1, title),
+          &mind_map.mind_map_html,
+          Some(&format!("mind_map_section_{}", mind_map.section_index)),
+        )
+        .await?;
+    }
+
+    if !result.stage_metrics.is_empty() {
+      writer
+        .write_markdown(
+          &format!("{}_stage_metrics", base_filename),
+          &format!(
+            "# Stage metrics: {}\n\n{}",
+            result.paper_id,
+            stage_metrics_table(&result.stage_metrics)
+          ),
+          None,
+        )
+        .await?;
     }
-    
+
     // Save complete results as JSON
-    let json_path = format!("{}/{}_complete_results.json", output_dir, base_filename);
-    let json_content = serde_json::to_string_pretty(result)?;
-    tokio::fs::write(&json_path, json_content).await?;
-    
-    log::info!("Results saved to directory: {}", output_dir);
-    
+    let json_path = writer
+      .write_json(&format!("{}_complete_results", base_filename), result, None)
+      .await?
+      .to_string_lossy()
+      .into_owned();
+
+    // Record the paper so later runs with the same settings skip it
+    let mut index = ProcessedIndex::load(&self.config.output_directory)?;
+    let result_path = std::path::absolute(&json_path)
+      .map(|path| path.to_string_lossy().into_owned())
+      .unwrap_or(json_path);
+    index.record(result, &config_hash(&self.config), &result_path);
+    index.save(&self.config.output_directory)?;
+
     Ok(())
   }
 
+  /// Save `result` as one shareable document,
+  /// `<output_dir>/<paper>_report.<html|pdf>`: metadata, summaries,
+  /// translations, mind maps and the poster (see [`report`]). PDF needs
+  /// weasyprint or a headless Chromium on `PATH`. Returns the report's
+  /// path.
+  pub async fn save_results_report(
+    &self,
+    result: &PaperProcessingResult,
+    output_dir: &str,
+    format: ReportFormat,
+  ) -> Result<String> {
+    tokio::fs::create_dir_all(output_dir).await?;
+    let file_stem = format!("{}_report", base_filename(&result.paper_id));
+    let path = report::render_report(result, output_dir, &file_stem, format).await?;
+    log::info!("Report saved to: {}", path);
+    Ok(path)
+  }
+
+  /// Save the results of [`Self::process_papers`]: each processed paper
+  /// goes to its own [`paper_dir_name`] subdirectory, as `save_results`
+  /// writes it, and `index.md` links every paper's outputs and lists the
+  /// papers that failed. One `manifest.json` lists every file. `results`
+  /// must be in the order of `urls`. Returns the index path.
+  pub async fn save_batch_results(
+    &self,
+    urls: &[String],
+    results: &[Result<PaperProcessingResult>],
+    output_dir: &str,
+  ) -> Result<String> {
+    let writer = ArtifactWriter::new(output_dir).await?;
+
+    for (index, (url, result)) in urls.iter().zip(results).enumerate() {
+      if let Ok(result) = result {
+        let paper_writer = writer.subdir(&paper_dir_name(index, url)).await?;
+        self.write_results(result, &paper_writer).await?;
+      }
+    }
+
+    let index_path = writer
+      .write_markdown("index", &batch_index_markdown(urls, results), None)
+      .await?
+      .to_string_lossy()
+      .into_owned();
+    log::info!("Batch index saved to: {}", index_path);
+
+    Ok(index_path)
+  }
+
   /// Get the current configuration
   pub fn config(&self) -> &PaperAssistantConfig {
     &self.config
@@ -270,15 +551,342 @@ impl PaperAssistant {
   }
 }
 
-impl Default for PaperAssistant {
-  fn default() -> Self {
-    Self::new().expect("Failed to create default PaperAssistant")
+/// Runs `workflow` for one paper over `shared_state` and collects the
+/// results it left there.
+async fn run_paper<W: PaperWorkflow>(
+  workflow: &mut W,
+  shared_state: &SharedState,
+  arxiv_url: &str,
+  config: &PaperAssistantConfig,
+) -> Result<PaperProcessingResult> {
+  let start_time = std::time::Instant::now();
+
+  // Set the arXiv URL in shared state
+  shared_state.insert("arxiv_url".to_string(), json!(arxiv_url));
+
+  // Generate a unique processing ID
+  let processing_id = Uuid::new_v4().to_string();
+  shared_state.insert("processing_id".to_string(), json!(processing_id));
+
+  log::info!("Starting paper processing for URL: {}", arxiv_url);
+
+  // Execute the workflow
+  workflow.execute(shared_state).await?;
+
+  let processing_time = start_time.elapsed();
+
+  // Extract results from shared state
+  let paper_processing_result = extract_processing_result(
+    shared_state,
+    arxiv_url,
+    processing_time.as_millis() as u64,
+    config,
+  )?;
+
+  log::info!(
+    "Paper processing completed in {}ms",
+    processing_time.as_millis()
+  );
+
+  Ok(paper_processing_result)
+}
+
+/// Extract and format the processing results from shared state
+fn extract_processing_result(
+  shared_state: &SharedState,
+  original_url: &str,
+  processing_time_ms: u64,
+  config: &PaperAssistantConfig,
+) -> Result<PaperProcessingResult> {
+  // Extract ArXiv paper information
+  let arxiv_output = shared_state
+    .get("arxiv_fetch_output")
+    .ok_or_else(|| anyhow::anyhow!("ArXiv fetch output not found"))?;
+
+  let paper_id = arxiv_output["paper_id"]
+    .as_str()
+    .unwrap_or("unknown")
+    .to_string();
+
+  // Extract each language's summary and translation
+  let mut outputs = BTreeMap::new();
+  for language in &config.languages {
+    let summary_output = shared_state
+      .get(&summary_key(language))
+      .ok_or_else(|| anyhow::anyhow!("Summary output ({}) not found", language))?;
+    let translation_output = shared_state
+      .get(&translation_key(language))
+      .ok_or_else(|| anyhow::anyhow!("Translation output ({}) not found", language))?;
+
+    outputs.insert(
+      language.clone(),
+      LanguageOutput {
+        summary: summary_output["response"]
+          .as_str()
+          .unwrap_or("Summary generation failed")
+          .to_string(),
+        translation: translation_output["response"]
+          .as_str()
+          .unwrap_or("Translation failed")
+          .to_string(),
+        glossary_violations: serde_json::from_value(
+          translation_output["glossary_violations"].clone(),
+        )
+        .unwrap_or_default(),
+      },
+    );
+  }
+
+  // Extract mind maps
+  let mind_maps = extract_mind_maps(shared_state)?;
+  let mind_map_failures = extract_mind_map_failures(shared_state)?;
+
+  // Extract the poster image path, and the prompt it was drawn from
+  let poster_output = shared_state
+    .get("poster_image_output")
+    .unwrap_or(Value::Null);
+  let poster_field = |name: &str| {
+    poster_output
+      .get(name)
+      .and_then(|value| value.as_str())
+      .map(|s| s.to_string())
+  };
+  let poster_image_path = poster_field("image_path");
+  let poster_prompt = poster_field("prompt");
+  let poster_filter_reason = poster_field("filter_reason");
+
+  let enrichment = match shared_state.get(ENRICHMENT_KEY) {
+    Some(value) => {
+      Some(serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Invalid enrichment: {}", e))?)
+    }
+    None => None,
+  };
+
+  let stage_metrics = match shared_state.get(STAGE_METRICS_KEY) {
+    Some(value) => {
+      serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Invalid stage metrics: {}", e))?
+    }
+    None => Vec::new(),
+  };
+
+  Ok(PaperProcessingResult {
+    paper_id,
+    original_url: original_url.to_string(),
+    primary_language: config.primary_language().to_string(),
+    outputs,
+    stage_models: config.resolved_stage_models(),
+    enrichment,
+    mind_maps,
+    mind_map_failures,
+    poster_image_path,
+    poster_prompt,
+    poster_filter_reason,
+    processing_time_ms,
+    stage_metrics,
+    timestamp: chrono::Utc::now().to_rfc3339(),
+    from_cache: false,
+  })
+}
+
+/// Read the workflow's mind maps from shared state, ordered by section
+/// number ("2.9" before "2.10"); unnumbered sections follow in section
+/// order.
+fn extract_mind_maps(shared_state: &SharedState) -> Result<Vec<MindMapResult>> {
+  let mut mind_maps: Vec<MindMapResult> = match shared_state.get(workflow::MIND_MAPS_KEY) {
+    Some(value) => serde_json::from_value(value)
+      .map_err(|e| anyhow::anyhow!("Invalid mind map output: {}", e))?,
+    None => Vec::new(),
+  };
+
+  mind_maps.sort_by(|a, b| match (&a.section_number, &b.section_number) {
+    (Some(a_num), Some(b_num)) => {
+      utils::compare_section_numbers(a_num, b_num).then(a.section_index.cmp(&b.section_index))
+    }
+    (Some(_), None) => std::cmp::Ordering::Less,
+    (None, Some(_)) => std::cmp::Ordering::Greater,
+    (None, None) => a.section_index.cmp(&b.section_index),
+  });
+
+  Ok(mind_maps)
+}
+
+/// Read the sections whose mind map failed from shared state
+fn extract_mind_map_failures(shared_state: &SharedState) -> Result<Vec<MindMapFailure>> {
+  match shared_state.get(workflow::MIND_MAP_FAILURES_KEY) {
+    Some(value) => serde_json::from_value(value)
+      .map_err(|e| anyhow::anyhow!("Invalid mind map failure list: {}", e)),
+    None => Ok(Vec::new()),
   }
 }
 
+/// The subdirectory of a batch's output directory for the paper at
+/// `index` in the reading list, e.g. `03_2312.07104` for
+/// `https://arxiv.org/abs/2312.07104`.
+pub fn paper_dir_name(index: usize, url: &str) -> String {
+  format!("{:02}_{}", index + 1, utils::arxiv_id(url))
+}
+
+/// The `index.md` of a batch: one row per paper, linking the files
+/// `save_results` wrote into its subdirectory.
+fn batch_index_markdown(urls: &[String], results: &[Result<PaperProcessingResult>]) -> String {
+  let succeeded = results.iter().filter(|result| result.is_ok()).count();
+  let skipped = results
+    .iter()
+    .filter(|result| result.as_ref().is_ok_and(|result| result.from_cache))
+    .count();
+  let mut index = format!(
+    "# 论文批处理索引\n\n**论文数:** {}\n**成功:** {}\n**跳过（已处理）:** {}\n**失败:** {}\n**生成时间:** {}\n\n",
+    urls.len(),
+    succeeded,
+    skipped,
+    urls.len() - succeeded,
+    chrono::Utc::now().to_rfc3339()
+  );
+  index.push_str("| # | 论文 | 状态 | 摘要 | 翻译 | 思维导图 | 完整结果 |\n");
+  index.push_str("|---|------|------|------|------|----------|----------|\n");
+
+  for (i, (url, result)) in urls.iter().zip(results).enumerate() {
+    let row = match result {
+      Ok(result) => {
+        let prefix = format!(
+          "{}/{}",
+          paper_dir_name(i, url),
+          base_filename(&result.paper_id)
+        );
+        let links = |kind: &str| {
+          result
+            .outputs
+            .keys()
+            .map(|language| format!("[{}]({}_{}_{}.md)", language, prefix, kind, language))
+            .collect::<Vec<_>>()
+            .join(" ")
+        };
+        format!(
+          "| {} | [{}]({}) | {} | {} | {} | {} | [JSON]({}_complete_results.json) |",
+          i + 1,
+          result.paper_id,
+          url,
+          if result.from_cache {
+            "✅ 已缓存"
+          } else {
+            "✅"
+          },
+          links("summary"),
+          links("translation"),
+          result.mind_maps.len(),
+          prefix
+        )
+      }
+      Err(e) => format!(
+        "| {} | {} | ❌ {} | | | | |",
+        i + 1,
+        url,
+        e.to_string().replace('|', "\\|").replace('\n', " ")
+      ),
+    };
+    index.push_str(&row);
+    index.push('\n');
+  }
+
+  index
+}
+
+/// The headings of the summary and translation files `save_results`
+/// writes: Chinese for `zh`, English for every other language.
+struct MarkdownLabels {
+  summary_title: String,
+  summary_heading: String,
+  translation_title: String,
+  translation_heading: String,
+  paper_id: &'static str,
+  original_url: &'static str,
+  processed_at: &'static str,
+  citations: &'static str,
+  influential: &'static str,
+  fields_of_study: &'static str,
+  related_papers: &'static str,
+}
+
+impl MarkdownLabels {
+  fn for_language(language: &str) -> Self {
+    if language == "zh" {
+      return Self {
+        summary_title: "论文摘要".to_string(),
+        summary_heading: "中文摘要".to_string(),
+        translation_title: "论文中文翻译".to_string(),
+        translation_heading: "翻译内容".to_string(),
+        paper_id: "论文ID",
+        original_url: "原始URL",
+        processed_at: "处理时间",
+        citations: "被引次数",
+        influential: "高影响力引用",
+        fields_of_study: "研究领域",
+        related_papers: "相关论文",
+      };
+    }
+    let name = config::language_name(language);
+    Self {
+      summary_title: "Paper Summary".to_string(),
+      summary_heading: format!("Summary ({})", name),
+      translation_title: format!("Paper Translation ({})", name),
+      translation_heading: "Translation".to_string(),
+      paper_id: "Paper ID",
+      original_url: "Original URL",
+      processed_at: "Processed at",
+      citations: "Citations",
+      influential: "Influential",
+      fields_of_study: "Fields of study",
+      related_papers: "Related papers",
+    }
+  }
+}
+
+/// The citation lines of a summary file's header; empty without an
+/// enrichment or when Semantic Scholar had nothing on the paper.
+fn enrichment_header(enrichment: Option<&PaperEnrichment>, labels: &MarkdownLabels) -> String {
+  let Some(enrichment) = enrichment.filter(|enrichment| !enrichment.is_empty()) else {
+    return String::new();
+  };
+  let mut header = String::new();
+  if let Some(citations) = enrichment.citation_count {
+    header.push_str(&format!("**{}:** {}", labels.citations, citations));
+    if let Some(influential) = enrichment.influential_citation_count {
+      header.push_str(&format!(" ({}: {})", labels.influential, influential));
+    }
+    header.push('\n');
+  }
+  if !enrichment.fields_of_study.is_empty() {
+    header.push_str(&format!(
+      "**{}:** {}\n",
+      labels.fields_of_study,
+      enrichment.fields_of_study.join(", ")
+    ));
+  }
+  if !enrichment.related_papers.is_empty() {
+    header.push_str(&format!("\n**{}:**\n", labels.related_papers));
+    for paper in &enrichment.related_papers {
+      let title = match &paper.url {
+        Some(url) => format!("[{}]({})", paper.title, url),
+        None => paper.title.clone(),
+      };
+      match paper.year {
+        Some(year) => header.push_str(&format!("- {} ({})\n", title, year)),
+        None => header.push_str(&format!("- {}\n", title)),
+      }
+    }
+  }
+  header
+}
+
+/// The prefix of every file `save_results` writes for a paper.
+fn base_filename(paper_id: &str) -> String {
+  format!("{}_paper_assistant", paper_id.replace('/', "_"))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use agentflow_agents::{CacheControl, CacheStats};
 
   #[tokio::test]
   async fn test_paper_assistant_creation() {
@@ -289,12 +897,13 @@ mod tests {
   #[test]
   fn test_mind_map_result_creation() {
     let mind_map = MindMapResult {
+      section_index: 1,
       section_title: "Introduction".to_string(),
       section_number: Some("1".to_string()),
       mind_map_html: "<html>test</html>".to_string(),
       mind_map_markdown: "# Introduction".to_string(),
     };
-    
+
     assert_eq!(mind_map.section_title, "Introduction");
     assert_eq!(mind_map.section_number, Some("1".to_string()));
   }
@@ -304,16 +913,785 @@ mod tests {
     let result = PaperProcessingResult {
       paper_id: "2312.07104".to_string(),
       original_url: "https://arxiv.org/abs/2312.07104".to_string(),
-      chinese_summary: "测试摘要".to_string(),
-      chinese_translation: "测试翻译".to_string(),
+      primary_language: "zh".to_string(),
+      outputs: BTreeMap::from([(
+        "zh".to_string(),
+        LanguageOutput {
+          summary: "测试摘要".to_string(),
+          translation: "测试翻译".to_string(),
+          glossary_violations: Vec::new(),
+        },
+      )]),
+      stage_models: BTreeMap::from([("translation".to_string(), "claude-3-5-sonnet".to_string())]),
+      enrichment: None,
       mind_maps: vec![],
+      mind_map_failures: vec![],
       poster_image_path: None,
+      poster_prompt: None,
+      poster_filter_reason: None,
       processing_time_ms: 1500,
+      stage_metrics: vec![
+        StageMetric::new("summary_zh", std::time::Duration::from_millis(900))
+          .with_model("qwen-turbo")
+          .with_call(Some(120), Some(30)),
+      ],
       timestamp: "2025-01-01T00:00:00Z".to_string(),
+      from_cache: false,
     };
-    
+
     let json = serde_json::to_string(&result).unwrap();
     assert!(json.contains("2312.07104"));
     assert!(json.contains("测试摘要"));
+    assert!(json.contains(r#""stage_models":{"translation":"claude-3-5-sonnet"}"#));
+    assert!(json.contains(
+      r#""stage_metrics":[{"stage":"summary_zh","model":"qwen-turbo","duration_ms":900,"calls":1,"prompt_tokens":120,"completion_tokens":30,"retries":0}]"#
+    ));
+    let parsed: PaperProcessingResult = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.stage_metrics, result.stage_metrics);
+  }
+
+  /// Stands in for the model pipeline: writes the outputs
+  /// `extract_processing_result` reads, derived from the paper's URL, and
+  /// fails for a URL containing "broken".
+  struct MockWorkflow;
+
+  #[async_trait::async_trait]
+  impl PaperWorkflow for MockWorkflow {
+    async fn execute(&mut self, shared_state: &SharedState) -> Result<Value> {
+      // Another paper's entries would show up here if states were shared.
+      assert!(shared_state.get("summary_output_zh").is_none());
+      let url = shared_state
+        .get("arxiv_url")
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_string();
+      if url.contains("broken") {
+        anyhow::bail!("ArXiv fetch failed: 404");
+      }
+      tokio::task::yield_now().await;
+
+      let id = url.rsplit('/').next().unwrap().to_string();
+      shared_state.insert("arxiv_fetch_output".to_string(), json!({ "paper_id": id }));
+      shared_state.insert(
+        summary_key("zh"),
+        json!({ "response": format!("摘要 {}", id) }),
+      );
+      shared_state.insert(
+        translation_key("zh"),
+        json!({ "response": format!("翻译 {}", id) }),
+      );
+      if id == "2312.07104" {
+        shared_state.insert(
+          workflow::MIND_MAPS_KEY.to_string(),
+          json!([{
+            "section_index": 1,
+            "section_title": "引言",
+            "section_number": "1",
+            "mind_map_html": "<html>引言</html>",
+            "mind_map_markdown": "# 引言"
+          }]),
+        );
+      }
+      Ok(json!({ "status": "completed" }))
+    }
+  }
+
+  fn reading_list() -> Vec<String> {
+    vec![
+      "https://arxiv.org/abs/2312.07104".to_string(),
+      "https://arxiv.org/abs/broken".to_string(),
+      "https://arxiv.org/abs/2401.00001".to_string(),
+    ]
+  }
+
+  fn temp_output_dir() -> String {
+    std::env::temp_dir()
+      .join(format!("paper_assistant_batch_{}", Uuid::new_v4()))
+      .to_string_lossy()
+      .into_owned()
   }
-}
\ No newline at end of file
+
+  #[tokio::test]
+  async fn test_process_papers_isolates_each_paper() {
+    let assistant = PaperAssistant::new().unwrap();
+    let urls = reading_list();
+    let results = assistant
+      .process_papers_with(&urls, 2, |_config: &PaperAssistantConfig| Ok(MockWorkflow))
+      .await;
+
+    assert_eq!(results.len(), 3);
+    let first = results[0].as_ref().unwrap();
+    assert_eq!(first.paper_id, "2312.07104");
+    assert_eq!(first.outputs["zh"].summary, "摘要 2312.07104");
+    assert_eq!(first.mind_maps.len(), 1);
+
+    let error = results[1].as_ref().unwrap_err().to_string();
+    assert!(error.contains("404"));
+
+    let third = results[2].as_ref().unwrap();
+    assert_eq!(third.original_url, "https://arxiv.org/abs/2401.00001");
+    assert_eq!(third.outputs["zh"].translation, "翻译 2401.00001");
+    assert!(third.mind_maps.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_save_batch_results_writes_an_index() {
+    let output_dir = temp_output_dir();
+    let assistant =
+      PaperAssistant::with_config(PaperAssistantConfig::with_output_directory(&output_dir))
+        .unwrap();
+    let urls = reading_list();
+    let results = assistant
+      .process_papers_with(&urls, 3, |_config: &PaperAssistantConfig| Ok(MockWorkflow))
+      .await;
+
+    let index_path = assistant
+      .save_batch_results(&urls, &results, &output_dir)
+      .await
+      .unwrap();
+    let index = std::fs::read_to_string(&index_path).unwrap();
+    assert!(index.contains("**成功:** 2"));
+    assert!(index.contains("[zh](01_2312.07104/2312.07104_paper_assistant_summary_zh.md)"));
+    assert!(index.contains("| 2 | https://arxiv.org/abs/broken | ❌ ArXiv fetch failed: 404 |"));
+    assert!(
+      index.contains("[JSON](03_2401.00001/2401.00001_paper_assistant_complete_results.json)")
+    );
+
+    let summary = std::fs::read_to_string(format!(
+      "{}/03_2401.00001/2401.00001_paper_assistant_summary_zh.md",
+      output_dir
+    ))
+    .unwrap();
+    assert!(summary.contains("摘要 2401.00001"));
+    assert!(!std::path::Path::new(&format!("{}/02_broken", output_dir)).exists());
+
+    // One manifest lists the index and every paper's files.
+    let manifest =
+      agentflow_agents::ArtifactManifest::load(std::path::Path::new(&output_dir)).unwrap();
+    assert!(manifest.get("index.md").is_some());
+    let summary_entry = manifest
+      .get("01_2312.07104/2312.07104_paper_assistant_summary_zh.md")
+      .unwrap();
+    assert_eq!(summary_entry.source.as_deref(), Some("summary_zh"));
+    assert!(
+      manifest
+        .get("03_2401.00001/2401.00001_paper_assistant_complete_results.json")
+        .is_some()
+    );
+    assert!(manifest.artifacts.iter().any(|entry| {
+      entry
+        .path
+        .starts_with("01_2312.07104/2312.07104_paper_assistant_mindmap_01_")
+    }));
+    for entry in &manifest.artifacts {
+      assert!(
+        std::path::Path::new(&output_dir).join(&entry.path).exists(),
+        "{}",
+        entry.path
+      );
+    }
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  #[test]
+  fn test_paper_dir_name() {
+    assert_eq!(
+      paper_dir_name(0, "https://arxiv.org/abs/2312.07104"),
+      "01_2312.07104"
+    );
+    assert_eq!(
+      paper_dir_name(11, "https://arxiv.org/pdf/2312.07104v2.pdf"),
+      "12_2312.07104v2"
+    );
+    assert_eq!(paper_dir_name(2, "2401.00001"), "03_2401.00001");
+  }
+
+  #[derive(Default)]
+  struct StageCalls {
+    fetch: std::sync::atomic::AtomicUsize,
+    summarize: std::sync::atomic::AtomicUsize,
+    translate: std::sync::atomic::AtomicUsize,
+    poster: std::sync::atomic::AtomicUsize,
+  }
+
+  /// Counts the calls into arXiv and the models; the poster fails while
+  /// `fail_poster` is set.
+  struct CountingStages {
+    calls: Arc<StageCalls>,
+    fail_poster: bool,
+  }
+
+  #[async_trait::async_trait]
+  impl workflow::PaperStages for CountingStages {
+    async fn fetch(&self, _arxiv_url: &str) -> Result<Value> {
+      self
+        .calls
+        .fetch
+        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+      Ok(json!({ "paper_id": "2312.07104", "simple_latex_content": "\\title{Mock}\n\nBody." }))
+    }
+
+    async fn summarize(&self, language: &str, _paper_content: &str) -> Result<Value> {
+      self
+        .calls
+        .summarize
+        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+      Ok(json!({ "response": format!("{} summary", language) }))
+    }
+
+    async fn translate_chunk(&self, language: &str, chunk: &str) -> Result<String> {
+      self
+        .calls
+        .translate
+        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+      Ok(format!("[{}] {}", language, chunk))
+    }
+
+    async fn extract_sections(&self, _paper_content: &str) -> Result<String> {
+      Ok("## 章节 1：引言\n### 内容摘要\n引言内容\n---".to_string())
+    }
+
+    async fn generate_mind_map(
+      &self,
+      _n: usize,
+      section: &workflow::PaperSection,
+    ) -> Result<workflow::MindMap> {
+      Ok(workflow::MindMap {
+        markdown: format!("# {}", section.title),
+        html: "<html></html>".to_string(),
+      })
+    }
+
+    async fn generate_poster(&self, _prompt: &str) -> Result<Value> {
+      self
+        .calls
+        .poster
+        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+      if self.fail_poster {
+        anyhow::bail!("Poster generation failed: quota exceeded");
+      }
+      Ok(json!({ "image_path": "poster.png" }))
+    }
+
+    async fn enrich(&self, _arxiv_id: &str) -> PaperEnrichment {
+      PaperEnrichment {
+        citation_count: Some(120000),
+        influential_citation_count: Some(9000),
+        fields_of_study: vec!["Computer Science".to_string()],
+        related_papers: vec![agentflow_agents::RelatedPaper {
+          title: "BERT".to_string(),
+          year: Some(2019),
+          citation_count: Some(90000),
+          url: Some("https://www.semanticscholar.org/paper/bert".to_string()),
+        }],
+        ..Default::default()
+      }
+    }
+  }
+
+  fn counting_assistant(
+    config: PaperAssistantConfig,
+    calls: &Arc<StageCalls>,
+    fail_poster: bool,
+  ) -> PaperAssistant {
+    let stages = CountingStages {
+      calls: Arc::clone(calls),
+      fail_poster,
+    };
+    let run_dir = std::path::Path::new(&config.output_directory).join("runs");
+    PaperAssistant {
+      workflow: PaperAssistantWorkflow::with_stages(&config, Box::new(stages))
+        .with_run_dir(run_dir),
+      config,
+      shared_state: SharedState::new(),
+      progress: ProgressHandle::default(),
+      force: false,
+      stage_cache: StageCache::new(std::env::temp_dir(), CacheControl::Off),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_stage_cache_reuses_outputs_until_the_prompt_changes() {
+    use std::sync::atomic::Ordering;
+
+    let output_dir = temp_output_dir();
+    let mut config = PaperAssistantConfig::with_output_directory(&output_dir);
+    config.save_intermediate_files = false;
+    let cache = StageCache::new(format!("{}/cache", output_dir), CacheControl::ReadWrite);
+    let calls = Arc::new(StageCalls::default());
+    let run = |config: PaperAssistantConfig| {
+      let assistant = counting_assistant(config, &calls, false);
+      PaperAssistant {
+        workflow: assistant.workflow.with_cache(cache.clone()),
+        stage_cache: cache.clone(),
+        ..assistant
+      }
+    };
+
+    run(config.clone())
+      .process_paper("https://arxiv.org/abs/2312.07104")
+      .await
+      .unwrap();
+    let result = run(config.clone())
+      .process_paper("https://arxiv.org/abs/2312.07104")
+      .await
+      .unwrap();
+    assert_eq!(result.outputs["zh"].summary, "zh summary");
+    assert_eq!(calls.summarize.load(Ordering::SeqCst), 1);
+    assert_eq!(calls.translate.load(Ordering::SeqCst), 1);
+    assert_eq!(
+      cache.stage_stats()["summary_zh"],
+      CacheStats {
+        hits: 1,
+        misses: 1,
+        writes: 1
+      }
+    );
+
+    // A new summary prompt misses; the translation is still reused.
+    config.summary_prompts.insert(
+      "zh".to_string(),
+      "用三句话总结：{{paper_content}}".to_string(),
+    );
+    run(config)
+      .process_paper("https://arxiv.org/abs/2312.07104")
+      .await
+      .unwrap();
+    assert_eq!(calls.summarize.load(Ordering::SeqCst), 2);
+    assert_eq!(calls.translate.load(Ordering::SeqCst), 1);
+    assert_eq!(
+      cache.stats(),
+      CacheStats {
+        hits: 3,
+        misses: 3,
+        writes: 3
+      }
+    );
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_resume_skips_completed_stages() {
+    use std::sync::atomic::Ordering;
+
+    let output_dir = temp_output_dir();
+    let url = "https://arxiv.org/abs/2312.07104";
+    let calls = Arc::new(StageCalls::default());
+    let config = || PaperAssistantConfig::with_output_directory(&output_dir);
+
+    let error = counting_assistant(config(), &calls, true)
+      .process_paper(url)
+      .await
+      .unwrap_err();
+    assert!(error.to_string().contains("quota exceeded"));
+    assert!(
+      std::path::Path::new(&format!(
+        "{}/stage_outputs/translation_output_zh.json",
+        output_dir
+      ))
+      .exists()
+    );
+
+    let result = counting_assistant(config(), &calls, false)
+      .resume(&output_dir, url)
+      .await
+      .unwrap();
+    assert_eq!(result.outputs["zh"].summary, "zh summary");
+    assert_eq!(
+      result.outputs["zh"].translation,
+      "[zh] \\title{Mock}\n\nBody."
+    );
+    assert_eq!(result.mind_maps.len(), 1);
+    assert_eq!(result.poster_image_path.as_deref(), Some("poster.png"));
+
+    // Only the poster ran again.
+    assert_eq!(calls.fetch.load(Ordering::SeqCst), 1);
+    assert_eq!(calls.summarize.load(Ordering::SeqCst), 1);
+    assert_eq!(calls.translate.load(Ordering::SeqCst), 1);
+    assert_eq!(calls.poster.load(Ordering::SeqCst), 2);
+
+    let error = counting_assistant(config(), &calls, false)
+      .resume(&output_dir, "https://arxiv.org/abs/2401.00001")
+      .await
+      .unwrap_err();
+    assert!(
+      error
+        .to_string()
+        .contains("holds partial results for https://arxiv.org/abs/2312.07104")
+    );
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_mocked_run_fills_the_whole_result() {
+    let output_dir = temp_output_dir();
+    let mut config = PaperAssistantConfig::with_output_directory(&output_dir);
+    config.save_intermediate_files = false;
+    let calls = Arc::new(StageCalls::default());
+
+    let result = counting_assistant(config.clone(), &calls, false)
+      .process_paper("https://arxiv.org/abs/2312.07104")
+      .await
+      .unwrap();
+    let mut result = serde_json::to_value(&result).unwrap();
+    assert!(result["timestamp"].as_str().is_some());
+    result["timestamp"] = json!("");
+    result["processing_time_ms"] = json!(0);
+    // Stages run side by side, so their metrics come in no set order
+    let mut stages: Vec<&str> = result["stage_metrics"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|metric| metric["stage"].as_str().unwrap())
+      .collect();
+    stages.sort();
+    assert_eq!(
+      stages,
+      [
+        "arxiv_fetch",
+        "mind_maps",
+        "poster",
+        "section_extraction",
+        "summary_zh",
+        "translation_zh"
+      ]
+    );
+    result.as_object_mut().unwrap().remove("stage_metrics");
+    assert_eq!(
+      result,
+      json!({
+        "paper_id": "2312.07104",
+        "original_url": "https://arxiv.org/abs/2312.07104",
+        "primary_language": "zh",
+        "outputs": { "zh": { "summary": "zh summary", "translation": "[zh] \\title{Mock}\n\nBody.", "glossary_violations": [] } },
+        "stage_models": config.resolved_stage_models(),
+        "mind_maps": [{
+          "section_index": 1,
+          "section_title": "引言",
+          "section_number": "1",
+          "mind_map_html": "<html></html>",
+          "mind_map_markdown": "# 引言",
+        }],
+        "mind_map_failures": [],
+        "poster_image_path": "poster.png",
+        "poster_prompt": concat!(
+          "Create a research poster for this paper.\n\n",
+          "Title: Mock\nField: science\nKey findings:\n1. zh summary\n\n",
+          "Layout: a square poster with the title at the top and one panel per key finding, ",
+          "each with a visual that supports it.\n",
+          "Style: clean, modern academic conference poster, white background, muted blue accents, ",
+          "clear section panels and professional typography",
+        ),
+        "poster_filter_reason": null,
+        "processing_time_ms": 0,
+        "timestamp": "",
+        "from_cache": false,
+      })
+    );
+
+    let _ = std::fs::remove_dir_all(&output_dir);
+  }
+
+  #[tokio::test]
+  async fn test_every_language_gets_its_own_outputs() {
+    let output_dir = temp_output_dir();
+    let mut config = PaperAssistantConfig::with_output_directory(&output_dir);
+    config.languages = vec!["en".to_string(), "zh".to_string()];
+    config.save_intermediate_files = false;
+    let calls = Arc::new(StageCalls::default());
+    let mut assistant = counting_assistant(config, &calls, false);
+
+    let result = assistant
+      .process_paper("https://arxiv.org/abs/2312.07104")
+      .await
+      .unwrap();
+    assert_eq!(result.primary_language, "en");
+    assert_eq!(result.outputs.keys().collect::<Vec<_>>(), vec!["en", "zh"]);
+    assert_eq!(result.outputs["en"].summary, "en summary");
+    assert_eq!(
+      result.outputs["zh"].translation,
+      "[zh] \\title{Mock}\n\nBody."
+    );
+    assert_eq!(calls.summarize.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    assistant.save_results(&result, &output_dir).await.unwrap();
+    let read = |name: &str| {
+      std::fs::read_to_string(format!(
+        "{}/2312.07104_paper_assistant_{}",
+        output_dir, name
+      ))
+      .unwrap()
+    };
+    assert!(read("summary_en.md").contains("## Summary (English)\n\nen summary"));
+    assert!(read("translation_en.md").contains("[en] \\title{Mock}"));
+    assert!(read("summary_zh.md").contains("## 中文摘要\n\nzh summary"));
+    assert!(read("translation_zh.md").contains("[zh] \\title{Mock}"));
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_enrichment_heads_the_summary_files() {
+    let output_dir = temp_output_dir();
+    let mut config = PaperAssistantConfig::with_output_directory(&output_dir);
+    config.languages = vec!["en".to_string(), "zh".to_string()];
+    config.save_intermediate_files = false;
+    config.enable_enrichment = true;
+    let calls = Arc::new(StageCalls::default());
+    let mut assistant = counting_assistant(config, &calls, false);
+
+    let result = assistant
+      .process_paper("https://arxiv.org/abs/2312.07104")
+      .await
+      .unwrap();
+    let enrichment = result.enrichment.as_ref().unwrap();
+    assert_eq!(enrichment.citation_count, Some(120000));
+    assert_eq!(enrichment.related_papers[0].title, "BERT");
+
+    assistant.save_results(&result, &output_dir).await.unwrap();
+    let read = |name: &str| {
+      std::fs::read_to_string(format!(
+        "{}/2312.07104_paper_assistant_{}",
+        output_dir, name
+      ))
+      .unwrap()
+    };
+    assert!(read("summary_en.md").contains(
+      "**Citations:** 120000 (Influential: 9000)\n**Fields of study:** Computer Science\n\n**Related papers:**\n- [BERT](https://www.semanticscholar.org/paper/bert) (2019)\n\n## Summary (English)"
+    ));
+    assert!(
+      read("summary_zh.md")
+        .contains("**被引次数:** 120000 (高影响力引用: 9000)\n**研究领域:** Computer Science")
+    );
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  #[test]
+  fn test_mind_maps_sort_by_section_number_and_failures_surface() {
+    let shared_state = SharedState::new();
+    let mind_map = |index: usize, number: Option<&str>, title: &str| {
+      json!({
+        "section_index": index,
+        "section_number": number,
+        "section_title": title,
+        "mind_map_html": "",
+        "mind_map_markdown": ""
+      })
+    };
+    shared_state.insert(
+      workflow::MIND_MAPS_KEY.to_string(),
+      json!([
+        mind_map(5, Some("10"), "Conclusion"),
+        mind_map(4, Some("2.10"), "Ablations"),
+        mind_map(6, None, "Appendix"),
+        mind_map(2, Some("2"), "Method"),
+        mind_map(3, Some("2.9"), "Training"),
+      ]),
+    );
+    shared_state.insert(
+      workflow::MIND_MAP_FAILURES_KEY.to_string(),
+      json!([{ "section_index": 1, "section_title": "Introduction", "error": "timeout" }]),
+    );
+
+    let titles: Vec<String> = extract_mind_maps(&shared_state)
+      .unwrap()
+      .into_iter()
+      .map(|mind_map| mind_map.section_title)
+      .collect();
+    assert_eq!(
+      titles,
+      vec!["Method", "Training", "Ablations", "Conclusion", "Appendix"]
+    );
+
+    let failures = extract_mind_map_failures(&shared_state).unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].section_title, "Introduction");
+    assert_eq!(failures[0].error, "timeout");
+  }
+
+  #[tokio::test]
+  async fn test_processed_papers_are_skipped_until_settings_change() {
+    use std::sync::atomic::Ordering;
+
+    let output_dir = temp_output_dir();
+    let url = "https://arxiv.org/abs/2312.07104";
+    let calls = Arc::new(StageCalls::default());
+    let config = || {
+      let mut config = PaperAssistantConfig::with_output_directory(&output_dir);
+      config.save_intermediate_files = false;
+      config
+    };
+
+    let mut assistant = counting_assistant(config(), &calls, false);
+    let first = assistant.process_paper(url).await.unwrap();
+    assert!(!first.from_cache);
+    assistant.save_results(&first, &output_dir).await.unwrap();
+    let index = cache::ProcessedIndex::load(&output_dir).unwrap();
+    assert_eq!(index.papers.len(), 1);
+
+    // Same settings: the saved result comes back without fetching.
+    let cached = counting_assistant(config(), &calls, false)
+      .process_paper(url)
+      .await
+      .unwrap();
+    assert!(cached.from_cache);
+    assert_eq!(cached.outputs["zh"].summary, "zh summary");
+    assert_eq!(calls.fetch.load(Ordering::SeqCst), 1);
+
+    let forced = counting_assistant(config(), &calls, false)
+      .with_force(true)
+      .process_paper(url)
+      .await
+      .unwrap();
+    assert!(!forced.from_cache);
+    assert_eq!(calls.fetch.load(Ordering::SeqCst), 2);
+
+    let mut english = config();
+    english.languages = vec!["en".to_string()];
+    let changed = counting_assistant(english, &calls, false)
+      .process_paper(url)
+      .await
+      .unwrap();
+    assert!(!changed.from_cache);
+    assert_eq!(calls.fetch.load(Ordering::SeqCst), 3);
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  /// `CountingStages` with each stage finishing at a set time, in
+  /// milliseconds after it starts
+  struct StaggeredStages(CountingStages);
+
+  async fn sleep_ms(ms: u64) {
+    tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+  }
+
+  #[async_trait::async_trait]
+  impl workflow::PaperStages for StaggeredStages {
+    async fn fetch(&self, arxiv_url: &str) -> Result<Value> {
+      self.0.fetch(arxiv_url).await
+    }
+
+    async fn summarize(&self, language: &str, paper_content: &str) -> Result<Value> {
+      sleep_ms(if language == "zh" { 150 } else { 0 }).await;
+      self.0.summarize(language, paper_content).await
+    }
+
+    async fn translate_chunk(&self, language: &str, chunk: &str) -> Result<String> {
+      sleep_ms(if language == "zh" { 100 } else { 50 }).await;
+      self.0.translate_chunk(language, chunk).await
+    }
+
+    async fn extract_sections(&self, paper_content: &str) -> Result<String> {
+      self.0.extract_sections(paper_content).await
+    }
+
+    async fn generate_mind_map(
+      &self,
+      n: usize,
+      section: &workflow::PaperSection,
+    ) -> Result<workflow::MindMap> {
+      sleep_ms(200).await;
+      self.0.generate_mind_map(n, section).await
+    }
+
+    async fn generate_poster(&self, prompt: &str) -> Result<Value> {
+      self.0.generate_poster(prompt).await
+    }
+
+    async fn enrich(&self, arxiv_id: &str) -> PaperEnrichment {
+      self.0.enrich(arxiv_id).await
+    }
+  }
+
+  fn staggered_assistant(output_dir: &str, fail_poster: bool) -> PaperAssistant {
+    let mut config = PaperAssistantConfig::with_output_directory(output_dir);
+    config.languages = vec!["en".to_string(), "zh".to_string()];
+    config.save_intermediate_files = false;
+    let stages = StaggeredStages(CountingStages {
+      calls: Arc::default(),
+      fail_poster,
+    });
+    PaperAssistant {
+      workflow: PaperAssistantWorkflow::with_stages(&config, Box::new(stages))
+        .with_run_dir(format!("{}/runs", output_dir)),
+      ..counting_assistant(config, &Arc::default(), fail_poster)
+    }
+  }
+
+  #[tokio::test]
+  async fn test_streaming_yields_stages_in_completion_order() {
+    use tokio_stream::StreamExt;
+
+    let output_dir = temp_output_dir();
+    let assistant = staggered_assistant(&output_dir, false);
+    let (stream, handle) = assistant.process_paper_streaming("https://arxiv.org/abs/2312.07104");
+    let items: Vec<StageOutput> = stream.collect().await;
+    let result = handle.await.unwrap().unwrap();
+
+    let stages: Vec<&str> = items.iter().map(|item| item.stage.as_str()).collect();
+    assert_eq!(
+      stages,
+      vec![
+        "arxiv_fetch",
+        "summary_en",
+        "translation_en",
+        "translation_zh",
+        "summary_zh",
+        "mind_map",
+        "poster",
+        "done"
+      ]
+    );
+    assert_eq!(
+      items[0].output,
+      json!({ "paper_id": "2312.07104", "paper_title": "Mock" })
+    );
+    assert_eq!(
+      items[1].output,
+      json!({ "language": "en", "summary": "en summary" })
+    );
+    assert_eq!(
+      items[3].output["translation"],
+      "[zh] \\title{Mock}\n\nBody."
+    );
+    assert_eq!(items[5].output["section_title"], "引言");
+    assert_eq!(items[6].output["image_path"], "poster.png");
+    assert!(items[7].is_terminal());
+    assert_eq!(items[7].output["status"], "completed");
+    assert_eq!(
+      items[7].output["processing_time_ms"],
+      result.processing_time_ms
+    );
+
+    let _ = std::fs::remove_dir_all(&output_dir);
+  }
+
+  #[tokio::test]
+  async fn test_streaming_ends_with_the_failure() {
+    use tokio_stream::StreamExt;
+
+    let output_dir = temp_output_dir();
+    let assistant = staggered_assistant(&output_dir, true);
+    let (stream, handle) = assistant.process_paper_streaming("https://arxiv.org/abs/2312.07104");
+    let items: Vec<StageOutput> = stream.collect().await;
+    let error = handle.await.unwrap().unwrap_err();
+
+    let last = items.last().unwrap();
+    assert!(last.is_terminal());
+    assert_eq!(
+      last.output,
+      json!({ "status": "failed", "error": error.to_string() })
+    );
+    assert_eq!(items.iter().filter(|item| item.is_terminal()).count(), 1);
+    assert!(!items.iter().any(|item| item.stage == "poster"));
+    assert!(items.iter().any(|item| item.stage == "summary_zh"));
+
+    let _ = std::fs::remove_dir_all(&output_dir);
+  }
+}
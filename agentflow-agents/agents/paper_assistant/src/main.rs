@@ -2,366 +2,607 @@
 //!
 //! A command-line tool for comprehensive arXiv paper processing using AI agents.
 //! Provides Chinese summarization, translation, mind mapping, and poster generation.
+//!
+//! The command line is `agentflow_agents::run_agent_cli`'s: `run`, `config
+//! show|create` and `version`, with the configuration read from `--config`,
+//! `$PAPER_ASSISTANT_CONFIG` or `~/.agentflow/agents/paper-assistant.yml`.
+
+use agentflow_agents::{
+  AgentApplication, AgentConfig, AgentResult, CacheControl, run_agent_cli, stage_metrics_table,
+};
+use async_trait::async_trait;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Arg, ArgAction, ArgMatches};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::process::ExitCode;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use paper_assistant::{
+  ConfigBuilder, PaperAssistant, PaperAssistantConfig, PaperProcessingResult, PosterStyle,
+  ProgressEvent, ProgressReporter, ReportFormat,
+};
 
-use anyhow::Result;
-use clap::{Arg, Command, ArgMatches};
-use env_logger;
-use log::{info, error, warn};
-use std::path::Path;
-use tokio;
+#[tokio::main]
+async fn main() -> ExitCode {
+  run_agent_cli::<PaperAssistantApp>().await
+}
 
-use paper_assistant::{PaperAssistant, PaperAssistantConfig, ConfigBuilder};
+/// The `run` flags that are not settings of the assistant
+#[derive(Debug, Clone, Default)]
+struct RunOptions {
+  force: bool,
+  resume: bool,
+  report: Option<ReportFormat>,
+  concurrency: usize,
+}
 
-#[tokio::main]
-async fn main() -> Result<()> {
-  // Initialize logging
-  env_logger::Builder::from_default_env()
-    .filter_level(log::LevelFilter::Info)
-    .init();
-
-  // Parse command line arguments
-  let matches = create_cli_app().get_matches();
-
-  match matches.subcommand() {
-    Some(("process", sub_matches)) => {
-      process_paper_command(sub_matches).await?;
-    },
-    Some(("config", sub_matches)) => {
-      config_command(sub_matches).await?;
-    },
-    Some(("examples", _)) => {
-      show_examples();
-    },
-    _ => {
-      println!("Use --help for usage information or 'examples' subcommand for examples");
+/// The agent's configuration: the assistant's settings, as in the config
+/// file, and this run's flags
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PaperAssistantCliConfig {
+  #[serde(flatten)]
+  assistant: PaperAssistantConfig,
+  #[serde(skip)]
+  run: RunOptions,
+}
+
+impl AgentConfig for PaperAssistantCliConfig {
+  fn validate(&self) -> AgentResult<()> {
+    self.assistant.validate().map_err(Into::into)
+  }
+}
+
+impl From<PaperAssistantConfig> for PaperAssistantCliConfig {
+  fn from(assistant: PaperAssistantConfig) -> Self {
+    Self {
+      assistant,
+      run: RunOptions::default(),
     }
   }
+}
 
-  Ok(())
+/// Paper Assistant as an `AgentApplication`
+struct PaperAssistantApp {
+  assistant: Mutex<PaperAssistant>,
+  run: RunOptions,
 }
 
-/// Create the CLI application structure
-fn create_cli_app() -> Command {
-  Command::new("paper-assistant")
-    .version("0.1.0")
-    .author("AgentFlow Team")
-    .about("AI Agent for comprehensive arXiv paper processing with Chinese translation and mind mapping")
-    .subcommand(
-      Command::new("process")
-        .about("Process an arXiv paper")
-        .arg(
-          Arg::new("url")
-            .help("arXiv paper URL or ID (e.g., https://arxiv.org/abs/2312.07104 or 2312.07104)")
-            .required(true)
-            .index(1)
-        )
-        .arg(
-          Arg::new("output")
-            .short('o')
-            .long("output")
-            .help("Output directory for results")
-            .default_value("./paper_assistant_output")
-        )
-        .arg(
-          Arg::new("config")
-            .short('c')
-            .long("config")
-            .help("Path to configuration JSON file")
-        )
-        .arg(
-          Arg::new("fast")
-            .long("fast")
-            .help("Use fast processing mode (skip image generation)")
-            .action(clap::ArgAction::SetTrue)
-        )
-        .arg(
-          Arg::new("comprehensive")
-            .long("comprehensive")
-            .help("Use comprehensive analysis mode")
-            .action(clap::ArgAction::SetTrue)
-        )
-        .arg(
-          Arg::new("no-mindmaps")
-            .long("no-mindmaps")
-            .help("Skip mind map generation")
-            .action(clap::ArgAction::SetTrue)
-        )
-        .arg(
-          Arg::new("no-poster")
-            .long("no-poster")
-            .help("Skip poster generation")
-            .action(clap::ArgAction::SetTrue)
-        )
-        .arg(
-          Arg::new("max-sections")
-            .long("max-sections")
-            .help("Maximum number of sections for mind mapping")
-            .value_parser(clap::value_parser!(usize))
-        )
-    )
-    .subcommand(
-      Command::new("config")
-        .about("Configuration management")
-        .subcommand(
-          Command::new("show")
-            .about("Show current default configuration")
-        )
-        .subcommand(
-          Command::new("create")
-            .about("Create a configuration file")
-            .arg(
-              Arg::new("output")
-                .short('o')
-                .long("output")
-                .help("Output path for configuration file")
-                .default_value("paper-assistant-config.json")
-            )
-            .arg(
-              Arg::new("type")
-                .short('t')
-                .long("type")
-                .help("Configuration type")
-                .value_parser(["default", "fast", "comprehensive"])
-                .default_value("default")
+#[async_trait]
+impl AgentApplication for PaperAssistantApp {
+  type Config = PaperAssistantCliConfig;
+  type Result = Value;
+
+  const NAME: &'static str = "paper-assistant";
+  const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+  const ABOUT: &'static str =
+    "AI Agent for comprehensive arXiv paper processing with Chinese translation and mind mapping";
+
+  async fn initialize(config: Self::Config) -> AgentResult<Self> {
+    let assistant = PaperAssistant::with_config(config.assistant)?
+      .with_progress(Arc::new(ChecklistReporter))
+      .with_force(config.run.force);
+    Ok(Self {
+      assistant: Mutex::new(assistant),
+      run: config.run,
+    })
+  }
+
+  /// Process one paper and save its results
+  async fn execute(&self, url: &str) -> AgentResult<Value> {
+    let mut assistant = self.assistant.lock().await;
+    let output_dir = assistant.config().output_directory.clone();
+
+    let outcome = if self.run.resume {
+      info!("Resuming paper processing from: {}", output_dir);
+      assistant.resume(&output_dir, url).await
+    } else {
+      info!("Processing paper: {}", url);
+      assistant.process_paper(url).await
+    };
+    let result = match outcome {
+      Ok(result) => result,
+      Err(e) => {
+        error!("Paper processing failed: {}", e);
+        if assistant.config().save_intermediate_files {
+          warn!(
+            "Completed stages are saved in {}/stage_outputs; rerun with --resume to continue",
+            output_dir
+          );
+        }
+        save_partial_state(&assistant, &output_dir).await;
+        return Err(e.into());
+      }
+    };
+    if result.from_cache {
+      info!(
+        "Already processed with these settings; reusing the saved results (pass --force to redo)"
+      );
+    }
+
+    assistant.save_results(&result, &output_dir).await?;
+    info!("Results saved to: {}", output_dir);
+    let report_path = match self.run.report {
+      Some(format) => Some(
+        assistant
+          .save_results_report(&result, &output_dir, format)
+          .await?,
+      ),
+      None => None,
+    };
+
+    log_summary(&result, &output_dir);
+    Ok(paper_summary(&result, &output_dir, report_path.as_deref()))
+  }
+
+  /// One paper is processed in the output directory; several as a batch,
+  /// each in its own subdirectory with an `index.md` linking them
+  async fn run(&self, urls: Vec<String>) -> AgentResult<Value> {
+    match urls.len() {
+      0 => Err("Give the arXiv URL or ID of at least one paper".into()),
+      1 => self.execute(&urls[0]).await,
+      _ => {
+        if self.run.resume {
+          warn!("--resume continues a single paper; processing the batch from the start");
+        }
+        let assistant = self.assistant.lock().await;
+        let output_dir = assistant.config().output_directory.clone();
+        let results = assistant.process_papers(&urls, self.run.concurrency).await;
+        let index_path = assistant
+          .save_batch_results(&urls, &results, &output_dir)
+          .await?;
+
+        for result in results
+          .iter()
+          .flatten()
+          .filter(|result| !result.stage_metrics.is_empty())
+        {
+          println!(
+            "\nStage metrics, {}:\n{}",
+            result.paper_id,
+            stage_metrics_table(&result.stage_metrics)
+          );
+        }
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        info!(
+          "Processed {} of {} papers; index: {}",
+          urls.len() - failed,
+          urls.len(),
+          index_path
+        );
+        if failed > 0 {
+          return Err(
+            format!(
+              "{} of {} papers failed; see {}",
+              failed,
+              urls.len(),
+              index_path
             )
-        )
-    )
-    .subcommand(
-      Command::new("examples")
-        .about("Show usage examples")
-    )
-}
+            .into(),
+          );
+        }
+        let papers: Vec<Value> = results
+          .iter()
+          .flatten()
+          .map(|result| json!({ "paper_id": result.paper_id, "original_url": result.original_url }))
+          .collect();
+        Ok(json!({ "papers": papers, "index": index_path }))
+      }
+    }
+  }
 
-/// Handle paper processing command
-async fn process_paper_command(matches: &ArgMatches) -> Result<()> {
-  let url = matches.get_one::<String>("url").unwrap();
-  let output_dir = matches.get_one::<String>("output").unwrap();
+  /// Report how much the stage cache saved
+  async fn shutdown(&self) -> AgentResult<()> {
+    let assistant = self.assistant.lock().await;
+    let cache = assistant.stage_cache();
+    for (stage, stats) in cache.stage_stats() {
+      info!("Stage cache, {}: {}", stage, stats);
+    }
+    if cache.control() != CacheControl::Off {
+      info!("Stage cache ({}): {}", cache.dir().display(), cache.stats());
+    }
+    Ok(())
+  }
 
-  info!("Starting paper processing for: {}", url);
-  info!("Output directory: {}", output_dir);
+  /// The defaults, with the `QWEN_*_MODEL` and `PAPER_ASSISTANT_*`
+  /// variables `ConfigBuilder::from_env` reads
+  fn default_config() -> Self::Config {
+    let config = ConfigBuilder::new().from_env().build().unwrap_or_else(|e| {
+      warn!("Ignoring the environment configuration: {}", e);
+      PaperAssistantConfig::default()
+    });
+    config.into()
+  }
 
-  // Load or create configuration
-  let config = if let Some(config_path) = matches.get_one::<String>("config") {
-    info!("Loading configuration from: {}", config_path);
-    PaperAssistantConfig::from_json_file(config_path)?
-  } else {
-    // Create configuration based on command line flags
-    let mut config = if matches.get_flag("fast") {
+  fn config_presets() -> Vec<(&'static str, Self::Config)> {
+    vec![
+      ("default", PaperAssistantConfig::default().into()),
+      ("fast", PaperAssistantConfig::fast_processing().into()),
+      (
+        "comprehensive",
+        PaperAssistantConfig::comprehensive_analysis().into(),
+      ),
+    ]
+  }
+
+  fn run_args() -> Vec<Arg> {
+    vec![
+      Arg::new("output")
+        .short('o')
+        .long("output")
+        .help("Output directory for results"),
+      Arg::new("fast")
+        .long("fast")
+        .help("Use fast processing mode (skip image generation)")
+        .action(ArgAction::SetTrue)
+        .conflicts_with("comprehensive"),
+      Arg::new("comprehensive")
+        .long("comprehensive")
+        .help("Use comprehensive analysis mode")
+        .action(ArgAction::SetTrue),
+      Arg::new("no-mindmaps")
+        .long("no-mindmaps")
+        .help("Skip mind map generation")
+        .action(ArgAction::SetTrue),
+      Arg::new("no-poster")
+        .long("no-poster")
+        .help("Skip poster generation")
+        .action(ArgAction::SetTrue),
+      Arg::new("poster-style")
+        .long("poster-style")
+        .help("Poster style preset")
+        .value_parser(
+          PossibleValuesParser::new(PosterStyle::ALL.map(PosterStyle::as_str))
+            .map(|style| style.parse::<PosterStyle>().unwrap_or_default()),
+        ),
+      Arg::new("max-sections")
+        .long("max-sections")
+        .help("Maximum number of sections for mind mapping")
+        .value_parser(clap::value_parser!(usize)),
+      Arg::new("languages")
+        .long("languages")
+        .help("Comma-separated language codes to summarize and translate into, primary first (e.g. en,zh)")
+        .value_delimiter(','),
+      Arg::new("report")
+        .long("report")
+        .help("Also save everything as one report document")
+        .value_parser(["html", "pdf"]),
+      Arg::new("force")
+        .long("force")
+        .help("Process the paper even if the output directory already has it with the same settings")
+        .action(ArgAction::SetTrue),
+      Arg::new("resume")
+        .long("resume")
+        .help("Reuse the stage outputs a failed run saved in the output directory and run only the missing stages")
+        .action(ArgAction::SetTrue),
+      Arg::new("no-cache")
+        .long("no-cache")
+        .help("Neither reuse nor save summaries and translations in the stage cache")
+        .action(ArgAction::SetTrue),
+      Arg::new("enrich")
+        .long("enrich")
+        .help("Add citation counts, fields of study and related papers from Semantic Scholar")
+        .action(ArgAction::SetTrue),
+      Arg::new("pricing")
+        .long("pricing")
+        .value_name("FILE")
+        .help("Pricing table (pricing.yml) to report each stage's cost from"),
+      Arg::new("concurrency")
+        .long("concurrency")
+        .help("Papers processed at once when several are given")
+        .value_parser(clap::value_parser!(usize))
+        .default_value("1"),
+    ]
+  }
+
+  fn apply_run_args(config: &mut Self::Config, matches: &ArgMatches) -> AgentResult<()> {
+    let assistant = &mut config.assistant;
+    if matches.get_flag("fast") {
       info!("Using fast processing mode");
-      PaperAssistantConfig::fast_processing()
+      apply_mode(assistant, PaperAssistantConfig::fast_processing())?;
     } else if matches.get_flag("comprehensive") {
       info!("Using comprehensive analysis mode");
-      PaperAssistantConfig::comprehensive_analysis()
-    } else {
-      ConfigBuilder::new().from_env().build()?
-    };
-
-    // Override output directory
-    config.output_directory = output_dir.clone();
+      apply_mode(assistant, PaperAssistantConfig::comprehensive_analysis())?;
+    }
 
-    // Apply command line flags
+    if let Some(output_dir) = matches.get_one::<String>("output") {
+      assistant.output_directory = output_dir.clone();
+    }
     if matches.get_flag("no-mindmaps") {
-      config.enable_mind_maps = false;
+      assistant.enable_mind_maps = false;
       info!("Mind map generation disabled");
     }
-
     if matches.get_flag("no-poster") {
-      config.enable_poster_generation = false;
+      assistant.enable_poster_generation = false;
       info!("Poster generation disabled");
     }
-
+    if let Some(style) = matches.get_one::<PosterStyle>("poster-style") {
+      assistant.poster_style = *style;
+      info!("Poster style: {}", style);
+    }
     if let Some(max_sections) = matches.get_one::<usize>("max-sections") {
-      config.max_sections_for_mind_maps = Some(*max_sections);
+      assistant.max_sections_for_mind_maps = Some(*max_sections);
       info!("Maximum sections for mind mapping set to: {}", max_sections);
     }
+    if matches.get_flag("no-cache") {
+      assistant.cache = CacheControl::Off;
+    }
+    if matches.get_flag("enrich") {
+      assistant.enable_enrichment = true;
+    }
+    if let Some(pricing) = matches.get_one::<String>("pricing") {
+      assistant.pricing_file = Some(pricing.clone());
+    }
+    if let Some(languages) = matches.get_many::<String>("languages") {
+      assistant.languages = languages
+        .map(|language| language.trim().to_string())
+        .collect();
+      info!("Output languages: {}", assistant.languages.join(", "));
+    }
 
-    config
-  };
-
-  // Validate configuration
-  config.validate().map_err(|e| anyhow::anyhow!("Configuration error: {}", e))?;
-
-  // Create and run paper assistant
-  let mut assistant = PaperAssistant::with_config(config)?;
-
-  info!("Processing paper...");
-  match assistant.process_paper(url).await {
-    Ok(result) => {
-      info!("Paper processing completed successfully!");
-      info!("Paper ID: {}", result.paper_id);
-      info!("Processing time: {}ms", result.processing_time_ms);
-      info!("Sections with mind maps: {}", result.mind_maps.len());
-
-      if result.poster_image_path.is_some() {
-        info!("Poster image generated");
-      }
+    config.run = RunOptions {
+      force: matches.get_flag("force"),
+      resume: matches.get_flag("resume"),
+      report: matches
+        .get_one::<String>("report")
+        .map(|format| ReportFormat::parse(format))
+        .transpose()?,
+      concurrency: matches
+        .get_one::<usize>("concurrency")
+        .copied()
+        .unwrap_or(1),
+    };
+    Ok(())
+  }
+}
 
-      // Save results to files
-      assistant.save_results(&result, output_dir).await?;
-      info!("Results saved to: {}", output_dir);
-
-      // Print summary
-      println!("\n=== Paper Processing Summary ===");
-      println!("Paper ID: {}", result.paper_id);
-      println!("Original URL: {}", result.original_url);
-      println!("Processing time: {}ms", result.processing_time_ms);
-      println!("Chinese summary generated: ✓");
-      println!("Chinese translation generated: ✓");
-      println!("Mind maps created: {}", result.mind_maps.len());
-      if result.poster_image_path.is_some() {
-        println!("Poster image generated: ✓");
-      } else {
-        println!("Poster image generated: ✗");
-      }
-      println!("Output directory: {}", output_dir);
-      println!("\nProcessing completed successfully!");
-    },
-    Err(e) => {
-      error!("Paper processing failed: {}", e);
-      println!("Error: {}", e);
-      
-      // Try to save partial results if available
-      if let Some(shared_state) = Some(assistant.shared_state()) {
-        if !shared_state.is_empty() {
-          warn!("Attempting to save partial results...");
-          let partial_output_dir = format!("{}/partial_results", output_dir);
-          
-          // Create output directory
-          if let Err(e) = tokio::fs::create_dir_all(&partial_output_dir).await {
-            error!("Failed to create partial results directory: {}", e);
-          } else {
-            // Save shared state as JSON for debugging
-            let debug_path = format!("{}/debug_state.json", partial_output_dir);
-            if let Ok(json_content) = serde_json::to_string_pretty(&shared_state.clone()) {
-              if let Err(e) = tokio::fs::write(&debug_path, json_content).await {
-                error!("Failed to save debug state: {}", e);
-              } else {
-                info!("Debug state saved to: {}", debug_path);
-              }
-            }
-          }
-        }
+/// Apply the settings in which `mode` differs from the defaults, keeping
+/// the rest of `config`
+fn apply_mode(config: &mut PaperAssistantConfig, mode: PaperAssistantConfig) -> AgentResult<()> {
+  let defaults = serde_json::to_value(PaperAssistantConfig::default())?;
+  let mut value = serde_json::to_value(&*config)?;
+  if let (Value::Object(defaults), Value::Object(mode), Some(value)) =
+    (defaults, serde_json::to_value(mode)?, value.as_object_mut())
+  {
+    for (key, setting) in mode {
+      if defaults.get(&key) != Some(&setting) {
+        value.insert(key, setting);
       }
-      
-      std::process::exit(1);
     }
   }
-
+  *config = serde_json::from_value(value)?;
   Ok(())
 }
 
-/// Handle configuration commands
-async fn config_command(matches: &ArgMatches) -> Result<()> {
-  match matches.subcommand() {
-    Some(("show", _)) => {
-      println!("=== Default Paper Assistant Configuration ===");
-      let config = PaperAssistantConfig::default();
-      let json_output = serde_json::to_string_pretty(&config)?;
-      println!("{}", json_output);
-    },
-    Some(("create", sub_matches)) => {
-      let output_path = sub_matches.get_one::<String>("output").unwrap();
-      let config_type = sub_matches.get_one::<String>("type").unwrap();
-
-      let config = match config_type.as_str() {
-        "fast" => PaperAssistantConfig::fast_processing(),
-        "comprehensive" => PaperAssistantConfig::comprehensive_analysis(),
-        _ => PaperAssistantConfig::default(),
-      };
-
-      config.to_json_file(output_path)?;
-      println!("Configuration file created at: {}", output_path);
-      println!("Type: {}", config_type);
-    },
-    _ => {
-      println!("Use 'config show' or 'config create' subcommands");
+/// Save the shared state of a failed run for debugging
+async fn save_partial_state(assistant: &PaperAssistant, output_dir: &str) {
+  let shared_state = assistant.shared_state();
+  if shared_state.is_empty() {
+    return;
+  }
+  warn!("Attempting to save partial results...");
+  let partial_output_dir = format!("{}/partial_results", output_dir);
+  if let Err(e) = tokio::fs::create_dir_all(&partial_output_dir).await {
+    error!("Failed to create partial results directory: {}", e);
+    return;
+  }
+  let debug_path = format!("{}/debug_state.json", partial_output_dir);
+  if let Ok(json_content) = serde_json::to_string_pretty(&shared_state.snapshot()) {
+    match tokio::fs::write(&debug_path, json_content).await {
+      Ok(()) => info!("Debug state saved to: {}", debug_path),
+      Err(e) => error!("Failed to save debug state: {}", e),
     }
   }
-
-  Ok(())
 }
 
-/// Show usage examples
-fn show_examples() {
-  println!("=== Paper Assistant Usage Examples ===\n");
-
-  println!("1. Basic paper processing:");
-  println!("   paper-assistant process https://arxiv.org/abs/2312.07104\n");
-
-  println!("2. Process with custom output directory:");
-  println!("   paper-assistant process 2312.07104 -o ./my_output\n");
-
-  println!("3. Fast processing mode (skip image generation):");
-  println!("   paper-assistant process https://arxiv.org/abs/2312.07104 --fast\n");
-
-  println!("4. Comprehensive analysis mode:");
-  println!("   paper-assistant process 2312.07104 --comprehensive\n");
-
-  println!("5. Skip mind maps generation:");
-  println!("   paper-assistant process 2312.07104 --no-mindmaps\n");
-
-  println!("6. Limit sections for mind mapping:");
-  println!("   paper-assistant process 2312.07104 --max-sections 5\n");
-
-  println!("7. Use custom configuration file:");
-  println!("   paper-assistant process 2312.07104 -c my-config.json\n");
-
-  println!("8. Create custom configuration:");
-  println!("   paper-assistant config create -t comprehensive -o my-config.json\n");
+fn log_summary(result: &PaperProcessingResult, output_dir: &str) {
+  info!("Paper processing completed successfully!");
+  info!("Paper ID: {}", result.paper_id);
+  info!("Processing time: {}ms", result.processing_time_ms);
+  for language in result.outputs.keys() {
+    info!("Summary and translation ({}): ✓", language);
+  }
+  info!("Mind maps created: {}", result.mind_maps.len());
+  for failure in &result.mind_map_failures {
+    warn!(
+      "Mind map failed for section {} ({}): {}",
+      failure.section_index, failure.section_title, failure.error
+    );
+  }
+  info!(
+    "Poster image generated: {}",
+    if result.poster_image_path.is_some() {
+      "✓"
+    } else {
+      "✗"
+    }
+  );
+  if let Some(reason) = &result.poster_filter_reason {
+    warn!(
+      "Poster drawn from a simplified prompt after the content filter rejected the full one: {}",
+      reason
+    );
+  }
+  info!("Output directory: {}", output_dir);
+  if !result.stage_metrics.is_empty() {
+    println!(
+      "\nStage metrics:\n{}",
+      stage_metrics_table(&result.stage_metrics)
+    );
+  }
+}
 
-  println!("9. Show default configuration:");
-  println!("   paper-assistant config show\n");
+/// What `run` prints for one paper
+fn paper_summary(
+  result: &PaperProcessingResult,
+  output_dir: &str,
+  report_path: Option<&str>,
+) -> Value {
+  json!({
+    "paper_id": result.paper_id,
+    "original_url": result.original_url,
+    "processing_time_ms": result.processing_time_ms,
+    "from_cache": result.from_cache,
+    "languages": result.outputs.keys().collect::<Vec<_>>(),
+    "mind_maps": result.mind_maps.len(),
+    "mind_map_failures": result.mind_map_failures.len(),
+    "poster_image_path": result.poster_image_path,
+    "poster_filter_reason": result.poster_filter_reason,
+    "stage_metrics": result.stage_metrics,
+    "output_directory": output_dir,
+    "report": report_path,
+  })
+}
 
-  println!("=== Environment Variables ===");
-  println!("QWEN_TURBO_MODEL      - Override Qwen turbo model name");
-  println!("QWEN_IMAGE_MODEL      - Override Qwen image model name");
-  println!("PAPER_ASSISTANT_OUTPUT_DIR - Default output directory");
-  println!("PAPER_ASSISTANT_TEMPERATURE - LLM temperature (0.0-2.0)");
-  println!("PAPER_ASSISTANT_MAX_TOKENS  - Maximum tokens per request");
-  println!("DASHSCOPE_API_KEY     - Required for Qwen models");
-  println!("RUST_LOG             - Set to 'debug' for verbose logging\n");
+/// Renders the pipeline stages as a checklist on stdout
+struct ChecklistReporter;
 
-  println!("=== Supported arXiv URL Formats ===");
-  println!("- https://arxiv.org/abs/2312.07104");
-  println!("- https://arxiv.org/abs/2312.07104v2");
-  println!("- https://arxiv.org/pdf/2312.07104.pdf");
-  println!("- 2312.07104");
-  println!("- 2312.07104v2");
+impl ProgressReporter for ChecklistReporter {
+  fn report(&self, event: &ProgressEvent) {
+    match event {
+      ProgressEvent::ArxivFetchStarted { url } => println!("[ ] Fetching paper: {}", url),
+      ProgressEvent::ArxivFetchFinished { paper_id } => println!("[✓] Fetched paper {}", paper_id),
+      ProgressEvent::SummaryStarted { language } => {
+        println!("[ ] Generating {} summary...", language)
+      }
+      ProgressEvent::SummaryFinished {
+        language,
+        prompt_tokens,
+        completion_tokens,
+      } => match (prompt_tokens, completion_tokens) {
+        (Some(prompt), Some(completion)) => println!(
+          "[✓] {} summary generated ({} prompt + {} completion tokens)",
+          language, prompt, completion
+        ),
+        _ => println!("[✓] {} summary generated", language),
+      },
+      ProgressEvent::TranslationProgress {
+        language,
+        chunk,
+        total,
+      } => {
+        let mark = if chunk == total { "✓" } else { " " };
+        println!(
+          "[{}] Translating into {}: chunk {}/{}",
+          mark, language, chunk, total
+        );
+      }
+      ProgressEvent::MindMapGenerated { section } => println!("[✓] Mind map: {}", section),
+      ProgressEvent::PosterStarted => println!("[ ] Generating poster image..."),
+      ProgressEvent::PosterFinished { image_path } => match image_path {
+        Some(path) => println!("[✓] Poster image: {}", path),
+        None => println!("[✗] Poster image not generated"),
+      },
+    }
+  }
 }
 
 /// Print application banner
 #[allow(dead_code)]
 fn print_banner() {
-  println!(r#"
- ____                        _                _     _              _   
-|  _ \ __ _ _ __   ___ _ __   / \   ___ ___(_)___| |_ __ _ _ __ | |_ 
+  println!(
+    r#"
+ ____                        _                _     _              _
+|  _ \ __ _ _ __   ___ _ __   / \   ___ ___(_)___| |_ __ _ _ __ | |_
 | |_) / _` | '_ \ / _ \ '__| / _ \ / __/ __| / __| __/ _` | '_ \| __|
-|  __/ (_| | |_) |  __/ |   / ___ \\__ \__ \ \__ \ || (_| | | | | |_ 
+|  __/ (_| | |_) |  __/ |   / ___ \\__ \__ \ \__ \ || (_| | | | | |_
 |_|   \__,_| .__/ \___|_|  /_/   \_\___/___/_|___/\__\__,_|_| |_|\__|
-           |_|                                                      
+           |_|
 
 AI Agent for arXiv Paper Processing with Chinese Translation & Mind Mapping
 Version 0.1.0
-"#);
+"#
+  );
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use agentflow_agents::{CliEnv, agent_command, load_agent_config};
 
   #[test]
   fn test_cli_app_creation() {
-    let app = create_cli_app();
+    let app = agent_command::<PaperAssistantApp>();
     assert_eq!(app.get_name(), "paper-assistant");
-    
+
     // Test that required subcommands exist
-    let subcommands: Vec<&str> = app.get_subcommands()
-      .map(|cmd| cmd.get_name())
-      .collect();
-    
-    assert!(subcommands.contains(&"process"));
+    let subcommands: Vec<&str> = app.get_subcommands().map(|cmd| cmd.get_name()).collect();
+
+    assert!(subcommands.contains(&"run"));
     assert!(subcommands.contains(&"config"));
-    assert!(subcommands.contains(&"examples"));
+    assert!(subcommands.contains(&"version"));
+  }
+
+  #[test]
+  fn test_run_flags_layer_over_the_config() {
+    let matches = agent_command::<PaperAssistantApp>()
+      .try_get_matches_from([
+        "paper-assistant",
+        "run",
+        "2312.07104",
+        "--set",
+        "temperature=0.5",
+        "--fast",
+        "-o",
+        "./out",
+        "--no-mindmaps",
+        "--languages",
+        "en,zh",
+        "--report",
+        "html",
+        "--pricing",
+        "pricing.yml",
+        "--enrich",
+      ])
+      .unwrap();
+    let (_, run) = matches.subcommand().unwrap();
+    let sets = vec!["temperature=0.5".to_string()];
+    let config = load_agent_config::<PaperAssistantApp>(None, &sets, &CliEnv::default(), Some(run))
+      .unwrap()
+      .config;
+
+    // --fast wins over --set for the settings it changes
+    assert_eq!(config.assistant.temperature, Some(0.1));
+    assert!(!config.assistant.enable_poster_generation);
+    assert!(!config.assistant.enable_mind_maps);
+    assert_eq!(config.assistant.output_directory, "./out");
+    assert_eq!(config.assistant.languages, ["en", "zh"]);
+    assert_eq!(
+      config.assistant.pricing_file.as_deref(),
+      Some("pricing.yml")
+    );
+    assert!(config.assistant.enable_enrichment);
+    assert_eq!(config.run.report, Some(ReportFormat::Html));
+    assert_eq!(config.run.concurrency, 1);
+  }
+
+  #[test]
+  fn test_poster_style_flag() {
+    let run_config = |args: &[&str]| {
+      let matches = agent_command::<PaperAssistantApp>()
+        .try_get_matches_from([&["paper-assistant", "run", "2312.07104"], args].concat())?;
+      let (_, run) = matches.subcommand().unwrap();
+      Ok::<_, clap::Error>(
+        load_agent_config::<PaperAssistantApp>(None, &[], &CliEnv::default(), Some(run))
+          .unwrap()
+          .config,
+      )
+    };
+
+    assert_eq!(
+      run_config(&[]).unwrap().assistant.poster_style,
+      PosterStyle::Academic
+    );
+    assert_eq!(
+      run_config(&["--poster-style", "cyberpunk"])
+        .unwrap()
+        .assistant
+        .poster_style,
+      PosterStyle::Cyberpunk
+    );
+    assert!(run_config(&["--poster-style", "vaporwave"]).is_err());
   }
 
   #[tokio::test]
@@ -381,4 +622,4 @@ mod tests {
     assert!(comprehensive.enable_mind_maps);
     assert_eq!(comprehensive.max_sections_for_mind_maps, Some(15));
   }
-}
\ No newline at end of file
+}
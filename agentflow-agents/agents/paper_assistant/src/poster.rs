@@ -0,0 +1,437 @@
+//! Poster prompt synthesis
+//!
+//! The poster is drawn from a prompt composed out of what the paper found
+//! rather than its raw summary: `PosterInsights` pulls the title, the
+//! first three key findings and the arXiv field out of the summary and the
+//! fetched metadata, and `PosterPromptBuilder` renders them into the poster
+//! prompt template together with a `PosterStyle` preset and the poster's
+//! aspect. When the image model's content filter rejects that prompt, the
+//! workflow tries once more with `PosterPromptBuilder::build_simplified`,
+//! which leaves the paper's text out.
+
+use crate::config::fill_placeholders;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How many key findings a poster shows
+pub const POSTER_KEY_FINDINGS: usize = 3;
+
+/// The look of the poster
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PosterStyle {
+  #[default]
+  Academic,
+  Infographic,
+  Minimalist,
+  Cyberpunk,
+}
+
+impl PosterStyle {
+  pub const ALL: [PosterStyle; 4] = [
+    PosterStyle::Academic,
+    PosterStyle::Infographic,
+    PosterStyle::Minimalist,
+    PosterStyle::Cyberpunk,
+  ];
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      PosterStyle::Academic => "academic",
+      PosterStyle::Infographic => "infographic",
+      PosterStyle::Minimalist => "minimalist",
+      PosterStyle::Cyberpunk => "cyberpunk",
+    }
+  }
+
+  /// What the preset asks of the image model
+  pub fn description(self) -> &'static str {
+    match self {
+      PosterStyle::Academic => {
+        "clean, modern academic conference poster, white background, muted blue accents, \
+         clear section panels and professional typography"
+      }
+      PosterStyle::Infographic => {
+        "bold infographic with flat icons, charts and arrows linking the findings, \
+         bright contrasting colors and large numbered callouts"
+      }
+      PosterStyle::Minimalist => {
+        "minimalist design with generous white space, a single accent color, \
+         thin lines and very little text"
+      }
+      PosterStyle::Cyberpunk => {
+        "cyberpunk style, dark background with neon magenta and cyan glow, \
+         futuristic circuit patterns and glitch accents"
+      }
+    }
+  }
+}
+
+impl fmt::Display for PosterStyle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl FromStr for PosterStyle {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    PosterStyle::ALL
+      .into_iter()
+      .find(|style| style.as_str().eq_ignore_ascii_case(s.trim()))
+      .ok_or_else(|| {
+        let names: Vec<_> = PosterStyle::ALL
+          .iter()
+          .map(|style| style.as_str())
+          .collect();
+        format!(
+          "Unknown poster style '{}' (expected one of: {})",
+          s,
+          names.join(", ")
+        )
+      })
+  }
+}
+
+/// The width and height of a `WIDTHxHEIGHT` (or `WIDTH*HEIGHT`) size
+pub fn parse_poster_size(size: &str) -> Option<(u32, u32)> {
+  let (width, height) = size.split_once(['x', 'X', '*'])?;
+  Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+/// The orientation a `WIDTHxHEIGHT` size gives the poster, as prompts
+/// describe it; an unparsable size counts as square
+pub fn poster_aspect(size: &str) -> &'static str {
+  match parse_poster_size(size) {
+    Some((width, height)) if width > height => "landscape",
+    Some((width, height)) if width < height => "portrait",
+    _ => "square",
+  }
+}
+
+/// What a poster is about, pulled out of the paper's summary and metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PosterInsights {
+  pub title: String,
+  /// At most [`POSTER_KEY_FINDINGS`]
+  pub key_findings: Vec<String>,
+  /// The paper's field, e.g. "Artificial Intelligence"
+  pub domain: Option<String>,
+}
+
+impl PosterInsights {
+  /// The findings listed under the summary's findings heading (主要发现,
+  /// Key Findings, Results, ...), or its first lines when it has none
+  pub fn from_summary(title: &str, summary: &str, domain: Option<String>) -> Self {
+    let mut key_findings = findings_section(summary);
+    if key_findings.is_empty() {
+      key_findings = summary
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(strip_list_marker)
+        .collect();
+    }
+    key_findings.truncate(POSTER_KEY_FINDINGS);
+    Self {
+      title: title.to_string(),
+      key_findings,
+      domain,
+    }
+  }
+}
+
+/// Headings a summary lists its findings under
+const FINDINGS_HEADINGS: [&str; 6] = [
+  "主要发现",
+  "关键发现",
+  "key findings",
+  "main findings",
+  "findings",
+  "results",
+];
+
+/// The list items (or paragraphs) under the first findings heading
+fn findings_section(summary: &str) -> Vec<String> {
+  let mut lines = summary.lines().map(str::trim);
+  let found = lines.by_ref().any(|line| {
+    let heading = line
+      .trim_start_matches('#')
+      .trim()
+      .trim_end_matches([':', '：'])
+      .to_lowercase();
+    line.starts_with('#') && FINDINGS_HEADINGS.iter().any(|name| heading.contains(name))
+  });
+  if !found {
+    return Vec::new();
+  }
+  lines
+    .take_while(|line| !line.starts_with('#'))
+    .filter(|line| !line.is_empty())
+    .map(strip_list_marker)
+    .filter(|finding| !finding.is_empty())
+    .collect()
+}
+
+/// The line without a leading `-`, `*`, `1.` or `1)` marker
+fn strip_list_marker(line: &str) -> String {
+  let line = line.trim_start_matches(['-', '*', '•']).trim_start();
+  let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+  let rest = &line[digits..];
+  let rest = match rest.strip_prefix(['.', ')', '、']) {
+    Some(rest) if digits > 0 => rest,
+    _ => line,
+  };
+  rest.trim().to_string()
+}
+
+/// The field an arXiv category such as `cs.AI` belongs to
+pub fn arxiv_domain(category: &str) -> String {
+  let name = match category {
+    "cs.AI" => "Artificial Intelligence",
+    "cs.CL" => "Computation and Language",
+    "cs.CV" => "Computer Vision",
+    "cs.LG" | "stat.ML" => "Machine Learning",
+    "cs.RO" => "Robotics",
+    "cs.CR" => "Cryptography and Security",
+    "cs.DB" => "Databases",
+    "cs.DC" => "Distributed Computing",
+    "cs.IR" => "Information Retrieval",
+    "cs.NE" => "Neural and Evolutionary Computing",
+    "cs.PL" => "Programming Languages",
+    "cs.SE" => "Software Engineering",
+    _ => match category.split('.').next().unwrap_or(category) {
+      "cs" => "Computer Science",
+      "math" => "Mathematics",
+      "stat" => "Statistics",
+      "eess" => "Electrical Engineering and Systems Science",
+      "econ" => "Economics",
+      "q-bio" => "Quantitative Biology",
+      "q-fin" => "Quantitative Finance",
+      "physics" | "astro-ph" | "cond-mat" | "hep-th" | "hep-ph" | "quant-ph" | "gr-qc" => "Physics",
+      _ => return category.to_string(),
+    },
+  };
+  name.to_string()
+}
+
+/// Composes the poster prompt from [`PosterInsights`], a style preset and
+/// the poster size.
+///
+/// The template's placeholders are `{{paper_title}}`, `{{key_findings}}`
+/// (a numbered list), `{{domain}}`, `{{style}}` (the preset's
+/// description), `{{aspect}}` and `{{summary}}` (the full summary, for
+/// templates written before the findings were pulled out).
+#[derive(Debug, Clone)]
+pub struct PosterPromptBuilder {
+  template: String,
+  insights: PosterInsights,
+  summary: String,
+  style: PosterStyle,
+  size: String,
+}
+
+impl PosterPromptBuilder {
+  pub fn new(template: &str, insights: PosterInsights) -> Self {
+    Self {
+      template: template.to_string(),
+      insights,
+      summary: String::new(),
+      style: PosterStyle::default(),
+      size: "1024x1024".to_string(),
+    }
+  }
+
+  pub fn with_summary(mut self, summary: &str) -> Self {
+    self.summary = summary.to_string();
+    self
+  }
+
+  pub fn with_style(mut self, style: PosterStyle) -> Self {
+    self.style = style;
+    self
+  }
+
+  /// `WIDTHxHEIGHT`, e.g. `1024x1536`
+  pub fn with_size(mut self, size: &str) -> Self {
+    self.size = size.to_string();
+    self
+  }
+
+  /// The template with every placeholder filled in
+  pub fn build(&self) -> String {
+    let key_findings = self
+      .insights
+      .key_findings
+      .iter()
+      .enumerate()
+      .map(|(i, finding)| format!("{}. {}", i + 1, finding))
+      .collect::<Vec<_>>()
+      .join("\n");
+    fill_placeholders(
+      &self.template,
+      &[
+        ("paper_title", &self.insights.title),
+        ("key_findings", &key_findings),
+        ("domain", self.domain()),
+        ("style", self.style.description()),
+        ("aspect", poster_aspect(&self.size)),
+        ("summary", &self.summary),
+        // The name poster prompts used before `languages` existed
+        ("chinese_summary", &self.summary),
+      ],
+    )
+  }
+
+  /// A prompt with none of the paper's text but its title, for a second
+  /// try after the content filter rejected [`Self::build`]
+  pub fn build_simplified(&self) -> String {
+    format!(
+      "A {} poster for a research paper in {} titled \"{}\". Abstract geometric shapes and \
+       diagrams only, no people and no text besides the title. Style: {}",
+      poster_aspect(&self.size),
+      self.domain(),
+      self.insights.title,
+      self.style.description()
+    )
+  }
+
+  fn domain(&self) -> &str {
+    self.insights.domain.as_deref().unwrap_or("science")
+  }
+}
+
+/// Messages image services send when their content filter rejects a
+/// prompt or an image
+const CONTENT_FILTER_MARKERS: [&str; 6] = [
+  "content_filtered",
+  "content filter",
+  "content_policy",
+  "datainspectionfailed",
+  "inappropriate content",
+  "safety system",
+];
+
+/// Why the content filter rejected a generation, when `error` says it did
+pub fn content_filter_reason(error: &anyhow::Error) -> Option<String> {
+  let message = format!("{:#}", error);
+  let lower = message.to_lowercase();
+  CONTENT_FILTER_MARKERS
+    .iter()
+    .any(|marker| lower.contains(marker))
+    .then_some(message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SUMMARY: &str = "# 研究论文摘要\n\n## 摘要总结\n提出了一种新方法。\n\n## 主要发现\n1. 准确率提升 12%\n2. 推理速度快两倍\n3. 显存减半\n4. 泛化到新任务\n\n## 结论\n方法有效。";
+
+  fn builder(style: PosterStyle) -> PosterPromptBuilder {
+    let insights =
+      PosterInsights::from_summary("Fast Attention", SUMMARY, Some(arxiv_domain("cs.LG")));
+    PosterPromptBuilder::new(
+      "{{paper_title}} | {{domain}} | {{aspect}}\n{{key_findings}}\nStyle: {{style}}",
+      insights,
+    )
+    .with_style(style)
+    .with_size("1024x1536")
+  }
+
+  #[test]
+  fn test_insights_take_three_findings_from_the_summary() {
+    let insights = PosterInsights::from_summary("Fast Attention", SUMMARY, None);
+    assert_eq!(
+      insights.key_findings,
+      ["准确率提升 12%", "推理速度快两倍", "显存减半"]
+    );
+
+    let english = PosterInsights::from_summary(
+      "T",
+      "## Key Findings:\n- Faster\n- Smaller\n## Limits\n- None",
+      None,
+    );
+    assert_eq!(english.key_findings, ["Faster", "Smaller"]);
+
+    // Without a findings heading the summary's first lines stand in
+    let plain = PosterInsights::from_summary("T", "# Title\nOne.\n\nTwo.", None);
+    assert_eq!(plain.key_findings, ["One.", "Two."]);
+  }
+
+  #[test]
+  fn test_prompt_assembly_per_preset() {
+    for style in PosterStyle::ALL {
+      let prompt = builder(style).build();
+      assert_eq!(
+        prompt,
+        format!(
+          "Fast Attention | Machine Learning | portrait\n1. 准确率提升 12%\n2. 推理速度快两倍\n3. 显存减半\nStyle: {}",
+          style.description()
+        )
+      );
+    }
+    assert!(builder(PosterStyle::Cyberpunk).build().contains("neon"));
+    assert!(
+      builder(PosterStyle::Minimalist)
+        .build_simplified()
+        .contains("white space")
+    );
+  }
+
+  #[test]
+  fn test_simplified_prompt_leaves_the_findings_out() {
+    let simplified = builder(PosterStyle::Academic).build_simplified();
+    assert!(simplified.starts_with(
+      "A portrait poster for a research paper in Machine Learning titled \"Fast Attention\""
+    ));
+    assert!(!simplified.contains("准确率"));
+  }
+
+  #[test]
+  fn test_legacy_summary_placeholders() {
+    let insights = PosterInsights::from_summary("T", SUMMARY, None);
+    let prompt = PosterPromptBuilder::new("{{chinese_summary}} in {{domain}}", insights)
+      .with_summary("摘要")
+      .build();
+    assert_eq!(prompt, "摘要 in science");
+  }
+
+  #[test]
+  fn test_style_and_aspect_parsing() {
+    assert_eq!(
+      "Infographic".parse::<PosterStyle>().unwrap(),
+      PosterStyle::Infographic
+    );
+    assert!(
+      "vaporwave"
+        .parse::<PosterStyle>()
+        .unwrap_err()
+        .contains("academic, infographic")
+    );
+    assert_eq!(poster_aspect("1536x1024"), "landscape");
+    assert_eq!(poster_aspect("1024*1024"), "square");
+    assert_eq!(poster_aspect("auto"), "square");
+    assert_eq!(arxiv_domain("cs.AI"), "Artificial Intelligence");
+    assert_eq!(arxiv_domain("math.CO"), "Mathematics");
+    assert_eq!(arxiv_domain("nlin.AO"), "nlin.AO");
+  }
+
+  #[test]
+  fn test_content_filter_reason() {
+    let filtered = anyhow::anyhow!("Poster generation failed: image 0 was content_filtered");
+    assert_eq!(
+      content_filter_reason(&filtered).as_deref(),
+      Some("Poster generation failed: image 0 was content_filtered")
+    );
+    assert!(
+      content_filter_reason(&anyhow::anyhow!(
+        "DataInspectionFailed: Input data may contain inappropriate content."
+      ))
+      .is_some()
+    );
+    assert!(content_filter_reason(&anyhow::anyhow!("quota exceeded")).is_none());
+  }
+}
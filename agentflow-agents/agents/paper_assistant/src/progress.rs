@@ -0,0 +1,163 @@
+//! Progress reporting for the paper processing pipeline
+//!
+//! The workflow reports a `ProgressEvent` as each stage starts and
+//! finishes. `LogProgressReporter` (the default) writes them to the log,
+//! and `ChannelProgressReporter` forwards them to a UI over a channel.
+
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// A pipeline stage starting or finishing
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+  ArxivFetchStarted {
+    url: String,
+  },
+  ArxivFetchFinished {
+    paper_id: String,
+  },
+  /// The summary in `language` (a code such as `zh`) has started.
+  SummaryStarted {
+    language: String,
+  },
+  /// Token counts are present when the model reported usage.
+  SummaryFinished {
+    language: String,
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+  },
+  /// Chunk `chunk` of `total` has been translated into `language`
+  /// (1-based).
+  TranslationProgress {
+    language: String,
+    chunk: usize,
+    total: usize,
+  },
+  MindMapGenerated {
+    section: String,
+  },
+  PosterStarted,
+  PosterFinished {
+    image_path: Option<String>,
+  },
+}
+
+/// Receives the workflow's progress events
+pub trait ProgressReporter: Send + Sync {
+  fn report(&self, event: &ProgressEvent);
+}
+
+/// Writes each event to the log at info level
+#[derive(Debug, Default)]
+pub struct LogProgressReporter;
+
+impl ProgressReporter for LogProgressReporter {
+  fn report(&self, event: &ProgressEvent) {
+    match event {
+      ProgressEvent::ArxivFetchStarted { url } => log::info!("Fetching paper from arXiv: {}", url),
+      ProgressEvent::ArxivFetchFinished { paper_id } => log::info!("Fetched paper {}", paper_id),
+      ProgressEvent::SummaryStarted { language } => log::info!("Generating {} summary", language),
+      ProgressEvent::SummaryFinished {
+        language,
+        prompt_tokens,
+        completion_tokens,
+      } => log::info!(
+        "{} summary generated (prompt tokens: {}, completion tokens: {})",
+        language,
+        prompt_tokens.map_or("unknown".to_string(), |n| n.to_string()),
+        completion_tokens.map_or("unknown".to_string(), |n| n.to_string())
+      ),
+      ProgressEvent::TranslationProgress {
+        language,
+        chunk,
+        total,
+      } => {
+        log::info!("Translated chunk {}/{} into {}", chunk, total, language)
+      }
+      ProgressEvent::MindMapGenerated { section } => log::info!("Mind map generated: {}", section),
+      ProgressEvent::PosterStarted => log::info!("Generating poster image"),
+      ProgressEvent::PosterFinished { image_path } => match image_path {
+        Some(path) => log::info!("Poster image generated: {}", path),
+        None => log::info!("Poster generation finished without an image"),
+      },
+    }
+  }
+}
+
+/// Sends each event down an unbounded channel, for a UI to render
+#[derive(Debug, Clone)]
+pub struct ChannelProgressReporter {
+  sender: mpsc::UnboundedSender<ProgressEvent>,
+}
+
+impl ChannelProgressReporter {
+  /// A reporter and the receiving end of its channel
+  pub fn new() -> (Self, mpsc::UnboundedReceiver<ProgressEvent>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (Self { sender }, receiver)
+  }
+}
+
+impl ProgressReporter for ChannelProgressReporter {
+  fn report(&self, event: &ProgressEvent) {
+    // A closed receiver only means nobody is watching any more.
+    let _ = self.sender.send(event.clone());
+  }
+}
+
+/// The reporter the workflow holds; cheap to clone into batch tasks
+#[derive(Clone)]
+pub(crate) struct ProgressHandle(Arc<dyn ProgressReporter>);
+
+impl ProgressHandle {
+  pub(crate) fn new(reporter: Arc<dyn ProgressReporter>) -> Self {
+    Self(reporter)
+  }
+
+  pub(crate) fn report(&self, event: ProgressEvent) {
+    self.0.report(&event);
+  }
+}
+
+impl Default for ProgressHandle {
+  fn default() -> Self {
+    Self(Arc::new(LogProgressReporter))
+  }
+}
+
+impl fmt::Debug for ProgressHandle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("ProgressHandle")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn test_channel_reporter_forwards_events() {
+    let (reporter, mut receiver) = ChannelProgressReporter::new();
+    let zh = || "zh".to_string();
+    reporter.report(&ProgressEvent::SummaryStarted { language: zh() });
+    reporter.report(&ProgressEvent::TranslationProgress {
+      language: zh(),
+      chunk: 1,
+      total: 2,
+    });
+
+    assert_eq!(
+      receiver.recv().await,
+      Some(ProgressEvent::SummaryStarted { language: zh() })
+    );
+    assert_eq!(
+      receiver.recv().await,
+      Some(ProgressEvent::TranslationProgress {
+        language: zh(),
+        chunk: 1,
+        total: 2
+      })
+    );
+  }
+}
@@ -0,0 +1,268 @@
+//! Single-document reports of a processed paper
+//!
+//! `PaperAssistant::save_results_report` assembles the metadata, every
+//! language's summary and translation, the mind maps and the poster into
+//! one markdown document and renders it with `DocumentRenderNode`: a
+//! self-contained HTML page (the poster inlined, each mind map's markmap
+//! HTML inlined in an iframe), or a PDF through the node's external
+//! converter (weasyprint or a headless Chromium).
+
+use agentflow_agents::stage_metrics_table;
+use agentflow_core::async_node::{AsyncNode, AsyncNodeInputs};
+use agentflow_core::value::FlowValue;
+use agentflow_nodes::nodes::document_render::{DocumentFormat, DocumentRenderNode};
+use anyhow::Result;
+use serde_json::json;
+
+use crate::PaperProcessingResult;
+use crate::config::language_name;
+
+/// The format of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+  Html,
+  Pdf,
+}
+
+impl ReportFormat {
+  pub fn parse(name: &str) -> Result<Self> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "html" => Ok(Self::Html),
+      "pdf" => Ok(Self::Pdf),
+      other => Err(anyhow::anyhow!(
+        "Unknown report format '{}' (expected html or pdf)",
+        other
+      )),
+    }
+  }
+
+  pub fn extension(self) -> &'static str {
+    match self {
+      Self::Html => "html",
+      Self::Pdf => "pdf",
+    }
+  }
+}
+
+/// Render `result` into `<output_dir>/<file_stem>.<html|pdf>`. Relative
+/// poster paths are resolved against `output_dir`. Returns the report's
+/// path.
+pub async fn render_report(
+  result: &PaperProcessingResult,
+  output_dir: &str,
+  file_stem: &str,
+  format: ReportFormat,
+) -> Result<String> {
+  let node = DocumentRenderNode::new("paper_report", "{{ report }}")
+    .with_formats(vec![match format {
+      ReportFormat::Html => DocumentFormat::Html,
+      ReportFormat::Pdf => DocumentFormat::Pdf,
+    }])
+    .with_output_dir(output_dir)
+    .with_file_stem(file_stem)
+    .with_title(&format!("论文报告 {}", result.paper_id))
+    .with_table_of_contents(true);
+
+  let mut inputs = AsyncNodeInputs::new();
+  inputs.insert(
+    "report".to_string(),
+    FlowValue::Json(json!(report_markdown(result, format))),
+  );
+  node.execute(&inputs).await.map_err(|e| match format {
+    ReportFormat::Pdf => anyhow::anyhow!("PDF report needs an HTML-to-PDF converter: {}", e),
+    ReportFormat::Html => anyhow::anyhow!("Report rendering failed: {}", e),
+  })?;
+
+  Ok(format!(
+    "{}/{}.{}",
+    output_dir,
+    file_stem,
+    format.extension()
+  ))
+}
+
+/// The report's markdown. HTML reports embed each mind map's interactive
+/// page in an iframe; PDF reports, which cannot run it, get its outline.
+pub fn report_markdown(result: &PaperProcessingResult, format: ReportFormat) -> String {
+  let mut report = format!(
+    "# 论文报告：{}\n\n| | |\n|---|---|\n| 论文ID | {} |\n| 原始URL | {} |\n| 处理时间 | {} |\n| 处理耗时 | {} ms |\n\n",
+    result.paper_id,
+    result.paper_id,
+    result.original_url,
+    result.timestamp,
+    result.processing_time_ms
+  );
+  if !result.stage_models.is_empty() {
+    let models: Vec<String> = result
+      .stage_models
+      .iter()
+      .map(|(stage, model)| format!("{}: `{}`", stage, model))
+      .collect();
+    report.push_str(&format!("**模型:** {}\n\n", models.join(", ")));
+  }
+
+  for (language, output) in &result.outputs {
+    report.push_str(&format!(
+      "## 摘要（{}）\n\n{}\n\n",
+      language_name(language),
+      output.summary.trim()
+    ));
+  }
+  for (language, output) in &result.outputs {
+    report.push_str(&format!(
+      "## 翻译（{}）\n\n{}\n\n",
+      language_name(language),
+      output.translation.trim()
+    ));
+  }
+
+  if !result.mind_maps.is_empty() || !result.mind_map_failures.is_empty() {
+    report.push_str("## 思维导图\n\n");
+    for mind_map in &result.mind_maps {
+      let heading = match &mind_map.section_number {
+        Some(number) => format!("{} {}", number, mind_map.section_title),
+        None => mind_map.section_title.clone(),
+      };
+      report.push_str(&format!("### {}\n\n", heading));
+      match format {
+        ReportFormat::Html if !mind_map.mind_map_html.is_empty() => report.push_str(&format!(
+          "<iframe class=\"mind-map\" srcdoc=\"{}\" style=\"width: 100%; height: 480px; border: 1px solid #d1d9e0;\"></iframe>\n\n",
+          escape_attribute(&mind_map.mind_map_html)
+        )),
+        _ => report.push_str(&format!("```text\n{}\n```\n\n", mind_map.mind_map_markdown.trim())),
+      }
+    }
+    for failure in &result.mind_map_failures {
+      report.push_str(&format!(
+        "> ⚠ 第 {} 节「{}」的思维导图生成失败：{}\n\n",
+        failure.section_index, failure.section_title, failure.error
+      ));
+    }
+  }
+
+  if let Some(poster) = &result.poster_image_path {
+    let file_name = poster.rsplit(['/', '\\']).next().unwrap_or(poster);
+    report.push_str(&format!("## 海报\n\n![{}]({})\n\n", file_name, poster));
+  }
+
+  if !result.stage_metrics.is_empty() {
+    report.push_str(&format!(
+      "## 阶段统计\n\n{}",
+      stage_metrics_table(&result.stage_metrics)
+    ));
+  }
+
+  report
+}
+
+/// Escape `html` for a double-quoted attribute, newlines included, so the
+/// iframe stays on one line of the markdown's HTML block
+fn escape_attribute(html: &str) -> String {
+  let mut escaped = String::with_capacity(html.len() + html.len() / 4);
+  for c in html.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\n' => escaped.push_str("&#10;"),
+      '\r' => {}
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{LanguageOutput, MindMapFailure, MindMapResult};
+  use std::collections::BTreeMap;
+
+  fn processed_paper() -> PaperProcessingResult {
+    PaperProcessingResult {
+      paper_id: "2312.07104".to_string(),
+      original_url: "https://arxiv.org/abs/2312.07104".to_string(),
+      primary_language: "zh".to_string(),
+      outputs: BTreeMap::from([(
+        "zh".to_string(),
+        LanguageOutput {
+          summary: "本文提出一种新方法。".to_string(),
+          translation: "引言译文".to_string(),
+          glossary_violations: Vec::new(),
+        },
+      )]),
+      stage_models: BTreeMap::from([("summary".to_string(), "qwen-turbo".to_string())]),
+      enrichment: None,
+      mind_maps: vec![MindMapResult {
+        section_index: 1,
+        section_title: "引言".to_string(),
+        section_number: Some("1".to_string()),
+        mind_map_html: "<html>\n<svg id=\"markmap\"></svg>\n</html>".to_string(),
+        mind_map_markdown: "# 引言\n## 背景".to_string(),
+      }],
+      mind_map_failures: vec![MindMapFailure {
+        section_index: 2,
+        section_title: "方法".to_string(),
+        error: "timeout".to_string(),
+      }],
+      poster_image_path: Some("poster.png".to_string()),
+      poster_prompt: None,
+      poster_filter_reason: None,
+      processing_time_ms: 1200,
+      stage_metrics: vec![
+        agentflow_agents::StageMetric::new("poster", std::time::Duration::from_millis(2000))
+          .with_retries(1),
+      ],
+      timestamp: "2025-01-01T00:00:00Z".to_string(),
+      from_cache: false,
+    }
+  }
+
+  #[tokio::test]
+  async fn test_html_report_contains_every_section() {
+    let output_dir = std::env::temp_dir()
+      .join(format!("paper_assistant_report_{}", uuid::Uuid::new_v4()))
+      .to_string_lossy()
+      .into_owned();
+    std::fs::create_dir_all(&output_dir).unwrap();
+    std::fs::write(format!("{}/poster.png", output_dir), b"\x89PNG\r\n\x1a\n").unwrap();
+
+    let path = render_report(
+      &processed_paper(),
+      &output_dir,
+      "2312.07104_report",
+      ReportFormat::Html,
+    )
+    .await
+    .unwrap();
+    assert_eq!(path, format!("{}/2312.07104_report.html", output_dir));
+
+    let html = std::fs::read_to_string(&path).unwrap();
+    assert!(html.contains("论文报告：2312.07104"));
+    assert!(html.contains("https://arxiv.org/abs/2312.07104"));
+    assert!(html.contains("摘要（中文）"));
+    assert!(html.contains("本文提出一种新方法。"));
+    assert!(html.contains("翻译（中文）"));
+    assert!(html.contains("引言译文"));
+    assert!(html.contains(
+      "<iframe class=\"mind-map\" srcdoc=\"&lt;html&gt;&#10;&lt;svg id=&quot;markmap&quot;&gt;"
+    ));
+    assert!(html.contains("「方法」的思维导图生成失败：timeout"));
+    assert!(html.contains("alt=\"poster.png\""));
+    assert!(html.contains("data:image/png;base64,"));
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+  }
+
+  #[test]
+  fn test_pdf_report_uses_mind_map_outlines() {
+    let markdown = report_markdown(&processed_paper(), ReportFormat::Pdf);
+    assert!(markdown.contains("```text\n# 引言\n## 背景\n```"));
+    assert!(markdown.contains("## 阶段统计\n\n| Stage |"));
+    assert!(markdown.contains("| poster | - | 2.0 | 0 | - | - | - | 1 |"));
+    assert!(!markdown.contains("<iframe"));
+    assert!(ReportFormat::parse("PDF").is_ok());
+    assert!(ReportFormat::parse("docx").is_err());
+  }
+}
@@ -0,0 +1,116 @@
+//! Stage outputs as the pipeline stores them
+//!
+//! `PaperAssistant::process_paper_streaming` hands each stage's output to
+//! a stream as soon as the workflow has it, for a caller (a web service,
+//! say) that shows the summary before the poster is drawn instead of
+//! waiting for the whole `PaperProcessingResult`.
+//!
+//! # Ordering
+//!
+//! Items arrive in the order the stages complete. The arXiv fetch is
+//! always first, since every other stage reads the paper. The enrichment,
+//! summaries, translations and mind maps run concurrently and interleave
+//! in whatever order they finish; mind maps come one per section, not in
+//! section order. The poster comes after the primary language's summary
+//! and every mind map. A stage answered by the stage cache is emitted like
+//! one that ran. A stage that fails emits nothing, except a mind map,
+//! which emits [`MIND_MAP_FAILED`] and lets the others go on.
+//!
+//! # Termination
+//!
+//! The last item is always the terminal one, stage [`DONE`]: its output
+//! has `status` `"completed"` (with `paper_id` and `processing_time_ms`)
+//! or `"failed"` (with the `error`). It follows every stage item, on
+//! failure too, and then the stream ends. A paper `processed.json`
+//! already has yields only the terminal item, with `from_cache` set; its
+//! outputs are in the result.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+use crate::PaperProcessingResult;
+
+/// The stage of the terminal item
+pub const DONE: &str = "done";
+
+/// The stage of a section whose mind map could not be generated; its
+/// output is the `MindMapFailure`
+pub const MIND_MAP_FAILED: &str = "mind_map_failed";
+
+/// One stage's output, as soon as it is stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageOutput {
+  /// The stage, named like the flow's nodes: `arxiv_fetch`,
+  /// `enrichment`, `summary_{language}`, `translation_{language}`,
+  /// `mind_map`, [`MIND_MAP_FAILED`], `poster`, or [`DONE`]
+  pub stage: String,
+  /// `arxiv_fetch`: `paper_id` and `paper_title`. `enrichment`: the
+  /// `PaperEnrichment`. Summaries and translations: `language` and the
+  /// `summary` or `translation` text. `mind_map`: the `MindMapResult`.
+  /// `poster`: `image_path`, `prompt` and `filter_reason`.
+  pub output: Value,
+}
+
+impl StageOutput {
+  pub fn new(stage: impl Into<String>, output: Value) -> Self {
+    Self {
+      stage: stage.into(),
+      output,
+    }
+  }
+
+  /// The item ending the stream, for the run's result
+  pub(crate) fn terminal(result: &anyhow::Result<PaperProcessingResult>) -> Self {
+    let output = match result {
+      Ok(result) => json!({
+        "status": "completed",
+        "paper_id": result.paper_id,
+        "processing_time_ms": result.processing_time_ms,
+        "from_cache": result.from_cache,
+      }),
+      Err(e) => json!({ "status": "failed", "error": e.to_string() }),
+    };
+    Self::new(DONE, output)
+  }
+
+  /// Whether this is the last item of the stream
+  pub fn is_terminal(&self) -> bool {
+    self.stage == DONE
+  }
+}
+
+/// Where the workflow sends stage outputs; nowhere unless a stream is
+/// reading them
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StageOutputSink(Option<mpsc::UnboundedSender<StageOutput>>);
+
+impl StageOutputSink {
+  pub(crate) fn new(sender: mpsc::UnboundedSender<StageOutput>) -> Self {
+    Self(Some(sender))
+  }
+
+  pub(crate) fn send(&self, stage: &str, output: Value) {
+    if let Some(sender) = &self.0 {
+      // A dropped stream only means nobody is reading any more; the
+      // run goes on for its result.
+      let _ = sender.send(StageOutput::new(stage, output));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_terminal_item_carries_the_status() {
+    let failed = StageOutput::terminal(&Err(anyhow::anyhow!("quota exceeded")));
+    assert!(failed.is_terminal());
+    assert_eq!(
+      failed.output,
+      json!({ "status": "failed", "error": "quota exceeded" })
+    );
+    assert!(!StageOutput::new("poster", json!({})).is_terminal());
+  }
+}
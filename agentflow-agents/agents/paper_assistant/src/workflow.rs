@@ -1,139 +1,300 @@
 //! Paper Assistant Workflow Implementation
 //!
-//! This module defines the workflow for processing arXiv papers with Chinese translation,
-//! summarization, mind mapping, and poster generation.
+//! This module defines the workflow for processing arXiv papers with translation and
+//! summarization into each configured language, mind mapping, and poster generation.
+//! The pipeline is an agentflow-core [`Flow`]: the arXiv fetch feeds the summaries,
+//! translations and section extraction, which run concurrently; the sections fan out
+//! to a Map node drawing one mind map each; the poster comes last. With
+//! `enable_enrichment`, the paper is also looked up on Semantic Scholar.
 
-use agentflow_core::{AsyncFlow, AsyncNode, SharedState, AgentFlowError};
-use agentflow_nodes::{ArxivNode, LlmNode, MarkMapNode, TextToImageNode};
+use agentflow_agents::{
+  EnrichmentNode, PaperEnrichment, StageCache, StageCacheKey, StageMetric, StageMetrics,
+  TranslatorNode, glossary_violations,
+};
+use agentflow_core::async_node::{AsyncNodeInputs, AsyncNodeResult};
+use agentflow_core::{
+  AgentFlowError, AsyncNode, Flow, FlowExecutionConfig, FlowExt, FlowValue, GraphNode, NodeType,
+  SharedState,
+};
+use agentflow_llm::ModelRegistry;
+use agentflow_nodes::nodes::{arxiv::ArxivNode, markmap::MarkMapNode};
+use agentflow_nodes_ai::{LlmNode, TextToImageNode};
 use anyhow::Result;
-use serde_json::{json, Value};
-use std::collections::HashMap;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use crate::config::PaperAssistantConfig;
-use crate::utils::{extract_paper_sections, create_section_markdown};
+use crate::checkpoint::{CHECKPOINT_URL_KEY, StageCheckpoint};
+use crate::config::{PaperAssistantConfig, Stage, fill_placeholders, language_name};
+use crate::poster::{PosterInsights, PosterPromptBuilder, arxiv_domain, content_filter_reason};
+use crate::progress::{ProgressEvent, ProgressHandle, ProgressReporter};
+use crate::streaming::{MIND_MAP_FAILED, StageOutputSink};
+use crate::utils::{
+  create_section_markdown, extract_paper_sections, sanitize_filename, split_into_chunks,
+};
+use crate::{MindMapFailure, MindMapResult};
+
+/// The shared-state key of the summary in `language`
+pub fn summary_key(language: &str) -> String {
+  format!("summary_output_{}", language)
+}
+
+/// The shared-state key of the full translation into `language`
+pub fn translation_key(language: &str) -> String {
+  format!("translation_output_{}", language)
+}
+
+/// The shared-state key of the generated mind maps, a `MindMapResult`
+/// array in section order
+pub const MIND_MAPS_KEY: &str = "mind_maps";
+
+/// The shared-state key of the sections whose mind map failed, a
+/// `MindMapFailure` array
+pub const MIND_MAP_FAILURES_KEY: &str = "mind_map_failures";
+
+/// The shared-state key of the paper's Semantic Scholar enrichment, a
+/// `PaperEnrichment`
+pub const ENRICHMENT_KEY: &str = "enrichment_output";
+
+/// The shared-state key of the run's stage metrics, a `StageMetric`
+/// array in the order the stages finished
+pub const STAGE_METRICS_KEY: &str = "stage_metrics";
+
+/// The fallback poster summary when the primary language has none
+const NO_SUMMARY: &str = "无摘要可用";
+
+/// The pipeline `PaperAssistant` runs for one paper: it reads `arxiv_url`
+/// from the shared state and leaves the `*_output` entries there.
+#[async_trait]
+pub trait PaperWorkflow: Send {
+  async fn execute(&mut self, shared_state: &SharedState) -> Result<Value>;
+}
+
+/// The model-backed steps of the pipeline. Each gets its inputs as
+/// arguments and returns its output; the workflow passes them along the
+/// graph. The default implementation runs the agentflow nodes; tests
+/// substitute their own.
+#[async_trait]
+pub trait PaperStages: Send + Sync {
+  /// Fetches the paper; returns what `arxiv_fetch_output` holds.
+  async fn fetch(&self, arxiv_url: &str) -> Result<Value>;
+  /// Summarizes the paper in `language`; returns the LLM output, with
+  /// `response` and `usage`.
+  async fn summarize(&self, language: &str, paper_content: &str) -> Result<Value>;
+  /// Translates one chunk of the paper content into `language`, keeping
+  /// to the configured glossary.
+  async fn translate_chunk(&self, language: &str, chunk: &str) -> Result<String>;
+  /// Lists the paper's sections, in the text format
+  /// `extract_paper_sections` parses.
+  async fn extract_sections(&self, paper_content: &str) -> Result<String>;
+  /// Draws the mind map of the `n`th section (1-based).
+  async fn generate_mind_map(&self, n: usize, section: &PaperSection) -> Result<MindMap>;
+  /// Draws the poster from `prompt`; returns what `poster_image_output`
+  /// holds. A rejection by the content filter is an error that says so
+  /// (see [`content_filter_reason`]).
+  async fn generate_poster(&self, prompt: &str) -> Result<Value>;
+  /// Looks the paper up on Semantic Scholar. Never fails: an unreachable
+  /// API gives an empty enrichment with a warning.
+  async fn enrich(&self, arxiv_id: &str) -> PaperEnrichment;
+}
 
 /// Paper Assistant Workflow orchestrates the complete paper processing pipeline
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct PaperAssistantWorkflow {
-  /// ArXiv paper fetching node
-  arxiv_node: ArxivNode,
-  /// LLM node for Chinese summary generation
-  summary_node: LlmNode,
-  /// LLM node for Chinese translation
-  translation_node: LlmNode,
-  /// LLM node for section content extraction
-  section_extraction_node: LlmNode,
-  /// Text-to-image node for poster generation
-  poster_node: TextToImageNode,
+  /// The steps that call arXiv and the models
+  stages: Arc<dyn PaperStages>,
+  /// Where stage events are reported
+  progress: ProgressHandle,
+  /// Where stage outputs are streamed as they are stored
+  outputs: StageOutputSink,
+  /// Where completed stage outputs are saved
+  checkpoint: Option<StageCheckpoint>,
+  /// Where the flow executor keeps its runs (default `~/.agentflow/runs`)
+  run_dir: Option<PathBuf>,
+  /// Where summaries and translations are reused from
+  cache: Option<StageCache>,
   /// Configuration
   config: PaperAssistantConfig,
 }
 
+impl std::fmt::Debug for PaperAssistantWorkflow {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PaperAssistantWorkflow")
+      .field("progress", &self.progress)
+      .field("checkpoint", &self.checkpoint)
+      .field("run_dir", &self.run_dir)
+      .field("cache", &self.cache)
+      .field("config", &self.config)
+      .finish_non_exhaustive()
+  }
+}
+
 impl PaperAssistantWorkflow {
-  /// Create a new workflow with the given configuration
+  /// Create a new workflow with the given configuration. Fails when a
+  /// model in `stage_models` is missing from the loaded model registry.
   pub fn new(config: &PaperAssistantConfig) -> Result<Self> {
-    // Create ArXiv node
-    let arxiv_node = ArxivNode::new("arxiv_fetch", "{{arxiv_url}}")
-      .with_output_key("arxiv_fetch_output")
-      .with_output_directory(&config.output_directory);
-
-    // Create Chinese summary LLM node
-    let summary_node = LlmNode::new("chinese_summary", &config.qwen_turbo_model)
-      .with_prompt(&config.chinese_summary_prompt)
-      .with_temperature(config.temperature.unwrap_or(0.3))
-      .with_max_tokens(config.max_tokens.unwrap_or(4000))
-      .with_output_key("chinese_summary_output")
-      .with_input_keys(vec!["paper_content".to_string()]);
-
-    // Create Chinese translation LLM node
-    let translation_node = LlmNode::new("chinese_translation", &config.qwen_turbo_model)
-      .with_prompt(&config.chinese_translation_prompt)
-      .with_temperature(config.temperature.unwrap_or(0.3))
-      .with_max_tokens(config.max_tokens.unwrap_or(8000))
-      .with_output_key("chinese_translation_output")
-      .with_input_keys(vec!["paper_content".to_string()]);
-
-    // Create section extraction LLM node
-    let section_extraction_node = LlmNode::new("section_extraction", &config.qwen_turbo_model)
-      .with_prompt(&config.section_extraction_prompt)
-      .with_temperature(config.temperature.unwrap_or(0.2))
-      .with_max_tokens(config.max_tokens.unwrap_or(6000))
-      .with_output_key("sections_output")
-      .with_input_keys(vec!["paper_content".to_string()]);
-
-    // Create poster generation node
-    let poster_node = TextToImageNode::new("poster_generation", &config.qwen_image_model)
-      .with_prompt(&config.poster_generation_prompt)
-      .with_output_key("poster_image_output")
-      .with_input_keys(vec!["chinese_summary".to_string(), "paper_title".to_string()])
-      .with_size("1024x1024");
+    // Until the registry is loaded (by the first LLM call) there is
+    // nothing to check against.
+    let registry = ModelRegistry::global();
+    if !registry.list_models().is_empty() {
+      config
+        .check_stage_models(registry)
+        .map_err(|e| anyhow::anyhow!("Config validation failed: {}", e))?;
+    }
+    Ok(
+      Self::with_stages(config, Box::new(NodeStages::new(config))).with_cache(config.stage_cache()),
+    )
+  }
 
-    Ok(Self {
-      arxiv_node,
-      summary_node,
-      translation_node,
-      section_extraction_node,
-      poster_node,
+  /// Create a workflow that runs `stages` instead of the agentflow nodes.
+  /// It has no stage cache until [`Self::with_cache`] gives it one.
+  pub fn with_stages(config: &PaperAssistantConfig, stages: Box<dyn PaperStages>) -> Self {
+    Self {
+      stages: Arc::from(stages),
+      progress: ProgressHandle::default(),
+      outputs: StageOutputSink::default(),
+      checkpoint: config
+        .save_intermediate_files
+        .then(|| StageCheckpoint::new(&config.output_directory)),
+      run_dir: None,
+      cache: None,
       config: config.clone(),
-    })
+    }
   }
 
-  /// Execute the complete paper processing workflow
-  pub async fn execute(&mut self, shared_state: &SharedState) -> Result<Value> {
-    log::info!("Starting paper assistant workflow execution");
+  /// Reuse summaries and translations from `cache`, and save new ones
+  /// to it
+  pub fn with_cache(mut self, cache: StageCache) -> Self {
+    self.cache = Some(cache);
+    self
+  }
 
-    // Step 1: Fetch paper from arXiv
-    log::info!("Step 1: Fetching paper from arXiv");
-    self.arxiv_node.run_async(shared_state).await
-      .map_err(|e| anyhow::anyhow!("ArXiv fetch failed: {}", e))?;
+  /// Report stage events to `reporter` instead of the log
+  pub fn with_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+    self.progress = ProgressHandle::new(reporter);
+    self
+  }
 
-    // Extract paper content for subsequent processing
-    let arxiv_output = shared_state.get("arxiv_fetch_output")
-      .ok_or_else(|| anyhow::anyhow!("ArXiv output not found"))?;
+  /// Keep the flow executor's runs under `dir`
+  pub fn with_run_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.run_dir = Some(dir.into());
+    self
+  }
 
-    // Get the best available content (expanded LaTeX or simple content)
-    let paper_content = self.extract_paper_content(&arxiv_output)?;
-    shared_state.insert("paper_content".to_string(), json!(paper_content));
+  pub(crate) fn set_progress(&mut self, progress: ProgressHandle) {
+    self.progress = progress;
+  }
 
-    // Extract paper title for poster generation
-    let paper_title = self.extract_paper_title(&paper_content);
-    shared_state.insert("paper_title".to_string(), json!(paper_title));
+  pub(crate) fn set_stage_outputs(&mut self, outputs: StageOutputSink) {
+    self.outputs = outputs;
+  }
 
-    // Step 2: Generate Chinese summary
-    log::info!("Step 2: Generating Chinese summary");
-    self.summary_node.run_async(shared_state).await
-      .map_err(|e| anyhow::anyhow!("Chinese summary generation failed: {}", e))?;
+  pub(crate) fn set_checkpoint(&mut self, checkpoint: StageCheckpoint) {
+    self.checkpoint = Some(checkpoint);
+  }
 
-    // Step 3: Generate Chinese translation
-    log::info!("Step 3: Generating Chinese translation");
-    self.translation_node.run_async(shared_state).await
-      .map_err(|e| anyhow::anyhow!("Chinese translation failed: {}", e))?;
+  /// The flow `execute` runs for the paper at `arxiv_url` in
+  /// `shared_state`, with its stages reusing the outputs already there
+  pub fn build_flow(&self, shared_state: &SharedState) -> Flow {
+    self.plan(shared_state).0
+  }
 
-    // Step 4: Extract paper sections for mind mapping
-    log::info!("Step 4: Extracting paper sections");
-    self.section_extraction_node.run_async(shared_state).await
-      .map_err(|e| anyhow::anyhow!("Section extraction failed: {}", e))?;
+  /// Execute the complete paper processing workflow.
+  ///
+  /// A stage whose output is already in `shared_state` (loaded from a
+  /// checkpoint by `PaperAssistant::resume`) is not run again. Each stage
+  /// that runs is saved to the checkpoint when it completes. Independent
+  /// stages keep running when one fails, so a resumed run only repeats the
+  /// failed stage and the ones after it.
+  pub async fn execute(&mut self, shared_state: &SharedState) -> Result<Value> {
+    log::info!("Starting paper assistant workflow execution");
+    if let (Some(checkpoint), Some(url)) = (&self.checkpoint, shared_state.get(CHECKPOINT_URL_KEY))
+      && let Err(e) = checkpoint.save(CHECKPOINT_URL_KEY, &url)
+    {
+      log::warn!("Failed to save stage output {}: {}", CHECKPOINT_URL_KEY, e);
+    }
+
+    let (flow, context) = self.plan(shared_state);
+    let mut run_config = FlowExecutionConfig::concurrent(2 * self.config.languages.len() + 2);
+    run_config.fail_fast = false;
+    if let Some(run_dir) = &self.run_dir {
+      run_config = run_config.with_run_base_dir(run_dir.clone());
+    }
+    let state_pool = flow
+      .execute_from_inputs_with_config(HashMap::new(), run_config)
+      .await
+      .map_err(|e| anyhow::anyhow!("Paper workflow failed: {}", e))?;
 
-    // Step 5: Generate mind maps for each section
-    log::info!("Step 5: Generating mind maps for sections");
-    self.generate_section_mind_maps(shared_state).await?;
-
-    // Step 6: Generate poster image
-    log::info!("Step 6: Generating poster image");
-    
-    // Prepare summary for poster generation
-    let summary_output = match shared_state.get("chinese_summary_output") {
-      Some(output) => match output.get("response") {
-        Some(response) => response.as_str().unwrap_or("无摘要可用").to_string(),
-        None => "无摘要可用".to_string(),
+    // Leave every stage output where `PaperAssistant` reads it
+    let output = |node: &str, name: &str| match state_pool.get(node) {
+      Some(Ok(outputs)) => match outputs.get(name) {
+        Some(FlowValue::Json(value)) => Some(value.clone()),
+        _ => None,
       },
-      None => "无摘要可用".to_string(),
+      _ => None,
     };
-    
-    shared_state.insert("chinese_summary".to_string(), json!(summary_output));
+    for name in ["arxiv_fetch_output", "paper_content", "paper_title"] {
+      if let Some(value) = output(ARXIV_FETCH, name) {
+        shared_state.insert(name.to_string(), value);
+      }
+    }
+    for language in &self.config.languages {
+      if let Some(value) = output(&summary_node_id(language), "summary") {
+        shared_state.insert(summary_key(language), value);
+      }
+      if let Some(value) = output(&translation_node_id(language), "translation") {
+        shared_state.insert(translation_key(language), value);
+      }
+    }
+    if let Some(value) = output(ENRICHMENT, "enrichment") {
+      shared_state.insert(ENRICHMENT_KEY.to_string(), value);
+    }
+    if let Some(value) = output(SECTION_EXTRACTION, "section_extraction_output") {
+      shared_state.insert("section_extraction_output".to_string(), value);
+    }
+    if state_pool.contains_key(MIND_MAPS) {
+      shared_state.insert(MIND_MAPS_KEY.to_string(), context.mind_maps_value());
+      let failures = context
+        .failures
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+      shared_state.insert(
+        MIND_MAP_FAILURES_KEY.to_string(),
+        serde_json::to_value(&failures)?,
+      );
+    }
+    if let Some(value) = output(POSTER, "poster_image_output") {
+      shared_state.insert("poster_image_output".to_string(), value);
+    }
+    if let Some(summary) = output(POSTER, "summary") {
+      shared_state.insert("summary".to_string(), summary.clone());
+      // The name poster prompts used before `languages` existed
+      shared_state.insert("chinese_summary".to_string(), summary);
+    }
+    shared_state.insert(
+      STAGE_METRICS_KEY.to_string(),
+      serde_json::to_value(context.metrics.metrics())?,
+    );
 
-    self.poster_node.run_async(shared_state).await
-      .map_err(|e| anyhow::anyhow!("Poster generation failed: {}", e))?;
+    // The first stage that failed, in pipeline order; the stages skipped
+    // because of it are not errors of their own
+    for node_id in self.node_ids() {
+      match state_pool.get(&node_id) {
+        Some(Err(AgentFlowError::NodeSkipped | AgentFlowError::DependencyNotMet { .. }))
+        | Some(Ok(_))
+        | None => {}
+        Some(Err(AgentFlowError::NodeExecutionFailed { message })) => anyhow::bail!("{}", message),
+        Some(Err(e)) => anyhow::bail!("{}", e),
+      }
+    }
 
     log::info!("Paper assistant workflow completed successfully");
 
@@ -144,131 +305,1090 @@ impl PaperAssistantWorkflow {
     }))
   }
 
-  /// Extract the best available paper content from ArXiv output
-  fn extract_paper_content(&self, arxiv_output: &Value) -> Result<String> {
-    // Try to get expanded LaTeX content first (most comprehensive)
-    if let Some(latex_info) = arxiv_output.get("latex_info") {
-      if let Some(expanded_content) = latex_info.get("expanded_content") {
-        if let Some(content) = expanded_content.as_str() {
-          if !content.trim().is_empty() {
-            return Ok(content.to_string());
-          }
-        }
+  /// The ids of the flow's nodes, in pipeline order
+  fn node_ids(&self) -> Vec<String> {
+    let mut ids = vec![ARXIV_FETCH.to_string()];
+    if self.config.enable_enrichment {
+      ids.push(ENRICHMENT.to_string());
+    }
+    ids.extend(
+      self
+        .config
+        .languages
+        .iter()
+        .map(|language| summary_node_id(language)),
+    );
+    ids.extend(
+      self
+        .config
+        .languages
+        .iter()
+        .map(|language| translation_node_id(language)),
+    );
+    ids.extend([SECTION_EXTRACTION, MIND_MAPS, POSTER].map(String::from));
+    ids
+  }
+
+  /// The flow, and the context its stages share
+  fn plan(&self, shared_state: &SharedState) -> (Flow, Arc<StageContext>) {
+    let context = Arc::new(StageContext::new(self, shared_state));
+    let node = |id: &str, step: Step, dependencies: &[&str], mapping: &[(&str, &str, &str)]| {
+      stage_node(id, step, &context, dependencies, mapping)
+    };
+    let paper_content = [("paper_content", ARXIV_FETCH, "paper_content")];
+
+    let mut arxiv_fetch = node(ARXIV_FETCH, Step::Fetch, &[], &[]);
+    let arxiv_url = shared_state.get("arxiv_url").unwrap_or(Value::Null);
+    arxiv_fetch
+      .initial_inputs
+      .insert("arxiv_url".to_string(), FlowValue::Json(arxiv_url));
+    let mut nodes = vec![arxiv_fetch];
+
+    if self.config.enable_enrichment {
+      nodes.push(node(
+        ENRICHMENT,
+        Step::Enrichment,
+        &[ARXIV_FETCH],
+        &[("arxiv_fetch_output", ARXIV_FETCH, "arxiv_fetch_output")],
+      ));
+    }
+
+    for language in &self.config.languages {
+      nodes.push(node(
+        &summary_node_id(language),
+        Step::Summary(language.clone()),
+        &[ARXIV_FETCH],
+        &paper_content,
+      ));
+      nodes.push(node(
+        &translation_node_id(language),
+        Step::Translation(language.clone()),
+        &[ARXIV_FETCH],
+        &paper_content,
+      ));
+    }
+    nodes.push(node(
+      SECTION_EXTRACTION,
+      Step::Sections,
+      &[ARXIV_FETCH],
+      &paper_content,
+    ));
+
+    // One sub-flow per section; a failed mind map is recorded, not fatal
+    nodes.push(GraphNode {
+      id: MIND_MAPS.to_string(),
+      node_type: NodeType::Map {
+        template: vec![node(MIND_MAP, Step::MindMap, &[], &[])],
+        parallel: true,
+        max_concurrent: Some(self.config.mind_map_concurrency.max(1)),
+      },
+      dependencies: vec![SECTION_EXTRACTION.to_string()],
+      input_mapping: Some(HashMap::from([(
+        "input_list".to_string(),
+        (SECTION_EXTRACTION.to_string(), "sections".to_string()),
+      )])),
+      run_if: None,
+      initial_inputs: HashMap::new(),
+    });
+
+    let summary_node = summary_node_id(self.config.primary_language());
+    nodes.push(node(
+      POSTER,
+      Step::Poster,
+      &[ARXIV_FETCH, summary_node.as_str(), MIND_MAPS],
+      &[
+        ("summary", summary_node.as_str(), "summary"),
+        ("paper_title", ARXIV_FETCH, "paper_title"),
+        ("paper_domain", ARXIV_FETCH, "paper_domain"),
+      ],
+    ));
+
+    (Flow::new(nodes), context)
+  }
+}
+
+#[async_trait]
+impl PaperWorkflow for PaperAssistantWorkflow {
+  async fn execute(&mut self, shared_state: &SharedState) -> Result<Value> {
+    PaperAssistantWorkflow::execute(self, shared_state).await
+  }
+}
+
+const ARXIV_FETCH: &str = "arxiv_fetch";
+const ENRICHMENT: &str = "enrichment";
+const SECTION_EXTRACTION: &str = "section_extraction";
+const MIND_MAPS: &str = "mind_maps";
+const POSTER: &str = "poster";
+/// The Map node's template, one per section
+const MIND_MAP: &str = "mind_map";
+
+fn summary_node_id(language: &str) -> String {
+  format!("summary_{}", language)
+}
+
+fn translation_node_id(language: &str) -> String {
+  format!("translation_{}", language)
+}
+
+/// A node running `step`. Each input named in `mapping` is `(input,
+/// node, output)`.
+fn stage_node(
+  id: &str,
+  step: Step,
+  context: &Arc<StageContext>,
+  dependencies: &[&str],
+  mapping: &[(&str, &str, &str)],
+) -> GraphNode {
+  GraphNode {
+    id: id.to_string(),
+    node_type: NodeType::Standard(Arc::new(StageNode {
+      step,
+      context: Arc::clone(context),
+    })),
+    dependencies: dependencies
+      .iter()
+      .map(|dependency| dependency.to_string())
+      .collect(),
+    input_mapping: (!mapping.is_empty()).then(|| {
+      mapping
+        .iter()
+        .map(|(input, node, output)| (input.to_string(), (node.to_string(), output.to_string())))
+        .collect()
+    }),
+    run_if: None,
+    initial_inputs: HashMap::new(),
+  }
+}
+
+/// What a node of the flow does
+enum Step {
+  Fetch,
+  Enrichment,
+  Summary(String),
+  Translation(String),
+  Sections,
+  MindMap,
+  Poster,
+}
+
+/// What the nodes of one run share: the stages, the outputs saved by an
+/// earlier run, the mind maps drawn so far and the stage metrics
+struct StageContext {
+  stages: Arc<dyn PaperStages>,
+  progress: ProgressHandle,
+  outputs: StageOutputSink,
+  metrics: StageMetrics,
+  checkpoint: Option<StageCheckpoint>,
+  cache: Option<StageCache>,
+  /// Stage outputs already in the shared state, by key
+  saved: HashMap<String, Value>,
+  config: PaperAssistantConfig,
+  /// Saved and newly drawn mind maps
+  mind_maps: Mutex<Vec<MindMapResult>>,
+  failures: Mutex<Vec<MindMapFailure>>,
+}
+
+impl StageContext {
+  fn new(workflow: &PaperAssistantWorkflow, shared_state: &SharedState) -> Self {
+    let config = &workflow.config;
+    let mut keys = vec![
+      "arxiv_fetch_output".to_string(),
+      "section_extraction_output".to_string(),
+    ];
+    keys.extend(
+      config
+        .languages
+        .iter()
+        .map(|language| summary_key(language)),
+    );
+    keys.extend(
+      config
+        .languages
+        .iter()
+        .map(|language| translation_key(language)),
+    );
+    keys.push("poster_image_output".to_string());
+    keys.push(ENRICHMENT_KEY.to_string());
+    let saved = keys
+      .into_iter()
+      .filter_map(|key| shared_state.get(&key).map(|value| (key, value)))
+      .collect();
+
+    // Mind maps saved by an earlier run are kept; failed ones are retried
+    let mind_maps = shared_state
+      .get(MIND_MAPS_KEY)
+      .and_then(|value| serde_json::from_value(value).ok())
+      .unwrap_or_default();
+
+    Self {
+      stages: Arc::clone(&workflow.stages),
+      progress: workflow.progress.clone(),
+      outputs: workflow.outputs.clone(),
+      metrics: config.stage_metrics(),
+      checkpoint: workflow.checkpoint.clone(),
+      cache: workflow.cache.clone(),
+      saved,
+      config: config.clone(),
+      mind_maps: Mutex::new(mind_maps),
+      failures: Mutex::new(Vec::new()),
+    }
+  }
+
+  /// The output an earlier run saved under `key`, if the stage writing it
+  /// does not have to run again
+  fn saved(&self, key: &str) -> Option<Value> {
+    let value = self.saved.get(key).cloned();
+    if value.is_some() {
+      log::info!("Reusing saved stage output: {}", key);
+    }
+    value
+  }
+
+  /// Save `value` to the checkpoint. A failed save only costs resuming.
+  fn persist(&self, key: &str, value: &Value) {
+    if let Some(checkpoint) = &self.checkpoint
+      && let Err(e) = checkpoint.save(key, value)
+    {
+      log::warn!("Failed to save stage output {}: {}", key, e);
+    }
+  }
+
+  /// The output `key` has in the stage cache, or that of `compute`, and
+  /// whether `compute` ran
+  async fn cached<T>(
+    &self,
+    key: StageCacheKey,
+    compute: impl Future<Output = Result<T>>,
+  ) -> Result<(T, bool)>
+  where
+    T: Serialize + DeserializeOwned,
+  {
+    let ran = AtomicBool::new(false);
+    let compute = async {
+      ran.store(true, Ordering::Relaxed);
+      compute.await
+    };
+    let output = match &self.cache {
+      Some(cache) => cache.get_or_compute(&key, || compute).await?,
+      None => compute.await?,
+    };
+    Ok((output, ran.load(Ordering::Relaxed)))
+  }
+
+  /// A metric of `stage` since `started`, on the model `stage_model` has
+  fn metric(&self, stage: &str, stage_model: Option<Stage>, started: Instant) -> StageMetric {
+    let metric = StageMetric::new(stage, started.elapsed());
+    match stage_model {
+      Some(stage_model) => metric.with_model(self.config.stage_model(stage_model)),
+      None => metric,
+    }
+  }
+
+  /// The mind maps so far, in section order
+  fn mind_maps_value(&self) -> Value {
+    let mut mind_maps = self
+      .mind_maps
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clone();
+    mind_maps.sort_by_key(|mind_map| mind_map.section_index);
+    serde_json::to_value(&mind_maps).unwrap_or_else(|_| json!([]))
+  }
+
+  async fn fetch(&self, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    let started = Instant::now();
+    let arxiv_output = match self.saved("arxiv_fetch_output") {
+      Some(output) => output,
+      None => {
+        let arxiv_url = text_input(inputs, "arxiv_url").unwrap_or_default();
+        self.progress.report(ProgressEvent::ArxivFetchStarted {
+          url: arxiv_url.clone(),
+        });
+        let output = self.stages.fetch(&arxiv_url).await?;
+        self.persist("arxiv_fetch_output", &output);
+        output
       }
-      
-      // Fall back to main content
-      if let Some(main_content) = latex_info.get("main_content") {
-        if let Some(content) = main_content.as_str() {
-          if !content.trim().is_empty() {
-            return Ok(content.to_string());
-          }
+    };
+    self.progress.report(ProgressEvent::ArxivFetchFinished {
+      paper_id: arxiv_output["paper_id"]
+        .as_str()
+        .unwrap_or("unknown")
+        .to_string(),
+    });
+
+    // The best available content (expanded LaTeX or simple content), and
+    // the title and field for the poster
+    let paper_content = extract_paper_content(&arxiv_output)?;
+    let paper_title = extract_paper_title(&paper_content);
+    let paper_domain = arxiv_output["metadata"]["primary_category"]
+      .as_str()
+      .map(arxiv_domain);
+    self.metrics.record(self.metric(ARXIV_FETCH, None, started));
+    self.outputs.send(
+      ARXIV_FETCH,
+      json!({
+        "paper_id": arxiv_output["paper_id"],
+        "paper_title": paper_title,
+      }),
+    );
+    Ok(outputs([
+      ("arxiv_fetch_output", arxiv_output),
+      ("paper_content", json!(paper_content)),
+      ("paper_title", json!(paper_title)),
+      ("paper_domain", json!(paper_domain)),
+    ]))
+  }
+
+  /// Looks the paper up on Semantic Scholar. A degraded enrichment is
+  /// kept in the results but not saved, so a resumed run asks again.
+  async fn enrich(&self, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    let started = Instant::now();
+    let enrichment = match self.saved(ENRICHMENT_KEY) {
+      Some(output) => output,
+      None => {
+        let paper_id = match inputs.get("arxiv_fetch_output") {
+          Some(FlowValue::Json(arxiv_output)) => arxiv_output["paper_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+          _ => anyhow::bail!("Missing input arxiv_fetch_output"),
+        };
+        let enrichment = self.stages.enrich(&paper_id).await;
+        let output = serde_json::to_value(&enrichment)?;
+        match &enrichment.warning {
+          Some(warning) => log::warn!("Enrichment of {} is incomplete: {}", paper_id, warning),
+          None => self.persist(ENRICHMENT_KEY, &output),
         }
+        output
+      }
+    };
+    self.metrics.record(self.metric(ENRICHMENT, None, started));
+    self.outputs.send(ENRICHMENT, enrichment.clone());
+    Ok(outputs([("enrichment", enrichment)]))
+  }
+
+  async fn summarize(&self, language: &str, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    let started = Instant::now();
+    let key = summary_key(language);
+    let (summary, called) = match self.saved(&key) {
+      Some(output) => (output, false),
+      None => {
+        self.progress.report(ProgressEvent::SummaryStarted {
+          language: language.to_string(),
+        });
+        let paper_content = text_input(inputs, "paper_content")?;
+        let cache_key = StageCacheKey::new(
+          &summary_node_id(language),
+          &paper_content,
+          self.config.stage_model(Stage::Summary),
+          &self.config.summary_prompt(language),
+        );
+        let (output, called) = self
+          .cached(cache_key, self.stages.summarize(language, &paper_content))
+          .await?;
+        self.persist(&key, &output);
+        (output, called)
+      }
+    };
+    let usage = summary.get("usage").cloned().unwrap_or(Value::Null);
+    let (prompt_tokens, completion_tokens) = (
+      usage["prompt_tokens"].as_u64(),
+      usage["completion_tokens"].as_u64(),
+    );
+    self.progress.report(ProgressEvent::SummaryFinished {
+      language: language.to_string(),
+      prompt_tokens,
+      completion_tokens,
+    });
+    let metric = self.metric(&summary_node_id(language), Some(Stage::Summary), started);
+    self.metrics.record(if called {
+      metric.with_call(prompt_tokens, completion_tokens)
+    } else {
+      metric
+    });
+    self.outputs.send(
+      &summary_node_id(language),
+      json!({ "language": language, "summary": summary["response"] }),
+    );
+    Ok(outputs([("summary", summary)]))
+  }
+
+  /// Translates the paper chunk by chunk
+  async fn translate(&self, language: &str, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    let started = Instant::now();
+    let stage = translation_node_id(language);
+    let key = translation_key(language);
+    if let Some(output) = self.saved(&key) {
+      self
+        .metrics
+        .record(self.metric(&stage, Some(Stage::Translation), started));
+      self.outputs.send(
+        &stage,
+        json!({ "language": language, "translation": output["response"] }),
+      );
+      return Ok(outputs([("translation", output)]));
+    }
+    let paper_content = text_input(inputs, "paper_content")?;
+    let chunks = split_into_chunks(
+      &paper_content,
+      self.config.translation_chunk_chars.unwrap_or(usize::MAX),
+    );
+    // The glossary shapes every chunk's prompt
+    let prompt = format!(
+      "{}\n{}",
+      self.config.translation_prompt(language),
+      serde_json::to_string(&self.config.glossary)?
+    );
+    let mut translation = Vec::with_capacity(chunks.len());
+    let mut violations = Vec::new();
+    let mut metric = StageMetric::default();
+    for (i, chunk) in chunks.iter().enumerate() {
+      let cache_key = StageCacheKey::new(
+        &stage,
+        chunk,
+        self.config.stage_model(Stage::Translation),
+        &prompt,
+      );
+      let (translated, called) = self
+        .cached(cache_key, self.stages.translate_chunk(language, chunk))
+        .await?;
+      if called {
+        metric = metric.with_call(None, None);
       }
+      violations.extend(glossary_violations(
+        &self.config.glossary,
+        i,
+        chunk,
+        &translated,
+      ));
+      translation.push(translated);
+      self.progress.report(ProgressEvent::TranslationProgress {
+        language: language.to_string(),
+        chunk: i + 1,
+        total: chunks.len(),
+      });
     }
+    let output = json!({ "response": translation.join("\n\n"), "glossary_violations": violations });
+    self.persist(&key, &output);
+    self.metrics.record(StageMetric {
+      calls: metric.calls,
+      ..self.metric(&stage, Some(Stage::Translation), started)
+    });
+    self.outputs.send(
+      &stage,
+      json!({ "language": language, "translation": output["response"] }),
+    );
+    Ok(outputs([("translation", output)]))
+  }
 
-    // Fall back to simple LaTeX content
-    if let Some(simple_content) = arxiv_output.get("simple_latex_content") {
-      if let Some(content) = simple_content.as_str() {
-        if !content.trim().is_empty() {
-          return Ok(content.to_string());
-        }
+  /// Extracts the sections, the Map node's items
+  async fn extract_sections(&self, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    let started = Instant::now();
+    let (sections_output, called) = match self.saved("section_extraction_output") {
+      Some(output) => (output, false),
+      None => {
+        log::info!("Extracting paper sections");
+        let output = json!(
+          self
+            .stages
+            .extract_sections(&text_input(inputs, "paper_content")?)
+            .await?
+        );
+        self.persist("section_extraction_output", &output);
+        (output, true)
+      }
+    };
+    let metric = self.metric(SECTION_EXTRACTION, Some(Stage::MindMap), started);
+    self.metrics.record(if called {
+      metric.with_call(None, None)
+    } else {
+      metric
+    });
+    let sections_text = sections_output
+      .as_str()
+      .ok_or_else(|| anyhow::anyhow!("No sections text in output"))?;
+    let sections = extract_paper_sections(sections_text)?;
+    log::info!("Found {} sections for mind mapping", sections.len());
+
+    let items = sections
+      .into_iter()
+      .enumerate()
+      .map(|(i, section)| json!({ "index": i + 1, "section": section }))
+      .collect();
+    Ok(outputs([
+      ("section_extraction_output", sections_output),
+      ("sections", Value::Array(items)),
+    ]))
+  }
+
+  /// Draws the mind map of one Map item; a failure is recorded for the
+  /// result and the other sections go on
+  async fn mind_map(&self, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    let item = match inputs.get("item") {
+      Some(FlowValue::Json(item)) => item.clone(),
+      _ => anyhow::bail!("Mind map node got no section"),
+    };
+    let index = item["index"].as_u64().unwrap_or(0) as usize;
+    let section: PaperSection = serde_json::from_value(item["section"].clone())?;
+
+    let saved = self
+      .mind_maps
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .iter()
+      .find(|mind_map| mind_map.section_index == index)
+      .cloned();
+    if let Some(mind_map) = saved {
+      log::info!(
+        "Reusing saved mind map for section {}: {}",
+        index,
+        section.title
+      );
+      self
+        .outputs
+        .send(MIND_MAP, serde_json::to_value(&mind_map)?);
+      return Ok(outputs([("section_index", json!(index))]));
+    }
+
+    log::info!(
+      "Generating mind map for section {}: {}",
+      index,
+      section.title
+    );
+    let started = Instant::now();
+    let generated = self.stages.generate_mind_map(index, &section).await;
+    self.metrics.record(
+      self
+        .metric(MIND_MAPS, Some(Stage::MindMap), started)
+        .with_call(None, None),
+    );
+    match generated {
+      Ok(mind_map) => {
+        let mind_map = MindMapResult {
+          section_index: index,
+          section_title: section.title.clone(),
+          section_number: section.number.clone(),
+          mind_map_html: mind_map.html,
+          mind_map_markdown: mind_map.markdown,
+        };
+        let output = serde_json::to_value(&mind_map)?;
+        self
+          .mind_maps
+          .lock()
+          .unwrap_or_else(|e| e.into_inner())
+          .push(mind_map);
+        self.persist(MIND_MAPS_KEY, &self.mind_maps_value());
+        self.outputs.send(MIND_MAP, output);
+        self.progress.report(ProgressEvent::MindMapGenerated {
+          section: section.title.clone(),
+        });
+      }
+      Err(e) => {
+        log::warn!("Failed to generate mind map for section {}: {}", index, e);
+        let failure = MindMapFailure {
+          section_index: index,
+          section_title: section.title.clone(),
+          error: e.to_string(),
+        };
+        self
+          .outputs
+          .send(MIND_MAP_FAILED, serde_json::to_value(&failure)?);
+        self
+          .failures
+          .lock()
+          .unwrap_or_else(|e| e.into_inner())
+          .push(failure);
       }
     }
+    Ok(outputs([("section_index", json!(index))]))
+  }
 
-    Err(anyhow::anyhow!("No usable paper content found in ArXiv output"))
+  async fn generate_poster(&self, inputs: &AsyncNodeInputs) -> Result<Outputs> {
+    // The primary language's summary
+    let summary = match inputs.get("summary") {
+      Some(FlowValue::Json(output)) => output["response"]
+        .as_str()
+        .unwrap_or(NO_SUMMARY)
+        .to_string(),
+      _ => NO_SUMMARY.to_string(),
+    };
+    let started = Instant::now();
+    let poster_output = match self.saved("poster_image_output") {
+      Some(output) => {
+        self
+          .metrics
+          .record(self.metric(POSTER, Some(Stage::Poster), started));
+        output
+      }
+      None => {
+        self.progress.report(ProgressEvent::PosterStarted);
+        let paper_title = text_input(inputs, "paper_title")?;
+        let domain = text_input(inputs, "paper_domain").ok();
+        let builder = PosterPromptBuilder::new(
+          self.config.poster_prompt(),
+          PosterInsights::from_summary(&paper_title, &summary, domain),
+        )
+        .with_summary(&summary)
+        .with_style(self.config.poster_style)
+        .with_size(&self.config.poster_size);
+        let output = self.draw_poster(&builder).await?;
+        // A rejected prompt is drawn once more from the simplified one
+        let retries = u32::from(!output["filter_reason"].is_null());
+        let mut metric = self
+          .metric(POSTER, Some(Stage::Poster), started)
+          .with_retries(retries);
+        for _ in 0..=retries {
+          metric = metric.with_call(None, None);
+        }
+        self.metrics.record(metric);
+        self.persist("poster_image_output", &output);
+        self.progress.report(ProgressEvent::PosterFinished {
+          image_path: output
+            .get("image_path")
+            .and_then(|path| path.as_str().map(|s| s.to_string())),
+        });
+        output
+      }
+    };
+    self.outputs.send(
+      POSTER,
+      json!({
+        "image_path": poster_output["image_path"],
+        "prompt": poster_output["prompt"],
+        "filter_reason": poster_output["filter_reason"],
+      }),
+    );
+    Ok(outputs([
+      ("poster_image_output", poster_output),
+      ("summary", json!(summary)),
+    ]))
   }
 
-  /// Extract paper title from LaTeX content
-  fn extract_paper_title(&self, paper_content: &str) -> String {
-    // Look for \title{...} in LaTeX content
-    if let Some(start) = paper_content.find("\\title{") {
-      let title_start = start + 7; // Length of "\title{"
-      if let Some(end) = paper_content[title_start..].find('}') {
-        let title = &paper_content[title_start..title_start + end];
-        // Clean up LaTeX commands and return
-        return self.clean_latex_text(title);
+  /// Draw the poster from the builder's prompt, or once more from its
+  /// simplified prompt when the content filter rejects the first. The
+  /// output records the prompt drawn from as `prompt`, and the rejection
+  /// as `filter_reason`.
+  async fn draw_poster(&self, builder: &PosterPromptBuilder) -> Result<Value> {
+    let prompt = builder.build();
+    let (mut output, prompt, filter_reason) = match self.stages.generate_poster(&prompt).await {
+      Ok(output) => (output, prompt, None),
+      Err(e) => {
+        let reason = content_filter_reason(&e).ok_or(e)?;
+        log::warn!(
+          "Poster prompt rejected by the content filter, retrying with a simplified prompt: {}",
+          reason
+        );
+        let simplified = builder.build_simplified();
+        let output = self
+          .stages
+          .generate_poster(&simplified)
+          .await
+          .map_err(|e| {
+            anyhow::anyhow!(
+              "{} (after the content filter rejected the full prompt: {})",
+              e,
+              reason
+            )
+          })?;
+        (output, simplified, Some(reason))
       }
+    };
+    if output.get("image_path").and_then(Value::as_str).is_none() {
+      log::warn!("Poster generation returned no image path");
     }
+    if let Value::Object(fields) = &mut output {
+      fields.insert("prompt".to_string(), json!(prompt));
+      fields.insert("filter_reason".to_string(), json!(filter_reason));
+    }
+    Ok(output)
+  }
+}
 
-    // Fall back to first line or default
-    paper_content.lines()
-      .next()
-      .map(|line| self.clean_latex_text(line))
-      .filter(|line| !line.trim().is_empty())
-      .unwrap_or_else(|| "未知论文标题".to_string())
-  }
-
-  /// Clean LaTeX text by removing common commands
-  fn clean_latex_text(&self, text: &str) -> String {
-    text.replace("\\textbf{", "")
-        .replace("\\textit{", "")
-        .replace("\\emph{", "")
-        .replace("\\section{", "")
-        .replace("\\subsection{", "")
-        .replace("\\subsubsection{", "")
-        .replace('}', "")
-        .replace('\\', "")
-        .trim()
-        .to_string()
+type Outputs = HashMap<String, FlowValue>;
+
+fn outputs<const N: usize>(entries: [(&str, Value); N]) -> Outputs {
+  entries
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), FlowValue::Json(value)))
+    .collect()
+}
+
+/// The text input `name`
+fn text_input(inputs: &AsyncNodeInputs, name: &str) -> Result<String> {
+  match inputs.get(name) {
+    Some(FlowValue::Json(Value::String(text))) => Ok(text.clone()),
+    _ => Err(anyhow::anyhow!("Missing input {}", name)),
   }
+}
 
-  /// Generate mind maps for each extracted section
-  async fn generate_section_mind_maps(&self, shared_state: &SharedState) -> Result<()> {
-    let sections_output = shared_state.get("section_extraction_output")
-      .ok_or_else(|| anyhow::anyhow!("Sections output not found"))?;
+/// One node of the flow, running its step against the run's context
+struct StageNode {
+  step: Step,
+  context: Arc<StageContext>,
+}
 
-    let sections_text = sections_output.as_str()
-      .ok_or_else(|| anyhow::anyhow!("No sections text in output"))?;
+#[async_trait]
+impl AsyncNode for StageNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let context = &self.context;
+    let result = match &self.step {
+      Step::Fetch => context.fetch(inputs).await,
+      Step::Enrichment => context.enrich(inputs).await,
+      Step::Summary(language) => context.summarize(language, inputs).await,
+      Step::Translation(language) => context.translate(language, inputs).await,
+      Step::Sections => context.extract_sections(inputs).await,
+      Step::MindMap => context.mind_map(inputs).await,
+      Step::Poster => context.generate_poster(inputs).await,
+    };
+    result.map_err(|e| AgentFlowError::NodeExecutionFailed {
+      message: e.to_string(),
+    })
+  }
+}
 
-    // Parse sections from the extraction output
-    let sections = extract_paper_sections(sections_text)?;
-    
-    log::info!("Found {} sections for mind mapping", sections.len());
+/// Extract the best available paper content from ArXiv output
+fn extract_paper_content(arxiv_output: &Value) -> Result<String> {
+  // Try to get expanded LaTeX content first (most comprehensive)
+  if let Some(latex_info) = arxiv_output.get("latex_info") {
+    if let Some(expanded_content) = latex_info.get("expanded_content")
+      && let Some(content) = expanded_content.as_str()
+      && !content.trim().is_empty()
+    {
+      return Ok(content.to_string());
+    }
 
-    for (i, section) in sections.iter().enumerate() {
-      log::info!("Generating mind map for section {}: {}", i + 1, section.title);
+    // Fall back to main content
+    if let Some(main_content) = latex_info.get("main_content")
+      && let Some(content) = main_content.as_str()
+      && !content.trim().is_empty()
+    {
+      return Ok(content.to_string());
+    }
+  }
+
+  // Fall back to simple LaTeX content
+  if let Some(simple_content) = arxiv_output.get("simple_latex_content")
+    && let Some(content) = simple_content.as_str()
+    && !content.trim().is_empty()
+  {
+    return Ok(content.to_string());
+  }
+
+  Err(anyhow::anyhow!(
+    "No usable paper content found in ArXiv output"
+  ))
+}
+
+/// Extract paper title from LaTeX content
+fn extract_paper_title(paper_content: &str) -> String {
+  // Look for \title{...} in LaTeX content
+  if let Some(start) = paper_content.find("\\title{") {
+    let title_start = start + 7; // Length of "\title{"
+    if let Some(end) = paper_content[title_start..].find('}') {
+      let title = &paper_content[title_start..title_start + end];
+      // Clean up LaTeX commands and return
+      return clean_latex_text(title);
+    }
+  }
+
+  // Fall back to first line or default
+  paper_content
+    .lines()
+    .next()
+    .map(clean_latex_text)
+    .filter(|line| !line.trim().is_empty())
+    .unwrap_or_else(|| "未知论文标题".to_string())
+}
+
+/// Clean LaTeX text by removing common commands
+fn clean_latex_text(text: &str) -> String {
+  text
+    .replace("\\textbf{", "")
+    .replace("\\textit{", "")
+    .replace("\\emph{", "")
+    .replace("\\section{", "")
+    .replace("\\subsection{", "")
+    .replace("\\subsubsection{", "")
+    .replace(['}', '\\'], "")
+    .trim()
+    .to_string()
+}
+
+/// The stages backed by the agentflow nodes. Each call runs its node
+/// on inputs of its own, so stages can run side by side.
+struct NodeStages {
+  /// ArXiv paper fetching node
+  arxiv_node: ArxivNode,
+  /// Runs every summary and section extraction call
+  llm_node: LlmNode,
+  /// Summary calls, by language
+  summary_calls: HashMap<String, LlmCall>,
+  /// Translator nodes, one chunk at a time, by language
+  translation_nodes: HashMap<String, TranslatorNode>,
+  /// Section content extraction call
+  section_extraction_call: LlmCall,
+  /// Text-to-image node for poster generation
+  poster_node: TextToImageNode,
+  /// Semantic Scholar lookups
+  enrichment_node: EnrichmentNode,
+  /// Where the mind map HTML files go
+  output_directory: String,
+}
+
+impl NodeStages {
+  fn new(config: &PaperAssistantConfig) -> Self {
+    // Create ArXiv node; the LaTeX source is the paper content
+    let mut arxiv_node = ArxivNode::new("arxiv_fetch", "{{arxiv_url}}")
+      .with_fetch_source(true)
+      .with_simplify_latex(true);
+    if let Some(cache_dir) = &config.arxiv_cache_dir {
+      arxiv_node = arxiv_node.with_cache_dir(cache_dir);
+    }
+
+    let mut summary_calls = HashMap::new();
+    let mut translation_nodes = HashMap::new();
+    for language in &config.languages {
+      // Create the summary LLM call
+      let summary_call = LlmCall {
+        prompt: config.summary_prompt(language),
+        model: config.stage_model(Stage::Summary).to_string(),
+        temperature: config.temperature.unwrap_or(0.3),
+        max_tokens: config.max_tokens.unwrap_or(4000),
+      };
+      summary_calls.insert(language.clone(), summary_call);
 
-      // Create markdown content for this section
-      let section_markdown = create_section_markdown(&section.title, &section.content);
-      
-      // Create a MarkMap node for this section
-      let mut markmap_node = MarkMapNode::new(
-        &format!("mind_map_section_{}", i + 1),
-        &section_markdown,
+      // Create the translator node; the prompt's paper content is the
+      // chunk being translated, and a chunk is cut again at its headings
+      // when its translation might not fit in the reply
+      let max_tokens = config.max_tokens.unwrap_or(8000);
+      let translation_node = TranslatorNode::new(
+        &format!("translation_{}", language),
+        config.stage_model(Stage::Translation),
+        language_name(language),
       )
-      .with_output_key(format!("mind_map_section_{}_output", i + 1))
-      .with_file_output(format!(
-        "{}/mind_map_section_{}_{}.html",
-        self.config.output_directory,
-        i + 1,
-        section.title.chars().take(20).collect::<String>()
-          .replace(' ', "_")
-          .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
-      ));
+      .with_prompt(&fill_placeholders(
+        &config.translation_prompt(language),
+        &[("paper_content", "{{text}}")],
+      ))
+      .with_temperature(config.temperature.unwrap_or(0.3))
+      .with_max_tokens(max_tokens)
+      .with_max_chunk_tokens(max_tokens as usize / 2)
+      .with_glossary(config.glossary.clone());
+      translation_nodes.insert(language.clone(), translation_node);
+    }
 
-      // Set section metadata in shared state
-      shared_state.insert(format!("section_{}_title", i + 1), json!(section.title));
-      shared_state.insert(format!("section_{}_number", i + 1), json!(section.number));
+    // Create section extraction LLM call; sections are written in the
+    // primary language, so the mind maps are too
+    let section_extraction_call = LlmCall {
+      prompt: config.section_prompt(),
+      model: config.stage_model(Stage::MindMap).to_string(),
+      temperature: config.temperature.unwrap_or(0.2),
+      max_tokens: config.max_tokens.unwrap_or(6000),
+    };
 
-      // Execute the MarkMap node
-      match markmap_node.run_async(shared_state).await {
-        Ok(_) => {
-          log::info!("Successfully generated mind map for section {}", i + 1);
-          // Note: Section metadata was already set before executing the node
-        },
-        Err(e) => {
-          log::warn!("Failed to generate mind map for section {}: {}", i + 1, e);
-          // Continue with other sections even if one fails
-        }
-      }
+    // Create poster generation node; the workflow composes its prompt,
+    // and the image is saved next to the other outputs
+    let poster_node = TextToImageNode::new("poster_generation", config.stage_model(Stage::Poster))
+      .with_prompt("{{poster_prompt}}")
+      .with_output_key("poster_image_output")
+      .with_size(&config.poster_size)
+      .with_output_path(&format!("{}/poster.{{ext}}", config.output_directory));
+
+    // Semantic Scholar lookups, cached across runs
+    let mut enrichment_node = EnrichmentNode::new("enrichment")
+      .with_api_key(std::env::var("SEMANTIC_SCHOLAR_API_KEY").ok());
+    let enrichment_cache_dir = config
+      .enrichment_cache_dir
+      .clone()
+      .map(PathBuf::from)
+      .or_else(EnrichmentNode::default_cache_dir);
+    if let Some(cache_dir) = enrichment_cache_dir {
+      enrichment_node = enrichment_node.with_cache_dir(cache_dir);
     }
 
-    Ok(())
+    Self {
+      arxiv_node,
+      enrichment_node,
+      llm_node: LlmNode::new(),
+      summary_calls,
+      translation_nodes,
+      section_extraction_call,
+      poster_node,
+      output_directory: config.output_directory.clone(),
+    }
+  }
+
+  /// Runs `call` on `paper_content`; the reply is `response`
+  async fn complete(&self, call: &LlmCall, paper_content: &str) -> Result<Value> {
+    let output = json_outputs(self.llm_node.execute(&call.inputs(paper_content)).await?);
+    Ok(json!({
+      "response": output["output"],
+      "model": output["model"],
+      "usage": output["usage"],
+    }))
   }
 }
 
-/// Represents a paper section for mind mapping
+/// The prompt and settings of one `LlmNode` call; the prompt's
+/// `{{paper_content}}` is filled from the call's input
+#[derive(Debug)]
+struct LlmCall {
+  prompt: String,
+  model: String,
+  temperature: f32,
+  max_tokens: u32,
+}
+
+impl LlmCall {
+  fn inputs(&self, paper_content: &str) -> AsyncNodeInputs {
+    [
+      ("prompt", json!(self.prompt)),
+      ("model", json!(self.model)),
+      ("temperature", json!(self.temperature)),
+      ("max_tokens", json!(self.max_tokens)),
+      ("paper_content", json!(paper_content)),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), FlowValue::Json(value)))
+    .collect()
+  }
+}
+
+/// A node's outputs as one JSON object
+fn json_outputs(outputs: HashMap<String, FlowValue>) -> Value {
+  Value::Object(
+    outputs
+      .into_iter()
+      .map(|(name, value)| {
+        let value = match value {
+          FlowValue::Json(value) => value,
+          other => serde_json::to_value(other).unwrap_or(Value::Null),
+        };
+        (name, value)
+      })
+      .collect(),
+  )
+}
+
+#[async_trait]
+impl PaperStages for NodeStages {
+  async fn fetch(&self, arxiv_url: &str) -> Result<Value> {
+    let inputs =
+      AsyncNodeInputs::from([("arxiv_url".to_string(), FlowValue::Json(json!(arxiv_url)))]);
+    let mut output = json_outputs(
+      self
+        .arxiv_node
+        .execute(&inputs)
+        .await
+        .map_err(|e| anyhow::anyhow!("ArXiv fetch failed: {}", e))?,
+    );
+    // The LaTeX source is kept under `latex_info`, as the checkpoints
+    // of earlier runs hold it
+    if let Value::Object(fields) = &mut output {
+      let latex_info: serde_json::Map<String, Value> = ["expanded_content", "main_content"]
+        .into_iter()
+        .filter_map(|name| fields.remove(name).map(|value| (name.to_string(), value)))
+        .collect();
+      fields.insert("latex_info".to_string(), Value::Object(latex_info));
+    }
+    Ok(output)
+  }
+
+  async fn summarize(&self, language: &str, paper_content: &str) -> Result<Value> {
+    let call = self
+      .summary_calls
+      .get(language)
+      .ok_or_else(|| anyhow::anyhow!("No summary call for language {}", language))?;
+    self
+      .complete(call, paper_content)
+      .await
+      .map_err(|e| anyhow::anyhow!("Summary generation ({}) failed: {}", language, e))
+  }
+
+  async fn translate_chunk(&self, language: &str, chunk: &str) -> Result<String> {
+    let node = self
+      .translation_nodes
+      .get(language)
+      .ok_or_else(|| anyhow::anyhow!("No translation node for language {}", language))?;
+    let translation = node
+      .translate(chunk, &BTreeMap::new())
+      .await
+      .map_err(|e| anyhow::anyhow!("Translation ({}) failed: {}", language, e))?;
+    Ok(translation.text)
+  }
+
+  async fn extract_sections(&self, paper_content: &str) -> Result<String> {
+    let output = self
+      .complete(&self.section_extraction_call, paper_content)
+      .await
+      .map_err(|e| anyhow::anyhow!("Section extraction failed: {}", e))?;
+    output["response"]
+      .as_str()
+      .map(|text| text.to_string())
+      .ok_or_else(|| anyhow::anyhow!("No sections text in output"))
+  }
+
+  async fn generate_mind_map(&self, n: usize, section: &PaperSection) -> Result<MindMap> {
+    // Create markdown content for this section
+    let section_markdown = create_section_markdown(&section.title, &section.content);
+
+    // Create a MarkMap node for this section
+    let markmap_node =
+      MarkMapNode::new(format!("mind_map_section_{}", n), section_markdown.as_str())
+        .with_file_output(format!(
+          "{}/mind_map_section_{}_{}.html",
+          self.output_directory,
+          n,
+          sanitize_filename(&section.title)
+        ));
+
+    let output = json_outputs(
+      markmap_node
+        .execute(&AsyncNodeInputs::new())
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?,
+    );
+    Ok(MindMap {
+      markdown: output["markdown"]
+        .as_str()
+        .unwrap_or(&section_markdown)
+        .to_string(),
+      html: output["html"].as_str().unwrap_or("").to_string(),
+    })
+  }
+
+  async fn generate_poster(&self, prompt: &str) -> Result<Value> {
+    let inputs =
+      AsyncNodeInputs::from([("poster_prompt".to_string(), FlowValue::Json(json!(prompt)))]);
+    let mut output = json_outputs(
+      self
+        .poster_node
+        .execute(&inputs)
+        .await
+        .map_err(|e| anyhow::anyhow!("Poster generation failed: {}", e))?,
+    );
+    // The saved file is `image_path`
+    let image_path = output["image_paths"][0].clone();
+    if let Value::Object(fields) = &mut output {
+      fields.insert("image_path".to_string(), image_path);
+    }
+    Ok(output)
+  }
+
+  async fn enrich(&self, arxiv_id: &str) -> PaperEnrichment {
+    self.enrichment_node.enrich(arxiv_id).await
+  }
+}
+
+/// A section's mind map, as `PaperStages::generate_mind_map` draws it
 #[derive(Debug, Clone)]
+pub struct MindMap {
+  pub markdown: String,
+  pub html: String,
+}
+
+/// Represents a paper section for mind mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaperSection {
   pub title: String,
   pub number: Option<String>,
@@ -279,6 +1399,7 @@ pub struct PaperSection {
 mod tests {
   use super::*;
   use crate::config::PaperAssistantConfig;
+  use crate::poster::PosterStyle;
 
   #[test]
   fn test_workflow_creation() {
@@ -287,27 +1408,37 @@ mod tests {
     assert!(workflow.is_ok());
   }
 
+  #[test]
+  fn test_nodes_get_the_stage_models() {
+    let mut config = PaperAssistantConfig::default();
+    config
+      .stage_models
+      .set(Stage::Translation, "claude-3-5-sonnet");
+    config.stage_models.set(Stage::Poster, "qwen-image-plus");
+    let stages = NodeStages::new(&config);
+
+    assert_eq!(stages.translation_nodes["zh"].model, "claude-3-5-sonnet");
+    assert_eq!(stages.summary_calls["zh"].model, "qwen-turbo");
+    assert_eq!(stages.section_extraction_call.model, "qwen-turbo");
+    assert_eq!(stages.poster_node.model, "qwen-image-plus");
+    assert_eq!(stages.translation_nodes.len(), 1);
+  }
+
   #[test]
   fn test_paper_title_extraction() {
-    let config = PaperAssistantConfig::default();
-    let workflow = PaperAssistantWorkflow::new(&config).unwrap();
-    
     let latex_content = r#"\documentclass{article}
 \title{A Great Paper About Machine Learning}
 \author{John Doe}
 \begin{document}"#;
-    
-    let title = workflow.extract_paper_title(latex_content);
+
+    let title = extract_paper_title(latex_content);
     assert_eq!(title, "A Great Paper About Machine Learning");
   }
 
   #[test]
   fn test_latex_text_cleaning() {
-    let config = PaperAssistantConfig::default();
-    let workflow = PaperAssistantWorkflow::new(&config).unwrap();
-    
     let dirty_text = r#"\textbf{Bold Text} and \textit{Italic Text}"#;
-    let clean_text = workflow.clean_latex_text(dirty_text);
+    let clean_text = clean_latex_text(dirty_text);
     assert_eq!(clean_text, "Bold Text and Italic Text");
   }
 
@@ -318,9 +1449,478 @@ mod tests {
       number: Some("1".to_string()),
       content: "This is the introduction section.".to_string(),
     };
-    
+
     assert_eq!(section.title, "Introduction");
     assert_eq!(section.number, Some("1".to_string()));
     assert!(section.content.contains("introduction"));
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_flow_shape() {
+    let config = PaperAssistantConfig {
+      languages: vec!["en".to_string(), "zh".to_string()],
+      ..PaperAssistantConfig::default()
+    };
+    let workflow = PaperAssistantWorkflow::with_stages(&config, Box::new(MockStages::default()));
+
+    let flow = workflow.build_flow(&SharedState::new());
+    assert_eq!(
+      flow.to_mermaid(),
+      [
+        "flowchart TD",
+        "  n0[\"arxiv_fetch\"]",
+        "  n1[\"section_extraction\"]",
+        "  n2[\"summary_en\"]",
+        "  n3[\"summary_zh\"]",
+        "  n4[\"translation_en\"]",
+        "  n5[\"translation_zh\"]",
+        "  n6[[\"mind_maps (map)\"]]",
+        "  n7[\"poster\"]",
+        "  n0 --> n1",
+        "  n0 --> n2",
+        "  n0 --> n3",
+        "  n0 --> n4",
+        "  n0 --> n5",
+        "  n1 --> n6",
+        "  n0 --> n7",
+        "  n2 --> n7",
+        "  n6 --> n7",
+        "",
+      ]
+      .join("\n")
+    );
+    match &flow.nodes()["mind_maps"].node_type {
+      NodeType::Map {
+        parallel,
+        max_concurrent,
+        ..
+      } => {
+        assert!(*parallel);
+        assert_eq!(*max_concurrent, Some(config.mind_map_concurrency));
+      }
+      _ => panic!("mind_maps is not a Map node"),
+    }
+  }
+
+  /// Stands in for arXiv and the models; the mind map of section
+  /// `fail_mind_map` fails, the content filter rejects the first
+  /// `filtered_posters` poster prompts, and Semantic Scholar is down when
+  /// `enrichment_offline`
+  #[derive(Default)]
+  struct MockStages {
+    fail_mind_map: Option<usize>,
+    filtered_posters: usize,
+    enrichment_offline: bool,
+    poster_prompts: Arc<Mutex<Vec<String>>>,
+  }
+
+  #[async_trait]
+  impl PaperStages for MockStages {
+    async fn fetch(&self, _arxiv_url: &str) -> Result<Value> {
+      Ok(json!({
+        "paper_id": "2312.07104",
+        "simple_latex_content": "\\title{Mock Paper}\n\nFirst paragraph.\n\nSecond paragraph."
+      }))
+    }
+
+    async fn summarize(&self, _language: &str, _paper_content: &str) -> Result<Value> {
+      Ok(json!({ "response": "摘要", "usage": { "prompt_tokens": 120, "completion_tokens": 30 } }))
+    }
+
+    async fn translate_chunk(&self, language: &str, chunk: &str) -> Result<String> {
+      Ok(format!("[{}] {}", language, chunk))
+    }
+
+    async fn extract_sections(&self, _paper_content: &str) -> Result<String> {
+      Ok("## 章节 1：引言\n### 内容摘要\n引言内容\n---\n## 章节 2：方法\n### 内容摘要\n方法内容\n---".to_string())
+    }
+
+    async fn generate_mind_map(&self, n: usize, section: &PaperSection) -> Result<MindMap> {
+      if self.fail_mind_map == Some(n) {
+        anyhow::bail!("MarkMap service unavailable");
+      }
+      Ok(MindMap {
+        markdown: format!("# {}", section.title),
+        html: format!("<html>{}</html>", section.title),
+      })
+    }
+
+    async fn generate_poster(&self, prompt: &str) -> Result<Value> {
+      let mut prompts = self.poster_prompts.lock().unwrap();
+      prompts.push(prompt.to_string());
+      if prompts.len() <= self.filtered_posters {
+        anyhow::bail!("Poster generation failed: image 0 was content_filtered");
+      }
+      Ok(json!({ "image_path": "poster.png" }))
+    }
+
+    async fn enrich(&self, arxiv_id: &str) -> PaperEnrichment {
+      if self.enrichment_offline {
+        return PaperEnrichment::degraded("Semantic Scholar unavailable: connection refused");
+      }
+      PaperEnrichment {
+        citation_count: Some(42),
+        fields_of_study: vec![format!("Computer Science ({})", arxiv_id)],
+        ..PaperEnrichment::default()
+      }
+    }
+  }
+
+  #[derive(Default)]
+  struct RecordingReporter(std::sync::Mutex<Vec<ProgressEvent>>);
+
+  impl ProgressReporter for RecordingReporter {
+    fn report(&self, event: &ProgressEvent) {
+      self.0.lock().unwrap().push(event.clone());
+    }
+  }
+
+  fn temp_run_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("paper_assistant_runs_{}", uuid::Uuid::new_v4()))
+  }
+
+  #[tokio::test]
+  async fn test_stage_events_are_reported_in_order() {
+    let config = PaperAssistantConfig {
+      translation_chunk_chars: Some(40),
+      save_intermediate_files: false,
+      glossary: BTreeMap::from([("second".to_string(), "第二".to_string())]),
+      ..PaperAssistantConfig::default()
+    };
+    let reporter = Arc::new(RecordingReporter::default());
+    let run_dir = temp_run_dir();
+    let mut workflow =
+      PaperAssistantWorkflow::with_stages(&config, Box::new(MockStages::default()))
+        .with_progress(reporter.clone())
+        .with_run_dir(&run_dir);
+
+    let shared_state = SharedState::new();
+    shared_state.insert(
+      "arxiv_url".to_string(),
+      json!("https://arxiv.org/abs/2312.07104"),
+    );
+    workflow.execute(&shared_state).await.unwrap();
+
+    // The fetch comes first and the poster last; the stages in between
+    // run side by side, each reporting in its own order.
+    let events = reporter.0.lock().unwrap().clone();
+    assert_eq!(events.len(), 10);
+    assert_eq!(
+      events[..2],
+      [
+        ProgressEvent::ArxivFetchStarted {
+          url: "https://arxiv.org/abs/2312.07104".to_string()
+        },
+        ProgressEvent::ArxivFetchFinished {
+          paper_id: "2312.07104".to_string()
+        },
+      ]
+    );
+    assert_eq!(
+      events[8..],
+      [
+        ProgressEvent::PosterStarted,
+        ProgressEvent::PosterFinished {
+          image_path: Some("poster.png".to_string())
+        },
+      ]
+    );
+    let middle = |keep: fn(&ProgressEvent) -> bool| -> Vec<ProgressEvent> {
+      events[2..8]
+        .iter()
+        .filter(|event| keep(event))
+        .cloned()
+        .collect()
+    };
+    assert_eq!(
+      middle(|event| matches!(
+        event,
+        ProgressEvent::SummaryStarted { .. } | ProgressEvent::SummaryFinished { .. }
+      )),
+      vec![
+        ProgressEvent::SummaryStarted {
+          language: "zh".to_string()
+        },
+        ProgressEvent::SummaryFinished {
+          language: "zh".to_string(),
+          prompt_tokens: Some(120),
+          completion_tokens: Some(30),
+        },
+      ]
+    );
+    assert_eq!(
+      middle(|event| matches!(event, ProgressEvent::TranslationProgress { .. })),
+      vec![
+        ProgressEvent::TranslationProgress {
+          language: "zh".to_string(),
+          chunk: 1,
+          total: 2
+        },
+        ProgressEvent::TranslationProgress {
+          language: "zh".to_string(),
+          chunk: 2,
+          total: 2
+        },
+      ]
+    );
+    let mut sections = middle(|event| matches!(event, ProgressEvent::MindMapGenerated { .. }));
+    sections.sort_by_key(|event| format!("{:?}", event));
+    assert_eq!(
+      sections,
+      vec![
+        ProgressEvent::MindMapGenerated {
+          section: "引言".to_string()
+        },
+        ProgressEvent::MindMapGenerated {
+          section: "方法".to_string()
+        },
+      ]
+    );
+
+    let translation = shared_state.get("translation_output_zh").unwrap();
+    assert_eq!(
+      translation["response"],
+      "[zh] \\title{Mock Paper}\n\nFirst paragraph.\n\n[zh] Second paragraph."
+    );
+    // The mock leaves "Second" untranslated in the second chunk.
+    assert_eq!(
+      translation["glossary_violations"],
+      json!([{ "chunk": 1, "term": "second", "expected": "第二" }])
+    );
+    assert_eq!(
+      shared_state.get("paper_title").unwrap(),
+      json!("Mock Paper")
+    );
+    assert_eq!(shared_state.get("summary").unwrap(), json!("摘要"));
+
+    let _ = std::fs::remove_dir_all(&run_dir);
+  }
+
+  #[tokio::test]
+  async fn test_failed_mind_maps_are_listed() {
+    let config = PaperAssistantConfig {
+      save_intermediate_files: false,
+      ..PaperAssistantConfig::default()
+    };
+    let stages = MockStages {
+      fail_mind_map: Some(1),
+      ..Default::default()
+    };
+    let run_dir = temp_run_dir();
+    let mut workflow =
+      PaperAssistantWorkflow::with_stages(&config, Box::new(stages)).with_run_dir(&run_dir);
+
+    let shared_state = SharedState::new();
+    shared_state.insert(
+      "arxiv_url".to_string(),
+      json!("https://arxiv.org/abs/2312.07104"),
+    );
+    workflow.execute(&shared_state).await.unwrap();
+
+    let mind_maps: Vec<MindMapResult> =
+      serde_json::from_value(shared_state.get(MIND_MAPS_KEY).unwrap()).unwrap();
+    assert_eq!(mind_maps.len(), 1);
+    assert_eq!(mind_maps[0].section_index, 2);
+    assert_eq!(mind_maps[0].section_number.as_deref(), Some("2"));
+    assert_eq!(mind_maps[0].mind_map_html, "<html>方法</html>");
+
+    let failures: Vec<MindMapFailure> =
+      serde_json::from_value(shared_state.get(MIND_MAP_FAILURES_KEY).unwrap()).unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].section_title, "引言");
+    assert!(failures[0].error.contains("MarkMap service unavailable"));
+
+    let _ = std::fs::remove_dir_all(&run_dir);
+  }
+
+  async fn run_poster(config: &PaperAssistantConfig, stages: MockStages) -> Result<Value> {
+    let run_dir = temp_run_dir();
+    let mut workflow =
+      PaperAssistantWorkflow::with_stages(config, Box::new(stages)).with_run_dir(&run_dir);
+    let shared_state = SharedState::new();
+    shared_state.insert(
+      "arxiv_url".to_string(),
+      json!("https://arxiv.org/abs/2312.07104"),
+    );
+    let result = workflow.execute(&shared_state).await;
+    let _ = std::fs::remove_dir_all(&run_dir);
+    result.map(|_| shared_state.get("poster_image_output").unwrap())
+  }
+
+  #[tokio::test]
+  async fn test_poster_prompt_is_composed_from_the_summary() {
+    let config = PaperAssistantConfig {
+      save_intermediate_files: false,
+      poster_style: PosterStyle::Infographic,
+      poster_size: "1024x1536".to_string(),
+      ..PaperAssistantConfig::default()
+    };
+    let stages = MockStages::default();
+    let prompts = Arc::clone(&stages.poster_prompts);
+
+    let poster = run_poster(&config, stages).await.unwrap();
+    let prompt = prompts.lock().unwrap()[0].clone();
+    assert!(prompt.contains("Title: Mock Paper\nField: science\nKey findings:\n1. 摘要\n"));
+    assert!(prompt.contains("a portrait poster"));
+    assert!(prompt.contains(PosterStyle::Infographic.description()));
+    assert_eq!(poster["prompt"], json!(prompt));
+    assert_eq!(poster["filter_reason"], Value::Null);
+  }
+
+  #[tokio::test]
+  async fn test_poster_retries_once_with_a_simplified_prompt_when_filtered() {
+    let config = PaperAssistantConfig {
+      save_intermediate_files: false,
+      ..PaperAssistantConfig::default()
+    };
+    let stages = MockStages {
+      filtered_posters: 1,
+      ..Default::default()
+    };
+    let prompts = Arc::clone(&stages.poster_prompts);
+
+    let poster = run_poster(&config, stages).await.unwrap();
+    let prompts = prompts.lock().unwrap().clone();
+    assert_eq!(prompts.len(), 2);
+    assert!(prompts[0].contains("Key findings"));
+    assert!(
+      prompts[1]
+        .starts_with("A square poster for a research paper in science titled \"Mock Paper\"")
+    );
+    assert_eq!(poster["image_path"], "poster.png");
+    assert_eq!(poster["prompt"], json!(prompts[1]));
+    assert!(
+      poster["filter_reason"]
+        .as_str()
+        .unwrap()
+        .contains("content_filtered")
+    );
+
+    // A second rejection fails the stage instead of trying again
+    let stages = MockStages {
+      filtered_posters: 2,
+      ..Default::default()
+    };
+    let prompts = Arc::clone(&stages.poster_prompts);
+    let error = run_poster(&config, stages).await.unwrap_err();
+    assert_eq!(prompts.lock().unwrap().len(), 2);
+    assert!(
+      error
+        .to_string()
+        .contains("after the content filter rejected the full prompt")
+    );
+  }
+
+  #[tokio::test]
+  async fn test_stage_metrics_are_collected_and_priced() {
+    let pricing_file = std::env::temp_dir().join(format!(
+      "paper_assistant_pricing_{}.yml",
+      uuid::Uuid::new_v4()
+    ));
+    std::fs::write(
+      &pricing_file,
+      "models:\n  qwen-turbo:\n    input_per_1k: 1.0\n    output_per_1k: 2.0\n",
+    )
+    .unwrap();
+    let config = PaperAssistantConfig {
+      save_intermediate_files: false,
+      translation_chunk_chars: Some(40),
+      pricing_file: Some(pricing_file.to_string_lossy().into_owned()),
+      ..PaperAssistantConfig::default()
+    };
+    let stages = MockStages {
+      filtered_posters: 1,
+      ..Default::default()
+    };
+    let run_dir = temp_run_dir();
+    let mut workflow =
+      PaperAssistantWorkflow::with_stages(&config, Box::new(stages)).with_run_dir(&run_dir);
+
+    let shared_state = SharedState::new();
+    shared_state.insert(
+      "arxiv_url".to_string(),
+      json!("https://arxiv.org/abs/2312.07104"),
+    );
+    workflow.execute(&shared_state).await.unwrap();
+    let _ = std::fs::remove_dir_all(&run_dir);
+    let _ = std::fs::remove_file(&pricing_file);
+
+    let metrics: Vec<StageMetric> =
+      serde_json::from_value(shared_state.get(STAGE_METRICS_KEY).unwrap()).unwrap();
+    let metric = |stage: &str| {
+      metrics
+        .iter()
+        .find(|metric| metric.stage == stage)
+        .unwrap()
+        .clone()
+    };
+    assert_eq!(metrics.len(), 6);
+    assert_eq!(metrics[0].stage, ARXIV_FETCH);
+    assert_eq!(metrics[5].stage, POSTER);
+
+    let summary = metric("summary_zh");
+    assert_eq!(summary.model.as_deref(), Some("qwen-turbo"));
+    assert_eq!(
+      (
+        summary.calls,
+        summary.prompt_tokens,
+        summary.completion_tokens
+      ),
+      (1, Some(120), Some(30))
+    );
+    assert_eq!(summary.cost_usd, Some(0.18));
+    // One row per stage: the translation's chunks and the sections' mind
+    // maps are added up
+    assert_eq!(metric("translation_zh").calls, 2);
+    assert_eq!(metric("translation_zh").cost_usd, None);
+    assert_eq!(metric(MIND_MAPS).calls, 2);
+    assert_eq!((metric(POSTER).calls, metric(POSTER).retries), (2, 1));
+  }
+
+  /// Runs the paper with `stages` and returns the shared state it left
+  async fn run_paper(config: &PaperAssistantConfig, stages: MockStages) -> SharedState {
+    let run_dir = temp_run_dir();
+    let mut workflow =
+      PaperAssistantWorkflow::with_stages(config, Box::new(stages)).with_run_dir(&run_dir);
+    let shared_state = SharedState::new();
+    shared_state.insert(
+      "arxiv_url".to_string(),
+      json!("https://arxiv.org/abs/2312.07104"),
+    );
+    workflow.execute(&shared_state).await.unwrap();
+    let _ = std::fs::remove_dir_all(&run_dir);
+    shared_state
+  }
+
+  #[tokio::test]
+  async fn test_enrichment_is_opt_in_and_degrades_when_offline() {
+    let mut config = PaperAssistantConfig {
+      save_intermediate_files: false,
+      ..PaperAssistantConfig::default()
+    };
+    let shared_state = run_paper(&config, MockStages::default()).await;
+    assert!(shared_state.get(ENRICHMENT_KEY).is_none());
+
+    config.enable_enrichment = true;
+    let shared_state = run_paper(&config, MockStages::default()).await;
+    let enrichment: PaperEnrichment =
+      serde_json::from_value(shared_state.get(ENRICHMENT_KEY).unwrap()).unwrap();
+    assert_eq!(enrichment.citation_count, Some(42));
+    assert_eq!(
+      enrichment.fields_of_study,
+      vec!["Computer Science (2312.07104)"]
+    );
+
+    // Semantic Scholar being down does not fail the paper
+    let stages = MockStages {
+      enrichment_offline: true,
+      ..Default::default()
+    };
+    let shared_state = run_paper(&config, stages).await;
+    let enrichment: PaperEnrichment =
+      serde_json::from_value(shared_state.get(ENRICHMENT_KEY).unwrap()).unwrap();
+    assert!(enrichment.is_empty());
+    assert!(enrichment.warning.unwrap().contains("unavailable"));
+    assert!(shared_state.get("poster_image_output").is_some());
+  }
+}
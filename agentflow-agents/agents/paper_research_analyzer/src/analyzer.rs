@@ -1,32 +1,115 @@
 //! Paper Research Analyzer Core Implementation
 
-use crate::config::{AnalyzerConfig, AnalysisDepth};
+use crate::batch::{
+  BatchOptions, BatchProgress, ProgressCallback, batch_processor, config_hash, from_directory,
+  print_progress,
+};
+use crate::chunking::{ChunkedAnalysis, map_reduce};
+use crate::citations::Citations;
+use crate::comparison::{Comparison, comparison_markdown, model_comparison, structured_comparison};
+use crate::config::{AnalysisDepth, AnalyzerConfig};
+use crate::extraction::{Backend, LocalPdfExtractor, extract_with, with_ocr_fallback};
+use crate::insights::PaperInsights;
+use crate::llm::ModelLlm;
+use agentflow_agents::eval::PricingTable;
 use agentflow_agents::{
-  AgentApplication, FileAgent, AgentResult, AgentConfig,
-  AsyncFlow, SharedState, AgentFlow,
-  StepFunPDFParser, BatchProcessor, default_batch_processor
+  AdaptiveThrottle, AgentApplication, AgentConfig, AgentFlow, AgentResult, ArtifactWriter,
+  BatchProcessor, CacheControl, DirectoryOptions, FileAgent, OcrBackend, PDFContent, PageImage,
+  PdfParser, RetryPolicy, StageCache, StageMetric, StageMetrics, extract_pdf_images, page_scans,
+  stage_metrics_table,
 };
+use agentflow_core::legacy::v1::{AsyncFlow, AsyncNode, SharedState};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::path::Path;
-use std::collections::HashMap;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The StepFun parser, reusing uploads recorded in the shared upload cache
+fn pdf_parser(config: &AnalyzerConfig) -> PdfParser {
+  let parser = PdfParser::new(config.stepfun_api_key.clone());
+  match PdfParser::default_cache_file() {
+    Some(cache_file) => parser.with_cache_file(cache_file),
+    None => parser,
+  }
+}
+
+/// The stage cache `config.cache` and `config.cache_dir` describe
+fn stage_cache(config: &AnalyzerConfig) -> StageCache {
+  match &config.cache_dir {
+    Some(dir) => StageCache::new(dir, config.cache),
+    None => StageCache::at_default_dir(config.cache),
+  }
+}
+
+/// A sink for one paper's stage metrics, pricing tokens with
+/// `config.pricing_file` when it loads
+fn stage_metrics(config: &AnalyzerConfig) -> StageMetrics {
+  let Some(path) = &config.pricing_file else {
+    return StageMetrics::new();
+  };
+  match PricingTable::load_from_yaml(path) {
+    Ok(pricing) => StageMetrics::new().with_pricing(pricing),
+    Err(e) => {
+      println!("⚠️  Reporting stage metrics without costs: {}", e);
+      StageMetrics::new()
+    }
+  }
+}
+
+/// `node`, recording its work as `stage` in `metrics`
+fn observed(
+  stage: &str,
+  node: impl AsyncNode + 'static,
+  metrics: &StageMetrics,
+) -> Box<dyn AsyncNode> {
+  Box::new(crate::nodes::StageObserverNode::new(
+    stage,
+    Box::new(node),
+    metrics.clone(),
+  ))
+}
 
 /// PDF Research Paper Analyzer
 pub struct PDFAnalyzer {
   config: AnalyzerConfig,
-  pdf_parser: StepFunPDFParser,
-  batch_processor: BatchProcessor,
+  pdf_parser: PdfParser,
+  batch_state_path: Option<PathBuf>,
+  resume: bool,
+  batch_retry: Option<RetryPolicy>,
+  batch_throttle: Option<AdaptiveThrottle>,
+  batch_progress: ProgressCallback,
+  /// Search a batch directory's subdirectories too
+  recursive: bool,
+  /// Analyze the PDFs symlinks in a batch directory point to
+  follow_symlinks: bool,
+  /// Summaries and insights reused across runs; every paper of a batch
+  /// shares it
+  stage_cache: StageCache,
 }
 
 impl PDFAnalyzer {
   pub fn new(stepfun_api_key: String) -> Self {
-    let mut config = AnalyzerConfig::default();
-    config.stepfun_api_key = stepfun_api_key.clone();
-    
+    Self::with_config(AnalyzerConfig {
+      stepfun_api_key,
+      ..AnalyzerConfig::default()
+    })
+  }
+
+  fn with_config(config: AnalyzerConfig) -> Self {
     Self {
-      pdf_parser: StepFunPDFParser::new(stepfun_api_key),
-      batch_processor: default_batch_processor(),
+      pdf_parser: pdf_parser(&config),
+      batch_state_path: None,
+      resume: false,
+      batch_retry: None,
+      batch_throttle: None,
+      batch_progress: Arc::new(print_progress),
+      recursive: false,
+      follow_symlinks: false,
+      stage_cache: stage_cache(&config),
       config,
     }
   }
@@ -37,6 +120,40 @@ impl PDFAnalyzer {
     self
   }
 
+  /// Whether summaries and insights are reused from and saved to the
+  /// stage cache
+  pub fn cache(mut self, control: CacheControl) -> Self {
+    self.config.cache = control;
+    self.stage_cache = stage_cache(&self.config);
+    self
+  }
+
+  /// Keep the stage cache in `dir` instead of `~/.agentflow/cache/agents`
+  pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.config.cache_dir = Some(dir.into());
+    self.stage_cache = stage_cache(&self.config);
+    self
+  }
+
+  /// The stage cache, with its hits and misses so far
+  pub fn stage_cache(&self) -> &StageCache {
+    &self.stage_cache
+  }
+
+  /// Price each stage's tokens in the stage metrics with the pricing
+  /// table (`pricing.yml`) at `path`
+  pub fn pricing_file(mut self, path: impl Into<PathBuf>) -> Self {
+    self.config.pricing_file = Some(path.into());
+    self
+  }
+
+  /// Terms the translation must render exactly as given, e.g.
+  /// "attention" → "注意力"
+  pub fn glossary(mut self, glossary: BTreeMap<String, String>) -> Self {
+    self.config.glossary = glossary;
+    self
+  }
+
   pub fn analysis_depth(mut self, depth: AnalysisDepth) -> Self {
     self.config.analysis_depth = depth;
     self
@@ -52,103 +169,369 @@ impl PDFAnalyzer {
     self
   }
 
+  /// Whether the model drafts the mind map, or it is built from the
+  /// summary and insights without one
+  pub fn mind_map_use_llm(mut self, use_llm: bool) -> Self {
+    self.config.mind_map_use_llm = use_llm;
+    self
+  }
+
+  /// Parse the paper's references; `clean_up` has the model repair the
+  /// entries the heuristics could not
+  pub fn extract_citations(mut self, enable: bool, clean_up: bool) -> Self {
+    self.config.extract_citations = enable;
+    self.config.clean_citations = clean_up;
+    self
+  }
+
+  /// Where PDF text comes from: StepFun (the default), local extraction, or
+  /// local extraction with a StepFun fallback
+  pub fn extraction_backend(mut self, backend: Backend) -> Self {
+    self.config.extraction_backend = backend;
+    self
+  }
+
+  /// Read scanned PDFs, whose extracted text is too thin, with `ocr`
+  pub fn ocr(mut self, ocr: OcrBackend) -> Self {
+    self.config.ocr = ocr;
+    self
+  }
+
+  /// Save the PDF's embedded images with the results, linked from the
+  /// summary
+  pub fn extract_figures(mut self, enable: bool) -> Self {
+    self.config.extract_figures = enable;
+    self
+  }
+
+  /// Analyze only `pages` (0-based, end exclusive) of each paper
+  pub fn pages(mut self, pages: Range<usize>) -> Self {
+    self.config.pages = Some(pages);
+    self
+  }
+
+  /// How many papers a batch analyzes at once
+  pub fn concurrency(mut self, limit: usize) -> Self {
+    self.config.concurrency_limit = limit;
+    self
+  }
+
+  /// Record each finished paper of a batch in `path` (usually
+  /// `<output_dir>/batch_state.json`) as soon as it finishes
+  pub fn batch_state(mut self, path: impl Into<PathBuf>) -> Self {
+    self.batch_state_path = Some(path.into());
+    self
+  }
+
+  /// Skip the papers the batch state records as finished with the same
+  /// file and settings
+  pub fn resume(mut self, resume: bool) -> Self {
+    self.resume = resume;
+    self
+  }
+
+  /// Retry a paper of a batch that failed, under `policy`
+  pub fn batch_retry(mut self, policy: RetryPolicy) -> Self {
+    self.batch_retry = Some(policy);
+    self
+  }
+
+  /// Slow a batch down while too many recent papers fail, as they do when
+  /// the provider starts rate limiting
+  pub fn batch_throttle(mut self, throttle: AdaptiveThrottle) -> Self {
+    self.batch_throttle = Some(throttle);
+    self
+  }
+
+  /// Receive each batch file starting, retrying, finishing or failing,
+  /// instead of the printed progress lines
+  pub fn on_batch_progress(
+    mut self,
+    callback: impl Fn(&BatchProgress) + Send + Sync + 'static,
+  ) -> Self {
+    self.batch_progress = Arc::new(callback);
+    self
+  }
+
+  /// Also analyze the PDFs in the batch directory's subdirectories
+  pub fn recursive(mut self, recursive: bool) -> Self {
+    self.recursive = recursive;
+    self
+  }
+
+  /// Analyze the PDFs symlinks in the batch directory point to, instead
+  /// of skipping them
+  pub fn follow_symlinks(mut self, follow: bool) -> Self {
+    self.follow_symlinks = follow;
+    self
+  }
+
+  /// How many chunks of a long paper are analyzed at once
+  pub fn chunk_concurrency(mut self, limit: usize) -> Self {
+    self.config.chunk_concurrency = limit;
+    self
+  }
+
   /// Get model capacity based on model name
   fn get_model_capacity(&self) -> usize {
     match self.config.model.as_str() {
-      m if m.contains("qwen-turbo") || m.contains("qwen-plus-latest") || m.contains("qwen-long") => 800_000,
+      m if m.contains("qwen-turbo")
+        || m.contains("qwen-plus-latest")
+        || m.contains("qwen-long") =>
+      {
+        800_000
+      }
       m if m.contains("256k") => 200_000,
       m if m.contains("32k") => 80_000,
       m if m.contains("claude") => 180_000,
       m if m.contains("gpt-4o") => 120_000,
-      _ => 30_000
+      _ => 30_000,
     }
   }
 
   /// Analyze a single PDF research paper
   pub async fn analyze_paper<P: AsRef<Path>>(&self, pdf_path: P) -> AgentResult<AnalysisResult> {
     // Initialize AgentFlow LLM
-    std::env::set_var("STEP_API_KEY", &self.config.stepfun_api_key);
+    if !self.config.stepfun_api_key.is_empty() && std::env::var_os("STEP_API_KEY").is_none() {
+      // SAFETY: the key is only written when the environment lacks it, once,
+      // before this analysis makes any model call that reads it back.
+      unsafe {
+        std::env::set_var("STEP_API_KEY", &self.config.stepfun_api_key);
+      }
+    }
     AgentFlow::init().await?;
+    let metrics = stage_metrics(&self.config);
 
     // Extract PDF content first
-    let pdf_content = self.pdf_parser.extract_content(&pdf_path).await?;
-    
+    let started = Instant::now();
+    let pdf_content = self.extract_pdf_content(&pdf_path).await?;
+    metrics.record(StageMetric::new("pdf_extraction", started.elapsed()));
+
+    let figures = if self.config.extract_figures {
+      let started = Instant::now();
+      let figures = self
+        .extract_figures_of(pdf_path.as_ref(), pdf_content.file_id == "ocr")
+        .await;
+      metrics.record(StageMetric::new("figure_extraction", started.elapsed()));
+      figures
+    } else {
+      Vec::new()
+    };
+
+    // Papers longer than the model's capacity are summarized chunk by chunk
+    let chunked = if pdf_content.content.len() > self.get_model_capacity() {
+      println!(
+        "⚠️  Content too long ({}) for model {}, analyzing in chunks",
+        pdf_content.content.len(),
+        self.config.model
+      );
+      let started = Instant::now();
+      let chunked = self.analyze_with_chunking(&pdf_content.content).await?;
+      metrics.record(chunked.stage_metric(&self.config.model, started.elapsed()));
+      Some(chunked)
+    } else {
+      None
+    };
+
     // Create workflow with analysis nodes
     let pdf_parser = crate::nodes::PDFParserNode::new(
       pdf_path.as_ref().to_path_buf(),
       self.config.stepfun_api_key.clone(),
-      pdf_content.clone()
+      pdf_content.clone(),
     );
     let mut flow = AsyncFlow::new(Box::new(pdf_parser));
 
     // Add workflow nodes based on configuration
-    self.setup_workflow_nodes(&mut flow).await?;
+    self.setup_workflow_nodes(&mut flow, &metrics).await?;
 
     // Create shared state and add configuration markers
     let shared_state = SharedState::new();
     self.configure_shared_state(&shared_state);
+    if let Some(chunked) = &chunked {
+      shared_state.insert(
+        "chunked_summary".to_string(),
+        Value::String(chunked.summary.clone()),
+      );
+      shared_state.insert(
+        "chunked_insights".to_string(),
+        serde_json::to_value(&chunked.insights)?,
+      );
+      shared_state.insert("chunking".to_string(), chunked.usage_json());
+    }
 
     // Execute workflow
     let _execution_result = flow.run_async(&shared_state).await?;
 
     // Extract final results
-    let final_result = shared_state.get("final_analysis")
+    let final_result = shared_state
+      .get("final_analysis")
       .ok_or("Analysis result not found")?
       .clone();
-    
+
     let analysis_result = final_result
       .as_object()
       .ok_or("Invalid analysis result format")?;
 
-    Ok(AnalysisResult::from_json(analysis_result.clone()))
+    let mut result = AnalysisResult::from_json(analysis_result.clone());
+    if pdf_content.file_id == "ocr" {
+      result
+        .metadata
+        .insert("text_source".to_string(), json!("ocr"));
+    }
+    result.figures = figures;
+    result.stage_metrics = metrics.metrics();
+    Ok(result)
+  }
+
+  /// Extract the PDF's text with the configured backend, reading a scanned
+  /// PDF with the configured OCR
+  pub async fn extract_pdf_content<P: AsRef<Path>>(&self, pdf_path: P) -> AgentResult<PDFContent> {
+    let extracted = extract_with(
+      self.config.extraction_backend,
+      &LocalPdfExtractor,
+      &self.pdf_parser,
+      pdf_path.as_ref(),
+      self.config.pages.clone(),
+    )
+    .await;
+    with_ocr_fallback(
+      extracted,
+      &self.config.ocr,
+      self.config.ocr_min_chars_per_page,
+      pdf_path.as_ref(),
+      self.config.pages.clone(),
+    )
+    .await
   }
 
-  /// Setup workflow nodes based on configuration
-  async fn setup_workflow_nodes(&self, flow: &mut AsyncFlow) -> AgentResult<()> {
+  /// The PDF's images of at least `figure_min_size` pixels a side. When
+  /// the text was read by OCR, each page's largest image is its scan and
+  /// not a figure. Failing to read them only costs the figures.
+  async fn extract_figures_of(&self, pdf_path: &Path, scanned: bool) -> Vec<PageImage> {
+    let path = pdf_path.to_path_buf();
+    let pages = self.config.pages.clone();
+    let min_size = self.config.figure_min_size;
+    let images = tokio::task::spawn_blocking(move || extract_pdf_images(&path, pages, min_size))
+      .await
+      .map_err(|e| e.to_string())
+      .and_then(|images| images.map_err(|e| e.to_string()));
+    let images = match images {
+      Ok(images) => images,
+      Err(e) => {
+        println!("⚠️  No figures extracted: {}", e);
+        return Vec::new();
+      }
+    };
+    let figures: Vec<PageImage> = if scanned {
+      let scans: Vec<(usize, usize)> = page_scans(&images)
+        .iter()
+        .map(|scan| (scan.page, scan.index))
+        .collect();
+      images
+        .into_iter()
+        .filter(|image| !scans.contains(&(image.page, image.index)))
+        .collect()
+    } else {
+      images
+    };
+    println!("🖼️  Extracted {} figures", figures.len());
+    figures
+  }
+
+  /// Map-reduce analysis of `content`: summaries and insights of each
+  /// section-aligned chunk, merged into one summary and one set of insights
+  pub async fn analyze_with_chunking(&self, content: &str) -> AgentResult<ChunkedAnalysis> {
+    let llm = ModelLlm::new(self.config.model.clone());
+    map_reduce(
+      &llm,
+      content,
+      self.get_model_capacity(),
+      self.config.chunk_concurrency,
+    )
+    .await
+  }
+
+  /// Setup workflow nodes based on configuration; each node that does
+  /// work records it in `metrics`
+  async fn setup_workflow_nodes(
+    &self,
+    flow: &mut AsyncFlow,
+    metrics: &StageMetrics,
+  ) -> AgentResult<()> {
     // Summary Generation Node (always included)
-    let summarizer = crate::nodes::SummaryNode::new(self.config.model.clone());
-    flow.add_node("summarizer".to_string(), Box::new(summarizer));
+    let summarizer = crate::nodes::SummaryNode::new(self.config.model.clone())
+      .with_cache(self.stage_cache.clone());
+    flow.add_node(
+      "summarizer".to_string(),
+      observed("summarizer", summarizer, metrics),
+    );
 
     let has_insights = matches!(
-      self.config.analysis_depth, 
+      self.config.analysis_depth,
       AnalysisDepth::Insights | AnalysisDepth::Comprehensive | AnalysisDepth::WithTranslation
     );
-    let has_mindmap = self.config.generate_mind_map && matches!(
-      self.config.analysis_depth, 
-      AnalysisDepth::Comprehensive | AnalysisDepth::WithTranslation
-    );
-    let has_translation = matches!(self.config.analysis_depth, AnalysisDepth::WithTranslation) 
+    let has_mindmap = self.config.generate_mind_map
+      && matches!(
+        self.config.analysis_depth,
+        AnalysisDepth::Comprehensive | AnalysisDepth::WithTranslation
+      );
+    let has_translation = matches!(self.config.analysis_depth, AnalysisDepth::WithTranslation)
       && self.config.target_language != "en";
 
+    // Citation Extraction Node (conditional)
+    if self.config.extract_citations {
+      let citation_extractor = crate::nodes::CitationExtractorNode::new(self.config.model.clone())
+        .with_llm_cleanup(self.config.clean_citations);
+      flow.add_node(
+        "citation_extractor".to_string(),
+        observed("citation_extractor", citation_extractor, metrics),
+      );
+    }
+
     // Key Insights Extraction Node (conditional)
     if has_insights {
-      let insights_extractor = crate::nodes::InsightsNode::new(self.config.model.clone());
-      flow.add_node("insights_extractor".to_string(), Box::new(insights_extractor));
+      let insights_extractor = crate::nodes::InsightsNode::new(self.config.model.clone())
+        .with_cache(self.stage_cache.clone());
+      flow.add_node(
+        "insights_extractor".to_string(),
+        observed("insights_extractor", insights_extractor, metrics),
+      );
     }
 
     // Mind Map Generation Node (conditional)
     if has_mindmap {
-      let mind_mapper = crate::nodes::MindMapNode::new(self.config.model.clone());
-      flow.add_node("mind_mapper".to_string(), Box::new(mind_mapper));
-      
+      let mind_mapper = crate::nodes::MindMapNode::new(self.config.model.clone())
+        .use_llm(self.config.mind_map_use_llm);
+      flow.add_node(
+        "mind_mapper".to_string(),
+        observed("mind_mapper", mind_mapper, metrics),
+      );
+
       // Add MarkMap Visualizer Node for visual output
       let markmap_visualizer = crate::nodes::MarkMapVisualizerNode::new("png".to_string())
         .with_auto_open(false)
         .with_output_dir("./analysis_output");
-      flow.add_node("markmap_visualizer".to_string(), Box::new(markmap_visualizer));
+      flow.add_node(
+        "markmap_visualizer".to_string(),
+        observed("markmap_visualizer", markmap_visualizer, metrics),
+      );
     }
 
     // Translation Node (conditional)
     if has_translation {
       let translator = crate::nodes::TranslationNode::new(
-        self.config.model.clone(), 
-        self.config.target_language.clone()
+        self.config.model.clone(),
+        self.config.target_language.clone(),
+      )
+      .with_glossary(self.config.glossary.clone());
+      flow.add_node(
+        "translator".to_string(),
+        observed("translator", translator, metrics),
       );
-      flow.add_node("translator".to_string(), Box::new(translator));
     }
 
     // Results Compilation Node
-    let compiler = crate::nodes::ResultsCompilerNode::new(self.config.analysis_depth.clone());
+    let compiler = crate::nodes::ResultsCompilerNode::new(self.config.analysis_depth);
     flow.add_node("compiler".to_string(), Box::new(compiler));
 
     Ok(())
@@ -157,72 +540,53 @@ impl PDFAnalyzer {
   /// Configure shared state with workflow markers
   fn configure_shared_state(&self, shared_state: &SharedState) {
     let has_insights = matches!(
-      self.config.analysis_depth, 
+      self.config.analysis_depth,
       AnalysisDepth::Insights | AnalysisDepth::Comprehensive | AnalysisDepth::WithTranslation
     );
-    let has_mindmap = self.config.generate_mind_map && matches!(
-      self.config.analysis_depth, 
-      AnalysisDepth::Comprehensive | AnalysisDepth::WithTranslation
-    );
-    let has_translation = matches!(self.config.analysis_depth, AnalysisDepth::WithTranslation) 
+    let has_mindmap = self.config.generate_mind_map
+      && matches!(
+        self.config.analysis_depth,
+        AnalysisDepth::Comprehensive | AnalysisDepth::WithTranslation
+      );
+    let has_translation = matches!(self.config.analysis_depth, AnalysisDepth::WithTranslation)
       && self.config.target_language != "en";
     let has_visual_mindmap = has_mindmap; // Enable visual mind map when mind map is enabled
-    
+
     shared_state.insert("has_insights".to_string(), Value::Bool(has_insights));
     shared_state.insert("has_mindmap".to_string(), Value::Bool(has_mindmap));
     shared_state.insert("has_translation".to_string(), Value::Bool(has_translation));
-    shared_state.insert("has_visual_mindmap".to_string(), Value::Bool(has_visual_mindmap));
-  }
-
-  /// Batch process multiple PDF papers
-  pub async fn analyze_batch<P: AsRef<Path>>(&self, pdf_directory: P) -> AgentResult<BatchAnalysisResult> {
-    use agentflow_agents::{discover_files_with_extensions};
-    
-    // Find all PDF files in directory
-    let pdf_files = discover_files_with_extensions(&pdf_directory, &["pdf"]).await?;
-    
-    println!("Found {} PDF files to process", pdf_files.len());
-
-    if pdf_files.is_empty() {
-      return Ok(BatchAnalysisResult {
-        successful_analyses: Vec::new(),
-        failed_analyses: Vec::new(),
-        total_processed: 0,
-      });
-    }
-
-    // Process files with progress reporting
-    let analyzer = self.clone();
-    let results = self.batch_processor.process_with_progress(
-      pdf_files,
-      move |pdf_path| {
-        let analyzer = analyzer.clone();
-        async move {
-          analyzer.analyze_paper(&pdf_path).await
-        }
-      },
-      |completed, total| {
-        println!("Progress: {}/{} files processed", completed, total);
-      }
-    ).await;
+    shared_state.insert(
+      "has_visual_mindmap".to_string(),
+      Value::Bool(has_visual_mindmap),
+    );
+    shared_state.insert(
+      "has_citations".to_string(),
+      Value::Bool(self.config.extract_citations),
+    );
+  }
 
-    // Separate successful and failed analyses
-    let mut successful_analyses = Vec::new();
-    let mut failed_analyses = Vec::new();
+  /// Batch process the PDF papers in a directory (see
+  /// [`Self::recursive`] and [`Self::follow_symlinks`])
+  pub async fn analyze_batch<P: AsRef<Path>>(
+    &self,
+    pdf_directory: P,
+  ) -> AgentResult<BatchAnalysisResult> {
+    let directory = self
+      .process_directory(pdf_directory.as_ref(), self.recursive, self.follow_symlinks)
+      .await?;
+    Ok(from_directory(directory))
+  }
 
-    for (pdf_path, result) in results {
-      match result {
-        Ok(analysis) => successful_analyses.push((pdf_path, analysis)),
-        Err(e) => failed_analyses.push((pdf_path, e.to_string())),
-      }
+  fn batch_options(&self) -> BatchOptions {
+    BatchOptions {
+      concurrency: self.config.concurrency_limit,
+      state_path: self.batch_state_path.clone(),
+      resume: self.resume,
+      config_hash: config_hash(&self.config),
+      retry: self.batch_retry.clone(),
+      throttle: self.batch_throttle.clone(),
+      progress: self.batch_progress.clone(),
     }
-
-    let total_processed = successful_analyses.len() + failed_analyses.len();
-    Ok(BatchAnalysisResult {
-      successful_analyses,
-      failed_analyses,
-      total_processed,
-    })
   }
 }
 
@@ -230,8 +594,15 @@ impl Clone for PDFAnalyzer {
   fn clone(&self) -> Self {
     Self {
       config: self.config.clone(),
-      pdf_parser: StepFunPDFParser::new(self.config.stepfun_api_key.clone()),
-      batch_processor: default_batch_processor(),
+      pdf_parser: self.pdf_parser.clone(),
+      batch_state_path: self.batch_state_path.clone(),
+      resume: self.resume,
+      batch_retry: self.batch_retry.clone(),
+      batch_throttle: self.batch_throttle.clone(),
+      batch_progress: self.batch_progress.clone(),
+      recursive: self.recursive,
+      follow_symlinks: self.follow_symlinks,
+      stage_cache: self.stage_cache.clone(),
     }
   }
 }
@@ -241,41 +612,38 @@ impl AgentApplication for PDFAnalyzer {
   type Config = AnalyzerConfig;
   type Result = AnalysisResult;
 
+  const NAME: &'static str = "paper-research-analyzer";
+
   async fn initialize(config: Self::Config) -> AgentResult<Self> {
     config.validate()?;
-    
-    let pdf_parser = StepFunPDFParser::new(config.stepfun_api_key.clone());
-    let batch_processor = BatchProcessor::new(config.concurrency_limit);
-    
-    Ok(Self {
-      config,
-      pdf_parser,
-      batch_processor,
-    })
+    Ok(Self::with_config(config))
   }
 
   async fn execute(&self, input: &str) -> AgentResult<Self::Result> {
     self.analyze_paper(input).await
   }
-
-  fn name(&self) -> &'static str {
-    "paper-research-analyzer"
-  }
 }
 
 #[async_trait]
 impl FileAgent for PDFAnalyzer {
-  async fn process_file<P: AsRef<Path> + Send + Sync>(&self, file_path: P) -> AgentResult<Self::Result> {
-    self.analyze_paper(file_path).await
+  fn supported_extensions(&self) -> Vec<&'static str> {
+    vec!["pdf"]
   }
 
-  async fn process_directory<P: AsRef<Path> + Send + Sync>(&self, directory: P) -> AgentResult<Vec<(std::path::PathBuf, Self::Result)>> {
-    let batch_result = self.analyze_batch(directory).await?;
-    Ok(batch_result.successful_analyses)
+  async fn process_file(&self, path: &Path) -> AgentResult<Value> {
+    Ok(serde_json::to_value(self.analyze_paper(path).await?)?)
   }
 
-  fn supported_extensions(&self) -> Vec<&'static str> {
-    vec!["pdf"]
+  fn directory_options(&self) -> DirectoryOptions {
+    DirectoryOptions {
+      concurrency: self.config.concurrency_limit,
+      ..DirectoryOptions::default()
+    }
+  }
+
+  /// The batch state, retries, throttle and progress the builder set
+  fn batch_processor(&self) -> BatchProcessor<PathBuf, Value> {
+    batch_processor(&self.batch_options())
   }
 }
 
@@ -283,12 +651,23 @@ impl FileAgent for PDFAnalyzer {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisResult {
   pub summary: Option<String>,
-  pub key_insights: Option<Value>,
+  pub key_insights: Option<PaperInsights>,
   pub mind_map: Option<String>,
+  /// Which path produced the mind map: `llm` or `outline`
+  #[serde(default)]
+  pub mind_map_source: Option<String>,
   pub translated_summary: Option<String>,
   pub target_language: Option<String>,
+  pub citations: Option<Citations>,
+  /// The PDF's embedded figures, with `extract_figures`; saved under
+  /// `figures/` and linked from the summary
+  #[serde(default)]
+  pub figures: Vec<PageImage>,
   pub processing_stats: HashMap<String, bool>,
   pub metadata: HashMap<String, Value>,
+  /// How long each stage took, its model, tokens, cost and retries
+  #[serde(default)]
+  pub stage_metrics: Vec<StageMetric>,
 }
 
 impl AnalysisResult {
@@ -313,42 +692,135 @@ impl AnalysisResult {
     }
 
     Self {
-      summary: value.get("summary").and_then(|v| v.as_str()).map(|s| s.to_string()),
-      key_insights: value.get("key_insights").cloned(),
-      mind_map: value.get("mind_map").and_then(|v| v.as_str()).map(|s| s.to_string()),
-      translated_summary: value.get("translated_summary").and_then(|v| v.as_str()).map(|s| s.to_string()),
-      target_language: value.get("target_language").and_then(|v| v.as_str()).map(|s| s.to_string()),
+      summary: value
+        .get("summary")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string()),
+      key_insights: value.get("key_insights").map(|insights| {
+        PaperInsights::from_value(insights)
+          .unwrap_or_else(|_| PaperInsights::raw(&insights.to_string()))
+      }),
+      mind_map: value
+        .get("mind_map")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string()),
+      mind_map_source: value
+        .get("mind_map_source")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string()),
+      translated_summary: value
+        .get("translated_summary")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string()),
+      target_language: value
+        .get("target_language")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string()),
+      citations: value
+        .get("citations")
+        .and_then(|v| serde_json::from_value(v.clone()).ok()),
+      figures: Vec::new(),
       processing_stats,
       metadata,
+      stage_metrics: value
+        .get("stage_metrics")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default(),
     }
   }
 
-  /// Save analysis results to files
+  /// Save analysis results to files, listed in the directory's
+  /// `manifest.json`
   pub async fn save_to_files<P: AsRef<Path>>(&self, output_dir: P) -> AgentResult<()> {
-    use agentflow_agents::{format_json_pretty, save_comprehensive_output};
-    
-    let mut outputs = Vec::new();
-    
-    // Summary as markdown
+    let writer = ArtifactWriter::new(output_dir.as_ref()).await?;
+    self.write_artifacts(&writer).await?;
+    println!("✅ Analysis results saved to: {}", writer.dir().display());
+    Ok(())
+  }
+
+  /// Write each part of the analysis with `writer`
+  pub async fn write_artifacts(&self, writer: &ArtifactWriter) -> AgentResult<()> {
+    // Figures first, so the summary can link the names they got
+    let mut figure_links = Vec::new();
+    let saved: Vec<&PageImage> = self
+      .figures
+      .iter()
+      .filter(|figure| !figure.data.is_empty())
+      .collect();
+    if !saved.is_empty() {
+      let figures_writer = writer.subdir("figures").await?;
+      for figure in saved {
+        let path = figures_writer
+          .write_binary(
+            &figure.file_stem(),
+            figure.format.extension(),
+            &figure.data,
+            Some("figure_extraction"),
+          )
+          .await?;
+        let relative = path
+          .strip_prefix(writer.dir())
+          .unwrap_or(&path)
+          .to_string_lossy()
+          .replace('\\', "/");
+        figure_links.push(format!(
+          "![Page {}, figure {}]({})",
+          figure.page, figure.index, relative
+        ));
+      }
+    }
+
     if let Some(summary) = &self.summary {
-      outputs.push(("summary".to_string(), summary.clone(), "md".to_string()));
+      let summary = if figure_links.is_empty() {
+        summary.clone()
+      } else {
+        format!(
+          "{}\n\n## Figures\n\n{}\n",
+          summary.trim_end(),
+          figure_links.join("\n\n")
+        )
+      };
+      writer
+        .write_markdown("summary", &summary, Some("summarizer"))
+        .await?;
     }
 
-    // Insights as JSON
     if let Some(insights) = &self.key_insights {
-      let insights_pretty = format_json_pretty(insights)?;
-      outputs.push(("key_insights".to_string(), insights_pretty, "json".to_string()));
+      writer
+        .write_json("key_insights", insights, Some("insights_extractor"))
+        .await?;
     }
 
     // Mind map as markdown (MarkMap format)
     if let Some(mind_map) = &self.mind_map {
-      outputs.push(("mind_map".to_string(), mind_map.clone(), "md".to_string()));
+      writer
+        .write_markdown("mind_map", mind_map, Some("mind_mapper"))
+        .await?;
     }
 
-    // Translation
     if let Some(translation) = &self.translated_summary {
       let lang = self.target_language.as_deref().unwrap_or("unknown");
-      outputs.push((format!("summary_{}", lang), translation.clone(), "md".to_string()));
+      writer
+        .write_markdown(
+          &format!("summary_{}", lang),
+          translation,
+          Some("translator"),
+        )
+        .await?;
+    }
+
+    if let Some(citations) = &self.citations {
+      writer
+        .write_json("citations", citations, Some("citation_extractor"))
+        .await?;
+    }
+
+    if !self.stage_metrics.is_empty() {
+      let table = format!(
+        "# Stage Metrics\n\n{}",
+        stage_metrics_table(&self.stage_metrics)
+      );
+      writer.write_markdown("stage_metrics", &table, None).await?;
     }
 
     // Complete analysis as JSON
@@ -356,15 +828,18 @@ impl AnalysisResult {
       "summary": self.summary,
       "key_insights": self.key_insights,
       "mind_map": self.mind_map,
+      "mind_map_source": self.mind_map_source,
       "translated_summary": self.translated_summary,
       "target_language": self.target_language,
+      "citations": self.citations,
+      "figures": self.figures,
       "processing_stats": self.processing_stats,
-      "metadata": self.metadata
+      "metadata": self.metadata,
+      "stage_metrics": self.stage_metrics
     });
-    let analysis_pretty = format_json_pretty(&complete_analysis)?;
-    outputs.push(("complete_analysis".to_string(), analysis_pretty, "json".to_string()));
-
-    save_comprehensive_output(output_dir, "Analysis", &outputs).await?;
+    writer
+      .write_json("complete_analysis", &complete_analysis, Some("compiler"))
+      .await?;
     Ok(())
   }
 }
@@ -375,20 +850,40 @@ pub struct BatchAnalysisResult {
   pub successful_analyses: Vec<(std::path::PathBuf, AnalysisResult)>,
   pub failed_analyses: Vec<(std::path::PathBuf, String)>,
   pub total_processed: usize,
+  /// Papers whose recorded results a resumed run reused
+  pub resumed: usize,
+  /// Papers analyzed in this run
+  pub newly_processed: usize,
+  /// Set by `generate_comparison`
+  pub comparison: Option<Comparison>,
 }
 
 impl BatchAnalysisResult {
-  /// Save batch results to directory
+  /// Compare the batch's papers with `model`, or from their insights alone
+  /// without one. `save_to_directory` writes the comparison.
+  pub async fn generate_comparison(&mut self, model: Option<&str>) -> &Comparison {
+    let comparison = match model {
+      Some(model) => model_comparison(self, &ModelLlm::new(model.to_string()), model).await,
+      None => structured_comparison(self),
+    };
+    self.comparison.insert(comparison)
+  }
+
+  /// Save batch results to directory, with `comparison.md` and
+  /// `comparison.json` (built from the insights when `generate_comparison`
+  /// was not called)
   pub async fn save_to_directory<P: AsRef<Path>>(&self, output_dir: P) -> AgentResult<()> {
-    use agentflow_agents::{create_timestamped_output_dir, save_content, format_json_pretty};
-    
+    use agentflow_agents::create_timestamped_output_dir;
+
     let final_output_dir = create_timestamped_output_dir(&output_dir, "batch_analysis").await?;
+    let writer = ArtifactWriter::new(&final_output_dir).await?;
 
-    // Save individual results
+    // Save individual results, one directory per paper
     for (pdf_path, analysis) in &self.successful_analyses {
-      let filename = pdf_path.file_stem().unwrap().to_string_lossy();
-      let result_dir = final_output_dir.join(&*filename);
-      analysis.save_to_files(result_dir).await?;
+      let filename = pdf_path.file_stem().unwrap_or_default().to_string_lossy();
+      analysis
+        .write_artifacts(&writer.subdir(&filename).await?)
+        .await?;
     }
 
     // Save batch summary report
@@ -397,24 +892,38 @@ impl BatchAnalysisResult {
         "total_processed": self.total_processed,
         "successful": self.successful_analyses.len(),
         "failed": self.failed_analyses.len(),
+        "resumed": self.resumed,
+        "newly_processed": self.newly_processed,
         "success_rate": (self.successful_analyses.len() as f64 / self.total_processed as f64 * 100.0).round()
       },
       "successful_files": self.successful_analyses.iter()
-        .map(|(path, _)| path.file_name().unwrap().to_string_lossy())
+        .map(|(path, _)| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy())
         .collect::<Vec<_>>(),
       "failed_files": self.failed_analyses.iter()
         .map(|(path, error)| json!({
-          "filename": path.file_name().unwrap().to_string_lossy(),
+          "filename": path.file_name().unwrap_or(path.as_os_str()).to_string_lossy(),
           "error": error
         }))
         .collect::<Vec<_>>()
     });
 
-    let report_pretty = format_json_pretty(&batch_report)?;
-    let report_path = final_output_dir.join("batch_analysis_report.json");
-    save_content(report_path, &report_pretty).await?;
-
-    println!("✅ Batch analysis results saved to: {}", final_output_dir.display());
+    writer
+      .write_json("batch_analysis_report", &batch_report, None)
+      .await?;
+
+    let comparison = self
+      .comparison
+      .clone()
+      .unwrap_or_else(|| structured_comparison(self));
+    writer
+      .write_markdown("comparison", &comparison_markdown(&comparison), None)
+      .await?;
+    writer.write_json("comparison", &comparison, None).await?;
+
+    println!(
+      "✅ Batch analysis results saved to: {}",
+      final_output_dir.display()
+    );
     Ok(())
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,476 @@
+//! Resumable batch runs
+//!
+//! `run_batch` analyzes files a few at a time on agentflow-agents'
+//! `BatchProcessor`, reports each file starting, retrying, finishing or
+//! failing, and, given a state file, records every finished file in it as
+//! soon as it finishes. `batch_processor` sets up the same processor for
+//! `FileAgent::process_directory`, and `from_directory` turns what that
+//! returns into a `BatchAnalysisResult`. A resumed run reuses the recorded results of files
+//! whose path, modification time and analysis settings are unchanged, and
+//! analyzes only the rest.
+
+use crate::analyzer::{AnalysisResult, BatchAnalysisResult};
+use crate::config::AnalyzerConfig;
+use agentflow_agents::{
+  AdaptiveThrottle, AgentResult, BatchCheckpoint, BatchEvent, BatchProcessor, DirectoryResult,
+  RetryPolicy,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The state file's default name in the output directory
+pub const BATCH_STATE_FILE: &str = "batch_state.json";
+
+/// A file of the batch starting, retrying, finishing or failing, or the
+/// batch slowing down. Counts cover the files analyzed in this run, not
+/// those resumed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchProgress {
+  Started {
+    path: PathBuf,
+    started: usize,
+    total: usize,
+  },
+  Retrying {
+    path: PathBuf,
+    attempt: u32,
+    error: String,
+  },
+  Finished {
+    path: PathBuf,
+    completed: usize,
+    total: usize,
+  },
+  Failed {
+    path: PathBuf,
+    error: String,
+    completed: usize,
+    total: usize,
+  },
+  Throttled {
+    delay: Duration,
+  },
+}
+
+impl BatchProgress {
+  fn from_event(event: &BatchEvent<'_, PathBuf>) -> Self {
+    match event {
+      BatchEvent::Started {
+        item,
+        started,
+        total,
+      } => Self::Started {
+        path: item.to_path_buf(),
+        started: *started,
+        total: *total,
+      },
+      BatchEvent::Retrying {
+        item,
+        attempt,
+        error,
+        ..
+      } => Self::Retrying {
+        path: item.to_path_buf(),
+        attempt: *attempt,
+        error: error.clone(),
+      },
+      BatchEvent::Finished {
+        item,
+        completed,
+        total,
+      } => Self::Finished {
+        path: item.to_path_buf(),
+        completed: *completed,
+        total: *total,
+      },
+      BatchEvent::Failed {
+        item,
+        error,
+        completed,
+        total,
+      } => Self::Failed {
+        path: item.to_path_buf(),
+        error: error.clone(),
+        completed: *completed,
+        total: *total,
+      },
+      BatchEvent::Throttled { delay, .. } => Self::Throttled { delay: *delay },
+    }
+  }
+}
+
+/// Receives the batch's progress
+pub type ProgressCallback = Arc<dyn Fn(&BatchProgress) + Send + Sync>;
+
+/// The default progress callback: one line per event
+pub fn print_progress(event: &BatchProgress) {
+  match event {
+    BatchProgress::Started {
+      path,
+      started,
+      total,
+    } => {
+      println!("▶️  [{}/{}] Analyzing {}", started, total, path.display())
+    }
+    BatchProgress::Retrying {
+      path,
+      attempt,
+      error,
+    } => {
+      println!(
+        "🔁 Retrying {} (attempt {} failed: {})",
+        path.display(),
+        attempt,
+        error
+      )
+    }
+    BatchProgress::Finished {
+      path,
+      completed,
+      total,
+    } => {
+      println!("✅ [{}/{}] Finished {}", completed, total, path.display())
+    }
+    BatchProgress::Failed {
+      path,
+      error,
+      completed,
+      total,
+    } => {
+      println!(
+        "❌ [{}/{}] Failed {}: {}",
+        completed,
+        total,
+        path.display(),
+        error
+      )
+    }
+    BatchProgress::Throttled { delay } => {
+      println!(
+        "🐢 Too many recent failures; waiting {:?} before each paper",
+        delay
+      )
+    }
+  }
+}
+
+/// `batch_state.json`: finished files by path, modification time and
+/// config hash
+pub type BatchState = BatchCheckpoint<AnalysisResult>;
+
+/// A file's key in the state file
+fn state_key(path: &Path, config_hash: &str) -> String {
+  format!("{}@{}@{}", path.display(), modified_secs(path), config_hash)
+}
+
+fn modified_secs(path: &Path) -> u64 {
+  std::fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+    .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+    .map_or(0, |age| age.as_secs())
+}
+
+/// A hash of the settings that change an analysis. Keys and concurrency
+/// do not count.
+pub fn config_hash(config: &AnalyzerConfig) -> String {
+  let settings = serde_json::json!({
+    "target_language": config.target_language,
+    "analysis_depth": config.analysis_depth,
+    "generate_mind_map": config.generate_mind_map,
+    "model": config.model,
+    "extraction_backend": config.extraction_backend,
+    "extract_citations": config.extract_citations,
+    "clean_citations": config.clean_citations,
+    "pages": config.pages,
+    "glossary": config.glossary,
+  });
+  let digest = Sha256::digest(settings.to_string().as_bytes());
+  digest[..8]
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
+
+/// How a batch runs
+#[derive(Clone)]
+pub struct BatchOptions {
+  pub concurrency: usize,
+  /// Where finished files are recorded; `None` keeps no state
+  pub state_path: Option<PathBuf>,
+  /// Reuse the results recorded in the state file
+  pub resume: bool,
+  pub config_hash: String,
+  /// Retry a failed file under this policy
+  pub retry: Option<RetryPolicy>,
+  /// Slow down while too many recent files fail
+  pub throttle: Option<AdaptiveThrottle>,
+  pub progress: ProgressCallback,
+}
+
+/// The processor a batch runs on: `options.concurrency` files at once,
+/// with the options' progress, state file, retries and throttle
+pub fn batch_processor<O>(options: &BatchOptions) -> BatchProcessor<PathBuf, O>
+where
+  O: Clone + Serialize + DeserializeOwned,
+{
+  let progress = options.progress.clone();
+  let mut processor = BatchProcessor::<PathBuf, O>::new(options.concurrency)
+    .with_progress(move |event| progress(&BatchProgress::from_event(event)));
+  if let Some(state_path) = &options.state_path {
+    let config_hash = options.config_hash.clone();
+    processor = processor
+      .with_checkpoint(state_path, move |path| state_key(path, &config_hash))
+      .resume(options.resume);
+  }
+  if let Some(policy) = &options.retry {
+    processor = processor.with_retry(policy.clone());
+  }
+  if let Some(throttle) = &options.throttle {
+    processor = processor.with_throttle(throttle.clone());
+  }
+  processor
+}
+
+/// Analyze `files` with `analyze`, at most `options.concurrency` at once.
+/// Results keep the order of `files`.
+pub async fn run_batch<F, Fut>(
+  files: Vec<PathBuf>,
+  options: &BatchOptions,
+  analyze: F,
+) -> AgentResult<BatchAnalysisResult>
+where
+  F: Fn(PathBuf) -> Fut,
+  Fut: Future<Output = AgentResult<AnalysisResult>>,
+{
+  let total = files.len();
+  let report = batch_processor::<AnalysisResult>(options)
+    .process(files, analyze)
+    .await?;
+  if report.resumed > 0 {
+    println!(
+      "⏭️  Resuming: {} of {} files already analyzed",
+      report.resumed, total
+    );
+  }
+  Ok(batch_result(
+    report.results,
+    report.resumed,
+    report.processed,
+  ))
+}
+
+/// The batch result of a directory `FileAgent::process_directory`
+/// analyzed. An output that is not an `AnalysisResult` counts as failed.
+pub fn from_directory(directory: DirectoryResult) -> BatchAnalysisResult {
+  if directory.resumed > 0 {
+    println!(
+      "⏭️  Resuming: {} of {} files already analyzed",
+      directory.resumed,
+      directory.results.len()
+    );
+  }
+  let results = directory
+    .results
+    .into_iter()
+    .map(|(path, outcome)| {
+      let analysis = outcome.and_then(|output| {
+        serde_json::from_value(output).map_err(|e| format!("Invalid analysis result: {}", e))
+      });
+      (path, analysis)
+    })
+    .collect();
+  batch_result(results, directory.resumed, directory.processed)
+}
+
+fn batch_result(
+  results: Vec<(PathBuf, Result<AnalysisResult, String>)>,
+  resumed: usize,
+  newly_processed: usize,
+) -> BatchAnalysisResult {
+  let mut successful_analyses = Vec::new();
+  let mut failed_analyses = Vec::new();
+  for (path, outcome) in results {
+    match outcome {
+      Ok(analysis) => successful_analyses.push((path, analysis)),
+      Err(error) => failed_analyses.push((path, error)),
+    }
+  }
+
+  BatchAnalysisResult {
+    total_processed: successful_analyses.len() + failed_analyses.len(),
+    successful_analyses,
+    failed_analyses,
+    resumed,
+    newly_processed,
+    comparison: None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use std::sync::Mutex;
+
+  fn analysis(path: &Path) -> AnalysisResult {
+    let name = path.file_name().unwrap().to_string_lossy();
+    AnalysisResult::from_json(json!({ "summary": name }).as_object().unwrap().clone())
+  }
+
+  fn options(
+    state_path: &Path,
+    resume: bool,
+    events: &Arc<Mutex<Vec<BatchProgress>>>,
+  ) -> BatchOptions {
+    let events = events.clone();
+    BatchOptions {
+      concurrency: 1,
+      state_path: Some(state_path.to_path_buf()),
+      resume,
+      config_hash: config_hash(&AnalyzerConfig::default()),
+      retry: None,
+      throttle: None,
+      progress: Arc::new(move |event| events.lock().unwrap().push(event.clone())),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_interrupted_batch_resumes_where_it_stopped() {
+    let dir = std::env::temp_dir().join(format!(
+      "paper_research_batch_{}_{}",
+      std::process::id(),
+      chrono::Utc::now().timestamp_nanos_opt().unwrap()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files: Vec<PathBuf> = ["a.pdf", "b.pdf", "c.pdf", "d.pdf"]
+      .iter()
+      .map(|name| dir.join(name))
+      .collect();
+    for file in &files {
+      std::fs::write(file, b"%PDF-1.4").unwrap();
+    }
+    let state_path = dir.join(BATCH_STATE_FILE);
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    // The third file never finishes; the run is dropped while it hangs.
+    let first = options(&state_path, false, &events);
+    let interrupted = tokio::time::timeout(
+      Duration::from_millis(200),
+      run_batch(files.clone(), &first, |path| async move {
+        if path.ends_with("c.pdf") {
+          std::future::pending::<()>().await;
+        }
+        Ok(analysis(&path))
+      }),
+    )
+    .await;
+    assert!(interrupted.is_err());
+    assert_eq!(BatchState::load(&state_path).unwrap().items.len(), 2);
+
+    events.lock().unwrap().clear();
+    let analyzed = Mutex::new(Vec::new());
+    let result = run_batch(
+      files.clone(),
+      &options(&state_path, true, &events),
+      |path| {
+        analyzed.lock().unwrap().push(path.clone());
+        async move { Ok(analysis(&path)) }
+      },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(*analyzed.lock().unwrap(), files[2..].to_vec());
+    assert_eq!(
+      (
+        result.resumed,
+        result.newly_processed,
+        result.total_processed
+      ),
+      (2, 2, 4)
+    );
+    let summaries: Vec<_> = result
+      .successful_analyses
+      .iter()
+      .map(|(_, analysis)| analysis.summary.clone().unwrap())
+      .collect();
+    assert_eq!(summaries, ["a.pdf", "b.pdf", "c.pdf", "d.pdf"]);
+    assert_eq!(
+      events.lock().unwrap().last(),
+      Some(&BatchProgress::Finished {
+        path: files[3].clone(),
+        completed: 2,
+        total: 2
+      })
+    );
+
+    // Other settings analyze everything again.
+    let mut changed = options(&state_path, true, &events);
+    changed.config_hash = "other".to_string();
+    let result = run_batch(files.clone(), &changed, |path| async move {
+      if path.ends_with("d.pdf") {
+        return Err("broken".into());
+      }
+      Ok(analysis(&path))
+    })
+    .await
+    .unwrap();
+    assert_eq!((result.resumed, result.newly_processed), (0, 4));
+    assert_eq!(
+      result.failed_analyses,
+      vec![(files[3].clone(), "broken".to_string())]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_directory_results_become_analyses() {
+    let directory = DirectoryResult {
+      directory: PathBuf::from("papers"),
+      results: vec![
+        (
+          PathBuf::from("papers/a.PDF"),
+          Ok(serde_json::to_value(analysis(Path::new("a.PDF"))).unwrap()),
+        ),
+        (PathBuf::from("papers/b.pdf"), Ok(json!("not an analysis"))),
+        (
+          PathBuf::from("papers/sub/c.pdf"),
+          Err("unreadable".to_string()),
+        ),
+      ],
+      resumed: 1,
+      processed: 2,
+    };
+
+    let result = from_directory(directory);
+    assert_eq!(
+      (
+        result.total_processed,
+        result.resumed,
+        result.newly_processed
+      ),
+      (3, 1, 2)
+    );
+    assert_eq!(
+      result.successful_analyses[0].1.summary.as_deref(),
+      Some("a.PDF")
+    );
+    assert_eq!(result.failed_analyses.len(), 2);
+    assert!(
+      result.failed_analyses[0]
+        .1
+        .starts_with("Invalid analysis result")
+    );
+    assert_eq!(
+      result.failed_analyses[1],
+      (PathBuf::from("papers/sub/c.pdf"), "unreadable".to_string())
+    );
+  }
+}
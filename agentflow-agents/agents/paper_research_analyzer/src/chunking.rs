@@ -0,0 +1,635 @@
+//! Map-reduce analysis of papers too long for one model call
+//!
+//! `chunk_by_sections` splits the extracted text at section headings into
+//! chunks that fit the model. The map pass summarizes each chunk and
+//! extracts its insights, a few chunks at a time; the reduce pass merges
+//! the chunk summaries into one structured summary (in rounds when they do
+//! not fit one prompt) and merges the chunks' `PaperInsights`. Prompt building and merging are plain functions, so
+//! they can be tested without a model.
+
+use crate::extraction::is_page_marker;
+use crate::insights::{INSIGHTS_FORMAT, PaperInsights, extract_insights};
+use crate::llm::{AnalysisLlm, LlmReply};
+use agentflow_agents::{AgentResult, StageMetric};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Bytes of each prompt kept free for its instructions, so a chunk plus
+/// its prompt fits the model capacity
+pub const PROMPT_RESERVE: usize = 2_000;
+
+/// One section-aligned piece of a paper
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentChunk {
+  /// 0-based position in the paper
+  pub index: usize,
+  /// The heading of the section the chunk starts in, if any
+  pub title: Option<String>,
+  pub text: String,
+}
+
+/// The map pass's output for one chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkAnalysis {
+  pub index: usize,
+  pub title: Option<String>,
+  pub summary: String,
+  pub insights: PaperInsights,
+  pub usage: ChunkUsage,
+}
+
+/// The size and token usage of one chunk's model calls
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkUsage {
+  pub index: usize,
+  pub title: Option<String>,
+  pub chars: usize,
+  pub prompt_tokens: Option<u32>,
+  pub completion_tokens: Option<u32>,
+  /// Model calls made for the chunk
+  #[serde(default)]
+  pub calls: u32,
+}
+
+impl ChunkUsage {
+  fn add(&mut self, reply: &LlmReply) {
+    self.calls += 1;
+    self.prompt_tokens = sum_tokens(self.prompt_tokens, reply.prompt_tokens);
+    self.completion_tokens = sum_tokens(self.completion_tokens, reply.completion_tokens);
+  }
+}
+
+fn sum_tokens(total: Option<u32>, more: Option<u32>) -> Option<u32> {
+  match (total, more) {
+    (None, None) => None,
+    (total, more) => Some(total.unwrap_or(0) + more.unwrap_or(0)),
+  }
+}
+
+/// The merged result of a chunked analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkedAnalysis {
+  pub summary: String,
+  pub insights: PaperInsights,
+  /// Per-chunk usage, in paper order
+  pub chunks: Vec<ChunkUsage>,
+  /// Usage of the reduce calls
+  pub reduce_prompt_tokens: Option<u32>,
+  pub reduce_completion_tokens: Option<u32>,
+  pub reduce_calls: u32,
+}
+
+impl ChunkedAnalysis {
+  /// The usage record stored with the analysis metadata
+  pub fn usage_json(&self) -> Value {
+    json!({
+      "chunk_count": self.chunks.len(),
+      "chunks": self.chunks,
+      "reduce": {
+        "prompt_tokens": self.reduce_prompt_tokens,
+        "completion_tokens": self.reduce_completion_tokens
+      }
+    })
+  }
+
+  /// The analysis as the `chunked_analysis` stage: every chunk's calls
+  /// and the reduce calls, on `model`, taking `duration`
+  pub fn stage_metric(&self, model: &str, duration: Duration) -> StageMetric {
+    let tokens = |chunk: fn(&ChunkUsage) -> Option<u32>, reduce: Option<u32>| {
+      self
+        .chunks
+        .iter()
+        .map(chunk)
+        .fold(reduce, sum_tokens)
+        .map(u64::from)
+    };
+    StageMetric {
+      calls: self.chunks.iter().map(|chunk| chunk.calls).sum::<u32>() + self.reduce_calls,
+      prompt_tokens: tokens(|chunk| chunk.prompt_tokens, self.reduce_prompt_tokens),
+      completion_tokens: tokens(
+        |chunk| chunk.completion_tokens,
+        self.reduce_completion_tokens,
+      ),
+      ..StageMetric::new("chunked_analysis", duration).with_model(model)
+    }
+  }
+}
+
+/// Split `content` at section headings (markdown `#` headings and numbered
+/// headings such as `3.2 Results`) and page markers into chunks of at most `max_chars`
+/// bytes. Consecutive sections share a chunk while they fit; a section
+/// longer than `max_chars` is split at paragraphs, then at characters.
+pub fn chunk_by_sections(content: &str, max_chars: usize) -> Vec<ContentChunk> {
+  let max_chars = max_chars.max(1);
+  let mut chunks: Vec<ContentChunk> = Vec::new();
+  let mut current = String::new();
+  let mut current_title: Option<String> = None;
+
+  for (title, section) in split_sections(content) {
+    if current.len() + section.len() <= max_chars {
+      if current.is_empty() {
+        current_title = title;
+      }
+      current.push_str(&section);
+      continue;
+    }
+
+    push_chunk(&mut chunks, &mut current, &mut current_title);
+    if section.len() <= max_chars {
+      current_title = title;
+      current = section;
+      continue;
+    }
+
+    let mut piece_title = title;
+    for piece in split_oversized(&section, max_chars) {
+      if current.len() + piece.len() > max_chars {
+        push_chunk(&mut chunks, &mut current, &mut current_title);
+      }
+      if current.is_empty() {
+        current_title = piece_title.take();
+      }
+      current.push_str(&piece);
+    }
+  }
+  push_chunk(&mut chunks, &mut current, &mut current_title);
+
+  chunks
+}
+
+fn push_chunk(chunks: &mut Vec<ContentChunk>, text: &mut String, title: &mut Option<String>) {
+  let text = std::mem::take(text);
+  let title = title.take();
+  if !text.trim().is_empty() {
+    chunks.push(ContentChunk {
+      index: chunks.len(),
+      title,
+      text,
+    });
+  }
+}
+
+/// `content` as (heading, text) sections; text before the first heading has
+/// no heading. Page markers also start a section, under the heading they
+/// fall in, so long sections are cut at page boundaries first; a marker
+/// right before a heading joins the heading's section.
+fn split_sections(content: &str) -> Vec<(Option<String>, String)> {
+  let mut sections: Vec<(Option<String>, String)> = Vec::new();
+  for line in content.split_inclusive('\n') {
+    if let Some(title) = section_heading(line) {
+      let only_markers = sections.last_mut().filter(|(_, text)| {
+        text
+          .lines()
+          .all(|line| line.trim().is_empty() || is_page_marker(line))
+      });
+      match only_markers {
+        Some(section) => section.0 = Some(title),
+        None => sections.push((Some(title), String::new())),
+      }
+    } else if is_page_marker(line) {
+      let heading = sections.iter().rev().find_map(|(title, _)| title.clone());
+      sections.push((heading, String::new()));
+    }
+    match sections.last_mut() {
+      Some((_, text)) => text.push_str(line),
+      None => sections.push((None, line.to_string())),
+    }
+  }
+  sections
+}
+
+/// The heading `line` opens, if it is a markdown or numbered heading
+fn section_heading(line: &str) -> Option<String> {
+  let line = line.trim();
+  if line.is_empty() || line.len() > 120 {
+    return None;
+  }
+  if line.starts_with('#') {
+    let title = line.trim_start_matches('#').trim();
+    return (!title.is_empty()).then(|| title.to_string());
+  }
+
+  let (number, rest) = line.split_once(' ')?;
+  let number = number.trim_end_matches('.');
+  let numbered = !number.is_empty()
+    && number
+      .split('.')
+      .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+  let titled = rest.chars().next().is_some_and(|c| c.is_alphabetic()) && !rest.ends_with('.');
+  (numbered && titled).then(|| line.to_string())
+}
+
+/// Pieces of at most `max_chars` bytes, cut at paragraph breaks where
+/// possible and at character boundaries otherwise
+fn split_oversized(section: &str, max_chars: usize) -> Vec<String> {
+  let mut pieces = Vec::new();
+  let mut current = String::new();
+  for paragraph in section.split_inclusive("\n\n") {
+    if current.len() + paragraph.len() > max_chars && !current.is_empty() {
+      pieces.push(std::mem::take(&mut current));
+    }
+    if paragraph.len() <= max_chars {
+      current.push_str(paragraph);
+      continue;
+    }
+    let mut rest = paragraph;
+    while rest.len() > max_chars {
+      let mut cut = max_chars;
+      while !rest.is_char_boundary(cut) {
+        cut -= 1;
+      }
+      pieces.push(rest[..cut].to_string());
+      rest = &rest[cut..];
+    }
+    current.push_str(rest);
+  }
+  if !current.is_empty() {
+    pieces.push(current);
+  }
+  pieces
+}
+
+/// The map prompt summarizing one chunk
+pub fn chunk_summary_prompt(chunk: &ContentChunk, total: usize) -> String {
+  format!(
+    r#"
+以下是一篇研究论文的第 {} / {} 部分{}。请用中文概括这一部分的要点，保留标题、作者、研究问题、方法、实验结果和结论等信息（如本部分包含），不要编造本部分没有的内容。
+
+Paper Part:
+{}
+"#,
+    chunk.index + 1,
+    total,
+    chunk
+      .title
+      .as_ref()
+      .map(|title| format!("（{}）", title))
+      .unwrap_or_default(),
+    chunk.text
+  )
+}
+
+/// The map prompt extracting one chunk's insights as JSON
+pub fn chunk_insights_prompt(chunk: &ContentChunk, total: usize) -> String {
+  format!(
+    r#"
+以下是一篇研究论文的第 {} / {} 部分。请只根据这一部分，按以下JSON格式提取洞察，本部分未提及的字段留空（year 为 null，impact 和 reproducibility 为 "unclear"）：
+
+{}
+
+Paper Part:
+{}
+"#,
+    chunk.index + 1,
+    total,
+    INSIGHTS_FORMAT,
+    chunk.text
+  )
+}
+
+/// The reduce prompt merging chunk summaries, in paper order, into the
+/// structured summary `SummaryNode` produces for short papers
+pub fn reduce_prompt(summaries: &[String]) -> String {
+  let parts: Vec<String> = summaries
+    .iter()
+    .enumerate()
+    .map(|(i, summary)| format!("### 第 {} 部分\n{}", i + 1, summary.trim()))
+    .collect();
+  format!(
+    r#"
+以下是一篇研究论文各部分的摘要，按原文顺序排列。请将它们合并为一份完整的中文摘要，去除重复内容，并按以下结构输出：
+
+# 研究论文摘要
+
+## 标题和作者
+## 摘要总结
+## 研究问题
+## 研究方法
+## 主要发现
+## 结论
+## 重要性
+## 局限性
+
+Part Summaries:
+{}
+"#,
+    parts.join("\n\n")
+  )
+}
+
+/// Reconcile the chunks' insights in paper order (see
+/// `PaperInsights::merge`)
+pub fn merge_insights(chunks: &[ChunkAnalysis]) -> PaperInsights {
+  let mut merged = PaperInsights::default();
+  for chunk in chunks {
+    merged.merge(&chunk.insights);
+  }
+  merged
+}
+
+/// Analyze `content` chunk by chunk with at most `concurrency` chunks in
+/// flight, keeping every prompt within `capacity` bytes, then reduce.
+pub async fn map_reduce(
+  llm: &dyn AnalysisLlm,
+  content: &str,
+  capacity: usize,
+  concurrency: usize,
+) -> AgentResult<ChunkedAnalysis> {
+  let budget = capacity.saturating_sub(PROMPT_RESERVE).max(1);
+  let chunks = chunk_by_sections(content, budget);
+  let total = chunks.len();
+  println!(
+    "🧩 Analyzing {} chunks ({} at a time)...",
+    total,
+    concurrency.max(1)
+  );
+
+  let analyses: Vec<ChunkAnalysis> = stream::iter(chunks)
+    .map(|chunk| analyze_chunk(llm, chunk, total))
+    .buffered(concurrency.max(1))
+    .try_collect()
+    .await?;
+
+  let mut reduce_usage = ChunkUsage::default();
+  let mut summaries: Vec<String> = analyses.iter().map(|a| a.summary.clone()).collect();
+  loop {
+    let groups = group_for_reduce(&summaries, budget);
+    let mut reduced = Vec::with_capacity(groups.len());
+    for group in &groups {
+      let reply = llm.complete(&reduce_prompt(group), 0.3, 2000).await?;
+      reduce_usage.add(&reply);
+      reduced.push(reply.content);
+    }
+    summaries = reduced;
+    if groups.len() == 1 {
+      break;
+    }
+  }
+  println!("✅ Merged {} chunk analyses", total);
+
+  Ok(ChunkedAnalysis {
+    summary: summaries.pop().unwrap_or_default(),
+    insights: merge_insights(&analyses),
+    chunks: analyses
+      .into_iter()
+      .map(|analysis| analysis.usage)
+      .collect(),
+    reduce_prompt_tokens: reduce_usage.prompt_tokens,
+    reduce_completion_tokens: reduce_usage.completion_tokens,
+    reduce_calls: reduce_usage.calls,
+  })
+}
+
+async fn analyze_chunk(
+  llm: &dyn AnalysisLlm,
+  chunk: ContentChunk,
+  total: usize,
+) -> AgentResult<ChunkAnalysis> {
+  let summary = llm
+    .complete(&chunk_summary_prompt(&chunk, total), 0.3, 1500)
+    .await
+    .map_err(|e| format!("Summary of chunk {} failed: {}", chunk.index + 1, e))?;
+  let (insights, insight_replies) = extract_insights(llm, &chunk_insights_prompt(&chunk, total))
+    .await
+    .map_err(|e| format!("Insights of chunk {} failed: {}", chunk.index + 1, e))?;
+
+  let mut usage = ChunkUsage {
+    index: chunk.index,
+    title: chunk.title.clone(),
+    chars: chunk.text.len(),
+    ..Default::default()
+  };
+  usage.add(&summary);
+  for reply in &insight_replies {
+    usage.add(reply);
+  }
+
+  Ok(ChunkAnalysis {
+    index: chunk.index,
+    title: chunk.title,
+    summary: summary.content,
+    insights,
+    usage,
+  })
+}
+
+/// Consecutive summaries grouped so each group's text fits `budget`; always
+/// at least one group, and at least two summaries per group when there are
+/// several so every round shrinks the list
+fn group_for_reduce(summaries: &[String], budget: usize) -> Vec<Vec<String>> {
+  let mut groups: Vec<Vec<String>> = Vec::new();
+  let mut size = 0;
+  for summary in summaries {
+    let fits = groups
+      .last()
+      .is_some_and(|group| group.len() < 2 || size + summary.len() <= budget);
+    if !fits {
+      groups.push(Vec::new());
+      size = 0;
+    }
+    size += summary.len();
+    if let Some(group) = groups.last_mut() {
+      group.push(summary.clone());
+    }
+  }
+  if groups.is_empty() {
+    groups.push(Vec::new());
+  }
+  groups
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::extraction::page_marker;
+  use async_trait::async_trait;
+  use std::sync::Mutex;
+
+  /// Answers summary prompts with the part's number, insights prompts with
+  /// canned JSON, and records every prompt's length
+  struct MockLlm {
+    prompt_lengths: Mutex<Vec<usize>>,
+  }
+
+  #[async_trait]
+  impl AnalysisLlm for MockLlm {
+    async fn complete(
+      &self,
+      prompt: &str,
+      _temperature: f32,
+      _max_tokens: u32,
+    ) -> AgentResult<LlmReply> {
+      self.prompt_lengths.lock().unwrap().push(prompt.len());
+      let content = if prompt.contains("Part Summaries:") {
+        "# 研究论文摘要\n合并摘要".to_string()
+      } else if prompt.contains("JSON") {
+        let (title, authors, metrics) = if prompt.contains("第 1 /") {
+          (
+            "Attention Is All You Need",
+            json!(["Ashish Vaswani", "Noam Shazeer"]),
+            json!(["BLEU"]),
+          )
+        } else {
+          (
+            "",
+            json!(["ashish  vaswani", "Niki Parmar"]),
+            json!(["BLEU", "Perplexity"]),
+          )
+        };
+        json!({
+          "title": title,
+          "authors": authors,
+          "datasets_used": ["WMT 2014"],
+          "evaluation_metrics": metrics
+        })
+        .to_string()
+      } else {
+        "部分摘要".to_string()
+      };
+      Ok(LlmReply {
+        content,
+        prompt_tokens: Some(100),
+        completion_tokens: Some(10),
+      })
+    }
+  }
+
+  fn long_paper() -> String {
+    let mut paper = String::from("Attention Is All You Need\nAshish Vaswani, Noam Shazeer\n\n");
+    for section in 1..=6 {
+      paper.push_str(&format!("{} Section {}\n", section, section));
+      for paragraph in 0..8 {
+        paper.push_str(&format!(
+          "{}\n\n",
+          format!("Paragraph {} of section {}. ", paragraph, section).repeat(8)
+        ));
+      }
+    }
+    paper
+  }
+
+  #[test]
+  fn test_chunks_follow_sections_and_fit() {
+    let paper = long_paper();
+    let chunks = chunk_by_sections(&paper, 3_000);
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|chunk| chunk.text.len() <= 3_000));
+    assert_eq!(
+      chunks
+        .iter()
+        .map(|chunk| chunk.text.as_str())
+        .collect::<String>(),
+      paper
+    );
+    assert_eq!(chunks[0].title, None);
+    assert_eq!(chunks[1].title.as_deref(), Some("2 Section 2"));
+
+    let short = chunk_by_sections("# Intro\nshort\n# Method\nshort\n", 3_000);
+    assert_eq!(short.len(), 1);
+    assert_eq!(short[0].title.as_deref(), Some("Intro"));
+
+    let wide = chunk_by_sections(&"论文".repeat(100), 10);
+    assert!(wide.iter().all(|chunk| chunk.text.len() <= 10));
+  }
+
+  #[test]
+  fn test_long_sections_are_cut_at_page_markers() {
+    let page = "Results continue on this page.\n\n".repeat(4);
+    let paper = format!(
+      "{}\n\n2 Results\n{}{}\n\n{}{}\n\n{}",
+      page_marker(1),
+      page,
+      page_marker(2),
+      page,
+      page_marker(3),
+      page
+    );
+    let chunks = chunk_by_sections(&paper, page.len() * 2 + 60);
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].text.starts_with(&page_marker(1)));
+    assert!(chunks[1].text.starts_with(&page_marker(3)));
+    assert!(
+      chunks
+        .iter()
+        .all(|chunk| chunk.title.as_deref() == Some("2 Results"))
+    );
+  }
+
+  #[test]
+  fn test_reduce_keeps_paper_order() {
+    let chunk = |index: usize, title: &str| ChunkAnalysis {
+      index,
+      title: None,
+      summary: String::new(),
+      insights: PaperInsights {
+        title: title.to_string(),
+        ..Default::default()
+      },
+      usage: ChunkUsage::default(),
+    };
+    assert_eq!(
+      merge_insights(&[chunk(0, ""), chunk(1, "Paper"), chunk(2, "Later")]).title,
+      "Paper"
+    );
+
+    let prompt = reduce_prompt(&["第一部分".to_string(), "第二部分".to_string()]);
+    assert!(
+      prompt.find("第 1 部分\n第一部分").unwrap() < prompt.find("第 2 部分\n第二部分").unwrap()
+    );
+  }
+
+  #[tokio::test]
+  async fn test_map_reduce_merges_chunks_within_capacity() {
+    let llm = MockLlm {
+      prompt_lengths: Mutex::new(Vec::new()),
+    };
+    let capacity = 6_000;
+    let analysis = map_reduce(&llm, &long_paper(), capacity, 2).await.unwrap();
+
+    assert!(analysis.chunks.len() > 1);
+    assert!(
+      analysis
+        .chunks
+        .iter()
+        .enumerate()
+        .all(|(i, usage)| usage.index == i)
+    );
+    assert!(
+      analysis
+        .chunks
+        .iter()
+        .all(|usage| usage.prompt_tokens == Some(200) && usage.completion_tokens == Some(20))
+    );
+    assert!(
+      llm
+        .prompt_lengths
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|&len| len <= capacity)
+    );
+
+    assert_eq!(analysis.summary, "# 研究论文摘要\n合并摘要");
+    assert_eq!(analysis.insights.title, "Attention Is All You Need");
+    assert_eq!(
+      analysis.insights.authors,
+      vec!["Ashish Vaswani", "Noam Shazeer", "Niki Parmar"]
+    );
+    assert_eq!(analysis.insights.datasets, vec!["WMT 2014"]);
+    assert_eq!(analysis.insights.metrics, vec!["BLEU", "Perplexity"]);
+    assert_eq!(analysis.reduce_prompt_tokens, Some(100));
+    assert_eq!(analysis.usage_json()["chunk_count"], analysis.chunks.len());
+
+    let metric = analysis.stage_metric("step-2-16k", Duration::from_millis(40));
+    let chunk_calls: u32 = analysis.chunks.iter().map(|usage| usage.calls).sum();
+    assert!(analysis.chunks.iter().all(|usage| usage.calls >= 2));
+    assert_eq!(metric.calls, chunk_calls + analysis.reduce_calls);
+    assert_eq!(
+      metric.prompt_tokens,
+      Some(200 * analysis.chunks.len() as u64 + 100)
+    );
+    assert_eq!(metric.model.as_deref(), Some("step-2-16k"));
+  }
+}
@@ -0,0 +1,636 @@
+//! References of a paper as structured records
+//!
+//! `extract_citations` finds the references section of the extracted text,
+//! splits it into entries and parses each into authors, title, venue, year
+//! and DOI or arXiv id with regex heuristics. Where the body's citation
+//! markers can be recognized — `[3]` or `[1–3]` for a numbered bibliography,
+//! "Vaswani et al. (2017)" or "(Hochreiter & Schmidhuber, 1997)" for an
+//! author-year one — each reference also gets its in-text citation count.
+//! `clean_references` has a model repair the entries the heuristics could
+//! not fully parse.
+
+use crate::extraction::is_page_marker;
+use crate::llm::AnalysisLlm;
+use agentflow_agents::AgentResult;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+macro_rules! regex {
+  ($pattern:expr) => {{
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new($pattern).unwrap())
+  }};
+}
+
+/// How the bibliography labels its entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CitationStyle {
+  /// `[1]` or `1.` before each entry, cited by number
+  Numbered,
+  /// Entries sorted by author, cited by author and year
+  AuthorYear,
+}
+
+/// One entry of the bibliography
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Reference {
+  /// The entry's number in a numbered bibliography
+  pub number: Option<usize>,
+  pub authors: Vec<String>,
+  pub title: Option<String>,
+  pub venue: Option<String>,
+  pub year: Option<i32>,
+  pub doi: Option<String>,
+  pub arxiv_id: Option<String>,
+  /// How often the body cites the entry; `None` when its citation markers
+  /// cannot be recognized
+  pub in_text_citations: Option<usize>,
+  /// Whether a model repaired the parse
+  pub cleaned: bool,
+  /// The entry as it appears in the paper, lines joined
+  pub raw: String,
+}
+
+impl Reference {
+  /// Whether the heuristics missed the authors, title or year
+  pub fn needs_cleanup(&self) -> bool {
+    self.authors.is_empty() || self.title.is_none() || self.year.is_none()
+  }
+
+  /// The first author's surname
+  fn first_surname(&self) -> Option<&str> {
+    let author = self.authors.first()?;
+    match author.split_once(',') {
+      Some((surname, _)) => Some(surname.trim()),
+      None => author.split_whitespace().last(),
+    }
+  }
+}
+
+/// The bibliography of a paper; `citations` in the shared state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citations {
+  pub style: CitationStyle,
+  pub references: Vec<Reference>,
+}
+
+fn is_references_heading(line: &str) -> bool {
+  regex!(r"(?i)^\s*(?:#+\s*)?(?:[\dIVX]+\.?\s+)?(?:references|bibliography|works cited|literature cited|参考文献)\s*:?\s*$")
+    .is_match(line)
+}
+
+fn ends_references(line: &str) -> bool {
+  line.trim_start().starts_with('#')
+    || regex!(r"(?i)^\s*(?:[A-Z]\.?\s+)?(?:appendix|appendices|supplementary material)\b")
+      .is_match(line)
+}
+
+/// The text before the last references heading, and the references section
+/// up to the next heading or appendix
+pub fn split_references(text: &str) -> Option<(&str, &str)> {
+  let mut offset = 0;
+  let mut heading = None;
+  for line in text.split_inclusive('\n') {
+    if is_references_heading(line) {
+      heading = Some((offset, offset + line.len()));
+    }
+    offset += line.len();
+  }
+  let (start, section_start) = heading?;
+
+  let section = &text[section_start..];
+  let mut end = section.len();
+  let mut offset = 0;
+  for line in section.split_inclusive('\n') {
+    if ends_references(line) {
+      end = offset;
+      break;
+    }
+    offset += line.len();
+  }
+  Some((&text[..start], &section[..end]))
+}
+
+/// The entries of a references section: numbered when at least two lines
+/// open with consecutive `[n]` or `n.` labels, author-year otherwise
+fn split_entries(section: &str) -> (CitationStyle, Vec<(Option<usize>, String)>) {
+  let label = regex!(r"^\s*(?:\[(\d{1,3})\]|(\d{1,3})\.)\s+");
+  let lines: Vec<&str> = section
+    .lines()
+    .filter(|line| !is_page_marker(line))
+    .collect();
+
+  let label_of = |line: &str| {
+    label
+      .captures(line)
+      .and_then(|caps| caps.get(1).or(caps.get(2))?.as_str().parse::<usize>().ok())
+  };
+  let mut expected = None;
+  let mut labelled = 0;
+  for line in &lines {
+    if let Some(number) = label_of(line)
+      && expected.is_none_or(|next| number == next)
+    {
+      labelled += 1;
+      expected = Some(number + 1);
+    }
+  }
+
+  let mut entries: Vec<(Option<usize>, String)> = Vec::new();
+  if labelled >= 2 {
+    let mut expected = None;
+    for line in lines {
+      match label_of(line) {
+        Some(number) if expected.is_none_or(|next| number == next) => {
+          expected = Some(number + 1);
+          entries.push((Some(number), label.replace(line, "").trim().to_string()));
+        }
+        _ => join_line(entries.last_mut().map(|(_, entry)| entry), line),
+      }
+    }
+    return (CitationStyle::Numbered, entries);
+  }
+
+  // A new author-year entry opens with "Surname, I" once the current one
+  // has its year, or after a blank line.
+  let opening = regex!(r"^\s*\p{Lu}[\p{L}'’\-]+(?:\s\p{Lu}[\p{L}'’\-]+)*,\s+\p{Lu}");
+  let year = regex!(r"\b(?:19|20)\d{2}");
+  let mut closed = true;
+  for line in lines {
+    if line.trim().is_empty() {
+      closed = true;
+      continue;
+    }
+    let current = entries.last().map(|(_, entry)| entry.as_str());
+    if closed || (opening.is_match(line) && current.is_some_and(|entry| year.is_match(entry))) {
+      entries.push((None, line.trim().to_string()));
+      closed = false;
+    } else {
+      join_line(entries.last_mut().map(|(_, entry)| entry), line);
+    }
+  }
+  (CitationStyle::AuthorYear, entries)
+}
+
+fn join_line(entry: Option<&mut String>, line: &str) {
+  let line = line.trim();
+  if let Some(entry) = entry.filter(|_| !line.is_empty()) {
+    // Words hyphenated across lines are rejoined
+    if entry.ends_with('-') && !entry.ends_with(" -") {
+      entry.pop();
+    } else {
+      entry.push(' ');
+    }
+    entry.push_str(line);
+  }
+}
+
+/// Whether `word` is an initial such as "A", "M.-W" or "J."
+fn is_initials(word: &str) -> bool {
+  regex!(r"^(?:\p{Lu}\.?\s?-?)+$").is_match(word.trim())
+}
+
+/// `text` split after its first sentence; dots after initials and "al" do
+/// not end one
+fn split_sentence(text: &str) -> (&str, &str) {
+  let bytes = text.as_bytes();
+  for (i, c) in text.char_indices() {
+    if !matches!(c, '.' | '?' | '!')
+      || !bytes
+        .get(i + 1)
+        .is_none_or(|next| next.is_ascii_whitespace())
+    {
+      continue;
+    }
+    let word = text[..i].rsplit(char::is_whitespace).next().unwrap_or("");
+    if c == '.' && (is_initials(word) || word == "al") {
+      continue;
+    }
+    let end = if c == '.' { i } else { i + 1 };
+    return (text[..end].trim(), text[i + 1..].trim());
+  }
+  (text.trim(), "")
+}
+
+fn parse_authors(text: &str) -> Vec<String> {
+  let text = regex!(r"(?i),?\s*\bet\s+al\.?").replace_all(text, "");
+  let text = regex!(r",?\s+and\s+|,?\s*&\s*").replace_all(&text, ", ");
+  let separator = if text.contains(';') { ';' } else { ',' };
+  let parts: Vec<&str> = text
+    .split(separator)
+    .map(str::trim)
+    .filter(|part| !part.is_empty())
+    .collect();
+
+  // "Vaswani, A., Shazeer, N." pairs each surname with its initials
+  let mut authors: Vec<String> = Vec::new();
+  for part in parts {
+    match authors.last_mut() {
+      Some(last) if separator == ',' && is_initials(part) && !last.contains(',') => {
+        last.push_str(", ");
+        last.push_str(part);
+      }
+      _ => authors.push(part.to_string()),
+    }
+  }
+  authors
+}
+
+fn clean_field(text: &str) -> Option<String> {
+  let text = text
+    .trim()
+    .trim_matches(|c: char| c == ',' || c == '.' || c == ';' || c == ':' || c.is_whitespace());
+  (!text.is_empty()).then(|| text.to_string())
+}
+
+fn clean_venue(text: &str) -> Option<String> {
+  let text = regex!(r"^(?i)in:?\s+").replace(text.trim(), "");
+  let text = regex!(r",?\s*\(?\bpp?\.\s*\d+\s*[–\-]*\s*\d*\)?").replace_all(&text, "");
+  let text = regex!(r",?\s*\(?\b(?:19|20)\d{2}[a-z]?\b\)?").replace_all(&text, "");
+  clean_field(&text)
+}
+
+/// Parse one entry, label already removed
+pub fn parse_reference(raw: &str, number: Option<usize>) -> Reference {
+  let doi = regex!(r"(?i)(?:doi:\s*|https?://(?:dx\.)?doi\.org/)?\b(10\.\d{4,9}/[^\s,;]+)");
+  let arxiv = regex!(r"(?i)(?:arxiv:\s*|arxiv\.org/abs/)(\d{4}\.\d{4,5}|[a-z\-]+/\d{7})(?:v\d+)?");
+  let mut reference = Reference {
+    number,
+    raw: raw.to_string(),
+    doi: doi
+      .captures(raw)
+      .map(|caps| caps[1].trim_end_matches('.').to_string()),
+    arxiv_id: arxiv.captures(raw).map(|caps| caps[1].to_string()),
+    ..Default::default()
+  };
+
+  let text = doi.replace_all(raw, "");
+  let text = arxiv.replace_all(&text, "");
+  let text = regex!(r"https?://\S+").replace_all(&text, "");
+  let text = text.trim();
+
+  reference.year = regex!(r"\((\d{4})[a-z]?\)")
+    .captures(text)
+    .or_else(|| {
+      regex!(r"\b((?:19|20)\d{2})[a-z]?\b")
+        .captures_iter(text)
+        .last()
+    })
+    .and_then(|caps| caps[1].parse().ok());
+
+  let (authors, title, venue) =
+    if let Some(caps) = regex!(r#"^(.*?)[“"](.+?)[”"](.*)$"#).captures(text) {
+      // IEEE: A. Author and B. Author, "Title," in Venue, 2017.
+      (
+        caps[1].to_string(),
+        caps[2].to_string(),
+        caps[3].to_string(),
+      )
+    } else if let Some(caps) = regex!(r"^(.*?)\s*\((\d{4})[a-z]?\)\.?\s*(.*)$").captures(text) {
+      // APA: Author, A., & Author, B. (2017). Title. Venue.
+      let (title, venue) = split_sentence(caps.get(3).map_or("", |m| m.as_str()));
+      (caps[1].to_string(), title.to_string(), venue.to_string())
+    } else {
+      // ACM and NeurIPS: A. Author and B. Author. Title. In Venue, 2017.
+      let (authors, rest) = split_sentence(text);
+      let (title, venue) = split_sentence(rest);
+      (authors.to_string(), title.to_string(), venue.to_string())
+    };
+
+  reference.authors = parse_authors(&authors);
+  reference.title = clean_field(&title);
+  reference.venue = clean_venue(&venue);
+  reference
+}
+
+/// In-text citations of each numbered reference: `[2]`, `[1, 3]`, `[1–3]`.
+/// `None` when the body has no such markers.
+fn count_numbered(body: &str) -> Option<HashMap<usize, usize>> {
+  let marker =
+    regex!(r"\[(\d{1,3}(?:\s*[–\-]\s*\d{1,3})?(?:\s*[,;]\s*\d{1,3}(?:\s*[–\-]\s*\d{1,3})?)*)\]");
+  let mut counts = HashMap::new();
+  let mut found = false;
+  for caps in marker.captures_iter(body) {
+    found = true;
+    for part in caps[1].split([',', ';']) {
+      let bounds: Vec<usize> = part
+        .split(['–', '-'])
+        .filter_map(|n| n.trim().parse().ok())
+        .collect();
+      let (first, last) = match bounds.as_slice() {
+        [n] => (*n, *n),
+        [first, last] if first <= last && last - first <= 100 => (*first, *last),
+        _ => continue,
+      };
+      for number in first..=last {
+        *counts.entry(number).or_insert(0) += 1;
+      }
+    }
+  }
+  found.then_some(counts)
+}
+
+/// In-text citations of an author-year reference: "Surname (2017)",
+/// "Surname et al., 2017", "Surname and Other (2017)"
+fn count_author_year(body: &str, reference: &Reference) -> Option<usize> {
+  let surname = reference.first_surname()?;
+  let year = reference.year?;
+  let pattern = format!(
+    r"\b{}\b(?:\s+et\s+al\.?|\s+(?:and|&)\s+\p{{Lu}}[\p{{L}}'’\-]+)?,?\s*\(?\s*{}[a-z]?\b",
+    regex::escape(surname),
+    year
+  );
+  Regex::new(&pattern)
+    .ok()
+    .map(|re| re.find_iter(body).count())
+}
+
+/// The references of `text` with their in-text citation counts; `None`
+/// when no references section is found
+pub fn extract_citations(text: &str) -> Option<Citations> {
+  let (body, section) = split_references(text)?;
+  let (style, entries) = split_entries(section);
+  if entries.is_empty() {
+    return None;
+  }
+
+  let mut references: Vec<Reference> = entries
+    .into_iter()
+    .map(|(number, raw)| parse_reference(&raw, number))
+    .collect();
+
+  match style {
+    CitationStyle::Numbered => {
+      if let Some(counts) = count_numbered(body) {
+        for reference in &mut references {
+          reference.in_text_citations = reference
+            .number
+            .map(|n| counts.get(&n).copied().unwrap_or(0));
+        }
+      }
+    }
+    CitationStyle::AuthorYear => {
+      for reference in &mut references {
+        reference.in_text_citations = count_author_year(body, reference);
+      }
+    }
+  }
+  Some(Citations { style, references })
+}
+
+#[derive(Deserialize)]
+struct CleanedReference {
+  id: usize,
+  #[serde(default)]
+  authors: Vec<String>,
+  #[serde(default)]
+  title: String,
+  #[serde(default)]
+  venue: String,
+  #[serde(default)]
+  year: Option<i32>,
+}
+
+fn cleanup_schema() -> Value {
+  json!({
+    "type": "object",
+    "properties": {
+      "references": { "type": "array", "items": {
+        "type": "object",
+        "properties": {
+          "id": { "type": "integer" },
+          "authors": { "type": "array", "items": { "type": "string" } },
+          "title": { "type": "string" },
+          "venue": { "type": "string" },
+          "year": { "type": ["integer", "null"] }
+        },
+        "required": ["id", "authors", "title", "venue", "year"],
+        "additionalProperties": false
+      }}
+    },
+    "required": ["references"],
+    "additionalProperties": false
+  })
+}
+
+/// Have `llm` repair the references that `needs_cleanup`, all in one
+/// call; DOI and arXiv ids are kept. Returns how many were repaired.
+pub async fn clean_references(
+  llm: &dyn AnalysisLlm,
+  references: &mut [Reference],
+) -> AgentResult<usize> {
+  let messy: Vec<Value> = references
+    .iter()
+    .enumerate()
+    .filter(|(_, reference)| reference.needs_cleanup())
+    .map(|(id, reference)| json!({ "id": id, "entry": reference.raw }))
+    .collect();
+  if messy.is_empty() {
+    return Ok(0);
+  }
+
+  let prompt = format!(
+    r#"
+以下是从论文PDF中提取的参考文献条目，可能因换行或排版而混乱。请解析每一条，返回JSON：
+{{"references": [{{"id": 条目id, "authors": ["作者"], "title": "标题", "venue": "期刊或会议", "year": 年份或null}}]}}
+无法确定的字段使用空字符串或空列表，不要编造。
+
+Entries:
+{}
+"#,
+    serde_json::to_string_pretty(&messy)?
+  );
+  let reply = llm
+    .complete_json(&prompt, "references", &cleanup_schema(), 0.0, 4000)
+    .await?;
+
+  #[derive(Deserialize)]
+  struct Reply {
+    references: Vec<CleanedReference>,
+  }
+  let start = reply
+    .content
+    .find('{')
+    .ok_or("the cleanup reply holds no JSON object")?;
+  let end = reply
+    .content
+    .rfind('}')
+    .filter(|&end| end > start)
+    .ok_or("the cleanup reply is not closed")?;
+  let parsed: Reply = serde_json::from_str(&reply.content[start..=end])
+    .map_err(|e| format!("Invalid reference cleanup reply: {}", e))?;
+
+  let mut cleaned = 0;
+  for item in parsed.references {
+    let Some(reference) = references
+      .get_mut(item.id)
+      .filter(|reference| reference.needs_cleanup())
+    else {
+      continue;
+    };
+    if !item.authors.is_empty() {
+      reference.authors = item.authors;
+    }
+    if let Some(title) = clean_field(&item.title) {
+      reference.title = Some(title);
+    }
+    if let Some(venue) = clean_field(&item.venue) {
+      reference.venue = Some(venue);
+    }
+    reference.year = item.year.or(reference.year);
+    reference.cleaned = true;
+    cleaned += 1;
+  }
+  Ok(cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::llm::LlmReply;
+  use async_trait::async_trait;
+
+  const NUMBERED: &str = include_str!("../tests/fixtures/references_numbered.txt");
+  const AUTHOR_YEAR: &str = include_str!("../tests/fixtures/references_author_year.txt");
+
+  fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+  }
+
+  #[test]
+  fn test_numbered_references() {
+    let citations = extract_citations(NUMBERED).unwrap();
+    assert_eq!(citations.style, CitationStyle::Numbered);
+    let refs = &citations.references;
+    assert_eq!(refs.len(), 4);
+
+    assert_eq!(refs[0].number, Some(1));
+    assert_eq!(refs[0].authors.len(), 8);
+    assert_eq!(refs[0].authors[5], "A. N. Gomez");
+    assert_eq!(refs[0].title.as_deref(), Some("Attention is all you need"));
+    assert_eq!(
+      refs[0].venue.as_deref(),
+      Some("Advances in Neural Information Processing Systems")
+    );
+    assert_eq!(refs[0].year, Some(2017));
+
+    assert_eq!(
+      refs[1].authors,
+      strings(&["S. Hochreiter", "J. Schmidhuber"])
+    );
+    assert_eq!(refs[1].doi.as_deref(), Some("10.1162/neco.1997.9.8.1735"));
+    assert_eq!(
+      refs[1].venue.as_deref(),
+      Some("Neural Computation, vol. 9, no. 8")
+    );
+    assert_eq!(refs[1].year, Some(1997));
+
+    assert_eq!(
+      refs[2].authors,
+      strings(&["J. Devlin", "M.-W. Chang", "K. Lee", "K. Toutanova"])
+    );
+    assert_eq!(
+      refs[2].title.as_deref(),
+      Some("BERT: Pre-training of deep bidirectional transformers for language understanding")
+    );
+    assert_eq!(refs[2].venue.as_deref(), Some("Proceedings of NAACL-HLT"));
+
+    assert_eq!(refs[3].authors, strings(&["T. Brown"]));
+    assert_eq!(refs[3].arxiv_id.as_deref(), Some("2005.14165"));
+    assert_eq!(refs[3].year, Some(2020));
+
+    let counts: Vec<_> = refs.iter().map(|r| r.in_text_citations).collect();
+    assert_eq!(counts, [Some(3), Some(2), Some(3), Some(0)]);
+  }
+
+  #[test]
+  fn test_author_year_references() {
+    let citations = extract_citations(AUTHOR_YEAR).unwrap();
+    assert_eq!(citations.style, CitationStyle::AuthorYear);
+    let refs = &citations.references;
+    assert_eq!(refs.len(), 3);
+
+    assert_eq!(
+      refs[0].authors,
+      strings(&["Devlin, J.", "Chang, M.-W.", "Lee, K.", "Toutanova, K."])
+    );
+    assert_eq!(refs[0].year, Some(2019));
+    assert_eq!(
+      refs[0].title.as_deref(),
+      Some("BERT: Pre-training of deep bidirectional transformers for language understanding")
+    );
+    assert_eq!(refs[0].venue.as_deref(), Some("Proceedings of NAACL-HLT"));
+    assert_eq!(refs[0].doi.as_deref(), Some("10.18653/v1/N19-1423"));
+
+    assert_eq!(
+      refs[1].authors,
+      strings(&["Hochreiter, S.", "Schmidhuber, J."])
+    );
+    assert_eq!(refs[1].title.as_deref(), Some("Long short-term memory"));
+
+    assert_eq!(refs[2].authors.len(), 8);
+    assert_eq!(refs[2].authors[5], "Gomez, A. N.");
+    assert_eq!(refs[2].title.as_deref(), Some("Attention is all you need"));
+    assert_eq!(
+      refs[2].venue.as_deref(),
+      Some("Advances in Neural Information Processing Systems, 30")
+    );
+
+    let counts: Vec<_> = refs.iter().map(|r| r.in_text_citations).collect();
+    assert_eq!(counts, [Some(1), Some(1), Some(2)]);
+  }
+
+  struct CannedLlm(&'static str);
+
+  #[async_trait]
+  impl AnalysisLlm for CannedLlm {
+    async fn complete(
+      &self,
+      _prompt: &str,
+      _temperature: f32,
+      _max_tokens: u32,
+    ) -> AgentResult<LlmReply> {
+      Ok(LlmReply {
+        content: self.0.to_string(),
+        ..Default::default()
+      })
+    }
+  }
+
+  #[tokio::test]
+  async fn test_cleanup_repairs_only_messy_entries() {
+    let mut references = vec![
+      parse_reference(
+        "Y. LeCun, Y. Bengio, and G. Hinton. Deep learning. Nature, 2015.",
+        Some(1),
+      ),
+      parse_reference(
+        "Goodfellow Bengio Courville deep learning MIT press",
+        Some(2),
+      ),
+    ];
+    assert!(!references[0].needs_cleanup());
+    assert!(references[1].needs_cleanup());
+
+    let llm = CannedLlm(
+      r#"{"references": [
+        {"id": 0, "authors": ["wrong"], "title": "", "venue": "", "year": null},
+        {"id": 1, "authors": ["I. Goodfellow", "Y. Bengio", "A. Courville"], "title": "Deep Learning", "venue": "MIT Press", "year": 2016}
+      ]}"#,
+    );
+    assert_eq!(clean_references(&llm, &mut references).await.unwrap(), 1);
+    assert_eq!(
+      references[0].authors,
+      strings(&["Y. LeCun", "Y. Bengio", "G. Hinton"])
+    );
+    assert!(!references[0].cleaned);
+    assert_eq!(references[1].title.as_deref(), Some("Deep Learning"));
+    assert_eq!(references[1].year, Some(2016));
+    assert!(references[1].cleaned);
+  }
+}
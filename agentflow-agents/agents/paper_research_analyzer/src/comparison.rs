@@ -0,0 +1,452 @@
+//! Cross-paper comparison of a batch
+//!
+//! `BatchAnalysisResult::generate_comparison` hands the typed insights of
+//! every analyzed paper to a model, which returns a comparison matrix
+//! (methods, datasets, metrics, findings) and the papers grouped by theme.
+//! Without a model, or when its reply cannot be read, the same report is
+//! built from the insights alone: the matrix verbatim and the papers
+//! grouped by field. Papers without usable insights are listed as
+//! excluded.
+
+use crate::analyzer::BatchAnalysisResult;
+use crate::insights::PaperInsights;
+use crate::llm::AnalysisLlm;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// One paper's row of the comparison matrix
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComparisonRow {
+  pub paper: String,
+  pub methods: Vec<String>,
+  pub datasets: Vec<String>,
+  pub metrics: Vec<String>,
+  pub findings: Vec<String>,
+}
+
+/// Papers sharing a theme
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeCluster {
+  pub theme: String,
+  pub papers: Vec<String>,
+  pub summary: String,
+}
+
+/// A paper left out of the comparison, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExcludedPaper {
+  pub file: String,
+  pub reason: String,
+}
+
+/// How a comparison was built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonSource {
+  /// By the model named in `Comparison::model`
+  Model,
+  /// From the structured insights alone
+  Structured,
+}
+
+/// A comparison of a batch's papers; `comparison.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+  pub source: ComparisonSource,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub model: Option<String>,
+  pub rows: Vec<ComparisonRow>,
+  pub clusters: Vec<ThemeCluster>,
+  pub excluded: Vec<ExcludedPaper>,
+}
+
+fn file_name(path: &Path) -> String {
+  path.file_name().map_or_else(
+    || path.display().to_string(),
+    |name| name.to_string_lossy().into_owned(),
+  )
+}
+
+/// The papers with usable insights, by name, and the papers excluded
+fn comparable_papers(
+  batch: &BatchAnalysisResult,
+) -> (Vec<(String, &PaperInsights)>, Vec<ExcludedPaper>) {
+  let mut papers = Vec::new();
+  let mut excluded = Vec::new();
+  for (path, analysis) in &batch.successful_analyses {
+    match &analysis.key_insights {
+      Some(insights) if insights.raw.is_none() || !insights.title.is_empty() => {
+        let name = if insights.title.is_empty() {
+          file_name(path)
+        } else {
+          insights.title.clone()
+        };
+        papers.push((name, insights));
+      }
+      Some(_) => excluded.push(ExcludedPaper {
+        file: file_name(path),
+        reason: "insights extraction failed".to_string(),
+      }),
+      None => excluded.push(ExcludedPaper {
+        file: file_name(path),
+        reason: "no insights extracted".to_string(),
+      }),
+    }
+  }
+  for (path, error) in &batch.failed_analyses {
+    excluded.push(ExcludedPaper {
+      file: file_name(path),
+      reason: format!("analysis failed: {}", error),
+    });
+  }
+  (papers, excluded)
+}
+
+/// The comparison built from the insights alone: each paper's row as
+/// extracted, and the papers grouped by field with the datasets and
+/// metrics they share
+pub fn structured_comparison(batch: &BatchAnalysisResult) -> Comparison {
+  let (papers, excluded) = comparable_papers(batch);
+  let rows = papers
+    .iter()
+    .map(|(name, insights)| ComparisonRow {
+      paper: name.clone(),
+      methods: insights.methodology.clone(),
+      datasets: insights.datasets.clone(),
+      metrics: insights.metrics.clone(),
+      findings: insights.contributions.clone(),
+    })
+    .collect();
+
+  let mut fields: BTreeMap<String, Vec<&(String, &PaperInsights)>> = BTreeMap::new();
+  for paper in &papers {
+    let field = if paper.1.field.is_empty() {
+      "Unspecified field".to_string()
+    } else {
+      paper.1.field.clone()
+    };
+    fields.entry(field).or_default().push(paper);
+  }
+  let clusters = fields
+    .into_iter()
+    .map(|(theme, members)| {
+      let shared = |items: fn(&PaperInsights) -> &Vec<String>| -> Vec<String> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (_, insights) in &members {
+          for item in items(insights) {
+            *counts.entry(item.clone()).or_default() += 1;
+          }
+        }
+        counts
+          .into_iter()
+          .filter(|(_, count)| *count > 1)
+          .map(|(item, _)| item)
+          .collect()
+      };
+      let mut summary = format!("{} paper(s)", members.len());
+      for (label, items) in [
+        ("shared datasets", shared(|i| &i.datasets)),
+        ("shared metrics", shared(|i| &i.metrics)),
+      ] {
+        if !items.is_empty() {
+          summary.push_str(&format!("; {}: {}", label, items.join(", ")));
+        }
+      }
+      ThemeCluster {
+        theme,
+        papers: members.iter().map(|(name, _)| name.clone()).collect(),
+        summary,
+      }
+    })
+    .collect();
+
+  Comparison {
+    source: ComparisonSource::Structured,
+    model: None,
+    rows,
+    clusters,
+    excluded,
+  }
+}
+
+/// The schema of the model's comparison reply
+fn comparison_schema() -> Value {
+  let list = json!({ "type": "array", "items": { "type": "string" } });
+  json!({
+    "type": "object",
+    "properties": {
+      "rows": { "type": "array", "items": {
+        "type": "object",
+        "properties": {
+          "paper": { "type": "string" },
+          "methods": list, "datasets": list, "metrics": list, "findings": list
+        },
+        "required": ["paper", "methods", "datasets", "metrics", "findings"],
+        "additionalProperties": false
+      }},
+      "clusters": { "type": "array", "items": {
+        "type": "object",
+        "properties": {
+          "theme": { "type": "string" },
+          "papers": list,
+          "summary": { "type": "string" }
+        },
+        "required": ["theme", "papers", "summary"],
+        "additionalProperties": false
+      }}
+    },
+    "required": ["rows", "clusters"],
+    "additionalProperties": false
+  })
+}
+
+/// The prompt comparing the papers' insights
+pub fn comparison_prompt(papers: &[(String, &PaperInsights)]) -> String {
+  let insights: Vec<Value> = papers
+    .iter()
+    .map(|(name, insights)| json!({ "paper": name, "insights": insights }))
+    .collect();
+  format!(
+    r#"
+下面是一批研究论文的结构化洞察。请对它们进行横向比较，并以JSON返回：
+- "rows"：每篇论文一行，"paper" 使用下面给出的名称，并概括其 "methods"、"datasets"、"metrics" 和 "findings"，用统一的术语以便比较；
+- "clusters"：按研究主题对论文分组，每组给出 "theme"、所含论文名称 "papers" 和一段比较性的 "summary"（共同点、差异与趋势）。
+
+Paper Insights:
+{}
+"#,
+    serde_json::to_string_pretty(&insights).unwrap_or_default()
+  )
+}
+
+/// Compare with `llm`, falling back to `structured_comparison` when the
+/// reply cannot be read
+pub async fn model_comparison(
+  batch: &BatchAnalysisResult,
+  llm: &dyn AnalysisLlm,
+  model: &str,
+) -> Comparison {
+  let fallback = structured_comparison(batch);
+  let (papers, excluded) = comparable_papers(batch);
+  if papers.is_empty() {
+    return fallback;
+  }
+
+  let reply = match llm
+    .complete_json(
+      &comparison_prompt(&papers),
+      "paper_comparison",
+      &comparison_schema(),
+      0.3,
+      4000,
+    )
+    .await
+  {
+    Ok(reply) => reply,
+    Err(e) => {
+      println!(
+        "⚠️  Comparison by {} failed ({}), using the structured comparison",
+        model, e
+      );
+      return fallback;
+    }
+  };
+
+  #[derive(Deserialize)]
+  struct Reply {
+    rows: Vec<ComparisonRow>,
+    #[serde(default)]
+    clusters: Vec<ThemeCluster>,
+  }
+  let parsed = reply
+    .content
+    .find('{')
+    .zip(reply.content.rfind('}'))
+    .and_then(|(start, end)| serde_json::from_str::<Reply>(reply.content.get(start..=end)?).ok());
+  match parsed {
+    Some(parsed) if !parsed.rows.is_empty() => Comparison {
+      source: ComparisonSource::Model,
+      model: Some(model.to_string()),
+      rows: parsed.rows,
+      clusters: parsed.clusters,
+      excluded,
+    },
+    _ => {
+      println!(
+        "⚠️  Unreadable comparison from {}, using the structured comparison",
+        model
+      );
+      fallback
+    }
+  }
+}
+
+fn cell(items: &[String]) -> String {
+  if items.is_empty() {
+    return "—".to_string();
+  }
+  items.join("; ").replace('|', "\\|").replace('\n', " ")
+}
+
+/// `comparison.md`
+pub fn comparison_markdown(comparison: &Comparison) -> String {
+  let mut markdown = String::from("# Paper Comparison\n\n");
+  markdown.push_str(&match (&comparison.source, &comparison.model) {
+    (ComparisonSource::Model, Some(model)) => format!("_Compared by {}._\n\n", model),
+    _ => "_Built from the extracted insights._\n\n".to_string(),
+  });
+
+  markdown.push_str("## Comparison Matrix\n\n| Paper | Methods | Datasets | Metrics | Findings |\n|---|---|---|---|---|\n");
+  for row in &comparison.rows {
+    markdown.push_str(&format!(
+      "| {} | {} | {} | {} | {} |\n",
+      row.paper.replace('|', "\\|"),
+      cell(&row.methods),
+      cell(&row.datasets),
+      cell(&row.metrics),
+      cell(&row.findings)
+    ));
+  }
+
+  markdown.push_str("\n## Themes\n");
+  for cluster in &comparison.clusters {
+    markdown.push_str(&format!(
+      "\n### {}\n\nPapers: {}\n\n{}\n",
+      cluster.theme,
+      cluster.papers.join(", "),
+      cluster.summary
+    ));
+  }
+
+  if !comparison.excluded.is_empty() {
+    markdown.push_str("\n## Excluded Papers\n\n");
+    for paper in &comparison.excluded {
+      markdown.push_str(&format!("- {}: {}\n", paper.file, paper.reason));
+    }
+  }
+  markdown
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analyzer::AnalysisResult;
+  use crate::llm::LlmReply;
+  use agentflow_agents::AgentResult;
+  use async_trait::async_trait;
+  use std::path::PathBuf;
+
+  fn analyzed(file: &str, insights: Option<PaperInsights>) -> (PathBuf, AnalysisResult) {
+    let mut analysis = AnalysisResult::from_json(serde_json::Map::new());
+    analysis.key_insights = insights;
+    (PathBuf::from(format!("papers/{}", file)), analysis)
+  }
+
+  fn insights(title: &str, field: &str, datasets: &[&str], metrics: &[&str]) -> PaperInsights {
+    let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+    PaperInsights {
+      title: title.to_string(),
+      field: field.to_string(),
+      methodology: vec![format!("{} method", title)],
+      datasets: strings(datasets),
+      metrics: strings(metrics),
+      contributions: vec![format!("{} | finding", title)],
+      ..Default::default()
+    }
+  }
+
+  fn batch() -> BatchAnalysisResult {
+    BatchAnalysisResult {
+      successful_analyses: vec![
+        analyzed(
+          "a.pdf",
+          Some(insights("Transformer", "NLP", &["WMT 2014"], &["BLEU"])),
+        ),
+        analyzed(
+          "b.pdf",
+          Some(insights(
+            "BERT",
+            "NLP",
+            &["WMT 2014", "GLUE"],
+            &["BLEU", "F1"],
+          )),
+        ),
+        analyzed("c.pdf", Some(insights("ResNet", "", &["ImageNet"], &[]))),
+        analyzed("d.pdf", Some(PaperInsights::raw("not json"))),
+      ],
+      failed_analyses: vec![(
+        PathBuf::from("papers/e.pdf"),
+        "PDF upload failed".to_string(),
+      )],
+      total_processed: 5,
+      resumed: 0,
+      newly_processed: 5,
+      comparison: None,
+    }
+  }
+
+  #[test]
+  fn test_structured_comparison_markdown() {
+    let comparison = structured_comparison(&batch());
+    assert_eq!(comparison.source, ComparisonSource::Structured);
+    assert_eq!(comparison.clusters[0].theme, "NLP");
+    assert_eq!(
+      comparison.clusters[0].summary,
+      "2 paper(s); shared datasets: WMT 2014; shared metrics: BLEU"
+    );
+
+    let markdown = comparison_markdown(&comparison);
+    assert_eq!(
+      markdown,
+      "# Paper Comparison\n\n\
+       _Built from the extracted insights._\n\n\
+       ## Comparison Matrix\n\n\
+       | Paper | Methods | Datasets | Metrics | Findings |\n\
+       |---|---|---|---|---|\n\
+       | Transformer | Transformer method | WMT 2014 | BLEU | Transformer \\| finding |\n\
+       | BERT | BERT method | WMT 2014; GLUE | BLEU; F1 | BERT \\| finding |\n\
+       | ResNet | ResNet method | ImageNet | — | ResNet \\| finding |\n\
+       \n## Themes\n\
+       \n### NLP\n\nPapers: Transformer, BERT\n\n2 paper(s); shared datasets: WMT 2014; shared metrics: BLEU\n\
+       \n### Unspecified field\n\nPapers: ResNet\n\n1 paper(s)\n\
+       \n## Excluded Papers\n\n\
+       - d.pdf: insights extraction failed\n\
+       - e.pdf: analysis failed: PDF upload failed\n"
+    );
+  }
+
+  struct CannedLlm(&'static str);
+
+  #[async_trait]
+  impl AnalysisLlm for CannedLlm {
+    async fn complete(
+      &self,
+      _prompt: &str,
+      _temperature: f32,
+      _max_tokens: u32,
+    ) -> AgentResult<LlmReply> {
+      Ok(LlmReply {
+        content: self.0.to_string(),
+        ..Default::default()
+      })
+    }
+  }
+
+  #[tokio::test]
+  async fn test_model_comparison_falls_back_when_unreadable() {
+    let reply = r#"{"rows": [{"paper": "Transformer", "methods": ["attention"], "datasets": [], "metrics": [], "findings": []}],
+      "clusters": [{"theme": "Sequence models", "papers": ["Transformer", "BERT"], "summary": "Both use attention."}]}"#;
+    let comparison = model_comparison(&batch(), &CannedLlm(reply), "qwen-turbo").await;
+    assert_eq!(comparison.source, ComparisonSource::Model);
+    assert_eq!(comparison.rows[0].methods, vec!["attention"]);
+    assert_eq!(comparison.excluded.len(), 2);
+    assert!(comparison_markdown(&comparison).contains("_Compared by qwen-turbo._"));
+
+    let comparison = model_comparison(&batch(), &CannedLlm("no table today"), "qwen-turbo").await;
+    assert_eq!(comparison, structured_comparison(&batch()));
+  }
+}
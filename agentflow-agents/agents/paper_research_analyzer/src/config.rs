@@ -1,7 +1,13 @@
 //! Configuration for Paper Research Analyzer
 
-use agentflow_agents::{AgentConfig, AgentResult};
+use crate::extraction::Backend;
+use agentflow_agents::{
+  AgentConfig, AgentResult, CacheControl, DEFAULT_MIN_CHARS_PER_PAGE, OcrBackend,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalyzerConfig {
@@ -9,15 +15,79 @@ pub struct AnalyzerConfig {
   pub target_language: String,
   pub analysis_depth: AnalysisDepth,
   pub generate_mind_map: bool,
+  /// Have the model draft the mind map; without it, or when its draft
+  /// fails validation, the outline is built from the summary and insights
+  #[serde(default = "default_mind_map_use_llm")]
+  pub mind_map_use_llm: bool,
   pub model: String,
   pub concurrency_limit: usize,
+  /// Chunks of a long paper analyzed at once
+  #[serde(default = "default_chunk_concurrency")]
+  pub chunk_concurrency: usize,
+  /// Where PDF text comes from
+  #[serde(default)]
+  pub extraction_backend: Backend,
+  /// How a scanned PDF, whose extracted text is too thin, is read instead
+  #[serde(default)]
+  pub ocr: OcrBackend,
+  /// Letters and digits a page of extracted text needs on average before
+  /// it is not taken for a scan
+  #[serde(default = "default_ocr_min_chars_per_page")]
+  pub ocr_min_chars_per_page: usize,
+  /// Save the PDF's embedded images, with links from the summary
+  #[serde(default)]
+  pub extract_figures: bool,
+  /// Pixels a figure is at least wide and high; smaller images are icons
+  /// and logos
+  #[serde(default = "default_figure_min_size")]
+  pub figure_min_size: u32,
+  /// Parse the references section into `citations`
+  #[serde(default)]
+  pub extract_citations: bool,
+  /// Have the model repair references the heuristics could not parse
+  #[serde(default)]
+  pub clean_citations: bool,
+  /// Analyze only these pages (0-based, end exclusive)
+  #[serde(default)]
+  pub pages: Option<Range<usize>>,
+  /// Terms the translation must render exactly as given
+  #[serde(default)]
+  pub glossary: BTreeMap<String, String>,
+  /// Whether summaries and insights are read from and saved to the stage
+  /// cache, which reuses them while the paper, model and prompt are
+  /// unchanged
+  #[serde(default)]
+  pub cache: CacheControl,
+  /// Where the stage cache is kept (default `~/.agentflow/cache/agents`)
+  #[serde(default)]
+  pub cache_dir: Option<PathBuf>,
+  /// A pricing table (`pricing.yml`) that prices each stage's tokens in
+  /// the stage metrics; without one no cost is reported
+  #[serde(default)]
+  pub pricing_file: Option<PathBuf>,
+}
+
+fn default_chunk_concurrency() -> usize {
+  4
+}
+
+fn default_mind_map_use_llm() -> bool {
+  true
+}
+
+fn default_ocr_min_chars_per_page() -> usize {
+  DEFAULT_MIN_CHARS_PER_PAGE
+}
+
+fn default_figure_min_size() -> u32 {
+  150
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum AnalysisDepth {
-  Summary,      // Generate summary only
-  Insights,     // Extract key insights only  
-  Comprehensive, // Full analysis with summary + insights + mind map
+  Summary,         // Generate summary only
+  Insights,        // Extract key insights only
+  Comprehensive,   // Full analysis with summary + insights + mind map
   WithTranslation, // Everything + translation
 }
 
@@ -28,22 +98,40 @@ impl Default for AnalyzerConfig {
       target_language: "en".to_string(),
       analysis_depth: AnalysisDepth::Comprehensive,
       generate_mind_map: true,
+      mind_map_use_llm: default_mind_map_use_llm(),
       model: "step-2-16k".to_string(),
       concurrency_limit: 3,
+      chunk_concurrency: default_chunk_concurrency(),
+      extraction_backend: Backend::default(),
+      ocr: OcrBackend::default(),
+      ocr_min_chars_per_page: default_ocr_min_chars_per_page(),
+      extract_figures: false,
+      figure_min_size: default_figure_min_size(),
+      extract_citations: false,
+      clean_citations: false,
+      pages: None,
+      glossary: BTreeMap::new(),
+      cache: CacheControl::default(),
+      cache_dir: None,
+      pricing_file: None,
     }
   }
 }
 
 impl AgentConfig for AnalyzerConfig {
   fn validate(&self) -> AgentResult<()> {
-    if self.stepfun_api_key.is_empty() {
+    if self.stepfun_api_key.is_empty() && self.extraction_backend != Backend::Local {
       return Err("StepFun API key is required".into());
     }
-    
+
     if self.concurrency_limit == 0 {
       return Err("Concurrency limit must be greater than 0".into());
     }
-    
+
+    if self.chunk_concurrency == 0 {
+      return Err("Chunk concurrency must be greater than 0".into());
+    }
+
     Ok(())
   }
-}
\ No newline at end of file
+}
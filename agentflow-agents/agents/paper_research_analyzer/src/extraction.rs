@@ -0,0 +1,468 @@
+//! PDF text extraction backends
+//!
+//! `Backend::StepFun` uploads the PDF to the StepFun document parser;
+//! `Backend::Local` extracts its text layer on this machine with
+//! `pdf-extract` (the `local-pdf` feature), so confidential papers never
+//! leave it; `Backend::Auto` tries local extraction first and falls back to
+//! StepFun when it finds no text, as with scanned PDFs. Local text keeps a
+//! `<!-- page N -->` marker at the start of every page, which
+//! `chunk_by_sections` prefers as a cut point. Either backend can extract
+//! just a range of pages.
+//!
+//! Text too thin for the number of pages (see `needs_ocr`) comes from a
+//! scan; `with_ocr_fallback` then reads the pages with the configured
+//! `OcrBackend`, or fails saying OCR is off.
+
+use agentflow_agents::{
+  AgentResult, OcrBackend, OcrPage, PDFContent, PdfParser, needs_ocr, ocr_pdf, pdf_page_count,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+/// Where PDF text comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+  /// Extract locally; fails for PDFs without a text layer
+  Local,
+  /// Upload to the StepFun document parser
+  #[default]
+  StepFun,
+  /// Extract locally, falling back to StepFun when no text comes out
+  Auto,
+}
+
+impl Backend {
+  pub fn parse(name: &str) -> AgentResult<Self> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "local" => Ok(Self::Local),
+      "stepfun" => Ok(Self::StepFun),
+      "auto" => Ok(Self::Auto),
+      other => Err(
+        format!(
+          "Unknown extraction backend '{}' (expected local, stepfun or auto)",
+          other
+        )
+        .into(),
+      ),
+    }
+  }
+}
+
+/// Parse `--pages`: `N` or `N-M`, 1-based and inclusive, into 0-based
+/// page indices
+pub fn parse_page_range(spec: &str) -> AgentResult<Range<usize>> {
+  let invalid = || {
+    format!(
+      "Invalid page range '{}' (expected N or N-M, counting from 1)",
+      spec
+    )
+  };
+  let (first, last) = spec
+    .trim()
+    .split_once('-')
+    .unwrap_or((spec.trim(), spec.trim()));
+  let first: usize = first.trim().parse().map_err(|_| invalid())?;
+  let last: usize = last.trim().parse().map_err(|_| invalid())?;
+  if first == 0 || last < first {
+    return Err(invalid().into());
+  }
+  Ok(first - 1..last)
+}
+
+/// The marker opening page `number` (1-based) of locally extracted text
+pub fn page_marker(number: usize) -> String {
+  format!("<!-- page {} -->", number)
+}
+
+/// Whether `line` is a page marker
+pub fn is_page_marker(line: &str) -> bool {
+  let line = line.trim();
+  line
+    .strip_prefix("<!-- page ")
+    .and_then(|rest| rest.strip_suffix(" -->"))
+    .is_some_and(|number| number.parse::<usize>().is_ok())
+}
+
+/// Whether `content` holds any text besides page markers
+fn has_text(content: &str) -> bool {
+  content
+    .lines()
+    .any(|line| !line.trim().is_empty() && !is_page_marker(line))
+}
+
+/// Extracts the text of a PDF file
+#[async_trait]
+pub trait PdfExtractor: Send + Sync {
+  /// Extract `pages` (0-based, end exclusive), or every page for `None`
+  async fn extract(&self, pdf_path: &Path, pages: Option<Range<usize>>) -> AgentResult<PDFContent>;
+}
+
+#[async_trait]
+impl PdfExtractor for PdfParser {
+  async fn extract(&self, pdf_path: &Path, pages: Option<Range<usize>>) -> AgentResult<PDFContent> {
+    Ok(PdfParser::extract(self, pdf_path, pages).await?)
+  }
+}
+
+/// Extracts the PDF's text layer with `pdf-extract`
+#[derive(Debug, Default, Clone)]
+pub struct LocalPdfExtractor;
+
+#[async_trait]
+impl PdfExtractor for LocalPdfExtractor {
+  #[cfg(feature = "local-pdf")]
+  async fn extract(&self, pdf_path: &Path, pages: Option<Range<usize>>) -> AgentResult<PDFContent> {
+    let path = pdf_path.to_path_buf();
+    if !path.exists() {
+      return Err(format!("PDF file does not exist: {}", path.display()).into());
+    }
+    println!("📄 Extracting PDF text locally: {}", path.display());
+
+    // pdf-extract is CPU-bound and can panic on malformed input; keep both
+    // off the async worker.
+    let page_texts = tokio::task::spawn_blocking({
+      let path = path.clone();
+      move || pdf_extract::extract_text_by_pages(&path)
+    })
+    .await
+    .map_err(|e| format!("Local PDF extraction panicked: {}", e))?
+    .map_err(|e| format!("Local PDF extraction failed: {}", e))?;
+
+    let range = pages.unwrap_or(0..page_texts.len());
+    if range.start >= range.end || range.end > page_texts.len() {
+      return Err(
+        format!(
+          "Invalid page range {}..{} for a PDF with {} pages",
+          range.start,
+          range.end,
+          page_texts.len()
+        )
+        .into(),
+      );
+    }
+    let selected = &page_texts[range.clone()];
+    let content = with_page_markers(selected, range.start + 1);
+    println!(
+      "✅ Extracted {} characters from {} pages",
+      content.len(),
+      selected.len()
+    );
+
+    Ok(PDFContent {
+      file_id: "local".to_string(),
+      token_count: (content.len() / 4) as u64,
+      content,
+      filename: path
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "local.pdf".to_string()),
+    })
+  }
+
+  #[cfg(not(feature = "local-pdf"))]
+  async fn extract(
+    &self,
+    _pdf_path: &Path,
+    _pages: Option<Range<usize>>,
+  ) -> AgentResult<PDFContent> {
+    Err("Local PDF extraction needs the `local-pdf` feature".into())
+  }
+}
+
+/// The pages' text, each behind its page marker; the first is page
+/// `first` (1-based)
+pub fn with_page_markers(pages: &[String], first: usize) -> String {
+  pages
+    .iter()
+    .enumerate()
+    .map(|(i, page)| format!("{}\n\n{}\n\n", page_marker(first + i), page.trim()))
+    .collect()
+}
+
+/// Extract `pages` of `pdf_path` with `backend`.
+pub async fn extract_with(
+  backend: Backend,
+  local: &dyn PdfExtractor,
+  stepfun: &dyn PdfExtractor,
+  pdf_path: &Path,
+  pages: Option<Range<usize>>,
+) -> AgentResult<PDFContent> {
+  match backend {
+    Backend::Local => {
+      let content = local.extract(pdf_path, pages).await?;
+      if !has_text(&content.content) {
+        return Err(
+          format!(
+            "No text found in {} (scanned PDF?); use the stepfun or auto extraction backend",
+            pdf_path.display()
+          )
+          .into(),
+        );
+      }
+      Ok(content)
+    }
+    Backend::StepFun => stepfun.extract(pdf_path, pages).await,
+    Backend::Auto => {
+      match local.extract(pdf_path, pages.clone()).await {
+        Ok(content) if has_text(&content.content) => return Ok(content),
+        Ok(_) => println!("⚠️  No text found locally (scanned PDF?), falling back to StepFun"),
+        Err(e) => println!("⚠️  {}, falling back to StepFun", e),
+      }
+      stepfun.extract(pdf_path, pages).await
+    }
+  }
+}
+
+/// `extracted`, unless it is too thin to be the text of its pages, as with
+/// a scanned PDF: then the pages are read with `ocr`. An extraction that
+/// failed is retried with OCR too, when it is on.
+pub async fn with_ocr_fallback(
+  extracted: AgentResult<PDFContent>,
+  ocr: &OcrBackend,
+  min_chars_per_page: usize,
+  pdf_path: &Path,
+  pages: Option<Range<usize>>,
+) -> AgentResult<PDFContent> {
+  let page_count = match &pages {
+    Some(range) => range.len(),
+    None => pdf_page_count(pdf_path).unwrap_or(1),
+  };
+  match extracted {
+    Ok(content) if !needs_ocr(&content.content, page_count, min_chars_per_page) => {
+      return Ok(content);
+    }
+    Ok(content) if !ocr.is_enabled() => {
+      return Err(format!(
+        "Only {} characters of text in the {} pages of {} (scanned PDF?); set `ocr` (--ocr tesseract or --ocr vision) to read the pages",
+        content.content.trim().chars().count(),
+        page_count,
+        pdf_path.display()
+      ).into());
+    }
+    Ok(_) => println!(
+      "⚠️  Too little text in {} (scanned PDF?), reading its pages with OCR",
+      pdf_path.display()
+    ),
+    Err(e) if ocr.is_enabled() => println!("⚠️  {}, reading the pages with OCR", e),
+    Err(e) => return Err(e),
+  }
+
+  let ocr_pages = ocr_pdf(ocr, pdf_path, pages).await?;
+  let content = with_ocr_page_markers(&ocr_pages);
+  println!(
+    "✅ OCR read {} characters from {} pages",
+    content.len(),
+    ocr_pages.len()
+  );
+  Ok(PDFContent {
+    file_id: "ocr".to_string(),
+    token_count: (content.len() / 4) as u64,
+    content,
+    filename: pdf_path
+      .file_name()
+      .map(|s| s.to_string_lossy().into_owned())
+      .unwrap_or_else(|| "scanned.pdf".to_string()),
+  })
+}
+
+/// OCR text of each page behind its page marker
+pub fn with_ocr_page_markers(pages: &[OcrPage]) -> String {
+  pages
+    .iter()
+    .map(|page| format!("{}\n\n{}\n\n", page_marker(page.page), page.text.trim()))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/two_pages.pdf");
+
+  /// Returns `content`, counts its calls and keeps the last page range
+  struct MockExtractor {
+    content: &'static str,
+    calls: AtomicUsize,
+    pages: Mutex<Option<Range<usize>>>,
+  }
+
+  impl MockExtractor {
+    fn new(content: &'static str) -> Self {
+      Self {
+        content,
+        calls: AtomicUsize::new(0),
+        pages: Mutex::new(None),
+      }
+    }
+  }
+
+  #[async_trait]
+  impl PdfExtractor for MockExtractor {
+    async fn extract(
+      &self,
+      _pdf_path: &Path,
+      pages: Option<Range<usize>>,
+    ) -> AgentResult<PDFContent> {
+      self.calls.fetch_add(1, Ordering::SeqCst);
+      *self.pages.lock().unwrap() = pages;
+      Ok(PDFContent {
+        file_id: "mock".to_string(),
+        content: self.content.to_string(),
+        token_count: 0,
+        filename: "paper.pdf".to_string(),
+      })
+    }
+  }
+
+  #[tokio::test]
+  async fn test_auto_falls_back_to_stepfun_without_text() {
+    let path = Path::new("paper.pdf");
+    let scanned = MockExtractor::new("<!-- page 1 -->\n\n\n\n<!-- page 2 -->\n\n");
+    let stepfun = MockExtractor::new("OCR text");
+
+    let content = extract_with(Backend::Auto, &scanned, &stepfun, path, None)
+      .await
+      .unwrap();
+    assert_eq!(content.content, "OCR text");
+    assert_eq!(stepfun.calls.load(Ordering::SeqCst), 1);
+
+    let text = MockExtractor::new("<!-- page 1 -->\n\nLocal text");
+    let content = extract_with(Backend::Auto, &text, &stepfun, path, None)
+      .await
+      .unwrap();
+    assert_eq!(content.content, "<!-- page 1 -->\n\nLocal text");
+    assert_eq!(stepfun.calls.load(Ordering::SeqCst), 1);
+
+    assert!(
+      extract_with(Backend::Local, &scanned, &stepfun, path, None)
+        .await
+        .is_err()
+    );
+    extract_with(Backend::StepFun, &text, &stepfun, path, None)
+      .await
+      .unwrap();
+    assert_eq!(text.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(stepfun.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(Backend::parse("StepFun").unwrap(), Backend::StepFun);
+
+    // Auto passes the range to both backends.
+    extract_with(Backend::Auto, &scanned, &stepfun, path, Some(2..5))
+      .await
+      .unwrap();
+    assert_eq!(*scanned.pages.lock().unwrap(), Some(2..5));
+    assert_eq!(*stepfun.pages.lock().unwrap(), Some(2..5));
+  }
+
+  #[tokio::test]
+  async fn test_thin_text_needs_ocr_to_go_on() {
+    let path = Path::new(FIXTURE);
+    let extracted = |content: &str| {
+      Ok(PDFContent {
+        file_id: "local".to_string(),
+        content: content.to_string(),
+        token_count: 0,
+        filename: "two_pages.pdf".to_string(),
+      })
+    };
+
+    let text = "Attention is all you need. ".repeat(10);
+    let content = with_ocr_fallback(extracted(&text), &OcrBackend::Off, 100, path, None)
+      .await
+      .unwrap();
+    assert_eq!(content.content, text);
+
+    // Two pages need 200 letters; the page range counts one
+    let short = "Attention is all you need. ".repeat(6);
+    let error = with_ocr_fallback(extracted(&short), &OcrBackend::Off, 100, path, None)
+      .await
+      .unwrap_err();
+    assert!(error.to_string().contains("--ocr tesseract"), "{}", error);
+    assert!(
+      with_ocr_fallback(extracted(&short), &OcrBackend::Off, 100, path, Some(0..1))
+        .await
+        .is_ok()
+    );
+
+    let pages = [
+      OcrPage {
+        page: 2,
+        text: "Abstract\n".to_string(),
+      },
+      OcrPage {
+        page: 3,
+        text: "1 Introduction".to_string(),
+      },
+    ];
+    assert_eq!(
+      with_ocr_page_markers(&pages),
+      "<!-- page 2 -->\n\nAbstract\n\n<!-- page 3 -->\n\n1 Introduction\n\n"
+    );
+  }
+
+  #[test]
+  fn test_parse_page_range() {
+    assert_eq!(parse_page_range("3-10").unwrap(), 2..10);
+    assert_eq!(parse_page_range(" 4 ").unwrap(), 3..4);
+    for spec in ["0-3", "5-2", "a-b", ""] {
+      assert!(parse_page_range(spec).is_err(), "{}", spec);
+    }
+  }
+
+  #[cfg(feature = "local-pdf")]
+  #[tokio::test]
+  async fn test_local_extraction_marks_pages() {
+    let content = LocalPdfExtractor
+      .extract(Path::new(FIXTURE), None)
+      .await
+      .unwrap();
+    assert_eq!(content.filename, "two_pages.pdf");
+
+    let page_one = content.content.find("<!-- page 1 -->").unwrap();
+    let introduction = content.content.find("1 Introduction").unwrap();
+    let page_two = content.content.find("<!-- page 2 -->").unwrap();
+    let results = content
+      .content
+      .find("Page boundaries are preserved.")
+      .unwrap();
+    assert!(page_one < introduction && introduction < page_two && page_two < results);
+
+    let chunks = crate::chunking::chunk_by_sections(&content.content, 100);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[1].title.as_deref(), Some("2 Results"));
+    assert!(chunks[1].text.starts_with("<!-- page 2 -->"));
+
+    let second = LocalPdfExtractor
+      .extract(Path::new(FIXTURE), Some(1..2))
+      .await
+      .unwrap();
+    assert!(second.content.starts_with("<!-- page 2 -->"));
+    assert!(!second.content.contains("1 Introduction"));
+    assert!(
+      LocalPdfExtractor
+        .extract(Path::new(FIXTURE), Some(1..3))
+        .await
+        .is_err()
+    );
+  }
+
+  #[cfg(not(feature = "local-pdf"))]
+  #[tokio::test]
+  async fn test_local_extraction_needs_the_feature() {
+    let stepfun = MockExtractor::new("StepFun text");
+    let content = extract_with(
+      Backend::Auto,
+      &LocalPdfExtractor,
+      &stepfun,
+      Path::new(FIXTURE),
+      None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(content.content, "StepFun text");
+  }
+}
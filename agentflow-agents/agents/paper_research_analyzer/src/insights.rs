@@ -0,0 +1,446 @@
+//! Key insights of a paper as a typed structure
+//!
+//! Insights are requested with a JSON schema (`insights_schema`) and the
+//! reply is coerced into `PaperInsights` by `parse_insights`: years given
+//! as strings, lists given as one string, ratings in any case, and the
+//! field names of the older free-form prompt are all accepted. A reply
+//! that still does not fit gets one repair call; if that fails too the
+//! reply is kept in `raw`.
+
+use crate::llm::{AnalysisLlm, LlmReply};
+use agentflow_agents::AgentResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// The schema name sent with structured-output requests
+pub const INSIGHTS_SCHEMA_NAME: &str = "paper_insights";
+
+/// The JSON fields the insights prompts ask for
+pub const INSIGHTS_FORMAT: &str = r#"{
+  "title": "论文确切标题",
+  "authors": ["作者列表"],
+  "year": 2024,
+  "field": "主要研究领域",
+  "methodology": ["使用的方法列表"],
+  "contributions": ["主要贡献"],
+  "datasets": ["提到的数据集"],
+  "metrics": ["用于评估的指标"],
+  "future_work": ["建议的未来研究方向"],
+  "impact": "high/medium/low/unclear",
+  "reproducibility": "high/medium/low/unclear"
+}"#;
+
+/// A high/medium/low assessment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+  High,
+  Medium,
+  Low,
+  #[default]
+  Unclear,
+}
+
+impl Rating {
+  fn coerce(text: &str) -> Self {
+    let text = text.trim().to_lowercase();
+    if text.starts_with("high") || text.starts_with('高') {
+      Self::High
+    } else if text.starts_with("medium") || text.starts_with("moderate") || text.starts_with('中')
+    {
+      Self::Medium
+    } else if text.starts_with("low") || text.starts_with('低') {
+      Self::Low
+    } else {
+      Self::Unclear
+    }
+  }
+}
+
+/// The key insights of a paper
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaperInsights {
+  pub title: String,
+  pub authors: Vec<String>,
+  pub year: Option<i32>,
+  pub field: String,
+  pub methodology: Vec<String>,
+  pub contributions: Vec<String>,
+  pub datasets: Vec<String>,
+  pub metrics: Vec<String>,
+  pub future_work: Vec<String>,
+  pub impact: Rating,
+  pub reproducibility: Rating,
+  /// The model's reply when it could not be read as insights
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub raw: Option<String>,
+}
+
+impl PaperInsights {
+  /// Insights holding only an unreadable reply
+  pub fn raw(reply: &str) -> Self {
+    Self {
+      raw: Some(reply.to_string()),
+      ..Default::default()
+    }
+  }
+
+  /// Coerce a JSON object into insights. Fails, naming the field, when a
+  /// value has a shape that cannot be coerced.
+  pub fn from_value(value: &Value) -> Result<Self, String> {
+    let object = value.as_object().ok_or("insights must be a JSON object")?;
+    Ok(Self {
+      title: text_field(object, &["title"])?,
+      authors: list_field(object, &["authors"])?,
+      year: year_field(object, &["year", "publication_year"])?,
+      field: text_field(object, &["field", "field_of_study"])?,
+      methodology: list_field(object, &["methodology"])?,
+      contributions: list_field(object, &["contributions", "key_contributions"])?,
+      datasets: list_field(object, &["datasets", "datasets_used"])?,
+      metrics: list_field(object, &["metrics", "evaluation_metrics"])?,
+      future_work: list_field(object, &["future_work"])?,
+      impact: Rating::coerce(&text_field(object, &["impact", "impact_potential"])?),
+      reproducibility: Rating::coerce(&text_field(object, &["reproducibility"])?),
+      raw: text_field(object, &["raw"])
+        .ok()
+        .filter(|raw| !raw.is_empty()),
+    })
+  }
+
+  /// Fold `other` in: lists gain the items they lack (compared
+  /// case-insensitively), and empty or unclear fields take its values
+  pub fn merge(&mut self, other: &PaperInsights) {
+    if self.title.is_empty() {
+      self.title = other.title.clone();
+    }
+    if self.year.is_none() {
+      self.year = other.year;
+    }
+    if self.field.is_empty() {
+      self.field = other.field.clone();
+    }
+    for (list, more) in [
+      (&mut self.authors, &other.authors),
+      (&mut self.methodology, &other.methodology),
+      (&mut self.contributions, &other.contributions),
+      (&mut self.datasets, &other.datasets),
+      (&mut self.metrics, &other.metrics),
+      (&mut self.future_work, &other.future_work),
+    ] {
+      for item in more {
+        let key = dedup_key(item);
+        if !list.iter().any(|existing| dedup_key(existing) == key) {
+          list.push(item.clone());
+        }
+      }
+    }
+    if self.impact == Rating::Unclear {
+      self.impact = other.impact;
+    }
+    if self.reproducibility == Rating::Unclear {
+      self.reproducibility = other.reproducibility;
+    }
+    if let Some(raw) = &other.raw {
+      self.raw = Some(match self.raw.take() {
+        Some(existing) => format!("{}\n\n{}", existing, raw),
+        None => raw.clone(),
+      });
+    }
+  }
+}
+
+fn dedup_key(item: &str) -> String {
+  item
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_lowercase()
+}
+
+fn lookup<'a>(object: &'a Map<String, Value>, names: &[&str]) -> Option<(&'a str, &'a Value)> {
+  names.iter().find_map(|name| {
+    object
+      .get_key_value(*name)
+      .map(|(key, value)| (key.as_str(), value))
+  })
+}
+
+fn text_field(object: &Map<String, Value>, names: &[&str]) -> Result<String, String> {
+  match lookup(object, names) {
+    None | Some((_, Value::Null)) => Ok(String::new()),
+    Some((_, Value::String(text))) => Ok(text.trim().to_string()),
+    Some((_, value @ (Value::Number(_) | Value::Bool(_)))) => Ok(value.to_string()),
+    Some((name, _)) => Err(format!("`{}` must be a string", name)),
+  }
+}
+
+fn list_field(object: &Map<String, Value>, names: &[&str]) -> Result<Vec<String>, String> {
+  let items = match lookup(object, names) {
+    None | Some((_, Value::Null)) => return Ok(Vec::new()),
+    Some((_, Value::String(text))) => text
+      .split([',', ';', '，', '；', '、', '\n'])
+      .map(|item| item.trim().to_string())
+      .collect(),
+    Some((name, Value::Array(values))) => values
+      .iter()
+      .map(|value| match value {
+        Value::String(text) => Ok(text.trim().to_string()),
+        Value::Number(_) => Ok(value.to_string()),
+        Value::Object(item) => match item.get("name") {
+          Some(Value::String(text)) => Ok(text.trim().to_string()),
+          _ => Err(format!("`{}` must be a list of strings", name)),
+        },
+        _ => Err(format!("`{}` must be a list of strings", name)),
+      })
+      .collect::<Result<Vec<_>, _>>()?,
+    Some((name, _)) => return Err(format!("`{}` must be a list of strings", name)),
+  };
+  Ok(items.into_iter().filter(|item| !item.is_empty()).collect())
+}
+
+/// A year given as a number or as text containing one ("2017", "2017年")
+fn year_field(object: &Map<String, Value>, names: &[&str]) -> Result<Option<i32>, String> {
+  match lookup(object, names) {
+    None | Some((_, Value::Null)) => Ok(None),
+    Some((name, Value::Number(number))) => number
+      .as_f64()
+      .filter(|year| year.fract() == 0.0)
+      .map(|year| Some(year as i32))
+      .ok_or_else(|| format!("`{}` must be a whole year", name)),
+    Some((_, Value::String(text))) => {
+      let digits: String = text
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+      Ok(digits.parse().ok().filter(|_| digits.len() == 4))
+    }
+    Some((name, _)) => Err(format!("`{}` must be a year", name)),
+  }
+}
+
+/// The JSON schema of the insights reply
+pub fn insights_schema() -> Value {
+  let list = json!({ "type": "array", "items": { "type": "string" } });
+  let rating = json!({ "type": "string", "enum": ["high", "medium", "low", "unclear"] });
+  json!({
+    "type": "object",
+    "properties": {
+      "title": { "type": "string" },
+      "authors": list,
+      "year": { "type": ["integer", "null"] },
+      "field": { "type": "string" },
+      "methodology": list,
+      "contributions": list,
+      "datasets": list,
+      "metrics": list,
+      "future_work": list,
+      "impact": rating,
+      "reproducibility": rating
+    },
+    "required": [
+      "title", "authors", "year", "field", "methodology", "contributions",
+      "datasets", "metrics", "future_work", "impact", "reproducibility"
+    ],
+    "additionalProperties": false
+  })
+}
+
+/// Read a reply as insights, tolerating prose or a code fence around the
+/// JSON object
+pub fn parse_insights(reply: &str) -> Result<PaperInsights, String> {
+  let start = reply.find('{').ok_or("the reply holds no JSON object")?;
+  let end = reply
+    .rfind('}')
+    .filter(|&end| end > start)
+    .ok_or("the JSON object is not closed")?;
+  let value: Value = serde_json::from_str(&reply[start..=end])
+    .map_err(|e| format!("the reply is not valid JSON: {}", e))?;
+  PaperInsights::from_value(&value)
+}
+
+/// The prompt asking the model to fix a reply that failed `parse_insights`
+pub fn repair_prompt(reply: &str, error: &str) -> String {
+  format!(
+    r#"
+The following reply was supposed to be a JSON object with a paper's key insights, but {}.
+Return only the corrected JSON object, with exactly these fields:
+
+{}
+
+Reply:
+{}
+"#,
+    error, INSIGHTS_FORMAT, reply
+  )
+}
+
+/// Ask for insights with `prompt`, repairing an unreadable reply once.
+/// Returns the insights and every reply, for token accounting.
+pub async fn extract_insights(
+  llm: &dyn AnalysisLlm,
+  prompt: &str,
+) -> AgentResult<(PaperInsights, Vec<LlmReply>)> {
+  let schema = insights_schema();
+  let reply = llm
+    .complete_json(prompt, INSIGHTS_SCHEMA_NAME, &schema, 0.2, 1500)
+    .await?;
+  let error = match parse_insights(&reply.content) {
+    Ok(insights) => return Ok((insights, vec![reply])),
+    Err(error) => error,
+  };
+
+  println!("⚠️  Insights reply needs repair: {}", error);
+  let repaired = llm
+    .complete_json(
+      &repair_prompt(&reply.content, &error),
+      INSIGHTS_SCHEMA_NAME,
+      &schema,
+      0.0,
+      1500,
+    )
+    .await?;
+  let insights = match parse_insights(&repaired.content) {
+    Ok(insights) => insights,
+    Err(error) => {
+      println!(
+        "⚠️  Repaired insights still unreadable ({}), keeping the raw reply",
+        error
+      );
+      PaperInsights::raw(&reply.content)
+    }
+  };
+  Ok((insights, vec![reply, repaired]))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use async_trait::async_trait;
+  use std::sync::Mutex;
+
+  /// Replies with `replies` in order and records the prompts
+  struct ScriptedLlm {
+    replies: Mutex<Vec<&'static str>>,
+    prompts: Mutex<Vec<String>>,
+  }
+
+  impl ScriptedLlm {
+    fn new(replies: &[&'static str]) -> Self {
+      Self {
+        replies: Mutex::new(replies.iter().rev().copied().collect()),
+        prompts: Mutex::new(Vec::new()),
+      }
+    }
+  }
+
+  #[async_trait]
+  impl AnalysisLlm for ScriptedLlm {
+    async fn complete(
+      &self,
+      prompt: &str,
+      _temperature: f32,
+      _max_tokens: u32,
+    ) -> AgentResult<LlmReply> {
+      self.prompts.lock().unwrap().push(prompt.to_string());
+      let content = self
+        .replies
+        .lock()
+        .unwrap()
+        .pop()
+        .ok_or("no more replies")?;
+      Ok(LlmReply {
+        content: content.to_string(),
+        ..Default::default()
+      })
+    }
+  }
+
+  #[test]
+  fn test_fields_are_coerced() {
+    let insights = parse_insights(
+      r#"Here you go:
+```json
+{"title": "Attention Is All You Need", "authors": "Ashish Vaswani, Noam Shazeer",
+ "publication_year": "2017年", "field_of_study": "NLP", "key_contributions": ["Transformer", ""],
+ "evaluation_metrics": [{"name": "BLEU"}], "impact_potential": "High", "reproducibility": "中等"}
+```"#,
+    )
+    .unwrap();
+    assert_eq!(insights.title, "Attention Is All You Need");
+    assert_eq!(insights.authors, vec!["Ashish Vaswani", "Noam Shazeer"]);
+    assert_eq!(insights.year, Some(2017));
+    assert_eq!(insights.field, "NLP");
+    assert_eq!(insights.contributions, vec!["Transformer"]);
+    assert_eq!(insights.metrics, vec!["BLEU"]);
+    assert_eq!(insights.impact, Rating::High);
+    assert_eq!(insights.reproducibility, Rating::Medium);
+
+    assert_eq!(
+      parse_insights(r#"{"year": 2017}"#).unwrap().year,
+      Some(2017)
+    );
+    assert_eq!(parse_insights(r#"{"year": "unknown"}"#).unwrap().year, None);
+    assert_eq!(
+      parse_insights(r#"{"authors": {"first": "A"}}"#).unwrap_err(),
+      "`authors` must be a list of strings"
+    );
+
+    let saved = serde_json::to_value(&insights).unwrap();
+    assert_eq!(saved["year"], 2017);
+    assert_eq!(saved["impact"], "high");
+    assert!(saved.get("raw").is_none());
+    assert_eq!(
+      serde_json::from_value::<PaperInsights>(saved).unwrap(),
+      insights
+    );
+  }
+
+  #[tokio::test]
+  async fn test_malformed_reply_is_repaired_once() {
+    let llm = ScriptedLlm::new(&[
+      r#"{"title": "Paper", "authors": ["A"#,
+      r#"{"title": "Paper", "authors": ["A"]}"#,
+    ]);
+    let (insights, replies) = extract_insights(&llm, "extract").await.unwrap();
+    assert_eq!(insights.title, "Paper");
+    assert_eq!(insights.authors, vec!["A"]);
+    assert_eq!(replies.len(), 2);
+    assert!(llm.prompts.lock().unwrap()[1].contains(
+      r#"Reply:
+{"title": "Paper", "authors": ["A"#
+    ));
+
+    let llm = ScriptedLlm::new(&["no idea", "still no idea"]);
+    let (insights, replies) = extract_insights(&llm, "extract").await.unwrap();
+    assert_eq!(insights.raw.as_deref(), Some("no idea"));
+    assert_eq!(replies.len(), 2);
+
+    let llm = ScriptedLlm::new(&[r#"{"title": "Paper"}"#]);
+    let (_, replies) = extract_insights(&llm, "extract").await.unwrap();
+    assert_eq!(replies.len(), 1);
+  }
+
+  #[test]
+  fn test_merge_deduplicates_lists() {
+    let mut merged = PaperInsights {
+      authors: vec!["A. Author".to_string()],
+      datasets: vec!["ImageNet".to_string()],
+      ..Default::default()
+    };
+    merged.merge(&PaperInsights {
+      title: "Paper".to_string(),
+      authors: vec!["a.  author".to_string(), "B. Author".to_string()],
+      datasets: vec!["COCO".to_string()],
+      impact: Rating::Low,
+      ..Default::default()
+    });
+    merged.merge(&PaperInsights::raw("not json"));
+    assert_eq!(merged.title, "Paper");
+    assert_eq!(merged.authors, vec!["A. Author", "B. Author"]);
+    assert_eq!(merged.datasets, vec!["ImageNet", "COCO"]);
+    assert_eq!(merged.impact, Rating::Low);
+    assert_eq!(merged.raw.as_deref(), Some("not json"));
+  }
+}
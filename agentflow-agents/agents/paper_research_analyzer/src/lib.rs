@@ -3,11 +3,23 @@
 //! A comprehensive PDF research paper analysis system using AgentFlow.
 
 pub mod analyzer;
+pub mod batch;
+pub mod chunking;
+pub mod citations;
+pub mod comparison;
 pub mod config;
+pub mod extraction;
+pub mod insights;
+pub mod llm;
 pub mod nodes;
+pub mod outline;
 
 pub use analyzer::*;
+pub use citations::{Citations, Reference};
+pub use comparison::Comparison;
 pub use config::*;
+pub use extraction::Backend;
+pub use insights::{PaperInsights, Rating};
 
 // Re-export for convenience
-pub use agentflow_agents::{AgentApplication, FileAgent, AgentResult};
\ No newline at end of file
+pub use agentflow_agents::{AgentApplication, AgentResult, FileAgent};
@@ -0,0 +1,118 @@
+//! The model calls of the analysis helpers
+//!
+//! Chunked analysis and insights extraction talk to the model through
+//! `AnalysisLlm`, so they can run against a canned model in tests.
+//! `ModelLlm` is the real one, over an AgentFlow model.
+
+use agentflow_agents::{AgentFlow, AgentResult};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+/// A model reply and its token usage, when the provider reports it
+#[derive(Debug, Clone, Default)]
+pub struct LlmReply {
+  pub content: String,
+  pub prompt_tokens: Option<u32>,
+  pub completion_tokens: Option<u32>,
+}
+
+/// The `usage` a node reports with its output: how many model calls it
+/// made and the tokens they used, for its stage metric
+pub fn usage_json(replies: &[LlmReply]) -> Value {
+  let sum = |tokens: fn(&LlmReply) -> Option<u32>| {
+    replies
+      .iter()
+      .filter_map(tokens)
+      .map(u64::from)
+      .reduce(|a, b| a + b)
+  };
+  json!({
+    "calls": replies.len(),
+    "prompt_tokens": sum(|reply| reply.prompt_tokens),
+    "completion_tokens": sum(|reply| reply.completion_tokens)
+  })
+}
+
+#[async_trait]
+pub trait AnalysisLlm: Send + Sync {
+  async fn complete(
+    &self,
+    prompt: &str,
+    temperature: f32,
+    max_tokens: u32,
+  ) -> AgentResult<LlmReply>;
+
+  /// A reply constrained to `schema`. Models without structured output
+  /// get the plain prompt.
+  async fn complete_json(
+    &self,
+    prompt: &str,
+    _schema_name: &str,
+    _schema: &Value,
+    temperature: f32,
+    max_tokens: u32,
+  ) -> AgentResult<LlmReply> {
+    self.complete(prompt, temperature, max_tokens).await
+  }
+}
+
+/// `AnalysisLlm` over an AgentFlow model
+pub struct ModelLlm {
+  model: String,
+}
+
+impl ModelLlm {
+  pub fn new(model: String) -> Self {
+    Self { model }
+  }
+}
+
+fn reply(response: agentflow_llm::LLMResponse) -> LlmReply {
+  LlmReply {
+    prompt_tokens: response
+      .usage
+      .as_ref()
+      .and_then(|usage| usage.prompt_tokens),
+    completion_tokens: response
+      .usage
+      .as_ref()
+      .and_then(|usage| usage.completion_tokens),
+    content: response.content,
+  }
+}
+
+#[async_trait]
+impl AnalysisLlm for ModelLlm {
+  async fn complete(
+    &self,
+    prompt: &str,
+    temperature: f32,
+    max_tokens: u32,
+  ) -> AgentResult<LlmReply> {
+    let response = AgentFlow::model(&self.model)
+      .prompt(prompt)
+      .temperature(temperature)
+      .max_tokens(max_tokens)
+      .execute_full()
+      .await?;
+    Ok(reply(response))
+  }
+
+  async fn complete_json(
+    &self,
+    prompt: &str,
+    schema_name: &str,
+    schema: &Value,
+    temperature: f32,
+    max_tokens: u32,
+  ) -> AgentResult<LlmReply> {
+    let response = AgentFlow::model(&self.model)
+      .prompt(prompt)
+      .temperature(temperature)
+      .max_tokens(max_tokens)
+      .json_schema(schema_name, schema.clone())
+      .execute_full()
+      .await?;
+    Ok(reply(response))
+  }
+}
@@ -2,143 +2,327 @@
 //!
 //! A comprehensive PDF research paper analysis agent built with AgentFlow.
 
+use agentflow_agents::{
+  AdaptiveThrottle, CacheControl, OcrBackend, RetryPolicy, StageCache, StageMetric, StageMetrics,
+  stage_metrics_table,
+};
 use clap::Parser;
-use paper_research_analyzer::{PDFAnalyzer, AnalysisDepth};
+use paper_research_analyzer::batch::BATCH_STATE_FILE;
+use paper_research_analyzer::extraction::parse_page_range;
+use paper_research_analyzer::{AnalysisDepth, Backend, PDFAnalyzer};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "paper-research-analyzer")]
 #[command(about = "Analyze PDF research papers using AI", long_about = None)]
 struct Cli {
-    /// Path to PDF file (for single analysis)
-    #[arg(long = "pdf-path")]
-    pdf_path: Option<PathBuf>,
-    
-    /// Directory containing PDF files (for batch analysis)  
-    #[arg(long = "batch-dir")]
-    batch_dir: Option<PathBuf>,
-    
-    /// Output directory
-    #[arg(short, long, default_value = "./analysis_output")]
-    output_dir: PathBuf,
-    
-    /// Analysis depth
-    #[arg(short, long, default_value = "comprehensive")]
-    depth: String,
-    
-    /// Target language for translation
-    #[arg(short, long, default_value = "zh")]
-    language: String,
-    
-    /// Model to use
-    #[arg(short, long, default_value = "qwen-turbo")]
-    model: String,
-    
-    /// Generate mind map
-    #[arg(long)]
-    mind_map: bool,
-    
-    /// Concurrency for batch processing
-    #[arg(long, default_value = "3")]
-    concurrency: usize,
+  /// Path to PDF file (for single analysis)
+  #[arg(long = "pdf-path")]
+  pdf_path: Option<PathBuf>,
+
+  /// Directory containing PDF files (for batch analysis)  
+  #[arg(long = "batch-dir")]
+  batch_dir: Option<PathBuf>,
+
+  /// Also analyze the PDFs in the batch directory's subdirectories
+  #[arg(long)]
+  recursive: bool,
+
+  /// Analyze the PDFs symlinks in the batch directory point to instead of skipping them
+  #[arg(long = "follow-symlinks")]
+  follow_symlinks: bool,
+
+  /// Output directory
+  #[arg(short, long, default_value = "./analysis_output")]
+  output_dir: PathBuf,
+
+  /// Analysis depth
+  #[arg(short, long, default_value = "comprehensive")]
+  depth: String,
+
+  /// Target language for translation
+  #[arg(short, long, default_value = "zh")]
+  language: String,
+
+  /// Model to use
+  #[arg(short, long, default_value = "qwen-turbo")]
+  model: String,
+
+  /// Generate mind map
+  #[arg(long)]
+  mind_map: bool,
+
+  /// Build the mind map from the summary's outline instead of asking the model
+  #[arg(long = "mind-map-no-llm")]
+  mind_map_no_llm: bool,
+
+  /// PDF text extraction: stepfun, local (needs the local-pdf feature) or auto
+  #[arg(long = "extraction-backend", default_value = "stepfun")]
+  extraction_backend: String,
+
+  /// Read scanned PDFs with OCR: off, tesseract, vision or vision:<model>
+  #[arg(long, default_value = "off")]
+  ocr: String,
+
+  /// Save the PDF's embedded figures under figures/ and link them from the summary
+  #[arg(long = "extract-figures")]
+  extract_figures: bool,
+
+  /// Analyze only these pages of each paper, e.g. 1-10 (counting from 1)
+  #[arg(long)]
+  pages: Option<String>,
+
+  /// Neither reuse nor save summaries and insights in the stage cache
+  #[arg(long = "no-cache")]
+  no_cache: bool,
+
+  /// Pricing table (pricing.yml) to report each stage's cost from
+  #[arg(long)]
+  pricing: Option<PathBuf>,
+
+  /// JSON file mapping terms to the translation they must always get
+  #[arg(long)]
+  glossary: Option<PathBuf>,
+
+  /// Concurrency for batch processing
+  #[arg(long, default_value = "3")]
+  concurrency: usize,
+
+  /// Skip the papers a previous batch run into the same output directory finished
+  #[arg(long)]
+  resume: bool,
+
+  /// Retry a batch paper that failed with a network, timeout or rate-limit error up to N times
+  #[arg(long, default_value = "0")]
+  retries: u32,
+
+  /// Slow the batch down while most recent papers fail, as when the provider rate limits
+  #[arg(long = "adaptive-throttle")]
+  adaptive_throttle: bool,
+
+  /// Extract the references section into structured citations
+  #[arg(long)]
+  citations: bool,
+
+  /// Have the model repair references the heuristics could not parse (implies --citations)
+  #[arg(long = "clean-citations")]
+  clean_citations: bool,
+
+  /// Model comparing a batch's papers; without it the comparison is built from their insights
+  #[arg(long = "compare-model")]
+  compare_model: Option<String>,
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Cli::parse();
-    
-    // Get API key from environment
-    let api_key = std::env::var("STEP_API_KEY")
-        .or_else(|_| std::env::var("API_KEY"))
-        .unwrap_or_else(|_| "demo_key".to_string());
-        
-    let analysis_depth = parse_analysis_depth(&args.depth)?;
-
-    // Determine operation mode based on arguments
-    if let Some(pdf_path) = args.pdf_path {
-        // Single PDF analysis
-        println!("🔍 Analyzing single PDF...");
-        println!("📄 File: {}", pdf_path.display());
-        println!("🎯 Depth: {}", args.depth);
-        println!("🤖 Model: {}", args.model);
-        println!("🧠 Mind Map: {}", args.mind_map);
-
-        let mut analyzer = PDFAnalyzer::new(api_key)
-            .analysis_depth(analysis_depth)
-            .model(&args.model)
-            .generate_mind_map(args.mind_map);
-            
-        if analysis_depth == AnalysisDepth::WithTranslation {
-            analyzer = analyzer.target_language(&args.language);
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let args = Cli::parse();
+
+  // Get API key from environment
+  let api_key = std::env::var("STEP_API_KEY")
+    .or_else(|_| std::env::var("API_KEY"))
+    .unwrap_or_else(|_| "demo_key".to_string());
+
+  let analysis_depth = parse_analysis_depth(&args.depth)?;
+  let extraction_backend = Backend::parse(&args.extraction_backend)?;
+  let ocr = OcrBackend::parse(&args.ocr)?;
+  let pages = args.pages.as_deref().map(parse_page_range).transpose()?;
+  let glossary: BTreeMap<String, String> = match &args.glossary {
+    Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+    None => BTreeMap::new(),
+  };
+  let cache = if args.no_cache {
+    CacheControl::Off
+  } else {
+    CacheControl::ReadWrite
+  };
+
+  // Determine operation mode based on arguments
+  if let Some(pdf_path) = args.pdf_path {
+    // Single PDF analysis
+    println!("🔍 Analyzing single PDF...");
+    println!("📄 File: {}", pdf_path.display());
+    println!("🎯 Depth: {}", args.depth);
+    println!("🤖 Model: {}", args.model);
+    println!("🧠 Mind Map: {}", args.mind_map);
+
+    let mut analyzer = PDFAnalyzer::new(api_key)
+      .analysis_depth(analysis_depth)
+      .model(&args.model)
+      .generate_mind_map(args.mind_map)
+      .mind_map_use_llm(!args.mind_map_no_llm)
+      .extraction_backend(extraction_backend)
+      .ocr(ocr)
+      .extract_figures(args.extract_figures)
+      .extract_citations(args.citations || args.clean_citations, args.clean_citations)
+      .cache(cache);
+    if let Some(pages) = pages.clone() {
+      analyzer = analyzer.pages(pages);
+    }
+    if let Some(pricing) = &args.pricing {
+      analyzer = analyzer.pricing_file(pricing);
+    }
+
+    if analysis_depth == AnalysisDepth::WithTranslation {
+      analyzer = analyzer
+        .target_language(&args.language)
+        .glossary(glossary.clone());
+    }
+
+    match analyzer.analyze_paper(&pdf_path).await {
+      Ok(result) => {
+        println!("✅ Analysis completed successfully!");
+        print_cache_stats(analyzer.stage_cache());
+        print_stage_metrics(&result.stage_metrics);
+        if let Err(e) = result.save_to_files(&args.output_dir).await {
+          eprintln!("❌ Failed to save results: {}", e);
+          std::process::exit(1);
         }
+      }
+      Err(e) => {
+        eprintln!("❌ Analysis failed: {}", e);
+        std::process::exit(1);
+      }
+    }
+  } else if let Some(batch_directory) = args.batch_dir {
+    // Batch analysis
+    println!("🔄 Starting batch analysis...");
+    println!("📁 Directory: {}", batch_directory.display());
+    println!("🎯 Depth: {}", args.depth);
+    println!("🤖 Model: {}", args.model);
+    println!("⚡ Concurrency: {}", args.concurrency);
 
-        match analyzer.analyze_paper(&pdf_path).await {
-            Ok(result) => {
-                println!("✅ Analysis completed successfully!");
-                if let Err(e) = result.save_to_files(&args.output_dir).await {
-                    eprintln!("❌ Failed to save results: {}", e);
-                    std::process::exit(1);
-                }
-            }
-            Err(e) => {
-                eprintln!("❌ Analysis failed: {}", e);
-                std::process::exit(1);
-            }
+    let mut analyzer = PDFAnalyzer::new(api_key)
+      .analysis_depth(analysis_depth)
+      .model(&args.model)
+      .generate_mind_map(args.mind_map)
+      .mind_map_use_llm(!args.mind_map_no_llm)
+      .extraction_backend(extraction_backend)
+      .ocr(ocr)
+      .extract_figures(args.extract_figures)
+      .extract_citations(args.citations || args.clean_citations, args.clean_citations)
+      .concurrency(args.concurrency)
+      .recursive(args.recursive)
+      .follow_symlinks(args.follow_symlinks)
+      .batch_state(args.output_dir.join(BATCH_STATE_FILE))
+      .resume(args.resume)
+      .glossary(glossary)
+      .cache(cache);
+    if let Some(pages) = pages {
+      analyzer = analyzer.pages(pages);
+    }
+    if let Some(pricing) = &args.pricing {
+      analyzer = analyzer.pricing_file(pricing);
+    }
+    if args.retries > 0 {
+      analyzer = analyzer.batch_retry(RetryPolicy {
+        max_attempts: args.retries,
+        ..RetryPolicy::default()
+      });
+    }
+    if args.adaptive_throttle {
+      analyzer = analyzer.batch_throttle(AdaptiveThrottle::default());
+    }
+
+    match analyzer.analyze_batch(&batch_directory).await {
+      Ok(mut batch_result) => {
+        println!("✅ Batch analysis completed!");
+        println!("📊 Processed: {} papers", batch_result.total_processed);
+        if batch_result.resumed > 0 {
+          println!(
+            "⏭️  Resumed: {} papers, newly analyzed: {}",
+            batch_result.resumed, batch_result.newly_processed
+          );
+        }
+        println!(
+          "✅ Successful: {} papers",
+          batch_result.successful_analyses.len()
+        );
+        println!("❌ Failed: {} papers", batch_result.failed_analyses.len());
+        print_cache_stats(analyzer.stage_cache());
+        // Each stage added up over the batch's papers
+        let batch_metrics = StageMetrics::new();
+        for (_, analysis) in &batch_result.successful_analyses {
+          analysis
+            .stage_metrics
+            .iter()
+            .cloned()
+            .for_each(|metric| batch_metrics.record(metric));
+        }
+        print_stage_metrics(&batch_metrics.metrics());
+
+        if !batch_result.failed_analyses.is_empty() {
+          println!("\n❌ Failed files:");
+          for (path, error) in &batch_result.failed_analyses {
+            println!("  - {}: {}", path.display(), error);
+          }
         }
-    } else if let Some(batch_directory) = args.batch_dir {
-        // Batch analysis
-        println!("🔄 Starting batch analysis...");
-        println!("📁 Directory: {}", batch_directory.display());
-        println!("🎯 Depth: {}", args.depth);
-        println!("🤖 Model: {}", args.model);
-        println!("⚡ Concurrency: {}", args.concurrency);
-
-        let analyzer = PDFAnalyzer::new(api_key)
-            .analysis_depth(analysis_depth)
-            .model(&args.model)
-            .generate_mind_map(args.mind_map);
-
-        match analyzer.analyze_batch(&batch_directory).await {
-            Ok(batch_result) => {
-                println!("✅ Batch analysis completed!");
-                println!("📊 Processed: {} papers", batch_result.total_processed);
-                println!("✅ Successful: {} papers", batch_result.successful_analyses.len());
-                println!("❌ Failed: {} papers", batch_result.failed_analyses.len());
-                
-                if !batch_result.failed_analyses.is_empty() {
-                    println!("\n❌ Failed files:");
-                    for (path, error) in &batch_result.failed_analyses {
-                        println!("  - {}: {}", path.display(), error);
-                    }
-                }
-                
-                if let Err(e) = batch_result.save_to_directory(&args.output_dir).await {
-                    eprintln!("❌ Failed to save batch results: {}", e);
-                    std::process::exit(1);
-                }
-            }
-            Err(e) => {
-                eprintln!("❌ Batch analysis failed: {}", e);
-                std::process::exit(1);
-            }
+
+        let comparison = batch_result
+          .generate_comparison(args.compare_model.as_deref())
+          .await;
+        println!(
+          "📑 Compared {} papers ({} excluded)",
+          comparison.rows.len(),
+          comparison.excluded.len()
+        );
+
+        if let Err(e) = batch_result.save_to_directory(&args.output_dir).await {
+          eprintln!("❌ Failed to save batch results: {}", e);
+          std::process::exit(1);
         }
-    } else {
-        eprintln!("❌ Error: Must provide either --pdf-path for single analysis or --batch-dir for batch analysis");
-        eprintln!("Usage examples:");
-        eprintln!("  Single PDF:  cargo run -- --pdf-path paper.pdf --depth comprehensive --mind-map");
-        eprintln!("  Batch mode:  cargo run -- --batch-dir ./papers/ --depth summary");
+      }
+      Err(e) => {
+        eprintln!("❌ Batch analysis failed: {}", e);
         std::process::exit(1);
+      }
     }
+  } else {
+    eprintln!(
+      "❌ Error: Must provide either --pdf-path for single analysis or --batch-dir for batch analysis"
+    );
+    eprintln!("Usage examples:");
+    eprintln!("  Single PDF:  cargo run -- --pdf-path paper.pdf --depth comprehensive --mind-map");
+    eprintln!("  Batch mode:  cargo run -- --batch-dir ./papers/ --depth summary");
+    std::process::exit(1);
+  }
 
-    Ok(())
+  Ok(())
 }
 
-fn parse_analysis_depth(depth: &str) -> Result<AnalysisDepth, Box<dyn std::error::Error>> {
-    match depth.to_lowercase().as_str() {
-        "summary" => Ok(AnalysisDepth::Summary),
-        "insights" => Ok(AnalysisDepth::Insights),
-        "comprehensive" => Ok(AnalysisDepth::Comprehensive),
-        "translation" | "with-translation" => Ok(AnalysisDepth::WithTranslation),
-        _ => Err(format!("Invalid analysis depth: {}. Valid options: summary, insights, comprehensive, translation", depth).into()),
-    }
-}
\ No newline at end of file
+/// One line per stage the cache was asked for, then the total
+fn print_cache_stats(cache: &StageCache) {
+  let stages = cache.stage_stats();
+  if stages.is_empty() {
+    return;
+  }
+  for (stage, stats) in &stages {
+    println!("🗄️  Cache, {}: {}", stage, stats);
+  }
+  println!("🗄️  Cache ({}): {}", cache.dir().display(), cache.stats());
+}
+
+/// The stage metrics table, when there are metrics
+fn print_stage_metrics(metrics: &[StageMetric]) {
+  if !metrics.is_empty() {
+    println!("\n⏱️  Stage metrics:\n{}", stage_metrics_table(metrics));
+  }
+}
+
+fn parse_analysis_depth(
+  depth: &str,
+) -> Result<AnalysisDepth, Box<dyn std::error::Error + Send + Sync>> {
+  match depth.to_lowercase().as_str() {
+    "summary" => Ok(AnalysisDepth::Summary),
+    "insights" => Ok(AnalysisDepth::Insights),
+    "comprehensive" => Ok(AnalysisDepth::Comprehensive),
+    "translation" | "with-translation" => Ok(AnalysisDepth::WithTranslation),
+    _ => Err(
+      format!(
+        "Invalid analysis depth: {}. Valid options: summary, insights, comprehensive, translation",
+        depth
+      )
+      .into(),
+    ),
+  }
+}
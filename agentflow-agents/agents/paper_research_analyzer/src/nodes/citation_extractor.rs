@@ -0,0 +1,93 @@
+//! Citation Extraction Node - Parse the bibliography into structured references
+
+use crate::citations::{clean_references, extract_citations};
+use crate::llm::ModelLlm;
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+pub struct CitationExtractorNode {
+  model: String,
+  llm_cleanup: bool,
+}
+
+impl CitationExtractorNode {
+  pub fn new(model: String) -> Self {
+    Self {
+      model,
+      llm_cleanup: false,
+    }
+  }
+
+  /// Have the model repair entries the heuristics could not fully parse
+  pub fn with_llm_cleanup(mut self, enable: bool) -> Self {
+    self.llm_cleanup = enable;
+    self
+  }
+}
+
+#[async_trait]
+impl AsyncNode for CitationExtractorNode {
+  async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
+    let content = shared
+      .get("pdf_content")
+      .ok_or_else(|| AgentFlowError::AsyncExecutionError {
+        message: "PDF content not available".to_string(),
+      })?;
+
+    Ok(json!({ "content": content }))
+  }
+
+  async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
+    let content = prep_result["content"].as_str().unwrap_or("");
+
+    println!("📚 Extracting references...");
+
+    let Some(mut citations) = extract_citations(content) else {
+      println!("⚠️  No references section found");
+      return Ok(json!({ "citations": null }));
+    };
+
+    let messy = citations
+      .references
+      .iter()
+      .filter(|reference| reference.needs_cleanup())
+      .count();
+    if self.llm_cleanup && messy > 0 {
+      println!(
+        "🧹 Cleaning up {} messy references with {}",
+        messy, self.model
+      );
+      let llm = ModelLlm::new(self.model.clone());
+      match clean_references(&llm, &mut citations.references).await {
+        Ok(cleaned) => println!("✅ Cleaned {} references", cleaned),
+        // The heuristic parse is still worth keeping
+        Err(e) => println!("⚠️  Reference cleanup failed: {}", e),
+      }
+    }
+
+    println!("✅ Extracted {} references", citations.references.len());
+
+    let citations_json =
+      serde_json::to_value(&citations).map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Citations serialization failed: {}", e),
+      })?;
+
+    Ok(json!({ "citations": citations_json }))
+  }
+
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
+    println!("📚 CitationExtractorNode: Storing citations in shared state");
+    shared.insert("citations".to_string(), exec_result["citations"].clone());
+    Ok(Some("summarizer".to_string()))
+  }
+
+  fn get_node_id(&self) -> Option<String> {
+    Some("citation_extractor".to_string())
+  }
+}
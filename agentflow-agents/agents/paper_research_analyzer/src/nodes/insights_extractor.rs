@@ -1,27 +1,52 @@
 //! Key Insights Extraction Node - Extract structured metadata and insights
 
-use agentflow_agents::{AsyncNode, SharedState, AgentFlowError, AgentFlow};
+use crate::insights::{INSIGHTS_FORMAT, extract_insights};
+use crate::llm::{ModelLlm, usage_json};
+use agentflow_agents::{StageCache, StageCacheKey};
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use serde_json::{Value, json};
+use std::sync::Mutex;
+
+const INSIGHTS_PROMPT: &str = r#"
+分析这篇研究论文，并按以下JSON格式提取关键洞察（未提及的年份为 null，无法判断的评级为 "unclear"）：
+
+{{format}}
+
+Research Paper Content:
+{{paper_content}}
+"#;
 
 pub struct InsightsNode {
   model: String,
+  cache: Option<StageCache>,
 }
 
 impl InsightsNode {
   pub fn new(model: String) -> Self {
-    Self { model }
+    Self { model, cache: None }
+  }
+
+  /// Reuse the insights of an unchanged paper from `cache`
+  pub fn with_cache(mut self, cache: StageCache) -> Self {
+    self.cache = Some(cache);
+    self
   }
 
   /// Get model capacity for insights extraction (75% of full capacity)
   fn get_model_capacity_for_insights(&self) -> usize {
     let full_capacity = match self.model.as_str() {
-      m if m.contains("qwen-turbo") || m.contains("qwen-plus-latest") || m.contains("qwen-long") => 800_000,
+      m if m.contains("qwen-turbo")
+        || m.contains("qwen-plus-latest")
+        || m.contains("qwen-long") =>
+      {
+        800_000
+      }
       m if m.contains("256k") => 200_000,
       m if m.contains("32k") => 80_000,
       m if m.contains("claude") => 180_000,
       m if m.contains("gpt-4o") => 120_000,
-      _ => 30_000
+      _ => 30_000,
     };
     (full_capacity as f64 * 0.75) as usize
   }
@@ -30,86 +55,112 @@ impl InsightsNode {
 #[async_trait]
 impl AsyncNode for InsightsNode {
   async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
-    let content = shared.get("pdf_content").ok_or_else(|| 
-      AgentFlowError::AsyncExecutionError { message: "PDF content not available".to_string() })?;
-    
+    let content = shared
+      .get("pdf_content")
+      .ok_or_else(|| AgentFlowError::AsyncExecutionError {
+        message: "PDF content not available".to_string(),
+      })?;
+
     Ok(json!({
       "content": content,
-      "model": self.model
+      "model": self.model,
+      "chunked_insights": shared.get("chunked_insights")
     }))
   }
 
   async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
-    let content = prep_result["content"].as_str().unwrap();
-    
-    // Calculate reasonable truncation based on model context window  
+    // Long papers arrive with the insights merged across chunks
+    if !prep_result["chunked_insights"].is_null() {
+      println!("✅ Using the merged insights of the chunked analysis");
+      return Ok(json!({
+        "insights": prep_result["chunked_insights"],
+        "model_used": self.model
+      }));
+    }
+
+    let content =
+      prep_result["content"]
+        .as_str()
+        .ok_or_else(|| AgentFlowError::AsyncExecutionError {
+          message: "Paper content not available".to_string(),
+        })?;
+
+    // Calculate reasonable truncation based on model context window
     let max_content_chars = self.get_model_capacity_for_insights();
-    
+
     let truncated_content = if content.len() > max_content_chars {
-      println!("⚠️  Content too long for insights extraction ({}), truncating to {} characters for model {}", 
-               content.len(), max_content_chars, self.model);
+      println!(
+        "⚠️  Content too long for insights extraction ({}), truncating to {} characters for model {}",
+        content.len(),
+        max_content_chars,
+        self.model
+      );
       &content[..max_content_chars]
     } else {
       content
     };
-    
-    println!("🔍 Extracting key insights and metadata...");
 
-    let insights_prompt = format!(r#"
-分析这篇研究论文，并按以下JSON格式提取关键洞察：
-
-{{
-  "title": "论文确切标题",
-  "authors": ["作者列表"],
-  "publication_year": "发表年份（如有）",
-  "field_of_study": "主要研究领域",
-  "research_type": "理论/实证/实验/综述/评论",
-  "methodology": ["使用的方法列表"],
-  "key_contributions": ["主要贡献"],
-  "novel_concepts": ["引入的新概念"],
-  "datasets_used": ["提到的数据集"],
-  "evaluation_metrics": ["用于评估的指标"],
-  "future_work": ["建议的未来研究方向"],
-  "citations_mentioned": "参考文献数量",
-  "research_gap": "填补了什么空白",
-  "impact_potential": "high/medium/low",
-  "reproducibility": "high/medium/low/unclear"
-}}
+    println!("🔍 Extracting key insights and metadata...");
 
-Research Paper Content:
-{}
-"#, truncated_content);
-
-    let response = AgentFlow::model(&self.model)
-      .prompt(&insights_prompt)
-      .temperature(0.2)
-      .max_tokens(1500)
-      .execute()
-      .await
-      .map_err(|e| AgentFlowError::AsyncExecutionError { 
-        message: format!("Insights extraction failed: {}", e) 
-      })?;
+    let template = INSIGHTS_PROMPT.replace("{{format}}", INSIGHTS_FORMAT);
+    let insights_prompt = template.replace("{{paper_content}}", truncated_content);
+    // Insights from the cache cost no call
+    let replies = Mutex::new(Vec::new());
+    let extract = || async {
+      let llm = ModelLlm::new(self.model.clone());
+      let (insights, extraction_replies) =
+        extract_insights(&llm, &insights_prompt)
+          .await
+          .map_err(|e| AgentFlowError::AsyncExecutionError {
+            message: format!("Insights extraction failed: {}", e),
+          })?;
+      replies
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(extraction_replies);
+      serde_json::to_value(&insights).map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Insights serialization failed: {}", e),
+      })
+    };
+    let insights_json = match &self.cache {
+      Some(cache) => {
+        let key = StageCacheKey::new("insights", truncated_content, &self.model, &template);
+        cache.get_or_compute(&key, extract).await?
+      }
+      None => extract().await?,
+    };
 
     println!("✅ Key insights extracted successfully");
 
-    // Try to parse as JSON to validate structure
-    let insights_json: Value = serde_json::from_str(&response)
-      .unwrap_or_else(|_| json!({"raw_response": response}));
-
+    let replies = replies.into_inner().unwrap_or_else(|e| e.into_inner());
     Ok(json!({
       "insights": insights_json,
-      "model_used": self.model
+      "model_used": self.model,
+      "usage": usage_json(&replies)
     }))
   }
 
-  async fn post_async(&self, shared: &SharedState, _prep_result: Value, exec_result: Value) -> Result<Option<String>, AgentFlowError> {
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
     println!("🔍 InsightsNode: Storing insights in shared state");
     shared.insert("insights".to_string(), exec_result);
-    
+
     // Determine next node
-    if shared.get("has_mindmap").and_then(|v| v.as_bool()).unwrap_or(false) {
+    if shared
+      .get("has_mindmap")
+      .and_then(|v| v.as_bool())
+      .unwrap_or(false)
+    {
       Ok(Some("mind_mapper".to_string()))
-    } else if shared.get("has_translation").and_then(|v| v.as_bool()).unwrap_or(false) {
+    } else if shared
+      .get("has_translation")
+      .and_then(|v| v.as_bool())
+      .unwrap_or(false)
+    {
       Ok(Some("translator".to_string()))
     } else {
       Ok(Some("compiler".to_string()))
@@ -119,4 +170,4 @@ Research Paper Content:
   fn get_node_id(&self) -> Option<String> {
     Some("insights_extractor".to_string())
   }
-}
\ No newline at end of file
+}
@@ -1,37 +1,90 @@
 //! Mind Map Generation Node - Create MarkMap mind map visualization
+//!
+//! The model drafts the outline from the insights. A reply that fails
+//! `check_mind_map`, or a node with `use_llm(false)`, gets the outline
+//! built from the summary and insights instead; `mind_map_source` says
+//! which path produced the map.
 
-use agentflow_agents::{AsyncNode, SharedState, AgentFlowError, AgentFlow};
+use crate::insights::PaperInsights;
+use crate::llm::{AnalysisLlm, ModelLlm, usage_json};
+use crate::outline::{MindMapSource, check_mind_map, outline_mind_map};
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use serde_json::{Value, json};
+use std::sync::Arc;
 
 pub struct MindMapNode {
   model: String,
+  use_llm: bool,
+  llm: Arc<dyn AnalysisLlm>,
 }
 
 impl MindMapNode {
   pub fn new(model: String) -> Self {
-    Self { model }
+    Self {
+      llm: Arc::new(ModelLlm::new(model.clone())),
+      model,
+      use_llm: true,
+    }
+  }
+
+  /// Whether the model drafts the outline; without it the outline is
+  /// built from the summary and insights
+  pub fn use_llm(mut self, use_llm: bool) -> Self {
+    self.use_llm = use_llm;
+    self
+  }
+
+  /// Draft outlines with `llm` instead of the node's model
+  pub fn with_llm(mut self, llm: Arc<dyn AnalysisLlm>) -> Self {
+    self.llm = llm;
+    self
   }
 }
 
+/// The outline built without the model, flagged with why
+fn outline_output(prep_result: &Value, reason: Option<String>) -> Value {
+  let summary = prep_result["summary"].as_str();
+  let insights = PaperInsights::from_value(&prep_result["insights"]["insights"]).ok();
+  json!({
+    "mind_map": outline_mind_map(summary, insights.as_ref()),
+    "mind_map_source": MindMapSource::Outline.as_str(),
+    "fallback_reason": reason
+  })
+}
+
 #[async_trait]
 impl AsyncNode for MindMapNode {
   async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
-    let insights = shared.get("insights").ok_or_else(|| 
-      AgentFlowError::AsyncExecutionError { message: "Insights not available".to_string() })?;
-    
+    let insights = shared.get("insights");
+    if self.use_llm && insights.is_none() {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: "Insights not available".to_string(),
+      });
+    }
+    let summary = shared
+      .get("summary")
+      .and_then(|summary| summary["summary"].as_str().map(str::to_string));
+
     Ok(json!({
       "insights": insights,
+      "summary": summary,
       "model": self.model
     }))
   }
 
   async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
+    if !self.use_llm {
+      println!("🧠 Building the mind map outline from the summary and insights...");
+      return Ok(outline_output(&prep_result, None));
+    }
+
     let insights = &prep_result["insights"];
-    
+
     println!("🧠 Generating mind map visualization...");
 
-    let mindmap_prompt = format!(r#"
+    let mindmap_prompt = format!(
+      r#"
 基于提取的研究洞察，创建一个MarkMap思维导图（使用中文）。
 重点关注主要概念、方法论、发现和关系。
 
@@ -65,36 +118,65 @@ impl AsyncNode for MindMapNode {
 
 请用中文创建具体的思维导图内容，基于以下论文洞察：
 {}
-"#, insights);
+"#,
+      insights
+    );
 
-    let response = AgentFlow::model(&self.model)
-      .prompt(&mindmap_prompt)
-      .temperature(0.4)
-      .max_tokens(1000)
-      .execute()
+    let reply = self
+      .llm
+      .complete(&mindmap_prompt, 0.4, 1000)
       .await
-      .map_err(|e| AgentFlowError::AsyncExecutionError { 
-        message: format!("Mind map generation failed: {}", e) 
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Mind map generation failed: {}", e),
       })?;
+    let usage = usage_json(std::slice::from_ref(&reply));
 
-    println!("✅ Mind map generated successfully");
-
-    Ok(json!({
-      "mind_map": response,
-      "model_used": self.model
-    }))
+    match check_mind_map(&reply.content) {
+      Ok(mind_map) => {
+        println!("✅ Mind map generated successfully");
+        Ok(json!({
+          "mind_map": mind_map,
+          "mind_map_source": MindMapSource::Llm.as_str(),
+          "model_used": self.model,
+          "usage": usage
+        }))
+      }
+      Err(reason) => {
+        println!(
+          "⚠️  The model's mind map is unusable ({}), building the outline instead",
+          reason
+        );
+        let mut output = outline_output(&prep_result, Some(reason));
+        output["model_used"] = json!(self.model);
+        output["usage"] = usage;
+        Ok(output)
+      }
+    }
   }
 
-  async fn post_async(&self, shared: &SharedState, _prep_result: Value, exec_result: Value) -> Result<Option<String>, AgentFlowError> {
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
     println!("🧠 MindMapNode: Storing mind map in shared state");
     shared.insert("mind_map".to_string(), exec_result);
-    
+
     // Check if we should generate visual mind map
-    if shared.get("has_visual_mindmap").and_then(|v| v.as_bool()).unwrap_or(false) {
+    if shared
+      .get("has_visual_mindmap")
+      .and_then(|v| v.as_bool())
+      .unwrap_or(false)
+    {
       Ok(Some("markmap_visualizer".to_string()))
     }
     // Determine next node based on translation
-    else if shared.get("has_translation").and_then(|v| v.as_bool()).unwrap_or(false) {
+    else if shared
+      .get("has_translation")
+      .and_then(|v| v.as_bool())
+      .unwrap_or(false)
+    {
       Ok(Some("translator".to_string()))
     } else {
       Ok(Some("compiler".to_string()))
@@ -104,4 +186,109 @@ impl AsyncNode for MindMapNode {
   fn get_node_id(&self) -> Option<String> {
     Some("mind_map_generator".to_string())
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::llm::LlmReply;
+  use agentflow_agents::AgentResult;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  /// Replies with `content` and counts the calls
+  struct MockLlm {
+    content: &'static str,
+    calls: AtomicUsize,
+  }
+
+  #[async_trait]
+  impl AnalysisLlm for MockLlm {
+    async fn complete(
+      &self,
+      _prompt: &str,
+      _temperature: f32,
+      _max_tokens: u32,
+    ) -> AgentResult<LlmReply> {
+      self.calls.fetch_add(1, Ordering::SeqCst);
+      Ok(LlmReply {
+        content: self.content.to_string(),
+        prompt_tokens: Some(500),
+        completion_tokens: Some(40),
+      })
+    }
+  }
+
+  fn shared_state() -> SharedState {
+    let shared = SharedState::new();
+    shared.insert(
+      "summary".to_string(),
+      json!({
+        "summary": "# 研究论文摘要\n\n## 研究方法\n- 自注意力\n\n## 主要发现\n1. BLEU 提升 2 分\n"
+      }),
+    );
+    shared.insert(
+      "insights".to_string(),
+      json!({
+        "insights": { "title": "Attention Is All You Need", "contributions": ["Transformer 架构"] }
+      }),
+    );
+    shared
+  }
+
+  async fn run(node: &MindMapNode) -> Value {
+    let prep = node.prep_async(&shared_state()).await.unwrap();
+    node.exec_async(prep).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_invalid_reply_falls_back_to_the_outline() {
+    let llm = Arc::new(MockLlm {
+      content: "```markdown\n# 论文\n- 只有一半",
+      calls: AtomicUsize::new(0),
+    });
+    let output = run(&MindMapNode::new("step-2-16k".to_string()).with_llm(llm.clone())).await;
+
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(output["mind_map_source"], "outline");
+    assert_eq!(output["fallback_reason"], "unbalanced code fences");
+    assert_eq!(
+      output["mind_map"],
+      "# Attention Is All You Need\n## 研究方法\n- 自注意力\n## 主要发现\n- BLEU 提升 2 分\n## 主要贡献\n- Transformer 架构\n"
+    );
+    // The rejected call still counts
+    assert_eq!(output["usage"]["calls"], 1);
+  }
+
+  #[tokio::test]
+  async fn test_valid_reply_is_used() {
+    let llm = Arc::new(MockLlm {
+      content: "```markdown\n# 论文\n## 方法\n- 自注意力\n- 多头注意力\n```",
+      calls: AtomicUsize::new(0),
+    });
+    let output = run(&MindMapNode::new("step-2-16k".to_string()).with_llm(llm)).await;
+
+    assert_eq!(output["mind_map_source"], "llm");
+    assert_eq!(
+      output["mind_map"],
+      "# 论文\n## 方法\n- 自注意力\n- 多头注意力"
+    );
+    assert!(output.get("fallback_reason").is_none());
+  }
+
+  #[tokio::test]
+  async fn test_outline_without_the_model() {
+    let llm = Arc::new(MockLlm {
+      content: "",
+      calls: AtomicUsize::new(0),
+    });
+    let node = MindMapNode::new("step-2-16k".to_string())
+      .with_llm(llm.clone())
+      .use_llm(false);
+    let output = run(&node).await;
+
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 0);
+    assert_eq!(output["mind_map_source"], "outline");
+    assert!(output["fallback_reason"].is_null());
+    assert!(output.get("usage").is_none());
+  }
+}
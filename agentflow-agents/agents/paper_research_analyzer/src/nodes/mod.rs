@@ -1,15 +1,19 @@
-pub mod pdf_parser;
-pub mod summarizer;
+pub mod citation_extractor;
 pub mod insights_extractor;
-pub mod mind_mapper;
 pub mod markmap_visualizer;
-pub mod translator;
+pub mod mind_mapper;
+pub mod pdf_parser;
 pub mod results_compiler;
+pub mod stage_observer;
+pub mod summarizer;
+pub mod translator;
 
-pub use pdf_parser::*;
-pub use summarizer::*;
+pub use citation_extractor::*;
 pub use insights_extractor::*;
-pub use mind_mapper::*;
 pub use markmap_visualizer::*;
+pub use mind_mapper::*;
+pub use pdf_parser::*;
+pub use results_compiler::*;
+pub use stage_observer::*;
+pub use summarizer::*;
 pub use translator::*;
-pub use results_compiler::*;
\ No newline at end of file
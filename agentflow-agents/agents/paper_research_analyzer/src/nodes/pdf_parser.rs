@@ -1,8 +1,9 @@
 //! PDF Parser Node - Upload PDF to StepFun and extract text content
 
-use agentflow_agents::{AsyncNode, SharedState, AgentFlowError, PDFContent};
+use agentflow_agents::PDFContent;
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use serde_json::{Value, json};
 use std::path::PathBuf;
 
 pub struct PDFParserNode {
@@ -13,8 +14,8 @@ pub struct PDFParserNode {
 
 impl PDFParserNode {
   pub fn new(pdf_path: PathBuf, api_key: String, cached_content: PDFContent) -> Self {
-    Self { 
-      pdf_path, 
+    Self {
+      pdf_path,
       api_key,
       cached_content: Some(cached_content),
     }
@@ -45,19 +46,32 @@ impl AsyncNode for PDFParserNode {
 
     // Fallback to API extraction (original logic would go here)
     Err(AgentFlowError::AsyncExecutionError {
-      message: "PDF content not cached and API extraction not implemented in node".to_string()
+      message: "PDF content not cached and API extraction not implemented in node".to_string(),
     })
   }
 
-  async fn post_async(&self, shared: &SharedState, _prep_result: Value, exec_result: Value) -> Result<Option<String>, AgentFlowError> {
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
     println!("📄 PDFParserNode: Storing content and metadata in shared state");
     shared.insert("pdf_content".to_string(), exec_result["content"].clone());
     shared.insert("pdf_metadata".to_string(), exec_result);
     // Return next node ID
-    Ok(Some("summarizer".to_string()))
+    if shared
+      .get("has_citations")
+      .and_then(|v| v.as_bool())
+      .unwrap_or(false)
+    {
+      Ok(Some("citation_extractor".to_string()))
+    } else {
+      Ok(Some("summarizer".to_string()))
+    }
   }
 
   fn get_node_id(&self) -> Option<String> {
     Some("pdf_parser".to_string())
   }
-}
\ No newline at end of file
+}
@@ -1,9 +1,9 @@
 //! Results Compiler Node - Compile all analysis results into final output
 
 use crate::config::AnalysisDepth;
-use agentflow_agents::{AsyncNode, SharedState, AgentFlowError};
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use serde_json::{Value, json};
 
 pub struct ResultsCompilerNode {
   analysis_depth: AnalysisDepth,
@@ -18,18 +18,22 @@ impl ResultsCompilerNode {
 #[async_trait]
 impl AsyncNode for ResultsCompilerNode {
   async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
-    let pdf_metadata = shared.get("pdf_metadata").map(|v| v.clone()).unwrap_or_else(|| json!({}));
-    let summary = shared.get("summary").map(|v| v.clone()).unwrap_or_else(|| json!({}));
-    let insights = shared.get("insights").map(|v| v.clone()).unwrap_or_else(|| json!({}));
-    let mind_map = shared.get("mind_map").map(|v| v.clone()).unwrap_or_else(|| json!({}));
-    let translation = shared.get("translation").map(|v| v.clone()).unwrap_or_else(|| json!({}));
-    
+    let pdf_metadata = shared.get("pdf_metadata").unwrap_or_else(|| json!({}));
+    let summary = shared.get("summary").unwrap_or_else(|| json!({}));
+    let insights = shared.get("insights").unwrap_or_else(|| json!({}));
+    let mind_map = shared.get("mind_map").unwrap_or_else(|| json!({}));
+    let translation = shared.get("translation").unwrap_or_else(|| json!({}));
+    let chunking = shared.get("chunking").unwrap_or(Value::Null);
+    let citations = shared.get("citations").unwrap_or(Value::Null);
+
     Ok(json!({
       "pdf_metadata": pdf_metadata,
       "summary": summary,
       "insights": insights,
       "mind_map": mind_map,
       "translation": translation,
+      "chunking": chunking,
+      "citations": citations,
       "analysis_depth": format!("{:?}", self.analysis_depth)
     }))
   }
@@ -48,6 +52,11 @@ impl AsyncNode for ResultsCompilerNode {
       }
     });
 
+    // Per-chunk token usage of a chunked analysis
+    if !prep_result["chunking"].is_null() {
+      final_result["analysis_metadata"]["chunking"] = prep_result["chunking"].clone();
+    }
+
     // Always include summary
     if let Some(summary) = prep_result["summary"]["summary"].as_str() {
       final_result["summary"] = json!(summary);
@@ -61,19 +70,34 @@ impl AsyncNode for ResultsCompilerNode {
     // Include mind map if available
     if !prep_result["mind_map"]["mind_map"].is_null() {
       final_result["mind_map"] = prep_result["mind_map"]["mind_map"].clone();
+      final_result["mind_map_source"] = prep_result["mind_map"]["mind_map_source"].clone();
+      if !prep_result["mind_map"]["fallback_reason"].is_null() {
+        final_result["analysis_metadata"]["mind_map_fallback_reason"] =
+          prep_result["mind_map"]["fallback_reason"].clone();
+      }
     }
 
     // Include translation if available
     if !prep_result["translation"]["translated_summary"].is_null() {
       final_result["translated_summary"] = prep_result["translation"]["translated_summary"].clone();
       final_result["target_language"] = prep_result["translation"]["target_language"].clone();
+      final_result["analysis_metadata"]["translation"] = json!({
+        "chunks": prep_result["translation"]["chunks"],
+        "glossary_violations": prep_result["translation"]["glossary_violations"]
+      });
+    }
+
+    // Include references if extracted
+    if !prep_result["citations"].is_null() {
+      final_result["citations"] = prep_result["citations"].clone();
     }
 
     final_result["processing_stats"] = json!({
       "summary_generated": !prep_result["summary"]["summary"].is_null(),
       "insights_extracted": !prep_result["insights"]["insights"].is_null(),
       "mind_map_created": !prep_result["mind_map"]["mind_map"].is_null(),
-      "translation_completed": !prep_result["translation"]["translated_summary"].is_null()
+      "translation_completed": !prep_result["translation"]["translated_summary"].is_null(),
+      "citations_extracted": !prep_result["citations"].is_null()
     });
 
     println!("✅ Analysis compilation completed successfully");
@@ -81,7 +105,12 @@ impl AsyncNode for ResultsCompilerNode {
     Ok(final_result)
   }
 
-  async fn post_async(&self, shared: &SharedState, _prep_result: Value, exec_result: Value) -> Result<Option<String>, AgentFlowError> {
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
     println!("📊 ResultsCompilerNode: Storing final analysis in shared state");
     shared.insert("final_analysis".to_string(), exec_result);
     // End of workflow - return None to stop execution
@@ -91,4 +120,4 @@ impl AsyncNode for ResultsCompilerNode {
   fn get_node_id(&self) -> Option<String> {
     Some("results_compiler".to_string())
   }
-}
\ No newline at end of file
+}
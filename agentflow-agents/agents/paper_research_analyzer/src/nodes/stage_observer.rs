@@ -0,0 +1,153 @@
+//! Stage Observer Node - Record how long a node's work took as a stage metric
+//!
+//! Wraps a workflow node: its `exec_async` is timed, and the model in the
+//! output's `model_used` and the calls and tokens in its `usage` go into
+//! the run's `StageMetrics` under the node's stage name.
+
+use agentflow_agents::{StageMetric, StageMetrics};
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+pub struct StageObserverNode {
+  stage: String,
+  node: Box<dyn AsyncNode>,
+  metrics: StageMetrics,
+}
+
+impl StageObserverNode {
+  pub fn new(stage: &str, node: Box<dyn AsyncNode>, metrics: StageMetrics) -> Self {
+    Self {
+      stage: stage.to_string(),
+      node,
+      metrics,
+    }
+  }
+}
+
+/// The metric of a node's output that took `duration`
+pub fn stage_metric(stage: &str, duration: Duration, exec_result: &Value) -> StageMetric {
+  let usage = &exec_result["usage"];
+  let metric = StageMetric {
+    calls: usage["calls"].as_u64().unwrap_or(0) as u32,
+    prompt_tokens: usage["prompt_tokens"].as_u64(),
+    completion_tokens: usage["completion_tokens"].as_u64(),
+    ..StageMetric::new(stage, duration)
+  };
+  match exec_result["model_used"].as_str() {
+    Some(model) => metric.with_model(model),
+    None => metric,
+  }
+}
+
+#[async_trait]
+impl AsyncNode for StageObserverNode {
+  async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
+    self.node.prep_async(shared).await
+  }
+
+  async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
+    let started = Instant::now();
+    let exec_result = self.node.exec_async(prep_result).await?;
+    self
+      .metrics
+      .record(stage_metric(&self.stage, started.elapsed(), &exec_result));
+    Ok(exec_result)
+  }
+
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
+    self.node.post_async(shared, prep_result, exec_result).await
+  }
+
+  fn get_node_id(&self) -> Option<String> {
+    self.node.get_node_id()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// Answers like the summary node, with canned usage
+  struct CannedNode;
+
+  #[async_trait]
+  impl AsyncNode for CannedNode {
+    async fn prep_async(&self, _shared: &SharedState) -> Result<Value, AgentFlowError> {
+      Ok(Value::Null)
+    }
+
+    async fn exec_async(&self, _prep_result: Value) -> Result<Value, AgentFlowError> {
+      Ok(json!({
+        "summary": "摘要",
+        "model_used": "step-2-16k",
+        "usage": { "calls": 1, "prompt_tokens": 1200, "completion_tokens": 300 }
+      }))
+    }
+
+    async fn post_async(
+      &self,
+      shared: &SharedState,
+      _prep_result: Value,
+      exec_result: Value,
+    ) -> Result<Option<String>, AgentFlowError> {
+      shared.insert("summary".to_string(), exec_result);
+      Ok(Some("compiler".to_string()))
+    }
+
+    fn get_node_id(&self) -> Option<String> {
+      Some("summary_generator".to_string())
+    }
+  }
+
+  #[tokio::test]
+  async fn test_observed_node_records_its_stage() {
+    let metrics = StageMetrics::new();
+    let node = StageObserverNode::new("summarizer", Box::new(CannedNode), metrics.clone());
+    let shared = SharedState::new();
+    let prep = node.prep_async(&shared).await.unwrap();
+    let exec = node.exec_async(prep.clone()).await.unwrap();
+    assert_eq!(
+      node
+        .post_async(&shared, prep, exec)
+        .await
+        .unwrap()
+        .as_deref(),
+      Some("compiler")
+    );
+    assert_eq!(shared.get("summary").unwrap()["summary"], "摘要");
+
+    let recorded = metrics.metrics();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].stage, "summarizer");
+    assert_eq!(recorded[0].model.as_deref(), Some("step-2-16k"));
+    assert_eq!(
+      (
+        recorded[0].calls,
+        recorded[0].prompt_tokens,
+        recorded[0].completion_tokens
+      ),
+      (1, Some(1200), Some(300))
+    );
+  }
+
+  #[test]
+  fn test_output_without_usage_records_only_its_duration() {
+    let metric = stage_metric(
+      "citation_extractor",
+      Duration::from_millis(5),
+      &json!({ "citations": null }),
+    );
+    assert_eq!(
+      metric,
+      StageMetric::new("citation_extractor", Duration::from_millis(5))
+    );
+  }
+}
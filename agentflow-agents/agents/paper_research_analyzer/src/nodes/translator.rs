@@ -1,26 +1,54 @@
 //! Translation Node - Translate summary to target language
+//!
+//! The summary goes through agentflow-agents' `TranslatorNode`, so a long
+//! summary is translated section by section and checked against the
+//! configured glossary.
 
-use agentflow_agents::{AsyncNode, SharedState, AgentFlowError, AgentFlow};
+use agentflow_agents::{ChunkUsage, TranslatorNode};
+use agentflow_core::legacy::v1::{AgentFlowError, AsyncNode, SharedState};
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+const TRANSLATION_PROMPT: &str = r#"
+Please translate this research paper summary to {{language}}.
+Maintain all technical terms and academic formatting. If technical terms don't have direct translations, keep them in English with brief explanations.
+
+Original Summary:
+{{text}}
+"#;
 
 pub struct TranslationNode {
   model: String,
   target_language: String,
+  glossary: BTreeMap<String, String>,
 }
 
 impl TranslationNode {
   pub fn new(model: String, target_language: String) -> Self {
-    Self { model, target_language }
+    Self {
+      model,
+      target_language,
+      glossary: BTreeMap::new(),
+    }
+  }
+
+  /// Terms the translation must always render the same way
+  pub fn with_glossary(mut self, glossary: BTreeMap<String, String>) -> Self {
+    self.glossary = glossary;
+    self
   }
 }
 
 #[async_trait]
 impl AsyncNode for TranslationNode {
   async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
-    let summary = shared.get("summary").ok_or_else(|| 
-      AgentFlowError::AsyncExecutionError { message: "Summary not available".to_string() })?;
-    
+    let summary = shared
+      .get("summary")
+      .ok_or_else(|| AgentFlowError::AsyncExecutionError {
+        message: "Summary not available".to_string(),
+      })?;
+
     Ok(json!({
       "summary": summary,
       "target_language": self.target_language,
@@ -29,38 +57,65 @@ impl AsyncNode for TranslationNode {
   }
 
   async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
-    let summary = prep_result["summary"]["summary"].as_str().unwrap();
-    
-    println!("🌍 Translating summary to {}...", self.target_language);
+    let summary = prep_result["summary"]["summary"].as_str().ok_or_else(|| {
+      AgentFlowError::AsyncExecutionError {
+        message: "Summary not available".to_string(),
+      }
+    })?;
 
-    let translation_prompt = format!(r#"
-Please translate this research paper summary to {}.
-Maintain all technical terms and academic formatting. If technical terms don't have direct translations, keep them in English with brief explanations.
+    println!("🌍 Translating summary to {}...", self.target_language);
 
-Original Summary:
-{}
-"#, self.target_language, summary);
-
-    let response = AgentFlow::model(&self.model)
-      .prompt(&translation_prompt)
-      .temperature(0.1)
-      .max_tokens(2500)
-      .execute()
+    let translator = TranslatorNode::new("translator", &self.model, &self.target_language)
+      .with_prompt(TRANSLATION_PROMPT)
+      .with_temperature(0.1)
+      .with_max_tokens(2500)
+      .with_glossary(self.glossary.clone());
+    let translation = translator
+      .translate(summary, &BTreeMap::new())
       .await
-      .map_err(|e| AgentFlowError::AsyncExecutionError { 
-        message: format!("Translation failed: {}", e) 
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Translation failed: {}", e),
       })?;
 
-    println!("✅ Translation completed successfully");
+    if translation.glossary_violations.is_empty() {
+      println!("✅ Translation completed successfully");
+    } else {
+      println!(
+        "⚠️  Translation completed; {} glossary term(s) translated differently",
+        translation.glossary_violations.len()
+      );
+    }
+
+    let sum = |tokens: fn(&ChunkUsage) -> Option<u32>| {
+      translation
+        .chunks
+        .iter()
+        .filter_map(tokens)
+        .map(u64::from)
+        .reduce(|a, b| a + b)
+    };
+    let usage = json!({
+      "calls": translation.chunks.len(),
+      "prompt_tokens": sum(|chunk| chunk.prompt_tokens),
+      "completion_tokens": sum(|chunk| chunk.completion_tokens)
+    });
 
     Ok(json!({
-      "translated_summary": response,
+      "translated_summary": translation.text,
       "target_language": self.target_language,
-      "model_used": self.model
+      "model_used": self.model,
+      "usage": usage,
+      "chunks": translation.chunks,
+      "glossary_violations": translation.glossary_violations
     }))
   }
 
-  async fn post_async(&self, shared: &SharedState, _prep_result: Value, exec_result: Value) -> Result<Option<String>, AgentFlowError> {
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
     println!("🌍 TranslationNode: Storing translation in shared state");
     shared.insert("translation".to_string(), exec_result);
     // Always go to compiler after translation
@@ -70,4 +125,4 @@ Original Summary:
   fn get_node_id(&self) -> Option<String> {
     Some("translator".to_string())
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,214 @@
+//! Mind map outlines built and checked without a model
+//!
+//! `MindMapNode` asks the model for a MarkMap outline (hierarchical
+//! markdown). `check_mind_map` decides whether the reply can be drawn:
+//! its code fences must be balanced, it must not be a Mermaid diagram and
+//! it needs at least `MIN_MIND_MAP_NODES` headings and list items. When it
+//! fails, or the model is not used at all, `outline_mind_map` builds the
+//! outline from the headings and lists of the summary and the insights.
+
+use crate::insights::PaperInsights;
+
+/// Headings and list items a drawable mind map has at least
+pub const MIN_MIND_MAP_NODES: usize = 4;
+
+/// Characters of a summary line kept as a leaf of the outline
+const MAX_LEAF_CHARS: usize = 80;
+
+/// Which path produced a mind map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MindMapSource {
+  /// The model's outline, which passed `check_mind_map`
+  Llm,
+  /// Built by `outline_mind_map`
+  Outline,
+}
+
+impl MindMapSource {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Llm => "llm",
+      Self::Outline => "outline",
+    }
+  }
+}
+
+/// The drawable outline in a model reply, or why there is none. A reply
+/// wrapped in one ```markdown fence is unwrapped.
+pub fn check_mind_map(reply: &str) -> Result<String, String> {
+  let fences = reply
+    .lines()
+    .filter(|line| line.trim_start().starts_with("```"))
+    .count();
+  if fences % 2 != 0 {
+    return Err("unbalanced code fences".to_string());
+  }
+
+  let mut outline = reply.trim();
+  if fences == 2 && outline.starts_with("```") && outline.ends_with("```") {
+    let (opening, body) = outline.split_once('\n').unwrap_or((outline, ""));
+    if opening.contains("mermaid") {
+      return Err("a Mermaid diagram, not a MarkMap outline".to_string());
+    }
+    outline = body.trim_end().trim_end_matches("```").trim();
+  }
+  if outline.starts_with("mindmap")
+    || outline.starts_with("graph ")
+    || outline.starts_with("flowchart")
+  {
+    return Err("a Mermaid diagram, not a MarkMap outline".to_string());
+  }
+
+  let nodes = outline.lines().filter(|line| is_node(line)).count();
+  if nodes < MIN_MIND_MAP_NODES {
+    return Err(format!(
+      "{} nodes, fewer than {}",
+      nodes, MIN_MIND_MAP_NODES
+    ));
+  }
+  Ok(outline.to_string())
+}
+
+/// Whether `line` is a heading or a list item
+fn is_node(line: &str) -> bool {
+  let line = line.trim_start();
+  heading_level(line).is_some() || list_item(line).is_some()
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+  let level = line.chars().take_while(|c| *c == '#').count();
+  (level > 0 && line[level..].starts_with(' ')).then_some(level)
+}
+
+fn list_item(line: &str) -> Option<&str> {
+  let item = line
+    .strip_prefix("- ")
+    .or_else(|| line.strip_prefix("* "))
+    .or_else(|| line.strip_prefix("+ "))
+    .or_else(|| {
+      let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+      (digits > 0)
+        .then(|| line[digits..].strip_prefix(". "))
+        .flatten()
+    })?;
+  Some(item.trim())
+}
+
+/// A MarkMap outline of the paper from the heading and list structure of
+/// its `summary`, with the insights' lists as further branches. The root
+/// is the insights' title, else the summary's first top-level heading.
+pub fn outline_mind_map(summary: Option<&str>, insights: Option<&PaperInsights>) -> String {
+  let summary = summary.unwrap_or("");
+  let summary_title = summary
+    .lines()
+    .find(|line| heading_level(line) == Some(1))
+    .map(|line| line[1..].trim().to_string());
+  let title = insights
+    .map(|insights| insights.title.trim().to_string())
+    .filter(|title| !title.is_empty())
+    .or(summary_title)
+    .unwrap_or_else(|| "研究论文".to_string());
+
+  let mut outline = vec![format!("# {}", title)];
+  let mut in_fence = false;
+  for line in summary.lines() {
+    let line = line.trim();
+    if line.starts_with("```") {
+      in_fence = !in_fence;
+      continue;
+    }
+    if in_fence || line.is_empty() {
+      continue;
+    }
+    match heading_level(line) {
+      Some(1) => {}
+      // Deeper headings are branches under the root
+      Some(level) => outline.push(format!(
+        "{} {}",
+        "#".repeat(level.min(4)),
+        line[level..].trim()
+      )),
+      None => {
+        let text = list_item(line).unwrap_or(line);
+        outline.push(format!("- {}", leaf(text)));
+      }
+    }
+  }
+
+  if let Some(insights) = insights.filter(|insights| insights.raw.is_none()) {
+    let branches = [
+      ("研究方法", &insights.methodology),
+      ("主要贡献", &insights.contributions),
+      ("数据集", &insights.datasets),
+      ("评估指标", &insights.metrics),
+      ("未来方向", &insights.future_work),
+    ];
+    for (branch, items) in branches {
+      if items.is_empty() {
+        continue;
+      }
+      outline.push(format!("## {}", branch));
+      outline.extend(items.iter().map(|item| format!("- {}", leaf(item))));
+    }
+  }
+  outline.join("\n") + "\n"
+}
+
+/// `text` cut to a leaf's length, at a character boundary
+fn leaf(text: &str) -> String {
+  let text = text.trim();
+  match text.char_indices().nth(MAX_LEAF_CHARS) {
+    Some((end, _)) => format!("{}…", &text[..end]),
+    None => text.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fenced_outline_is_unwrapped() {
+    let reply = "```markdown\n# 论文\n## 方法\n- 注意力\n- 多头\n```";
+    assert_eq!(
+      check_mind_map(reply).unwrap(),
+      "# 论文\n## 方法\n- 注意力\n- 多头"
+    );
+  }
+
+  #[test]
+  fn test_unusable_replies_are_rejected() {
+    assert_eq!(
+      check_mind_map("```markdown\n# 论文\n## 方法\n- 注意力\n- 多头").unwrap_err(),
+      "unbalanced code fences"
+    );
+    assert!(
+      check_mind_map("```mermaid\nmindmap\n  root((论文))\n```")
+        .unwrap_err()
+        .contains("Mermaid")
+    );
+    assert_eq!(
+      check_mind_map("抱歉，我无法生成思维导图。\n# 论文").unwrap_err(),
+      format!("1 nodes, fewer than {}", MIN_MIND_MAP_NODES)
+    );
+  }
+
+  #[test]
+  fn test_outline_follows_the_summary_and_insights() {
+    let summary = "# 研究论文摘要\n\n## 研究问题\n序列建模依赖循环网络。\n\n## 主要发现\n1. BLEU 提升 2 分\n- 训练更快\n";
+    let insights = PaperInsights {
+      title: "Attention Is All You Need".to_string(),
+      datasets: vec!["WMT 2014".to_string()],
+      ..Default::default()
+    };
+    let outline = outline_mind_map(Some(summary), Some(&insights));
+    assert_eq!(
+      outline,
+      "# Attention Is All You Need\n## 研究问题\n- 序列建模依赖循环网络。\n## 主要发现\n- BLEU 提升 2 分\n- 训练更快\n## 数据集\n- WMT 2014\n"
+    );
+    assert!(check_mind_map(&outline).is_ok());
+
+    let untitled = outline_mind_map(Some(summary), None);
+    assert!(untitled.starts_with("# 研究论文摘要\n## 研究问题"));
+  }
+}
@@ -0,0 +1,772 @@
+//! The standard command line of agent binaries.
+//!
+//! [`run_agent_cli`] is the whole `main` of an agent binary:
+//!
+//! ```text
+//! <name> run [INPUT]... [--config FILE] [--set KEY=VALUE]... [AGENT FLAGS]
+//! <name> config show [--config FILE] [--set KEY=VALUE]...
+//! <name> config create [--output FILE] [--preset NAME] [--force]
+//! <name> version
+//! ```
+//!
+//! The configuration starts from [`AgentApplication::default_config`] and
+//! each layer overrides the one before it:
+//!
+//! 1. the config file (YAML or JSON): `--config`, else `$<PREFIX>_CONFIG`,
+//!    else `~/.agentflow/agents/<name>.yml` when it exists;
+//! 2. `<PREFIX>_<FIELD>` environment variables, one per top-level field;
+//! 3. `--set path.to.field=value` flags, then the agent's own
+//!    [`AgentApplication::run_args`].
+//!
+//! `<PREFIX>` is the agent name uppercased with `-` as `_`. Override values
+//! are read as YAML, so `true`, `0.3` and `[en, zh]` keep their types; a
+//! field that is a string takes the text as written.
+//!
+//! Logs go to stderr at `info` (`-v` debug, `-vv` trace, `-q` warnings
+//! only; `RUST_LOG` wins). `run` prints its result to stdout: a string as
+//! written, any other non-null value as pretty JSON. The exit code is 0 on
+//! success, [`EXIT_FAILURE`] when the agent fails and [`EXIT_USAGE`] for
+//! bad arguments or configuration.
+
+use crate::{AgentApplication, AgentConfig, AgentResult};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Exit code when initializing, running or shutting down the agent fails.
+pub const EXIT_FAILURE: u8 = 1;
+
+/// Exit code for invalid arguments or configuration.
+pub const EXIT_USAGE: u8 = 2;
+
+/// The environment the command line reads: variables and the home
+/// directory. Tests build one instead of touching the process's.
+#[derive(Debug, Clone, Default)]
+pub struct CliEnv {
+  vars: HashMap<String, String>,
+  home: Option<PathBuf>,
+}
+
+impl CliEnv {
+  /// The process's environment.
+  pub fn from_process() -> Self {
+    Self {
+      vars: std::env::vars().collect(),
+      home: dirs::home_dir(),
+    }
+  }
+
+  pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.vars.insert(name.into(), value.into());
+    self
+  }
+
+  pub fn with_home(mut self, home: impl Into<PathBuf>) -> Self {
+    self.home = Some(home.into());
+    self
+  }
+
+  pub fn var(&self, name: &str) -> Option<&str> {
+    self.vars.get(name).map(String::as_str)
+  }
+}
+
+/// The prefix of an agent's environment variables.
+pub fn env_prefix(name: &str) -> String {
+  name.to_uppercase().replace('-', "_")
+}
+
+/// `~/.agentflow/agents/<name>.yml`.
+pub fn default_config_path<A: AgentApplication>(env: &CliEnv) -> Option<PathBuf> {
+  env.home.as_ref().map(|home| {
+    home
+      .join(".agentflow")
+      .join("agents")
+      .join(format!("{}.yml", A::NAME))
+  })
+}
+
+/// A configuration resolved through every layer.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig<C> {
+  pub config: C,
+  /// The config file read, if any.
+  pub file: Option<PathBuf>,
+}
+
+/// Resolve `A`'s configuration: the config file (`file`, or the
+/// environment's, or the default path), the environment overrides, the
+/// `--set` overrides in `sets`, then the agent's `run_args` in
+/// `run_matches`. The result is validated.
+pub fn load_agent_config<A: AgentApplication>(
+  file: Option<&Path>,
+  sets: &[String],
+  env: &CliEnv,
+  run_matches: Option<&ArgMatches>,
+) -> AgentResult<LoadedConfig<A::Config>> {
+  let prefix = env_prefix(A::NAME);
+  let mut value = serde_json::to_value(A::default_config())?;
+  if !value.is_object() {
+    return Err(format!("{} config must serialize to a map", A::NAME).into());
+  }
+
+  let file = match file {
+    Some(file) => Some(file.to_path_buf()),
+    None => match env.var(&format!("{prefix}_CONFIG")) {
+      Some(file) => Some(PathBuf::from(file)),
+      None => default_config_path::<A>(env).filter(|path| path.exists()),
+    },
+  };
+  if let Some(path) = &file {
+    let content = std::fs::read_to_string(path)
+      .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+    let overlay: Value = serde_yaml::from_str(&content)
+      .map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+    match overlay {
+      Value::Object(_) => merge(&mut value, overlay),
+      Value::Null => {}
+      _ => return Err(format!("Config {} must be a map", path.display()).into()),
+    }
+  }
+
+  let fields: Vec<String> = value
+    .as_object()
+    .map(|map| map.keys().cloned().collect())
+    .unwrap_or_default();
+  for field in fields {
+    if let Some(raw) = env.var(&format!("{}_{}", prefix, field.to_uppercase())) {
+      set_path(&mut value, &field, raw)?;
+    }
+  }
+
+  for set in sets {
+    let (path, raw) = set
+      .split_once('=')
+      .ok_or_else(|| format!("--set expects KEY=VALUE, got '{set}'"))?;
+    set_path(&mut value, path.trim(), raw)?;
+  }
+
+  let mut config: A::Config =
+    serde_json::from_value(value).map_err(|e| format!("Invalid configuration: {e}"))?;
+  if let Some(matches) = run_matches {
+    A::apply_run_args(&mut config, matches)?;
+  }
+  config.validate()?;
+  Ok(LoadedConfig { config, file })
+}
+
+/// Overlay `overlay` on `base`, map by map.
+fn merge(base: &mut Value, overlay: Value) {
+  match (base, overlay) {
+    (Value::Object(base), Value::Object(overlay)) => {
+      for (key, value) in overlay {
+        merge(base.entry(key).or_insert(Value::Null), value);
+      }
+    }
+    (base, overlay) => *base = overlay,
+  }
+}
+
+/// Set the field at dotted `path` to `raw`, read as YAML unless the field
+/// holds a string.
+fn set_path(value: &mut Value, path: &str, raw: &str) -> AgentResult<()> {
+  let mut slot = value;
+  for key in path.split('.') {
+    if key.is_empty() {
+      return Err(format!("Invalid config path '{path}'").into());
+    }
+    if slot.is_null() {
+      *slot = Value::Object(Default::default());
+    }
+    slot = match slot {
+      Value::Object(map) => map.entry(key.to_string()).or_insert(Value::Null),
+      _ => {
+        return Err(format!("Config path '{path}' runs through a value that is not a map").into());
+      }
+    };
+  }
+  *slot = match slot {
+    Value::String(_) => Value::String(raw.to_string()),
+    _ => serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+  };
+  Ok(())
+}
+
+/// The command line of `A`.
+pub fn agent_command<A: AgentApplication>() -> Command {
+  let config = Arg::new("config")
+    .short('c')
+    .long("config")
+    .value_name("FILE")
+    .help(format!(
+      "Config file [default: ${}_CONFIG, then ~/.agentflow/agents/{}.yml]",
+      env_prefix(A::NAME),
+      A::NAME
+    ));
+  let set = Arg::new("set")
+    .long("set")
+    .value_name("KEY=VALUE")
+    .action(ArgAction::Append)
+    .help("Override a config field by its dotted path, e.g. --set temperature=0.2");
+
+  let mut create = Command::new("create")
+    .about("Write a config file")
+    .arg(
+      Arg::new("output")
+        .short('o')
+        .long("output")
+        .value_name("FILE")
+        .help(format!(
+          "Where to write it [default: ~/.agentflow/agents/{}.yml]",
+          A::NAME
+        )),
+    )
+    .arg(
+      Arg::new("force")
+        .long("force")
+        .action(ArgAction::SetTrue)
+        .help("Overwrite an existing file"),
+    );
+  let presets: Vec<&'static str> = A::config_presets()
+    .into_iter()
+    .map(|(name, _)| name)
+    .collect();
+  if !presets.is_empty() {
+    create = create.arg(
+      Arg::new("preset")
+        .short('p')
+        .long("preset")
+        .value_parser(clap::builder::PossibleValuesParser::new(presets))
+        .help("Start from a named configuration instead of the defaults"),
+    );
+  }
+
+  Command::new(A::NAME)
+    .version(A::VERSION)
+    .about(A::ABOUT)
+    .subcommand_required(true)
+    .arg_required_else_help(true)
+    .arg(
+      Arg::new("verbose")
+        .short('v')
+        .long("verbose")
+        .action(ArgAction::Count)
+        .global(true)
+        .help("Log more: -v debug, -vv trace"),
+    )
+    .arg(
+      Arg::new("quiet")
+        .short('q')
+        .long("quiet")
+        .action(ArgAction::SetTrue)
+        .global(true)
+        .help("Log warnings and errors only"),
+    )
+    .subcommand(
+      Command::new("run")
+        .about("Run the agent")
+        .arg(
+          Arg::new("inputs")
+            .value_name("INPUT")
+            .num_args(0..)
+            .action(ArgAction::Append),
+        )
+        .arg(config.clone())
+        .arg(set.clone())
+        .args(A::run_args()),
+    )
+    .subcommand(
+      Command::new("config")
+        .about("Show or create the configuration")
+        .subcommand_required(true)
+        .subcommand(
+          Command::new("show")
+            .about("Print the configuration `run` would use")
+            .arg(config)
+            .arg(set),
+        )
+        .subcommand(create),
+    )
+    .subcommand(Command::new("version").about("Print the version"))
+}
+
+/// Run `A` from the process's command line and environment; an agent
+/// binary's `main` is
+///
+/// ```ignore
+/// #[tokio::main]
+/// async fn main() -> std::process::ExitCode {
+///   agentflow_agents::run_agent_cli::<MyAgent>().await
+/// }
+/// ```
+pub async fn run_agent_cli<A: AgentApplication>() -> ExitCode {
+  let env = CliEnv::from_process();
+  let code = run_agent_cli_with::<A, _, _>(std::env::args_os(), &env, &mut std::io::stdout()).await;
+  ExitCode::from(code)
+}
+
+/// Run `A` from `args` (the first being the binary name) in `env`, writing
+/// results to `out`. Returns the exit code.
+pub async fn run_agent_cli_with<A, I, T>(args: I, env: &CliEnv, out: &mut dyn Write) -> u8
+where
+  A: AgentApplication,
+  I: IntoIterator<Item = T>,
+  T: Into<OsString> + Clone,
+{
+  let matches = match agent_command::<A>().try_get_matches_from(args) {
+    Ok(matches) => matches,
+    Err(e) => {
+      use clap::error::ErrorKind;
+      return match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => {
+          let _ = write!(out, "{e}");
+          0
+        }
+        _ => {
+          eprint!("{e}");
+          EXIT_USAGE
+        }
+      };
+    }
+  };
+  init_logging(&matches, env);
+
+  match matches.subcommand() {
+    Some(("run", matches)) => run_command::<A>(matches, env, out).await,
+    Some(("config", matches)) => match matches.subcommand() {
+      Some(("show", matches)) => config_show::<A>(matches, env, out),
+      Some(("create", matches)) => config_create::<A>(matches, env, out),
+      _ => EXIT_USAGE,
+    },
+    Some(("version", _)) => match writeln!(out, "{} {}", A::NAME, A::VERSION) {
+      Ok(()) => 0,
+      Err(e) => fail(EXIT_FAILURE, "Failed to write the version", e),
+    },
+    _ => EXIT_USAGE,
+  }
+}
+
+fn init_logging(matches: &ArgMatches, env: &CliEnv) {
+  use tracing_subscriber::EnvFilter;
+
+  let level = if matches.get_flag("quiet") {
+    "warn"
+  } else {
+    match matches.get_count("verbose") {
+      0 => "info",
+      1 => "debug",
+      _ => "trace",
+    }
+  };
+  let filter = env
+    .var("RUST_LOG")
+    .and_then(|filter| EnvFilter::try_new(filter).ok())
+    .unwrap_or_else(|| EnvFilter::new(level));
+  // A subscriber installed earlier, as in tests, stays.
+  let _ = tracing_subscriber::fmt()
+    .with_env_filter(filter)
+    .with_writer(std::io::stderr)
+    .with_target(false)
+    .try_init();
+}
+
+fn fail(code: u8, context: &str, error: impl Display) -> u8 {
+  eprintln!("Error: {context}: {error}");
+  code
+}
+
+fn load<A: AgentApplication>(
+  matches: &ArgMatches,
+  env: &CliEnv,
+  run_matches: Option<&ArgMatches>,
+) -> AgentResult<LoadedConfig<A::Config>> {
+  let file = matches.get_one::<String>("config").map(Path::new);
+  let sets: Vec<String> = matches
+    .get_many::<String>("set")
+    .into_iter()
+    .flatten()
+    .cloned()
+    .collect();
+  load_agent_config::<A>(file, &sets, env, run_matches)
+}
+
+async fn run_command<A: AgentApplication>(
+  matches: &ArgMatches,
+  env: &CliEnv,
+  out: &mut dyn Write,
+) -> u8 {
+  let config = match load::<A>(matches, env, Some(matches)) {
+    Ok(loaded) => loaded.config,
+    Err(e) => return fail(EXIT_USAGE, "Configuration error", e),
+  };
+  let agent = match A::initialize(config).await {
+    Ok(agent) => agent,
+    Err(e) => return fail(EXIT_FAILURE, "Initialization failed", e),
+  };
+
+  let inputs: Vec<String> = matches
+    .get_many::<String>("inputs")
+    .into_iter()
+    .flatten()
+    .cloned()
+    .collect();
+  tracing::debug!(agent = A::NAME, inputs = inputs.len(), "running agent");
+  let outcome = agent.run(inputs).await;
+  let shutdown = agent.shutdown().await;
+
+  let value = match (outcome, shutdown) {
+    (Ok(value), Ok(())) => value,
+    (Err(e), shutdown) => {
+      if let Err(shutdown) = shutdown {
+        tracing::warn!("shutdown after a failed run also failed: {shutdown}");
+      }
+      return fail(EXIT_FAILURE, "Run failed", e);
+    }
+    (Ok(_), Err(e)) => return fail(EXIT_FAILURE, "Shutdown failed", e),
+  };
+
+  let written = match &value {
+    Value::Null => Ok(()),
+    Value::String(text) => writeln!(out, "{text}"),
+    value => match serde_json::to_string_pretty(value) {
+      Ok(json) => writeln!(out, "{json}"),
+      Err(e) => return fail(EXIT_FAILURE, "Failed to serialize the result", e),
+    },
+  };
+  match written {
+    Ok(()) => 0,
+    Err(e) => fail(EXIT_FAILURE, "Failed to write the result", e),
+  }
+}
+
+fn config_show<A: AgentApplication>(matches: &ArgMatches, env: &CliEnv, out: &mut dyn Write) -> u8 {
+  let loaded = match load::<A>(matches, env, None) {
+    Ok(loaded) => loaded,
+    Err(e) => return fail(EXIT_USAGE, "Configuration error", e),
+  };
+  let yaml = match serde_yaml::to_string(&loaded.config) {
+    Ok(yaml) => yaml,
+    Err(e) => return fail(EXIT_FAILURE, "Failed to serialize the configuration", e),
+  };
+  let source = match &loaded.file {
+    Some(file) => format!("# {}", file.display()),
+    None => "# no config file; defaults".to_string(),
+  };
+  match write!(out, "{source}\n{yaml}") {
+    Ok(()) => 0,
+    Err(e) => fail(EXIT_FAILURE, "Failed to write the configuration", e),
+  }
+}
+
+fn config_create<A: AgentApplication>(
+  matches: &ArgMatches,
+  env: &CliEnv,
+  out: &mut dyn Write,
+) -> u8 {
+  let Some(path) = matches
+    .get_one::<String>("output")
+    .map(PathBuf::from)
+    .or_else(|| default_config_path::<A>(env))
+  else {
+    return fail(
+      EXIT_USAGE,
+      "Configuration error",
+      "no home directory; pass --output",
+    );
+  };
+  if path.exists() && !matches.get_flag("force") {
+    return fail(
+      EXIT_USAGE,
+      "Configuration error",
+      format!(
+        "{} already exists; pass --force to overwrite it",
+        path.display()
+      ),
+    );
+  }
+
+  let preset = matches.try_get_one::<String>("preset").ok().flatten();
+  let config = match preset {
+    Some(name) => A::config_presets()
+      .into_iter()
+      .find(|(preset, _)| preset == name)
+      .map(|(_, config)| config)
+      .unwrap_or_else(A::default_config),
+    None => A::default_config(),
+  };
+  if let Err(e) = config.validate() {
+    return fail(EXIT_USAGE, "Configuration error", e);
+  }
+
+  let written = serde_yaml::to_string(&config)
+    .map_err(|e| e.to_string())
+    .and_then(|yaml| {
+      if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+      }
+      std::fs::write(&path, yaml).map_err(|e| e.to_string())
+    });
+  if let Err(e) = written {
+    return fail(
+      EXIT_FAILURE,
+      &format!("Failed to write {}", path.display()),
+      e,
+    );
+  }
+  match writeln!(out, "Wrote {}", path.display()) {
+    Ok(()) => 0,
+    Err(e) => fail(EXIT_FAILURE, "Failed to write the result", e),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use async_trait::async_trait;
+  use serde::{Deserialize, Serialize};
+
+  #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+  #[serde(default)]
+  struct EchoConfig {
+    greeting: String,
+    repeat: usize,
+    shout: bool,
+    style: EchoStyle,
+    /// Written on shutdown, so tests can tell it ran
+    shutdown_marker: Option<PathBuf>,
+  }
+
+  #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+  #[serde(default)]
+  struct EchoStyle {
+    prefix: String,
+  }
+
+  impl Default for EchoConfig {
+    fn default() -> Self {
+      Self {
+        greeting: "hello".to_string(),
+        repeat: 1,
+        shout: false,
+        style: EchoStyle::default(),
+        shutdown_marker: None,
+      }
+    }
+  }
+
+  impl AgentConfig for EchoConfig {
+    fn validate(&self) -> AgentResult<()> {
+      if self.repeat == 0 {
+        return Err("repeat must be at least 1".into());
+      }
+      Ok(())
+    }
+  }
+
+  struct EchoAgent {
+    config: EchoConfig,
+  }
+
+  #[async_trait]
+  impl AgentApplication for EchoAgent {
+    type Config = EchoConfig;
+    type Result = String;
+
+    const NAME: &'static str = "echo-agent";
+    const ABOUT: &'static str = "Greets its inputs";
+
+    async fn initialize(config: Self::Config) -> AgentResult<Self> {
+      Ok(Self { config })
+    }
+
+    async fn execute(&self, input: &str) -> AgentResult<Self::Result> {
+      if input == "fail" {
+        return Err("cannot greet 'fail'".into());
+      }
+      let greeting = format!(
+        "{}{} {}",
+        self.config.style.prefix, self.config.greeting, input
+      );
+      let greeting = if self.config.shout {
+        greeting.to_uppercase()
+      } else {
+        greeting
+      };
+      Ok(vec![greeting; self.config.repeat].join(" "))
+    }
+
+    async fn shutdown(&self) -> AgentResult<()> {
+      if let Some(marker) = &self.config.shutdown_marker {
+        std::fs::write(marker, "shut down")?;
+      }
+      Ok(())
+    }
+
+    fn config_presets() -> Vec<(&'static str, Self::Config)> {
+      vec![(
+        "loud",
+        EchoConfig {
+          shout: true,
+          ..EchoConfig::default()
+        },
+      )]
+    }
+
+    fn run_args() -> Vec<Arg> {
+      vec![Arg::new("shout").long("shout").action(ArgAction::SetTrue)]
+    }
+
+    fn apply_run_args(config: &mut Self::Config, matches: &ArgMatches) -> AgentResult<()> {
+      if matches.get_flag("shout") {
+        config.shout = true;
+      }
+      Ok(())
+    }
+  }
+
+  async fn cli(args: &[&str], env: &CliEnv) -> (u8, String) {
+    let mut out = Vec::new();
+    let args = std::iter::once("echo-agent").chain(args.iter().copied());
+    let code = run_agent_cli_with::<EchoAgent, _, _>(args, env, &mut out).await;
+    (code, String::from_utf8(out).unwrap())
+  }
+
+  fn home() -> (tempfile::TempDir, CliEnv) {
+    let home = tempfile::tempdir().unwrap();
+    let env = CliEnv::default().with_home(home.path());
+    (home, env)
+  }
+
+  #[tokio::test]
+  async fn run_layers_file_then_env_then_flags() {
+    let (home, env) = home();
+    let (code, out) = cli(&["run", "world"], &env).await;
+    assert_eq!((code, out.as_str()), (0, "hello world\n"));
+
+    let config_dir = home.path().join(".agentflow/agents");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+      config_dir.join("echo-agent.yml"),
+      "greeting: hi\nrepeat: 3\nstyle:\n  prefix: '>'\n",
+    )
+    .unwrap();
+    let (code, out) = cli(&["run", "world"], &env).await;
+    assert_eq!((code, out.as_str()), (0, ">hi world >hi world >hi world\n"));
+
+    let env = env
+      .with_var("ECHO_AGENT_REPEAT", "2")
+      .with_var("ECHO_AGENT_GREETING", "hey");
+    let (_, out) = cli(&["run", "world"], &env).await;
+    assert_eq!(out, ">hey world >hey world\n");
+
+    // A string field keeps the text even when it reads as a number.
+    let (_, out) = cli(
+      &[
+        "run",
+        "world",
+        "--set",
+        "style.prefix=*",
+        "--set",
+        "greeting=42",
+        "--shout",
+      ],
+      &env,
+    )
+    .await;
+    assert_eq!(out, "*42 WORLD *42 WORLD\n");
+
+    let (code, out) = cli(&["run", "a", "b", "--set", "repeat=1"], &env).await;
+    assert_eq!(code, 0);
+    assert_eq!(
+      serde_json::from_str::<Value>(&out).unwrap(),
+      serde_json::json!([">hey a", ">hey b"])
+    );
+  }
+
+  #[tokio::test]
+  async fn config_create_and_show() {
+    let (home, env) = home();
+    let path = home.path().join("loud.yml");
+    let path_arg = path.to_str().unwrap();
+
+    let (code, out) = cli(
+      &["config", "create", "--preset", "loud", "-o", path_arg],
+      &env,
+    )
+    .await;
+    assert_eq!(code, 0);
+    assert_eq!(out, format!("Wrote {}\n", path.display()));
+    let (code, _) = cli(
+      &["config", "create", "--preset", "loud", "-o", path_arg],
+      &env,
+    )
+    .await;
+    assert_eq!(code, EXIT_USAGE);
+    let (code, _) = cli(&["config", "create", "-o", path_arg, "--force"], &env).await;
+    assert_eq!(code, 0);
+    let (code, _) = cli(
+      &["config", "create", "--preset", "quiet", "-o", path_arg],
+      &env,
+    )
+    .await;
+    assert_eq!(code, EXIT_USAGE);
+
+    let env = env.with_var("ECHO_AGENT_CONFIG", path_arg);
+    let (code, out) = cli(&["config", "show", "--set", "shout=true"], &env).await;
+    assert_eq!(code, 0);
+    assert!(out.starts_with(&format!("# {}\n", path.display())));
+    let shown: EchoConfig = serde_yaml::from_str(&out).unwrap();
+    assert_eq!(
+      shown,
+      EchoConfig {
+        shout: true,
+        ..EchoConfig::default()
+      }
+    );
+
+    // The default path is created when no --output is given.
+    let (code, _) = cli(
+      &["config", "create"],
+      &CliEnv::default().with_home(home.path()),
+    )
+    .await;
+    assert_eq!(code, 0);
+    assert!(
+      home
+        .path()
+        .join(".agentflow/agents/echo-agent.yml")
+        .exists()
+    );
+  }
+
+  #[tokio::test]
+  async fn exit_codes() {
+    let (home, env) = home();
+    let marker = home.path().join("shutdown");
+    let marker_set = format!("shutdown_marker={}", marker.display());
+
+    let (code, out) = cli(&["run", "fail", "--set", &marker_set], &env).await;
+    assert_eq!((code, out.as_str()), (EXIT_FAILURE, ""));
+    assert!(marker.exists(), "shutdown runs after a failed run");
+
+    let (code, _) = cli(&["run", "world", "--set", "repeat=0"], &env).await;
+    assert_eq!(code, EXIT_USAGE);
+    let (code, _) = cli(&["run", "--set", "repeat"], &env).await;
+    assert_eq!(code, EXIT_USAGE);
+    let (code, _) = cli(&["run", "--config", "/nonexistent/echo.yml"], &env).await;
+    assert_eq!(code, EXIT_USAGE);
+    let (code, _) = cli(&["dance"], &env).await;
+    assert_eq!(code, EXIT_USAGE);
+
+    let (code, out) = cli(&["version"], &env).await;
+    assert_eq!((code, out.as_str()), (0, "echo-agent 0.1.0\n"));
+    let (code, out) = cli(&["--help"], &env).await;
+    assert_eq!(code, 0);
+    assert!(out.contains("Greets its inputs") && out.contains("run") && out.contains("config"));
+  }
+}
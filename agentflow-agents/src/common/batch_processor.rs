@@ -1,127 +1,696 @@
 //! Batch processing utilities for agents
+//!
+//! [`BatchProcessor`] runs an async closure over a list of items, at most
+//! `concurrency` at once, and returns the results in the items' order. On
+//! top of that it can
+//!
+//! - retry a failed item under a [`RetryPolicy`], the same policies
+//!   workflow nodes use;
+//! - report every item starting, retrying, finishing or failing;
+//! - record each finished item in a checkpoint file as soon as it
+//!   finishes, so an interrupted batch resumes with only the rest;
+//! - slow down when too many recent attempts fail ([`AdaptiveThrottle`]),
+//!   as they do when a provider starts rate limiting.
 
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use agentflow_async_util::retry::RetryContext;
+use agentflow_graph::AgentFlowError;
+use futures::stream::{self, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-/// Batch processor with concurrency control
-pub struct BatchProcessor {
+// The retry vocabulary, so agents configure batches without another dependency
+pub use agentflow_async_util::retry::{ErrorPattern, RetryPolicy, RetryStrategy};
+
+/// An item of the batch starting, retrying, finishing or failing, or the
+/// batch slowing down. Counts cover the items processed in this run, not
+/// those resumed from the checkpoint.
+#[derive(Debug)]
+pub enum BatchEvent<'a, I> {
+  Started {
+    item: &'a I,
+    started: usize,
+    total: usize,
+  },
+  /// `attempt` failed with `error`; the next starts after `delay`
+  Retrying {
+    item: &'a I,
+    attempt: u32,
+    delay: Duration,
+    error: String,
+  },
+  Finished {
+    item: &'a I,
+    completed: usize,
+    total: usize,
+  },
+  Failed {
+    item: &'a I,
+    error: String,
+    completed: usize,
+    total: usize,
+  },
+  /// `failure_rate` of the recent attempts failed; every attempt now waits
+  /// `delay` before it starts
+  Throttled { delay: Duration, failure_rate: f64 },
+}
+
+/// Receives the batch's events
+pub type BatchEventCallback<I> = Arc<dyn Fn(&BatchEvent<'_, I>) + Send + Sync>;
+
+/// Names an item in the checkpoint file. Equal keys are the same work: a
+/// key should change with anything that changes the item's result.
+pub type CheckpointKey<I> = Arc<dyn Fn(&I) -> String + Send + Sync>;
+
+/// Slows a batch down while too many recent attempts fail.
+///
+/// Once `failure_threshold` of the last `window` attempts (and at least
+/// `min_samples` of them) have failed, every attempt waits `initial_delay`
+/// before it starts; each further trigger doubles the wait, up to
+/// `max_delay`. Every success halves it, until it drops below
+/// `initial_delay` and the batch runs at full speed again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveThrottle {
+  pub window: usize,
+  pub min_samples: usize,
+  pub failure_threshold: f64,
+  pub initial_delay: Duration,
+  pub max_delay: Duration,
+}
+
+impl Default for AdaptiveThrottle {
+  fn default() -> Self {
+    Self {
+      window: 10,
+      min_samples: 4,
+      failure_threshold: 0.5,
+      initial_delay: Duration::from_secs(1),
+      max_delay: Duration::from_secs(60),
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+  recent: VecDeque<bool>,
+  delay: Duration,
+}
+
+impl ThrottleState {
+  /// Record an attempt; returns the new delay and the failure rate when
+  /// this attempt made the batch back off
+  fn record(&mut self, throttle: &AdaptiveThrottle, failed: bool) -> Option<(Duration, f64)> {
+    self.recent.push_back(failed);
+    while self.recent.len() > throttle.window.max(1) {
+      self.recent.pop_front();
+    }
+
+    let failures = self.recent.iter().filter(|failed| **failed).count();
+    let failure_rate = failures as f64 / self.recent.len() as f64;
+    if failed
+      && self.recent.len() >= throttle.min_samples
+      && failure_rate >= throttle.failure_threshold
+    {
+      self.delay = if self.delay.is_zero() {
+        throttle.initial_delay
+      } else {
+        (self.delay * 2).min(throttle.max_delay)
+      };
+      // The next back-off needs failures of its own, not these again.
+      self.recent.clear();
+      return Some((self.delay, failure_rate));
+    }
+    if !failed && !self.delay.is_zero() {
+      self.delay /= 2;
+      if self.delay < throttle.initial_delay {
+        self.delay = Duration::ZERO;
+      }
+    }
+    None
+  }
+}
+
+/// A finished item recorded in the checkpoint file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointEntry<O> {
+  pub completed_at: String,
+  pub output: O,
+}
+
+/// The checkpoint file: finished items by key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCheckpoint<O> {
+  pub items: BTreeMap<String, CheckpointEntry<O>>,
+}
+
+impl<O> Default for BatchCheckpoint<O> {
+  fn default() -> Self {
+    Self {
+      items: BTreeMap::new(),
+    }
+  }
+}
+
+impl<O: Serialize + DeserializeOwned> BatchCheckpoint<O> {
+  /// The checkpoint at `path`; empty when there is none yet
+  pub fn load(path: &Path) -> crate::AgentResult<Self> {
+    match std::fs::read_to_string(path) {
+      Ok(content) => serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid batch checkpoint {}: {}", path.display(), e).into()),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(e) => Err(format!("Failed to read batch checkpoint {}: {}", path.display(), e).into()),
+    }
+  }
+
+  /// Write the checkpoint through a temporary file, so an interrupted
+  /// write leaves the previous one intact
+  pub fn save(&self, path: &Path) -> crate::AgentResult<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+  }
+}
+
+/// The results of a batch, in the order of its items
+#[derive(Debug, Clone)]
+pub struct BatchReport<I, O> {
+  pub results: Vec<(I, Result<O, String>)>,
+  /// Items whose output came from the checkpoint
+  pub resumed: usize,
+  /// Items processed in this run
+  pub processed: usize,
+}
+
+impl<I, O> BatchReport<I, O> {
+  pub fn succeeded(&self) -> usize {
+    self
+      .results
+      .iter()
+      .filter(|(_, result)| result.is_ok())
+      .count()
+  }
+
+  pub fn failed(&self) -> usize {
+    self.results.len() - self.succeeded()
+  }
+}
+
+struct Checkpoint<I> {
+  path: PathBuf,
+  key: CheckpointKey<I>,
+  resume: bool,
+}
+
+/// Batch processor with concurrency control, retries, checkpointing and
+/// adaptive throttling
+pub struct BatchProcessor<I, O> {
   concurrency_limit: usize,
+  retry: Option<RetryPolicy>,
+  progress: Option<BatchEventCallback<I>>,
+  checkpoint: Option<Checkpoint<I>>,
+  throttle: Option<AdaptiveThrottle>,
+  _output: PhantomData<fn() -> O>,
 }
 
-impl BatchProcessor {
+impl<I, O> BatchProcessor<I, O>
+where
+  I: Clone,
+  O: Clone + Serialize + DeserializeOwned,
+{
   pub fn new(concurrency_limit: usize) -> Self {
-    Self { concurrency_limit }
+    Self {
+      concurrency_limit,
+      retry: None,
+      progress: None,
+      checkpoint: None,
+      throttle: None,
+      _output: PhantomData,
+    }
+  }
+
+  /// Retry failed items under `policy`. An error that is not an
+  /// `AgentFlowError` is matched against the policy's patterns by its
+  /// message.
+  pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+    self.retry = Some(policy);
+    self
+  }
+
+  pub fn with_progress(
+    mut self,
+    callback: impl Fn(&BatchEvent<'_, I>) + Send + Sync + 'static,
+  ) -> Self {
+    self.progress = Some(Arc::new(callback));
+    self
+  }
+
+  /// Record each finished item in the checkpoint file at `path`, under
+  /// `key(item)`
+  pub fn with_checkpoint(
+    mut self,
+    path: impl Into<PathBuf>,
+    key: impl Fn(&I) -> String + Send + Sync + 'static,
+  ) -> Self {
+    self.checkpoint = Some(Checkpoint {
+      path: path.into(),
+      key: Arc::new(key),
+      resume: false,
+    });
+    self
+  }
+
+  /// Reuse the outputs the checkpoint file records instead of processing
+  /// those items again; without it the checkpoint starts over
+  pub fn resume(mut self, resume: bool) -> Self {
+    if let Some(checkpoint) = &mut self.checkpoint {
+      checkpoint.resume = resume;
+    }
+    self
   }
 
-  /// Process items concurrently with semaphore control
-  pub async fn process_concurrent<T, R, F, Fut>(
+  pub fn with_throttle(mut self, throttle: AdaptiveThrottle) -> Self {
+    self.throttle = Some(throttle);
+    self
+  }
+
+  /// Process `items` with `processor`. Fails only when the checkpoint to
+  /// resume from cannot be read; failed items are in the report.
+  pub async fn process<F, Fut>(
     &self,
-    items: Vec<T>,
+    items: Vec<I>,
     processor: F,
-  ) -> Vec<(T, crate::AgentResult<R>)>
+  ) -> crate::AgentResult<BatchReport<I, O>>
   where
-    T: Clone + Send + 'static,
-    R: Send + 'static,
-    F: Fn(T) -> Fut + Send + Sync + 'static,
-    Fut: std::future::Future<Output = crate::AgentResult<R>> + Send,
+    F: Fn(I) -> Fut,
+    Fut: Future<Output = crate::AgentResult<O>>,
   {
-    let semaphore = Arc::new(Semaphore::new(self.concurrency_limit));
-    let processor = Arc::new(processor);
-    let mut handles = Vec::new();
-
-    for item in items {
-      let sem = semaphore.clone();
-      let proc = processor.clone();
-      let item_clone = item.clone();
-
-      let handle = tokio::spawn(async move {
-        // `Semaphore::acquire` only errors when the semaphore is closed; we
-        // hold the only `Arc<Semaphore>` and never call `close()`, so this
-        // is a build-time invariant (Q5.1).
-        #[allow(
-          clippy::unwrap_used,
-          reason = "semaphore is private + never closed within this function"
-        )]
-        let _permit = sem.acquire().await.unwrap();
-        let result = proc(item_clone.clone()).await;
-        (item_clone, result)
-      });
+    let checkpoint = match &self.checkpoint {
+      Some(checkpoint) if checkpoint.resume => BatchCheckpoint::<O>::load(&checkpoint.path)?,
+      _ => BatchCheckpoint::default(),
+    };
 
-      handles.push(handle);
+    let mut outputs: Vec<Option<Result<O, String>>> = vec![None; items.len()];
+    let mut pending = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+      let resumed = self
+        .checkpoint
+        .as_ref()
+        .and_then(|settings| checkpoint.items.get(&(settings.key)(item)));
+      match resumed {
+        Some(entry) => outputs[index] = Some(Ok(entry.output.clone())),
+        None => pending.push(index),
+      }
     }
+    let resumed = items.len() - pending.len();
+    if resumed > 0 {
+      tracing::info!(
+        "Resuming: {} of {} items already processed",
+        resumed,
+        items.len()
+      );
+    }
+
+    let total = pending.len();
+    let checkpoint = Mutex::new(checkpoint);
+    let throttle = Mutex::new(ThrottleState::default());
+    let started = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+    let (items_ref, processor) = (&items, &processor);
+    let (checkpoint, throttle) = (&checkpoint, &throttle);
+    let (started, completed) = (&started, &completed);
+
+    let finished: Vec<(usize, Result<O, String>)> = stream::iter(pending)
+      .map(|index| async move {
+        let item = &items_ref[index];
+        let number = started.fetch_add(1, Ordering::SeqCst) + 1;
+        self.emit(&BatchEvent::Started {
+          item,
+          started: number,
+          total,
+        });
 
-    let mut results = Vec::new();
-    for handle in handles {
-      match handle.await {
-        Ok((item, result)) => results.push((item, result)),
-        Err(e) => {
-          // Handle join error - create a synthetic error result
-          // This is tricky because we don't have the original item
-          // In practice, this should rarely happen
-          eprintln!("Task join error: {}", e);
+        let result = self.attempt(item, processor, throttle).await;
+        let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+        match &result {
+          Ok(output) => {
+            self.record(checkpoint, item, output);
+            self.emit(&BatchEvent::Finished {
+              item,
+              completed: done,
+              total,
+            });
+          }
+          Err(error) => self.emit(&BatchEvent::Failed {
+            item,
+            error: error.clone(),
+            completed: done,
+            total,
+          }),
         }
-      }
+        (index, result)
+      })
+      .buffer_unordered(self.concurrency_limit.max(1))
+      .collect()
+      .await;
+
+    for (index, result) in finished {
+      outputs[index] = Some(result);
     }
+    let results = items
+      .into_iter()
+      .zip(outputs)
+      .filter_map(|(item, output)| output.map(|output| (item, output)))
+      .collect();
 
-    results
+    Ok(BatchReport {
+      results,
+      resumed,
+      processed: total,
+    })
   }
 
-  /// Process items with progress reporting
-  pub async fn process_with_progress<T, R, F, Fut>(
+  /// Run one item, retrying it as the policy allows
+  async fn attempt<F, Fut>(
     &self,
-    items: Vec<T>,
-    processor: F,
-    progress_callback: impl Fn(usize, usize) + Send + Sync + 'static,
-  ) -> Vec<(T, crate::AgentResult<R>)>
+    item: &I,
+    processor: &F,
+    throttle: &Mutex<ThrottleState>,
+  ) -> Result<O, String>
   where
-    T: Clone + Send + 'static,
-    R: Send + 'static,
-    F: Fn(T) -> Fut + Send + Sync + 'static,
-    Fut: std::future::Future<Output = crate::AgentResult<R>> + Send,
+    F: Fn(I) -> Fut,
+    Fut: Future<Output = crate::AgentResult<O>>,
   {
-    let total = items.len();
-    let semaphore = Arc::new(Semaphore::new(self.concurrency_limit));
-    let processor = Arc::new(processor);
-    let progress = Arc::new(progress_callback);
-    let completed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
-    let mut handles = Vec::new();
-
-    for item in items {
-      let sem = semaphore.clone();
-      let proc = processor.clone();
-      let prog = progress.clone();
-      let comp = completed.clone();
-
-      let handle = tokio::spawn(async move {
-        // See `process_items` above for the unwrap rationale (Q5.1).
-        #[allow(
-          clippy::unwrap_used,
-          reason = "semaphore is private + never closed within this function"
-        )]
-        let _permit = sem.acquire().await.unwrap();
-        let result = proc(item.clone()).await;
-
-        let current = comp.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
-        prog(current, total);
-
-        (item, result)
-      });
+    let mut context = RetryContext::new();
+    loop {
+      if self.throttle.is_some() {
+        let delay = lock(throttle).delay;
+        if !delay.is_zero() {
+          tokio::time::sleep(delay).await;
+        }
+      }
 
-      handles.push(handle);
-    }
+      let outcome = processor(item.clone()).await;
+      let backoff = self
+        .throttle
+        .as_ref()
+        .and_then(|settings| lock(throttle).record(settings, outcome.is_err()));
+      if let Some((delay, failure_rate)) = backoff {
+        tracing::warn!(
+          "{:.0}% of recent batch items failed; slowing down to one attempt per {:?}",
+          failure_rate * 100.0,
+          delay
+        );
+        self.emit(&BatchEvent::Throttled {
+          delay,
+          failure_rate,
+        });
+      }
 
-    let mut results = Vec::new();
-    for handle in handles {
-      if let Ok((item, result)) = handle.await {
-        results.push((item, result));
+      let error = match outcome {
+        Ok(output) => return Ok(output),
+        Err(error) => error,
+      };
+      let wrapped;
+      let flow_error = match error.downcast_ref::<AgentFlowError>() {
+        Some(flow_error) => flow_error,
+        None => {
+          wrapped = AgentFlowError::AsyncExecutionError {
+            message: error.to_string(),
+          };
+          &wrapped
+        }
+      };
+      match &self.retry {
+        Some(policy) if context.should_retry(policy, flow_error) => {
+          let delay = policy.calculate_delay(context.attempt);
+          context.record_failure(flow_error);
+          self.emit(&BatchEvent::Retrying {
+            item,
+            attempt: context.attempt,
+            delay,
+            error: error.to_string(),
+          });
+          tokio::time::sleep(delay).await;
+        }
+        _ => return Err(error.to_string()),
       }
     }
+  }
 
-    results
+  fn record(&self, checkpoint: &Mutex<BatchCheckpoint<O>>, item: &I, output: &O) {
+    let Some(settings) = &self.checkpoint else {
+      return;
+    };
+    let mut checkpoint = lock(checkpoint);
+    checkpoint.items.insert(
+      (settings.key)(item),
+      CheckpointEntry {
+        completed_at: chrono::Utc::now().to_rfc3339(),
+        output: output.clone(),
+      },
+    );
+    if let Err(e) = checkpoint.save(&settings.path) {
+      tracing::warn!(
+        "Failed to save batch checkpoint {}: {}",
+        settings.path.display(),
+        e
+      );
+    }
   }
+
+  fn emit(&self, event: &BatchEvent<'_, I>) {
+    if let Some(progress) = &self.progress {
+      progress(event);
+    }
+  }
+}
+
+/// A poisoned lock only means another item panicked mid-update; the data
+/// is still usable
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+  mutex
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
 /// Default batch processor with reasonable concurrency limit
-pub fn default_batch_processor() -> BatchProcessor {
+pub fn default_batch_processor<I, O>() -> BatchProcessor<I, O>
+where
+  I: Clone,
+  O: Clone + Serialize + DeserializeOwned,
+{
   BatchProcessor::new(3)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  fn retry(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy::builder()
+      .max_attempts(max_attempts)
+      .strategy(RetryStrategy::fixed(1))
+      .build()
+  }
+
+  #[tokio::test]
+  async fn checkpoint_resumes_an_interrupted_batch() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checkpoint.json");
+    let items: Vec<u32> = (1..=5).collect();
+
+    // The third item hangs; the batch is dropped while it does.
+    let first = BatchProcessor::<u32, u32>::new(1).with_checkpoint(&path, |item| item.to_string());
+    let interrupted = tokio::time::timeout(
+      Duration::from_millis(200),
+      first.process(items.clone(), |item| async move {
+        if item == 3 {
+          std::future::pending::<()>().await;
+        }
+        Ok(item * 10)
+      }),
+    )
+    .await;
+    assert!(interrupted.is_err());
+    assert_eq!(BatchCheckpoint::<u32>::load(&path).unwrap().items.len(), 2);
+
+    let seen = Mutex::new(Vec::new());
+    let resumed = BatchProcessor::<u32, u32>::new(2)
+      .with_checkpoint(&path, |item| item.to_string())
+      .resume(true);
+    let report = resumed
+      .process(items.clone(), |item| {
+        seen.lock().unwrap().push(item);
+        async move { Ok(item * 10) }
+      })
+      .await
+      .unwrap();
+
+    seen.lock().unwrap().sort();
+    assert_eq!(*seen.lock().unwrap(), [3, 4, 5]);
+    assert_eq!(
+      (report.resumed, report.processed, report.succeeded()),
+      (2, 3, 5)
+    );
+    let outputs: Vec<u32> = report
+      .results
+      .iter()
+      .map(|(_, output)| *output.as_ref().unwrap())
+      .collect();
+    assert_eq!(outputs, [10, 20, 30, 40, 50]);
+    assert_eq!(BatchCheckpoint::<u32>::load(&path).unwrap().items.len(), 5);
+
+    // Without resume the checkpoint starts over.
+    let fresh = BatchProcessor::<u32, u32>::new(1).with_checkpoint(&path, |item| item.to_string());
+    let report = fresh
+      .process(vec![1], |item| async move { Ok(item) })
+      .await
+      .unwrap();
+    assert_eq!((report.resumed, report.processed), (0, 1));
+    assert_eq!(BatchCheckpoint::<u32>::load(&path).unwrap().items.len(), 1);
+  }
+
+  #[tokio::test]
+  async fn retries_until_the_policy_gives_up() {
+    let attempts = Mutex::new(HashMap::<&str, u32>::new());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let processor = BatchProcessor::<&str, String>::new(2)
+      .with_retry(retry(2))
+      .with_progress(move |event| {
+        if let BatchEvent::Retrying { item, attempt, .. } = event {
+          recorded.lock().unwrap().push((item.to_string(), *attempt));
+        }
+      });
+
+    let report = processor
+      .process(vec!["flaky", "broken", "fine"], |item| {
+        let attempt = {
+          let mut attempts = attempts.lock().unwrap();
+          let count = attempts.entry(item).or_default();
+          *count += 1;
+          *count
+        };
+        async move {
+          match item {
+            "flaky" if attempt < 2 => Err("connection reset".into()),
+            "broken" => Err(format!("broken on attempt {attempt}").into()),
+            _ => Ok(format!("{item} after {attempt}")),
+          }
+        }
+      })
+      .await
+      .unwrap();
+
+    assert_eq!(report.results[0].1, Ok("flaky after 2".to_string()));
+    assert_eq!(report.results[1].1, Err("broken on attempt 3".to_string()));
+    assert_eq!(report.results[2].1, Ok("fine after 1".to_string()));
+    let mut retried = events.lock().unwrap().clone();
+    retried.sort();
+    assert_eq!(
+      retried,
+      [
+        ("broken".to_string(), 1),
+        ("broken".to_string(), 2),
+        ("flaky".to_string(), 1)
+      ]
+    );
+
+    // Errors the policy does not list are not retried.
+    let rate_limits_only = RetryPolicy::builder()
+      .max_attempts(3)
+      .strategy(RetryStrategy::fixed(1))
+      .retryable_error(ErrorPattern::RateLimitError)
+      .build();
+    let calls = AtomicUsize::new(0);
+    let report = BatchProcessor::<u32, u32>::new(1)
+      .with_retry(rate_limits_only)
+      .process(vec![1, 2], |item| {
+        calls.fetch_add(1, Ordering::SeqCst);
+        async move {
+          match item {
+            1 => Err("HTTP 429 Too Many Requests".into()),
+            _ => Err("invalid input".into()),
+          }
+        }
+      })
+      .await
+      .unwrap();
+    assert_eq!(report.failed(), 2);
+    assert_eq!(calls.load(Ordering::SeqCst), 4 + 1);
+  }
+
+  #[tokio::test]
+  async fn throttle_backs_off_when_recent_items_fail() {
+    let throttle = AdaptiveThrottle {
+      window: 4,
+      min_samples: 4,
+      failure_threshold: 0.5,
+      initial_delay: Duration::from_millis(20),
+      max_delay: Duration::from_millis(40),
+    };
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let processor = BatchProcessor::<u32, u32>::new(1)
+      .with_throttle(throttle.clone())
+      .with_progress(move |event| {
+        if let BatchEvent::Throttled {
+          delay,
+          failure_rate,
+        } = event
+        {
+          recorded.lock().unwrap().push((*delay, *failure_rate));
+        }
+      });
+
+    // Items 1-3 succeed, 4-13 fail as if rate limited, the rest succeed.
+    let started = std::time::Instant::now();
+    let report = processor
+      .process((1..=15).collect(), |item| async move {
+        match item {
+          4..=13 => Err("rate limit exceeded".into()),
+          _ => Ok(item),
+        }
+      })
+      .await
+      .unwrap();
+    assert_eq!(report.failed(), 10);
+
+    // Item 5 makes 2 of the last 4 attempts failures; 4 more failures
+    // double the delay, and 4 more keep it at the cap.
+    let events = events.lock().unwrap().clone();
+    assert_eq!(
+      events,
+      [
+        (Duration::from_millis(20), 0.5),
+        (Duration::from_millis(40), 1.0),
+        (Duration::from_millis(40), 1.0)
+      ]
+    );
+    assert!(started.elapsed() >= Duration::from_millis(20 * 4 + 40 * 4));
+
+    // Once throttled, a success halves the delay and the next one clears it.
+    let mut state = ThrottleState::default();
+    for _ in 0..4 {
+      state.record(&throttle, true);
+    }
+    assert_eq!(state.delay, Duration::from_millis(20));
+    assert_eq!(state.record(&throttle, false), None);
+    assert_eq!(state.delay, Duration::ZERO);
+    // Failures short of the threshold do not trigger it.
+    for failed in [true, false, false, false, true] {
+      assert_eq!(state.record(&throttle, failed), None);
+    }
+  }
+}
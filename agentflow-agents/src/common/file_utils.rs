@@ -1,26 +1,144 @@
 //! File handling utilities for agents
 
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
-/// Discover files with specific extensions in a directory
+/// How deep [`FileDiscovery`] descends below the directory by default
+pub const DEFAULT_MAX_DEPTH: usize = 16;
+
+/// Finds the files with given extensions in a directory.
+///
+/// Extensions match case-insensitively, with or without their dot, so
+/// `pdf` finds `paper.PDF`. Hidden entries (names starting with `.`) are
+/// skipped unless `include_hidden` is set. A recursive search goes at most
+/// `max_depth` directories down. Symlinks are skipped unless
+/// `follow_symlinks` is set; a followed directory link already visited
+/// through another path is not searched twice, so link cycles end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiscovery {
+  extensions: Vec<String>,
+  pub recursive: bool,
+  pub follow_symlinks: bool,
+  pub max_depth: usize,
+  pub include_hidden: bool,
+}
+
+impl FileDiscovery {
+  pub fn new(extensions: &[&str]) -> Self {
+    Self {
+      extensions: extensions
+        .iter()
+        .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+        .collect(),
+      recursive: false,
+      follow_symlinks: false,
+      max_depth: DEFAULT_MAX_DEPTH,
+      include_hidden: false,
+    }
+  }
+
+  pub fn recursive(mut self, recursive: bool) -> Self {
+    self.recursive = recursive;
+    self
+  }
+
+  pub fn follow_symlinks(mut self, follow: bool) -> Self {
+    self.follow_symlinks = follow;
+    self
+  }
+
+  pub fn max_depth(mut self, depth: usize) -> Self {
+    self.max_depth = depth;
+    self
+  }
+
+  pub fn include_hidden(mut self, include: bool) -> Self {
+    self.include_hidden = include;
+    self
+  }
+
+  /// Whether `path` has one of the extensions
+  pub fn matches(&self, path: &Path) -> bool {
+    path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .is_some_and(|ext| {
+        self
+          .extensions
+          .iter()
+          .any(|wanted| wanted.eq_ignore_ascii_case(ext))
+      })
+  }
+
+  /// The matching files under `directory`, sorted by path
+  pub async fn discover<P: AsRef<Path>>(&self, directory: P) -> crate::AgentResult<Vec<PathBuf>> {
+    let root = directory.as_ref().to_path_buf();
+    let mut visited = HashSet::new();
+    if let Ok(canonical) = fs::canonicalize(&root).await {
+      visited.insert(canonical);
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![(root, 0)];
+    while let Some((dir, depth)) = pending.pop() {
+      let mut entries = fs::read_dir(&dir).await?;
+      while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !self.include_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+          continue;
+        }
+        let mut file_type = entry.file_type().await?;
+        if file_type.is_symlink() {
+          if !self.follow_symlinks {
+            tracing::debug!("Skipping symlink {}", path.display());
+            continue;
+          }
+          match fs::metadata(&path).await {
+            Ok(metadata) => file_type = metadata.file_type(),
+            Err(e) => {
+              tracing::warn!("Skipping broken symlink {}: {}", path.display(), e);
+              continue;
+            }
+          }
+        }
+
+        if file_type.is_dir() {
+          if !self.recursive {
+            continue;
+          }
+          if depth >= self.max_depth {
+            tracing::debug!(
+              "Not descending into {}: deeper than {} levels",
+              path.display(),
+              self.max_depth
+            );
+            continue;
+          }
+          let canonical = fs::canonicalize(&path)
+            .await
+            .unwrap_or_else(|_| path.clone());
+          if visited.insert(canonical) {
+            pending.push((path, depth + 1));
+          }
+        } else if file_type.is_file() && self.matches(&path) {
+          files.push(path);
+        }
+      }
+    }
+
+    files.sort();
+    Ok(files)
+  }
+}
+
+/// Discover files with specific extensions in a directory, not its
+/// subdirectories; see [`FileDiscovery`] for the rest
 pub async fn discover_files_with_extensions<P: AsRef<Path>>(
   directory: P,
   extensions: &[&str],
 ) -> crate::AgentResult<Vec<PathBuf>> {
-  let mut files = Vec::new();
-  let mut dir = fs::read_dir(directory).await?;
-
-  while let Some(entry) = dir.next_entry().await? {
-    let path = entry.path();
-    if let Some(ext) = path.extension().and_then(|s| s.to_str())
-      && extensions.contains(&ext)
-    {
-      files.push(path);
-    }
-  }
-
-  Ok(files)
+  FileDiscovery::new(extensions).discover(directory).await
 }
 
 /// Create timestamped output directory
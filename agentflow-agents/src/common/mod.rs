@@ -1,9 +1,17 @@
 pub mod batch_processor;
 pub mod file_utils;
+pub mod ocr;
 pub mod output_formatter;
+pub mod pdf_images;
 pub mod pdf_parser;
+pub mod stage_cache;
+pub mod telemetry;
 
 pub use batch_processor::*;
 pub use file_utils::*;
+pub use ocr::*;
 pub use output_formatter::*;
+pub use pdf_images::*;
 pub use pdf_parser::*;
+pub use stage_cache::*;
+pub use telemetry::*;
@@ -0,0 +1,518 @@
+//! OCR of scanned PDFs
+//!
+//! A scanned paper has no text layer, so extracting its text returns next
+//! to nothing. [`needs_ocr`] spots that from the letters per page, and
+//! [`ocr_pdf`] reads the text off each page's scan (its largest image)
+//! with an [`OcrBackend`]: an external command such as `tesseract`, or a
+//! vision model that transcribes the page.
+
+use crate::{PageImage, PdfError, extract_pdf_images};
+use agentflow_llm::{AgentFlow, MultimodalMessage};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Letters and digits a page of extracted text has at least, on average,
+/// before it is taken for a text layer
+pub const DEFAULT_MIN_CHARS_PER_PAGE: usize = 100;
+
+/// The vision model `OcrBackend::Vision` uses by default
+pub const DEFAULT_VISION_OCR_MODEL: &str = "step-1o-turbo-vision";
+
+/// How long an OCR command may take over one page before it is killed
+pub const OCR_COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What the vision model is asked for each page
+const VISION_OCR_PROMPT: &str = "This is a page of a research paper. Transcribe all of its text exactly as written, in reading order. Put headings on their own lines and write formulas in LaTeX. Reply with the text only.";
+
+/// How scanned pages are turned into text
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum OcrBackend {
+  /// No OCR; a scanned PDF fails extraction
+  #[default]
+  Off,
+  /// Run `program` on each page image and take its standard output as the
+  /// page's text. `{image}` in `args` is replaced by the image's path,
+  /// which is appended when no argument has it.
+  Command {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+  },
+  /// Have a vision model transcribe each page
+  Vision {
+    #[serde(default = "default_vision_model")]
+    model: String,
+  },
+}
+
+fn default_vision_model() -> String {
+  DEFAULT_VISION_OCR_MODEL.to_string()
+}
+
+impl OcrBackend {
+  /// `tesseract <image> stdout`
+  pub fn tesseract() -> Self {
+    Self::Command {
+      program: "tesseract".to_string(),
+      args: vec!["{image}".to_string(), "stdout".to_string()],
+    }
+  }
+
+  pub fn vision(model: impl Into<String>) -> Self {
+    Self::Vision {
+      model: model.into(),
+    }
+  }
+
+  /// `off`, `tesseract`, `vision` or `vision:<model>`
+  pub fn parse(spec: &str) -> Result<Self, OcrError> {
+    let spec = spec.trim();
+    match spec.to_ascii_lowercase().as_str() {
+      "off" | "none" => Ok(Self::Off),
+      "tesseract" => Ok(Self::tesseract()),
+      "vision" => Ok(Self::vision(DEFAULT_VISION_OCR_MODEL)),
+      _ => match spec.split_once(':') {
+        Some((backend, model))
+          if backend.eq_ignore_ascii_case("vision") && !model.trim().is_empty() =>
+        {
+          Ok(Self::vision(model.trim()))
+        }
+        _ => Err(OcrError::UnknownBackend(spec.to_string())),
+      },
+    }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    *self != Self::Off
+  }
+}
+
+/// Why OCR failed
+#[derive(Debug, thiserror::Error)]
+pub enum OcrError {
+  #[error("Unknown OCR backend '{0}' (expected off, tesseract, vision or vision:<model>)")]
+  UnknownBackend(String),
+
+  #[error("OCR is off")]
+  Disabled,
+
+  #[error(transparent)]
+  Pdf(#[from] PdfError),
+
+  #[error("No page images to OCR in {0}")]
+  NoPageImages(PathBuf),
+
+  #[error("Failed to write the image of page {page} for OCR: {source}")]
+  ImageFile {
+    page: usize,
+    #[source]
+    source: std::io::Error,
+  },
+
+  #[error("Failed to run {program}: {source}")]
+  Spawn {
+    program: String,
+    #[source]
+    source: std::io::Error,
+  },
+
+  #[error("{program} did not finish page {page} within {}s", timeout.as_secs())]
+  CommandTimedOut {
+    program: String,
+    page: usize,
+    timeout: Duration,
+  },
+
+  #[error("{program} failed on page {page} ({status}): {stderr}")]
+  CommandFailed {
+    program: String,
+    page: usize,
+    status: std::process::ExitStatus,
+    stderr: String,
+  },
+
+  #[error("Vision OCR of page {page} failed: {message}")]
+  Vision { page: usize, message: String },
+}
+
+/// The text OCR read off one page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrPage {
+  /// Counting from 1
+  pub page: usize,
+  pub text: String,
+}
+
+/// Whether `text`, extracted from `page_count` pages, is too thin to be a
+/// text layer: fewer than `min_chars_per_page` letters and digits a page
+/// on average. Lines holding only an HTML comment, such as `<!-- page 3
+/// -->` markers, do not count.
+pub fn needs_ocr(text: &str, page_count: usize, min_chars_per_page: usize) -> bool {
+  let chars = text
+    .lines()
+    .map(str::trim)
+    .filter(|line| !(line.starts_with("<!--") && line.ends_with("-->")))
+    .flat_map(str::chars)
+    .filter(|c| c.is_alphanumeric())
+    .count();
+  chars < page_count.max(1) * min_chars_per_page
+}
+
+/// The largest image of each page, which on a scanned page is the scan
+pub fn page_scans(images: &[PageImage]) -> Vec<&PageImage> {
+  let mut scans: Vec<&PageImage> = Vec::new();
+  for image in images {
+    match scans.last_mut() {
+      Some(scan) if scan.page == image.page => {
+        if image.pixels() > scan.pixels() {
+          *scan = image;
+        }
+      }
+      _ => scans.push(image),
+    }
+  }
+  scans
+}
+
+/// The text of `pages` (0-based, end exclusive; `None` for all) of the PDF
+/// at `path`, read off each page's scan with `backend`. Pages without an
+/// image are left out.
+pub async fn ocr_pdf(
+  backend: &OcrBackend,
+  path: &Path,
+  pages: Option<Range<usize>>,
+) -> Result<Vec<OcrPage>, OcrError> {
+  if !backend.is_enabled() {
+    return Err(OcrError::Disabled);
+  }
+  let images = tokio::task::spawn_blocking({
+    let path = path.to_path_buf();
+    move || extract_pdf_images(&path, pages, 0)
+  })
+  .await
+  .map_err(|e| PdfError::Parse(e.to_string()))??;
+
+  let scans = page_scans(&images);
+  if scans.is_empty() {
+    return Err(OcrError::NoPageImages(path.to_path_buf()));
+  }
+  let mut text = Vec::with_capacity(scans.len());
+  for scan in scans {
+    tracing::info!(page = scan.page, "OCR of a scanned page");
+    text.push(OcrPage {
+      page: scan.page,
+      text: ocr_image(backend, scan).await?,
+    });
+  }
+  Ok(text)
+}
+
+/// The text on `image`, read with `backend`
+pub async fn ocr_image(backend: &OcrBackend, image: &PageImage) -> Result<String, OcrError> {
+  match backend {
+    OcrBackend::Off => Err(OcrError::Disabled),
+    OcrBackend::Command { program, args } => {
+      run_command(program, args, image, OCR_COMMAND_TIMEOUT).await
+    }
+    OcrBackend::Vision { model } => {
+      let data = base64::engine::general_purpose::STANDARD.encode(&image.data);
+      let message = MultimodalMessage::user()
+        .add_text(VISION_OCR_PROMPT)
+        .add_image_url(format!(
+          "data:{};base64,{}",
+          image.format.media_type(),
+          data
+        ))
+        .build();
+      AgentFlow::model(model)
+        .multimodal_prompt(message)
+        .temperature(0.0)
+        .execute()
+        .await
+        .map(|text| text.trim().to_string())
+        .map_err(|e| OcrError::Vision {
+          page: image.page,
+          message: e.to_string(),
+        })
+    }
+  }
+}
+
+/// Runs `program` on `image`, killing it once `timeout` has passed
+async fn run_command(
+  program: &str,
+  args: &[String],
+  image: &PageImage,
+  timeout: Duration,
+) -> Result<String, OcrError> {
+  let image_path = std::env::temp_dir().join(format!(
+    "agentflow-ocr-{}.{}",
+    uuid::Uuid::new_v4(),
+    image.format.extension()
+  ));
+  tokio::fs::write(&image_path, &image.data)
+    .await
+    .map_err(|source| OcrError::ImageFile {
+      page: image.page,
+      source,
+    })?;
+
+  let path = image_path.to_string_lossy();
+  let mut command_args: Vec<String> = args
+    .iter()
+    .map(|arg| arg.replace("{image}", &path))
+    .collect();
+  if !args.iter().any(|arg| arg.contains("{image}")) {
+    command_args.push(path.to_string());
+  }
+  // Dropping the output future on a timeout kills the command
+  let output = tokio::time::timeout(
+    timeout,
+    tokio::process::Command::new(program)
+      .args(&command_args)
+      .kill_on_drop(true)
+      .output(),
+  )
+  .await;
+  let _ = tokio::fs::remove_file(&image_path).await;
+
+  let output = output
+    .map_err(|_| OcrError::CommandTimedOut {
+      program: program.to_string(),
+      page: image.page,
+      timeout,
+    })?
+    .map_err(|source| OcrError::Spawn {
+      program: program.to_string(),
+      source,
+    })?;
+  if !output.status.success() {
+    return Err(OcrError::CommandFailed {
+      program: program.to_string(),
+      page: image.page,
+      status: output.status,
+      stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    });
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::common::pdf_images::tests::{image_stream, pdf_with_images};
+
+  #[test]
+  fn thin_text_needs_ocr() {
+    let markers_only = "<!-- page 1 -->\n\n\n\n<!-- page 2 -->\n\n";
+    assert!(needs_ocr(markers_only, 2, DEFAULT_MIN_CHARS_PER_PAGE));
+    assert!(needs_ocr("", 0, DEFAULT_MIN_CHARS_PER_PAGE));
+
+    let page = "Attention is all you need. ".repeat(6);
+    let two_pages = format!("<!-- page 1 -->\n\n{}\n\n<!-- page 2 -->\n\n{}", page, page);
+    // 126 letters a page
+    assert!(!needs_ocr(&two_pages, 2, 100));
+    assert!(needs_ocr(&two_pages, 2, 150));
+    // Four pages with text on two fall short on average
+    assert!(needs_ocr(&two_pages, 4, 100));
+    // Punctuation and whitespace do not count; CJK characters do
+    assert!(needs_ocr(&".,; -".repeat(100), 1, 100));
+    assert!(!needs_ocr(&"注意力".repeat(40), 1, 100));
+  }
+
+  #[test]
+  fn backends_parse_from_their_names() {
+    assert_eq!(OcrBackend::parse("off").unwrap(), OcrBackend::Off);
+    assert_eq!(
+      OcrBackend::parse("Tesseract").unwrap(),
+      OcrBackend::tesseract()
+    );
+    assert_eq!(
+      OcrBackend::parse("vision").unwrap(),
+      OcrBackend::vision(DEFAULT_VISION_OCR_MODEL)
+    );
+    assert_eq!(
+      OcrBackend::parse("vision:qwen-vl-max").unwrap(),
+      OcrBackend::vision("qwen-vl-max")
+    );
+    assert!(matches!(
+      OcrBackend::parse("abbyy"),
+      Err(OcrError::UnknownBackend(_))
+    ));
+
+    let config: OcrBackend =
+      serde_json::from_value(serde_json::json!({ "backend": "vision" })).unwrap();
+    assert_eq!(config, OcrBackend::vision(DEFAULT_VISION_OCR_MODEL));
+  }
+
+  #[test]
+  fn the_largest_image_of_a_page_is_its_scan() {
+    let image = |page, index, width| PageImage {
+      page,
+      index,
+      width,
+      height: 100,
+      format: crate::ImageFormat::Png,
+      data: Vec::new(),
+    };
+    let images = [
+      image(1, 1, 50),
+      image(1, 2, 900),
+      image(1, 3, 80),
+      image(2, 1, 850),
+    ];
+    let scans = page_scans(&images);
+    assert_eq!(
+      scans
+        .iter()
+        .map(|scan| (scan.page, scan.index))
+        .collect::<Vec<_>>(),
+      vec![(1, 2), (2, 1)]
+    );
+  }
+
+  /// An executable script standing in for tesseract
+  #[cfg(unix)]
+  fn stub(dir: &Path, script: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    let path = dir.join("ocr-stub");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.to_string_lossy().into_owned()
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn command_backend_reads_each_page_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let pdf = dir.path().join("scanned.pdf");
+    pdf_with_images(
+      &pdf,
+      vec![
+        vec![image_stream(40, 40, false), image_stream(600, 800, true)],
+        vec![],
+        vec![image_stream(600, 800, false)],
+      ],
+    );
+    // Prints the image's size and the second argument, as tesseract
+    // prints the text of `<image> stdout`
+    let program = stub(
+      dir.path(),
+      r#"test -s "$1" || exit 3; echo "$(wc -c < "$1" | tr -d ' ') bytes to $2""#,
+    );
+    let backend = OcrBackend::Command {
+      program,
+      args: vec!["{image}".to_string(), "stdout".to_string()],
+    };
+
+    let pages = ocr_pdf(&backend, &pdf, None).await.unwrap();
+    assert_eq!(
+      pages.iter().map(|page| page.page).collect::<Vec<_>>(),
+      vec![1, 3]
+    );
+    // The JPEG scan of page 1, not its 40×40 icon
+    assert_eq!(pages[0].text, "15 bytes to stdout");
+    assert!(pages[1].text.ends_with("bytes to stdout"));
+
+    let only_third = ocr_pdf(&backend, &pdf, Some(2..3)).await.unwrap();
+    assert_eq!(only_third.len(), 1);
+    assert!(matches!(
+      ocr_pdf(&backend, &pdf, Some(1..2)).await,
+      Err(OcrError::NoPageImages(_))
+    ));
+    assert!(matches!(
+      ocr_pdf(&OcrBackend::Off, &pdf, None).await,
+      Err(OcrError::Disabled)
+    ));
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn command_failures_carry_the_page_and_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    let pdf = dir.path().join("scanned.pdf");
+    pdf_with_images(&pdf, vec![vec![image_stream(300, 300, true)]]);
+    let program = stub(
+      dir.path(),
+      r#"echo "Error: tessdata not found" >&2; exit 1"#,
+    );
+    let backend = OcrBackend::Command {
+      program,
+      args: Vec::new(),
+    };
+    match ocr_pdf(&backend, &pdf, None).await {
+      Err(OcrError::CommandFailed { page, stderr, .. }) => {
+        assert_eq!(page, 1);
+        assert_eq!(stderr, "Error: tessdata not found");
+      }
+      other => panic!("expected a command failure, got {:?}", other),
+    }
+
+    let missing = OcrBackend::Command {
+      program: dir
+        .path()
+        .join("no-such-ocr")
+        .to_string_lossy()
+        .into_owned(),
+      args: Vec::new(),
+    };
+    assert!(matches!(
+      ocr_pdf(&missing, &pdf, None).await,
+      Err(OcrError::Spawn { .. })
+    ));
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn a_hung_command_is_killed_at_the_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let pidfile = dir.path().join("pid");
+    let program = stub(
+      dir.path(),
+      &format!("echo $$ > {}; exec sleep 30", pidfile.display()),
+    );
+    let image = PageImage {
+      page: 2,
+      index: 1,
+      width: 1,
+      height: 1,
+      format: crate::ImageFormat::Png,
+      data: vec![0],
+    };
+
+    let started = std::time::Instant::now();
+    let result = run_command(&program, &[], &image, Duration::from_millis(300)).await;
+    assert!(started.elapsed() < Duration::from_secs(10));
+    match result {
+      Err(OcrError::CommandTimedOut { page, timeout, .. }) => {
+        assert_eq!(page, 2);
+        assert_eq!(timeout, Duration::from_millis(300));
+      }
+      other => panic!("expected a timeout, got {:?}", other),
+    }
+
+    // `kill` with signal 0 fails once the process is gone
+    let pid = std::fs::read_to_string(&pidfile).unwrap();
+    let gone = async {
+      loop {
+        let alive = std::process::Command::new("kill")
+          .args(["-0", pid.trim()])
+          .stderr(std::process::Stdio::null())
+          .status()
+          .unwrap()
+          .success();
+        if !alive {
+          break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+      }
+    };
+    tokio::time::timeout(Duration::from_secs(5), gone)
+      .await
+      .expect("the command outlived its timeout");
+  }
+}
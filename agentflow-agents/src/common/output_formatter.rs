@@ -1,7 +1,17 @@
 //! Output formatting utilities for agents
+//!
+//! [`ArtifactWriter`] writes an agent's output files into one directory. It
+//! sanitizes their names, applies an [`OverwritePolicy`] to files left by
+//! earlier runs, and lists each file in `manifest.json` with its kind,
+//! source node and SHA-256.
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
 /// Format JSON output with pretty printing
 pub fn format_json_pretty(value: &Value) -> crate::AgentResult<String> {
@@ -89,3 +99,510 @@ pub async fn save_comprehensive_output<P: AsRef<Path>>(
   );
   Ok(())
 }
+
+/// The manifest's file name in an artifact directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Longest file name stem, in bytes, [`ArtifactWriter`] writes
+pub const MAX_FILENAME_BYTES: usize = 120;
+
+/// A file name stem safe on every platform: letters and digits of any
+/// script, `-`, `_` and `.` are kept, runs of anything else become one `_`,
+/// and the result is cut to at most `max_bytes` on a character boundary.
+/// Never empty.
+pub fn sanitize_filename(name: &str, max_bytes: usize) -> String {
+  let mut sanitized = String::new();
+  for c in name.chars() {
+    if c.is_alphanumeric() || c == '-' || c == '.' {
+      sanitized.push(c);
+    } else if !sanitized.ends_with('_') {
+      sanitized.push('_');
+    }
+  }
+
+  let mut capped = String::new();
+  for c in sanitized.trim_matches(['_', '.']).chars() {
+    if capped.len() + c.len_utf8() > max_bytes {
+      break;
+    }
+    capped.push(c);
+  }
+  let capped = capped.trim_end_matches(['_', '.']);
+  if capped.is_empty() {
+    "artifact".to_string()
+  } else {
+    capped.to_string()
+  }
+}
+
+/// What to do when an artifact's file is already there from an earlier run.
+/// Two artifacts of the same name written by one writer never overwrite
+/// each other; the second one gets a `_2` suffix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwritePolicy {
+  /// Fail with [`ArtifactError::Exists`]
+  Error,
+  /// Overwrite the file
+  #[default]
+  Replace,
+  /// Keep the file and write the new one as `<name>_2`, `<name>_3`, ...
+  Version,
+}
+
+/// The format of an artifact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+  Markdown,
+  Json,
+  Html,
+  Binary,
+}
+
+/// One file of `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactEntry {
+  /// Relative to the manifest's directory, `/`-separated
+  pub path: String,
+  pub kind: ArtifactKind,
+  /// The node that produced it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub source: Option<String>,
+  pub sha256: String,
+  pub bytes: u64,
+  pub written_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// `manifest.json`: every artifact written into a directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactManifest {
+  pub artifacts: Vec<ArtifactEntry>,
+}
+
+impl ArtifactManifest {
+  /// The manifest in `dir`; empty when there is none
+  pub fn load(dir: &Path) -> Result<Self, ArtifactError> {
+    let path = dir.join(MANIFEST_FILE);
+    match std::fs::read_to_string(&path) {
+      Ok(content) => {
+        serde_json::from_str(&content).map_err(|source| ArtifactError::Manifest { path, source })
+      }
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(source) => Err(ArtifactError::Io { path, source }),
+    }
+  }
+
+  pub fn get(&self, path: &str) -> Option<&ArtifactEntry> {
+    self.artifacts.iter().find(|entry| entry.path == path)
+  }
+}
+
+/// Why writing an artifact failed
+#[derive(Debug, thiserror::Error)]
+pub enum ArtifactError {
+  #[error("Artifact already exists: {0}")]
+  Exists(PathBuf),
+
+  #[error("Failed to write {path}: {source}")]
+  Io {
+    path: PathBuf,
+    #[source]
+    source: std::io::Error,
+  },
+
+  #[error("Invalid artifact manifest {path}: {source}")]
+  Manifest {
+    path: PathBuf,
+    #[source]
+    source: serde_json::Error,
+  },
+}
+
+struct ArtifactState {
+  manifest: ArtifactManifest,
+  /// Paths written by this writer and its subdirectories
+  written: HashSet<PathBuf>,
+}
+
+/// Writes artifacts into a base directory and keeps its `manifest.json`
+/// up to date after every file.
+///
+/// ```rust,no_run
+/// # async fn example() -> agentflow_agents::AgentResult<()> {
+/// use agentflow_agents::{ArtifactWriter, OverwritePolicy};
+///
+/// let writer = ArtifactWriter::new("./output")
+///   .await?
+///   .with_policy(OverwritePolicy::Version);
+/// writer.write_markdown("摘要: Attention Is All You Need", "...", Some("summarizer")).await?;
+/// let paper = writer.subdir("paper_01").await?;
+/// paper.write_json("results", &serde_json::json!({ "ok": true }), None).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ArtifactWriter {
+  root: PathBuf,
+  dir: PathBuf,
+  policy: OverwritePolicy,
+  state: Arc<Mutex<ArtifactState>>,
+}
+
+impl ArtifactWriter {
+  /// A writer into `base_dir`, created if missing. Artifacts recorded in
+  /// its manifest by earlier runs stay listed.
+  pub async fn new(base_dir: impl Into<PathBuf>) -> Result<Self, ArtifactError> {
+    let root = base_dir.into();
+    create_dir(&root).await?;
+    let manifest = ArtifactManifest::load(&root)?;
+    Ok(Self {
+      dir: root.clone(),
+      root,
+      policy: OverwritePolicy::default(),
+      state: Arc::new(Mutex::new(ArtifactState {
+        manifest,
+        written: HashSet::new(),
+      })),
+    })
+  }
+
+  pub fn with_policy(mut self, policy: OverwritePolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// The directory this writer writes into
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// A writer into the subdirectory `name` (sanitized), listing its
+  /// artifacts in the same manifest
+  pub async fn subdir(&self, name: &str) -> Result<Self, ArtifactError> {
+    let dir = self.dir.join(sanitize_filename(name, MAX_FILENAME_BYTES));
+    create_dir(&dir).await?;
+    Ok(Self {
+      dir,
+      ..self.clone()
+    })
+  }
+
+  /// The manifest as it stands
+  pub async fn manifest(&self) -> ArtifactManifest {
+    self.state.lock().await.manifest.clone()
+  }
+
+  pub async fn write_markdown(
+    &self,
+    name: &str,
+    content: &str,
+    source: Option<&str>,
+  ) -> Result<PathBuf, ArtifactError> {
+    self
+      .write(
+        name,
+        "md",
+        ArtifactKind::Markdown,
+        content.as_bytes(),
+        source,
+      )
+      .await
+  }
+
+  /// `value` as pretty-printed JSON
+  pub async fn write_json<T: Serialize + ?Sized>(
+    &self,
+    name: &str,
+    value: &T,
+    source: Option<&str>,
+  ) -> Result<PathBuf, ArtifactError> {
+    let content =
+      serde_json::to_string_pretty(value).map_err(|source| ArtifactError::Manifest {
+        path: self.dir.join(name),
+        source,
+      })?;
+    self
+      .write(name, "json", ArtifactKind::Json, content.as_bytes(), source)
+      .await
+  }
+
+  pub async fn write_html(
+    &self,
+    name: &str,
+    content: &str,
+    source: Option<&str>,
+  ) -> Result<PathBuf, ArtifactError> {
+    self
+      .write(name, "html", ArtifactKind::Html, content.as_bytes(), source)
+      .await
+  }
+
+  pub async fn write_binary(
+    &self,
+    name: &str,
+    extension: &str,
+    data: &[u8],
+    source: Option<&str>,
+  ) -> Result<PathBuf, ArtifactError> {
+    self
+      .write(name, extension, ArtifactKind::Binary, data, source)
+      .await
+  }
+
+  /// Write `data` as `<name>.<extension>` (name sanitized) and record it in
+  /// the manifest. Returns the path written.
+  pub async fn write(
+    &self,
+    name: &str,
+    extension: &str,
+    kind: ArtifactKind,
+    data: &[u8],
+    source: Option<&str>,
+  ) -> Result<PathBuf, ArtifactError> {
+    let mut state = self.state.lock().await;
+    let stem = sanitize_filename(name, MAX_FILENAME_BYTES);
+    let extension = sanitize_filename(extension, 16);
+    let path = self.free_path(&state, &stem, &extension)?;
+
+    tokio::fs::write(&path, data)
+      .await
+      .map_err(|source| ArtifactError::Io {
+        path: path.clone(),
+        source,
+      })?;
+    state.written.insert(path.clone());
+
+    let relative = path
+      .strip_prefix(&self.root)
+      .unwrap_or(&path)
+      .components()
+      .map(|component| component.as_os_str().to_string_lossy())
+      .collect::<Vec<_>>()
+      .join("/");
+    let entry = ArtifactEntry {
+      path: relative,
+      kind,
+      source: source.map(str::to_string),
+      sha256: Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect(),
+      bytes: data.len() as u64,
+      written_at: chrono::Utc::now(),
+    };
+    let artifacts = &mut state.manifest.artifacts;
+    match artifacts
+      .iter_mut()
+      .find(|existing| existing.path == entry.path)
+    {
+      Some(existing) => *existing = entry,
+      None => artifacts.push(entry),
+    }
+    self.save_manifest(&state.manifest).await?;
+    Ok(path)
+  }
+
+  /// The path for `<stem>.<extension>`: the first of `<stem>`, `<stem>_2`,
+  /// ... that this writer has not written and, unless replacing, that does
+  /// not exist
+  fn free_path(
+    &self,
+    state: &ArtifactState,
+    stem: &str,
+    extension: &str,
+  ) -> Result<PathBuf, ArtifactError> {
+    for version in 1.. {
+      let file_name = match version {
+        1 => format!("{}.{}", stem, extension),
+        n => format!("{}_{}.{}", stem, n, extension),
+      };
+      let path = self.dir.join(file_name);
+      if state.written.contains(&path) || path == self.root.join(MANIFEST_FILE) {
+        continue;
+      }
+      if !path.exists() {
+        return Ok(path);
+      }
+      match self.policy {
+        OverwritePolicy::Replace => return Ok(path),
+        OverwritePolicy::Error => return Err(ArtifactError::Exists(path)),
+        OverwritePolicy::Version => {}
+      }
+    }
+    unreachable!("an unbounded range always yields a free path")
+  }
+
+  async fn save_manifest(&self, manifest: &ArtifactManifest) -> Result<(), ArtifactError> {
+    let path = self.root.join(MANIFEST_FILE);
+    let json =
+      serde_json::to_string_pretty(manifest).map_err(|source| ArtifactError::Manifest {
+        path: path.clone(),
+        source,
+      })?;
+    let temp = path.with_extension("json.tmp");
+    tokio::fs::write(&temp, json)
+      .await
+      .map_err(|source| ArtifactError::Io {
+        path: temp.clone(),
+        source,
+      })?;
+    tokio::fs::rename(&temp, &path)
+      .await
+      .map_err(|source| ArtifactError::Io { path, source })
+  }
+}
+
+async fn create_dir(dir: &Path) -> Result<(), ArtifactError> {
+  tokio::fs::create_dir_all(dir)
+    .await
+    .map_err(|source| ArtifactError::Io {
+      path: dir.to_path_buf(),
+      source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sanitize_filename_keeps_unicode_and_caps_bytes() {
+    assert_eq!(
+      sanitize_filename("Introduction: Background & Motivation?", 50),
+      "Introduction_Background_Motivation"
+    );
+    assert_eq!(
+      sanitize_filename("2312.07104v2_summary_zh", 50),
+      "2312.07104v2_summary_zh"
+    );
+    assert_eq!(sanitize_filename("引言 / 背景", 50), "引言_背景");
+    assert_eq!(sanitize_filename("../..//", 50), "artifact");
+
+    // 3-byte characters are never split.
+    let capped = sanitize_filename(&"模型".repeat(30), 100);
+    assert_eq!((capped.len(), capped.chars().count()), (99, 33));
+    assert_eq!(sanitize_filename("abc def ghi", 8), "abc_def");
+  }
+
+  #[tokio::test]
+  async fn colliding_names_are_suffixed_and_policies_apply_to_earlier_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let writer = ArtifactWriter::new(dir.path()).await.unwrap();
+    let first = writer
+      .write_markdown("Mind map: 引言", "one", None)
+      .await
+      .unwrap();
+    let second = writer
+      .write_markdown("Mind map / 引言", "two", None)
+      .await
+      .unwrap();
+    assert_eq!(first.file_name().unwrap(), "Mind_map_引言.md");
+    assert_eq!(second.file_name().unwrap(), "Mind_map_引言_2.md");
+    // The manifest's own name is taken too.
+    let manifest = writer.write_json("manifest", &1, None).await.unwrap();
+    assert_eq!(manifest.file_name().unwrap(), "manifest_2.json");
+
+    // A later run replaces by default...
+    let rerun = ArtifactWriter::new(dir.path()).await.unwrap();
+    assert_eq!(
+      rerun
+        .write_markdown("Mind map: 引言", "three", None)
+        .await
+        .unwrap(),
+      first
+    );
+    assert_eq!(std::fs::read_to_string(&first).unwrap(), "three");
+
+    // ...or keeps the old files,
+    let versioned = ArtifactWriter::new(dir.path())
+      .await
+      .unwrap()
+      .with_policy(OverwritePolicy::Version);
+    let third = versioned
+      .write_markdown("Mind map: 引言", "four", None)
+      .await
+      .unwrap();
+    assert_eq!(third.file_name().unwrap(), "Mind_map_引言_3.md");
+
+    // ...or refuses.
+    let strict = ArtifactWriter::new(dir.path())
+      .await
+      .unwrap()
+      .with_policy(OverwritePolicy::Error);
+    let refused = strict.write_markdown("Mind map: 引言", "five", None).await;
+    assert!(matches!(refused, Err(ArtifactError::Exists(path)) if path == first));
+    assert_eq!(std::fs::read_to_string(&first).unwrap(), "three");
+  }
+
+  #[tokio::test]
+  async fn the_manifest_lists_every_artifact() {
+    let dir = tempfile::tempdir().unwrap();
+    let writer = ArtifactWriter::new(dir.path()).await.unwrap();
+    writer
+      .write_markdown("summary", "# Summary", Some("summarizer"))
+      .await
+      .unwrap();
+    writer
+      .write_json(
+        "insights",
+        &serde_json::json!({ "novelty": 4 }),
+        Some("insights_extractor"),
+      )
+      .await
+      .unwrap();
+    let paper = writer.subdir("02 Paper: Two").await.unwrap();
+    paper
+      .write_html("mind map", "<html></html>", Some("markmap"))
+      .await
+      .unwrap();
+    paper
+      .write_binary("poster", "png", b"\x89PNG", None)
+      .await
+      .unwrap();
+
+    let manifest = ArtifactManifest::load(dir.path()).unwrap();
+    assert_eq!(manifest, writer.manifest().await);
+    let listed: Vec<_> = manifest
+      .artifacts
+      .iter()
+      .map(|entry| (entry.path.as_str(), entry.kind, entry.source.as_deref()))
+      .collect();
+    assert_eq!(
+      listed,
+      [
+        ("summary.md", ArtifactKind::Markdown, Some("summarizer")),
+        (
+          "insights.json",
+          ArtifactKind::Json,
+          Some("insights_extractor")
+        ),
+        (
+          "02_Paper_Two/mind_map.html",
+          ArtifactKind::Html,
+          Some("markmap")
+        ),
+        ("02_Paper_Two/poster.png", ArtifactKind::Binary, None),
+      ]
+    );
+    for entry in &manifest.artifacts {
+      let data = std::fs::read(dir.path().join(&entry.path)).unwrap();
+      assert_eq!(entry.bytes, data.len() as u64);
+      let digest: String = Sha256::digest(&data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+      assert_eq!(entry.sha256, digest);
+    }
+
+    // Rewriting a file updates its entry instead of adding one.
+    let rerun = ArtifactWriter::new(dir.path()).await.unwrap();
+    rerun
+      .write_markdown("summary", "# Summary v2", Some("summarizer"))
+      .await
+      .unwrap();
+    let manifest = rerun.manifest().await;
+    assert_eq!(manifest.artifacts.len(), 4);
+    assert_eq!(manifest.get("summary.md").unwrap().bytes, 12);
+  }
+}
@@ -0,0 +1,356 @@
+//! Images embedded in a PDF
+//!
+//! [`extract_pdf_images`] reads the image XObjects of each page with
+//! `lopdf`: the scans of a scanned paper, or the figures of a born-digital
+//! one. JPEG and JPEG 2000 streams are kept as they are; 8-bit (or, for
+//! gray, 1/2/4-bit) RGB and gray streams, raw or Flate-compressed, are
+//! re-encoded as PNG. Other encodings (CCITT fax, JBIG2, CMYK, indexed
+//! colour, image masks) are skipped.
+
+use crate::PdfError;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use lopdf::{Dictionary, Document, Object};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// How an extracted image is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+  Jpeg,
+  Jpeg2000,
+  Png,
+}
+
+impl ImageFormat {
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Self::Jpeg => "jpg",
+      Self::Jpeg2000 => "jp2",
+      Self::Png => "png",
+    }
+  }
+
+  pub fn media_type(&self) -> &'static str {
+    match self {
+      Self::Jpeg => "image/jpeg",
+      Self::Jpeg2000 => "image/jp2",
+      Self::Png => "image/png",
+    }
+  }
+}
+
+/// An image of one page of a PDF
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageImage {
+  /// The page it is on, counting from 1
+  pub page: usize,
+  /// Its place among the page's extracted images, counting from 1
+  pub index: usize,
+  pub width: u32,
+  pub height: u32,
+  pub format: ImageFormat,
+  /// The encoded image; not serialized
+  #[serde(skip)]
+  pub data: Vec<u8>,
+}
+
+impl PageImage {
+  /// `page3_image1.png`
+  pub fn file_name(&self) -> String {
+    format!("{}.{}", self.file_stem(), self.format.extension())
+  }
+
+  /// `page3_image1`
+  pub fn file_stem(&self) -> String {
+    format!("page{}_image{}", self.page, self.index)
+  }
+
+  pub fn pixels(&self) -> u64 {
+    self.width as u64 * self.height as u64
+  }
+}
+
+/// The number of pages of the PDF at `path`
+pub fn pdf_page_count(path: &Path) -> Result<usize, PdfError> {
+  Ok(load(path)?.get_pages().len())
+}
+
+/// The images on `pages` (0-based, end exclusive; `None` for all) of the
+/// PDF at `path` that are at least `min_size` pixels wide and high, page
+/// by page. An image a page shows twice is extracted once.
+pub fn extract_pdf_images(
+  path: &Path,
+  pages: Option<Range<usize>>,
+  min_size: u32,
+) -> Result<Vec<PageImage>, PdfError> {
+  let document = load(path)?;
+  let page_ids = document.get_pages();
+  let range = pages.unwrap_or(0..page_ids.len());
+  if range.start >= range.end || range.end > page_ids.len() {
+    return Err(PdfError::InvalidPageRange {
+      start: range.start,
+      end: range.end,
+      page_count: page_ids.len(),
+    });
+  }
+
+  let mut images = Vec::new();
+  // lopdf numbers pages from 1
+  for (number, page_id) in page_ids {
+    let page = number as usize;
+    if !range.contains(&(page - 1)) {
+      continue;
+    }
+    let mut seen = HashSet::new();
+    let mut index = 0;
+    // A page without an XObject dictionary has no images
+    for image in document.get_page_images(page_id).unwrap_or_default() {
+      if !seen.insert(image.id) || image.width < min_size as i64 || image.height < min_size as i64 {
+        continue;
+      }
+      let Some((format, data)) = encode(&image) else {
+        tracing::debug!(page, id = ?image.id, filters = ?image.filters, "Skipping an image in an unsupported encoding");
+        continue;
+      };
+      index += 1;
+      images.push(PageImage {
+        page,
+        index,
+        width: image.width as u32,
+        height: image.height as u32,
+        format,
+        data,
+      });
+    }
+  }
+  Ok(images)
+}
+
+fn load(path: &Path) -> Result<Document, PdfError> {
+  if !path.exists() {
+    return Err(PdfError::NotFound(path.to_path_buf()));
+  }
+  Document::load(path).map_err(|e| PdfError::Parse(e.to_string()))
+}
+
+/// The image as a file, when its encoding is one we can write
+fn encode(image: &lopdf::xobject::PdfImage) -> Option<(ImageFormat, Vec<u8>)> {
+  let filters = image.filters.clone().unwrap_or_default();
+  match filters
+    .iter()
+    .map(String::as_str)
+    .collect::<Vec<_>>()
+    .as_slice()
+  {
+    ["DCTDecode"] => Some((ImageFormat::Jpeg, image.content.to_vec())),
+    ["JPXDecode"] => Some((ImageFormat::Jpeg2000, image.content.to_vec())),
+    [] => raw_png(image, image.content.to_vec(), None),
+    ["FlateDecode"] => {
+      let mut decoded = Vec::new();
+      ZlibDecoder::new(image.content)
+        .read_to_end(&mut decoded)
+        .ok()?;
+      let params = image
+        .origin_dict
+        .get(b"DecodeParms")
+        .and_then(Object::as_dict)
+        .ok();
+      raw_png(image, decoded, params)
+    }
+    _ => None,
+  }
+}
+
+/// Raw samples as a PNG. Samples already PNG-predicted (`Predictor` 10 or
+/// more) carry the per-row filter bytes PNG expects.
+fn raw_png(
+  image: &lopdf::xobject::PdfImage,
+  samples: Vec<u8>,
+  params: Option<&Dictionary>,
+) -> Option<(ImageFormat, Vec<u8>)> {
+  let (color_type, channels) = match image.color_space.as_deref() {
+    Some("DeviceRGB") => (2, 3),
+    Some("DeviceGray") => (0, 1),
+    _ => return None,
+  };
+  let depth = image.bits_per_component.unwrap_or(8);
+  let supported = match color_type {
+    0 => matches!(depth, 1 | 2 | 4 | 8),
+    _ => depth == 8,
+  };
+  if !supported {
+    return None;
+  }
+
+  let (width, height) = (image.width as usize, image.height as usize);
+  let row = (width * channels * depth as usize).div_ceil(8);
+  let predictor = params
+    .and_then(|params| params.get(b"Predictor").ok())
+    .and_then(|predictor| predictor.as_i64().ok())
+    .unwrap_or(1);
+  let filtered = match predictor {
+    1 if samples.len() >= row * height => samples
+      .chunks(row)
+      .take(height)
+      .flat_map(|line| std::iter::once(0).chain(line.iter().copied()))
+      .collect(),
+    10.. if samples.len() == (row + 1) * height => samples,
+    _ => return None,
+  };
+  Some((
+    ImageFormat::Png,
+    encode_png(
+      width as u32,
+      height as u32,
+      depth as u8,
+      color_type,
+      &filtered,
+    ),
+  ))
+}
+
+/// A PNG of `filtered` rows, each led by its filter byte
+pub(crate) fn encode_png(
+  width: u32,
+  height: u32,
+  depth: u8,
+  color_type: u8,
+  filtered: &[u8],
+) -> Vec<u8> {
+  let mut header = Vec::with_capacity(13);
+  header.extend_from_slice(&width.to_be_bytes());
+  header.extend_from_slice(&height.to_be_bytes());
+  header.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+
+  let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+  // Writing into a Vec does not fail
+  let _ = encoder.write_all(filtered);
+  let compressed = encoder.finish().unwrap_or_default();
+
+  let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+  for (kind, data) in [
+    (b"IHDR", &header[..]),
+    (b"IDAT", &compressed[..]),
+    (b"IEND", &[][..]),
+  ] {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32fast::hash(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+  }
+  png
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+  use lopdf::{Stream, dictionary};
+
+  /// An image XObject of `width` × `height`: gray samples, or JPEG bytes
+  pub(crate) fn image_stream(width: i64, height: i64, jpeg: bool) -> Stream {
+    let dict = dictionary! {
+      "Type" => "XObject",
+      "Subtype" => "Image",
+      "Width" => width,
+      "Height" => height,
+      "ColorSpace" => "DeviceGray",
+      "BitsPerComponent" => 8,
+    };
+    if jpeg {
+      let mut dict = dict;
+      dict.set("Filter", "DCTDecode");
+      Stream::new(dict, b"\xff\xd8\xff\xe0fake jpeg\xff\xd9".to_vec())
+    } else {
+      Stream::new(dict, vec![128; (width * height) as usize])
+    }
+  }
+
+  /// A PDF whose pages show `images`, one list per page
+  pub(crate) fn pdf_with_images(path: &Path, images: Vec<Vec<Stream>>) {
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+    let kids: Vec<Object> = images
+      .into_iter()
+      .map(|streams| {
+        let mut xobjects = Dictionary::new();
+        for (i, stream) in streams.into_iter().enumerate() {
+          let id = document.add_object(stream);
+          xobjects.set(format!("Im{}", i), id);
+        }
+        document
+          .add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Resources" => dictionary! { "XObject" => xobjects },
+          })
+          .into()
+      })
+      .collect();
+    let count = kids.len() as i64;
+    document.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => kids,
+        "Count" => count,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+      }),
+    );
+    let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    document.trailer.set("Root", catalog_id);
+    document.save(path).unwrap();
+  }
+
+  #[test]
+  fn images_are_extracted_above_the_size_threshold() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("paper.pdf");
+    pdf_with_images(
+      &path,
+      vec![
+        vec![image_stream(400, 300, true), image_stream(16, 16, false)],
+        vec![],
+        vec![image_stream(200, 200, false)],
+      ],
+    );
+    assert_eq!(pdf_page_count(&path).unwrap(), 3);
+
+    let images = extract_pdf_images(&path, None, 100).unwrap();
+    assert_eq!(images.len(), 2);
+    assert_eq!((images[0].page, images[0].index), (1, 1));
+    assert_eq!(images[0].format, ImageFormat::Jpeg);
+    assert!(images[0].data.starts_with(b"\xff\xd8"));
+    assert_eq!(images[1].file_name(), "page3_image1.png");
+    assert!(images[1].data.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!((images[1].width, images[1].height), (200, 200));
+
+    // The icon passes without a threshold; a range keeps only its pages
+    assert_eq!(extract_pdf_images(&path, None, 0).unwrap().len(), 3);
+    let third = extract_pdf_images(&path, Some(2..3), 0).unwrap();
+    assert_eq!(
+      third.iter().map(|image| image.page).collect::<Vec<_>>(),
+      vec![3]
+    );
+    assert!(matches!(
+      extract_pdf_images(&path, Some(2..4), 0),
+      Err(PdfError::InvalidPageRange { page_count: 3, .. })
+    ));
+  }
+
+  #[test]
+  fn png_chunks_carry_their_crc() {
+    let png = encode_png(2, 1, 8, 0, &[0, 10, 20]);
+    assert_eq!(&png[12..16], b"IHDR");
+    let ihdr_crc = u32::from_be_bytes(png[29..33].try_into().unwrap());
+    assert_eq!(ihdr_crc, crc32fast::hash(&png[12..29]));
+    assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+  }
+}
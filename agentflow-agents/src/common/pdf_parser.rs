@@ -1,146 +1,384 @@
 //! Common PDF parsing utilities using StepFun API
+//!
+//! [`PdfParser`] uploads a PDF to the StepFun document parser and polls for
+//! its text. Uploads are cached by the file's content hash, in memory and
+//! optionally in a cache file, so the same PDF is never uploaded twice; a
+//! cached upload the service no longer has is uploaded again. StepFun
+//! parses whole documents, so a page range is cut out of the PDF locally
+//! before the upload.
 
 use serde_json::{Value, json};
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The StepFun API the parser talks to by default
+pub const STEPFUN_API_BASE: &str = "https://api.stepfun.com/v1";
+
+/// Why extracting a PDF failed
+#[derive(Debug, thiserror::Error)]
+pub enum PdfError {
+  #[error("PDF file does not exist: {0}")]
+  NotFound(PathBuf),
+
+  #[error("Failed to read {path}: {source}")]
+  Read {
+    path: PathBuf,
+    #[source]
+    source: std::io::Error,
+  },
+
+  #[error("Invalid page range {start}..{end} for a PDF with {page_count} pages")]
+  InvalidPageRange {
+    start: usize,
+    end: usize,
+    page_count: usize,
+  },
+
+  #[error("Failed to select pages of the PDF: {0}")]
+  PageSelection(String),
+
+  #[error("Failed to parse the PDF: {0}")]
+  Parse(String),
+
+  #[error("PDF upload failed: {0}")]
+  UploadFailed(String),
+
+  #[error("PDF processing did not finish within {0:?}")]
+  ProcessingTimeout(Duration),
+
+  #[error("Content retrieval failed: {0}")]
+  RetrievalFailed(String),
+
+  #[error("No text was extracted from {0}")]
+  EmptyExtraction(String),
+}
 
 /// PDF parser using StepFun Document Parser API
-pub struct StepFunPDFParser {
+#[derive(Clone)]
+pub struct PdfParser {
   api_key: String,
   client: reqwest::Client,
+  base_url: String,
+  poll_interval: Duration,
+  timeout: Duration,
+  /// Uploaded file ids by content hash (and page range)
+  uploads: Arc<Mutex<HashMap<String, String>>>,
+  cache_file: Option<PathBuf>,
 }
 
-impl StepFunPDFParser {
-  pub fn new(api_key: String) -> Self {
+/// The former name of [`PdfParser`]
+pub type StepFunPDFParser = PdfParser;
+
+impl PdfParser {
+  pub fn new(api_key: impl Into<String>) -> Self {
     Self {
-      api_key,
+      api_key: api_key.into(),
       client: reqwest::Client::new(),
+      base_url: STEPFUN_API_BASE.to_string(),
+      poll_interval: Duration::from_secs(3),
+      timeout: Duration::from_secs(60),
+      uploads: Arc::new(Mutex::new(HashMap::new())),
+      cache_file: None,
     }
   }
 
+  /// Talk to another StepFun-compatible endpoint
+  pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+    self.base_url = base_url.into().trim_end_matches('/').to_string();
+    self
+  }
+
+  /// How often to ask whether the uploaded PDF is parsed (default 3s)
+  pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+    self.poll_interval = interval;
+    self
+  }
+
+  /// How long to wait for the PDF to be parsed (default 60s)
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Keep the upload cache in `path` as well, so later runs reuse it too
+  pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+    let path = path.into();
+    let cached: HashMap<String, String> = std::fs::read_to_string(&path)
+      .ok()
+      .and_then(|content| serde_json::from_str(&content).ok())
+      .unwrap_or_default();
+    lock(&self.uploads).extend(cached);
+    self.cache_file = Some(path);
+    self
+  }
+
+  /// `~/.agentflow/cache/pdf_uploads.json`
+  pub fn default_cache_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+      home
+        .join(".agentflow")
+        .join("cache")
+        .join("pdf_uploads.json")
+    })
+  }
+
   /// Extract text content from PDF file
   pub async fn extract_content<P: AsRef<Path>>(
     &self,
     pdf_path: P,
   ) -> crate::AgentResult<PDFContent> {
-    let path = pdf_path.as_ref();
+    Ok(self.extract(pdf_path.as_ref(), None).await?)
+  }
 
+  /// Extract the text of `pages` (0-based, end exclusive; `None` for all)
+  /// of the PDF at `path`
+  pub async fn extract(
+    &self,
+    path: &Path,
+    pages: Option<Range<usize>>,
+  ) -> Result<PDFContent, PdfError> {
     if !path.exists() {
-      return Err(format!("PDF file does not exist: {}", path.display()).into());
+      return Err(PdfError::NotFound(path.to_path_buf()));
     }
-
-    let file_data = tokio::fs::read(path).await?;
-    println!("📄 Uploading PDF: {}", path.display());
-    println!("📋 File size: {} bytes", file_data.len());
+    let file_data = tokio::fs::read(path)
+      .await
+      .map_err(|source| PdfError::Read {
+        path: path.to_path_buf(),
+        source,
+      })?;
 
     // `path.file_name()` returns `None` only for `..`-terminated paths; we
     // already `tokio::fs::read`-ed the file above, so the path must be a
     // real file. Fall back to a sentinel so the upload still succeeds
     // rather than panicking on a degenerate caller-supplied path (Q5.1).
-    let upload_filename = path
+    let filename = path
       .file_name()
       .map(|s| s.to_string_lossy().into_owned())
       .unwrap_or_else(|| "upload.pdf".to_string());
 
-    // Step 1: Upload PDF
+    let mut cache_key = content_hash(&file_data);
+    let file_data = match &pages {
+      Some(range) => {
+        cache_key = format!("{}#pages={}..{}", cache_key, range.start, range.end);
+        select_pages(&file_data, range)?
+      }
+      None => file_data,
+    };
+
+    let cached = lock(&self.uploads).get(&cache_key).cloned();
+    if let Some(file_id) = cached {
+      println!("♻️  Reusing the upload of {}", path.display());
+      match self.wait_for_content(&file_id).await? {
+        Some(content) => return self.content(path, file_id, filename, content),
+        // The service no longer has it; upload it again.
+        None => {
+          lock(&self.uploads).remove(&cache_key);
+        }
+      }
+    }
+
+    println!("📄 Uploading PDF: {}", path.display());
+    println!("📋 File size: {} bytes", file_data.len());
+    let file_id = self.upload(file_data, &filename).await?;
+    lock(&self.uploads).insert(cache_key, file_id.clone());
+    self.save_cache();
+
+    println!("⏳ Processing PDF content extraction...");
+    match self.wait_for_content(&file_id).await? {
+      Some(content) => self.content(path, file_id, filename, content),
+      None => Err(PdfError::RetrievalFailed(format!(
+        "uploaded file {} not found",
+        file_id
+      ))),
+    }
+  }
+
+  async fn upload(&self, file_data: Vec<u8>, filename: &str) -> Result<String, PdfError> {
+    let part = reqwest::multipart::Part::bytes(file_data)
+      .file_name(filename.to_string())
+      .mime_str("application/pdf")
+      .map_err(|e| PdfError::UploadFailed(e.to_string()))?;
     let form = reqwest::multipart::Form::new()
-      .part(
-        "file",
-        reqwest::multipart::Part::bytes(file_data)
-          .file_name(upload_filename)
-          .mime_str("application/pdf")?,
-      )
+      .part("file", part)
       .text("purpose", "file-extract");
 
     let upload_response = self
       .client
-      .post("https://api.stepfun.com/v1/files")
+      .post(format!("{}/files", self.base_url))
       .header("Authorization", format!("Bearer {}", self.api_key))
       .multipart(form)
       .send()
-      .await?;
+      .await
+      .map_err(|e| PdfError::UploadFailed(e.to_string()))?;
 
     if !upload_response.status().is_success() {
+      let status = upload_response.status();
       let error_text = upload_response.text().await.unwrap_or_default();
-      return Err(format!("PDF upload failed: {}", error_text).into());
+      return Err(PdfError::UploadFailed(format!(
+        "{}: {}",
+        status, error_text
+      )));
     }
 
-    let upload_text = upload_response.text().await?;
-    let upload_result: Value = serde_json::from_str(&upload_text)?;
-
-    let file_id = upload_result["id"]
+    let upload_result: Value = upload_response
+      .json()
+      .await
+      .map_err(|e| PdfError::UploadFailed(format!("invalid response: {}", e)))?;
+    upload_result["id"]
       .as_str()
-      .ok_or("No file ID in upload response")?;
-
-    // Step 2: Wait for processing and retrieve content
-    println!("⏳ Processing PDF content extraction...");
-
-    let mut attempts = 0;
-    let max_attempts = 20;
+      .map(str::to_string)
+      .ok_or_else(|| PdfError::UploadFailed("no file ID in upload response".to_string()))
+  }
 
+  /// Poll until the file is parsed; `None` when the service does not have
+  /// the file
+  async fn wait_for_content(&self, file_id: &str) -> Result<Option<String>, PdfError> {
+    let started = Instant::now();
     loop {
-      tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-
       let content_response = self
         .client
-        .get(format!(
-          "https://api.stepfun.com/v1/files/{}/content",
-          file_id
-        ))
+        .get(format!("{}/files/{}/content", self.base_url, file_id))
         .header("Authorization", format!("Bearer {}", self.api_key))
         .send()
-        .await?;
+        .await
+        .map_err(|e| PdfError::RetrievalFailed(e.to_string()))?;
 
       let status = content_response.status();
-
-      if status.as_u16() == 202 {
-        attempts += 1;
-        if attempts >= max_attempts {
-          return Err("PDF processing timeout".into());
+      if status == reqwest::StatusCode::ACCEPTED {
+        if started.elapsed() + self.poll_interval > self.timeout {
+          return Err(PdfError::ProcessingTimeout(self.timeout));
         }
+        tokio::time::sleep(self.poll_interval).await;
         continue;
       }
-
+      if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+      }
       if !status.is_success() {
         let error_text = content_response.text().await.unwrap_or_default();
-        return Err(format!("Content retrieval failed: {}", error_text).into());
+        return Err(PdfError::RetrievalFailed(format!(
+          "{}: {}",
+          status, error_text
+        )));
       }
 
-      let response_text = content_response.text().await?;
+      return content_response
+        .text()
+        .await
+        .map(Some)
+        .map_err(|e| PdfError::RetrievalFailed(e.to_string()));
+    }
+  }
 
-      let content_result: Value =
-        if response_text.trim().starts_with('{') || response_text.trim().starts_with('[') {
-          serde_json::from_str(&response_text)?
-        } else {
-          json!({
-            "content": response_text,
-            "token_count": response_text.len() / 4
-          })
-        };
+  fn content(
+    &self,
+    path: &Path,
+    file_id: String,
+    filename: String,
+    response_text: String,
+  ) -> Result<PDFContent, PdfError> {
+    let content_result: Value =
+      if response_text.trim().starts_with('{') || response_text.trim().starts_with('[') {
+        serde_json::from_str(&response_text)
+          .map_err(|e| PdfError::RetrievalFailed(format!("invalid content response: {}", e)))?
+      } else {
+        json!({
+          "content": response_text,
+          "token_count": response_text.len() / 4
+        })
+      };
 
-      println!("✅ PDF content extracted successfully");
+    let final_content = content_result["content"].as_str().unwrap_or("").to_string();
+    if final_content.trim().is_empty() {
+      return Err(PdfError::EmptyExtraction(path.display().to_string()));
+    }
+    let token_count = content_result["token_count"].as_u64().unwrap_or(0);
 
-      let final_content = content_result["content"].as_str().unwrap_or("").to_string();
-      let token_count = content_result["token_count"].as_u64().unwrap_or(0);
+    println!("✅ PDF content extracted successfully");
+    println!(
+      "📋 Extracted {} characters, ~{} tokens",
+      final_content.len(),
+      token_count
+    );
 
-      println!(
-        "📋 Extracted {} characters, ~{} tokens",
-        final_content.len(),
-        token_count
-      );
+    Ok(PDFContent {
+      file_id,
+      content: final_content,
+      token_count,
+      filename,
+    })
+  }
 
-      return Ok(PDFContent {
-        file_id: file_id.to_string(),
-        content: final_content,
-        token_count,
-        filename: path
-          .file_name()
-          .map(|s| s.to_string_lossy().into_owned())
-          .unwrap_or_else(|| "upload.pdf".to_string()),
+  fn save_cache(&self) {
+    let Some(path) = &self.cache_file else {
+      return;
+    };
+    let saved = serde_json::to_string_pretty(&*lock(&self.uploads))
+      .map_err(std::io::Error::other)
+      .and_then(|json| {
+        if let Some(dir) = path.parent() {
+          std::fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, path)
       });
+    if let Err(e) = saved {
+      tracing::warn!(
+        "Failed to save the PDF upload cache {}: {}",
+        path.display(),
+        e
+      );
     }
   }
 }
 
+fn content_hash(data: &[u8]) -> String {
+  Sha256::digest(data)
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
+
+/// A poisoned lock only means another extraction panicked mid-update; the
+/// cache is still usable
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+  mutex
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The PDF `data` with only `pages` (0-based, end exclusive) left
+pub fn select_pages(data: &[u8], pages: &Range<usize>) -> Result<Vec<u8>, PdfError> {
+  let mut document =
+    lopdf::Document::load_mem(data).map_err(|e| PdfError::PageSelection(e.to_string()))?;
+  let page_count = document.get_pages().len();
+  if pages.start >= pages.end || pages.end > page_count {
+    return Err(PdfError::InvalidPageRange {
+      start: pages.start,
+      end: pages.end,
+      page_count,
+    });
+  }
+
+  // lopdf numbers pages from 1
+  let dropped: Vec<u32> = (1..=page_count as u32)
+    .filter(|number| !pages.contains(&(*number as usize - 1)))
+    .collect();
+  document.delete_pages(&dropped);
+  document.prune_objects();
+
+  let mut selected = Vec::new();
+  document
+    .save_to(&mut selected)
+    .map_err(|e| PdfError::PageSelection(e.to_string()))?;
+  Ok(selected)
+}
+
 /// PDF content structure
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PDFContent {
@@ -149,3 +387,199 @@ pub struct PDFContent {
   pub token_count: u64,
   pub filename: String,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lopdf::{Document, Object, dictionary};
+  use wiremock::matchers::{method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  /// A PDF of `count` empty pages
+  fn pdf(count: usize) -> Vec<u8> {
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+    let kids: Vec<Object> = (0..count)
+      .map(|_| {
+        document
+          .add_object(dictionary! { "Type" => "Page", "Parent" => pages_id })
+          .into()
+      })
+      .collect();
+    document.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => kids,
+        "Count" => count as i64,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+      }),
+    );
+    let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    document.trailer.set("Root", catalog_id);
+    let mut data = Vec::new();
+    document.save_to(&mut data).unwrap();
+    data
+  }
+
+  fn parser(server: &MockServer) -> PdfParser {
+    PdfParser::new("test-key")
+      .with_base_url(server.uri())
+      .with_poll_interval(Duration::from_millis(5))
+      .with_timeout(Duration::from_millis(200))
+  }
+
+  async fn mock_upload(server: &MockServer, file_id: &str, times: u64) {
+    Mock::given(method("POST"))
+      .and(path("/files"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": file_id })))
+      .expect(times)
+      .mount(server)
+      .await;
+  }
+
+  #[tokio::test]
+  async fn cached_uploads_are_not_uploaded_again() {
+    let server = MockServer::start().await;
+    mock_upload(&server, "file-1", 1).await;
+    // Still processing on the first poll
+    Mock::given(method("GET"))
+      .and(path("/files/file-1/content"))
+      .respond_with(ResponseTemplate::new(202))
+      .up_to_n_times(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/files/file-1/content"))
+      .respond_with(ResponseTemplate::new(200).set_body_string("Attention is all you need"))
+      .mount(&server)
+      .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let paper = dir.path().join("paper.pdf");
+    std::fs::write(&paper, pdf(3)).unwrap();
+    let cache_file = dir.path().join("cache/uploads.json");
+
+    let first = parser(&server).with_cache_file(&cache_file);
+    let content = first.extract(&paper, None).await.unwrap();
+    assert_eq!(
+      (
+        content.file_id.as_str(),
+        content.content.as_str(),
+        content.filename.as_str()
+      ),
+      ("file-1", "Attention is all you need", "paper.pdf")
+    );
+    first.extract(&paper, None).await.unwrap();
+
+    // A copy of the same PDF, through a parser reading the cache file
+    let copy = dir.path().join("copy.pdf");
+    std::fs::copy(&paper, &copy).unwrap();
+    let second = parser(&server).with_cache_file(&cache_file);
+    assert_eq!(second.extract(&copy, None).await.unwrap().file_id, "file-1");
+    // `expect(1)` on the upload is checked when the server drops.
+  }
+
+  #[tokio::test]
+  async fn a_page_range_or_a_forgotten_upload_uploads_again() {
+    let server = MockServer::start().await;
+    mock_upload(&server, "file-2", 2).await;
+    Mock::given(method("GET"))
+      .and(path("/files/file-2/content"))
+      .respond_with(
+        ResponseTemplate::new(200).set_body_json(json!({ "content": "Pages", "token_count": 1 })),
+      )
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/files/gone/content"))
+      .respond_with(ResponseTemplate::new(404))
+      .mount(&server)
+      .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let paper = dir.path().join("paper.pdf");
+    let data = pdf(3);
+    std::fs::write(&paper, &data).unwrap();
+
+    let parser = parser(&server);
+    lock(&parser.uploads).insert(content_hash(&data), "gone".to_string());
+    let content = parser.extract(&paper, None).await.unwrap();
+    assert_eq!(
+      (content.file_id.as_str(), content.token_count),
+      ("file-2", 1)
+    );
+
+    // The range is a different upload, then cached like any other.
+    parser.extract(&paper, Some(1..3)).await.unwrap();
+    parser.extract(&paper, Some(1..3)).await.unwrap();
+    assert_eq!(lock(&parser.uploads).len(), 2);
+  }
+
+  #[tokio::test]
+  async fn errors_say_which_step_failed() {
+    let server = MockServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let paper = dir.path().join("paper.pdf");
+    std::fs::write(&paper, pdf(1)).unwrap();
+
+    let missing = parser(&server)
+      .extract(&dir.path().join("missing.pdf"), None)
+      .await;
+    assert!(matches!(missing, Err(PdfError::NotFound(_))));
+
+    Mock::given(method("POST"))
+      .and(path("/files"))
+      .respond_with(ResponseTemplate::new(500).set_body_string("quota exceeded"))
+      .up_to_n_times(1)
+      .mount(&server)
+      .await;
+    let upload = parser(&server).extract(&paper, None).await;
+    assert!(
+      matches!(upload, Err(PdfError::UploadFailed(message)) if message.contains("quota exceeded"))
+    );
+
+    mock_upload(&server, "slow", 1).await;
+    Mock::given(method("GET"))
+      .and(path("/files/slow/content"))
+      .respond_with(ResponseTemplate::new(202))
+      .mount(&server)
+      .await;
+    let timeout = parser(&server).extract(&paper, None).await;
+    assert!(matches!(timeout, Err(PdfError::ProcessingTimeout(_))));
+
+    server.reset().await;
+    mock_upload(&server, "blank", 1).await;
+    Mock::given(method("GET"))
+      .and(path("/files/blank/content"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "content": "  \n" })))
+      .mount(&server)
+      .await;
+    let empty = parser(&server).extract(&paper, None).await;
+    assert!(matches!(empty, Err(PdfError::EmptyExtraction(_))));
+
+    let range = parser(&server).extract(&paper, Some(0..2)).await;
+    assert!(matches!(
+      range,
+      Err(PdfError::InvalidPageRange {
+        start: 0,
+        end: 2,
+        page_count: 1
+      })
+    ));
+  }
+
+  #[test]
+  fn select_pages_keeps_only_the_range() {
+    let selected = select_pages(&pdf(5), &(1..3)).unwrap();
+    assert_eq!(Document::load_mem(&selected).unwrap().get_pages().len(), 2);
+    assert!(matches!(
+      select_pages(&pdf(5), &(3..3)),
+      Err(PdfError::InvalidPageRange { .. })
+    ));
+    assert!(matches!(
+      select_pages(b"not a pdf", &(0..1)),
+      Err(PdfError::PageSelection(_))
+    ));
+  }
+}
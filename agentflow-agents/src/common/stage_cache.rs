@@ -0,0 +1,356 @@
+//! Stage output cache shared by the agents
+//!
+//! A [`StageCache`] keeps what an agent's stage produced, keyed by the
+//! stage's name, a hash of its input, the model and a hash of the prompt
+//! template, so rerunning an agent on the same paper only calls the models
+//! for the stages whose input, model or prompt changed. Entries are JSON
+//! files under `~/.agentflow/cache/agents/<stage>/` by default.
+//! [`CacheControl`] turns reading and writing on or off, and the hits and
+//! misses of each stage are counted for the end of the run.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Whether a [`StageCache`] is read and written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheControl {
+  /// Neither read nor written
+  Off,
+  /// Read, but new outputs are not saved
+  Read,
+  /// Read, and new outputs saved
+  #[default]
+  ReadWrite,
+}
+
+impl CacheControl {
+  pub fn reads(self) -> bool {
+    self != Self::Off
+  }
+
+  pub fn writes(self) -> bool {
+    self == Self::ReadWrite
+  }
+}
+
+/// What a stage's cached output depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageCacheKey {
+  pub stage: String,
+  /// SHA-256 of the stage's input
+  pub input_hash: String,
+  pub model: String,
+  /// SHA-256 of the prompt template
+  pub prompt_hash: String,
+}
+
+impl StageCacheKey {
+  pub fn new(stage: &str, input: &str, model: &str, prompt_template: &str) -> Self {
+    Self {
+      stage: stage.to_string(),
+      input_hash: sha256_hex(input.as_bytes()),
+      model: model.to_string(),
+      prompt_hash: sha256_hex(prompt_template.as_bytes()),
+    }
+  }
+
+  /// The entry's file name under its stage's directory
+  fn file_name(&self) -> String {
+    let key = format!(
+      "{}\n{}\n{}\n{}",
+      self.stage, self.input_hash, self.model, self.prompt_hash
+    );
+    format!("{}.json", &sha256_hex(key.as_bytes())[..32])
+  }
+}
+
+/// Hits and misses of one stage, or of a whole run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+  pub hits: usize,
+  pub misses: usize,
+  /// Outputs saved to the cache
+  pub writes: usize,
+}
+
+impl std::ops::AddAssign for CacheStats {
+  fn add_assign(&mut self, other: Self) {
+    self.hits += other.hits;
+    self.misses += other.misses;
+    self.writes += other.writes;
+  }
+}
+
+impl std::fmt::Display for CacheStats {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} hit(s), {} miss(es), {} saved",
+      self.hits, self.misses, self.writes
+    )
+  }
+}
+
+/// Stage outputs on disk, by [`StageCacheKey`]. Clones share their
+/// statistics, so every stage of a run can hold one.
+#[derive(Debug, Clone)]
+pub struct StageCache {
+  dir: PathBuf,
+  control: CacheControl,
+  stats: Arc<Mutex<BTreeMap<String, CacheStats>>>,
+}
+
+impl StageCache {
+  pub fn new(dir: impl Into<PathBuf>, control: CacheControl) -> Self {
+    Self {
+      dir: dir.into(),
+      control,
+      stats: Arc::default(),
+    }
+  }
+
+  /// A cache in [`Self::default_dir`], or one that is off without a home
+  /// directory
+  pub fn at_default_dir(control: CacheControl) -> Self {
+    match Self::default_dir() {
+      Some(dir) => Self::new(dir, control),
+      None => Self::new(PathBuf::new(), CacheControl::Off),
+    }
+  }
+
+  /// `~/.agentflow/cache/agents`
+  pub fn default_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".agentflow").join("cache").join("agents"))
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  pub fn control(&self) -> CacheControl {
+    self.control
+  }
+
+  /// The output `key` has in the cache. A missing or unreadable entry is
+  /// a miss; nothing is counted while the cache is off.
+  pub fn get<T: DeserializeOwned>(&self, key: &StageCacheKey) -> Option<T> {
+    if !self.control.reads() {
+      return None;
+    }
+    let path = self.entry_path(key);
+    let output = std::fs::read_to_string(&path)
+      .ok()
+      .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+      .and_then(|entry| serde_json::from_value(entry["output"].clone()).ok());
+    self.record(&key.stage, |stats| match output {
+      Some(_) => stats.hits += 1,
+      None => stats.misses += 1,
+    });
+    if output.is_some() {
+      tracing::debug!(stage = %key.stage, "Stage output read from the cache");
+    }
+    output
+  }
+
+  /// Save `output` under `key` when the cache is written. Failing to save
+  /// is logged; the run goes on without it.
+  pub fn put<T: Serialize>(&self, key: &StageCacheKey, output: &T) {
+    if !self.control.writes() {
+      return;
+    }
+    let path = self.entry_path(key);
+    let entry = json!({
+      "stage": key.stage,
+      "model": key.model,
+      "input_hash": key.input_hash,
+      "prompt_hash": key.prompt_hash,
+      "created_at": chrono::Utc::now().to_rfc3339(),
+      "output": output,
+    });
+    let saved = serde_json::to_string_pretty(&entry)
+      .map_err(std::io::Error::other)
+      .and_then(|json| {
+        if let Some(dir) = path.parent() {
+          std::fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &path)
+      });
+    match saved {
+      Ok(()) => self.record(&key.stage, |stats| stats.writes += 1),
+      Err(e) => tracing::warn!(
+        "Failed to save the {} output to the cache {}: {}",
+        key.stage,
+        path.display(),
+        e
+      ),
+    }
+  }
+
+  /// The cached output of `key`, or the output of `compute`, which is then
+  /// cached
+  pub async fn get_or_compute<T, E, F, Fut>(&self, key: &StageCacheKey, compute: F) -> Result<T, E>
+  where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+  {
+    if let Some(output) = self.get(key) {
+      return Ok(output);
+    }
+    let output = compute().await?;
+    self.put(key, &output);
+    Ok(output)
+  }
+
+  /// Hits and misses of the whole run so far
+  pub fn stats(&self) -> CacheStats {
+    let mut total = CacheStats::default();
+    for stats in lock(&self.stats).values() {
+      total += *stats;
+    }
+    total
+  }
+
+  /// Hits and misses by stage name
+  pub fn stage_stats(&self) -> BTreeMap<String, CacheStats> {
+    lock(&self.stats).clone()
+  }
+
+  fn entry_path(&self, key: &StageCacheKey) -> PathBuf {
+    self
+      .dir
+      .join(crate::sanitize_filename(&key.stage, 64))
+      .join(key.file_name())
+  }
+
+  fn record(&self, stage: &str, update: impl FnOnce(&mut CacheStats)) {
+    update(lock(&self.stats).entry(stage.to_string()).or_default());
+  }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+  Sha256::digest(data)
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
+
+/// A poisoned lock only means another stage panicked mid-update; the
+/// counts are still usable
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+  mutex
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  async fn summarize(cache: &StageCache, prompt: &str, calls: &AtomicUsize) -> String {
+    let key = StageCacheKey::new("summary", "paper text", "qwen-turbo", prompt);
+    cache
+      .get_or_compute(&key, || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok::<_, std::io::Error>(format!("summary of {}", prompt))
+      })
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn identical_inputs_hit_and_a_changed_prompt_misses() {
+    let dir = tempfile::tempdir().unwrap();
+    let calls = AtomicUsize::new(0);
+
+    let first = StageCache::new(dir.path(), CacheControl::ReadWrite);
+    assert_eq!(
+      summarize(&first, "Summarize: {{paper_content}}", &calls).await,
+      "summary of Summarize: {{paper_content}}"
+    );
+
+    // A later run reads what the first one saved.
+    let second = StageCache::new(dir.path(), CacheControl::ReadWrite);
+    assert_eq!(
+      summarize(&second, "Summarize: {{paper_content}}", &calls).await,
+      "summary of Summarize: {{paper_content}}"
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(
+      second.stats(),
+      CacheStats {
+        hits: 1,
+        misses: 0,
+        writes: 0
+      }
+    );
+
+    summarize(&second, "Summarize briefly: {{paper_content}}", &calls).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+      second.stage_stats()["summary"],
+      CacheStats {
+        hits: 1,
+        misses: 1,
+        writes: 1
+      }
+    );
+
+    // Another model misses too.
+    let key = StageCacheKey::new(
+      "summary",
+      "paper text",
+      "gpt-4o",
+      "Summarize: {{paper_content}}",
+    );
+    assert_eq!(second.get::<String>(&key), None);
+  }
+
+  #[tokio::test]
+  async fn read_only_and_off_caches() {
+    let dir = tempfile::tempdir().unwrap();
+    let calls = AtomicUsize::new(0);
+
+    let read_only = StageCache::new(dir.path(), CacheControl::Read);
+    summarize(&read_only, "p", &calls).await;
+    summarize(&read_only, "p", &calls).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+      read_only.stats(),
+      CacheStats {
+        hits: 0,
+        misses: 2,
+        writes: 0
+      }
+    );
+
+    StageCache::new(dir.path(), CacheControl::ReadWrite).put(
+      &StageCacheKey::new("summary", "paper text", "qwen-turbo", "p"),
+      &"saved",
+    );
+    let off = StageCache::new(dir.path(), CacheControl::Off);
+    assert_eq!(summarize(&off, "p", &calls).await, "summary of p");
+    assert_eq!(off.stats(), CacheStats::default());
+    assert_eq!(summarize(&read_only, "p", &calls).await, "saved");
+  }
+
+  #[test]
+  fn cache_control_reads_snake_case() {
+    let control: CacheControl = serde_json::from_str("\"read_write\"").unwrap();
+    assert_eq!(control, CacheControl::ReadWrite);
+    assert_eq!(
+      serde_json::to_string(&CacheControl::Off).unwrap(),
+      "\"off\""
+    );
+  }
+}
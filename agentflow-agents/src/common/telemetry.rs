@@ -0,0 +1,417 @@
+//! Per-stage telemetry shared by the agents
+//!
+//! A [`StageMetrics`] sink collects one [`StageMetric`] per stage run: how
+//! long it took, the model it called, the tokens the model reported, what
+//! that cost and how often it retried. Runs of the same stage (the chunks
+//! of a translation, say) are added up into one row, and
+//! [`stage_metrics_table`] renders the rows for the markdown outputs and
+//! the end of a CLI run.
+
+use crate::eval::PricingTable;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// What one stage of an agent run took
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageMetric {
+  pub stage: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub model: Option<String>,
+  /// `name@version` of the prompt template the stage rendered, so runs of
+  /// two versions can be compared
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub prompt: Option<String>,
+  pub duration_ms: u64,
+  /// Model calls the stage made; a run served from a cache or checkpoint
+  /// makes none
+  #[serde(default)]
+  pub calls: u32,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub prompt_tokens: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub completion_tokens: Option<u64>,
+  /// USD, when the model has a price and reported its tokens
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost_usd: Option<f64>,
+  #[serde(default)]
+  pub retries: u32,
+}
+
+impl StageMetric {
+  pub fn new(stage: impl Into<String>, duration: Duration) -> Self {
+    Self {
+      stage: stage.into(),
+      duration_ms: duration.as_millis() as u64,
+      ..Self::default()
+    }
+  }
+
+  pub fn with_model(mut self, model: impl Into<String>) -> Self {
+    self.model = Some(model.into());
+    self
+  }
+
+  pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+    self.prompt = Some(prompt.into());
+    self
+  }
+
+  /// One model call and the tokens it reported
+  pub fn with_call(mut self, prompt_tokens: Option<u64>, completion_tokens: Option<u64>) -> Self {
+    self.calls += 1;
+    self.prompt_tokens = add(self.prompt_tokens, prompt_tokens);
+    self.completion_tokens = add(self.completion_tokens, completion_tokens);
+    self
+  }
+
+  pub fn with_retries(mut self, retries: u32) -> Self {
+    self.retries = retries;
+    self
+  }
+
+  /// Add `other`, a later run of the same stage
+  fn merge(&mut self, other: StageMetric) {
+    self.model = self.model.take().or(other.model);
+    self.prompt = self.prompt.take().or(other.prompt);
+    self.duration_ms += other.duration_ms;
+    self.calls += other.calls;
+    self.prompt_tokens = add(self.prompt_tokens, other.prompt_tokens);
+    self.completion_tokens = add(self.completion_tokens, other.completion_tokens);
+    self.cost_usd = match (self.cost_usd, other.cost_usd) {
+      (Some(a), Some(b)) => Some(a + b),
+      (a, b) => a.or(b),
+    };
+    self.retries += other.retries;
+  }
+}
+
+/// The sum of two token counts, either of which may be unreported
+fn add(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a + b),
+    (a, b) => a.or(b),
+  }
+}
+
+/// Where a run's stages record their metrics. Clones share the records,
+/// so every stage of a run can hold one.
+#[derive(Debug, Clone, Default)]
+pub struct StageMetrics {
+  records: Arc<Mutex<Vec<StageMetric>>>,
+  pricing: Option<Arc<PricingTable>>,
+  recorded: Arc<Notify>,
+  /// Sink every record is passed on to, for a [`Self::child`]
+  parent: Option<Box<StageMetrics>>,
+}
+
+impl StageMetrics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Price the recorded tokens with `pricing`; without it no cost is
+  /// recorded
+  pub fn with_pricing(mut self, pricing: PricingTable) -> Self {
+    self.pricing = Some(Arc::new(pricing));
+    self
+  }
+
+  /// An empty sink for one run, priced as this one, that also records
+  /// each run here: watching the child counts only that run's stages
+  pub fn child(&self) -> Self {
+    Self {
+      pricing: self.pricing.clone(),
+      parent: Some(Box::new(self.clone())),
+      ..Self::default()
+    }
+  }
+
+  /// Record one run of a stage, pricing its tokens
+  pub fn record(&self, mut metric: StageMetric) {
+    if let (Some(pricing), Some(model)) = (&self.pricing, &metric.model)
+      && (metric.prompt_tokens.is_some() || metric.completion_tokens.is_some())
+    {
+      let tokens = |n: Option<u64>| n.map(|n| n.min(u32::MAX as u64) as u32);
+      metric.cost_usd = Some(pricing.lookup(model).cost_for_call(
+        tokens(metric.prompt_tokens),
+        tokens(metric.completion_tokens),
+      ));
+    }
+    self.push(metric);
+  }
+
+  fn push(&self, metric: StageMetric) {
+    if let Some(parent) = &self.parent {
+      parent.push(metric.clone());
+    }
+    self
+      .records
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .push(metric);
+    self.recorded.notify_waiters();
+  }
+
+  /// One row per stage, in the order the stages first finished
+  pub fn metrics(&self) -> Vec<StageMetric> {
+    self.metrics_since(0)
+  }
+
+  /// How many runs have been recorded, to pass to [`Self::metrics_since`]
+  pub fn mark(&self) -> usize {
+    self.records.lock().unwrap_or_else(|e| e.into_inner()).len()
+  }
+
+  /// [`Self::metrics`] of the runs recorded after `mark`
+  pub fn metrics_since(&self, mark: usize) -> Vec<StageMetric> {
+    let records = self
+      .records
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .get(mark..)
+      .unwrap_or_default()
+      .to_vec();
+    let mut rows: Vec<StageMetric> = Vec::new();
+    for record in records {
+      match rows.iter_mut().find(|row| row.stage == record.stage) {
+        Some(row) => row.merge(record),
+        None => rows.push(record),
+      }
+    }
+    rows
+  }
+
+  /// Resolves once the runs recorded after `mark` cost more than `limit`
+  /// USD, with what they cost. Never resolves without pricing.
+  pub async fn cost_exceeds(&self, mark: usize, limit: f64) -> f64 {
+    loop {
+      // Registered before the check, so a record in between still wakes it
+      let recorded = self.recorded.notified();
+      if let Some(cost) = stage_metrics_total(&self.metrics_since(mark)).cost_usd
+        && cost > limit
+      {
+        return cost;
+      }
+      recorded.await;
+    }
+  }
+}
+
+/// The rows added up, as the `total` row of a table
+pub fn stage_metrics_total(metrics: &[StageMetric]) -> StageMetric {
+  let mut total = StageMetric::new("total", Duration::ZERO);
+  for metric in metrics {
+    total.merge(StageMetric {
+      model: None,
+      prompt: None,
+      ..metric.clone()
+    });
+  }
+  total
+}
+
+/// A markdown table of `metrics` with a total row; empty without metrics
+pub fn stage_metrics_table(metrics: &[StageMetric]) -> String {
+  if metrics.is_empty() {
+    return String::new();
+  }
+  let count = |n: Option<u64>| n.map_or("-".to_string(), |n| n.to_string());
+  let row = |metric: &StageMetric| {
+    format!(
+      "| {} | {} | {:.1} | {} | {} | {} | {} | {} |\n",
+      metric.stage,
+      metric.model.as_deref().unwrap_or("-"),
+      metric.duration_ms as f64 / 1000.0,
+      metric.calls,
+      count(metric.prompt_tokens),
+      count(metric.completion_tokens),
+      metric
+        .cost_usd
+        .map_or("-".to_string(), |cost| format!("${:.4}", cost)),
+      metric.retries,
+    )
+  };
+
+  let mut table = String::from(
+    "| Stage | Model | Duration (s) | Calls | Prompt tokens | Completion tokens | Cost | Retries |\n\
+     |---|---|---:|---:|---:|---:|---:|---:|\n",
+  );
+  for metric in metrics {
+    table.push_str(&row(metric));
+  }
+  table.push_str(&row(&stage_metrics_total(metrics)));
+  table
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::eval::ModelPricing;
+
+  fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+  }
+
+  #[test]
+  fn runs_of_a_stage_are_added_up_in_first_seen_order() {
+    let metrics = StageMetrics::new();
+    let sink = metrics.clone();
+    sink.record(
+      StageMetric::new("translation", ms(300))
+        .with_model("m")
+        .with_prompt("translate@2")
+        .with_call(Some(100), Some(80)),
+    );
+    sink.record(
+      StageMetric::new("summary", ms(1200))
+        .with_model("m")
+        .with_call(Some(500), None),
+    );
+    sink.record(
+      StageMetric::new("translation", ms(200))
+        .with_call(Some(50), Some(40))
+        .with_retries(1),
+    );
+    sink.record(StageMetric::new("poster", ms(10)));
+
+    let rows = metrics.metrics();
+    assert_eq!(
+      rows
+        .iter()
+        .map(|row| row.stage.as_str())
+        .collect::<Vec<_>>(),
+      vec!["translation", "summary", "poster"]
+    );
+    assert_eq!(rows[0].duration_ms, 500);
+    assert_eq!(rows[0].calls, 2);
+    assert_eq!(rows[0].prompt_tokens, Some(150));
+    assert_eq!(rows[0].completion_tokens, Some(120));
+    assert_eq!(rows[0].retries, 1);
+    assert_eq!(rows[0].model.as_deref(), Some("m"));
+    assert_eq!(rows[0].prompt.as_deref(), Some("translate@2"));
+    assert_eq!(rows[1].completion_tokens, None);
+    assert_eq!(rows[2].calls, 0);
+
+    let total = stage_metrics_total(&rows);
+    assert_eq!(total.duration_ms, 1710);
+    assert_eq!(total.prompt_tokens, Some(650));
+    assert_eq!(total.calls, 3);
+  }
+
+  #[test]
+  fn tokens_are_priced_only_with_a_pricing_table() {
+    let pricing = PricingTable::default().with_model(
+      "m",
+      ModelPricing {
+        input_per_1k: 1.0,
+        output_per_1k: 2.0,
+      },
+    );
+    let priced = StageMetrics::new().with_pricing(pricing);
+    priced.record(
+      StageMetric::new("summary", ms(1))
+        .with_model("m")
+        .with_call(Some(1000), Some(500)),
+    );
+    priced.record(StageMetric::new("poster", ms(1)).with_model("m"));
+    let rows = priced.metrics();
+    assert_eq!(rows[0].cost_usd, Some(2.0));
+    assert_eq!(rows[1].cost_usd, None);
+
+    let unpriced = StageMetrics::new();
+    unpriced.record(
+      StageMetric::new("summary", ms(1))
+        .with_model("m")
+        .with_call(Some(1000), Some(500)),
+    );
+    assert_eq!(unpriced.metrics()[0].cost_usd, None);
+  }
+
+  #[tokio::test]
+  async fn cost_is_watched_from_a_mark() {
+    let pricing = PricingTable::default().with_model(
+      "m",
+      ModelPricing {
+        input_per_1k: 1.0,
+        output_per_1k: 0.0,
+      },
+    );
+    let metrics = StageMetrics::new().with_pricing(pricing);
+    let call = |tokens| {
+      StageMetric::new("summary", ms(1))
+        .with_model("m")
+        .with_call(Some(tokens), None)
+    };
+    metrics.record(call(5000));
+    let mark = metrics.mark();
+    assert_eq!(mark, 1);
+
+    let watcher = tokio::spawn({
+      let metrics = metrics.clone();
+      async move { metrics.cost_exceeds(mark, 1.5).await }
+    });
+    metrics.record(call(1000));
+    tokio::time::sleep(ms(20)).await;
+    assert!(!watcher.is_finished());
+    metrics.record(call(1000));
+    assert_eq!(watcher.await.unwrap(), 2.0);
+    assert_eq!(metrics.metrics_since(mark)[0].calls, 2);
+    assert!(metrics.metrics_since(10).is_empty());
+  }
+
+  #[test]
+  fn a_child_counts_only_its_own_runs() {
+    let pricing = PricingTable::default().with_model(
+      "m",
+      ModelPricing {
+        input_per_1k: 1.0,
+        output_per_1k: 0.0,
+      },
+    );
+    let shared = StageMetrics::new().with_pricing(pricing);
+    let (first, second) = (shared.child(), shared.child());
+    let call = StageMetric::new("summary", ms(1))
+      .with_model("m")
+      .with_call(Some(1000), None);
+    first.record(call.clone());
+    second.record(call.clone());
+    second.record(call);
+
+    assert_eq!(first.metrics()[0].cost_usd, Some(1.0));
+    assert_eq!(second.metrics()[0].cost_usd, Some(2.0));
+    assert_eq!(shared.metrics()[0].calls, 3);
+    assert_eq!(shared.metrics()[0].cost_usd, Some(3.0));
+  }
+
+  #[test]
+  fn metrics_round_trip_through_json_and_render_a_table() {
+    let rows = vec![
+      StageMetric {
+        cost_usd: Some(0.0125),
+        ..StageMetric::new("summary", ms(1500))
+          .with_model("gpt-4o")
+          .with_call(Some(1200), Some(300))
+      },
+      StageMetric::new("poster", ms(260)).with_retries(1),
+    ];
+    let json = serde_json::to_value(&rows).unwrap();
+    assert_eq!(
+      json[1],
+      serde_json::json!({
+        "stage": "poster", "duration_ms": 260, "calls": 0, "retries": 1
+      })
+    );
+    assert_eq!(
+      serde_json::from_value::<Vec<StageMetric>>(json).unwrap(),
+      rows
+    );
+
+    let table = stage_metrics_table(&rows);
+    assert!(table.contains("| summary | gpt-4o | 1.5 | 1 | 1200 | 300 | $0.0125 | 0 |"));
+    assert!(table.contains("| poster | - | 0.3 | 0 | - | - | - | 1 |"));
+    assert!(table.contains("| total | - | 1.8 | 1 | 1200 | 300 | $0.0125 | 1 |"));
+    assert_eq!(stage_metrics_table(&[]), "");
+  }
+}
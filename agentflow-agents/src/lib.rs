@@ -6,6 +6,7 @@
 //! ## ReAct Agent (Phase 1)
 //! Use [`react::ReActAgent`] for autonomous Thought/Action/Observation loops.
 
+pub mod cli;
 pub mod common;
 pub mod dynamic;
 pub mod eval;
@@ -23,6 +24,7 @@ pub mod tools;
 pub mod traits;
 
 // Re-export common types and utilities
+pub use cli::{CliEnv, agent_command, load_agent_config, run_agent_cli, run_agent_cli_with};
 pub use common::*;
 pub use traits::*;
 
@@ -41,7 +43,9 @@ pub use agentflow_tools;
 // Re-export M3 multi-agent building blocks
 pub use nodes::{
   AgentNode, AgentNodeResumeContract, AgentNodeResumeMode, AgentNodeToolReplayPolicy,
-  AgentNodeToolResumeRecord,
+  AgentNodeToolResumeRecord, ChunkUsage, EnrichmentNode, GlossaryViolation, PaperEnrichment,
+  RelatedPaper, SubAgentBudget, SubAgentNode, SubAgentTelemetry, Translation, TranslationChunk,
+  TranslatorNode, glossary_violations,
 };
 pub use plan_execute::{PlanExecuteAgent, PlanExecuteConfig, PlanExecuteError, PlanExecuteStep};
 pub use react::{
@@ -0,0 +1,638 @@
+//! `EnrichmentNode` — citation context of an arXiv paper from the
+//! Semantic Scholar public API.
+//!
+//! The paper is looked up by its arXiv id for its citation count,
+//! influential citations and fields of study, and the recommendations
+//! endpoint gives the related papers. Requests are spaced at least
+//! `min_interval` apart (the public API allows about one a second), and a
+//! `429` is waited out and retried. Enrichments are kept in a cache
+//! directory for `cache_ttl`; when the API cannot be reached a stale entry
+//! is used, and without one the enrichment is empty with a `warning`. The
+//! node never fails a flow because Semantic Scholar is down.
+//!
+//! # Input keys
+//! | Key        | Type                      | Required |
+//! |------------|---------------------------|----------|
+//! | `arxiv_id` | `FlowValue::Json(String)` id or arXiv URL | yes |
+//!
+//! # Output keys
+//! | Key          | Type                                        |
+//! |--------------|---------------------------------------------|
+//! | `enrichment` | `FlowValue::Json(Object)` [`PaperEnrichment`] |
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use agentflow_graph::{
+  AsyncNode,
+  async_node::{AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// The Semantic Scholar API the node talks to by default
+pub const SEMANTIC_SCHOLAR_API_BASE: &str = "https://api.semanticscholar.org";
+
+const PAPER_FIELDS: &str =
+  "paperId,title,citationCount,influentialCitationCount,fieldsOfStudy,s2FieldsOfStudy";
+const RELATED_FIELDS: &str = "title,year,citationCount,url";
+
+/// Times a rate-limited request is retried
+const RATE_LIMIT_RETRIES: usize = 2;
+/// The longest `Retry-After` waited out
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// What Semantic Scholar knows of a paper's reception
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaperEnrichment {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub semantic_scholar_id: Option<String>,
+  #[serde(default)]
+  pub citation_count: Option<u64>,
+  #[serde(default)]
+  pub influential_citation_count: Option<u64>,
+  #[serde(default)]
+  pub fields_of_study: Vec<String>,
+  #[serde(default)]
+  pub related_papers: Vec<RelatedPaper>,
+  /// Why the enrichment is empty, partial or out of date
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub warning: Option<String>,
+}
+
+impl PaperEnrichment {
+  /// An enrichment with nothing in it but `warning`
+  pub fn degraded(warning: impl Into<String>) -> Self {
+    Self {
+      warning: Some(warning.into()),
+      ..Self::default()
+    }
+  }
+
+  /// Whether there is nothing to show
+  pub fn is_empty(&self) -> bool {
+    self.citation_count.is_none()
+      && self.influential_citation_count.is_none()
+      && self.fields_of_study.is_empty()
+      && self.related_papers.is_empty()
+  }
+}
+
+/// A paper Semantic Scholar recommends alongside the enriched one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RelatedPaper {
+  pub title: String,
+  #[serde(default)]
+  pub year: Option<i32>,
+  #[serde(default)]
+  pub citation_count: Option<u64>,
+  #[serde(default)]
+  pub url: Option<String>,
+}
+
+/// A cache file: the enrichment and when it was fetched
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+  fetched_at: DateTime<Utc>,
+  enrichment: PaperEnrichment,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnrichmentNode {
+  pub name: String,
+  pub base_url: String,
+  /// Sent as `x-api-key`; the public API needs none
+  pub api_key: Option<String>,
+  /// Related papers asked for
+  pub max_related: usize,
+  /// Least time between two requests
+  pub min_interval: Duration,
+  /// Where enrichments are kept, one JSON file per paper
+  pub cache_dir: Option<PathBuf>,
+  /// How long a cached enrichment is used without asking again
+  pub cache_ttl: Duration,
+  client: reqwest::Client,
+  /// When the last request went out; clones space their requests together
+  last_request: Arc<tokio::sync::Mutex<Option<Instant>>>,
+}
+
+impl EnrichmentNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      base_url: SEMANTIC_SCHOLAR_API_BASE.to_string(),
+      api_key: None,
+      max_related: 5,
+      min_interval: Duration::from_secs(1),
+      cache_dir: None,
+      cache_ttl: Duration::from_secs(7 * 24 * 3600),
+      client: reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default(),
+      last_request: Arc::new(tokio::sync::Mutex::new(None)),
+    }
+  }
+
+  /// Talk to another Semantic Scholar-compatible endpoint
+  pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+    self.base_url = base_url.into().trim_end_matches('/').to_string();
+    self
+  }
+
+  pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+    self.api_key = api_key;
+    self
+  }
+
+  pub fn with_max_related(mut self, max_related: usize) -> Self {
+    self.max_related = max_related;
+    self
+  }
+
+  pub fn with_min_interval(mut self, interval: Duration) -> Self {
+    self.min_interval = interval;
+    self
+  }
+
+  pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.cache_dir = Some(dir.into());
+    self
+  }
+
+  pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+    self.cache_ttl = ttl;
+    self
+  }
+
+  /// `~/.agentflow/cache/semantic_scholar`
+  pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+      home
+        .join(".agentflow")
+        .join("cache")
+        .join("semantic_scholar")
+    })
+  }
+
+  /// The enrichment of the paper `arxiv_id` (an id or arXiv URL). Never
+  /// fails: what could not be fetched is named in `warning`.
+  pub async fn enrich(&self, arxiv_id: &str) -> PaperEnrichment {
+    let id = normalize_arxiv_id(arxiv_id);
+    let cached = self.cached(&id);
+    if let Some((fetched_at, enrichment)) = &cached
+      && age(*fetched_at) < self.cache_ttl
+    {
+      return enrichment.clone();
+    }
+
+    match self.fetch(&id).await {
+      Ok(enrichment) => {
+        if enrichment.warning.is_none() {
+          self.save(&id, &enrichment);
+        }
+        enrichment
+      }
+      Err(e) => {
+        tracing::warn!("Semantic Scholar enrichment of {} failed: {}", id, e);
+        match cached {
+          Some((fetched_at, enrichment)) => PaperEnrichment {
+            warning: Some(format!(
+              "Semantic Scholar unavailable ({}); cached on {}",
+              e,
+              fetched_at.format("%Y-%m-%d")
+            )),
+            ..enrichment
+          },
+          None => PaperEnrichment::degraded(format!("Semantic Scholar unavailable: {}", e)),
+        }
+      }
+    }
+  }
+
+  /// The paper's details, then its recommendations; failing
+  /// recommendations only leave a warning
+  async fn fetch(&self, id: &str) -> Result<PaperEnrichment, String> {
+    let paper = self
+      .get(
+        &format!("{}/graph/v1/paper/arXiv:{}", self.base_url, id),
+        &[("fields", PAPER_FIELDS.to_string())],
+      )
+      .await?;
+    let Some(paper) = paper else {
+      return Ok(PaperEnrichment::degraded(format!(
+        "arXiv:{} is not on Semantic Scholar",
+        id
+      )));
+    };
+
+    let mut enrichment = PaperEnrichment {
+      semantic_scholar_id: paper["paperId"].as_str().map(str::to_string),
+      citation_count: paper["citationCount"].as_u64(),
+      influential_citation_count: paper["influentialCitationCount"].as_u64(),
+      fields_of_study: fields_of_study(&paper),
+      ..PaperEnrichment::default()
+    };
+    if self.max_related == 0 {
+      return Ok(enrichment);
+    }
+
+    let related = self
+      .get(
+        &format!(
+          "{}/recommendations/v1/papers/forpaper/arXiv:{}",
+          self.base_url, id
+        ),
+        &[
+          ("fields", RELATED_FIELDS.to_string()),
+          ("limit", self.max_related.to_string()),
+        ],
+      )
+      .await;
+    match related {
+      Ok(Some(related)) => enrichment.related_papers = related_papers(&related, self.max_related),
+      Ok(None) => {}
+      Err(e) => {
+        tracing::warn!("Semantic Scholar recommendations for {} failed: {}", id, e);
+        enrichment.warning = Some(format!("related papers unavailable: {}", e));
+      }
+    }
+    Ok(enrichment)
+  }
+
+  /// GET `url`, spaced from the previous request and retried while rate
+  /// limited; `None` when Semantic Scholar does not have it
+  async fn get(&self, url: &str, query: &[(&str, String)]) -> Result<Option<Value>, String> {
+    for attempt in 0..=RATE_LIMIT_RETRIES {
+      self.wait_turn().await;
+      let mut request = self.client.get(url).query(query);
+      if let Some(api_key) = &self.api_key {
+        request = request.header("x-api-key", api_key);
+      }
+      let response = request.send().await.map_err(|e| e.to_string())?;
+
+      let status = response.status();
+      if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < RATE_LIMIT_RETRIES {
+        let wait = response
+          .headers()
+          .get(reqwest::header::RETRY_AFTER)
+          .and_then(|value| value.to_str().ok())
+          .and_then(|value| value.parse().ok())
+          .map(Duration::from_secs)
+          .unwrap_or(self.min_interval)
+          .min(MAX_RETRY_AFTER);
+        tracing::debug!("Semantic Scholar rate limited; retrying in {:?}", wait);
+        tokio::time::sleep(wait).await;
+        continue;
+      }
+      if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+      }
+      if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("{}: {}", status, error_text.trim()));
+      }
+      return response
+        .json()
+        .await
+        .map(Some)
+        .map_err(|e| format!("invalid response: {}", e));
+    }
+    Err("rate limited".to_string())
+  }
+
+  /// Sleep until `min_interval` has passed since the last request
+  async fn wait_turn(&self) {
+    let mut last_request = self.last_request.lock().await;
+    if let Some(last) = *last_request {
+      let next = last + self.min_interval;
+      let now = Instant::now();
+      if next > now {
+        tokio::time::sleep(next - now).await;
+      }
+    }
+    *last_request = Some(Instant::now());
+  }
+
+  fn cache_path(&self, id: &str) -> Option<PathBuf> {
+    self
+      .cache_dir
+      .as_ref()
+      .map(|dir| dir.join(format!("{}.json", id.replace('/', "_"))))
+  }
+
+  fn cached(&self, id: &str) -> Option<(DateTime<Utc>, PaperEnrichment)> {
+    let content = std::fs::read_to_string(self.cache_path(id)?).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+    Some((entry.fetched_at, entry.enrichment))
+  }
+
+  /// Keep `enrichment` in the cache. A failed save only costs a request.
+  fn save(&self, id: &str, enrichment: &PaperEnrichment) {
+    let Some(path) = self.cache_path(id) else {
+      return;
+    };
+    let entry = CacheEntry {
+      fetched_at: Utc::now(),
+      enrichment: enrichment.clone(),
+    };
+    if let Err(e) = write_entry(&path, &entry) {
+      tracing::warn!(
+        "Failed to save the Semantic Scholar cache {}: {}",
+        path.display(),
+        e
+      );
+    }
+  }
+}
+
+fn write_entry(path: &Path, entry: &CacheEntry) -> std::io::Result<()> {
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  let json = serde_json::to_string_pretty(entry).map_err(std::io::Error::other)?;
+  let temp = path.with_extension("json.tmp");
+  std::fs::write(&temp, json)?;
+  std::fs::rename(&temp, path)
+}
+
+fn age(fetched_at: DateTime<Utc>) -> Duration {
+  (Utc::now() - fetched_at).to_std().unwrap_or_default()
+}
+
+/// `fieldsOfStudy`, then the categories of `s2FieldsOfStudy`, each once
+fn fields_of_study(paper: &Value) -> Vec<String> {
+  let listed = paper["fieldsOfStudy"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(Value::as_str);
+  let categorized = paper["s2FieldsOfStudy"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|field| field["category"].as_str());
+  let mut fields: Vec<String> = Vec::new();
+  for field in listed.chain(categorized) {
+    if !fields.iter().any(|known| known == field) {
+      fields.push(field.to_string());
+    }
+  }
+  fields
+}
+
+fn related_papers(response: &Value, max: usize) -> Vec<RelatedPaper> {
+  response["recommendedPapers"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|paper| {
+      Some(RelatedPaper {
+        title: paper["title"].as_str()?.to_string(),
+        year: paper["year"].as_i64().map(|year| year as i32),
+        citation_count: paper["citationCount"].as_u64(),
+        url: paper["url"].as_str().map(str::to_string),
+      })
+    })
+    .take(max)
+    .collect()
+}
+
+/// The bare id of `arxiv_id`: no `arXiv:` prefix, URL or version suffix
+pub fn normalize_arxiv_id(arxiv_id: &str) -> String {
+  let mut id = arxiv_id.trim();
+  for marker in ["/abs/", "/pdf/"] {
+    if let Some(position) = id.find(marker) {
+      id = &id[position + marker.len()..];
+    }
+  }
+  id = id.trim_end_matches('/').trim_end_matches(".pdf");
+  if id.len() > 6 && id[..6].eq_ignore_ascii_case("arxiv:") {
+    id = &id[6..];
+  }
+  if let Some(position) = id.rfind('v') {
+    let version = &id[position + 1..];
+    if position > 0 && !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) {
+      id = &id[..position];
+    }
+  }
+  id.to_string()
+}
+
+#[async_trait]
+impl AsyncNode for EnrichmentNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let arxiv_id = match inputs.get("arxiv_id") {
+      Some(FlowValue::Json(Value::String(id))) if !id.trim().is_empty() => id.clone(),
+      _ => {
+        return Err(AgentFlowError::NodeInputError {
+          message: format!(
+            "Enrichment '{}' requires a string 'arxiv_id' input",
+            self.name
+          ),
+        });
+      }
+    };
+    let enrichment = self.enrich(&arxiv_id).await;
+
+    let mut outputs = HashMap::new();
+    outputs.insert("enrichment".to_string(), FlowValue::Json(json!(enrichment)));
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use wiremock::matchers::{method, path, query_param};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  const PAPER: &str = include_str!("../../tests/fixtures/semantic_scholar/paper_1706.03762.json");
+  const RECOMMENDATIONS: &str =
+    include_str!("../../tests/fixtures/semantic_scholar/recommendations_1706.03762.json");
+
+  fn node(server: &MockServer) -> EnrichmentNode {
+    EnrichmentNode::new("enrichment")
+      .with_base_url(server.uri())
+      .with_min_interval(Duration::from_millis(1))
+      .with_max_related(2)
+  }
+
+  async fn mock_recorded(server: &MockServer, times: u64) {
+    Mock::given(method("GET"))
+      .and(path("/graph/v1/paper/arXiv:1706.03762"))
+      .and(query_param("fields", PAPER_FIELDS))
+      .respond_with(ResponseTemplate::new(200).set_body_string(PAPER))
+      .expect(times)
+      .mount(server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/recommendations/v1/papers/forpaper/arXiv:1706.03762"))
+      .and(query_param("limit", "2"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(RECOMMENDATIONS))
+      .expect(times)
+      .mount(server)
+      .await;
+  }
+
+  #[tokio::test]
+  async fn recorded_responses_are_enriched_and_cached() {
+    let server = MockServer::start().await;
+    mock_recorded(&server, 1).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let first = node(&server).with_cache_dir(dir.path());
+    let enrichment = first.enrich("https://arxiv.org/abs/1706.03762v7").await;
+    assert_eq!(enrichment.citation_count, Some(131245));
+    assert_eq!(enrichment.influential_citation_count, Some(16873));
+    assert_eq!(
+      enrichment.fields_of_study,
+      vec!["Computer Science", "Linguistics"]
+    );
+    assert_eq!(
+      enrichment.related_papers,
+      vec![
+        RelatedPaper {
+          title: "BERT: Pre-training of Deep Bidirectional Transformers for Language Understanding"
+            .to_string(),
+          year: Some(2019),
+          citation_count: Some(98732),
+          url: Some(
+            "https://www.semanticscholar.org/paper/df2b0e26d0599ce3e70df8a9da02e51594e0e992"
+              .to_string()
+          ),
+        },
+        RelatedPaper {
+          title: "Language Models are Unsupervised Multitask Learners".to_string(),
+          year: Some(2019),
+          citation_count: None,
+          url: Some(
+            "https://www.semanticscholar.org/paper/9405cc0d6169988371b2755e573cc28650d14dfe"
+              .to_string()
+          ),
+        },
+      ]
+    );
+    assert_eq!(enrichment.warning, None);
+
+    // Another node on the same cache asks nothing; `expect(1)` is
+    // checked when the server drops
+    let second = node(&server).with_cache_dir(dir.path());
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "arxiv_id".to_string(),
+      FlowValue::Json(json!("arXiv:1706.03762")),
+    );
+    let outputs = second.execute(&inputs).await.unwrap();
+    match outputs.get("enrichment") {
+      Some(FlowValue::Json(value)) => assert_eq!(value, &json!(enrichment)),
+      other => panic!("unexpected enrichment output {:?}", other),
+    }
+  }
+
+  #[tokio::test]
+  async fn an_unreachable_api_degrades_to_an_empty_enrichment() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(503).set_body_string("upstream down"))
+      .mount(&server)
+      .await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let enrichment = node(&server)
+      .with_cache_dir(dir.path())
+      .enrich("1706.03762")
+      .await;
+    assert!(enrichment.is_empty());
+    assert!(
+      enrichment
+        .warning
+        .as_deref()
+        .is_some_and(|warning| warning.contains("503") && warning.contains("upstream down")),
+      "{:?}",
+      enrichment.warning
+    );
+    // Nothing degraded is cached
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    // Nothing listening at all
+    let offline = EnrichmentNode::new("enrichment")
+      .with_base_url("http://127.0.0.1:9")
+      .with_min_interval(Duration::ZERO)
+      .enrich("1706.03762")
+      .await;
+    assert!(offline.is_empty() && offline.warning.is_some());
+  }
+
+  #[tokio::test]
+  async fn a_stale_entry_stands_in_while_the_api_is_down() {
+    let dir = tempfile::tempdir().unwrap();
+    let stale = PaperEnrichment {
+      citation_count: Some(100),
+      ..PaperEnrichment::default()
+    };
+    write_entry(
+      &dir.path().join("1706.03762.json"),
+      &CacheEntry {
+        fetched_at: Utc::now() - chrono::Duration::days(30),
+        enrichment: stale,
+      },
+    )
+    .unwrap();
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(500))
+      .mount(&server)
+      .await;
+    let enrichment = node(&server)
+      .with_cache_dir(dir.path())
+      .enrich("1706.03762")
+      .await;
+    assert_eq!(enrichment.citation_count, Some(100));
+    assert!(enrichment.warning.unwrap().contains("cached on"));
+  }
+
+  #[tokio::test]
+  async fn rate_limits_are_waited_out_and_unknown_papers_are_empty() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/graph/v1/paper/arXiv:2401.00001"))
+      .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+      .up_to_n_times(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/graph/v1/paper/arXiv:2401.00001"))
+      .respond_with(ResponseTemplate::new(404))
+      .mount(&server)
+      .await;
+
+    let enrichment = node(&server).enrich("2401.00001").await;
+    assert!(enrichment.is_empty());
+    assert_eq!(
+      enrichment.warning.as_deref(),
+      Some("arXiv:2401.00001 is not on Semantic Scholar")
+    );
+  }
+
+  #[test]
+  fn arxiv_ids_are_normalized() {
+    assert_eq!(
+      normalize_arxiv_id("https://arxiv.org/abs/2312.07104v2"),
+      "2312.07104"
+    );
+    assert_eq!(
+      normalize_arxiv_id("https://arxiv.org/pdf/2312.07104.pdf"),
+      "2312.07104"
+    );
+    assert_eq!(normalize_arxiv_id("arXiv:1706.03762"), "1706.03762");
+    assert_eq!(normalize_arxiv_id("hep-th/9901001v1"), "hep-th/9901001");
+  }
+}
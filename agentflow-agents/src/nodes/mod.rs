@@ -1,8 +1,16 @@
 //! Node implementations for common agent patterns.
 
 pub mod agent_node;
+pub mod enrichment;
+pub mod sub_agent;
+pub mod translator;
 
 pub use agent_node::{
   AgentNode, AgentNodeResumeContract, AgentNodeResumeMode, AgentNodeToolReplayPolicy,
   AgentNodeToolResumeRecord,
 };
+pub use enrichment::{EnrichmentNode, PaperEnrichment, RelatedPaper, normalize_arxiv_id};
+pub use sub_agent::{SubAgentBudget, SubAgentNode, SubAgentTelemetry};
+pub use translator::{
+  ChunkUsage, GlossaryViolation, Translation, TranslationChunk, TranslatorNode, glossary_violations,
+};
@@ -0,0 +1,903 @@
+//! `SubAgentNode` — runs a nested [`Flow`] or [`AgentApplication`] as one
+//! node of a parent workflow, under its own cost and time budget.
+//!
+//! The sub-agent sees only the inputs the node hands it (all of the node's
+//! inputs, or those named with [`SubAgentNode::with_input`]) and none of the
+//! parent's state. Its stages are recorded in a [`StageMetrics`] sink; when
+//! the stages of a run cost more than `max_cost_usd`, or the run takes
+//! longer than `max_duration_ms`, the run is cancelled and the node fails
+//! with [`AgentFlowError::BudgetExceeded`], carrying what was produced so
+//! far. A sub-flow is cancelled through its [`FlowCancellationToken`] as
+//! well as by dropping it, so nodes it spawned stop too.
+//!
+//! A sub-flow's stages are its nodes: each completed node is recorded with
+//! its duration, and with its `model` and `usage` outputs when it has them,
+//! as the `llm` node does. Each run records in a [`StageMetrics::child`] of
+//! the node's sink, so concurrent runs count only their own stages against
+//! the budget. An agent records its own stages in the sink passed with
+//! [`SubAgentNode::with_metrics`], shared by all of its runs, so runs of an
+//! agent take turns.
+//!
+//! # Output keys
+//! | Key         | Type                      |
+//! |-------------|---------------------------|
+//! | each declared output (flow); `<node>.<key>` for every node output when none is declared | as produced |
+//! | `output` (agent) | `FlowValue::Json`    |
+//! | `telemetry` | `FlowValue::Json(Object)`, a [`SubAgentTelemetry`] |
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use agentflow_graph::{
+  AsyncNode, Flow, FlowCancellationToken, FlowRunner,
+  async_node::{AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  events::{EventListener, WorkflowEvent},
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::eval::PricingTable;
+use crate::{AgentApplication, AgentResult, StageMetric, StageMetrics, stage_metrics_total};
+
+/// Cost and time limits of one sub-agent run; `None` disables a bound
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubAgentBudget {
+  /// USD the run's priced stages may cost
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_cost_usd: Option<f64>,
+  /// Wall-clock limit in milliseconds
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_duration_ms: Option<u64>,
+}
+
+impl SubAgentBudget {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_max_cost_usd(mut self, max_cost_usd: f64) -> Self {
+    self.max_cost_usd = Some(max_cost_usd);
+    self
+  }
+
+  pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+    self.max_duration_ms = Some(max_duration.as_millis() as u64);
+    self
+  }
+}
+
+/// What a sub-agent run took, returned under the `telemetry` output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubAgentTelemetry {
+  pub duration_ms: u64,
+  /// One row per stage, in the order the stages first finished
+  pub stages: Vec<StageMetric>,
+  /// The stages added up
+  pub total: StageMetric,
+  pub budget: SubAgentBudget,
+  /// The bound that cancelled the run, `max_cost_usd` or `max_duration_ms`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub exceeded: Option<String>,
+}
+
+/// An [`AsyncNode`] that delegates to a nested flow or agent application.
+///
+/// # Example
+/// ```rust,no_run
+/// use agentflow_agents::nodes::{SubAgentBudget, SubAgentNode};
+/// use agentflow_graph::{Flow, FlowRunner};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// # fn build(research: Flow, runner: Arc<dyn FlowRunner>) {
+/// let node = SubAgentNode::flow("research", research, runner)
+///   .with_input("topic", "subtopic")
+///   .with_output("notes", "summarize", "output")
+///   .with_budget(
+///     SubAgentBudget::new()
+///       .with_max_cost_usd(0.05)
+///       .with_max_duration(Duration::from_secs(120)),
+///   );
+/// # }
+/// ```
+pub struct SubAgentNode {
+  /// Logical name for this node (appears in workflow logs).
+  pub name: String,
+  target: SubAgentTarget,
+  /// `(sub-agent input, node input)` pairs; empty passes every input
+  inputs: Vec<(String, String)>,
+  budget: SubAgentBudget,
+  metrics: StageMetrics,
+  /// Held for an agent run, whose stages are only told apart by order
+  agent_turn: tokio::sync::Mutex<()>,
+}
+
+enum SubAgentTarget {
+  Flow {
+    flow: Arc<Flow>,
+    runner: Arc<dyn FlowRunner>,
+    /// `(output, node, key)` triples
+    outputs: Vec<(String, String, String)>,
+  },
+  Agent(Arc<dyn ApplicationRunner>),
+}
+
+/// [`AgentApplication::run`] as a trait object, which the application
+/// trait itself cannot be
+#[async_trait]
+trait ApplicationRunner: Send + Sync {
+  async fn run(&self, inputs: Vec<String>) -> AgentResult<Value>;
+}
+
+#[async_trait]
+impl<A: AgentApplication> ApplicationRunner for A {
+  async fn run(&self, inputs: Vec<String>) -> AgentResult<Value> {
+    AgentApplication::run(self, inputs).await
+  }
+}
+
+impl SubAgentNode {
+  /// Run `flow` with `runner` (the surface passes
+  /// `agentflow_core::CoreFlowRunner`).
+  pub fn flow(name: impl Into<String>, flow: Flow, runner: Arc<dyn FlowRunner>) -> Self {
+    Self::new(
+      name,
+      SubAgentTarget::Flow {
+        flow: Arc::new(flow),
+        runner,
+        outputs: Vec::new(),
+      },
+    )
+  }
+
+  /// Run an initialized agent application on the `input` input: a string,
+  /// or an array of them for several inputs. Its result is the `output`
+  /// output.
+  pub fn agent<A: AgentApplication + 'static>(name: impl Into<String>, agent: A) -> Self {
+    Self::new(name, SubAgentTarget::Agent(Arc::new(agent)))
+  }
+
+  fn new(name: impl Into<String>, target: SubAgentTarget) -> Self {
+    Self {
+      name: name.into(),
+      target,
+      inputs: Vec::new(),
+      budget: SubAgentBudget::default(),
+      metrics: StageMetrics::new(),
+      agent_turn: tokio::sync::Mutex::new(()),
+    }
+  }
+
+  /// Pass the node input `from` to the sub-agent as `name`. Once any input
+  /// is named, the others are not passed.
+  pub fn with_input(mut self, name: impl Into<String>, from: impl Into<String>) -> Self {
+    self.inputs.push((name.into(), from.into()));
+    self
+  }
+
+  /// Return the sub-flow's `nodes.<node>.outputs.<key>` as `name`. An
+  /// output whose node did not run is left out. Ignored for an agent.
+  pub fn with_output(
+    mut self,
+    name: impl Into<String>,
+    node: impl Into<String>,
+    key: impl Into<String>,
+  ) -> Self {
+    if let SubAgentTarget::Flow { outputs, .. } = &mut self.target {
+      outputs.push((name.into(), node.into(), key.into()));
+    }
+    self
+  }
+
+  pub fn with_budget(mut self, budget: SubAgentBudget) -> Self {
+    self.budget = budget;
+    self
+  }
+
+  /// Price the recorded stages with `pricing`; without it `max_cost_usd`
+  /// never trips
+  pub fn with_pricing(mut self, pricing: PricingTable) -> Self {
+    self.metrics = std::mem::take(&mut self.metrics).with_pricing(pricing);
+    self
+  }
+
+  /// Record the stages in `metrics`, priced as that sink is. Give an agent
+  /// a clone so its stages count against the budget.
+  pub fn with_metrics(mut self, metrics: StageMetrics) -> Self {
+    self.metrics = metrics;
+    self
+  }
+
+  fn scoped_inputs(&self, inputs: &AsyncNodeInputs) -> Result<AsyncNodeInputs, AgentFlowError> {
+    if self.inputs.is_empty() {
+      return Ok(inputs.clone());
+    }
+    self
+      .inputs
+      .iter()
+      .map(|(name, from)| match inputs.get(from) {
+        Some(value) => Ok((name.clone(), value.clone())),
+        None => Err(AgentFlowError::NodeInputError {
+          message: format!(
+            "SubAgentNode '{}': required input '{}' is missing",
+            self.name, from
+          ),
+        }),
+      })
+      .collect()
+  }
+
+  async fn run_target(
+    &self,
+    payload: AsyncNodeInputs,
+    recorder: Arc<SubFlowRecorder>,
+    token: FlowCancellationToken,
+  ) -> AsyncNodeResult {
+    match &self.target {
+      SubAgentTarget::Flow {
+        flow,
+        runner,
+        outputs,
+      } => {
+        let flow = flow.as_ref().clone().with_event_listener(recorder);
+        let results = runner
+          .run_with_cancellation(&flow, payload, token)
+          .await
+          .map_err(|e| self.failed(e))?;
+        let failed = results
+          .iter()
+          .filter_map(|(node, result)| result.as_ref().err().map(|err| (node, err)))
+          .min_by_key(|(node, _)| *node);
+        if let Some((node, err)) = failed {
+          return Err(self.failed(format!("sub-flow node '{}' failed: {}", node, err)));
+        }
+        let nodes = results
+          .into_iter()
+          .filter_map(|(node, result)| result.ok().map(|outputs| (node, outputs)))
+          .collect();
+        Ok(flow_outputs(&nodes, outputs))
+      }
+      SubAgentTarget::Agent(agent) => {
+        let inputs = self.agent_inputs(&payload)?;
+        let result = agent.run(inputs).await.map_err(|e| self.failed(e))?;
+        Ok(HashMap::from([(
+          "output".to_string(),
+          FlowValue::Json(result),
+        )]))
+      }
+    }
+  }
+
+  fn agent_inputs(&self, payload: &AsyncNodeInputs) -> Result<Vec<String>, AgentFlowError> {
+    let text = |value: &Value| {
+      value
+        .as_str()
+        .map_or_else(|| value.to_string(), str::to_string)
+    };
+    match payload.get("input") {
+      Some(FlowValue::Json(Value::Array(items))) => Ok(items.iter().map(text).collect()),
+      Some(FlowValue::Json(value)) => Ok(vec![text(value)]),
+      Some(other) => Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "SubAgentNode '{}': 'input' must be FlowValue::Json, got {:?}",
+          self.name, other
+        ),
+      }),
+      None => Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "SubAgentNode '{}': required input 'input' is missing",
+          self.name
+        ),
+      }),
+    }
+  }
+
+  fn failed(&self, err: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::NodeExecutionFailed {
+      message: format!("SubAgentNode '{}': {}", self.name, err),
+    }
+  }
+
+  /// Resolves when the run's stages, those in `metrics` since `mark`, cost
+  /// more than the budget or `started` is more than its duration ago
+  async fn budget_exceeded(
+    &self,
+    metrics: &StageMetrics,
+    mark: usize,
+    started: Instant,
+  ) -> BudgetBreach {
+    let cost = async {
+      match self.budget.max_cost_usd {
+        Some(limit) => {
+          let spent = metrics.cost_exceeds(mark, limit).await;
+          BudgetBreach {
+            bound: "max_cost_usd",
+            message: format!("cost ${:.4}, over its ${:.4} budget", spent, limit),
+          }
+        }
+        None => std::future::pending().await,
+      }
+    };
+    let duration = async {
+      match self.budget.max_duration_ms {
+        Some(limit) => {
+          tokio::time::sleep_until((started + Duration::from_millis(limit)).into()).await;
+          BudgetBreach {
+            bound: "max_duration_ms",
+            message: format!("ran past its {}ms budget", limit),
+          }
+        }
+        None => std::future::pending().await,
+      }
+    };
+    tokio::select! {
+      breach = cost => breach,
+      breach = duration => breach,
+    }
+  }
+
+  fn telemetry(
+    &self,
+    metrics: &StageMetrics,
+    mark: usize,
+    started: Instant,
+    exceeded: Option<&str>,
+  ) -> FlowValue {
+    let stages = metrics.metrics_since(mark);
+    let telemetry = SubAgentTelemetry {
+      duration_ms: started.elapsed().as_millis() as u64,
+      total: stage_metrics_total(&stages),
+      stages,
+      budget: self.budget,
+      exceeded: exceeded.map(str::to_string),
+    };
+    FlowValue::Json(serde_json::to_value(telemetry).unwrap_or(Value::Null))
+  }
+
+  /// What a cancelled or failed run produced: the outputs of the sub-flow
+  /// nodes that finished
+  fn partial_outputs(&self, recorder: &SubFlowRecorder) -> AsyncNodeInputs {
+    match &self.target {
+      SubAgentTarget::Flow { outputs, .. } => flow_outputs(&recorder.outputs(), outputs),
+      SubAgentTarget::Agent(_) => AsyncNodeInputs::new(),
+    }
+  }
+}
+
+struct BudgetBreach {
+  bound: &'static str,
+  message: String,
+}
+
+/// The declared outputs of a sub-flow, or every node output as
+/// `<node>.<key>` when none is declared
+fn flow_outputs(
+  nodes: &HashMap<String, AsyncNodeInputs>,
+  declared: &[(String, String, String)],
+) -> AsyncNodeInputs {
+  if declared.is_empty() {
+    return nodes
+      .iter()
+      .flat_map(|(node, outputs)| {
+        outputs
+          .iter()
+          .map(move |(key, value)| (format!("{}.{}", node, key), value.clone()))
+      })
+      .collect();
+  }
+  declared
+    .iter()
+    .filter_map(|(name, node, key)| {
+      let value = nodes.get(node)?.get(key)?;
+      Some((name.clone(), value.clone()))
+    })
+    .collect()
+}
+
+#[async_trait]
+impl AsyncNode for SubAgentNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let payload = self.scoped_inputs(inputs)?;
+    let listener = match &self.target {
+      SubAgentTarget::Flow { flow, .. } => flow.event_listener().cloned(),
+      SubAgentTarget::Agent(_) => None,
+    };
+    let _turn;
+    let (metrics, mark) = match &self.target {
+      SubAgentTarget::Flow { .. } => (self.metrics.child(), 0),
+      SubAgentTarget::Agent(_) => {
+        _turn = self.agent_turn.lock().await;
+        (self.metrics.clone(), self.metrics.mark())
+      }
+    };
+    let recorder = Arc::new(SubFlowRecorder::new(metrics.clone(), listener));
+    let token = FlowCancellationToken::new();
+    let started = Instant::now();
+
+    // Dropping the losing run cancels the sub-agent's pending node or model
+    // call; the token stops what a sub-flow spawned, such as map items
+    let result = tokio::select! {
+      result = self.run_target(payload, recorder.clone(), token.clone()) => result,
+      breach = self.budget_exceeded(&metrics, mark, started) => {
+        token.cancel();
+        let mut partial_outputs = self.partial_outputs(&recorder);
+        partial_outputs.insert(
+          "telemetry".to_string(),
+          self.telemetry(&metrics, mark, started, Some(breach.bound)),
+        );
+        return Err(AgentFlowError::BudgetExceeded {
+          message: format!("SubAgentNode '{}' {}", self.name, breach.message),
+          partial_outputs,
+        });
+      }
+    };
+
+    match result {
+      Ok(mut outputs) => {
+        outputs.insert(
+          "telemetry".to_string(),
+          self.telemetry(&metrics, mark, started, None),
+        );
+        Ok(outputs)
+      }
+      Err(AgentFlowError::NodeExecutionFailed { message }) => {
+        let mut partial_outputs = self.partial_outputs(&recorder);
+        partial_outputs.insert(
+          "telemetry".to_string(),
+          self.telemetry(&metrics, mark, started, None),
+        );
+        Err(AgentFlowError::NodePartialExecutionFailed {
+          message,
+          partial_outputs,
+        })
+      }
+      Err(other) => Err(other),
+    }
+  }
+}
+
+/// Listens to a sub-flow run: keeps each node's outputs and records each
+/// completed node as a stage, then passes the event on to the flow's own
+/// listener
+struct SubFlowRecorder {
+  metrics: StageMetrics,
+  outputs: Mutex<HashMap<String, AsyncNodeInputs>>,
+  inner: Option<Arc<dyn EventListener>>,
+}
+
+impl SubFlowRecorder {
+  fn new(metrics: StageMetrics, inner: Option<Arc<dyn EventListener>>) -> Self {
+    Self {
+      metrics,
+      outputs: Mutex::new(HashMap::new()),
+      inner,
+    }
+  }
+
+  fn outputs(&self) -> HashMap<String, AsyncNodeInputs> {
+    self
+      .outputs
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clone()
+  }
+
+  /// A completed node as a stage, with the model call its `model`,
+  /// `prompt` and `usage` outputs describe
+  fn stage(&self, node_id: &str, duration: Duration) -> StageMetric {
+    let outputs = self.outputs.lock().unwrap_or_else(|e| e.into_inner());
+    let output = |key: &str| match outputs.get(node_id).and_then(|outputs| outputs.get(key)) {
+      Some(FlowValue::Json(value)) => Some(value.clone()),
+      _ => None,
+    };
+    let mut stage = StageMetric::new(node_id, duration);
+    if let Some(Value::String(model)) = output("model") {
+      stage = stage.with_model(model);
+    }
+    if let Some(Value::String(prompt)) = output("prompt") {
+      stage = stage.with_prompt(prompt);
+    }
+    if let Some(usage) = output("usage").filter(Value::is_object) {
+      stage = stage.with_call(
+        usage["prompt_tokens"].as_u64(),
+        usage["completion_tokens"].as_u64(),
+      );
+    }
+    stage
+  }
+}
+
+impl EventListener for SubFlowRecorder {
+  fn on_event(&self, event: &WorkflowEvent) {
+    match event {
+      WorkflowEvent::NodeOutputCaptured {
+        node_id, output, ..
+      } => {
+        if let Ok(outputs) = serde_json::from_value::<AsyncNodeInputs>(output.clone()) {
+          self
+            .outputs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(node_id.clone(), outputs);
+        }
+      }
+      WorkflowEvent::NodeCompleted {
+        node_id, duration, ..
+      } => self.metrics.record(self.stage(node_id, *duration)),
+      _ => {}
+    }
+    if let Some(inner) = &self.inner {
+      inner.on_event(event);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::AgentConfig;
+  use crate::eval::ModelPricing;
+  use agentflow_core::flow::{GraphNode, NodeType};
+  use serde_json::json;
+
+  /// Writes a draft with a model call of 1000 prompt and 500 completion
+  /// tokens, and reports which inputs it saw
+  struct DraftNode;
+
+  #[async_trait]
+  impl agentflow_core::AsyncNode for DraftNode {
+    async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      let mut seen: Vec<_> = inputs.keys().cloned().collect();
+      seen.sort();
+      Ok(HashMap::from([
+        ("output".to_string(), FlowValue::Json(json!("a draft"))),
+        ("seen".to_string(), FlowValue::Json(json!(seen))),
+        ("model".to_string(), FlowValue::Json(json!("m"))),
+        (
+          "usage".to_string(),
+          FlowValue::Json(json!({"prompt_tokens": 1000, "completion_tokens": 500})),
+        ),
+      ]))
+    }
+  }
+
+  struct SlowNode(Duration);
+
+  #[async_trait]
+  impl agentflow_core::AsyncNode for SlowNode {
+    async fn execute(&self, _inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      tokio::time::sleep(self.0).await;
+      Ok(HashMap::from([(
+        "output".to_string(),
+        FlowValue::Json(json!("polished")),
+      )]))
+    }
+  }
+
+  fn graph_node(id: &str, node: Arc<dyn agentflow_core::AsyncNode>, after: &[&str]) -> GraphNode {
+    GraphNode {
+      id: id.to_string(),
+      node_type: NodeType::Standard(node),
+      dependencies: after.iter().map(|id| id.to_string()).collect(),
+      input_mapping: None,
+      run_if: None,
+      initial_inputs: AsyncNodeInputs::new(),
+    }
+  }
+
+  /// `draft`, then a `polish` that takes `polish_time`
+  fn draft_then_polish(polish_time: Duration) -> Flow {
+    Flow::new(vec![
+      graph_node("draft", Arc::new(DraftNode), &[]),
+      graph_node("polish", Arc::new(SlowNode(polish_time)), &["draft"]),
+    ])
+  }
+
+  /// $1 per 1k prompt tokens, $2 per 1k completion tokens: a draft costs $2
+  fn pricing() -> PricingTable {
+    PricingTable::default().with_model(
+      "m",
+      ModelPricing {
+        input_per_1k: 1.0,
+        output_per_1k: 2.0,
+      },
+    )
+  }
+
+  fn research_node(flow: Flow) -> SubAgentNode {
+    SubAgentNode::flow(
+      "research",
+      flow,
+      Arc::new(agentflow_core::CoreFlowRunner::serial()),
+    )
+    .with_input("topic", "subtopic")
+    .with_output("draft", "draft", "output")
+    .with_output("seen", "draft", "seen")
+    .with_output("final", "polish", "output")
+    .with_pricing(pricing())
+  }
+
+  fn parent_inputs() -> AsyncNodeInputs {
+    HashMap::from([
+      ("subtopic".to_string(), FlowValue::Json(json!("tides"))),
+      (
+        "parent_notes".to_string(),
+        FlowValue::Json(json!("private")),
+      ),
+    ])
+  }
+
+  fn json_output(outputs: &AsyncNodeInputs, key: &str) -> Value {
+    match outputs.get(key) {
+      Some(FlowValue::Json(value)) => value.clone(),
+      other => panic!("{key} should be a JSON output, got {other:?}"),
+    }
+  }
+
+  fn telemetry(outputs: &AsyncNodeInputs) -> SubAgentTelemetry {
+    serde_json::from_value(json_output(outputs, "telemetry")).unwrap()
+  }
+
+  fn use_writable_home() {
+    let home =
+      std::env::temp_dir().join(format!("agentflow-sub-agent-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&home).unwrap();
+    // SAFETY: this test helper sets HOME before the workflow code reads it and
+    // does not race with another environment mutation in the same test.
+    unsafe {
+      std::env::set_var("HOME", home);
+    }
+  }
+
+  #[tokio::test]
+  async fn a_sub_flow_returns_its_declared_outputs_and_telemetry() {
+    use_writable_home();
+    let node = research_node(draft_then_polish(Duration::ZERO));
+
+    let outputs = node.execute(&parent_inputs()).await.unwrap();
+
+    assert_eq!(json_output(&outputs, "draft"), json!("a draft"));
+    assert_eq!(json_output(&outputs, "final"), json!("polished"));
+    // Only the scoped input reaches the sub-flow
+    assert_eq!(json_output(&outputs, "seen"), json!(["topic"]));
+    let telemetry = telemetry(&outputs);
+    assert_eq!(
+      telemetry
+        .stages
+        .iter()
+        .map(|stage| stage.stage.as_str())
+        .collect::<Vec<_>>(),
+      ["draft", "polish"]
+    );
+    assert_eq!(telemetry.total.calls, 1);
+    assert_eq!(telemetry.total.cost_usd, Some(2.0));
+    assert_eq!(telemetry.exceeded, None);
+
+    let err = node.execute(&HashMap::new()).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::NodeInputError { .. }),
+      "{err:?}"
+    );
+  }
+
+  #[tokio::test]
+  async fn exceeding_the_cost_budget_cancels_the_run_with_partial_results() {
+    use_writable_home();
+    let node = research_node(draft_then_polish(Duration::from_secs(30)))
+      .with_budget(SubAgentBudget::new().with_max_cost_usd(0.5));
+
+    let started = Instant::now();
+    let err = node.execute(&parent_inputs()).await.unwrap_err();
+
+    // Cancelled after the draft, not after the 30s polish
+    assert!(started.elapsed() < Duration::from_secs(10));
+    let AgentFlowError::BudgetExceeded {
+      message,
+      partial_outputs,
+    } = err
+    else {
+      panic!("expected BudgetExceeded, got {err:?}");
+    };
+    assert!(message.contains("'research'"), "{message}");
+    assert!(message.contains("$2.0000"), "{message}");
+    assert_eq!(json_output(&partial_outputs, "draft"), json!("a draft"));
+    assert!(!partial_outputs.contains_key("final"));
+    let report = telemetry(&partial_outputs);
+    assert_eq!(report.exceeded.as_deref(), Some("max_cost_usd"));
+    assert_eq!(report.total.cost_usd, Some(2.0));
+    assert_eq!(report.budget.max_cost_usd, Some(0.5));
+
+    // The budget covers one run: a second run starts from nothing spent
+    let err = node.execute(&parent_inputs()).await.unwrap_err();
+    let AgentFlowError::BudgetExceeded {
+      partial_outputs, ..
+    } = err
+    else {
+      panic!("expected BudgetExceeded, got {err:?}");
+    };
+    assert_eq!(telemetry(&partial_outputs).total.cost_usd, Some(2.0));
+  }
+
+  #[tokio::test]
+  async fn concurrent_runs_count_only_their_own_cost() {
+    use_writable_home();
+    // Each run's draft costs $2; both drafts together would be over $3
+    let node = research_node(draft_then_polish(Duration::from_millis(100)))
+      .with_budget(SubAgentBudget::new().with_max_cost_usd(3.0));
+
+    let inputs = parent_inputs();
+    let (first, second) = tokio::join!(node.execute(&inputs), node.execute(&inputs));
+
+    for outputs in [first.unwrap(), second.unwrap()] {
+      assert_eq!(telemetry(&outputs).total.cost_usd, Some(2.0));
+    }
+    assert_eq!(node.metrics.metrics()[0].calls, 2);
+  }
+
+  /// Runs with the core runner, keeping the token each run was given
+  #[derive(Default)]
+  struct TokenRunner {
+    tokens: Mutex<Vec<FlowCancellationToken>>,
+  }
+
+  #[async_trait]
+  impl FlowRunner for TokenRunner {
+    async fn run(
+      &self,
+      flow: &Flow,
+      inputs: AsyncNodeInputs,
+    ) -> Result<HashMap<String, AsyncNodeResult>, AgentFlowError> {
+      agentflow_core::CoreFlowRunner::serial()
+        .run(flow, inputs)
+        .await
+    }
+
+    async fn run_with_cancellation(
+      &self,
+      flow: &Flow,
+      inputs: AsyncNodeInputs,
+      token: FlowCancellationToken,
+    ) -> Result<HashMap<String, AsyncNodeResult>, AgentFlowError> {
+      self.tokens.lock().unwrap().push(token.clone());
+      agentflow_core::CoreFlowRunner::serial()
+        .run_with_cancellation(flow, inputs, token)
+        .await
+    }
+  }
+
+  #[tokio::test]
+  async fn a_budget_breach_cancels_the_sub_flow_token() {
+    use_writable_home();
+    let runner = Arc::new(TokenRunner::default());
+    let node = SubAgentNode::flow(
+      "research",
+      draft_then_polish(Duration::from_secs(30)),
+      runner.clone(),
+    )
+    .with_pricing(pricing())
+    .with_budget(SubAgentBudget::new().with_max_cost_usd(0.5));
+
+    let err = node.execute(&parent_inputs()).await.unwrap_err();
+
+    assert!(
+      matches!(err, AgentFlowError::BudgetExceeded { .. }),
+      "{err:?}"
+    );
+    let tokens = runner.tokens.lock().unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens[0].is_cancelled());
+  }
+
+  #[tokio::test]
+  async fn undeclared_outputs_are_returned_per_node() {
+    use_writable_home();
+    let node = SubAgentNode::flow(
+      "research",
+      draft_then_polish(Duration::ZERO),
+      Arc::new(agentflow_core::CoreFlowRunner::serial()),
+    );
+
+    let outputs = node.execute(&parent_inputs()).await.unwrap();
+
+    assert_eq!(json_output(&outputs, "draft.output"), json!("a draft"));
+    assert_eq!(json_output(&outputs, "polish.output"), json!("polished"));
+    // Both parent inputs pass through when none is named
+    assert_eq!(
+      json_output(&outputs, "draft.seen"),
+      json!(["parent_notes", "subtopic"])
+    );
+    // Unpriced: the call is counted but costs nothing
+    assert_eq!(telemetry(&outputs).total.cost_usd, None);
+  }
+
+  #[derive(Debug, Default, Serialize, Deserialize)]
+  struct EchoConfig;
+
+  impl AgentConfig for EchoConfig {
+    fn validate(&self) -> AgentResult<()> {
+      Ok(())
+    }
+  }
+
+  /// Echoes its input after `delay`
+  struct EchoAgent {
+    delay: Duration,
+  }
+
+  #[async_trait]
+  impl AgentApplication for EchoAgent {
+    type Config = EchoConfig;
+    type Result = String;
+    const NAME: &'static str = "echo";
+
+    async fn initialize(_config: EchoConfig) -> AgentResult<Self> {
+      Ok(Self {
+        delay: Duration::ZERO,
+      })
+    }
+
+    async fn execute(&self, input: &str) -> AgentResult<String> {
+      tokio::time::sleep(self.delay).await;
+      if input == "fail" {
+        return Err("cannot echo".into());
+      }
+      Ok(input.to_uppercase())
+    }
+  }
+
+  fn echo_node(delay: Duration) -> SubAgentNode {
+    SubAgentNode::agent("echo", EchoAgent { delay }).with_input("input", "question")
+  }
+
+  fn question(value: Value) -> AsyncNodeInputs {
+    HashMap::from([("question".to_string(), FlowValue::Json(value))])
+  }
+
+  #[tokio::test]
+  async fn an_agent_returns_its_result_as_output() {
+    let node = echo_node(Duration::ZERO);
+
+    let outputs = node.execute(&question(json!("hi"))).await.unwrap();
+    assert_eq!(json_output(&outputs, "output"), json!("HI"));
+    assert!(telemetry(&outputs).stages.is_empty());
+
+    let outputs = node.execute(&question(json!(["a", "b"]))).await.unwrap();
+    assert_eq!(json_output(&outputs, "output"), json!(["A", "B"]));
+
+    let err = node.execute(&question(json!("fail"))).await.unwrap_err();
+    let AgentFlowError::NodePartialExecutionFailed {
+      message,
+      partial_outputs,
+    } = err
+    else {
+      panic!("expected NodePartialExecutionFailed, got {err:?}");
+    };
+    assert!(message.contains("cannot echo"), "{message}");
+    assert!(partial_outputs.contains_key("telemetry"));
+  }
+
+  #[tokio::test]
+  async fn exceeding_the_duration_budget_cancels_an_agent() {
+    let node = echo_node(Duration::from_secs(30))
+      .with_budget(SubAgentBudget::new().with_max_duration(Duration::from_millis(20)));
+
+    let err = node.execute(&question(json!("hi"))).await.unwrap_err();
+
+    let AgentFlowError::BudgetExceeded {
+      message,
+      partial_outputs,
+    } = err
+    else {
+      panic!("expected BudgetExceeded, got {err:?}");
+    };
+    assert!(message.contains("ran past its 20ms budget"), "{message}");
+    let telemetry = telemetry(&partial_outputs);
+    assert_eq!(telemetry.exceeded.as_deref(), Some("max_duration_ms"));
+    assert!(telemetry.duration_ms >= 20);
+    assert!(telemetry.duration_ms < 10_000);
+  }
+}
@@ -0,0 +1,754 @@
+//! `TranslatorNode` — translates a document of any length, chunk by chunk,
+//! with a fixed glossary.
+//!
+//! The text is cut at headings (Markdown `#` and LaTeX `\section`-style)
+//! into chunks of at most `max_chunk_tokens`, counted with
+//! [`agentflow_llm::counter_for_model`]. A section too long for one chunk
+//! is split between paragraphs, then between words. Chunks are translated
+//! in order: each prompt carries the glossary and, as context only, the
+//! end of the previous chunk and its translation. Each translation is
+//! checked against the glossary, and a chunk that started with a Markdown
+//! heading keeps it when the model drops the `#` marks.
+//!
+//! # Input keys
+//! | Key        | Type                             | Required |
+//! |------------|----------------------------------|----------|
+//! | `text`     | `FlowValue::Json(String)`        | yes      |
+//! | `glossary` | `FlowValue::Json(Object)` term → translation, over the configured one | no |
+//!
+//! # Output keys
+//! | Key                   | Type                      |
+//! |-----------------------|---------------------------|
+//! | `translation`         | `FlowValue::Json(String)` |
+//! | `chunks`              | `FlowValue::Json(Array)` of [`ChunkUsage`] |
+//! | `usage`               | `FlowValue::Json(Object)` token totals |
+//! | `glossary_violations` | `FlowValue::Json(Array)` of [`GlossaryViolation`] |
+
+use std::collections::{BTreeMap, HashMap};
+
+use agentflow_graph::{
+  AsyncNode,
+  async_node::{AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_llm::{AgentFlow, TokenCounter, counter_for_model};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// The instructions a chunk prompt starts with unless
+/// [`TranslatorNode::with_prompt`] replaces them
+const DEFAULT_PROMPT: &str = "Translate the following text into {{language}}. Keep its structure: \
+  headings, lists, formulas, citations and code stay where they are. Reply with the translation only.\n\n\
+  {{text}}";
+
+/// One piece of the document, as translated in one model call
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslationChunk {
+  /// 0-based position in the document
+  pub index: usize,
+  /// The heading of the section the chunk starts in, if any
+  pub heading: Option<String>,
+  pub text: String,
+}
+
+/// A chunk's size and the tokens its translation used
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkUsage {
+  pub index: usize,
+  pub heading: Option<String>,
+  /// The chunk's tokens, by the splitter's count
+  pub source_tokens: usize,
+  pub prompt_tokens: Option<u32>,
+  pub completion_tokens: Option<u32>,
+  pub total_tokens: Option<u32>,
+}
+
+/// A glossary term that occurs in a chunk whose translation lacks the
+/// term's fixed translation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlossaryViolation {
+  pub chunk: usize,
+  pub term: String,
+  pub expected: String,
+}
+
+/// A translated document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Translation {
+  pub text: String,
+  pub chunks: Vec<ChunkUsage>,
+  pub glossary_violations: Vec<GlossaryViolation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranslatorNode {
+  pub name: String,
+  pub model: String,
+  pub target_language: String,
+  /// Largest chunk sent in one call, in tokens
+  pub max_chunk_tokens: usize,
+  /// How much of the previous chunk and its translation each prompt
+  /// repeats as context, in characters
+  pub context_chars: usize,
+  /// Term → the translation it must always get
+  pub glossary: BTreeMap<String, String>,
+  /// Prompt template with `{{text}}` and `{{language}}`
+  pub prompt: String,
+  pub temperature: f32,
+  pub max_tokens: Option<u32>,
+  pub text_key: String,
+}
+
+impl TranslatorNode {
+  pub fn new(name: &str, model: &str, target_language: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      model: model.to_string(),
+      target_language: target_language.to_string(),
+      max_chunk_tokens: 2000,
+      context_chars: 300,
+      glossary: BTreeMap::new(),
+      prompt: DEFAULT_PROMPT.to_string(),
+      temperature: 0.2,
+      max_tokens: None,
+      text_key: "text".to_string(),
+    }
+  }
+
+  pub fn with_max_chunk_tokens(mut self, max_chunk_tokens: usize) -> Self {
+    self.max_chunk_tokens = max_chunk_tokens;
+    self
+  }
+
+  pub fn with_context_chars(mut self, context_chars: usize) -> Self {
+    self.context_chars = context_chars;
+    self
+  }
+
+  pub fn with_glossary(mut self, glossary: BTreeMap<String, String>) -> Self {
+    self.glossary = glossary;
+    self
+  }
+
+  /// Replace the translation instructions. `{{text}}` is the chunk and
+  /// `{{language}}` the target language; a template without `{{text}}`
+  /// gets the chunk after it.
+  pub fn with_prompt(mut self, prompt: &str) -> Self {
+    self.prompt = prompt.to_string();
+    self
+  }
+
+  pub fn with_temperature(mut self, temperature: f32) -> Self {
+    self.temperature = temperature;
+    self
+  }
+
+  pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+    self.max_tokens = Some(max_tokens);
+    self
+  }
+
+  pub fn with_text_key(mut self, key: &str) -> Self {
+    self.text_key = key.to_string();
+    self
+  }
+
+  /// Cut `text` into the chunks [`Self::translate`] sends, starting a new
+  /// chunk only at a heading unless a section alone is too large
+  pub fn split(&self, text: &str) -> Vec<TranslationChunk> {
+    let counter = counter_for_model(&self.model);
+    let budget = self.max_chunk_tokens.max(1);
+    let mut chunks: Vec<TranslationChunk> = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    let mut heading = None;
+    let mut flush = |current: &mut String, heading: &Option<String>| {
+      let text = current.trim_end();
+      if !text.trim().is_empty() {
+        chunks.push(TranslationChunk {
+          index: chunks.len(),
+          heading: heading.clone(),
+          text: text.to_string(),
+        });
+      }
+      current.clear();
+    };
+
+    for section in sections(text) {
+      let tokens = counter.count_tokens(&section.text) as usize;
+      if current_tokens > 0 && current_tokens + tokens > budget {
+        flush(&mut current, &heading);
+        current_tokens = 0;
+      }
+      if current.is_empty() {
+        heading = section.heading.clone().or(heading);
+      }
+      if tokens <= budget {
+        current.push_str(&section.text);
+        current_tokens += tokens;
+        continue;
+      }
+      // Too large for any chunk: fill chunks between paragraphs
+      for piece in split_oversized(&section.text, budget, counter.as_ref()) {
+        let piece_tokens = counter.count_tokens(&piece) as usize;
+        if current_tokens > 0 && current_tokens + piece_tokens > budget {
+          flush(&mut current, &heading);
+          current_tokens = 0;
+          heading = section.heading.clone();
+        }
+        current.push_str(&piece);
+        current_tokens += piece_tokens;
+      }
+    }
+    flush(&mut current, &heading);
+    chunks
+  }
+
+  /// Translate `text`, with `extra_glossary` over the configured glossary
+  pub async fn translate(
+    &self,
+    text: &str,
+    extra_glossary: &BTreeMap<String, String>,
+  ) -> Result<Translation, AgentFlowError> {
+    let mut glossary = self.glossary.clone();
+    glossary.extend(
+      extra_glossary
+        .iter()
+        .map(|(term, fixed)| (term.clone(), fixed.clone())),
+    );
+    let counter = counter_for_model(&self.model);
+
+    let mut translation = Translation::default();
+    let mut parts: Vec<String> = Vec::new();
+    let chunks = self.split(text);
+    for chunk in &chunks {
+      let previous = chunk
+        .index
+        .checked_sub(1)
+        .map(|previous| (chunks[previous].text.as_str(), parts[previous].as_str()));
+      let prompt = self.chunk_prompt(&chunk.text, &glossary, previous);
+
+      let mut request = AgentFlow::model(&self.model)
+        .prompt(&prompt)
+        .temperature(self.temperature);
+      if let Some(max_tokens) = self.max_tokens {
+        request = request.max_tokens(max_tokens);
+      }
+      let response =
+        request
+          .execute_full()
+          .await
+          .map_err(|e| AgentFlowError::NodeExecutionFailed {
+            message: format!(
+              "Translator '{}': chunk {} of {} failed: {}",
+              self.name,
+              chunk.index + 1,
+              chunks.len(),
+              e
+            ),
+          })?;
+
+      let translated = restore_heading(&chunk.text, response.content.trim());
+      for violation in glossary_violations(&glossary, chunk.index, &chunk.text, &translated) {
+        tracing::warn!(
+          translator = %self.name,
+          chunk = violation.chunk,
+          term = %violation.term,
+          expected = %violation.expected,
+          "glossary term translated differently"
+        );
+        translation.glossary_violations.push(violation);
+      }
+      let usage = response.usage.as_ref();
+      translation.chunks.push(ChunkUsage {
+        index: chunk.index,
+        heading: chunk.heading.clone(),
+        source_tokens: counter.count_tokens(&chunk.text) as usize,
+        prompt_tokens: usage.and_then(|u| u.prompt_tokens),
+        completion_tokens: usage.and_then(|u| u.completion_tokens),
+        total_tokens: usage.and_then(|u| u.total_tokens),
+      });
+      parts.push(translated);
+    }
+    translation.text = parts.join("\n\n");
+    Ok(translation)
+  }
+
+  /// The prompt for one chunk: the instructions, the glossary, and the end
+  /// of the previous chunk with its translation
+  fn chunk_prompt(
+    &self,
+    chunk: &str,
+    glossary: &BTreeMap<String, String>,
+    previous: Option<(&str, &str)>,
+  ) -> String {
+    let mut preamble = String::new();
+    if !glossary.is_empty() {
+      preamble.push_str("Glossary. Always translate these terms exactly as given:\n");
+      for (term, fixed) in glossary {
+        preamble.push_str(&format!("- {} → {}\n", term, fixed));
+      }
+      preamble.push('\n');
+    }
+    if let Some((source, translated)) = previous
+      && self.context_chars > 0
+    {
+      preamble.push_str(
+        "Context. The end of the previous part and its translation, for continuity only. Do not translate it again:\n",
+      );
+      preamble.push_str(&format!(
+        "{}\n---\n{}\n\n",
+        tail(source, self.context_chars),
+        tail(translated, self.context_chars)
+      ));
+    }
+
+    let body = match fill_prompt(&self.prompt, &self.target_language, chunk) {
+      (body, true) => body,
+      (instructions, false) => format!("{}\n\n{}", instructions, chunk),
+    };
+    format!("{}{}", preamble, body)
+  }
+}
+
+#[async_trait]
+impl AsyncNode for TranslatorNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let text = match inputs.get(&self.text_key) {
+      Some(FlowValue::Json(Value::String(text))) => text.clone(),
+      _ => {
+        return Err(AgentFlowError::NodeInputError {
+          message: format!(
+            "Translator '{}' requires a string '{}' input",
+            self.name, self.text_key
+          ),
+        });
+      }
+    };
+    let glossary = match inputs.get("glossary") {
+      Some(FlowValue::Json(value)) => {
+        serde_json::from_value(value.clone()).map_err(|e| AgentFlowError::NodeInputError {
+          message: format!(
+            "Translator '{}': 'glossary' must map terms to strings: {}",
+            self.name, e
+          ),
+        })?
+      }
+      _ => BTreeMap::new(),
+    };
+
+    let translation = self.translate(&text, &glossary).await?;
+    let total = |tokens: fn(&ChunkUsage) -> Option<u32>| -> u32 {
+      translation.chunks.iter().filter_map(tokens).sum()
+    };
+    let usage = json!({
+      "prompt_tokens": total(|chunk| chunk.prompt_tokens),
+      "completion_tokens": total(|chunk| chunk.completion_tokens),
+      "total_tokens": total(|chunk| chunk.total_tokens),
+    });
+
+    let mut outputs = HashMap::new();
+    outputs.insert(
+      "translation".to_string(),
+      FlowValue::Json(json!(translation.text)),
+    );
+    outputs.insert(
+      "chunks".to_string(),
+      FlowValue::Json(json!(translation.chunks)),
+    );
+    outputs.insert("usage".to_string(), FlowValue::Json(usage));
+    outputs.insert(
+      "glossary_violations".to_string(),
+      FlowValue::Json(json!(translation.glossary_violations)),
+    );
+    Ok(outputs)
+  }
+}
+
+/// The glossary terms in `source` (ignoring case) whose fixed translation
+/// is missing from `translated`
+pub fn glossary_violations(
+  glossary: &BTreeMap<String, String>,
+  chunk: usize,
+  source: &str,
+  translated: &str,
+) -> Vec<GlossaryViolation> {
+  let source = source.to_lowercase();
+  let translated = translated.to_lowercase();
+  glossary
+    .iter()
+    .filter(|(term, fixed)| {
+      !term.is_empty()
+        && source.contains(&term.to_lowercase())
+        && !translated.contains(&fixed.to_lowercase())
+    })
+    .map(|(term, fixed)| GlossaryViolation {
+      chunk,
+      term: term.clone(),
+      expected: fixed.clone(),
+    })
+    .collect()
+}
+
+/// A run of lines from one heading to the next
+struct Section {
+  heading: Option<String>,
+  text: String,
+}
+
+/// Cut `text` before every heading outside fenced code blocks
+fn sections(text: &str) -> Vec<Section> {
+  let mut sections = vec![Section {
+    heading: None,
+    text: String::new(),
+  }];
+  let mut in_fence = false;
+  for line in text.split_inclusive('\n') {
+    if line.trim_start().starts_with("```") {
+      in_fence = !in_fence;
+    }
+    if !in_fence && let Some(title) = heading_title(line) {
+      sections.push(Section {
+        heading: Some(title),
+        text: String::new(),
+      });
+    }
+    if let Some(section) = sections.last_mut() {
+      section.text.push_str(line);
+    }
+  }
+  sections.retain(|section| !section.text.is_empty());
+  sections
+}
+
+/// The title of a Markdown or LaTeX heading line
+fn heading_title(line: &str) -> Option<String> {
+  let line = line.trim();
+  let hashes = line.chars().take_while(|c| *c == '#').count();
+  if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+    return Some(line[hashes..].trim().to_string());
+  }
+  for command in ["\\chapter", "\\section", "\\subsection", "\\subsubsection"] {
+    if let Some(rest) = line.strip_prefix(command) {
+      let rest = rest.strip_prefix('*').unwrap_or(rest);
+      if let Some(title) = rest.strip_prefix('{') {
+        return Some(
+          title
+            .split('}')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+        );
+      }
+    }
+  }
+  None
+}
+
+/// Cut a section larger than `budget` tokens between paragraphs, and a
+/// paragraph still too large between words (or characters, for text
+/// without spaces)
+fn split_oversized(text: &str, budget: usize, counter: &dyn TokenCounter) -> Vec<String> {
+  let mut pieces = Vec::new();
+  for paragraph in text.split_inclusive("\n\n") {
+    if counter.count_tokens(paragraph) as usize <= budget {
+      pieces.push(paragraph.to_string());
+      continue;
+    }
+    let mut piece = String::new();
+    for word in paragraph.split_inclusive(char::is_whitespace) {
+      let candidate = format!("{}{}", piece, word);
+      if !piece.is_empty() && counter.count_tokens(&candidate) as usize > budget {
+        pieces.push(std::mem::take(&mut piece));
+      }
+      if counter.count_tokens(word) as usize > budget {
+        for c in word.chars() {
+          if !piece.is_empty() && counter.count_tokens(&format!("{}{}", piece, c)) as usize > budget
+          {
+            pieces.push(std::mem::take(&mut piece));
+          }
+          piece.push(c);
+        }
+      } else {
+        piece.push_str(word);
+      }
+    }
+    if !piece.is_empty() {
+      pieces.push(piece);
+    }
+  }
+  pieces
+}
+
+/// `translated`, with the Markdown heading marks `source` started with
+/// put back when the model dropped them
+fn restore_heading(source: &str, translated: &str) -> String {
+  let Some(first_line) = source.lines().find(|line| !line.trim().is_empty()) else {
+    return translated.to_string();
+  };
+  let first_line = first_line.trim_start();
+  let hashes = first_line.chars().take_while(|c| *c == '#').count();
+  if !(1..=6).contains(&hashes)
+    || !first_line[hashes..].starts_with(' ')
+    || translated.starts_with('#')
+  {
+    return translated.to_string();
+  }
+  format!("{} {}", &first_line[..hashes], translated)
+}
+
+/// The last `chars` characters of `text`
+/// Fills `{{ language }}` and `{{ text }}` in one pass, so a chunk that
+/// itself contains `{{language}}` is left alone; also reports whether the
+/// template had a `{{ text }}`. `\{{` and any other placeholder stay as
+/// written, as in the nodes' lenient placeholder rendering.
+fn fill_prompt(template: &str, language: &str, text: &str) -> (String, bool) {
+  let mut filled = String::with_capacity(template.len());
+  let mut text_filled = false;
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    if rest[..start].ends_with('\\') {
+      filled.push_str(&rest[..start - 1]);
+      filled.push_str("{{");
+      rest = &rest[start + 2..];
+      continue;
+    }
+    let Some(len) = rest[start + 2..].find("}}") else {
+      break;
+    };
+    let end = start + 2 + len + 2;
+    filled.push_str(&rest[..start]);
+    match rest[start + 2..start + 2 + len].trim() {
+      "language" => filled.push_str(language),
+      "text" => {
+        filled.push_str(text);
+        text_filled = true;
+      }
+      _ => filled.push_str(&rest[start..end]),
+    }
+    rest = &rest[end..];
+  }
+  filled.push_str(rest);
+  (filled, text_filled)
+}
+
+fn tail(text: &str, chars: usize) -> &str {
+  let count = text.chars().count();
+  match text.char_indices().nth(count.saturating_sub(chars)) {
+    Some((start, _)) if count > chars => &text[start..],
+    _ => text,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A model the tokenizer lookup does not know, so it counts with the
+  /// heuristic counter
+  const MODEL: &str = "translator-test-model";
+
+  fn document() -> String {
+    [
+      "# Introduction\n\nLarge language models translate papers.\n\n",
+      "## Method\n\nWe split the paper at headings and keep a glossary.\n\n",
+      "## Results\n\n",
+      &"The attention mechanism works well on long inputs. ".repeat(30),
+      "\n\nA final paragraph.\n",
+    ]
+    .concat()
+  }
+
+  #[test]
+  fn chunks_start_at_headings_and_fit_the_budget() {
+    let node = TranslatorNode::new("t", MODEL, "中文").with_max_chunk_tokens(120);
+    let counter = counter_for_model(MODEL);
+    let chunks = node.split(&document());
+
+    // Introduction and Method share a chunk; Results is too large for one
+    // and is filled between paragraphs and words.
+    assert!(chunks.len() >= 3, "{:?}", chunks);
+    assert!(chunks[0].text.starts_with("# Introduction"));
+    assert!(chunks[0].text.contains("## Method"));
+    assert!(chunks[1].text.starts_with("## Results"));
+    assert_eq!(chunks[1].heading.as_deref(), Some("Results"));
+    assert_eq!(chunks.last().unwrap().heading.as_deref(), Some("Results"));
+    for chunk in &chunks {
+      assert!(
+        counter.count_tokens(&chunk.text) as usize <= 120,
+        "{:?}",
+        chunk
+      );
+    }
+    let indexes: Vec<_> = chunks.iter().map(|chunk| chunk.index).collect();
+    assert_eq!(indexes, (0..chunks.len()).collect::<Vec<_>>());
+
+    // Nothing is lost between chunks
+    let rejoined: String = chunks
+      .iter()
+      .map(|chunk| chunk.text.split_whitespace().collect::<Vec<_>>().join(" "))
+      .collect::<Vec<_>>()
+      .join(" ");
+    assert_eq!(
+      rejoined,
+      document().split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+  }
+
+  #[test]
+  fn headings_inside_code_fences_and_latex_sections() {
+    let text =
+      "\\section{Introduction}\nText.\n```\n# not a heading\n```\n\\subsection*{Setup}\nMore.\n";
+    let sections = sections(text);
+    let headings: Vec<_> = sections
+      .iter()
+      .map(|section| section.heading.as_deref())
+      .collect();
+    assert_eq!(headings, vec![Some("Introduction"), Some("Setup")]);
+  }
+
+  #[test]
+  fn glossary_violations_flag_terms_translated_differently() {
+    let glossary = BTreeMap::from([
+      ("attention".to_string(), "注意力".to_string()),
+      ("Transformer".to_string(), "Transformer".to_string()),
+      ("dropout".to_string(), "丢弃".to_string()),
+    ]);
+    let violations = glossary_violations(
+      &glossary,
+      2,
+      "The Transformer uses Attention.",
+      "Transformer 使用关注机制。",
+    );
+    assert_eq!(
+      violations,
+      vec![GlossaryViolation {
+        chunk: 2,
+        term: "attention".to_string(),
+        expected: "注意力".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn prompts_carry_the_glossary_and_the_previous_chunk() {
+    let node = TranslatorNode::new("t", MODEL, "中文").with_context_chars(5);
+    let glossary = BTreeMap::from([("attention".to_string(), "注意力".to_string())]);
+    let prompt = node.chunk_prompt(
+      "Second part.",
+      &glossary,
+      Some(("First part.", "第一部分。")),
+    );
+    assert!(prompt.contains("- attention → 注意力"));
+    assert!(prompt.contains("part.\n---\n第一部分。"));
+    assert!(prompt.contains("into 中文"));
+    assert!(prompt.ends_with("Second part."));
+
+    assert!(
+      !node
+        .chunk_prompt("First part.", &BTreeMap::new(), None)
+        .contains("Context.")
+    );
+  }
+
+  #[test]
+  fn prompt_placeholders_are_filled_once() {
+    let node = TranslatorNode::new("t", MODEL, "中文")
+      .with_prompt("Into {{ language }}, keeping \\{{text}} literal:\n{{text}}");
+    let prompt = node.chunk_prompt("Use {{language}} here.", &BTreeMap::new(), None);
+    assert_eq!(
+      prompt,
+      "Into 中文, keeping {{text}} literal:\nUse {{language}} here."
+    );
+
+    let appended = TranslatorNode::new("t", MODEL, "中文")
+      .with_prompt("Into {{language}}.")
+      .chunk_prompt("Body.", &BTreeMap::new(), None);
+    assert_eq!(appended, "Into 中文.\n\nBody.");
+  }
+
+  async fn init_mock_model(model: &str) {
+    let path = std::env::temp_dir().join(format!(
+      "agentflow-translator-mock-{}.yml",
+      uuid::Uuid::new_v4()
+    ));
+    std::fs::write(
+      &path,
+      format!(
+        r#"
+models:
+  {model}:
+    vendor: mock
+    type: text
+    model_id: {model}
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#
+      ),
+    )
+    .unwrap();
+    AgentFlow::init_with_config(path.to_str().unwrap())
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn translates_chunk_by_chunk_and_reports_glossary_violations() {
+    let _guard = crate::LLM_TEST_LOCK.lock().await;
+    let model = format!("mock-translator-{}", uuid::Uuid::new_v4());
+    // SAFETY: LLM_TEST_LOCK serializes mutation of process-wide mock env vars.
+    unsafe {
+      std::env::set_var(
+        "AGENTFLOW_MOCK_RESPONSES",
+        serde_json::to_string(&vec![
+          "# 引言\n\n注意力很重要。",
+          "方法\n\n我们使用关注机制。",
+        ])
+        .unwrap(),
+      );
+      std::env::remove_var("AGENTFLOW_MOCK_TOOL_CALLS");
+    }
+    init_mock_model(&model).await;
+
+    let node = TranslatorNode::new("translator", &model, "中文")
+      .with_max_chunk_tokens(12)
+      .with_glossary(BTreeMap::from([(
+        "attention".to_string(),
+        "注意力".to_string(),
+      )]));
+    let inputs = HashMap::from([(
+      "text".to_string(),
+      FlowValue::Json(json!(
+        "# Introduction\n\nAttention matters.\n\n## Method\n\nWe use attention.\n"
+      )),
+    )]);
+    let outputs = node.execute(&inputs).await.unwrap();
+    // SAFETY: cleanup of the dedicated mock env var after the run.
+    unsafe {
+      std::env::remove_var("AGENTFLOW_MOCK_RESPONSES");
+    }
+
+    let output = |key: &str| match &outputs[key] {
+      FlowValue::Json(value) => value.clone(),
+      other => panic!("{key} is not JSON: {other:?}"),
+    };
+    // The second chunk's dropped `##` is put back.
+    assert_eq!(
+      output("translation"),
+      json!("# 引言\n\n注意力很重要。\n\n## 方法\n\n我们使用关注机制。")
+    );
+    let chunks: Vec<ChunkUsage> = serde_json::from_value(output("chunks")).unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[1].heading.as_deref(), Some("Method"));
+    assert_eq!(chunks[0].prompt_tokens, Some(50));
+    assert_eq!(output("usage")["prompt_tokens"], json!(100));
+    assert_eq!(
+      output("glossary_violations"),
+      json!([{ "chunk": 1, "term": "attention", "expected": "注意力" }])
+    );
+  }
+}
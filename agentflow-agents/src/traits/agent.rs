@@ -1,20 +1,36 @@
 //! Agent Application Trait
 //!
-//! Defines the common interface for all AI agent applications
+//! Defines the common interface for all AI agent applications. An agent's
+//! lifecycle is `initialize(config)`, `run(inputs)`, then `shutdown()`;
+//! [`crate::run_agent_cli`] drives it from the command line.
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::{BatchProcessor, FileDiscovery};
 
 /// Common interface for AI agent applications
 #[async_trait]
-pub trait AgentApplication {
+pub trait AgentApplication: Send + Sync {
   /// Configuration type for this agent
-  type Config: for<'de> Deserialize<'de> + Send + Sync;
+  type Config: AgentConfig + Default;
 
   /// Result type produced by this agent
   type Result: Serialize + Send + Sync;
 
+  /// Agent name: the binary's name, the stem of its config file
+  /// `~/.agentflow/agents/<NAME>.yml` and, uppercased, the prefix of its
+  /// environment overrides
+  const NAME: &'static str;
+
+  /// Agent version
+  const VERSION: &'static str = "0.1.0";
+
+  /// One-line description for `--help`
+  const ABOUT: &'static str = "";
+
   /// Initialize the agent with configuration
   async fn initialize(config: Self::Config) -> crate::AgentResult<Self>
   where
@@ -23,6 +39,25 @@ pub trait AgentApplication {
   /// Execute the agent on a single input
   async fn execute(&self, input: &str) -> crate::AgentResult<Self::Result>;
 
+  /// Run the agent on the inputs of one invocation. By default each input
+  /// is executed in turn; one input gives its result, several an array.
+  async fn run(&self, inputs: Vec<String>) -> crate::AgentResult<Value> {
+    let mut results = Vec::with_capacity(inputs.len());
+    for input in &inputs {
+      results.push(serde_json::to_value(self.execute(input).await?)?);
+    }
+    Ok(match results.len() {
+      1 => results.remove(0),
+      _ => Value::Array(results),
+    })
+  }
+
+  /// Release what `initialize` acquired; called after `run`, whether or
+  /// not it succeeded
+  async fn shutdown(&self) -> crate::AgentResult<()> {
+    Ok(())
+  }
+
   /// Batch process multiple inputs (default implementation)
   async fn batch_process(&self, inputs: Vec<&str>) -> crate::AgentResult<Vec<Self::Result>> {
     let mut results = Vec::with_capacity(inputs.len());
@@ -34,31 +69,156 @@ pub trait AgentApplication {
   }
 
   /// Get agent name/identifier
-  fn name(&self) -> &'static str;
+  fn name(&self) -> &'static str {
+    Self::NAME
+  }
 
   /// Get agent version
   fn version(&self) -> &'static str {
-    "0.1.0"
+    Self::VERSION
+  }
+
+  /// The configuration the file, environment and flag layers apply to
+  fn default_config() -> Self::Config
+  where
+    Self: Sized,
+  {
+    Self::Config::default()
+  }
+
+  /// Named configurations offered by `config create --preset`
+  fn config_presets() -> Vec<(&'static str, Self::Config)>
+  where
+    Self: Sized,
+  {
+    Vec::new()
+  }
+
+  /// Agent-specific arguments of the `run` subcommand
+  fn run_args() -> Vec<clap::Arg>
+  where
+    Self: Sized,
+  {
+    Vec::new()
+  }
+
+  /// Apply the `run_args` to the configuration; runs after every other
+  /// layer, so these flags win
+  fn apply_run_args(
+    _config: &mut Self::Config,
+    _matches: &clap::ArgMatches,
+  ) -> crate::AgentResult<()>
+  where
+    Self: Sized,
+  {
+    Ok(())
+  }
+}
+
+/// How [`FileAgent::process_directory`] searches and batches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryOptions {
+  /// Directories below the given one searched when recursive
+  pub max_depth: usize,
+  /// Also process files and directories whose names start with `.`
+  pub include_hidden: bool,
+  /// Files processed at once
+  pub concurrency: usize,
+}
+
+impl Default for DirectoryOptions {
+  fn default() -> Self {
+    Self {
+      max_depth: crate::DEFAULT_MAX_DEPTH,
+      include_hidden: false,
+      concurrency: 3,
+    }
+  }
+}
+
+/// What [`FileAgent::process_directory`] did with each file it found, in
+/// path order
+#[derive(Debug, Clone)]
+pub struct DirectoryResult {
+  pub directory: PathBuf,
+  pub results: Vec<(PathBuf, Result<Value, String>)>,
+  /// Files whose output came from the batch checkpoint
+  pub resumed: usize,
+  /// Files processed in this run
+  pub processed: usize,
+}
+
+impl DirectoryResult {
+  pub fn succeeded(&self) -> usize {
+    self
+      .results
+      .iter()
+      .filter(|(_, result)| result.is_ok())
+      .count()
+  }
+
+  pub fn failed(&self) -> usize {
+    self.results.len() - self.succeeded()
   }
 }
 
-/// File-based agent that processes files
+/// An agent that processes files of given types, one at a time or every
+/// one in a directory
 #[async_trait]
-pub trait FileAgent: AgentApplication {
+pub trait FileAgent: Send + Sync {
+  /// The extensions of the files the agent processes, without the dot;
+  /// they match case-insensitively
+  fn supported_extensions(&self) -> Vec<&'static str>;
+
   /// Process a single file
-  async fn process_file<P: AsRef<Path> + Send + Sync>(
-    &self,
-    file_path: P,
-  ) -> crate::AgentResult<Self::Result>;
+  async fn process_file(&self, path: &Path) -> crate::AgentResult<Value>;
 
-  /// Process multiple files in a directory
-  async fn process_directory<P: AsRef<Path> + Send + Sync>(
+  /// How `process_directory` searches and batches
+  fn directory_options(&self) -> DirectoryOptions {
+    DirectoryOptions::default()
+  }
+
+  /// The processor `process_directory` runs the files on; override it to
+  /// add retries, progress or a checkpoint
+  fn batch_processor(&self) -> BatchProcessor<PathBuf, Value> {
+    BatchProcessor::new(self.directory_options().concurrency)
+  }
+
+  /// Process every supported file in `directory`, and in its
+  /// subdirectories when `recursive`. Symlinks are skipped unless
+  /// `follow_symlinks`. Fails only when the directory cannot be read; a
+  /// file that fails is in the result.
+  async fn process_directory(
     &self,
-    directory: P,
-  ) -> crate::AgentResult<Vec<(std::path::PathBuf, Self::Result)>>;
+    directory: &Path,
+    recursive: bool,
+    follow_symlinks: bool,
+  ) -> crate::AgentResult<DirectoryResult> {
+    let options = self.directory_options();
+    let files = FileDiscovery::new(&self.supported_extensions())
+      .recursive(recursive)
+      .follow_symlinks(follow_symlinks)
+      .max_depth(options.max_depth)
+      .include_hidden(options.include_hidden)
+      .discover(directory)
+      .await?;
+    tracing::info!(
+      "Found {} file(s) to process in {}",
+      files.len(),
+      directory.display()
+    );
 
-  /// Get supported file extensions
-  fn supported_extensions(&self) -> Vec<&'static str>;
+    let report = self
+      .batch_processor()
+      .process(files, |path| async move { self.process_file(&path).await })
+      .await?;
+    Ok(DirectoryResult {
+      directory: directory.to_path_buf(),
+      results: report.results,
+      resumed: report.resumed,
+      processed: report.processed,
+    })
+  }
 }
 
 /// Batch processing capabilities
@@ -94,3 +254,193 @@ pub trait AgentConfig: for<'de> Deserialize<'de> + Serialize + Send + Sync {
     Ok(config)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// Outputs each file's name; a file named `broken.pdf` fails
+  struct NameAgent {
+    options: DirectoryOptions,
+  }
+
+  #[async_trait]
+  impl FileAgent for NameAgent {
+    fn supported_extensions(&self) -> Vec<&'static str> {
+      vec!["pdf", ".md"]
+    }
+
+    async fn process_file(&self, path: &Path) -> crate::AgentResult<Value> {
+      let name = path.file_name().unwrap().to_string_lossy().to_string();
+      if name == "broken.pdf" {
+        return Err("unreadable".into());
+      }
+      Ok(json!(name))
+    }
+
+    fn directory_options(&self) -> DirectoryOptions {
+      self.options.clone()
+    }
+  }
+
+  fn agent() -> NameAgent {
+    NameAgent {
+      options: DirectoryOptions::default(),
+    }
+  }
+
+  fn touch(path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, b"").unwrap();
+  }
+
+  /// The processed files, relative to `root`, with their outputs
+  fn outputs(root: &Path, result: &DirectoryResult) -> Vec<(String, Result<Value, String>)> {
+    result
+      .results
+      .iter()
+      .map(|(path, output)| {
+        let relative = path
+          .strip_prefix(root)
+          .unwrap()
+          .to_string_lossy()
+          .replace('\\', "/");
+        (relative, output.clone())
+      })
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn matches_extensions_case_insensitively_and_recurses() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for name in [
+      "a.pdf",
+      "B.PDF",
+      "notes.Md",
+      "image.png",
+      "noext",
+      "sub/c.Pdf",
+      "sub/deeper/d.pdf",
+    ] {
+      touch(&root.join(name));
+    }
+    touch(&root.join(".hidden/e.pdf"));
+    touch(&root.join(".f.pdf"));
+    std::fs::create_dir_all(root.join("folder.pdf")).unwrap();
+
+    let flat = agent().process_directory(root, false, false).await.unwrap();
+    let names: Vec<_> = outputs(root, &flat)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect();
+    assert_eq!(names, ["B.PDF", "a.pdf", "notes.Md"]);
+    assert_eq!((flat.succeeded(), flat.processed, flat.resumed), (3, 3, 0));
+
+    let recursive = agent().process_directory(root, true, false).await.unwrap();
+    let names: Vec<_> = outputs(root, &recursive)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect();
+    assert_eq!(
+      names,
+      [
+        "B.PDF",
+        "a.pdf",
+        "notes.Md",
+        "sub/c.Pdf",
+        "sub/deeper/d.pdf"
+      ]
+    );
+
+    let shallow = NameAgent {
+      options: DirectoryOptions {
+        max_depth: 1,
+        ..DirectoryOptions::default()
+      },
+    };
+    let result = shallow.process_directory(root, true, false).await.unwrap();
+    assert!(
+      outputs(root, &result)
+        .iter()
+        .all(|(name, _)| name != "sub/deeper/d.pdf")
+    );
+    assert_eq!(result.results.len(), 4);
+
+    let with_hidden = NameAgent {
+      options: DirectoryOptions {
+        include_hidden: true,
+        ..DirectoryOptions::default()
+      },
+    };
+    let result = with_hidden
+      .process_directory(root, true, false)
+      .await
+      .unwrap();
+    let names: Vec<_> = outputs(root, &result)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect();
+    assert!(names.contains(&".hidden/e.pdf".to_string()));
+    assert!(names.contains(&".f.pdf".to_string()));
+  }
+
+  #[tokio::test]
+  async fn failed_files_are_in_the_result() {
+    let dir = tempfile::tempdir().unwrap();
+    touch(&dir.path().join("broken.pdf"));
+    touch(&dir.path().join("good.pdf"));
+
+    let result = agent()
+      .process_directory(dir.path(), false, false)
+      .await
+      .unwrap();
+    assert_eq!(
+      outputs(dir.path(), &result),
+      [
+        ("broken.pdf".to_string(), Err("unreadable".to_string())),
+        ("good.pdf".to_string(), Ok(json!("good.pdf"))),
+      ]
+    );
+    assert_eq!((result.succeeded(), result.failed()), (1, 1));
+
+    assert!(
+      agent()
+        .process_directory(&dir.path().join("missing"), false, false)
+        .await
+        .is_err()
+    );
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn symlinks_are_followed_only_when_asked() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (root, elsewhere) = (dir.path().join("root"), dir.path().join("elsewhere"));
+    touch(&root.join("a.pdf"));
+    touch(&elsewhere.join("linked_dir/b.pdf"));
+    touch(&elsewhere.join("c.pdf"));
+    symlink(elsewhere.join("linked_dir"), root.join("linked_dir")).unwrap();
+    symlink(elsewhere.join("c.pdf"), root.join("c.pdf")).unwrap();
+    symlink(elsewhere.join("gone.pdf"), root.join("gone.pdf")).unwrap();
+    // A cycle back to the root is searched once
+    symlink(&root, root.join("loop")).unwrap();
+
+    let result = agent().process_directory(&root, true, false).await.unwrap();
+    let names: Vec<_> = outputs(&root, &result)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect();
+    assert_eq!(names, ["a.pdf"]);
+
+    let result = agent().process_directory(&root, true, true).await.unwrap();
+    let names: Vec<_> = outputs(&root, &result)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect();
+    assert_eq!(names, ["a.pdf", "c.pdf", "linked_dir/b.pdf"]);
+  }
+}
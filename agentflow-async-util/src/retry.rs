@@ -280,6 +280,7 @@ impl ErrorPattern {
           AgentFlowError::ResourcePoolExhausted { .. } => "ResourcePoolExhausted",
           AgentFlowError::MemoryLimitExceeded { .. } => "MemoryLimitExceeded",
           AgentFlowError::ConcurrencyLimitExceeded { .. } => "ConcurrencyLimitExceeded",
+          AgentFlowError::BudgetExceeded { .. } => "BudgetExceeded",
           AgentFlowError::DependencyNotMet { .. } => "DependencyNotMet",
           AgentFlowError::SharedStateError { .. } => "SharedStateError",
           AgentFlowError::TaskCancelled => "TaskCancelled",
@@ -0,0 +1,392 @@
+//! Exit codes and the structured error object for scripts wrapping the
+//! CLI.
+//!
+//! | Code | Category       | Meaning                                            |
+//! |------|----------------|----------------------------------------------------|
+//! | 0    | —              | Success                                            |
+//! | 1    | `runtime`      | Any other failure                                  |
+//! | 2    | `usage`        | Bad arguments or flags, a path that does not exist |
+//! | 3    | `validation`   | Invalid workflow, config or input document         |
+//! | 4    | `node_failure` | A workflow node failed at runtime                  |
+//! | 5    | `provider`     | Provider auth, quota, rate limit or API error      |
+//! | 6    | `timeout`      | A timeout expired or the work was cancelled        |
+//!
+//! SIGINT / SIGTERM keep their POSIX exit codes (130 / 143), and the
+//! commands that document their own codes (`doctor`, `eval run`,
+//! `serve`, `backup`) keep them.
+//!
+//! A handler picks the category by returning a [`CliError`] somewhere in
+//! its error chain; otherwise [`classify`] infers it from the typed
+//! errors in the chain (`LLMError`, `AgentFlowError`, I/O and parse
+//! errors). A workflow whose nodes failed exits by the first failed
+//! node's error, through [`node_error_category`]: 5 when a provider
+//! refused or could not be reached, 6 when it timed out, else 4. With
+//! `--error-format json` the error is printed to stderr as
+//! one [`ErrorReport`] line instead of `Error: …`.
+
+use agentflow_core::AgentFlowError;
+use agentflow_llm::LLMError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+  Runtime,
+  Usage,
+  Validation,
+  NodeFailure,
+  Provider,
+  Timeout,
+}
+
+impl ErrorCategory {
+  pub fn exit_code(self) -> i32 {
+    match self {
+      Self::Runtime => 1,
+      Self::Usage => 2,
+      Self::Validation => 3,
+      Self::NodeFailure => 4,
+      Self::Provider => 5,
+      Self::Timeout => 6,
+    }
+  }
+}
+
+/// An error whose category the handler knows.
+#[derive(Debug)]
+pub struct CliError {
+  pub category: ErrorCategory,
+  pub message: String,
+  /// The workflow node the error is about, if any.
+  pub node_id: Option<String>,
+}
+
+impl CliError {
+  pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+    Self {
+      category,
+      message: message.into(),
+      node_id: None,
+    }
+  }
+
+  pub fn usage(message: impl Into<String>) -> Self {
+    Self::new(ErrorCategory::Usage, message)
+  }
+
+  pub fn validation(message: impl Into<String>) -> Self {
+    Self::new(ErrorCategory::Validation, message)
+  }
+
+  pub fn node_failure(node_id: impl Into<String>, message: impl Into<String>) -> Self {
+    Self {
+      node_id: Some(node_id.into()),
+      ..Self::new(ErrorCategory::NodeFailure, message)
+    }
+  }
+
+  /// A node failure in the category of the node's `error`.
+  pub fn failed_node(
+    node_id: impl Into<String>,
+    error: &AgentFlowError,
+    message: impl Into<String>,
+  ) -> Self {
+    Self {
+      category: node_error_category(error),
+      ..Self::node_failure(node_id, message)
+    }
+  }
+
+  pub fn timeout(message: impl Into<String>) -> Self {
+    Self::new(ErrorCategory::Timeout, message)
+  }
+}
+
+impl fmt::Display for CliError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for CliError {}
+
+/// The `--error-format json` object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReport {
+  pub code: i32,
+  pub category: ErrorCategory,
+  /// The full error chain, as `Error: …` prints it.
+  pub message: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub node_id: Option<String>,
+}
+
+impl ErrorReport {
+  pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+    Self {
+      code: category.exit_code(),
+      category,
+      message: message.into(),
+      node_id: None,
+    }
+  }
+}
+
+/// The category of `err`: the first error in its chain, outermost
+/// first, that maps to one.
+pub fn classify(err: &anyhow::Error) -> ErrorReport {
+  let (category, node_id) = err
+    .chain()
+    .find_map(category_of)
+    .unwrap_or((ErrorCategory::Runtime, None));
+  ErrorReport {
+    node_id,
+    ..ErrorReport::new(category, format!("{:#}", err))
+  }
+}
+
+fn category_of(err: &(dyn std::error::Error + 'static)) -> Option<(ErrorCategory, Option<String>)> {
+  if let Some(err) = err.downcast_ref::<CliError>() {
+    return Some((err.category, err.node_id.clone()));
+  }
+  if let Some(err) = err.downcast_ref::<LLMError>() {
+    return Some((llm_category(err), None));
+  }
+  if let Some(err) = err.downcast_ref::<AgentFlowError>() {
+    return flow_category(err);
+  }
+  if let Some(err) = err.downcast_ref::<std::io::Error>() {
+    return (err.kind() == std::io::ErrorKind::NotFound).then_some((ErrorCategory::Usage, None));
+  }
+  if err.is::<serde_yaml::Error>() || err.is::<serde_json::Error>() {
+    return Some((ErrorCategory::Validation, None));
+  }
+  None
+}
+
+fn llm_category(err: &LLMError) -> ErrorCategory {
+  match err {
+    LLMError::TimeoutError { .. } => ErrorCategory::Timeout,
+    LLMError::ConfigurationError { .. }
+    | LLMError::ModelNotFound { .. }
+    | LLMError::UnsupportedProvider { .. }
+    | LLMError::InvalidModelConfig { .. }
+    | LLMError::ContextOverflow { .. }
+    | LLMError::PromptTemplateError { .. } => ErrorCategory::Validation,
+    LLMError::MissingApiKey { .. }
+    | LLMError::HttpError { .. }
+    | LLMError::RateLimitExceeded { .. }
+    | LLMError::AuthenticationError { .. }
+    | LLMError::QuotaExceeded { .. }
+    | LLMError::ServiceUnavailable { .. }
+    | LLMError::NetworkError { .. }
+    | LLMError::ApiError { .. } => ErrorCategory::Provider,
+    _ => ErrorCategory::Runtime,
+  }
+}
+
+fn flow_category(err: &AgentFlowError) -> Option<(ErrorCategory, Option<String>)> {
+  let category = match err {
+    AgentFlowError::TimeoutExceeded { .. } | AgentFlowError::TaskCancelled => {
+      ErrorCategory::Timeout
+    }
+    AgentFlowError::FlowDefinitionError { .. }
+    | AgentFlowError::CircularFlow
+    | AgentFlowError::ConfigurationError { .. }
+    | AgentFlowError::ValidationError(_) => ErrorCategory::Validation,
+    AgentFlowError::NodeExecutionFailed { .. }
+    | AgentFlowError::NodePartialExecutionFailed { .. }
+    | AgentFlowError::BudgetExceeded { .. }
+    | AgentFlowError::NodeInputError { .. }
+    | AgentFlowError::RetryExhausted { .. } => ErrorCategory::NodeFailure,
+    AgentFlowError::CircuitBreakerOpen { node_id } => {
+      return Some((ErrorCategory::NodeFailure, Some(node_id.clone())));
+    }
+    AgentFlowError::DependencyNotMet { node_id, .. } => {
+      return Some((ErrorCategory::NodeFailure, Some(node_id.clone())));
+    }
+    _ => return None,
+  };
+  Some((category, None))
+}
+
+/// Text of the `LLMError`s a node reports through its message, as
+/// `LlmNode` wraps them.
+const TIMEOUT_MARKERS: &[&str] = &["Request timeout after "];
+const PROVIDER_MARKERS: &[&str] = &[
+  "API key missing for provider '",
+  "Authentication failed for provider '",
+  "Rate limit exceeded for provider '",
+  "Quota exceeded for provider '",
+  "Service unavailable for provider '",
+  "HTTP request failed: ",
+  "API error from '",
+  "Network error: ",
+];
+
+/// The category of the error a workflow node failed with: `timeout`
+/// for an expired or cancelled node, `provider` for a provider that
+/// refused the request or could not be reached, else `node_failure`.
+/// Nodes carry model errors as text, so those are recognised by the
+/// `LLMError` message they contain.
+pub fn node_error_category(err: &AgentFlowError) -> ErrorCategory {
+  let has = |message: &str, markers: &[&str]| markers.iter().any(|m| message.contains(m));
+  match err {
+    AgentFlowError::TimeoutExceeded { .. } | AgentFlowError::TaskCancelled => {
+      ErrorCategory::Timeout
+    }
+    AgentFlowError::NetworkError { .. } | AgentFlowError::RateLimitExceeded { .. } => {
+      ErrorCategory::Provider
+    }
+    AgentFlowError::RetryExhausted { last_error, .. } => node_error_category(last_error),
+    AgentFlowError::AsyncExecutionError { message }
+    | AgentFlowError::NodeExecutionFailed { message } => {
+      if has(message, TIMEOUT_MARKERS) {
+        ErrorCategory::Timeout
+      } else if has(message, PROVIDER_MARKERS) {
+        ErrorCategory::Provider
+      } else {
+        ErrorCategory::NodeFailure
+      }
+    }
+    _ => ErrorCategory::NodeFailure,
+  }
+}
+
+/// `--error-format`, read from the raw arguments for errors clap itself
+/// reports, before there is a parsed [`ErrorFormat`] flag to look at.
+pub fn error_format_from_args() -> ErrorFormat {
+  let args: Vec<String> = std::env::args().collect();
+  let json = args.iter().enumerate().any(|(index, arg)| {
+    arg == "--error-format=json"
+      || (arg == "--error-format" && args.get(index + 1).is_some_and(|next| next == "json"))
+  });
+  if json {
+    ErrorFormat::Json
+  } else {
+    ErrorFormat::Text
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+  #[default]
+  Text,
+  Json,
+}
+
+impl std::str::FromStr for ErrorFormat {
+  type Err = anyhow::Error;
+
+  fn from_str(value: &str) -> anyhow::Result<Self> {
+    match value {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      other => anyhow::bail!("unknown error format '{}'", other),
+    }
+  }
+}
+
+/// Prints `report` to stderr in `format` and exits with its code.
+pub fn exit_with(report: &ErrorReport, format: ErrorFormat) -> ! {
+  match format {
+    ErrorFormat::Json => eprintln!(
+      "{}",
+      serde_json::to_string(report).unwrap_or_else(|_| report.message.clone())
+    ),
+    // Same text the CLI always printed.
+    ErrorFormat::Text => eprintln!("Error: {}", report.message),
+  }
+  std::process::exit(report.code)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use anyhow::Context;
+
+  #[test]
+  fn the_first_categorised_error_in_the_chain_wins() {
+    let err = anyhow::Error::new(LLMError::AuthenticationError {
+      provider: "openai".into(),
+      message: "bad key".into(),
+    })
+    .context("Request to model 'gpt-4o' failed");
+    let report = classify(&err);
+    assert_eq!((report.code, report.category), (5, ErrorCategory::Provider));
+    assert!(
+      report
+        .message
+        .starts_with("Request to model 'gpt-4o' failed: ")
+    );
+
+    let err = anyhow::Error::new(CliError::node_failure("fetch", "1 node(s) failed: fetch"))
+      .context("outer");
+    let report = classify(&err);
+    assert_eq!(report.code, 4);
+    assert_eq!(report.node_id.as_deref(), Some("fetch"));
+
+    let missing = std::fs::read("/nonexistent/agentflow").context("Failed to read workflow file");
+    assert_eq!(
+      classify(&missing.unwrap_err()).category,
+      ErrorCategory::Usage
+    );
+    assert_eq!(classify(&anyhow::anyhow!("boom")).code, 1);
+  }
+
+  #[test]
+  fn failed_nodes_exit_by_their_error() {
+    let llm_failure = |err: LLMError| AgentFlowError::AsyncExecutionError {
+      message: format!("LLM execution failed: {}", err),
+    };
+    let cases = [
+      (
+        llm_failure(LLMError::AuthenticationError {
+          provider: "openai".into(),
+          message: "bad key".into(),
+        }),
+        5,
+      ),
+      (
+        llm_failure(LLMError::RateLimitExceeded {
+          provider: "openai".into(),
+          message: "slow down".into(),
+        }),
+        5,
+      ),
+      (
+        llm_failure(LLMError::TimeoutError { timeout_ms: 30_000 }),
+        6,
+      ),
+      (
+        AgentFlowError::RetryExhausted {
+          attempts: 3,
+          last_error: Box::new(AgentFlowError::TimeoutExceeded { duration_ms: 10 }),
+        },
+        6,
+      ),
+      (
+        AgentFlowError::NodeInputError {
+          message: "missing 'prompt'".into(),
+        },
+        4,
+      ),
+    ];
+    for (err, code) in cases {
+      let err = anyhow::Error::new(CliError::failed_node("ask", &err, "1 node(s) failed: ask"));
+      let report = classify(&err);
+      assert_eq!(report.code, code, "{}", report.message);
+      assert_eq!(report.node_id.as_deref(), Some("ask"));
+    }
+  }
+
+  #[test]
+  fn json_report_omits_an_absent_node_id() {
+    let report = ErrorReport::new(ErrorCategory::Timeout, "timed out");
+    assert_eq!(
+      serde_json::to_string(&report).unwrap(),
+      r#"{"code":6,"category":"timeout","message":"timed out"}"#
+    );
+  }
+}
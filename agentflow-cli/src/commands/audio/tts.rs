@@ -1,17 +1,230 @@
-use agentflow_llm::{AgentFlow, TtsRequest};
-use anyhow::Result;
+//! `agentflow audio tts` — text-to-speech through the modality
+//! dispatcher.
+//!
+//! The text comes inline, from `--input-file <path>` or from stdin
+//! (`--input-file -`). Text longer than `--max-chars` is split at
+//! sentence boundaries and synthesised chunk by chunk, written either
+//! as ordered files (`<stem>_001.<ext>`, …) or, with `--merge`, joined
+//! into one WAV. `--chapters` splits the text on markdown headings
+//! first and names each chapter's file after its heading
+//! (`<stem>_01_<slug>.<ext>`).
+
+use agentflow_llm::{
+  AgentFlow, TtsProvider, TtsRequest,
+  providers::modality::{merge_wav, split_tts_text},
+};
+use anyhow::{Context, Result, bail};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-pub async fn execute(
+pub const DEFAULT_MODEL: &str = "step-tts-mini";
+const PREVIEW_CHARS: usize = 80;
+
+pub struct TtsOptions {
+  pub text: Option<String>,
+  pub input_file: Option<String>,
+  pub voice: String,
+  pub output: String,
+  pub model: Option<String>,
+  pub format: String,
+  pub speed: f32,
+  pub emotion: Option<String>,
+  pub max_chars: usize,
+  pub chapters: bool,
+  pub merge: bool,
+}
+
+/// Splits the `[TEXT] <VOICE> <OUTPUT>` positionals: TEXT is left out
+/// when the text comes from `--input-file`.
+pub fn split_positionals(
+  args: Vec<String>,
+  input_file: bool,
+) -> Result<(Option<String>, String, String)> {
+  let count = args.len();
+  let mut args = args.into_iter();
+  let text = if input_file { None } else { args.next() };
+  match (args.next(), args.next(), args.next()) {
+    (Some(voice), Some(output), None) => Ok((text, voice, output)),
+    _ if input_file => bail!(
+      "Expected <VOICE> <OUTPUT> after --input-file, got {} argument(s)",
+      count
+    ),
+    _ => bail!(
+      "Expected <TEXT> <VOICE> <OUTPUT> (or --input-file <FILE> <VOICE> <OUTPUT>), got {} argument(s)",
+      count
+    ),
+  }
+}
+
+/// A markdown section of the input: the text under one heading.
+#[derive(Debug, Clone, PartialEq)]
+struct Chapter {
+  /// Heading text; `None` for text before the first heading.
+  title: Option<String>,
   text: String,
-  model: Option<String>,
-  voice: String,
-  format: String,
-  speed: f32,
-  output: String,
-  emotion: Option<String>,
-) -> Result<()> {
-  let model = model.unwrap_or_else(|| "step-tts-mini".to_string());
+}
+
+/// One output file and the chunks synthesised into it.
+#[derive(Debug, Clone, PartialEq)]
+struct Part {
+  file: PathBuf,
+  label: String,
+  chunks: Vec<String>,
+}
+
+/// Splits markdown on `#`…`######` headings, outside fenced code
+/// blocks. Each chapter's spoken text starts with its heading, so the
+/// title is read out. Chapters with no text at all are dropped.
+fn split_chapters(text: &str) -> Vec<Chapter> {
+  let mut chapters = vec![Chapter {
+    title: None,
+    text: String::new(),
+  }];
+  let mut in_fence = false;
+  for line in text.lines() {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      in_fence = !in_fence;
+    }
+    let hashes = trimmed.chars().take_while(|&ch| ch == '#').count();
+    let heading = (!in_fence && (1..=6).contains(&hashes))
+      .then(|| trimmed[hashes..].strip_prefix([' ', '\t']))
+      .flatten()
+      .map(|title| title.trim().trim_end_matches('#').trim());
+    match heading {
+      Some(title) if !title.is_empty() => chapters.push(Chapter {
+        title: Some(title.to_string()),
+        text: format!("{title}\n"),
+      }),
+      _ => {
+        if let Some(chapter) = chapters.last_mut() {
+          chapter.text.push_str(line);
+          chapter.text.push('\n');
+        }
+      }
+    }
+  }
+  chapters.retain(|chapter| !chapter.text.trim().is_empty());
+  chapters
+}
+
+/// `<dir>/<stem>` and the extension output files share.
+fn output_base(output: &Path, format: &str) -> (PathBuf, String) {
+  let stem = output.file_stem().map_or_else(
+    || "speech".into(),
+    |stem| stem.to_string_lossy().into_owned(),
+  );
+  let ext = output.extension().map_or_else(
+    || format.to_string(),
+    |ext| ext.to_string_lossy().into_owned(),
+  );
+  (output.with_file_name(stem), ext)
+}
+
+/// `base` with `suffix` appended to its file name.
+fn appended(base: &Path, suffix: &str) -> PathBuf {
+  let mut name = base.file_name().unwrap_or_default().to_os_string();
+  name.push(suffix);
+  base.with_file_name(name)
+}
+
+/// `file` for all of `chunks` with `merge` (or when there is only
+/// one chunk), else one `<stem>_NNN.<ext>` file per chunk.
+fn push_parts(
+  parts: &mut Vec<Part>,
+  file: PathBuf,
+  stem: &Path,
+  label: &str,
+  chunks: Vec<String>,
+  ext: &str,
+  merge: bool,
+) {
+  if merge || chunks.len() == 1 {
+    parts.push(Part {
+      file,
+      label: label.to_string(),
+      chunks,
+    });
+    return;
+  }
+  for (index, chunk) in chunks.into_iter().enumerate() {
+    parts.push(Part {
+      file: appended(stem, &format!("_{:03}.{ext}", index + 1)),
+      label: format!("{label} part {}", index + 1),
+      chunks: vec![chunk],
+    });
+  }
+}
+
+fn plan_parts(text: &str, options: &TtsOptions) -> Result<Vec<Part>> {
+  let (base, ext) = output_base(Path::new(&options.output), &options.format);
+  let mut parts = Vec::new();
+  if !options.chapters {
+    let chunks = split_tts_text(text, options.max_chars);
+    if chunks.is_empty() {
+      bail!("No text to synthesise");
+    }
+    let file = PathBuf::from(&options.output);
+    push_parts(&mut parts, file, &base, "text", chunks, &ext, options.merge);
+    return Ok(parts);
+  }
+  let chapters = split_chapters(text);
+  if chapters.is_empty() {
+    bail!("No text to synthesise");
+  }
+  for (index, chapter) in chapters.into_iter().enumerate() {
+    let title = chapter.title.as_deref().unwrap_or("intro");
+    let slug = crate::commands::image::generate::slug(title, "chapter");
+    let stem = appended(&base, &format!("_{:02}_{slug}", index + 1));
+    let chunks = split_tts_text(&chapter.text, options.max_chars);
+    let label = format!("chapter {} \"{title}\"", index + 1);
+    let file = appended(&stem, &format!(".{ext}"));
+    push_parts(&mut parts, file, &stem, &label, chunks, &ext, options.merge);
+  }
+  Ok(parts)
+}
+
+fn read_text(options: &TtsOptions) -> Result<(String, String)> {
+  match options.input_file.as_deref() {
+    Some("-") => {
+      let mut text = String::new();
+      std::io::stdin()
+        .read_to_string(&mut text)
+        .context("Failed to read text from stdin")?;
+      Ok((text, "stdin".to_string()))
+    }
+    Some(path) => {
+      let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read input file {path}"))?;
+      Ok((text, path.to_string()))
+    }
+    None => Ok((
+      options.text.clone().unwrap_or_default(),
+      "inline".to_string(),
+    )),
+  }
+}
+
+fn preview(text: &str) -> String {
+  let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+  if line.chars().count() <= PREVIEW_CHARS {
+    return line;
+  }
+  format!("{}…", line.chars().take(PREVIEW_CHARS).collect::<String>())
+}
+
+pub async fn execute(options: TtsOptions) -> Result<()> {
+  let model = options
+    .model
+    .clone()
+    .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+  if options.merge && !options.format.eq_ignore_ascii_case("wav") {
+    bail!("--merge joins WAV audio; use --format wav");
+  }
+  let (text, source) = read_text(&options)?;
+  let parts = plan_parts(&text, &options)?;
+  let total_chunks: usize = parts.iter().map(|part| part.chunks.len()).sum();
 
   // Initialize AgentFlow so the registry knows about all configured
   // models. The TTS dispatcher then resolves vendor + API key from
@@ -20,18 +233,32 @@ pub async fn execute(
 
   println!("🎙️  AgentFlow Text-to-Speech");
   println!("Model: {}", model);
-  println!("Text: {}", text);
-  println!("Voice: {}", voice);
-  println!("Format: {}", format);
-  println!("Speed: {}", speed);
-  if emotion.is_some() {
+  println!(
+    "Text: {} ({} chars, {})",
+    preview(&text),
+    text.chars().count(),
+    source
+  );
+  println!("Voice: {}", options.voice);
+  println!("Format: {}", options.format);
+  println!("Speed: {}", options.speed);
+  if options.emotion.is_some() {
     // `emotion` was a StepFun voice-label extension. The cross-vendor
     // TtsRequest doesn't carry it; the dispatcher hides vendor-specific
     // niceties for now. Surface the warning so operators know it's
     // dropped rather than silently ignored.
     println!("⚠️  --emotion is currently dropped (vendor-specific knob).");
   }
-  println!("Output: {}", output);
+  if total_chunks > 1 {
+    println!(
+      "Chunks: {} (at most {} chars each) into {} file(s)",
+      total_chunks,
+      options.max_chars,
+      parts.len()
+    );
+  } else {
+    println!("Output: {}", options.output);
+  }
   println!();
 
   let provider = AgentFlow::tts(&model).await?;
@@ -41,32 +268,120 @@ pub async fn execute(
     model
   );
 
+  let start_time = std::time::Instant::now();
+  let mut done = 0;
+  for part in &parts {
+    let mut audio = Vec::with_capacity(part.chunks.len());
+    for chunk in &part.chunks {
+      done += 1;
+      let started = std::time::Instant::now();
+      let bytes = synthesize(provider.as_ref(), &model, chunk, &options)
+        .await
+        .with_context(|| format!("Chunk {done}/{total_chunks} ({}) failed", part.label))?;
+      println!(
+        "  [{done}/{total_chunks}] {}: {} chars → {} bytes in {:?}",
+        part.label,
+        chunk.chars().count(),
+        bytes.len(),
+        started.elapsed()
+      );
+      audio.push(bytes);
+    }
+    let bytes = match <[Vec<u8>; 1]>::try_from(audio) {
+      Ok([bytes]) => bytes,
+      Err(audio) => merge_wav(&audio).with_context(|| format!("Failed to merge {}", part.label))?,
+    };
+    if let Some(parent) = part.file.parent()
+      && !parent.as_os_str().is_empty()
+    {
+      fs::create_dir_all(parent).await?;
+    }
+    fs::write(&part.file, &bytes)
+      .await
+      .with_context(|| format!("Failed to write {}", part.file.display()))?;
+    println!("💾 Saved {} ({} bytes)", part.file.display(), bytes.len());
+  }
+
+  println!();
+  println!("✅ Speech generated in {:?}", start_time.elapsed());
+  println!("🎉 Text-to-speech conversion completed successfully!");
+  Ok(())
+}
+
+async fn synthesize(
+  provider: &dyn TtsProvider,
+  model: &str,
+  chunk: &str,
+  options: &TtsOptions,
+) -> Result<Vec<u8>> {
   let request = TtsRequest {
-    model: model.clone(),
-    input: text,
-    voice,
-    response_format: Some(format),
-    speed: Some(speed),
+    model: model.to_string(),
+    input: chunk.to_string(),
+    voice: options.voice.clone(),
+    response_format: Some(options.format.clone()),
+    speed: Some(options.speed),
     volume: None,
     sample_rate: None,
   };
+  Ok(provider.synthesize(request).await?.audio)
+}
 
-  let start_time = std::time::Instant::now();
-  let response = provider.synthesize(request).await?;
-  let duration = start_time.elapsed();
-
-  println!("✅ Speech generated in {:?}", duration);
+/// `--list-voices`: the voices the model's provider reports.
+pub async fn list_voices(model: Option<String>) -> Result<()> {
+  let model = model.unwrap_or_else(|| DEFAULT_MODEL.to_string());
+  AgentFlow::init().await?;
+  let provider = AgentFlow::tts(&model).await?;
+  let voices = provider.list_voices().await?;
   println!(
-    "💾 Audio size: {} bytes ({})",
-    response.audio.len(),
-    response.mime_type
+    "🎙️  Voices from provider '{}' (model '{}'):",
+    provider.name(),
+    model
   );
-  println!();
+  if voices.is_empty() {
+    println!("  (none reported)");
+  }
+  for voice in &voices {
+    let mut line = format!("  {}", voice.id);
+    if let Some(name) = &voice.name {
+      line.push_str(&format!("  {name}"));
+    }
+    if let Some(description) = &voice.description {
+      line.push_str(&format!("  — {description}"));
+    }
+    println!("{line}");
+  }
+  Ok(())
+}
 
-  println!("💾 Saving audio to: {}", output);
-  fs::write(&output, &response.audio).await?;
-  println!("✅ Audio saved successfully");
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-  println!("🎉 Text-to-speech conversion completed successfully!");
-  Ok(())
+  #[test]
+  fn chapters_split_on_headings_outside_code_fences() {
+    let chapters = split_chapters(
+      "Preface line.\n\n# One\nFirst.\n```\n# not a heading\n```\n## Two ##\nSecond.\n#hashtag\n",
+    );
+    let titles: Vec<_> = chapters.iter().map(|c| c.title.as_deref()).collect();
+    assert_eq!(titles, [None, Some("One"), Some("Two")]);
+    assert!(chapters[1].text.contains("# not a heading"));
+    assert_eq!(chapters[2].text, "Two\nSecond.\n#hashtag\n");
+  }
+
+  #[test]
+  fn positionals_leave_out_text_with_an_input_file() {
+    let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+      split_positionals(args(&["voice", "out.wav"]), true).unwrap(),
+      (None, "voice".to_string(), "out.wav".to_string())
+    );
+    assert_eq!(
+      split_positionals(args(&["hi", "voice", "out.wav"]), false)
+        .unwrap()
+        .0
+        .as_deref(),
+      Some("hi")
+    );
+    assert!(split_positionals(args(&["hi", "voice", "out.wav"]), true).is_err());
+  }
 }
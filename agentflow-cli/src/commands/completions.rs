@@ -0,0 +1,72 @@
+//! `agentflow completions <shell>`: print a completion script for bash,
+//! zsh, fish, elvish or PowerShell.
+//!
+//! Every `--model` flag completes to the configured model names (the
+//! user's `models.yml`, else the built-in defaults), captured when the
+//! script is generated; regenerate it after `config models update`.
+//! PowerShell scripts complete subcommands and flags only.
+
+use agentflow_llm::LLMConfig;
+use anyhow::Result;
+use clap::Command;
+use clap::builder::PossibleValuesParser;
+use clap_complete::Shell;
+use std::io::Write;
+
+pub async fn execute(cmd: Command, shell: Shell) -> Result<()> {
+  let models = completion_models().await;
+  // clap_complete panics on write errors, so render into memory and
+  // let a closed pipe surface as an ordinary error.
+  let mut script = Vec::new();
+  write_completions(cmd, shell, &models, &mut script);
+  let mut stdout = std::io::stdout();
+  stdout.write_all(&script)?;
+  stdout.flush()?;
+  Ok(())
+}
+
+/// Configured model names, sorted; empty when no configuration loads.
+pub async fn completion_models() -> Vec<String> {
+  let config = match LLMConfig::from_default_source().await {
+    Ok((config, _)) => Some(config),
+    Err(_) => LLMConfig::builtin().ok(),
+  };
+  let mut models: Vec<String> = config
+    .map(|config| config.models.into_keys().collect())
+    .unwrap_or_default();
+  models.sort();
+  models
+}
+
+pub fn write_completions(cmd: Command, shell: Shell, models: &[String], out: &mut dyn Write) {
+  let mut cmd = with_model_values(cmd, models);
+  let name = cmd.get_name().to_string();
+  clap_complete::generate(shell, &mut cmd, name, out);
+}
+
+/// Offers `models` as the values of every `--model` flag in `cmd` and
+/// its subcommands.
+pub fn with_model_values(cmd: Command, models: &[String]) -> Command {
+  if models.is_empty() {
+    return cmd;
+  }
+  let mut cmd = cmd.mut_args(|arg| {
+    if arg.get_long() != Some("model") || !arg.get_action().takes_values() {
+      return arg;
+    }
+    // Defaults must stay parseable, or clap's debug assertions reject
+    // the command.
+    let mut values = models.to_vec();
+    for default in arg.get_default_values() {
+      let default = default.to_string_lossy().into_owned();
+      if !values.contains(&default) {
+        values.push(default);
+      }
+    }
+    arg.value_parser(PossibleValuesParser::new(values))
+  });
+  for sub in cmd.get_subcommands_mut() {
+    *sub = with_model_values(std::mem::take(sub), models);
+  }
+  cmd
+}
@@ -0,0 +1,406 @@
+//! `agentflow config doctor` — a pass/warn/fail checklist for the
+//! model configuration.
+//!
+//! Checks that the config loads, that each provider its models use has
+//! an API key, that configured base URLs answer a `HEAD` request, that
+//! every keyed provider has a model, and that `~/.agentflow` is
+//! writable. `--live` also calls each provider's `validate_config`. Any
+//! failure makes the command exit non-zero; warnings do not.
+
+use crate::redaction::redact_cli_text;
+use agentflow_llm::{AgentFlow, LLMConfig, ModelRegistry};
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+
+/// Models listed by name in a passing model check.
+const LISTED_MODELS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+  Pass,
+  Warn,
+  Fail,
+}
+
+impl CheckStatus {
+  fn label(self) -> &'static str {
+    match self {
+      Self::Pass => "pass",
+      Self::Warn => "warn",
+      Self::Fail => "FAIL",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Check {
+  pub name: String,
+  pub status: CheckStatus,
+  pub detail: String,
+}
+
+impl Check {
+  fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+    Self {
+      name: name.into(),
+      status,
+      detail: detail.into(),
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorOutput<'a> {
+  source: String,
+  checks: &'a [Check],
+  passed: usize,
+  warnings: usize,
+  failures: usize,
+}
+
+pub async fn execute(live: bool, timeout: Duration, json: bool) -> Result<()> {
+  let mut checks = Vec::new();
+  let source = LLMConfig::resolve_default_source()?;
+  let config = match LLMConfig::from_default_source().await {
+    Ok((config, _)) => match config.validate() {
+      Ok(()) => Some(config),
+      Err(e) => {
+        checks.push(Check::new("config", CheckStatus::Fail, e.to_string()));
+        None
+      }
+    },
+    Err(e) => {
+      checks.push(Check::new("config", CheckStatus::Fail, e.to_string()));
+      None
+    }
+  };
+
+  if let Some(config) = &config {
+    checks.push(Check::new(
+      "config",
+      CheckStatus::Pass,
+      format!(
+        "{} model(s), {} provider(s) from {}",
+        config.models.len(),
+        config.providers.len(),
+        source.display_path()
+      ),
+    ));
+    let by_vendor = models_by_vendor(config);
+    let (key_checks, keyed) = api_key_checks(config, &by_vendor, |vendor| {
+      config.get_api_key(vendor).is_ok()
+    });
+    checks.extend(key_checks);
+    checks.extend(endpoint_checks(&endpoints(config, &keyed), timeout).await);
+    let live_results = if live {
+      Some(live_validation().await)
+    } else {
+      None
+    };
+    checks.extend(model_checks(&by_vendor, &keyed, live_results.as_ref()));
+  }
+  checks.push(writable_check(
+    &dirs::home_dir().unwrap_or_default().join(".agentflow"),
+  ));
+
+  let count = |status| checks.iter().filter(|c| c.status == status).count();
+  let failures = count(CheckStatus::Fail);
+  if json {
+    let output = DoctorOutput {
+      source: source.display_path(),
+      checks: &checks,
+      passed: count(CheckStatus::Pass),
+      warnings: count(CheckStatus::Warn),
+      failures,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+  } else {
+    println!("AgentFlow config doctor");
+    println!("Config: {}", source.display_path());
+    println!();
+    for check in &checks {
+      println!(
+        "  [{}] {}: {}",
+        check.status.label(),
+        check.name,
+        check.detail
+      );
+    }
+    println!();
+    println!(
+      "{} passed, {} warning(s), {} failure(s)",
+      count(CheckStatus::Pass),
+      count(CheckStatus::Warn),
+      failures
+    );
+  }
+  if failures > 0 {
+    bail!("config doctor found {failures} failure(s)");
+  }
+  Ok(())
+}
+
+/// Vendor → its model names, both sorted.
+fn models_by_vendor(config: &LLMConfig) -> BTreeMap<String, Vec<String>> {
+  let mut by_vendor: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  for (name, model) in &config.models {
+    by_vendor
+      .entry(model.vendor.clone())
+      .or_default()
+      .push(name.clone());
+  }
+  for models in by_vendor.values_mut() {
+    models.sort();
+  }
+  by_vendor
+}
+
+/// One check per vendor; a missing key is a warning, since the other
+/// providers still work. Also returns the vendors that have a key.
+fn api_key_checks(
+  config: &LLMConfig,
+  by_vendor: &BTreeMap<String, Vec<String>>,
+  has_key: impl Fn(&str) -> bool,
+) -> (Vec<Check>, BTreeSet<String>) {
+  let mut checks = Vec::new();
+  let mut keyed = BTreeSet::new();
+  for (vendor, models) in by_vendor {
+    let env = config
+      .get_provider(vendor)
+      .map_or("its API key env var", |provider| {
+        provider.api_key_env.as_str()
+      });
+    let name = format!("api key {vendor}");
+    if has_key(vendor) {
+      keyed.insert(vendor.clone());
+      checks.push(Check::new(name, CheckStatus::Pass, format!("{env} is set")));
+    } else {
+      checks.push(Check::new(
+        name,
+        CheckStatus::Warn,
+        format!("{env} is not set; {} model(s) unavailable", models.len()),
+      ));
+    }
+  }
+  (checks, keyed)
+}
+
+/// Distinct base URLs the keyed vendors' models call: the model's own
+/// `base_url`, else its provider's. Models on a provider's built-in
+/// endpoint have no URL to check.
+fn endpoints(config: &LLMConfig, keyed: &BTreeSet<String>) -> BTreeSet<String> {
+  config
+    .models
+    .values()
+    .filter(|model| keyed.contains(&model.vendor))
+    .filter_map(|model| {
+      model.base_url.clone().or_else(|| {
+        config
+          .get_provider(&model.vendor)
+          .and_then(|provider| provider.base_url.clone())
+      })
+    })
+    .collect()
+}
+
+/// Any HTTP response counts as reachable; only connection errors and
+/// timeouts fail.
+async fn endpoint_checks(urls: &BTreeSet<String>, timeout: Duration) -> Vec<Check> {
+  let client = match reqwest::Client::builder().timeout(timeout).build() {
+    Ok(client) => client,
+    Err(e) => {
+      return vec![Check::new(
+        "endpoints",
+        CheckStatus::Fail,
+        format!("cannot build HTTP client: {e}"),
+      )];
+    }
+  };
+  let mut checks = Vec::new();
+  for url in urls {
+    let name = format!("endpoint {url}");
+    checks.push(match client.head(url).send().await {
+      Ok(response) => Check::new(
+        name,
+        CheckStatus::Pass,
+        format!("reachable (HTTP {})", response.status().as_u16()),
+      ),
+      Err(e) if e.is_timeout() => Check::new(
+        name,
+        CheckStatus::Fail,
+        format!("no response within {}s", timeout.as_secs_f32()),
+      ),
+      Err(e) => Check::new(
+        name,
+        CheckStatus::Fail,
+        format!("unreachable: {}", redact_cli_text(e.to_string())),
+      ),
+    });
+  }
+  checks
+}
+
+fn writable_check(dir: &Path) -> Check {
+  let name = format!("writable {}", dir.display());
+  let probe = dir.join(".config-doctor-probe");
+  let result = std::fs::create_dir_all(dir)
+    .and_then(|()| std::fs::write(&probe, b"probe"))
+    .and_then(|()| std::fs::remove_file(&probe));
+  match result {
+    Ok(()) => Check::new(name, CheckStatus::Pass, "can create files"),
+    Err(e) => Check::new(name, CheckStatus::Fail, e.to_string()),
+  }
+}
+
+/// `validate_config` of every provider the registry initialised:
+/// vendor → `Ok` or the error.
+async fn live_validation() -> BTreeMap<String, std::result::Result<(), String>> {
+  let mut results = BTreeMap::new();
+  if let Err(e) = AgentFlow::init().await {
+    results.insert(String::new(), Err(e.to_string()));
+    return results;
+  }
+  match ModelRegistry::global().validate_all_providers().await {
+    Ok(report) => {
+      for vendor in report.valid_providers {
+        results.insert(vendor, Ok(()));
+      }
+      for (vendor, error) in report.invalid_providers {
+        results.insert(vendor, Err(redact_cli_text(error)));
+      }
+    }
+    Err(e) => {
+      results.insert(String::new(), Err(e.to_string()));
+    }
+  }
+  results
+}
+
+/// One check per keyed vendor. Without `live` results a vendor passes
+/// when it has a model; with them, when its `validate_config` passed.
+fn model_checks(
+  by_vendor: &BTreeMap<String, Vec<String>>,
+  keyed: &BTreeSet<String>,
+  live: Option<&BTreeMap<String, std::result::Result<(), String>>>,
+) -> Vec<Check> {
+  let mut checks = Vec::new();
+  for (vendor, models) in by_vendor.iter().filter(|(v, _)| keyed.contains(*v)) {
+    let name = format!("models {vendor}");
+    let mut listed = models
+      .iter()
+      .take(LISTED_MODELS)
+      .cloned()
+      .collect::<Vec<_>>()
+      .join(", ");
+    if models.len() > LISTED_MODELS {
+      listed.push_str(", …");
+    }
+    let Some(live) = live else {
+      checks.push(Check::new(
+        name,
+        CheckStatus::Pass,
+        format!("{} model(s) configured: {listed}", models.len()),
+      ));
+      continue;
+    };
+    let result = live.get(vendor.as_str()).or_else(|| live.get(""));
+    checks.push(match result {
+      Some(Ok(())) => Check::new(
+        name,
+        CheckStatus::Pass,
+        format!(
+          "validate_config passed; {} model(s): {listed}",
+          models.len()
+        ),
+      ),
+      Some(Err(error)) => Check::new(name, CheckStatus::Fail, error.clone()),
+      None => Check::new(
+        name,
+        CheckStatus::Warn,
+        "provider was not initialised, so it was not validated",
+      ),
+    });
+  }
+  checks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config() -> LLMConfig {
+    LLMConfig::from_yaml(
+      r#"
+models:
+  step-a:
+    vendor: stepfun
+    base_url: http://127.0.0.1:9/v1
+  step-b:
+    vendor: stepfun
+  gpt:
+    vendor: openai
+providers:
+  stepfun:
+    api_key_env: STEPFUN_API_KEY
+    base_url: http://127.0.0.1:10/v1
+  openai:
+    api_key_env: OPENAI_API_KEY
+"#,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn missing_keys_warn_and_only_keyed_vendors_are_probed() {
+    let config = config();
+    let by_vendor = models_by_vendor(&config);
+    let (checks, keyed) = api_key_checks(&config, &by_vendor, |vendor| vendor == "stepfun");
+    assert_eq!(
+      checks,
+      [
+        Check::new(
+          "api key openai",
+          CheckStatus::Warn,
+          "OPENAI_API_KEY is not set; 1 model(s) unavailable"
+        ),
+        Check::new(
+          "api key stepfun",
+          CheckStatus::Pass,
+          "STEPFUN_API_KEY is set"
+        ),
+      ]
+    );
+    assert_eq!(
+      endpoints(&config, &keyed).into_iter().collect::<Vec<_>>(),
+      ["http://127.0.0.1:10/v1", "http://127.0.0.1:9/v1"]
+    );
+
+    let checks = model_checks(&by_vendor, &keyed, None);
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].detail, "2 model(s) configured: step-a, step-b");
+
+    let live = BTreeMap::from([("stepfun".to_string(), Err("401 invalid key".to_string()))]);
+    let checks = model_checks(&by_vendor, &keyed, Some(&live));
+    assert_eq!(checks[0].status, CheckStatus::Fail);
+    assert_eq!(checks[0].detail, "401 invalid key");
+  }
+
+  #[test]
+  fn writable_check_fails_under_a_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    assert_eq!(
+      writable_check(&dir.path().join(".agentflow")).status,
+      CheckStatus::Pass
+    );
+    let file = dir.path().join("not-a-dir");
+    std::fs::write(&file, "").unwrap();
+    assert_eq!(
+      writable_check(&file.join(".agentflow")).status,
+      CheckStatus::Fail
+    );
+  }
+}
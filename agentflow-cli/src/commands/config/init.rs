@@ -1,7 +1,23 @@
-use agentflow_llm::AgentFlow;
-use anyhow::Result;
+//! `agentflow config init` — write the `~/.agentflow/models.yml` and
+//! `.env` templates.
+//!
+//! `--interactive` then walks through provider setup: pick providers
+//! from a numbered checklist, type each one's API key (hidden on a
+//! terminal) into `.env`, optionally check the keys live against the
+//! vendors' model lists, and pick the `defaults.model` the CLI uses when
+//! a command gets no `--model`. Existing files are edited in place, so
+//! the wizard can be re-run to add a provider; `--force` still starts
+//! over from the templates. Answers are read line by line from stdin, so
+//! the wizard can also be scripted.
 
-pub async fn execute(force: bool) -> Result<()> {
+use agentflow_llm::discovery::{ModelFetcher, VendorConfig};
+use agentflow_llm::{AgentFlow, LLMConfig, ModelType};
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+pub async fn execute(force: bool, interactive: bool) -> Result<()> {
   println!("🚀 Initializing AgentFlow configuration...");
 
   // Check if config directory already exists
@@ -11,6 +27,26 @@ pub async fn execute(force: bool) -> Result<()> {
   let config_file = config_dir.join("models.yml");
   let env_file = config_dir.join(".env");
 
+  if interactive {
+    if force || !config_file.exists() {
+      AgentFlow::generate_config()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to generate configuration: {}", e))?;
+    } else {
+      println!(
+        "📁 Editing the existing files in ~/.agentflow/ (--force starts over from the templates)"
+      );
+    }
+    let stdin = std::io::stdin();
+    let secret_from_terminal = stdin.is_terminal();
+    let mut wizard = Wizard {
+      input: &mut stdin.lock(),
+      output: &mut std::io::stdout(),
+      secret_from_terminal,
+    };
+    return wizard.run(&config_file, &env_file).await;
+  }
+
   // Check for existing files if not forcing
   if !force && (config_file.exists() || env_file.exists()) {
     println!("⚠️  Configuration files already exist in ~/.agentflow/");
@@ -22,6 +58,9 @@ pub async fn execute(force: bool) -> Result<()> {
     }
     println!();
     println!("Use --force to overwrite existing configuration files.");
+    println!(
+      "Or run 'agentflow config init --interactive' to add API keys and pick a default model."
+    );
     println!("Or run 'agentflow config show' to view current configuration.");
     return Ok(());
   }
@@ -38,6 +77,7 @@ pub async fn execute(force: bool) -> Result<()> {
       println!();
       println!("🔧 Next steps:");
       println!("   1. Edit ~/.agentflow/.env and add your API keys");
+      println!("      (or run 'agentflow config init --interactive')");
       println!("   2. Uncomment the API keys you want to use:");
       println!("      # OPENAI_API_KEY=sk-your-key-here");
       println!("      OPENAI_API_KEY=sk-your-actual-key-here");
@@ -63,3 +103,369 @@ pub async fn execute(force: bool) -> Result<()> {
 
   Ok(())
 }
+
+struct Wizard<'a> {
+  input: &'a mut dyn BufRead,
+  output: &'a mut dyn Write,
+  /// Read API keys without echo; only when stdin is a terminal.
+  secret_from_terminal: bool,
+}
+
+impl Wizard<'_> {
+  async fn run(&mut self, config_file: &Path, env_file: &Path) -> Result<()> {
+    let config = LLMConfig::from_file(config_file)
+      .await
+      .with_context(|| format!("Failed to load {}", config_file.display()))?;
+    let mut env_text = std::fs::read_to_string(env_file).unwrap_or_default();
+    let mut models_text = std::fs::read_to_string(config_file)
+      .with_context(|| format!("Failed to read {}", config_file.display()))?;
+
+    // Provider → its key variable, sorted by provider.
+    let providers: BTreeMap<&str, &str> = config
+      .providers
+      .iter()
+      .map(|(name, provider)| (name.as_str(), provider.api_key_env.as_str()))
+      .collect();
+    writeln!(self.output, "\n🔑 Providers ([x] = key already in .env):")?;
+    for (index, (name, env)) in providers.iter().enumerate() {
+      let mark = if env_value(&env_text, env).is_some() {
+        "x"
+      } else {
+        " "
+      };
+      writeln!(self.output, "  {:>2}. [{mark}] {name:<12} {env}", index + 1)?;
+    }
+    let names: Vec<&str> = providers.keys().copied().collect();
+    let selected = loop {
+      let answer = self
+        .ask("Providers to set up (numbers or names, comma-separated; `all`; Enter for none): ")?;
+      match select(&answer, &names) {
+        Ok(selected) => break selected,
+        Err(e) => writeln!(self.output, "⚠️  {e}")?,
+      }
+    };
+
+    let mut entered: Vec<(&str, &str, String)> = Vec::new();
+    for provider in selected {
+      let env = providers[provider];
+      let prompt = if env_value(&env_text, env).is_some() {
+        format!("{env} for {provider} (Enter keeps the current key): ")
+      } else {
+        format!("{env} for {provider} (Enter skips): ")
+      };
+      let key = self.ask_secret(&prompt)?;
+      if key.is_empty() {
+        continue;
+      }
+      env_text = set_env_value(&env_text, env, &key);
+      entered.push((provider, env, key));
+    }
+    if !entered.is_empty() {
+      write_private(env_file, &env_text)?;
+      writeln!(
+        self.output,
+        "💾 Saved {} key(s) to {}",
+        entered.len(),
+        env_file.display()
+      )?;
+      let answer = self.ask("Test the keys against the vendors' model lists now? [y/N]: ")?;
+      if matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes") {
+        self.check_keys(&entered).await?;
+      }
+    }
+
+    // Chat models of the providers that now have a key.
+    let keyed: Vec<&str> = providers
+      .iter()
+      .filter(|(_, env)| env_value(&env_text, env).is_some())
+      .map(|(name, _)| *name)
+      .collect();
+    let mut candidates: Vec<&str> = config
+      .models
+      .iter()
+      .filter(|(_, model)| {
+        keyed.contains(&model.vendor.as_str()) && model.granular_type() == ModelType::Chat
+      })
+      .map(|(name, _)| name.as_str())
+      .collect();
+    candidates.sort();
+    if candidates.is_empty() {
+      writeln!(
+        self.output,
+        "\nNo provider has a key yet, so no default model was picked."
+      )?;
+    } else {
+      writeln!(self.output, "\n🤖 Chat models of your providers:")?;
+      for (index, name) in candidates.iter().enumerate() {
+        writeln!(self.output, "  {:>3}. {name}", index + 1)?;
+      }
+      let current = config.defaults.model.as_deref().unwrap_or("none");
+      let model = loop {
+        let answer = self.ask(&format!(
+          "Default model (number or name; Enter keeps {current}): "
+        ))?;
+        if answer.is_empty() {
+          break None;
+        }
+        let picked = answer
+          .parse::<usize>()
+          .ok()
+          .and_then(|number| candidates.get(number.wrapping_sub(1)).copied())
+          .or_else(|| {
+            config
+              .models
+              .contains_key(answer.as_str())
+              .then_some(answer.as_str())
+          });
+        match picked {
+          Some(model) => break Some(model.to_string()),
+          None => writeln!(self.output, "⚠️  '{answer}' is not a configured model")?,
+        }
+      };
+      if let Some(model) = model {
+        models_text = set_default_model(&models_text, &model);
+        std::fs::write(config_file, &models_text)
+          .with_context(|| format!("Failed to write {}", config_file.display()))?;
+        writeln!(
+          self.output,
+          "💾 Default model set to {model} in {}",
+          config_file.display()
+        )?;
+      }
+    }
+
+    writeln!(self.output, "\n✅ Setup complete. Check it with:")?;
+    writeln!(self.output, "   agentflow config doctor")?;
+    writeln!(self.output, "   agentflow llm prompt \"Hello\"")?;
+    Ok(())
+  }
+
+  /// One trimmed answer line.
+  fn ask(&mut self, prompt: &str) -> Result<String> {
+    write!(self.output, "{prompt}")?;
+    self.output.flush()?;
+    let mut line = String::new();
+    if self.input.read_line(&mut line)? == 0 {
+      bail!("stdin closed before the setup finished");
+    }
+    Ok(line.trim().to_string())
+  }
+
+  fn ask_secret(&mut self, prompt: &str) -> Result<String> {
+    if !self.secret_from_terminal {
+      return self.ask(prompt);
+    }
+    write!(self.output, "{prompt}")?;
+    self.output.flush()?;
+    let key = console::Term::stderr()
+      .read_secure_line()
+      .context("Failed to read the API key")?;
+    Ok(key.trim().to_string())
+  }
+
+  /// Lists each vendor's models with the key just entered. Failures are
+  /// reported, not fatal: the key may be fine and the network down.
+  async fn check_keys(&mut self, entered: &[(&str, &str, String)]) -> Result<()> {
+    let fetcher = ModelFetcher::new()?;
+    for (provider, _, key) in entered {
+      let vendor = VendorConfig::get_by_name(provider).filter(|vendor| vendor.supports_model_list);
+      let Some(vendor) = vendor else {
+        writeln!(
+          self.output,
+          "  – {provider}: no model list endpoint to check against"
+        )?;
+        continue;
+      };
+      match fetcher.fetch_models_with_key(&vendor, key).await {
+        Ok(models) => writeln!(
+          self.output,
+          "  ✅ {provider}: {} model(s) listed",
+          models.len()
+        )?,
+        Err(e) => writeln!(
+          self.output,
+          "  ❌ {provider}: {}",
+          crate::redaction::redact_cli_text(e.to_string())
+        )?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The providers an answer names: 1-based numbers or names, separated
+/// by commas or spaces, or `all`.
+fn select<'a>(answer: &str, names: &[&'a str]) -> Result<Vec<&'a str>> {
+  if answer.eq_ignore_ascii_case("all") {
+    return Ok(names.to_vec());
+  }
+  let mut selected = Vec::new();
+  for token in answer
+    .split(|c: char| c == ',' || c.is_whitespace())
+    .filter(|token| !token.is_empty())
+  {
+    let name = match token.parse::<usize>() {
+      Ok(number) => names.get(number.wrapping_sub(1)).copied(),
+      Err(_) => names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(token))
+        .copied(),
+    };
+    match name {
+      Some(name) if !selected.contains(&name) => selected.push(name),
+      Some(_) => {}
+      None => bail!("'{token}' is not one of the listed providers"),
+    }
+  }
+  Ok(selected)
+}
+
+/// The value of an uncommented `KEY=value` line, when not empty.
+fn env_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+  text.lines().find_map(|line| {
+    let value = line.trim_start().strip_prefix(key)?.strip_prefix('=')?;
+    let value = value.trim().trim_matches('"');
+    (!value.is_empty()).then_some(value)
+  })
+}
+
+/// `text` with `key` set to `value`: the first `KEY=` line, commented
+/// out or not, is replaced, else the line is appended.
+fn set_env_value(text: &str, key: &str, value: &str) -> String {
+  let assignment = format!("{key}={value}");
+  let mut replaced = false;
+  let mut lines: Vec<String> = text
+    .lines()
+    .map(|line| {
+      let bare = line.trim_start().trim_start_matches('#').trim_start();
+      if !replaced
+        && bare
+          .strip_prefix(key)
+          .is_some_and(|rest| rest.starts_with('='))
+      {
+        replaced = true;
+        assignment.clone()
+      } else {
+        line.to_string()
+      }
+    })
+    .collect();
+  if !replaced {
+    lines.push(assignment);
+  }
+  let mut out = lines.join("\n");
+  out.push('\n');
+  out
+}
+
+/// `text` with `defaults.model` set, editing the YAML as text so the
+/// template's comments survive: the `model:` line of the top-level
+/// `defaults:` block is replaced or added, or the block appended.
+fn set_default_model(text: &str, model: &str) -> String {
+  let entry = format!("  model: {model}");
+  let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+  match lines.iter().position(|line| line.trim_end() == "defaults:") {
+    Some(start) => {
+      let end = lines[start + 1..]
+        .iter()
+        .position(|line| !line.is_empty() && !line.starts_with(' '))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+      match (start + 1..end).find(|&index| lines[index].trim_start().starts_with("model:")) {
+        Some(index) => lines[index] = entry,
+        None => lines.insert(start + 1, entry),
+      }
+    }
+    None => {
+      lines.push("defaults:".to_string());
+      lines.push(entry);
+    }
+  }
+  let mut out = lines.join("\n");
+  out.push('\n');
+  out
+}
+
+/// Writes `.env`, readable only by the user on Unix. The text goes to a
+/// temporary file in the same directory, created with mode 0600, which
+/// then replaces `path`, so the keys are never readable by others.
+fn write_private(path: &Path, text: &str) -> Result<()> {
+  let parent = match path.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new("."),
+  };
+  std::fs::create_dir_all(parent)
+    .with_context(|| format!("Failed to create {}", parent.display()))?;
+  let mut file = tempfile::NamedTempFile::new_in(parent)
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+  file
+    .write_all(text.as_bytes())
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+  file
+    .persist(path)
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn env_values_are_set_in_place_or_appended() {
+    let text = "# OpenAI\n# OPENAI_API_KEY=sk-your-key\nSTEP_API_KEY=old\n";
+    assert_eq!(env_value(text, "OPENAI_API_KEY"), None);
+    assert_eq!(env_value(text, "STEP_API_KEY"), Some("old"));
+    let text = set_env_value(text, "OPENAI_API_KEY", "sk-new");
+    let text = set_env_value(&text, "GEMINI_API_KEY", "g-1");
+    assert_eq!(
+      text,
+      "# OpenAI\nOPENAI_API_KEY=sk-new\nSTEP_API_KEY=old\nGEMINI_API_KEY=g-1\n"
+    );
+  }
+
+  #[test]
+  fn default_model_goes_into_the_defaults_block() {
+    let text = "models: {}\ndefaults:\n  timeout_seconds: 60\n\n# end\n";
+    let once = set_default_model(text, "gpt-4o");
+    assert_eq!(
+      once,
+      "models: {}\ndefaults:\n  model: gpt-4o\n  timeout_seconds: 60\n\n# end\n"
+    );
+    assert_eq!(
+      set_default_model(&once, "claude-3-5-sonnet"),
+      once.replace("gpt-4o", "claude-3-5-sonnet")
+    );
+    assert_eq!(
+      set_default_model("models: {}\n", "gpt-4o"),
+      "models: {}\ndefaults:\n  model: gpt-4o\n"
+    );
+  }
+
+  #[test]
+  fn providers_are_picked_by_number_or_name() {
+    let names = ["anthropic", "openai", "step"];
+    assert_eq!(select("3, openai", &names).unwrap(), ["step", "openai"]);
+    assert_eq!(select("all", &names).unwrap(), names);
+    assert!(select("", &names).unwrap().is_empty());
+    assert!(select("9", &names).is_err());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn env_file_is_replaced_with_a_private_one() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().unwrap();
+    let env_file = dir.path().join(".env");
+    std::fs::write(&env_file, "OLD=1\n").unwrap();
+    std::fs::set_permissions(&env_file, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    write_private(&env_file, "OPENAI_API_KEY=sk-new\n").unwrap();
+
+    assert_eq!(
+      std::fs::read_to_string(&env_file).unwrap(),
+      "OPENAI_API_KEY=sk-new\n"
+    );
+    let mode = std::fs::metadata(&env_file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+  }
+}
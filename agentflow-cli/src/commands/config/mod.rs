@@ -1,3 +1,5 @@
+pub mod doctor;
 pub mod init;
+pub mod models;
 pub mod show;
 pub mod validate;
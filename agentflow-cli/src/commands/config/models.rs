@@ -0,0 +1,213 @@
+//! `agentflow config models update|validate`: keep the model registry in
+//! step with what each vendor's `/models` endpoint actually serves.
+//!
+//! `update` merges discovered models into the configuration the CLI
+//! loads, as [`LLMConfig::resolve_default_source`] picks it: the
+//! `AGENTFLOW_MODELS_CONFIG` file, else `~/.agentflow/models.yml`, else
+//! `~/.agentflow/models.yaml`, else a new `~/.agentflow/models.yml` seeded
+//! from the built-in defaults. `validate` checks every configured model against its vendor's
+//! list and fails when any is missing.
+
+use agentflow_llm::discovery::config_updater::UpdateResult;
+use agentflow_llm::discovery::model_validator::ValidationResult;
+use agentflow_llm::discovery::{ModelFetcher, ModelSource, VendorConfig};
+use agentflow_llm::{ConfigUpdater, LLMConfig, ModelValidator};
+use anyhow::{Context, Result, bail};
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Where `config models update` writes: the file the CLI loads models
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelsTarget {
+  pub path: PathBuf,
+}
+
+impl ModelsTarget {
+  /// The source [`LLMConfig::resolve_default_source_from`] picks for the
+  /// same inputs, or `models.yml` under `config_dir` when that is the
+  /// built-in defaults.
+  pub fn resolve(config_dir: Option<&Path>, env_override: Option<OsString>) -> Result<Self> {
+    let source = LLMConfig::resolve_default_source_from(config_dir, env_override)?;
+    let path = match source.path {
+      Some(path) => path,
+      None => config_dir
+        .context("Could not determine the ~/.agentflow directory")?
+        .join("models.yml"),
+    };
+    Ok(Self { path })
+  }
+
+  pub fn describe(&self) -> String {
+    self.path.display().to_string()
+  }
+
+  async fn load(&self) -> Result<LLMConfig> {
+    if !self.path.exists() {
+      return LLMConfig::builtin().context("Failed to parse the built-in model defaults");
+    }
+    LLMConfig::from_file(&self.path)
+      .await
+      .with_context(|| format!("Failed to load {}", self.path.display()))
+  }
+
+  async fn save(&self, updater: &ConfigUpdater, config: &LLMConfig) -> Result<()> {
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    updater
+      .write_config(config, &self.path.to_string_lossy())
+      .await
+      .with_context(|| format!("Failed to write {}", self.path.display()))
+  }
+}
+
+/// The vendors to query: `vendor` alone, or every vendor that publishes
+/// a model list.
+pub fn discovery_vendors(vendor: Option<&str>) -> Result<Vec<String>> {
+  let Some(vendor) = vendor else {
+    return Ok(
+      VendorConfig::vendors_with_model_list()
+        .into_iter()
+        .map(|vendor| vendor.name)
+        .collect(),
+    );
+  };
+  let supported = || {
+    VendorConfig::vendors_with_model_list()
+      .into_iter()
+      .map(|vendor| vendor.name)
+      .collect::<Vec<_>>()
+      .join(", ")
+  };
+  match VendorConfig::get_by_name(vendor) {
+    Some(config) if config.supports_model_list => Ok(vec![config.name]),
+    Some(config) => bail!(
+      "Vendor '{}' does not publish a model list; supported: {}",
+      config.name,
+      supported()
+    ),
+    None => bail!("Unknown vendor '{}'; supported: {}", vendor, supported()),
+  }
+}
+
+pub async fn update(vendor: Option<String>, dry_run: bool) -> Result<()> {
+  let target = ModelsTarget::resolve(
+    dirs::home_dir()
+      .map(|home| home.join(".agentflow"))
+      .as_deref(),
+    std::env::var_os("AGENTFLOW_MODELS_CONFIG"),
+  )?;
+  let vendors = discovery_vendors(vendor.as_deref())?;
+  let source = Arc::new(ModelFetcher::new().context("Failed to build the discovery client")?);
+  let result = run_update(source, &target, &vendors, dry_run).await?;
+  print!("{}", update_report(&target, &result, dry_run));
+  Ok(())
+}
+
+/// Merges `vendors`' discovered models into `target`, writing it back
+/// unless `dry_run`.
+pub async fn run_update(
+  source: Arc<dyn ModelSource>,
+  target: &ModelsTarget,
+  vendors: &[String],
+  dry_run: bool,
+) -> Result<UpdateResult> {
+  let mut config = target.load().await?;
+  let updater = ConfigUpdater::with_source(source);
+  let result = updater.merge_discovered(&mut config, vendors).await?;
+  if !dry_run && result.added_models + result.updated_models > 0 {
+    target.save(&updater, &config).await?;
+  }
+  Ok(result)
+}
+
+/// `+` added, `~` updated, `!` vendors that could not be fetched.
+pub fn update_report(target: &ModelsTarget, result: &UpdateResult, dry_run: bool) -> String {
+  let mut report = String::new();
+  let _ = writeln!(
+    report,
+    "{} {}\n",
+    if dry_run {
+      "Dry run — would update"
+    } else {
+      "Updated"
+    },
+    target.describe()
+  );
+  let mut added = result.added_model_names.clone();
+  added.sort();
+  for name in &added {
+    let _ = writeln!(report, "+ {}", name);
+  }
+  let mut updated = result.updated_model_names.clone();
+  updated.sort();
+  for name in &updated {
+    let _ = writeln!(report, "~ {}", name);
+  }
+  for failure in &result.failed_vendors {
+    let _ = writeln!(report, "! {}", failure);
+  }
+  if added.is_empty() && updated.is_empty() {
+    report.push_str("(no changes)\n");
+  }
+  let _ = writeln!(
+    report,
+    "\n{} added, {} updated, {} unchanged, {} vendor(s) failed",
+    result.added_models,
+    result.updated_models,
+    result.unchanged_models,
+    result.failed_vendors.len()
+  );
+  report
+}
+
+pub async fn validate(vendor: Option<String>) -> Result<()> {
+  let (config, source) = LLMConfig::from_default_source().await?;
+  for warning in &source.warnings {
+    eprintln!("Warning: {warning}");
+  }
+  println!("Configuration: {}\n", source.display_path());
+  let validator = ModelValidator::new().context("Failed to build the discovery client")?;
+  let result = run_validate(&validator, &config, vendor.as_deref()).await?;
+  print!("{}", validator.create_report(&result));
+  if !result.invalid_models.is_empty() {
+    bail!(
+      "{} configured model(s) failed validation",
+      result.invalid_models.len()
+    );
+  }
+  Ok(())
+}
+
+/// Validates the models in `config`, only those of `vendor` when given.
+pub async fn run_validate(
+  validator: &ModelValidator,
+  config: &LLMConfig,
+  vendor: Option<&str>,
+) -> Result<ValidationResult> {
+  let mut config = config.clone();
+  if let Some(vendor) = vendor {
+    let mut configured: Vec<String> = config
+      .models
+      .values()
+      .map(|model| model.vendor.clone())
+      .collect();
+    configured.sort();
+    configured.dedup();
+    config
+      .models
+      .retain(|_, model| model.vendor.eq_ignore_ascii_case(vendor));
+    if config.models.is_empty() {
+      bail!(
+        "No configured models use vendor '{}'; configured vendors: {}",
+        vendor,
+        configured.join(", ")
+      );
+    }
+  }
+  Ok(validator.validate_config(&config).await)
+}
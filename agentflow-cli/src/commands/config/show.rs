@@ -1,8 +1,16 @@
 use crate::redaction::redact_cli_value;
-use agentflow_llm::{LLMConfig, LLMConfigSourceKind};
+use agentflow_llm::{
+  AgentFlow, ConfigExplanation, EntryExplanation, FieldExplanation, LLMConfig, LLMConfigSourceKind,
+  ModelRegistry,
+};
 use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+use std::fmt::Write as _;
 
-pub async fn execute(section: Option<String>) -> Result<()> {
+pub async fn execute(section: Option<String>, resolved: bool) -> Result<()> {
+  if resolved {
+    return execute_resolved(section).await;
+  }
   let source = LLMConfig::resolve_default_source()?;
   for warning in &source.warnings {
     eprintln!("Warning: {warning}");
@@ -54,3 +62,138 @@ pub async fn execute(section: Option<String>) -> Result<()> {
   print!("{redacted_yaml}");
   Ok(())
 }
+
+/// `config show --resolved`: the configuration the registry actually
+/// loaded, every field annotated with its origin.
+async fn execute_resolved(section: Option<String>) -> Result<()> {
+  if let Some(other) = section
+    .as_deref()
+    .filter(|section| !matches!(*section, "models" | "providers" | "defaults"))
+  {
+    bail!("Unknown config section '{other}' (expected models, providers, or defaults)");
+  }
+  AgentFlow::init().await?;
+  let explanation = ModelRegistry::global().explain().await?;
+  print!(
+    "{}",
+    render_resolved(&explanation, section.as_deref(), |name| {
+      std::env::var_os(name).is_some()
+    })
+  );
+  Ok(())
+}
+
+fn render_resolved(
+  explanation: &ConfigExplanation,
+  section: Option<&str>,
+  env_is_set: impl Fn(&str) -> bool,
+) -> String {
+  let mut out = String::new();
+  let source = match &explanation.source_path {
+    Some(path) => format!("{:?} ({})", explanation.source_kind, path.display()),
+    None => "built-in default_models.yml".to_string(),
+  };
+  let _ = writeln!(out, "# source: {source}");
+  let _ = writeln!(
+    out,
+    "# origins: 'file' = set by the file only, 'file, same as built-in' = repeats the built-in value, 'built-in' = no file in effect"
+  );
+  let unavailable = &explanation.unavailable_builtin_models;
+  if !unavailable.is_empty() {
+    let _ = writeln!(
+      out,
+      "# {} built-in model(s) are not available while this file is in effect: {}",
+      unavailable.len(),
+      unavailable.join(", ")
+    );
+  }
+  let show = |name: &str| section.is_none_or(|section| section == name);
+  if show("models") {
+    render_entries(&mut out, "models", &explanation.models, &env_is_set);
+  }
+  if show("providers") {
+    render_entries(&mut out, "providers", &explanation.providers, &env_is_set);
+  }
+  if show("defaults") {
+    let _ = writeln!(out, "defaults:");
+    render_fields(&mut out, &explanation.defaults, &env_is_set);
+  }
+  out
+}
+
+fn render_entries(
+  out: &mut String,
+  name: &str,
+  entries: &[EntryExplanation],
+  env_is_set: &impl Fn(&str) -> bool,
+) {
+  let _ = writeln!(out, "{name}:");
+  for entry in entries {
+    let _ = writeln!(out, "  {}: # {}", entry.name, entry.origin.label());
+    render_fields(out, &entry.fields, env_is_set);
+  }
+}
+
+fn render_fields(
+  out: &mut String,
+  fields: &[FieldExplanation],
+  env_is_set: &impl Fn(&str) -> bool,
+) {
+  for field in fields {
+    // Redact as a one-key object so key-name rules (`api_key`, …)
+    // apply as they do for `config show`.
+    let mut wrapped = json!({ &field.field: field.value });
+    redact_cli_value(&mut wrapped);
+    let value = &wrapped[&field.field];
+    let mut note = field.origin.label().to_string();
+    if field.field == "api_key_env"
+      && let Value::String(env) = value
+    {
+      note.push_str(if env_is_set(env) {
+        "; env var set"
+      } else {
+        "; env var not set"
+      });
+    }
+    let _ = writeln!(out, "    {}: {value} # {note}", field.field);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_llm::LLMConfigSource;
+  use std::path::PathBuf;
+
+  #[test]
+  fn resolved_output_annotates_fields_and_env_state() {
+    let config = LLMConfig::from_yaml(
+      "models:\n  gpt-4o:\n    vendor: openai\n  local:\n    vendor: openai\n    base_url: http://localhost:1234/v1\nproviders:\n  openai:\n    api_key_env: OPENAI_API_KEY\n",
+    )
+    .unwrap();
+    let explanation = config
+      .explain(&LLMConfigSource {
+        kind: LLMConfigSourceKind::UserModelsYml,
+        path: Some(PathBuf::from("/home/u/.agentflow/models.yml")),
+        warnings: Vec::new(),
+      })
+      .unwrap();
+    let text = render_resolved(&explanation, None, |name| name == "OPENAI_API_KEY");
+    assert!(text.contains("# source: UserModelsYml (/home/u/.agentflow/models.yml)"));
+    assert!(text.contains("built-in model(s) are not available"));
+    assert!(
+      text.contains("  gpt-4o: # file, same as built-in\n"),
+      "{text}"
+    );
+    assert!(
+      text.contains("    vendor: \"openai\" # file, same as built-in\n"),
+      "{text}"
+    );
+    assert!(text.contains("  local: # file\n    base_url: \"http://localhost:1234/v1\" # file\n"));
+    assert!(text.contains("api_key_env: \"OPENAI_API_KEY\" # file, same as built-in; env var set"));
+
+    let providers = render_resolved(&explanation, Some("providers"), |_| false);
+    assert!(!providers.contains("models:"));
+    assert!(providers.contains("env var not set"));
+  }
+}
@@ -188,7 +188,7 @@ fn glob_match_inner(pattern: &[char], candidate: &[char]) -> bool {
 /// Malformed YAML *is* an error and short-circuits the run with a
 /// structured anyhow message so operators don't silently lose cost
 /// tracking they expected to have.
-fn load_pricing_table() -> Result<PricingTable> {
+pub(crate) fn load_pricing_table() -> Result<PricingTable> {
   if let Ok(path) = std::env::var("AGENTFLOW_PRICING_TABLE") {
     let path = PathBuf::from(path);
     return PricingTable::load_from_yaml(&path)
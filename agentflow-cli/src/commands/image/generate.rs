@@ -1,44 +1,198 @@
+//! `agentflow image generate` — text-to-image through any provider the
+//! modality dispatcher knows (StepFun, DashScope).
+//!
+//! One prompt and `--output <file>` writes a single image. A batch —
+//! `--prompts-file`, `--count N` or `--output-dir` — writes one file per
+//! image into the directory, named by `--name-template`, plus a
+//! `manifest.json` describing every image. Images are generated up to
+//! `--parallel` at a time, and one failing image is recorded in the
+//! manifest without stopping the rest.
+
 use agentflow_llm::{
-  AgentFlow, providers::modality::Text2ImageRequest as ModalityText2ImageRequest,
+  AgentFlow,
+  providers::modality::{
+    GeneratedImage, Text2ImageProvider, Text2ImageRequest as ModalityText2ImageRequest,
+  },
 };
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow, bail};
 use base64::{Engine as _, engine::general_purpose};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::fs;
 
-#[allow(clippy::too_many_arguments)]
-pub async fn execute(
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const DEFAULT_NAME_TEMPLATE: &str = "{index}.png";
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(120);
+/// Placeholders `--name-template` understands.
+const NAME_PLACEHOLDERS: &[&str] = &["index", "prompt_index", "n", "seed", "slug"];
+const SLUG_CHARS: usize = 40;
+
+pub struct GenerateOptions {
+  pub prompt: Option<String>,
+  pub prompts_file: Option<String>,
+  pub model: Option<String>,
+  pub provider: Option<String>,
+  pub size: String,
+  pub output: Option<String>,
+  pub output_dir: Option<String>,
+  pub format: String,
+  pub steps: u32,
+  pub cfg_scale: f32,
+  pub seed: Option<u64>,
+  pub count: u32,
+  pub parallel: usize,
+  pub name_template: String,
+  pub strength: Option<f32>,
+  pub input_image: Option<String>,
+}
+
+/// The default model of each `--provider`.
+fn default_model(provider: Option<&str>) -> &'static str {
+  match provider {
+    Some("dashscope") => "wanx2.1-t2i-turbo",
+    _ => "step-1x-medium",
+  }
+}
+
+/// One line of `--prompts-file` in its JSONL form.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PromptLine {
+  prompt: String,
+  #[serde(default)]
+  size: Option<String>,
+  #[serde(default)]
+  seed: Option<u64>,
+}
+
+/// A prompt with its per-item overrides.
+#[derive(Debug, Clone, PartialEq)]
+struct PromptItem {
+  prompt: String,
+  size: Option<String>,
+  seed: Option<u64>,
+}
+
+/// One image to generate.
+#[derive(Debug, Clone, PartialEq)]
+struct Job {
+  /// 1-based position among all images of the batch.
+  index: usize,
+  /// 1-based line of the prompt in the batch.
+  prompt_index: usize,
+  /// 1-based image number for this prompt (`--count`).
+  n: u32,
   prompt: String,
-  model: Option<String>,
   size: String,
-  output: String,
-  format: String,
-  steps: u32,
-  cfg_scale: f32,
   seed: Option<u64>,
-  strength: Option<f32>,
-  input_image: Option<String>,
-) -> Result<()> {
-  let model = model.unwrap_or_else(|| "step-1x-medium".to_string());
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageStatus {
+  Succeeded,
+  Failed,
+}
+
+/// One image's entry in `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageRecord {
+  pub index: usize,
+  pub prompt_index: usize,
+  pub n: u32,
+  pub prompt: String,
+  pub size: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub seed: Option<u64>,
+  pub status: ImageStatus,
+  /// Path relative to the output directory.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub file: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub bytes: Option<usize>,
+  pub duration_ms: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchManifest {
+  pub provider: String,
+  pub model: String,
+  pub format: String,
+  pub name_template: String,
+  pub total: usize,
+  pub succeeded: usize,
+  pub failed: usize,
+  /// Every image in batch order.
+  pub images: Vec<ImageRecord>,
+}
+
+pub async fn execute(options: GenerateOptions) -> Result<()> {
+  let batch = options.prompts_file.is_some() || options.count > 1 || options.output_dir.is_some();
+  if batch && options.output.is_some() {
+    bail!("--output names a single image; use --output-dir for --prompts-file or --count");
+  }
+  if batch && options.output_dir.is_none() {
+    bail!("Pass --output-dir <dir> for --prompts-file or --count");
+  }
+  if !batch && options.output.is_none() {
+    bail!("Pass --output <file> for one image, or --output-dir <dir> for a batch");
+  }
+  if !matches!(options.format.as_str(), "b64_json" | "url") {
+    bail!(
+      "Unsupported format: {} (expected b64_json or url)",
+      options.format
+    );
+  }
+  check_name_template(&options.name_template)?;
+
+  let items = match (&options.prompts_file, &options.prompt) {
+    (Some(path), _) => {
+      let text = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read prompts file {}", path))?;
+      parse_prompts(&text).with_context(|| format!("Invalid prompts file {}", path))?
+    }
+    (None, Some(prompt)) => vec![PromptItem {
+      prompt: prompt.clone(),
+      size: None,
+      seed: None,
+    }],
+    (None, None) => bail!("Pass a prompt, or --prompts-file with one prompt per line"),
+  };
+  let jobs = plan_jobs(&items, options.count, &options.size, options.seed);
+
+  let model = options
+    .model
+    .clone()
+    .unwrap_or_else(|| default_model(options.provider.as_deref()).to_string());
 
   // Initialize AgentFlow so the registry knows about all configured
   // models. The dispatcher resolves vendor + API key by model name.
   AgentFlow::init().await?;
+  let provider = AgentFlow::text2image_for(&model).await?;
+  if let Some(wanted) = options.provider.as_deref()
+    && provider.name() != wanted
+  {
+    bail!(
+      "Model '{}' is served by {}, not {}; pick a {} model with --model",
+      model,
+      provider.name(),
+      wanted,
+      wanted
+    );
+  }
 
   println!("🎨 AgentFlow Image Generation");
+  println!("Provider: {}", provider.name());
   println!("Model: {}", model);
-  println!("Prompt: {}", prompt);
-  println!("Size: {}", size);
-  println!("Format: {}", format);
-  println!("Output: {}", output);
-  if let Some(seed) = seed {
-    println!("Seed: {}", seed);
-  }
-  if let Some(input) = &input_image {
-    println!("Input image: {}", input);
-  }
-  println!();
-
-  if strength.is_some() || input_image.is_some() {
+  if options.strength.is_some() || options.input_image.is_some() {
     // Image-to-image takes a different dispatcher path
     // (`AgentFlow::image2image`). The text-to-image CLI command stays
     // text-only for now — wire `agentflow image generate-i2i` for the
@@ -49,85 +203,431 @@ pub async fn execute(
     );
   }
 
-  let provider = AgentFlow::text2image_for(&model).await?;
-  println!(
-    "🖼️  Generating via provider '{}' (model '{}')...",
-    provider.name(),
-    model
-  );
-
-  let request = ModalityText2ImageRequest {
-    model: model.clone(),
-    prompt,
-    size: Some(size.clone()),
-    n: Some(1),
-    response_format: Some(format.clone()),
-    seed: seed.map(|s| s as i32),
-    steps: Some(steps),
-    cfg_scale: Some(cfg_scale),
-  };
-
-  let start_time = std::time::Instant::now();
-  let response = match tokio::time::timeout(
-    std::time::Duration::from_secs(120),
-    provider.generate(request),
-  )
-  .await
-  {
-    Ok(result) => result.map_err(|e| {
-      eprintln!("Error: Internal LLM error: {}", e);
-      anyhow::anyhow!("Image generation failed: {}", e)
-    })?,
-    Err(_) => {
-      return Err(anyhow::anyhow!(
-        "Image generation timed out after 2 minutes. This may be due to:\n  - Network connectivity issues\n  - API server overload\n  - Complex prompt requiring more processing time\n\nTry again with a simpler prompt or check your internet connection."
-      ));
+  match (&options.output, &options.output_dir) {
+    (Some(output), _) => {
+      generate_single(provider.as_ref(), &model, &jobs[0], &options, output).await
     }
-  };
+    (None, dir) => {
+      let dir = PathBuf::from(dir.as_deref().unwrap_or_default());
+      generate_batch(provider.as_ref(), &model, jobs, &options, &dir).await
+    }
+  }
+}
 
-  let duration = start_time.elapsed();
-  println!("✅ Image generated in {:?}", duration);
+async fn generate_single(
+  provider: &dyn Text2ImageProvider,
+  model: &str,
+  job: &Job,
+  options: &GenerateOptions,
+  output: &str,
+) -> Result<()> {
+  println!("Prompt: {}", job.prompt);
+  println!("Size: {}", job.size);
+  println!("Format: {}", options.format);
+  println!("Output: {}", output);
+  if let Some(seed) = job.seed {
+    println!("Seed: {}", seed);
+  }
   println!();
+  println!("🖼️  Generating...");
 
-  println!("💾 Saving image to: {}", output);
+  let start_time = Instant::now();
+  let image = generate_one(provider, model, job, options).await?;
+  println!("✅ Image generated in {:?}", start_time.elapsed());
+  println!();
 
-  let first_image = response
-    .images
-    .first()
-    .ok_or_else(|| anyhow::anyhow!("No image data received in response"))?;
-
-  match format.as_str() {
-    "b64_json" => {
-      let b64_data = first_image
-        .b64_json
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No base64 image data received"))?;
+  println!("💾 Saving image to: {}", output);
+  match (&image.b64_json, &image.url) {
+    (Some(b64_data), _) if options.format == "b64_json" => {
       let image_bytes = general_purpose::STANDARD.decode(b64_data)?;
-      fs::write(&output, &image_bytes).await?;
+      fs::write(output, &image_bytes).await?;
       println!(
         "✅ Image saved as base64 data ({} bytes)",
         image_bytes.len()
       );
     }
-    "url" => {
-      let url = first_image
-        .url
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No image URL received"))?;
+    (_, Some(url)) => {
       let url_output = format!("{}.url", output);
       fs::write(&url_output, url).await?;
       println!("✅ Image URL saved to: {}", url_output);
       println!("🔗 Image URL: {}", url);
     }
-    other => {
-      return Err(anyhow::anyhow!("Unsupported format: {}", other));
-    }
+    _ => bail!("No {} image data received", options.format),
   }
 
-  if let Some(seed) = first_image.seed {
+  if let Some(seed) = image.seed {
     println!("🎯 Generation seed: {}", seed);
   }
 
   println!("🎉 Image generation completed successfully!");
   Ok(())
 }
+
+async fn generate_batch(
+  provider: &dyn Text2ImageProvider,
+  model: &str,
+  jobs: Vec<Job>,
+  options: &GenerateOptions,
+  dir: &Path,
+) -> Result<()> {
+  fs::create_dir_all(dir)
+    .await
+    .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+  let total = jobs.len();
+  println!(
+    "Images: {} ({} at a time) → {}",
+    total,
+    options.parallel,
+    dir.display()
+  );
+  println!();
+
+  let mut results: Vec<(Job, Result<GeneratedImage>, Duration)> = futures::stream::iter(jobs)
+    .map(|job| async move {
+      let start = Instant::now();
+      let result = generate_one(provider, model, &job, options).await;
+      (job, result, start.elapsed())
+    })
+    .buffer_unordered(options.parallel.max(1))
+    .collect()
+    .await;
+  results.sort_by_key(|(job, _, _)| job.index);
+
+  // Files are written in batch order so a name collision fails the
+  // later image, whatever order the provider answered in.
+  let mut names: HashMap<String, usize> = HashMap::new();
+  let mut records = Vec::with_capacity(total);
+  for (job, result, duration) in results {
+    let record = match save_image(dir, &job, result, options, total, &mut names).await {
+      Ok(saved) => {
+        println!(
+          "✅ [{}/{}] {} ({:.1?})",
+          job.index, total, saved.file, duration
+        );
+        ImageRecord {
+          seed: saved.seed,
+          status: ImageStatus::Succeeded,
+          file: Some(saved.file),
+          url: saved.url,
+          bytes: saved.bytes,
+          ..record_for(&job, duration)
+        }
+      }
+      Err(err) => {
+        let error = format!("{:#}", err);
+        println!(
+          "❌ [{}/{}] prompt {} image {} failed: {}",
+          job.index, total, job.prompt_index, job.n, error
+        );
+        ImageRecord {
+          error: Some(error),
+          ..record_for(&job, duration)
+        }
+      }
+    };
+    records.push(record);
+  }
+
+  let succeeded = records
+    .iter()
+    .filter(|record| record.status == ImageStatus::Succeeded)
+    .count();
+  let manifest = BatchManifest {
+    provider: provider.name().to_string(),
+    model: model.to_string(),
+    format: options.format.clone(),
+    name_template: options.name_template.clone(),
+    total,
+    succeeded,
+    failed: total - succeeded,
+    images: records,
+  };
+  let manifest_path = dir.join(MANIFEST_FILE);
+  fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+    .await
+    .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+
+  println!();
+  println!("📒 Manifest written to {}", manifest_path.display());
+  if manifest.failed > 0 {
+    bail!(
+      "{} of {} image(s) failed; see {}",
+      manifest.failed,
+      total,
+      manifest_path.display()
+    );
+  }
+  println!("🎉 Generated {} image(s)", succeeded);
+  Ok(())
+}
+
+async fn generate_one(
+  provider: &dyn Text2ImageProvider,
+  model: &str,
+  job: &Job,
+  options: &GenerateOptions,
+) -> Result<GeneratedImage> {
+  let request = ModalityText2ImageRequest {
+    model: model.to_string(),
+    prompt: job.prompt.clone(),
+    size: Some(job.size.clone()),
+    n: Some(1),
+    response_format: Some(options.format.clone()),
+    seed: job.seed.map(|s| s as i32),
+    steps: Some(options.steps),
+    cfg_scale: Some(options.cfg_scale),
+  };
+  let response = match tokio::time::timeout(IMAGE_TIMEOUT, provider.generate(request)).await {
+    Ok(result) => result.map_err(|e| anyhow!("Image generation failed: {}", e))?,
+    Err(_) => bail!(
+      "Image generation timed out after {} seconds",
+      IMAGE_TIMEOUT.as_secs()
+    ),
+  };
+  let image = response
+    .images
+    .into_iter()
+    .next()
+    .ok_or_else(|| anyhow!("No image data received in response"))?;
+  if image.url.is_none() && image.b64_json.is_none() {
+    bail!(
+      "No image returned (finish reason: {})",
+      image.finish_reason.as_deref().unwrap_or("unknown")
+    );
+  }
+  Ok(image)
+}
+
+struct SavedImage {
+  file: String,
+  seed: Option<u64>,
+  url: Option<String>,
+  bytes: Option<usize>,
+}
+
+async fn save_image(
+  dir: &Path,
+  job: &Job,
+  result: Result<GeneratedImage>,
+  options: &GenerateOptions,
+  total: usize,
+  names: &mut HashMap<String, usize>,
+) -> Result<SavedImage> {
+  let image = result?;
+  let seed = image
+    .seed
+    .and_then(|seed| u64::try_from(seed).ok())
+    .or(job.seed);
+  let mut file = render_name(&options.name_template, job, seed, total);
+  let (payload, url) = match (&image.b64_json, &image.url) {
+    (Some(b64_data), _) if options.format == "b64_json" => {
+      (general_purpose::STANDARD.decode(b64_data)?, None)
+    }
+    (_, Some(url)) => {
+      file.push_str(".url");
+      (url.clone().into_bytes(), Some(url.clone()))
+    }
+    _ => bail!("No {} image data received", options.format),
+  };
+  if let Some(other) = names.get(&file) {
+    bail!("file name '{}' is already used by image {}", file, other);
+  }
+  names.insert(file.clone(), job.index);
+  let path = dir.join(&file);
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).await?;
+  }
+  fs::write(&path, &payload)
+    .await
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+  Ok(SavedImage {
+    file,
+    seed,
+    bytes: url.is_none().then_some(payload.len()),
+    url,
+  })
+}
+
+fn record_for(job: &Job, duration: Duration) -> ImageRecord {
+  ImageRecord {
+    index: job.index,
+    prompt_index: job.prompt_index,
+    n: job.n,
+    prompt: job.prompt.clone(),
+    size: job.size.clone(),
+    seed: job.seed,
+    status: ImageStatus::Failed,
+    file: None,
+    url: None,
+    bytes: None,
+    duration_ms: duration.as_millis() as u64,
+    error: None,
+  }
+}
+
+/// Plain lines are prompts; lines starting with `{` are JSON objects
+/// with `prompt` and optional `size` / `seed`. Blank lines are skipped.
+fn parse_prompts(text: &str) -> Result<Vec<PromptItem>> {
+  let mut items = Vec::new();
+  for (number, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() {
+      continue;
+    }
+    if line.starts_with('{') {
+      let parsed: PromptLine =
+        serde_json::from_str(line).with_context(|| format!("line {}", number + 1))?;
+      items.push(PromptItem {
+        prompt: parsed.prompt,
+        size: parsed.size,
+        seed: parsed.seed,
+      });
+    } else {
+      items.push(PromptItem {
+        prompt: line.to_string(),
+        size: None,
+        seed: None,
+      });
+    }
+  }
+  if items.is_empty() {
+    bail!("no prompts found");
+  }
+  Ok(items)
+}
+
+/// `count` images per prompt. A prompt's seed (its own, else `--seed`)
+/// is used for its first image and incremented for the rest, so every
+/// image of a seeded batch is reproducible and distinct.
+fn plan_jobs(items: &[PromptItem], count: u32, size: &str, seed: Option<u64>) -> Vec<Job> {
+  let mut jobs = Vec::new();
+  for (prompt_index, item) in items.iter().enumerate() {
+    for n in 1..=count.max(1) {
+      jobs.push(Job {
+        index: jobs.len() + 1,
+        prompt_index: prompt_index + 1,
+        n,
+        prompt: item.prompt.clone(),
+        size: item.size.clone().unwrap_or_else(|| size.to_string()),
+        seed: item.seed.or(seed).map(|seed| seed + u64::from(n - 1)),
+      });
+    }
+  }
+  jobs
+}
+
+fn check_name_template(template: &str) -> Result<()> {
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    let Some(end) = rest[start..].find('}') else {
+      bail!("--name-template has an unclosed '{{': {}", template);
+    };
+    let name = &rest[start + 1..start + end];
+    if !NAME_PLACEHOLDERS.contains(&name) {
+      bail!(
+        "--name-template placeholder '{{{}}}' is not one of {}",
+        name,
+        NAME_PLACEHOLDERS
+          .iter()
+          .map(|name| format!("{{{name}}}"))
+          .collect::<Vec<_>>()
+          .join(", ")
+      );
+    }
+    rest = &rest[start + end + 1..];
+  }
+  Ok(())
+}
+
+/// Fills a `--name-template`. `{index}` is zero-padded to the batch
+/// size so names sort in batch order; an unknown seed renders as
+/// `random`.
+fn render_name(template: &str, job: &Job, seed: Option<u64>, total: usize) -> String {
+  let width = total.to_string().len();
+  template
+    .replace("{index}", &format!("{:0width$}", job.index))
+    .replace("{prompt_index}", &job.prompt_index.to_string())
+    .replace("{n}", &job.n.to_string())
+    .replace(
+      "{seed}",
+      &seed.map_or_else(|| "random".to_string(), |seed| seed.to_string()),
+    )
+    .replace("{slug}", &slug(&job.prompt, "image"))
+}
+
+/// Lowercase alphanumeric runs of `text` joined by `-`, at most
+/// `SLUG_CHARS` long; `fallback` when nothing is left. Also names
+/// `audio tts --chapters` files.
+pub(crate) fn slug(text: &str, fallback: &str) -> String {
+  let mut slug = String::new();
+  for ch in text.chars() {
+    if ch.is_alphanumeric() {
+      slug.extend(ch.to_lowercase());
+    } else if !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+    if slug.chars().count() >= SLUG_CHARS {
+      break;
+    }
+  }
+  let slug = slug.trim_end_matches('-');
+  if slug.is_empty() {
+    fallback.to_string()
+  } else {
+    slug.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn prompts_file_mixes_plain_and_jsonl_lines() {
+    let items = parse_prompts(
+      "a red fox\n\n{\"prompt\": \"a blue whale\", \"size\": \"512x512\", \"seed\": 9}\n",
+    )
+    .unwrap();
+    assert_eq!(
+      items,
+      [
+        PromptItem {
+          prompt: "a red fox".into(),
+          size: None,
+          seed: None
+        },
+        PromptItem {
+          prompt: "a blue whale".into(),
+          size: Some("512x512".into()),
+          seed: Some(9)
+        },
+      ]
+    );
+    let err = parse_prompts("{\"prompt\": \"x\", \"sead\": 1}").unwrap_err();
+    assert!(format!("{err:#}").contains("line 1"), "{err:#}");
+    assert!(parse_prompts("\n  \n").is_err());
+  }
+
+  #[test]
+  fn jobs_count_seeds_and_names() {
+    let items = parse_prompts("A red fox!\n{\"prompt\": \"whale\", \"seed\": 9}").unwrap();
+    let jobs = plan_jobs(&items, 2, "1024x1024", None);
+    let seeds: Vec<_> = jobs.iter().map(|job| job.seed).collect();
+    assert_eq!(seeds, [None, None, Some(9), Some(10)]);
+    assert_eq!(jobs[3].index, 4);
+    assert_eq!((jobs[3].prompt_index, jobs[3].n), (2, 2));
+
+    let template = "{index}_{prompt_index}-{n}_{seed}_{slug}.png";
+    check_name_template(template).unwrap();
+    assert_eq!(
+      render_name(template, &jobs[0], None, 12),
+      "01_1-1_random_a-red-fox.png"
+    );
+    assert_eq!(
+      render_name(template, &jobs[3], Some(10), 4),
+      "4_2-2_10_whale.png"
+    );
+    assert!(check_name_template("{index}_{width}.png").is_err());
+    assert!(check_name_template("{index.png").is_err());
+  }
+}
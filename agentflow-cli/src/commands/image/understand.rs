@@ -1,11 +1,96 @@
-use anyhow::Result;
+//! `agentflow image understand` — ask a vision model about an image.
+//!
+//! One image goes straight to StepFun's chat API. `--dir` switches to a
+//! batch: the same prompt runs over every image under the directory that
+//! matches `--glob`, through the model registry, up to `--parallel` at a
+//! time and paced to the provider's `rate_limit.requests_per_minute`
+//! from `models.yml`. Each image's result is written to `--output-dir`
+//! as `<image>.json`, or as one line of `--jsonl`. A failing image is
+//! recorded and reported at the end without stopping the rest.
+
+use agentflow_llm::{AgentFlow, LLMConfig, MultimodalMessage};
+use anyhow::{Context, Result, bail};
 use base64::Engine;
+use futures::StreamExt;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use reqwest;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::env;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::fs;
 
-pub async fn execute(
+pub const DEFAULT_MODEL: &str = "step-1v-8k";
+/// Extensions a batch picks up when no `--glob` is given.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+pub struct UnderstandOptions {
+  /// The first positional: the image, or with `--dir` the prompt.
+  pub image_path: String,
+  /// The second positional, which `--dir` leaves out.
+  pub prompt: Option<String>,
+  pub model: Option<String>,
+  pub temperature: Option<f32>,
+  pub max_tokens: Option<u32>,
+  pub output: Option<String>,
+  pub dir: Option<PathBuf>,
+  pub glob: Option<String>,
+  pub parallel: usize,
+  pub output_dir: Option<PathBuf>,
+  pub jsonl: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultStatus {
+  Succeeded,
+  Failed,
+}
+
+/// One image's result: a `--jsonl` line or an `--output-dir` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageResult {
+  /// Path relative to `--dir`.
+  pub image: String,
+  pub model: String,
+  pub status: ResultStatus,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub response: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub prompt_tokens: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub completion_tokens: Option<u32>,
+  pub duration_ms: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+pub async fn execute(options: UnderstandOptions) -> Result<()> {
+  match (&options.dir, &options.prompt) {
+    (Some(_), None) => {
+      let prompt = options.image_path.clone();
+      execute_batch(options, prompt).await
+    }
+    (Some(_), Some(_)) => {
+      bail!("--dir takes the prompt as the only argument; drop the image path")
+    }
+    (None, Some(prompt)) => {
+      execute_single(
+        options.image_path,
+        prompt.clone(),
+        options.model,
+        options.temperature,
+        options.max_tokens,
+        options.output,
+      )
+      .await
+    }
+    (None, None) => bail!("Pass an image path and a prompt, or --dir <dir> and a prompt"),
+  }
+}
+
+async fn execute_single(
   image_path: String,
   prompt: String,
   model: Option<String>,
@@ -13,7 +98,7 @@ pub async fn execute(
   max_tokens: Option<u32>,
   output: Option<String>,
 ) -> Result<()> {
-  let model = model.unwrap_or_else(|| "step-1v-8k".to_string());
+  let model = model.unwrap_or_else(|| DEFAULT_MODEL.to_string());
 
   println!("👁️  AgentFlow Image Understanding");
   println!("Model: {}", model);
@@ -142,3 +227,269 @@ pub async fn execute(
   println!("🎉 Image understanding completed successfully!");
   Ok(())
 }
+
+async fn execute_batch(options: UnderstandOptions, prompt: String) -> Result<()> {
+  let dir = options.dir.clone().unwrap_or_default();
+  if options.output.is_some() {
+    bail!("--output names a single result; use --output-dir or --jsonl with --dir");
+  }
+  if options.output_dir.is_none() && options.jsonl.is_none() {
+    bail!("Pass --output-dir <dir> or --jsonl <file> for --dir");
+  }
+  let images = find_images(&dir, options.glob.as_deref())?;
+  if images.is_empty() {
+    bail!(
+      "No images under {} match {}",
+      dir.display(),
+      options.glob.as_deref().unwrap_or("the image extensions")
+    );
+  }
+  let model = options
+    .model
+    .clone()
+    .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+
+  AgentFlow::init()
+    .await
+    .context("Failed to initialise AgentFlow — is your API key configured?")?;
+  let pacer = Pacer::for_model(&model).await;
+
+  let total = images.len();
+  println!("👁️  AgentFlow Image Understanding");
+  println!("Model: {}", model);
+  println!("Prompt: {}", prompt);
+  println!(
+    "Images: {} under {} ({} at a time)",
+    total,
+    dir.display(),
+    options.parallel.max(1)
+  );
+  println!();
+
+  let bar = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+  bar.set_style(
+    ProgressStyle::with_template("{bar:30} {pos}/{len} {msg}")
+      .unwrap_or_else(|_| ProgressStyle::default_bar()),
+  );
+  let mut results: Vec<(usize, ImageResult)> =
+    futures::stream::iter(images.into_iter().enumerate())
+      .map(|(index, path)| {
+        let (options, prompt, model, pacer, bar, dir) =
+          (&options, &prompt, &model, &pacer, &bar, &dir);
+        async move {
+          let start = Instant::now();
+          pacer.wait().await;
+          let outcome = analyze(&path, prompt, options, model).await;
+          let image = relative_name(dir, &path);
+          bar.set_message(image.clone());
+          bar.inc(1);
+          let mut result = ImageResult {
+            image,
+            model: model.clone(),
+            status: ResultStatus::Failed,
+            response: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            duration_ms: start.elapsed().as_millis() as u64,
+            error: None,
+          };
+          match outcome {
+            Ok((response, usage)) => {
+              result.status = ResultStatus::Succeeded;
+              result.response = Some(response);
+              (result.prompt_tokens, result.completion_tokens) = usage;
+            }
+            Err(err) => result.error = Some(format!("{:#}", err)),
+          }
+          (index, result)
+        }
+      })
+      .buffer_unordered(options.parallel.max(1))
+      .collect()
+      .await;
+  bar.finish_and_clear();
+  results.sort_by_key(|(index, _)| *index);
+  let results: Vec<ImageResult> = results.into_iter().map(|(_, result)| result).collect();
+
+  if let Some(out_dir) = &options.output_dir {
+    for result in &results {
+      let path = out_dir.join(format!("{}.json", result.image));
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+          .await
+          .with_context(|| format!("Failed to create {}", parent.display()))?;
+      }
+      fs::write(&path, serde_json::to_string_pretty(result)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    println!("💾 Results written under {}", out_dir.display());
+  }
+  if let Some(path) = &options.jsonl {
+    let mut lines = String::new();
+    for result in &results {
+      lines.push_str(&serde_json::to_string(result)?);
+      lines.push('\n');
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+      fs::create_dir_all(parent)
+        .await
+        .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, lines)
+      .await
+      .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("💾 Results written to {}", path.display());
+  }
+
+  let failures: Vec<&ImageResult> = results
+    .iter()
+    .filter(|result| result.status == ResultStatus::Failed)
+    .collect();
+  if !failures.is_empty() {
+    println!();
+    println!("❌ {} image(s) failed:", failures.len());
+    for failure in &failures {
+      println!(
+        "  {}: {}",
+        failure.image,
+        failure.error.as_deref().unwrap_or_default()
+      );
+    }
+    bail!("{} of {} image(s) failed", failures.len(), total);
+  }
+  println!("🎉 Analyzed {} image(s)", total);
+  Ok(())
+}
+
+/// Files under `dir` matching `pattern` (relative to `dir`), else every
+/// file with an image extension directly in it; sorted.
+fn find_images(dir: &Path, pattern: Option<&str>) -> Result<Vec<PathBuf>> {
+  if !dir.is_dir() {
+    bail!("--dir {} is not a directory", dir.display());
+  }
+  let mut images = match pattern {
+    Some(pattern) => {
+      let full = format!(
+        "{}/{}",
+        glob::Pattern::escape(&dir.to_string_lossy()),
+        pattern
+      );
+      glob::glob(&full)
+        .with_context(|| format!("Invalid --glob '{}'", pattern))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| !path.is_dir())
+        .collect::<Vec<_>>()
+    }
+    None => std::fs::read_dir(dir)
+      .with_context(|| format!("Failed to read {}", dir.display()))?
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| {
+        !path.is_dir()
+          && path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+      })
+      .collect(),
+  };
+  images.sort();
+  Ok(images)
+}
+
+/// `path` relative to `dir`, with `/` separators.
+fn relative_name(dir: &Path, path: &Path) -> String {
+  path
+    .strip_prefix(dir)
+    .unwrap_or(path)
+    .components()
+    .map(|part| part.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+/// One batch image through the model registry: the reply and its token
+/// counts, when the provider reported them.
+async fn analyze(
+  path: &Path,
+  prompt: &str,
+  options: &UnderstandOptions,
+  model: &str,
+) -> Result<(String, (Option<u32>, Option<u32>))> {
+  let bytes = fs::read(path)
+    .await
+    .with_context(|| format!("Failed to read image {}", path.display()))?;
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(str::to_ascii_lowercase);
+  let mime_type = match extension.as_deref() {
+    Some("png") => "image/png",
+    Some("gif") => "image/gif",
+    Some("webp") => "image/webp",
+    Some("bmp") => "image/bmp",
+    _ => "image/jpeg",
+  };
+  let message = MultimodalMessage::user()
+    .add_text(prompt)
+    .add_image_url(format!(
+      "data:{};base64,{}",
+      mime_type,
+      base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+    .build();
+  let mut request = AgentFlow::model(model)
+    .multimodal_messages(vec![message])
+    .enable_logging(false);
+  if let Some(temperature) = options.temperature {
+    request = request.temperature(temperature);
+  }
+  if let Some(max_tokens) = options.max_tokens {
+    request = request.max_tokens(max_tokens);
+  }
+  let response = request
+    .execute_full()
+    .await
+    .with_context(|| format!("Request to model '{}' failed", model))?;
+  let usage = response
+    .usage
+    .map(|usage| (usage.prompt_tokens, usage.completion_tokens))
+    .unwrap_or_default();
+  Ok((response.content, usage))
+}
+
+/// Spaces request starts to the provider's configured
+/// `rate_limit.requests_per_minute`; a no-op when it has none.
+struct Pacer {
+  interval: Option<tokio::sync::Mutex<tokio::time::Interval>>,
+}
+
+impl Pacer {
+  async fn for_model(model: &str) -> Self {
+    let requests_per_minute = LLMConfig::from_default_source()
+      .await
+      .ok()
+      .and_then(|(config, _)| {
+        let vendor = config.models.get(model)?.vendor.clone();
+        config
+          .get_provider(&vendor)?
+          .rate_limit
+          .as_ref()
+          .map(|limit| limit.requests_per_minute)
+      })
+      .filter(|rpm| *rpm > 0);
+    Self {
+      interval: requests_per_minute.map(|rpm| {
+        let mut interval = tokio::time::interval(Duration::from_secs(60) / rpm);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        tokio::sync::Mutex::new(interval)
+      }),
+    }
+  }
+
+  async fn wait(&self) {
+    if let Some(interval) = &self.interval {
+      interval.lock().await.tick().await;
+    }
+  }
+}
@@ -0,0 +1,306 @@
+//! The `llm models --detailed` / `--json` catalog: configured models
+//! merged with cached discovery results and capability metadata.
+//!
+//! Configured entries that share a vendor and model id collapse into one
+//! row; the entry named after the model id (else the first name) leads
+//! and the others are listed as aliases. Context window and pricing come
+//! from the entries' optional `context_window` and `pricing` keys (see
+//! [`ModelConfig::context_window`]), input types from `accepts`.
+//!
+//! Discovery results are cached in `~/.agentflow/cache/model_discovery.json`
+//! so the view works offline; `--refresh` re-fetches the vendors' model
+//! lists first. A vendor with a cached list marks each configured model
+//! as discovered or not, and adds the models only the vendor lists.
+
+use agentflow_llm::discovery::ModelSource;
+use agentflow_llm::{InputType, LLMConfig, ModelConfig, ModelPricing};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Bump on a breaking change to [`DiscoveryCache`]; older files are then
+/// ignored rather than misread.
+const CACHE_SCHEMA_VERSION: u32 = 1;
+
+/// Input types in display order.
+const INPUT_ORDER: [(InputType, &str); 5] = [
+  (InputType::Text, "text"),
+  (InputType::Image, "image"),
+  (InputType::Audio, "audio"),
+  (InputType::Video, "video"),
+  (InputType::Document, "document"),
+];
+
+/// Model ids per vendor, as last fetched from the vendors' model lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryCache {
+  pub version: u32,
+  #[serde(default)]
+  pub vendors: BTreeMap<String, VendorListing>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VendorListing {
+  pub fetched_at: DateTime<Utc>,
+  pub models: BTreeSet<String>,
+}
+
+impl Default for DiscoveryCache {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DiscoveryCache {
+  pub fn new() -> Self {
+    Self {
+      version: CACHE_SCHEMA_VERSION,
+      vendors: BTreeMap::new(),
+    }
+  }
+
+  /// `~/.agentflow/cache/model_discovery.json`.
+  pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+      home
+        .join(".agentflow")
+        .join("cache")
+        .join("model_discovery.json")
+    })
+  }
+
+  /// An empty cache when the file is missing, unreadable or from another
+  /// schema version.
+  pub fn load(path: &Path) -> Self {
+    std::fs::read_to_string(path)
+      .ok()
+      .and_then(|raw| serde_json::from_str::<Self>(&raw).ok())
+      .filter(|cache| cache.version == CACHE_SCHEMA_VERSION)
+      .unwrap_or_default()
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)
+        .with_context(|| format!("creating cache directory {}", parent.display()))?;
+    }
+    let raw = serde_json::to_string_pretty(self).context("serialising discovery cache")?;
+    std::fs::write(path, raw).with_context(|| format!("writing cache file {}", path.display()))
+  }
+
+  /// Fetch each vendor's model list from `source`. A vendor that fails
+  /// keeps its previous listing; the failures are returned as
+  /// `(vendor, error)`.
+  pub async fn refresh(
+    &mut self,
+    source: &dyn ModelSource,
+    vendors: &[String],
+  ) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for vendor in vendors {
+      match source.list_models(vendor).await {
+        Ok(models) => {
+          self.vendors.insert(
+            vendor.clone(),
+            VendorListing {
+              fetched_at: Utc::now(),
+              models: models.into_iter().map(|model| model.id).collect(),
+            },
+          );
+        }
+        Err(e) => failures.push((vendor.clone(), e.to_string())),
+      }
+    }
+    failures
+  }
+}
+
+/// How a catalog row was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+  /// Configured and in the vendor's discovered list.
+  Discovered,
+  /// Configured; the vendor has no cached list to check against.
+  Configured,
+  /// Configured, but missing from the vendor's discovered list: a typo,
+  /// a retired model or a private deployment.
+  NotDiscovered,
+  /// Listed by the vendor, not configured.
+  DiscoveredOnly,
+}
+
+impl Availability {
+  pub fn label(self) -> &'static str {
+    match self {
+      Self::Discovered => "configured+discovered",
+      Self::Configured => "configured",
+      Self::NotDiscovered => "configured, NOT discovered",
+      Self::DiscoveredOnly => "discovered-only",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogEntry {
+  pub vendor: String,
+  /// The id sent to the vendor.
+  pub model: String,
+  /// The configured name; `None` for discovered-only models.
+  pub name: Option<String>,
+  pub aliases: Vec<String>,
+  pub context_window: Option<u32>,
+  /// Empty for discovered-only models, whose inputs are unknown.
+  pub inputs: Vec<String>,
+  pub pricing: Option<ModelPricing>,
+  pub availability: Availability,
+}
+
+/// The configured models of `config` merged with `cache`, sorted by
+/// vendor and model id.
+pub fn merge(config: &LLMConfig, cache: &DiscoveryCache) -> Vec<CatalogEntry> {
+  let mut groups: BTreeMap<(String, String), Vec<(&String, &ModelConfig)>> = BTreeMap::new();
+  for (name, model) in &config.models {
+    let id = model.model_id.clone().unwrap_or_else(|| name.clone());
+    groups
+      .entry((model.vendor.clone(), id))
+      .or_default()
+      .push((name, model));
+  }
+
+  let mut entries = Vec::new();
+  for ((vendor, id), mut group) in groups {
+    group.sort_by_key(|(name, _)| (**name != id, (*name).clone()));
+    let availability = match cache.vendors.get(&vendor) {
+      Some(listing) if listing.models.contains(&id) => Availability::Discovered,
+      Some(_) => Availability::NotDiscovered,
+      None => Availability::Configured,
+    };
+    let (name, lead) = group[0];
+    let accepts = lead.get_capabilities().accepts;
+    entries.push(CatalogEntry {
+      vendor,
+      name: Some(name.clone()),
+      aliases: group[1..].iter().map(|(name, _)| (*name).clone()).collect(),
+      context_window: group.iter().find_map(|(_, model)| model.context_window()),
+      inputs: INPUT_ORDER
+        .iter()
+        .filter(|(input, _)| accepts.contains(input))
+        .map(|(_, label)| label.to_string())
+        .collect(),
+      pricing: group.iter().find_map(|(_, model)| model.pricing()),
+      model: id,
+      availability,
+    });
+  }
+
+  for (vendor, listing) in &cache.vendors {
+    for id in &listing.models {
+      let configured = entries
+        .iter()
+        .any(|entry| entry.vendor == *vendor && entry.model == *id);
+      if !configured {
+        entries.push(CatalogEntry {
+          vendor: vendor.clone(),
+          model: id.clone(),
+          name: None,
+          aliases: Vec::new(),
+          context_window: None,
+          inputs: Vec::new(),
+          pricing: None,
+          availability: Availability::DiscoveredOnly,
+        });
+      }
+    }
+  }
+  entries.sort_by(|a, b| a.vendor.cmp(&b.vendor).then_with(|| a.model.cmp(&b.model)));
+  entries
+}
+
+/// Aligned columns, one row per entry.
+pub fn render_table(entries: &[CatalogEntry]) -> String {
+  let header = [
+    "VENDOR", "MODEL", "ALIASES", "CONTEXT", "INPUTS", "PRICE/1M", "STATUS",
+  ]
+  .map(String::from);
+  let rows: Vec<[String; 7]> = entries
+    .iter()
+    .map(|entry| {
+      let mut names = entry.name.iter().cloned().collect::<Vec<_>>();
+      names.retain(|name| *name != entry.model);
+      names.extend(entry.aliases.iter().cloned());
+      [
+        entry.vendor.clone(),
+        entry.model.clone(),
+        dash_if_empty(names.join(", ")),
+        entry
+          .context_window
+          .map_or_else(|| "-".to_string(), format_tokens),
+        dash_if_empty(entry.inputs.join(",")),
+        entry.pricing.as_ref().map_or_else(
+          || "-".to_string(),
+          |price| format!("{}/{} {}", price.input, price.output, price.currency),
+        ),
+        entry.availability.label().to_string(),
+      ]
+    })
+    .collect();
+
+  let mut widths = header.clone().map(|cell| cell.chars().count());
+  for row in &rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+  let mut out = String::new();
+  for row in std::iter::once(&header).chain(&rows) {
+    let line = row
+      .iter()
+      .zip(widths)
+      .map(|(cell, width)| format!("{cell:<width$}"))
+      .collect::<Vec<_>>()
+      .join("  ");
+    out.push_str(line.trim_end());
+    out.push('\n');
+  }
+  out
+}
+
+fn dash_if_empty(text: String) -> String {
+  if text.is_empty() {
+    "-".to_string()
+  } else {
+    text
+  }
+}
+
+/// `128000` → `128k`, `1048576` → `1M`; other counts as-is.
+fn format_tokens(tokens: u32) -> String {
+  if tokens >= 1_000_000 && tokens.is_multiple_of(1_000_000) {
+    format!("{}M", tokens / 1_000_000)
+  } else if tokens == 1 << 20 {
+    "1M".to_string()
+  } else if tokens >= 1_000 && tokens.is_multiple_of(1_000) {
+    format!("{}k", tokens / 1_000)
+  } else if tokens >= 1_024 && tokens.is_multiple_of(1_024) {
+    format!("{}k", tokens / 1_024)
+  } else {
+    tokens.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn token_counts_are_abbreviated() {
+    assert_eq!(format_tokens(128_000), "128k");
+    assert_eq!(format_tokens(32_768), "32k");
+    assert_eq!(format_tokens(1_048_576), "1M");
+    assert_eq!(format_tokens(2_000_000), "2M");
+    assert_eq!(format_tokens(4_097), "4097");
+  }
+}
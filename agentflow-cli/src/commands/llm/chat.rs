@@ -0,0 +1,477 @@
+//! `agentflow llm chat`: a multi-turn conversation straight against one
+//! model, streaming each reply as it arrives.
+//!
+//! The conversation lives in a [`ChatHistory`], which is also the file
+//! format behind `--save`, `--load` and the `/save` / `/load` slash
+//! commands. When stdin is not a terminal the command runs a single
+//! turn instead: the whole of stdin is the prompt and only the reply is
+//! written to stdout, so `echo "hi" | agentflow llm chat` composes with
+//! other tools.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::commands::repl::{LineReader, ReadLine};
+use crate::redaction::redact_cli_text;
+use crate::shutdown::{SIGINT_EXIT_CODE, shutdown_signal};
+use agentflow_llm::tokenizer::count_tokens_for_model;
+use agentflow_llm::{AgentFlow, LLMConfig, MultimodalMessage, StreamingResponse};
+
+/// Model used when neither `--model`, a loaded history nor the
+/// configuration's `defaults.model` names one.
+pub(crate) const DEFAULT_MODEL: &str = "gpt-4o";
+
+/// `defaults.model` of the model configuration, else [`DEFAULT_MODEL`].
+pub(crate) async fn default_model() -> String {
+  LLMConfig::from_default_source()
+    .await
+    .ok()
+    .and_then(|(config, _)| config.defaults.model)
+    .unwrap_or_else(|| DEFAULT_MODEL.to_string())
+}
+
+const HELP_TEXT: &str = "\
+Commands:
+  /model [name]     — show or switch the model
+  /system [prompt]  — show or replace the system prompt
+  /save <file>      — write the conversation to a JSON file
+  /load <file>      — replace the conversation with a saved one
+  /clear            — forget every turn (model and system prompt stay)
+  /tokens           — show token usage so far
+  /help             — show this help message
+  /exit, /quit      — end the session
+";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+  /// `user` or `assistant`; the system prompt is kept separately.
+  pub role: String,
+  pub content: String,
+}
+
+/// Tokens spent so far. Providers that report usage on the stream are
+/// counted exactly; otherwise both sides are estimated with the model's
+/// tokenizer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatUsage {
+  pub prompt_tokens: u64,
+  pub completion_tokens: u64,
+}
+
+impl ChatUsage {
+  pub fn total(&self) -> u64 {
+    self.prompt_tokens + self.completion_tokens
+  }
+}
+
+/// A conversation, as written by `--save` and read by `--load`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatHistory {
+  pub model: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub system: Option<String>,
+  #[serde(default)]
+  pub messages: Vec<ChatMessage>,
+  #[serde(default)]
+  pub usage: ChatUsage,
+}
+
+impl ChatHistory {
+  pub fn new(model: impl Into<String>, system: Option<String>) -> Self {
+    Self {
+      model: model.into(),
+      system,
+      messages: Vec::new(),
+      usage: ChatUsage::default(),
+    }
+  }
+
+  pub fn load(path: &Path) -> Result<Self> {
+    let raw = std::fs::read_to_string(path)
+      .with_context(|| format!("Failed to read chat history {}", path.display()))?;
+    serde_json::from_str(&raw)
+      .with_context(|| format!("{} is not a chat history file", path.display()))
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    if let Some(parent) = path
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+    {
+      std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(self)?)
+      .with_context(|| format!("Failed to write chat history {}", path.display()))
+  }
+
+  /// Number of completed user/assistant exchanges.
+  pub fn turns(&self) -> usize {
+    self
+      .messages
+      .iter()
+      .filter(|message| message.role == "assistant")
+      .count()
+  }
+
+  /// The request for the next turn: the system prompt, every earlier
+  /// message, then `prompt`.
+  fn request_messages(&self, prompt: &str) -> Vec<MultimodalMessage> {
+    let mut messages = Vec::with_capacity(self.messages.len() + 2);
+    if let Some(system) = &self.system {
+      messages.push(MultimodalMessage::text("system", system.as_str()));
+    }
+    messages.extend(
+      self
+        .messages
+        .iter()
+        .map(|message| MultimodalMessage::text(message.role.as_str(), message.content.as_str())),
+    );
+    messages.push(MultimodalMessage::text("user", prompt));
+    messages
+  }
+
+  /// Sends `prompt` with the conversation so far, writing the reply to
+  /// `out` chunk by chunk. The exchange is only added to the history
+  /// once the stream has finished.
+  pub async fn send(&mut self, prompt: &str, out: &mut impl Write) -> Result<String> {
+    let messages = self.request_messages(prompt);
+    let prompt_text = messages
+      .iter()
+      .map(MultimodalMessage::get_text)
+      .collect::<Vec<_>>()
+      .join("\n");
+    let stream = AgentFlow::model(&self.model)
+      .multimodal_messages(messages)
+      .enable_logging(false)
+      .execute_streaming()
+      .await
+      .with_context(|| format!("Request to model '{}' failed", self.model))?;
+    let (reply, reported) = stream_to(stream, out).await?;
+
+    self.usage.prompt_tokens += reported
+      .as_ref()
+      .and_then(|usage| usage.prompt_tokens)
+      .map(u64::from)
+      .unwrap_or_else(|| u64::from(count_tokens_for_model(&self.model, &prompt_text)));
+    self.usage.completion_tokens += reported
+      .as_ref()
+      .and_then(|usage| usage.completion_tokens)
+      .map(u64::from)
+      .unwrap_or_else(|| u64::from(count_tokens_for_model(&self.model, &reply)));
+    self.messages.push(ChatMessage {
+      role: "user".to_string(),
+      content: prompt.to_string(),
+    });
+    self.messages.push(ChatMessage {
+      role: "assistant".to_string(),
+      content: reply.clone(),
+    });
+    Ok(reply)
+  }
+}
+
+/// Drains `stream` into `out` as chunks arrive and returns the full
+/// text plus the last usage figures the provider reported, if any.
+pub async fn stream_to(
+  mut stream: Box<dyn StreamingResponse>,
+  out: &mut impl Write,
+) -> Result<(String, Option<agentflow_llm::client::streaming::TokenUsage>)> {
+  let mut text = String::new();
+  let mut usage = None;
+  while let Some(chunk) = stream
+    .next_chunk()
+    .await
+    .context("Streaming response failed")?
+  {
+    if !chunk.content.is_empty() {
+      out.write_all(chunk.content.as_bytes())?;
+      out.flush()?;
+      text.push_str(&chunk.content);
+    }
+    if chunk.usage.is_some() {
+      usage = chunk.usage;
+    }
+  }
+  Ok((text, usage))
+}
+
+/// A `/`-prefixed REPL line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashCommand {
+  Model(Option<String>),
+  System(Option<String>),
+  Save(PathBuf),
+  Load(PathBuf),
+  Clear,
+  Tokens,
+  Help,
+  Exit,
+}
+
+impl SlashCommand {
+  /// `Ok(None)` for ordinary chat input; an error for an unknown command
+  /// or a missing argument.
+  pub fn parse(line: &str) -> Result<Option<Self>> {
+    let Some(rest) = line.trim().strip_prefix('/') else {
+      return Ok(None);
+    };
+    let (name, arg) = match rest.split_once(char::is_whitespace) {
+      Some((name, arg)) => (name, Some(arg.trim()).filter(|arg| !arg.is_empty())),
+      None => (rest, None),
+    };
+    let path = |command: &str| {
+      arg
+        .map(PathBuf::from)
+        .with_context(|| format!("/{command} needs a file path"))
+    };
+    Ok(Some(match name {
+      "model" => Self::Model(arg.map(str::to_string)),
+      "system" => Self::System(arg.map(str::to_string)),
+      "save" => Self::Save(path("save")?),
+      "load" => Self::Load(path("load")?),
+      "clear" => Self::Clear,
+      "tokens" => Self::Tokens,
+      "help" => Self::Help,
+      "exit" | "quit" => Self::Exit,
+      other => bail!("Unknown command /{other}; type /help for the list"),
+    }))
+  }
+}
+
+pub async fn execute(
+  model: Option<String>,
+  system: Option<String>,
+  save: Option<PathBuf>,
+  load: Option<PathBuf>,
+) -> Result<()> {
+  let mut history = match &load {
+    Some(path) => ChatHistory::load(path)?,
+    None => ChatHistory::new(default_model().await, None),
+  };
+  if let Some(model) = model {
+    history.model = model;
+  }
+  if system.is_some() {
+    history.system = system;
+  }
+
+  AgentFlow::init()
+    .await
+    .context("Failed to initialise AgentFlow — is your API key configured?")?;
+
+  if std::io::stdin().is_terminal() {
+    repl(history, save).await
+  } else {
+    one_shot(history, save).await
+  }
+}
+
+/// Piped stdin: one turn, reply only on stdout.
+async fn one_shot(mut history: ChatHistory, save: Option<PathBuf>) -> Result<()> {
+  let mut prompt = String::new();
+  std::io::stdin()
+    .read_to_string(&mut prompt)
+    .context("Failed to read the prompt from stdin")?;
+  let prompt = prompt.trim();
+  if prompt.is_empty() {
+    bail!("No prompt on stdin; pipe a question in or run `agentflow llm chat` in a terminal");
+  }
+
+  let mut stdout = std::io::stdout();
+  history.send(prompt, &mut stdout).await?;
+  writeln!(stdout)?;
+  if let Some(path) = &save {
+    history.save(path)?;
+  }
+  Ok(())
+}
+
+async fn repl(mut history: ChatHistory, save: Option<PathBuf>) -> Result<()> {
+  println!("╔══════════════════════════════════════════════════╗");
+  println!("║  💬  LLM Chat");
+  println!("║  Model: {}", history.model);
+  if let Some(path) = &save {
+    println!("║  Saving to: {}", path.display());
+  }
+  println!("╚══════════════════════════════════════════════════╝");
+  if !history.messages.is_empty() {
+    println!("Loaded {} earlier turn(s).", history.turns());
+  }
+  println!("Type a message or /help for commands. Ctrl-D to exit.\n");
+
+  let mut reader = LineReader::new();
+  let mut stdout = std::io::stdout();
+  loop {
+    let line = match reader.read_line("› ").await? {
+      ReadLine::Line(line) => line,
+      ReadLine::Interrupted => continue,
+      ReadLine::Eof => break,
+    };
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+      continue;
+    }
+
+    match SlashCommand::parse(trimmed) {
+      Ok(Some(SlashCommand::Exit)) => break,
+      Ok(Some(command)) => {
+        if let Err(err) = run_command(&mut history, command) {
+          eprintln!("⚠  {:#}", err);
+        }
+        continue;
+      }
+      Ok(None) => {}
+      Err(err) => {
+        eprintln!("⚠  {:#}", err);
+        continue;
+      }
+    }
+
+    // Ctrl-C while a reply streams ends the session, like `skill chat`;
+    // the turn in flight is not added to the history.
+    let outcome = {
+      let send = history.send(trimmed, &mut stdout);
+      tokio::pin!(send);
+      tokio::select! {
+        biased;
+        res = &mut send => Some(res),
+        _ = shutdown_signal() => None,
+      }
+    };
+    match outcome {
+      Some(Ok(_)) => {
+        println!("\n");
+        if let Some(path) = &save {
+          history.save(path)?;
+        }
+      }
+      Some(Err(err)) => {
+        eprintln!("\n❌  {}", redact_cli_text(format!("{:#}", err)));
+      }
+      None => {
+        eprintln!("\n🛑 Cancelled (received SIGINT/SIGTERM)");
+        if let Some(path) = &save {
+          history.save(path)?;
+        }
+        std::process::exit(SIGINT_EXIT_CODE);
+      }
+    }
+  }
+
+  if let Some(path) = &save {
+    history.save(path)?;
+    println!("💾 Conversation saved to {}", path.display());
+  }
+  println!("👋 Bye!");
+  Ok(())
+}
+
+fn run_command(history: &mut ChatHistory, command: SlashCommand) -> Result<()> {
+  match command {
+    SlashCommand::Model(None) => println!("🧠 Model: {}", history.model),
+    SlashCommand::Model(Some(model)) => {
+      history.model = model;
+      println!("🧠 Switched to {}", history.model);
+    }
+    SlashCommand::System(None) => match &history.system {
+      Some(system) => println!("📝 System prompt: {}", system),
+      None => println!("📝 No system prompt set."),
+    },
+    SlashCommand::System(Some(system)) => {
+      history.system = Some(system);
+      println!("📝 System prompt updated.");
+    }
+    SlashCommand::Save(path) => {
+      history.save(&path)?;
+      println!("💾 Conversation saved to {}", path.display());
+    }
+    SlashCommand::Load(path) => {
+      *history = ChatHistory::load(&path)?;
+      println!(
+        "📂 Loaded {} turn(s) from {} (model: {})",
+        history.turns(),
+        path.display(),
+        history.model
+      );
+    }
+    SlashCommand::Clear => {
+      history.messages.clear();
+      history.usage = ChatUsage::default();
+      println!("🧹 Conversation cleared.");
+    }
+    SlashCommand::Tokens => println!(
+      "📊 Tokens so far: {} prompt + {} completion = {} over {} turn(s)",
+      history.usage.prompt_tokens,
+      history.usage.completion_tokens,
+      history.usage.total(),
+      history.turns()
+    ),
+    SlashCommand::Help => print!("{}", HELP_TEXT),
+    SlashCommand::Exit => {}
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn slash_commands_parse_with_and_without_arguments() {
+    assert_eq!(SlashCommand::parse("hello /model").unwrap(), None);
+    assert_eq!(
+      SlashCommand::parse("/model  mock-model ").unwrap(),
+      Some(SlashCommand::Model(Some("mock-model".into())))
+    );
+    assert_eq!(
+      SlashCommand::parse("/model").unwrap(),
+      Some(SlashCommand::Model(None))
+    );
+    assert_eq!(
+      SlashCommand::parse("/system Answer in French.").unwrap(),
+      Some(SlashCommand::System(Some("Answer in French.".into())))
+    );
+    assert_eq!(
+      SlashCommand::parse("/save chats/today.json").unwrap(),
+      Some(SlashCommand::Save(PathBuf::from("chats/today.json")))
+    );
+    assert_eq!(
+      SlashCommand::parse("/quit").unwrap(),
+      Some(SlashCommand::Exit)
+    );
+    assert!(SlashCommand::parse("/load").is_err());
+    assert!(SlashCommand::parse("/teleport").is_err());
+  }
+
+  #[test]
+  fn request_carries_system_prompt_and_earlier_turns() {
+    let mut history = ChatHistory::new("mock-model", Some("Be brief.".into()));
+    history.messages.push(ChatMessage {
+      role: "user".into(),
+      content: "hi".into(),
+    });
+    history.messages.push(ChatMessage {
+      role: "assistant".into(),
+      content: "hello".into(),
+    });
+
+    let roles: Vec<_> = history
+      .request_messages("again")
+      .iter()
+      .map(|message| (message.role.clone(), message.get_text()))
+      .collect();
+    assert_eq!(
+      roles,
+      vec![
+        ("system".to_string(), "Be brief.".to_string()),
+        ("user".to_string(), "hi".to_string()),
+        ("assistant".to_string(), "hello".to_string()),
+        ("user".to_string(), "again".to_string()),
+      ]
+    );
+    assert_eq!(history.turns(), 1);
+  }
+}
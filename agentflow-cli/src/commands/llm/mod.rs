@@ -1 +1,4 @@
+pub mod catalog;
+pub mod chat;
 pub mod models;
+pub mod prompt;
@@ -1,5 +1,7 @@
+use super::catalog::{self, Availability, DiscoveryCache};
 use agentflow_llm::{
-  AgentFlow, LLMConfig, LLMConfigSourceKind,
+  AgentFlow, LLMConfig, LLMConfigSourceKind, ModelFetcher,
+  discovery::VendorConfig,
   registry::{ModelRegistry, model_registry::ModelInfo},
 };
 use anyhow::{Context, Result};
@@ -11,6 +13,8 @@ pub async fn execute(
   provider: Option<String>,
   detailed: bool,
   refresh_from_api: bool,
+  refresh: bool,
+  json: bool,
   format: String,
 ) -> Result<()> {
   let source = LLMConfig::resolve_default_source()?;
@@ -24,6 +28,9 @@ pub async fn execute(
   if refresh_from_api {
     return execute_refresh(provider, source).await;
   }
+  if json || refresh || (detailed && format != "json-envelope") {
+    return execute_catalog(provider, refresh, json).await;
+  }
 
   let models = match source.kind {
     LLMConfigSourceKind::BuiltInDefault => printable_models_from_registry().await?,
@@ -83,12 +90,93 @@ pub async fn execute(
     return Ok(());
   }
 
-  if detailed {
-    print_detailed_models(&filtered_models);
-  } else {
-    print_simple_models(&filtered_models);
+  print_simple_models(&filtered_models);
+  Ok(())
+}
+
+/// `--detailed` / `--json`: the configured models merged with the
+/// discovery cache (see [`catalog`]); `--refresh` re-fetches the model
+/// lists of the discovery vendors `--provider` matches first.
+async fn execute_catalog(provider: Option<String>, refresh: bool, json: bool) -> Result<()> {
+  let (config, source) = LLMConfig::from_default_source()
+    .await
+    .context("Failed to load the model configuration")?;
+  let matches = |vendor: &str| {
+    provider
+      .as_ref()
+      .is_none_or(|filter| vendor.to_lowercase().contains(&filter.to_lowercase()))
+  };
+  let cache_path = DiscoveryCache::default_path().context("Could not determine home directory")?;
+  let mut cache = DiscoveryCache::load(&cache_path);
+  if refresh {
+    let vendors: Vec<String> = VendorConfig::vendors_with_model_list()
+      .into_iter()
+      .map(|vendor| vendor.name)
+      .filter(|vendor| matches(vendor))
+      .collect();
+    let fetcher = ModelFetcher::new().context("Failed to build the discovery client")?;
+    for (vendor, error) in cache.refresh(&fetcher, &vendors).await {
+      eprintln!("Warning: could not list {vendor} models: {error}");
+    }
+    cache.save(&cache_path)?;
   }
 
+  let entries: Vec<_> = catalog::merge(&config, &cache)
+    .into_iter()
+    .filter(|entry| matches(&entry.vendor))
+    .collect();
+  if json {
+    let discovered_at: std::collections::BTreeMap<_, _> = cache
+      .vendors
+      .iter()
+      .filter(|(vendor, _)| matches(vendor))
+      .map(|(vendor, listing)| (vendor, listing.fetched_at))
+      .collect();
+    let payload = serde_json::json!({
+      "source": source.display_path(),
+      "discovery_cache": cache_path,
+      "discovered_at": discovered_at,
+      "models": entries,
+    });
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+    return Ok(());
+  }
+
+  println!("{}", "Available Models (Detailed):".bold().blue());
+  println!("Config: {}", source.display_path());
+  match cache
+    .vendors
+    .values()
+    .map(|listing| listing.fetched_at)
+    .min()
+  {
+    Some(oldest) => println!(
+      "Discovery: {} vendor(s), oldest list from {} (--refresh to update)",
+      cache.vendors.len(),
+      oldest.format("%Y-%m-%d %H:%M UTC")
+    ),
+    None => println!("Discovery: no cached model lists (--refresh to fetch them)"),
+  }
+  println!();
+  if entries.is_empty() {
+    println!(
+      "No models found for provider: {}",
+      provider.unwrap_or_default()
+    );
+    return Ok(());
+  }
+  print!("{}", catalog::render_table(&entries));
+  let missing = entries
+    .iter()
+    .filter(|entry| entry.availability == Availability::NotDiscovered)
+    .count();
+  if missing > 0 {
+    println!();
+    println!(
+      "{} {missing} configured model(s) are missing from their vendor's model list: check for typos, retired models or private deployments.",
+      "Warning:".yellow()
+    );
+  }
   Ok(())
 }
 
@@ -461,42 +549,3 @@ fn print_simple_models(models: &[PrintableModel]) {
     println!("  • {}", model_name);
   }
 }
-
-fn print_detailed_models(models: &[PrintableModel]) {
-  println!("{}", "Available Models (Detailed):".bold().blue());
-  println!();
-
-  let mut current_provider = String::new();
-  for model in models {
-    if model.vendor != current_provider {
-      current_provider = model.vendor.clone();
-      println!("{}", format!("{}:", current_provider).bold().green());
-      println!();
-    }
-
-    let model_name = if model.model_id.starts_with(&model.vendor) {
-      model.model_id.clone()
-    } else {
-      format!("{}/{}", model.vendor, model.model_id)
-    };
-
-    println!("  {}", model_name.bold());
-    println!("    Name: {}", model.name);
-    println!("    Vendor: {}", model.vendor);
-    println!("    Model ID: {}", model.model_id);
-    if let Some(base_url) = &model.base_url {
-      println!("    Base URL: {}", base_url);
-    }
-
-    if let Some(temperature) = model.temperature {
-      println!("    Temperature: {}", temperature.to_string().yellow());
-    }
-
-    if let Some(max_tokens) = model.max_tokens {
-      println!("    Max Tokens: {}", max_tokens.to_string().yellow());
-    }
-
-    println!("    Supports Streaming: {}", model.supports_streaming);
-    println!();
-  }
-}
@@ -0,0 +1,185 @@
+//! `agentflow llm prompt`: ask a model one question and print the answer.
+//!
+//! `--file` contents are appended to the question under a `--- <path> ---`
+//! header, and `--image` turns the request into a multimodal message
+//! (local files are sent inline as `data:` URIs, URLs as they are). The
+//! reply streams to stdout when stdout is a terminal and is written in
+//! one piece otherwise, which keeps redirected output byte-for-byte the
+//! model's answer. `--usage` reports token counts on stderr.
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use super::chat::{default_model, stream_to};
+use agentflow_llm::tokenizer::count_tokens_for_model;
+use agentflow_llm::{AgentFlow, MultimodalMessage};
+
+/// Largest `--file` accepted; bigger documents belong in a RAG index or
+/// a workflow, not a single prompt.
+pub const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+pub struct PromptOptions {
+  pub prompt: String,
+  pub model: Option<String>,
+  pub system: Option<String>,
+  pub temperature: Option<f32>,
+  pub max_tokens: Option<u32>,
+  pub images: Vec<String>,
+  pub files: Vec<PathBuf>,
+  pub json: bool,
+  pub output: Option<PathBuf>,
+  pub usage: bool,
+}
+
+pub async fn execute(options: PromptOptions) -> Result<()> {
+  let model = match options.model.clone() {
+    Some(model) => model,
+    None => default_model().await,
+  };
+  let text = with_files(&options.prompt, &options.files)?;
+
+  let mut user = MultimodalMessage::user().add_text(text.as_str());
+  for image in &options.images {
+    user = user.add_image_url(image_url(image)?);
+  }
+  let mut messages = Vec::with_capacity(2);
+  if let Some(system) = &options.system {
+    messages.push(MultimodalMessage::text("system", system.as_str()));
+  }
+  messages.push(user.build());
+
+  AgentFlow::init()
+    .await
+    .context("Failed to initialise AgentFlow — is your API key configured?")?;
+
+  let mut request = AgentFlow::model(&model)
+    .multimodal_messages(messages)
+    .enable_logging(false);
+  if let Some(temperature) = options.temperature {
+    request = request.temperature(temperature);
+  }
+  if let Some(max_tokens) = options.max_tokens {
+    request = request.max_tokens(max_tokens);
+  }
+  if options.json {
+    request = request.json_mode();
+  }
+
+  let mut stdout = std::io::stdout();
+  let stream = options.output.is_none() && stdout.is_terminal();
+  let (reply, reported) = if stream {
+    let response = request
+      .execute_streaming()
+      .await
+      .with_context(|| format!("Request to model '{}' failed", model))?;
+    let (reply, usage) = stream_to(response, &mut stdout).await?;
+    writeln!(stdout)?;
+    (
+      reply,
+      usage.map(|usage| (usage.prompt_tokens, usage.completion_tokens)),
+    )
+  } else {
+    let response = request
+      .execute_full()
+      .await
+      .with_context(|| format!("Request to model '{}' failed", model))?;
+    (
+      response.content,
+      response
+        .usage
+        .map(|usage| (usage.prompt_tokens, usage.completion_tokens)),
+    )
+  };
+
+  if let Some(path) = &options.output {
+    std::fs::write(path, &reply)
+      .with_context(|| format!("Failed to write response to {}", path.display()))?;
+    eprintln!("💾 Response written to {}", path.display());
+  } else if !stream {
+    writeln!(stdout, "{}", reply)?;
+  }
+
+  if options.usage {
+    let (prompt_tokens, completion_tokens) = reported.unwrap_or_default();
+    let estimated = prompt_tokens.is_none() || completion_tokens.is_none();
+    let prompt_tokens = prompt_tokens.unwrap_or_else(|| {
+      let system = options.system.as_deref().unwrap_or_default();
+      count_tokens_for_model(&model, system) + count_tokens_for_model(&model, &text)
+    });
+    let completion_tokens =
+      completion_tokens.unwrap_or_else(|| count_tokens_for_model(&model, &reply));
+    eprintln!(
+      "📊 Usage ({}): {} prompt + {} completion = {} tokens{}",
+      model,
+      prompt_tokens,
+      completion_tokens,
+      prompt_tokens + completion_tokens,
+      if estimated { " (estimated)" } else { "" }
+    );
+  }
+  Ok(())
+}
+
+/// Appends each file to `prompt` under a `--- <path> ---` header,
+/// rejecting files over [`MAX_FILE_BYTES`] or not valid UTF-8.
+pub fn with_files(prompt: &str, files: &[PathBuf]) -> Result<String> {
+  let mut text = prompt.to_string();
+  for path in files {
+    let size = std::fs::metadata(path)
+      .with_context(|| format!("Failed to read --file {}", path.display()))?
+      .len();
+    if size > MAX_FILE_BYTES {
+      bail!(
+        "--file {} is {} bytes, over the {} byte limit",
+        path.display(),
+        size,
+        MAX_FILE_BYTES
+      );
+    }
+    let contents = std::fs::read_to_string(path)
+      .with_context(|| format!("--file {} is not a UTF-8 text file", path.display()))?;
+    text.push_str(&format!(
+      "\n\n--- {} ---\n{}",
+      path.display(),
+      contents.trim_end()
+    ));
+  }
+  Ok(text)
+}
+
+/// `http(s)://` and `data:` sources pass through; anything else is read
+/// as a local image and inlined as a base64 `data:` URI.
+pub fn image_url(source: &str) -> Result<String> {
+  if source.starts_with("http://") || source.starts_with("https://") || source.starts_with("data:")
+  {
+    return Ok(source.to_string());
+  }
+  let path = Path::new(source);
+  let mime_type = image_mime_type(path)?;
+  let bytes =
+    std::fs::read(path).with_context(|| format!("Failed to read --image {}", path.display()))?;
+  Ok(format!(
+    "data:{};base64,{}",
+    mime_type,
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+  ))
+}
+
+fn image_mime_type(path: &Path) -> Result<&'static str> {
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(str::to_ascii_lowercase);
+  Ok(match extension.as_deref() {
+    Some("png") => "image/png",
+    Some("jpg" | "jpeg") => "image/jpeg",
+    Some("gif") => "image/gif",
+    Some("webp") => "image/webp",
+    _ => bail!(
+      "--image {} must be a .png, .jpg, .jpeg, .gif or .webp file, or an http(s) URL",
+      path.display()
+    ),
+  })
+}
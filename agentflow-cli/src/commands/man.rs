@@ -0,0 +1,42 @@
+//! `agentflow man`: render man pages from the CLI definition.
+//!
+//! Without `--out-dir` the top-level `agentflow(1)` page goes to stdout
+//! (`agentflow man | man -l -`). With it, one page per command is
+//! written, named like `agentflow-workflow-run.1`.
+
+use anyhow::{Context, Result};
+use clap::Command;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn execute(cmd: Command, out_dir: Option<PathBuf>) -> Result<()> {
+  match out_dir {
+    Some(dir) => {
+      let pages = write_man_pages(cmd, &dir)?;
+      println!("📖 Wrote {} man page(s) to {}", pages.len(), dir.display());
+    }
+    None => {
+      let mut stdout = std::io::stdout();
+      clap_mangen::Man::new(cmd)
+        .render(&mut stdout)
+        .context("Failed to render the man page")?;
+      stdout.flush()?;
+    }
+  }
+  Ok(())
+}
+
+/// Writes `<name>.1` for `cmd` and every visible subcommand below it,
+/// returning the written pages sorted by name.
+pub fn write_man_pages(cmd: Command, out_dir: &Path) -> Result<Vec<PathBuf>> {
+  std::fs::create_dir_all(out_dir)
+    .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+  clap_mangen::generate_to(cmd, out_dir)
+    .with_context(|| format!("Failed to write man pages to {}", out_dir.display()))?;
+  let mut pages: Vec<PathBuf> = std::fs::read_dir(out_dir)?
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().is_some_and(|ext| ext == "1"))
+    .collect();
+  pages.sort();
+  Ok(pages)
+}
@@ -1,26 +1,22 @@
-use agentflow_mcp::client::ClientBuilder;
+use super::connection::{McpConnection, print_envelope};
+use agentflow_mcp::client::{CallToolResult, Content, MCPClient};
 use anyhow::{Context, Result};
+use base64::Engine;
 use colored::*;
 use serde_json::Value;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
 
-/// Execute the call-tool command to invoke a tool on an MCP server
-#[allow(clippy::too_many_arguments)]
+/// Execute the call command to invoke a tool on an MCP server. Image
+/// contents are decoded into `save_dir` (the current directory by
+/// default) and their paths printed in place of the base64 payload.
 pub async fn execute(
-  server_command: Vec<String>,
+  connection: McpConnection,
   tool_name: String,
   tool_params: Option<String>,
-  timeout_ms: Option<u64>,
-  max_retries: Option<u32>,
   output_file: Option<String>,
+  save_dir: Option<PathBuf>,
   format: String,
 ) -> Result<()> {
-  if server_command.is_empty() {
-    anyhow::bail!(
-      "Server command cannot be empty. Example: npx -y @modelcontextprotocol/server-filesystem /tmp"
-    );
-  }
-
   let is_json_envelope = format == "json-envelope";
 
   // Parse tool parameters from JSON string
@@ -30,49 +26,7 @@ pub async fn execute(
     serde_json::json!({})
   };
 
-  if !is_json_envelope {
-    println!(
-      "{}",
-      format!("🔌 Connecting to MCP server: {:?}", server_command)
-        .bold()
-        .blue()
-    );
-  }
-
-  // Build MCP client with configuration
-  let mut client_builder = ClientBuilder::new().with_stdio(server_command.clone());
-
-  if let Some(timeout) = timeout_ms {
-    client_builder = client_builder.with_timeout(Duration::from_millis(timeout));
-  }
-
-  if let Some(retries) = max_retries {
-    client_builder = client_builder.with_max_retries(retries);
-  }
-
-  let mut client = client_builder
-    .build()
-    .await
-    .context("Failed to build MCP client")?;
-
-  // Connect and initialize
-  client
-    .connect()
-    .await
-    .context("Failed to connect to MCP server")?;
-
-  if !is_json_envelope {
-    println!("{}", "✅ Connected to MCP server".green());
-  }
-
-  let tools = client
-    .list_tools()
-    .await
-    .context("Failed to list MCP tools before call")?;
-  let tool = tools
-    .iter()
-    .find(|tool| tool.name == tool_name)
-    .with_context(|| format!("MCP tool '{}' was not found on this server", tool_name))?;
+  let mut client = connection.connect(is_json_envelope).await?;
 
   if !is_json_envelope {
     println!();
@@ -84,32 +38,30 @@ pub async fn execute(
     );
   }
 
-  let result = client
-    .call_tool_validated(tool, params.clone())
-    .await
-    .context(format!("Failed to call tool '{}'", tool_name))?;
+  let result = call(&mut client, &tool_name, params.clone()).await;
 
   // Disconnect gracefully
   client.disconnect().await.ok();
+  let result = result?;
 
+  let save_dir = save_dir.unwrap_or_else(|| PathBuf::from("."));
+  let saved_images = save_images(&result, &tool_name, &save_dir)?;
   let result_json = serde_json::to_value(&result).context("Failed to serialize tool result")?;
-  let pretty_result =
-    serde_json::to_string_pretty(&result_json).context("Failed to format result as JSON")?;
 
   if is_json_envelope {
-    // P3.3 migration: wrap the tool-call result in the canonical
-    // envelope. The `result` payload carries the input params + the
-    // tool's response so consumers can correlate the call with its
-    // output without a second round trip.
-    let payload = serde_json::json!({
-      "server_command": server_command,
-      "tool": tool_name,
-      "params": params,
-      "result": result_json,
-    });
-    let envelope = crate::json_envelope::CliJsonEnvelope::ok("mcp call-tool", &payload);
-    let envelope_str = serde_json::to_string_pretty(&envelope)?;
-    println!("{}", envelope_str);
+    // The payload carries the input params alongside the tool's
+    // response so consumers can correlate the call with its output
+    // without a second round trip.
+    let envelope_str = print_envelope(
+      "mcp call-tool",
+      &connection,
+      serde_json::json!({
+        "tool": tool_name,
+        "params": params,
+        "result": result_json,
+        "saved_images": saved_images,
+      }),
+    )?;
     if let Some(output_path) = output_file {
       // Envelope mode writes the envelope (not the bare result) to
       // disk so the file is self-describing.
@@ -119,14 +71,48 @@ pub async fn execute(
     return Ok(());
   }
 
-  println!("{}", "✅ Tool call completed".green());
+  if result.is_error() {
+    println!("{}", "❌ Tool reported an error".red().bold());
+  } else {
+    println!("{}", "✅ Tool call completed".green());
+  }
   println!();
   println!("{}", "Result:".bold().yellow());
   println!();
-  println!("{}", pretty_result);
+  let mut images = saved_images.iter();
+  for content in &result.content {
+    match content {
+      Content::Text { text } => println!("{}", text),
+      Content::Image { data, mime_type } => {
+        match is_base64(data).then(|| images.next()).flatten() {
+          Some(path) => println!(
+            "{}",
+            format!("🖼️  {} saved to: {}", mime_type, path.display()).green()
+          ),
+          None => println!(
+            "{}",
+            format!("🖼️  {} (not base64; not saved)", mime_type).dimmed()
+          ),
+        }
+      }
+      Content::Resource {
+        uri,
+        mime_type,
+        text,
+      } => {
+        let mime = mime_type.as_deref().unwrap_or("unknown type");
+        println!("{}", format!("📄 {} ({})", uri, mime).cyan());
+        if let Some(text) = text {
+          println!("{}", text);
+        }
+      }
+    }
+  }
 
   // Save to file if requested
   if let Some(output_path) = output_file {
+    let pretty_result =
+      serde_json::to_string_pretty(&result_json).context("Failed to format result as JSON")?;
     std::fs::write(&output_path, pretty_result)
       .context(format!("Failed to write result to {}", output_path))?;
 
@@ -139,5 +125,83 @@ pub async fn execute(
     );
   }
 
+  if result.is_error() {
+    anyhow::bail!("MCP tool '{}' reported an error", tool_name);
+  }
   Ok(())
 }
+
+/// Looks `tool_name` up on the server and calls it with `params`,
+/// validated against the tool's input schema.
+pub async fn call(
+  client: &mut MCPClient,
+  tool_name: &str,
+  params: Value,
+) -> Result<CallToolResult> {
+  let tools = client
+    .list_tools()
+    .await
+    .context("Failed to list MCP tools before call")?;
+  let tool = tools
+    .iter()
+    .find(|tool| tool.name == tool_name)
+    .with_context(|| format!("MCP tool '{}' was not found on this server", tool_name))?;
+  client
+    .call_tool_validated(tool, params)
+    .await
+    .context(format!("Failed to call tool '{}'", tool_name))
+}
+
+/// Writes each base64 image in `result` to `dir` as
+/// `<tool>-<n>.<ext>` (`n` counts images from 1) and returns the paths
+/// in content order. Images whose data is not base64 (servers may send
+/// a URL) are skipped.
+pub fn save_images(result: &CallToolResult, tool_name: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+  let stem: String = tool_name
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect();
+  let mut saved = Vec::new();
+  let mut index = 0;
+  for content in &result.content {
+    let Content::Image { data, mime_type } = content else {
+      continue;
+    };
+    index += 1;
+    let Some(bytes) = decode(data) else {
+      continue;
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}-{}.{}", stem, index, image_extension(mime_type)));
+    std::fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    saved.push(path);
+  }
+  Ok(saved)
+}
+
+fn decode(data: &str) -> Option<Vec<u8>> {
+  base64::engine::general_purpose::STANDARD
+    .decode(data.trim())
+    .ok()
+}
+
+fn is_base64(data: &str) -> bool {
+  decode(data).is_some()
+}
+
+fn image_extension(mime_type: &str) -> &str {
+  match mime_type {
+    "image/jpeg" | "image/jpg" => "jpg",
+    "image/svg+xml" => "svg",
+    other => other
+      .strip_prefix("image/")
+      .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+      .unwrap_or("bin"),
+  }
+}
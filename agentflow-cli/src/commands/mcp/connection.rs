@@ -0,0 +1,204 @@
+use agentflow_mcp::client::{ClientBuilder, MCPClient};
+use anyhow::{Context, Result, bail};
+use colored::*;
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// Where an `agentflow mcp` command finds its server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum McpServer {
+  /// Spawn the server and speak JSON-RPC over its stdin/stdout.
+  Stdio(Vec<String>),
+  /// POST JSON-RPC to an HTTP endpoint.
+  Http(String),
+}
+
+/// Connection settings shared by every `agentflow mcp` subcommand.
+#[derive(Debug, Clone)]
+pub struct McpConnection {
+  pub server: McpServer,
+  pub timeout: Duration,
+  pub max_retries: u32,
+}
+
+impl McpConnection {
+  /// Resolves the connection flags. `--stdio` takes one shell-quoted
+  /// string (`--stdio "npx -y @modelcontextprotocol/server-everything"`);
+  /// the trailing positional command is the older spelling of the same
+  /// thing. Exactly one of the three forms must be given.
+  pub fn from_flags(
+    server_command: Vec<String>,
+    stdio: Option<&str>,
+    http: Option<String>,
+    timeout: Duration,
+    max_retries: u32,
+  ) -> Result<Self> {
+    let given = usize::from(!server_command.is_empty())
+      + usize::from(stdio.is_some())
+      + usize::from(http.is_some());
+    if given > 1 {
+      bail!("Pass only one of --stdio, --http or a positional server command");
+    }
+    let server = if let Some(url) = http {
+      McpServer::Http(url)
+    } else if let Some(line) = stdio {
+      McpServer::Stdio(split_command(line)?)
+    } else if !server_command.is_empty() {
+      McpServer::Stdio(server_command)
+    } else {
+      bail!(
+        "No MCP server given. Example: --stdio \"npx -y @modelcontextprotocol/server-everything\" or --http http://localhost:3000/mcp"
+      );
+    };
+    Ok(Self {
+      server,
+      timeout,
+      max_retries,
+    })
+  }
+
+  /// A client builder for this connection, before the transport is
+  /// started.
+  pub fn builder(&self) -> ClientBuilder {
+    let builder = match &self.server {
+      McpServer::Stdio(command) => ClientBuilder::new().with_stdio(command.clone()),
+      McpServer::Http(url) => ClientBuilder::new().with_http(url.clone()),
+    };
+    builder
+      .with_timeout(self.timeout)
+      .with_max_retries(self.max_retries)
+  }
+
+  /// Builds, connects and initializes a client, printing progress in
+  /// text mode.
+  pub async fn connect(&self, quiet: bool) -> Result<MCPClient> {
+    if !quiet {
+      println!(
+        "{}",
+        format!("🔌 Connecting to MCP server: {}", self.describe())
+          .bold()
+          .blue()
+      );
+    }
+    let mut client = self
+      .builder()
+      .build()
+      .await
+      .context("Failed to build MCP client")?;
+    client
+      .connect()
+      .await
+      .context("Failed to connect to MCP server")?;
+    if !quiet {
+      println!("{}", "✅ Connected to MCP server".green());
+    }
+    Ok(client)
+  }
+
+  pub fn describe(&self) -> String {
+    match &self.server {
+      McpServer::Stdio(command) => format!("{:?}", command),
+      McpServer::Http(url) => url.clone(),
+    }
+  }
+
+  /// The payload fields naming the server: `server_command` for stdio
+  /// (unchanged from earlier releases), `server_url` for HTTP.
+  pub fn payload(&self) -> Map<String, Value> {
+    let mut payload = Map::new();
+    match &self.server {
+      McpServer::Stdio(command) => {
+        payload.insert("server_command".to_string(), serde_json::json!(command));
+      }
+      McpServer::Http(url) => {
+        payload.insert("server_url".to_string(), Value::String(url.clone()));
+      }
+    }
+    payload
+  }
+}
+
+/// Splits a `--stdio` command line with POSIX shell quoting rules.
+pub fn split_command(line: &str) -> Result<Vec<String>> {
+  let words =
+    shlex::split(line).with_context(|| format!("Unbalanced quotes in --stdio: {line}"))?;
+  if words.is_empty() {
+    bail!("--stdio needs a command, e.g. \"npx -y @modelcontextprotocol/server-everything\"");
+  }
+  Ok(words)
+}
+
+/// Wraps `fields` in `connection`'s payload and prints the envelope.
+pub fn print_envelope(command: &str, connection: &McpConnection, fields: Value) -> Result<String> {
+  let mut payload = connection.payload();
+  if let Value::Object(fields) = fields {
+    payload.extend(fields);
+  }
+  let payload = Value::Object(payload);
+  let envelope = crate::json_envelope::CliJsonEnvelope::ok(command, &payload);
+  let rendered = serde_json::to_string_pretty(&envelope)?;
+  println!("{}", rendered);
+  Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stdio_lines_split_with_shell_quoting() {
+    assert_eq!(
+      split_command(r#"npx -y "@scope/server with space" --root '/tmp/a b'"#).unwrap(),
+      vec![
+        "npx",
+        "-y",
+        "@scope/server with space",
+        "--root",
+        "/tmp/a b"
+      ]
+    );
+    assert!(split_command("npx \"unterminated").is_err());
+    assert!(split_command("   ").is_err());
+  }
+
+  #[test]
+  fn exactly_one_server_form_is_required() {
+    let timeout = Duration::from_secs(5);
+    let http = McpConnection::from_flags(
+      vec![],
+      None,
+      Some("http://localhost:3000/mcp".into()),
+      timeout,
+      1,
+    )
+    .unwrap();
+    assert_eq!(
+      http.server,
+      McpServer::Http("http://localhost:3000/mcp".into())
+    );
+    assert_eq!(
+      http.payload().get("server_url"),
+      Some(&Value::String("http://localhost:3000/mcp".into()))
+    );
+
+    let legacy =
+      McpConnection::from_flags(vec!["node".into(), "s.js".into()], None, None, timeout, 1)
+        .unwrap();
+    assert_eq!(
+      legacy.server,
+      McpServer::Stdio(vec!["node".into(), "s.js".into()])
+    );
+
+    assert!(McpConnection::from_flags(vec![], None, None, timeout, 1).is_err());
+    assert!(
+      McpConnection::from_flags(
+        vec![],
+        Some("node s.js"),
+        Some("http://x".into()),
+        timeout,
+        1
+      )
+      .is_err()
+    );
+  }
+}
@@ -0,0 +1,77 @@
+use super::connection::{McpConnection, print_envelope};
+use anyhow::{Context, Result};
+use colored::*;
+
+/// Execute the list-prompts command to discover prompt templates on an MCP server
+pub async fn execute(connection: McpConnection, format: String) -> Result<()> {
+  let is_json_envelope = format == "json-envelope";
+  let mut client = connection.connect(is_json_envelope).await?;
+
+  let prompts = client
+    .list_prompts()
+    .await
+    .context("Failed to list prompts from MCP server");
+
+  // Disconnect gracefully
+  client.disconnect().await.ok();
+  let prompts = prompts?;
+
+  if is_json_envelope {
+    print_envelope(
+      "mcp list-prompts",
+      &connection,
+      serde_json::json!({ "prompts": &prompts, "total": prompts.len() }),
+    )?;
+    return Ok(());
+  }
+
+  if prompts.is_empty() {
+    println!("{}", "⚠️  No prompts found".yellow());
+    return Ok(());
+  }
+
+  println!();
+  println!(
+    "{}",
+    format!("Available Prompts ({}):", prompts.len())
+      .bold()
+      .green()
+  );
+  println!();
+
+  for prompt in &prompts {
+    println!("  {}", format!("• {}", prompt.name).bold());
+
+    if let Some(description) = &prompt.description {
+      println!("    {}", description.dimmed());
+    }
+
+    if !prompt.arguments.is_empty() {
+      println!("    {}:", "Arguments".italic());
+      for argument in &prompt.arguments {
+        let required = if argument.is_required() {
+          "required".yellow()
+        } else {
+          "optional".dimmed()
+        };
+        println!(
+          "      - {} ({}): {}",
+          argument.name.cyan(),
+          required,
+          argument.description.as_deref().unwrap_or("").dimmed()
+        );
+      }
+    }
+
+    println!();
+  }
+
+  println!(
+    "{}",
+    format!("Total: {} prompts available", prompts.len())
+      .bold()
+      .green()
+  );
+
+  Ok(())
+}
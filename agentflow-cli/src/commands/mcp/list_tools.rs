@@ -1,59 +1,12 @@
-use agentflow_mcp::client::ClientBuilder;
+use super::connection::{McpConnection, print_envelope};
 use anyhow::{Context, Result};
 use colored::*;
-use std::time::Duration;
 
 /// Execute the list-tools command to discover available tools from an MCP server
-pub async fn execute(
-  server_command: Vec<String>,
-  timeout_ms: Option<u64>,
-  max_retries: Option<u32>,
-  format: String,
-) -> Result<()> {
-  if server_command.is_empty() {
-    anyhow::bail!(
-      "Server command cannot be empty. Example: npx -y @modelcontextprotocol/server-filesystem /tmp"
-    );
-  }
-
+pub async fn execute(connection: McpConnection, format: String) -> Result<()> {
   let is_json_envelope = format == "json-envelope";
+  let mut client = connection.connect(is_json_envelope).await?;
 
-  if !is_json_envelope {
-    println!(
-      "{}",
-      format!("🔌 Connecting to MCP server: {:?}", server_command)
-        .bold()
-        .blue()
-    );
-  }
-
-  // Build MCP client with configuration
-  let mut client_builder = ClientBuilder::new().with_stdio(server_command.clone());
-
-  if let Some(timeout) = timeout_ms {
-    client_builder = client_builder.with_timeout(Duration::from_millis(timeout));
-  }
-
-  if let Some(retries) = max_retries {
-    client_builder = client_builder.with_max_retries(retries);
-  }
-
-  let mut client = client_builder
-    .build()
-    .await
-    .context("Failed to build MCP client")?;
-
-  // Connect and initialize
-  client
-    .connect()
-    .await
-    .context("Failed to connect to MCP server")?;
-
-  if !is_json_envelope {
-    println!("{}", "✅ Connected to MCP server".green());
-  }
-
-  // List available tools
   let tools = client
     .list_tools()
     .await
@@ -63,17 +16,11 @@ pub async fn execute(
   client.disconnect().await.ok();
 
   if is_json_envelope {
-    // P3.3 migration: emit the canonical `CliJsonEnvelope`. The
-    // `result` payload preserves the full upstream `Tool` struct
-    // (name + description + input_schema) so downstream tooling
-    // doesn't lose schema info that the text path also shows.
-    let payload = serde_json::json!({
-      "server_command": server_command,
-      "tools": &tools,
-      "total": tools.len(),
-    });
-    let envelope = crate::json_envelope::CliJsonEnvelope::ok("mcp list-tools", &payload);
-    println!("{}", serde_json::to_string_pretty(&envelope)?);
+    print_envelope(
+      "mcp list-tools",
+      &connection,
+      serde_json::json!({ "tools": &tools, "total": tools.len() }),
+    )?;
     return Ok(());
   }
 
@@ -1,4 +1,7 @@
 pub mod call_tool;
 pub mod config;
+pub mod connection;
+pub mod list_prompts;
 pub mod list_resources;
 pub mod list_tools;
+pub mod read_resource;
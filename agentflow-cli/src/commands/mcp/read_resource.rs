@@ -0,0 +1,55 @@
+use super::connection::{McpConnection, print_envelope};
+use anyhow::{Context, Result};
+use colored::*;
+
+/// Execute the read-resource command to fetch one resource from an MCP server
+pub async fn execute(connection: McpConnection, uri: String, format: String) -> Result<()> {
+  let is_json_envelope = format == "json-envelope";
+  let mut client = connection.connect(is_json_envelope).await?;
+
+  let result = client
+    .read_resource(uri.clone())
+    .await
+    .with_context(|| format!("Failed to read resource '{}'", uri));
+
+  // Disconnect gracefully
+  client.disconnect().await.ok();
+  let result = result?;
+
+  if is_json_envelope {
+    print_envelope(
+      "mcp read-resource",
+      &connection,
+      serde_json::json!({ "uri": uri, "contents": &result.contents }),
+    )?;
+    return Ok(());
+  }
+
+  if result.contents.is_empty() {
+    println!("{}", "⚠️  Resource has no contents".yellow());
+    return Ok(());
+  }
+
+  for content in &result.contents {
+    println!();
+    let mime = content.mime_type().unwrap_or("unknown type");
+    println!(
+      "{}",
+      format!("📄 {} ({})", content.uri(), mime).bold().cyan()
+    );
+    if let Some(text) = content.as_text() {
+      println!("{}", text);
+    } else if let Some(blob) = content.as_blob() {
+      println!(
+        "{}",
+        format!(
+          "<binary, {} base64 chars; use --json to get the data>",
+          blob.len()
+        )
+        .dimmed()
+      );
+    }
+  }
+
+  Ok(())
+}
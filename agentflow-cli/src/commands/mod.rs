@@ -2,12 +2,14 @@ pub mod agent;
 pub mod audio;
 pub mod backup;
 pub mod cleanup;
+pub mod completions;
 pub mod config;
 pub mod doctor;
 pub mod eval;
 pub mod harness;
 pub mod image;
 pub mod llm;
+pub mod man;
 pub mod marketplace;
 pub mod mcp;
 pub mod memory;
@@ -15,6 +17,7 @@ pub mod memory;
 pub mod plugin;
 pub mod rag;
 pub mod repl;
+pub mod runs;
 pub mod serve;
 pub mod skill;
 pub mod trace;
@@ -0,0 +1,74 @@
+//! `agentflow runs clean --older-than 7d` — delete run directories
+//! whose run finished (or, without a finish time, started) before the
+//! cutoff. Runs still marked `running` are never removed, and
+//! `--keep-failed` also spares failed runs for post-mortems.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use super::manifest::{self, RunStatus};
+use super::resolve_run_root;
+use crate::commands::memory::prune::parse_retention_duration;
+
+pub async fn execute(
+  run_dir: Option<String>,
+  older_than: String,
+  keep_failed: bool,
+  dry_run: bool,
+  json: bool,
+) -> Result<()> {
+  let root = resolve_run_root(run_dir)?;
+  let window = parse_retention_duration(&older_than)
+    .with_context(|| format!("Invalid --older-than value '{}'", older_than))?;
+  let cutoff = Utc::now()
+    - chrono::Duration::from_std(window)
+      .with_context(|| format!("--older-than '{}' is too large", older_than))?;
+
+  let mut removed = Vec::new();
+  let mut kept = 0usize;
+  for (run_dir, manifest) in manifest::load_all(&root)? {
+    let ended_at = manifest.finished_at.unwrap_or(manifest.started_at);
+    let keep = ended_at >= cutoff
+      || manifest.status == RunStatus::Running
+      || (keep_failed && manifest.status == RunStatus::Failed);
+    if keep {
+      kept += 1;
+      continue;
+    }
+    if !dry_run {
+      std::fs::remove_dir_all(&run_dir)
+        .with_context(|| format!("Failed to remove {}", run_dir.display()))?;
+    }
+    removed.push(manifest);
+  }
+
+  if json {
+    let payload = serde_json::json!({
+      "run_dir": root,
+      "dry_run": dry_run,
+      "removed": removed.iter().map(|run| &run.run_id).collect::<Vec<_>>(),
+      "kept": kept,
+    });
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+    return Ok(());
+  }
+
+  let verb = if dry_run { "Would remove" } else { "Removed" };
+  for run in &removed {
+    println!(
+      "  - {}  {}  {}  {}",
+      run.run_id,
+      run.workflow,
+      run.status,
+      run.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+  }
+  println!(
+    "🧹 {} {} run(s) older than {}; kept {}.",
+    verb,
+    removed.len(),
+    older_than,
+    kept
+  );
+  Ok(())
+}
@@ -0,0 +1,66 @@
+//! `agentflow runs cost <run-id>` — the run's token usage and cost,
+//! rebuilt from its persisted `<node>_outputs.json` files and priced
+//! with the current pricing table, so an updated `pricing.yml` applies
+//! to past runs too.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use super::manifest::RunManifest;
+use super::{find_run, resolve_run_root};
+use crate::commands::workflow::cost::{self, CostReport};
+use crate::commands::workflow::report::decode_flow_values;
+use crate::config::v2::FlowDefinitionV2;
+
+pub async fn execute(run_id: String, run_dir: Option<String>, json: bool) -> Result<()> {
+  let root = resolve_run_root(run_dir)?;
+  let (run_dir, manifest) = find_run(&root, &run_id)?;
+  match (json, run_cost(&run_dir, &manifest)?) {
+    (true, report) => println!("{}", serde_json::to_string_pretty(&report)?),
+    (false, Some(report)) => {
+      println!("Run: {} ({})", manifest.run_id, manifest.workflow);
+      report.print_table();
+    }
+    (false, None) => println!("Run {} made no model calls.", manifest.run_id),
+  }
+  Ok(())
+}
+
+/// The run's usage and cost under the current pricing table; `None`
+/// when it made no model calls.
+pub(super) fn run_cost(run_dir: &Path, manifest: &RunManifest) -> Result<Option<CostReport>> {
+  // Nodes whose outputs don't name their model fall back to the
+  // workflow's `model` parameters, when the file is still there.
+  let default_models = std::fs::read_to_string(&manifest.workflow_file)
+    .ok()
+    .and_then(|yaml| serde_yaml::from_str::<FlowDefinitionV2>(&yaml).ok())
+    .map(|flow_def| cost::default_models(&flow_def, None))
+    .unwrap_or_default();
+
+  let mut nodes = Vec::new();
+  for node in &manifest.nodes {
+    if let Some(outputs) = read_outputs(run_dir, &node.id)? {
+      nodes.push((node.id.as_str(), outputs));
+    }
+  }
+  Ok(CostReport::from_outputs(
+    nodes,
+    &default_models,
+    &cost::load_pricing_or_warn(),
+  ))
+}
+
+/// A node's persisted successful outputs; `None` for a failed or
+/// skipped node, or one that wrote no file.
+pub(super) fn read_outputs(run_dir: &Path, node_id: &str) -> Result<Option<Map<String, Value>>> {
+  let path = run_dir.join(format!("{}_outputs.json", node_id));
+  if !path.is_file() {
+    return Ok(None);
+  }
+  let text =
+    std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+  let result: Value =
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+  Ok(result.get("Ok").map(decode_flow_values))
+}
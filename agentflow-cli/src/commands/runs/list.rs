@@ -0,0 +1,63 @@
+//! `agentflow runs list` — one row per run, newest first.
+
+use anyhow::Result;
+
+use super::manifest::{self, RunStatus};
+use super::resolve_run_root;
+use crate::commands::workflow::report::format_duration;
+
+pub async fn execute(
+  run_dir: Option<String>,
+  limit: Option<usize>,
+  failed_only: bool,
+  json: bool,
+) -> Result<()> {
+  let root = resolve_run_root(run_dir)?;
+  let runs: Vec<_> = manifest::load_all(&root)?
+    .into_iter()
+    .map(|(_, manifest)| manifest)
+    .filter(|manifest| !failed_only || manifest.status == RunStatus::Failed)
+    .take(limit.unwrap_or(usize::MAX))
+    .collect();
+
+  if json {
+    let payload = serde_json::json!({
+      "run_dir": root,
+      "runs": runs,
+    });
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+    return Ok(());
+  }
+
+  if runs.is_empty() {
+    println!("(no runs found under {})", root.display());
+    return Ok(());
+  }
+  let id_width = runs
+    .iter()
+    .map(|run| run.run_id.chars().count())
+    .max()
+    .unwrap_or(0)
+    .max("RUN ID".len());
+  let name_width = runs
+    .iter()
+    .map(|run| run.workflow.chars().count())
+    .max()
+    .unwrap_or(0)
+    .max("WORKFLOW".len());
+  println!(
+    "{:<id_width$}  {:<name_width$}  {:<19}  {:<9}  DURATION",
+    "RUN ID", "WORKFLOW", "STARTED (UTC)", "STATUS"
+  );
+  for run in &runs {
+    println!(
+      "{:<id_width$}  {:<name_width$}  {:<19}  {:<9}  {}",
+      run.run_id,
+      run.workflow,
+      run.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+      run.status.as_str(),
+      format_duration(run.duration_ms)
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,189 @@
+//! `manifest.json`: the summary `workflow run` keeps next to the
+//! per-node `<node>_outputs.json` files in every run directory.
+//!
+//! The manifest is written as `running` before the first node starts
+//! and rewritten when the run completes, fails or is cancelled, so a
+//! run that crashed part-way still shows up in `runs list`. Node
+//! outputs stay in their own files; the manifest only records status,
+//! timing, (redacted) errors and token usage / cost.
+
+use crate::commands::workflow::cost::CostReport;
+use crate::commands::workflow::report::{NodeReport, RunReport};
+use crate::commands::workflow::vars::WorkflowVars;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+  Running,
+  Completed,
+  Failed,
+  Cancelled,
+}
+
+impl RunStatus {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Running => "running",
+      Self::Completed => "completed",
+      Self::Failed => "failed",
+      Self::Cancelled => "cancelled",
+    }
+  }
+}
+
+impl fmt::Display for RunStatus {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+  pub run_id: String,
+  pub workflow: String,
+  /// Absolute path of the workflow YAML, so `runs resume` can rebuild
+  /// the flow.
+  pub workflow_file: PathBuf,
+  /// The `--profile` the run was started with; `runs resume` loads it
+  /// again to fill the workflow's placeholders.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub profile: Option<String>,
+  /// The `--set` values the run was started with, reapplied by
+  /// `runs resume`.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub set: BTreeMap<String, String>,
+  pub started_at: DateTime<Utc>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub finished_at: Option<DateTime<Utc>>,
+  pub status: RunStatus,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub duration_ms: Option<u64>,
+  /// Why the run ended early (timeout, cancellation, executor error).
+  /// Per-node failures are on the nodes.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  /// Nodes in execution order, without their outputs.
+  #[serde(default)]
+  pub nodes: Vec<NodeReport>,
+  /// Token usage and cost, when any node called a model.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost: Option<CostReport>,
+}
+
+impl RunManifest {
+  /// A manifest for a run that is about to start.
+  pub fn started(run_id: &str, workflow: &str, workflow_file: &Path) -> Self {
+    Self {
+      run_id: run_id.to_string(),
+      workflow: workflow.to_string(),
+      workflow_file: fs::canonicalize(workflow_file)
+        .unwrap_or_else(|_| workflow_file.to_path_buf()),
+      profile: None,
+      set: BTreeMap::new(),
+      started_at: Utc::now(),
+      finished_at: None,
+      status: RunStatus::Running,
+      duration_ms: None,
+      error: None,
+      nodes: Vec::new(),
+      cost: None,
+    }
+  }
+
+  /// Records the `--profile` and `--set` values the workflow was
+  /// substituted with.
+  pub fn with_vars(mut self, vars: &WorkflowVars) -> Self {
+    self.profile = vars.profile_name().map(str::to_string);
+    self.set = vars.set_values().clone();
+    self
+  }
+
+  /// Records a finished run: `failed` when any node failed.
+  pub fn finish(&mut self, report: &RunReport) {
+    self.finished_at = Some(Utc::now());
+    self.status = if report.failed_nodes().is_empty() {
+      RunStatus::Completed
+    } else {
+      RunStatus::Failed
+    };
+    self.duration_ms = Some(report.duration_ms);
+    self.error = None;
+    self.nodes = report
+      .nodes
+      .iter()
+      .map(|node| NodeReport {
+        outputs: Default::default(),
+        ..node.clone()
+      })
+      .collect();
+    self.cost = report.cost.clone();
+  }
+
+  /// Records a run that ended without a report.
+  pub fn abort(&mut self, status: RunStatus, error: Option<String>) {
+    let finished_at = Utc::now();
+    self.duration_ms = (finished_at - self.started_at)
+      .to_std()
+      .ok()
+      .map(|d| d.as_millis() as u64);
+    self.finished_at = Some(finished_at);
+    self.status = status;
+    self.error = error;
+  }
+
+  pub fn path(run_dir: &Path) -> PathBuf {
+    run_dir.join(MANIFEST_FILE)
+  }
+
+  pub fn load(run_dir: &Path) -> Result<Self> {
+    let path = Self::path(run_dir);
+    let text =
+      fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+  }
+
+  pub fn save(&self, run_dir: &Path) -> Result<()> {
+    fs::create_dir_all(run_dir)
+      .with_context(|| format!("Failed to create run directory {}", run_dir.display()))?;
+    let path = Self::path(run_dir);
+    fs::write(&path, serde_json::to_string_pretty(self)?)
+      .with_context(|| format!("Failed to write {}", path.display()))
+  }
+}
+
+/// Every run under `root` that has a manifest, newest first. Other
+/// directories (harness sessions, runs from older releases) are
+/// skipped; a manifest that does not parse is reported on stderr.
+pub fn load_all(root: &Path) -> Result<Vec<(PathBuf, RunManifest)>> {
+  let entries = match fs::read_dir(root) {
+    Ok(entries) => entries,
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(err) => return Err(err).with_context(|| format!("Failed to read {}", root.display())),
+  };
+  let mut runs = Vec::new();
+  for entry in entries.flatten() {
+    let run_dir = entry.path();
+    if !RunManifest::path(&run_dir).is_file() {
+      continue;
+    }
+    match RunManifest::load(&run_dir) {
+      Ok(manifest) => runs.push((run_dir, manifest)),
+      Err(err) => eprintln!("⚠️  Skipping {}: {:#}", run_dir.display(), err),
+    }
+  }
+  runs.sort_by(|(_, a), (_, b)| {
+    b.started_at
+      .cmp(&a.started_at)
+      .then_with(|| a.run_id.cmp(&b.run_id))
+  });
+  Ok(runs)
+}
@@ -0,0 +1,69 @@
+//! `agentflow runs …` — inspect and tidy the per-run directories
+//! `workflow run` leaves under `~/.agentflow/runs` (or `--run-dir` /
+//! `AGENTFLOW_RUN_DIR`).
+//!
+//! - `list` — table of runs read from each directory's manifest.
+//! - `show` — per-node status and timing for one run, or one node's
+//!   persisted outputs with `--node`.
+//! - `resume` — continue a run from its latest checkpoint.
+//! - `cost` — per-node token usage and cost, recomputed from the run's
+//!   persisted outputs.
+//! - `report` — the run as a self-contained HTML file: graph, per-node
+//!   cards and the cost table.
+//! - `clean` — delete runs older than a retention window.
+
+pub mod clean;
+pub mod cost;
+pub mod list;
+pub mod manifest;
+pub mod report;
+pub mod resume;
+pub mod show;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use manifest::{MANIFEST_FILE, RunManifest};
+
+/// Resolve the workflow run-artifact root: `--run-dir`, else
+/// `AGENTFLOW_RUN_DIR`, else `~/.agentflow/runs` — the same root
+/// `workflow run` writes to.
+pub(crate) fn resolve_run_root(run_dir: Option<String>) -> Result<PathBuf> {
+  if let Some(dir) = run_dir {
+    return Ok(PathBuf::from(dir));
+  }
+  if let Ok(dir) = std::env::var("AGENTFLOW_RUN_DIR")
+    && !dir.trim().is_empty()
+  {
+    return Ok(PathBuf::from(dir));
+  }
+  Ok(
+    dirs::home_dir()
+      .context("Could not determine home directory for default run directory")?
+      .join(".agentflow")
+      .join("runs"),
+  )
+}
+
+/// Finds a run by id, or by a prefix that matches exactly one run.
+pub(crate) fn find_run(root: &Path, run_id: &str) -> Result<(PathBuf, RunManifest)> {
+  let exact = root.join(run_id);
+  if exact.join(MANIFEST_FILE).is_file() {
+    let manifest = RunManifest::load(&exact)?;
+    return Ok((exact, manifest));
+  }
+  let mut matches: Vec<_> = manifest::load_all(root)?
+    .into_iter()
+    .filter(|(_, manifest)| manifest.run_id.starts_with(run_id))
+    .collect();
+  match matches.len() {
+    0 => bail!("no run '{}' under {}", run_id, root.display()),
+    1 => Ok(matches.remove(0)),
+    n => bail!(
+      "run id prefix '{}' matches {} runs; use more characters",
+      run_id,
+      n
+    ),
+  }
+}
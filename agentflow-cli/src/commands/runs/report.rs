@@ -0,0 +1,527 @@
+//! `agentflow runs report <run-id>` — one run as a single HTML file to
+//! share or attach to a ticket.
+//!
+//! The report holds the workflow graph as an inline Mermaid block colored
+//! by node status, a collapsible card per node with its status, duration,
+//! configured inputs and persisted outputs, and the usage and cost table.
+//! Output values longer than [`INLINE_CHARS`] are cut short in the card;
+//! the full value goes to a sidecar file under `<report>_files/` that the
+//! card links to. Images — `data:image/…` URLs and image files an output
+//! names — are inlined as base64 thumbnails, so the page needs nothing
+//! but the Mermaid script from the CDN. `--include-artifacts <dir>`
+//! copies every existing file the outputs name into `<dir>/<node>/` and
+//! links the copies from the cards.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use super::manifest::RunManifest;
+use super::{cost, find_run, resolve_run_root};
+use crate::commands::workflow::cost::{CostReport, format_usd};
+use crate::commands::workflow::debug::html_escape;
+use crate::commands::workflow::report::{NodeReport, NodeStatus, format_duration};
+use crate::config::v2::FlowDefinitionV2;
+
+/// Characters of an output value shown in its card before the rest is
+/// moved to a sidecar file.
+pub const INLINE_CHARS: usize = 2000;
+
+/// Image files larger than this are linked, not inlined.
+const MAX_INLINE_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+const IMAGE_EXTENSIONS: [(&str, &str); 6] = [
+  ("png", "image/png"),
+  ("jpg", "image/jpeg"),
+  ("jpeg", "image/jpeg"),
+  ("gif", "image/gif"),
+  ("webp", "image/webp"),
+  ("svg", "image/svg+xml"),
+];
+
+const STYLE: &str = r#"body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 70rem; padding: 0 1rem; color: #1f2328; }
+table { border-collapse: collapse; margin: 0.5rem 0 1rem; }
+th, td { border: 1px solid #d0d7de; padding: 0.25rem 0.6rem; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+details.node { border: 1px solid #d0d7de; border-left-width: 6px; border-radius: 6px; margin: 0.6rem 0; padding: 0.4rem 0.8rem; }
+details.node > summary { cursor: pointer; font-weight: 600; }
+details.completed { border-left-color: #2da44e; }
+details.failed { border-left-color: #cf222e; }
+details.skipped, details.not_run { border-left-color: #8c959f; }
+pre { background: #f6f8fa; padding: 0.6rem; overflow-x: auto; white-space: pre-wrap; word-break: break-word; }
+pre.error { background: #ffebe9; }
+img.thumb { max-width: 240px; max-height: 240px; border: 1px solid #d0d7de; margin: 0.25rem; }
+.muted { color: #59636e; }"#;
+
+pub async fn execute(
+  run_id: String,
+  run_dir: Option<String>,
+  output: PathBuf,
+  include_artifacts: Option<PathBuf>,
+) -> Result<()> {
+  let root = resolve_run_root(run_dir)?;
+  let (run_dir, manifest) = find_run(&root, &run_id)?;
+  let flow_def = std::fs::read_to_string(&manifest.workflow_file)
+    .ok()
+    .and_then(|yaml| serde_yaml::from_str::<FlowDefinitionV2>(&yaml).ok());
+  let cost = cost::run_cost(&run_dir, &manifest)?;
+
+  let mut files = ReportFiles::new(&output, include_artifacts)?;
+  let html = render(
+    &run_dir,
+    &manifest,
+    flow_def.as_ref(),
+    cost.as_ref(),
+    &mut files,
+  )?;
+  std::fs::write(&output, html)
+    .with_context(|| format!("Failed to write report to {}", output.display()))?;
+
+  println!(
+    "📄 Report for run {} written to {}",
+    manifest.run_id,
+    output.display()
+  );
+  if files.sidecars > 0 {
+    println!(
+      "   {} long value(s) saved under {}",
+      files.sidecars,
+      files.sidecar_dir.display()
+    );
+  }
+  if let Some(dir) = &files.artifact_dir {
+    println!(
+      "   {} artifact(s) copied to {}",
+      files.artifacts,
+      dir.display()
+    );
+  }
+  Ok(())
+}
+
+/// The files written next to the report, and how the report links them.
+struct ReportFiles {
+  /// The directory holding the report; links are relative to it when
+  /// they can be.
+  report_dir: PathBuf,
+  sidecar_dir: PathBuf,
+  artifact_dir: Option<PathBuf>,
+  sidecars: usize,
+  artifacts: usize,
+}
+
+impl ReportFiles {
+  fn new(output: &Path, artifact_dir: Option<PathBuf>) -> Result<Self> {
+    let report_dir = output
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+      .unwrap_or(Path::new("."))
+      .to_path_buf();
+    std::fs::create_dir_all(&report_dir)
+      .with_context(|| format!("Failed to create {}", report_dir.display()))?;
+    let stem = output
+      .file_stem()
+      .map_or("report".into(), |stem| stem.to_string_lossy());
+    if let Some(dir) = &artifact_dir {
+      std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create artifact directory {}", dir.display()))?;
+    }
+    Ok(Self {
+      sidecar_dir: report_dir.join(format!("{stem}_files")),
+      report_dir,
+      artifact_dir,
+      sidecars: 0,
+      artifacts: 0,
+    })
+  }
+
+  /// Writes the full text of a truncated value and returns its link.
+  fn sidecar(&mut self, node_id: &str, key: &str, text: &str) -> Result<String> {
+    std::fs::create_dir_all(&self.sidecar_dir)
+      .with_context(|| format!("Failed to create {}", self.sidecar_dir.display()))?;
+    let path = self
+      .sidecar_dir
+      .join(format!("{}.{}.json", file_name(node_id), file_name(key)));
+    std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    self.sidecars += 1;
+    Ok(self.href(&path))
+  }
+
+  /// Copies `source` into the artifact directory and returns its link;
+  /// `None` without `--include-artifacts`.
+  fn artifact(&mut self, node_id: &str, source: &Path) -> Result<Option<String>> {
+    let Some(dir) = &self.artifact_dir else {
+      return Ok(None);
+    };
+    let dir = dir.join(file_name(node_id));
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let target = dir.join(source.file_name().unwrap_or(source.as_os_str()));
+    std::fs::copy(source, &target).with_context(|| {
+      format!(
+        "Failed to copy {} to {}",
+        source.display(),
+        target.display()
+      )
+    })?;
+    self.artifacts += 1;
+    Ok(Some(self.href(&target)))
+  }
+
+  fn href(&self, path: &Path) -> String {
+    let relative = match (
+      std::fs::canonicalize(&self.report_dir),
+      std::fs::canonicalize(path),
+    ) {
+      (Ok(base), Ok(path)) => path.strip_prefix(&base).map(Path::to_path_buf).ok(),
+      _ => None,
+    };
+    match relative {
+      Some(relative) => relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"),
+      None => format!(
+        "file://{}",
+        std::fs::canonicalize(path)
+          .unwrap_or_else(|_| path.to_path_buf())
+          .display()
+      ),
+    }
+  }
+}
+
+/// `id` with anything but letters, digits, `-` and `_` replaced, for use
+/// in a file name.
+fn file_name(id: &str) -> String {
+  id.chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect()
+}
+
+fn render(
+  run_dir: &Path,
+  manifest: &RunManifest,
+  flow_def: Option<&FlowDefinitionV2>,
+  cost: Option<&CostReport>,
+  files: &mut ReportFiles,
+) -> Result<String> {
+  let title = html_escape(&format!("{} — run {}", manifest.workflow, manifest.run_id));
+  let mut html = format!(
+    "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+  );
+
+  html.push_str("<table>\n");
+  let mut summary = vec![
+    ("Status", manifest.status.to_string()),
+    (
+      "Workflow file",
+      manifest.workflow_file.display().to_string(),
+    ),
+    (
+      "Started",
+      manifest
+        .started_at
+        .format("%Y-%m-%d %H:%M:%S UTC")
+        .to_string(),
+    ),
+  ];
+  if let Some(finished_at) = manifest.finished_at {
+    summary.push((
+      "Finished",
+      finished_at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    ));
+  }
+  summary.push(("Duration", format_duration(manifest.duration_ms)));
+  if let Some(error) = &manifest.error {
+    summary.push(("Error", error.clone()));
+  }
+  for (label, value) in summary {
+    let _ = writeln!(
+      html,
+      "<tr><th>{label}</th><td>{}</td></tr>",
+      html_escape(&value)
+    );
+  }
+  html.push_str("</table>\n");
+
+  let _ = write!(
+    html,
+    "<h2>Workflow</h2>\n<pre class=\"mermaid\">\n{}</pre>\n",
+    html_escape(&mermaid(manifest, flow_def))
+  );
+
+  if let Some(cost) = cost {
+    html.push_str("<h2>Usage and cost</h2>\n");
+    html.push_str(&cost_table(cost));
+  }
+
+  html.push_str("<h2>Nodes</h2>\n");
+  let definitions: BTreeMap<&str, _> = flow_def
+    .map(|flow_def| {
+      flow_def
+        .nodes
+        .iter()
+        .map(|node| (node.id.as_str(), node))
+        .collect()
+    })
+    .unwrap_or_default();
+  for node in &manifest.nodes {
+    let outputs = cost::read_outputs(run_dir, &node.id)?;
+    let _ = write!(
+      html,
+      "<details class=\"node {status}\" id=\"node-{id}\"{open}>\n<summary><code>{id}</code> <span class=\"muted\">{status} · {duration}</span></summary>\n",
+      id = html_escape(&node.id),
+      status = node.status.as_str(),
+      duration = format_duration(node.duration_ms),
+      open = if node.status == NodeStatus::Failed {
+        " open"
+      } else {
+        ""
+      },
+    );
+    if let Some(error) = &node.error {
+      let _ = writeln!(html, "<pre class=\"error\">{}</pre>", html_escape(error));
+    }
+    if let Some(definition) = definitions.get(node.id.as_str()) {
+      let mut inputs = Map::new();
+      inputs.insert("type".into(), Value::String(definition.node_type.clone()));
+      if !definition.dependencies.is_empty() {
+        inputs.insert(
+          "dependencies".into(),
+          definition.dependencies.clone().into(),
+        );
+      }
+      if let Some(condition) = &definition.run_if {
+        inputs.insert("run_if".into(), Value::String(condition.clone()));
+      }
+      if !definition.input_mapping.is_empty() {
+        let mapping: BTreeMap<_, _> = definition.input_mapping.iter().collect();
+        inputs.insert("input_mapping".into(), serde_json::to_value(mapping)?);
+      }
+      if !definition.parameters.is_empty() {
+        let parameters: BTreeMap<_, _> = definition.parameters.iter().collect();
+        inputs.insert("parameters".into(), serde_json::to_value(parameters)?);
+      }
+      html.push_str("<h3>Inputs</h3>\n");
+      html.push_str(&value_block(node, "inputs", &Value::Object(inputs), files)?);
+    }
+    if let Some(outputs) = outputs.filter(|outputs| !outputs.is_empty()) {
+      html.push_str("<h3>Outputs</h3>\n");
+      for (key, value) in &outputs {
+        let _ = writeln!(html, "<h4><code>{}</code></h4>", html_escape(key));
+        html.push_str(&value_block(node, key, value, files)?);
+      }
+    }
+    html.push_str("</details>\n");
+  }
+
+  html.push_str(
+    r#"<script type="module">
+  import mermaid from "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+  mermaid.initialize({ startOnLoad: true });
+</script>
+</body>
+</html>
+"#,
+  );
+  Ok(html)
+}
+
+/// One value of a card: image thumbnails and artifact links first, then
+/// the JSON with data URLs elided, cut at [`INLINE_CHARS`].
+fn value_block(
+  node: &NodeReport,
+  key: &str,
+  value: &Value,
+  files: &mut ReportFiles,
+) -> Result<String> {
+  let mut value = value.clone();
+  let mut images = Vec::new();
+  let mut paths = Vec::new();
+  collect_media(&mut value, &mut images, &mut paths);
+
+  let mut html = String::new();
+  for path in &paths {
+    if let Some(data_url) = image_file_data_url(path) {
+      images.push(data_url);
+    }
+  }
+  for image in &images {
+    let _ = writeln!(
+      html,
+      "<img class=\"thumb\" src=\"{}\" alt=\"{} image\">",
+      html_escape(image),
+      html_escape(key)
+    );
+  }
+  let mut links = Vec::new();
+  for path in &paths {
+    if let Some(href) = files.artifact(&node.id, path)? {
+      links.push(format!(
+        "<a href=\"{}\">{}</a>",
+        html_escape(&href),
+        html_escape(&path.display().to_string())
+      ));
+    }
+  }
+  if !links.is_empty() {
+    let _ = writeln!(html, "<p>Artifacts: {}</p>", links.join(", "));
+  }
+
+  let text = match &value {
+    Value::String(text) => text.clone(),
+    other => serde_json::to_string_pretty(other)?,
+  };
+  let chars = text.chars().count();
+  if chars > INLINE_CHARS {
+    let shown: String = text.chars().take(INLINE_CHARS).collect();
+    let href = files.sidecar(&node.id, key, &text)?;
+    let _ = writeln!(
+      html,
+      "<pre>{}…</pre>\n<p class=\"muted\">Showing {INLINE_CHARS} of {chars} characters. <a href=\"{}\" download>Download the full value</a>.</p>",
+      html_escape(&shown),
+      html_escape(&href)
+    );
+  } else {
+    let _ = writeln!(html, "<pre>{}</pre>", html_escape(&text));
+  }
+  Ok(html)
+}
+
+/// Moves the `data:image/…` URLs in `value` to `images`, leaving a
+/// placeholder, and gathers the strings that name existing files.
+fn collect_media(value: &mut Value, images: &mut Vec<String>, paths: &mut Vec<PathBuf>) {
+  match value {
+    Value::String(text) if text.starts_with("data:image/") => {
+      images.push(std::mem::take(text));
+      *text = format!("<image {} shown above>", images.len());
+    }
+    Value::String(text) => {
+      let path = Path::new(text.as_str());
+      if !text.contains('\n') && path.is_file() && !paths.iter().any(|known| known == path) {
+        paths.push(path.to_path_buf());
+      }
+    }
+    Value::Array(items) => {
+      for item in items {
+        collect_media(item, images, paths);
+      }
+    }
+    Value::Object(fields) => {
+      for field in fields.values_mut() {
+        collect_media(field, images, paths);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// `path` as a base64 data URL, when it is a small enough image.
+fn image_file_data_url(path: &Path) -> Option<String> {
+  let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+  let (_, mime) = IMAGE_EXTENSIONS
+    .iter()
+    .find(|(known, _)| *known == extension)?;
+  if std::fs::metadata(path).ok()?.len() > MAX_INLINE_IMAGE_BYTES {
+    return None;
+  }
+  let bytes = std::fs::read(path).ok()?;
+  Some(format!(
+    "data:{mime};base64,{}",
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+  ))
+}
+
+/// A `flowchart TD` of the run's nodes with `classDef`s per status.
+/// Edges come from the workflow's dependencies; when the workflow file
+/// is gone the nodes are drawn without them.
+fn mermaid(manifest: &RunManifest, flow_def: Option<&FlowDefinitionV2>) -> String {
+  let mut statuses: Vec<(String, &str)> = manifest
+    .nodes
+    .iter()
+    .map(|node| (node.id.clone(), node.status.as_str()))
+    .collect();
+  if let Some(flow_def) = flow_def {
+    for node in &flow_def.nodes {
+      if !statuses.iter().any(|(id, _)| *id == node.id) {
+        statuses.push((node.id.clone(), NodeStatus::NotRun.as_str()));
+      }
+    }
+  }
+  let handle = |id: &str| {
+    statuses
+      .iter()
+      .position(|(known, _)| known == id)
+      .map(|index| format!("n{index}"))
+  };
+
+  let mut out = String::from("flowchart TD\n");
+  for (index, (id, _)) in statuses.iter().enumerate() {
+    let _ = writeln!(out, "  n{index}[\"{}\"]", id.replace('"', "#quot;"));
+  }
+  for node in flow_def.iter().flat_map(|flow_def| &flow_def.nodes) {
+    for dependency in &node.dependencies {
+      if let (Some(from), Some(to)) = (handle(dependency), handle(&node.id)) {
+        let _ = writeln!(out, "  {from} --> {to}");
+      }
+    }
+  }
+  let styles = [
+    ("completed", "fill:#dafbe1,stroke:#2da44e"),
+    ("failed", "fill:#ffebe9,stroke:#cf222e"),
+    (
+      "skipped",
+      "fill:#f6f8fa,stroke:#8c959f,stroke-dasharray: 5 5",
+    ),
+    ("not_run", "fill:#f6f8fa,stroke:#8c959f"),
+  ];
+  for (status, style) in styles {
+    let members: Vec<String> = statuses
+      .iter()
+      .enumerate()
+      .filter(|(_, (_, node_status))| *node_status == status)
+      .map(|(index, _)| format!("n{index}"))
+      .collect();
+    if !members.is_empty() {
+      let _ = writeln!(out, "  classDef {status} {style}");
+      let _ = writeln!(out, "  class {} {status}", members.join(","));
+    }
+  }
+  out
+}
+
+fn cost_table(cost: &CostReport) -> String {
+  let mut html = String::from(
+    "<table>\n<tr><th>Node</th><th>Model</th><th>Calls</th><th>Prompt</th><th>Completion</th><th>Cost (USD)</th></tr>\n",
+  );
+  for node in &cost.nodes {
+    let _ = writeln!(
+      html,
+      "<tr><td><a href=\"#node-{id}\"><code>{id}</code></a></td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+      html_escape(node.model.as_deref().unwrap_or("—")),
+      node.calls,
+      node.prompt_tokens,
+      node.completion_tokens,
+      format_usd(node.cost_usd),
+      id = html_escape(&node.id),
+    );
+  }
+  let _ = writeln!(
+    html,
+    "<tr><th colspan=\"3\">Total</th><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n</table>",
+    cost.prompt_tokens,
+    cost.completion_tokens,
+    format_usd(cost.cost_usd)
+  );
+  html
+}
@@ -0,0 +1,94 @@
+//! `agentflow runs resume <run-id>` — rebuild the run's workflow from
+//! the file recorded in its manifest, with the `--profile` and `--set`
+//! values the run was started with, and continue it from the latest
+//! checkpoint via `Flow::resume_with_options`. The resume plan is
+//! checked first, so a run with unresolved non-idempotent tool calls
+//! is refused unless `--force-replay` is given (inspect it with
+//! `workflow resume-plan`).
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+
+use agentflow_core::checkpoint::CheckpointConfig;
+use agentflow_core::{FlowExt, ResumePlanOptions};
+
+use super::{find_run, resolve_run_root};
+use crate::commands::workflow::cost::{self, CostReport};
+use crate::commands::workflow::report::{NodeTimings, RunReport};
+use crate::commands::workflow::vars::WorkflowVars;
+use crate::config::v2::FlowDefinitionV2;
+use crate::executor::build_flow_from_definition;
+
+pub async fn execute(
+  run_id: String,
+  run_dir: Option<String>,
+  checkpoint_dir: Option<String>,
+  force_replay: bool,
+) -> Result<()> {
+  let root = resolve_run_root(run_dir)?;
+  let (run_dir, mut manifest) = find_run(&root, &run_id)?;
+
+  let yaml = std::fs::read_to_string(&manifest.workflow_file).with_context(|| {
+    format!(
+      "Failed to read workflow file {} recorded for run '{}'",
+      manifest.workflow_file.display(),
+      manifest.run_id
+    )
+  })?;
+  let vars = WorkflowVars::from_parts(manifest.profile.as_deref(), manifest.set.clone())
+    .with_context(|| format!("Failed to load the variables of run '{}'", manifest.run_id))?;
+  let yaml = vars.substitute(&yaml)?;
+  let flow_def: FlowDefinitionV2 =
+    serde_yaml::from_str(&yaml).with_context(|| "Failed to parse V2 workflow YAML.")?;
+
+  let mut config = CheckpointConfig::default();
+  if let Some(dir) = checkpoint_dir {
+    config = config.with_checkpoint_dir(PathBuf::from(dir));
+  }
+  let timings = NodeTimings::default();
+  let flow = build_flow_from_definition(&flow_def, None)?
+    .with_checkpointing(config)
+    .context("Failed to enable checkpointing")?
+    .with_event_listener(Arc::new(timings.clone()));
+  let order = flow
+    .execution_order()
+    .context("Failed to build workflow execution order")?;
+
+  println!(
+    "🔄 Resuming run {} of workflow '{}'",
+    manifest.run_id, flow_def.name
+  );
+  let start_time = std::time::Instant::now();
+  // The manifest is left alone when the resume is refused (no
+  // checkpoint, blocked plan), so the original outcome stays visible.
+  let state = flow
+    .resume_with_options(&manifest.run_id, &ResumePlanOptions { force_replay })
+    .await
+    .with_context(|| format!("Failed to resume run '{}'", manifest.run_id))?;
+
+  let report = RunReport::from_state(
+    &flow_def.name,
+    &manifest.run_id,
+    start_time.elapsed(),
+    &order,
+    &state,
+    &timings,
+  )
+  .with_cost(CostReport::from_state(
+    &order,
+    &state,
+    &cost::default_models(&flow_def, None),
+    &cost::load_pricing_or_warn(),
+  ));
+  manifest.finish(&report);
+  manifest.save(&run_dir)?;
+  report.print_summary();
+
+  let failed = report.failed_nodes();
+  if !failed.is_empty() {
+    bail!("{} node(s) failed: {}", failed.len(), failed.join(", "));
+  }
+  Ok(())
+}
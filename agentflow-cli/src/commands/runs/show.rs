@@ -0,0 +1,103 @@
+//! `agentflow runs show <run-id>` — a run's manifest as a per-node
+//! table, or one node's persisted `<node>_outputs.json` with `--node`.
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+use super::{find_run, resolve_run_root};
+use crate::commands::workflow::report::format_duration;
+
+pub async fn execute(
+  run_id: String,
+  run_dir: Option<String>,
+  node: Option<String>,
+  json: bool,
+) -> Result<()> {
+  let root = resolve_run_root(run_dir)?;
+  let (run_dir, manifest) = find_run(&root, &run_id)?;
+
+  if let Some(node_id) = node {
+    let path = run_dir.join(format!("{}_outputs.json", node_id));
+    if !path.is_file() {
+      bail!(
+        "run '{}' has no outputs for node '{}'",
+        manifest.run_id,
+        node_id
+      );
+    }
+    let text = std::fs::read_to_string(&path)
+      .with_context(|| format!("Failed to read {}", path.display()))?;
+    let outputs: Value =
+      serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+    // The file holds the node's `Result`; text mode unwraps it.
+    match (json, &outputs) {
+      (true, _) => println!("{}", serde_json::to_string_pretty(&outputs)?),
+      (false, Value::Object(result)) if result.contains_key("Err") => {
+        println!("Node '{}' in run {} failed:", node_id, manifest.run_id);
+        println!("{}", serde_json::to_string_pretty(&result["Err"])?);
+      }
+      (false, _) => {
+        println!("Outputs of node '{}' in run {}:", node_id, manifest.run_id);
+        let outputs = outputs.get("Ok").unwrap_or(&outputs);
+        println!("{}", serde_json::to_string_pretty(outputs)?);
+      }
+    }
+    return Ok(());
+  }
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    return Ok(());
+  }
+
+  println!("Run: {}", manifest.run_id);
+  println!(
+    "  workflow: {} ({})",
+    manifest.workflow,
+    manifest.workflow_file.display()
+  );
+  println!("  status:   {}", manifest.status);
+  println!(
+    "  started:  {}",
+    manifest.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+  );
+  if let Some(finished_at) = manifest.finished_at {
+    println!(
+      "  finished: {}",
+      finished_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+  }
+  println!("  duration: {}", format_duration(manifest.duration_ms));
+  println!("  dir:      {}", run_dir.display());
+  if let Some(error) = &manifest.error {
+    println!("  error:    {}", error);
+  }
+  if manifest.nodes.is_empty() {
+    println!("\n(no node results recorded)");
+    return Ok(());
+  }
+
+  let width = manifest
+    .nodes
+    .iter()
+    .map(|node| node.id.chars().count())
+    .max()
+    .unwrap_or(0)
+    .max("NODE".len());
+  println!();
+  println!(
+    "{:<width$}  {:<9}  {:>9}  ERROR",
+    "NODE", "STATUS", "DURATION"
+  );
+  for node in &manifest.nodes {
+    let line = format!(
+      "{:<width$}  {:<9}  {:>9}  {}",
+      node.id,
+      node.status.as_str(),
+      format_duration(node.duration_ms),
+      node.error.as_deref().unwrap_or_default()
+    );
+    println!("{}", line.trim_end());
+  }
+  Ok(())
+}
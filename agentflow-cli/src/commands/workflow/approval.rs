@@ -0,0 +1,685 @@
+//! `workflow run` answering `approval` nodes from the terminal.
+//!
+//! An approval node announces itself with an `approval.pending`
+//! `NodeProgress` event and then waits on its `file` or `http` channel.
+//! [`ApprovalPrompter`] listens for that event. When it may prompt, it
+//! renders the pending content as markdown on stderr and asks
+//! `[a]pprove / [r]eject / [e]dit`; `e` opens the content in `$VISUAL` /
+//! `$EDITOR` and asks again with the edited version. The answer goes
+//! back through the node's own channel - written to its sentinel file,
+//! or posted to its callback URL - so the node behaves as if a reviewer
+//! had used that channel. Otherwise the prompter prints how to answer on
+//! the channel instead.
+//!
+//! One thread reads stdin for the whole run, a line at a time as prompts
+//! ask for them, and prompts take turns. A prompt is dropped as soon as
+//! its node completes, fails or is skipped - answered on its channel, or
+//! timed out - and a line typed for it is not handed to the next one.
+//!
+//! Nodes on the `stdin` channel prompt by themselves and are left alone.
+
+use super::progress::RunProgress;
+use agentflow_core::events::{EventListener, WorkflowEvent};
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How often a prompt waiting for a line checks whether its node has
+/// been resolved.
+const RESOLVED_POLL: Duration = Duration::from_millis(100);
+
+/// `--approval`: whether pending approvals are answered on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApprovalMode {
+  /// Prompt when stdin and stderr are terminals.
+  #[default]
+  Auto,
+  /// Always prompt, reading answers from stdin even when it is piped.
+  Prompt,
+  /// Never prompt; print how to answer on the node's channel.
+  Channel,
+}
+
+impl ApprovalMode {
+  pub fn parse(value: &str) -> Result<Self> {
+    match value {
+      "auto" => Ok(Self::Auto),
+      "prompt" => Ok(Self::Prompt),
+      "channel" => Ok(Self::Channel),
+      other => bail!("Unknown approval mode '{other}'; expected auto, prompt or channel"),
+    }
+  }
+
+  fn prompts(self) -> bool {
+    match self {
+      Self::Auto => std::io::stdin().is_terminal() && std::io::stderr().is_terminal(),
+      Self::Prompt => true,
+      Self::Channel => false,
+    }
+  }
+}
+
+/// Where a pending approval wants its decision.
+#[derive(Debug, Clone, PartialEq)]
+enum Reply {
+  File(PathBuf),
+  Http(String),
+}
+
+/// One `approval.pending` event.
+#[derive(Debug, Clone, PartialEq)]
+struct Pending {
+  node_id: String,
+  prompt: String,
+  content: Value,
+  reply: Reply,
+}
+
+impl Pending {
+  fn from_event(event: &WorkflowEvent) -> Option<Self> {
+    let WorkflowEvent::NodeProgress {
+      node_id, detail, ..
+    } = event
+    else {
+      return None;
+    };
+    if detail.get("event").and_then(Value::as_str) != Some("approval.pending") {
+      return None;
+    }
+    let text = |key: &str| detail.get(key).and_then(Value::as_str);
+    let reply = match text("channel")? {
+      "file" => Reply::File(PathBuf::from(text("sentinel_path")?)),
+      "http" => Reply::Http(text("url")?.to_string()),
+      _ => return None,
+    };
+    Some(Self {
+      node_id: node_id.clone(),
+      prompt: text("prompt").unwrap_or("Approve?").to_string(),
+      content: detail.get("content").cloned().unwrap_or(Value::Null),
+      reply,
+    })
+  }
+
+  /// How to answer without the prompt.
+  fn instructions(&self) -> String {
+    match &self.reply {
+      Reply::File(path) => format!(
+        "✋ Approval '{}' is waiting: write `approve`, `reject <feedback>` or a JSON decision to {}",
+        self.node_id,
+        path.display()
+      ),
+      Reply::Http(url) => format!(
+        "✋ Approval '{}' is waiting: POST {{\"approved\": true}} (or false, with \"feedback\") to {}",
+        self.node_id, url
+      ),
+    }
+  }
+}
+
+/// A reviewer's answer, in the JSON shape approval nodes parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Answer {
+  pub approved: bool,
+  pub feedback: String,
+  /// The edited content; `None` when it was not edited.
+  pub content: Option<Value>,
+}
+
+impl Answer {
+  fn to_json(&self) -> Value {
+    let mut decision = json!({ "approved": self.approved, "feedback": self.feedback });
+    if let Some(content) = &self.content {
+      decision["content"] = content.clone();
+    }
+    decision
+  }
+}
+
+/// The prompt's node was resolved without it: answered on its channel,
+/// timed out, or the run ended.
+#[derive(Debug)]
+struct Resolved;
+
+impl fmt::Display for Resolved {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("the approval was resolved without the prompt")
+  }
+}
+
+impl std::error::Error for Resolved {}
+
+/// A line from stdin, `None` at end of input, and when it was read.
+struct Line {
+  text: Option<String>,
+  read_at: Instant,
+}
+
+/// The one reader of stdin. It reads a line only when a prompt asks for
+/// one, so an editor started from a prompt has the terminal to itself.
+struct StdinLines {
+  requests: mpsc::Sender<()>,
+  /// Held by the prompt on screen, so prompts take turns.
+  state: Mutex<LineState>,
+}
+
+struct LineState {
+  lines: mpsc::Receiver<std::io::Result<Line>>,
+  /// A line was asked for and has not been taken; the prompt that asked
+  /// may have been dropped since.
+  requested: bool,
+}
+
+impl StdinLines {
+  fn spawn() -> Self {
+    let stdin = std::io::stdin();
+    Self::spawn_with(move |line| stdin.lock().read_line(line))
+  }
+
+  fn spawn_with(
+    mut read_line: impl FnMut(&mut String) -> std::io::Result<usize> + Send + 'static,
+  ) -> Self {
+    let (requests, asked) = mpsc::channel::<()>();
+    let (sender, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+      while asked.recv().is_ok() {
+        let mut text = String::new();
+        let line = read_line(&mut text).map(|read| Line {
+          text: (read > 0).then_some(text),
+          read_at: Instant::now(),
+        });
+        if sender.send(line).is_err() {
+          break;
+        }
+      }
+    });
+    Self {
+      requests,
+      state: Mutex::new(LineState {
+        lines,
+        requested: false,
+      }),
+    }
+  }
+
+  /// The next line typed from now on, or `None` at end of input. A line
+  /// read earlier was meant for a prompt that has been dropped and is
+  /// skipped. Fails with [`Resolved`] once `resolved` is set.
+  fn next_line(&self, state: &mut LineState, resolved: &AtomicBool) -> Result<Option<String>> {
+    let since = Instant::now();
+    loop {
+      if resolved.load(Ordering::SeqCst) {
+        return Err(Resolved.into());
+      }
+      if !state.requested {
+        self
+          .requests
+          .send(())
+          .map_err(|_| anyhow!("the stdin reader stopped"))?;
+        state.requested = true;
+      }
+      match state.lines.recv_timeout(RESOLVED_POLL) {
+        Ok(line) => {
+          state.requested = false;
+          let line = line.context("failed to read stdin")?;
+          if line.read_at >= since {
+            return Ok(line.text);
+          }
+        }
+        Err(RecvTimeoutError::Timeout) => {}
+        Err(RecvTimeoutError::Disconnected) => bail!("the stdin reader stopped"),
+      }
+    }
+  }
+}
+
+/// Listens for pending approvals; see the module docs.
+pub struct ApprovalPrompter {
+  mode: ApprovalMode,
+  /// Hidden while a prompt is up.
+  progress: Option<Arc<RunProgress>>,
+  /// Runs the callback POST of `http` approvals.
+  runtime: Option<tokio::runtime::Handle>,
+  /// Started with the first prompt.
+  stdin: OnceLock<Arc<StdinLines>>,
+  /// The `resolved` flag of each node with a prompt queued or up.
+  waiting: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl ApprovalPrompter {
+  pub fn new(mode: ApprovalMode, progress: Option<Arc<RunProgress>>) -> Self {
+    Self {
+      mode,
+      progress,
+      runtime: tokio::runtime::Handle::try_current().ok(),
+      stdin: OnceLock::new(),
+      waiting: Arc::default(),
+    }
+  }
+
+  fn say(&self, text: &str) {
+    match &self.progress {
+      Some(progress) => progress.suspend(|| eprintln!("{text}")),
+      None => eprintln!("{text}"),
+    }
+  }
+
+  /// Prompts and delivers the answer; runs on its own thread so the
+  /// node can start waiting on its channel.
+  fn answer(&self, pending: Pending) {
+    let resolved = Arc::new(AtomicBool::new(false));
+    self
+      .waiting
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .insert(pending.node_id.clone(), resolved.clone());
+    let stdin = self
+      .stdin
+      .get_or_init(|| Arc::new(StdinLines::spawn()))
+      .clone();
+    let waiting = self.waiting.clone();
+    let progress = self.progress.clone();
+    let runtime = self.runtime.clone();
+    std::thread::spawn(move || {
+      let mut lines = stdin.state.lock().unwrap_or_else(|e| e.into_inner());
+      let answer = if resolved.load(Ordering::SeqCst) {
+        Err(Resolved.into())
+      } else {
+        let mut prompt = || {
+          let mut stderr = std::io::stderr();
+          ask(
+            &pending,
+            &mut || stdin.next_line(&mut lines, &resolved),
+            &mut stderr,
+            &mut |content| edit_in_editor(content),
+          )
+        };
+        match &progress {
+          Some(progress) => progress.suspend(prompt),
+          None => prompt(),
+        }
+      };
+      drop(lines);
+      {
+        let mut waiting = waiting.lock().unwrap_or_else(|e| e.into_inner());
+        if waiting
+          .get(&pending.node_id)
+          .is_some_and(|flag| Arc::ptr_eq(flag, &resolved))
+        {
+          waiting.remove(&pending.node_id);
+        }
+      }
+      let delivered = answer.and_then(|answer| {
+        if resolved.load(Ordering::SeqCst) {
+          return Err(Resolved.into());
+        }
+        deliver(&pending.reply, &answer, runtime.as_ref())
+      });
+      match delivered {
+        Ok(()) => {}
+        Err(e) if e.is::<Resolved>() => {
+          tracing::info!(node_id = %pending.node_id, "approval resolved without the prompt");
+        }
+        Err(e) => {
+          eprintln!("⚠️  Approval '{}': {:#}", pending.node_id, e);
+          eprintln!("{}", pending.instructions());
+        }
+      }
+    });
+  }
+
+  /// Drops the prompt of a node that finished, or of every node once the
+  /// run has ended.
+  fn resolve(&self, event: &WorkflowEvent) {
+    let mut waiting = self.waiting.lock().unwrap_or_else(|e| e.into_inner());
+    match event {
+      WorkflowEvent::NodeCompleted { node_id, .. }
+      | WorkflowEvent::NodeFailed { node_id, .. }
+      | WorkflowEvent::NodeSkipped { node_id, .. } => {
+        if let Some(resolved) = waiting.remove(node_id) {
+          resolved.store(true, Ordering::SeqCst);
+        }
+      }
+      WorkflowEvent::WorkflowCompleted { .. }
+      | WorkflowEvent::WorkflowFailed { .. }
+      | WorkflowEvent::WorkflowCancelled { .. } => {
+        for (_, resolved) in waiting.drain() {
+          resolved.store(true, Ordering::SeqCst);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+impl EventListener for ApprovalPrompter {
+  fn on_event(&self, event: &WorkflowEvent) {
+    let Some(pending) = Pending::from_event(event) else {
+      self.resolve(event);
+      return;
+    };
+    if self.mode.prompts() {
+      self.answer(pending);
+    } else {
+      tracing::info!(node_id = %pending.node_id, "approval pending");
+      self.say(&pending.instructions());
+    }
+  }
+}
+
+/// The pending content as markdown: text as it is, an object as a list
+/// of its fields, anything else as a JSON block.
+pub fn render_markdown(content: &Value) -> String {
+  let block = |value: &Value| {
+    format!(
+      "```json\n{}\n```",
+      serde_json::to_string_pretty(value).unwrap_or_default()
+    )
+  };
+  match content {
+    Value::String(text) => text.clone(),
+    Value::Object(fields) if !fields.is_empty() => fields
+      .iter()
+      .map(|(key, value)| match value {
+        Value::String(text) if !text.contains('\n') => format!("- **{key}**: {text}"),
+        Value::String(text) => format!("- **{key}**:\n\n{text}\n"),
+        Value::Object(_) | Value::Array(_) => format!("- **{key}**:\n\n{}\n", block(value)),
+        other => format!("- **{key}**: {other}"),
+      })
+      .collect::<Vec<_>>()
+      .join("\n"),
+    other => block(other),
+  }
+}
+
+/// Headings bold, `**strong**` bold, code fences dimmed.
+fn style_markdown(markdown: &str) -> String {
+  let mut in_fence = false;
+  markdown
+    .lines()
+    .map(|line| {
+      if line.trim_start().starts_with("```") {
+        in_fence = !in_fence;
+        return console::style(line).dim().to_string();
+      }
+      if in_fence {
+        return line.to_string();
+      }
+      if line.starts_with('#') {
+        return console::style(line.trim_start_matches('#').trim())
+          .bold()
+          .underlined()
+          .to_string();
+      }
+      let mut styled = String::new();
+      for (i, part) in line.split("**").enumerate() {
+        if i % 2 == 1 {
+          styled.push_str(&console::style(part).bold().to_string());
+        } else {
+          styled.push_str(part);
+        }
+      }
+      styled
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The prompt loop: shows the content, reads `a`, `r` or `e` (each may
+/// be followed by feedback) until one decides. `read_line` returns the
+/// next line, `None` at end of input; `edit` returns the edited content.
+fn ask(
+  pending: &Pending,
+  read_line: &mut dyn FnMut() -> Result<Option<String>>,
+  output: &mut dyn Write,
+  edit: &mut dyn FnMut(&Value) -> Result<Value>,
+) -> Result<Answer> {
+  let mut content = pending.content.clone();
+  let mut edited = false;
+  loop {
+    writeln!(
+      output,
+      "\n✋ {} {}\n\n{}\n",
+      console::style(format!("[{}]", pending.node_id)).cyan(),
+      console::style(&pending.prompt).bold(),
+      style_markdown(&render_markdown(&content))
+    )?;
+    write!(
+      output,
+      "[a]pprove / [r]eject / [e]dit (feedback may follow): "
+    )?;
+    output.flush()?;
+    let Some(line) = read_line()? else {
+      bail!("stdin closed before a decision");
+    };
+    let line = line.trim();
+    let (choice, feedback) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let answer = |approved| Answer {
+      approved,
+      feedback: feedback.trim().to_string(),
+      content: edited.then(|| content.clone()),
+    };
+    match choice.to_ascii_lowercase().as_str() {
+      "a" | "approve" | "y" | "yes" => return Ok(answer(true)),
+      "r" | "reject" | "n" | "no" => return Ok(answer(false)),
+      "e" | "edit" => match edit(&content) {
+        Ok(new_content) => {
+          edited |= new_content != content;
+          content = new_content;
+        }
+        Err(e) => writeln!(output, "⚠️  {:#}", e)?,
+      },
+      _ => writeln!(output, "Please answer a, r or e.")?,
+    }
+  }
+}
+
+/// Opens `content` in `$VISUAL` / `$EDITOR` (default `vi`): text as a
+/// `.md` file, anything else as pretty `.json`, which must still parse.
+fn edit_in_editor(content: &Value) -> Result<Value> {
+  let (text, suffix) = match content {
+    Value::String(text) => (text.clone(), ".md"),
+    other => (serde_json::to_string_pretty(other)?, ".json"),
+  };
+  let mut file = tempfile::Builder::new()
+    .prefix("agentflow-approval-")
+    .suffix(suffix)
+    .tempfile()?;
+  file.write_all(text.as_bytes())?;
+  file.flush()?;
+  run_editor(file.path())?;
+  let edited = std::fs::read_to_string(file.path())?;
+  match content {
+    Value::String(original) => {
+      let edited = match edited.strip_suffix('\n') {
+        Some(trimmed) if !original.ends_with('\n') => trimmed.to_string(),
+        _ => edited,
+      };
+      Ok(Value::String(edited))
+    }
+    _ => serde_json::from_str(&edited).context("the edited content is not valid JSON"),
+  }
+}
+
+fn run_editor(path: &Path) -> Result<()> {
+  let editor = std::env::var("VISUAL")
+    .ok()
+    .filter(|value| !value.trim().is_empty())
+    .or_else(|| std::env::var("EDITOR").ok())
+    .filter(|value| !value.trim().is_empty())
+    .unwrap_or_else(|| "vi".to_string());
+  let words = shlex::split(&editor).filter(|words| !words.is_empty());
+  let Some((program, args)) = words.as_ref().and_then(|words| words.split_first()) else {
+    bail!("cannot parse the editor command '{editor}'");
+  };
+  let status = std::process::Command::new(program)
+    .args(args)
+    .arg(path)
+    .status()
+    .with_context(|| format!("failed to start editor '{editor}'"))?;
+  if !status.success() {
+    bail!("editor '{editor}' exited with {status}");
+  }
+  Ok(())
+}
+
+/// Sends the answer down the node's channel. The sentinel is written
+/// to a temporary name and renamed, so the polling node never reads half
+/// a decision.
+fn deliver(reply: &Reply, answer: &Answer, runtime: Option<&tokio::runtime::Handle>) -> Result<()> {
+  let decision = answer.to_json();
+  match reply {
+    Reply::File(path) => {
+      let mut partial = path.clone().into_os_string();
+      partial.push(".partial");
+      let partial = PathBuf::from(partial);
+      std::fs::write(&partial, serde_json::to_vec(&decision)?)
+        .with_context(|| format!("failed to write {}", partial.display()))?;
+      std::fs::rename(&partial, path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Reply::Http(url) => {
+      let runtime = runtime.context("no async runtime to post the decision with")?;
+      runtime.block_on(async {
+        reqwest::Client::new()
+          .post(url)
+          .json(&decision)
+          .send()
+          .await
+          .and_then(reqwest::Response::error_for_status)
+          .with_context(|| format!("failed to post the decision to {url}"))
+      })?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Reads `text` a line at a time, as [`ask`] reads stdin.
+  fn lines(text: &str) -> impl FnMut() -> Result<Option<String>> {
+    let mut lines: Vec<String> = text.lines().rev().map(str::to_string).collect();
+    move || Ok(lines.pop())
+  }
+
+  fn pending(dir: &Path) -> Pending {
+    let event = WorkflowEvent::NodeProgress {
+      workflow_id: "run".to_string(),
+      node_id: "review".to_string(),
+      detail: json!({
+        "event": "approval.pending",
+        "prompt": "Send it?",
+        "content": { "subject": "Deploy v2", "body": "Line one\nLine two" },
+        "channel": "file",
+        "sentinel_path": dir.join("decision.json").to_string_lossy(),
+      }),
+      timestamp: Instant::now(),
+    };
+    Pending::from_event(&event).unwrap()
+  }
+
+  #[test]
+  fn edit_then_approve_sends_the_edited_content() {
+    let dir = tempfile::tempdir().unwrap();
+    let pending = pending(dir.path());
+    let mut output = Vec::new();
+    let mut edits = 0;
+    let answer = ask(
+      &pending,
+      &mut lines("x\ne\na looks good\n"),
+      &mut output,
+      &mut |content| {
+        edits += 1;
+        let mut content = content.clone();
+        content["subject"] = json!("Deploy v2.0.1");
+        Ok(content)
+      },
+    )
+    .unwrap();
+    assert_eq!(edits, 1);
+    assert_eq!(
+      answer,
+      Answer {
+        approved: true,
+        feedback: "looks good".to_string(),
+        content: Some(json!({ "subject": "Deploy v2.0.1", "body": "Line one\nLine two" })),
+      }
+    );
+    let output = console::strip_ansi_codes(std::str::from_utf8(&output).unwrap()).into_owned();
+    assert!(output.contains("[review] Send it?"), "{output}");
+    assert!(output.contains("- subject: Deploy v2\n"), "{output}");
+    assert!(output.contains("- subject: Deploy v2.0.1\n"), "{output}");
+    assert!(output.contains("Please answer a, r or e."));
+
+    deliver(&pending.reply, &answer, None).unwrap();
+    let written: Value =
+      serde_json::from_slice(&std::fs::read(dir.path().join("decision.json")).unwrap()).unwrap();
+    assert_eq!(written["approved"], true);
+    assert_eq!(written["content"]["subject"], "Deploy v2.0.1");
+  }
+
+  #[test]
+  fn reject_keeps_feedback_and_eof_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let pending = pending(dir.path());
+    let mut no_edit = |_: &Value| -> Result<Value> { unreachable!() };
+    let answer = ask(
+      &pending,
+      &mut lines("r wrong recipient\n"),
+      &mut Vec::new(),
+      &mut no_edit,
+    )
+    .unwrap();
+    assert!(!answer.approved);
+    assert_eq!(answer.feedback, "wrong recipient");
+    assert_eq!(answer.content, None);
+
+    assert!(ask(&pending, &mut lines(""), &mut Vec::new(), &mut no_edit).is_err());
+    assert!(pending.instructions().contains("decision.json"));
+  }
+
+  #[test]
+  fn prompts_skip_lines_typed_for_a_resolved_node() {
+    let mut input = ["late answer\n", "a\n"].into_iter();
+    let stdin = StdinLines::spawn_with(move |line| {
+      let next = input.next().unwrap_or_default();
+      line.push_str(next);
+      Ok(next.len())
+    });
+    let mut state = stdin.state.lock().unwrap();
+
+    let resolved = AtomicBool::new(true);
+    let err = stdin.next_line(&mut state, &resolved).unwrap_err();
+    assert!(err.is::<Resolved>(), "{err:#}");
+
+    // A prompt asked for a line and was dropped; the line is read before
+    // the next prompt asks.
+    stdin.requests.send(()).unwrap();
+    state.requested = true;
+    std::thread::sleep(Duration::from_millis(50));
+
+    let open = AtomicBool::new(false);
+    assert_eq!(
+      stdin.next_line(&mut state, &open).unwrap().as_deref(),
+      Some("a\n")
+    );
+    assert_eq!(stdin.next_line(&mut state, &open).unwrap(), None);
+  }
+
+  #[test]
+  fn markdown_renders_objects_as_field_lists() {
+    assert_eq!(render_markdown(&json!("# Title\nbody")), "# Title\nbody");
+    assert_eq!(
+      render_markdown(&json!({ "n": 2, "tags": ["a"] })),
+      "- **n**: 2\n- **tags**:\n\n```json\n[\n  \"a\"\n]\n```\n"
+    );
+  }
+}
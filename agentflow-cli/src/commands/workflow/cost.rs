@@ -0,0 +1,365 @@
+//! Token usage and cost per node of a workflow run.
+//!
+//! Nodes that call a model report it in their outputs: `usage`
+//! (`prompt_tokens`, `completion_tokens`) next to the `model` that
+//! answered. A Map node's `results` hold its sub-flows' outputs, so the
+//! LLM calls inside a Map are counted against the Map node. Prices come
+//! from the same [`PricingTable`] `eval run` uses
+//! (`AGENTFLOW_PRICING_TABLE` or `~/.agentflow/pricing.yml`); a model
+//! with no entry and no table default has no cost.
+//!
+//! The report is built from the raw run state, or from the persisted
+//! `<node>_outputs.json` files for `runs cost`, never from the report's
+//! redacted outputs (which mask `*_tokens` keys).
+
+use agentflow_agents::eval::PricingTable;
+use agentflow_core::async_node::AsyncNodeResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+
+use super::report::{decode_flow_values, flow_value_json};
+use crate::config::v2::FlowDefinitionV2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeCost {
+  pub id: String,
+  /// The model(s) that answered, comma-separated when a Map node's
+  /// calls used several.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub model: Option<String>,
+  pub calls: u64,
+  pub prompt_tokens: u64,
+  pub completion_tokens: u64,
+  /// `None` when none of the node's models has a price.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostReport {
+  /// Nodes that made at least one model call, in execution order.
+  pub nodes: Vec<NodeCost>,
+  pub prompt_tokens: u64,
+  pub completion_tokens: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost_usd: Option<f64>,
+}
+
+/// One model call found in a node's outputs.
+struct Call {
+  model: Option<String>,
+  prompt_tokens: u64,
+  completion_tokens: u64,
+}
+
+impl CostReport {
+  /// Builds the report from node outputs in their JSON form, in order.
+  /// `default_models` names the model of nodes whose outputs don't.
+  /// Returns `None` when no node reported usage.
+  pub fn from_outputs<'a>(
+    nodes: impl IntoIterator<Item = (&'a str, Map<String, Value>)>,
+    default_models: &HashMap<String, String>,
+    pricing: &PricingTable,
+  ) -> Option<Self> {
+    let rows: Vec<NodeCost> = nodes
+      .into_iter()
+      .filter_map(|(id, outputs)| {
+        let mut calls = Vec::new();
+        collect_calls(&outputs, &mut calls);
+        if calls.is_empty() {
+          return None;
+        }
+        let mut models = BTreeSet::new();
+        let mut cost: Option<f64> = None;
+        for call in &calls {
+          let Some(model) = call
+            .model
+            .as_deref()
+            .or(default_models.get(id).map(String::as_str))
+          else {
+            continue;
+          };
+          models.insert(model.to_string());
+          if is_priced(pricing, model) {
+            let call_cost = pricing.lookup(model).cost_for_call(
+              Some(saturating_u32(call.prompt_tokens)),
+              Some(saturating_u32(call.completion_tokens)),
+            );
+            *cost.get_or_insert(0.0) += call_cost;
+          }
+        }
+        Some(NodeCost {
+          id: id.to_string(),
+          model: (!models.is_empty()).then(|| models.into_iter().collect::<Vec<_>>().join(", ")),
+          calls: calls.len() as u64,
+          prompt_tokens: calls.iter().map(|call| call.prompt_tokens).sum(),
+          completion_tokens: calls.iter().map(|call| call.completion_tokens).sum(),
+          cost_usd: cost,
+        })
+      })
+      .collect();
+    if rows.is_empty() {
+      return None;
+    }
+    let costs: Vec<f64> = rows.iter().filter_map(|row| row.cost_usd).collect();
+    Some(Self {
+      prompt_tokens: rows.iter().map(|row| row.prompt_tokens).sum(),
+      completion_tokens: rows.iter().map(|row| row.completion_tokens).sum(),
+      cost_usd: (!costs.is_empty()).then(|| costs.iter().sum()),
+      nodes: rows,
+    })
+  }
+
+  /// Builds the report from a finished run's state pool.
+  pub fn from_state(
+    order: &[String],
+    state: &HashMap<String, AsyncNodeResult>,
+    default_models: &HashMap<String, String>,
+    pricing: &PricingTable,
+  ) -> Option<Self> {
+    let nodes = order.iter().filter_map(|id| match state.get(id) {
+      Some(Ok(outputs)) => Some((
+        id.as_str(),
+        outputs
+          .iter()
+          .map(|(key, value)| (key.clone(), flow_value_json(value)))
+          .collect(),
+      )),
+      _ => None,
+    });
+    Self::from_outputs(nodes, default_models, pricing)
+  }
+
+  /// The table printed after the run summary.
+  pub fn print_table(&self) {
+    println!("\n💰 Usage and cost:");
+    for line in self.table_lines() {
+      println!("  {}", line);
+    }
+  }
+
+  fn table_lines(&self) -> Vec<String> {
+    let mut rows: Vec<[String; 5]> = vec![[
+      "NODE".into(),
+      "MODEL".into(),
+      "PROMPT".into(),
+      "COMPLETION".into(),
+      "COST (USD)".into(),
+    ]];
+    for node in &self.nodes {
+      rows.push([
+        node.id.clone(),
+        node.model.clone().unwrap_or_else(|| "—".into()),
+        node.prompt_tokens.to_string(),
+        node.completion_tokens.to_string(),
+        format_usd(node.cost_usd),
+      ]);
+    }
+    rows.push([
+      "total".into(),
+      String::new(),
+      self.prompt_tokens.to_string(),
+      self.completion_tokens.to_string(),
+      format_usd(self.cost_usd),
+    ]);
+    let width = |column: usize| {
+      rows
+        .iter()
+        .map(|row| row[column].chars().count())
+        .max()
+        .unwrap_or(0)
+    };
+    let widths = [width(0), width(1), width(2), width(3), width(4)];
+    rows
+      .iter()
+      .map(|row| {
+        format!(
+          "{:<w0$}  {:<w1$}  {:>w2$}  {:>w3$}  {:>w4$}",
+          row[0],
+          row[1],
+          row[2],
+          row[3],
+          row[4],
+          w0 = widths[0],
+          w1 = widths[1],
+          w2 = widths[2],
+          w3 = widths[3],
+          w4 = widths[4],
+        )
+      })
+      .collect()
+  }
+
+  /// A Markdown table for the `--output` report.
+  pub fn to_markdown(&self) -> String {
+    let mut out = String::from(
+      "| Node | Model | Prompt tokens | Completion tokens | Cost (USD) |\n| --- | --- | ---: | ---: | ---: |\n",
+    );
+    for node in &self.nodes {
+      out.push_str(&format!(
+        "| `{}` | {} | {} | {} | {} |\n",
+        node.id,
+        node.model.as_deref().unwrap_or("—"),
+        node.prompt_tokens,
+        node.completion_tokens,
+        format_usd(node.cost_usd)
+      ));
+    }
+    out.push_str(&format!(
+      "| **total** | | {} | {} | {} |\n",
+      self.prompt_tokens,
+      self.completion_tokens,
+      format_usd(self.cost_usd)
+    ));
+    out
+  }
+}
+
+/// The pricing table `eval run` uses. A table that fails to load only
+/// costs the dollar column: tokens are still reported.
+pub fn load_pricing_or_warn() -> PricingTable {
+  crate::commands::eval::load_pricing_table().unwrap_or_else(|err| {
+    eprintln!(
+      "⚠️  Failed to load pricing table, costs are not shown: {:#}",
+      err
+    );
+    PricingTable::empty()
+  })
+}
+
+/// The `model` parameter of each top-level node, or `model_override`
+/// (`workflow run --model`) for nodes that take one.
+pub fn default_models(
+  flow_def: &FlowDefinitionV2,
+  model_override: Option<&str>,
+) -> HashMap<String, String> {
+  flow_def
+    .nodes
+    .iter()
+    .filter_map(|node| {
+      let configured = node
+        .parameters
+        .get("model")
+        .and_then(serde_yaml::Value::as_str);
+      let model = match (model_override, configured) {
+        (Some(model), _) if matches!(node.node_type.as_str(), "llm" | "agent" | "skill_agent") => {
+          model
+        }
+        (_, Some(model)) => model,
+        _ => return None,
+      };
+      Some((node.id.clone(), model.to_string()))
+    })
+    .collect()
+}
+
+/// Records every call in `outputs`, recursing into a Map node's
+/// `results` (one `{child_id: {"Ok": outputs}}` object per item).
+fn collect_calls(outputs: &Map<String, Value>, calls: &mut Vec<Call>) {
+  if let Some(usage) = outputs.get("usage").and_then(Value::as_object) {
+    let count = |key: &str| usage.get(key).and_then(Value::as_u64);
+    let (prompt, completion) = (count("prompt_tokens"), count("completion_tokens"));
+    if prompt.is_some() || completion.is_some() {
+      calls.push(Call {
+        model: outputs
+          .get("model")
+          .and_then(Value::as_str)
+          .map(str::to_string),
+        prompt_tokens: prompt.unwrap_or(0),
+        completion_tokens: completion.unwrap_or(0),
+      });
+    }
+  }
+  let items = outputs.get("results").and_then(Value::as_array);
+  for item in items.into_iter().flatten() {
+    for child in item.as_object().into_iter().flat_map(|item| item.values()) {
+      if let Some(child_outputs) = child.get("Ok") {
+        collect_calls(&decode_flow_values(child_outputs), calls);
+      }
+    }
+  }
+}
+
+fn is_priced(pricing: &PricingTable, model: &str) -> bool {
+  pricing.models.contains_key(model) || pricing.default.is_some()
+}
+
+fn saturating_u32(value: u64) -> u32 {
+  u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+pub(crate) fn format_usd(cost: Option<f64>) -> String {
+  match cost {
+    None => "—".to_string(),
+    Some(cost) => format!("${:.6}", cost),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_agents::eval::ModelPricing;
+  use serde_json::json;
+
+  fn outputs(value: Value) -> Map<String, Value> {
+    value.as_object().unwrap().clone()
+  }
+
+  #[test]
+  fn map_results_are_counted_against_the_map_node() {
+    let call = |model: &str, prompt: u64, completion: u64| {
+      json!({ "summarize": { "Ok": {
+        "output": { "type": "json", "value": "…" },
+        "model": { "type": "json", "value": model },
+        "usage": { "type": "json", "value": {
+          "prompt_tokens": prompt, "completion_tokens": completion,
+        } },
+      } } })
+    };
+    let pricing = PricingTable::empty().with_model(
+      "gpt-4o",
+      ModelPricing {
+        input_per_1k: 5.0,
+        output_per_1k: 15.0,
+      },
+    );
+    let report = CostReport::from_outputs(
+      [
+        ("fetch", outputs(json!({ "output": "page" }))),
+        (
+          "map",
+          outputs(json!({ "results": [call("gpt-4o", 1000, 100), call("local", 10, 1)] })),
+        ),
+        (
+          "ask",
+          outputs(json!({ "usage": { "prompt_tokens": 200, "completion_tokens": 20 } })),
+        ),
+      ],
+      &HashMap::from([("ask".to_string(), "gpt-4o".to_string())]),
+      &pricing,
+    )
+    .unwrap();
+
+    assert_eq!(report.nodes.len(), 2);
+    let map = &report.nodes[0];
+    assert_eq!(map.model.as_deref(), Some("gpt-4o, local"));
+    assert_eq!(
+      (map.calls, map.prompt_tokens, map.completion_tokens),
+      (2, 1010, 101)
+    );
+    assert!((map.cost_usd.unwrap() - 6.5).abs() < 1e-9);
+    assert_eq!(report.nodes[1].model.as_deref(), Some("gpt-4o"));
+    assert_eq!(
+      (report.prompt_tokens, report.completion_tokens),
+      (1210, 121)
+    );
+    assert!((report.cost_usd.unwrap() - 7.8).abs() < 1e-9);
+
+    assert_eq!(
+      report.table_lines().last().unwrap(),
+      "total                   1210         121   $7.800000"
+    );
+    assert!(CostReport::from_outputs([], &HashMap::new(), &pricing).is_none());
+  }
+}
@@ -1,74 +1,130 @@
 //! Workflow debugging and inspection commands
-
-use crate::config::{schema::validate_flow_definition, v2::FlowDefinitionV2};
+//!
+//! Every section works on the `Flow` the executor would run, built from
+//! the YAML by the same factories as `workflow run`: `--validate` reports
+//! `Flow::validate` on top of the schema checks, `--visualize` renders
+//! `Flow::to_mermaid` / `Flow::to_dot`, `--analyze` summarises the
+//! dependency structure, and `--plan` prints `Flow::dry_run`.
+
+use super::validate::parse_workflow;
+use super::vars::WorkflowVars;
+use crate::config::{
+  schema::{
+    UnknownParameterMode, WorkflowValidationOptions, validate_flow_definition_with_options,
+  },
+  v2::FlowDefinitionV2,
+};
+use crate::executor::build_flow_from_definition;
+use agentflow_core::{Flow, PlanStatus};
 use anyhow::{Context, Result, bail};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
 use std::fs;
+use std::path::PathBuf;
+
+/// Diagram format for `--visualize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+  Mermaid,
+  Dot,
+}
+
+impl DiagramFormat {
+  pub fn parse(value: &str) -> Result<Self> {
+    match value {
+      "mermaid" => Ok(Self::Mermaid),
+      "dot" => Ok(Self::Dot),
+      other => bail!(
+        "Unknown diagram format '{}'; expected mermaid or dot",
+        other
+      ),
+    }
+  }
+}
+
+pub struct DebugOptions {
+  pub workflow_file: String,
+  pub visualize: bool,
+  pub format: DiagramFormat,
+  /// Write the `--visualize` diagram here instead of printing it.
+  pub output: Option<PathBuf>,
+  /// Also write a standalone HTML page that renders the diagram.
+  pub html: Option<PathBuf>,
+  pub analyze: bool,
+  pub validate: bool,
+  /// `--plan`, or its older spelling `--dry-run`.
+  pub plan: bool,
+  pub verbose: bool,
+  /// `--profile` / `--set` values, substituted as `workflow run` does.
+  pub vars: WorkflowVars,
+}
 
 /// Execute workflow debug command
-pub async fn execute(
-  workflow_file: String,
-  visualize: bool,
-  dry_run: bool,
-  analyze: bool,
-  validate: bool,
-  plan: bool,
-  verbose: bool,
-) -> Result<()> {
+pub async fn execute(options: DebugOptions) -> Result<()> {
   // If no specific flags, show all info
-  let show_all = !visualize && !dry_run && !analyze && !validate && !plan;
+  let show_all = !options.visualize && !options.analyze && !options.validate && !options.plan;
 
-  println!("🔍 Debugging workflow: {}\n", workflow_file);
+  println!("🔍 Debugging workflow: {}\n", options.workflow_file);
 
   // Read and parse workflow file
-  let yaml_content = fs::read_to_string(&workflow_file)
-    .with_context(|| format!("Failed to read workflow file: {}", workflow_file))?;
+  let yaml_content = fs::read_to_string(&options.workflow_file)
+    .with_context(|| format!("Failed to read workflow file: {}", options.workflow_file))?;
+  let yaml_content = options.vars.substitute(&yaml_content)?;
 
-  let flow_def: FlowDefinitionV2 =
-    serde_yaml::from_str(&yaml_content).with_context(|| "Failed to parse workflow YAML")?;
+  let (flow_def, located_warnings) = parse_workflow(&options.workflow_file, &yaml_content)?;
+  let flow = build_flow_from_definition(&flow_def, None);
 
   // Workflow validation
-  if validate || show_all {
-    println!("═══════════════════════════════════════════════════════════");
-    println!("📋 WORKFLOW VALIDATION");
-    println!("═══════════════════════════════════════════════════════════\n");
-    validate_workflow(&flow_def, verbose)?;
+  if options.validate || show_all {
+    section("📋 WORKFLOW VALIDATION");
+    let (report, errors) = validation_report(&flow_def, &flow, located_warnings, options.verbose);
+    print!("{}", report);
+    if errors > 0 {
+      bail!(
+        "workflow '{}' failed validation with {} error(s)",
+        flow_def.name,
+        errors
+      );
+    }
     println!();
   }
 
+  let flow = flow.with_context(|| format!("Failed to build workflow '{}'", flow_def.name))?;
+
   // Workflow visualization
-  if visualize || show_all {
-    println!("═══════════════════════════════════════════════════════════");
-    println!("🌳 WORKFLOW VISUALIZATION");
-    println!("═══════════════════════════════════════════════════════════\n");
-    visualize_workflow(&flow_def, verbose)?;
+  if options.visualize || show_all {
+    section("🌳 WORKFLOW VISUALIZATION");
+    let diagram = match options.format {
+      DiagramFormat::Mermaid => flow.to_mermaid(),
+      DiagramFormat::Dot => flow.to_dot(),
+    };
+    match &options.output {
+      Some(path) => {
+        fs::write(path, &diagram)
+          .with_context(|| format!("Failed to write diagram to {}", path.display()))?;
+        println!("💾 Diagram written to {}", path.display());
+      }
+      None => print!("{}", diagram),
+    }
+    if let Some(path) = &options.html {
+      fs::write(path, html_preview(&flow_def.name, options.format, &diagram))
+        .with_context(|| format!("Failed to write HTML preview to {}", path.display()))?;
+      println!("🌐 HTML preview written to {}", path.display());
+    }
     println!();
   }
 
   // Workflow analysis
-  if analyze || show_all {
-    println!("═══════════════════════════════════════════════════════════");
-    println!("📊 WORKFLOW ANALYSIS");
-    println!("═══════════════════════════════════════════════════════════\n");
-    analyze_workflow(&flow_def, verbose)?;
+  if options.analyze || show_all {
+    section("📊 WORKFLOW ANALYSIS");
+    print!("{}", analysis_report(&flow_def, &flow, options.verbose)?);
     println!();
   }
 
   // Execution plan
-  if plan || show_all {
-    println!("═══════════════════════════════════════════════════════════");
-    println!("📅 EXECUTION PLAN");
-    println!("═══════════════════════════════════════════════════════════\n");
-    show_execution_plan(&flow_def, verbose)?;
-    println!();
-  }
-
-  // Dry run
-  if dry_run {
-    println!("═══════════════════════════════════════════════════════════");
-    println!("🧪 DRY RUN");
-    println!("═══════════════════════════════════════════════════════════\n");
-    dry_run_workflow(&flow_def, verbose)?;
+  if options.plan || show_all {
+    section("📅 EXECUTION PLAN");
+    print!("{}", plan_report(&flow_def, &flow, options.verbose)?);
     println!();
   }
 
@@ -76,481 +132,245 @@ pub async fn execute(
   Ok(())
 }
 
-/// Validate workflow configuration
-fn validate_workflow(flow_def: &FlowDefinitionV2, verbose: bool) -> Result<()> {
-  let mut issues = Vec::new();
-  let mut warnings = Vec::new();
-
-  // Check for empty workflow
-  if flow_def.nodes.is_empty() {
-    issues.push("Workflow has no nodes defined".to_string());
-  }
-
-  // Check for duplicate node IDs
-  let mut seen_ids = HashSet::new();
-  for node in &flow_def.nodes {
-    if !seen_ids.insert(&node.id) {
-      issues.push(format!("Duplicate node ID: '{}'", node.id));
-    }
-  }
+fn section(title: &str) {
+  println!("═══════════════════════════════════════════════════════════");
+  println!("{}", title);
+  println!("═══════════════════════════════════════════════════════════\n");
+}
 
-  // Check for invalid dependencies
-  let valid_ids: HashSet<_> = flow_def.nodes.iter().map(|n| &n.id).collect();
-  for node in &flow_def.nodes {
-    for dep in &node.dependencies {
-      if !valid_ids.contains(dep) {
-        issues.push(format!(
-          "Node '{}' depends on non-existent node '{}'",
-          node.id, dep
-        ));
-      }
+/// Schema issues, factory failures and `Flow::validate` errors, then
+/// located, schema and flow warnings.
+fn findings(
+  flow_def: &FlowDefinitionV2,
+  flow: &Result<Flow>,
+  mut warnings: Vec<String>,
+) -> (Vec<String>, Vec<String>) {
+  let schema = validate_flow_definition_with_options(
+    flow_def,
+    WorkflowValidationOptions {
+      unknown_parameters: UnknownParameterMode::Ignore,
+    },
+  );
+  let mut errors = schema.issues;
+  warnings.extend(schema.warnings);
+  match flow {
+    Ok(flow) => {
+      let report = flow.validate();
+      errors.extend(report.errors);
+      warnings.extend(report.warnings);
     }
+    Err(err) => errors.push(format!("{:#}", err)),
   }
+  (errors, warnings)
+}
 
-  // Check for circular dependencies
-  if let Err(e) = detect_cycles(flow_def) {
-    issues.push(format!("Circular dependency detected: {}", e));
-  }
-
-  let schema_report = validate_flow_definition(flow_def);
-  issues.extend(schema_report.issues);
-  warnings.extend(schema_report.warnings);
-
-  // Check for unreachable nodes (warnings)
-  let reachable = find_reachable_nodes(flow_def);
-  for node in &flow_def.nodes {
-    if !reachable.contains(&node.id) {
-      warnings.push(format!("Node '{}' may be unreachable", node.id));
+/// The printed report and its error count.
+pub fn validation_report(
+  flow_def: &FlowDefinitionV2,
+  flow: &Result<Flow>,
+  located_warnings: Vec<String>,
+  verbose: bool,
+) -> (String, usize) {
+  let (errors, warnings) = findings(flow_def, flow, located_warnings);
+  let mut out = String::new();
+  let _ = writeln!(out, "Workflow: {}", flow_def.name);
+  let _ = writeln!(out, "Total nodes: {}\n", flow_def.nodes.len());
+
+  if errors.is_empty() && warnings.is_empty() {
+    out.push_str("✅ No validation issues found\n");
+  }
+  if !errors.is_empty() {
+    let _ = writeln!(out, "❌ Errors: {}", errors.len());
+    for (i, error) in errors.iter().enumerate() {
+      let _ = writeln!(out, "  {}. {}", i + 1, error);
     }
   }
-
-  // Print results
-  println!("Workflow: {}", flow_def.name);
-  println!("Total nodes: {}", flow_def.nodes.len());
-  println!();
-
-  if issues.is_empty() && warnings.is_empty() {
-    println!("✅ No validation issues found");
-  } else {
-    if !issues.is_empty() {
-      println!("❌ Issues found: {}", issues.len());
-      for (i, issue) in issues.iter().enumerate() {
-        println!("  {}. {}", i + 1, issue);
-      }
-      println!();
-    }
-
-    if !warnings.is_empty() {
-      println!("⚠️  Warnings: {}", warnings.len());
-      for (i, warning) in warnings.iter().enumerate() {
-        println!("  {}. {}", i + 1, warning);
-      }
-      println!();
+  if !warnings.is_empty() {
+    let _ = writeln!(out, "⚠️  Warnings: {}", warnings.len());
+    for (i, warning) in warnings.iter().enumerate() {
+      let _ = writeln!(out, "  {}. {}", i + 1, warning);
     }
   }
 
   if verbose {
-    println!("Node types summary:");
-    let mut type_counts: HashMap<&str, usize> = HashMap::new();
-    for node in &flow_def.nodes {
-      *type_counts.entry(&node.node_type).or_insert(0) += 1;
-    }
-    for (node_type, count) in type_counts {
-      println!("  - {}: {}", node_type, count);
-    }
-  }
-
-  if !issues.is_empty() {
-    bail!(
-      "workflow '{}' failed validation with {} issue(s)",
-      flow_def.name,
-      issues.len()
-    );
-  }
-
-  Ok(())
-}
-
-/// Visualize workflow as text-based DAG
-fn visualize_workflow(flow_def: &FlowDefinitionV2, verbose: bool) -> Result<()> {
-  println!("Workflow: {}", flow_def.name);
-  println!();
-
-  // Build dependency graph
-  let dep_graph = build_dependency_graph(flow_def);
-
-  // Find root nodes (nodes with no dependencies)
-  let roots: Vec<_> = flow_def
-    .nodes
-    .iter()
-    .filter(|n| n.dependencies.is_empty())
-    .collect();
-
-  if roots.is_empty() {
-    println!("⚠️  No root nodes found (all nodes have dependencies)");
-    println!();
-  }
-
-  // Print tree structure
-  let mut visited = HashSet::new();
-  for root in &roots {
-    print_node_tree(
-      root.id.as_str(),
-      flow_def,
-      &dep_graph,
-      &mut visited,
-      0,
-      verbose,
-    );
-  }
-
-  // Print dependency summary
-  println!("\nDependencies:");
-  let mut has_deps = false;
-  for node in &flow_def.nodes {
-    if !node.dependencies.is_empty() {
-      has_deps = true;
-      println!("  {} ← {:?}", node.id, node.dependencies);
+    out.push_str("\nNode types summary:\n");
+    for (node_type, count) in type_counts(flow_def) {
+      let _ = writeln!(out, "  - {}: {}", node_type, count);
     }
   }
-  if !has_deps {
-    println!("  (No dependencies defined)");
-  }
-
-  Ok(())
+  (out, errors.len())
 }
 
-/// Analyze workflow structure and complexity
-fn analyze_workflow(flow_def: &FlowDefinitionV2, verbose: bool) -> Result<()> {
-  // Calculate metrics
-  let total_nodes = flow_def.nodes.len();
+pub fn analysis_report(flow_def: &FlowDefinitionV2, flow: &Flow, verbose: bool) -> Result<String> {
+  let levels = flow.levels()?;
+  let critical_path = flow.critical_path()?;
   let total_deps: usize = flow_def.nodes.iter().map(|n| n.dependencies.len()).sum();
-  let avg_deps = if total_nodes > 0 {
-    total_deps as f64 / total_nodes as f64
-  } else {
-    0.0
-  };
-
-  let max_deps = flow_def
-    .nodes
+  let parallel: Vec<(usize, &Vec<String>)> = levels
     .iter()
-    .map(|n| n.dependencies.len())
-    .max()
-    .unwrap_or(0);
-
-  // Calculate workflow depth
-  let depth = calculate_depth(flow_def);
-
-  // Find bottlenecks (nodes with many dependents)
-  let dependents_count = count_dependents(flow_def);
-  let max_dependents = dependents_count.values().max().copied().unwrap_or(0);
-
-  println!("Workflow Metrics:");
-  println!("  Total nodes:        {}", total_nodes);
-  println!("  Total dependencies: {}", total_deps);
-  println!("  Average dependencies per node: {:.1}", avg_deps);
-  println!("  Max dependencies on single node: {}", max_deps);
-  println!("  Workflow depth:     {} levels", depth);
-  println!("  Max dependents:     {}", max_dependents);
-  println!();
-
-  // Identify potential bottlenecks
-  if max_dependents > 3 {
-    println!("⚠️  Potential bottlenecks (nodes with many dependents):");
-    for (node_id, count) in &dependents_count {
-      if *count > 3 {
-        println!("  - '{}': {} nodes depend on it", node_id, count);
-      }
-    }
-    println!();
-  }
-
-  // Show parallelism opportunities
-  let parallel_levels = find_parallel_levels(flow_def);
-  if verbose && !parallel_levels.is_empty() {
-    println!("Parallelism opportunities:");
-    for (level, nodes) in parallel_levels.iter().enumerate() {
-      if nodes.len() > 1 {
-        println!(
-          "  Level {}: {} nodes can run in parallel",
-          level,
-          nodes.len()
-        );
-        println!("    {:?}", nodes);
-      }
-    }
-    println!();
-  }
-
-  // Node type distribution
-  println!("Node Type Distribution:");
-  let mut type_counts: HashMap<&str, usize> = HashMap::new();
-  for node in &flow_def.nodes {
-    *type_counts.entry(&node.node_type).or_insert(0) += 1;
-  }
-  for (node_type, count) in type_counts {
-    let percentage = (count as f64 / total_nodes as f64) * 100.0;
-    println!("  - {:12} : {:3} ({:5.1}%)", node_type, count, percentage);
-  }
-
-  Ok(())
-}
-
-/// Show estimated execution plan
-fn show_execution_plan(flow_def: &FlowDefinitionV2, verbose: bool) -> Result<()> {
-  let levels = find_parallel_levels(flow_def);
+    .enumerate()
+    .filter(|(_, nodes)| nodes.len() > 1)
+    .collect();
 
-  println!("Estimated Execution Plan:");
-  println!("(Nodes at the same level can execute in parallel)\n");
-  println!(
-    "Concurrent mode hint: `workflow run --execution-mode concurrent` may run same-level ready nodes at the same time, bounded by `--max-concurrency`.\n"
+  let mut out = String::new();
+  out.push_str("Workflow Metrics:\n");
+  let _ = writeln!(out, "  Total nodes:          {}", flow_def.nodes.len());
+  let _ = writeln!(out, "  Total dependencies:   {}", total_deps);
+  let _ = writeln!(out, "  Dependency depth:     {} level(s)", levels.len());
+  let _ = writeln!(
+    out,
+    "  Critical path:        {} node(s): {}",
+    critical_path.len(),
+    critical_path.join(" → ")
   );
-
-  for (level, nodes) in levels.iter().enumerate() {
-    println!(
-      "Level {} ({} node{}):",
-      level,
-      nodes.len(),
-      if nodes.len() == 1 { "" } else { "s" }
-    );
-
-    for node_id in nodes {
-      let node = flow_def
-        .nodes
-        .iter()
-        .find(|n| &n.id == node_id)
-        .ok_or_else(|| anyhow::anyhow!("Node '{}' not found in workflow definition", node_id))?;
-
-      if verbose {
-        let deps_str = if node.dependencies.is_empty() {
-          String::from("(no dependencies)")
-        } else {
-          format!("depends on: {:?}", node.dependencies)
-        };
-        println!("  ├─ [{}] {} - {}", node.node_type, node.id, deps_str);
-      } else {
-        println!("  ├─ [{}] {}", node.node_type, node.id);
-      }
-    }
-    println!();
-  }
-
-  println!("Total execution levels: {}", levels.len());
-  println!(
-    "Maximum parallelism: {}",
-    levels.iter().map(|l| l.len()).max().unwrap_or(0)
+  let _ = writeln!(
+    out,
+    "  Max parallelism:      {}",
+    levels.iter().map(Vec::len).max().unwrap_or(0)
   );
-
-  Ok(())
-}
-
-/// Perform dry run simulation
-fn dry_run_workflow(flow_def: &FlowDefinitionV2, verbose: bool) -> Result<()> {
-  println!("Simulating workflow execution: {}", flow_def.name);
-  println!();
-
-  let levels = find_parallel_levels(flow_def);
-
-  for (level, nodes) in levels.iter().enumerate() {
-    println!("📍 Level {} - Executing {} node(s)", level, nodes.len());
-
-    for node_id in nodes {
-      let node = flow_def
-        .nodes
-        .iter()
-        .find(|n| &n.id == node_id)
-        .ok_or_else(|| anyhow::anyhow!("Node '{}' not found in workflow definition", node_id))?;
-
-      if verbose {
-        println!("  ▶️  Node: {}", node.id);
-        println!("      Type: {}", node.node_type);
-        if !node.dependencies.is_empty() {
-          println!("      Dependencies: {:?}", node.dependencies);
-        }
-        if !node.parameters.is_empty() {
-          println!("      Parameters: {} configured", node.parameters.len());
-        }
-        println!("      ✓ Simulation passed");
-      } else {
-        println!("  ✓ {}", node.id);
-      }
-    }
-    println!();
-  }
-
-  println!("✅ Dry run completed successfully");
-  println!("   Total levels: {}", levels.len());
-  println!("   Total nodes: {}", flow_def.nodes.len());
-
-  Ok(())
-}
-
-// Helper functions
-
-fn build_dependency_graph(flow_def: &FlowDefinitionV2) -> HashMap<String, Vec<String>> {
-  let mut graph: HashMap<String, Vec<String>> = HashMap::new();
-
-  for node in &flow_def.nodes {
-    graph.entry(node.id.clone()).or_default();
-    for dep in &node.dependencies {
-      graph.entry(dep.clone()).or_default().push(node.id.clone());
-    }
-  }
-
-  graph
-}
-
-fn print_node_tree(
-  node_id: &str,
-  flow_def: &FlowDefinitionV2,
-  dep_graph: &HashMap<String, Vec<String>>,
-  visited: &mut HashSet<String>,
-  indent: usize,
-  verbose: bool,
-) {
-  if visited.contains(node_id) {
-    return;
-  }
-  visited.insert(node_id.to_string());
-
-  let node = flow_def.nodes.iter().find(|n| n.id == node_id);
-  if let Some(node) = node {
-    let prefix = "  ".repeat(indent);
-    let symbol = if indent == 0 { "├─" } else { "└─" };
-
-    if verbose {
-      println!("{}{} [{}] {}", prefix, symbol, node.node_type, node.id);
-      if !node.parameters.is_empty() {
-        println!("{}   └─ {} parameter(s)", prefix, node.parameters.len());
-      }
-    } else {
-      println!("{}{} [{}] {}", prefix, symbol, node.node_type, node.id);
-    }
-
-    // Print dependents
-    if let Some(dependents) = dep_graph.get(node_id) {
-      for dependent in dependents {
-        print_node_tree(dependent, flow_def, dep_graph, visited, indent + 1, verbose);
-      }
-    }
+  out.push('\n');
+
+  let _ = writeln!(out, "Parallelizable groups: {}", parallel.len());
+  for (level, nodes) in &parallel {
+    let _ = writeln!(out, "  Level {}: {}", level, nodes.join(", "));
+  }
+  out.push('\n');
+
+  out.push_str("Node Type Distribution:\n");
+  let total = flow_def.nodes.len().max(1) as f64;
+  for (node_type, count) in type_counts(flow_def) {
+    let _ = writeln!(
+      out,
+      "  - {:12} : {:3} ({:5.1}%)",
+      node_type,
+      count,
+      count as f64 / total * 100.0
+    );
   }
-}
-
-fn detect_cycles(flow_def: &FlowDefinitionV2) -> Result<(), String> {
-  fn visit(
-    node_id: &str,
-    flow_def: &FlowDefinitionV2,
-    visited: &mut HashSet<String>,
-    rec_stack: &mut HashSet<String>,
-  ) -> Result<(), String> {
-    visited.insert(node_id.to_string());
-    rec_stack.insert(node_id.to_string());
 
-    let node = flow_def.nodes.iter().find(|n| n.id == node_id);
-    if let Some(node) = node {
+  if verbose {
+    let mut dependents: BTreeMap<&str, usize> = BTreeMap::new();
+    for node in &flow_def.nodes {
       for dep in &node.dependencies {
-        if !visited.contains(dep) {
-          visit(dep, flow_def, visited, rec_stack)?;
-        } else if rec_stack.contains(dep) {
-          return Err(format!("{} -> {}", node_id, dep));
-        }
+        *dependents.entry(dep.as_str()).or_insert(0) += 1;
       }
     }
-
-    rec_stack.remove(node_id);
-    Ok(())
-  }
-
-  let mut visited = HashSet::new();
-  let mut rec_stack = HashSet::new();
-
-  for node in &flow_def.nodes {
-    if !visited.contains(&node.id) {
-      visit(&node.id, flow_def, &mut visited, &mut rec_stack)?;
+    out.push_str("\nDependents per node:\n");
+    for (node_id, count) in dependents {
+      let _ = writeln!(out, "  - {}: {}", node_id, count);
     }
   }
-
-  Ok(())
+  Ok(out)
 }
 
-fn find_reachable_nodes(flow_def: &FlowDefinitionV2) -> HashSet<String> {
-  let mut reachable = HashSet::new();
-
-  // Start from root nodes
-  let roots: Vec<_> = flow_def
+pub fn plan_report(flow_def: &FlowDefinitionV2, flow: &Flow, verbose: bool) -> Result<String> {
+  let plan = flow.dry_run()?;
+  let types: HashMap<&str, &str> = flow_def
     .nodes
     .iter()
-    .filter(|n| n.dependencies.is_empty())
-    .map(|n| n.id.clone())
+    .map(|node| (node.id.as_str(), node.node_type.as_str()))
     .collect();
 
-  let dep_graph = build_dependency_graph(flow_def);
-
-  fn dfs(node_id: &str, dep_graph: &HashMap<String, Vec<String>>, reachable: &mut HashSet<String>) {
-    reachable.insert(node_id.to_string());
-    if let Some(dependents) = dep_graph.get(node_id) {
-      for dependent in dependents {
-        if !reachable.contains(dependent) {
-          dfs(dependent, dep_graph, reachable);
-        }
+  let mut rows = vec![[
+    "LEVEL".to_string(),
+    "NODE".to_string(),
+    "TYPE".to_string(),
+    "STATUS".to_string(),
+    "REASON".to_string(),
+  ]];
+  for node in &plan.nodes {
+    let mut reason = node.reason.clone().unwrap_or_default();
+    if verbose && !node.dependencies.is_empty() {
+      if !reason.is_empty() {
+        reason.push_str("; ");
       }
+      reason.push_str(&format!("after {}", node.dependencies.join(", ")));
+    }
+    rows.push([
+      node.level.to_string(),
+      node.node_id.clone(),
+      types
+        .get(node.node_id.as_str())
+        .copied()
+        .unwrap_or(node.kind)
+        .to_string(),
+      node.status.to_string(),
+      reason,
+    ]);
+  }
+  let mut widths = [0usize; 4];
+  for row in &rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
     }
   }
 
-  for root in &roots {
-    dfs(root, &dep_graph, &mut reachable);
-  }
-
-  reachable
+  let mut out = String::new();
+  out.push_str(
+    "Concurrent mode hint: `workflow run --execution-mode concurrent` may run same-level ready nodes at the same time, bounded by `--max-concurrency`.\n\n",
+  );
+  for row in &rows {
+    let mut line = String::new();
+    for (cell, width) in row.iter().zip(widths) {
+      let _ = write!(line, "{:width$}  ", cell, width = width);
+    }
+    line.push_str(&row[4]);
+    let _ = writeln!(out, "{}", line.trim_end());
+  }
+  let _ = writeln!(
+    out,
+    "\n{} run, {} skip, {} unknown across {} level(s)",
+    plan.count(PlanStatus::Run),
+    plan.count(PlanStatus::Skip),
+    plan.count(PlanStatus::Unknown),
+    plan.levels()
+  );
+  Ok(out)
 }
 
-fn calculate_depth(flow_def: &FlowDefinitionV2) -> usize {
-  let levels = find_parallel_levels(flow_def);
-  levels.len()
+/// A standalone page that renders `diagram` client-side (Mermaid or
+/// Graphviz via viz.js, both loaded from a CDN).
+pub fn html_preview(title: &str, format: DiagramFormat, diagram: &str) -> String {
+  let title = html_escape(title);
+  let body = match format {
+    DiagramFormat::Mermaid => format!(
+      r#"<pre class="mermaid">
+{}</pre>
+<script type="module">
+  import mermaid from "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+  mermaid.initialize({{ startOnLoad: true }});
+</script>"#,
+      html_escape(diagram)
+    ),
+    DiagramFormat::Dot => format!(
+      r#"<div id="graph"></div>
+<pre id="dot" hidden>
+{}</pre>
+<script type="module">
+  import {{ instance }} from "https://cdn.jsdelivr.net/npm/@viz-js/viz@3/lib/viz-standalone.mjs";
+  const viz = await instance();
+  const dot = document.getElementById("dot").textContent;
+  document.getElementById("graph").appendChild(viz.renderSVGElement(dot));
+</script>"#,
+      html_escape(diagram)
+    ),
+  };
+  format!(
+    "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}\n</body>\n</html>\n"
+  )
 }
 
-fn count_dependents(flow_def: &FlowDefinitionV2) -> HashMap<String, usize> {
-  let mut counts: HashMap<String, usize> = HashMap::new();
+pub(crate) fn html_escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
 
+fn type_counts(flow_def: &FlowDefinitionV2) -> BTreeMap<&str, usize> {
+  let mut counts = BTreeMap::new();
   for node in &flow_def.nodes {
-    for dep in &node.dependencies {
-      *counts.entry(dep.clone()).or_insert(0) += 1;
-    }
+    *counts.entry(node.node_type.as_str()).or_insert(0) += 1;
   }
-
   counts
 }
-
-fn find_parallel_levels(flow_def: &FlowDefinitionV2) -> Vec<Vec<String>> {
-  let mut levels: Vec<Vec<String>> = Vec::new();
-  let mut scheduled: HashSet<String> = HashSet::new();
-
-  loop {
-    let mut current_level = Vec::new();
-
-    for node in &flow_def.nodes {
-      if scheduled.contains(&node.id) {
-        continue;
-      }
-
-      // Check if all dependencies are scheduled
-      let all_deps_scheduled = node.dependencies.iter().all(|d| scheduled.contains(d));
-
-      if all_deps_scheduled {
-        current_level.push(node.id.clone());
-      }
-    }
-
-    if current_level.is_empty() {
-      break;
-    }
-
-    for node_id in &current_level {
-      scheduled.insert(node_id.clone());
-    }
-
-    levels.push(current_level);
-  }
-
-  levels
-}
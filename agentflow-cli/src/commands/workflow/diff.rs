@@ -0,0 +1,266 @@
+//! `agentflow workflow diff <old> <new>` — structural diff of two
+//! workflow versions.
+//!
+//! Both files are substituted with the `--profile` and `--set` values,
+//! checked and parsed as `workflow run` would, and their definitions are
+//! compared node by node: ids, types, dependencies, input mappings and
+//! `run_if`, with none of the formatting noise of a text diff. The nodes
+//! of `map` templates and `while` bodies are compared the same way and
+//! reported as `parent/child`. No node is built, so `$secret` references
+//! are not resolved and nothing needs a credential. Exits 0 when the
+//! graphs match and 1 when they differ, so it can gate a review in CI.
+
+use super::validate::parse_workflow;
+use super::vars::WorkflowVars;
+use crate::config::v2::{FlowDefinitionV2, NodeDefinitionV2};
+use agentflow_core::{FlowDiff, NodeChange, ValueChange};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+pub async fn execute(
+  old_file: String,
+  new_file: String,
+  format: String,
+  vars: WorkflowVars,
+) -> Result<()> {
+  let old = load_definition(&old_file, &vars)?;
+  let new = load_definition(&new_file, &vars)?;
+  let diff = diff_definitions(&old, &new)?;
+
+  let payload = serde_json::json!({
+    "old": &old_file,
+    "new": &new_file,
+    "changed": !diff.is_empty(),
+    "diff": &diff,
+  });
+  match format.as_str() {
+    "json" => println!("{}", serde_json::to_string_pretty(&payload)?),
+    "json-envelope" => {
+      let envelope = crate::json_envelope::CliJsonEnvelope::ok("workflow diff", &payload);
+      println!("{}", serde_json::to_string_pretty(&envelope)?);
+    }
+    _ => {
+      println!("--- {}", old_file);
+      println!("+++ {}", new_file);
+      print!("{}", diff);
+    }
+  }
+
+  // Exit 1 = the graphs differ; unreadable or invalid workflows fail
+  // through anyhow with their own exit codes.
+  if !diff.is_empty() {
+    std::process::exit(1);
+  }
+  Ok(())
+}
+
+fn load_definition(workflow_file: &str, vars: &WorkflowVars) -> Result<FlowDefinitionV2> {
+  let yaml_content = fs::read_to_string(workflow_file)
+    .with_context(|| format!("Failed to read workflow file: {}", workflow_file))?;
+  let yaml_content = vars.substitute(&yaml_content)?;
+  let (flow_def, _warnings) = parse_workflow(workflow_file, &yaml_content)?;
+  Ok(flow_def)
+}
+
+/// Compares `old` with `new`, the later version.
+fn diff_definitions(old: &FlowDefinitionV2, new: &FlowDefinitionV2) -> Result<FlowDiff> {
+  let mut diff = FlowDiff::default();
+  diff_nodes(&old.nodes, &new.nodes, "", &mut diff)?;
+  Ok(diff)
+}
+
+fn diff_nodes(
+  old: &[NodeDefinitionV2],
+  new: &[NodeDefinitionV2],
+  prefix: &str,
+  diff: &mut FlowDiff,
+) -> Result<()> {
+  let old: BTreeMap<&str, &NodeDefinitionV2> = old.iter().map(|n| (n.id.as_str(), n)).collect();
+  let new: BTreeMap<&str, &NodeDefinitionV2> = new.iter().map(|n| (n.id.as_str(), n)).collect();
+
+  for (id, node) in &old {
+    if !new.contains_key(id) {
+      diff
+        .removed
+        .insert(format!("{}{}", prefix, id), node.node_type.clone());
+    }
+  }
+  for (id, node) in &new {
+    let path = format!("{}{}", prefix, id);
+    let Some(before) = old.get(id) else {
+      diff.added.insert(path, node.node_type.clone());
+      continue;
+    };
+    let change = diff_node(before, node);
+    // Templates are only comparable while the kind stays the same
+    if change.node_type.is_none()
+      && let (Some(old_template), Some(new_template)) = (template_of(before)?, template_of(node)?)
+    {
+      diff_nodes(&old_template, &new_template, &format!("{}/", path), diff)?;
+    }
+    if change != NodeChange::default() {
+      diff.changed.insert(path, change);
+    }
+  }
+  Ok(())
+}
+
+fn diff_node(old: &NodeDefinitionV2, new: &NodeDefinitionV2) -> NodeChange {
+  let old_deps: BTreeSet<&String> = old.dependencies.iter().collect();
+  let new_deps: BTreeSet<&String> = new.dependencies.iter().collect();
+  let (old_mapping, new_mapping) = (mapping_of(old), mapping_of(new));
+  let inputs: BTreeSet<&str> = old_mapping
+    .keys()
+    .chain(new_mapping.keys())
+    .copied()
+    .collect();
+
+  NodeChange {
+    node_type: (old.node_type != new.node_type).then(|| ValueChange {
+      old: old.node_type.clone(),
+      new: new.node_type.clone(),
+    }),
+    dependencies_added: new_deps
+      .difference(&old_deps)
+      .map(|d| d.to_string())
+      .collect(),
+    dependencies_removed: old_deps
+      .difference(&new_deps)
+      .map(|d| d.to_string())
+      .collect(),
+    input_mapping: inputs
+      .into_iter()
+      .filter_map(|input| {
+        let (old, new) = (old_mapping.get(input), new_mapping.get(input));
+        (old != new).then(|| {
+          (
+            input.to_string(),
+            ValueChange {
+              old: old.cloned(),
+              new: new.cloned(),
+            },
+          )
+        })
+      })
+      .collect(),
+    run_if: (old.run_if != new.run_if).then(|| ValueChange {
+      old: old.run_if.clone(),
+      new: new.run_if.clone(),
+    }),
+  }
+}
+
+/// The nodes of a `map` template or a `while` body.
+fn template_of(node: &NodeDefinitionV2) -> Result<Option<Vec<NodeDefinitionV2>>> {
+  let key = match node.node_type.as_str() {
+    "map" => "template",
+    "while" => "do",
+    _ => return Ok(None),
+  };
+  node
+    .parameters
+    .get(key)
+    .map(|nodes| {
+      serde_yaml::from_value(nodes.clone()).with_context(|| {
+        format!(
+          "{} node '{}': '{}' must be a list of nodes",
+          node.node_type, node.id, key
+        )
+      })
+    })
+    .transpose()
+}
+
+/// Sources written `node.output`, as `Flow::diff` reports them, so
+/// `{{nodes.a.outputs.b}}` and `{{ nodes.a.outputs.b }}` are the same
+/// mapping. Anything else, such as `item.field`, is kept as written.
+fn mapping_of(node: &NodeDefinitionV2) -> BTreeMap<&str, String> {
+  node
+    .input_mapping
+    .iter()
+    .map(|(input, source)| {
+      let path = source
+        .trim()
+        .trim_start_matches("{{")
+        .trim_end_matches("}}")
+        .trim();
+      let parts: Vec<&str> = path.split('.').collect();
+      let source = match parts.as_slice() {
+        ["nodes", source, "outputs", output] => format!("{}.{}", source, output),
+        _ => path.to_string(),
+      };
+      (input.as_str(), source)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn definition(yaml: &str) -> FlowDefinitionV2 {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  #[test]
+  fn definitions_are_compared_without_building_nodes() {
+    let old = definition(
+      r#"
+name: review
+nodes:
+  - id: ask
+    type: llm
+    parameters:
+      prompt: "Summarise"
+      api_key: { $secret: OPENAI_API_KEY }
+  - id: publish
+    type: template
+    dependencies: [ask]
+    input_mapping:
+      body: "{{ nodes.ask.outputs.response }}"
+  - id: each
+    type: map
+    parameters:
+      template:
+        - id: step
+          type: template
+"#,
+    );
+    let new = definition(
+      r#"
+name: review
+nodes:
+  - id: ask
+    type: llm
+    parameters:
+      prompt: "Summarise briefly"
+      api_key: { $secret: OPENAI_API_KEY }
+  - id: publish
+    type: template
+    dependencies: [ask]
+    run_if: "{{ nodes.ask.outputs.ok }}"
+    input_mapping:
+      body: "{{nodes.ask.outputs.response}}"
+      title: "{{ nodes.ask.outputs.title }}"
+  - id: each
+    type: map
+    parameters:
+      template:
+        - id: step
+          type: llm
+"#,
+    );
+
+    let diff = diff_definitions(&old, &new).unwrap();
+    assert_eq!(
+      diff.to_string(),
+      "~ each/step\n    \
+       type: template -> llm\n\
+       ~ publish\n    \
+       input title: (none) -> ask.title\n    \
+       run_if: (none) -> {{ nodes.ask.outputs.ok }}\n"
+    );
+    assert!(diff_definitions(&old, &old).unwrap().is_empty());
+  }
+}
@@ -1,6 +1,16 @@
+pub mod approval;
+pub mod cost;
 pub mod debug;
+pub mod diff;
 pub mod dynamic;
+pub mod new;
+pub mod progress;
+pub mod quiet;
+pub mod report;
 pub mod resume_plan;
 pub mod run;
+pub mod run_many;
 pub mod server_ops;
 pub mod validate;
+pub mod vars;
+pub mod watch;
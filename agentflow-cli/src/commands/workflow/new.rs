@@ -0,0 +1,227 @@
+//! `agentflow workflow new <dir> --template <name>` — scaffold a
+//! commented starter `workflow.yml` and a matching `inputs.example.json`.
+//!
+//! Templates are embedded from `templates/`. One that uses a node type
+//! this build cannot construct (per [`NodeRegistry::schema_bundle`]) is
+//! listed as unavailable and refused, so a scaffold always validates.
+
+use anyhow::{Context, Result, bail};
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::registry::NodeRegistry;
+
+pub const WORKFLOW_FILE: &str = "workflow.yml";
+pub const INPUTS_FILE: &str = "inputs.example.json";
+
+/// Replaced with the workflow's name when a template is written.
+const NAME_PLACEHOLDER: &str = "__NAME__";
+
+pub struct Template {
+  pub name: &'static str,
+  pub description: &'static str,
+  workflow: &'static str,
+  inputs: &'static str,
+}
+
+pub const TEMPLATES: &[Template] = &[
+  Template {
+    name: "basic",
+    description: "Two template nodes, the second using the first's output",
+    workflow: include_str!("templates/basic.yml"),
+    inputs: include_str!("templates/basic.inputs.json"),
+  },
+  Template {
+    name: "llm-chain",
+    description: "An outline LLM call feeding a drafting LLM call",
+    workflow: include_str!("templates/llm-chain.yml"),
+    inputs: include_str!("templates/llm-chain.inputs.json"),
+  },
+  Template {
+    name: "map-reduce",
+    description: "Summarize each document in parallel, then combine the summaries",
+    workflow: include_str!("templates/map-reduce.yml"),
+    inputs: include_str!("templates/map-reduce.inputs.json"),
+  },
+  Template {
+    name: "rag",
+    description: "Retrieve from a Qdrant collection and answer with an LLM",
+    workflow: include_str!("templates/rag.yml"),
+    inputs: include_str!("templates/rag.inputs.json"),
+  },
+  Template {
+    name: "agent-loop",
+    description: "A while loop letting an LLM refine its answer until it is done",
+    workflow: include_str!("templates/agent-loop.yml"),
+    inputs: include_str!("templates/agent-loop.inputs.json"),
+  },
+];
+
+impl Template {
+  pub fn find(name: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|template| template.name == name)
+  }
+
+  /// Every node type the template uses, including nested `map` /
+  /// `while` sub-workflows.
+  pub fn node_types(&self) -> BTreeSet<String> {
+    let mut types = BTreeSet::new();
+    if let Ok(document) = serde_yaml::from_str::<Value>(self.workflow) {
+      collect_node_types(&document["nodes"], &mut types);
+    }
+    types
+  }
+
+  /// Node types the template uses that this build cannot construct.
+  pub fn missing_node_types(&self) -> Vec<String> {
+    let bundle = NodeRegistry::schema_bundle();
+    self
+      .node_types()
+      .into_iter()
+      .filter(|node_type| bundle["$defs"].get(node_type).is_none())
+      .collect()
+  }
+
+  /// The workflow YAML with its name filled in.
+  pub fn render_workflow(&self, workflow_name: &str) -> String {
+    // A JSON string is a valid YAML scalar, whatever the name contains.
+    let quoted = serde_json::to_string(workflow_name).unwrap_or_default();
+    self
+      .workflow
+      .replace(&format!("\"{NAME_PLACEHOLDER}\""), &quoted)
+      .replace(NAME_PLACEHOLDER, workflow_name)
+  }
+}
+
+fn collect_node_types(nodes: &Value, types: &mut BTreeSet<String>) {
+  for node in nodes.as_sequence().into_iter().flatten() {
+    if let Some(node_type) = node["type"].as_str() {
+      types.insert(node_type.to_string());
+    }
+    for nested in ["template", "do"] {
+      collect_node_types(&node["parameters"][nested], types);
+    }
+  }
+}
+
+pub async fn execute(dir: Option<String>, template: String, list: bool, force: bool) -> Result<()> {
+  if list {
+    print_templates();
+    return Ok(());
+  }
+  let Some(dir) = dir else {
+    bail!("Pass the directory to create, or --list to see the templates");
+  };
+  let Some(template) = Template::find(&template) else {
+    bail!(
+      "Unknown template '{}'; available: {}",
+      template,
+      TEMPLATES
+        .iter()
+        .map(|template| template.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+    );
+  };
+  let missing = template.missing_node_types();
+  if !missing.is_empty() {
+    bail!(
+      "Template '{}' uses node type(s) not enabled in this build: {}",
+      template.name,
+      missing.join(", ")
+    );
+  }
+
+  let dir = PathBuf::from(dir);
+  let workflow_name = dir
+    .file_name()
+    .and_then(|name| name.to_str())
+    .filter(|name| !name.is_empty())
+    .context("Could not infer a workflow name from the directory")?
+    .to_string();
+  let workflow_path = dir.join(WORKFLOW_FILE);
+  let inputs_path = dir.join(INPUTS_FILE);
+  if !force {
+    for path in [&workflow_path, &inputs_path] {
+      if path.exists() {
+        bail!(
+          "Refusing to overwrite existing file '{}'; pass --force to overwrite it",
+          path.display()
+        );
+      }
+    }
+  }
+
+  fs::create_dir_all(&dir)
+    .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+  write_file(&workflow_path, &template.render_workflow(&workflow_name))?;
+  write_file(&inputs_path, template.inputs)?;
+
+  println!(
+    "Created workflow '{}' from the {} template in {}",
+    workflow_name,
+    template.name,
+    dir.display()
+  );
+  println!("  {}", WORKFLOW_FILE);
+  println!("  {}", INPUTS_FILE);
+  println!();
+  println!(
+    "Next: agentflow workflow run {} --inputs-file {}",
+    workflow_path.display(),
+    inputs_path.display()
+  );
+  Ok(())
+}
+
+fn print_templates() {
+  let width = TEMPLATES
+    .iter()
+    .map(|template| template.name.len())
+    .max()
+    .unwrap_or(0);
+  println!("Workflow templates:");
+  for template in TEMPLATES {
+    let missing = template.missing_node_types();
+    let note = if missing.is_empty() {
+      String::new()
+    } else {
+      format!(" (unavailable: needs {})", missing.join(", "))
+    };
+    println!(
+      "  {:<width$}  {}{}",
+      template.name, template.description, note
+    );
+  }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+  fs::write(path, content).with_context(|| format!("Failed to write '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn node_types_include_nested_workflows() {
+    let map_reduce = Template::find("map-reduce").unwrap();
+    assert_eq!(
+      map_reduce.node_types().into_iter().collect::<Vec<_>>(),
+      ["llm", "map"]
+    );
+    let agent_loop = Template::find("agent-loop").unwrap();
+    assert!(agent_loop.node_types().contains("while"));
+  }
+
+  #[test]
+  fn rendered_names_are_quoted_for_yaml() {
+    let basic = Template::find("basic").unwrap();
+    let yaml = basic.render_workflow("say \"hi\"");
+    let document: Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(document["name"].as_str(), Some("say \"hi\""));
+    assert!(yaml.starts_with("# say \"hi\": a starter"));
+  }
+}
@@ -0,0 +1,353 @@
+//! `workflow run` live node status.
+//!
+//! [`RunProgress`] is an [`EventListener`] sitting in front of the trace
+//! collector. On a terminal it draws one indicatif line per node:
+//! pending, running (with elapsed time), done, failed or skipped. Map
+//! nodes add their item count from `map.progress` events and streaming
+//! LLM nodes their received chunks / tokens from `llm.stream` events.
+//! With `--no-progress`, or when stderr is not a terminal, the same
+//! transitions are written as plain stderr lines instead.
+//!
+//! `-v` echoes a one-line preview of each completed node's outputs and
+//! `-vv` the full outputs; both are redacted.
+
+use super::report::{decode_flow_values, format_duration};
+use crate::redaction::{redact_cli_text, redact_cli_value};
+use agentflow_core::events::{EventListener, WorkflowEvent};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Longest `-v` output preview.
+const PREVIEW_CHARS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+  /// Redrawn per-node lines on stderr.
+  Bars,
+  /// One stderr line per node transition.
+  Plain,
+}
+
+impl ProgressMode {
+  /// Bars on an interactive stderr unless `--no-progress` was given.
+  pub fn detect(no_progress: bool) -> Self {
+    if !no_progress && std::io::IsTerminal::is_terminal(&std::io::stderr()) {
+      Self::Bars
+    } else {
+      Self::Plain
+    }
+  }
+}
+
+/// What is known about a node beyond its state.
+#[derive(Default)]
+struct NodeLine {
+  bar: Option<ProgressBar>,
+  items: Option<(u64, u64)>,
+  chunks: Option<u64>,
+  tokens: Option<u64>,
+  outputs: Option<Map<String, Value>>,
+}
+
+impl NodeLine {
+  /// `2/5 items`, `14 chunks` or `120 tokens`, joined with `·`.
+  fn counters(&self) -> String {
+    let mut parts = Vec::new();
+    if let Some((done, total)) = self.items {
+      parts.push(format!("{done}/{total} items"));
+    }
+    match (self.tokens, self.chunks) {
+      (Some(tokens), _) => parts.push(format!("{tokens} tokens")),
+      (None, Some(chunks)) => parts.push(format!("{chunks} chunks")),
+      (None, None) => {}
+    }
+    parts.join(" · ")
+  }
+}
+
+pub struct RunProgress {
+  verbose: u8,
+  multi: Option<MultiProgress>,
+  width: usize,
+  nodes: Mutex<HashMap<String, NodeLine>>,
+  inner: Option<Arc<dyn EventListener>>,
+}
+
+impl RunProgress {
+  /// A display with one pending line per node in `order`, forwarding
+  /// every event to `inner`.
+  pub fn new(
+    mode: ProgressMode,
+    verbose: u8,
+    order: &[String],
+    inner: Option<Arc<dyn EventListener>>,
+  ) -> Self {
+    let width = order.iter().map(|id| id.chars().count()).max().unwrap_or(0);
+    let multi = (mode == ProgressMode::Bars)
+      .then(|| MultiProgress::with_draw_target(ProgressDrawTarget::stderr()));
+    let nodes = order
+      .iter()
+      .map(|id| {
+        let bar = multi.as_ref().map(|multi| {
+          let bar = multi.add(ProgressBar::new_spinner());
+          bar.set_style(static_style(width));
+          bar.set_prefix(id.clone());
+          bar.set_message("· pending");
+          bar
+        });
+        (
+          id.clone(),
+          NodeLine {
+            bar,
+            ..NodeLine::default()
+          },
+        )
+      })
+      .collect();
+    Self {
+      verbose,
+      multi,
+      width,
+      nodes: Mutex::new(nodes),
+      inner,
+    }
+  }
+
+  /// Freezes the lines of nodes that never finished as `not run`.
+  pub fn finish(&self) {
+    for line in self
+      .nodes
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .values()
+    {
+      if let Some(bar) = &line.bar
+        && !bar.is_finished()
+      {
+        bar.set_style(static_style(self.width));
+        bar.finish_with_message("⏸ not run");
+      }
+    }
+  }
+
+  /// Runs `f` with the bars hidden, e.g. to prompt on stderr.
+  pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+    match &self.multi {
+      Some(multi) => multi.suspend(f),
+      None => f(),
+    }
+  }
+
+  fn handle(&self, event: &WorkflowEvent) {
+    let mut nodes = self.nodes.lock().unwrap_or_else(|e| e.into_inner());
+    match event {
+      WorkflowEvent::NodeStarted { node_id, .. } => {
+        let line = nodes.entry(node_id.clone()).or_default();
+        match &line.bar {
+          Some(bar) => {
+            bar.set_style(running_style(self.width));
+            bar.set_message(String::new());
+            bar.reset_elapsed();
+            bar.enable_steady_tick(Duration::from_millis(120));
+          }
+          None => self.say(format!("▶ {node_id} started")),
+        }
+      }
+      WorkflowEvent::NodeProgress {
+        node_id, detail, ..
+      } => {
+        let line = nodes.entry(node_id.clone()).or_default();
+        let number = |key: &str| detail.get(key).and_then(Value::as_u64);
+        match detail.get("event").and_then(Value::as_str) {
+          Some("map.progress") => {
+            let (Some(done), Some(total)) = (number("completed"), number("total")) else {
+              return;
+            };
+            line.items = Some((done, total));
+            if line.bar.is_none() && done > 0 {
+              self.say(format!("… {node_id} {done}/{total} items"));
+            }
+          }
+          // Too frequent for plain lines; the total shows on completion.
+          Some("llm.stream") => {
+            line.chunks = number("chunks");
+            line.tokens = number("completion_tokens").or(line.tokens);
+          }
+          _ => return,
+        }
+        if let Some(bar) = &line.bar {
+          bar.set_message(line.counters());
+        }
+      }
+      WorkflowEvent::NodeOutputCaptured {
+        node_id, output, ..
+      } => {
+        let line = nodes.entry(node_id.clone()).or_default();
+        let outputs = decode_flow_values(output);
+        if let Some(tokens) = outputs
+          .get("usage")
+          .and_then(|usage| usage.get("completion_tokens"))
+          .and_then(Value::as_u64)
+        {
+          line.tokens = Some(tokens);
+        }
+        line.outputs = Some(outputs);
+      }
+      WorkflowEvent::NodeCompleted {
+        node_id, duration, ..
+      } => {
+        let line = nodes.entry(node_id.clone()).or_default();
+        let mut status = format!("done in {}", elapsed(*duration));
+        let counters = line.counters();
+        if !counters.is_empty() {
+          status = format!("{status} · {counters}");
+        }
+        self.end(node_id, line, format!("✓ {status}"));
+        if self.verbose > 0
+          && let Some(outputs) = line.outputs.take()
+        {
+          self.say(echo_outputs(node_id, outputs, self.verbose));
+        }
+      }
+      WorkflowEvent::NodeFailed {
+        node_id,
+        error,
+        duration,
+        ..
+      } => {
+        let line = nodes.entry(node_id.clone()).or_default();
+        let error = redact_cli_text(error.lines().next().unwrap_or_default());
+        self.end(
+          node_id,
+          line,
+          format!("✗ failed in {}: {}", elapsed(*duration), error),
+        );
+      }
+      WorkflowEvent::NodeSkipped {
+        node_id, reason, ..
+      } => {
+        let line = nodes.entry(node_id.clone()).or_default();
+        self.end(node_id, line, format!("⏭ skipped: {reason}"));
+      }
+      _ => {}
+    }
+  }
+
+  /// Freezes a node's line at its final state (or prints it in plain
+  /// mode).
+  fn end(&self, node_id: &str, line: &NodeLine, status: String) {
+    match &line.bar {
+      Some(bar) => {
+        bar.set_style(static_style(self.width));
+        bar.finish_with_message(status);
+      }
+      None => {
+        let (mark, rest) = status.split_once(' ').unwrap_or((&status, ""));
+        self.say(format!("{mark} {node_id} {rest}"));
+      }
+    }
+  }
+
+  /// A line above the bars, or on stderr in plain mode.
+  fn say(&self, text: String) {
+    match &self.multi {
+      Some(multi) => {
+        let _ = multi.println(text);
+      }
+      None => eprintln!("{}", text),
+    }
+  }
+}
+
+impl EventListener for RunProgress {
+  fn on_event(&self, event: &WorkflowEvent) {
+    self.handle(event);
+    if let Some(inner) = &self.inner {
+      inner.on_event(event);
+    }
+  }
+}
+
+fn running_style(width: usize) -> ProgressStyle {
+  ProgressStyle::with_template(&format!(
+    "{{spinner:.cyan}} {{prefix:{width}}}  running {{elapsed:>4}}  {{msg}}"
+  ))
+  .unwrap_or_else(|_| ProgressStyle::default_spinner())
+}
+
+fn static_style(width: usize) -> ProgressStyle {
+  ProgressStyle::with_template(&format!("  {{prefix:{width}}}  {{msg}}"))
+    .unwrap_or_else(|_| ProgressStyle::default_spinner())
+}
+
+fn elapsed(duration: Duration) -> String {
+  format_duration(Some(duration.as_millis() as u64))
+}
+
+/// `-v`: `  ↳ key: value; …` on one line; `-vv`: the pretty outputs.
+fn echo_outputs(node_id: &str, outputs: Map<String, Value>, verbose: u8) -> String {
+  let mut outputs = Value::Object(outputs);
+  redact_cli_value(&mut outputs);
+  if verbose > 1 {
+    let body = serde_json::to_string_pretty(&outputs).unwrap_or_default();
+    return format!("  ↳ {node_id} outputs:\n{body}");
+  }
+  let preview = outputs
+    .as_object()
+    .map(|outputs| {
+      outputs
+        .iter()
+        .map(|(key, value)| match value {
+          Value::String(text) => format!("{key}: {text}"),
+          other => format!("{key}: {other}"),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+    })
+    .unwrap_or_default();
+  let preview = preview.split_whitespace().collect::<Vec<_>>().join(" ");
+  let preview = if preview.chars().count() > PREVIEW_CHARS {
+    let mut short: String = preview.chars().take(PREVIEW_CHARS - 1).collect();
+    short.push('…');
+    short
+  } else {
+    preview
+  };
+  format!("  ↳ {node_id}: {preview}")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn counters_prefer_tokens_over_chunks() {
+    let mut line = NodeLine {
+      items: Some((2, 5)),
+      chunks: Some(14),
+      ..NodeLine::default()
+    };
+    assert_eq!(line.counters(), "2/5 items · 14 chunks");
+    line.tokens = Some(120);
+    assert_eq!(line.counters(), "2/5 items · 120 tokens");
+  }
+
+  #[test]
+  fn captured_outputs_are_decoded_and_previewed() {
+    let output = json!({
+      "output": { "type": "json", "value": "hello\n  world" },
+      "stats": { "type": "json", "value": { "words": 2 } },
+    });
+    let outputs = decode_flow_values(&output);
+    assert_eq!(outputs["stats"]["words"], 2);
+    assert_eq!(
+      echo_outputs("ask", outputs.clone(), 1),
+      r#"  ↳ ask: output: hello world; stats: {"words":2}"#
+    );
+    assert!(echo_outputs("ask", outputs, 2).starts_with("  ↳ ask outputs:\n{"));
+  }
+}
@@ -0,0 +1,67 @@
+//! `workflow run --quiet`: silence stdout while the flow runs.
+//!
+//! Progress lines come from the CLI, the flow executor and the nodes
+//! themselves, so the only place to suppress all of them is the process's
+//! stdout descriptor. [`QuietStdout`] points it at `/dev/null` and puts
+//! it back on drop; stderr, where errors go, is untouched. On non-Unix
+//! platforms it is a no-op.
+
+use std::io::Write;
+
+pub struct QuietStdout {
+  #[cfg(unix)]
+  saved: Option<libc::c_int>,
+}
+
+impl QuietStdout {
+  pub fn new() -> Self {
+    let _ = std::io::stdout().flush();
+    Self {
+      #[cfg(unix)]
+      saved: silence(),
+    }
+  }
+}
+
+impl Default for QuietStdout {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(unix)]
+fn silence() -> Option<libc::c_int> {
+  use std::os::fd::AsRawFd;
+  let null = std::fs::OpenOptions::new()
+    .write(true)
+    .open("/dev/null")
+    .ok()?;
+  // SAFETY: plain descriptor calls on stdout and a file we own; every
+  // failure is checked and leaves stdout as it was.
+  unsafe {
+    let saved = libc::dup(libc::STDOUT_FILENO);
+    if saved < 0 {
+      return None;
+    }
+    if libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+      libc::close(saved);
+      return None;
+    }
+    Some(saved)
+  }
+}
+
+impl Drop for QuietStdout {
+  fn drop(&mut self) {
+    let _ = std::io::stdout().flush();
+    #[cfg(unix)]
+    if let Some(saved) = self.saved.take() {
+      // SAFETY: `saved` is the descriptor `silence` duplicated from
+      // stdout and nothing else closes it.
+      unsafe {
+        libc::dup2(saved, libc::STDOUT_FILENO);
+        libc::close(saved);
+      }
+    }
+  }
+}
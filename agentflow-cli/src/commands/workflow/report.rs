@@ -0,0 +1,586 @@
+//! `workflow run` results: a per-node report written by `--output` as
+//! JSON, YAML or Markdown, and the summary table printed at the end of
+//! every run.
+//!
+//! A workflow that declares `outputs:` gets those values at the top level
+//! of the JSON and YAML reports, with the full report under `debug`.
+//!
+//! Node outputs are serialized the same way in every format: a JSON
+//! value is written as itself, and a file or URL reference as
+//! `{ "type": "file", "path", "mime_type" }` /
+//! `{ "type": "url", "url", "mime_type" }`.
+
+use super::cost::CostReport;
+use crate::config::io::OutputRef;
+use crate::redaction::{redact_cli_text, redact_cli_value};
+use agentflow_core::{
+  async_node::AsyncNodeResult, error::AgentFlowError, events::EventListener, events::WorkflowEvent,
+  value::FlowValue,
+};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Longest output preview shown in the summary table.
+const PREVIEW_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+  Json,
+  Yaml,
+  Markdown,
+}
+
+impl OutputFormat {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw {
+      "json" => Ok(Self::Json),
+      "yaml" => Ok(Self::Yaml),
+      "markdown" => Ok(Self::Markdown),
+      other => bail!("Unknown --output-format '{other}'; expected json, yaml or markdown"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+  Completed,
+  Failed,
+  Skipped,
+  /// The node never produced a result, e.g. the run hit its deadline.
+  NotRun,
+}
+
+impl NodeStatus {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Completed => "completed",
+      Self::Failed => "failed",
+      Self::Skipped => "skipped",
+      Self::NotRun => "not_run",
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      Self::Completed => "✅ completed",
+      Self::Failed => "❌ failed",
+      Self::Skipped => "⏭️ skipped",
+      Self::NotRun => "⏸️ not run",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeReport {
+  pub id: String,
+  pub status: NodeStatus,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub duration_ms: Option<u64>,
+  #[serde(default, skip_serializing_if = "Map::is_empty")]
+  pub outputs: Map<String, Value>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunReport {
+  pub workflow: String,
+  pub workflow_id: String,
+  /// `failed` when any node failed, else `completed`.
+  pub status: NodeStatus,
+  pub duration_ms: u64,
+  /// Nodes in execution order.
+  pub nodes: Vec<NodeReport>,
+  /// Token usage and cost, when any node called a model.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost: Option<CostReport>,
+  /// The workflow's declared `outputs:`, when it has any; rendered
+  /// around the report rather than in it.
+  #[serde(skip)]
+  pub declared_outputs: Option<Map<String, Value>>,
+}
+
+/// The `--output` document of a workflow with declared outputs.
+#[derive(Serialize)]
+struct DeclaredOutputsDocument<'a> {
+  #[serde(flatten)]
+  outputs: &'a Map<String, Value>,
+  debug: &'a RunReport,
+}
+
+impl RunReport {
+  /// Builds the report from a finished run's state pool. Outputs and
+  /// errors are redacted.
+  pub fn from_state(
+    workflow: &str,
+    workflow_id: &str,
+    duration: Duration,
+    order: &[String],
+    state: &HashMap<String, AsyncNodeResult>,
+    timings: &NodeTimings,
+  ) -> Self {
+    let timings = timings.snapshot();
+    let mut ids: Vec<&String> = order.iter().collect();
+    let mut extra: Vec<&String> = state.keys().filter(|id| !order.contains(id)).collect();
+    extra.sort();
+    ids.extend(extra);
+
+    let nodes: Vec<NodeReport> = ids
+      .into_iter()
+      .map(|id| {
+        let (status, outputs, error) = match state.get(id) {
+          Some(Ok(outputs)) => {
+            let mut outputs: Map<String, Value> = outputs
+              .iter()
+              .map(|(key, value)| (key.clone(), flow_value_json(value)))
+              .collect();
+            outputs.sort_keys();
+            let mut outputs = Value::Object(outputs);
+            redact_cli_value(&mut outputs);
+            let Value::Object(outputs) = outputs else {
+              unreachable!("redaction keeps objects as objects")
+            };
+            (NodeStatus::Completed, outputs, None)
+          }
+          Some(Err(AgentFlowError::NodeSkipped)) => (NodeStatus::Skipped, Map::new(), None),
+          Some(Err(err)) => (
+            NodeStatus::Failed,
+            Map::new(),
+            Some(redact_cli_text(err.to_string())),
+          ),
+          None => (NodeStatus::NotRun, Map::new(), None),
+        };
+        NodeReport {
+          id: id.clone(),
+          status,
+          duration_ms: timings.get(id).map(|duration| duration.as_millis() as u64),
+          outputs,
+          error,
+        }
+      })
+      .collect();
+
+    let status = if nodes.iter().any(|node| node.status == NodeStatus::Failed) {
+      NodeStatus::Failed
+    } else {
+      NodeStatus::Completed
+    };
+    Self {
+      workflow: workflow.to_string(),
+      workflow_id: workflow_id.to_string(),
+      status,
+      duration_ms: duration.as_millis() as u64,
+      nodes,
+      cost: None,
+      declared_outputs: None,
+    }
+  }
+
+  pub fn with_cost(mut self, cost: Option<CostReport>) -> Self {
+    self.cost = cost;
+    self
+  }
+
+  /// Reads the workflow's `outputs:` (name to `nodes.<id>.outputs.<key>`)
+  /// from the node outputs; one the run did not produce is null.
+  pub fn with_declared_outputs(mut self, outputs: &HashMap<String, String>) -> Self {
+    if outputs.is_empty() {
+      return self;
+    }
+    let mut names: Vec<&String> = outputs.keys().collect();
+    names.sort();
+    let declared: Map<String, Value> = names
+      .into_iter()
+      .map(|name| {
+        let value = OutputRef::parse(&outputs[name])
+          .and_then(|output| {
+            self
+              .nodes
+              .iter()
+              .find(|node| node.id == output.node)
+              .and_then(|node| node.outputs.get(&output.output).cloned())
+          })
+          .unwrap_or(Value::Null);
+        (name.clone(), value)
+      })
+      .collect();
+    self.declared_outputs = Some(declared);
+    self
+  }
+
+  /// Parses an `--output` JSON file, with or without declared outputs.
+  pub fn from_output_json(text: &str) -> Option<Self> {
+    let mut document: Value = serde_json::from_str(text).ok()?;
+    let report = match document.get_mut("debug").map(Value::take) {
+      Some(debug) => debug,
+      None => document,
+    };
+    serde_json::from_value(report).ok()
+  }
+
+  pub fn failed_nodes(&self) -> Vec<&str> {
+    self
+      .nodes
+      .iter()
+      .filter(|node| node.status == NodeStatus::Failed)
+      .map(|node| node.id.as_str())
+      .collect()
+  }
+
+  pub fn render(&self, format: OutputFormat) -> Result<String> {
+    if let Some(outputs) = &self.declared_outputs
+      && format != OutputFormat::Markdown
+    {
+      let document = DeclaredOutputsDocument {
+        outputs,
+        debug: self,
+      };
+      return Ok(match format {
+        OutputFormat::Yaml => serde_yaml::to_string(&document)?,
+        _ => serde_json::to_string_pretty(&document)?,
+      });
+    }
+    Ok(match format {
+      OutputFormat::Json => serde_json::to_string_pretty(self)?,
+      OutputFormat::Yaml => serde_yaml::to_string(self)?,
+      OutputFormat::Markdown => self.to_markdown(),
+    })
+  }
+
+  /// A summary table plus every node's full outputs in a collapsible
+  /// `<details>` section.
+  pub fn to_markdown(&self) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", self.workflow);
+    let _ = writeln!(out, "- **Status:** {}", self.status.label());
+    let _ = writeln!(out, "- **Workflow ID:** `{}`", self.workflow_id);
+    let _ = writeln!(
+      out,
+      "- **Duration:** {}",
+      format_duration(Some(self.duration_ms))
+    );
+    let _ = writeln!(out, "- **Nodes:** {}\n", self.counts());
+
+    if let Some(outputs) = &self.declared_outputs {
+      let body = serde_json::to_string_pretty(outputs).unwrap_or_default();
+      let _ = writeln!(out, "## Workflow outputs\n\n```json\n{}\n```\n", body);
+    }
+    out.push_str("## Summary\n\n");
+    out.push_str("| Node | Status | Duration | Outputs |\n");
+    out.push_str("| --- | --- | --- | --- |\n");
+    for node in &self.nodes {
+      let _ = writeln!(
+        out,
+        "| `{}` | {} | {} | {} |",
+        node.id,
+        node.status.label(),
+        format_duration(node.duration_ms),
+        table_cell(&node_preview(node))
+      );
+    }
+
+    out.push_str("\n## Outputs\n");
+    for node in &self.nodes {
+      let _ = write!(
+        out,
+        "\n<details>\n<summary><code>{}</code> — {}</summary>\n\n",
+        node.id,
+        node.status.label()
+      );
+      if let Some(error) = &node.error {
+        let _ = writeln!(out, "```text\n{}\n```", error);
+      } else if node.outputs.is_empty() {
+        out.push_str("_No outputs._\n");
+      } else {
+        let body = serde_json::to_string_pretty(&node.outputs).unwrap_or_default();
+        let _ = writeln!(out, "```json\n{}\n```", body);
+      }
+      out.push_str("\n</details>\n");
+    }
+    if let Some(cost) = &self.cost {
+      out.push_str("\n## Usage and cost\n\n");
+      out.push_str(&cost.to_markdown());
+    }
+    out
+  }
+
+  /// The end-of-run table printed to the terminal.
+  pub fn print_summary(&self) {
+    let width = self
+      .nodes
+      .iter()
+      .map(|node| node.id.chars().count())
+      .max()
+      .unwrap_or(4)
+      .max(4);
+    println!("\n📊 Run summary ({}):", self.counts());
+    for node in &self.nodes {
+      println!(
+        "  {:<width$}  {:<14}  {:>8}  {}",
+        node.id,
+        node.status.label(),
+        format_duration(node.duration_ms),
+        node_preview(node),
+        width = width
+      );
+    }
+    if let Some(outputs) = &self.declared_outputs {
+      println!("\n📤 Workflow outputs:");
+      for (name, value) in outputs {
+        let text = match value {
+          Value::String(text) => text.clone(),
+          other => other.to_string(),
+        };
+        println!(
+          "  {}: {}",
+          name,
+          truncate(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+        );
+      }
+    }
+    if let Some(cost) = &self.cost {
+      cost.print_table();
+    }
+  }
+
+  fn counts(&self) -> String {
+    let count = |status| {
+      self
+        .nodes
+        .iter()
+        .filter(|node| node.status == status)
+        .count()
+    };
+    let mut parts = vec![
+      format!("{} completed", count(NodeStatus::Completed)),
+      format!("{} failed", count(NodeStatus::Failed)),
+      format!("{} skipped", count(NodeStatus::Skipped)),
+    ];
+    let not_run = count(NodeStatus::NotRun);
+    if not_run > 0 {
+      parts.push(format!("{} not run", not_run));
+    }
+    parts.join(", ")
+  }
+}
+
+/// The JSON form of a node output shared by every report format.
+pub fn flow_value_json(value: &FlowValue) -> Value {
+  match value {
+    FlowValue::Json(value) => value.clone(),
+    FlowValue::File { path, mime_type } => json!({
+      "type": "file",
+      "path": path,
+      "mime_type": mime_type,
+    }),
+    FlowValue::Url { url, mime_type } => json!({
+      "type": "url",
+      "url": url,
+      "mime_type": mime_type,
+    }),
+  }
+}
+
+/// Turns outputs serialized as [`FlowValue`]s (`{"type": "json",
+/// "value": …}`), as in `NodeOutputCaptured` events and persisted
+/// `<node>_outputs.json` files, back into the report's JSON form.
+pub fn decode_flow_values(outputs: &Value) -> Map<String, Value> {
+  let Some(map) = outputs.as_object() else {
+    return Map::new();
+  };
+  let mut decoded: Map<String, Value> = map
+    .iter()
+    .map(|(key, value)| {
+      let value = serde_json::from_value::<FlowValue>(value.clone())
+        .map(|value| flow_value_json(&value))
+        .unwrap_or_else(|_| value.clone());
+      (key.clone(), value)
+    })
+    .collect();
+  decoded.sort_keys();
+  decoded
+}
+
+fn node_preview(node: &NodeReport) -> String {
+  let text = match &node.error {
+    Some(error) => error.clone(),
+    None => node
+      .outputs
+      .iter()
+      .map(|(key, value)| match value {
+        Value::String(text) => format!("{key}: {text}"),
+        other => format!("{key}: {other}"),
+      })
+      .collect::<Vec<_>>()
+      .join("; "),
+  };
+  truncate(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn truncate(text: &str) -> String {
+  if text.chars().count() <= PREVIEW_CHARS {
+    return text.to_string();
+  }
+  let mut short: String = text.chars().take(PREVIEW_CHARS - 1).collect();
+  short.push('…');
+  short
+}
+
+fn table_cell(text: &str) -> String {
+  text.replace('|', "\\|")
+}
+
+pub(crate) fn format_duration(ms: Option<u64>) -> String {
+  match ms {
+    None => "—".to_string(),
+    Some(ms) if ms < 1_000 => format!("{ms} ms"),
+    Some(ms) => format!("{:.2} s", ms as f64 / 1_000.0),
+  }
+}
+
+/// Event listener recording how long each node ran, forwarding every
+/// event to `inner` (the trace collector, when tracing is on).
+#[derive(Clone, Default)]
+pub struct NodeTimings {
+  durations: Arc<Mutex<HashMap<String, Duration>>>,
+  inner: Option<Arc<dyn EventListener>>,
+}
+
+impl NodeTimings {
+  pub fn forwarding_to(inner: Option<Arc<dyn EventListener>>) -> Self {
+    Self {
+      durations: Arc::default(),
+      inner,
+    }
+  }
+
+  fn snapshot(&self) -> HashMap<String, Duration> {
+    self
+      .durations
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clone()
+  }
+}
+
+impl EventListener for NodeTimings {
+  fn on_event(&self, event: &WorkflowEvent) {
+    match event {
+      WorkflowEvent::NodeCompleted {
+        node_id, duration, ..
+      }
+      | WorkflowEvent::NodeFailed {
+        node_id, duration, ..
+      } => {
+        self
+          .durations
+          .lock()
+          .unwrap_or_else(|e| e.into_inner())
+          .insert(node_id.clone(), *duration);
+      }
+      _ => {}
+    }
+    if let Some(inner) = &self.inner {
+      inner.on_event(event);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn previews_are_flattened_truncated_and_escaped() {
+    let node = NodeReport {
+      id: "n".into(),
+      status: NodeStatus::Completed,
+      duration_ms: Some(1_250),
+      outputs: [(
+        "output".to_string(),
+        json!(format!("a | b\n{}", "x".repeat(100))),
+      )]
+      .into_iter()
+      .collect(),
+      error: None,
+    };
+
+    let preview = node_preview(&node);
+    assert_eq!(preview.chars().count(), PREVIEW_CHARS);
+    assert!(preview.starts_with("output: a | b xxx") && preview.ends_with('…'));
+    assert!(table_cell(&preview).starts_with("output: a \\| b"));
+    assert_eq!(format_duration(node.duration_ms), "1.25 s");
+    assert_eq!(format_duration(Some(7)), "7 ms");
+  }
+
+  #[test]
+  fn declared_outputs_lead_the_report_with_the_rest_under_debug() {
+    let node = |id: &str, outputs: Value| NodeReport {
+      id: id.into(),
+      status: NodeStatus::Completed,
+      duration_ms: None,
+      outputs: outputs.as_object().cloned().unwrap_or_default(),
+      error: None,
+    };
+    let report = RunReport {
+      workflow: "Declared".into(),
+      workflow_id: "run-1".into(),
+      status: NodeStatus::Completed,
+      duration_ms: 10,
+      nodes: vec![
+        node("draft", json!({ "output": "first draft" })),
+        node("review", json!({ "output": "final", "score": 8 })),
+      ],
+      cost: None,
+      declared_outputs: None,
+    }
+    .with_declared_outputs(&HashMap::from([
+      (
+        "answer".to_string(),
+        "nodes.review.outputs.output".to_string(),
+      ),
+      (
+        "score".to_string(),
+        "{{ nodes.review.outputs.score }}".to_string(),
+      ),
+      (
+        "notes".to_string(),
+        "nodes.review.outputs.notes".to_string(),
+      ),
+    ]));
+
+    let rendered: Value =
+      serde_json::from_str(&report.render(OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(rendered["answer"], json!("final"));
+    assert_eq!(rendered["score"], json!(8));
+    assert_eq!(rendered["notes"], Value::Null);
+    assert_eq!(rendered["debug"]["workflow_id"], json!("run-1"));
+    assert_eq!(
+      rendered["debug"]["nodes"][0]["outputs"]["output"],
+      json!("first draft")
+    );
+    assert!(rendered.get("nodes").is_none());
+
+    let yaml: Value = serde_yaml::from_str(&report.render(OutputFormat::Yaml).unwrap()).unwrap();
+    assert_eq!(yaml["answer"], json!("final"));
+    assert!(report.to_markdown().contains("## Workflow outputs"));
+
+    // `run-many` still reads the run id and cost from either shape
+    let text = report.render(OutputFormat::Json).unwrap();
+    assert_eq!(
+      RunReport::from_output_json(&text).unwrap().workflow_id,
+      "run-1"
+    );
+    let plain = serde_json::to_string(&report).unwrap();
+    assert_eq!(
+      RunReport::from_output_json(&plain).unwrap().workflow_id,
+      "run-1"
+    );
+  }
+}
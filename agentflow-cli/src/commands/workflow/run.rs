@@ -1,10 +1,26 @@
-use crate::redaction::redact_cli_value;
+use super::approval::{ApprovalMode, ApprovalPrompter};
+use super::cost::{self, CostReport};
+use super::progress::{ProgressMode, RunProgress};
+use super::quiet::QuietStdout;
+use super::report::{NodeTimings, OutputFormat, RunReport};
+use super::vars::WorkflowVars;
+use crate::cli_error::CliError;
+use crate::commands::runs::{
+  manifest::{RunManifest, RunStatus},
+  resolve_run_root,
+};
+use crate::logging::TracingListener;
+use crate::redaction::redact_cli_text;
 use crate::shutdown::{DEFAULT_TRACE_FLUSH_TIMEOUT, SIGINT_EXIT_CODE, shutdown_signal};
 use crate::{
-  commands::workflow::validate::print_schema_report, config::schema::validate_flow_definition,
-  config::v2::FlowDefinitionV2, executor::build_flow_from_definition,
+  commands::workflow::validate::{parse_workflow, print_schema_report},
+  config::io::{ResolvedInputs, resolve_inputs},
+  config::schema::{
+    UnknownParameterMode, WorkflowValidationOptions, validate_flow_definition_with_options,
+  },
+  executor::build_flow_from_definition,
 };
-use agentflow_core::FlowExt;
+use agentflow_core::{EventListener, FlowExt, MultiListener};
 use agentflow_core::{
   FlowCancellationToken, FlowExecutionConfig, async_node::AsyncNodeInputs, flow::Flow,
   value::FlowValue,
@@ -13,54 +29,221 @@ use agentflow_tracing::{TraceCollector, TraceConfig, storage::file::FileTraceSto
 use anyhow::{Context, Result, bail};
 use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use uuid::Uuid;
 
+/// As the workflow file or `--inputs-file`, and as an `--input` value:
+/// read the document piped on stdin.
+pub const STDIN_ARG: &str = "-";
+
+/// The `workflow run` flags, kept together so `--watch` can re-run the
+/// workflow with the same settings.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+  /// A path, or [`STDIN_ARG`] for YAML piped on stdin.
+  pub workflow_file: String,
+  pub output: Option<String>,
+  /// `--output-format`: json, yaml or markdown.
+  pub output_format: String,
+  /// `--quiet`: silence progress output until the final summary.
+  pub quiet: bool,
+  /// `--no-progress`: plain status lines even on a terminal.
+  pub no_progress: bool,
+  /// `-v` / `-vv`: echo each completed node's outputs.
+  pub verbose: u8,
+  pub model: Option<String>,
+  pub input: Vec<(String, String)>,
+  /// `--inputs-file`: a JSON or YAML mapping loaded before `input`.
+  pub inputs_file: Option<String>,
+  /// Stdin, read once up front when the workflow file, `--inputs-file`
+  /// or an `--input KEY -` pair asks for it, so `--watch` re-runs see
+  /// the same document.
+  pub stdin: Option<String>,
+  pub dry_run: bool,
+  pub timeout: String,
+  pub max_retries: u32,
+  pub execution_mode: String,
+  pub max_concurrency: usize,
+  pub run_dir: Option<String>,
+  /// `--profile` / `--set` values substituted into the workflow YAML.
+  pub vars: WorkflowVars,
+  /// `--approval`: how pending `approval` nodes are answered.
+  pub approval: ApprovalMode,
+}
+
+/// How a single run ended.
+#[derive(Debug)]
+pub enum RunEnd<T> {
+  Completed,
+  /// The interrupt future resolved first; the flow was cancelled and the
+  /// trace drained before returning.
+  Interrupted(T),
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn execute(
-  workflow_file: String,
+  mut workflow_file: String,
   watch: bool,
-  output: Option<String>,
+  mut output: Option<String>,
+  output_format: String,
+  quiet: bool,
+  no_progress: bool,
+  verbose: u8,
   model: Option<String>,
-  input: Vec<(String, String)>,
+  mut input: Vec<(String, String)>,
+  mut inputs_file: Option<String>,
   dry_run: bool,
   timeout: String,
   max_retries: u32,
   execution_mode: String,
   max_concurrency: usize,
-  run_dir: Option<String>,
+  mut run_dir: Option<String>,
+  profile: Option<String>,
+  set: Vec<String>,
+  approval: String,
+  base_dir: Option<PathBuf>,
 ) -> Result<()> {
+  if watch && workflow_file == STDIN_ARG {
+    bail!(CliError::usage(
+      "--watch needs a workflow file to watch, not YAML piped on stdin; write the workflow to a file and run `agentflow workflow run <file> --watch`"
+    ));
+  }
+  let vars = WorkflowVars::load(profile.as_deref(), &set)?;
+  let approval = ApprovalMode::parse(&approval)?;
+  if let Some(base_dir) = &base_dir {
+    // The path flags keep meaning paths from where the command was
+    // typed; only the workflow's own relative paths move.
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+    let anchor = |path: String| {
+      if path.is_empty() || path == STDIN_ARG || Path::new(&path).is_absolute() {
+        path
+      } else {
+        cwd.join(path).to_string_lossy().into_owned()
+      }
+    };
+    workflow_file = anchor(workflow_file);
+    output = output.map(anchor);
+    inputs_file = inputs_file.map(anchor);
+    run_dir = run_dir
+      .or_else(|| std::env::var("AGENTFLOW_RUN_DIR").ok())
+      .map(anchor);
+    for (_, value) in &mut input {
+      if let Some(path) = value.strip_prefix('@')
+        && !path.starts_with('@')
+      {
+        *value = format!("@{}", anchor(path.to_string()));
+      }
+    }
+    std::env::set_current_dir(base_dir)
+      .with_context(|| format!("Failed to enter --base-dir {}", base_dir.display()))?;
+  }
+  let stdin = read_stdin(&workflow_file, inputs_file.as_deref(), &input)?;
+  let options = RunOptions {
+    workflow_file,
+    output,
+    output_format,
+    quiet,
+    no_progress,
+    verbose,
+    model,
+    input,
+    inputs_file,
+    stdin,
+    dry_run,
+    timeout,
+    max_retries,
+    execution_mode,
+    max_concurrency,
+    run_dir,
+    vars,
+    approval,
+  };
   if watch {
-    bail!("--watch is not implemented yet; run without --watch or use workflow debug --dry-run");
+    return super::watch::watch(options).await;
   }
 
-  println!(
-    "🚀 Starting AgentFlow V2 workflow execution: {}",
-    workflow_file
-  );
+  let interrupt = async {
+    shutdown_signal().await;
+    eprintln!("\n🛑 Cancelled (received SIGINT/SIGTERM)");
+  };
+  match run_once(&options, interrupt).await? {
+    RunEnd::Completed => Ok(()),
+    RunEnd::Interrupted(()) => std::process::exit(SIGINT_EXIT_CODE),
+  }
+}
+
+/// Loads, validates and executes the workflow once. When `interrupt`
+/// resolves mid-run the flow is cancelled after its current node and the
+/// trace is flushed before returning [`RunEnd::Interrupted`].
+pub async fn run_once<T>(
+  options: &RunOptions,
+  interrupt: impl Future<Output = T>,
+) -> Result<RunEnd<T>> {
+  let RunOptions {
+    workflow_file,
+    output,
+    output_format,
+    quiet,
+    no_progress,
+    verbose,
+    model,
+    input,
+    inputs_file,
+    stdin,
+    dry_run,
+    timeout,
+    max_retries,
+    execution_mode,
+    max_concurrency,
+    run_dir,
+    vars,
+    approval,
+  } = options.clone();
+  let output_format = OutputFormat::parse(&output_format)?;
+  let quiet_stdout = quiet.then(QuietStdout::new);
+
+  let source = if workflow_file == STDIN_ARG {
+    "<stdin>"
+  } else {
+    &workflow_file
+  };
+  println!("🚀 Starting AgentFlow V2 workflow execution: {}", source);
 
   // 1. Read and parse the V2 workflow file
-  let yaml_content = fs::read_to_string(&workflow_file)
-    .with_context(|| format!("Failed to read workflow file: {}", workflow_file))?;
-  let flow_def: FlowDefinitionV2 =
-    serde_yaml::from_str(&yaml_content).with_context(|| "Failed to parse V2 workflow YAML.")?;
+  let yaml_content = if workflow_file == STDIN_ARG {
+    stdin
+      .clone()
+      .ok_or_else(|| CliError::usage("no workflow YAML was piped on stdin"))?
+  } else {
+    fs::read_to_string(&workflow_file)
+      .with_context(|| format!("Failed to read workflow file: {}", workflow_file))?
+  };
+  let yaml_content = vars.substitute(&yaml_content)?;
+  let (flow_def, located_warnings) = parse_workflow(source, &yaml_content)?;
 
   println!("📄 Workflow '\'{}\'\' loaded.", flow_def.name);
-  let schema_report = validate_flow_definition(&flow_def);
+  let mut schema_report = validate_flow_definition_with_options(
+    &flow_def,
+    WorkflowValidationOptions {
+      unknown_parameters: UnknownParameterMode::Ignore,
+    },
+  );
+  schema_report.warnings.splice(0..0, located_warnings);
   if !schema_report.is_valid() || !schema_report.warnings.is_empty() {
     print_schema_report(&flow_def.name, &schema_report);
   }
   if !schema_report.is_valid() {
-    bail!(
+    bail!(CliError::validation(format!(
       "workflow '{}' failed schema validation with {} issue(s)",
       flow_def.name,
       schema_report.issues.len()
-    );
+    )));
   }
 
-  let mut flow = build_flow_from_definition(&flow_def, model.as_deref())?;
+  let mut flow = build_flow_from_definition(&flow_def, model.as_deref())
+    .map_err(|e| CliError::validation(format!("{:#}", e)))?;
   if let Some(model) = &model {
     println!("🤖 Model override: {}", model);
   }
@@ -74,8 +257,12 @@ pub async fn execute(
     for (idx, node_id) in order.iter().enumerate() {
       println!("  {}. {}", idx + 1, node_id);
     }
-    return Ok(());
+    return Ok(RunEnd::Completed);
   }
+  let order = flow
+    .execution_order()
+    .context("Failed to build workflow execution order")?;
+  let pricing = cost::load_pricing_or_warn();
 
   // Stable workflow_id for both the flow's emitted events and the trace
   // file. Printing it up front so the operator can run `agentflow trace
@@ -87,6 +274,7 @@ pub async fn execute(
   // the JSONL trace file the CLI just told the operator to inspect
   // could be missing the terminal `WorkflowCancelled` event.
   let mut trace_collector: Option<Arc<TraceCollector>> = None;
+  let mut trace_listener: Option<Arc<dyn EventListener>> = None;
   if let Some(dir) = trace_dir.as_ref() {
     fs::create_dir_all(dir)
       .with_context(|| format!("Failed to create trace dir {}", dir.display()))?;
@@ -95,7 +283,7 @@ pub async fn execute(
         .with_context(|| format!("Failed to initialise trace storage at {}", dir.display()))?,
     );
     let collector = Arc::new(TraceCollector::new(storage, TraceConfig::development()));
-    flow = flow.with_event_listener(collector.clone());
+    trace_listener = Some(collector.clone());
     trace_collector = Some(collector);
     println!(
       "📓 Tracing enabled — workflow_id={} dir={}",
@@ -109,7 +297,37 @@ pub async fn execute(
     );
   }
 
-  let initial_inputs = parse_inputs(input)?;
+  // The live node display, skipped under --quiet; bars redraw stderr,
+  // so the flow's own stdout lines are held back while they are up.
+  let progress_mode = ProgressMode::detect(no_progress);
+  let progress = (!quiet).then(|| {
+    Arc::new(RunProgress::new(
+      progress_mode,
+      verbose,
+      &order,
+      trace_listener.clone(),
+    ))
+  });
+  let timings = NodeTimings::forwarding_to(match &progress {
+    Some(progress) => Some(progress.clone() as Arc<dyn EventListener>),
+    None => trace_listener,
+  });
+  // Every event also reaches the `--log-file` / `RUST_LOG` subscriber
+  // and the approval prompter.
+  flow = flow.with_event_listener(Arc::new(MultiListener::new(vec![
+    Box::new(timings.clone()),
+    Box::new(TracingListener),
+    Box::new(ApprovalPrompter::new(approval, progress.clone())),
+  ])));
+
+  let initial_inputs = load_inputs(inputs_file.as_deref(), &input, stdin.as_deref())?;
+  let ResolvedInputs {
+    inputs: initial_inputs,
+    warnings: input_warnings,
+  } = resolve_inputs(&flow_def, initial_inputs).map_err(|e| CliError::usage(format!("{:#}", e)))?;
+  for warning in &input_warnings {
+    eprintln!("⚠️  {}", warning);
+  }
   if !initial_inputs.is_empty() {
     println!("📥 Loaded {} CLI input value(s).", initial_inputs.len());
   }
@@ -132,28 +350,55 @@ pub async fn execute(
     println!("📁 Run artifacts directory: {}", run_base_dir.display());
   }
 
+  // The manifest `runs list` / `runs show` read. A manifest that cannot
+  // be written only costs that bookkeeping, never the run.
+  let manifest_dir = match &execution_config.run_base_dir {
+    Some(dir) => Some(dir.clone()),
+    None => resolve_run_root(None).ok(),
+  }
+  .map(|root| root.join(&workflow_id));
+  let save_manifest = |manifest: &RunManifest| {
+    if let Some(dir) = &manifest_dir
+      && let Err(err) = manifest.save(dir)
+    {
+      eprintln!("⚠️  Failed to write run manifest: {:#}", err);
+    }
+  };
+  let mut manifest =
+    RunManifest::started(&workflow_id, &flow_def.name, Path::new(&workflow_file)).with_vars(&vars);
+  save_manifest(&manifest);
+
   // 3. Execute the flow
   println!("\n▶️  Running flow...");
+  let bars_stdout =
+    (progress.is_some() && progress_mode == ProgressMode::Bars).then(QuietStdout::new);
   let start_time = std::time::Instant::now();
   let run_future = run_with_retries(
     flow,
-    workflow_id,
+    workflow_id.clone(),
     initial_inputs,
     timeout_duration,
     max_retries,
     execution_config,
   );
-  // Q3.1.2: race the run against SIGINT/SIGTERM. On signal we flip
-  // the cancellation token (the flow then emits `WorkflowCancelled`
-  // and returns `TaskCancelled` after the current node finishes),
-  // wait for the trace drain to catch up, then exit 130. Without
-  // this Ctrl-C silently corrupts the JSONL trace file.
+  // Q3.1.2: race the run against SIGINT/SIGTERM (or, under --watch, a
+  // file change). On interrupt we flip the cancellation token (the flow
+  // then emits `WorkflowCancelled` and returns `TaskCancelled` after the
+  // current node finishes) and wait for the trace drain to catch up; the
+  // caller then exits 130. Without this Ctrl-C silently corrupts the
+  // JSONL trace file.
   tokio::pin!(run_future);
   let final_state = tokio::select! {
     biased;
-    res = &mut run_future => res?,
-    _ = shutdown_signal() => {
-      eprintln!("\n🛑 Cancelled (received SIGINT/SIGTERM)");
+    res = &mut run_future => match res {
+      Ok(state) => state,
+      Err(err) => {
+        manifest.abort(RunStatus::Failed, Some(redact_cli_text(format!("{:#}", err))));
+        save_manifest(&manifest);
+        return Err(err);
+      }
+    },
+    reason = interrupt => {
       cancel_token.cancel();
       // Give the in-flight node a bounded window to observe the
       // cancellation and let the flow emit `WorkflowCancelled`.
@@ -170,35 +415,68 @@ pub async fn execute(
           );
         }
       }
-      std::process::exit(SIGINT_EXIT_CODE);
+      manifest.abort(RunStatus::Cancelled, Some("interrupted before completion".to_string()));
+      save_manifest(&manifest);
+      return Ok(RunEnd::Interrupted(reason));
     }
   };
   let duration = start_time.elapsed();
-  println!("\n✅ Workflow completed in {:.2?}.", duration);
+  if let Some(progress) = &progress {
+    progress.finish();
+  }
+  drop(bars_stdout);
+  drop(quiet_stdout);
 
-  // 4. Print or save the results
-  let mut redacted_final_state =
-    serde_json::to_value(&final_state).context("Failed to serialize final state for redaction.")?;
-  redact_cli_value(&mut redacted_final_state);
-  let final_state_json = serde_json::to_string_pretty(&redacted_final_state)
-    .context("Failed to serialize redacted final state to JSON.")?;
+  // 4. Summarise, and print or save the report
+  let report = RunReport::from_state(
+    &flow_def.name,
+    &workflow_id,
+    duration,
+    &order,
+    &final_state,
+    &timings,
+  )
+  .with_cost(CostReport::from_state(
+    &order,
+    &final_state,
+    &cost::default_models(&flow_def, model.as_deref()),
+    &pricing,
+  ))
+  .with_declared_outputs(&flow_def.outputs);
+  manifest.finish(&report);
+  save_manifest(&manifest);
+  let failed = report.failed_nodes();
+  if failed.is_empty() {
+    println!("\n✅ Workflow completed in {:.2?}.", duration);
+  } else {
+    println!(
+      "\n❌ Workflow finished in {:.2?} with {} failed node(s).",
+      duration,
+      failed.len()
+    );
+  }
 
   match output.as_deref() {
     Some("-") => {
-      println!("{}", final_state_json);
+      println!("{}", report.render(output_format)?);
     }
     Some(path) => {
-      fs::write(path, &final_state_json)
+      fs::write(path, report.render(output_format)?)
         .with_context(|| format!("Failed to write workflow output to {}", path))?;
+      report.print_summary();
       println!("💾 Final state written to {}", path);
     }
-    None => {
-      println!("\n📊 Final State Pool:");
-      println!("{}", final_state_json);
-    }
+    None => report.print_summary(),
   }
 
-  Ok(())
+  if let Some(first) = failed.first() {
+    let message = format!("{} node(s) failed: {}", failed.len(), failed.join(", "));
+    bail!(match final_state.get(*first) {
+      Some(Err(error)) => CliError::failed_node(*first, error, message),
+      _ => CliError::node_failure(*first, message),
+    });
+  }
+  Ok(RunEnd::Completed)
 }
 
 /// Resolve where to write trace JSON: explicit `AGENTFLOW_TRACE_DIR` env
@@ -216,23 +494,142 @@ fn resolve_trace_dir() -> Result<Option<PathBuf>> {
   Ok(dirs::home_dir().map(|h| h.join(".agentflow").join("traces")))
 }
 
-fn parse_inputs(input: Vec<(String, String)>) -> Result<AsyncNodeInputs> {
+/// Builds the initial inputs. `--inputs-file` (a JSON or YAML mapping,
+/// or `-` for one piped on stdin) is loaded first and every `--input
+/// KEY VALUE` pair overrides it. A
+/// VALUE of `@path` reads that file (`.json` / `.yaml` / `.yml` files
+/// are parsed, anything else is kept as text), `-` takes the document
+/// piped on stdin, and `@@...` escapes a literal leading `@`. Any other
+/// value, and stdin, is parsed as JSON when it can be, so `3`, `true`
+/// and `{"a": 1}` keep their types.
+pub fn load_inputs(
+  inputs_file: Option<&str>,
+  input: &[(String, String)],
+  stdin: Option<&str>,
+) -> Result<AsyncNodeInputs> {
   let mut inputs = AsyncNodeInputs::new();
+  if let Some(path) = inputs_file {
+    let document = if path == STDIN_ARG {
+      let text = stdin.context("--inputs-file - needs a document piped on stdin")?;
+      serde_yaml::from_str(text).context("Failed to parse --inputs-file from stdin")?
+    } else {
+      read_structured_file(path)
+        .with_context(|| format!("Failed to load --inputs-file {}", path))?
+    };
+    let Value::Object(map) = document else {
+      bail!(
+        "--inputs-file {} must contain a mapping of input names to values",
+        path
+      );
+    };
+    for (key, value) in map {
+      inputs.insert(key, FlowValue::Json(value));
+    }
+  }
   for (key, raw_value) in input {
     if key.trim().is_empty() {
       bail!("Input key cannot be empty");
     }
-    let value = parse_input_value(&raw_value);
-    inputs.insert(key, FlowValue::Json(value));
+    let value = if raw_value == "-" {
+      let text = stdin.context("--input value '-' needs a document piped on stdin")?;
+      parse_input_value(text)
+    } else if let Some(literal) = raw_value.strip_prefix("@@") {
+      Value::String(format!("@{}", literal))
+    } else if let Some(path) = raw_value.strip_prefix('@') {
+      read_input_file(path).with_context(|| format!("Failed to read --input {} @{}", key, path))?
+    } else {
+      parse_input_value(raw_value)
+    };
+    inputs.insert(key.clone(), FlowValue::Json(value));
   }
   Ok(inputs)
 }
 
+/// Reads stdin when the workflow file, `--inputs-file` or an `--input
+/// KEY -` pair is `-`. Only one of them may be, since stdin can be read
+/// once.
+fn read_stdin(
+  workflow_file: &str,
+  inputs_file: Option<&str>,
+  input: &[(String, String)],
+) -> Result<Option<String>> {
+  let readers: Vec<&str> = [
+    (workflow_file == STDIN_ARG).then_some("the workflow file"),
+    (inputs_file == Some(STDIN_ARG)).then_some("--inputs-file"),
+  ]
+  .into_iter()
+  .flatten()
+  .chain(
+    input
+      .iter()
+      .filter(|(_, value)| value == STDIN_ARG)
+      .map(|_| "--input"),
+  )
+  .collect();
+  match readers.as_slice() {
+    [] => Ok(None),
+    [reader] => {
+      let mut text = String::new();
+      std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)
+        .with_context(|| format!("Failed to read {reader} from stdin"))?;
+      Ok(Some(text))
+    }
+    _ if readers.iter().all(|reader| *reader == "--input") => {
+      bail!(CliError::usage(
+        "Only one --input may read from stdin ('-')"
+      ))
+    }
+    [first @ .., last] => bail!(CliError::usage(format!(
+      "Only one of {} and {last} may read from stdin ('-')",
+      first.join(", ")
+    ))),
+  }
+}
+
+/// Reads the workflow YAML at `path`, or from stdin for [`STDIN_ARG`].
+pub fn read_workflow_file(path: &str) -> Result<String> {
+  if path == STDIN_ARG {
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)
+      .context("Failed to read the workflow from stdin")?;
+    return Ok(text);
+  }
+  fs::read_to_string(path).with_context(|| format!("failed to read workflow file '{path}'"))
+}
+
+fn read_input_file(path: &str) -> Result<Value> {
+  if is_structured_file(path) {
+    return read_structured_file(path);
+  }
+  Ok(Value::String(fs::read_to_string(path)?))
+}
+
+fn is_structured_file(path: &str) -> bool {
+  matches!(
+    std::path::Path::new(path)
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .map(str::to_ascii_lowercase)
+      .as_deref(),
+    Some("json" | "yaml" | "yml")
+  )
+}
+
+/// Parses a JSON file, or anything else as YAML (a superset of JSON).
+fn read_structured_file(path: &str) -> Result<Value> {
+  let text = fs::read_to_string(path)?;
+  if path.to_ascii_lowercase().ends_with(".json") {
+    return Ok(serde_json::from_str(&text)?);
+  }
+  Ok(serde_yaml::from_str(&text)?)
+}
+
 fn parse_input_value(raw_value: &str) -> Value {
   serde_json::from_str(raw_value).unwrap_or_else(|_| Value::String(raw_value.to_string()))
 }
 
-fn parse_duration(raw: &str) -> Result<Duration> {
+/// Parses `500ms`, `30s`, `5m` or a bare number of seconds.
+pub fn parse_duration(raw: &str) -> Result<Duration> {
   let raw = raw.trim();
   if raw.is_empty() {
     bail!("duration cannot be empty");
@@ -264,16 +661,21 @@ fn parse_execution_config(
     "serial" => FlowExecutionConfig::serial(),
     "concurrent" => {
       if max_concurrency == 0 {
-        bail!("--max-concurrency must be greater than zero");
+        bail!(CliError::usage(
+          "--max-concurrency must be greater than zero"
+        ));
       }
       FlowExecutionConfig::concurrent(max_concurrency)
     }
-    other => bail!("unsupported execution mode '{}'", other),
+    other => bail!(CliError::usage(format!(
+      "unsupported execution mode '{}'",
+      other
+    ))),
   };
 
   if let Some(run_dir) = run_dir.or_else(|| std::env::var("AGENTFLOW_RUN_DIR").ok()) {
     if run_dir.trim().is_empty() {
-      bail!("--run-dir cannot be empty");
+      bail!(CliError::usage("--run-dir cannot be empty"));
     }
     config = config.with_run_base_dir(PathBuf::from(run_dir));
   }
@@ -311,12 +713,10 @@ async fn run_with_retries(
         );
       }
       Err(_) => {
-        last_error = Some(anyhow::anyhow!(
+        last_error = Some(anyhow::Error::new(CliError::timeout(format!(
           "workflow attempt {}/{} timed out after {:?}",
-          attempt,
-          attempts,
-          timeout_duration
-        ));
+          attempt, attempts, timeout_duration
+        ))));
       }
     }
 
@@ -327,3 +727,89 @@ async fn run_with_retries(
 
   Err(last_error.unwrap_or_else(|| anyhow::anyhow!("workflow execution failed")))
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn pair(key: &str, value: &str) -> (String, String) {
+    (key.to_string(), value.to_string())
+  }
+
+  fn json_input(inputs: &AsyncNodeInputs, key: &str) -> Value {
+    match inputs.get(key) {
+      Some(FlowValue::Json(value)) => value.clone(),
+      other => panic!("{key} should be a JSON input, got {other:?}"),
+    }
+  }
+
+  #[test]
+  fn input_pairs_override_the_inputs_file_and_keep_types() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("inputs.yaml");
+    fs::write(
+      &file,
+      "user: { name: Ada, langs: [en, fr] }\nlimit: 5\ntopic: from file\n",
+    )
+    .unwrap();
+    let notes = dir.path().join("notes.txt");
+    fs::write(&notes, "line one\nline two\n").unwrap();
+    let config = dir.path().join("config.json");
+    fs::write(&config, r#"{"depth": 2}"#).unwrap();
+
+    let inputs = load_inputs(
+      Some(file.to_str().unwrap()),
+      &[
+        pair("topic", "from flag"),
+        pair("limit", "10"),
+        pair("verbose", "true"),
+        pair("notes", &format!("@{}", notes.display())),
+        pair("config", &format!("@{}", config.display())),
+        pair("handle", "@@ada"),
+        pair("doc", "-"),
+      ],
+      Some("{\"title\": \"piped\"}\n"),
+    )
+    .unwrap();
+
+    assert_eq!(
+      json_input(&inputs, "user"),
+      json!({ "name": "Ada", "langs": ["en", "fr"] })
+    );
+    assert_eq!(json_input(&inputs, "topic"), json!("from flag"));
+    assert_eq!(json_input(&inputs, "limit"), json!(10));
+    assert_eq!(json_input(&inputs, "verbose"), json!(true));
+    assert_eq!(json_input(&inputs, "notes"), json!("line one\nline two\n"));
+    assert_eq!(json_input(&inputs, "config"), json!({ "depth": 2 }));
+    assert_eq!(json_input(&inputs, "handle"), json!("@ada"));
+    assert_eq!(json_input(&inputs, "doc"), json!({ "title": "piped" }));
+  }
+
+  #[test]
+  fn bad_input_sources_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = dir.path().join("list.json");
+    fs::write(&list, "[1, 2]").unwrap();
+
+    let err = load_inputs(Some(list.to_str().unwrap()), &[], None).unwrap_err();
+    assert!(
+      err.to_string().contains("must contain a mapping"),
+      "{err:#}"
+    );
+    let err = load_inputs(None, &[pair("doc", "@/nonexistent/file.txt")], None).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("--input doc @/nonexistent/file.txt"),
+      "{err:#}"
+    );
+    assert!(load_inputs(None, &[pair("doc", "-")], None).is_err());
+    assert!(read_stdin("flow.yml", None, &[pair("a", "-"), pair("b", "-")]).is_err());
+    let err = read_stdin("-", None, &[pair("a", "-")]).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Only one of the workflow file and --input may read from stdin ('-')"
+    );
+  }
+}
@@ -0,0 +1,364 @@
+//! `agentflow workflow run-many` — run every workflow in a directory or
+//! glob, up to `--parallel` at a time.
+//!
+//! Each workflow runs as its own `agentflow workflow run` child process,
+//! so it gets its own run directory, trace and Ctrl-C handling exactly as
+//! when it is run alone. The children's output is interleaved line by
+//! line, each line prefixed with `[<workflow>]`. Their JSON run reports
+//! and `--error-format json` errors feed the aggregate summary printed at
+//! the end and written to `--summary`.
+//!
+//! The first failure stops new workflows from starting (the running ones
+//! finish) unless `--continue-on-error` is set. Any failure makes the
+//! command exit non-zero, unless `--allow-failures` is set as well.
+
+use super::report::RunReport;
+use crate::cli_error::{CliError, ErrorReport};
+use anyhow::{Context, Result, bail};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+pub struct RunManyOptions {
+  /// A directory of `*.yml` / `*.yaml` files, or a glob.
+  pub target: String,
+  pub parallel: usize,
+  pub continue_on_error: bool,
+  pub allow_failures: bool,
+  pub summary: Option<PathBuf>,
+  /// Passed through to each `workflow run`.
+  pub model: Option<String>,
+  pub timeout: String,
+  pub run_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStatus {
+  Succeeded,
+  Failed,
+  /// Never started because an earlier workflow failed.
+  Skipped,
+}
+
+/// One workflow's line in the summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowSummary {
+  pub file: String,
+  pub status: WorkflowStatus,
+  /// The child's exit code.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub exit_code: Option<i32>,
+  /// Wall time of the child process.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub duration_ms: Option<u64>,
+  /// The run id, which names the workflow's run directory.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub workflow_id: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub prompt_tokens: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub completion_tokens: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost_usd: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+impl WorkflowSummary {
+  fn new(file: &Path, status: WorkflowStatus) -> Self {
+    Self {
+      file: file.display().to_string(),
+      status,
+      exit_code: None,
+      duration_ms: None,
+      workflow_id: None,
+      prompt_tokens: None,
+      completion_tokens: None,
+      cost_usd: None,
+      error: None,
+    }
+  }
+}
+
+/// The `--summary` document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManySummary {
+  pub total: usize,
+  pub succeeded: usize,
+  pub failed: usize,
+  pub skipped: usize,
+  pub duration_ms: u64,
+  /// Sum over the workflows that reported a cost.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cost_usd: Option<f64>,
+  /// In discovery order.
+  pub workflows: Vec<WorkflowSummary>,
+}
+
+impl RunManySummary {
+  fn new(workflows: Vec<WorkflowSummary>, duration_ms: u64) -> Self {
+    let count = |status| workflows.iter().filter(|w| w.status == status).count();
+    let costs: Vec<f64> = workflows.iter().filter_map(|w| w.cost_usd).collect();
+    Self {
+      total: workflows.len(),
+      succeeded: count(WorkflowStatus::Succeeded),
+      failed: count(WorkflowStatus::Failed),
+      skipped: count(WorkflowStatus::Skipped),
+      duration_ms,
+      cost_usd: (!costs.is_empty()).then(|| costs.iter().sum()),
+      workflows,
+    }
+  }
+
+  fn print(&self) {
+    println!();
+    println!(
+      "📊 {} workflow(s): {} succeeded, {} failed, {} skipped in {:.2}s",
+      self.total,
+      self.succeeded,
+      self.failed,
+      self.skipped,
+      self.duration_ms as f64 / 1000.0
+    );
+    for workflow in &self.workflows {
+      let icon = match workflow.status {
+        WorkflowStatus::Succeeded => "✅",
+        WorkflowStatus::Failed => "❌",
+        WorkflowStatus::Skipped => "⏭️",
+      };
+      let mut line = format!("  {} {}", icon, workflow.file);
+      if let Some(ms) = workflow.duration_ms {
+        line.push_str(&format!("  {:.2}s", ms as f64 / 1000.0));
+      }
+      if let Some(cost) = workflow.cost_usd {
+        line.push_str(&format!("  ${:.4}", cost));
+      }
+      if let Some(error) = &workflow.error {
+        line.push_str(&format!("  {}", error));
+      }
+      println!("{}", line);
+    }
+    if let Some(cost) = self.cost_usd {
+      println!("💰 Total cost: ${:.4}", cost);
+    }
+  }
+}
+
+pub async fn execute(options: RunManyOptions) -> Result<()> {
+  let files = discover(&options.target)?;
+  let labels = labels(&files);
+  let exe = std::env::current_exe().context("Failed to locate the agentflow executable")?;
+  let reports = tempfile::tempdir().context("Failed to create a directory for run reports")?;
+  let parallel = options.parallel.max(1);
+  println!(
+    "🚀 Running {} workflow(s) from {} ({} at a time)",
+    files.len(),
+    options.target,
+    parallel
+  );
+
+  let start = Instant::now();
+  let stop = AtomicBool::new(false);
+  let mut workflows: Vec<(usize, WorkflowSummary)> =
+    futures::stream::iter(files.iter().enumerate())
+      .map(|(index, file)| {
+        let (options, exe, stop, label) = (&options, &exe, &stop, &labels[index]);
+        let report_path = reports.path().join(format!("{}.json", index));
+        async move {
+          if stop.load(Ordering::SeqCst) {
+            return (index, WorkflowSummary::new(file, WorkflowStatus::Skipped));
+          }
+          let summary = run_one(exe, file, label, &report_path, options).await;
+          if summary.status == WorkflowStatus::Failed && !options.continue_on_error {
+            stop.store(true, Ordering::SeqCst);
+          }
+          (index, summary)
+        }
+      })
+      .buffer_unordered(parallel)
+      .collect()
+      .await;
+  workflows.sort_by_key(|(index, _)| *index);
+  let summary = RunManySummary::new(
+    workflows
+      .into_iter()
+      .map(|(_, workflow)| workflow)
+      .collect(),
+    start.elapsed().as_millis() as u64,
+  );
+
+  summary.print();
+  if let Some(path) = &options.summary {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+      std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&summary)?)
+      .with_context(|| format!("Failed to write summary to {}", path.display()))?;
+    println!("💾 Summary written to {}", path.display());
+  }
+
+  if summary.failed > 0 && !(options.continue_on_error && options.allow_failures) {
+    bail!("{} of {} workflow(s) failed", summary.failed, summary.total);
+  }
+  Ok(())
+}
+
+/// Runs `file` as a `workflow run` child, echoing its output under
+/// `[label]`.
+async fn run_one(
+  exe: &Path,
+  file: &Path,
+  label: &str,
+  report_path: &Path,
+  options: &RunManyOptions,
+) -> WorkflowSummary {
+  let start = Instant::now();
+  let mut summary = WorkflowSummary::new(file, WorkflowStatus::Failed);
+  let mut command = Command::new(exe);
+  command
+    .args(["--error-format", "json", "workflow", "run"])
+    .arg(file)
+    .args(["--no-progress", "--output-format", "json", "--output"])
+    .arg(report_path)
+    .args(["--timeout", &options.timeout]);
+  if let Some(model) = &options.model {
+    command.args(["--model", model]);
+  }
+  if let Some(run_dir) = &options.run_dir {
+    command.args(["--run-dir", run_dir]);
+  }
+  command
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  let mut child = match command.spawn() {
+    Ok(child) => child,
+    Err(err) => {
+      summary.error = Some(format!("Failed to start workflow run: {}", err));
+      return summary;
+    }
+  };
+
+  let stdout = child.stdout.take();
+  let stderr = child.stderr.take();
+  let echo_stdout = async {
+    if let Some(stdout) = stdout {
+      let mut lines = BufReader::new(stdout).lines();
+      while let Ok(Some(line)) = lines.next_line().await {
+        println!("[{}] {}", label, line);
+      }
+    }
+  };
+  // The child's `--error-format json` line becomes the summary's error.
+  let echo_stderr = async {
+    let mut error = None;
+    if let Some(stderr) = stderr {
+      let mut lines = BufReader::new(stderr).lines();
+      while let Ok(Some(line)) = lines.next_line().await {
+        match serde_json::from_str::<ErrorReport>(&line) {
+          Ok(report) => {
+            eprintln!("[{}] Error: {}", label, report.message);
+            error = Some(report.message);
+          }
+          Err(_) => eprintln!("[{}] {}", label, line),
+        }
+      }
+    }
+    error
+  };
+  let ((), error, status) = tokio::join!(echo_stdout, echo_stderr, child.wait());
+  summary.duration_ms = Some(start.elapsed().as_millis() as u64);
+  match status {
+    Ok(status) => {
+      summary.exit_code = status.code();
+      if status.success() {
+        summary.status = WorkflowStatus::Succeeded;
+      } else {
+        summary.error =
+          Some(error.unwrap_or_else(|| format!("workflow run exited with {}", status)));
+      }
+    }
+    Err(err) => summary.error = Some(format!("Failed to wait for workflow run: {}", err)),
+  }
+
+  // A workflow that failed before running has no report.
+  if let Ok(text) = std::fs::read_to_string(report_path)
+    && let Some(report) = RunReport::from_output_json(&text)
+  {
+    summary.workflow_id = Some(report.workflow_id);
+    if let Some(cost) = report.cost {
+      summary.prompt_tokens = Some(cost.prompt_tokens);
+      summary.completion_tokens = Some(cost.completion_tokens);
+      summary.cost_usd = cost.cost_usd;
+    }
+  }
+  summary
+}
+
+/// The workflow files `target` names, sorted: the `*.yml` / `*.yaml`
+/// files directly in a directory, or the files a glob matches.
+fn discover(target: &str) -> Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  let path = Path::new(target);
+  if path.is_dir() {
+    let entries =
+      std::fs::read_dir(path).with_context(|| format!("Failed to read directory {}", target))?;
+    for entry in entries {
+      let file = entry?.path();
+      let is_yaml = file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "yml" | "yaml"));
+      if is_yaml && file.is_file() {
+        files.push(file);
+      }
+    }
+  } else {
+    let matches = glob::glob(target)
+      .map_err(|err| CliError::usage(format!("Invalid glob '{}': {}", target, err)))?;
+    files.extend(
+      matches
+        .filter_map(|entry| entry.ok())
+        .filter(|file| file.is_file()),
+    );
+  }
+  if files.is_empty() {
+    bail!(CliError::usage(format!(
+      "No workflow files found at '{}'",
+      target
+    )));
+  }
+  files.sort();
+  Ok(files)
+}
+
+/// Output prefixes: the file names, or the full paths when two files
+/// share a name.
+fn labels(files: &[PathBuf]) -> Vec<String> {
+  let names: Vec<String> = files
+    .iter()
+    .map(|file| {
+      file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file.display().to_string())
+    })
+    .collect();
+  if names.iter().collect::<HashSet<_>>().len() == names.len() {
+    names
+  } else {
+    files
+      .iter()
+      .map(|file| file.display().to_string())
+      .collect()
+  }
+}
@@ -66,7 +66,7 @@ pub async fn cancel(
 /// `POST /v1/runs` wire body only accepts `{ workflow, tenant_id }`
 /// — every per-run knob the local executor consumes
 /// (`--model` / `--execution-mode` / `--max-concurrency` /
-/// `--run-dir` / `--watch` / `--output` / `--input` / `--dry-run` /
+/// `--run-dir` / `--watch` / `--output` / `--input` / `--inputs-file` / `--dry-run` /
 /// `--timeout` / `--max-retries`) would be silently dropped without
 /// this guard, which is exactly the class of bug operators only
 /// catch in prod.
@@ -78,7 +78,7 @@ pub async fn cancel(
 ///   and event stream, so these never make sense remotely.
 /// - **Future API addition**: server-side execution knobs that the
 ///   wire format could accept but doesn't today (`--model`,
-///   `--execution-mode`, `--max-concurrency`, `--input`,
+///   `--execution-mode`, `--max-concurrency`, `--input`, `--inputs-file`,
 ///   `--timeout`, `--max-retries`). Each error names the gap and
 ///   points at the local-mode workaround.
 ///
@@ -97,6 +97,7 @@ pub fn reject_local_only_flags(
   watch: bool,
   output: Option<&str>,
   input: &[String],
+  inputs_file: Option<&str>,
   dry_run: bool,
   timeout: &str,
   timeout_default: &str,
@@ -161,6 +162,13 @@ pub fn reject_local_only_flags(
        before submission, or run locally with --input."
     );
   }
+  if let Some(path) = inputs_file {
+    anyhow::bail!(
+      "--inputs-file '{path}' is not yet wired to the server (POST /v1/runs body does not \
+       accept initial inputs today; tracked under P10.11.4). Either bake the values into the \
+       workflow YAML before submission, or run locally with --inputs-file."
+    );
+  }
   if timeout != timeout_default {
     anyhow::bail!(
       "--timeout '{timeout}' is not yet wired to the server (POST /v1/runs body does not \
@@ -576,6 +584,7 @@ mod tests {
       watch,
       output,
       input,
+      None,
       dry_run,
       timeout,
       "60s",
@@ -733,6 +742,32 @@ mod tests {
     assert!(msg.contains("workflow YAML"), "{msg}");
   }
 
+  #[test]
+  fn workflow_run_server_rejects_inputs_file() {
+    let err = reject_local_only_flags(
+      None,
+      "serial",
+      "serial",
+      4,
+      4,
+      None,
+      false,
+      None,
+      &[],
+      Some("inputs.yaml"),
+      false,
+      "60s",
+      "60s",
+      0,
+    )
+    .expect_err("--inputs-file must be rejected");
+    let msg = err.to_string();
+    assert!(
+      msg.contains("--inputs-file 'inputs.yaml' is not yet wired"),
+      "{msg}"
+    );
+  }
+
   #[test]
   fn workflow_run_server_rejects_timeout_when_changed() {
     let err = run_validator(None, "serial", 4, None, false, None, &[], false, "120s", 0)
@@ -1,4 +1,6 @@
+use crate::cli_error::CliError;
 use crate::config::{
+  located::{Severity, check_workflow_yaml},
   schema::{
     UnknownParameterMode, WorkflowValidationOptions, validate_flow_definition_with_options,
   },
@@ -77,16 +79,49 @@ pub async fn execute(
   }
 
   if !report.is_valid() {
-    bail!(
+    bail!(CliError::validation(format!(
       "workflow '{}' failed schema validation with {} issue(s)",
       flow_def.name,
       report.issues.len()
-    );
+    )));
   }
 
   Ok(())
 }
 
+/// Parses workflow YAML for `workflow run` and `workflow debug`. The
+/// located checks of [`check_workflow_yaml`] run first: their errors are
+/// printed as `source:line:column: …` and fail the command before a
+/// `Flow` is built. Returns the definition and the located warnings,
+/// rendered, for the caller's own report; the caller's
+/// `validate_flow_definition` then leaves unknown parameters to them
+/// ([`UnknownParameterMode::Ignore`]).
+pub fn parse_workflow(source: &str, yaml: &str) -> Result<(FlowDefinitionV2, Vec<String>)> {
+  let diagnostics = check_workflow_yaml(yaml, WorkflowValidationOptions::default());
+  let (errors, warnings): (Vec<_>, Vec<_>) = diagnostics
+    .iter()
+    .partition(|diagnostic| diagnostic.severity == Severity::Error);
+  let warnings: Vec<String> = warnings.iter().map(|d| d.render(source)).collect();
+  if !errors.is_empty() {
+    let name = serde_yaml::from_str::<YamlValue>(yaml)
+      .ok()
+      .and_then(|value| value.get("name")?.as_str().map(str::to_string))
+      .unwrap_or_else(|| source.to_string());
+    let report = crate::config::schema::WorkflowValidationReport {
+      issues: errors.iter().map(|d| d.render(source)).collect(),
+      warnings,
+    };
+    print_schema_report(&name, &report);
+    bail!(CliError::validation(format!(
+      "workflow '{}' failed schema validation with {} issue(s)",
+      name,
+      errors.len()
+    )));
+  }
+  let flow_def = serde_yaml::from_str(yaml).with_context(|| "Failed to parse V2 workflow YAML.")?;
+  Ok((flow_def, warnings))
+}
+
 pub fn print_schema_report(
   workflow_name: &str,
   report: &crate::config::schema::WorkflowValidationReport,
@@ -211,9 +246,144 @@ fn classify_node(node: &NodeDefinitionV2) -> NodePermission {
   let mut constraints: BTreeMap<String, String> = BTreeMap::new();
   let mut notes: Vec<String> = Vec::new();
   let (category, capabilities) = match node.node_type.as_str() {
-    "template" | "markmap" | "batch" | "conditional" | "while" => {
+    "template" | "markmap" | "batch" | "conditional" | "while" | "text_splitter"
+    | "json_transform" | "text_extract" | "delay" => (PermissionCategory::Pure, vec![]),
+    "schedule_gate" => {
+      summarize_param(&node.parameters, "policy", &mut constraints);
       (PermissionCategory::Pure, vec![])
     }
+    "tabular_read" | "tabular_write" => {
+      summarize_param(&node.parameters, "path", &mut constraints);
+      let capability = if node.node_type == "tabular_read" {
+        "fs.read"
+      } else {
+        "fs.write"
+      };
+      match node.parameters.get("path") {
+        Some(_) => (PermissionCategory::Filesystem, vec![capability.to_string()]),
+        // Inline `content` / no output path: pure data reshaping.
+        None if !node.input_mapping.contains_key("path") => (PermissionCategory::Pure, vec![]),
+        None => (PermissionCategory::Filesystem, vec![capability.to_string()]),
+      }
+    }
+    "throttle" => {
+      summarize_param(&node.parameters, "group", &mut constraints);
+      match node.parameters.get("state_dir").and_then(|v| v.as_str()) {
+        Some("memory") => (PermissionCategory::Pure, vec![]),
+        _ => {
+          summarize_param(&node.parameters, "state_dir", &mut constraints);
+          // The group's last-run timestamp is persisted between runs.
+          (
+            PermissionCategory::Filesystem,
+            vec!["fs.read".to_string(), "fs.write".to_string()],
+          )
+        }
+      }
+    }
+    "vector_search" => {
+      summarize_param(&node.parameters, "corpus_path", &mut constraints);
+      summarize_param(&node.parameters, "index_path", &mut constraints);
+      // In-memory corpora are pure; the index file is written when missing.
+      let mut capabilities = Vec::new();
+      if node.parameters.contains_key("corpus_path") || node.parameters.contains_key("index_path") {
+        capabilities.push("fs.read".to_string());
+      }
+      if node.parameters.contains_key("index_path") {
+        capabilities.push("fs.write".to_string());
+      }
+      if capabilities.is_empty() {
+        (PermissionCategory::Pure, capabilities)
+      } else {
+        (PermissionCategory::Filesystem, capabilities)
+      }
+    }
+    "document_render" => {
+      summarize_list_param(&node.parameters, "formats", &mut constraints);
+      summarize_param(&node.parameters, "output_dir", &mut constraints);
+      let wants_pdf = node
+        .parameters
+        .get("formats")
+        .and_then(|v| v.as_sequence())
+        .is_some_and(|formats| formats.iter().any(|f| f.as_str() == Some("pdf")));
+      // Relative images are read for embedding; files land in output_dir.
+      let mut capabilities = vec!["fs.read".to_string()];
+      if node.parameters.contains_key("output_dir") {
+        capabilities.push("fs.write".to_string());
+      }
+      if wants_pdf {
+        summarize_list_param(&node.parameters, "pdf_command", &mut constraints);
+        notes.push("pdf: runs an external HTML-to-PDF converter".to_string());
+        capabilities.insert(0, "exec".to_string());
+        (PermissionCategory::Exec, capabilities)
+      } else {
+        (PermissionCategory::Filesystem, capabilities)
+      }
+    }
+    "email" => {
+      summarize_param(&node.parameters, "host", &mut constraints);
+      let mut capabilities = Vec::new();
+      if node.parameters.contains_key("attachments")
+        || node.input_mapping.contains_key("attachments")
+      {
+        capabilities.push("fs.read".to_string());
+      }
+      if node.parameters.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+        notes
+          .push("dry_run: renders the message to the run directory instead of sending".to_string());
+        capabilities.push("fs.write".to_string());
+        (PermissionCategory::Filesystem, capabilities)
+      } else {
+        capabilities.insert(0, "net".to_string());
+        (PermissionCategory::Network, capabilities)
+      }
+    }
+    "git" => {
+      summarize_param(&node.parameters, "operation", &mut constraints);
+      summarize_param(&node.parameters, "url", &mut constraints);
+      match node.parameters.get("operation").and_then(|v| v.as_str()) {
+        Some("clone") => (
+          PermissionCategory::Network,
+          vec!["net".to_string(), "fs.write".to_string()],
+        ),
+        _ => (PermissionCategory::Filesystem, vec!["fs.read".to_string()]),
+      }
+    }
+    "object_store" => {
+      summarize_param(&node.parameters, "operation", &mut constraints);
+      summarize_param(&node.parameters, "bucket", &mut constraints);
+      summarize_param(&node.parameters, "endpoint", &mut constraints);
+      let has_source =
+        |key: &str| node.parameters.contains_key(key) || node.input_mapping.contains_key(key);
+      let mut capabilities = vec!["net".to_string()];
+      if has_source("file") {
+        capabilities.push("fs.read".to_string());
+      }
+      if has_source("output_path") {
+        capabilities.push("fs.write".to_string());
+      }
+      (PermissionCategory::Network, capabilities)
+    }
+    "sql" => {
+      summarize_param(&node.parameters, "mode", &mut constraints);
+      let connection = node
+        .parameters
+        .get("connection")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+      let writes = node.parameters.get("mode").and_then(|v| v.as_str()) == Some("execute");
+      // Only the scheme is reported: connection strings carry credentials.
+      if connection.starts_with("postgres://") || connection.starts_with("postgresql://") {
+        constraints.insert("database".to_string(), "postgres".to_string());
+        (PermissionCategory::Network, vec!["net".to_string()])
+      } else {
+        constraints.insert("database".to_string(), "sqlite".to_string());
+        let mut capabilities = vec!["fs.read".to_string()];
+        if writes {
+          capabilities.push("fs.write".to_string());
+        }
+        (PermissionCategory::Filesystem, capabilities)
+      }
+    }
     "llm" => {
       if let Some(model) = node.parameters.get("model").and_then(yaml_summary) {
         constraints.insert("model".to_string(), model);
@@ -230,6 +400,51 @@ fn classify_node(node: &NodeDefinitionV2) -> NodePermission {
       }
       (PermissionCategory::Network, vec!["net".to_string()])
     }
+    "poll_until" => {
+      summarize_param(&node.parameters, "url", &mut constraints);
+      (PermissionCategory::Network, vec!["net".to_string()])
+    }
+    "feed" => {
+      summarize_param(&node.parameters, "url", &mut constraints);
+      summarize_param(&node.parameters, "state_file", &mut constraints);
+      let mut capabilities = vec!["net".to_string()];
+      if node.parameters.contains_key("state_file") {
+        capabilities.extend(["fs.read".to_string(), "fs.write".to_string()]);
+      }
+      (PermissionCategory::Network, capabilities)
+    }
+    "web_extract" => {
+      summarize_param(&node.parameters, "url", &mut constraints);
+      (PermissionCategory::Network, vec!["net".to_string()])
+    }
+    "webhook_wait" => {
+      summarize_param(&node.parameters, "host", &mut constraints);
+      summarize_param(&node.parameters, "port", &mut constraints);
+      // Inbound rather than outbound: the node opens a listening socket.
+      (PermissionCategory::Network, vec!["net.listen".to_string()])
+    }
+    "approval" => {
+      summarize_param(&node.parameters, "channel", &mut constraints);
+      summarize_param(&node.parameters, "on_timeout", &mut constraints);
+      match node.parameters.get("channel").and_then(|v| v.as_str()) {
+        Some("http") => {
+          summarize_param(&node.parameters, "port", &mut constraints);
+          (PermissionCategory::Network, vec!["net.listen".to_string()])
+        }
+        Some("stdin") => {
+          notes.push("interactive: needs a terminal on stdin".to_string());
+          (PermissionCategory::Pure, vec![])
+        }
+        _ => {
+          summarize_param(&node.parameters, "sentinel_path", &mut constraints);
+          // The sentinel is read, then removed once consumed.
+          (
+            PermissionCategory::Filesystem,
+            vec!["fs.read".to_string(), "fs.write".to_string()],
+          )
+        }
+      }
+    }
     "file" => {
       summarize_param(&node.parameters, "operation", &mut constraints);
       summarize_param(&node.parameters, "path", &mut constraints);
@@ -259,6 +474,14 @@ fn classify_node(node: &NodeDefinitionV2) -> NodePermission {
       }
       (PermissionCategory::Exec, vec!["exec".to_string()])
     }
+    "command" => {
+      summarize_param(&node.parameters, "program", &mut constraints);
+      summarize_list_param(&node.parameters, "args", &mut constraints);
+      if node.parameters.get("allow_shell").and_then(|v| v.as_bool()) == Some(true) {
+        notes.push("allow_shell: program runs through `sh -c`".to_string());
+      }
+      (PermissionCategory::Exec, vec!["exec".to_string()])
+    }
     "mcp" => {
       summarize_list_param(&node.parameters, "server_command", &mut constraints);
       summarize_param(&node.parameters, "tool_name", &mut constraints);
@@ -283,8 +506,8 @@ fn classify_node(node: &NodeDefinitionV2) -> NodePermission {
       );
       (PermissionCategory::Agent, vec!["agent.runtime".to_string()])
     }
-    "rag" | "arxiv" | "asr" | "tts" | "text_to_image" | "image_to_image" | "image_edit"
-    | "image_understand" => (PermissionCategory::Network, vec!["net".to_string()]),
+    "rag" | "embedding" | "arxiv" | "asr" | "tts" | "text_to_image" | "image_to_image"
+    | "image_edit" | "image_understand" => (PermissionCategory::Network, vec!["net".to_string()]),
     _ => (PermissionCategory::Unknown, vec![]),
   };
 
@@ -0,0 +1,392 @@
+//! `${VAR}` / `${profile.key}` substitution in workflow YAML, for
+//! `--profile <name>` and `--set key=value`.
+//!
+//! Placeholders are filled in the document's string scalars, keys
+//! included, so a placeholder can stand in a prompt, a base URL or a whole
+//! scalar, and a value is always one scalar: a `: `, `#` or newline in it
+//! cannot change the workflow's structure. A scalar that is nothing but a
+//! placeholder takes the value's type when it is a number or boolean, as
+//! if it had been written there. When rewriting the text in place gives
+//! the same document, the rewritten text is returned, so later messages
+//! keep their line numbers; otherwise the substituted document is written
+//! out again.
+//!
+//! `${VAR}` is looked up in `--set`, then the profile, then the
+//! environment; `${profile.key}` in `--set`, then the profile only. The
+//! first placeholder nothing resolves fails the command with its name and
+//! line. `$${...}` is left as a literal `${...}`, and anything after `${`
+//! that is not a name (a Tera `${{ price }}`, say) is not touched.
+
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Leads a placeholder that may only come from the profile or `--set`.
+const PROFILE_PREFIX: &str = "profile.";
+
+/// The `--profile` and `--set` values of one command.
+#[derive(Debug, Clone, Default)]
+pub struct WorkflowVars {
+  set: BTreeMap<String, String>,
+  /// The profile name and its keys.
+  profile: Option<(String, BTreeMap<String, String>)>,
+}
+
+impl WorkflowVars {
+  /// Reads `~/.agentflow/profiles/<profile>.yml` and parses the `--set`
+  /// pairs; later `--set` pairs win over earlier ones.
+  pub fn load(profile: Option<&str>, set: &[String]) -> Result<Self> {
+    let set = set
+      .iter()
+      .map(|pair| parse_set(pair))
+      .collect::<Result<BTreeMap<_, _>>>()?;
+    Self::from_parts(profile, set)
+  }
+
+  /// Reads the profile and takes the already parsed `--set` values, as
+  /// recorded in a run manifest.
+  pub fn from_parts(profile: Option<&str>, set: BTreeMap<String, String>) -> Result<Self> {
+    let profile = match profile {
+      Some(name) => {
+        let path = profile_path(name)?;
+        let text = std::fs::read_to_string(&path)
+          .with_context(|| format!("Failed to read profile '{}' ({})", name, path.display()))?;
+        Some((name.to_string(), parse_profile(name, &text)?))
+      }
+      None => None,
+    };
+    Ok(Self { set, profile })
+  }
+
+  /// The `--profile` name, if one was given.
+  pub fn profile_name(&self) -> Option<&str> {
+    self.profile.as_ref().map(|(name, _)| name.as_str())
+  }
+
+  /// The `--set` values.
+  pub fn set_values(&self) -> &BTreeMap<String, String> {
+    &self.set
+  }
+
+  /// Substitutes against the process environment.
+  pub fn substitute(&self, text: &str) -> Result<String> {
+    self.substitute_with(text, |name| std::env::var(name).ok())
+  }
+
+  fn substitute_with(&self, text: &str, env: impl Fn(&str) -> Option<String>) -> Result<String> {
+    // Also reports the first unresolved placeholder with its line.
+    let rewritten = self.substitute_text(text, &env)?;
+    if rewritten == text {
+      return Ok(rewritten);
+    }
+    let Ok(document) = serde_yaml::from_str::<serde_yaml::Value>(text) else {
+      // Left for the workflow parser to report where it is invalid.
+      return Ok(rewritten);
+    };
+    let strings = self.substitute_value(document.clone(), &env, false)?;
+    if serde_yaml::from_str::<serde_yaml::Value>(&rewritten)
+      .is_ok_and(|parsed| same_document(&strings, &parsed))
+    {
+      return Ok(rewritten);
+    }
+    let typed = self.substitute_value(document, &env, true)?;
+    serde_yaml::to_string(&typed).context("Failed to write the substituted workflow YAML")
+  }
+
+  /// Fills the placeholders in every string scalar of `value`. With
+  /// `typed`, a scalar that is only a placeholder becomes a number or
+  /// boolean when its value reads as one.
+  fn substitute_value(
+    &self,
+    value: serde_yaml::Value,
+    env: &impl Fn(&str) -> Option<String>,
+    typed: bool,
+  ) -> Result<serde_yaml::Value> {
+    use serde_yaml::Value;
+    Ok(match value {
+      Value::String(text) => {
+        let whole = text
+          .strip_prefix("${")
+          .and_then(placeholder)
+          .filter(|(_, len)| 2 + len + 1 == text.len());
+        let substituted = self.substitute_text(&text, env)?;
+        match serde_yaml::from_str::<Value>(&substituted) {
+          Ok(scalar @ (Value::Number(_) | Value::Bool(_))) if typed && whole.is_some() => scalar,
+          _ => Value::String(substituted),
+        }
+      }
+      Value::Sequence(items) => Value::Sequence(
+        items
+          .into_iter()
+          .map(|item| self.substitute_value(item, env, typed))
+          .collect::<Result<_>>()?,
+      ),
+      Value::Mapping(mapping) => Value::Mapping(
+        mapping
+          .into_iter()
+          .map(|(key, value)| {
+            Ok((
+              self.substitute_value(key, env, false)?,
+              self.substitute_value(value, env, typed)?,
+            ))
+          })
+          .collect::<Result<_>>()?,
+      ),
+      Value::Tagged(mut tagged) => {
+        tagged.value = self.substitute_value(tagged.value, env, typed)?;
+        Value::Tagged(tagged)
+      }
+      other => other,
+    })
+  }
+
+  fn substitute_text(&self, text: &str, env: &impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('$') {
+      out.push_str(&rest[..at]);
+      let tail = &rest[at..];
+      if let Some(escaped) = tail.strip_prefix("$${")
+        && placeholder(escaped).is_some()
+      {
+        out.push_str("${");
+        rest = escaped;
+        continue;
+      }
+      let Some((name, len)) = tail.strip_prefix("${").and_then(placeholder) else {
+        out.push('$');
+        rest = &tail[1..];
+        continue;
+      };
+      let line = text[..text.len() - tail.len()].matches('\n').count() + 1;
+      out.push_str(&self.resolve(name, line, env)?);
+      rest = &tail[2 + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+  }
+
+  fn resolve(
+    &self,
+    name: &str,
+    line: usize,
+    env: &impl Fn(&str) -> Option<String>,
+  ) -> Result<String> {
+    let (key, from_env) = match name.strip_prefix(PROFILE_PREFIX) {
+      Some(key) => (key, false),
+      None => (name, true),
+    };
+    let profile_value = self
+      .profile
+      .as_ref()
+      .and_then(|(_, keys)| keys.get(key).cloned());
+    if let Some(value) = self.set.get(key).cloned().or(profile_value) {
+      return Ok(value);
+    }
+    if from_env && let Some(value) = env(key) {
+      return Ok(value);
+    }
+    let profile = match &self.profile {
+      Some((profile, _)) => format!("profile '{profile}' has no key '{key}'"),
+      None => "no --profile was given".to_string(),
+    };
+    if from_env {
+      bail!(
+        "unresolved placeholder ${{{name}}} on line {line}: no --set {key}=…, {profile}, and the {key} environment variable is not set"
+      );
+    }
+    bail!("unresolved placeholder ${{{name}}} on line {line}: no --set {key}=… and {profile}");
+  }
+}
+
+/// Whether `parsed`, the rewritten text, is the document `expected` with
+/// its placeholders filled as strings. A number, boolean or null in
+/// `parsed` matches a string that reads as the same scalar, as an unquoted
+/// whole-scalar placeholder does.
+fn same_document(expected: &serde_yaml::Value, parsed: &serde_yaml::Value) -> bool {
+  use serde_yaml::Value;
+  match (expected, parsed) {
+    (Value::String(text), Value::Number(_) | Value::Bool(_) | Value::Null) => {
+      serde_yaml::from_str::<Value>(text).is_ok_and(|scalar| &scalar == parsed)
+    }
+    (Value::Sequence(expected), Value::Sequence(parsed)) => {
+      expected.len() == parsed.len()
+        && expected
+          .iter()
+          .zip(parsed)
+          .all(|(expected, parsed)| same_document(expected, parsed))
+    }
+    (Value::Mapping(expected), Value::Mapping(parsed)) => {
+      expected.len() == parsed.len()
+        && expected
+          .iter()
+          .zip(parsed)
+          .all(|((ek, ev), (pk, pv))| same_document(ek, pk) && same_document(ev, pv))
+    }
+    (Value::Tagged(expected), Value::Tagged(parsed)) => {
+      expected.tag == parsed.tag && same_document(&expected.value, &parsed.value)
+    }
+    (expected, parsed) => expected == parsed,
+  }
+}
+
+/// The name at the start of `text` and its length, when `text` reads
+/// `NAME}`: an identifier, or `profile.` and a profile key.
+fn placeholder(text: &str) -> Option<(&str, usize)> {
+  let end = text.find('}')?;
+  let name = &text[..end];
+  let valid = match name.strip_prefix(PROFILE_PREFIX) {
+    Some(key) => !key.is_empty() && key.chars().all(is_key_char),
+    None => {
+      name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+  };
+  valid.then_some((name, end))
+}
+
+fn is_key_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Splits a `--set key=value` pair at its first `=`.
+pub fn parse_set(pair: &str) -> Result<(String, String)> {
+  match pair.split_once('=') {
+    Some((key, value)) if !key.is_empty() && key.chars().all(is_key_char) => {
+      Ok((key.to_string(), value.to_string()))
+    }
+    _ => bail!("--set expects key=value, got '{pair}'"),
+  }
+}
+
+fn profile_path(name: &str) -> Result<PathBuf> {
+  if name.is_empty() || name.starts_with('.') || !name.chars().all(is_key_char) {
+    bail!("invalid profile name '{name}'; use letters, digits, '-', '_' and '.'");
+  }
+  let home = dirs::home_dir().context("Could not determine home directory")?;
+  Ok(
+    home
+      .join(".agentflow")
+      .join("profiles")
+      .join(format!("{name}.yml")),
+  )
+}
+
+/// A profile is a flat mapping of keys to strings, numbers or booleans.
+fn parse_profile(name: &str, text: &str) -> Result<BTreeMap<String, String>> {
+  let document: serde_yaml::Value =
+    serde_yaml::from_str(text).with_context(|| format!("Failed to parse profile '{name}'"))?;
+  let mapping = match document {
+    serde_yaml::Value::Mapping(mapping) => mapping,
+    serde_yaml::Value::Null => return Ok(BTreeMap::new()),
+    _ => bail!("profile '{name}' must be a mapping of keys to values"),
+  };
+  mapping
+    .into_iter()
+    .map(|(key, value)| {
+      let key = match key {
+        serde_yaml::Value::String(key) => key,
+        other => bail!("profile '{name}' has a non-string key: {other:?}"),
+      };
+      let value = match value {
+        serde_yaml::Value::String(value) => value,
+        serde_yaml::Value::Number(value) => value.to_string(),
+        serde_yaml::Value::Bool(value) => value.to_string(),
+        _ => bail!("profile '{name}' key '{key}' must be a string, number or boolean"),
+      };
+      Ok((key, value))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn vars(set: &[&str], profile: &str) -> WorkflowVars {
+    WorkflowVars {
+      set: set.iter().map(|pair| parse_set(pair).unwrap()).collect(),
+      profile: Some(("dev".to_string(), parse_profile("dev", profile).unwrap())),
+    }
+  }
+
+  fn env(name: &str) -> Option<String> {
+    match name {
+      "REGION" => Some("env-region".to_string()),
+      "ENDPOINT" => Some("env-endpoint".to_string()),
+      "TOKEN" => Some("env-token".to_string()),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn set_beats_profile_beats_env() {
+    let vars = vars(
+      &["endpoint=https://set.example", "retries=5"],
+      "endpoint: https://profile.example\nREGION: eu-1\nretries: 2\nverbose: true\n",
+    );
+    let text =
+      "a: ${endpoint}\nb: ${REGION}\nc: ${TOKEN}\nd: ${profile.retries}\ne: ${profile.verbose}\n";
+    assert_eq!(
+      vars.substitute_with(text, env).unwrap(),
+      "a: https://set.example\nb: eu-1\nc: env-token\nd: 5\ne: true\n"
+    );
+  }
+
+  #[test]
+  fn unresolved_placeholders_are_named_with_their_line() {
+    let vars = vars(&[], "REGION: eu-1\n");
+    let err = vars
+      .substitute_with("a: ok\nb: ${profile.TOKEN}\n", env)
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "unresolved placeholder ${profile.TOKEN} on line 2: no --set TOKEN=… and profile 'dev' has no key 'TOKEN'"
+    );
+    let err = WorkflowVars::default()
+      .substitute_with("x: ${MISSING_VAR}", env)
+      .unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .starts_with("unresolved placeholder ${MISSING_VAR} on line 1: no --set MISSING_VAR=…, no --profile was given")
+    );
+  }
+
+  #[test]
+  fn values_stay_one_scalar() {
+    let quoted = vars(&["greeting=hi: there # not a comment", "retries=3"], "");
+    let text = "a: Say ${greeting}\nb: ${retries}\n";
+    let document: serde_yaml::Value =
+      serde_yaml::from_str(&quoted.substitute_with(text, env).unwrap()).unwrap();
+    assert_eq!(
+      document["a"].as_str(),
+      Some("Say hi: there # not a comment")
+    );
+    assert_eq!(document["b"].as_u64(), Some(3));
+
+    // Rewritten in place when that parses to the same document.
+    let in_place = vars(&["quoted=7", "retries=3"], "");
+    assert_eq!(
+      in_place
+        .substitute_with("b: ${retries}\nc: \"${quoted}\"\n", env)
+        .unwrap(),
+      "b: 3\nc: \"7\"\n"
+    );
+  }
+
+  #[test]
+  fn escapes_and_non_names_are_left_alone() {
+    let vars = WorkflowVars::default();
+    let text = "price: ${{ price | round }}\nlit: $${HOME}\nshell: echo $1 ${1}\nend: $";
+    assert_eq!(
+      vars.substitute_with(text, env).unwrap(),
+      "price: ${{ price | round }}\nlit: ${HOME}\nshell: echo $1 ${1}\nend: $"
+    );
+    assert!(parse_set("novalue").is_err());
+    assert_eq!(
+      parse_set("url=http://x?a=b").unwrap(),
+      ("url".to_string(), "http://x?a=b".to_string())
+    );
+  }
+}
@@ -0,0 +1,397 @@
+//! `workflow run --watch`: re-run a workflow whenever its YAML, or a file
+//! it references, is saved.
+//!
+//! The watched set is the workflow file, the `--inputs-file`, and every
+//! existing file named by a node parameter, a workflow input default or a
+//! `--input` value (`@path` included). Relative paths resolve the way the
+//! nodes resolve them: against the node's `base_dir` parameter when it has
+//! one, else the current directory. Files the run writes are left out, so a
+//! run cannot retrigger itself: output parameters such as `output_path` or
+//! `save_to_file`, the `path` of a file node that writes or deletes, the
+//! `--output` file and everything under the run directory. The set is
+//! recomputed before each run, so a newly referenced prompt file is picked
+//! up on the next save. A change that lands mid-run cancels the in-flight
+//! flow through its `FlowCancellationToken` before the re-run starts.
+//! Ctrl+C stops the current run, drops the watcher and exits.
+
+use super::run::{RunEnd, RunOptions, run_once};
+use crate::shutdown::shutdown_signal;
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+/// Node parameters that name a file or directory the node writes.
+const WRITE_PARAMETERS: &[&str] = &[
+  "output",
+  "output_path",
+  "output_file",
+  "output_dir",
+  "save_to_file",
+  "assets_dir",
+  "cache_dir",
+  "state_dir",
+  "target_dir",
+];
+
+/// `file` node operations whose `path` is written rather than read.
+const WRITE_OPERATIONS: &[&str] = &["write", "append", "delete"];
+
+/// Quiet period after the last file event before a save counts as done.
+/// Editors often write a file in several steps (truncate, write,
+/// rename), which must not trigger several runs.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+enum Interrupt {
+  Changed,
+  Shutdown,
+}
+
+pub async fn watch(options: RunOptions) -> Result<()> {
+  let (tx, mut events) = unbounded_channel();
+  let mut watcher = notify::recommended_watcher(move |event| {
+    let _ = tx.send(event);
+  })
+  .context("Failed to start the file watcher")?;
+  let mut watched_dirs = BTreeSet::new();
+
+  for run in 1.. {
+    let files = watched_files(&options);
+    let dirs: BTreeSet<PathBuf> = files
+      .iter()
+      .filter_map(|file| file.parent().map(Path::to_path_buf))
+      .collect();
+    for dir in watched_dirs.difference(&dirs) {
+      let _ = watcher.unwatch(dir);
+    }
+    for dir in dirs.difference(&watched_dirs) {
+      watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+    watched_dirs = dirs;
+
+    print_separator(run, &files);
+    let interrupt = async {
+      tokio::select! {
+        _ = next_change(&mut events, &files) => Interrupt::Changed,
+        _ = shutdown_signal() => Interrupt::Shutdown,
+      }
+    };
+    let interrupted = match run_once(&options, interrupt).await {
+      Ok(RunEnd::Completed) => None,
+      Ok(RunEnd::Interrupted(reason)) => Some(reason),
+      Err(err) => {
+        eprintln!("❌ {err:#}");
+        None
+      }
+    };
+    let reason = match interrupted {
+      Some(reason) => reason,
+      None => {
+        println!("\n👀 Watching for changes (Ctrl+C to stop)...");
+        tokio::select! {
+          _ = next_change(&mut events, &files) => Interrupt::Changed,
+          _ = shutdown_signal() => Interrupt::Shutdown,
+        }
+      }
+    };
+    match reason {
+      Interrupt::Changed => println!("\n🔁 Change detected; re-running."),
+      Interrupt::Shutdown => {
+        eprintln!("\n🛑 Stopped watching (received SIGINT/SIGTERM)");
+        break;
+      }
+    }
+  }
+  Ok(())
+}
+
+fn print_separator(run: usize, files: &[PathBuf]) {
+  println!(
+    "\n──────── run #{} · {} ────────",
+    run,
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+  );
+  println!("👀 Watching {} file(s)", files.len());
+}
+
+/// Resolves once a debounced change to one of `files` arrives. Pends
+/// forever if the watcher shuts down, leaving Ctrl+C as the way out.
+async fn next_change(events: &mut UnboundedReceiver<notify::Result<Event>>, files: &[PathBuf]) {
+  loop {
+    let Some(event) = events.recv().await else {
+      return std::future::pending().await;
+    };
+    if is_relevant(&event, files) {
+      break;
+    }
+  }
+  while let Ok(Some(_)) = tokio::time::timeout(DEBOUNCE, events.recv()).await {}
+}
+
+fn is_relevant(event: &notify::Result<Event>, files: &[PathBuf]) -> bool {
+  let Ok(event) = event else {
+    return false;
+  };
+  matches!(
+    event.kind,
+    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+  ) && event.paths.iter().any(|path| files.contains(path))
+}
+
+/// The workflow file plus every existing file it references and does not
+/// write. Paths are absolute so they compare equal to the paths in watcher
+/// events.
+pub fn watched_files(options: &RunOptions) -> Vec<PathBuf> {
+  let workflow = absolute(Path::new(&options.workflow_file));
+  let cwd = std::env::current_dir().unwrap_or_default();
+  // (candidate, directory it is relative to)
+  let mut candidates = Vec::new();
+  let mut written = run_base_dir(options).into_iter().collect::<Vec<_>>();
+  written.extend(
+    options
+      .output
+      .iter()
+      .map(|output| absolute(Path::new(output))),
+  );
+  if let Ok(yaml) = std::fs::read_to_string(&workflow)
+    && let Ok(yaml) = options.vars.substitute(&yaml)
+    && let Ok(document) = serde_yaml::from_str::<serde_yaml::Value>(&yaml)
+  {
+    for node in document
+      .get("nodes")
+      .and_then(serde_yaml::Value::as_sequence)
+      .into_iter()
+      .flatten()
+    {
+      let Some(parameters) = node
+        .get("parameters")
+        .and_then(serde_yaml::Value::as_mapping)
+      else {
+        continue;
+      };
+      let base = parameters
+        .get("base_dir")
+        .and_then(serde_yaml::Value::as_str)
+        .map_or_else(|| cwd.clone(), |base_dir| cwd.join(base_dir));
+      let writes_path = node.get("type").and_then(serde_yaml::Value::as_str) == Some("file")
+        && parameters
+          .get("operation")
+          .and_then(serde_yaml::Value::as_str)
+          .is_some_and(|operation| WRITE_OPERATIONS.contains(&operation));
+      for (key, value) in parameters {
+        let key = key.as_str().unwrap_or_default();
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+        if WRITE_PARAMETERS.contains(&key) || (writes_path && key == "path") {
+          written.extend(strings.iter().map(|path| absolute(&base.join(path))));
+        } else {
+          candidates.extend(strings.into_iter().map(|path| (path, base.clone())));
+        }
+      }
+    }
+    if let Some(inputs) = document
+      .get("inputs")
+      .and_then(serde_yaml::Value::as_mapping)
+    {
+      for input in inputs.values() {
+        if let Some(default) = input.get("default") {
+          let mut strings = Vec::new();
+          collect_strings(default, &mut strings);
+          candidates.extend(strings.into_iter().map(|path| (path, cwd.clone())));
+        }
+      }
+    }
+  }
+  candidates.extend(
+    options
+      .input
+      .iter()
+      .map(|(_, value)| (value.clone(), cwd.clone())),
+  );
+
+  let mut files = vec![workflow.clone()];
+  // `--inputs-file` and `--input KEY @path` are read relative to the
+  // current directory, like any other CLI path.
+  let input_files = options.inputs_file.iter().map(String::as_str).chain(
+    options
+      .input
+      .iter()
+      .filter_map(|(_, value)| value.strip_prefix('@'))
+      .filter(|path| !path.starts_with('@')),
+  );
+  for path in input_files {
+    let path = absolute(Path::new(path));
+    if path.is_file() && !files.contains(&path) {
+      files.push(path);
+    }
+  }
+  for (candidate, base) in candidates {
+    if candidate.contains("{{") || candidate.contains('\n') || candidate.contains("://") {
+      continue;
+    }
+    let path = base.join(&candidate);
+    if path.is_file() {
+      let path = absolute(&path);
+      if !files.contains(&path) && !written.iter().any(|target| path.starts_with(target)) {
+        files.push(path);
+      }
+    }
+  }
+  files
+}
+
+/// The directory runs are recorded under, as `workflow run` picks it.
+fn run_base_dir(options: &RunOptions) -> Option<PathBuf> {
+  options
+    .run_dir
+    .clone()
+    .or_else(|| std::env::var("AGENTFLOW_RUN_DIR").ok())
+    .filter(|run_dir| !run_dir.trim().is_empty())
+    .map(PathBuf::from)
+    .or_else(|| dirs::home_dir().map(|home| home.join(".agentflow").join("runs")))
+    .map(|run_dir| absolute(&run_dir))
+}
+
+fn collect_strings(value: &serde_yaml::Value, out: &mut Vec<String>) {
+  match value {
+    serde_yaml::Value::String(text) => out.push(text.clone()),
+    serde_yaml::Value::Sequence(items) => items.iter().for_each(|item| collect_strings(item, out)),
+    serde_yaml::Value::Mapping(map) => map.values().for_each(|item| collect_strings(item, out)),
+    _ => {}
+  }
+}
+
+/// Canonical where possible (watcher events report resolved paths), else
+/// joined onto the current directory.
+fn absolute(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| {
+    std::env::current_dir()
+      .map(|cwd| cwd.join(path))
+      .unwrap_or_else(|_| path.to_path_buf())
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn options(workflow: &Path, input: Vec<(String, String)>) -> RunOptions {
+    RunOptions {
+      workflow_file: workflow.to_string_lossy().into_owned(),
+      output: None,
+      output_format: "json".to_string(),
+      quiet: false,
+      no_progress: false,
+      verbose: 0,
+      model: None,
+      input,
+      inputs_file: None,
+      stdin: None,
+      dry_run: false,
+      timeout: "60s".to_string(),
+      max_retries: 0,
+      execution_mode: "serial".to_string(),
+      max_concurrency: 4,
+      run_dir: None,
+      vars: Default::default(),
+      approval: Default::default(),
+    }
+  }
+
+  #[test]
+  fn referenced_files_are_watched_alongside_the_workflow() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("prompt.md"), "Summarise {{ text }}").unwrap();
+    std::fs::write(dir.path().join("data.csv"), "a,b\n1,2\n").unwrap();
+    let workflow = dir.path().join("flow.yml");
+    std::fs::write(
+      &workflow,
+      format!(
+        r#"
+name: watched
+inputs:
+  table: {{ required: false, default: "{root}/data.csv" }}
+nodes:
+  - id: read
+    type: file
+    parameters:
+      operation: read
+      path: prompt.md
+      base_dir: "{root}"
+      note: "not a file"
+      url: "https://example.com/prompt.md"
+"#,
+        root = dir.path().display()
+      ),
+    )
+    .unwrap();
+    let options = options(
+      &workflow,
+      vec![("extra".to_string(), "missing.txt".to_string())],
+    );
+
+    let files = watched_files(&options);
+
+    let root = dir.path().canonicalize().unwrap();
+    assert_eq!(
+      files,
+      vec![
+        root.join("flow.yml"),
+        root.join("prompt.md"),
+        root.join("data.csv")
+      ]
+    );
+  }
+
+  #[test]
+  fn files_the_run_writes_are_not_watched() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["prompt.md", "notes.md", "poster.png", "report.json"] {
+      std::fs::write(dir.path().join(name), "x").unwrap();
+    }
+    std::fs::create_dir(dir.path().join("runs")).unwrap();
+    std::fs::write(dir.path().join("runs").join("outputs.json"), "{}").unwrap();
+    let workflow = dir.path().join("flow.yml");
+    std::fs::write(
+      &workflow,
+      format!(
+        r#"
+name: writes
+nodes:
+  - id: read
+    type: file
+    parameters: {{ operation: read, path: "{root}/prompt.md" }}
+  - id: append
+    type: file
+    parameters: {{ operation: append, path: "{root}/notes.md", content: x }}
+  - id: draw
+    type: text_to_image
+    parameters: {{ model: m, prompt: p, output_path: "{root}/poster.png" }}
+  - id: echo
+    type: file
+    parameters: {{ operation: read, path: "{root}/runs/outputs.json" }}
+"#,
+        root = dir.path().display()
+      ),
+    )
+    .unwrap();
+    let mut options = options(&workflow, Vec::new());
+    options.output = Some(
+      dir
+        .path()
+        .join("report.json")
+        .to_string_lossy()
+        .into_owned(),
+    );
+    options.run_dir = Some(dir.path().join("runs").to_string_lossy().into_owned());
+
+    let files = watched_files(&options);
+
+    let root = dir.path().canonicalize().unwrap();
+    assert_eq!(files, vec![root.join("flow.yml"), root.join("prompt.md")]);
+  }
+}
@@ -0,0 +1,272 @@
+//! Global `--log-level`, `--log-file` and `--log-format` handling.
+//!
+//! [`init`] installs one `tracing` subscriber for the whole CLI before a
+//! command runs. Everything the workspace crates log through `tracing`
+//! (the `agentflow-llm` request summaries, for one) and through the
+//! `log` facade ends up there, as do the flow events a `workflow run`
+//! forwards via [`TracingListener`].
+//!
+//! The filter is `RUST_LOG` when it is set, else `--log-level`, else
+//! `warn`. Logs go to stderr; with `--log-file` they go to the file and
+//! stderr keeps only warnings and errors, so a verbose file log does not
+//! bury the command's own output.
+
+use agentflow_core::{EventListener, WorkflowEvent};
+use anyhow::{Context, Result, bail};
+use std::fs::OpenOptions;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Filter used when neither `RUST_LOG` nor `--log-level` is given.
+pub const DEFAULT_LOG_LEVEL: &str = "warn";
+
+/// Target of the events [`TracingListener`] emits, so `RUST_LOG` can
+/// select them on their own (`RUST_LOG=agentflow_core::events=info`).
+pub const EVENT_TARGET: &str = "agentflow_core::events";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+  #[default]
+  Text,
+  /// One JSON object per line.
+  Json,
+}
+
+impl std::str::FromStr for LogFormat {
+  type Err = anyhow::Error;
+
+  fn from_str(value: &str) -> Result<Self> {
+    match value {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      other => bail!("unknown log format '{other}' (expected text or json)"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+  /// `--log-level`: a level (`info`) or any `RUST_LOG`-style directive
+  /// list (`warn,agentflow_llm=debug`).
+  pub level: Option<String>,
+  pub file: Option<PathBuf>,
+  pub format: LogFormat,
+}
+
+/// The filter directives in effect: `RUST_LOG` wins over `--log-level`.
+fn directives(options: &LogOptions) -> String {
+  match std::env::var("RUST_LOG") {
+    Ok(value) if !value.trim().is_empty() => value,
+    _ => options
+      .level
+      .clone()
+      .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string()),
+  }
+}
+
+fn filter(directives: &str) -> Result<EnvFilter> {
+  EnvFilter::builder()
+    .parse(directives)
+    .with_context(|| format!("invalid log filter '{directives}'"))
+}
+
+type BoxedLayer = Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync>;
+
+fn layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+  W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+  let layer = tracing_subscriber::fmt::layer()
+    .with_writer(writer)
+    .with_ansi(ansi);
+  match format {
+    LogFormat::Text => layer.boxed(),
+    LogFormat::Json => layer.json().flatten_event(true).boxed(),
+  }
+}
+
+/// Install the CLI's subscriber. Fails on a bad filter or an unwritable
+/// `--log-file`; a subscriber installed earlier is left in place.
+pub fn init(options: &LogOptions) -> Result<()> {
+  let directives = directives(options);
+  let stderr = layer(
+    options.format,
+    std::io::stderr,
+    std::io::stderr().is_terminal(),
+  );
+  let layers: Vec<BoxedLayer> = match &options.file {
+    None => vec![stderr.with_filter(filter(&directives)?).boxed()],
+    Some(path) => {
+      if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+          .with_context(|| format!("Failed to create log directory {}", parent.display()))?;
+      }
+      let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+      vec![
+        layer(options.format, Arc::new(file), false)
+          .with_filter(filter(&directives)?)
+          .boxed(),
+        stderr
+          .with_filter(filter(&directives)?)
+          .with_filter(LevelFilter::WARN)
+          .boxed(),
+      ]
+    }
+  };
+  let _ = tracing_subscriber::registry().with(layers).try_init();
+  Ok(())
+}
+
+/// Forwards flow events to `tracing` under [`EVENT_TARGET`]: workflow
+/// and node transitions at `info`, the rest at `debug`. Failures stay
+/// at `info` too, since the command reports them itself and the default
+/// `warn` filter would print them twice. Node outputs and LLM prompts
+/// are not logged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingListener;
+
+impl EventListener for TracingListener {
+  fn on_event(&self, event: &WorkflowEvent) {
+    let kind = event.event_type();
+    match event {
+      WorkflowEvent::WorkflowStarted { workflow_id, .. } => {
+        tracing::info!(target: EVENT_TARGET, event = kind, workflow_id, "workflow started");
+      }
+      WorkflowEvent::WorkflowCompleted {
+        workflow_id,
+        duration,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        duration_ms = duration.as_millis() as u64,
+        "workflow completed"
+      ),
+      WorkflowEvent::WorkflowFailed {
+        workflow_id,
+        error,
+        duration,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        error = %crate::redaction::redact_cli_text(error.clone()),
+        duration_ms = duration.as_millis() as u64,
+        "workflow failed"
+      ),
+      WorkflowEvent::WorkflowCancelled {
+        workflow_id,
+        reason,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        reason,
+        "workflow cancelled"
+      ),
+      WorkflowEvent::NodeStarted {
+        workflow_id,
+        node_id,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        node_id,
+        "node started"
+      ),
+      WorkflowEvent::NodeCompleted {
+        workflow_id,
+        node_id,
+        duration,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        node_id,
+        duration_ms = duration.as_millis() as u64,
+        "node completed"
+      ),
+      WorkflowEvent::NodeFailed {
+        workflow_id,
+        node_id,
+        error,
+        duration,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        node_id,
+        error = %crate::redaction::redact_cli_text(error.clone()),
+        duration_ms = duration.as_millis() as u64,
+        "node failed"
+      ),
+      WorkflowEvent::NodeSkipped {
+        workflow_id,
+        node_id,
+        reason,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        node_id,
+        reason,
+        "node skipped"
+      ),
+      WorkflowEvent::RetryAttempt {
+        workflow_id,
+        node_id,
+        attempt,
+        max_attempts,
+        ..
+      } => tracing::info!(
+        target: EVENT_TARGET,
+        event = kind,
+        workflow_id,
+        node_id,
+        attempt,
+        max_attempts,
+        "retrying node"
+      ),
+      WorkflowEvent::NodeOutputCaptured { .. }
+      | WorkflowEvent::LLMPromptSent { .. }
+      | WorkflowEvent::LLMResponseReceived { .. } => {}
+      other => {
+        tracing::debug!(target: EVENT_TARGET, event = kind, workflow_id = other.workflow_id(), "{other}");
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn log_format_parses_known_names_only() {
+    assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+    assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+    assert!("yaml".parse::<LogFormat>().is_err());
+  }
+
+  #[test]
+  fn level_directives_are_validated() {
+    assert!(filter("info").is_ok());
+    assert!(filter("warn,agentflow_llm=debug").is_ok());
+    assert!(filter("agentflow_llm=loud").is_err());
+  }
+}
@@ -1,5 +1,8 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 
+use agentflow_cli::cli_error::{
+  ErrorCategory, ErrorFormat, ErrorReport, classify, error_format_from_args, exit_with,
+};
 use agentflow_cli::commands;
 
 #[cfg(feature = "plugin")]
@@ -7,14 +10,28 @@ use commands::plugin;
 #[cfg(feature = "rag")]
 use commands::rag;
 use commands::{
-  agent, audio, backup as backup_cmd, cleanup as cleanup_cmd, config as config_cmd, doctor,
-  eval as eval_cmd, harness, image, llm, marketplace, mcp, memory, serve as serve_cmd, skill,
-  trace, workflow,
+  agent, audio, backup as backup_cmd, cleanup as cleanup_cmd, completions, config as config_cmd,
+  doctor, eval as eval_cmd, harness, image, llm, man, marketplace, mcp, memory, runs,
+  serve as serve_cmd, skill, trace, workflow,
 };
 
 #[derive(Parser)]
 #[command(name = "agentflow", version, about = "AgentFlow V2 CLI")]
 struct Cli {
+  /// Log filter: a level (error, warn, info, debug, trace) or
+  /// `RUST_LOG`-style directives. `RUST_LOG` overrides it; default warn
+  #[arg(long, global = true, value_name = "LEVEL")]
+  log_level: Option<String>,
+  /// Write logs to this file (appended); stderr then keeps warnings and errors only
+  #[arg(long, global = true, value_name = "PATH")]
+  log_file: Option<std::path::PathBuf>,
+  /// Log line format
+  #[arg(long, global = true, default_value = "text", value_parser = ["text", "json"])]
+  log_format: String,
+  /// How a failing command reports its error on stderr: `Error: …` text,
+  /// or one JSON object `{code, category, message, node_id?}`
+  #[arg(long, global = true, default_value = "text", value_parser = ["text", "json"])]
+  error_format: String,
   #[command(subcommand)]
   command: Commands,
 }
@@ -23,13 +40,16 @@ struct Cli {
 enum Commands {
   /// Workflow orchestration commands
   Workflow(WorkflowArgs),
+  /// Inspect, resume and clean up persisted workflow runs
+  #[command(visible_alias = "run")]
+  Runs(RunsArgs),
   /// Audio generation and transcription commands
   Audio(AudioArgs),
   /// Configuration management commands
   Config(ConfigArgs),
   /// Image generation and understanding commands
   Image(ImageArgs),
-  /// LLM model discovery commands
+  /// LLM model discovery and chat commands
   Llm(LlmArgs),
   /// Model Context Protocol (MCP) commands
   Mcp(McpArgs),
@@ -55,6 +75,10 @@ enum Commands {
   Backup(BackupArgs),
   /// Run an agent eval dataset and emit a structured report
   Eval(EvalArgs),
+  /// Print a shell completion script (bash, zsh, fish, elvish or powershell)
+  Completions(CompletionsArgs),
+  /// Render man pages for agentflow and its subcommands
+  Man(ManArgs),
   #[cfg(feature = "plugin")]
   /// Plugin management commands (subprocess plugins)
   Plugin(PluginArgs),
@@ -75,6 +99,11 @@ struct WorkflowArgs {
   command: WorkflowCommands,
 }
 #[derive(Args)]
+struct RunsArgs {
+  #[command(subcommand)]
+  command: RunsCommands,
+}
+#[derive(Args)]
 struct AudioArgs {
   #[command(subcommand)]
   command: AudioCommands,
@@ -183,6 +212,21 @@ enum AgentCommands {
   },
 }
 
+#[derive(Args)]
+struct CompletionsArgs {
+  /// Shell to generate the script for
+  #[arg(value_enum)]
+  shell: clap_complete::Shell,
+}
+
+#[derive(Args)]
+struct ManArgs {
+  /// Write one page per command into this directory instead of printing
+  /// the top-level page to stdout
+  #[arg(long)]
+  out_dir: Option<std::path::PathBuf>,
+}
+
 #[derive(Args)]
 struct EvalArgs {
   #[command(subcommand)]
@@ -549,18 +593,59 @@ struct RagArgs {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum WorkflowCommands {
   Run {
+    /// Workflow YAML file, or `-` to read it from stdin
     workflow_file: String,
     #[arg(short, long)]
     watch: bool,
+    /// Write the run report to this path (`-` for stdout)
     #[arg(short, long)]
     output: Option<String>,
+    /// Format of the `--output` report: json, yaml or markdown
+    #[arg(long, default_value = "json", value_parser = ["json", "yaml", "markdown"])]
+    output_format: String,
+    /// Suppress progress output; print only the final summary
+    #[arg(short, long)]
+    quiet: bool,
+    /// Print plain per-node status lines instead of live progress bars
+    /// (the default when stderr is not a terminal)
+    #[arg(long)]
+    no_progress: bool,
+    /// Echo each completed node's outputs: `-v` a one-line preview,
+    /// `-vv` the full outputs
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
     /// Override the model used by LLM nodes in this workflow
     #[arg(short = 'm', long)]
     model: Option<String>,
+    /// Initial input; VALUE is parsed as JSON when possible, `@path`
+    /// reads a file and `-` reads stdin. Overrides `--inputs-file`.
     #[arg(short, long, num_args = 2, value_names = ["KEY", "VALUE"])]
     input: Vec<String>,
+    /// JSON or YAML file holding a mapping of initial inputs (`-` for
+    /// stdin)
+    #[arg(long)]
+    inputs_file: Option<String>,
+    /// Load `~/.agentflow/profiles/<NAME>.yml` for `${VAR}` and
+    /// `${profile.key}` placeholders in the workflow YAML
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Placeholder value; wins over the profile and the environment
+    #[arg(long, value_name = "KEY=VALUE")]
+    set: Vec<String>,
+    /// How `approval` nodes on the file or http channel are answered:
+    /// `auto` prompts when stdin and stderr are terminals, `prompt`
+    /// always reads the answer from stdin, `channel` only prints where
+    /// to answer
+    #[arg(long, default_value = "auto", value_parser = ["auto", "prompt", "channel"])]
+    approval: String,
+    /// Directory that relative paths inside the workflow resolve
+    /// against (default: the current directory); useful when the
+    /// workflow is piped on stdin
+    #[arg(long, value_name = "DIR")]
+    base_dir: Option<std::path::PathBuf>,
     #[arg(long)]
     dry_run: bool,
     #[arg(long, default_value = "60s")]
@@ -597,6 +682,35 @@ enum WorkflowCommands {
     #[arg(long, default_value = "text", value_parser = ["text", "json-envelope"])]
     format: String,
   },
+  /// Run every workflow in a directory or glob, several at a time, each
+  /// in its own run directory
+  RunMany {
+    /// Directory of `*.yml` / `*.yaml` workflows, or a glob such as
+    /// `flows/**/*.yml`
+    target: String,
+    /// How many workflows run at once
+    #[arg(long, default_value_t = 4)]
+    parallel: usize,
+    /// Keep starting workflows after one fails
+    #[arg(long)]
+    continue_on_error: bool,
+    /// With --continue-on-error, exit 0 even when some workflows failed
+    #[arg(long, requires = "continue_on_error")]
+    allow_failures: bool,
+    /// Write the aggregate summary (per-workflow status, duration and
+    /// cost) to this JSON file
+    #[arg(long, value_name = "FILE")]
+    summary: Option<std::path::PathBuf>,
+    /// Override the model used by LLM nodes in every workflow
+    #[arg(short = 'm', long)]
+    model: Option<String>,
+    /// Timeout for each workflow
+    #[arg(long, default_value = "60s")]
+    timeout: String,
+    /// Base directory for per-run workflow artifacts. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+  },
   /// List recent workflow runs from a remote server. Requires --server.
   List {
     #[arg(long)]
@@ -666,6 +780,21 @@ enum WorkflowCommands {
     #[arg(long, default_value = "text", value_parser = ["text", "json", "json-envelope"])]
     format: String,
   },
+  /// Scaffold a starter workflow and example inputs from a built-in template
+  New {
+    /// Directory to create; its name becomes the workflow name
+    #[arg(required_unless_present = "list")]
+    dir: Option<String>,
+    /// basic, llm-chain, map-reduce, rag or agent-loop
+    #[arg(short, long, default_value = "basic")]
+    template: String,
+    /// List the templates and whether this build can run them
+    #[arg(long)]
+    list: bool,
+    /// Overwrite workflow.yml / inputs.example.json if they exist
+    #[arg(long)]
+    force: bool,
+  },
   /// Validate workflow schema and dependencies without execution
   Validate {
     workflow_file: String,
@@ -680,6 +809,23 @@ enum WorkflowCommands {
     #[arg(long = "explain-permissions")]
     explain_permissions: bool,
   },
+  /// Compare two versions of a workflow node by node; exits 1 when they differ
+  Diff {
+    /// The earlier workflow YAML
+    old_file: String,
+    /// The later workflow YAML
+    new_file: String,
+    /// Output format: text, json (legacy bare body), or json-envelope
+    /// (canonical `CliJsonEnvelope` — `agentflow.cli/1` wire schema)
+    #[arg(long, default_value = "text", value_parser = ["text", "json", "json-envelope"])]
+    format: String,
+    /// Profile for `${...}` placeholders in both files, as for `workflow run`
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Placeholder value, as for `workflow run`
+    #[arg(long, value_name = "KEY=VALUE")]
+    set: Vec<String>,
+  },
   /// Inspect the resume plan for a checkpointed workflow run
   ResumePlan {
     /// Run / workflow id whose checkpoint should be inspected
@@ -698,24 +844,39 @@ enum WorkflowCommands {
   /// Debug and inspect workflow structure
   Debug {
     workflow_file: String,
-    /// Visualize the workflow DAG
+    /// Render the workflow DAG as a Mermaid (default) or DOT diagram
     #[arg(long)]
     visualize: bool,
-    /// Perform dry run without execution
+    /// Diagram format for --visualize
+    #[arg(long, default_value = "mermaid", value_parser = ["mermaid", "dot"])]
+    format: String,
+    /// Write the --visualize diagram to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<std::path::PathBuf>,
+    /// Also write an HTML page that renders the --visualize diagram
+    #[arg(long)]
+    html: Option<std::path::PathBuf>,
+    /// Same as --plan
     #[arg(long)]
     dry_run: bool,
-    /// Analyze workflow structure and dependencies
+    /// Print dependency depth, critical path, parallel groups and node types
     #[arg(long)]
     analyze: bool,
-    /// Validate workflow configuration
+    /// Validate the workflow; exits non-zero when it has errors
     #[arg(long)]
     validate: bool,
-    /// Show execution plan
+    /// Print the dry-run execution plan with run / skip / unknown per node
     #[arg(long)]
     plan: bool,
     /// Enable verbose output
     #[arg(short, long)]
     verbose: bool,
+    /// Profile for `${...}` placeholders, as for `workflow run`
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Placeholder value, as for `workflow run`
+    #[arg(long, value_name = "KEY=VALUE")]
+    set: Vec<String>,
   },
   /// Run a dynamic workflow: an LLM authors a declarative plan for the goal,
   /// which is compiled to a Flow and executed (in parallel where the plan
@@ -756,6 +917,96 @@ enum WorkflowCommands {
   },
 }
 
+#[derive(Subcommand)]
+enum RunsCommands {
+  /// List runs, newest first
+  List {
+    /// Run-artifact root. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+    /// Show at most this many runs
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Only show failed runs
+    #[arg(long)]
+    failed_only: bool,
+    /// Print the manifests as JSON
+    #[arg(long)]
+    json: bool,
+  },
+  /// Show per-node status and durations for one run
+  Show {
+    /// Run id, or a prefix matching exactly one run
+    run_id: String,
+    /// Print this node's persisted outputs instead
+    #[arg(long)]
+    node: Option<String>,
+    /// Run-artifact root. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+    /// Print the manifest (or node outputs) as JSON
+    #[arg(long)]
+    json: bool,
+  },
+  /// Continue a run from its latest checkpoint
+  Resume {
+    /// Run id, or a prefix matching exactly one run
+    run_id: String,
+    /// Run-artifact root. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+    /// Checkpoint directory (default: ~/.agentflow/checkpoints)
+    #[arg(long)]
+    checkpoint_dir: Option<String>,
+    /// Treat `Unknown` idempotency calls as safe to replay
+    #[arg(long)]
+    force_replay: bool,
+  },
+  /// Per-node token usage and cost of a run, priced with the current pricing table
+  Cost {
+    /// Run id, or a prefix matching exactly one run
+    run_id: String,
+    /// Run-artifact root. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+  },
+  /// Write a run as a self-contained HTML report
+  Report {
+    /// Run id, or a prefix matching exactly one run
+    run_id: String,
+    /// Report file to write; long values go to `<stem>_files/` beside it
+    #[arg(short, long, default_value = "report.html")]
+    output: std::path::PathBuf,
+    /// Copy the files the node outputs name into this directory and link them
+    #[arg(long, value_name = "DIR")]
+    include_artifacts: Option<std::path::PathBuf>,
+    /// Run-artifact root. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+  },
+  /// Delete runs that ended before a retention window
+  Clean {
+    /// Retention window, e.g. 12h, 7d or 4w
+    #[arg(long, default_value = "7d")]
+    older_than: String,
+    /// Keep failed runs regardless of age
+    #[arg(long)]
+    keep_failed: bool,
+    /// List what would be removed without deleting anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Run-artifact root. Defaults to AGENTFLOW_RUN_DIR or ~/.agentflow/runs.
+    #[arg(long)]
+    run_dir: Option<String>,
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+  },
+}
+
 #[derive(Subcommand)]
 enum AudioCommands {
   Asr {
@@ -790,10 +1041,31 @@ enum AudioCommands {
     #[arg(long, default_value = "wav")]
     format: String,
   },
+  /// Synthesise speech: `tts <TEXT> <VOICE> <OUTPUT>`, or
+  /// `tts --input-file <FILE|-> <VOICE> <OUTPUT>`
   Tts {
-    input: String,
-    voice: String,
-    output: String,
+    /// [TEXT] <VOICE> <OUTPUT>; TEXT is left out with --input-file
+    #[arg(
+      value_name = "ARGS",
+      num_args = 0..=3,
+      required_unless_present = "list_voices"
+    )]
+    args: Vec<String>,
+    /// Read the text from a file, or from stdin with `-`
+    #[arg(long)]
+    input_file: Option<String>,
+    /// Longest chunk sent in one request; longer text is split at sentence ends
+    #[arg(long, default_value_t = agentflow_llm::providers::modality::DEFAULT_TTS_CHUNK_CHARS as u64, value_parser = clap::value_parser!(u64).range(1..))]
+    max_chars: u64,
+    /// Split on markdown headings and write one file per chapter
+    #[arg(long)]
+    chapters: bool,
+    /// Join the chunks of the text (or of each chapter) into one WAV
+    #[arg(long)]
+    merge: bool,
+    /// Print the voices the model's provider reports and exit
+    #[arg(long)]
+    list_voices: bool,
     #[arg(short, long)]
     model: Option<String>,
     #[arg(long, default_value_t = 1.0)]
@@ -810,25 +1082,92 @@ enum ConfigCommands {
   Init {
     #[arg(short, long)]
     force: bool,
+    /// Pick providers, enter their API keys and choose a default model
+    #[arg(short, long)]
+    interactive: bool,
   },
   Show {
     section: Option<String>,
+    /// Show the configuration the registry loads, each field annotated
+    /// with its origin, API keys masked
+    #[arg(long)]
+    resolved: bool,
   },
   Validate,
+  /// Check API keys, endpoints, `~/.agentflow` and models per provider
+  Doctor {
+    /// Also call each provider's `validate_config` against its API
+    #[arg(long)]
+    live: bool,
+    /// Seconds to wait for each endpoint HEAD request
+    #[arg(long, default_value_t = 3)]
+    timeout: u64,
+    #[arg(long)]
+    json: bool,
+  },
+  /// Sync and check configured models against vendor model lists
+  Models {
+    #[command(subcommand)]
+    command: ConfigModelsCommands,
+  },
+}
+
+#[derive(Subcommand)]
+enum ConfigModelsCommands {
+  /// Add newly discovered models to your configuration
+  Update {
+    /// Only query this vendor (moonshot, dashscope, anthropic, google, step)
+    #[arg(long)]
+    vendor: Option<String>,
+    /// Print the changes without writing them
+    #[arg(long)]
+    dry_run: bool,
+  },
+  /// Check every configured model exists at its vendor; exits non-zero otherwise
+  Validate {
+    /// Only check models of this vendor
+    #[arg(long)]
+    vendor: Option<String>,
+  },
 }
 
 #[derive(Subcommand)]
 enum ImageCommands {
+  /// Generate images from one prompt, or a batch of prompts
   Generate {
-    prompt: String,
+    #[arg(
+      required_unless_present = "prompts_file",
+      conflicts_with = "prompts_file"
+    )]
+    prompt: Option<String>,
+    /// One prompt per line, or JSONL lines with `prompt` and optional `size` / `seed`
+    #[arg(long)]
+    prompts_file: Option<String>,
+    /// Text-to-image model (default: the provider's default model)
     #[arg(short, long)]
     model: Option<String>,
+    /// Provider to generate with; picks its default model
+    #[arg(long, value_parser = ["stepfun", "dashscope"])]
+    provider: Option<String>,
     #[arg(short, long, default_value = "1024x1024")]
     size: String,
-    #[arg(short, long)]
-    output: String,
+    /// File for a single image
+    #[arg(short, long, conflicts_with = "output_dir")]
+    output: Option<String>,
+    /// Directory for a batch; also gets a manifest.json
+    #[arg(long)]
+    output_dir: Option<String>,
     #[arg(short, long, default_value = "b64_json")]
     format: String,
+    /// Images per prompt
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    count: u32,
+    /// Images generated at the same time
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    parallel: u64,
+    /// Batch file names; placeholders {index}, {prompt_index}, {n}, {seed}, {slug}
+    #[arg(long, default_value = image::generate::DEFAULT_NAME_TEMPLATE)]
+    name_template: String,
     #[arg(long, default_value_t = 20)]
     steps: u32,
     #[arg(long, default_value_t = 7.5)]
@@ -841,8 +1180,12 @@ enum ImageCommands {
     input_image: Option<String>,
   },
   Understand {
+    /// The image to analyse; with --dir, the prompt
+    #[arg(value_name = "IMAGE_PATH")]
     image_path: String,
-    prompt: String,
+    /// The question about the image; omitted with --dir
+    #[arg(value_name = "PROMPT")]
+    prompt: Option<String>,
     #[arg(short, long)]
     model: Option<String>,
     #[arg(short, long)]
@@ -851,6 +1194,21 @@ enum ImageCommands {
     max_tokens: Option<u32>,
     #[arg(short, long)]
     output: Option<String>,
+    /// Run the prompt over every image in this directory
+    #[arg(long, value_name = "DIR")]
+    dir: Option<std::path::PathBuf>,
+    /// Images to pick under --dir, e.g. "*.jpg" or "**/*.png" (default: every image file in it)
+    #[arg(long, requires = "dir")]
+    glob: Option<String>,
+    /// Images analysed at the same time
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    parallel: u64,
+    /// Write each image's result to <DIR>/<image>.json
+    #[arg(long, value_name = "DIR", requires = "dir")]
+    output_dir: Option<std::path::PathBuf>,
+    /// Write all results to one JSONL file
+    #[arg(long, value_name = "FILE", requires = "dir")]
+    jsonl: Option<std::path::PathBuf>,
   },
 }
 
@@ -859,8 +1217,17 @@ enum LlmCommands {
   Models {
     #[arg(short, long)]
     provider: Option<String>,
+    /// Table of configured and discovered models with aliases, context
+    /// window, input types, price and whether the vendor lists them
     #[arg(short, long)]
     detailed: bool,
+    /// Re-fetch the vendors' model lists into the discovery cache
+    /// before showing the detailed view
+    #[arg(long, conflicts_with = "refresh_from_api")]
+    refresh: bool,
+    /// Print the detailed view as JSON
+    #[arg(long, conflicts_with = "refresh_from_api")]
+    json: bool,
     /// Live-query each OpenAI-compatible provider's `/v1/models`
     /// endpoint and print the delta vs the local registry: which
     /// models are NEW on the provider (not yet in your `models.yml`)
@@ -878,80 +1245,162 @@ enum LlmCommands {
     #[arg(long, default_value = "text", value_parser = ["text", "json-envelope"])]
     format: String,
   },
-  /// Deprecated compatibility stub. Use `skill chat`, `skill run`, or workflow `skill_agent`.
-  ///
-  /// Q3.5.1: the previous `--model` / `--system` / `--save` / `--load`
-  /// flags were accepted by clap but unconditionally dropped by the
-  /// handler — accepting structured arguments that the command will
-  /// never read is misleading. All flags removed; the command itself
-  /// stays so users who still type `agentflow llm chat` get the
-  /// redirect message.
-  ///
-  /// Old invocations almost always carry `--model <X>` and friends
-  /// because that was the pre-retirement contract. Without
-  /// `allow_hyphen_values`, clap would reject those flags as
-  /// "unexpected argument" with exit code 2 before our redirect
-  /// handler runs, leaving the user staring at a confusing clap
-  /// error instead of the migration message. Collect leftover args
-  /// into a `_extra` sink so any legacy flag form lands on the
-  /// retired-message handler.
-  #[command(hide = true)]
+  /// Ask a model one question and print the answer
+  Prompt {
+    /// The question
+    prompt: String,
+    /// Model to ask; defaults to gpt-4o
+    #[arg(short, long)]
+    model: Option<String>,
+    /// System prompt
+    #[arg(short, long)]
+    system: Option<String>,
+    #[arg(short, long)]
+    temperature: Option<f32>,
+    #[arg(long)]
+    max_tokens: Option<u32>,
+    /// Attach an image file or URL (repeatable)
+    #[arg(long = "image", value_name = "PATH_OR_URL")]
+    images: Vec<String>,
+    /// Append a text file to the prompt (repeatable, 256 KiB each)
+    #[arg(long = "file", value_name = "PATH")]
+    files: Vec<std::path::PathBuf>,
+    /// Ask the model for a JSON object
+    #[arg(long)]
+    json: bool,
+    /// Write the answer to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<std::path::PathBuf>,
+    /// Print token usage to stderr
+    #[arg(long)]
+    usage: bool,
+  },
+  /// Chat with a model, streaming each reply. Runs an interactive
+  /// session on a terminal and a single turn when stdin is piped.
   Chat {
-    #[arg(trailing_var_arg = true, allow_hyphen_values = true, hide = true)]
-    _extra: Vec<String>,
+    /// Model to talk to; defaults to the loaded history's model, then gpt-4o
+    #[arg(short, long)]
+    model: Option<String>,
+    /// System prompt for the conversation
+    #[arg(short, long)]
+    system: Option<String>,
+    /// Write the conversation to this JSON file after every turn
+    #[arg(long, value_name = "FILE")]
+    save: Option<std::path::PathBuf>,
+    /// Continue a conversation written by `--save` or `/save`
+    #[arg(long, value_name = "FILE")]
+    load: Option<std::path::PathBuf>,
   },
 }
 
+/// How an `agentflow mcp` subcommand reaches its server.
+#[derive(Args)]
+struct McpConnectionArgs {
+  /// Server command to execute (e.g., "npx -y @modelcontextprotocol/server-filesystem /tmp").
+  /// Kept for compatibility; prefer `--stdio`.
+  server_command: Vec<String>,
+  /// Spawn the server over stdio from one shell-quoted command line
+  #[arg(long, value_name = "COMMAND")]
+  stdio: Option<String>,
+  /// Connect to an HTTP MCP endpoint instead of spawning a process
+  #[arg(long, value_name = "URL")]
+  http: Option<String>,
+  /// Request timeout such as `30s` or `500ms`; overrides `--timeout-ms`
+  #[arg(long)]
+  timeout: Option<String>,
+  #[arg(long, default_value_t = 30000)]
+  timeout_ms: u64,
+  #[arg(long, default_value_t = 3)]
+  max_retries: u32,
+  /// Output format: text (colored progress) or json-envelope
+  /// (canonical `CliJsonEnvelope` — `agentflow.cli/1` wire schema)
+  #[arg(long, default_value = "text", value_parser = ["text", "json-envelope"])]
+  format: String,
+  /// Shorthand for `--format json-envelope`
+  #[arg(long)]
+  json: bool,
+}
+
+impl McpConnectionArgs {
+  fn into_parts(self) -> anyhow::Result<(mcp::connection::McpConnection, String)> {
+    let timeout = match self.timeout.as_deref() {
+      Some(raw) => workflow::run::parse_duration(raw)?,
+      None => std::time::Duration::from_millis(self.timeout_ms),
+    };
+    let connection = mcp::connection::McpConnection::from_flags(
+      self.server_command,
+      self.stdio.as_deref(),
+      self.http,
+      timeout,
+      self.max_retries,
+    )?;
+    let format = if self.json {
+      "json-envelope".to_string()
+    } else {
+      self.format
+    };
+    Ok((connection, format))
+  }
+}
+
 #[derive(Subcommand)]
 enum McpCommands {
   /// List available tools from an MCP server
   ListTools {
-    /// Server command to execute (e.g., "npx -y @modelcontextprotocol/server-filesystem /tmp")
-    server_command: Vec<String>,
-    #[arg(long, default_value_t = 30000)]
-    timeout_ms: u64,
-    #[arg(long, default_value_t = 3)]
-    max_retries: u32,
-    /// Output format: text (colored progress) or json-envelope
-    /// (canonical `CliJsonEnvelope` — `agentflow.cli/1` wire schema)
-    #[arg(long, default_value = "text", value_parser = ["text", "json-envelope"])]
-    format: String,
+    #[command(flatten)]
+    connection: McpConnectionArgs,
   },
-  /// Call a tool on an MCP server
+  /// Call a tool on an MCP server: `call <tool> --args '<json>'`
+  Call {
+    /// Tool name to call
+    tool: String,
+    /// Tool arguments as a JSON object
+    #[arg(long)]
+    args: Option<String>,
+    /// Output file path to save the result
+    #[arg(short, long)]
+    output: Option<String>,
+    /// Directory image results are saved to (default: current directory)
+    #[arg(long)]
+    save_dir: Option<std::path::PathBuf>,
+    #[command(flatten)]
+    connection: McpConnectionArgs,
+  },
+  /// Call a tool on an MCP server (older spelling of `call`)
   CallTool {
-    /// Server command to execute
-    server_command: Vec<String>,
     /// Tool name to call
     #[arg(short, long)]
     tool: String,
     /// Tool parameters as JSON string
     #[arg(short, long)]
     params: Option<String>,
-    #[arg(long, default_value_t = 30000)]
-    timeout_ms: u64,
-    #[arg(long, default_value_t = 3)]
-    max_retries: u32,
-    /// Output file path to save the result
+    /// Output file path to save the result. In envelope mode the file
+    /// carries the envelope, not the bare result, so it is
+    /// self-describing.
     #[arg(short, long)]
     output: Option<String>,
-    /// Output format: text (default) or json-envelope. In envelope
-    /// mode the file written by `--output` carries the envelope, not
-    /// the bare result, so the file is self-describing.
-    #[arg(long, default_value = "text", value_parser = ["text", "json-envelope"])]
-    format: String,
+    /// Directory image results are saved to (default: current directory)
+    #[arg(long)]
+    save_dir: Option<std::path::PathBuf>,
+    #[command(flatten)]
+    connection: McpConnectionArgs,
   },
   /// List available resources from an MCP server
   ListResources {
-    /// Server command to execute
-    server_command: Vec<String>,
-    #[arg(long, default_value_t = 30000)]
-    timeout_ms: u64,
-    #[arg(long, default_value_t = 3)]
-    max_retries: u32,
-    /// Output format: text (colored progress) or json-envelope
-    /// (canonical `CliJsonEnvelope` — `agentflow.cli/1` wire schema)
-    #[arg(long, default_value = "text", value_parser = ["text", "json-envelope"])]
-    format: String,
+    #[command(flatten)]
+    connection: McpConnectionArgs,
+  },
+  /// Read one resource from an MCP server
+  ReadResource {
+    /// Resource URI, e.g. `file:///tmp/notes.txt`
+    uri: String,
+    #[command(flatten)]
+    connection: McpConnectionArgs,
+  },
+  /// List prompt templates offered by an MCP server
+  ListPrompts {
+    #[command(flatten)]
+    connection: McpConnectionArgs,
   },
   /// Manage `~/.agentflow/mcp.toml` — the top-level MCP server registry
   Config {
@@ -1611,16 +2060,85 @@ fn load_agentflow_dotenv() {
 #[tokio::main]
 async fn main() {
   load_agentflow_dotenv();
-  let cli = Cli::parse();
+  let cli = match Cli::try_parse() {
+    Ok(cli) => cli,
+    Err(e) if e.use_stderr() && error_format_from_args() == ErrorFormat::Json => {
+      let message = e.render().to_string();
+      exit_with(
+        &ErrorReport::new(ErrorCategory::Usage, message.trim_end()),
+        ErrorFormat::Json,
+      )
+    }
+    Err(e) => e.exit(),
+  };
+  let error_format: ErrorFormat = cli.error_format.parse().unwrap_or_default();
+  let log_options = agentflow_cli::logging::LogOptions {
+    level: cli.log_level.clone(),
+    file: cli.log_file.clone(),
+    format: cli.log_format.parse().unwrap_or_default(),
+  };
+  if let Err(e) = agentflow_cli::logging::init(&log_options) {
+    exit_with(
+      &ErrorReport::new(ErrorCategory::Usage, format!("{:#}", e)),
+      error_format,
+    );
+  }
 
   let result = match cli.command {
+    Commands::Runs(args) => match args.command {
+      RunsCommands::List {
+        run_dir,
+        limit,
+        failed_only,
+        json,
+      } => runs::list::execute(run_dir, limit, failed_only, json).await,
+      RunsCommands::Show {
+        run_id,
+        node,
+        run_dir,
+        json,
+      } => runs::show::execute(run_id, run_dir, node, json).await,
+      RunsCommands::Resume {
+        run_id,
+        run_dir,
+        checkpoint_dir,
+        force_replay,
+      } => runs::resume::execute(run_id, run_dir, checkpoint_dir, force_replay).await,
+      RunsCommands::Cost {
+        run_id,
+        run_dir,
+        json,
+      } => runs::cost::execute(run_id, run_dir, json).await,
+      RunsCommands::Report {
+        run_id,
+        output,
+        include_artifacts,
+        run_dir,
+      } => runs::report::execute(run_id, run_dir, output, include_artifacts).await,
+      RunsCommands::Clean {
+        older_than,
+        keep_failed,
+        dry_run,
+        run_dir,
+        json,
+      } => runs::clean::execute(run_dir, older_than, keep_failed, dry_run, json).await,
+    },
     Commands::Workflow(args) => match args.command {
       WorkflowCommands::Run {
         workflow_file,
         watch,
         output,
+        output_format,
+        quiet,
+        no_progress,
+        verbose,
         model,
         input,
+        inputs_file,
+        profile,
+        set,
+        approval,
+        base_dir,
         dry_run,
         timeout,
         max_retries,
@@ -1654,37 +2172,49 @@ async fn main() {
           const EXECUTION_MODE_DEFAULT: &str = "serial";
           const MAX_CONCURRENCY_DEFAULT: usize = 4;
           const TIMEOUT_DEFAULT: &str = "60s";
-          let validation = workflow::server_ops::reject_local_only_flags(
-            model.as_deref(),
-            &execution_mode,
-            EXECUTION_MODE_DEFAULT,
-            max_concurrency,
-            MAX_CONCURRENCY_DEFAULT,
-            run_dir.as_deref(),
-            watch,
-            output.as_deref(),
-            &input,
-            dry_run,
-            &timeout,
-            TIMEOUT_DEFAULT,
-            max_retries,
-          );
+          let validation = if base_dir.is_some() {
+            Err(anyhow::anyhow!(
+              "--base-dir is local-only (the server resolves workflow paths on its own filesystem). Drop --base-dir when using --server."
+            ))
+          } else {
+            workflow::server_ops::reject_local_only_flags(
+              model.as_deref(),
+              &execution_mode,
+              EXECUTION_MODE_DEFAULT,
+              max_concurrency,
+              MAX_CONCURRENCY_DEFAULT,
+              run_dir.as_deref(),
+              watch,
+              output.as_deref(),
+              &input,
+              inputs_file.as_deref(),
+              dry_run,
+              &timeout,
+              TIMEOUT_DEFAULT,
+              max_retries,
+            )
+          };
           match validation {
             Err(err) => Err(err),
-            Ok(()) => match std::fs::read_to_string(&workflow_file) {
-              Ok(body) => {
-                workflow::server_ops::run_via_server(
-                  &server_url,
-                  auth_token.as_deref(),
-                  tenant.as_deref(),
-                  &body,
-                  &format,
-                )
-                .await
-              }
-              Err(e) => Err(anyhow::anyhow!(
-                "failed to read workflow file '{workflow_file}': {e}"
-              )),
+            // Placeholders are filled in here, so the server gets
+            // the same YAML an in-process run would parse.
+            Ok(()) => match workflow::run::read_workflow_file(&workflow_file) {
+              Ok(body) => match workflow::vars::WorkflowVars::load(profile.as_deref(), &set)
+                .and_then(|vars| vars.substitute(&body))
+              {
+                Ok(body) => {
+                  workflow::server_ops::run_via_server(
+                    &server_url,
+                    auth_token.as_deref(),
+                    tenant.as_deref(),
+                    &body,
+                    &format,
+                  )
+                  .await
+                }
+                Err(e) => Err(e),
+              },
+              Err(e) => Err(e),
             },
           }
         } else {
@@ -1696,14 +2226,23 @@ async fn main() {
             workflow_file,
             watch,
             output,
+            output_format,
+            quiet,
+            no_progress,
+            verbose,
             model,
             input_pairs,
+            inputs_file,
             dry_run,
             timeout,
             max_retries,
             execution_mode,
             max_concurrency,
             run_dir,
+            profile,
+            set,
+            approval,
+            base_dir,
           )
           .await
         }
@@ -1779,12 +2318,50 @@ async fn main() {
           "workflow logs requires --server <url> or AGENTFLOW_SERVER_URL to be set"
         )),
       },
+      WorkflowCommands::New {
+        dir,
+        template,
+        list,
+        force,
+      } => workflow::new::execute(dir, template, list, force).await,
+      WorkflowCommands::RunMany {
+        target,
+        parallel,
+        continue_on_error,
+        allow_failures,
+        summary,
+        model,
+        timeout,
+        run_dir,
+      } => {
+        workflow::run_many::execute(workflow::run_many::RunManyOptions {
+          target,
+          parallel,
+          continue_on_error,
+          allow_failures,
+          summary,
+          model,
+          timeout,
+          run_dir,
+        })
+        .await
+      }
       WorkflowCommands::Validate {
         workflow_file,
         format,
         strict,
         explain_permissions,
       } => workflow::validate::execute(workflow_file, format, strict, explain_permissions).await,
+      WorkflowCommands::Diff {
+        old_file,
+        new_file,
+        format,
+        profile,
+        set,
+      } => match workflow::vars::WorkflowVars::load(profile.as_deref(), &set) {
+        Ok(vars) => workflow::diff::execute(old_file, new_file, format, vars).await,
+        Err(e) => Err(e),
+      },
       WorkflowCommands::ResumePlan {
         run_id,
         checkpoint_dir,
@@ -1794,23 +2371,37 @@ async fn main() {
       WorkflowCommands::Debug {
         workflow_file,
         visualize,
+        format,
+        output,
+        html,
         dry_run,
         analyze,
         validate,
         plan,
         verbose,
-      } => {
-        workflow::debug::execute(
-          workflow_file,
-          visualize,
-          dry_run,
-          analyze,
-          validate,
-          plan,
-          verbose,
-        )
-        .await
-      }
+        profile,
+        set,
+      } => match (
+        workflow::debug::DiagramFormat::parse(&format),
+        workflow::vars::WorkflowVars::load(profile.as_deref(), &set),
+      ) {
+        (Ok(format), Ok(vars)) => {
+          workflow::debug::execute(workflow::debug::DebugOptions {
+            workflow_file,
+            visualize,
+            format,
+            output,
+            html,
+            analyze,
+            validate,
+            plan: plan || dry_run,
+            verbose,
+            vars,
+          })
+          .await
+        }
+        (Err(e), _) | (_, Err(e)) => Err(e),
+      },
       WorkflowCommands::Dynamic {
         goal,
         model,
@@ -1854,45 +2445,99 @@ async fn main() {
         output,
       } => audio::clone::execute(file_id, text, model, format, output).await,
       AudioCommands::Tts {
+        args,
+        input_file,
+        max_chars,
+        chapters,
+        merge,
+        list_voices,
         model,
-        voice,
-        input,
-        output,
         speed,
         format,
         emotion,
-      } => audio::tts::execute(input, model, voice, format, speed, output, emotion).await,
+      } => {
+        if list_voices {
+          audio::tts::list_voices(model).await
+        } else {
+          match audio::tts::split_positionals(args, input_file.is_some()) {
+            Ok((text, voice, output)) => {
+              audio::tts::execute(audio::tts::TtsOptions {
+                text,
+                input_file,
+                voice,
+                output,
+                model,
+                format,
+                speed,
+                emotion,
+                max_chars: max_chars as usize,
+                chapters,
+                merge,
+              })
+              .await
+            }
+            Err(e) => Err(e),
+          }
+        }
+      }
     },
     Commands::Config(args) => match args.command {
-      ConfigCommands::Init { force } => config_cmd::init::execute(force).await,
-      ConfigCommands::Show { section } => config_cmd::show::execute(section).await,
+      ConfigCommands::Init { force, interactive } => {
+        config_cmd::init::execute(force, interactive).await
+      }
+      ConfigCommands::Show { section, resolved } => {
+        config_cmd::show::execute(section, resolved).await
+      }
       ConfigCommands::Validate => config_cmd::validate::execute().await,
+      ConfigCommands::Doctor {
+        live,
+        timeout,
+        json,
+      } => config_cmd::doctor::execute(live, std::time::Duration::from_secs(timeout), json).await,
+      ConfigCommands::Models { command } => match command {
+        ConfigModelsCommands::Update { vendor, dry_run } => {
+          config_cmd::models::update(vendor, dry_run).await
+        }
+        ConfigModelsCommands::Validate { vendor } => config_cmd::models::validate(vendor).await,
+      },
     },
     Commands::Image(args) => match args.command {
       ImageCommands::Generate {
         prompt,
+        prompts_file,
         model,
+        provider,
         size,
         output,
+        output_dir,
         format,
+        count,
+        parallel,
+        name_template,
         steps,
         cfg_scale,
         seed,
         strength,
         input_image,
       } => {
-        image::generate::execute(
+        image::generate::execute(image::generate::GenerateOptions {
           prompt,
+          prompts_file,
           model,
+          provider,
           size,
           output,
+          output_dir,
           format,
           steps,
           cfg_scale,
           seed,
+          count,
+          parallel: parallel as usize,
+          name_template,
           strength,
           input_image,
-        )
+        })
         .await
       }
       ImageCommands::Understand {
@@ -1902,8 +2547,26 @@ async fn main() {
         temperature,
         max_tokens,
         output,
+        dir,
+        glob,
+        parallel,
+        output_dir,
+        jsonl,
       } => {
-        image::understand::execute(image_path, prompt, model, temperature, max_tokens, output).await
+        image::understand::execute(image::understand::UnderstandOptions {
+          image_path,
+          prompt,
+          model,
+          temperature,
+          max_tokens,
+          output,
+          dir,
+          glob,
+          parallel: parallel as usize,
+          output_dir,
+          jsonl,
+        })
+        .await
       }
     },
     Commands::Llm(args) => match args.command {
@@ -1911,50 +2574,79 @@ async fn main() {
         provider,
         detailed,
         refresh_from_api,
+        refresh,
+        json,
         format,
-      } => llm::models::execute(provider, detailed, refresh_from_api, format).await,
-      LlmCommands::Chat { _extra: _ } => Err(anyhow::anyhow!(
-        "`agentflow llm chat` has been retired. AgentFlow interactions are agent-first: use `agentflow skill chat`, `agentflow skill run`, or a workflow `skill_agent` node. Use `agentflow llm models` only for model discovery."
-      )),
-    },
-    Commands::Mcp(args) => match args.command {
-      McpCommands::ListTools {
-        server_command,
-        timeout_ms,
-        max_retries,
-        format,
-      } => {
-        mcp::list_tools::execute(server_command, Some(timeout_ms), Some(max_retries), format).await
-      }
-      McpCommands::CallTool {
-        server_command,
-        tool,
-        params,
-        timeout_ms,
-        max_retries,
+      } => llm::models::execute(provider, detailed, refresh_from_api, refresh, json, format).await,
+      LlmCommands::Prompt {
+        prompt,
+        model,
+        system,
+        temperature,
+        max_tokens,
+        images,
+        files,
+        json,
         output,
-        format,
+        usage,
       } => {
-        mcp::call_tool::execute(
-          server_command,
-          tool,
-          params,
-          Some(timeout_ms),
-          Some(max_retries),
+        llm::prompt::execute(llm::prompt::PromptOptions {
+          prompt,
+          model,
+          system,
+          temperature,
+          max_tokens,
+          images,
+          files,
+          json,
           output,
-          format,
-        )
+          usage,
+        })
         .await
       }
-      McpCommands::ListResources {
-        server_command,
-        timeout_ms,
-        max_retries,
-        format,
-      } => {
-        mcp::list_resources::execute(server_command, Some(timeout_ms), Some(max_retries), format)
-          .await
+      LlmCommands::Chat {
+        model,
+        system,
+        save,
+        load,
+      } => llm::chat::execute(model, system, save, load).await,
+    },
+    Commands::Mcp(args) => match args.command {
+      McpCommands::ListTools { connection } => match connection.into_parts() {
+        Ok((connection, format)) => mcp::list_tools::execute(connection, format).await,
+        Err(err) => Err(err),
+      },
+      McpCommands::Call {
+        tool,
+        args,
+        output,
+        save_dir,
+        connection,
       }
+      | McpCommands::CallTool {
+        tool,
+        params: args,
+        output,
+        save_dir,
+        connection,
+      } => match connection.into_parts() {
+        Ok((connection, format)) => {
+          mcp::call_tool::execute(connection, tool, args, output, save_dir, format).await
+        }
+        Err(err) => Err(err),
+      },
+      McpCommands::ListResources { connection } => match connection.into_parts() {
+        Ok((connection, format)) => mcp::list_resources::execute(connection, format).await,
+        Err(err) => Err(err),
+      },
+      McpCommands::ReadResource { uri, connection } => match connection.into_parts() {
+        Ok((connection, format)) => mcp::read_resource::execute(connection, uri, format).await,
+        Err(err) => Err(err),
+      },
+      McpCommands::ListPrompts { connection } => match connection.into_parts() {
+        Ok((connection, format)) => mcp::list_prompts::execute(connection, format).await,
+        Err(err) => Err(err),
+      },
       McpCommands::Config { command } => match command {
         McpConfigCommands::Path => mcp::config::run_path(),
         McpConfigCommands::Validate => mcp::config::run_validate(),
@@ -2225,6 +2917,8 @@ async fn main() {
         fail_on_status,
       } => eval_cmd::execute(dataset_dir, format, filter, fail_on_status).await,
     },
+    Commands::Completions(args) => completions::execute(Cli::command(), args.shell).await,
+    Commands::Man(args) => man::execute(Cli::command(), args.out_dir),
     Commands::Serve(args) => {
       serve_cmd::execute(
         args.bind,
@@ -2508,7 +3202,6 @@ async fn main() {
     // with just "Error: Validation failed", swallowing the
     // structured `SkillError::ValidationError.message` that explained
     // *why*). See P9.1 / F-AF-1 in the L1+L3 reflection doc.
-    eprintln!("Error: {:#}", e);
-    std::process::exit(1);
+    exit_with(&classify(&e), error_format);
   }
 }
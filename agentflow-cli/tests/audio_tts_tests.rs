@@ -0,0 +1,303 @@
+//! `agentflow audio tts` against a stub StepFun server: text from a
+//! file or stdin, chunk boundaries and file names of a multi-heading
+//! `--chapters` script, `--merge` into one WAV per chapter, and
+//! `--list-voices`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use assert_cmd::Command;
+use axum::{
+  Json, Router,
+  extract::{Query, State},
+  routing::{get, post},
+};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+const SCRIPT: &str = "Opening words before any heading.
+
+# The Beginning
+It was a quiet morning. The lighthouse keeper woke early and climbed the stairs.
+
+## Storm Warning
+Clouds gathered.
+
+# Epilogue
+All was calm again.
+";
+
+/// The chunks of `SCRIPT` at `--max-chars 40`, in order.
+const CHUNKS: &[&str] = &[
+  "Opening words before any heading.",
+  "The Beginning\nIt was a quiet morning.",
+  "The lighthouse keeper woke early and",
+  "climbed the stairs.",
+  "Storm Warning\nClouds gathered.",
+  "Epilogue\nAll was calm again.",
+];
+
+#[derive(Clone, Default)]
+struct Stub {
+  /// The `input` of every speech request, in arrival order.
+  inputs: Arc<Mutex<Vec<String>>>,
+}
+
+/// A 16 kHz mono 16-bit WAV whose samples are the text's bytes, so
+/// every file shows which chunks it holds.
+fn wav(data: &[u8]) -> Vec<u8> {
+  let mut wav = b"RIFF".to_vec();
+  wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+  wav.extend_from_slice(b"WAVEfmt ");
+  wav.extend_from_slice(&16u32.to_le_bytes());
+  wav.extend_from_slice(&[1, 0, 1, 0, 0x80, 0x3e, 0, 0, 0, 0x7d, 0, 0, 2, 0, 16, 0]);
+  wav.extend_from_slice(b"data");
+  wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+  wav.extend_from_slice(data);
+  wav
+}
+
+async fn speech(State(stub): State<Stub>, Json(body): Json<Value>) -> Vec<u8> {
+  let input = body["input"].as_str().unwrap_or_default().to_string();
+  stub.inputs.lock().unwrap().push(input.clone());
+  wav(input.as_bytes())
+}
+
+async fn voices(Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+  let voice = |id: &str| json!({ "id": id, "file_id": format!("file-{id}"), "created_at": 1 });
+  match query.get("after").map(String::as_str) {
+    None => Json(json!({
+      "object": "list",
+      "data": [voice("voice-a"), voice("voice-b")],
+      "has_more": true,
+      "last_id": "voice-b",
+    })),
+    Some(_) => Json(json!({
+      "object": "list",
+      "data": [voice("voice-c")],
+      "has_more": false,
+    })),
+  }
+}
+
+async fn spawn_stub() -> (String, Stub) {
+  let stub = Stub::default();
+  let router = Router::new()
+    .route("/v1/audio/speech", post(speech))
+    .route("/v1/audio/voices", get(voices))
+    .with_state(stub.clone());
+  let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+  let base = format!("http://{}", listener.local_addr().expect("local addr"));
+  tokio::spawn(async move {
+    let _ = axum::serve(listener, router.into_make_service()).await;
+  });
+  tokio::time::sleep(Duration::from_millis(80)).await;
+  (base, stub)
+}
+
+fn setup_home(base: &str) -> TempDir {
+  let home = TempDir::new().unwrap();
+  let config_dir = home.path().join(".agentflow");
+  std::fs::create_dir_all(&config_dir).unwrap();
+  std::fs::write(
+    config_dir.join("models.yml"),
+    format!(
+      r#"
+models:
+  stub-tts:
+    vendor: stepfun
+    type: tts
+    base_url: {base}/v1
+providers:
+  stepfun:
+    api_key_env: STEPFUN_API_KEY
+"#
+    ),
+  )
+  .unwrap();
+  std::fs::write(home.path().join("script.md"), SCRIPT).unwrap();
+  home
+}
+
+async fn run(home: &TempDir, args: &[&str], stdin: Option<&str>) -> std::process::Output {
+  let home_path = home.path().to_path_buf();
+  let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+  let stdin = stdin.map(str::to_string);
+  tokio::task::spawn_blocking(move || {
+    let mut cmd = Command::cargo_bin("agentflow").unwrap();
+    cmd
+      .env("HOME", &home_path)
+      .env("STEPFUN_API_KEY", "test-step")
+      .current_dir(&home_path)
+      .args(["audio", "tts", "--model", "stub-tts"])
+      .args(&args);
+    if let Some(stdin) = stdin {
+      cmd.write_stdin(stdin);
+    }
+    cmd.output().unwrap()
+  })
+  .await
+  .unwrap()
+}
+
+fn assert_success(output: &std::process::Output) {
+  assert!(
+    output.status.success(),
+    "stdout: {}\nstderr: {}",
+    String::from_utf8_lossy(&output.stdout),
+    String::from_utf8_lossy(&output.stderr)
+  );
+}
+
+/// File name → the text its WAV samples spell out.
+fn written(dir: &std::path::Path) -> Vec<(String, String)> {
+  let mut files: Vec<(String, String)> = std::fs::read_dir(dir)
+    .unwrap()
+    .map(|entry| {
+      let entry = entry.unwrap();
+      let bytes = std::fs::read(entry.path()).unwrap();
+      assert_eq!(&bytes[..4], b"RIFF");
+      (
+        entry.file_name().to_string_lossy().into_owned(),
+        String::from_utf8(bytes[44..].to_vec()).unwrap(),
+      )
+    })
+    .collect();
+  files.sort();
+  files
+}
+
+#[tokio::test]
+async fn chapters_from_a_file_are_chunked_and_named_in_order() {
+  let (base, stub) = spawn_stub().await;
+  let home = setup_home(&base);
+
+  let output = run(
+    &home,
+    &[
+      "--input-file",
+      "script.md",
+      "--chapters",
+      "--max-chars",
+      "40",
+      "--format",
+      "wav",
+      "cixingnansheng",
+      "out/book.wav",
+    ],
+    None,
+  )
+  .await;
+  assert_success(&output);
+  assert_eq!(*stub.inputs.lock().unwrap(), CHUNKS);
+
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(
+    stdout.contains("[3/6] chapter 2 \"The Beginning\" part 2: 36 chars"),
+    "{stdout}"
+  );
+  assert_eq!(
+    written(&home.path().join("out")),
+    [
+      ("book_01_intro.wav", CHUNKS[0]),
+      ("book_02_the-beginning_001.wav", CHUNKS[1]),
+      ("book_02_the-beginning_002.wav", CHUNKS[2]),
+      ("book_02_the-beginning_003.wav", CHUNKS[3]),
+      ("book_03_storm-warning.wav", CHUNKS[4]),
+      ("book_04_epilogue.wav", CHUNKS[5]),
+    ]
+    .map(|(name, text)| (name.to_string(), text.to_string()))
+  );
+}
+
+#[tokio::test]
+async fn stdin_chapters_merge_into_one_wav_each() {
+  let (base, stub) = spawn_stub().await;
+  let home = setup_home(&base);
+
+  let output = run(
+    &home,
+    &[
+      "--input-file",
+      "-",
+      "--chapters",
+      "--merge",
+      "--max-chars",
+      "40",
+      "--format",
+      "wav",
+      "cixingnansheng",
+      "merged/book.wav",
+    ],
+    Some(SCRIPT),
+  )
+  .await;
+  assert_success(&output);
+  assert_eq!(stub.inputs.lock().unwrap().len(), CHUNKS.len());
+  assert_eq!(
+    written(&home.path().join("merged")),
+    [
+      ("book_01_intro.wav", CHUNKS[0].to_string()),
+      ("book_02_the-beginning.wav", CHUNKS[1..4].concat()),
+      ("book_03_storm-warning.wav", CHUNKS[4].to_string()),
+      ("book_04_epilogue.wav", CHUNKS[5].to_string()),
+    ]
+    .map(|(name, text)| (name.to_string(), text))
+  );
+
+  // Plain text without --chapters or --merge: one file per chunk.
+  let output = run(
+    &home,
+    &[
+      "--max-chars",
+      "20",
+      "--format",
+      "wav",
+      "One short line. Then another one.",
+      "cixingnansheng",
+      "plain/speech.wav",
+    ],
+    None,
+  )
+  .await;
+  assert_success(&output);
+  assert_eq!(
+    written(&home.path().join("plain")),
+    [
+      ("speech_001.wav", "One short line."),
+      ("speech_002.wav", "Then another one."),
+    ]
+    .map(|(name, text)| (name.to_string(), text.to_string()))
+  );
+
+  let refused = run(
+    &home,
+    &["--merge", "hello", "cixingnansheng", "speech.mp3"],
+    None,
+  )
+  .await;
+  assert!(!refused.status.success());
+  assert!(String::from_utf8_lossy(&refused.stderr).contains("use --format wav"));
+}
+
+#[tokio::test]
+async fn list_voices_follows_pages() {
+  let (base, _stub) = spawn_stub().await;
+  let home = setup_home(&base);
+
+  let output = run(&home, &["--list-voices"], None).await;
+  assert_success(&output);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(
+    stdout.contains("Voices from provider 'stepfun'"),
+    "{stdout}"
+  );
+  for id in ["voice-a", "voice-b", "voice-c"] {
+    assert!(
+      stdout.contains(&format!("  {id}  — custom voice from file file-{id}")),
+      "{stdout}"
+    );
+  }
+}
@@ -0,0 +1,172 @@
+//! The global `--log-level`, `--log-file` and `--log-format` flags: a
+//! mock-provider `workflow run` writes parseable JSON lines holding the
+//! flow events and the LLM request summaries, and `RUST_LOG` overrides
+//! `--log-level`.
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+const WORKFLOW: &str = r#"
+name: "Logged"
+nodes:
+  - id: ask
+    type: llm
+    parameters:
+      model: "mock-model"
+      prompt: "Say hi"
+  - id: done
+    type: template
+    dependencies: [ask]
+    parameters:
+      template: "ok"
+"#;
+
+fn setup() -> TempDir {
+  let home = TempDir::new().unwrap();
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    r#"
+models:
+  mock-model:
+    vendor: mock
+    type: text
+    model_id: mock-model
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#,
+  )
+  .unwrap();
+  fs::write(home.path().join("logged.yml"), WORKFLOW).unwrap();
+  home
+}
+
+fn run(home: &TempDir, args: &[&str]) -> std::process::Output {
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .env_remove("RUST_LOG")
+    .env("MOCK_API_KEY", "test")
+    .env("AGENTFLOW_MOCK_RESPONSE", "hi there")
+    .current_dir(home.path())
+    .args(args)
+    .args(["workflow", "run", "logged.yml", "--no-progress"])
+    .output()
+    .unwrap()
+}
+
+fn json_lines(path: &std::path::Path) -> Vec<Value> {
+  fs::read_to_string(path)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{e}: {line}")))
+    .collect()
+}
+
+#[test]
+fn json_log_file_holds_flow_events_and_llm_summaries() {
+  let home = setup();
+  let output = run(
+    &home,
+    &[
+      "--log-level",
+      "info",
+      "--log-format",
+      "json",
+      "--log-file",
+      "logs/run.jsonl",
+    ],
+  );
+  assert!(output.status.success(), "{output:?}");
+  // Info lines go to the file only.
+  assert!(!String::from_utf8_lossy(&output.stderr).contains("\"level\""));
+
+  let lines = json_lines(&home.path().join("logs/run.jsonl"));
+  let events: Vec<(&str, &str)> = lines
+    .iter()
+    .filter(|line| line["target"] == "agentflow_core::events")
+    .map(|line| {
+      (
+        line["event"].as_str().unwrap(),
+        line["node_id"].as_str().unwrap_or(""),
+      )
+    })
+    .collect();
+  assert_eq!(
+    events,
+    [
+      ("workflow.started", ""),
+      ("node.started", "ask"),
+      ("node.completed", "ask"),
+      ("node.started", "done"),
+      ("node.completed", "done"),
+      ("workflow.completed", ""),
+    ]
+  );
+  assert!(lines.iter().all(|line| line["level"] == "INFO"));
+  let workflow_id = &lines[0]["workflow_id"];
+  assert!(workflow_id.is_string());
+  assert!(
+    lines
+      .iter()
+      .filter(|line| line["target"] == "agentflow_core::events")
+      .all(|line| &line["workflow_id"] == workflow_id)
+  );
+  assert!(lines.iter().any(|line| {
+    line["target"]
+      .as_str()
+      .is_some_and(|target| target.starts_with("agentflow_llm"))
+      && line["message"]
+        .as_str()
+        .is_some_and(|message| message.starts_with("LLM request completed: model=mock-model"))
+  }));
+  assert!(
+    !fs::read_to_string(home.path().join("logs/run.jsonl"))
+      .unwrap()
+      .contains("Say hi")
+  );
+}
+
+#[test]
+fn rust_log_overrides_log_level() {
+  let home = setup();
+  let output = Command::cargo_bin("agentflow")
+    .unwrap()
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .env("RUST_LOG", "agentflow_core::events=info")
+    .env("MOCK_API_KEY", "test")
+    .env("AGENTFLOW_MOCK_RESPONSE", "hi there")
+    .current_dir(home.path())
+    .args([
+      "--log-level",
+      "error",
+      "--log-format",
+      "json",
+      "--log-file",
+      "run.jsonl",
+      "workflow",
+      "run",
+      "logged.yml",
+      "--no-progress",
+    ])
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{output:?}");
+  let lines = json_lines(&home.path().join("run.jsonl"));
+  assert_eq!(lines.len(), 6);
+  assert!(
+    lines
+      .iter()
+      .all(|line| line["target"] == "agentflow_core::events")
+  );
+
+  let refused = run(&home, &["--log-level", "agentflow_llm=loud"]);
+  assert!(!refused.status.success());
+  assert!(String::from_utf8_lossy(&refused.stderr).contains("invalid log filter"));
+}
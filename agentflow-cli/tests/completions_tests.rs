@@ -0,0 +1,96 @@
+//! `agentflow completions` and `agentflow man`. These only inspect the
+//! generated text; no shell is spawned.
+
+use assert_cmd::Command;
+use std::fs;
+use tempfile::TempDir;
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_MODELS_CONFIG");
+  cmd
+}
+
+fn completions(home: &TempDir, shell: &str) -> String {
+  let output = agentflow(home)
+    .args(["completions", shell])
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn bash_script_completes_subcommands_and_builtin_models() {
+  let home = TempDir::new().unwrap();
+  let script = completions(&home, "bash");
+
+  let workflow = script
+    .split("agentflow__workflow)")
+    .nth(1)
+    .and_then(|rest| rest.lines().find(|line| line.trim().starts_with("opts=")))
+    .expect("workflow case in the bash script");
+  assert!(workflow.contains(" run "), "{workflow}");
+
+  let run = script
+    .split("agentflow__workflow__run)")
+    .nth(1)
+    .expect("workflow run case in the bash script");
+  let model = run
+    .split("--model)")
+    .nth(1)
+    .and_then(|rest| rest.lines().nth(1))
+    .expect("--model completion for workflow run");
+  assert!(model.contains("compgen -W \""), "{model}");
+  assert!(model.contains(" gpt-4o "), "{model}");
+  assert!(model.contains(" gpt-4o-mini "), "{model}");
+}
+
+#[test]
+fn model_values_come_from_the_user_configuration() {
+  let home = TempDir::new().unwrap();
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    "models:\n  house-model:\n    vendor: mock\n    type: chat\nproviders: {}\n",
+  )
+  .unwrap();
+
+  let zsh = completions(&home, "zsh");
+  assert!(zsh.contains(
+    "'--model=[Override the model used by LLM nodes in this workflow]:MODEL:(house-model)'"
+  ));
+  assert!(!zsh.contains("gpt-4o-mini"));
+
+  let fish = completions(&home, "fish");
+  assert!(fish.contains("-l model") && fish.contains("house-model"));
+
+  // PowerShell still gets a script, just without value hints.
+  assert!(completions(&home, "powershell").contains("Register-ArgumentCompleter"));
+}
+
+#[test]
+fn man_writes_a_page_per_command() {
+  let home = TempDir::new().unwrap();
+  let out_dir = home.path().join("man");
+
+  agentflow(&home)
+    .args(["man", "--out-dir", out_dir.to_str().unwrap()])
+    .assert()
+    .success();
+  let page = fs::read_to_string(out_dir.join("agentflow-workflow-run.1")).unwrap();
+  assert!(page.contains(".TH agentflow-workflow-run 1"));
+  assert!(page.contains("\\fB\\-\\-model\\fR \\fI<MODEL>\\fR"));
+  assert!(out_dir.join("agentflow.1").exists());
+  assert!(!out_dir.join("agentflow-help.1").exists());
+
+  let top = agentflow(&home).arg("man").assert().success();
+  let top = String::from_utf8(top.get_output().stdout.clone()).unwrap();
+  assert!(top.contains(".TH agentflow 1"));
+  assert!(top.contains("agentflow\\-workflow(1)"));
+}
@@ -176,32 +176,20 @@ fn llm_models_reads_user_model_config() {
     .assert()
     .success()
     .stdout(predicate::str::contains("test-model"))
-    .stdout(predicate::str::contains("Vendor: openai"));
+    .stdout(
+      predicate::str::is_match(r"(?m)^openai +test-model +- +- +text +- +configured$").unwrap(),
+    );
 }
 
 #[test]
-fn llm_chat_is_retired_with_agent_first_guidance() {
-  let mut cmd = Command::cargo_bin("agentflow").unwrap();
-  cmd
-    .args(["llm", "chat", "--model", "mock-model"])
-    .assert()
-    .failure()
-    .stderr(predicate::str::contains(
-      "`agentflow llm chat` has been retired",
-    ))
-    .stderr(predicate::str::contains("agentflow skill chat"))
-    .stderr(predicate::str::contains("skill_agent"));
-}
-
-#[test]
-fn llm_help_hides_chat_subcommand() {
+fn llm_help_lists_models_and_chat() {
   let mut cmd = Command::cargo_bin("agentflow").unwrap();
   cmd
     .args(["llm", "--help"])
     .assert()
     .success()
     .stdout(predicate::str::contains("models"))
-    .stdout(predicate::str::contains("chat").not());
+    .stdout(predicate::str::contains("chat"));
 }
 
 #[test]
@@ -292,3 +280,155 @@ fn plugin_command_explains_missing_feature_in_default_build() {
     .stderr(predicate::str::contains("not available in this binary"))
     .stderr(predicate::str::contains("--features plugin"));
 }
+
+#[test]
+fn config_show_resolved_annotates_origins_and_masks_keys() {
+  let home = TempDir::new().unwrap();
+  write_config(&home);
+
+  let output = Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["config", "show", "--resolved"])
+    .env("HOME", home.path())
+    .env("AGENTFLOW_MODELS_CONFIG", "")
+    .env("OPENAI_API_KEY", "sk-test-secret-value")
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{output:?}");
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert!(stdout.contains("# source: UserModelsYml ("), "{stdout}");
+  assert!(stdout.contains("built-in model(s) are not available while this file is in effect"));
+  assert!(stdout.contains("  test-model: # file\n"), "{stdout}");
+  assert!(stdout.contains("    model_id: \"test-model\" # file\n"));
+  assert!(
+    stdout.contains("    api_key_env: \"OPENAI_API_KEY\" # file, same as built-in; env var set\n")
+  );
+  assert!(stdout.contains("    base_url: \"https://api.openai.example/v1\" # file\n"));
+  assert!(stdout.contains("    timeout_seconds: 30 # file"));
+  assert!(!stdout.contains("should-not-print"));
+  assert!(!stdout.contains("sk-test-secret-value"));
+}
+
+/// Answers every connection with an empty `200 OK`, like a provider
+/// endpoint that is up.
+fn spawn_http_ok() -> String {
+  let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+  let base = format!("http://{}", listener.local_addr().unwrap());
+  std::thread::spawn(move || {
+    for stream in listener.incoming() {
+      let Ok(mut stream) = stream else { continue };
+      let mut buf = [0u8; 4096];
+      let _ = std::io::Read::read(&mut stream, &mut buf);
+      let _ = std::io::Write::write_all(
+        &mut stream,
+        b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+      );
+    }
+  });
+  base
+}
+
+fn closed_port_url() -> String {
+  let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+  format!("http://{}/v1", listener.local_addr().unwrap())
+}
+
+fn write_doctor_config(home: &TempDir, up: &str, down: Option<&str>) {
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  let down = down
+    .map(|url| format!("  down-model:\n    vendor: moonshot\n    base_url: {url}\n"))
+    .unwrap_or_default();
+  fs::write(
+    config_dir.join("models.yml"),
+    format!(
+      r#"
+models:
+  up-model:
+    vendor: openai
+    base_url: {up}/v1
+{down}  keyless-model:
+    vendor: anthropic
+providers:
+  openai:
+    api_key_env: OPENAI_API_KEY
+  moonshot:
+    api_key_env: MOONSHOT_API_KEY
+  anthropic:
+    api_key_env: ANTHROPIC_API_KEY
+"#
+    ),
+  )
+  .unwrap();
+}
+
+fn config_doctor(home: &TempDir, args: &[&str]) -> std::process::Output {
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["config", "doctor", "--timeout", "2"])
+    .args(args)
+    .env("HOME", home.path())
+    .env("AGENTFLOW_MODELS_CONFIG", "")
+    .env("OPENAI_API_KEY", "test-openai")
+    .env("MOONSHOT_API_KEY", "test-moonshot")
+    .env_remove("ANTHROPIC_API_KEY")
+    .env_remove("ANTHROPIC_KEY")
+    .env_remove("CLAUDE_API_KEY")
+    .current_dir(home.path())
+    .output()
+    .unwrap()
+}
+
+#[test]
+fn config_doctor_lists_checks_and_fails_on_unreachable_endpoints() {
+  let home = TempDir::new().unwrap();
+  let up = spawn_http_ok();
+  let down = closed_port_url();
+  write_doctor_config(&home, &up, Some(&down));
+
+  let output = config_doctor(&home, &[]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(!output.status.success(), "{stdout}");
+  assert!(
+    String::from_utf8_lossy(&output.stderr).contains("config doctor found 1 failure(s)"),
+    "{output:?}"
+  );
+  for expected in [
+    "[pass] config: 3 model(s), 3 provider(s) from ",
+    "[warn] api key anthropic: ANTHROPIC_API_KEY is not set; 1 model(s) unavailable",
+    "[pass] api key openai: OPENAI_API_KEY is set",
+    &format!("[pass] endpoint {up}/v1: reachable (HTTP 200)"),
+    &format!("[FAIL] endpoint {down}: unreachable"),
+    "[pass] models openai: 1 model(s) configured: up-model",
+    "[pass] models moonshot: 1 model(s) configured: down-model",
+    "[pass] writable ",
+    "7 passed, 1 warning(s), 1 failure(s)",
+  ] {
+    assert!(
+      stdout.contains(expected),
+      "missing {expected:?} in:\n{stdout}"
+    );
+  }
+  assert!(!stdout.contains("models anthropic"), "{stdout}");
+
+  // Without the unreachable endpoint only the warning is left.
+  write_doctor_config(&home, &up, None);
+  let output = config_doctor(&home, &["--json"]);
+  assert!(output.status.success(), "{output:?}");
+  let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+  assert_eq!(report["failures"], 0);
+  assert_eq!(report["warnings"], 1);
+  let statuses: Vec<(&str, &str)> = report["checks"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|check| {
+      (
+        check["name"].as_str().unwrap(),
+        check["status"].as_str().unwrap(),
+      )
+    })
+    .collect();
+  assert_eq!(statuses[0], ("config", "pass"));
+  assert!(statuses.contains(&("api key anthropic", "warn")));
+}
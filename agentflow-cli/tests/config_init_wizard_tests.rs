@@ -0,0 +1,76 @@
+//! `agentflow config init --interactive` driven by scripted stdin in a
+//! temp HOME. The live key checks are declined.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn init(home: &TempDir, args: &[&str], answers: &str) -> assert_cmd::assert::Assert {
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["config", "init", "--interactive"])
+    .args(args)
+    .env("HOME", home.path())
+    .env_remove("OPENAI_API_KEY")
+    .env_remove("ANTHROPIC_API_KEY")
+    .write_stdin(answers)
+    .assert()
+}
+
+#[test]
+fn wizard_writes_keys_and_default_model() {
+  let home = TempDir::new().unwrap();
+  init(
+    &home,
+    &[],
+    "openai, anthropic\nsk-test-openai\nsk-ant-test\nn\ngpt-4o\n",
+  )
+  .success()
+  .stdout(predicate::str::contains("[ ] openai"))
+  .stdout(predicate::str::contains("💾 Saved 2 key(s)"))
+  .stdout(predicate::str::contains("claude-3-5-sonnet"))
+  .stdout(predicate::str::contains("Default model set to gpt-4o"));
+
+  let config_dir = home.path().join(".agentflow");
+  let env = fs::read_to_string(config_dir.join(".env")).unwrap();
+  assert!(
+    env
+      .lines()
+      .any(|line| line == "OPENAI_API_KEY=sk-test-openai")
+  );
+  assert!(
+    env
+      .lines()
+      .any(|line| line == "ANTHROPIC_API_KEY=sk-ant-test")
+  );
+  assert!(!env.contains("# OPENAI_API_KEY="));
+  let models = fs::read_to_string(config_dir.join("models.yml")).unwrap();
+  assert!(models.contains("\ndefaults:\n  model: gpt-4o\n"));
+
+  // A second run edits the files in place: the existing key is marked,
+  // Enter keeps it and the default model.
+  init(&home, &[], "openai\n\n\n")
+    .success()
+    .stdout(predicate::str::contains("Editing the existing files"))
+    .stdout(predicate::str::contains("[x] openai"))
+    .stdout(predicate::str::contains("Enter keeps gpt-4o"));
+  let again = fs::read_to_string(config_dir.join(".env")).unwrap();
+  assert_eq!(again, env);
+}
+
+#[test]
+fn unknown_answers_are_asked_again_and_eof_stops() {
+  let home = TempDir::new().unwrap();
+  init(&home, &[], "nope\n99\n")
+    .failure()
+    .stdout(predicate::str::contains(
+      "'nope' is not one of the listed providers",
+    ))
+    .stdout(predicate::str::contains(
+      "'99' is not one of the listed providers",
+    ))
+    .stderr(predicate::str::contains(
+      "stdin closed before the setup finished",
+    ));
+}
@@ -0,0 +1,204 @@
+//! `agentflow config models update|validate` with a fixed model source
+//! standing in for the vendors' `/models` endpoints, plus the exit codes
+//! of the binary for the paths that need no network.
+
+use agentflow_cli::commands::config::models::{
+  ModelsTarget, discovery_vendors, run_update, run_validate, update_report,
+};
+use agentflow_llm::discovery::{DiscoveredModel, ModelSource};
+use agentflow_llm::{LLMConfig, LLMError, ModelValidator};
+use assert_cmd::Command;
+use async_trait::async_trait;
+use predicates::prelude::*;
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Moonshot serves two models; every other vendor fails like a missing
+/// API key.
+struct FixedSource;
+
+#[async_trait]
+impl ModelSource for FixedSource {
+  async fn list_models(&self, vendor: &str) -> agentflow_llm::Result<Vec<DiscoveredModel>> {
+    if vendor != "moonshot" {
+      return Err(LLMError::MissingApiKey {
+        provider: vendor.to_string(),
+      });
+    }
+    Ok(
+      ["moonshot-v1-8k", "moonshot-v1-128k"]
+        .into_iter()
+        .map(|id| DiscoveredModel {
+          id: id.to_string(),
+          vendor: vendor.to_string(),
+          display_name: None,
+          owned_by: None,
+          created: None,
+          object: None,
+        })
+        .collect(),
+    )
+  }
+}
+
+const CONFIGURED: &str = r#"
+models:
+  moonshot-v1-8k:
+    vendor: moonshot
+    type: chat
+    max_tokens: 1024
+  moonshot-v0-legacy:
+    vendor: moonshot
+    type: chat
+  mock-model:
+    vendor: mock
+    type: chat
+providers: {}
+"#;
+
+#[tokio::test]
+async fn update_reports_a_diff_and_dry_run_leaves_the_file_alone() {
+  let dir = TempDir::new().unwrap();
+  let path = dir.path().join("models.yml");
+  fs::write(&path, CONFIGURED).unwrap();
+  let target = ModelsTarget { path: path.clone() };
+  let vendors = vec!["moonshot".to_string(), "step".to_string()];
+
+  let result = run_update(Arc::new(FixedSource), &target, &vendors, true)
+    .await
+    .unwrap();
+  let report = update_report(&target, &result, true);
+  assert!(report.starts_with(&format!("Dry run — would update {}\n", path.display())));
+  assert!(report.contains("\n+ moonshot-v1-128k\n~ moonshot-v1-8k\n! step: "));
+  assert!(report.ends_with("1 added, 1 updated, 0 unchanged, 1 vendor(s) failed\n"));
+  assert_eq!(fs::read_to_string(&path).unwrap(), CONFIGURED);
+
+  run_update(Arc::new(FixedSource), &target, &vendors, false)
+    .await
+    .unwrap();
+  let written = LLMConfig::from_file(&path).await.unwrap();
+  assert!(written.models.contains_key("moonshot-v1-128k"));
+  assert!(written.models.contains_key("mock-model"));
+
+  let again = run_update(Arc::new(FixedSource), &target, &vendors, false)
+    .await
+    .unwrap();
+  assert!(update_report(&target, &again, false).contains("(no changes)\n"));
+  assert_eq!(again.unchanged_models, 2);
+}
+
+#[test]
+fn update_targets_the_file_the_cli_loads() {
+  let dir = TempDir::new().unwrap();
+  let target = |env: Option<&str>| {
+    ModelsTarget::resolve(Some(dir.path()), env.map(Into::into))
+      .unwrap()
+      .path
+  };
+  assert_eq!(target(None), dir.path().join("models.yml"));
+
+  // Split vendor files are not loaded, so they are not a target either.
+  fs::create_dir_all(dir.path().join("models")).unwrap();
+  fs::write(dir.path().join("models.yaml"), CONFIGURED).unwrap();
+  assert_eq!(target(None), dir.path().join("models.yaml"));
+  fs::write(dir.path().join("models.yml"), CONFIGURED).unwrap();
+  assert_eq!(target(None), dir.path().join("models.yml"));
+  assert_eq!(
+    target(Some("/etc/agentflow/models.yml")),
+    std::path::PathBuf::from("/etc/agentflow/models.yml")
+  );
+}
+
+#[tokio::test]
+async fn validate_reports_missing_models_per_vendor() {
+  let config = LLMConfig::from_yaml(CONFIGURED).unwrap();
+  let validator = ModelValidator::with_source(Arc::new(FixedSource));
+
+  let result = run_validate(&validator, &config, None).await.unwrap();
+  assert_eq!(result.valid_models, vec!["mock-model", "moonshot-v1-8k"]);
+  let report = validator.create_report(&result);
+  assert!(report.contains(
+    "❌ Invalid Models (1):\n  - moonshot-v0-legacy (moonshot): Model not found in vendor's model list\n"
+  ));
+  assert!(report.ends_with("Summary: 2/3 models validated successfully\n"));
+
+  let moonshot = run_validate(&validator, &config, Some("moonshot"))
+    .await
+    .unwrap();
+  assert_eq!(moonshot.valid_models, vec!["moonshot-v1-8k"]);
+  let err = run_validate(&validator, &config, Some("step"))
+    .await
+    .unwrap_err();
+  assert!(
+    err
+      .to_string()
+      .contains("configured vendors: mock, moonshot")
+  );
+}
+
+#[test]
+fn vendor_flag_accepts_only_listable_vendors() {
+  assert_eq!(
+    discovery_vendors(Some("Moonshot")).unwrap(),
+    vec!["moonshot"]
+  );
+  assert!(discovery_vendors(None).unwrap().len() >= 4);
+  assert!(
+    discovery_vendors(Some("openai"))
+      .unwrap_err()
+      .to_string()
+      .contains("does not publish a model list")
+  );
+  assert!(discovery_vendors(Some("acme")).is_err());
+}
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_MODELS_CONFIG")
+    .env_remove("MOONSHOT_API_KEY");
+  cmd
+}
+
+#[test]
+fn validate_exit_code_follows_the_report() {
+  let home = TempDir::new().unwrap();
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(config_dir.join("models.yml"), CONFIGURED).unwrap();
+
+  agentflow(&home)
+    .args(["config", "models", "validate", "--vendor", "mock"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "✅ Valid Models (1):\n  - mock-model",
+    ));
+
+  // Without MOONSHOT_API_KEY the moonshot models cannot be confirmed.
+  agentflow(&home)
+    .args(["config", "models", "validate"])
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains(
+      "⚠️  Unavailable Vendors (1):\n  - moonshot",
+    ))
+    .stderr(predicate::str::contains(
+      "2 configured model(s) failed validation",
+    ));
+
+  agentflow(&home)
+    .args([
+      "config",
+      "models",
+      "update",
+      "--vendor",
+      "openai",
+      "--dry-run",
+    ])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("does not publish a model list"));
+}
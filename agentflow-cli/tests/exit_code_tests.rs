@@ -0,0 +1,130 @@
+//! The exit-code contract and `--error-format json`: a missing file is a
+//! usage error (2), an invalid workflow a validation error (3) and a
+//! failing node a node failure (4).
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+const FAILING: &str = r#"
+name: Failing
+nodes:
+  - id: draft
+    type: llm
+    parameters:
+      model: mock-model
+      prompt: "Write a haiku"
+  - id: save
+    type: file
+    dependencies: [draft]
+    parameters:
+      operation: read
+      path: /nonexistent/agentflow-exit-code.txt
+"#;
+
+fn agentflow(home: &TempDir) -> Command {
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    "models:\n  mock-model:\n    vendor: mock\n    type: chat\nproviders:\n  mock:\n    api_key_env: MOCK_API_KEY\n",
+  )
+  .unwrap();
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env("MOCK_API_KEY", "test")
+    .env("AGENTFLOW_MOCK_RESPONSE", "an old pond")
+    .env("AGENTFLOW_TRACE_DIR", home.path().join("traces"))
+    .env("AGENTFLOW_RUN_DIR", home.path().join("runs"))
+    .current_dir(home.path());
+  cmd
+}
+
+/// Exit code and the JSON error object on stderr.
+fn run_json(home: &TempDir, args: &[&str]) -> (Option<i32>, Value) {
+  let output = agentflow(home)
+    .arg("--error-format")
+    .arg("json")
+    .args(args)
+    .output()
+    .unwrap();
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let line = stderr.lines().last().unwrap_or_default();
+  let error = serde_json::from_str(line).unwrap_or_else(|_| panic!("stderr: {stderr}"));
+  (output.status.code(), error)
+}
+
+#[test]
+fn missing_workflow_file_is_a_usage_error() {
+  let home = TempDir::new().unwrap();
+  let (code, error) = run_json(&home, &["workflow", "run", "nope.yml"]);
+  assert_eq!(code, Some(2));
+  assert_eq!(error["code"], 2);
+  assert_eq!(error["category"], "usage");
+  assert!(
+    error["message"]
+      .as_str()
+      .unwrap()
+      .starts_with("Failed to read workflow file: nope.yml: ")
+  );
+  assert!(error.get("node_id").is_none());
+
+  // Text stays the default, with the same code.
+  agentflow(&home)
+    .args(["workflow", "validate", "nope.yml"])
+    .assert()
+    .code(2)
+    .stderr(predicates::str::starts_with(
+      "Error: Failed to read workflow file: nope.yml",
+    ));
+}
+
+#[test]
+fn invalid_workflow_is_a_validation_error() {
+  let home = TempDir::new().unwrap();
+  fs::write(
+    home.path().join("bad.yml"),
+    "name: Bad\nnodes:\n  - id: a\n    type: template\n    dependencies: [ghost]\n    parameters:\n      template: hi\n",
+  )
+  .unwrap();
+  for command in ["run", "validate"] {
+    let (code, error) = run_json(&home, &["workflow", command, "bad.yml"]);
+    assert_eq!(code, Some(3), "workflow {command}");
+    assert_eq!(error["category"], "validation");
+    assert_eq!(
+      error["message"],
+      "workflow 'Bad' failed schema validation with 1 issue(s)"
+    );
+  }
+
+  fs::write(home.path().join("broken.yml"), "name: [unclosed\n").unwrap();
+  let (code, _) = run_json(&home, &["workflow", "run", "broken.yml"]);
+  assert_eq!(code, Some(3));
+}
+
+#[test]
+fn failing_node_is_a_node_failure_with_its_id() {
+  let home = TempDir::new().unwrap();
+  fs::write(home.path().join("failing.yml"), FAILING).unwrap();
+  let (code, error) = run_json(&home, &["workflow", "run", "failing.yml"]);
+  assert_eq!(code, Some(4));
+  assert_eq!(error["category"], "node_failure");
+  assert_eq!(error["node_id"], "save");
+  assert_eq!(error["message"], "1 node(s) failed: save");
+}
+
+#[test]
+fn bad_arguments_are_usage_errors_in_json_too() {
+  let home = TempDir::new().unwrap();
+  let (code, error) = run_json(&home, &["workflow", "run"]);
+  assert_eq!(code, Some(2));
+  assert_eq!(error["category"], "usage");
+  assert!(
+    error["message"]
+      .as_str()
+      .unwrap()
+      .contains("<WORKFLOW_FILE>")
+  );
+}
@@ -0,0 +1,418 @@
+//! `agentflow image generate` batches against a stub server speaking
+//! both the StepFun (`/images/generations`) and the DashScope
+//! (async image-synthesis task) APIs: file naming, the batch
+//! `manifest.json`, and one failing prompt not stopping the rest.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use assert_cmd::Command;
+use axum::{
+  Json, Router,
+  extract::{Path, State},
+  http::{HeaderMap, StatusCode},
+  routing::{get, post},
+};
+use base64::{Engine as _, engine::general_purpose};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+#[derive(Clone, Default)]
+struct Stub {
+  base: Arc<Mutex<String>>,
+  /// Every StepFun request body and every DashScope task submission.
+  stepfun: Arc<Mutex<Vec<Value>>>,
+  dashscope: Arc<Mutex<Vec<Value>>>,
+}
+
+/// Image bytes the stub "generates", so files can be matched to prompts.
+fn image_bytes(prompt: &str, seed: i64) -> Vec<u8> {
+  format!("PNG {prompt} {seed}").into_bytes()
+}
+
+async fn stepfun_generate(
+  State(stub): State<Stub>,
+  Json(body): Json<Value>,
+) -> (StatusCode, Json<Value>) {
+  stub.stepfun.lock().unwrap().push(body.clone());
+  let prompt = body["prompt"].as_str().unwrap_or_default();
+  if prompt.contains("forbidden") {
+    return (
+      StatusCode::BAD_REQUEST,
+      Json(json!({ "error": { "message": "prompt rejected" } })),
+    );
+  }
+  let seed = body["seed"].as_i64().unwrap_or(4242);
+  let data = general_purpose::STANDARD.encode(image_bytes(prompt, seed));
+  (
+    StatusCode::OK,
+    Json(json!({
+      "created": 1,
+      "data": [{ "finish_reason": "success", "seed": seed, "b64_json": data }],
+    })),
+  )
+}
+
+async fn dashscope_submit(
+  State(stub): State<Stub>,
+  headers: HeaderMap,
+  Json(body): Json<Value>,
+) -> (StatusCode, Json<Value>) {
+  if headers
+    .get("x-dashscope-async")
+    .and_then(|v| v.to_str().ok())
+    != Some("enable")
+  {
+    return (
+      StatusCode::BAD_REQUEST,
+      Json(json!({ "code": "InvalidParameter", "message": "async only" })),
+    );
+  }
+  let mut tasks = stub.dashscope.lock().unwrap();
+  tasks.push(body);
+  (
+    StatusCode::OK,
+    Json(json!({
+      "request_id": "req",
+      "output": { "task_id": format!("task-{}", tasks.len() - 1), "task_status": "PENDING" },
+    })),
+  )
+}
+
+async fn dashscope_task(State(stub): State<Stub>, Path(task_id): Path<String>) -> Json<Value> {
+  let index: usize = task_id.trim_start_matches("task-").parse().unwrap();
+  let body = stub.dashscope.lock().unwrap()[index].clone();
+  let prompt = body["input"]["prompt"].as_str().unwrap_or_default();
+  if prompt.contains("forbidden") {
+    return Json(json!({ "output": {
+      "task_id": task_id,
+      "task_status": "FAILED",
+      "code": "DataInspectionFailed",
+      "message": "prompt rejected",
+    } }));
+  }
+  let base = stub.base.lock().unwrap().clone();
+  Json(json!({
+    "request_id": "req",
+    "output": {
+      "task_id": task_id,
+      "task_status": "SUCCEEDED",
+      "results": [{ "url": format!("{base}/files/{index}") }],
+    },
+    "usage": { "image_count": 1 },
+  }))
+}
+
+async fn dashscope_file(State(stub): State<Stub>, Path(index): Path<usize>) -> Vec<u8> {
+  let body = stub.dashscope.lock().unwrap()[index].clone();
+  image_bytes(
+    body["input"]["prompt"].as_str().unwrap_or_default(),
+    body["parameters"]["seed"].as_i64().unwrap_or(0),
+  )
+}
+
+async fn spawn_stub() -> (String, Stub) {
+  let stub = Stub::default();
+  let router = Router::new()
+    .route("/stepfun/v1/images/generations", post(stepfun_generate))
+    .route(
+      "/dashscope/api/v1/services/aigc/text2image/image-synthesis",
+      post(dashscope_submit),
+    )
+    .route("/dashscope/api/v1/tasks/:id", get(dashscope_task))
+    .route("/files/:index", get(dashscope_file))
+    .with_state(stub.clone());
+  let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+  let base = format!("http://{}", listener.local_addr().expect("local addr"));
+  *stub.base.lock().unwrap() = base.clone();
+  tokio::spawn(async move {
+    let _ = axum::serve(listener, router.into_make_service()).await;
+  });
+  tokio::time::sleep(Duration::from_millis(80)).await;
+  (base, stub)
+}
+
+fn setup_home(base: &str) -> TempDir {
+  let home = TempDir::new().unwrap();
+  let config_dir = home.path().join(".agentflow");
+  std::fs::create_dir_all(&config_dir).unwrap();
+  std::fs::write(
+    config_dir.join("models.yml"),
+    format!(
+      r#"
+models:
+  stub-step-image:
+    vendor: stepfun
+    type: text_to_image
+    base_url: {base}/stepfun/v1
+  stub-wanx:
+    vendor: dashscope
+    type: text_to_image
+    base_url: {base}/dashscope/api/v1
+providers:
+  stepfun:
+    api_key_env: STEPFUN_API_KEY
+  dashscope:
+    api_key_env: DASHSCOPE_API_KEY
+"#
+    ),
+  )
+  .unwrap();
+  std::fs::write(
+    home.path().join("prompts.txt"),
+    "a red fox\n\n{\"prompt\": \"a forbidden door\"}\n{\"prompt\": \"a blue whale\", \"size\": \"512x512\", \"seed\": 7}\n",
+  )
+  .unwrap();
+  home
+}
+
+async fn run(home: &TempDir, args: &[&str]) -> std::process::Output {
+  let home_path = home.path().to_path_buf();
+  let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+  tokio::task::spawn_blocking(move || {
+    Command::cargo_bin("agentflow")
+      .unwrap()
+      .env("HOME", &home_path)
+      .env("STEPFUN_API_KEY", "test-step")
+      .env("DASHSCOPE_API_KEY", "test-dashscope")
+      .current_dir(&home_path)
+      .args(["image", "generate"])
+      .args(&args)
+      .output()
+      .unwrap()
+  })
+  .await
+  .unwrap()
+}
+
+fn manifest(home: &TempDir, dir: &str) -> Value {
+  let path = home.path().join(dir).join("manifest.json");
+  serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn stepfun_batch_names_files_and_records_partial_failures() {
+  let (base, stub) = spawn_stub().await;
+  let home = setup_home(&base);
+
+  let output = run(
+    &home,
+    &[
+      "--prompts-file",
+      "prompts.txt",
+      "--model",
+      "stub-step-image",
+      "--provider",
+      "stepfun",
+      "--count",
+      "2",
+      "--seed",
+      "100",
+      "--parallel",
+      "3",
+      "--output-dir",
+      "out",
+      "--name-template",
+      "{index}_{seed}.png",
+    ],
+  )
+  .await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(!output.status.success());
+  assert!(
+    stderr.contains("2 of 6 image(s) failed"),
+    "stderr: {stderr}"
+  );
+
+  // Six images: two per prompt, the middle prompt's both rejected.
+  let out = home.path().join("out");
+  let expected = [
+    ("1_100.png", "a red fox", 100),
+    ("2_101.png", "a red fox", 101),
+    ("5_7.png", "a blue whale", 7),
+    ("6_8.png", "a blue whale", 8),
+  ];
+  for (file, prompt, seed) in expected {
+    assert_eq!(
+      std::fs::read(out.join(file)).unwrap(),
+      image_bytes(prompt, seed),
+      "{file}"
+    );
+  }
+  let mut on_disk: Vec<String> = std::fs::read_dir(&out)
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+    .collect();
+  on_disk.sort();
+  assert_eq!(
+    on_disk,
+    [
+      "1_100.png",
+      "2_101.png",
+      "5_7.png",
+      "6_8.png",
+      "manifest.json"
+    ]
+  );
+
+  let manifest = manifest(&home, "out");
+  assert_eq!(manifest["provider"], "stepfun");
+  assert_eq!(manifest["model"], "stub-step-image");
+  assert_eq!(
+    (
+      &manifest["total"],
+      &manifest["succeeded"],
+      &manifest["failed"]
+    ),
+    (&json!(6), &json!(4), &json!(2))
+  );
+  let images = manifest["images"].as_array().unwrap();
+  let summary: Vec<(u64, u64, &str, Option<&str>)> = images
+    .iter()
+    .map(|image| {
+      (
+        image["index"].as_u64().unwrap(),
+        image["prompt_index"].as_u64().unwrap(),
+        image["status"].as_str().unwrap(),
+        image["file"].as_str(),
+      )
+    })
+    .collect();
+  assert_eq!(
+    summary,
+    [
+      (1, 1, "succeeded", Some("1_100.png")),
+      (2, 1, "succeeded", Some("2_101.png")),
+      (3, 2, "failed", None),
+      (4, 2, "failed", None),
+      (5, 3, "succeeded", Some("5_7.png")),
+      (6, 3, "succeeded", Some("6_8.png")),
+    ]
+  );
+  assert_eq!(images[4]["size"], "512x512");
+  assert_eq!(images[4]["bytes"], image_bytes("a blue whale", 7).len());
+  assert!(
+    images[2]["error"]
+      .as_str()
+      .unwrap()
+      .contains("prompt rejected"),
+    "{}",
+    images[2]
+  );
+
+  let requests = stub.stepfun.lock().unwrap();
+  assert_eq!(requests.len(), 6);
+  assert!(
+    requests
+      .iter()
+      .any(|body| body["size"] == "512x512" && body["seed"] == 8)
+  );
+}
+
+#[tokio::test]
+async fn dashscope_provider_polls_tasks_and_downloads_images() {
+  let (base, stub) = spawn_stub().await;
+  let home = setup_home(&base);
+
+  let output = run(
+    &home,
+    &[
+      "a lighthouse at dusk",
+      "--model",
+      "stub-wanx",
+      "--provider",
+      "dashscope",
+      "--count",
+      "2",
+      "--seed",
+      "5",
+      "--size",
+      "1024x768",
+      "--output-dir",
+      "wanx",
+      "--name-template",
+      "{slug}-{n}.png",
+    ],
+  )
+  .await;
+  assert!(
+    output.status.success(),
+    "stderr: {}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+
+  let out = home.path().join("wanx");
+  assert_eq!(
+    std::fs::read(out.join("a-lighthouse-at-dusk-1.png")).unwrap(),
+    image_bytes("a lighthouse at dusk", 5)
+  );
+  assert_eq!(
+    std::fs::read(out.join("a-lighthouse-at-dusk-2.png")).unwrap(),
+    image_bytes("a lighthouse at dusk", 6)
+  );
+  let manifest = manifest(&home, "wanx");
+  assert_eq!(manifest["provider"], "dashscope");
+  assert_eq!(manifest["succeeded"], 2);
+
+  let first_task = stub.dashscope.lock().unwrap()[0].clone();
+  assert_eq!(first_task["model"], "stub-wanx");
+  assert_eq!(first_task["parameters"]["size"], "1024*768");
+
+  // A model of the other provider is refused up front.
+  let mismatch = run(
+    &home,
+    &[
+      "a fox",
+      "--model",
+      "stub-wanx",
+      "--provider",
+      "stepfun",
+      "--output",
+      "fox.png",
+    ],
+  )
+  .await;
+  assert!(!mismatch.status.success());
+  assert!(
+    String::from_utf8_lossy(&mismatch.stderr)
+      .contains("Model 'stub-wanx' is served by dashscope, not stepfun")
+  );
+}
+
+#[tokio::test]
+async fn single_prompt_still_writes_one_file() {
+  let (base, _stub) = spawn_stub().await;
+  let home = setup_home(&base);
+
+  let output = run(
+    &home,
+    &[
+      "a red fox",
+      "--model",
+      "stub-step-image",
+      "--seed",
+      "3",
+      "--output",
+      "fox.png",
+    ],
+  )
+  .await;
+  assert!(
+    output.status.success(),
+    "stderr: {}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  assert_eq!(
+    std::fs::read(home.path().join("fox.png")).unwrap(),
+    image_bytes("a red fox", 3)
+  );
+  assert!(!home.path().join("manifest.json").exists());
+
+  let batch_without_dir = run(
+    &home,
+    &["a red fox", "--model", "stub-step-image", "--count", "2"],
+  )
+  .await;
+  assert!(String::from_utf8_lossy(&batch_without_dir.stderr).contains("Pass --output-dir <dir>"));
+}
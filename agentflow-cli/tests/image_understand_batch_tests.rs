@@ -0,0 +1,166 @@
+//! `agentflow image understand --dir` against the mock provider over a
+//! temp directory of tiny images.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_mock_models_config(home: &Path) {
+  let config_dir = home.join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    r#"
+models:
+  mock-vision:
+    vendor: mock
+    type: chat
+    accepts: [text, image]
+    model_id: mock-model
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+    rate_limit:
+      requests_per_minute: 6000
+"#,
+  )
+  .unwrap();
+}
+
+/// `a.png`, `b.jpg`, `notes.txt`, `nested/c.png` and a `gone.png`
+/// symlink to a file that does not exist.
+fn seeded_home() -> TempDir {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+  let images = home.path().join("images");
+  fs::create_dir_all(images.join("nested")).unwrap();
+  fs::write(images.join("a.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+  fs::write(images.join("b.jpg"), b"\xff\xd8\xff").unwrap();
+  fs::write(images.join("notes.txt"), "not an image").unwrap();
+  fs::write(images.join("nested").join("c.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+  std::os::unix::fs::symlink(images.join("missing.png"), images.join("gone.png")).unwrap();
+  home
+}
+
+fn understand(home: &Path, args: &[&str]) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .args(["image", "understand"])
+    .args(args)
+    .env("HOME", home)
+    .env("MOCK_API_KEY", "test")
+    .env("AGENTFLOW_MOCK_RESPONSE", "a tiny test image")
+    .current_dir(home);
+  cmd
+}
+
+fn jsonl_rows(path: &Path) -> Vec<Value> {
+  fs::read_to_string(path)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect()
+}
+
+#[test]
+fn jsonl_rows_cover_every_image_and_isolate_failures() {
+  let home = seeded_home();
+  understand(
+    home.path(),
+    &[
+      "Describe it",
+      "--dir",
+      "images",
+      "--model",
+      "mock-vision",
+      "--parallel",
+      "3",
+      "--jsonl",
+      "out/labels.jsonl",
+    ],
+  )
+  .assert()
+  .failure()
+  .stdout(predicate::str::contains(
+    "Images: 3 under images (3 at a time)",
+  ))
+  .stdout(predicate::str::contains(
+    "❌ 1 image(s) failed:\n  gone.png: Failed to read image",
+  ))
+  .stderr(predicate::str::contains("1 of 3 image(s) failed"));
+
+  let rows = jsonl_rows(&home.path().join("out/labels.jsonl"));
+  let images: Vec<&str> = rows
+    .iter()
+    .map(|row| row["image"].as_str().unwrap())
+    .collect();
+  assert_eq!(images, ["a.png", "b.jpg", "gone.png"]);
+  for row in &rows[..2] {
+    assert_eq!(row["status"], "succeeded");
+    assert_eq!(row["model"], "mock-vision");
+    assert_eq!(row["response"], "a tiny test image");
+  }
+  assert_eq!(rows[2]["status"], "failed");
+  assert!(rows[2].get("response").is_none());
+}
+
+#[test]
+fn glob_picks_nested_images_and_output_dir_gets_one_file_each() {
+  let home = seeded_home();
+  understand(
+    home.path(),
+    &[
+      "Describe it",
+      "--dir",
+      "images",
+      "--glob",
+      "**/*.png",
+      "--model",
+      "mock-vision",
+      "--output-dir",
+      "results",
+    ],
+  )
+  .assert()
+  .failure()
+  .stdout(predicate::str::contains("Images: 3 under images"));
+
+  let nested: Value = serde_json::from_str(
+    &fs::read_to_string(home.path().join("results/nested/c.png.json")).unwrap(),
+  )
+  .unwrap();
+  assert_eq!(nested["image"], "nested/c.png");
+  assert_eq!(nested["response"], "a tiny test image");
+  assert!(home.path().join("results/a.png.json").is_file());
+  assert!(!home.path().join("results/b.jpg.json").exists());
+}
+
+#[test]
+fn batch_needs_a_destination_and_only_the_prompt() {
+  let home = seeded_home();
+  understand(home.path(), &["Describe it", "--dir", "images"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+      "Pass --output-dir <dir> or --jsonl <file> for --dir",
+    ));
+  understand(
+    home.path(),
+    &[
+      "a.png",
+      "Describe it",
+      "--dir",
+      "images",
+      "--jsonl",
+      "x.jsonl",
+    ],
+  )
+  .assert()
+  .failure()
+  .stderr(predicate::str::contains(
+    "--dir takes the prompt as the only argument",
+  ));
+}
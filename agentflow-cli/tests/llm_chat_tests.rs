@@ -0,0 +1,121 @@
+//! `agentflow llm chat` with piped stdin against the mock provider: one
+//! turn per run, and `--save` / `--load` carrying the conversation from
+//! one run to the next.
+
+use agentflow_cli::commands::llm::chat::ChatHistory;
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_mock_models_config(home: &Path) {
+  let config_dir = home.join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    r#"
+models:
+  mock-model:
+    vendor: mock
+    type: text
+    model_id: mock-model
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#,
+  )
+  .unwrap();
+}
+
+fn chat(home: &Path, args: &[&str], stdin: &str, reply: &str) -> assert_cmd::assert::Assert {
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["llm", "chat"])
+    .args(args)
+    .env("HOME", home)
+    .env("AGENTFLOW_MOCK_RESPONSE", reply)
+    .write_stdin(stdin)
+    .assert()
+}
+
+#[test]
+fn piped_stdin_runs_one_turn_and_prints_only_the_reply() {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+
+  chat(
+    home.path(),
+    &["--model", "mock-model"],
+    "What is 2 + 2?\n",
+    "Four.",
+  )
+  .success()
+  .stdout("Four.\n");
+
+  chat(home.path(), &["--model", "mock-model"], "  \n", "unused")
+    .failure()
+    .stderr(predicate::str::contains("No prompt on stdin"));
+}
+
+#[test]
+fn saved_history_round_trips_through_load() {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+  let first = home.path().join("chats/first.json");
+  let second = home.path().join("second.json");
+
+  chat(
+    home.path(),
+    &[
+      "--model",
+      "mock-model",
+      "--system",
+      "Be brief.",
+      "--save",
+      first.to_str().unwrap(),
+    ],
+    "Hello",
+    "Hi there.",
+  )
+  .success();
+
+  let saved = ChatHistory::load(&first).unwrap();
+  assert_eq!(saved.model, "mock-model");
+  assert_eq!(saved.system.as_deref(), Some("Be brief."));
+  assert_eq!(saved.turns(), 1);
+  assert!(saved.usage.total() > 0);
+
+  // The loaded file supplies the model and system prompt.
+  chat(
+    home.path(),
+    &[
+      "--load",
+      first.to_str().unwrap(),
+      "--save",
+      second.to_str().unwrap(),
+    ],
+    "And goodbye",
+    "Bye.",
+  )
+  .success()
+  .stdout("Bye.\n");
+
+  let resumed = ChatHistory::load(&second).unwrap();
+  let transcript: Vec<_> = resumed
+    .messages
+    .iter()
+    .map(|message| (message.role.as_str(), message.content.as_str()))
+    .collect();
+  assert_eq!(
+    transcript,
+    vec![
+      ("user", "Hello"),
+      ("assistant", "Hi there."),
+      ("user", "And goodbye"),
+      ("assistant", "Bye."),
+    ]
+  );
+  assert_eq!(resumed.system.as_deref(), Some("Be brief."));
+  assert!(resumed.usage.total() > saved.usage.total());
+}
@@ -0,0 +1,185 @@
+//! `agentflow llm models --detailed|--json|--refresh`: configured models
+//! merged with a discovery cache filled from a fixed model source, and
+//! the binary reading that cache offline.
+
+use agentflow_cli::commands::llm::catalog::{
+  Availability, CatalogEntry, DiscoveryCache, merge, render_table,
+};
+use agentflow_llm::discovery::{DiscoveredModel, ModelSource};
+use agentflow_llm::{LLMConfig, LLMError, ModelPricing};
+use assert_cmd::Command;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+/// Moonshot lists two models; every other vendor fails like a missing
+/// API key.
+struct FixedSource;
+
+#[async_trait]
+impl ModelSource for FixedSource {
+  async fn list_models(&self, vendor: &str) -> agentflow_llm::Result<Vec<DiscoveredModel>> {
+    if vendor != "moonshot" {
+      return Err(LLMError::MissingApiKey {
+        provider: vendor.to_string(),
+      });
+    }
+    Ok(
+      ["moonshot-v1-8k", "moonshot-v1-128k"]
+        .into_iter()
+        .map(|id| DiscoveredModel {
+          id: id.to_string(),
+          vendor: vendor.to_string(),
+          display_name: None,
+          owned_by: None,
+          created: None,
+          object: None,
+        })
+        .collect(),
+    )
+  }
+}
+
+const CONFIGURED: &str = r#"
+models:
+  moonshot-v1-8k:
+    vendor: moonshot
+    type: chat
+    context_window: 8192
+    pricing: { input: 1.5, output: 2, currency: CNY }
+  kimi-small:
+    vendor: moonshot
+    model_id: moonshot-v1-8k
+    type: chat
+  moonshot-v0-legacy:
+    vendor: moonshot
+    type: chat
+  vision:
+    vendor: step
+    type: chat
+    accepts: [text, image]
+    model_id: step-1v-8k
+providers: {}
+"#;
+
+fn entry<'a>(entries: &'a [CatalogEntry], model: &str) -> &'a CatalogEntry {
+  entries.iter().find(|entry| entry.model == model).unwrap()
+}
+
+#[tokio::test]
+async fn merge_groups_aliases_and_flags_undiscovered_models() {
+  let config = LLMConfig::from_yaml(CONFIGURED).unwrap();
+  let mut cache = DiscoveryCache::new();
+  let failures = cache
+    .refresh(&FixedSource, &["moonshot".to_string(), "step".to_string()])
+    .await;
+  assert_eq!(failures.len(), 1);
+  assert_eq!(failures[0].0, "step");
+  assert!(!cache.vendors.contains_key("step"));
+
+  let entries = merge(&config, &cache);
+  assert_eq!(
+    entries
+      .iter()
+      .map(|entry| (entry.model.as_str(), entry.availability))
+      .collect::<Vec<_>>(),
+    [
+      ("moonshot-v0-legacy", Availability::NotDiscovered),
+      ("moonshot-v1-128k", Availability::DiscoveredOnly),
+      ("moonshot-v1-8k", Availability::Discovered),
+      ("step-1v-8k", Availability::Configured),
+    ]
+  );
+  let small = entry(&entries, "moonshot-v1-8k");
+  assert_eq!(small.name.as_deref(), Some("moonshot-v1-8k"));
+  assert_eq!(small.aliases, ["kimi-small"]);
+  assert_eq!(small.context_window, Some(8192));
+  assert_eq!(
+    small.pricing,
+    Some(ModelPricing {
+      input: 1.5,
+      output: 2.0,
+      currency: "CNY".to_string()
+    })
+  );
+  assert_eq!(entry(&entries, "step-1v-8k").inputs, ["text", "image"]);
+  assert!(entry(&entries, "moonshot-v1-128k").name.is_none());
+
+  let table = render_table(&entries);
+  let lines: Vec<_> = table.lines().collect();
+  assert!(lines[0].starts_with("VENDOR    MODEL"));
+  assert!(lines[3].contains("kimi-small"));
+  assert!(lines[3].contains("8k"));
+  assert!(lines[3].contains("1.5/2 CNY"));
+  assert!(lines[3].ends_with("configured+discovered"));
+  assert!(lines[1].ends_with("configured, NOT discovered"));
+  assert!(lines[4].contains("vision"));
+}
+
+#[tokio::test]
+async fn a_failed_refresh_keeps_the_previous_listing() {
+  let dir = TempDir::new().unwrap();
+  let path = dir.path().join("cache/model_discovery.json");
+  let mut cache = DiscoveryCache::new();
+  cache.refresh(&FixedSource, &["moonshot".to_string()]).await;
+  cache.save(&path).unwrap();
+
+  struct Failing;
+  #[async_trait]
+  impl ModelSource for Failing {
+    async fn list_models(&self, vendor: &str) -> agentflow_llm::Result<Vec<DiscoveredModel>> {
+      Err(LLMError::MissingApiKey {
+        provider: vendor.to_string(),
+      })
+    }
+  }
+  let mut reloaded = DiscoveryCache::load(&path);
+  assert_eq!(reloaded.vendors, cache.vendors);
+  let failures = reloaded.refresh(&Failing, &["moonshot".to_string()]).await;
+  assert_eq!(failures.len(), 1);
+  assert_eq!(reloaded.vendors, cache.vendors);
+
+  fs::write(&path, r#"{"version": 99, "vendors": {}}"#).unwrap();
+  assert!(DiscoveryCache::load(&path).vendors.is_empty());
+}
+
+#[tokio::test]
+async fn binary_reads_the_cache_for_json_and_detailed_views() {
+  let home = TempDir::new().unwrap();
+  let agentflow = home.path().join(".agentflow");
+  fs::create_dir_all(&agentflow).unwrap();
+  fs::write(agentflow.join("models.yml"), CONFIGURED).unwrap();
+  let mut cache = DiscoveryCache::new();
+  cache.refresh(&FixedSource, &["moonshot".to_string()]).await;
+  cache
+    .save(&agentflow.join("cache/model_discovery.json"))
+    .unwrap();
+
+  let models = |args: &[&str]| {
+    Command::cargo_bin("agentflow")
+      .unwrap()
+      .env("HOME", home.path())
+      .env_remove("AGENTFLOW_MODELS_CONFIG")
+      .args(["llm", "models"])
+      .args(args)
+      .output()
+      .unwrap()
+  };
+
+  let output = models(&["--json", "--provider", "moon"]);
+  assert!(output.status.success(), "{output:?}");
+  let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+  let models_json = json["models"].as_array().unwrap();
+  assert_eq!(models_json.len(), 3);
+  assert_eq!(models_json[0]["availability"], "not_discovered");
+  assert_eq!(models_json[2]["aliases"][0], "kimi-small");
+  assert!(json["discovered_at"]["moonshot"].is_string());
+
+  let output = models(&["--detailed"]);
+  assert!(output.status.success(), "{output:?}");
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("Discovery: 1 vendor(s), oldest list from"));
+  assert!(stdout.contains("step-1v-8k"));
+  assert!(stdout.contains("1 configured model(s) are missing from their vendor's model list"));
+}
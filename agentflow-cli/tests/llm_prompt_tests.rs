@@ -0,0 +1,174 @@
+//! `agentflow llm prompt` against the mock provider. The mock's default
+//! reply quotes the start of the first message, which lets these tests
+//! see what the command actually sent.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_mock_models_config(home: &Path) {
+  let config_dir = home.join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    r#"
+models:
+  mock-model:
+    vendor: mock
+    type: chat
+    model_id: mock-model
+  mock-vision:
+    vendor: mock
+    type: chat
+    accepts: [text, image]
+    model_id: mock-model
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#,
+  )
+  .unwrap();
+}
+
+fn prompt(home: &Path, args: &[&str]) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .args(["llm", "prompt"])
+    .args(args)
+    .env("HOME", home)
+    .current_dir(home);
+  cmd
+}
+
+#[test]
+fn files_are_appended_and_usage_goes_to_stderr() {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+  fs::write(home.path().join("notes.md"), "alpha beta\n").unwrap();
+
+  prompt(
+    home.path(),
+    &[
+      "Summarize",
+      "--model",
+      "mock-model",
+      "--file",
+      "notes.md",
+      "--temperature",
+      "0.2",
+      "--max-tokens",
+      "64",
+      "--usage",
+    ],
+  )
+  .assert()
+  .success()
+  .stdout(predicate::str::contains(
+    "Mock response for: 'Summarize\n\n--- notes.md ---\nalpha beta'",
+  ))
+  .stderr(predicate::str::contains(
+    "📊 Usage (mock-model): 50 prompt +",
+  ));
+}
+
+#[test]
+fn system_json_and_output_flags() {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+
+  prompt(
+    home.path(),
+    &["Hello", "--model", "mock-model", "--system", "Be terse."],
+  )
+  .assert()
+  .success()
+  .stdout(predicate::str::contains("Mock response for: 'Be terse.'"));
+
+  let answer = home.path().join("answer.json");
+  prompt(
+    home.path(),
+    &[
+      "Give me JSON",
+      "--model",
+      "mock-model",
+      "--json",
+      "--output",
+      answer.to_str().unwrap(),
+    ],
+  )
+  .env("AGENTFLOW_MOCK_RESPONSE", r#"{"ok":true}"#)
+  .assert()
+  .success()
+  .stdout("")
+  .stderr(predicate::str::contains("Response written to"));
+  assert_eq!(fs::read_to_string(answer).unwrap(), r#"{"ok":true}"#);
+}
+
+#[test]
+fn images_need_a_vision_model_and_a_known_format() {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+  fs::write(home.path().join("chart.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+  fs::write(home.path().join("chart.tiff"), b"II*\0").unwrap();
+
+  prompt(
+    home.path(),
+    &[
+      "What is this?",
+      "--model",
+      "mock-vision",
+      "--image",
+      "chart.png",
+    ],
+  )
+  .env("AGENTFLOW_MOCK_RESPONSE", "A chart.")
+  .assert()
+  .success()
+  .stdout("A chart.\n");
+
+  prompt(
+    home.path(),
+    &[
+      "What is this?",
+      "--model",
+      "mock-model",
+      "--image",
+      "https://example.com/chart.png",
+    ],
+  )
+  .assert()
+  .failure()
+  .stderr(predicate::str::contains("does not support image input"));
+
+  prompt(
+    home.path(),
+    &[
+      "What is this?",
+      "--model",
+      "mock-vision",
+      "--image",
+      "chart.tiff",
+    ],
+  )
+  .assert()
+  .failure()
+  .stderr(predicate::str::contains("must be a .png"));
+}
+
+#[test]
+fn oversized_files_are_rejected_before_any_request() {
+  let home = TempDir::new().unwrap();
+  write_mock_models_config(home.path());
+  let big = home.path().join("big.txt");
+  fs::write(&big, "x".repeat(256 * 1024 + 1)).unwrap();
+
+  prompt(
+    home.path(),
+    &["Read this", "--model", "mock-model", "--file", "big.txt"],
+  )
+  .assert()
+  .failure()
+  .stderr(predicate::str::contains("over the 262144 byte limit"));
+}
@@ -0,0 +1,168 @@
+//! `agentflow mcp` against the in-process mock transport: `call` saves
+//! image results to files, and the flag surface resolves `--stdio` /
+//! `--http` the way the subcommands use it.
+
+use agentflow_cli::commands::mcp::{
+  call_tool,
+  connection::{McpConnection, McpServer},
+};
+use agentflow_mcp::client::{ClientBuilder, Content};
+use agentflow_mcp::transport::MockTransport;
+use assert_cmd::Command;
+use base64::Engine;
+use serde_json::json;
+use std::time::Duration;
+
+const PNG_BYTES: &[u8] = b"\x89PNG\r\n\x1a\nnot really a png";
+
+fn mock_server_with_image_tool() -> MockTransport {
+  let mut transport = MockTransport::new();
+  transport.add_responses(vec![
+    MockTransport::standard_initialize_response(),
+    MockTransport::tools_list_response(vec![json!({
+      "name": "render/chart",
+      "description": "Render a chart",
+      "inputSchema": {
+        "type": "object",
+        "properties": { "title": { "type": "string" } },
+        "required": ["title"]
+      }
+    })]),
+    MockTransport::tool_call_response(vec![
+      json!({ "type": "text", "text": "rendered 2 charts" }),
+      json!({
+        "type": "image",
+        "data": base64::engine::general_purpose::STANDARD.encode(PNG_BYTES),
+        "mimeType": "image/png"
+      }),
+      json!({ "type": "image", "data": "https://example.com/chart.jpg", "mimeType": "image/jpeg" }),
+      json!({ "type": "image", "data": "R0lGODlh", "mimeType": "image/gif" }),
+    ]),
+  ]);
+  transport
+}
+
+#[tokio::test]
+async fn call_saves_base64_images_and_skips_urls() {
+  let transport = mock_server_with_image_tool();
+  let sent = transport.sent_messages_handle();
+  let mut client = ClientBuilder::new()
+    .with_transport(transport)
+    .build()
+    .await
+    .unwrap();
+  client.connect().await.unwrap();
+
+  let result = call_tool::call(&mut client, "render/chart", json!({ "title": "Q3" }))
+    .await
+    .unwrap();
+  let dir = tempfile::tempdir().unwrap();
+  let saved = call_tool::save_images(&result, "render/chart", dir.path()).unwrap();
+
+  assert_eq!(
+    saved,
+    vec![
+      dir.path().join("render_chart-1.png"),
+      dir.path().join("render_chart-3.gif")
+    ]
+  );
+  assert_eq!(std::fs::read(&saved[0]).unwrap(), PNG_BYTES);
+  assert!(matches!(&result.content[0], Content::Text { text } if text == "rendered 2 charts"));
+
+  let call = sent
+    .lock()
+    .unwrap()
+    .iter()
+    .find(|message| message["method"] == "tools/call")
+    .cloned()
+    .expect("tools/call was sent");
+  assert_eq!(call["params"]["name"], "render/chart");
+  assert_eq!(call["params"]["arguments"], json!({ "title": "Q3" }));
+}
+
+#[tokio::test]
+async fn call_rejects_unknown_tools_and_invalid_arguments() {
+  let mut client = ClientBuilder::new()
+    .with_transport(mock_server_with_image_tool())
+    .build()
+    .await
+    .unwrap();
+  client.connect().await.unwrap();
+
+  let err = call_tool::call(&mut client, "render/table", json!({}))
+    .await
+    .unwrap_err();
+  assert!(
+    err.to_string().contains("'render/table' was not found"),
+    "{err:#}"
+  );
+
+  let mut client = ClientBuilder::new()
+    .with_transport(mock_server_with_image_tool())
+    .build()
+    .await
+    .unwrap();
+  client.connect().await.unwrap();
+  assert!(
+    call_tool::call(&mut client, "render/chart", json!({}))
+      .await
+      .is_err(),
+    "missing required `title` must fail schema validation"
+  );
+}
+
+#[test]
+fn stdio_and_http_flags_select_the_transport() {
+  let stdio = McpConnection::from_flags(
+    vec![],
+    Some("npx -y @modelcontextprotocol/server-everything"),
+    None,
+    Duration::from_secs(10),
+    0,
+  )
+  .unwrap();
+  assert_eq!(
+    stdio.server,
+    McpServer::Stdio(vec![
+      "npx".into(),
+      "-y".into(),
+      "@modelcontextprotocol/server-everything".into()
+    ])
+  );
+  assert_eq!(
+    stdio.payload()["server_command"],
+    json!(["npx", "-y", "@modelcontextprotocol/server-everything"])
+  );
+}
+
+#[test]
+fn subcommands_require_a_server() {
+  for args in [
+    vec!["mcp", "list-tools"],
+    vec!["mcp", "call", "echo", "--args", "{}"],
+    vec!["mcp", "list-resources", "--json"],
+    vec!["mcp", "read-resource", "file:///tmp/x"],
+    vec!["mcp", "list-prompts"],
+  ] {
+    Command::cargo_bin("agentflow")
+      .unwrap()
+      .args(&args)
+      .assert()
+      .failure()
+      .stderr(predicates::str::contains("No MCP server given"));
+  }
+
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .args([
+      "mcp",
+      "list-prompts",
+      "--stdio",
+      "node server.js",
+      "--http",
+      "http://localhost:1/mcp",
+    ])
+    .assert()
+    .failure()
+    .stderr(predicates::str::contains("only one of --stdio, --http"));
+}
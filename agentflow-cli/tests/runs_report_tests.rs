@@ -0,0 +1,146 @@
+//! `agentflow runs report` over a synthetic run: the HTML graph and node
+//! cards, the sidecar for a long value, inlined images and copied
+//! artifacts.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+const WORKFLOW: &str = r#"
+name: Illustrate
+nodes:
+  - id: draft
+    type: llm
+    parameters:
+      prompt: "Describe a lighthouse"
+  - id: paint
+    type: text_to_image
+    dependencies: [draft]
+    input_mapping:
+      prompt: "{{ nodes.draft.outputs.response }}"
+  - id: publish
+    type: template
+    dependencies: [paint]
+"#;
+
+/// A run whose `draft` node wrote a long response, `paint` a data URL
+/// and an image file, and `publish` failed.
+fn seeded_home() -> TempDir {
+  let home = TempDir::new().unwrap();
+  let workflow_file = home.path().join("illustrate.yml");
+  fs::write(&workflow_file, WORKFLOW).unwrap();
+  let image = home.path().join("lighthouse.png");
+  fs::write(&image, b"\x89PNG\r\n\x1a\nnot-really-a-png").unwrap();
+
+  let dir = home.path().join("runs").join("run-42");
+  fs::create_dir_all(&dir).unwrap();
+  let manifest = json!({
+    "run_id": "run-42",
+    "workflow": "Illustrate",
+    "workflow_file": workflow_file,
+    "started_at": "2026-03-03T09:00:00Z",
+    "finished_at": "2026-03-03T09:00:02Z",
+    "status": "failed",
+    "duration_ms": 2100,
+    "nodes": [
+      { "id": "draft", "status": "completed", "duration_ms": 1500 },
+      { "id": "paint", "status": "completed", "duration_ms": 600 },
+      { "id": "publish", "status": "failed", "duration_ms": 1, "error": "template <b>broke</b>" },
+    ],
+  });
+  fs::write(
+    dir.join("manifest.json"),
+    serde_json::to_string_pretty(&manifest).unwrap(),
+  )
+  .unwrap();
+  let long = "tide ".repeat(1000);
+  fs::write(
+    dir.join("draft_outputs.json"),
+    json!({ "Ok": { "response": { "type": "json", "value": long } } }).to_string(),
+  )
+  .unwrap();
+  fs::write(
+    dir.join("paint_outputs.json"),
+    json!({ "Ok": {
+      "image": { "type": "json", "value": "data:image/png;base64,aGVsbG8=" },
+      "image_paths": { "type": "json", "value": [image] },
+    } })
+    .to_string(),
+  )
+  .unwrap();
+  home
+}
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env("AGENTFLOW_RUN_DIR", home.path().join("runs"))
+    .current_dir(home.path());
+  cmd
+}
+
+#[test]
+fn report_contains_the_graph_and_a_card_per_node() {
+  let home = seeded_home();
+  agentflow(&home)
+    .args(["runs", "report", "run-4", "--output", "out/report.html"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "Report for run run-42 written to out/report.html",
+    ));
+
+  let html = fs::read_to_string(home.path().join("out/report.html")).unwrap();
+  assert!(html.contains("<pre class=\"mermaid\">\nflowchart TD\n"));
+  assert!(
+    html.contains("  n0[&quot;draft&quot;]\n  n1[&quot;paint&quot;]\n  n2[&quot;publish&quot;]\n")
+  );
+  assert!(html.contains("  n0 --&gt; n1\n  n1 --&gt; n2\n"));
+  assert!(html.contains("  class n2 failed\n"));
+  for id in ["draft", "paint", "publish"] {
+    assert!(
+      html.contains(&format!("id=\"node-{id}\"")),
+      "no card for {id}"
+    );
+  }
+  assert!(html.contains("<details class=\"node failed\" id=\"node-publish\" open>"));
+  assert!(html.contains("<pre class=\"error\">template &lt;b&gt;broke&lt;/b&gt;</pre>"));
+  assert!(html.contains("Describe a lighthouse"));
+
+  // The long response is cut short and saved beside the report.
+  assert!(html.contains("Showing 2000 of 5000 characters."));
+  assert!(html.contains("href=\"report_files/draft.response.json\" download"));
+  let full = fs::read_to_string(home.path().join("out/report_files/draft.response.json")).unwrap();
+  assert_eq!(full.len(), 5000);
+
+  // Both images become thumbnails; the data URL is elided from the JSON.
+  assert!(html.contains("<img class=\"thumb\" src=\"data:image/png;base64,aGVsbG8=\""));
+  assert!(html.contains("&lt;image 1 shown above&gt;"));
+  assert!(html.contains("src=\"data:image/png;base64,iVBORw0KGgpub3QtcmVhbGx5LWEtcG5n\""));
+  assert!(!home.path().join("out/artifacts").exists());
+}
+
+#[test]
+fn include_artifacts_copies_named_files() {
+  let home = seeded_home();
+  agentflow(&home)
+    .args([
+      "runs",
+      "report",
+      "run-42",
+      "--include-artifacts",
+      "artifacts",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "1 artifact(s) copied to artifacts",
+    ));
+
+  assert!(home.path().join("artifacts/paint/lighthouse.png").is_file());
+  let html = fs::read_to_string(home.path().join("report.html")).unwrap();
+  assert!(html.contains("<a href=\"artifacts/paint/lighthouse.png\">"));
+}
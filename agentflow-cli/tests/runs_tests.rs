@@ -0,0 +1,237 @@
+//! `agentflow runs list / show / clean` over synthetic run directories,
+//! plus the manifest a real `workflow run` leaves behind.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd.env("HOME", home.path()).env_remove("AGENTFLOW_RUN_DIR");
+  cmd
+}
+
+fn runs_root(home: &TempDir) -> std::path::PathBuf {
+  home.path().join(".agentflow").join("runs")
+}
+
+fn write_run(root: &Path, run_id: &str, workflow: &str, status: &str, started_at: &str) {
+  let dir = root.join(run_id);
+  fs::create_dir_all(&dir).unwrap();
+  let mut manifest = json!({
+    "run_id": run_id,
+    "workflow": workflow,
+    "workflow_file": "/workflows/example.yml",
+    "started_at": started_at,
+    "status": status,
+    "nodes": [
+      { "id": "fetch", "status": "completed", "duration_ms": 1500 },
+      { "id": "summarize", "status": if status == "failed" { "failed" } else { "completed" }, "duration_ms": 20 },
+    ],
+  });
+  if status != "running" {
+    manifest["finished_at"] = json!(started_at);
+    manifest["duration_ms"] = json!(1520);
+  }
+  if status == "failed" {
+    manifest["nodes"][1]["error"] = json!("model quota exceeded");
+  }
+  fs::write(
+    dir.join("manifest.json"),
+    serde_json::to_string_pretty(&manifest).unwrap(),
+  )
+  .unwrap();
+  fs::write(
+    dir.join("fetch_outputs.json"),
+    r#"{"Ok":{"output":{"type":"json","value":"page text"}}}"#,
+  )
+  .unwrap();
+}
+
+/// Three runs a day apart plus a directory without a manifest.
+fn seeded_home() -> TempDir {
+  let home = TempDir::new().unwrap();
+  let root = runs_root(&home);
+  write_run(
+    &root,
+    "run-new",
+    "Digest",
+    "completed",
+    "2026-03-03T09:00:00Z",
+  );
+  write_run(&root, "run-mid", "Digest", "failed", "2026-03-02T09:00:00Z");
+  write_run(
+    &root,
+    "run-old",
+    "Translate",
+    "completed",
+    "2026-03-01T09:00:00Z",
+  );
+  fs::create_dir_all(root.join("harness").join("sessions")).unwrap();
+  home
+}
+
+fn stdout(cmd: &mut Command) -> String {
+  let output = cmd.assert().success().get_output().stdout.clone();
+  String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn list_shows_newest_first_and_filters() {
+  let home = seeded_home();
+
+  let table = stdout(agentflow(&home).args(["runs", "list"]));
+  let lines: Vec<&str> = table.lines().collect();
+  assert_eq!(
+    lines,
+    [
+      "RUN ID   WORKFLOW   STARTED (UTC)        STATUS     DURATION",
+      "run-new  Digest     2026-03-03 09:00:00  completed  1.52 s",
+      "run-mid  Digest     2026-03-02 09:00:00  failed     1.52 s",
+      "run-old  Translate  2026-03-01 09:00:00  completed  1.52 s",
+    ]
+  );
+
+  let failed = stdout(agentflow(&home).args(["run", "list", "--failed-only"]));
+  assert!(failed.contains("run-mid") && !failed.contains("run-new"));
+
+  let json: Value = serde_json::from_str(&stdout(
+    agentflow(&home).args(["runs", "list", "--limit", "2", "--json"]),
+  ))
+  .unwrap();
+  let ids: Vec<&str> = json["runs"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|run| run["run_id"].as_str().unwrap())
+    .collect();
+  assert_eq!(ids, ["run-new", "run-mid"]);
+}
+
+#[test]
+fn show_prints_nodes_and_node_outputs() {
+  let home = seeded_home();
+
+  agentflow(&home)
+    .args(["runs", "show", "run-mid"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("  status:   failed\n"))
+    .stdout(predicate::str::contains(
+      "fetch      completed     1.50 s\nsummarize  failed         20 ms  model quota exceeded\n",
+    ));
+
+  agentflow(&home)
+    .args(["runs", "show", "run-o", "--node", "fetch"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "Outputs of node 'fetch' in run run-old:",
+    ))
+    .stdout(predicate::str::contains("\"value\": \"page text\""))
+    .stdout(predicate::str::contains("\"Ok\"").not());
+
+  agentflow(&home)
+    .args(["runs", "show", "run-new", "--node", "summarize"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+      "run 'run-new' has no outputs for node 'summarize'",
+    ));
+  agentflow(&home)
+    .args(["runs", "show", "run-"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("matches 3 runs"));
+}
+
+#[test]
+fn clean_removes_old_runs_and_can_keep_failed_ones() {
+  let home = seeded_home();
+  let root = runs_root(&home);
+  write_run(
+    &root,
+    "run-live",
+    "Digest",
+    "running",
+    "2026-01-01T09:00:00Z",
+  );
+
+  agentflow(&home)
+    .args(["runs", "clean", "--older-than", "7d", "--dry-run"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+      "🧹 Would remove 3 run(s) older than 7d; kept 1.",
+    ));
+  assert!(root.join("run-old").exists());
+
+  let json: Value = serde_json::from_str(&stdout(agentflow(&home).args([
+    "runs",
+    "clean",
+    "--older-than",
+    "7d",
+    "--keep-failed",
+    "--json",
+  ])))
+  .unwrap();
+  assert_eq!(json["removed"], json!(["run-new", "run-old"]));
+  assert_eq!(json["kept"], 2);
+  assert!(!root.join("run-new").exists() && !root.join("run-old").exists());
+  assert!(root.join("run-mid").exists() && root.join("run-live").exists());
+  assert!(root.join("harness").exists());
+
+  agentflow(&home)
+    .args(["runs", "clean", "--older-than", "7days"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+      "Invalid --older-than value '7days'",
+    ));
+}
+
+#[test]
+fn workflow_run_writes_a_manifest() {
+  let home = TempDir::new().unwrap();
+  let workflow = home.path().join("hello.yml");
+  fs::write(
+    &workflow,
+    r#"
+name: "Hello"
+nodes:
+  - id: greet
+    type: template
+    parameters:
+      template: "hello"
+"#,
+  )
+  .unwrap();
+
+  agentflow(&home)
+    .args(["workflow", "run"])
+    .arg(&workflow)
+    .assert()
+    .success();
+
+  let json: Value =
+    serde_json::from_str(&stdout(agentflow(&home).args(["runs", "list", "--json"]))).unwrap();
+  let run = &json["runs"][0];
+  assert_eq!(run["workflow"], "Hello");
+  assert_eq!(run["status"], "completed");
+  assert_eq!(run["nodes"][0]["id"], "greet");
+  assert!(run["nodes"][0].get("outputs").is_none());
+  assert_eq!(
+    run["workflow_file"].as_str().unwrap(),
+    fs::canonicalize(&workflow).unwrap().to_str().unwrap()
+  );
+
+  let run_id = run["run_id"].as_str().unwrap();
+  agentflow(&home)
+    .args(["runs", "show", run_id, "--node", "greet", "--json"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("\"value\": \"hello\""));
+}
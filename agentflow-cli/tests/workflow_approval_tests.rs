@@ -0,0 +1,103 @@
+//! `workflow run --approval`: a scripted stdin answers a file-channel
+//! `approval` node (approve after editing in `$EDITOR`, and reject with
+//! feedback), and `--approval channel` only prints where to answer.
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+const WORKFLOW: &str = r#"
+name: "Approved deploy"
+nodes:
+  - id: draft
+    type: template
+    parameters:
+      template: "Deploy v2 to prod"
+  - id: review
+    type: approval
+    dependencies: [draft]
+    input_mapping:
+      content: "{{ nodes.draft.outputs.output }}"
+    parameters:
+      channel: file
+      sentinel_path: review-decision.json
+      prompt: "Ship the deploy note?"
+      poll_interval_ms: 20
+      timeout_ms: 1500
+      on_timeout: reject
+"#;
+
+fn setup() -> TempDir {
+  let home = TempDir::new().unwrap();
+  fs::write(home.path().join("flow.yml"), WORKFLOW).unwrap();
+  home
+}
+
+fn run(home: &TempDir, approval: &str, stdin: &str) -> (std::process::Output, Value) {
+  let output = Command::cargo_bin("agentflow")
+    .unwrap()
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .env_remove("VISUAL")
+    .env(
+      "EDITOR",
+      r#"sh -c 'printf "Deploy v2.1 to prod" > "$1"' editor"#,
+    )
+    .current_dir(home.path())
+    .args(["workflow", "run", "flow.yml", "--no-progress"])
+    .args(["--approval", approval, "--output", "report.json"])
+    .write_stdin(stdin)
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{output:?}");
+  let report: Value =
+    serde_json::from_str(&fs::read_to_string(home.path().join("report.json")).unwrap()).unwrap();
+  let review = report["nodes"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .find(|node| node["id"] == "review")
+    .unwrap()["outputs"]
+    .clone();
+  (output, review)
+}
+
+#[test]
+fn scripted_stdin_edits_then_approves_or_rejects() {
+  let home = setup();
+  let (output, review) = run(&home, "prompt", "e\na ship it\n");
+  let stderr = console::strip_ansi_codes(&String::from_utf8_lossy(&output.stderr)).into_owned();
+  assert!(
+    stderr.contains("[review] Ship the deploy note?"),
+    "{stderr}"
+  );
+  assert!(stderr.contains("Deploy v2 to prod"));
+  assert!(stderr.contains("[a]pprove / [r]eject / [e]dit"));
+  assert_eq!(review["approved"], true);
+  assert_eq!(review["feedback"], "ship it");
+  assert_eq!(review["content"], "Deploy v2.1 to prod");
+  assert_eq!(review["decided_by"], "file");
+  assert!(!home.path().join("review-decision.json").exists());
+
+  let (_, review) = run(&home, "prompt", "r wrong environment\n");
+  assert_eq!(review["approved"], false);
+  assert_eq!(review["feedback"], "wrong environment");
+  assert_eq!(review["content"], "Deploy v2 to prod");
+}
+
+#[test]
+fn channel_mode_prints_where_to_approve() {
+  let home = setup();
+  let (output, review) = run(&home, "channel", "a\n");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains(
+      "✋ Approval 'review' is waiting: write `approve`, `reject <feedback>` or a JSON decision to review-decision.json"
+    ),
+    "{stderr}"
+  );
+  assert!(!stderr.contains("[a]pprove"));
+  assert_eq!(review["decided_by"], "timeout");
+  assert_eq!(review["approved"], false);
+}
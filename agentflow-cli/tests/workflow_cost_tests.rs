@@ -0,0 +1,200 @@
+//! Token usage and cost of a mock-provider `workflow run`: the summary
+//! table, the copies in the run manifest and the `--output` report, and
+//! `runs cost` recomputing it from the stored run.
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+/// Four words, so every mock call reports 50 prompt and 4 completion
+/// tokens.
+const MOCK_RESPONSE: &str = "one two three four";
+
+const WORKFLOW: &str = r#"
+name: "Costed"
+nodes:
+  - id: outline
+    type: llm
+    parameters:
+      model: "mock-model"
+      prompt: "Outline it"
+  - id: draft
+    type: llm
+    dependencies: [outline]
+    parameters:
+      model: "mock-model"
+      prompt: "Draft it"
+  - id: review
+    type: llm
+    dependencies: [draft]
+    parameters:
+      model: "unpriced-model"
+      prompt: "Review it"
+  - id: publish
+    type: template
+    dependencies: [review]
+    parameters:
+      template: "done"
+"#;
+
+fn setup(home: &TempDir) {
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    r#"
+models:
+  mock-model:
+    vendor: mock
+    type: text
+    model_id: mock-model
+  unpriced-model:
+    vendor: mock
+    type: text
+    model_id: unpriced-model
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#,
+  )
+  .unwrap();
+  fs::write(
+    home.path().join("pricing.yml"),
+    "models:\n  mock-model:\n    input_per_1k: 1.0\n    output_per_1k: 2.0\n",
+  )
+  .unwrap();
+  fs::write(home.path().join("costed.yml"), WORKFLOW).unwrap();
+}
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .env("MOCK_API_KEY", "test")
+    .env("AGENTFLOW_MOCK_RESPONSE", MOCK_RESPONSE)
+    .env(
+      "AGENTFLOW_PRICING_TABLE",
+      home.path().join("pricing.yml").to_str().unwrap(),
+    );
+  cmd
+}
+
+fn stdout(cmd: &mut Command) -> String {
+  String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+fn assert_cost(cost: &Value) {
+  let nodes = cost["nodes"].as_array().unwrap();
+  let ids: Vec<&str> = nodes
+    .iter()
+    .map(|node| node["id"].as_str().unwrap())
+    .collect();
+  assert_eq!(ids, ["outline", "draft", "review"]);
+  for node in nodes {
+    assert_eq!(node["calls"], 1);
+    assert_eq!(node["prompt_tokens"], 50);
+    assert_eq!(node["completion_tokens"], 4);
+  }
+  // 50 / 1000 * $1 + 4 / 1000 * $2 per priced call.
+  assert!((nodes[0]["cost_usd"].as_f64().unwrap() - 0.058).abs() < 1e-9);
+  assert_eq!(nodes[2]["model"], "unpriced-model");
+  assert!(nodes[2].get("cost_usd").is_none());
+  assert_eq!(cost["prompt_tokens"], 150);
+  assert_eq!(cost["completion_tokens"], 12);
+  assert!((cost["cost_usd"].as_f64().unwrap() - 0.116).abs() < 1e-9);
+}
+
+#[test]
+fn run_reports_usage_and_cost_per_node() {
+  let home = TempDir::new().unwrap();
+  setup(&home);
+  let report_path = home.path().join("report.json");
+
+  let out = stdout(
+    agentflow(&home)
+      .args(["workflow", "run"])
+      .arg(home.path().join("costed.yml"))
+      .arg("--output")
+      .arg(&report_path),
+  );
+  let table: Vec<String> = out
+    .lines()
+    .skip_while(|line| !line.contains("Usage and cost"))
+    .skip(1)
+    .take(5)
+    .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+    .collect();
+  assert_eq!(
+    table,
+    [
+      "NODE MODEL PROMPT COMPLETION COST (USD)",
+      "outline mock-model 50 4 $0.058000",
+      "draft mock-model 50 4 $0.058000",
+      "review unpriced-model 50 4 —",
+      "total 150 12 $0.116000",
+    ],
+    "{out}"
+  );
+
+  let report: Value = serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+  assert_cost(&report["cost"]);
+
+  let runs = home.path().join(".agentflow").join("runs");
+  let run_dir = fs::read_dir(&runs).unwrap().next().unwrap().unwrap().path();
+  let manifest: Value =
+    serde_json::from_str(&fs::read_to_string(run_dir.join("manifest.json")).unwrap()).unwrap();
+  assert_cost(&manifest["cost"]);
+
+  let run_id = run_dir.file_name().unwrap().to_str().unwrap().to_string();
+  let recomputed: Value = serde_json::from_str(&stdout(
+    agentflow(&home).args(["runs", "cost", &run_id, "--json"]),
+  ))
+  .unwrap();
+  assert_cost(&recomputed);
+  assert_eq!(recomputed, manifest["cost"]);
+
+  // Prices are read again, so a new entry applies to the stored run.
+  fs::write(
+    home.path().join("pricing.yml"),
+    "models:\n  mock-model:\n    input_per_1k: 1.0\n    output_per_1k: 2.0\n  unpriced-model:\n    input_per_1k: 2.0\n    output_per_1k: 0.0\n",
+  )
+  .unwrap();
+  let text = stdout(agentflow(&home).args(["runs", "cost", &run_id[..8]]));
+  assert!(text.contains(&format!("Run: {run_id} (Costed)")), "{text}");
+  let total = text.lines().last().unwrap();
+  assert_eq!(
+    total.split_whitespace().collect::<Vec<_>>(),
+    ["total", "150", "12", "$0.216000"]
+  );
+}
+
+#[test]
+fn runs_without_model_calls_have_no_cost() {
+  let home = TempDir::new().unwrap();
+  setup(&home);
+  fs::write(
+    home.path().join("plain.yml"),
+    "name: \"Plain\"\nnodes:\n  - id: greet\n    type: template\n    parameters:\n      template: \"hello\"\n",
+  )
+  .unwrap();
+
+  let out = stdout(
+    agentflow(&home)
+      .args(["workflow", "run"])
+      .arg(home.path().join("plain.yml")),
+  );
+  assert!(!out.contains("Usage and cost"), "{out}");
+
+  let runs = home.path().join(".agentflow").join("runs");
+  let run_dir = fs::read_dir(&runs).unwrap().next().unwrap().unwrap().path();
+  let manifest: Value =
+    serde_json::from_str(&fs::read_to_string(run_dir.join("manifest.json")).unwrap()).unwrap();
+  assert!(manifest.get("cost").is_none());
+  let run_id = run_dir.file_name().unwrap().to_str().unwrap();
+  assert!(
+    stdout(agentflow(&home).args(["runs", "cost", run_id]))
+      .contains(&format!("Run {run_id} made no model calls."))
+  );
+}
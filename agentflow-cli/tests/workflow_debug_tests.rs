@@ -0,0 +1,139 @@
+//! `workflow debug` over a fixture workflow: the `--visualize`,
+//! `--analyze` and `--plan` outputs are snapshotted, and `--validate`
+//! exits non-zero only when the flow has errors.
+//!
+//! Set `UPDATE_SNAPSHOTS=1` to rewrite `tests/snapshots/` after an
+//! intentional change.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn fixture() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/debug_workflow.yml")
+}
+
+fn debug(home: &TempDir, workflow: &Path, args: &[&str]) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .args(["workflow", "debug"])
+    .arg(workflow)
+    .args(args)
+    .env("HOME", home.path());
+  cmd
+}
+
+fn assert_snapshot(flag: &str, name: &str) {
+  let home = TempDir::new().unwrap();
+  let output = debug(&home, &fixture(), &[flag]).assert().success();
+  let actual = String::from_utf8(output.get_output().stdout.clone())
+    .unwrap()
+    .replace(fixture().to_str().unwrap(), "<fixture>");
+  let snapshot = Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests/snapshots")
+    .join(name);
+  if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+    fs::write(&snapshot, &actual).unwrap();
+  }
+  assert_eq!(actual, fs::read_to_string(&snapshot).unwrap());
+}
+
+#[test]
+fn visualize_matches_snapshot() {
+  assert_snapshot("--visualize", "workflow_debug_visualize.txt");
+}
+
+#[test]
+fn analyze_matches_snapshot() {
+  assert_snapshot("--analyze", "workflow_debug_analyze.txt");
+}
+
+#[test]
+fn plan_matches_snapshot() {
+  assert_snapshot("--plan", "workflow_debug_plan.txt");
+}
+
+#[test]
+fn visualize_writes_dot_and_an_html_preview() {
+  let home = TempDir::new().unwrap();
+  let dot = home.path().join("flow.dot");
+  let html = home.path().join("flow.html");
+
+  debug(
+    &home,
+    &fixture(),
+    &[
+      "--visualize",
+      "--format",
+      "dot",
+      "--output",
+      dot.to_str().unwrap(),
+      "--html",
+      html.to_str().unwrap(),
+    ],
+  )
+  .assert()
+  .success()
+  .stdout(predicate::str::contains("💾 Diagram written to"))
+  .stdout(predicate::str::contains("digraph").not());
+
+  let dot = fs::read_to_string(dot).unwrap();
+  assert!(dot.starts_with("digraph flow {\n"));
+  assert!(dot.contains("  \"sections\" [label=\"sections (map)\", shape=box3d];\n"));
+  assert!(dot.contains("  \"translate\" -> \"publish\";\n"));
+
+  let html = fs::read_to_string(html).unwrap();
+  assert!(html.contains("<title>Debug Fixture</title>"));
+  assert!(html.contains("@viz-js/viz"));
+  assert!(html.contains("&quot;review&quot; -&gt; &quot;translate&quot;;"));
+}
+
+#[test]
+fn validate_lists_errors_and_fails() {
+  let home = TempDir::new().unwrap();
+  let workflow = home.path().join("broken.yml");
+  fs::write(
+    &workflow,
+    r#"
+name: "Broken"
+nodes:
+  - id: a
+    type: template
+    dependencies: ["b"]
+    parameters:
+      template: "a"
+  - id: b
+    type: template
+    dependencies: ["a"]
+    parameters:
+      template: "b"
+  - id: c
+    type: template
+    input_mapping:
+      text: "{{ nodes.a.outputs.output }}"
+    parameters:
+      template: "{{ text }}"
+"#,
+  )
+  .unwrap();
+
+  debug(&home, &workflow, &["--validate"])
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains(
+      "❌ Errors: 1\n  1. circular dependency among: a, b",
+    ))
+    .stdout(predicate::str::contains(
+      "⚠️  Warnings: 1\n  1. input 'text' of node 'c' maps from 'a', which is not a dependency",
+    ))
+    .stderr(predicate::str::contains(
+      "workflow 'Broken' failed validation with 1 error(s)",
+    ));
+
+  debug(&home, &fixture(), &["--validate"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("✅ No validation issues found"));
+}
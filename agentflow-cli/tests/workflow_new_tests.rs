@@ -0,0 +1,109 @@
+//! `agentflow workflow new`: every built-in template scaffolds a workflow
+//! that `workflow debug --validate` accepts, and existing files are only
+//! replaced with `--force`.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+const TEMPLATES: &[&str] = &["basic", "llm-chain", "map-reduce", "rag", "agent-loop"];
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd.env("HOME", home.path()).current_dir(home.path());
+  cmd
+}
+
+#[test]
+fn every_template_scaffolds_a_valid_workflow() {
+  let home = TempDir::new().unwrap();
+
+  for template in TEMPLATES {
+    let dir = format!("{template}-flow");
+    agentflow(&home)
+      .args(["workflow", "new", &dir, "--template", template])
+      .assert()
+      .success()
+      .stdout(predicate::str::contains(format!(
+        "Created workflow '{dir}' from the {template} template"
+      )));
+
+    let workflow = home.path().join(&dir).join("workflow.yml");
+    let yaml: serde_yaml::Value =
+      serde_yaml::from_str(&fs::read_to_string(&workflow).unwrap()).unwrap();
+    assert_eq!(yaml["name"].as_str(), Some(dir.as_str()));
+    let inputs = fs::read_to_string(home.path().join(&dir).join("inputs.example.json")).unwrap();
+    assert!(serde_json::from_str::<Value>(&inputs).unwrap().is_object());
+
+    agentflow(&home)
+      .args(["workflow", "debug"])
+      .arg(&workflow)
+      .arg("--validate")
+      .assert()
+      .success()
+      .stdout(predicate::str::contains("No validation issues found"));
+  }
+}
+
+#[test]
+fn list_and_overwrite_rules() {
+  let home = TempDir::new().unwrap();
+
+  let listed = agentflow(&home)
+    .args(["workflow", "new", "--list"])
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  let listed = String::from_utf8(listed).unwrap();
+  for template in TEMPLATES {
+    assert!(listed.contains(&format!("  {template} ")), "{listed}");
+  }
+
+  agentflow(&home)
+    .args(["workflow", "new", "demo"])
+    .assert()
+    .success();
+  fs::write(home.path().join("demo").join("workflow.yml"), "edited").unwrap();
+  agentflow(&home)
+    .args(["workflow", "new", "demo", "--template", "llm-chain"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+      "Refusing to overwrite existing file 'demo/workflow.yml'",
+    ));
+  assert_eq!(
+    fs::read_to_string(home.path().join("demo").join("workflow.yml")).unwrap(),
+    "edited"
+  );
+
+  agentflow(&home)
+    .args([
+      "workflow",
+      "new",
+      "demo",
+      "--template",
+      "llm-chain",
+      "--force",
+    ])
+    .assert()
+    .success();
+  assert!(
+    fs::read_to_string(home.path().join("demo").join("workflow.yml"))
+      .unwrap()
+      .contains("type: llm")
+  );
+
+  agentflow(&home)
+    .args(["workflow", "new", "other", "--template", "chatbot"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Unknown template 'chatbot'"));
+  agentflow(&home)
+    .args(["workflow", "new"])
+    .assert()
+    .failure();
+}
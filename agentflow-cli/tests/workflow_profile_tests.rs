@@ -0,0 +1,125 @@
+//! `--profile` / `--set` substitution in workflow YAML: `--set` beats
+//! the profile, which beats the environment; unresolved placeholders
+//! fail `workflow run` and `workflow debug --validate` alike, naming
+//! the placeholder.
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+const WORKFLOW: &str = r#"
+name: "Profiled ${profile.stage}"
+nodes:
+  - id: greet
+    type: template
+    parameters:
+      template: "${greeting} from ${REGION} via ${endpoint}, price $${LITERAL}"
+"#;
+
+fn setup() -> TempDir {
+  let home = TempDir::new().unwrap();
+  let profiles = home.path().join(".agentflow/profiles");
+  fs::create_dir_all(&profiles).unwrap();
+  fs::write(
+    profiles.join("staging.yml"),
+    "stage: staging\nendpoint: https://staging.example\ngreeting: profile-hello\n",
+  )
+  .unwrap();
+  fs::write(home.path().join("flow.yml"), WORKFLOW).unwrap();
+  home
+}
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .env("REGION", "env-region")
+    .env("greeting", "env-hello")
+    .current_dir(home.path());
+  cmd
+}
+
+#[test]
+fn set_overrides_profile_which_overrides_env() {
+  let home = setup();
+  agentflow(&home)
+    .args([
+      "workflow",
+      "run",
+      "flow.yml",
+      "--quiet",
+      "--output",
+      "report.json",
+    ])
+    .args([
+      "--profile",
+      "staging",
+      "--set",
+      "endpoint=https://set.example",
+    ])
+    .assert()
+    .success();
+
+  let report: Value =
+    serde_json::from_str(&fs::read_to_string(home.path().join("report.json")).unwrap()).unwrap();
+  assert_eq!(report["workflow"], "Profiled staging");
+  assert_eq!(
+    report["nodes"][0]["outputs"]["output"],
+    "profile-hello from env-region via https://set.example, price ${LITERAL}"
+  );
+}
+
+#[test]
+fn unresolved_placeholders_fail_run_and_validate() {
+  let home = setup();
+  // Without the profile, `${profile.stage}` on line 2 has no value.
+  let output = agentflow(&home)
+    .args(["workflow", "run", "flow.yml", "--set", "endpoint=x"])
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+  assert!(
+    String::from_utf8_lossy(&output.stderr).contains(
+      "unresolved placeholder ${profile.stage} on line 2: no --set stage=… and no --profile was given"
+    ),
+    "{output:?}"
+  );
+
+  let output = agentflow(&home)
+    .args([
+      "workflow",
+      "debug",
+      "flow.yml",
+      "--validate",
+      "--profile",
+      "staging",
+    ])
+    .env_remove("REGION")
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+  assert!(
+    String::from_utf8_lossy(&output.stderr).contains("unresolved placeholder ${REGION} on line 7"),
+    "{output:?}"
+  );
+
+  agentflow(&home)
+    .args([
+      "workflow",
+      "debug",
+      "flow.yml",
+      "--validate",
+      "--profile",
+      "staging",
+    ])
+    .assert()
+    .success();
+
+  let output = agentflow(&home)
+    .args(["workflow", "run", "flow.yml", "--profile", "prod"])
+    .output()
+    .unwrap();
+  assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to read profile 'prod'"));
+}
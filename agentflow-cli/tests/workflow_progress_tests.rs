@@ -0,0 +1,104 @@
+//! `workflow run` node status lines: off a terminal (as under
+//! `assert_cmd`) the progress display falls back to plain stderr lines
+//! driven by the flow's events, and `-v` / `-vv` echo node outputs.
+
+use assert_cmd::Command;
+use std::fs;
+use tempfile::TempDir;
+
+const WORKFLOW: &str = r#"
+name: "Progress"
+nodes:
+  - id: sections
+    type: map
+    parameters:
+      template:
+        - id: render
+          type: template
+          parameters:
+            template: "Section {{ item }}"
+  - id: greet
+    type: template
+    dependencies: [sections]
+    parameters:
+      template: "hello"
+  - id: translate
+    type: template
+    dependencies: [greet]
+    run_if: "false"
+    parameters:
+      template: "bonjour"
+"#;
+
+fn run(home: &TempDir, extra: &[&str]) -> String {
+  let workflow = home.path().join("progress.yml");
+  fs::write(&workflow, WORKFLOW).unwrap();
+  let output = Command::cargo_bin("agentflow")
+    .unwrap()
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .args(["workflow", "run"])
+    .arg(&workflow)
+    .args(["--input", "input_list", r#"["a", "b"]"#])
+    .args(extra)
+    .assert()
+    .success()
+    .get_output()
+    .stderr
+    .clone();
+  String::from_utf8(output).unwrap()
+}
+
+/// Stderr lines with durations replaced, so they compare exactly.
+fn event_log(stderr: &str) -> Vec<String> {
+  stderr
+    .lines()
+    .map(|line| match line.split_once(" done in ") {
+      Some((head, tail)) => {
+        let rest = tail.split_once(" · ").map(|(_, rest)| rest);
+        match rest {
+          Some(rest) => format!("{head} done in <t> · {rest}"),
+          None => format!("{head} done in <t>"),
+        }
+      }
+      None => line.to_string(),
+    })
+    .collect()
+}
+
+#[test]
+fn plain_mode_logs_each_node_transition() {
+  let home = TempDir::new().unwrap();
+  let stderr = run(&home, &["--no-progress"]);
+
+  assert_eq!(
+    event_log(&stderr),
+    [
+      "▶ sections started",
+      "… sections 1/2 items",
+      "… sections 2/2 items",
+      "✓ sections done in <t> · 2/2 items",
+      "▶ greet started",
+      "✓ greet done in <t>",
+      "⏭ translate skipped: run_if evaluated to false",
+    ]
+  );
+}
+
+#[test]
+fn verbosity_controls_output_echo() {
+  let home = TempDir::new().unwrap();
+
+  let one = run(&home, &["-v"]);
+  assert!(one.contains("  ↳ greet: output: hello\n"), "{one}");
+  assert!(!one.contains("  ↳ greet outputs:"), "{one}");
+
+  let two = run(&home, &["-vv"]);
+  assert!(
+    two.contains("  ↳ greet outputs:\n{\n  \"output\": \"hello\"\n}\n"),
+    "{two}"
+  );
+
+  let quiet = run(&home, &["--quiet", "-v"]);
+  assert!(!quiet.contains('▶') && !quiet.contains('↳'), "{quiet}");
+}
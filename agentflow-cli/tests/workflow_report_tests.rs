@@ -0,0 +1,132 @@
+//! `workflow run --output` reports: the markdown rendering of a fixture
+//! run is snapshotted, JSON / YAML carry the same per-node shape, a
+//! failed node makes the run exit non-zero, and `--quiet` leaves only
+//! the final summary on stdout.
+//!
+//! Set `UPDATE_SNAPSHOTS=1` to rewrite `tests/snapshots/` after an
+//! intentional change.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn fixture() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/report_workflow.yml")
+}
+
+fn run_report(home: &TempDir, output: &Path, format: &str) -> assert_cmd::assert::Assert {
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["workflow", "run"])
+    .arg(fixture())
+    .args(["--input", "topic", "AgentFlow", "--output-format", format])
+    .arg("--output")
+    .arg(output)
+    .env("HOME", home.path())
+    .env("AGENTFLOW_TRACE_DIR", home.path().join("traces"))
+    .assert()
+}
+
+/// Replaces the run-specific workflow id and timings.
+fn normalize(markdown: &str) -> String {
+  let id = regex::Regex::new(r"`[0-9a-f]{8}-[0-9a-f-]{27}`").unwrap();
+  let duration = regex::Regex::new(r"\b\d+(\.\d+)? (ms|s)\b").unwrap();
+  let markdown = id.replace_all(markdown, "`<workflow-id>`");
+  duration.replace_all(&markdown, "<duration>").into_owned()
+}
+
+#[test]
+fn markdown_report_matches_snapshot_and_failed_node_fails_the_run() {
+  let home = TempDir::new().unwrap();
+  let output = home.path().join("report.md");
+
+  run_report(&home, &output, "markdown")
+    .failure()
+    .stderr(predicate::str::contains("1 node(s) failed: missing_file"));
+
+  let actual = normalize(&fs::read_to_string(&output).unwrap());
+  let snapshot =
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/workflow_run_report.md");
+  if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+    fs::write(&snapshot, &actual).unwrap();
+  }
+  assert_eq!(actual, fs::read_to_string(&snapshot).unwrap());
+}
+
+#[test]
+fn json_and_yaml_reports_share_the_node_shape() {
+  let home = TempDir::new().unwrap();
+  let json_path = home.path().join("report.json");
+  let yaml_path = home.path().join("report.yaml");
+  run_report(&home, &json_path, "json").failure();
+  run_report(&home, &yaml_path, "yaml").failure();
+
+  let from_json: Value = serde_json::from_str(&fs::read_to_string(json_path).unwrap()).unwrap();
+  let from_yaml: Value = serde_yaml::from_str(&fs::read_to_string(yaml_path).unwrap()).unwrap();
+  for report in [&from_json, &from_yaml] {
+    assert_eq!(report["workflow"], "Report Fixture");
+    assert_eq!(report["status"], "failed");
+    let nodes = report["nodes"].as_array().unwrap();
+    let ids: Vec<&str> = nodes
+      .iter()
+      .map(|node| node["id"].as_str().unwrap())
+      .collect();
+    assert_eq!(ids, ["greet", "detail", "missing_file"]);
+    assert_eq!(nodes[0]["status"], "completed");
+    assert_eq!(
+      nodes[0]["outputs"],
+      json!({ "output": "Hello AgentFlow | piped" })
+    );
+    assert!(nodes[0]["duration_ms"].is_u64());
+    assert_eq!(nodes[2]["status"], "failed");
+    assert!(
+      nodes[2]["error"]
+        .as_str()
+        .unwrap()
+        .contains("agentflow-report-fixture.txt")
+    );
+  }
+}
+
+#[test]
+fn quiet_prints_only_the_final_summary() {
+  let home = TempDir::new().unwrap();
+  let work = TempDir::new().unwrap();
+  let workflow = work.path().join("quiet.yml");
+  fs::write(
+    &workflow,
+    "name: quiet\nnodes:\n  - id: render\n    type: template\n    parameters:\n      template: \"Hello {{ topic }}\"\n",
+  )
+  .unwrap();
+
+  let assert = Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["workflow", "run"])
+    .arg(&workflow)
+    .args(["--input", "topic", "AgentFlow", "--quiet"])
+    .env("HOME", home.path())
+    .env("AGENTFLOW_TRACE_DIR", home.path().join("traces"))
+    .assert()
+    .success();
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+
+  assert!(stdout.contains("Workflow completed"), "{stdout}");
+  assert!(
+    stdout.contains("render") && stdout.contains("output: Hello AgentFlow"),
+    "{stdout}"
+  );
+  for chatter in [
+    "Starting AgentFlow",
+    "Executing node",
+    "Tracing enabled",
+    "Running flow",
+  ] {
+    assert!(
+      !stdout.contains(chatter),
+      "--quiet leaked {chatter:?}: {stdout}"
+    );
+  }
+}
@@ -0,0 +1,152 @@
+//! `agentflow workflow run-many` over a directory of three tiny mock
+//! workflows, the second of which fails.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const GREETING: &str = r#"
+name: Greeting
+nodes:
+  - id: draft
+    type: llm
+    parameters:
+      model: mock-model
+      prompt: "Write a haiku"
+"#;
+
+const FAILING: &str = r#"
+name: Failing
+nodes:
+  - id: save
+    type: file
+    parameters:
+      operation: read
+      path: /nonexistent/agentflow-run-many.txt
+"#;
+
+fn seeded_home() -> TempDir {
+  let home = TempDir::new().unwrap();
+  let config_dir = home.path().join(".agentflow");
+  fs::create_dir_all(&config_dir).unwrap();
+  fs::write(
+    config_dir.join("models.yml"),
+    "models:\n  mock-model:\n    vendor: mock\n    type: chat\nproviders:\n  mock:\n    api_key_env: MOCK_API_KEY\n",
+  )
+  .unwrap();
+  let flows = home.path().join("flows");
+  fs::create_dir_all(&flows).unwrap();
+  fs::write(flows.join("a_greeting.yml"), GREETING).unwrap();
+  fs::write(flows.join("b_failing.yaml"), FAILING).unwrap();
+  fs::write(
+    flows.join("c_greeting.yml"),
+    GREETING.replace("Greeting", "Greeting again"),
+  )
+  .unwrap();
+  fs::write(flows.join("notes.txt"), "not a workflow").unwrap();
+  home
+}
+
+fn run_many(home: &TempDir, args: &[&str]) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .args([
+      "workflow",
+      "run-many",
+      "flows",
+      "--summary",
+      "out/summary.json",
+    ])
+    .args(args)
+    .env("HOME", home.path())
+    .env("MOCK_API_KEY", "test")
+    .env("AGENTFLOW_MOCK_RESPONSE", "an old pond")
+    .env("AGENTFLOW_TRACE_DIR", home.path().join("traces"))
+    .env("AGENTFLOW_RUN_DIR", home.path().join("runs"))
+    .current_dir(home.path());
+  cmd
+}
+
+fn summary(home: &Path) -> Value {
+  serde_json::from_str(&fs::read_to_string(home.join("out/summary.json")).unwrap()).unwrap()
+}
+
+fn statuses(summary: &Value) -> Vec<(&str, &str)> {
+  summary["workflows"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|w| (w["file"].as_str().unwrap(), w["status"].as_str().unwrap()))
+    .collect()
+}
+
+#[test]
+fn every_workflow_runs_with_continue_on_error_and_the_failure_sets_the_exit_code() {
+  let home = seeded_home();
+  run_many(&home, &["--parallel", "3", "--continue-on-error"])
+    .assert()
+    .code(1)
+    .stdout(predicate::str::contains(
+      "🚀 Running 3 workflow(s) from flows (3 at a time)",
+    ))
+    .stdout(predicate::str::contains("[a_greeting.yml] "))
+    .stdout(predicate::str::contains(
+      "📊 3 workflow(s): 2 succeeded, 1 failed, 0 skipped",
+    ))
+    .stderr(predicate::str::contains(
+      "[b_failing.yaml] Error: 1 node(s) failed: save",
+    ))
+    .stderr(predicate::str::contains("1 of 3 workflow(s) failed"));
+
+  let summary = summary(home.path());
+  assert_eq!(
+    (&summary["total"], &summary["succeeded"], &summary["failed"]),
+    (&Value::from(3), &Value::from(2), &Value::from(1))
+  );
+  assert_eq!(
+    statuses(&summary),
+    [
+      ("flows/a_greeting.yml", "succeeded"),
+      ("flows/b_failing.yaml", "failed"),
+      ("flows/c_greeting.yml", "succeeded"),
+    ]
+  );
+  let workflows = summary["workflows"].as_array().unwrap();
+  let failed = &workflows[1];
+  assert_eq!(failed["exit_code"], 4);
+  assert_eq!(failed["error"], "1 node(s) failed: save");
+  for workflow in workflows {
+    assert!(workflow["duration_ms"].is_u64());
+    // Each workflow got its own run directory.
+    let run_id = workflow["workflow_id"].as_str().unwrap();
+    assert!(home.path().join("runs").join(run_id).is_dir(), "{run_id}");
+  }
+  assert_ne!(workflows[0]["workflow_id"], workflows[2]["workflow_id"]);
+}
+
+#[test]
+fn allow_failures_exits_zero_and_the_first_failure_otherwise_stops_the_rest() {
+  let home = seeded_home();
+  run_many(&home, &["--continue-on-error", "--allow-failures"])
+    .assert()
+    .success();
+  assert_eq!(summary(home.path())["failed"], 1);
+
+  run_many(&home, &["--parallel", "1"]).assert().code(1);
+  assert_eq!(
+    statuses(&summary(home.path())),
+    [
+      ("flows/a_greeting.yml", "succeeded"),
+      ("flows/b_failing.yaml", "failed"),
+      ("flows/c_greeting.yml", "skipped"),
+    ]
+  );
+
+  run_many(&home, &["--allow-failures"])
+    .assert()
+    .code(2)
+    .stderr(predicate::str::contains("--continue-on-error"));
+}
@@ -0,0 +1,78 @@
+//! Located schema errors from `workflow run` and `workflow debug
+//! --validate`: each fixture under `tests/fixtures/schema_errors/` has a
+//! mistake at a known line, reported before anything runs.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn fixture(name: &str) -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests/fixtures/schema_errors")
+    .join(name)
+}
+
+/// `workflow run` and `workflow debug --validate` on `name`, from the
+/// fixture directory so messages name the file relatively.
+fn both(name: &str) -> [assert_cmd::assert::Assert; 2] {
+  let home = TempDir::new().unwrap();
+  let run = |args: &[&str]| {
+    Command::cargo_bin("agentflow")
+      .unwrap()
+      .args(args)
+      .arg(name)
+      .current_dir(fixture(""))
+      .env("HOME", home.path())
+      .env("AGENTFLOW_TRACE_DIR", home.path().join("traces"))
+      .env("AGENTFLOW_RUN_DIR", home.path().join("runs"))
+      .assert()
+  };
+  [
+    run(&["workflow", "run"]),
+    run(&["workflow", "debug", "--validate"]),
+  ]
+}
+
+#[test]
+fn unknown_node_type_names_its_line_and_the_closest_type() {
+  for assert in both("unknown_type.yml") {
+    assert
+      .code(3)
+      .stdout(predicate::str::contains(
+        "unknown_type.yml:9:11: nodes[1].type: 'htpp' is not supported by the CLI workflow factory; did you mean 'http'?",
+      ))
+      .stderr(predicate::str::contains(
+        "workflow 'Unknown Type' failed schema validation with 1 issue(s)",
+      ));
+  }
+}
+
+#[test]
+fn wrong_typed_parameter_is_an_error_and_a_misspelled_one_a_warning() {
+  for assert in both("wrong_type.yml") {
+    assert
+      .code(3)
+      .stdout(predicate::str::contains(
+        "❌ Schema issues: 1\n  1. wrong_type.yml:10:19: nodes[0].parameters.max_tokens: must be an integer, found a string",
+      ))
+      .stdout(predicate::str::contains(
+        "wrong_type.yml:9:7: nodes[0].parameters.temprature: is not a parameter of node type 'llm'; did you mean 'temperature'?",
+      ));
+  }
+}
+
+#[test]
+fn missing_required_fields_point_at_the_node() {
+  for assert in both("missing_field.yml") {
+    assert
+      .code(3)
+      .stdout(predicate::str::contains(
+        "missing_field.yml:9:5: nodes[1]: node is missing the required field 'id'",
+      ))
+      .stdout(predicate::str::contains(
+        "missing_field.yml:14:5: nodes[2].parameters: node 'third' requires 'template' as a parameter",
+      ))
+      .stderr(predicate::str::contains("failed schema validation with 2 issue(s)"));
+  }
+}
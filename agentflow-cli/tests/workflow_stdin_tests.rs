@@ -0,0 +1,144 @@
+//! `agentflow workflow run -`: YAML piped on stdin, `--inputs-file -`,
+//! `--base-dir` resolution of relative file paths, and the flag
+//! combinations that cannot share stdin.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+/// Reads `notes.txt` relative to the working directory and writes a
+/// greeting built from it and the `name` input next to it.
+const WORKFLOW: &str = r#"
+name: "Piped"
+nodes:
+  - id: read
+    type: file
+    parameters:
+      operation: read
+      path: notes.txt
+  - id: greet
+    type: template
+    dependencies: [read]
+    input_mapping:
+      notes: "{{ nodes.read.outputs.content }}"
+    parameters:
+      template: "Hello {{ name }}: {{ notes }}"
+  - id: save
+    type: file
+    dependencies: [greet]
+    input_mapping:
+      content: "{{ nodes.greet.outputs.output }}"
+    parameters:
+      operation: write
+      path: out/greeting.txt
+"#;
+
+fn agentflow(home: &TempDir) -> Command {
+  let mut cmd = Command::cargo_bin("agentflow").unwrap();
+  cmd
+    .env("HOME", home.path())
+    .env_remove("AGENTFLOW_RUN_DIR")
+    .current_dir(home.path());
+  cmd
+}
+
+#[test]
+fn piped_workflow_resolves_relative_paths_against_base_dir() {
+  let home = TempDir::new().unwrap();
+  let project = home.path().join("project");
+  fs::create_dir_all(project.join("out")).unwrap();
+  fs::write(project.join("notes.txt"), "from the project").unwrap();
+  fs::write(home.path().join("notes.txt"), "from the cwd").unwrap();
+  fs::create_dir_all(home.path().join("out")).unwrap();
+
+  agentflow(&home)
+    .args([
+      "workflow",
+      "run",
+      "-",
+      "--base-dir",
+      "project",
+      "--no-progress",
+    ])
+    .args(["--input", "name", "CI", "--output", "report.json"])
+    .write_stdin(WORKFLOW)
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("execution: <stdin>"));
+  assert_eq!(
+    fs::read_to_string(project.join("out/greeting.txt")).unwrap(),
+    "Hello CI: from the project"
+  );
+  assert!(!home.path().join("out/greeting.txt").exists());
+  // --output stays relative to where the command was run.
+  let report: Value =
+    serde_json::from_str(&fs::read_to_string(home.path().join("report.json")).unwrap()).unwrap();
+  assert_eq!(report["workflow"], "Piped");
+
+  // Without --base-dir the working directory is the base.
+  agentflow(&home)
+    .args([
+      "workflow",
+      "run",
+      "-",
+      "--no-progress",
+      "--input",
+      "name",
+      "cwd",
+    ])
+    .write_stdin(WORKFLOW)
+    .assert()
+    .success();
+  assert_eq!(
+    fs::read_to_string(home.path().join("out/greeting.txt")).unwrap(),
+    "Hello cwd: from the cwd"
+  );
+}
+
+#[test]
+fn inputs_file_can_be_piped_instead() {
+  let home = TempDir::new().unwrap();
+  fs::write(home.path().join("flow.yml"), WORKFLOW).unwrap();
+  fs::write(home.path().join("notes.txt"), "notes").unwrap();
+  fs::create_dir_all(home.path().join("out")).unwrap();
+
+  agentflow(&home)
+    .args([
+      "workflow",
+      "run",
+      "flow.yml",
+      "--inputs-file",
+      "-",
+      "--no-progress",
+    ])
+    .write_stdin("name: YAML\n")
+    .assert()
+    .success();
+  assert_eq!(
+    fs::read_to_string(home.path().join("out/greeting.txt")).unwrap(),
+    "Hello YAML: notes"
+  );
+}
+
+#[test]
+fn stdin_workflow_rejects_watch_and_a_second_stdin_reader() {
+  let home = TempDir::new().unwrap();
+  agentflow(&home)
+    .args(["workflow", "run", "-", "--watch"])
+    .write_stdin(WORKFLOW)
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+      "--watch needs a workflow file to watch, not YAML piped on stdin",
+    ));
+  agentflow(&home)
+    .args(["workflow", "run", "-", "--inputs-file", "-"])
+    .write_stdin(WORKFLOW)
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+      "Only one of the workflow file and --inputs-file may read from stdin ('-')",
+    ));
+}
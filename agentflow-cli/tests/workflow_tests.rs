@@ -294,6 +294,51 @@ fn cli_workflow_run_accepts_inputs_and_writes_output_file() {
   assert!(saved.contains("Hello AgentFlow"));
 }
 
+#[test]
+fn cli_workflow_run_merges_inputs_file_flags_and_stdin() {
+  let home = TempDir::new().unwrap();
+  let work = TempDir::new().unwrap();
+  let workflow = work.path().join("nested_inputs.yml");
+  fs::write(
+    &workflow,
+    r#"
+name: Nested Inputs
+nodes:
+  - id: render
+    type: template
+    parameters:
+      template: "{{ user.name }} speaks {{ user.langs | length }} languages; {{ topic }}; {{ doc }}"
+"#,
+  )
+  .unwrap();
+  let inputs_file = work.path().join("inputs.json");
+  fs::write(
+    &inputs_file,
+    r#"{"user": {"name": "Ada", "langs": ["en", "fr"]}, "topic": "from file"}"#,
+  )
+  .unwrap();
+  let output = work.path().join("result.json");
+
+  Command::cargo_bin("agentflow")
+    .unwrap()
+    .args(["workflow", "run", workflow.to_str().unwrap()])
+    .arg("--inputs-file")
+    .arg(&inputs_file)
+    .args(["--input", "topic", "from flag", "--input", "doc", "-"])
+    .arg("--output")
+    .arg(&output)
+    .env("HOME", home.path())
+    .write_stdin("piped document\n")
+    .assert()
+    .success();
+
+  let saved: Value = serde_json::from_str(&fs::read_to_string(output).unwrap()).unwrap();
+  assert_eq!(
+    saved["nodes"][0]["outputs"]["output"],
+    json!("Ada speaks 2 languages; from flag; piped document\n")
+  );
+}
+
 #[test]
 fn cli_workflow_run_accepts_explicit_run_artifacts_directory() {
   let home = TempDir::new().unwrap();
@@ -335,7 +380,14 @@ fn cli_workflow_run_uses_env_run_artifacts_directory() {
 
   let mut cmd = Command::cargo_bin("agentflow").unwrap();
   cmd
-    .args(["workflow", "run", workflow.to_str().unwrap()])
+    .args([
+      "workflow",
+      "run",
+      workflow.to_str().unwrap(),
+      "--input",
+      "topic",
+      "AgentFlow",
+    ])
     .env("HOME", home.path())
     .env("AGENTFLOW_RUN_DIR", &run_dir)
     .assert()
@@ -423,7 +475,8 @@ fn cli_workflow_debug_plan_mentions_concurrent_ready_nodes() {
     .stdout(predicate::str::contains(
       "workflow run --execution-mode concurrent",
     ))
-    .stdout(predicate::str::contains("Level 1 (2 nodes):"));
+    .stdout(predicate::str::contains("1      branch_a  template  run"))
+    .stdout(predicate::str::contains("1      branch_b  template  run"));
 }
 
 #[test]
@@ -451,21 +504,6 @@ fn cli_workflow_run_rejects_zero_max_concurrency() {
     ));
 }
 
-#[test]
-fn cli_workflow_run_rejects_unimplemented_watch_flag() {
-  let home = TempDir::new().unwrap();
-  let work = TempDir::new().unwrap();
-  let workflow = write_template_workflow(&work);
-
-  let mut cmd = Command::cargo_bin("agentflow").unwrap();
-  cmd
-    .args(["workflow", "run", workflow.to_str().unwrap(), "--watch"])
-    .env("HOME", home.path())
-    .assert()
-    .failure()
-    .stderr(predicate::str::contains("--watch is not implemented yet"));
-}
-
 #[test]
 fn cli_workflow_run_validates_required_node_parameters_before_execution() {
   let home = TempDir::new().unwrap();
@@ -1100,7 +1138,7 @@ async fn test_simple_two_step_llm_workflow() {
 
   let answer_generator_node = GraphNode {
     id: "answer_generator".to_string(),
-    node_type: NodeType::Standard(Arc::new(LlmNode)),
+    node_type: NodeType::Standard(Arc::new(LlmNode::default())),
     dependencies: vec!["prompt_generator".to_string()],
     input_mapping: Some({
       let mut map = HashMap::new();
@@ -1144,7 +1182,7 @@ async fn test_conditional_workflow_runs() {
 
   let condition_node = GraphNode {
     id: "condition_node".to_string(),
-    node_type: NodeType::Standard(Arc::new(LlmNode)),
+    node_type: NodeType::Standard(Arc::new(LlmNode::default())),
     dependencies: vec![],
     input_mapping: None,
     run_if: None,
@@ -1197,7 +1235,7 @@ async fn test_conditional_workflow_skips() {
 
   let condition_node = GraphNode {
     id: "condition_node".to_string(),
-    node_type: NodeType::Standard(Arc::new(LlmNode)),
+    node_type: NodeType::Standard(Arc::new(LlmNode::default())),
     dependencies: vec![],
     input_mapping: None,
     run_if: None,
@@ -1259,7 +1297,7 @@ async fn test_parallel_map_workflow() {
     },
     GraphNode {
       id: "poem_generator".to_string(),
-      node_type: NodeType::Standard(Arc::new(LlmNode)),
+      node_type: NodeType::Standard(Arc::new(LlmNode::default())),
       dependencies: vec!["poem_prompt".to_string()],
       input_mapping: Some(
         [(
@@ -1336,7 +1374,7 @@ async fn test_stateful_while_loop_workflow() {
     },
     GraphNode {
       id: "decrementer_llm".to_string(),
-      node_type: NodeType::Standard(Arc::new(LlmNode)),
+      node_type: NodeType::Standard(Arc::new(LlmNode::default())),
       dependencies: vec!["decrementer_prompt".to_string()],
       input_mapping: Some(
         [(
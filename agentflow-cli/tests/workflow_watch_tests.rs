@@ -0,0 +1,137 @@
+//! `workflow run --watch` end to end: the binary runs a fast template
+//! workflow, re-runs it exactly once per save of the YAML, and exits
+//! cleanly on SIGINT.
+#![cfg(unix)]
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const COMPLETED: &str = "Workflow completed";
+
+fn write_workflow(path: &Path, greeting: &str) {
+  fs::write(
+    path,
+    format!(
+      r#"
+name: watched
+nodes:
+  - id: render
+    type: template
+    parameters:
+      template: "{greeting} from watch"
+"#
+    ),
+  )
+  .unwrap();
+}
+
+/// Collects stdout lines until `done` holds or `timeout` passes.
+fn read_until(
+  lines: &Receiver<String>,
+  seen: &mut Vec<String>,
+  timeout: Duration,
+  done: impl Fn(&[String]) -> bool,
+) {
+  let deadline = Instant::now() + timeout;
+  while !done(seen) {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    match lines.recv_timeout(remaining) {
+      Ok(line) => seen.push(line),
+      Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+    }
+  }
+}
+
+fn completed_runs(lines: &[String]) -> usize {
+  lines.iter().filter(|line| line.contains(COMPLETED)).count()
+}
+
+#[test]
+fn each_save_triggers_exactly_one_rerun_and_sigint_exits_cleanly() {
+  let home = TempDir::new().unwrap();
+  let work = TempDir::new().unwrap();
+  let workflow = work.path().join("flow.yml");
+  write_workflow(&workflow, "Hello");
+
+  let mut child = Command::new(assert_cmd::cargo::cargo_bin("agentflow"))
+    .args(["workflow", "run", workflow.to_str().unwrap(), "--watch"])
+    .arg("--run-dir")
+    .arg(work.path().join("runs"))
+    .env("HOME", home.path())
+    .env("AGENTFLOW_TRACE_DIR", work.path().join("traces"))
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .unwrap();
+  let stdout = child.stdout.take().unwrap();
+  let (tx, lines) = std::sync::mpsc::channel();
+  std::thread::spawn(move || {
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+      if tx.send(line).is_err() {
+        break;
+      }
+    }
+  });
+  let mut seen = Vec::new();
+
+  read_until(&lines, &mut seen, Duration::from_secs(60), |seen| {
+    seen
+      .iter()
+      .any(|line| line.contains("Watching for changes"))
+  });
+  assert_eq!(completed_runs(&seen), 1, "first run: {seen:#?}");
+  assert!(seen.iter().any(|line| line.contains("run #1")));
+  assert!(seen.iter().any(|line| line.contains("Hello from watch")));
+
+  for (save, greeting) in [(2, "Bonjour"), (3, "Hola")] {
+    write_workflow(&workflow, greeting);
+    read_until(&lines, &mut seen, Duration::from_secs(60), |seen| {
+      completed_runs(seen) >= save
+        && seen
+          .iter()
+          .filter(|line| line.contains("Watching for changes"))
+          .count()
+          >= save
+    });
+    // Give a duplicate run time to show up before counting.
+    read_until(&lines, &mut seen, Duration::from_secs(2), |_| false);
+    assert_eq!(completed_runs(&seen), save, "after save {save}: {seen:#?}");
+    assert!(
+      seen
+        .iter()
+        .any(|line| line.contains(&format!("run #{save}")))
+    );
+    assert!(
+      seen
+        .iter()
+        .any(|line| line.contains(&format!("{greeting} from watch"))),
+      "re-run must read the saved YAML: {seen:#?}"
+    );
+  }
+
+  let pid = child.id().to_string();
+  assert!(
+    Command::new("kill")
+      .args(["-INT", &pid])
+      .status()
+      .unwrap()
+      .success()
+  );
+  let deadline = Instant::now() + Duration::from_secs(20);
+  let status = loop {
+    if let Some(status) = child.try_wait().unwrap() {
+      break status;
+    }
+    if Instant::now() > deadline {
+      let _ = child.kill();
+      panic!("watch did not exit after SIGINT");
+    }
+    std::thread::sleep(Duration::from_millis(50));
+  };
+  assert!(status.success(), "watch should exit cleanly: {status:?}");
+}
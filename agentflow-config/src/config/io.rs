@@ -0,0 +1,325 @@
+//! A workflow's declared inputs and outputs.
+//!
+//! [`resolve_inputs`] checks the inputs a run was given against the
+//! workflow's `inputs:` section before anything runs: every required input
+//! must be present, a missing optional one takes its `default`, and a value
+//! is coerced to the declared `type` (`"3"` to a number, `"true"` to a
+//! boolean, a number to a string). [`OutputRef`] is one entry of the
+//! `outputs:` section, the `nodes.<id>.outputs.<key>` a returned value is
+//! read from.
+
+use crate::config::located::closest;
+use crate::config::v2::{FlowDefinitionV2, InputDefinitionV2, InputType};
+use agentflow_core::{async_node::AsyncNodeInputs, value::FlowValue};
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+/// Output names the run report keeps for itself.
+pub const RESERVED_OUTPUT_NAMES: &[&str] = &["debug"];
+
+/// The inputs of a run, checked against the workflow's declarations.
+#[derive(Debug, Default)]
+pub struct ResolvedInputs {
+  pub inputs: AsyncNodeInputs,
+  /// Provided inputs the workflow does not declare, usually a typo.
+  pub warnings: Vec<String>,
+}
+
+/// Checks `provided` against the inputs `flow_def` declares, filling in
+/// defaults and coercing values to their declared types. Every missing
+/// required input and badly typed value is reported in one error. A
+/// workflow without an `inputs:` section accepts anything unchanged.
+pub fn resolve_inputs(
+  flow_def: &FlowDefinitionV2,
+  mut provided: AsyncNodeInputs,
+) -> Result<ResolvedInputs> {
+  let mut resolved = ResolvedInputs::default();
+  if flow_def.inputs.is_empty() {
+    resolved.inputs = provided;
+    return Ok(resolved);
+  }
+
+  let mut names: Vec<&str> = flow_def.inputs.keys().map(String::as_str).collect();
+  names.sort();
+  let mut problems = Vec::new();
+  for name in &names {
+    let declaration = &flow_def.inputs[*name];
+    let value = match provided.remove(*name) {
+      Some(FlowValue::Json(value)) => value,
+      // Files and URLs are passed through whatever the declared type.
+      Some(other) => {
+        resolved.inputs.insert(name.to_string(), other);
+        continue;
+      }
+      None => match &declaration.default {
+        Some(default) => match serde_json::to_value(default) {
+          Ok(value) => value,
+          Err(err) => {
+            problems.push(format!("input '{}' has an invalid default: {}", name, err));
+            continue;
+          }
+        },
+        None if declaration.required => {
+          problems.push(missing_input(name, declaration));
+          continue;
+        }
+        None => continue,
+      },
+    };
+    match coerce_input(&value, declaration.input_type) {
+      Ok(value) => {
+        resolved
+          .inputs
+          .insert(name.to_string(), FlowValue::Json(value));
+      }
+      Err(expected) => problems.push(format!(
+        "input '{}' must be {}, got {}",
+        name, expected, value
+      )),
+    }
+  }
+
+  let mut undeclared: Vec<_> = provided.into_iter().collect();
+  undeclared.sort_by(|(a, _), (b, _)| a.cmp(b));
+  for (name, value) in undeclared {
+    let mut warning = format!("input '{}' is not declared by the workflow", name);
+    if let Some(suggestion) = closest(&name, &names) {
+      warning.push_str(&format!("; did you mean '{}'?", suggestion));
+    }
+    resolved.warnings.push(warning);
+    resolved.inputs.insert(name, value);
+  }
+
+  if !problems.is_empty() {
+    bail!(
+      "workflow '{}' got invalid inputs:\n  - {}",
+      flow_def.name,
+      problems.join("\n  - ")
+    );
+  }
+  Ok(resolved)
+}
+
+fn missing_input(name: &str, declaration: &InputDefinitionV2) -> String {
+  match &declaration.description {
+    Some(description) => format!("missing required input '{}' ({})", name, description),
+    None => format!("missing required input '{}'", name),
+  }
+}
+
+/// `value` as the declared type, or what was expected when it cannot be.
+/// A string is parsed for a number, integer, boolean, object or array;
+/// a number or boolean is written out for a string.
+pub fn coerce_input(value: &Value, input_type: Option<InputType>) -> Result<Value, &'static str> {
+  let Some(input_type) = input_type else {
+    return Ok(value.clone());
+  };
+  let text = value.as_str().map(str::trim);
+  let coerced = match input_type {
+    InputType::String => match value {
+      Value::String(_) => Some(value.clone()),
+      Value::Number(number) => Some(Value::String(number.to_string())),
+      Value::Bool(flag) => Some(Value::String(flag.to_string())),
+      _ => None,
+    },
+    InputType::Number => match value {
+      Value::Number(_) => Some(value.clone()),
+      _ => text
+        .and_then(|text| text.parse::<f64>().ok())
+        .and_then(|number| serde_json::Number::from_f64(number).map(Value::Number))
+        .map(|number| integral(&number).unwrap_or(number)),
+    },
+    InputType::Integer => match value {
+      Value::Number(_) => integral(value),
+      _ => text
+        .and_then(|text| text.parse::<i64>().ok())
+        .map(Value::from),
+    },
+    InputType::Boolean => match (value, text) {
+      (Value::Bool(_), _) => Some(value.clone()),
+      (_, Some(text)) if text.eq_ignore_ascii_case("true") => Some(Value::Bool(true)),
+      (_, Some(text)) if text.eq_ignore_ascii_case("false") => Some(Value::Bool(false)),
+      _ => None,
+    },
+    InputType::Object => match value {
+      Value::Object(_) => Some(value.clone()),
+      _ => text
+        .and_then(|text| serde_json::from_str(text).ok())
+        .filter(Value::is_object),
+    },
+    InputType::Array => match value {
+      Value::Array(_) => Some(value.clone()),
+      _ => text
+        .and_then(|text| serde_json::from_str(text).ok())
+        .filter(Value::is_array),
+    },
+  };
+  coerced.ok_or(match input_type {
+    InputType::String => "a string",
+    InputType::Number => "a number",
+    InputType::Integer => "an integer",
+    InputType::Boolean => "a boolean",
+    InputType::Object => "an object/map",
+    InputType::Array => "a sequence/list",
+  })
+}
+
+/// A whole number as an integer, so `3.0` reads as `3`.
+fn integral(value: &Value) -> Option<Value> {
+  let number = value.as_number()?;
+  if number.is_i64() || number.is_u64() {
+    return Some(value.clone());
+  }
+  let float = number.as_f64()?;
+  (float.fract() == 0.0 && float.abs() < i64::MAX as f64).then(|| Value::from(float as i64))
+}
+
+/// Where a declared output is read from: `nodes.<node>.outputs.<output>`,
+/// with or without the `{{ }}` of an input mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputRef {
+  pub node: String,
+  pub output: String,
+}
+
+impl OutputRef {
+  pub fn parse(reference: &str) -> Option<Self> {
+    let path = reference
+      .trim()
+      .trim_start_matches("{{")
+      .trim_end_matches("}}")
+      .trim();
+    match path.split('.').collect::<Vec<_>>().as_slice() {
+      ["nodes", node, "outputs", output] if !node.is_empty() && !output.is_empty() => Some(Self {
+        node: node.to_string(),
+        output: output.to_string(),
+      }),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn flow(yaml: &str) -> FlowDefinitionV2 {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  fn inputs(pairs: &[(&str, Value)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+      .collect()
+  }
+
+  fn json_input(inputs: &AsyncNodeInputs, key: &str) -> Option<Value> {
+    match inputs.get(key) {
+      Some(FlowValue::Json(value)) => Some(value.clone()),
+      None => None,
+      other => panic!("{key} should be a JSON input, got {other:?}"),
+    }
+  }
+
+  const DECLARED: &str = r#"
+name: Declared
+inputs:
+  topic:
+    type: string
+    required: true
+    description: "What to write about"
+  limit:
+    type: integer
+    default: 5
+  verbose:
+    type: boolean
+  tone:
+    default: friendly
+nodes:
+  - id: write
+    type: template
+    parameters:
+      template: "{{ topic }}"
+"#;
+
+  #[test]
+  fn missing_required_inputs_are_reported_together() {
+    let err = resolve_inputs(
+      &flow(DECLARED),
+      inputs(&[("limit", json!("many")), ("verbose", json!("true"))]),
+    )
+    .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "workflow 'Declared' got invalid inputs:\n  - input 'limit' must be an integer, got \"many\"\n  - missing required input 'topic' (What to write about)"
+    );
+  }
+
+  #[test]
+  fn defaults_fill_in_and_values_are_coerced() {
+    let resolved = resolve_inputs(
+      &flow(DECLARED),
+      inputs(&[
+        ("topic", json!(42)),
+        ("verbose", json!("TRUE")),
+        ("topc", json!("typo")),
+      ]),
+    )
+    .unwrap();
+    let got = &resolved.inputs;
+    assert_eq!(json_input(got, "topic"), Some(json!("42")));
+    assert_eq!(json_input(got, "limit"), Some(json!(5)));
+    assert_eq!(json_input(got, "verbose"), Some(json!(true)));
+    assert_eq!(json_input(got, "tone"), Some(json!("friendly")));
+    // Undeclared inputs still reach the nodes, with a warning
+    assert_eq!(json_input(got, "topc"), Some(json!("typo")));
+    assert_eq!(
+      resolved.warnings,
+      vec!["input 'topc' is not declared by the workflow; did you mean 'topic'?"]
+    );
+
+    // An optional input without a default stays unset
+    let resolved = resolve_inputs(&flow(DECLARED), inputs(&[("topic", json!("x"))])).unwrap();
+    assert_eq!(json_input(&resolved.inputs, "verbose"), None);
+  }
+
+  #[test]
+  fn values_coerce_to_their_declared_type() {
+    let number = Some(InputType::Number);
+    assert_eq!(coerce_input(&json!("2.5"), number), Ok(json!(2.5)));
+    assert_eq!(coerce_input(&json!(" 3 "), number), Ok(json!(3)));
+    assert_eq!(
+      coerce_input(&json!(3.0), Some(InputType::Integer)),
+      Ok(json!(3))
+    );
+    assert_eq!(
+      coerce_input(&json!(3.5), Some(InputType::Integer)),
+      Err("an integer")
+    );
+    assert_eq!(
+      coerce_input(&json!("[1]"), Some(InputType::Array)),
+      Ok(json!([1]))
+    );
+    assert_eq!(
+      coerce_input(&json!("{}"), Some(InputType::Array)),
+      Err("a sequence/list")
+    );
+    assert_eq!(coerce_input(&json!([1]), None), Ok(json!([1])));
+  }
+
+  #[test]
+  fn output_references_name_a_node_output() {
+    assert_eq!(
+      OutputRef::parse("nodes.write.outputs.output"),
+      Some(OutputRef {
+        node: "write".to_string(),
+        output: "output".to_string()
+      })
+    );
+    assert!(OutputRef::parse("{{ nodes.write.outputs.output }}").is_some());
+    assert_eq!(OutputRef::parse("nodes.write.output"), None);
+    assert_eq!(OutputRef::parse("nodes..outputs.output"), None);
+  }
+}
@@ -0,0 +1,597 @@
+//! Schema checks on the workflow YAML text, reported at a line and
+//! column.
+//!
+//! [`validate_flow_definition`](super::schema::validate_flow_definition)
+//! runs on the deserialized [`FlowDefinitionV2`](super::v2::FlowDefinitionV2),
+//! which no longer knows where each value came from.
+//! [`check_workflow_yaml`] runs first, on a located parse of the same
+//! text, and checks what the node parameter tables behind
+//! [`NodeRegistry`] describe: the workflow has a `name` and `nodes`, each
+//! node an `id` and a `type` this build can construct, required
+//! parameters are present and parameters have their declared type.
+//! Unknown node types and parameters get a did-you-mean suggestion.
+//! Nested nodes (`map` / `batch` templates, `while` bodies, a batch
+//! `child`) are checked the same way. Dependencies, input mappings and
+//! the per-type validators stay with `validate_flow_definition`.
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_yaml::Value;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+use super::registry::NodeRegistry;
+use super::schema::{
+  UnknownParameterMode, WorkflowValidationOptions, describe_param_type, feature_hint,
+  param_type_matches, specs_for_node_type,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+/// One problem, at the place in the text it is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  /// 1-based.
+  pub line: usize,
+  /// 1-based.
+  pub column: usize,
+  /// Where in the document, e.g. `nodes[1].parameters.temperature`.
+  pub path: String,
+  /// The offending key.
+  pub key: String,
+  pub message: String,
+  /// The closest known name, for an unknown node type or parameter.
+  pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+  /// `source:line:column: path: message`, plus the suggestion.
+  pub fn render(&self, source: &str) -> String {
+    format!("{}:{}:{}: {}", source, self.line, self.column, self)
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.path, self.message)?;
+    if let Some(suggestion) = &self.suggestion {
+      write!(f, "; did you mean '{}'?", suggestion)?;
+    }
+    Ok(())
+  }
+}
+
+/// The schema problems in workflow `yaml`, in document order. Unknown
+/// parameters are warnings unless `options` makes them errors; a parse
+/// error is the only diagnostic.
+pub fn check_workflow_yaml(yaml: &str, options: WorkflowValidationOptions) -> Vec<Diagnostic> {
+  let value: Value = match serde_yaml::from_str(yaml) {
+    Ok(value) => value,
+    Err(err) => return vec![parse_error(&err)],
+  };
+  // serde_yaml accepted it, so the located parse only fails on YAML the
+  // two parsers disagree about; those keep the unlocated checks.
+  let Some(root) = parse_located(yaml) else {
+    return Vec::new();
+  };
+  let mut check = Check {
+    options,
+    diagnostics: Vec::new(),
+  };
+  check.workflow(&root, &value);
+  check.diagnostics.sort_by_key(|d| (d.line, d.column));
+  check.diagnostics
+}
+
+fn parse_error(err: &serde_yaml::Error) -> Diagnostic {
+  let (line, column) = err
+    .location()
+    .map_or((1, 1), |location| (location.line(), location.column()));
+  // The message repeats the location as " at line L column C".
+  let message = err.to_string();
+  let message = match message.rfind(" at line ") {
+    Some(at) => message[..at].to_string(),
+    None => message,
+  };
+  Diagnostic {
+    severity: Severity::Error,
+    line,
+    column,
+    path: "<document>".to_string(),
+    key: String::new(),
+    message: format!("invalid YAML: {}", message),
+    suggestion: None,
+  }
+}
+
+struct Check {
+  options: WorkflowValidationOptions,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl Check {
+  fn push(
+    &mut self,
+    severity: Severity,
+    at: &Located,
+    path: String,
+    key: &str,
+    message: String,
+    suggestion: Option<String>,
+  ) {
+    self.diagnostics.push(Diagnostic {
+      severity,
+      line: at.line,
+      column: at.column,
+      path,
+      key: key.to_string(),
+      message,
+      suggestion,
+    });
+  }
+
+  fn workflow(&mut self, root: &Located, value: &Value) {
+    let Kind::Mapping(entries) = &root.kind else {
+      self.push(
+        Severity::Error,
+        root,
+        "<document>".to_string(),
+        "",
+        "a workflow must be a mapping with `name` and `nodes`".to_string(),
+        None,
+      );
+      return;
+    };
+    for field in ["name", "nodes"] {
+      if get(entries, field).is_none() {
+        self.push(
+          Severity::Error,
+          root,
+          "<document>".to_string(),
+          field,
+          format!("workflow is missing the required field '{}'", field),
+          None,
+        );
+      }
+    }
+    if let Some((_, nodes)) = get(entries, "nodes") {
+      self.nodes(nodes, &value["nodes"], "nodes", true);
+    }
+  }
+
+  fn nodes(&mut self, nodes: &Located, value: &Value, path: &str, id_required: bool) {
+    let Kind::Sequence(items) = &nodes.kind else {
+      self.push(
+        Severity::Error,
+        nodes,
+        path.to_string(),
+        path.rsplit('.').next().unwrap_or(path),
+        "must be a list of node definitions".to_string(),
+        None,
+      );
+      return;
+    };
+    for (index, node) in items.iter().enumerate() {
+      self.node(
+        node,
+        &value[index],
+        &format!("{}[{}]", path, index),
+        id_required,
+      );
+    }
+  }
+
+  fn node(&mut self, node: &Located, value: &Value, path: &str, id_required: bool) {
+    let Kind::Mapping(entries) = &node.kind else {
+      self.push(
+        Severity::Error,
+        node,
+        path.to_string(),
+        "",
+        "a node must be a mapping with `id` and `type`".to_string(),
+        None,
+      );
+      return;
+    };
+    for field in ["id", "type"] {
+      if field == "id" && !id_required {
+        continue;
+      }
+      match get(entries, field) {
+        None => self.push(
+          Severity::Error,
+          node,
+          path.to_string(),
+          field,
+          format!("node is missing the required field '{}'", field),
+          None,
+        ),
+        Some((_, scalar)) if !matches!(scalar.kind, Kind::Scalar(_)) => self.push(
+          Severity::Error,
+          scalar,
+          format!("{}.{}", path, field),
+          field,
+          "must be a string".to_string(),
+          None,
+        ),
+        Some(_) => {}
+      }
+    }
+    let Some((_, type_node)) = get(entries, "type") else {
+      return;
+    };
+    let Kind::Scalar(node_type) = &type_node.kind else {
+      return;
+    };
+    let Some(specs) = specs_for_node_type(node_type) else {
+      let known: Vec<&str> = NodeRegistry::list().iter().map(|info| info.name).collect();
+      self.push(
+        Severity::Error,
+        type_node,
+        format!("{}.type", path),
+        "type",
+        format!(
+          "'{}' is not supported by the CLI workflow factory{}",
+          node_type,
+          feature_hint(node_type)
+        ),
+        closest(node_type, &known),
+      );
+      return;
+    };
+
+    let parameters = get(entries, "parameters");
+    let params_value = &value["parameters"];
+    let param_entries: &[(Located, Located)] = match parameters {
+      Some((
+        _,
+        Located {
+          kind: Kind::Mapping(entries),
+          ..
+        },
+      )) => entries,
+      Some(_) if params_value.is_null() => &[],
+      Some((_, params)) => {
+        self.push(
+          Severity::Error,
+          params,
+          format!("{}.parameters", path),
+          "parameters",
+          "must be a mapping".to_string(),
+          None,
+        );
+        return;
+      }
+      None => &[],
+    };
+    let mapped: Vec<&str> = match get(entries, "input_mapping") {
+      Some((
+        _,
+        Located {
+          kind: Kind::Mapping(mapping),
+          ..
+        },
+      )) => mapping.iter().filter_map(|(key, _)| key.scalar()).collect(),
+      _ => Vec::new(),
+    };
+    let node_id = entries
+      .iter()
+      .find(|(key, _)| key.scalar() == Some("id"))
+      .and_then(|(_, id)| id.scalar())
+      .unwrap_or("?");
+
+    for spec in &specs {
+      match get(param_entries, spec.name) {
+        None => {
+          if spec.required && !(spec.input_allowed && mapped.contains(&spec.name)) {
+            let at = parameters.map_or(node, |(key, _)| key);
+            self.push(
+              Severity::Error,
+              at,
+              format!("{}.parameters", path),
+              spec.name,
+              format!(
+                "node '{}' requires '{}' as a parameter{}",
+                node_id,
+                spec.name,
+                if spec.input_allowed {
+                  " or input_mapping"
+                } else {
+                  ""
+                }
+              ),
+              None,
+            );
+          }
+        }
+        Some((_, param)) => {
+          let typed = &params_value[spec.name];
+          if !param_type_matches(typed, spec.kind) {
+            self.push(
+              Severity::Error,
+              param,
+              format!("{}.parameters.{}", path, spec.name),
+              spec.name,
+              format!(
+                "must be {}, found {}",
+                describe_param_type(spec.kind),
+                describe_value(typed)
+              ),
+              None,
+            );
+          }
+        }
+      }
+    }
+
+    // `template` renders its other parameters as context (see
+    // `validate_node_schema`).
+    if node_type != "template" {
+      let names: Vec<&str> = specs.iter().map(|spec| spec.name).collect();
+      for (key, _) in param_entries {
+        let Some(name) = key.scalar() else { continue };
+        if names.contains(&name) {
+          continue;
+        }
+        let severity = match self.options.unknown_parameters {
+          UnknownParameterMode::Error => Severity::Error,
+          UnknownParameterMode::Warning => Severity::Warning,
+          UnknownParameterMode::Ignore => continue,
+        };
+        self.push(
+          severity,
+          key,
+          format!("{}.parameters.{}", path, name),
+          name,
+          format!("is not a parameter of node type '{}'", node_type),
+          closest(name, &names),
+        );
+      }
+    }
+
+    let nested: &[(&str, bool)] = match node_type.as_str() {
+      "map" => &[("template", true)],
+      "batch" => &[("template", true), ("child", false)],
+      "while" => &[("do", true)],
+      _ => &[],
+    };
+    for (key, is_list) in nested {
+      let Some((_, child)) = get(param_entries, key) else {
+        continue;
+      };
+      let child_path = format!("{}.parameters.{}", path, key);
+      if *is_list {
+        self.nodes(child, &params_value[*key], &child_path, true);
+      } else if matches!(child.kind, Kind::Mapping(_)) {
+        self.node(child, &params_value[*key], &child_path, false);
+      }
+    }
+  }
+}
+
+fn get<'a>(entries: &'a [(Located, Located)], key: &str) -> Option<(&'a Located, &'a Located)> {
+  entries
+    .iter()
+    .find(|(name, _)| name.scalar() == Some(key))
+    .map(|(name, value)| (name, value))
+}
+
+fn describe_value(value: &Value) -> &'static str {
+  match value {
+    Value::Null => "null",
+    Value::Bool(_) => "a boolean",
+    Value::Number(_) => "a number",
+    Value::String(_) => "a string",
+    Value::Sequence(_) => "a sequence/list",
+    Value::Mapping(_) => "an object/map",
+    Value::Tagged(_) => "a tagged value",
+  }
+}
+
+/// The candidate nearest to `name`, if it is close enough to be a typo.
+pub(crate) fn closest(name: &str, candidates: &[&str]) -> Option<String> {
+  let lower = name.to_ascii_lowercase();
+  candidates
+    .iter()
+    .map(|candidate| (edit_distance(&lower, candidate), *candidate))
+    .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(1))
+    .min()
+    .map(|(_, candidate)| candidate.to_string())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let above = row[j + 1];
+      row[j + 1] = if ca == *cb {
+        diagonal
+      } else {
+        1 + diagonal.min(above).min(row[j])
+      };
+      diagonal = above;
+    }
+  }
+  row[b.len()]
+}
+
+/// A YAML node and where it starts.
+#[derive(Debug)]
+struct Located {
+  kind: Kind,
+  line: usize,
+  column: usize,
+}
+
+#[derive(Debug)]
+enum Kind {
+  Scalar(String),
+  Sequence(Vec<Located>),
+  Mapping(Vec<(Located, Located)>),
+  /// An alias; its target was checked where it was defined.
+  Alias,
+}
+
+impl Located {
+  fn scalar(&self) -> Option<&str> {
+    match &self.kind {
+      Kind::Scalar(value) => Some(value),
+      _ => None,
+    }
+  }
+}
+
+/// The first document of `yaml`, or `None` when yaml-rust2 rejects it.
+fn parse_located(yaml: &str) -> Option<Located> {
+  let mut builder = Builder::default();
+  Parser::new(yaml.chars()).load(&mut builder, false).ok()?;
+  builder.root
+}
+
+/// Assembles [`Located`] nodes from parser events.
+#[derive(Default)]
+struct Builder {
+  /// Open sequences and mappings; a mapping collects keys and values
+  /// alternately.
+  open: Vec<(Located, Vec<Located>)>,
+  root: Option<Located>,
+}
+
+impl Builder {
+  fn finish(&mut self, node: Located) {
+    match self.open.last_mut() {
+      Some((_, children)) => children.push(node),
+      None => {
+        if self.root.is_none() {
+          self.root = Some(node);
+        }
+      }
+    }
+  }
+}
+
+impl MarkedEventReceiver for Builder {
+  fn on_event(&mut self, event: Event, mark: Marker) {
+    let at = |kind| Located {
+      kind,
+      line: mark.line(),
+      column: mark.col() + 1,
+    };
+    match event {
+      Event::Scalar(value, ..) => self.finish(at(Kind::Scalar(value))),
+      Event::Alias(_) => self.finish(at(Kind::Alias)),
+      Event::SequenceStart(..) => self.open.push((at(Kind::Sequence(Vec::new())), Vec::new())),
+      Event::MappingStart(..) => self.open.push((at(Kind::Mapping(Vec::new())), Vec::new())),
+      Event::SequenceEnd | Event::MappingEnd => {
+        let Some((mut node, children)) = self.open.pop() else {
+          return;
+        };
+        node.kind = match node.kind {
+          Kind::Mapping(_) => {
+            // A block mapping's start event is marked at its first `:`;
+            // its first key is where a reader looks for it.
+            if let Some(first) = children.first() {
+              (node.line, node.column) = (first.line, first.column);
+            }
+            let mut entries = Vec::new();
+            let mut children = children.into_iter();
+            while let (Some(key), Some(value)) = (children.next(), children.next()) {
+              entries.push((key, value));
+            }
+            Kind::Mapping(entries)
+          }
+          _ => Kind::Sequence(children),
+        };
+        self.finish(node);
+      }
+      _ => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(yaml: &str) -> Vec<Diagnostic> {
+    check_workflow_yaml(yaml, WorkflowValidationOptions::default())
+  }
+
+  #[test]
+  fn unknown_type_is_located_with_a_suggestion() {
+    let diagnostics = check("name: t\nnodes:\n  - id: a\n    type: tempalte\n");
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!((diagnostic.line, diagnostic.column), (4, 11));
+    assert_eq!(diagnostic.suggestion.as_deref(), Some("template"));
+    assert_eq!(
+      diagnostic.render("flow.yml"),
+      "flow.yml:4:11: nodes[0].type: 'tempalte' is not supported by the CLI workflow factory; did you mean 'template'?"
+    );
+  }
+
+  #[test]
+  fn parameters_are_checked_in_nested_nodes_too() {
+    let yaml = "\
+name: t
+nodes:
+  - id: each
+    type: map
+    parameters:
+      template:
+        - id: ask
+          type: llm
+          parameters:
+            prompt: hi
+            temprature: 0.2
+            max_tokens: lots
+";
+    let diagnostics = check(yaml);
+    let rendered: Vec<String> = diagnostics.iter().map(|d| d.render("f")).collect();
+    assert_eq!(
+      rendered,
+      [
+        "f:11:13: nodes[0].parameters.template[0].parameters.temprature: is not a parameter of node type 'llm'; did you mean 'temperature'?",
+        "f:12:25: nodes[0].parameters.template[0].parameters.max_tokens: must be an integer, found a string",
+      ]
+    );
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].key, "temprature");
+  }
+
+  #[test]
+  fn missing_fields_and_parse_errors_are_errors() {
+    let diagnostics = check("nodes:\n  - type: llm\n    parameters:\n      model: x\n");
+    let messages: Vec<String> = diagnostics.iter().map(|d| d.render("f")).collect();
+    assert_eq!(
+      messages,
+      [
+        "f:1:1: <document>: workflow is missing the required field 'name'",
+        "f:2:5: nodes[0]: node is missing the required field 'id'",
+        "f:3:5: nodes[0].parameters: node '?' requires 'prompt' as a parameter or input_mapping",
+      ]
+    );
+
+    let diagnostics = check("name: [unclosed\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.starts_with("invalid YAML: "));
+    assert_eq!(diagnostics[0].line, 2);
+  }
+
+  #[test]
+  fn typos_need_to_be_close_to_get_a_suggestion() {
+    assert_eq!(closest("htpp", &["http", "file"]).as_deref(), Some("http"));
+    assert_eq!(closest("LLM", &["llm"]).as_deref(), Some("llm"));
+    assert_eq!(closest("frobnicate", &["http", "file"]), None);
+  }
+}
@@ -1,2 +1,5 @@
+pub mod io;
+pub mod located;
+pub mod registry;
 pub mod schema;
 pub mod v2;
@@ -0,0 +1,517 @@
+//! Node type introspection for the config-first workflow factory.
+//!
+//! [`NodeRegistry`] enumerates the node types that
+//! `executor::factory` can build in this binary (feature-gated types
+//! only appear when their feature is compiled in), exports their parameter
+//! tables as JSON Schema for editors and `workflow debug`, and validates a
+//! single node's configuration through the same checks as
+//! [`validate_flow_definition`](super::schema::validate_flow_definition).
+//!
+//! The parameter tables themselves live in `schema.rs`; this module only
+//! projects them, so the schema bundle and `workflow validate` can't drift.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use super::schema::{
+  ParamSpec, ParamType, WorkflowValidationOptions, WorkflowValidationReport, specs_for_node_type,
+  validate_node_schema,
+};
+use super::v2::NodeDefinitionV2;
+
+/// Every node type the factory knows, with the cargo feature gating it.
+/// Keep in sync with `specs_for_node_type` and `executor::factory`.
+const NODE_TYPES: &[(&str, Option<&str>)] = &[
+  ("llm", None),
+  ("skill_agent", None),
+  ("agent", None),
+  ("multi_agent", None),
+  ("template", None),
+  ("file", None),
+  ("http", None),
+  ("command", None),
+  ("shell", None),
+  ("json_transform", None),
+  ("text_extract", None),
+  ("conditional", None),
+  ("vector_search", None),
+  ("document_render", None),
+  ("approval", None),
+  ("delay", None),
+  ("schedule_gate", None),
+  ("throttle", None),
+  ("tabular_read", None),
+  ("tabular_write", None),
+  ("webhook_wait", None),
+  ("poll_until", None),
+  ("feed", None),
+  ("web_extract", None),
+  ("arxiv", None),
+  ("markmap", None),
+  ("asr", None),
+  ("tts", None),
+  ("text_to_image", None),
+  ("image_edit", None),
+  ("image_to_image", None),
+  ("image_understand", None),
+  ("text_splitter", None),
+  ("batch", None),
+  ("map", None),
+  ("while", None),
+  ("mcp", Some("mcp")),
+  ("mcp_tool", Some("mcp")),
+  ("plugin", Some("plugin")),
+  ("sql", Some("sql")),
+  ("object_store", Some("s3")),
+  ("git", Some("git")),
+  ("email", Some("email")),
+  ("embedding", Some("rag")),
+  ("rag", Some("rag")),
+];
+
+/// Documented output keys per node type. Types whose output keys depend on
+/// configuration (`template`'s `output_key`, `map`'s child) are left open.
+fn output_keys(node_type: &str) -> &'static [&'static str] {
+  match node_type {
+    "llm" => &["output", "model", "usage", "response_json"],
+    "http" => &["status", "headers", "body", "body_text", "json", "url"],
+    "file" => &[
+      "content", "path", "exists", "is_dir", "count", "entries", "deleted",
+    ],
+    "command" | "shell" => &["stdout", "stderr", "exit_code", "success", "truncated"],
+    "conditional" => &["condition_result", "branch", "selected_value", "output"],
+    "text_extract" => &["extracted", "match_count", "text"],
+    "vector_search" => &["matches", "ids", "scores"],
+    "document_render" => &["markdown", "html", "pdf", "paths", "warnings"],
+    "approval" => &["approved", "feedback", "content", "decided_by"],
+    "delay" => &["delayed_ms"],
+    "schedule_gate" => &["open", "waited_ms", "next_open"],
+    "throttle" => &["waited_ms", "group"],
+    "tabular_read" => &["rows", "columns", "row_count", "skipped_rows", "truncated"],
+    "tabular_write" => &["path", "content", "row_count", "columns"],
+    "webhook_wait" => &["url", "method", "headers", "query", "body", "json"],
+    "poll_until" => &["json", "matched", "status", "attempts", "elapsed_ms", "url"],
+    "feed" => &["entries", "entry_count", "skipped", "feed"],
+    "web_extract" => &["url", "title", "text", "excerpt", "word_count", "metadata"],
+    "sql" => &["rows", "row_count", "columns", "truncated", "affected_rows"],
+    "email" => &[
+      "message_id",
+      "recipients",
+      "accepted_recipients",
+      "dry_run",
+      "eml_path",
+    ],
+    "git" => &[
+      "path",
+      "head",
+      "ref",
+      "files",
+      "skipped",
+      "count",
+      "truncated",
+      "total_bytes",
+      "commits",
+    ],
+    "object_store" => &[
+      "url",
+      "key",
+      "etag",
+      "size",
+      "multipart",
+      "parts",
+      "content",
+      "content_encoding",
+      "content_type",
+      "file",
+      "objects",
+      "keys",
+      "count",
+      "prefix",
+    ],
+    "markmap" => &[
+      "html",
+      "markdown",
+      "svg",
+      "data",
+      "title",
+      "truncated_nodes",
+    ],
+    "text_to_image" | "image_edit" => &["image_paths", "seeds", "finish_reasons", "warnings"],
+    "image_understand" => &["response", "usage"],
+    "text_splitter" => &["chunks", "chunk_metadata"],
+    "embedding" => &["vectors", "records", "dimension", "model", "ids", "texts"],
+    "batch" => &["results", "failed_items"],
+    _ => &[],
+  }
+}
+
+/// One entry of [`NodeRegistry::list`].
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeTypeInfo {
+  pub name: &'static str,
+  /// JSON Schema for the node's `parameters` mapping.
+  pub input_schema: Value,
+  /// JSON Schema for the node's outputs (documented keys, open-ended).
+  pub output_schema: Value,
+  /// Cargo feature the type needs, if any.
+  pub feature: Option<&'static str>,
+}
+
+/// The configuration of a single node, as it appears under `nodes[]`.
+#[derive(Debug, Clone, Default)]
+pub struct NodeConfig {
+  pub parameters: HashMap<String, serde_yaml::Value>,
+  pub input_mapping: HashMap<String, String>,
+}
+
+impl NodeConfig {
+  pub fn new(parameters: HashMap<String, serde_yaml::Value>) -> Self {
+    Self {
+      parameters,
+      input_mapping: HashMap::new(),
+    }
+  }
+
+  pub fn with_input_mapping(mut self, input_mapping: HashMap<String, String>) -> Self {
+    self.input_mapping = input_mapping;
+    self
+  }
+}
+
+/// Introspection over the node types the workflow factory can build.
+pub struct NodeRegistry;
+
+impl NodeRegistry {
+  /// Node types enabled in this build, in a stable order.
+  pub fn list() -> Vec<NodeTypeInfo> {
+    NODE_TYPES
+      .iter()
+      .filter_map(|(name, feature)| {
+        let specs = specs_for_node_type(name)?;
+        Some(NodeTypeInfo {
+          name,
+          input_schema: parameters_schema(name, &specs),
+          output_schema: outputs_schema(name),
+          feature: *feature,
+        })
+      })
+      .collect()
+  }
+
+  /// Whether `node_type` can be built by this binary.
+  pub fn contains(node_type: &str) -> bool {
+    specs_for_node_type(node_type).is_some()
+  }
+
+  /// A JSON Schema document for workflow files: `$defs` holds one node
+  /// definition per enabled type, and `nodes[]` must match one of them.
+  pub fn schema_bundle() -> Value {
+    let mut defs = Map::new();
+    let mut variants = Vec::new();
+    for info in Self::list() {
+      defs.insert(
+        info.name.to_string(),
+        json!({
+          "type": "object",
+          "properties": {
+            "id": { "type": "string", "minLength": 1 },
+            "type": { "const": info.name },
+            "dependencies": { "type": "array", "items": { "type": "string" } },
+            "input_mapping": {
+              "type": "object",
+              "additionalProperties": { "type": "string" },
+            },
+            "run_if": { "type": "string" },
+            "env": { "type": "object" },
+            "cwd": { "type": "string" },
+            "parameters": info.input_schema,
+          },
+          "required": ["id", "type"],
+          "x-agentflow-outputs": info.output_schema,
+        }),
+      );
+      variants.push(json!({ "$ref": format!("#/$defs/{}", info.name) }));
+    }
+
+    json!({
+      "$schema": "https://json-schema.org/draft/2020-12/schema",
+      "title": "AgentFlow workflow",
+      "type": "object",
+      "properties": {
+        "name": { "type": "string" },
+        "inputs": { "type": "object" },
+        "nodes": {
+          "type": "array",
+          "minItems": 1,
+          "items": { "oneOf": variants },
+        },
+      },
+      "required": ["name", "nodes"],
+      "$defs": defs,
+    })
+  }
+
+  /// Validates one node's configuration with the same checks
+  /// `workflow validate` runs, including the per-type validators.
+  pub fn validate_node_config(node_type: &str, config: &NodeConfig) -> WorkflowValidationReport {
+    let node = NodeDefinitionV2 {
+      id: node_type.to_string(),
+      node_type: node_type.to_string(),
+      dependencies: Vec::new(),
+      input_mapping: config.input_mapping.clone(),
+      run_if: None,
+      parameters: config.parameters.clone(),
+      env: HashMap::new(),
+      cwd: None,
+    };
+    let mut report = WorkflowValidationReport::default();
+    validate_node_schema(
+      &node,
+      "node",
+      WorkflowValidationOptions::default(),
+      &mut report,
+    );
+    report
+  }
+}
+
+fn parameters_schema(node_type: &str, specs: &[ParamSpec]) -> Value {
+  let properties: Map<_, _> = specs
+    .iter()
+    .map(|spec| (spec.name.to_string(), param_type_schema(spec.kind)))
+    .collect();
+  // Parameters that may come from input_mapping instead aren't required
+  // in the mapping itself.
+  let required: Vec<_> = specs
+    .iter()
+    .filter(|spec| spec.required && !spec.input_allowed)
+    .map(|spec| spec.name)
+    .collect();
+  json!({
+    "type": "object",
+    "properties": properties,
+    "required": required,
+    // Unknown keys are warnings by default (and template context for
+    // `template`), so the schema stays open.
+    "additionalProperties": true,
+    "description": format!("Parameters of a `{}` node", node_type),
+  })
+}
+
+fn outputs_schema(node_type: &str) -> Value {
+  let properties: Map<_, _> = output_keys(node_type)
+    .iter()
+    .map(|key| (key.to_string(), json!({})))
+    .collect();
+  json!({
+    "type": "object",
+    "properties": properties,
+    "additionalProperties": true,
+  })
+}
+
+fn param_type_schema(kind: ParamType) -> Value {
+  match kind {
+    ParamType::Any => json!({}),
+    ParamType::String => json!({ "type": "string" }),
+    ParamType::Number => json!({ "type": "number" }),
+    ParamType::Integer => json!({ "type": "integer" }),
+    ParamType::Bool => json!({ "type": "boolean" }),
+    ParamType::Object => json!({ "type": "object" }),
+    ParamType::Sequence => json!({ "type": "array" }),
+    ParamType::SequenceOfStrings => json!({ "type": "array", "items": { "type": "string" } }),
+    ParamType::Secret => json!({
+      "oneOf": [
+        { "type": "string" },
+        {
+          "type": "object",
+          "properties": { "$secret": { "type": "string" } },
+          "required": ["$secret"],
+          "additionalProperties": false,
+        },
+        {
+          "type": "object",
+          "properties": { "$secret_file": { "type": "string" } },
+          "required": ["$secret_file"],
+          "additionalProperties": false,
+        },
+      ],
+    }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn params(yaml: &str) -> HashMap<String, serde_yaml::Value> {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  #[test]
+  fn list_covers_every_enabled_factory_type() {
+    let infos = NodeRegistry::list();
+    let names: Vec<_> = infos.iter().map(|info| info.name).collect();
+    for name in ["llm", "http", "file", "template", "approval", "while"] {
+      assert!(names.contains(&name), "{name} missing from {names:?}");
+    }
+    assert_eq!(names.contains(&"sql"), cfg!(feature = "sql"));
+    assert_eq!(names.contains(&"rag"), cfg!(feature = "rag"));
+
+    let llm = infos.iter().find(|info| info.name == "llm").unwrap();
+    assert_eq!(
+      llm.input_schema["properties"]["temperature"]["type"],
+      "number"
+    );
+    assert!(llm.output_schema["properties"].get("output").is_some());
+  }
+
+  #[test]
+  fn schema_bundle_defines_core_node_types() {
+    let bundle = NodeRegistry::schema_bundle();
+    let defs = bundle["$defs"].as_object().unwrap();
+    for name in ["llm", "http", "file"] {
+      assert_eq!(defs[name]["properties"]["type"]["const"], name);
+    }
+    assert_eq!(
+      defs["template"]["properties"]["parameters"]["required"],
+      json!(["template"])
+    );
+    // `url` may come from input_mapping, so it isn't schema-required.
+    assert_eq!(
+      defs["http"]["properties"]["parameters"]["required"],
+      json!([])
+    );
+    assert_eq!(
+      bundle["properties"]["nodes"]["items"]["oneOf"]
+        .as_array()
+        .unwrap()
+        .len(),
+      defs.len()
+    );
+  }
+
+  /// `(type, cfg feature)` for every arm of `create_graph_node`, read from
+  /// the factory source so a new arm can't be missed here.
+  fn factory_arms() -> Vec<(String, Option<String>)> {
+    let source = include_str!("../executor/factory.rs");
+    let start = source.find("pub fn create_graph_node").unwrap();
+    let end = start + source[start..].find("Unknown node type").unwrap();
+    let mut arms = Vec::new();
+    let mut feature = None;
+    for line in source[start..end].lines() {
+      if let Some(gate) = line.strip_prefix("    #[cfg(feature = \"") {
+        feature = gate.split('"').next().map(str::to_string);
+      } else if let Some(arm) = line.strip_prefix("    \"") {
+        let (names, _) = arm.split_once(" => ").unwrap();
+        for name in names.split(" | ") {
+          arms.push((name.trim_matches('"').to_string(), feature.clone()));
+        }
+        feature = None;
+      }
+    }
+    arms
+  }
+
+  #[test]
+  fn node_types_match_the_factory_arms() {
+    let mut arms = factory_arms();
+    arms.sort();
+    let mut listed: Vec<_> = NODE_TYPES
+      .iter()
+      .map(|(name, feature)| (name.to_string(), feature.map(str::to_string)))
+      .collect();
+    listed.sort();
+    assert_eq!(listed, arms);
+
+    for info in NodeRegistry::list() {
+      let node = NodeDefinitionV2 {
+        id: "probe".to_string(),
+        node_type: info.name.to_string(),
+        dependencies: Vec::new(),
+        input_mapping: HashMap::new(),
+        run_if: None,
+        parameters: HashMap::new(),
+        env: HashMap::new(),
+        cwd: None,
+      };
+      if let Err(err) = crate::executor::factory::create_graph_node(&node) {
+        assert!(
+          !err.to_string().contains("Unknown node type"),
+          "{}: {err:#}",
+          info.name
+        );
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn documented_output_keys_cover_what_nodes_emit() {
+    for (node_type, parameters) in [
+      ("conditional", "condition: \"1 < 2\""),
+      (
+        "text_extract",
+        "text: 'see {\"a\": 1}'\noperations: [extract_json]",
+      ),
+      (
+        "document_render",
+        "template: \"# Title\"\nformats: [markdown, html]",
+      ),
+    ] {
+      let node = NodeDefinitionV2 {
+        id: "probe".to_string(),
+        node_type: node_type.to_string(),
+        dependencies: Vec::new(),
+        input_mapping: HashMap::new(),
+        run_if: None,
+        parameters: params(parameters),
+        env: HashMap::new(),
+        cwd: None,
+      };
+      let graph_node = crate::executor::factory::create_graph_node(&node).unwrap();
+      let agentflow_core::flow::NodeType::Standard(runnable) = graph_node.node_type else {
+        panic!("{node_type} should be a standard node");
+      };
+      let outputs = runnable.execute(&graph_node.initial_inputs).await.unwrap();
+      let documented = output_keys(node_type);
+      for key in outputs.keys() {
+        assert!(
+          documented.contains(&key.as_str()),
+          "{node_type} emits undocumented '{key}'"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn validate_node_config_dispatches_per_type_checks() {
+    let missing_url =
+      NodeRegistry::validate_node_config("http", &NodeConfig::new(params("method: POST")));
+    assert!(!missing_url.is_valid());
+    assert!(
+      missing_url.issues[0].contains("requires 'url'"),
+      "{:?}",
+      missing_url.issues
+    );
+
+    let mapped_url = NodeRegistry::validate_node_config(
+      "http",
+      &NodeConfig::new(params("method: GET")).with_input_mapping(HashMap::from([(
+        "url".to_string(),
+        "{{ nodes.fetch.outputs.url }}".to_string(),
+      )])),
+    );
+    assert!(mapped_url.is_valid(), "{:?}", mapped_url.issues);
+
+    let bad_policy = NodeRegistry::validate_node_config(
+      "approval",
+      &NodeConfig::new(params("sentinel_path: /tmp/ok\non_timeout: ignore")),
+    );
+    assert!(bad_policy.issues[0].contains("on_timeout is invalid"));
+
+    let unknown = NodeRegistry::validate_node_config("teleport", &NodeConfig::default());
+    assert!(unknown.issues[0].contains("'teleport' is not supported"));
+  }
+}
@@ -1,5 +1,6 @@
 use std::collections::{BTreeSet, HashSet};
 
+use crate::config::io::{OutputRef, RESERVED_OUTPUT_NAMES, coerce_input};
 use crate::config::v2::{FlowDefinitionV2, NodeDefinitionV2};
 use agentflow_core::expr;
 use serde::Serialize;
@@ -17,7 +18,7 @@ impl WorkflowValidationReport {
 }
 
 #[derive(Debug, Clone, Copy)]
-enum ParamType {
+pub(crate) enum ParamType {
   Any,
   String,
   Number,
@@ -26,14 +27,16 @@ enum ParamType {
   Object,
   Sequence,
   SequenceOfStrings,
+  /// A string, or a `{$secret: ENV}` / `{$secret_file: path}` reference.
+  Secret,
 }
 
 #[derive(Debug, Clone, Copy)]
-struct ParamSpec {
-  name: &'static str,
-  kind: ParamType,
-  required: bool,
-  input_allowed: bool,
+pub(crate) struct ParamSpec {
+  pub(crate) name: &'static str,
+  pub(crate) kind: ParamType,
+  pub(crate) required: bool,
+  pub(crate) input_allowed: bool,
 }
 
 impl ParamSpec {
@@ -75,6 +78,10 @@ pub enum UnknownParameterMode {
   #[default]
   Warning,
   Error,
+  /// Not reported: the caller already ran
+  /// [`check_workflow_yaml`](super::located::check_workflow_yaml), which
+  /// reports them with their line and a suggestion.
+  Ignore,
 }
 
 pub fn validate_flow_definition(flow_def: &FlowDefinitionV2) -> WorkflowValidationReport {
@@ -135,10 +142,112 @@ pub fn validate_flow_definition_with_options(
     }
   }
 
+  validate_workflow_io(flow_def, &valid_ids, &mut report);
   report
 }
 
-fn validate_node_schema(
+/// The `inputs:` defaults must have their declared type, and every
+/// `outputs:` entry must read an output of a node of the workflow.
+fn validate_workflow_io(
+  flow_def: &FlowDefinitionV2,
+  valid_ids: &HashSet<&str>,
+  report: &mut WorkflowValidationReport,
+) {
+  let mut inputs: Vec<_> = flow_def.inputs.iter().collect();
+  inputs.sort_by_key(|(name, _)| name.as_str());
+  for (name, input) in inputs {
+    let Some(default) = &input.default else {
+      continue;
+    };
+    let default = serde_json::to_value(default).unwrap_or_default();
+    if let Err(expected) = coerce_input(&default, input.input_type) {
+      report.issues.push(format!(
+        "inputs.{}.default must be {}, got {}",
+        name, expected, default
+      ));
+    }
+  }
+
+  let mut outputs: Vec<_> = flow_def.outputs.iter().collect();
+  outputs.sort_by_key(|(name, _)| name.as_str());
+  for (name, reference) in outputs {
+    if RESERVED_OUTPUT_NAMES.contains(&name.as_str()) {
+      report.issues.push(format!(
+        "outputs.{} is reserved for the run report; rename the output",
+        name
+      ));
+    }
+    match OutputRef::parse(reference) {
+      Some(output) if !valid_ids.contains(output.node.as_str()) => report.issues.push(format!(
+        "outputs.{} references unknown node '{}'",
+        name, output.node
+      )),
+      Some(_) => {}
+      None => report.issues.push(format!(
+        "outputs.{} must reference a node output as 'nodes.<id>.outputs.<key>', got '{}'",
+        name, reference
+      )),
+    }
+  }
+}
+
+/// Node types that spawn a process and apply a node's `env:` and `cwd:`.
+pub(crate) const PROCESS_ENV_NODE_TYPES: &[&str] = &["command", "mcp", "mcp_tool"];
+
+/// Why `node` cannot take the `env:` / `cwd:` it sets, if it cannot: only
+/// [`PROCESS_ENV_NODE_TYPES`] spawn a process, and `git` takes `cwd:` alone.
+pub(crate) fn unsupported_process_env(node: &NodeDefinitionV2) -> Option<String> {
+  let node_type = node.node_type.as_str();
+  if PROCESS_ENV_NODE_TYPES.contains(&node_type) {
+    return None;
+  }
+  let field = if !node.env.is_empty() {
+    "env"
+  } else if node.cwd.is_some() && node_type != "git" {
+    "cwd"
+  } else {
+    return None;
+  };
+  Some(format!(
+    "'{}' applies only to {} nodes{}, not '{}'",
+    field,
+    PROCESS_ENV_NODE_TYPES.join(", "),
+    if field == "cwd" { " and git" } else { "" },
+    node_type
+  ))
+}
+
+fn validate_process_env(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  if let Some(problem) = unsupported_process_env(node) {
+    report
+      .issues
+      .push(format!("{}.{}: {}", path, node.id, problem));
+  }
+  let mut names: Vec<_> = node.env.iter().collect();
+  names.sort_by_key(|(name, _)| name.as_str());
+  for (name, value) in names {
+    if !(param_type_matches(value, ParamType::Secret)
+      || matches!(
+        value,
+        serde_yaml::Value::Number(_) | serde_yaml::Value::Bool(_)
+      ))
+    {
+      report.issues.push(format!(
+        "{}.{}.env.{} must be {}",
+        path,
+        node.id,
+        name,
+        describe_param_type(ParamType::Secret)
+      ));
+    }
+  }
+}
+
+pub(crate) fn validate_node_schema(
   node: &NodeDefinitionV2,
   path: &str,
   options: WorkflowValidationOptions,
@@ -183,7 +292,7 @@ fn validate_node_schema(
 
   // F-A6-6: the `template` node is designed to consume arbitrary
   // Tera context — any key in `parameters` that isn't `template` /
-  // `output_key` / `output_format` is intentional input for the
+  // `output_key` / `output_format` / `strict` is intentional input for the
   // rendered template. Warning on those is friction for cross-product
   // builders / list constructors / any list-of-N template pattern.
   // Skip the unknown-key check for templates; the other ParamSpec
@@ -198,6 +307,7 @@ fn validate_node_schema(
         match options.unknown_parameters {
           UnknownParameterMode::Warning => report.warnings.push(message),
           UnknownParameterMode::Error => report.issues.push(message),
+          UnknownParameterMode::Ignore => {}
         }
       }
     }
@@ -210,6 +320,7 @@ fn validate_node_schema(
       .issues
       .push(format!("{}.{}.run_if is invalid: {}", path, node.id, err));
   }
+  validate_process_env(node, path, report);
 
   if node.node_type == "while"
     && let Some(condition) = node
@@ -226,7 +337,24 @@ fn validate_node_schema(
 
   match node.node_type.as_str() {
     "map" => validate_nested_nodes(node, path, "template", options, report),
+    "batch" => {
+      validate_nested_nodes(node, path, "template", options, report);
+      validate_batch_child(node, path, options, report);
+    }
     "while" => validate_nested_nodes(node, path, "do", options, report),
+    "json_transform" => validate_json_transform(node, path, report),
+    "text_extract" => validate_text_extract(node, path, report),
+    "conditional" => validate_conditional(node, path, report),
+    "document_render" => validate_document_render(node, path, report),
+    "approval" => validate_approval(node, path, report),
+    "schedule_gate" => validate_schedule_gate(node, path, report),
+    "tabular_read" => validate_tabular_read(node, path, report),
+    #[cfg(feature = "s3")]
+    "object_store" => validate_object_store(node, path, report),
+    #[cfg(feature = "git")]
+    "git" => validate_git(node, path, report),
+    #[cfg(feature = "email")]
+    "email" => validate_email(node, path, report),
     _ => {}
   }
 
@@ -299,7 +427,7 @@ fn validate_plugin_node_type(
   }
 }
 
-fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
+pub(crate) fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
   match node_type {
     "llm" => Some(vec![
       ParamSpec::required_input("prompt", ParamType::String),
@@ -307,6 +435,11 @@ fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
       ParamSpec::optional("system", ParamType::String),
       ParamSpec::optional("temperature", ParamType::Number),
       ParamSpec::optional("max_tokens", ParamType::Integer),
+      ParamSpec::optional("streaming", ParamType::Bool),
+      ParamSpec::optional("images", ParamType::SequenceOfStrings),
+      ParamSpec::optional("json_mode", ParamType::Bool),
+      ParamSpec::optional("response_schema", ParamType::Object),
+      ParamSpec::optional("api_key", ParamType::Secret),
     ]),
     "skill_agent" | "agent" => Some(vec![
       ParamSpec::required_input("skill", ParamType::String),
@@ -331,26 +464,131 @@ fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
       ParamSpec::optional("rounds", ParamType::Integer),
       ParamSpec::optional("judge_prompt", ParamType::String),
     ]),
+    "document_render" => Some(vec![
+      ParamSpec::required("template", ParamType::String),
+      ParamSpec::optional("formats", ParamType::SequenceOfStrings),
+      ParamSpec::optional("output_dir", ParamType::String),
+      ParamSpec::optional("file_stem", ParamType::String),
+      ParamSpec::optional("title", ParamType::String),
+      ParamSpec::optional("table_of_contents", ParamType::Bool),
+      ParamSpec::optional("embed_images", ParamType::Bool),
+      ParamSpec::optional("base_dir", ParamType::String),
+      ParamSpec::optional("stylesheet", ParamType::String),
+      ParamSpec::optional("pdf_command", ParamType::SequenceOfStrings),
+      ParamSpec::optional("pdf_timeout_ms", ParamType::Integer),
+    ]),
+    "approval" => Some(vec![
+      ParamSpec::optional("channel", ParamType::String),
+      ParamSpec::optional("sentinel_path", ParamType::String),
+      ParamSpec::optional("host", ParamType::String),
+      ParamSpec::optional("port", ParamType::Integer),
+      ParamSpec::optional("path", ParamType::String),
+      ParamSpec::optional("prompt", ParamType::String),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("on_timeout", ParamType::String),
+      ParamSpec::optional("poll_interval_ms", ParamType::Integer),
+    ]),
+    "delay" => Some(vec![
+      ParamSpec::required("duration_ms", ParamType::Integer),
+      ParamSpec::optional("jitter_ms", ParamType::Integer),
+    ]),
+    "schedule_gate" => Some(vec![
+      ParamSpec::required("windows", ParamType::SequenceOfStrings),
+      ParamSpec::optional("policy", ParamType::String),
+      ParamSpec::optional("max_wait_ms", ParamType::Integer),
+      ParamSpec::optional("utc_offset", ParamType::String),
+    ]),
+    "throttle" => Some(vec![
+      ParamSpec::required("group", ParamType::String),
+      ParamSpec::required("min_interval_ms", ParamType::Integer),
+      ParamSpec::optional("state_dir", ParamType::String),
+    ]),
+    "tabular_read" => Some(vec![
+      ParamSpec::optional("path", ParamType::String),
+      ParamSpec::optional("content", ParamType::String),
+      ParamSpec::optional("format", ParamType::String),
+      ParamSpec::optional("delimiter", ParamType::String),
+      ParamSpec::optional("has_headers", ParamType::Bool),
+      ParamSpec::optional("column_types", ParamType::Object),
+      ParamSpec::optional("max_rows", ParamType::Integer),
+      ParamSpec::optional("filter", ParamType::String),
+      ParamSpec::optional("sheet", ParamType::String),
+      ParamSpec::optional("strict", ParamType::Bool),
+    ]),
+    "tabular_write" => Some(vec![
+      ParamSpec::required_input("rows", ParamType::Sequence),
+      ParamSpec::optional("path", ParamType::String),
+      ParamSpec::optional("delimiter", ParamType::String),
+      ParamSpec::optional("has_headers", ParamType::Bool),
+      ParamSpec::optional("columns", ParamType::SequenceOfStrings),
+    ]),
+    "webhook_wait" => Some(vec![
+      ParamSpec::optional("host", ParamType::String),
+      ParamSpec::optional("port", ParamType::Integer),
+      ParamSpec::optional("path", ParamType::String),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+    ]),
+    "poll_until" => Some(vec![
+      ParamSpec::required_input("url", ParamType::String),
+      ParamSpec::required("until", ParamType::String),
+      ParamSpec::optional("equals", ParamType::Any),
+      ParamSpec::optional("headers", ParamType::Object),
+      ParamSpec::optional("interval_ms", ParamType::Integer),
+      ParamSpec::optional("backoff", ParamType::Number),
+      ParamSpec::optional("max_interval_ms", ParamType::Integer),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("max_attempts", ParamType::Integer),
+    ]),
+    "feed" => Some(vec![
+      ParamSpec::required_input("url", ParamType::String),
+      ParamSpec::optional("headers", ParamType::Object),
+      ParamSpec::optional("since", ParamType::String),
+      ParamSpec::optional("state_file", ParamType::String),
+      ParamSpec::optional("max_entries", ParamType::Integer),
+    ]),
+    "web_extract" => Some(vec![
+      ParamSpec::required_input("url", ParamType::String),
+      ParamSpec::optional("headers", ParamType::Object),
+      ParamSpec::optional("max_bytes", ParamType::Integer),
+      ParamSpec::optional("allowed_content_types", ParamType::SequenceOfStrings),
+    ]),
     "http" => Some(vec![
       ParamSpec::required_input("url", ParamType::String),
       ParamSpec::optional("method", ParamType::String),
       ParamSpec::optional("headers", ParamType::Object),
+      ParamSpec::optional("query", ParamType::Object),
       ParamSpec::optional("body", ParamType::String),
+      ParamSpec::optional("json", ParamType::Any),
+      ParamSpec::optional("form", ParamType::Object),
+      ParamSpec::optional("auth", ParamType::Object),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("retries", ParamType::Integer),
+      ParamSpec::optional("retry_delay_ms", ParamType::Integer),
     ]),
     "file" => Some(vec![
       ParamSpec::required_input("operation", ParamType::String),
       ParamSpec::required_input("path", ParamType::String),
-      ParamSpec::optional("content", ParamType::String),
+      ParamSpec::optional("content", ParamType::Any),
+      ParamSpec::optional("pattern", ParamType::String),
+      ParamSpec::optional("parse", ParamType::String),
+      ParamSpec::optional("max_bytes", ParamType::Integer),
+      ParamSpec::optional("base_dir", ParamType::String),
+      ParamSpec::optional("recursive", ParamType::Bool),
     ]),
     "template" => Some(vec![
       ParamSpec::required("template", ParamType::String),
       ParamSpec::optional("output_key", ParamType::String),
       ParamSpec::optional("output_format", ParamType::String),
+      ParamSpec::optional("strict", ParamType::Bool),
     ]),
     "arxiv" => Some(vec![
       ParamSpec::required("url", ParamType::String),
       ParamSpec::optional("fetch_source", ParamType::Bool),
       ParamSpec::optional("simplify_latex", ParamType::Bool),
+      ParamSpec::optional("full_text", ParamType::Bool),
+      ParamSpec::optional("cache", ParamType::Bool),
+      ParamSpec::optional("cache_dir", ParamType::String),
+      ParamSpec::optional("latest_ttl_secs", ParamType::Integer),
     ]),
     "asr" => Some(vec![
       ParamSpec::required("model", ParamType::String),
@@ -360,6 +598,13 @@ fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
       ParamSpec::required("model", ParamType::String),
       ParamSpec::required_input("prompt", ParamType::String),
       ParamSpec::required_input("image_source", ParamType::String),
+      ParamSpec::optional("size", ParamType::String),
+      ParamSpec::optional("response_format", ParamType::String),
+      ParamSpec::optional("seed", ParamType::Integer),
+      ParamSpec::optional("steps", ParamType::Integer),
+      ParamSpec::optional("cfg_scale", ParamType::Number),
+      ParamSpec::optional("output_path", ParamType::String),
+      ParamSpec::optional("allow_filtered", ParamType::Bool),
     ]),
     "image_to_image" => Some(vec![
       ParamSpec::required("model", ParamType::String),
@@ -369,15 +614,51 @@ fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
     "image_understand" => Some(vec![
       ParamSpec::required("model", ParamType::String),
       ParamSpec::required_input("text_prompt", ParamType::String),
-      ParamSpec::required_input("image_source", ParamType::String),
+      // At most one of the two, or neither with `files`; the factory
+      // enforces it.
+      ParamSpec::optional("image_source", ParamType::String),
+      ParamSpec::optional("images", ParamType::SequenceOfStrings),
+      ParamSpec::optional("files", ParamType::SequenceOfStrings),
+      ParamSpec::optional("system_message", ParamType::String),
+      ParamSpec::optional("temperature", ParamType::Number),
+      ParamSpec::optional("max_tokens", ParamType::Integer),
+      ParamSpec::optional("max_image_bytes", ParamType::Integer),
+      ParamSpec::optional("base_dir", ParamType::String),
     ]),
     "markmap" => Some(vec![
       ParamSpec::optional("markdown", ParamType::String),
       ParamSpec::optional("save_to_file", ParamType::String),
+      ParamSpec::optional("title", ParamType::String),
+      ParamSpec::optional("theme", ParamType::String),
+      ParamSpec::optional("color_scheme", ParamType::SequenceOfStrings),
+      ParamSpec::optional("initial_expand_level", ParamType::Integer),
+      ParamSpec::optional("color_freeze_level", ParamType::Integer),
+      ParamSpec::optional("max_depth", ParamType::Integer),
+      ParamSpec::optional("max_width", ParamType::Integer),
+      ParamSpec::optional("assets_dir", ParamType::String),
+      ParamSpec::optional("svg", ParamType::Bool),
+    ]),
+    "text_splitter" => Some(vec![
+      ParamSpec::required_input("text", ParamType::String),
+      ParamSpec::optional("strategy", ParamType::String),
+      ParamSpec::optional("chunk_size", ParamType::Integer),
+      ParamSpec::optional("chunk_overlap", ParamType::Integer),
+      ParamSpec::optional("min_chunk_size", ParamType::Integer),
+      ParamSpec::optional("model", ParamType::String),
     ]),
     "text_to_image" => Some(vec![
       ParamSpec::required("model", ParamType::String),
       ParamSpec::required_input("prompt", ParamType::String),
+      ParamSpec::optional("negative_prompt", ParamType::String),
+      ParamSpec::optional("size", ParamType::String),
+      ParamSpec::optional("response_format", ParamType::String),
+      ParamSpec::optional("steps", ParamType::Integer),
+      ParamSpec::optional("cfg_scale", ParamType::Number),
+      ParamSpec::optional("n", ParamType::Integer),
+      ParamSpec::optional("seed", ParamType::Integer),
+      ParamSpec::optional("output_path", ParamType::String),
+      ParamSpec::optional("allow_filtered", ParamType::Bool),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
     ]),
     "tts" => Some(vec![
       ParamSpec::required("model", ParamType::String),
@@ -402,6 +683,63 @@ fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
       ParamSpec::required("allowed_commands", ParamType::Sequence),
       ParamSpec::optional("allowed_paths", ParamType::Sequence),
     ]),
+    "command" => Some(vec![
+      ParamSpec::required("program", ParamType::String),
+      ParamSpec::optional("args", ParamType::SequenceOfStrings),
+      ParamSpec::optional("allow_shell", ParamType::Bool),
+      ParamSpec::optional("stdin_key", ParamType::String),
+      ParamSpec::optional("working_dir", ParamType::String),
+      ParamSpec::optional("env_allowlist", ParamType::SequenceOfStrings),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("max_output_bytes", ParamType::Integer),
+      ParamSpec::optional("fail_on_error", ParamType::Bool),
+    ]),
+    "conditional" => Some(vec![
+      // Exactly one of `condition` / `cases`; `validate_conditional`
+      // reports a missing or doubled shape.
+      ParamSpec::optional("condition", ParamType::String),
+      ParamSpec::optional("true_value", ParamType::Any),
+      ParamSpec::optional("false_value", ParamType::Any),
+      ParamSpec::optional("cases", ParamType::Sequence),
+      ParamSpec::optional("default", ParamType::Object),
+    ]),
+    "json_transform" => Some(vec![
+      ParamSpec::required("operations", ParamType::Sequence),
+      ParamSpec::optional("input_key", ParamType::String),
+      ParamSpec::optional("output_key", ParamType::String),
+      ParamSpec::optional("on_missing", ParamType::String),
+    ]),
+    "text_extract" => Some(vec![
+      ParamSpec::required("operations", ParamType::Sequence),
+      ParamSpec::optional("text", ParamType::Any),
+      ParamSpec::optional("input_key", ParamType::String),
+      ParamSpec::optional("on_missing", ParamType::String),
+    ]),
+    "vector_search" => Some(vec![
+      ParamSpec::required_input("query", ParamType::Sequence),
+      // One of `corpus` / `corpus_path` / an existing `index_path`; the
+      // node reports which is missing at run time.
+      ParamSpec::optional("corpus", ParamType::Sequence),
+      ParamSpec::optional("corpus_path", ParamType::String),
+      ParamSpec::optional("index_path", ParamType::String),
+      ParamSpec::optional("query_key", ParamType::String),
+      ParamSpec::optional("corpus_key", ParamType::String),
+      ParamSpec::optional("top_k", ParamType::Integer),
+      ParamSpec::optional("min_score", ParamType::Number),
+    ]),
+    "batch" => Some(vec![
+      ParamSpec::optional("items_key", ParamType::String),
+      ParamSpec::optional("child", ParamType::Object),
+      ParamSpec::optional("template", ParamType::Sequence),
+      ParamSpec::optional("batch_size", ParamType::Integer),
+      ParamSpec::optional("max_concurrent", ParamType::Integer),
+      ParamSpec::optional("batch_delay_ms", ParamType::Integer),
+      ParamSpec::optional("max_retries", ParamType::Integer),
+      ParamSpec::optional("retry_delay_ms", ParamType::Integer),
+      ParamSpec::optional("aggregation", ParamType::String),
+      ParamSpec::optional("separator", ParamType::String),
+      ParamSpec::optional("result_key", ParamType::String),
+    ]),
     "map" => Some(vec![
       ParamSpec::required("template", ParamType::Sequence),
       ParamSpec::optional("parallel", ParamType::Bool),
@@ -422,10 +760,86 @@ fn specs_for_node_type(node_type: &str) -> Option<Vec<ParamSpec>> {
       ParamSpec::optional("timeout_ms", ParamType::Integer),
       ParamSpec::optional("max_retries", ParamType::Integer),
     ]),
+    "mcp_tool" if cfg!(feature = "mcp") => Some(vec![
+      ParamSpec::optional("server_command", ParamType::SequenceOfStrings),
+      ParamSpec::optional("env", ParamType::Object),
+      ParamSpec::optional("url", ParamType::String),
+      ParamSpec::required("tool_name", ParamType::String),
+      ParamSpec::optional("arguments", ParamType::Object),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("reuse_connection", ParamType::Bool),
+      ParamSpec::optional("output_dir", ParamType::String),
+    ]),
     "plugin" if cfg!(feature = "plugin") => Some(vec![
       ParamSpec::required("manifest", ParamType::String),
       ParamSpec::required("node_type", ParamType::String),
     ]),
+    "sql" if cfg!(feature = "sql") => Some(vec![
+      ParamSpec::required("connection", ParamType::Secret),
+      ParamSpec::required("sql", ParamType::String),
+      ParamSpec::optional("mode", ParamType::String),
+      ParamSpec::optional("params", ParamType::Object),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("max_rows", ParamType::Integer),
+    ]),
+    "email" if cfg!(feature = "email") => Some(vec![
+      ParamSpec::required("host", ParamType::String),
+      ParamSpec::required("from", ParamType::String),
+      ParamSpec::required("subject", ParamType::String),
+      ParamSpec::optional("to", ParamType::Any),
+      ParamSpec::optional("cc", ParamType::Any),
+      ParamSpec::optional("bcc", ParamType::Any),
+      ParamSpec::optional("text", ParamType::String),
+      ParamSpec::optional("html", ParamType::String),
+      ParamSpec::optional("attachments", ParamType::Any),
+      ParamSpec::optional("port", ParamType::Integer),
+      ParamSpec::optional("tls", ParamType::String),
+      ParamSpec::optional("username", ParamType::String),
+      ParamSpec::optional("password", ParamType::Secret),
+      ParamSpec::optional("timeout_ms", ParamType::Integer),
+      ParamSpec::optional("max_attachment_bytes", ParamType::Integer),
+      ParamSpec::optional("dry_run", ParamType::Bool),
+    ]),
+    "git" if cfg!(feature = "git") => Some(vec![
+      ParamSpec::required("operation", ParamType::String),
+      ParamSpec::optional("url", ParamType::String),
+      ParamSpec::optional("ref", ParamType::String),
+      ParamSpec::optional("target_dir", ParamType::String),
+      ParamSpec::optional("path", ParamType::String),
+      ParamSpec::optional("depth", ParamType::Integer),
+      ParamSpec::optional("patterns", ParamType::SequenceOfStrings),
+      ParamSpec::optional("max_file_bytes", ParamType::Integer),
+      ParamSpec::optional("max_total_bytes", ParamType::Integer),
+      ParamSpec::optional("max_commits", ParamType::Integer),
+      ParamSpec::optional("username", ParamType::String),
+      ParamSpec::optional("token", ParamType::Secret),
+    ]),
+    "object_store" if cfg!(feature = "s3") => Some(vec![
+      ParamSpec::required("operation", ParamType::String),
+      ParamSpec::required("bucket", ParamType::String),
+      ParamSpec::optional("key", ParamType::String),
+      ParamSpec::optional("endpoint", ParamType::String),
+      ParamSpec::optional("region", ParamType::String),
+      ParamSpec::optional("path_style", ParamType::Bool),
+      ParamSpec::optional("access_key_id", ParamType::Secret),
+      ParamSpec::optional("secret_access_key", ParamType::Secret),
+      ParamSpec::optional("session_token", ParamType::Secret),
+      ParamSpec::optional("file", ParamType::Any),
+      ParamSpec::optional("content", ParamType::Any),
+      ParamSpec::optional("output_path", ParamType::String),
+      ParamSpec::optional("multipart_threshold", ParamType::Integer),
+      ParamSpec::optional("part_size", ParamType::Integer),
+      ParamSpec::optional("content_type", ParamType::String),
+      ParamSpec::optional("content_encoding", ParamType::String),
+      ParamSpec::optional("max_keys", ParamType::Integer),
+    ]),
+    "embedding" if cfg!(feature = "rag") => Some(vec![
+      ParamSpec::required_input("texts", ParamType::Any),
+      ParamSpec::optional("ids", ParamType::Sequence),
+      ParamSpec::optional("model", ParamType::String),
+      ParamSpec::optional("texts_key", ParamType::String),
+      ParamSpec::optional("batch_size", ParamType::Integer),
+    ]),
     "rag" if cfg!(feature = "rag") => Some(vec![
       ParamSpec::required("operation", ParamType::String),
       ParamSpec::required("collection", ParamType::String),
@@ -452,7 +866,18 @@ fn validate_param_type(
   kind: ParamType,
   report: &mut WorkflowValidationReport,
 ) {
-  let valid = match kind {
+  if !param_type_matches(value, kind) {
+    report.issues.push(format!(
+      "{}.parameters.{} must be {}",
+      path,
+      name,
+      describe_param_type(kind)
+    ));
+  }
+}
+
+pub(crate) fn param_type_matches(value: &serde_yaml::Value, kind: ParamType) -> bool {
+  match kind {
     ParamType::Any => true,
     ParamType::String => value.as_str().is_some(),
     ParamType::Number => {
@@ -466,15 +891,11 @@ fn validate_param_type(
       serde_yaml::Value::Sequence(items) => items.iter().all(|item| item.as_str().is_some()),
       _ => false,
     },
-  };
-
-  if !valid {
-    report.issues.push(format!(
-      "{}.parameters.{} must be {}",
-      path,
-      name,
-      describe_param_type(kind)
-    ));
+    ParamType::Secret => {
+      value.as_str().is_some()
+        || serde_json::to_value(value)
+          .is_ok_and(|value| agentflow_core::secret::is_secret_reference(&value))
+    }
   }
 }
 
@@ -506,6 +927,349 @@ fn validate_nested_nodes(
   }
 }
 
+/// A batch `child` is a single node definition whose `id` may be omitted.
+fn validate_batch_child(
+  node: &NodeDefinitionV2,
+  path: &str,
+  options: WorkflowValidationOptions,
+  report: &mut WorkflowValidationReport,
+) {
+  let Some(serde_yaml::Value::Mapping(child)) = node.parameters.get("child") else {
+    return;
+  };
+  let mut child = child.clone();
+  if !child.contains_key("id") {
+    child.insert("id".into(), format!("{}_child", node.id).into());
+  }
+  match serde_yaml::from_value::<NodeDefinitionV2>(serde_yaml::Value::Mapping(child)) {
+    Ok(child) => validate_node_schema(
+      &child,
+      &format!("{}.parameters.child", path),
+      options,
+      report,
+    ),
+    Err(_) => report.issues.push(format!(
+      "{}.parameters.child must be a workflow node definition",
+      path
+    )),
+  }
+}
+
+/// Compile a `json_transform` pipeline so a malformed JSONPath or unknown
+/// operation is reported at validate time.
+fn validate_json_transform(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  let Ok(params) = serde_json::to_value(&node.parameters) else {
+    return;
+  };
+  if let Err(err) =
+    agentflow_nodes::nodes::json_transform::JsonTransformNode::from_params(&node.id, &params)
+  {
+    report.issues.push(format!(
+      "{}.{}.parameters is invalid: {}",
+      path, node.id, err
+    ));
+  }
+}
+
+/// Compile a `text_extract` pipeline so a malformed regex or unknown
+/// operation is reported at validate time.
+fn validate_text_extract(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  let Ok(params) = serde_json::to_value(&node.parameters) else {
+    return;
+  };
+  if let Err(err) =
+    agentflow_nodes::nodes::text_extract::TextExtractNode::from_params(&node.id, &params)
+  {
+    report.issues.push(format!(
+      "{}.{}.parameters is invalid: {}",
+      path, node.id, err
+    ));
+  }
+}
+
+/// Build a `conditional` node so its `condition` / `cases[].when`
+/// expressions are compiled at validate time.
+fn validate_conditional(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  let Ok(params) = serde_json::to_value(&node.parameters) else {
+    return;
+  };
+  if let Err(err) =
+    agentflow_nodes::nodes::conditional::ConditionalNode::from_params(&node.id, &params)
+  {
+    report.issues.push(format!(
+      "{}.{}.parameters is invalid: {}",
+      path, node.id, err
+    ));
+  }
+}
+
+fn validate_approval(node: &NodeDefinitionV2, path: &str, report: &mut WorkflowValidationReport) {
+  use agentflow_nodes::nodes::approval::TimeoutPolicy;
+  let param = |key: &str| node.parameters.get(key).and_then(serde_yaml::Value::as_str);
+  match param("channel").unwrap_or("file") {
+    "file" if !node.parameters.contains_key("sentinel_path") => report.issues.push(format!(
+      "{}.{}.parameters.sentinel_path is required for the file channel",
+      path, node.id
+    )),
+    "file" | "http" | "stdin" => {}
+    other => report.issues.push(format!(
+      "{}.{}.parameters.channel '{}' is invalid (expected file, http or stdin)",
+      path, node.id, other
+    )),
+  }
+  if let Some(Err(err)) = param("on_timeout").map(TimeoutPolicy::parse) {
+    report.issues.push(format!(
+      "{}.{}.parameters.on_timeout is invalid: {}",
+      path, node.id, err
+    ));
+  }
+}
+
+fn validate_schedule_gate(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  use agentflow_nodes::nodes::schedule_gate::{GatePolicy, ScheduleWindow, parse_utc_offset};
+  let param = |key: &str| node.parameters.get(key).and_then(serde_yaml::Value::as_str);
+  let windows = node
+    .parameters
+    .get("windows")
+    .and_then(serde_yaml::Value::as_sequence);
+  if windows.is_some_and(|windows| windows.is_empty()) {
+    report.issues.push(format!(
+      "{}.{}.parameters.windows must list at least one window",
+      path, node.id
+    ));
+  }
+  for window in windows
+    .into_iter()
+    .flatten()
+    .filter_map(serde_yaml::Value::as_str)
+  {
+    if let Err(err) = ScheduleWindow::parse(window) {
+      report.issues.push(format!(
+        "{}.{}.parameters.windows is invalid: {}",
+        path, node.id, err
+      ));
+    }
+  }
+  if let Some(policy) = param("policy")
+    && GatePolicy::parse(policy).is_none()
+  {
+    report.issues.push(format!(
+      "{}.{}.parameters.policy '{}' is invalid (expected wait, skip or error)",
+      path, node.id, policy
+    ));
+  }
+  if let Some(Err(err)) = param("utc_offset").map(parse_utc_offset) {
+    report
+      .issues
+      .push(format!("{}.{}.parameters.{}", path, node.id, err));
+  }
+}
+
+fn validate_tabular_read(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  use agentflow_core::expr;
+  use agentflow_nodes::nodes::tabular::{ColumnType, TabularFormat};
+  let param = |key: &str| node.parameters.get(key).and_then(serde_yaml::Value::as_str);
+  let has_source =
+    |key: &str| node.parameters.contains_key(key) || node.input_mapping.contains_key(key);
+  if !has_source("path") && !has_source("content") {
+    report.issues.push(format!(
+      "{}.{} requires 'path' or 'content' as a parameter or input_mapping",
+      path, node.id
+    ));
+  }
+  if let Some(Err(err)) = param("format").map(TabularFormat::parse) {
+    report.issues.push(format!(
+      "{}.{}.parameters.format is invalid: {}",
+      path, node.id, err
+    ));
+  }
+  if let Some(column_types) = node
+    .parameters
+    .get("column_types")
+    .and_then(serde_yaml::Value::as_mapping)
+  {
+    for (column, column_type) in column_types {
+      let column = column.as_str().unwrap_or_default();
+      let column_type = column_type.as_str().unwrap_or_default();
+      if let Err(err) = ColumnType::parse(column_type) {
+        report.issues.push(format!(
+          "{}.{}.parameters.column_types.{} is invalid: {}",
+          path, node.id, column, err
+        ));
+      }
+    }
+  }
+  if let Some(filter) = param("filter")
+    && let Err(err) = expr::compile(expr::normalize_expression(filter))
+  {
+    report.issues.push(format!(
+      "{}.{}.parameters.filter '{}' is invalid: {}",
+      path, node.id, filter, err
+    ));
+  }
+}
+
+/// Literal addresses are checked here; templated ones (`{{ ... }}`) and
+/// mapped inputs only at run time.
+#[cfg(feature = "email")]
+fn validate_email(node: &NodeDefinitionV2, path: &str, report: &mut WorkflowValidationReport) {
+  use agentflow_nodes::nodes::email::{SmtpTls, parse_recipients};
+  let has_source =
+    |key: &str| node.parameters.contains_key(key) || node.input_mapping.contains_key(key);
+  if !["to", "cc", "bcc"].iter().any(|key| has_source(key)) {
+    report.issues.push(format!(
+      "{}.{} requires 'to', 'cc' or 'bcc' as a parameter or input_mapping",
+      path, node.id
+    ));
+  }
+  for key in ["from", "to", "cc", "bcc"] {
+    let Some(value) = node.parameters.get(key) else {
+      continue;
+    };
+    let Ok(value) = serde_json::to_value(value) else {
+      continue;
+    };
+    if value.to_string().contains("{{") {
+      continue;
+    }
+    if let Err(err) = parse_recipients(&value) {
+      report.issues.push(format!(
+        "{}.{}.parameters.{} is invalid: {}",
+        path, node.id, key, err
+      ));
+    }
+  }
+  if let Some(Err(err)) = node
+    .parameters
+    .get("tls")
+    .and_then(serde_yaml::Value::as_str)
+    .map(SmtpTls::parse)
+  {
+    report.issues.push(format!(
+      "{}.{}.parameters.tls is invalid: {}",
+      path, node.id, err
+    ));
+  }
+}
+
+#[cfg(feature = "git")]
+fn validate_git(node: &NodeDefinitionV2, path: &str, report: &mut WorkflowValidationReport) {
+  use agentflow_nodes::nodes::git::GitOperation;
+  let has_source =
+    |key: &str| node.parameters.contains_key(key) || node.input_mapping.contains_key(key);
+  let Some(operation) = node
+    .parameters
+    .get("operation")
+    .and_then(serde_yaml::Value::as_str)
+  else {
+    return;
+  };
+  let required = match GitOperation::parse(operation) {
+    Ok(GitOperation::Clone) => "url",
+    Ok(GitOperation::ReadFiles | GitOperation::Log) => "path",
+    Err(err) => {
+      report.issues.push(format!(
+        "{}.{}.parameters.operation is invalid: {}",
+        path, node.id, err
+      ));
+      return;
+    }
+  };
+  if !has_source(required) {
+    report.issues.push(format!(
+      "{}.{} {} requires '{}' as a parameter or input_mapping",
+      path, node.id, operation, required
+    ));
+  }
+}
+
+#[cfg(feature = "s3")]
+fn validate_object_store(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  use agentflow_nodes::nodes::object_store::{ContentEncoding, ObjectStoreOperation};
+  let param = |key: &str| node.parameters.get(key).and_then(serde_yaml::Value::as_str);
+  let has_source =
+    |key: &str| node.parameters.contains_key(key) || node.input_mapping.contains_key(key);
+  match param("operation").map(ObjectStoreOperation::parse) {
+    Some(Err(err)) => report.issues.push(format!(
+      "{}.{}.parameters.operation is invalid: {}",
+      path, node.id, err
+    )),
+    Some(Ok(ObjectStoreOperation::Put)) if !has_source("file") && !has_source("content") => {
+      report.issues.push(format!(
+        "{}.{} put requires 'file' or 'content' as a parameter or input_mapping",
+        path, node.id
+      ))
+    }
+    _ => {}
+  }
+  if let Some(Err(err)) = param("content_encoding").map(ContentEncoding::parse) {
+    report.issues.push(format!(
+      "{}.{}.parameters.content_encoding is invalid: {}",
+      path, node.id, err
+    ));
+  }
+}
+
+fn validate_document_render(
+  node: &NodeDefinitionV2,
+  path: &str,
+  report: &mut WorkflowValidationReport,
+) {
+  use agentflow_nodes::nodes::document_render::DocumentFormat;
+  let Some(formats) = node
+    .parameters
+    .get("formats")
+    .and_then(serde_yaml::Value::as_sequence)
+  else {
+    return;
+  };
+  let formats: Vec<_> = formats
+    .iter()
+    .filter_map(serde_yaml::Value::as_str)
+    .collect();
+  for format in &formats {
+    if let Err(err) = DocumentFormat::parse(format) {
+      report.issues.push(format!(
+        "{}.{}.parameters.formats is invalid: {}",
+        path, node.id, err
+      ));
+    }
+  }
+  let wants_pdf = formats
+    .iter()
+    .any(|format| DocumentFormat::parse(format).ok() == Some(DocumentFormat::Pdf));
+  if wants_pdf && !node.parameters.contains_key("output_dir") {
+    report.issues.push(format!(
+      "{}.{}.parameters.output_dir is required for pdf output",
+      path, node.id
+    ));
+  }
+}
+
 fn parse_mapping_source_node(mapping: &str) -> Option<&str> {
   let path = mapping
     .trim()
@@ -520,16 +1284,20 @@ fn parse_mapping_source_node(mapping: &str) -> Option<&str> {
   }
 }
 
-fn feature_hint(node_type: &str) -> &'static str {
+pub(crate) fn feature_hint(node_type: &str) -> &'static str {
   match node_type {
-    "mcp" => " (enable the `mcp` feature for MCP workflow nodes)",
-    "rag" => " (enable the `rag` feature for RAG workflow nodes)",
+    "mcp" | "mcp_tool" => " (enable the `mcp` feature for MCP workflow nodes)",
+    "rag" | "embedding" => " (enable the `rag` feature for RAG workflow nodes)",
     "plugin" => " (enable the `plugin` feature for plugin workflow nodes)",
+    "sql" => " (enable the `sql` feature for SQL workflow nodes)",
+    "object_store" => " (enable the `s3` feature for object storage workflow nodes)",
+    "git" => " (enable the `git` feature for git workflow nodes)",
+    "email" => " (enable the `email` feature for email workflow nodes)",
     _ => "",
   }
 }
 
-fn describe_param_type(kind: ParamType) -> &'static str {
+pub(crate) fn describe_param_type(kind: ParamType) -> &'static str {
   match kind {
     ParamType::Any => "any value",
     ParamType::String => "a string",
@@ -539,6 +1307,7 @@ fn describe_param_type(kind: ParamType) -> &'static str {
     ParamType::Object => "an object/map",
     ParamType::Sequence => "a sequence/list",
     ParamType::SequenceOfStrings => "a sequence/list of strings",
+    ParamType::Secret => "a string or a $secret / $secret_file reference",
   }
 }
 
@@ -607,6 +1376,16 @@ nodes:
           type: template
           parameters:
             template: "{{ item }}"
+  - id: per_item
+    type: batch
+    parameters:
+      batch_size: 5
+      max_retries: 1
+      aggregation: concat_text
+      child:
+        type: template
+        parameters:
+          template: "{{ item }}"
   - id: retry_loop
     type: while
     parameters:
@@ -651,6 +1430,44 @@ nodes:
     assert_eq!(report.issues, Vec::<String>::new());
   }
 
+  #[test]
+  fn declared_inputs_and_outputs_are_checked() {
+    let flow = parse_workflow(
+      r#"
+name: Declared IO
+inputs:
+  limit:
+    type: integer
+    default: many
+  topic:
+    type: string
+    required: true
+outputs:
+  greeting: "nodes.greet.outputs.output"
+  missing: "nodes.nowhere.outputs.output"
+  loose: "greet.output"
+  debug: "{{ nodes.greet.outputs.output }}"
+nodes:
+  - id: greet
+    type: template
+    parameters:
+      template: "Hello {{ topic }}"
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(
+      report.issues,
+      vec![
+        "inputs.limit.default must be an integer, got \"many\"",
+        "outputs.debug is reserved for the run report; rename the output",
+        "outputs.loose must reference a node output as 'nodes.<id>.outputs.<key>', got 'greet.output'",
+        "outputs.missing references unknown node 'nowhere'",
+      ]
+    );
+  }
+
   #[test]
   fn reports_parameter_type_mismatches_with_paths() {
     let flow = parse_workflow(
@@ -716,4 +1533,386 @@ nodes:
         .any(|issue| issue.contains("unknown function 'lenn'"))
     );
   }
+
+  #[test]
+  fn json_transform_pipelines_are_compiled_at_validate_time() {
+    let flow = parse_workflow(
+      r#"
+name: Reshape
+nodes:
+  - id: ok
+    type: json_transform
+    parameters:
+      operations:
+        - select: "$.choices[0].message.content"
+        - parse
+  - id: broken
+    type: json_transform
+    parameters:
+      operations:
+        - select: "$.choices[0"
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+    let issue = &report.issues[0];
+    assert!(issue.contains("broken.parameters is invalid"), "{issue}");
+    assert!(issue.contains("Invalid JSONPath '$.choices[0'"), "{issue}");
+  }
+
+  #[test]
+  fn text_extract_pipelines_are_compiled_at_validate_time() {
+    let flow = parse_workflow(
+      r#"
+name: Clean
+nodes:
+  - id: ok
+    type: text_extract
+    parameters:
+      operations:
+        - extract_code_blocks: { language: python }
+        - strip_markdown
+  - id: broken
+    type: text_extract
+    parameters:
+      operations:
+        - regex_capture: "(?P<name>[a-z+"
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+    let issue = &report.issues[0];
+    assert!(issue.contains("broken.parameters is invalid"), "{issue}");
+    assert!(issue.contains("'regex_capture' pattern"), "{issue}");
+  }
+
+  #[test]
+  fn conditional_expressions_are_compiled_at_validate_time() {
+    let flow = parse_workflow(
+      r#"
+name: Route
+nodes:
+  - id: grade
+    type: conditional
+    parameters:
+      cases:
+        - label: high
+          when: "score >= 80"
+        - label: low
+          when: "score <"
+  - id: both
+    type: conditional
+    parameters:
+      condition: "score > 1"
+      cases:
+        - label: high
+          when: "score >= 80"
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("grade.parameters is invalid: Configuration error: case 'low'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(report.issues[1].contains("not both"), "{:?}", report.issues);
+  }
+
+  #[test]
+  fn document_render_formats_are_checked_at_validate_time() {
+    let flow = parse_workflow(
+      r##"
+name: Report
+nodes:
+  - id: report
+    type: document_render
+    parameters:
+      template: "# {{ title }}"
+      formats: [markdown, docx]
+  - id: printable
+    type: document_render
+    parameters:
+      template: "# Printable"
+      formats: [html, pdf]
+"##,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("report.parameters.formats is invalid")
+        && report.issues[0].contains("'docx'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1].contains("printable.parameters.output_dir is required"),
+      "{:?}",
+      report.issues
+    );
+  }
+
+  #[test]
+  fn approval_channel_and_timeout_policy_are_checked_at_validate_time() {
+    let flow = parse_workflow(
+      r#"
+name: Sign-off
+nodes:
+  - id: review
+    type: approval
+    parameters:
+      on_timeout: ignore
+  - id: ping
+    type: approval
+    parameters:
+      channel: slack
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("review.parameters.sentinel_path is required"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1].contains("review.parameters.on_timeout is invalid"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[2].contains("ping.parameters.channel 'slack' is invalid"),
+      "{:?}",
+      report.issues
+    );
+  }
+
+  #[test]
+  fn schedule_gate_windows_and_policy_are_checked_at_validate_time() {
+    let flow = parse_workflow(
+      r#"
+name: Office hours
+nodes:
+  - id: gate
+    type: schedule_gate
+    parameters:
+      windows: ["Mon-Fri 09:00-17:00", "*/15 25 * * *"]
+      policy: later
+      utc_offset: Europe/Berlin
+  - id: pause
+    type: delay
+    parameters:
+      duration_ms: 500
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("gate.parameters.windows is invalid: invalid cron field '25'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1].contains("gate.parameters.policy 'later' is invalid"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[2].contains("gate.parameters.utc_offset 'Europe/Berlin'"),
+      "{:?}",
+      report.issues
+    );
+  }
+
+  #[test]
+  fn tabular_read_source_column_types_and_filter_are_checked() {
+    let flow = parse_workflow(
+      r#"
+name: Ledger
+nodes:
+  - id: load
+    type: tabular_read
+    parameters:
+      column_types:
+        amount: decimal
+      filter: "amount >"
+  - id: export
+    type: tabular_write
+    input_mapping:
+      rows: "{{ nodes.load.outputs.rows }}"
+    dependencies: [load]
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("load requires 'path' or 'content'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1].contains("load.parameters.column_types.amount is invalid"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[2].contains("load.parameters.filter 'amount >' is invalid"),
+      "{:?}",
+      report.issues
+    );
+  }
+
+  #[cfg(feature = "s3")]
+  #[test]
+  fn object_store_operation_and_put_source_are_checked() {
+    let flow = parse_workflow(
+      r#"
+name: Publish
+nodes:
+  - id: upload
+    type: object_store
+    parameters:
+      operation: put
+      bucket: artifacts
+      key: "reports/{{ name }}"
+  - id: fetch
+    type: object_store
+    parameters:
+      operation: download
+      bucket: artifacts
+      content_encoding: hex
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("upload put requires 'file' or 'content'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1].contains("fetch.parameters.operation is invalid"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[2].contains("fetch.parameters.content_encoding is invalid"),
+      "{:?}",
+      report.issues
+    );
+  }
+
+  #[cfg(feature = "git")]
+  #[test]
+  fn git_operation_and_its_source_are_checked() {
+    let flow = parse_workflow(
+      r#"
+name: Review
+nodes:
+  - id: checkout
+    type: git
+    parameters:
+      operation: clone
+      depth: 1
+  - id: sources
+    type: git
+    parameters:
+      operation: read_files
+    input_mapping:
+      path: "{{ nodes.checkout.outputs.path }}"
+    dependencies: [checkout]
+  - id: blame
+    type: git
+    parameters:
+      operation: blame
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("checkout clone requires 'url'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1].contains("blame.parameters.operation is invalid"),
+      "{:?}",
+      report.issues
+    );
+  }
+
+  #[cfg(feature = "email")]
+  #[test]
+  fn email_recipients_and_tls_mode_are_checked() {
+    let flow = parse_workflow(
+      r#"
+name: Notify
+nodes:
+  - id: ok
+    type: email
+    parameters:
+      host: smtp.example.com
+      from: "Bot <bot@example.com>"
+      subject: "Report for {{ client }}"
+      to: ["ada@example.com", "{{ owner }}"]
+  - id: broken
+    type: email
+    parameters:
+      host: smtp.example.com
+      from: bot
+      subject: Report
+      tls: ssl
+  - id: typo
+    type: email
+    parameters:
+      host: smtp.example.com
+      from: bot@example.com
+      subject: Report
+      cc: "ada@example.com; grace@example.com"
+"#,
+    );
+
+    let report = validate_flow_definition(&flow);
+
+    assert_eq!(report.issues.len(), 4, "{:?}", report.issues);
+    assert!(
+      report.issues[0].contains("broken requires 'to', 'cc' or 'bcc'"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[1]
+        .contains("broken.parameters.from is invalid: 'bot' is not a valid email address"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[2].contains("broken.parameters.tls is invalid"),
+      "{:?}",
+      report.issues
+    );
+    assert!(
+      report.issues[3].contains("typo.parameters.cc is invalid"),
+      "{:?}",
+      report.issues
+    );
+  }
 }
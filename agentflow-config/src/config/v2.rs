@@ -5,23 +5,57 @@ use std::collections::HashMap;
 #[derive(Debug, Deserialize)]
 pub struct FlowDefinitionV2 {
   pub name: String,
+  /// The inputs the workflow reads, checked against the provided ones
+  /// before it runs; see [`resolve_inputs`](super::io::resolve_inputs).
   #[serde(default)]
-  #[allow(dead_code)]
   pub inputs: HashMap<String, InputDefinitionV2>,
+  /// The values the workflow returns, by name: each maps to a
+  /// `nodes.<id>.outputs.<key>` reference.
+  #[serde(default)]
+  pub outputs: HashMap<String, String>,
   pub nodes: Vec<NodeDefinitionV2>,
 }
 
-/// Defines a required input for the workflow.
+/// Defines an input of the workflow.
 #[derive(Debug, Deserialize)]
 pub struct InputDefinitionV2 {
-  #[allow(dead_code)]
+  #[serde(default)]
   pub description: Option<String>,
-  #[allow(dead_code)]
+  /// The type a provided value is coerced to; any value when unset.
+  #[serde(rename = "type", default)]
+  pub input_type: Option<InputType>,
+  #[serde(default)]
   pub required: bool,
-  #[allow(dead_code)]
+  /// Used when the input is not provided.
+  #[serde(default)]
   pub default: Option<serde_yaml::Value>,
 }
 
+/// The declared type of a workflow input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+  String,
+  Number,
+  Integer,
+  Boolean,
+  Object,
+  Array,
+}
+
+impl InputType {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::String => "string",
+      Self::Number => "number",
+      Self::Integer => "integer",
+      Self::Boolean => "boolean",
+      Self::Object => "object",
+      Self::Array => "array",
+    }
+  }
+}
+
 /// Defines a single node in the V2 workflow graph.
 #[derive(Debug, Deserialize)]
 pub struct NodeDefinitionV2 {
@@ -36,4 +70,13 @@ pub struct NodeDefinitionV2 {
   pub run_if: Option<String>,
   #[serde(default)]
   pub parameters: HashMap<String, serde_yaml::Value>,
+  /// Environment variables for the process the node spawns (`command`,
+  /// `mcp`, `mcp_tool`). `${NAME}` is expanded from the host environment;
+  /// a `$secret` / `$secret_file` reference is resolved and redacted.
+  #[serde(default)]
+  pub env: HashMap<String, serde_yaml::Value>,
+  /// Working directory for that process; `git` resolves relative paths
+  /// against it. `${NAME}` is expanded.
+  #[serde(default)]
+  pub cwd: Option<String>,
 }
@@ -0,0 +1,210 @@
+//! `type: batch` YAML workflow node.
+//!
+//! Builds an [`agentflow_nodes::nodes::batch::BatchNode`] whose per-item
+//! child comes from the nested config: either `child` (one node definition)
+//! or `template` (a list of node definitions, like `map`). Both are built
+//! through [`create_graph_node`], so any node type the factory knows can run
+//! per item, and both run as a sub-flow whose initial inputs are the item's
+//! `item` and `index` — placeholders and `input_mapping` behave exactly as in
+//! a `map` template.
+//!
+//! The child's result is the output map of the sub-flow's last declared node;
+//! any node-level error in the sub-flow fails that item (and so counts
+//! towards the batch node's per-item retries).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use agentflow_core::{
+  FlowExt,
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  flow::{Flow, GraphNode},
+};
+use agentflow_nodes::nodes::batch::{BatchAggregation, BatchNode};
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::config::v2::NodeDefinitionV2;
+use crate::executor::factory::create_graph_node;
+
+/// Runs a template of graph nodes as one `AsyncNode`.
+struct SubFlowNode {
+  template: Vec<GraphNode>,
+  /// Node whose outputs are returned: the last one declared.
+  result_node: String,
+}
+
+#[async_trait]
+impl AsyncNode for SubFlowNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let mut state = Flow::new(self.template.clone())
+      .execute_from_inputs(inputs.clone())
+      .await?;
+    if let Some((node_id, Err(e))) = state.iter().find(|(_, result)| result.is_err()) {
+      return Err(AgentFlowError::NodeExecutionFailed {
+        message: format!("Batch child node '{}' failed: {}", node_id, e),
+      });
+    }
+    state
+      .remove(&self.result_node)
+      .unwrap_or_else(|| Ok(HashMap::new()))
+  }
+}
+
+/// Build the batch node (with its child sub-flow) from a `type: batch`
+/// definition.
+pub fn build_batch_node(node_def: &NodeDefinitionV2) -> Result<BatchNode> {
+  let params = &node_def.parameters;
+  let child_defs: Vec<NodeDefinitionV2> = match (params.get("child"), params.get("template")) {
+    (Some(child), None) => {
+      let mut child = child.clone();
+      // `id` is optional on a lone child; default it from the batch node.
+      if let serde_yaml::Value::Mapping(fields) = &mut child
+        && !fields.contains_key("id")
+      {
+        fields.insert("id".into(), format!("{}_child", node_def.id).into());
+      }
+      vec![serde_yaml::from_value(child).context("batch 'child' is not a valid node definition")?]
+    }
+    (None, Some(template)) => serde_yaml::from_value(template.clone())
+      .context("batch 'template' must be a list of node definitions")?,
+    _ => bail!("batch node requires exactly one of 'child' or 'template'"),
+  };
+  let result_node = child_defs
+    .last()
+    .map(|def| def.id.clone())
+    .ok_or_else(|| anyhow!("batch 'template' must contain at least one node"))?;
+  let template = child_defs
+    .iter()
+    .map(create_graph_node)
+    .collect::<Result<Vec<_>>>()?;
+
+  let items_key = params
+    .get("items_key")
+    .and_then(|v| v.as_str())
+    .unwrap_or("items");
+  let mut node =
+    BatchNode::new(&node_def.id, items_key).with_child_node(std::sync::Arc::new(SubFlowNode {
+      template,
+      result_node,
+    }));
+
+  let u64_param = |key: &str| params.get(key).and_then(|v| v.as_u64());
+  if let Some(batch_size) = u64_param("batch_size") {
+    node = node.with_batch_size(batch_size as usize);
+  }
+  if let Some(max_concurrent) = u64_param("max_concurrent") {
+    node = node.with_max_concurrent(max_concurrent as usize);
+  }
+  if let Some(delay_ms) = u64_param("batch_delay_ms") {
+    node = node.with_batch_delay(Duration::from_millis(delay_ms));
+  }
+  if let Some(max_retries) = u64_param("max_retries") {
+    let retry_delay = Duration::from_millis(u64_param("retry_delay_ms").unwrap_or(0));
+    node = node.with_retries(max_retries as u32, retry_delay);
+  }
+  if let Some(result_key) = params.get("result_key").and_then(|v| v.as_str()) {
+    node = node.with_result_key(result_key);
+  }
+  if let Some(mode) = params.get("aggregation").and_then(|v| v.as_str()) {
+    let mut aggregation: BatchAggregation = mode.parse()?;
+    if let (BatchAggregation::ConcatText { separator }, Some(custom)) = (
+      &mut aggregation,
+      params.get("separator").and_then(|v| v.as_str()),
+    ) {
+      *separator = custom.to_string();
+    }
+    node = node.with_aggregation(aggregation);
+  }
+  Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_core::value::FlowValue;
+  use serde_json::{Value, json};
+
+  fn batch_def(yaml: &str) -> NodeDefinitionV2 {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  fn items(values: Value) -> AsyncNodeInputs {
+    AsyncNodeInputs::from([("items".to_string(), FlowValue::Json(values))])
+  }
+
+  #[tokio::test]
+  async fn child_block_runs_per_item_and_concatenates() {
+    let node = build_batch_node(&batch_def(
+      r#"
+id: greet
+type: batch
+parameters:
+  aggregation: concat_text
+  separator: " | "
+  max_concurrent: 2
+  child:
+    type: template
+    parameters:
+      template: "hi {{ item }} #{{ index }}"
+"#,
+    ))
+    .unwrap();
+
+    let outputs = node.execute(&items(json!(["ann", "bo"]))).await.unwrap();
+
+    assert_eq!(
+      outputs["results"],
+      FlowValue::Json(json!("hi ann #0 | hi bo #1"))
+    );
+  }
+
+  #[tokio::test]
+  async fn template_block_returns_the_last_node_outputs() {
+    let node = build_batch_node(&batch_def(
+      r#"
+id: pipeline
+type: batch
+parameters:
+  result_key: output
+  template:
+    - id: first
+      type: template
+      parameters:
+        template: "{{ item }}!"
+    - id: second
+      type: template
+      dependencies: [first]
+      input_mapping:
+        shout: "{{ nodes.first.outputs.output }}"
+      parameters:
+        template: "<{{ shout }}>"
+"#,
+    ))
+    .unwrap();
+
+    let outputs = node.execute(&items(json!(["a", "b"]))).await.unwrap();
+
+    assert_eq!(outputs["results"], FlowValue::Json(json!(["<a!>", "<b!>"])));
+    assert_eq!(outputs["failed_items"], FlowValue::Json(json!([])));
+  }
+
+  #[test]
+  fn child_and_template_are_mutually_exclusive() {
+    let err = build_batch_node(&batch_def(
+      "id: b\ntype: batch\nparameters:\n  items_key: rows\n",
+    ))
+    .unwrap_err();
+    assert!(err.to_string().contains("exactly one"), "{err}");
+
+    let err = build_batch_node(&batch_def(
+      "id: b\ntype: batch\nparameters:\n  aggregation: sum\n  child: { type: template, parameters: { template: x } }\n",
+    ))
+    .unwrap_err();
+    assert!(
+      err.to_string().contains("Unknown batch aggregation"),
+      "{err}"
+    );
+  }
+}
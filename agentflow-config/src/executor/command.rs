@@ -0,0 +1,152 @@
+//! `type: command` YAML workflow node.
+//!
+//! Builds an [`agentflow_nodes::nodes::command::CommandNode`] from the
+//! `parameters` block. Unlike `type: shell` (which sandboxes a command
+//! string through `ShellTool`), `command` runs one program with an argv
+//! list and only involves `sh -c` when the workflow sets `allow_shell: true`.
+//!
+//! Which programs may run is a host decision, not a workflow one: embedders
+//! call [`set_command_allowlist`] before building flows, or set
+//! `AGENTFLOW_COMMAND_ALLOWLIST` (comma-separated program names). With
+//! neither, any program is allowed.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use agentflow_nodes::nodes::command::CommandNode;
+use anyhow::{Result, anyhow};
+
+/// Environment variable consulted when no allowlist was set in-process.
+pub const COMMAND_ALLOWLIST_ENV: &str = "AGENTFLOW_COMMAND_ALLOWLIST";
+
+static COMMAND_ALLOWLIST: RwLock<Option<Vec<String>>> = RwLock::new(None);
+
+/// Restrict every `type: command` node built afterwards to `programs`
+/// (`None` clears the restriction, falling back to the environment).
+pub fn set_command_allowlist(programs: Option<Vec<String>>) {
+  *COMMAND_ALLOWLIST
+    .write()
+    .unwrap_or_else(|poisoned| poisoned.into_inner()) = programs;
+}
+
+/// The allowlist in effect: the in-process one, else the environment's.
+pub fn command_allowlist() -> Option<Vec<String>> {
+  let configured = COMMAND_ALLOWLIST
+    .read()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+    .clone();
+  configured.or_else(|| {
+    std::env::var(COMMAND_ALLOWLIST_ENV).ok().map(|list| {
+      list
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+    })
+  })
+}
+
+pub(crate) fn string_list(
+  parameters: &HashMap<String, serde_yaml::Value>,
+  key: &str,
+) -> Result<Option<Vec<String>>> {
+  let Some(value) = parameters.get(key) else {
+    return Ok(None);
+  };
+  let items = value
+    .as_sequence()
+    .ok_or_else(|| anyhow!("'{}' must be a list of strings", key))?;
+  items
+    .iter()
+    .map(|item| match item {
+      serde_yaml::Value::String(s) => Ok(s.clone()),
+      serde_yaml::Value::Number(n) => Ok(n.to_string()),
+      serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+      _ => Err(anyhow!("'{}' must be a list of strings", key)),
+    })
+    .collect::<Result<Vec<_>>>()
+    .map(Some)
+}
+
+/// Build the node from a `type: command` parameters block.
+pub fn build_command_node(
+  name: &str,
+  parameters: &HashMap<String, serde_yaml::Value>,
+) -> Result<CommandNode> {
+  let program = parameters
+    .get("program")
+    .and_then(|v| v.as_str())
+    .filter(|program| !program.trim().is_empty())
+    .ok_or_else(|| anyhow!("requires a non-empty 'program' parameter"))?;
+  let flag = |key: &str| parameters.get(key).and_then(|v| v.as_bool());
+  let str_param = |key: &str| parameters.get(key).and_then(|v| v.as_str());
+  let u64_param = |key: &str| parameters.get(key).and_then(|v| v.as_u64());
+
+  let mut node = CommandNode::new(name, program);
+  if let Some(args) = string_list(parameters, "args")? {
+    node = node.with_args(args);
+  }
+  if let Some(allow_shell) = flag("allow_shell") {
+    node = node.with_allow_shell(allow_shell);
+  }
+  if let Some(key) = str_param("stdin_key") {
+    node = node.with_stdin_key(key);
+  }
+  if let Some(dir) = str_param("working_dir") {
+    node = node.with_working_dir(dir);
+  }
+  if let Some(names) = string_list(parameters, "env_allowlist")? {
+    node = node.with_env_allowlist(names);
+  }
+  if let Some(timeout_ms) = u64_param("timeout_ms") {
+    node = node.with_timeout(Duration::from_millis(timeout_ms));
+  }
+  if let Some(max) = u64_param("max_output_bytes") {
+    node = node.with_max_output_bytes(max as usize);
+  }
+  if let Some(fail_on_error) = flag("fail_on_error") {
+    node = node.with_fail_on_error(fail_on_error);
+  }
+  if let Some(programs) = command_allowlist() {
+    node = node.with_allowed_programs(programs);
+  }
+  Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn params(yaml: &str) -> HashMap<String, serde_yaml::Value> {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  #[test]
+  fn builds_from_parameters() {
+    let node = build_command_node(
+      "convert",
+      &params(
+        "program: pandoc\nargs: ['{{ input }}', -o, out.pdf]\nstdin_key: body\ntimeout_ms: 1500\nenv_allowlist: [HOME]\n",
+      ),
+    )
+    .unwrap();
+
+    assert_eq!(node.program, "pandoc");
+    assert_eq!(node.args, vec!["{{ input }}", "-o", "out.pdf"]);
+    assert_eq!(node.stdin_key.as_deref(), Some("body"));
+    assert_eq!(node.timeout, Duration::from_millis(1500));
+    assert_eq!(node.env_allowlist, vec!["HOME"]);
+    assert!(!node.allow_shell);
+  }
+
+  #[test]
+  fn program_is_required_and_args_must_be_a_list() {
+    let err = build_command_node("c", &params("args: [a]\n")).unwrap_err();
+    assert!(err.to_string().contains("'program'"), "{err}");
+
+    let err = build_command_node("c", &params("program: ls\nargs: -la\n")).unwrap_err();
+    assert!(err.to_string().contains("list of strings"), "{err}");
+  }
+}
@@ -1,20 +1,46 @@
+use crate::config::schema::unsupported_process_env;
 use crate::config::v2::NodeDefinitionV2;
 use agentflow_agents::{AgentNodeResumeContract, AgentRunResult};
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
   flow::{GraphNode, NodeType},
+  secret::{self, SecretValue},
   value::FlowValue,
 };
 use agentflow_llm::AgentFlow;
+use agentflow_nodes::common::process_env::ProcessEnv;
 // Tool-tier nodes (no capability deps) stay in `agentflow-nodes`; the
 // capability-backed nodes moved to `agentflow-nodes-ai` (P-A nodes split).
 use agentflow_nodes::nodes::{
-  arxiv::ArxivNode, file::FileNode, http::HttpNode, markmap::MarkMapNode, template::TemplateNode,
+  approval::{ApprovalChannel, ApprovalNode, TimeoutPolicy},
+  arxiv::ArxivNode,
+  conditional::ConditionalNode,
+  delay::DelayNode,
+  document_render::{DocumentFormat, DocumentRenderNode},
+  feed::FeedNode,
+  file::{FileNode, validate_file_options},
+  http::{HttpAuth, HttpNode},
+  json_transform::JsonTransformNode,
+  markmap::{MarkMapConfig, MarkMapNode},
+  poll_until::PollUntilNode,
+  schedule_gate::{GatePolicy, ScheduleGateNode, ScheduleWindow, parse_utc_offset},
+  tabular::{ColumnType, TabularFormat, TabularReadNode, TabularWriteNode},
+  template::{TemplateNode, validate_output_format},
+  text_extract::TextExtractNode,
+  throttle::ThrottleNode,
+  vector_search::VectorSearchNode,
+  web_extract::WebExtractNode,
+  webhook_wait::WebhookWaitNode,
 };
 use agentflow_nodes_ai::nodes::{
-  asr::ASRNode, image_edit::ImageEditNode, image_to_image::ImageToImageNode,
-  image_understand::ImageUnderstandNode, llm::LlmNode, text_to_image::TextToImageNode,
+  asr::ASRNode,
+  image_edit::ImageEditNode,
+  image_to_image::ImageToImageNode,
+  image_understand::ImageUnderstandNode,
+  llm::LlmNode,
+  text_splitter::TextSplitterNode,
+  text_to_image::{ImageResponseFormat, TextToImageNode},
   tts::TTSNode,
 };
 use agentflow_skills::{SkillBuilder, SkillLoader};
@@ -24,13 +50,30 @@ use serde_json::{Value, json};
 
 #[cfg(feature = "mcp")]
 use agentflow_nodes_ai::nodes::mcp::MCPNode;
+#[cfg(feature = "mcp")]
+use agentflow_nodes_ai::nodes::mcp_tool::McpToolNode;
 
 #[cfg(feature = "rag")]
-use agentflow_nodes_ai::nodes::rag::RAGNode;
+use agentflow_nodes_ai::nodes::{embedding::EmbeddingNode, rag::RAGNode};
+
+#[cfg(feature = "sql")]
+use agentflow_nodes::nodes::sql::{SqlMode, SqlNode};
+
+#[cfg(feature = "email")]
+use agentflow_nodes::nodes::email::{EmailNode, SmtpSettings, SmtpTls};
+
+#[cfg(feature = "git")]
+use agentflow_nodes::nodes::git::{GitNode, GitOperation};
+
+#[cfg(feature = "s3")]
+use agentflow_nodes::nodes::object_store::{
+  ContentEncoding, ObjectStoreNode, ObjectStoreOperation, S3Client,
+};
 
 use anyhow::{Context, Result, anyhow};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Duration;
 
 // Helper to get a string parameter from the node definition, returning a default if not found.
 fn get_string_param_optional(params: &HashMap<String, serde_yaml::Value>, key: &str) -> String {
@@ -42,8 +85,37 @@ fn get_string_param_optional(params: &HashMap<String, serde_yaml::Value>, key: &
 }
 
 pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
+  let process_env = process_env(node_def)?;
   let node_type = match node_def.node_type.as_str() {
-    "llm" => Ok(NodeType::Standard(Arc::new(LlmNode))),
+    "llm" => {
+      let params = &node_def.parameters;
+      let mut node = LlmNode::new();
+      if let Some(streaming) = params.get("streaming").and_then(|v| v.as_bool()) {
+        node = node.with_streaming(streaming);
+      }
+      if let Some(images) = params.get("images") {
+        let images: Vec<String> = serde_yaml::from_value(images.clone()).map_err(|err| {
+          anyhow!(
+            "llm '{}': 'images' must be a list of strings: {}",
+            node_def.id,
+            err
+          )
+        })?;
+        node = node.with_images(images);
+      }
+      if let Some(schema) = params.get("response_schema") {
+        let schema: serde_json::Value = serde_yaml::from_value(schema.clone())?;
+        node = node.with_response_schema(schema);
+      } else if params.get("json_mode").and_then(|v| v.as_bool()) == Some(true) {
+        node = node.with_json_mode();
+      }
+      if let Some(api_key) = params.get("api_key") {
+        let api_key = SecretValue::resolve(&serde_yaml::from_value(api_key.clone())?)
+          .map_err(|err| anyhow!("llm '{}': api_key: {}", node_def.id, err))?;
+        node = node.with_api_key(api_key);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
     "skill_agent" | "agent" => {
       let node = SkillAgentWorkflowNode::new(&node_def.id);
       Ok(NodeType::Standard(Arc::new(node)))
@@ -79,8 +151,424 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
       );
       Ok(NodeType::Standard(Arc::new(node)))
     }
-    "http" => Ok(NodeType::Standard(Arc::new(HttpNode::default()))),
-    "file" => Ok(NodeType::Standard(Arc::new(FileNode::default()))),
+    "http" => {
+      let mut node = HttpNode::default();
+      if let Some(auth) = node_def.parameters.get("auth") {
+        let auth: Value = serde_yaml::from_value(auth.clone())?;
+        let auth = auth
+          .as_object()
+          .ok_or_else(|| anyhow!("http node '{}': 'auth' must be a mapping", node_def.id))
+          .and_then(|auth| {
+            HttpAuth::from_config(auth)
+              .map_err(|err| anyhow!("http node '{}': {}", node_def.id, err))
+          })?;
+        node = node.with_auth(auth);
+      }
+      for (name, value) in secret_headers(node_def)? {
+        let value = SecretValue::resolve(&value)
+          .map_err(|err| anyhow!("http node '{}': header '{}': {}", node_def.id, name, err))?;
+        node = node.with_secret_header(&name, value);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "file" => {
+      // `operation` / `parse` may also arrive through input_mapping, in which
+      // case FileNode re-checks them at run time.
+      let operation = get_string_param_optional(&node_def.parameters, "operation");
+      let parse = get_string_param_optional(&node_def.parameters, "parse");
+      if !operation.is_empty() {
+        validate_file_options(&operation, Some(parse.as_str()).filter(|p| !p.is_empty()))
+          .map_err(|err| anyhow!("file node '{}': {}", node_def.id, err))?;
+      }
+      Ok(NodeType::Standard(Arc::new(FileNode::default())))
+    }
+    "batch" => {
+      let node = crate::executor::batch::build_batch_node(node_def)
+        .map_err(|err| anyhow!("batch node '{}': {}", node_def.id, err))?;
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "conditional" => {
+      let params = serde_json::to_value(&node_def.parameters)?;
+      let node = ConditionalNode::from_params(&node_def.id, &params)
+        .map_err(|err| anyhow!("conditional node '{}': {}", node_def.id, err))?;
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "json_transform" => {
+      let params = serde_json::to_value(&node_def.parameters)?;
+      let node = JsonTransformNode::from_params(&node_def.id, &params)
+        .map_err(|err| anyhow!("json_transform node '{}': {}", node_def.id, err))?;
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "text_extract" => {
+      let params = serde_json::to_value(&node_def.parameters)?;
+      let node = TextExtractNode::from_params(&node_def.id, &params)
+        .map_err(|err| anyhow!("text_extract node '{}': {}", node_def.id, err))?;
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "vector_search" => {
+      let params = &node_def.parameters;
+      let mut node = VectorSearchNode::new(&node_def.id);
+      let query_key = get_string_param_optional(params, "query_key");
+      if !query_key.is_empty() {
+        node = node.with_query_key(&query_key);
+      }
+      let corpus_key = get_string_param_optional(params, "corpus_key");
+      if !corpus_key.is_empty() {
+        node = node.with_corpus_key(&corpus_key);
+      }
+      let corpus_path = get_string_param_optional(params, "corpus_path");
+      if !corpus_path.is_empty() {
+        node = node.with_corpus_path(corpus_path);
+      }
+      let index_path = get_string_param_optional(params, "index_path");
+      if !index_path.is_empty() {
+        node = node.with_index_path(index_path);
+      }
+      if let Some(top_k) = params.get("top_k").and_then(|v| v.as_u64()) {
+        node = node.with_top_k(top_k as usize);
+      }
+      if let Some(min_score) = params.get("min_score").and_then(|v| v.as_f64()) {
+        node = node.with_min_score(min_score as f32);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "webhook_wait" => {
+      let params = &node_def.parameters;
+      let mut node = WebhookWaitNode::new(&node_def.id);
+      let host = get_string_param_optional(params, "host");
+      if !host.is_empty() {
+        node = node.with_host(&host);
+      }
+      if let Some(port) = params.get("port").and_then(|v| v.as_u64()) {
+        let port = u16::try_from(port).map_err(|_| {
+          anyhow!(
+            "webhook_wait node '{}': port {} is out of range",
+            node_def.id,
+            port
+          )
+        })?;
+        node = node.with_port(port);
+      }
+      let path = get_string_param_optional(params, "path");
+      if !path.is_empty() {
+        node = node.with_path(&path);
+      }
+      if let Some(timeout_ms) = params.get("timeout_ms").and_then(|v| v.as_u64()) {
+        node = node.with_timeout(Duration::from_millis(timeout_ms));
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "poll_until" => {
+      let params = &node_def.parameters;
+      let until = get_string_param_optional(params, "until");
+      let mut node = PollUntilNode::new(&node_def.id, &until)
+        .map_err(|err| anyhow!("poll_until node '{}': {}", node_def.id, err))?;
+      if let Some(equals) = params.get("equals") {
+        node = node.with_equals(serde_json::to_value(equals)?);
+      }
+      if let Some(interval_ms) = params.get("interval_ms").and_then(|v| v.as_u64()) {
+        node = node.with_interval(Duration::from_millis(interval_ms));
+      }
+      if let Some(backoff) = params.get("backoff").and_then(|v| v.as_f64()) {
+        node = node.with_backoff(backoff);
+      }
+      if let Some(max_interval_ms) = params.get("max_interval_ms").and_then(|v| v.as_u64()) {
+        node = node.with_max_interval(Duration::from_millis(max_interval_ms));
+      }
+      if let Some(timeout_ms) = params.get("timeout_ms").and_then(|v| v.as_u64()) {
+        node = node.with_timeout(Duration::from_millis(timeout_ms));
+      }
+      if let Some(max_attempts) = params.get("max_attempts").and_then(|v| v.as_u64()) {
+        node = node.with_max_attempts(u32::try_from(max_attempts).unwrap_or(u32::MAX));
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "approval" => {
+      let params = &node_def.parameters;
+      let channel = match get_string_param_optional(params, "channel").as_str() {
+        "" | "file" => {
+          let sentinel_path = get_string_param_optional(params, "sentinel_path");
+          if sentinel_path.is_empty() {
+            return Err(anyhow!(
+              "approval node '{}': the file channel requires 'sentinel_path'",
+              node_def.id
+            ));
+          }
+          ApprovalChannel::File {
+            sentinel_path: sentinel_path.into(),
+          }
+        }
+        "http" => {
+          let host = get_string_param_optional(params, "host");
+          let port = params.get("port").and_then(|v| v.as_u64()).unwrap_or(0);
+          let port = u16::try_from(port).map_err(|_| {
+            anyhow!(
+              "approval node '{}': port {} is out of range",
+              node_def.id,
+              port
+            )
+          })?;
+          let path = get_string_param_optional(params, "path");
+          ApprovalChannel::Http {
+            host: if host.is_empty() {
+              "127.0.0.1".to_string()
+            } else {
+              host
+            },
+            port,
+            path: if path.is_empty() {
+              "/approve".to_string()
+            } else {
+              path
+            },
+          }
+        }
+        "stdin" => ApprovalChannel::Stdin,
+        other => {
+          return Err(anyhow!(
+            "approval node '{}': unknown channel '{}' (expected file, http or stdin)",
+            node_def.id,
+            other
+          ));
+        }
+      };
+      let mut node = ApprovalNode::new(&node_def.id, channel);
+      let prompt = get_string_param_optional(params, "prompt");
+      if !prompt.is_empty() {
+        node = node.with_prompt(&prompt);
+      }
+      if let Some(timeout_ms) = params.get("timeout_ms").and_then(|v| v.as_u64()) {
+        node = node.with_timeout(Duration::from_millis(timeout_ms));
+      }
+      let on_timeout = get_string_param_optional(params, "on_timeout");
+      if !on_timeout.is_empty() {
+        let policy = TimeoutPolicy::parse(&on_timeout)
+          .map_err(|err| anyhow!("approval node '{}': {}", node_def.id, err))?;
+        node = node.with_on_timeout(policy);
+      }
+      if let Some(interval_ms) = params.get("poll_interval_ms").and_then(|v| v.as_u64()) {
+        node = node.with_poll_interval(Duration::from_millis(interval_ms));
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "delay" => {
+      let params = &node_def.parameters;
+      let duration_ms = params
+        .get("duration_ms")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| {
+          anyhow!(
+            "delay node '{}' requires a non-negative integer 'duration_ms'",
+            node_def.id
+          )
+        })?;
+      let mut node = DelayNode::new(&node_def.id, Duration::from_millis(duration_ms));
+      if let Some(jitter_ms) = params.get("jitter_ms").and_then(|v| v.as_u64()) {
+        node = node.with_jitter(Duration::from_millis(jitter_ms));
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "schedule_gate" => {
+      let params = &node_def.parameters;
+      let windows = crate::executor::command::string_list(params, "windows")
+        .map_err(|err| anyhow!("schedule_gate node '{}': {}", node_def.id, err))?
+        .unwrap_or_default()
+        .iter()
+        .map(|window| ScheduleWindow::parse(window))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| anyhow!("schedule_gate node '{}': {}", node_def.id, err))?;
+      if windows.is_empty() {
+        return Err(anyhow!(
+          "schedule_gate node '{}' requires at least one entry in 'windows'",
+          node_def.id
+        ));
+      }
+      let mut node = ScheduleGateNode::new(&node_def.id, windows);
+      let policy = get_string_param_optional(params, "policy");
+      if !policy.is_empty() {
+        node = node.with_policy(GatePolicy::parse(&policy).ok_or_else(|| {
+          anyhow!(
+            "schedule_gate node '{}': unknown policy '{}' (expected wait, skip or error)",
+            node_def.id,
+            policy
+          )
+        })?);
+      }
+      if let Some(max_wait_ms) = params.get("max_wait_ms").and_then(|v| v.as_u64()) {
+        node = node.with_max_wait(Duration::from_millis(max_wait_ms));
+      }
+      let utc_offset = get_string_param_optional(params, "utc_offset");
+      if !utc_offset.is_empty() {
+        let offset = parse_utc_offset(&utc_offset)
+          .map_err(|err| anyhow!("schedule_gate node '{}': {}", node_def.id, err))?;
+        node = node.with_utc_offset(offset);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "throttle" => {
+      let params = &node_def.parameters;
+      let group = get_string_param_optional(params, "group");
+      let min_interval_ms = params.get("min_interval_ms").and_then(|v| v.as_u64());
+      let (false, Some(min_interval_ms)) = (group.is_empty(), min_interval_ms) else {
+        return Err(anyhow!(
+          "throttle node '{}' requires 'group' and a non-negative integer 'min_interval_ms'",
+          node_def.id
+        ));
+      };
+      let mut node =
+        ThrottleNode::new(&node_def.id, &group, Duration::from_millis(min_interval_ms));
+      match get_string_param_optional(params, "state_dir").as_str() {
+        "" => {}
+        "memory" => node = node.in_memory(),
+        state_dir => node = node.with_state_dir(state_dir),
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "tabular_read" => {
+      let params = &node_def.parameters;
+      let context = |err: String| anyhow!("tabular_read node '{}': {}", node_def.id, err);
+      let mut node = TabularReadNode::new(&node_def.id);
+      let format = get_string_param_optional(params, "format");
+      if !format.is_empty() {
+        node = node.with_format(TabularFormat::parse(&format).map_err(context)?);
+      }
+      if let Some(delimiter) = tabular_delimiter(params).map_err(context)? {
+        node = node.with_delimiter(delimiter);
+      }
+      if let Some(has_headers) = params.get("has_headers").and_then(|v| v.as_bool()) {
+        node = node.with_headers(has_headers);
+      }
+      if let Some(column_types) = params.get("column_types") {
+        let column_types: HashMap<String, String> = serde_yaml::from_value(column_types.clone())
+          .map_err(|err| context(format!("column_types must map columns to types: {err}")))?;
+        for (column, column_type) in column_types {
+          node = node.with_column_type(&column, ColumnType::parse(&column_type).map_err(context)?);
+        }
+      }
+      if let Some(max_rows) = params.get("max_rows").and_then(|v| v.as_u64()) {
+        node = node.with_max_rows(max_rows as usize);
+      }
+      let filter = get_string_param_optional(params, "filter");
+      if !filter.is_empty() {
+        node = node
+          .with_filter(&filter)
+          .map_err(|err| context(err.to_string()))?;
+      }
+      let sheet = get_string_param_optional(params, "sheet");
+      if !sheet.is_empty() {
+        node = node.with_sheet(&sheet);
+      }
+      if params.get("strict").and_then(|v| v.as_bool()) == Some(true) {
+        node = node.strict();
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "tabular_write" => {
+      let params = &node_def.parameters;
+      let context = |err: String| anyhow!("tabular_write node '{}': {}", node_def.id, err);
+      let mut node = TabularWriteNode::new(&node_def.id);
+      if let Some(delimiter) = tabular_delimiter(params).map_err(context)? {
+        node = node.with_delimiter(delimiter);
+      }
+      if let Some(has_headers) = params.get("has_headers").and_then(|v| v.as_bool()) {
+        node = node.with_headers(has_headers);
+      }
+      if let Some(columns) = crate::executor::command::string_list(params, "columns")
+        .map_err(|err| context(err.to_string()))?
+      {
+        node = node.with_columns(columns);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "document_render" => {
+      let params = &node_def.parameters;
+      let string_list = |key: &str| {
+        crate::executor::command::string_list(params, key)
+          .map_err(|err| anyhow!("document_render node '{}': {}", node_def.id, err))
+      };
+      let template = get_string_param_optional(params, "template");
+      let mut node = DocumentRenderNode::new(&node_def.id, &template)
+        .with_allowed_programs(crate::executor::command::command_allowlist());
+      if let Some(formats) = string_list("formats")? {
+        let formats = formats
+          .iter()
+          .map(|format| DocumentFormat::parse(format))
+          .collect::<std::result::Result<Vec<_>, _>>()
+          .map_err(|err| anyhow!("document_render node '{}': {}", node_def.id, err))?;
+        node = node.with_formats(formats);
+      }
+      let output_dir = get_string_param_optional(params, "output_dir");
+      if !output_dir.is_empty() {
+        node = node.with_output_dir(output_dir);
+      }
+      let file_stem = get_string_param_optional(params, "file_stem");
+      if !file_stem.is_empty() {
+        node = node.with_file_stem(&file_stem);
+      }
+      let title = get_string_param_optional(params, "title");
+      if !title.is_empty() {
+        node = node.with_title(&title);
+      }
+      let base_dir = get_string_param_optional(params, "base_dir");
+      if !base_dir.is_empty() {
+        node = node.with_base_dir(base_dir);
+      }
+      let stylesheet = get_string_param_optional(params, "stylesheet");
+      if !stylesheet.is_empty() {
+        node = node.with_stylesheet(&stylesheet);
+      }
+      let flag = |key: &str| params.get(key).and_then(|v| v.as_bool());
+      if let Some(toc) = flag("table_of_contents") {
+        node = node.with_table_of_contents(toc);
+      }
+      if let Some(embed) = flag("embed_images") {
+        node = node.with_embed_images(embed);
+      }
+      if let Some(argv) = string_list("pdf_command")? {
+        node = node.with_pdf_command(argv);
+      }
+      if let Some(timeout_ms) = params.get("pdf_timeout_ms").and_then(|v| v.as_u64()) {
+        node = node.with_pdf_timeout(Duration::from_millis(timeout_ms));
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "feed" => {
+      let params = &node_def.parameters;
+      let mut node = FeedNode::new(&node_def.id);
+      let since = get_string_param_optional(params, "since");
+      if !since.is_empty() {
+        node = node
+          .with_since_rfc3339(&since)
+          .map_err(|err| anyhow!("feed node '{}': {}", node_def.id, err))?;
+      }
+      let state_file = get_string_param_optional(params, "state_file");
+      if !state_file.is_empty() {
+        node = node.with_state_file(state_file);
+      }
+      if let Some(max_entries) = params.get("max_entries").and_then(|v| v.as_u64()) {
+        node = node.with_max_entries(max_entries as usize);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "web_extract" => {
+      let params = &node_def.parameters;
+      let mut node = WebExtractNode::new(&node_def.id);
+      if let Some(max_bytes) = params.get("max_bytes").and_then(|v| v.as_u64()) {
+        node = node.with_max_bytes(max_bytes as usize);
+      }
+      if let Some(types) = crate::executor::command::string_list(params, "allowed_content_types")
+        .map_err(|err| anyhow!("web_extract node '{}': {}", node_def.id, err))?
+      {
+        node = node.with_allowed_content_types(types);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "command" => {
+      let node = crate::executor::command::build_command_node(&node_def.id, &node_def.parameters)
+        .map_err(|err| anyhow!("command node '{}': {}", node_def.id, err))?
+        .with_process_env(process_env);
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
     "shell" => {
       // F-A7-2 closure: shell node wraps `agentflow_tools::ShellTool`
       // with a SandboxPolicy built from YAML params. `allowed_commands`
@@ -103,27 +591,71 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
 
       let output_format = get_string_param_optional(&node_def.parameters, "output_format");
       if !output_format.is_empty() {
+        validate_output_format(&output_format)
+          .map_err(|err| anyhow!("template node '{}': {}", node_def.id, err))?;
         node = node.with_format(&output_format);
       }
 
+      if let Some(strict) = node_def.parameters.get("strict").and_then(|v| v.as_bool()) {
+        node = node.with_strict(strict);
+      }
+
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "arxiv" => {
       let url = get_string_param_optional(&node_def.parameters, "url");
-      let fetch_source = node_def
-        .parameters
-        .get("fetch_source")
-        .and_then(|v| v.as_bool());
-      let simplify_latex = node_def
+      let mut node = ArxivNode::new(&node_def.id, &url);
+      let flag = |key: &str| node_def.parameters.get(key).and_then(|v| v.as_bool());
+      if let Some(fetch_source) = flag("fetch_source") {
+        node = node.with_fetch_source(fetch_source);
+      }
+      if let Some(simplify_latex) = flag("simplify_latex") {
+        node = node.with_simplify_latex(simplify_latex);
+      }
+      if let Some(full_text) = flag("full_text") {
+        node = node.with_full_text(full_text);
+      }
+      if let Some(cache) = flag("cache") {
+        node = node.with_cache(cache);
+      }
+      let cache_dir = get_string_param_optional(&node_def.parameters, "cache_dir");
+      if !cache_dir.is_empty() {
+        node = node.with_cache_dir(cache_dir);
+      }
+      if let Some(ttl) = node_def
         .parameters
-        .get("simplify_latex")
-        .and_then(|v| v.as_bool());
-      let node = ArxivNode {
-        name: node_def.id.clone(),
-        url,
-        fetch_source,
-        simplify_latex,
+        .get("latest_ttl_secs")
+        .and_then(|v| v.as_u64())
+      {
+        node = node.with_latest_ttl(std::time::Duration::from_secs(ttl));
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    "text_splitter" => {
+      let params = &node_def.parameters;
+      let strategy = get_string_param_optional(params, "strategy");
+      let strategy = if strategy.is_empty() {
+        Default::default()
+      } else {
+        strategy
+          .parse()
+          .map_err(|err| anyhow!("text_splitter node '{}': {}", node_def.id, err))?
       };
+      let mut node = TextSplitterNode::new(&node_def.id, strategy);
+      let size = |key: &str| params.get(key).and_then(|v| v.as_u64()).map(|v| v as usize);
+      if let Some(chunk_size) = size("chunk_size") {
+        node = node.with_chunk_size(chunk_size);
+      }
+      if let Some(chunk_overlap) = size("chunk_overlap") {
+        node = node.with_chunk_overlap(chunk_overlap);
+      }
+      if let Some(min_chunk_size) = size("min_chunk_size") {
+        node = node.with_min_chunk_size(min_chunk_size);
+      }
+      let model = get_string_param_optional(params, "model");
+      if !model.is_empty() {
+        node = node.with_model(&model);
+      }
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "asr" => {
@@ -133,10 +665,50 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "image_edit" => {
-      let model = get_string_param_optional(&node_def.parameters, "model");
-      let prompt = get_string_param_optional(&node_def.parameters, "prompt");
-      let image_source = get_string_param_optional(&node_def.parameters, "image_source");
-      let node = ImageEditNode::new(&node_def.id, &model, &prompt, &image_source);
+      let params = &node_def.parameters;
+      let model = get_string_param_optional(params, "model");
+      // Mapped (rather than literal) prompt / image fall back to the input
+      // of the same name.
+      let mut prompt = get_string_param_optional(params, "prompt");
+      if prompt.is_empty() {
+        prompt = "{{ prompt }}".to_string();
+      }
+      let mut image_source = get_string_param_optional(params, "image_source");
+      if image_source.is_empty() {
+        image_source = "image_source".to_string();
+      }
+      let mut node = ImageEditNode::new(&node_def.id, &model, &prompt, &image_source);
+      let size = get_string_param_optional(params, "size");
+      if !size.is_empty() {
+        node = node.with_size(&size);
+      }
+      let response_format = get_string_param_optional(params, "response_format");
+      if !response_format.is_empty() {
+        node = node.with_response_format(&response_format);
+      }
+      if let Some(seed) = params.get("seed").and_then(|v| v.as_i64()) {
+        let seed = i32::try_from(seed).map_err(|_| {
+          anyhow!(
+            "image_edit node '{}': seed {} is out of range",
+            node_def.id,
+            seed
+          )
+        })?;
+        node = node.with_seed(seed);
+      }
+      if let Some(steps) = params.get("steps").and_then(|v| v.as_u64()) {
+        node = node.with_steps(steps as u32);
+      }
+      if let Some(cfg_scale) = params.get("cfg_scale").and_then(|v| v.as_f64()) {
+        node = node.with_cfg_scale(cfg_scale as f32);
+      }
+      let output_path = get_string_param_optional(params, "output_path");
+      if !output_path.is_empty() {
+        node = node.with_output_path(&output_path);
+      }
+      if let Some(allow) = params.get("allow_filtered").and_then(|v| v.as_bool()) {
+        node = node.with_allow_filtered(allow);
+      }
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "image_to_image" => {
@@ -149,29 +721,169 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
     "image_understand" => {
       let model = get_string_param_optional(&node_def.parameters, "model");
       let text_prompt = get_string_param_optional(&node_def.parameters, "text_prompt");
-      let image_source = get_string_param_optional(&node_def.parameters, "image_source");
-      let node = ImageUnderstandNode::new(&node_def.id, &model, &text_prompt, &image_source);
+      let params = &node_def.parameters;
+      let image_source = get_string_param_optional(params, "image_source");
+      let strings = |key: &str| -> Vec<String> {
+        params
+          .get(key)
+          .and_then(|v| v.as_sequence())
+          .map(|items| {
+            items
+              .iter()
+              .filter_map(|item| item.as_str().map(str::to_string))
+              .collect()
+          })
+          .unwrap_or_default()
+      };
+      let images = strings("images");
+      let files = strings("files");
+      if !image_source.is_empty() && !images.is_empty() {
+        return Err(anyhow!(
+          "image_understand node '{}' takes only one of 'image_source' or 'images'",
+          node_def.id
+        ));
+      }
+      if image_source.is_empty() && images.is_empty() && files.is_empty() {
+        return Err(anyhow!(
+          "image_understand node '{}' requires 'image_source', 'images' or 'files'",
+          node_def.id
+        ));
+      }
+      let mut node = ImageUnderstandNode::new(&node_def.id, &model, &text_prompt, &image_source);
+      if image_source.is_empty() {
+        node = node.with_images(images);
+      }
+      if !files.is_empty() {
+        node = node.with_files(files);
+      }
+      let system_message = get_string_param_optional(params, "system_message");
+      if !system_message.is_empty() {
+        node = node.with_system_message(&system_message);
+      }
+      if let Some(temperature) = params.get("temperature").and_then(|v| v.as_f64()) {
+        node = node.with_temperature(temperature as f32);
+      }
+      if let Some(max_tokens) = params.get("max_tokens").and_then(|v| v.as_u64()) {
+        node = node.with_max_tokens(max_tokens as u32);
+      }
+      if let Some(max_bytes) = params.get("max_image_bytes").and_then(|v| v.as_u64()) {
+        node = node.with_max_image_bytes(max_bytes as usize);
+      }
+      // Local images and uploads are read from under the working directory
+      // unless the workflow names another.
+      let base_dir = get_string_param_optional(params, "base_dir");
+      node = node.with_base_dir(if base_dir.is_empty() {
+        "."
+      } else {
+        base_dir.as_str()
+      });
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "markmap" => {
       // markdown content will be provided via input_mapping at runtime
       let markdown = get_string_param_optional(&node_def.parameters, "markdown");
-      let mut node = MarkMapNode::new(node_def.id.clone(), markdown);
+      let params = &node_def.parameters;
+      let mut config = MarkMapConfig::default();
+      let title = get_string_param_optional(params, "title");
+      if !title.is_empty() {
+        config.title = Some(title);
+      }
+      let theme = get_string_param_optional(params, "theme");
+      if !theme.is_empty() {
+        config.theme = Some(theme);
+      }
+      if let Some(colors) = params.get("color_scheme").and_then(|v| v.as_sequence()) {
+        config.color_scheme = Some(
+          colors
+            .iter()
+            .filter_map(|c| c.as_str().map(str::to_string))
+            .collect(),
+        );
+      }
+      if let Some(level) = params.get("initial_expand_level").and_then(|v| v.as_i64()) {
+        config.initial_expand_level = Some(level.clamp(-1, i64::from(i8::MAX)) as i8);
+      }
+      if let Some(level) = params.get("color_freeze_level").and_then(|v| v.as_u64()) {
+        config.color_freeze_level = Some(level.min(u64::from(u8::MAX)) as u8);
+      }
+      if let Some(depth) = params.get("max_depth").and_then(|v| v.as_u64()) {
+        config.max_depth = Some(depth as usize);
+      }
+      if let Some(width) = params.get("max_width").and_then(|v| v.as_u64()) {
+        config.max_width = Some(width.min(u64::from(u32::MAX)) as u32);
+      }
+      let assets_dir = get_string_param_optional(params, "assets_dir");
+      if !assets_dir.is_empty() {
+        config.assets_dir = Some(assets_dir.into());
+      }
+      if let Some(svg) = params.get("svg").and_then(|v| v.as_bool()) {
+        config.render_svg = Some(svg);
+      }
+      let mut node = MarkMapNode::new(node_def.id.clone(), markdown).with_config(config);
 
       // Check if save_to_file parameter is provided
-      if let Some(save_path) = node_def
-        .parameters
-        .get("save_to_file")
-        .and_then(|v| v.as_str())
-      {
+      if let Some(save_path) = params.get("save_to_file").and_then(|v| v.as_str()) {
         node.save_to_file = Some(save_path.to_string());
       }
 
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "text_to_image" => {
-      let model = get_string_param_optional(&node_def.parameters, "model");
-      let node = TextToImageNode::new(&node_def.id, &model);
+      let params = &node_def.parameters;
+      let model = get_string_param_optional(params, "model");
+      let mut prompt = get_string_param_optional(params, "prompt");
+      if prompt.is_empty() {
+        prompt = "{{ prompt }}".to_string();
+      }
+      let mut node = TextToImageNode::new(&node_def.id, &model).with_prompt(&prompt);
+      let negative_prompt = get_string_param_optional(params, "negative_prompt");
+      if !negative_prompt.is_empty() {
+        node = node.with_negative_prompt(&negative_prompt);
+      }
+      let size = get_string_param_optional(params, "size");
+      if !size.is_empty() {
+        node = node.with_size(&size);
+      }
+      match get_string_param_optional(params, "response_format").as_str() {
+        "" | "b64_json" => {}
+        "url" => node = node.with_response_format(ImageResponseFormat::Url),
+        other => {
+          return Err(anyhow!(
+            "text_to_image node '{}': unknown response_format '{}' (expected b64_json or url)",
+            node_def.id,
+            other
+          ));
+        }
+      }
+      if let Some(steps) = params.get("steps").and_then(|v| v.as_u64()) {
+        node = node.with_steps(steps as u32);
+      }
+      if let Some(cfg_scale) = params.get("cfg_scale").and_then(|v| v.as_f64()) {
+        node = node.with_cfg_scale(cfg_scale as f32);
+      }
+      if let Some(n) = params.get("n").and_then(|v| v.as_u64()) {
+        node = node.with_count(n as u32);
+      }
+      if let Some(seed) = params.get("seed").and_then(|v| v.as_i64()) {
+        let seed = i32::try_from(seed).map_err(|_| {
+          anyhow!(
+            "text_to_image node '{}': seed {} is out of range",
+            node_def.id,
+            seed
+          )
+        })?;
+        node = node.with_seed(seed);
+      }
+      let output_path = get_string_param_optional(params, "output_path");
+      if !output_path.is_empty() {
+        node = node.with_output_path(&output_path);
+      }
+      if let Some(allow) = params.get("allow_filtered").and_then(|v| v.as_bool()) {
+        node = node.with_allow_filtered(allow);
+      }
+      if let Some(timeout_ms) = params.get("timeout_ms").and_then(|v| v.as_u64()) {
+        node = node.with_timeout(timeout_ms);
+      }
       Ok(NodeType::Standard(Arc::new(node)))
     }
     "tts" => {
@@ -264,7 +976,9 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
         .unwrap_or(serde_json::json!({}));
 
       // Create MCPNode
-      let mut node = MCPNode::new(server_command, tool_name).with_params(tool_params);
+      let mut node = MCPNode::new(server_command, tool_name)
+        .with_params(tool_params)
+        .with_process_env(process_env);
 
       // Optional timeout_ms
       if let Some(timeout) = node_def
@@ -286,6 +1000,219 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
 
       Ok(NodeType::Standard(Arc::new(node)))
     }
+    #[cfg(feature = "mcp")]
+    "mcp_tool" => {
+      let params = serde_json::to_value(&node_def.parameters)?;
+      let node = McpToolNode::from_params(&params)
+        .and_then(|node| node.with_process_env(process_env))
+        .map_err(|err| anyhow!("mcp_tool '{}': {}", node_def.id, err))?;
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    #[cfg(feature = "sql")]
+    "sql" => {
+      let params = &node_def.parameters;
+      let connection = params
+        .get("connection")
+        .map(|connection| -> Result<SecretValue> {
+          SecretValue::resolve(&serde_yaml::from_value(connection.clone())?)
+            .map_err(|err| anyhow!("sql node '{}': connection: {}", node_def.id, err))
+        })
+        .transpose()?
+        .filter(|connection| !connection.expose_secret().is_empty());
+      let sql = get_string_param_optional(params, "sql");
+      let (Some(connection), false) = (connection, sql.is_empty()) else {
+        return Err(anyhow!(
+          "sql node '{}' requires 'connection' and 'sql' parameters",
+          node_def.id
+        ));
+      };
+      let mut node = SqlNode::new(&node_def.id, connection, &sql);
+      let mode = get_string_param_optional(params, "mode");
+      if !mode.is_empty() {
+        let mode =
+          SqlMode::parse(&mode).map_err(|err| anyhow!("sql node '{}': {}", node_def.id, err))?;
+        node = node.with_mode(mode);
+      }
+      if let Some(timeout_ms) = params.get("timeout_ms").and_then(|v| v.as_u64()) {
+        node = node.with_timeout(Duration::from_millis(timeout_ms));
+      }
+      if let Some(max_rows) = params.get("max_rows").and_then(|v| v.as_u64()) {
+        node = node.with_max_rows(max_rows as usize);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    #[cfg(feature = "email")]
+    "email" => {
+      let params = &node_def.parameters;
+      let context = |err: String| anyhow!("email node '{}': {}", node_def.id, err);
+      let host = get_string_param_optional(params, "host");
+      let from = get_string_param_optional(params, "from");
+      let subject = get_string_param_optional(params, "subject");
+      if host.is_empty() || from.is_empty() || subject.is_empty() {
+        return Err(context(
+          "requires 'host', 'from' and 'subject' parameters".to_string(),
+        ));
+      }
+      let mut smtp = SmtpSettings::new(&host);
+      if let Some(port) = params.get("port").and_then(|v| v.as_u64()) {
+        let port =
+          u16::try_from(port).map_err(|_| context(format!("port {} is out of range", port)))?;
+        smtp = smtp.with_port(port);
+      }
+      let tls = get_string_param_optional(params, "tls");
+      if !tls.is_empty() {
+        smtp = smtp.with_tls(SmtpTls::parse(&tls).map_err(context)?);
+      }
+      let username = get_string_param_optional(params, "username");
+      if let Some(password) = params.get("password") {
+        let password = SecretValue::resolve(&serde_yaml::from_value(password.clone())?)
+          .map_err(|err| context(format!("password: {err}")))?;
+        smtp = smtp.with_credentials(&username, password);
+      }
+      if let Some(timeout_ms) = params.get("timeout_ms").and_then(|v| v.as_u64()) {
+        smtp = smtp.with_timeout(Duration::from_millis(timeout_ms));
+      }
+
+      let mut node = EmailNode::new(&node_def.id, smtp, &from, &subject);
+      let text = get_string_param_optional(params, "text");
+      if !text.is_empty() {
+        node = node.with_text(&text);
+      }
+      let html = get_string_param_optional(params, "html");
+      if !html.is_empty() {
+        node = node.with_html(&html);
+      }
+      if let Some(max_bytes) = params.get("max_attachment_bytes").and_then(|v| v.as_u64()) {
+        node = node.with_max_attachment_bytes(max_bytes);
+      }
+      if params.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+        node = node.dry_run();
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    #[cfg(feature = "git")]
+    "git" => {
+      let params = &node_def.parameters;
+      let context = |err: String| anyhow!("git node '{}': {}", node_def.id, err);
+      let operation =
+        GitOperation::parse(&get_string_param_optional(params, "operation")).map_err(context)?;
+      let mut node = GitNode::new(&node_def.id, operation);
+      if let Some(cwd) = process_env.cwd {
+        node = node.with_working_dir(cwd);
+      }
+      if let Some(depth) = params.get("depth").and_then(|v| v.as_u64()) {
+        node = node.with_depth(depth as u32);
+      }
+      if let Some(patterns) = crate::executor::command::string_list(params, "patterns")
+        .map_err(|err| context(err.to_string()))?
+      {
+        node = node.with_patterns(patterns);
+      }
+      if let Some(max_file_bytes) = params.get("max_file_bytes").and_then(|v| v.as_u64()) {
+        node = node.with_max_file_bytes(max_file_bytes);
+      }
+      if let Some(max_total_bytes) = params.get("max_total_bytes").and_then(|v| v.as_u64()) {
+        node = node.with_max_total_bytes(max_total_bytes);
+      }
+      if let Some(max_commits) = params.get("max_commits").and_then(|v| v.as_u64()) {
+        node = node.with_max_commits(max_commits as usize);
+      }
+      if let Some(token) = params.get("token") {
+        let token = SecretValue::resolve(&serde_yaml::from_value(token.clone())?)
+          .map_err(|err| context(format!("token: {err}")))?;
+        let username = get_string_param_optional(params, "username");
+        let username = if username.is_empty() {
+          "x-access-token".to_string()
+        } else {
+          username
+        };
+        node = node.with_token(&username, token);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    #[cfg(feature = "s3")]
+    "object_store" => {
+      let params = &node_def.parameters;
+      let context = |err: String| anyhow!("object_store node '{}': {}", node_def.id, err);
+      // `$secret` references, plain strings, or the standard AWS variables.
+      let credential = |key: &str, env: &str| -> Result<Option<SecretValue>> {
+        let value = match params.get(key) {
+          Some(value) => Some(
+            SecretValue::resolve(&serde_yaml::from_value(value.clone())?)
+              .map_err(|err| context(format!("{key}: {err}")))?,
+          ),
+          None => std::env::var(env).ok().map(SecretValue::from),
+        };
+        Ok(value.filter(|value| !value.expose_secret().is_empty()))
+      };
+      let operation = ObjectStoreOperation::parse(&get_string_param_optional(params, "operation"))
+        .map_err(context)?;
+      let bucket = get_string_param_optional(params, "bucket");
+      let (Some(access_key_id), Some(secret_access_key), false) = (
+        credential("access_key_id", "AWS_ACCESS_KEY_ID")?,
+        credential("secret_access_key", "AWS_SECRET_ACCESS_KEY")?,
+        bucket.is_empty(),
+      ) else {
+        return Err(context(
+          "requires 'bucket', 'access_key_id' and 'secret_access_key' (or AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY)"
+            .to_string(),
+        ));
+      };
+      let mut client = S3Client::new(&bucket, access_key_id, secret_access_key);
+      let region = get_string_param_optional(params, "region");
+      if !region.is_empty() {
+        client = client.with_region(&region);
+      }
+      let endpoint = get_string_param_optional(params, "endpoint");
+      if !endpoint.is_empty() {
+        client = client.with_endpoint(&endpoint);
+      }
+      if let Some(path_style) = params.get("path_style").and_then(|v| v.as_bool()) {
+        client = client.with_path_style(path_style);
+      }
+      if let Some(session_token) = credential("session_token", "AWS_SESSION_TOKEN")? {
+        client = client.with_session_token(session_token);
+      }
+
+      let key = get_string_param_optional(params, "key");
+      let mut node = ObjectStoreNode::new(&node_def.id, operation, &key, Arc::new(client));
+      if let Some(threshold) = params.get("multipart_threshold").and_then(|v| v.as_u64()) {
+        node = node.with_multipart_threshold(threshold);
+      }
+      if let Some(part_size) = params.get("part_size").and_then(|v| v.as_u64()) {
+        node = node.with_part_size(part_size);
+      }
+      let content_type = get_string_param_optional(params, "content_type");
+      if !content_type.is_empty() {
+        node = node.with_content_type(&content_type);
+      }
+      let content_encoding = get_string_param_optional(params, "content_encoding");
+      if !content_encoding.is_empty() {
+        node =
+          node.with_content_encoding(ContentEncoding::parse(&content_encoding).map_err(context)?);
+      }
+      if let Some(max_keys) = params.get("max_keys").and_then(|v| v.as_u64()) {
+        node = node.with_max_keys(max_keys as usize);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
+    #[cfg(feature = "rag")]
+    "embedding" => {
+      let params = &node_def.parameters;
+      let mut node = EmbeddingNode::new(&node_def.id);
+      let model = get_string_param_optional(params, "model");
+      if !model.is_empty() {
+        node = node.with_model(&model);
+      }
+      let texts_key = get_string_param_optional(params, "texts_key");
+      if !texts_key.is_empty() {
+        node = node.with_texts_key(&texts_key);
+      }
+      if let Some(batch_size) = params.get("batch_size").and_then(|v| v.as_u64()) {
+        node = node.with_batch_size(batch_size as usize);
+      }
+      Ok(NodeType::Standard(Arc::new(node)))
+    }
     #[cfg(feature = "rag")]
     "rag" => {
       // Extract operation (required)
@@ -350,7 +1277,28 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
     if node_def.node_type == "plugin" && (k == "manifest" || k == "node_type") {
       continue;
     }
-    let json_val: serde_json::Value = serde_yaml::from_value(v.clone())?;
+    // A batch node's `child` block is its per-item node definition.
+    if node_def.node_type == "batch" && k == "child" {
+      continue;
+    }
+    // Secret-bearing parameters were resolved into the node itself above.
+    if is_node_secret_param(&node_def.node_type, k) {
+      continue;
+    }
+    let mut json_val: serde_json::Value = serde_yaml::from_value(v.clone())?;
+    if node_def.node_type == "http"
+      && k == "headers"
+      && let Value::Object(headers) = &mut json_val
+    {
+      headers.retain(|_, value| !secret::is_secret_reference(value));
+    }
+    if secret::contains_secret_reference(&json_val) {
+      return Err(anyhow!(
+        "node '{}': parameter '{}' holds a $secret reference, but only llm `api_key`, http `auth` / `headers`, sql `connection`, git `token`, email `password` and object_store credentials accept secrets",
+        node_def.id,
+        k
+      ));
+    }
     let flow_value = agentflow_core::value::FlowValue::Json(json_val);
     initial_inputs.insert(k.clone(), flow_value);
   }
@@ -365,6 +1313,69 @@ pub fn create_graph_node(node_def: &NodeDefinitionV2) -> Result<GraphNode> {
   })
 }
 
+/// A tabular node's single-byte `delimiter` (`\t` may be written `tab`).
+fn tabular_delimiter(
+  params: &HashMap<String, serde_yaml::Value>,
+) -> std::result::Result<Option<u8>, String> {
+  match get_string_param_optional(params, "delimiter").as_str() {
+    "" => Ok(None),
+    "tab" | "\\t" => Ok(Some(b'\t')),
+    delimiter if delimiter.len() == 1 => Ok(Some(delimiter.as_bytes()[0])),
+    delimiter => Err(format!(
+      "delimiter '{delimiter}' must be a single ASCII character"
+    )),
+  }
+}
+
+/// Parameters the factory resolves into a [`SecretValue`] on the node, and
+/// so never forwards as plaintext inputs.
+fn is_node_secret_param(node_type: &str, key: &str) -> bool {
+  matches!(
+    (node_type, key),
+    ("llm", "api_key")
+      | ("http", "auth")
+      | ("sql", "connection")
+      | ("git", "token")
+      | ("email", "password")
+      | (
+        "object_store",
+        "access_key_id" | "secret_access_key" | "session_token"
+      )
+  )
+}
+
+/// The node's `env:` and `cwd:`, resolved; empty for a node without them.
+fn process_env(node_def: &NodeDefinitionV2) -> Result<ProcessEnv> {
+  if node_def.env.is_empty() && node_def.cwd.is_none() {
+    return Ok(ProcessEnv::default());
+  }
+  if let Some(problem) = unsupported_process_env(node_def) {
+    return Err(anyhow!("node '{}': {}", node_def.id, problem));
+  }
+  let env = node_def
+    .env
+    .iter()
+    .map(|(name, value)| Ok((name.clone(), serde_yaml::from_value(value.clone())?)))
+    .collect::<Result<BTreeMap<String, Value>>>()?;
+  ProcessEnv::resolve(&env, node_def.cwd.as_deref())
+    .map_err(|err| anyhow!("node '{}': {}", node_def.id, err))
+}
+
+/// `headers` entries of an http node whose value is a `$secret` reference.
+fn secret_headers(node_def: &NodeDefinitionV2) -> Result<Vec<(String, Value)>> {
+  let Some(headers) = node_def.parameters.get("headers") else {
+    return Ok(Vec::new());
+  };
+  let headers: Value = serde_yaml::from_value(headers.clone())?;
+  Ok(match headers {
+    Value::Object(headers) => headers
+      .into_iter()
+      .filter(|(_, value)| secret::is_secret_reference(value))
+      .collect(),
+    _ => Vec::new(),
+  })
+}
+
 #[derive(Debug, Clone)]
 struct SkillAgentWorkflowNode {
   name: String,
@@ -513,3 +1524,138 @@ fn build_skill_agent_outputs(
   outputs.insert("agent_resume".to_string(), FlowValue::Json(agent_resume));
   Ok(outputs)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn node_def(yaml: &str) -> NodeDefinitionV2 {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  #[test]
+  fn secret_parameters_resolve_into_the_node_not_its_inputs() {
+    // SAFETY: the variable name is unique to this test.
+    unsafe { std::env::set_var("AGENTFLOW_FACTORY_TEST_TOKEN", "tok-factory-2468") };
+    let node = create_graph_node(&node_def(
+      r#"
+id: fetch
+type: http
+parameters:
+  url: https://example.com
+  auth: { type: bearer, token: { $secret: AGENTFLOW_FACTORY_TEST_TOKEN } }
+  headers:
+    accept: application/json
+    x-api-key: { $secret: AGENTFLOW_FACTORY_TEST_TOKEN }
+"#,
+    ))
+    .unwrap();
+
+    assert!(!node.initial_inputs.contains_key("auth"));
+    assert_eq!(
+      node.initial_inputs["headers"],
+      FlowValue::Json(json!({ "accept": "application/json" }))
+    );
+
+    let Err(err) = create_graph_node(&node_def(
+      r#"
+id: run
+type: template
+parameters:
+  template: "{{ token }}"
+  token: { $secret: AGENTFLOW_FACTORY_TEST_TOKEN }
+"#,
+    )) else {
+      panic!("a misplaced $secret must fail");
+    };
+    assert!(
+      format!("{err:#}").contains("holds a $secret reference"),
+      "{err:#}"
+    );
+
+    let Err(err) = create_graph_node(&node_def(
+      r#"
+id: answer
+type: llm
+parameters:
+  prompt: hi
+  api_key: { $secret: AGENTFLOW_FACTORY_TEST_UNSET }
+"#,
+    )) else {
+      panic!("an unset secret variable must fail");
+    };
+    assert!(format!("{err:#}").contains("is not set"), "{err:#}");
+  }
+
+  #[tokio::test]
+  async fn command_env_reaches_the_child_and_secrets_stay_out_of_the_run_dir() {
+    use agentflow_core::{Flow, FlowExecutionConfig, FlowExt};
+
+    // SAFETY: the variable names are unique to this test.
+    unsafe {
+      std::env::set_var("AGENTFLOW_FACTORY_ENV_TOKEN", "tok-env-redaction-1357");
+      std::env::set_var("AGENTFLOW_FACTORY_ENV_REGION", "eu-west-1");
+    }
+    let base = std::env::temp_dir().join(format!("agentflow-factory-env-{}", std::process::id()));
+    let workdir = base.join("work");
+    std::fs::create_dir_all(&workdir).unwrap();
+    let node = create_graph_node(&node_def(&format!(
+      r#"
+id: deploy
+type: command
+parameters:
+  program: 'echo "$REGION $TOKEN" && pwd'
+  allow_shell: true
+env:
+  REGION: "${{AGENTFLOW_FACTORY_ENV_REGION}}"
+  TOKEN: {{ $secret: AGENTFLOW_FACTORY_ENV_TOKEN }}
+cwd: {}
+"#,
+      workdir.display()
+    )))
+    .unwrap();
+
+    let runs = base.join("runs");
+    let state = Flow::new(vec![node])
+      .execute_from_inputs_with_config(
+        HashMap::new(),
+        FlowExecutionConfig::serial().with_run_base_dir(&runs),
+      )
+      .await
+      .unwrap();
+
+    // The child saw the real values, and ran in `cwd`.
+    let outputs = state["deploy"].as_ref().unwrap();
+    let FlowValue::Json(Value::String(stdout)) = &outputs["stdout"] else {
+      panic!("stdout should be a string");
+    };
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("eu-west-1 tok-env-redaction-1357"));
+    assert_eq!(
+      std::fs::canonicalize(lines.next().unwrap()).unwrap(),
+      std::fs::canonicalize(&workdir).unwrap()
+    );
+
+    // The persisted step JSON masks the secret but not the plain value.
+    let run_dir = std::fs::read_dir(&runs)
+      .unwrap()
+      .next()
+      .unwrap()
+      .unwrap()
+      .path();
+    let persisted = std::fs::read_to_string(run_dir.join("deploy_outputs.json")).unwrap();
+    assert!(!persisted.contains("tok-env-redaction-1357"), "{persisted}");
+    assert!(persisted.contains("eu-west-1 ***"), "{persisted}");
+    std::fs::remove_dir_all(&base).ok();
+
+    let Err(err) = create_graph_node(&node_def(
+      "id: render\ntype: template\nparameters:\n  template: hi\nenv:\n  A: b\n",
+    )) else {
+      panic!("a node that spawns nothing must refuse `env`");
+    };
+    assert!(
+      format!("{err:#}").contains("'env' applies only to"),
+      "{err:#}"
+    );
+  }
+}
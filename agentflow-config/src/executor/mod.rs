@@ -1,3 +1,5 @@
+pub mod batch;
+pub mod command;
 pub mod factory;
 pub mod multi_agent;
 pub mod shell;
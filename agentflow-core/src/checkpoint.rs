@@ -158,10 +158,14 @@ impl CheckpointManager {
     let checkpoint_path = workflow_dir.join(&filename);
     let temp_path = workflow_dir.join(format!(".{}.tmp", filename));
 
-    // Serialize checkpoint
-    let json = serde_json::to_string_pretty(&checkpoint).map_err(|e| {
+    // Serialize checkpoint; secrets a node echoed into its outputs never
+    // reach the checkpoint directory.
+    let serialization_error = |e: serde_json::Error| {
       AgentFlowError::SerializationError(format!("Failed to serialize checkpoint: {}", e))
-    })?;
+    };
+    let mut value = serde_json::to_value(checkpoint).map_err(serialization_error)?;
+    crate::secret::redact_value(&mut value);
+    let json = serde_json::to_string_pretty(&value).map_err(serialization_error)?;
 
     // Write to temporary file (atomic operation)
     let mut file =
@@ -452,6 +456,29 @@ mod tests {
     assert_eq!(checkpoint.status, WorkflowStatus::Running);
   }
 
+  #[tokio::test]
+  async fn test_checkpoint_redacts_live_secrets() {
+    let (manager, temp_dir) = create_test_manager();
+    let secret = crate::secret::SecretValue::new("sk-checkpoint-redaction-7f3a");
+
+    let mut state = HashMap::new();
+    state.insert(
+      "fetch".to_string(),
+      serde_json::json!({"error": format!("401 for token {}", secret.expose_secret())}),
+    );
+    manager
+      .save_checkpoint("secret_workflow", "fetch", &state)
+      .await
+      .unwrap();
+
+    let latest = temp_dir
+      .path()
+      .join("secret_workflow")
+      .join("checkpoint_latest.json");
+    let written = std::fs::read_to_string(latest).unwrap();
+    assert!(!written.contains(secret.expose_secret()), "{written}");
+  }
+
   #[tokio::test]
   async fn test_multiple_checkpoints() {
     let (manager, _temp_dir) = create_test_manager();
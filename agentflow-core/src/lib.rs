@@ -11,8 +11,17 @@
 // inside core and downstream — keeps compiling unchanged. The `Flow` orchestrator
 // + scheduler stay here for now (sub-step 2 moves the `Flow` *type* to graph).
 pub use agentflow_graph::{async_node, error, expr, node};
+// V1 `SharedState` / prep-exec-post compatibility layer (graph-side, since it
+// only builds IR). Surfaced at the root too so legacy `agentflow_core::SharedState`
+// imports resolve again.
+pub use agentflow_graph::legacy;
 pub mod error_context;
 pub mod flow;
+// Static inspection of a `Flow` (validate / dry-run plan / Mermaid + DOT
+// export) is IR-only, so it lives in graph alongside the type.
+pub use agentflow_graph::inspect;
+// Structural comparison of two `Flow`s, for reviewing workflow edits.
+pub use agentflow_graph::diff;
 
 // `FlowValue` lives in the `agentflow-value` leaf crate (P-A1.5); also re-exported
 // transitively by `agentflow-graph`. Surface it under the original
@@ -22,6 +31,7 @@ pub use agentflow_value as value;
 // Execution engine
 pub mod concurrency;
 pub mod health;
+pub mod node_context;
 // `retry` + `timeout` combinators moved to `agentflow-async-util` (P-A1.4);
 // `race_with_limits` added there (P-A3.2). Re-export under their original
 // `agentflow_core::{retry,timeout}` paths (+ `race`).
@@ -42,6 +52,8 @@ pub mod resource_limits;
 pub mod resource_manager;
 pub mod resume;
 pub mod scheduler;
+// `$secret` config references and run-output redaction.
+pub mod secret;
 pub mod state_monitor;
 
 // `state_size` (StateSizeObserver) and `events` (EventListener / WorkflowEvent)
@@ -63,11 +75,14 @@ pub mod plugin;
 pub use async_node::AsyncNode;
 pub use checkpoint::{Checkpoint, CheckpointConfig, CheckpointManager, WorkflowStatus};
 pub use concurrency::{ConcurrencyConfig, ConcurrencyLimiter, ConcurrencyStats};
+pub use diff::{FlowDiff, NodeChange, ValueChange};
 pub use error::{AgentFlowError, Result};
 pub use error_context::{ErrorContext, ErrorInfo};
 pub use events::{ConsoleListener, EventListener, MultiListener, NoOpListener, WorkflowEvent};
 pub use flow::{Flow, FlowExt, GraphNode, NodeType};
 pub use health::{HealthChecker, HealthReport, HealthStatus};
+pub use inspect::{ExecutionPlan, FlowValidation, PlanStatus, PlannedNode};
+pub use legacy::{AsyncFlow, LegacyAsyncNode, SharedState, SharedStateAdapterNode};
 pub use node::Node;
 pub use resource_limits::ResourceLimits;
 pub use resource_manager::{CombinedResourceStats, ResourceManager, ResourceManagerConfig};
@@ -78,6 +93,7 @@ pub use resume::{
 pub use retry::{ErrorPattern, RetryContext, RetryPolicy, RetryStrategy};
 pub use retry_executor::{execute_with_retry, execute_with_retry_and_context};
 pub use scheduler::{FlowCancellationToken, FlowExecutionConfig, FlowExecutionMode};
+pub use secret::SecretValue;
 pub use state_monitor::{ResourceAlert, ResourceStats, StateMonitor};
 pub use state_size::{StateSizeObserver, estimated_state_pool_bytes};
 pub use value::FlowValue;
@@ -0,0 +1,218 @@
+//! Ambient context for the node currently being executed.
+//!
+//! `AsyncNode::execute` only sees its inputs. Long-running nodes (webhook
+//! waits, pollers) additionally need to tell observers what they are doing
+//! and to stop when the flow is cancelled, so the executor scopes every node
+//! future in a task-local [`NodeContext`] carrying the run id, node id, the
+//! flow's [`EventListener`], its [`FlowCancellationToken`], the run
+//! directory (for nodes that write artifacts next to the run's outputs) and
+//! the flow's deadline, if it has one.
+//!
+//! Outside a flow (unit tests, direct `execute` calls) there is no context:
+//! [`NodeContext::current`] returns `None`, [`emit_progress`] is a no-op,
+//! [`cancelled`] never resolves and [`run_dir`] and [`remaining_budget`] are
+//! `None`.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::events::{EventListener, WorkflowEvent};
+use crate::scheduler::FlowCancellationToken;
+
+/// How often [`cancelled`] re-checks the (poll-only) cancellation flag.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+tokio::task_local! {
+  static NODE_CONTEXT: NodeContext;
+}
+
+#[derive(Clone)]
+pub struct NodeContext {
+  pub workflow_id: String,
+  pub node_id: String,
+  listener: Option<Arc<dyn EventListener>>,
+  cancellation: Option<FlowCancellationToken>,
+  run_dir: Option<PathBuf>,
+  deadline: Option<Instant>,
+}
+
+impl std::fmt::Debug for NodeContext {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("NodeContext")
+      .field("workflow_id", &self.workflow_id)
+      .field("node_id", &self.node_id)
+      .field("has_listener", &self.listener.is_some())
+      .field("cancellation", &self.cancellation)
+      .field("run_dir", &self.run_dir)
+      .field("deadline", &self.deadline)
+      .finish()
+  }
+}
+
+impl NodeContext {
+  /// A context for `node_id`. Without its own token the context inherits
+  /// the enclosing one, so nodes inside Map/While bodies still observe the
+  /// outer flow's cancellation; the run directory and the deadline are
+  /// inherited likewise.
+  pub fn new(
+    workflow_id: &str,
+    node_id: &str,
+    listener: Option<Arc<dyn EventListener>>,
+    cancellation: Option<FlowCancellationToken>,
+  ) -> Self {
+    let enclosing = Self::current();
+    let cancellation = cancellation.or_else(|| {
+      enclosing
+        .as_ref()
+        .and_then(|context| context.cancellation.clone())
+    });
+    Self {
+      workflow_id: workflow_id.to_string(),
+      node_id: node_id.to_string(),
+      listener,
+      cancellation,
+      run_dir: enclosing
+        .as_ref()
+        .and_then(|context| context.run_dir.clone()),
+      deadline: enclosing.and_then(|context| context.deadline),
+    }
+  }
+
+  /// Sets the directory the run persists its outputs to.
+  pub fn with_run_dir(mut self, run_dir: impl Into<PathBuf>) -> Self {
+    self.run_dir = Some(run_dir.into());
+    self
+  }
+
+  pub fn run_dir(&self) -> Option<&Path> {
+    self.run_dir.as_deref()
+  }
+
+  /// Sets the instant the flow's `with_deadline` budget runs out.
+  pub fn with_deadline(mut self, deadline: Instant) -> Self {
+    self.deadline = Some(deadline);
+    self
+  }
+
+  pub fn deadline(&self) -> Option<Instant> {
+    self.deadline
+  }
+
+  /// Time left before the flow's deadline; zero once it has passed.
+  pub fn remaining_budget(&self) -> Option<Duration> {
+    self
+      .deadline
+      .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+  }
+
+  /// The context of the node running on this task, if any.
+  pub fn current() -> Option<Self> {
+    NODE_CONTEXT.try_with(Clone::clone).ok()
+  }
+
+  /// Runs `work` with `self` as the current context.
+  pub async fn scope<F: Future>(self, work: F) -> F::Output {
+    NODE_CONTEXT.scope(self, work).await
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self
+      .cancellation
+      .as_ref()
+      .is_some_and(FlowCancellationToken::is_cancelled)
+  }
+
+  /// Emits a [`WorkflowEvent::NodeProgress`] to the flow's listener.
+  pub fn emit_progress(&self, detail: serde_json::Value) {
+    if let Some(listener) = &self.listener {
+      listener.on_event(&WorkflowEvent::NodeProgress {
+        workflow_id: self.workflow_id.clone(),
+        node_id: self.node_id.clone(),
+        detail,
+        timestamp: Instant::now(),
+      });
+    }
+  }
+}
+
+/// [`NodeContext::emit_progress`] on the current context, if any.
+pub fn emit_progress(detail: serde_json::Value) {
+  if let Some(context) = NodeContext::current() {
+    context.emit_progress(detail);
+  }
+}
+
+/// Whether the flow running the current node has been cancelled.
+pub fn is_cancelled() -> bool {
+  NodeContext::current().is_some_and(|context| context.is_cancelled())
+}
+
+/// The current run's directory, if the node runs inside a flow.
+pub fn run_dir() -> Option<PathBuf> {
+  NodeContext::current().and_then(|context| context.run_dir)
+}
+
+/// Time left before the current flow's deadline, if the node runs inside a
+/// flow built with `Flow::with_deadline`.
+pub fn remaining_budget() -> Option<Duration> {
+  NodeContext::current().and_then(|context| context.remaining_budget())
+}
+
+/// Resolves once the current flow is cancelled; pending forever when the
+/// node runs without a cancellation token. Meant for `tokio::select!`
+/// against a node's own wait.
+pub async fn cancelled() {
+  let Some(token) = NodeContext::current().and_then(|context| context.cancellation) else {
+    return std::future::pending().await;
+  };
+  while !token.is_cancelled() {
+    tokio::time::sleep(CANCELLATION_POLL_INTERVAL).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+
+  #[derive(Default)]
+  struct Recorder(Mutex<Vec<String>>);
+
+  impl EventListener for Recorder {
+    fn on_event(&self, event: &WorkflowEvent) {
+      self.0.lock().unwrap().push(event.to_string());
+    }
+  }
+
+  #[tokio::test]
+  async fn scoped_context_reports_progress_and_cancellation() {
+    let recorder = Arc::new(Recorder::default());
+    let token = FlowCancellationToken::new();
+    let context = NodeContext::new("run", "wait", Some(recorder.clone()), Some(token.clone()))
+      .with_run_dir("/tmp/runs/run");
+
+    context
+      .scope(async {
+        emit_progress(serde_json::json!({ "url": "http://127.0.0.1:1/hook" }));
+        assert!(!is_cancelled());
+        // A nested context without its own token keeps the outer one.
+        NodeContext::new("run", "inner", None, None)
+          .scope(async {
+            assert_eq!(run_dir(), Some(PathBuf::from("/tmp/runs/run")));
+            token.cancel();
+            cancelled().await;
+            assert!(is_cancelled());
+          })
+          .await;
+      })
+      .await;
+
+    assert_eq!(
+      *recorder.0.lock().unwrap(),
+      vec![r#"Node 'wait' progress: {"url":"http://127.0.0.1:1/hook"}"#.to_string()]
+    );
+    assert!(NodeContext::current().is_none());
+    assert!(!is_cancelled());
+  }
+}
@@ -13,6 +13,7 @@ use agentflow_graph::FlowRunner;
 use agentflow_graph::async_node::{AsyncNodeInputs, AsyncNodeResult};
 use agentflow_graph::error::AgentFlowError;
 use agentflow_graph::flow::Flow;
+use agentflow_graph::runner::FlowCancellationToken;
 
 use crate::FlowExt;
 use crate::scheduler::FlowExecutionConfig;
@@ -52,4 +53,14 @@ impl FlowRunner for CoreFlowRunner {
       .execute_from_inputs_with_config(inputs, self.config.clone())
       .await
   }
+
+  async fn run_with_cancellation(
+    &self,
+    flow: &Flow,
+    inputs: AsyncNodeInputs,
+    token: FlowCancellationToken,
+  ) -> Result<HashMap<String, AsyncNodeResult>, AgentFlowError> {
+    let config = self.config.clone().with_cancellation_token(token);
+    flow.execute_from_inputs_with_config(inputs, config).await
+  }
 }
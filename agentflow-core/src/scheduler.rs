@@ -1,10 +1,6 @@
-use std::{
-  path::PathBuf,
-  sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering},
-  },
-};
+use std::path::PathBuf;
+
+pub use agentflow_graph::runner::FlowCancellationToken;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FlowExecutionMode {
@@ -61,39 +57,3 @@ impl Default for FlowExecutionConfig {
     }
   }
 }
-
-/// Process-local cancellation signal for Flow execution.
-#[derive(Debug, Clone)]
-pub struct FlowCancellationToken {
-  cancelled: Arc<AtomicBool>,
-}
-
-impl FlowCancellationToken {
-  pub fn new() -> Self {
-    Self {
-      cancelled: Arc::new(AtomicBool::new(false)),
-    }
-  }
-
-  pub fn cancel(&self) {
-    self.cancelled.store(true, Ordering::SeqCst);
-  }
-
-  pub fn is_cancelled(&self) -> bool {
-    self.cancelled.load(Ordering::SeqCst)
-  }
-}
-
-impl Default for FlowCancellationToken {
-  fn default() -> Self {
-    Self::new()
-  }
-}
-
-impl PartialEq for FlowCancellationToken {
-  fn eq(&self, other: &Self) -> bool {
-    Arc::ptr_eq(&self.cancelled, &other.cancelled) || self.is_cancelled() == other.is_cancelled()
-  }
-}
-
-impl Eq for FlowCancellationToken {}
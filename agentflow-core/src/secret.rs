@@ -0,0 +1,257 @@
+//! Secret values in node configuration.
+//!
+//! Config values written as `{"$secret": "ENV_NAME"}` (an environment
+//! variable) or `{"$secret_file": "/path"}` (a file, trailing newline
+//! trimmed) are resolved once, when the node is constructed, into a
+//! [`SecretValue`]. The plaintext lives in a zeroizing buffer, and `Debug`,
+//! `Display` and `Serialize` all render it as [`REDACTED`].
+//!
+//! Every live secret is also registered for masking: [`redact_value`] and
+//! [`redact_text`] replace any occurrence of it with [`REDACTED`], which is
+//! how the executor keeps secrets a node echoes back (an `Authorization`
+//! header, a connection URL in an error) out of the run directory. A secret
+//! stops being masked once every clone of it is dropped.
+
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use zeroize::Zeroizing;
+
+use crate::error::AgentFlowError;
+
+/// What secrets render as.
+pub const REDACTED: &str = "***";
+
+const ENV_REFERENCE: &str = "$secret";
+const FILE_REFERENCE: &str = "$secret_file";
+
+/// Secrets shorter than this are not masked in text: replacing every
+/// occurrence of a two-letter string would shred unrelated output.
+const MIN_MASKED_LEN: usize = 4;
+
+static LIVE_SECRETS: LazyLock<Mutex<Vec<Weak<Zeroizing<String>>>>> =
+  LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// A resolved secret. Cheap to clone; the plaintext is zeroized when the
+/// last clone is dropped.
+#[derive(Clone)]
+pub struct SecretValue(Arc<Zeroizing<String>>);
+
+impl SecretValue {
+  /// Wraps (and registers for masking) an already-resolved secret.
+  pub fn new(secret: impl Into<String>) -> Self {
+    let secret = Arc::new(Zeroizing::new(secret.into()));
+    if secret.len() >= MIN_MASKED_LEN {
+      let mut live = LIVE_SECRETS.lock().unwrap_or_else(|e| e.into_inner());
+      live.retain(|weak| weak.strong_count() > 0);
+      live.push(Arc::downgrade(&secret));
+    }
+    Self(secret)
+  }
+
+  /// Resolves `{"$secret": ..}` / `{"$secret_file": ..}`; `Ok(None)` when
+  /// `value` is not a secret reference.
+  pub fn from_reference(value: &Value) -> Result<Option<Self>, AgentFlowError> {
+    let Some((kind, target)) = reference(value) else {
+      return Ok(None);
+    };
+    let target = target
+      .as_str()
+      .ok_or_else(|| AgentFlowError::ConfigurationError {
+        message: format!("'{kind}' must name a string, got {target}"),
+      })?;
+    let secret = if kind == ENV_REFERENCE {
+      std::env::var(target).map_err(|_| AgentFlowError::ConfigurationError {
+        message: format!("secret environment variable '{target}' is not set"),
+      })?
+    } else {
+      let mut contents =
+        std::fs::read_to_string(target).map_err(|e| AgentFlowError::ConfigurationError {
+          message: format!("failed to read secret file '{target}': {e}"),
+        })?;
+      let trimmed = contents.trim_end_matches(['\r', '\n']).len();
+      contents.truncate(trimmed);
+      contents
+    };
+    Ok(Some(Self::new(secret)))
+  }
+
+  /// Resolves a secret reference, or wraps a literal string so it is
+  /// redacted all the same.
+  pub fn resolve(value: &Value) -> Result<Self, AgentFlowError> {
+    match value {
+      Value::String(literal) => Ok(Self::new(literal.clone())),
+      other => Self::from_reference(other)?.ok_or_else(|| AgentFlowError::ConfigurationError {
+        message: format!(
+          "expected a string or a {{\"{ENV_REFERENCE}\": ..}} / {{\"{FILE_REFERENCE}\": ..}} reference, got {other}"
+        ),
+      }),
+    }
+  }
+
+  /// The plaintext. Keep the borrow short and never log it.
+  pub fn expose_secret(&self) -> &str {
+    self.0.as_str()
+  }
+}
+
+impl From<&str> for SecretValue {
+  fn from(secret: &str) -> Self {
+    Self::new(secret)
+  }
+}
+
+impl From<String> for SecretValue {
+  fn from(secret: String) -> Self {
+    Self::new(secret)
+  }
+}
+
+impl PartialEq for SecretValue {
+  fn eq(&self, other: &Self) -> bool {
+    self.expose_secret() == other.expose_secret()
+  }
+}
+
+impl Eq for SecretValue {}
+
+impl std::hash::Hash for SecretValue {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.expose_secret().hash(state);
+  }
+}
+
+impl fmt::Debug for SecretValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(REDACTED)
+  }
+}
+
+impl fmt::Display for SecretValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(REDACTED)
+  }
+}
+
+impl Serialize for SecretValue {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+  }
+}
+
+fn reference(value: &Value) -> Option<(&'static str, &Value)> {
+  let Value::Object(object) = value else {
+    return None;
+  };
+  if object.len() != 1 {
+    return None;
+  }
+  [ENV_REFERENCE, FILE_REFERENCE]
+    .into_iter()
+    .find_map(|kind| object.get(kind).map(|target| (kind, target)))
+}
+
+/// Whether `value` is a `$secret` / `$secret_file` reference.
+pub fn is_secret_reference(value: &Value) -> bool {
+  reference(value).is_some()
+}
+
+/// Whether `value` is, or contains, a secret reference.
+pub fn contains_secret_reference(value: &Value) -> bool {
+  reference(value).is_some()
+    || match value {
+      Value::Array(items) => items.iter().any(contains_secret_reference),
+      Value::Object(object) => object.values().any(contains_secret_reference),
+      _ => false,
+    }
+}
+
+/// Replaces every live secret in `text` with [`REDACTED`].
+pub fn redact_text(text: &str) -> String {
+  let live = LIVE_SECRETS.lock().unwrap_or_else(|e| e.into_inner());
+  let mut secrets: Vec<_> = live.iter().filter_map(Weak::upgrade).collect();
+  drop(live);
+  // Longest first, so a secret containing another is masked whole.
+  secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+  let mut text = text.to_string();
+  for secret in secrets {
+    if text.contains(secret.as_str()) {
+      text = text.replace(secret.as_str(), REDACTED);
+    }
+  }
+  text
+}
+
+/// Replaces every live secret in the strings (and object keys) of `value`.
+pub fn redact_value(value: &mut Value) {
+  match value {
+    Value::String(text) => {
+      let redacted = redact_text(text);
+      if redacted != *text {
+        *text = redacted;
+      }
+    }
+    Value::Array(items) => items.iter_mut().for_each(redact_value),
+    Value::Object(object) => {
+      let entries = std::mem::take(object);
+      for (key, mut item) in entries {
+        redact_value(&mut item);
+        object.insert(redact_text(&key), item);
+      }
+    }
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn resolves_env_and_file_references() {
+    // SAFETY: the variable name is unique to this test.
+    unsafe { std::env::set_var("AGENTFLOW_SECRET_TEST_TOKEN", "tok-env-1234") };
+    let secret = SecretValue::from_reference(&json!({ "$secret": "AGENTFLOW_SECRET_TEST_TOKEN" }))
+      .unwrap()
+      .unwrap();
+    assert_eq!(secret.expose_secret(), "tok-env-1234");
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("token");
+    std::fs::write(&path, "tok-file-5678\n").unwrap();
+    let secret = SecretValue::resolve(&json!({ "$secret_file": path })).unwrap();
+    assert_eq!(secret.expose_secret(), "tok-file-5678");
+
+    assert!(
+      SecretValue::from_reference(&json!({ "$secret": "AGENTFLOW_SECRET_TEST_UNSET" })).is_err()
+    );
+    assert!(
+      SecretValue::from_reference(&json!({ "name": "x" }))
+        .unwrap()
+        .is_none()
+    );
+  }
+
+  #[test]
+  fn renders_and_masks_as_redacted() {
+    let secret = SecretValue::new("sk-live-abcdef");
+    assert_eq!(format!("{secret:?} {secret}"), "*** ***");
+    assert_eq!(serde_json::to_value(&secret).unwrap(), json!("***"));
+
+    let mut value = json!({
+      "headers": { "authorization": "Bearer sk-live-abcdef" },
+      "sk-live-abcdef": ["sk-live-abcdef", 3],
+    });
+    redact_value(&mut value);
+    assert_eq!(
+      value,
+      json!({ "headers": { "authorization": "Bearer ***" }, "***": ["***", 3] })
+    );
+
+    drop(secret);
+    assert_eq!(redact_text("sk-live-abcdef"), "sk-live-abcdef");
+  }
+}
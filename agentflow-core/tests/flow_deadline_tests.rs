@@ -0,0 +1,177 @@
+//! Flow-level deadline behaviour: the run stops once `Flow::with_deadline`'s
+//! budget is spent, nodes see the remaining budget, and every node that never
+//! finished is persisted as timed out.
+
+use agentflow_core::FlowExt;
+use agentflow_core::{
+  AgentFlowError,
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  flow::{Flow, GraphNode, NodeType},
+  node_context,
+  scheduler::FlowExecutionConfig,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Sleeps for `millis`, then reports the budget its context had left and
+/// the input keys it was handed.
+struct SleepNode {
+  millis: u64,
+}
+
+#[async_trait]
+impl AsyncNode for SleepNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    tokio::time::sleep(Duration::from_millis(self.millis)).await;
+    let seen = node_context::remaining_budget().map_or(Value::Null, |remaining| {
+      Value::from(remaining.as_millis() as u64)
+    });
+    let mut keys: Vec<&String> = inputs.keys().collect();
+    keys.sort();
+    Ok(HashMap::from([
+      ("seen_deadline_ms".to_string(), FlowValue::Json(seen)),
+      ("input_keys".to_string(), FlowValue::Json(json!(keys))),
+    ]))
+  }
+}
+
+fn node(id: &str, millis: u64, dependencies: &[&str]) -> GraphNode {
+  GraphNode {
+    id: id.to_string(),
+    node_type: NodeType::Standard(Arc::new(SleepNode { millis })),
+    dependencies: dependencies.iter().map(ToString::to_string).collect(),
+    input_mapping: None,
+    run_if: None,
+    initial_inputs: HashMap::new(),
+  }
+}
+
+fn persisted(run_dir: &Path, node_id: &str) -> AsyncNodeResult {
+  let content = std::fs::read_to_string(run_dir.join(format!("{node_id}_outputs.json"))).unwrap();
+  serde_json::from_str(&content).unwrap()
+}
+
+fn assert_timed_out(result: &AsyncNodeResult) {
+  assert!(
+    matches!(
+      result,
+      Err(AgentFlowError::TimeoutExceeded { duration_ms: 200 })
+    ),
+    "expected a deadline timeout, got {result:?}"
+  );
+}
+
+async fn run(flow: Flow, config: FlowExecutionConfig, dir: &Path) -> (Duration, AgentFlowError) {
+  let started = Instant::now();
+  let result = flow
+    .execute_from_inputs_with_id_and_config(
+      "deadline-run".to_string(),
+      HashMap::new(),
+      config.with_run_base_dir(dir),
+    )
+    .await;
+  let Err(err) = result else {
+    panic!("flow should exceed its deadline");
+  };
+  (started.elapsed(), err)
+}
+
+#[tokio::test]
+async fn serial_run_stops_at_deadline_and_marks_remaining_nodes() {
+  let flow = Flow::new(vec![
+    node("fast", 20, &[]),
+    node("slow", 5_000, &["fast"]),
+    node("after", 10, &["slow"]),
+  ])
+  .with_deadline(Duration::from_millis(200));
+  let dir = tempfile::tempdir().unwrap();
+
+  let (elapsed, err) = run(flow, FlowExecutionConfig::serial(), dir.path()).await;
+
+  assert!(matches!(
+    err,
+    AgentFlowError::TimeoutExceeded { duration_ms: 200 }
+  ));
+  assert!(elapsed < Duration::from_millis(1_000), "took {elapsed:?}");
+
+  let run_dir = dir.path().join("deadline-run");
+  let Ok(fast) = persisted(&run_dir, "fast") else {
+    panic!("fast node should have completed");
+  };
+  let FlowValue::Json(Value::Number(seen)) = &fast["seen_deadline_ms"] else {
+    panic!("fast node should see the remaining budget");
+  };
+  assert!(seen.as_u64().unwrap() <= 200);
+  assert_eq!(fast["input_keys"], FlowValue::Json(json!([])));
+  assert_timed_out(&persisted(&run_dir, "slow"));
+  assert_timed_out(&persisted(&run_dir, "after"));
+}
+
+#[tokio::test]
+async fn concurrent_run_times_out_running_and_pending_nodes() {
+  let flow = Flow::new(vec![
+    node("left", 5_000, &[]),
+    node("right", 10, &[]),
+    node("join", 10, &["left", "right"]),
+  ])
+  .with_deadline(Duration::from_millis(200));
+  let dir = tempfile::tempdir().unwrap();
+
+  let (elapsed, err) = run(flow, FlowExecutionConfig::concurrent(4), dir.path()).await;
+
+  assert!(matches!(err, AgentFlowError::TimeoutExceeded { .. }));
+  assert!(elapsed < Duration::from_millis(1_000), "took {elapsed:?}");
+
+  let run_dir = dir.path().join("deadline-run");
+  assert!(persisted(&run_dir, "right").is_ok());
+  assert_timed_out(&persisted(&run_dir, "left"));
+  assert_timed_out(&persisted(&run_dir, "join"));
+}
+
+#[tokio::test]
+async fn map_sub_flows_share_the_parent_deadline() {
+  let flow = Flow::new(vec![GraphNode {
+    id: "fan_out".to_string(),
+    node_type: NodeType::Map {
+      template: vec![node("item_work", 5_000, &[])],
+      parallel: true,
+      max_concurrent: None,
+    },
+    dependencies: vec![],
+    input_mapping: None,
+    run_if: None,
+    initial_inputs: HashMap::from([("input_list".to_string(), FlowValue::Json(json!([1, 2, 3])))]),
+  }])
+  .with_deadline(Duration::from_millis(200));
+  let dir = tempfile::tempdir().unwrap();
+
+  let (elapsed, err) = run(flow, FlowExecutionConfig::serial(), dir.path()).await;
+
+  assert!(matches!(err, AgentFlowError::TimeoutExceeded { .. }));
+  assert!(elapsed < Duration::from_millis(1_000), "took {elapsed:?}");
+  assert_timed_out(&persisted(&dir.path().join("deadline-run"), "fan_out"));
+}
+
+#[tokio::test]
+async fn flow_without_deadline_hands_nodes_no_budget() {
+  let flow = Flow::new(vec![node("only", 1, &[])]);
+  let dir = tempfile::tempdir().unwrap();
+
+  let state_pool = flow
+    .execute_from_inputs_with_config(
+      HashMap::new(),
+      FlowExecutionConfig::serial().with_run_base_dir(dir.path()),
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(
+    state_pool["only"].as_ref().unwrap()["seen_deadline_ms"],
+    FlowValue::Json(Value::Null)
+  );
+}
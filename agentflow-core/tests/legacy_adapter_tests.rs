@@ -0,0 +1,190 @@
+//! End-to-end tests for the V1 `SharedState` compatibility layer.
+//!
+//! Runs prep/exec/post-style legacy nodes inside a V2 `Flow` — both wrapped
+//! by hand with `SharedStateAdapterNode` and converted wholesale via
+//! `Flow::from_legacy` — and checks the shared state survives the round trip
+//! through the scheduler's namespaced state pool.
+
+use agentflow_core::FlowExt;
+use agentflow_core::{
+  AgentFlowError, AsyncFlow, LegacyAsyncNode, SharedState, SharedStateAdapterNode,
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  flow::{Flow, GraphNode, NodeType},
+  legacy::{DEFAULT_START_ID, LEGACY_STATE_KEY},
+  scheduler::FlowExecutionConfig,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Appends `paragraph` to the `draft` state key, V1 style.
+struct AppendNode {
+  paragraph: &'static str,
+}
+
+#[async_trait]
+impl LegacyAsyncNode for AppendNode {
+  async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
+    Ok(shared.get("draft").unwrap_or(json!("")))
+  }
+
+  async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
+    let draft = prep_result.as_str().unwrap_or_default();
+    Ok(json!(format!("{draft}{}", self.paragraph)))
+  }
+
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
+    shared.insert("draft".to_string(), exec_result);
+    Ok(None)
+  }
+}
+
+/// A native V2 node consuming one key written by a legacy node.
+struct WordCountNode;
+
+#[async_trait]
+impl AsyncNode for WordCountNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let draft = match inputs.get("text") {
+      Some(FlowValue::Json(Value::String(text))) => text.clone(),
+      _ => {
+        return Err(AgentFlowError::NodeInputError {
+          message: "missing text".to_string(),
+        });
+      }
+    };
+    Ok(HashMap::from([(
+      "words".to_string(),
+      FlowValue::Json(json!(draft.split_whitespace().count())),
+    )]))
+  }
+}
+
+fn config() -> (tempfile::TempDir, FlowExecutionConfig) {
+  let dir = tempfile::tempdir().unwrap();
+  let config = FlowExecutionConfig::serial().with_run_base_dir(dir.path());
+  (dir, config)
+}
+
+fn output(state_pool: &HashMap<String, AsyncNodeResult>, node_id: &str, key: &str) -> FlowValue {
+  state_pool[node_id].as_ref().unwrap()[key].clone()
+}
+
+#[tokio::test]
+async fn legacy_node_runs_inside_v2_flow_and_feeds_native_node() {
+  let flow = Flow::new(vec![
+    GraphNode {
+      id: "intro".to_string(),
+      node_type: NodeType::Standard(Arc::new(SharedStateAdapterNode::new(Arc::new(
+        AppendNode {
+          paragraph: "hello legacy world",
+        },
+      )))),
+      dependencies: vec![],
+      input_mapping: None,
+      run_if: None,
+      initial_inputs: HashMap::new(),
+    },
+    GraphNode {
+      id: "count".to_string(),
+      node_type: NodeType::Standard(Arc::new(WordCountNode)),
+      dependencies: vec!["intro".to_string()],
+      input_mapping: Some(HashMap::from([(
+        "text".to_string(),
+        ("intro".to_string(), "draft".to_string()),
+      )])),
+      run_if: None,
+      initial_inputs: HashMap::new(),
+    },
+  ]);
+  let (_dir, config) = config();
+
+  let state_pool = flow
+    .execute_from_inputs_with_config(HashMap::new(), config)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    output(&state_pool, "count", "words"),
+    FlowValue::Json(json!(3))
+  );
+}
+
+#[tokio::test]
+async fn converted_legacy_flow_round_trips_shared_state() {
+  let legacy = AsyncFlow::new(Box::new(AppendNode { paragraph: "A" }))
+    .with_node("middle", Box::new(AppendNode { paragraph: "B" }))
+    .with_node("close", Box::new(AppendNode { paragraph: "C" }))
+    .connect(DEFAULT_START_ID, "middle")
+    .connect("middle", "close");
+
+  // The V1 interpreter is the reference result.
+  let shared = SharedState::new();
+  shared.insert("author".to_string(), json!("ada"));
+  legacy.run_async(&shared).await.unwrap();
+
+  let flow = Flow::from_legacy(legacy).unwrap();
+  let (_dir, config) = config();
+  let seed = HashMap::from([("author".to_string(), FlowValue::Json(json!("ada")))]);
+  let state_pool = flow
+    .execute_from_inputs_with_config(seed, config)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    output(&state_pool, "close", "draft"),
+    FlowValue::Json(json!("ABC"))
+  );
+  let FlowValue::Json(Value::Object(snapshot)) = output(&state_pool, "close", LEGACY_STATE_KEY)
+  else {
+    panic!("expected a JSON object snapshot");
+  };
+  let snapshot: HashMap<String, Value> = snapshot.into_iter().collect();
+  assert_eq!(snapshot, shared.snapshot());
+}
+
+#[tokio::test]
+async fn legacy_node_errors_surface_in_state_pool() {
+  struct Failing;
+
+  #[async_trait]
+  impl LegacyAsyncNode for Failing {
+    async fn prep_async(&self, _shared: &SharedState) -> Result<Value, AgentFlowError> {
+      Err(AgentFlowError::NodeInputError {
+        message: "no draft".to_string(),
+      })
+    }
+
+    async fn exec_async(&self, _prep_result: Value) -> Result<Value, AgentFlowError> {
+      unreachable!("prep failed")
+    }
+
+    async fn post_async(
+      &self,
+      _shared: &SharedState,
+      _prep_result: Value,
+      _exec_result: Value,
+    ) -> Result<Option<String>, AgentFlowError> {
+      unreachable!("prep failed")
+    }
+  }
+
+  let flow = Flow::from_legacy(AsyncFlow::new(Box::new(Failing))).unwrap();
+  let (_dir, config) = config();
+  let state_pool = flow
+    .execute_from_inputs_with_config(HashMap::new(), config)
+    .await
+    .unwrap();
+
+  assert!(matches!(
+    state_pool[DEFAULT_START_ID],
+    Err(AgentFlowError::NodeInputError { .. })
+  ));
+}
@@ -0,0 +1,243 @@
+//! Dropped optional inputs: the opt-in `missing_inputs` report, the
+//! `NodeInputDropped` event per drop reason, and strict-input mode.
+//!
+//! Optional mappings carry no dependency edge, so the tests lean on the serial
+//! scheduler's id-ordered topological sort to run sources before the consumer
+//! (`a_*` < `consumer`), and on a reverse edge to run `z_late` after it.
+
+use agentflow_core::FlowExt;
+use agentflow_core::{
+  AgentFlowError,
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  events::{EventListener, WorkflowEvent},
+  flow::{Flow, GraphNode, MISSING_INPUTS_KEY, NodeType},
+  scheduler::FlowExecutionConfig,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Returns a fixed set of outputs, or fails when `outputs` is `None`.
+struct FixedNode {
+  outputs: Option<Value>,
+}
+
+#[async_trait]
+impl AsyncNode for FixedNode {
+  async fn execute(&self, _inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let Some(Value::Object(outputs)) = &self.outputs else {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: "boom".to_string(),
+      });
+    };
+    Ok(
+      outputs
+        .iter()
+        .map(|(key, value)| (key.clone(), FlowValue::Json(value.clone())))
+        .collect(),
+    )
+  }
+}
+
+/// Echoes the names of the inputs it received plus the `missing_inputs` list.
+struct EchoNode;
+
+#[async_trait]
+impl AsyncNode for EchoNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let mut received: Vec<&String> = inputs
+      .keys()
+      .filter(|key| *key != MISSING_INPUTS_KEY)
+      .collect();
+    received.sort();
+    let missing = inputs
+      .get(MISSING_INPUTS_KEY)
+      .cloned()
+      .unwrap_or(FlowValue::Json(Value::Null));
+    Ok(HashMap::from([
+      ("received".to_string(), FlowValue::Json(json!(received))),
+      ("missing".to_string(), missing),
+    ]))
+  }
+}
+
+#[derive(Default)]
+struct DropRecorder {
+  drops: Mutex<Vec<(String, String)>>,
+}
+
+impl EventListener for DropRecorder {
+  fn on_event(&self, event: &WorkflowEvent) {
+    if let WorkflowEvent::NodeInputDropped { input, reason, .. } = event {
+      self
+        .drops
+        .lock()
+        .unwrap()
+        .push((input.clone(), reason.clone()));
+    }
+  }
+}
+
+fn fixed(id: &str, outputs: Option<Value>, dependencies: &[&str]) -> GraphNode {
+  GraphNode {
+    id: id.to_string(),
+    node_type: NodeType::Standard(Arc::new(FixedNode { outputs })),
+    dependencies: dependencies.iter().map(ToString::to_string).collect(),
+    input_mapping: None,
+    run_if: None,
+    initial_inputs: HashMap::new(),
+  }
+}
+
+fn consumer(mapping: &[(&str, &str, &str)]) -> GraphNode {
+  GraphNode {
+    id: "consumer".to_string(),
+    node_type: NodeType::Standard(Arc::new(EchoNode)),
+    dependencies: vec![],
+    input_mapping: Some(
+      mapping
+        .iter()
+        .map(|(input, node, output)| (input.to_string(), (node.to_string(), output.to_string())))
+        .collect(),
+    ),
+    run_if: None,
+    initial_inputs: HashMap::new(),
+  }
+}
+
+/// A flow exercising every drop reason, plus one input that resolves.
+fn every_drop_reason() -> Vec<GraphNode> {
+  let mut maybe = fixed("a_maybe", Some(json!({ "value": 1 })), &["a_guard"]);
+  maybe.run_if = Some("{{ nodes.a_guard.outputs.enabled }}".to_string());
+  let mut consumer = consumer(&[
+    ("kept", "a_source", "present"),
+    ("from_skipped", "a_maybe", "value"),
+    ("from_missing_key", "a_source", "absent"),
+    ("from_failed", "a_broken", "value"),
+    ("from_not_run", "z_late", "value"),
+  ]);
+  // Keeps the consumer level with `a_maybe` so the skip is decided first.
+  consumer.dependencies = vec!["a_guard".to_string()];
+  vec![
+    fixed("a_guard", Some(json!({ "enabled": false })), &[]),
+    maybe,
+    fixed("a_source", Some(json!({ "present": "yes" })), &[]),
+    fixed("a_broken", None, &[]),
+    fixed("z_late", Some(json!({ "value": 2 })), &["consumer"]),
+    consumer,
+  ]
+}
+
+async fn run(flow: Flow) -> Result<HashMap<String, AsyncNodeResult>, AgentFlowError> {
+  let dir = tempfile::tempdir().unwrap();
+  flow
+    .execute_from_inputs_with_config(
+      HashMap::new(),
+      FlowExecutionConfig::serial().with_run_base_dir(dir.path()),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn each_drop_reason_emits_an_event_and_is_reported() {
+  let recorder = Arc::new(DropRecorder::default());
+  let flow = Flow::new(every_drop_reason())
+    .with_event_listener(recorder.clone())
+    .with_missing_inputs_report();
+
+  let state_pool = run(flow).await.unwrap();
+
+  let outputs = state_pool["consumer"].as_ref().unwrap();
+  assert_eq!(outputs["received"], FlowValue::Json(json!(["kept"])));
+  assert_eq!(
+    outputs["missing"],
+    FlowValue::Json(json!([
+      "from_failed",
+      "from_missing_key",
+      "from_not_run",
+      "from_skipped"
+    ]))
+  );
+
+  let mut drops = recorder.drops.lock().unwrap().clone();
+  drops.sort();
+  assert_eq!(
+    drops,
+    vec![
+      ("from_failed".to_string(), "upstream_failed".to_string()),
+      ("from_missing_key".to_string(), "missing_key".to_string()),
+      ("from_not_run".to_string(), "upstream_not_run".to_string()),
+      ("from_skipped".to_string(), "skipped_upstream".to_string()),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn missing_inputs_report_is_opt_in() {
+  let flow = Flow::new(vec![
+    fixed("a_source", Some(json!({ "present": "yes" })), &[]),
+    consumer(&[("kept", "a_source", "present")]),
+  ]);
+
+  let state_pool = run(flow).await.unwrap();
+
+  assert_eq!(
+    state_pool["consumer"].as_ref().unwrap()["missing"],
+    FlowValue::Json(Value::Null)
+  );
+}
+
+#[tokio::test]
+async fn report_is_empty_when_nothing_was_dropped() {
+  let flow = Flow::new(vec![
+    fixed("a_source", Some(json!({ "present": "yes" })), &[]),
+    consumer(&[("kept", "a_source", "present")]),
+  ])
+  .with_missing_inputs_report();
+
+  let state_pool = run(flow).await.unwrap();
+
+  assert_eq!(
+    state_pool["consumer"].as_ref().unwrap()["missing"],
+    FlowValue::Json(json!([]))
+  );
+}
+
+#[tokio::test]
+async fn strict_inputs_turn_a_drop_into_an_input_error() {
+  let flow = Flow::new(vec![
+    fixed("a_source", Some(json!({ "present": "yes" })), &[]),
+    consumer(&[("from_missing_key", "a_source", "absent")]),
+  ])
+  .with_strict_inputs();
+
+  let Err(err) = run(flow).await else {
+    panic!("strict inputs should reject the dropped input");
+  };
+
+  let AgentFlowError::NodeInputError { message } = err else {
+    panic!("expected NodeInputError, got {err:?}");
+  };
+  assert!(message.contains("from_missing_key"), "{message}");
+  assert!(message.contains("missing_key"), "{message}");
+}
+
+#[tokio::test]
+async fn strict_inputs_reject_unknown_sources_before_running() {
+  let flow = Flow::new(vec![
+    fixed("a_source", Some(json!({ "present": "yes" })), &[]),
+    consumer(&[("typo", "a_sorce", "present")]),
+  ])
+  .with_strict_inputs();
+
+  let Err(err) = run(flow).await else {
+    panic!("strict inputs should reject the unknown source");
+  };
+
+  assert!(
+    matches!(&err, AgentFlowError::FlowDefinitionError { message } if message.contains("a_sorce")),
+    "{err:?}"
+  );
+}
@@ -25,6 +25,15 @@ pub trait AsyncNode: Send + Sync {
   /// An `AsyncNodeResult` which is a `Result` containing a map of output names to
   /// `FlowValue`s on success, or an `AgentFlowError` on failure.
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult;
+
+  /// Name of the implementing type, which [`Flow::diff`] compares to spot
+  /// a node whose type changed. Wrappers that delegate to an inner node
+  /// may report the inner node's name instead.
+  ///
+  /// [`Flow::diff`]: crate::flow::Flow::diff
+  fn type_name(&self) -> &'static str {
+    std::any::type_name::<Self>()
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,387 @@
+//! Structural comparison of two [`Flow`]s, for reviewing an edit to a
+//! workflow without the noise of a text diff.
+//!
+//! Nodes are matched by id, so a renamed node shows up as one removed and
+//! one added. For a node on both sides [`Flow::diff`] compares its type,
+//! its dependencies, its input mappings and its `run_if`. The nodes of Map
+//! and While templates are compared the same way and reported as
+//! `parent/child`.
+
+use crate::flow::{Flow, GraphNode, NodeType};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// What changed between two flows. Every collection is ordered by node id,
+/// so the [`Display`](fmt::Display) rendering and the JSON form are the
+/// same for the same pair of flows.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FlowDiff {
+  /// Nodes only in the new flow, with their type.
+  pub added: BTreeMap<String, String>,
+  /// Nodes only in the old flow, with their type.
+  pub removed: BTreeMap<String, String>,
+  /// Nodes in both flows that differ.
+  pub changed: BTreeMap<String, NodeChange>,
+}
+
+/// How one node differs between two flows. Aspects that did not change
+/// are empty and left out of the JSON form.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NodeChange {
+  /// `map`, `while`, or the node implementation's type name.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub node_type: Option<ValueChange<String>>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub dependencies_added: Vec<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub dependencies_removed: Vec<String>,
+  /// Keyed by input name. Sources are written `node.output`; `None` is an
+  /// input mapped on one side only.
+  #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+  pub input_mapping: BTreeMap<String, ValueChange<Option<String>>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub run_if: Option<ValueChange<Option<String>>>,
+}
+
+/// A value before and after the edit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValueChange<T> {
+  pub old: T,
+  pub new: T,
+}
+
+impl FlowDiff {
+  /// True when both flows have the same structure.
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+impl fmt::Display for FlowDiff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.is_empty() {
+      return writeln!(f, "No structural changes");
+    }
+    for (id, node_type) in &self.removed {
+      writeln!(f, "- {} ({})", id, node_type)?;
+    }
+    for (id, node_type) in &self.added {
+      writeln!(f, "+ {} ({})", id, node_type)?;
+    }
+    for (id, change) in &self.changed {
+      writeln!(f, "~ {}", id)?;
+      if let Some(node_type) = &change.node_type {
+        writeln!(f, "    type: {} -> {}", node_type.old, node_type.new)?;
+      }
+      for dependency in &change.dependencies_removed {
+        writeln!(f, "    - depends on {}", dependency)?;
+      }
+      for dependency in &change.dependencies_added {
+        writeln!(f, "    + depends on {}", dependency)?;
+      }
+      for (input, source) in &change.input_mapping {
+        writeln!(
+          f,
+          "    input {}: {} -> {}",
+          input,
+          or_none(&source.old),
+          or_none(&source.new)
+        )?;
+      }
+      if let Some(run_if) = &change.run_if {
+        writeln!(
+          f,
+          "    run_if: {} -> {}",
+          or_none(&run_if.old),
+          or_none(&run_if.new)
+        )?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Flow {
+  /// Compares this flow, the old version, with `other`, the new one.
+  pub fn diff(&self, other: &Flow) -> FlowDiff {
+    let mut diff = FlowDiff::default();
+    diff_nodes(self.nodes().values(), other.nodes().values(), "", &mut diff);
+    diff
+  }
+}
+
+fn diff_nodes<'a>(
+  old: impl IntoIterator<Item = &'a GraphNode>,
+  new: impl IntoIterator<Item = &'a GraphNode>,
+  prefix: &str,
+  diff: &mut FlowDiff,
+) {
+  let old: BTreeMap<&str, &GraphNode> = old.into_iter().map(|n| (n.id.as_str(), n)).collect();
+  let new: BTreeMap<&str, &GraphNode> = new.into_iter().map(|n| (n.id.as_str(), n)).collect();
+
+  for (id, node) in &old {
+    if !new.contains_key(id) {
+      diff
+        .removed
+        .insert(format!("{}{}", prefix, id), type_of(node));
+    }
+  }
+  for (id, node) in &new {
+    let path = format!("{}{}", prefix, id);
+    let Some(before) = old.get(id) else {
+      diff.added.insert(path, type_of(node));
+      continue;
+    };
+    let change = diff_node(before, node);
+    // Templates are only comparable while the kind stays the same
+    if change.node_type.is_none()
+      && let (Some(old_template), Some(new_template)) = (template_of(before), template_of(node))
+    {
+      diff_nodes(old_template, new_template, &format!("{}/", path), diff);
+    }
+    if change != NodeChange::default() {
+      diff.changed.insert(path, change);
+    }
+  }
+}
+
+fn diff_node(old: &GraphNode, new: &GraphNode) -> NodeChange {
+  let (old_type, new_type) = (type_of(old), type_of(new));
+  let old_deps: BTreeSet<&String> = old.dependencies.iter().collect();
+  let new_deps: BTreeSet<&String> = new.dependencies.iter().collect();
+  let (old_mapping, new_mapping) = (mapping_of(old), mapping_of(new));
+  let inputs: BTreeSet<&str> = old_mapping
+    .keys()
+    .chain(new_mapping.keys())
+    .copied()
+    .collect();
+
+  NodeChange {
+    node_type: (old_type != new_type).then_some(ValueChange {
+      old: old_type,
+      new: new_type,
+    }),
+    dependencies_added: new_deps
+      .difference(&old_deps)
+      .map(|d| d.to_string())
+      .collect(),
+    dependencies_removed: old_deps
+      .difference(&new_deps)
+      .map(|d| d.to_string())
+      .collect(),
+    input_mapping: inputs
+      .into_iter()
+      .filter_map(|input| {
+        let (old, new) = (old_mapping.get(input), new_mapping.get(input));
+        (old != new).then(|| {
+          (
+            input.to_string(),
+            ValueChange {
+              old: old.cloned(),
+              new: new.cloned(),
+            },
+          )
+        })
+      })
+      .collect(),
+    run_if: (old.run_if != new.run_if).then(|| ValueChange {
+      old: old.run_if.clone(),
+      new: new.run_if.clone(),
+    }),
+  }
+}
+
+fn type_of(node: &GraphNode) -> String {
+  match &node.node_type {
+    NodeType::Standard(inner) => short_type_name(inner.type_name()).to_string(),
+    other => other.kind().to_string(),
+  }
+}
+
+/// `agentflow_nodes::nodes::llm::LlmNode` -> `LlmNode`; generic arguments
+/// are kept as written.
+fn short_type_name(name: &str) -> &str {
+  let path_end = name.find('<').unwrap_or(name.len());
+  match name[..path_end].rfind("::") {
+    Some(at) => &name[at + 2..],
+    None => name,
+  }
+}
+
+fn template_of(node: &GraphNode) -> Option<&Vec<GraphNode>> {
+  match &node.node_type {
+    NodeType::Map { template, .. } | NodeType::While { template, .. } => Some(template),
+    NodeType::Standard(_) => None,
+  }
+}
+
+fn mapping_of(node: &GraphNode) -> BTreeMap<&str, String> {
+  node
+    .input_mapping
+    .iter()
+    .flatten()
+    .map(|(input, (source, output))| (input.as_str(), format!("{}.{}", source, output)))
+    .collect()
+}
+
+fn or_none(value: &Option<String>) -> &str {
+  value.as_deref().unwrap_or("(none)")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult};
+  use async_trait::async_trait;
+  use serde_json::json;
+  use std::collections::HashMap;
+  use std::sync::Arc;
+
+  struct Fetch;
+  struct Summarize;
+
+  #[async_trait]
+  impl AsyncNode for Fetch {
+    async fn execute(&self, _inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      Ok(HashMap::new())
+    }
+  }
+
+  #[async_trait]
+  impl AsyncNode for Summarize {
+    async fn execute(&self, _inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      Ok(HashMap::new())
+    }
+  }
+
+  fn node(id: &str, inner: Arc<dyn AsyncNode>, deps: &[&str]) -> GraphNode {
+    GraphNode {
+      id: id.to_string(),
+      node_type: NodeType::Standard(inner),
+      dependencies: deps.iter().map(|dep| dep.to_string()).collect(),
+      input_mapping: None,
+      run_if: None,
+      initial_inputs: HashMap::new(),
+    }
+  }
+
+  fn mapped(mut node: GraphNode, input: &str, source: &str) -> GraphNode {
+    node.input_mapping.get_or_insert_with(HashMap::new).insert(
+      input.to_string(),
+      (source.to_string(), "output".to_string()),
+    );
+    node
+  }
+
+  fn pipeline(summary_id: &str) -> Flow {
+    Flow::new(vec![
+      node("fetch", Arc::new(Fetch), &[]),
+      mapped(
+        node(summary_id, Arc::new(Summarize), &["fetch"]),
+        "text",
+        "fetch",
+      ),
+      mapped(
+        node("publish", Arc::new(Fetch), &[summary_id]),
+        "body",
+        summary_id,
+      ),
+    ])
+  }
+
+  #[test]
+  fn identical_flows_have_an_empty_diff() {
+    let diff = pipeline("summarize").diff(&pipeline("summarize"));
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "No structural changes\n");
+    assert_eq!(
+      serde_json::to_value(&diff).unwrap(),
+      json!({"added": {}, "removed": {}, "changed": {}})
+    );
+  }
+
+  #[test]
+  fn a_renamed_node_is_removed_and_added() {
+    let diff = pipeline("summarise").diff(&pipeline("summarize"));
+    assert_eq!(
+      diff.to_string(),
+      "- summarise (Summarize)\n\
+       + summarize (Summarize)\n\
+       ~ publish\n    \
+       - depends on summarise\n    \
+       + depends on summarize\n    \
+       input body: summarise.output -> summarize.output\n"
+    );
+    assert_eq!(
+      serde_json::to_value(&diff).unwrap(),
+      json!({
+        "added": {"summarize": "Summarize"},
+        "removed": {"summarise": "Summarize"},
+        "changed": {"publish": {
+          "dependencies_added": ["summarize"],
+          "dependencies_removed": ["summarise"],
+          "input_mapping": {"body": {
+            "old": "summarise.output",
+            "new": "summarize.output",
+          }},
+        }},
+      })
+    );
+  }
+
+  #[test]
+  fn a_rewired_mapping_type_and_run_if_are_reported_per_node() {
+    let old = pipeline("summarize");
+    let mut new = Flow::new(vec![
+      node("fetch", Arc::new(Fetch), &[]),
+      node("summarize", Arc::new(Fetch), &["fetch"]),
+      mapped(
+        mapped(
+          node("publish", Arc::new(Fetch), &["summarize"]),
+          "body",
+          "fetch",
+        ),
+        "title",
+        "summarize",
+      ),
+    ]);
+    new.add_node(GraphNode {
+      run_if: Some("{{ fetch.ok }}".to_string()),
+      ..node("fetch", Arc::new(Fetch), &[])
+    });
+
+    let diff = old.diff(&new);
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    assert_eq!(
+      diff.to_string(),
+      "~ fetch\n    \
+       run_if: (none) -> {{ fetch.ok }}\n\
+       ~ publish\n    \
+       input body: summarize.output -> fetch.output\n    \
+       input title: (none) -> summarize.output\n\
+       ~ summarize\n    \
+       type: Summarize -> Fetch\n    \
+       input text: fetch.output -> (none)\n"
+    );
+  }
+
+  #[test]
+  fn template_nodes_are_compared_under_their_parent() {
+    let looped = |inner: Arc<dyn AsyncNode>| {
+      Flow::new(vec![GraphNode {
+        node_type: NodeType::Map {
+          template: vec![node("step", inner, &[])],
+          parallel: false,
+          max_concurrent: None,
+        },
+        ..node("each", Arc::new(Fetch), &[])
+      }])
+    };
+    let diff = looped(Arc::new(Fetch)).diff(&looped(Arc::new(Summarize)));
+    assert_eq!(
+      diff.to_string(),
+      "~ each/step\n    type: Fetch -> Summarize\n"
+    );
+  }
+}
@@ -170,6 +170,14 @@ pub enum AgentFlowError {
   #[error("Concurrency limit exceeded: {limit}")]
   ConcurrencyLimitExceeded { limit: usize },
 
+  /// A sub-agent ran past its cost or time budget and was cancelled.
+  /// `partial_outputs` holds what it produced before the abort.
+  #[error("Budget exceeded: {message}")]
+  BudgetExceeded {
+    message: String,
+    partial_outputs: HashMap<String, FlowValue>,
+  },
+
   // ===== Timeout/Retry Errors =====
   #[error("Timeout exceeded after {duration_ms}ms")]
   TimeoutExceeded { duration_ms: u64 },
@@ -255,7 +263,8 @@ impl AgentFlowError {
 
       Self::ResourcePoolExhausted { .. }
       | Self::MemoryLimitExceeded { .. }
-      | Self::ConcurrencyLimitExceeded { .. } => ErrorCategory::Resource,
+      | Self::ConcurrencyLimitExceeded { .. }
+      | Self::BudgetExceeded { .. } => ErrorCategory::Resource,
 
       Self::ConfigurationError { .. } => ErrorCategory::Configuration,
 
@@ -109,6 +109,26 @@ pub enum WorkflowEvent {
     timestamp: Instant,
   },
 
+  /// A mapped input was dropped before the node ran (see
+  /// `agentflow_graph::flow::DroppedInputReason` for `reason` values).
+  NodeInputDropped {
+    workflow_id: String,
+    node_id: String,
+    input: String,
+    source_node_id: String,
+    reason: String,
+    timestamp: Instant,
+  },
+
+  /// A running node reported intermediate progress - e.g. the callback
+  /// URL a `webhook_wait` node is listening on. `detail` is node-defined.
+  NodeProgress {
+    workflow_id: String,
+    node_id: String,
+    detail: serde_json::Value,
+    timestamp: Instant,
+  },
+
   /// Checkpoint saved
   CheckpointSaved {
     workflow_id: String,
@@ -215,6 +235,8 @@ impl WorkflowEvent {
       | Self::NodeOutputCaptured { workflow_id, .. }
       | Self::NodeFailed { workflow_id, .. }
       | Self::NodeSkipped { workflow_id, .. }
+      | Self::NodeInputDropped { workflow_id, .. }
+      | Self::NodeProgress { workflow_id, .. }
       | Self::CheckpointSaved { workflow_id, .. }
       | Self::CheckpointRestored { workflow_id, .. }
       | Self::RetryAttempt { workflow_id, .. }
@@ -237,6 +259,8 @@ impl WorkflowEvent {
       | Self::NodeOutputCaptured { timestamp, .. }
       | Self::NodeFailed { timestamp, .. }
       | Self::NodeSkipped { timestamp, .. }
+      | Self::NodeInputDropped { timestamp, .. }
+      | Self::NodeProgress { timestamp, .. }
       | Self::CheckpointSaved { timestamp, .. }
       | Self::CheckpointRestored { timestamp, .. }
       | Self::RetryAttempt { timestamp, .. }
@@ -259,6 +283,8 @@ impl WorkflowEvent {
       Self::NodeOutputCaptured { .. } => "node.output.captured",
       Self::NodeFailed { .. } => "node.failed",
       Self::NodeSkipped { .. } => "node.skipped",
+      Self::NodeInputDropped { .. } => "node.input.dropped",
+      Self::NodeProgress { .. } => "node.progress",
       Self::CheckpointSaved { .. } => "checkpoint.saved",
       Self::CheckpointRestored { .. } => "checkpoint.restored",
       Self::RetryAttempt { .. } => "retry.attempt",
@@ -314,6 +340,24 @@ impl fmt::Display for WorkflowEvent {
       } => {
         write!(f, "Node '{}' skipped: {}", node_id, reason)
       }
+      Self::NodeInputDropped {
+        node_id,
+        input,
+        source_node_id,
+        reason,
+        ..
+      } => {
+        write!(
+          f,
+          "Node '{}' input '{}' from '{}' dropped: {}",
+          node_id, input, source_node_id, reason
+        )
+      }
+      Self::NodeProgress {
+        node_id, detail, ..
+      } => {
+        write!(f, "Node '{}' progress: {}", node_id, detail)
+      }
       Self::CheckpointSaved { checkpoint_id, .. } => {
         write!(f, "Checkpoint '{}' saved", checkpoint_id)
       }
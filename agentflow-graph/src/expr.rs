@@ -210,8 +210,47 @@ impl<'a> ExprContext<'a> {
   }
 }
 
+/// An expression parsed and validated once, for callers that evaluate the
+/// same source against many inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledExpr {
+  source: String,
+  ast: Expr,
+}
+
+impl CompiledExpr {
+  pub fn new(expr: &str) -> Result<Self, ExprError> {
+    let ast = Parser::new(expr).parse()?;
+    ast.validate()?;
+    Ok(Self {
+      source: expr.to_string(),
+      ast,
+    })
+  }
+
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  pub fn evaluate(
+    &self,
+    nodes: &HashMap<String, AsyncNodeResult>,
+    inputs: &HashMap<String, FlowValue>,
+  ) -> Result<ExprValue, ExprError> {
+    self.ast.eval(&ExprContext::new(nodes, inputs))
+  }
+
+  pub fn evaluate_bool(
+    &self,
+    nodes: &HashMap<String, AsyncNodeResult>,
+    inputs: &HashMap<String, FlowValue>,
+  ) -> Result<bool, ExprError> {
+    Ok(self.evaluate(nodes, inputs)?.truthy())
+  }
+}
+
 pub fn compile(expr: &str) -> Result<(), ExprError> {
-  Parser::new(expr).parse()?.validate()
+  CompiledExpr::new(expr).map(|_| ())
 }
 
 pub fn evaluate(
@@ -987,4 +1026,17 @@ mod tests {
     assert_eq!(error.col, 1);
     assert!(error.message.contains("unknown function 'lenn'"));
   }
+
+  #[test]
+  fn compiled_expression_evaluates_against_fresh_inputs() {
+    let compiled = CompiledExpr::new("inputs.iteration < 2").unwrap();
+    assert!(compiled.evaluate_bool(&HashMap::new(), &inputs()).unwrap());
+
+    let later = HashMap::from([(
+      "iteration".to_string(),
+      FlowValue::Json(serde_json::json!(5)),
+    )]);
+    assert!(!compiled.evaluate_bool(&HashMap::new(), &later).unwrap());
+    assert!(CompiledExpr::new("lenn(items)").is_err());
+  }
 }
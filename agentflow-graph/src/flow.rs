@@ -17,6 +17,56 @@ use crate::state_size::StateSizeObserver;
 use crate::value::FlowValue;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Input key carrying the names of mapped inputs the executor dropped, as a
+/// JSON array of strings, when [`Flow::with_missing_inputs_report`] is on.
+pub const MISSING_INPUTS_KEY: &str = "missing_inputs";
+
+/// Why the executor dropped a mapped input instead of handing it to a node.
+///
+/// Only mappings whose source is *not* listed in the node's `dependencies`
+/// (optional mappings) are dropped; the same conditions on a declared
+/// dependency fail the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroppedInputReason {
+  /// The source node was skipped by its `run_if`.
+  SkippedUpstream,
+  /// The source node succeeded but did not produce the mapped output key.
+  MissingKey,
+  /// The source node failed.
+  UpstreamFailed,
+  /// The source node had not run when the input was gathered.
+  UpstreamNotRun,
+}
+
+impl DroppedInputReason {
+  /// Stable snake_case name, used in events and error messages.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::SkippedUpstream => "skipped_upstream",
+      Self::MissingKey => "missing_key",
+      Self::UpstreamFailed => "upstream_failed",
+      Self::UpstreamNotRun => "upstream_not_run",
+    }
+  }
+}
+
+impl std::fmt::Display for DroppedInputReason {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// How the executor treats mapped inputs it cannot resolve. Both switches are
+/// off by default, which keeps the historical silent-drop behaviour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputPolicy {
+  /// Inject [`MISSING_INPUTS_KEY`] into every node with an input mapping.
+  pub report_missing: bool,
+  /// Fail instead of dropping (see [`Flow::with_strict_inputs`]).
+  pub strict: bool,
+}
 
 #[derive(Clone)]
 pub enum NodeType {
@@ -59,6 +109,8 @@ pub struct Flow {
   checkpoint_config: Option<CheckpointConfig>,
   event_listener: Option<Arc<dyn EventListener>>,
   state_size_observer: Option<Arc<dyn StateSizeObserver>>,
+  deadline: Option<Duration>,
+  input_policy: InputPolicy,
 }
 
 impl Flow {
@@ -70,6 +122,8 @@ impl Flow {
       checkpoint_config: None,
       event_listener: None,
       state_size_observer: None,
+      deadline: None,
+      input_policy: InputPolicy::default(),
     }
   }
 
@@ -90,6 +144,37 @@ impl Flow {
     self
   }
 
+  /// Bound the whole run by a wall-clock budget.
+  ///
+  /// The executor fixes the deadline when the run starts, caps every node
+  /// (including Map/While sub-flows, which share the parent's deadline) at the
+  /// remaining budget, exposes that remainder on the node's context
+  /// (`agentflow_core::node_context::remaining_budget`), and stops scheduling with
+  /// `AgentFlowError::TimeoutExceeded` once it is spent.
+  pub fn with_deadline(mut self, budget: Duration) -> Self {
+    self.deadline = Some(budget);
+    self
+  }
+
+  /// Hand every node that has an input mapping a [`MISSING_INPUTS_KEY`]
+  /// array naming the mapped inputs that were dropped (empty when none were),
+  /// so nodes can tell "not provided" apart from "provided as null".
+  pub fn with_missing_inputs_report(mut self) -> Self {
+    self.input_policy.report_missing = true;
+    self
+  }
+
+  /// Treat dropped inputs as errors.
+  ///
+  /// Before the first node runs, mappings that name a source node missing
+  /// from the flow are rejected with `FlowDefinitionError`; at run time, an
+  /// input that would have been dropped fails the node with
+  /// `NodeInputError` instead.
+  pub fn with_strict_inputs(mut self) -> Self {
+    self.input_policy.strict = true;
+    self
+  }
+
   /// Store a checkpoint configuration and enable checkpointing.
   ///
   /// This is the unvalidated IR setter; `agentflow_core::FlowExt::with_checkpointing`
@@ -119,6 +204,14 @@ impl Flow {
   pub fn event_listener(&self) -> Option<&Arc<dyn EventListener>> {
     self.event_listener.as_ref()
   }
+  /// The flow-level wall-clock budget, if any.
+  pub fn deadline(&self) -> Option<Duration> {
+    self.deadline
+  }
+  /// How unresolvable mapped inputs are handled.
+  pub fn input_policy(&self) -> InputPolicy {
+    self.input_policy
+  }
   /// The attached state-size observer, if any.
   pub fn state_size_observer(&self) -> Option<&Arc<dyn StateSizeObserver>> {
     self.state_size_observer.as_ref()
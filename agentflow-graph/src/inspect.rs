@@ -0,0 +1,706 @@
+//! Static inspection of a [`Flow`]: structural validation, a dry-run
+//! execution plan, and Mermaid / DOT export.
+//!
+//! Nothing here runs a node. [`Flow::dry_run`] evaluates `run_if`
+//! conditions the same way the executor does, against a state pool that
+//! only ever holds skipped nodes, so a condition that reads a real node
+//! output is reported as [`PlanStatus::Unknown`] rather than guessed.
+
+use crate::async_node::AsyncNodeResult;
+use crate::error::AgentFlowError;
+use crate::expr;
+use crate::flow::{Flow, GraphNode, NodeType};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+
+/// Input mappings with this source read the current Map item, not a node.
+const MAP_ITEM_SOURCE: &str = "!item";
+
+impl NodeType {
+  /// `standard`, `map` or `while`.
+  pub fn kind(&self) -> &'static str {
+    match self {
+      NodeType::Standard(_) => "standard",
+      NodeType::Map { .. } => "map",
+      NodeType::While { .. } => "while",
+    }
+  }
+}
+
+/// Result of [`Flow::validate`]. Errors make the flow unrunnable; warnings
+/// point at inputs the executor may silently drop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowValidation {
+  pub errors: Vec<String>,
+  pub warnings: Vec<String>,
+}
+
+impl FlowValidation {
+  pub fn is_valid(&self) -> bool {
+    self.errors.is_empty()
+  }
+}
+
+/// What the executor is expected to do with a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanStatus {
+  Run,
+  Skip,
+  /// Depends on values only known at run time.
+  Unknown,
+}
+
+impl PlanStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Run => "run",
+      Self::Skip => "skip",
+      Self::Unknown => "unknown",
+    }
+  }
+}
+
+impl std::fmt::Display for PlanStatus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// One node of an [`ExecutionPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedNode {
+  pub node_id: String,
+  /// Topological level; nodes on the same level may run concurrently.
+  pub level: usize,
+  /// [`NodeType::kind`] of the node.
+  pub kind: &'static str,
+  pub dependencies: Vec<String>,
+  pub status: PlanStatus,
+  pub reason: Option<String>,
+}
+
+/// The nodes of a flow in execution order, as produced by
+/// [`Flow::dry_run`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionPlan {
+  pub nodes: Vec<PlannedNode>,
+}
+
+impl ExecutionPlan {
+  pub fn count(&self, status: PlanStatus) -> usize {
+    self
+      .nodes
+      .iter()
+      .filter(|node| node.status == status)
+      .count()
+  }
+
+  pub fn levels(&self) -> usize {
+    self.nodes.last().map_or(0, |node| node.level + 1)
+  }
+}
+
+impl Flow {
+  /// Groups node ids by topological level, each level sorted by id. Level
+  /// `n` holds the nodes whose dependencies all sit on earlier levels.
+  ///
+  /// Fails with `FlowDefinitionError` when a dependency names a missing
+  /// node and with `CircularFlow` when the dependencies form a cycle.
+  pub fn levels(&self) -> Result<Vec<Vec<String>>, AgentFlowError> {
+    levels_of(self.nodes())
+  }
+
+  /// The longest dependency chain, from a root to a leaf. Ties go to the
+  /// chain that sorts first by node id.
+  pub fn critical_path(&self) -> Result<Vec<String>, AgentFlowError> {
+    let levels = self.levels()?;
+    let nodes = self.nodes();
+    // Longest chain ending at each node, built level by level.
+    let mut longest: HashMap<&str, Vec<String>> = HashMap::new();
+    for id in levels.iter().flatten() {
+      let mut chain = nodes[id]
+        .dependencies
+        .iter()
+        .filter_map(|dep| longest.get(dep.as_str()))
+        .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .cloned()
+        .unwrap_or_default();
+      chain.push(id.clone());
+      longest.insert(id.as_str(), chain);
+    }
+    Ok(
+      longest
+        .into_values()
+        .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .unwrap_or_default(),
+    )
+  }
+
+  /// Checks the flow's structure without running it: dependencies must
+  /// name existing nodes and be acyclic, `run_if` and `while` conditions
+  /// must parse, and Map / While templates (validated recursively) must
+  /// not be empty. Input mappings that can only be dropped are errors
+  /// under [`Flow::with_strict_inputs`] and warnings otherwise.
+  pub fn validate(&self) -> FlowValidation {
+    let mut report = FlowValidation::default();
+    if self.nodes().is_empty() {
+      report.errors.push("Flow has no nodes".to_string());
+    }
+    validate_nodes(self.nodes(), self.input_policy().strict, "", &mut report);
+    report
+  }
+
+  /// Plans a run without executing anything. Nodes without `run_if` run;
+  /// a condition that evaluates without real node outputs decides between
+  /// run and skip; anything else is unknown. A node that maps an input
+  /// from a skipped dependency is skipped too, as the executor does.
+  pub fn dry_run(&self) -> Result<ExecutionPlan, AgentFlowError> {
+    let levels = self.levels()?;
+    let nodes = self.nodes();
+    let mut pool: HashMap<String, AsyncNodeResult> = HashMap::new();
+    let mut statuses: HashMap<&str, PlanStatus> = HashMap::new();
+    let mut plan = ExecutionPlan::default();
+    for (level, ids) in levels.iter().enumerate() {
+      for id in ids {
+        let node = &nodes[id];
+        let (status, reason) = match skipped_dependency(node, &statuses) {
+          Some(dependency) => {
+            pool.insert(
+              id.clone(),
+              Err(AgentFlowError::DependencyNotMet {
+                node_id: id.clone(),
+                dependency_id: dependency.to_string(),
+              }),
+            );
+            (
+              PlanStatus::Skip,
+              Some(format!(
+                "maps an input from skipped dependency '{}'",
+                dependency
+              )),
+            )
+          }
+          None => plan_condition(node, &pool),
+        };
+        if status == PlanStatus::Skip && !pool.contains_key(id) {
+          pool.insert(id.clone(), Err(AgentFlowError::NodeSkipped));
+        }
+        statuses.insert(id.as_str(), status);
+        plan.nodes.push(PlannedNode {
+          node_id: id.clone(),
+          level,
+          kind: node.node_type.kind(),
+          dependencies: node.dependencies.clone(),
+          status,
+          reason,
+        });
+      }
+    }
+    Ok(plan)
+  }
+
+  /// Renders the flow as a Mermaid `flowchart`. Dependencies are solid
+  /// edges, input mappings from nodes that are not dependencies are dotted,
+  /// and nodes with a `run_if` get the dashed `conditional` class.
+  pub fn to_mermaid(&self) -> String {
+    let ids = display_order(self.nodes());
+    let handle: HashMap<&str, String> = ids
+      .iter()
+      .enumerate()
+      .map(|(index, id)| (id.as_str(), format!("n{}", index)))
+      .collect();
+    let mut out = String::from("flowchart TD\n");
+    for id in &ids {
+      let node = &self.nodes()[id];
+      let label = mermaid_escape(&label_of(node));
+      let shape = match node.node_type {
+        NodeType::Standard(_) => format!("[\"{}\"]", label),
+        NodeType::Map { .. } => format!("[[\"{}\"]]", label),
+        NodeType::While { .. } => format!("{{{{\"{}\"}}}}", label),
+      };
+      let _ = writeln!(out, "  {}{}", handle[id.as_str()], shape);
+    }
+    for (from, to, dependency) in edges(self.nodes(), &ids) {
+      let arrow = if dependency { "-->" } else { "-.->" };
+      let _ = writeln!(out, "  {} {} {}", handle[from], arrow, handle[to]);
+    }
+    let conditional: Vec<&str> = ids
+      .iter()
+      .filter(|id| self.nodes()[*id].run_if.is_some())
+      .map(|id| handle[id.as_str()].as_str())
+      .collect();
+    if !conditional.is_empty() {
+      out.push_str("  classDef conditional stroke-dasharray: 5 5\n");
+      let _ = writeln!(out, "  class {} conditional", conditional.join(","));
+    }
+    out
+  }
+
+  /// Renders the flow as a Graphviz `digraph`, with the same edge and
+  /// node conventions as [`Flow::to_mermaid`].
+  pub fn to_dot(&self) -> String {
+    let ids = display_order(self.nodes());
+    let mut out = String::from("digraph flow {\n  rankdir=TB;\n  node [shape=box];\n");
+    for id in &ids {
+      let node = &self.nodes()[id];
+      let mut attrs = vec![format!("label=\"{}\"", dot_escape(&label_of(node)))];
+      match node.node_type {
+        NodeType::Standard(_) => {}
+        NodeType::Map { .. } => attrs.push("shape=box3d".to_string()),
+        NodeType::While { .. } => attrs.push("shape=hexagon".to_string()),
+      }
+      if let Some(condition) = &node.run_if {
+        attrs.push("style=dashed".to_string());
+        attrs.push(format!("tooltip=\"run_if: {}\"", dot_escape(condition)));
+      }
+      let _ = writeln!(out, "  \"{}\" [{}];", dot_escape(id), attrs.join(", "));
+    }
+    for (from, to, dependency) in edges(self.nodes(), &ids) {
+      let style = if dependency { "" } else { " [style=dotted]" };
+      let _ = writeln!(
+        out,
+        "  \"{}\" -> \"{}\"{};",
+        dot_escape(from),
+        dot_escape(to),
+        style
+      );
+    }
+    out.push_str("}\n");
+    out
+  }
+}
+
+fn levels_of(nodes: &HashMap<String, GraphNode>) -> Result<Vec<Vec<String>>, AgentFlowError> {
+  for node in nodes.values() {
+    if let Some(missing) = node
+      .dependencies
+      .iter()
+      .find(|dep| !nodes.contains_key(*dep))
+    {
+      return Err(AgentFlowError::FlowDefinitionError {
+        message: format!("node '{}' depends on unknown node '{}'", node.id, missing),
+      });
+    }
+  }
+  let mut levels = Vec::new();
+  let mut placed: HashSet<&str> = HashSet::new();
+  while placed.len() < nodes.len() {
+    let mut level: Vec<String> = nodes
+      .values()
+      .filter(|node| !placed.contains(node.id.as_str()))
+      .filter(|node| {
+        node
+          .dependencies
+          .iter()
+          .all(|dep| placed.contains(dep.as_str()))
+      })
+      .map(|node| node.id.clone())
+      .collect();
+    if level.is_empty() {
+      return Err(AgentFlowError::CircularFlow);
+    }
+    level.sort();
+    placed.extend(
+      level
+        .iter()
+        .filter_map(|id| nodes.get_key_value(id).map(|(k, _)| k.as_str())),
+    );
+    levels.push(level);
+  }
+  Ok(levels)
+}
+
+fn validate_nodes(
+  nodes: &HashMap<String, GraphNode>,
+  strict: bool,
+  scope: &str,
+  report: &mut FlowValidation,
+) {
+  let mut ids: Vec<&String> = nodes.keys().collect();
+  ids.sort();
+  for id in &ids {
+    let node = &nodes[*id];
+    for dep in &node.dependencies {
+      if dep == *id {
+        report
+          .errors
+          .push(format!("{}node '{}' depends on itself", scope, id));
+      } else if !nodes.contains_key(dep) {
+        report.errors.push(format!(
+          "{}node '{}' depends on unknown node '{}'",
+          scope, id, dep
+        ));
+      }
+    }
+    if let Some(condition) = &node.run_if
+      && let Err(err) = expr::compile(condition)
+    {
+      report.errors.push(format!(
+        "{}node '{}' has an invalid run_if '{}': {}",
+        scope, id, condition, err
+      ));
+    }
+    match &node.node_type {
+      NodeType::Standard(_) => {}
+      NodeType::Map { template, .. } => {
+        validate_template(id, "map", template, strict, scope, report);
+      }
+      NodeType::While {
+        condition,
+        max_iterations,
+        template,
+      } => {
+        if let Err(err) = expr::compile(condition) {
+          report.errors.push(format!(
+            "{}while node '{}' has an invalid condition '{}': {}",
+            scope, id, condition, err
+          ));
+        }
+        if *max_iterations == 0 {
+          report.errors.push(format!(
+            "{}while node '{}' has max_iterations 0 and would never run its body",
+            scope, id
+          ));
+        }
+        validate_template(id, "while", template, strict, scope, report);
+      }
+    }
+  }
+  if levels_of(nodes).is_err() {
+    let cyclic = cycle_members(nodes);
+    if !cyclic.is_empty() {
+      report.errors.push(format!(
+        "{}circular dependency among: {}",
+        scope,
+        cyclic.into_iter().collect::<Vec<_>>().join(", ")
+      ));
+    }
+  }
+  for id in &ids {
+    let node = &nodes[*id];
+    let Some(mapping) = &node.input_mapping else {
+      continue;
+    };
+    let ancestors = ancestors_of(nodes, id);
+    let mut inputs: Vec<_> = mapping.iter().collect();
+    inputs.sort();
+    for (input, (source, output)) in inputs {
+      if source == MAP_ITEM_SOURCE {
+        continue;
+      }
+      if !nodes.contains_key(source) {
+        let message = format!(
+          "{}input '{}' of node '{}' maps from unknown node '{}' (output '{}')",
+          scope, input, id, source, output
+        );
+        if strict {
+          report.errors.push(message);
+        } else {
+          report
+            .warnings
+            .push(format!("{}; it will always be dropped", message));
+        }
+      } else if !ancestors.contains(source.as_str()) {
+        report.warnings.push(format!(
+          "{}input '{}' of node '{}' maps from '{}', which is not a dependency and may not have run yet",
+          scope, input, id, source
+        ));
+      }
+    }
+  }
+}
+
+fn validate_template(
+  id: &str,
+  kind: &str,
+  template: &[GraphNode],
+  strict: bool,
+  scope: &str,
+  report: &mut FlowValidation,
+) {
+  if template.is_empty() {
+    report.errors.push(format!(
+      "{}{} node '{}' has an empty template",
+      scope, kind, id
+    ));
+    return;
+  }
+  let nodes: HashMap<String, GraphNode> = template
+    .iter()
+    .map(|node| (node.id.clone(), node.clone()))
+    .collect();
+  validate_nodes(
+    &nodes,
+    strict,
+    &format!("{}{} '{}': ", scope, kind, id),
+    report,
+  );
+}
+
+/// Nodes on a cycle or downstream of one: what Kahn's algorithm cannot
+/// place once unknown dependencies are ignored.
+fn cycle_members(nodes: &HashMap<String, GraphNode>) -> BTreeSet<String> {
+  let mut remaining: BTreeSet<String> = nodes.keys().cloned().collect();
+  loop {
+    let ready: Vec<String> = remaining
+      .iter()
+      .filter(|id| {
+        nodes[*id]
+          .dependencies
+          .iter()
+          .all(|dep| !remaining.contains(dep) || !nodes.contains_key(dep))
+      })
+      .cloned()
+      .collect();
+    if ready.is_empty() {
+      return remaining;
+    }
+    for id in ready {
+      remaining.remove(&id);
+    }
+  }
+}
+
+fn ancestors_of<'a>(nodes: &'a HashMap<String, GraphNode>, id: &str) -> HashSet<&'a str> {
+  let mut seen = HashSet::new();
+  let mut stack: Vec<&str> = nodes
+    .get(id)
+    .map(|node| node.dependencies.iter().map(String::as_str).collect())
+    .unwrap_or_default();
+  while let Some(next) = stack.pop() {
+    if let Some((key, node)) = nodes.get_key_value(next)
+      && seen.insert(key.as_str())
+    {
+      stack.extend(node.dependencies.iter().map(String::as_str));
+    }
+  }
+  seen
+}
+
+fn skipped_dependency<'n>(
+  node: &'n GraphNode,
+  statuses: &HashMap<&str, PlanStatus>,
+) -> Option<&'n str> {
+  let mapping = node.input_mapping.as_ref()?;
+  let mut sources: Vec<&str> = mapping
+    .values()
+    .map(|(source, _)| source.as_str())
+    .filter(|source| node.dependencies.iter().any(|dep| dep == source))
+    .collect();
+  sources.sort_unstable();
+  sources
+    .into_iter()
+    .find(|source| statuses.get(source) == Some(&PlanStatus::Skip))
+}
+
+fn plan_condition(
+  node: &GraphNode,
+  pool: &HashMap<String, AsyncNodeResult>,
+) -> (PlanStatus, Option<String>) {
+  let Some(condition) = &node.run_if else {
+    return (PlanStatus::Run, None);
+  };
+  let shown = expr::normalize_expression(condition);
+  if let Err(err) = expr::compile(condition) {
+    return (
+      PlanStatus::Unknown,
+      Some(format!("invalid run_if `{}`: {}", shown, err.message)),
+    );
+  }
+  match expr::evaluate_bool(condition, pool, &HashMap::new()) {
+    Ok(true) => (PlanStatus::Run, Some(format!("run_if `{}` is true", shown))),
+    Ok(false) => (
+      PlanStatus::Skip,
+      Some(format!("run_if `{}` is false", shown)),
+    ),
+    Err(_) => (
+      PlanStatus::Unknown,
+      Some(format!("run_if `{}` reads runtime outputs", shown)),
+    ),
+  }
+}
+
+/// Topological order when the flow has one, else sorted by id.
+fn display_order(nodes: &HashMap<String, GraphNode>) -> Vec<String> {
+  match levels_of(nodes) {
+    Ok(levels) => levels.into_iter().flatten().collect(),
+    Err(_) => {
+      let mut ids: Vec<String> = nodes.keys().cloned().collect();
+      ids.sort();
+      ids
+    }
+  }
+}
+
+/// `(from, to, is_dependency)` in display order. Edges from nodes missing
+/// from the flow are left out.
+fn edges<'a>(
+  nodes: &'a HashMap<String, GraphNode>,
+  order: &'a [String],
+) -> Vec<(&'a str, &'a str, bool)> {
+  let mut edges = Vec::new();
+  for id in order {
+    let node = &nodes[id];
+    for dep in &node.dependencies {
+      if nodes.contains_key(dep) {
+        edges.push((dep.as_str(), id.as_str(), true));
+      }
+    }
+    let mut optional: BTreeSet<&str> = node
+      .input_mapping
+      .iter()
+      .flat_map(|mapping| mapping.values())
+      .map(|(source, _)| source.as_str())
+      .filter(|source| {
+        nodes.contains_key(*source) && !node.dependencies.iter().any(|d| d == source)
+      })
+      .collect();
+    optional.remove(id.as_str());
+    edges.extend(
+      optional
+        .into_iter()
+        .map(|source| (source, id.as_str(), false)),
+    );
+  }
+  edges
+}
+
+fn label_of(node: &GraphNode) -> String {
+  match node.node_type {
+    NodeType::Standard(_) => node.id.clone(),
+    _ => format!("{} ({})", node.id, node.node_type.kind()),
+  }
+}
+
+fn mermaid_escape(text: &str) -> String {
+  text.replace('"', "#quot;")
+}
+
+fn dot_escape(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::async_node::{AsyncNode, AsyncNodeInputs};
+  use async_trait::async_trait;
+  use std::sync::Arc;
+
+  struct Noop;
+
+  #[async_trait]
+  impl AsyncNode for Noop {
+    async fn execute(&self, _inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      Ok(HashMap::new())
+    }
+  }
+
+  fn node(id: &str, deps: &[&str], run_if: Option<&str>) -> GraphNode {
+    GraphNode {
+      id: id.to_string(),
+      node_type: NodeType::Standard(Arc::new(Noop)),
+      dependencies: deps.iter().map(|dep| dep.to_string()).collect(),
+      input_mapping: None,
+      run_if: run_if.map(str::to_string),
+      initial_inputs: HashMap::new(),
+    }
+  }
+
+  fn mapped(mut node: GraphNode, input: &str, source: &str) -> GraphNode {
+    node.input_mapping.get_or_insert_with(HashMap::new).insert(
+      input.to_string(),
+      (source.to_string(), "output".to_string()),
+    );
+    node
+  }
+
+  #[test]
+  fn levels_and_critical_path_follow_dependencies() {
+    let flow = Flow::new(vec![
+      node("fetch", &[], None),
+      node("left", &["fetch"], None),
+      node("right", &["fetch"], None),
+      node("merge", &["left", "right"], None),
+    ]);
+    assert_eq!(
+      flow.levels().unwrap(),
+      vec![vec!["fetch"], vec!["left", "right"], vec!["merge"]]
+    );
+    assert_eq!(
+      flow.critical_path().unwrap(),
+      vec!["fetch", "left", "merge"]
+    );
+
+    let cyclic = Flow::new(vec![node("a", &["b"], None), node("b", &["a"], None)]);
+    assert!(matches!(cyclic.levels(), Err(AgentFlowError::CircularFlow)));
+  }
+
+  #[test]
+  fn validate_reports_structure_errors_and_droppable_inputs() {
+    let flow = Flow::new(vec![
+      node("a", &["b"], None),
+      node("b", &["a"], None),
+      node("c", &["ghost"], Some("{{ nodes.a.outputs.x == }}")),
+      mapped(node("d", &[], None), "text", "a"),
+      mapped(node("e", &[], None), "text", "nowhere"),
+    ]);
+    let report = flow.validate();
+    assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+    assert!(report.errors[0].contains("'c' depends on unknown node 'ghost'"));
+    assert!(report.errors[1].contains("invalid run_if"));
+    assert_eq!(report.errors[2], "circular dependency among: a, b");
+    assert_eq!(report.warnings.len(), 2);
+    assert!(report.warnings[0].contains("maps from 'a', which is not a dependency"));
+    assert!(report.warnings[1].ends_with("it will always be dropped"));
+
+    let strict = flow.clone().with_strict_inputs().validate();
+    assert_eq!(strict.errors.len(), 4);
+  }
+
+  #[test]
+  fn dry_run_resolves_static_conditions_only() {
+    let flow = Flow::new(vec![
+      node("start", &[], None),
+      node("never", &["start"], Some("false")),
+      mapped(node("after_never", &["never"], None), "text", "never"),
+      node("maybe", &["start"], Some("{{ nodes.start.outputs.ok }}")),
+      node(
+        "was_skipped",
+        &["never"],
+        Some("{{ nodes.never.outputs.x == null }}"),
+      ),
+    ]);
+    let plan = flow.dry_run().unwrap();
+    let status: HashMap<&str, PlanStatus> = plan
+      .nodes
+      .iter()
+      .map(|node| (node.node_id.as_str(), node.status))
+      .collect();
+    assert_eq!(status["start"], PlanStatus::Run);
+    assert_eq!(status["never"], PlanStatus::Skip);
+    assert_eq!(status["after_never"], PlanStatus::Skip);
+    assert_eq!(status["maybe"], PlanStatus::Unknown);
+    assert_eq!(status["was_skipped"], PlanStatus::Run);
+    assert_eq!(plan.levels(), 3);
+    assert_eq!(plan.count(PlanStatus::Skip), 2);
+  }
+
+  #[test]
+  fn exports_draw_dependencies_and_optional_inputs() {
+    let flow = Flow::new(vec![
+      node("fetch", &[], None),
+      mapped(node("report", &["fetch"], Some("true")), "extra", "side"),
+      node("side", &[], None),
+    ]);
+    assert_eq!(
+      flow.to_mermaid(),
+      "flowchart TD\n  n0[\"fetch\"]\n  n1[\"side\"]\n  n2[\"report\"]\n  n0 --> n2\n  n1 -.-> n2\n  classDef conditional stroke-dasharray: 5 5\n  class n2 conditional\n"
+    );
+    let dot = flow.to_dot();
+    assert!(
+      dot.contains("  \"report\" [label=\"report\", style=dashed, tooltip=\"run_if: true\"];\n")
+    );
+    assert!(dot.contains("  \"fetch\" -> \"report\";\n  \"side\" -> \"report\" [style=dotted];\n"));
+  }
+}
@@ -0,0 +1,631 @@
+//! V1 compatibility: run `SharedState` + prep/exec/post nodes on the V2 IR.
+//!
+//! The original AgentFlow API threaded one mutable [`SharedState`] map through
+//! an `AsyncFlow` of nodes with a `prep → exec → post` lifecycle, where `post`
+//! picked the next node. The V2 `Flow` passes explicit, namespaced
+//! inputs/outputs instead. Agents written against V1 (the paper assistant, the
+//! paper research analyzer) can adopt the V2 scheduler without rewriting their
+//! nodes through two bridges:
+//!
+//! - [`SharedStateAdapterNode`] wraps one [`LegacyAsyncNode`] as a V2
+//!   [`AsyncNode`]: the node's inputs are materialized into a fresh
+//!   `SharedState` before `prep`, and the state after `post` becomes the
+//!   node's outputs (every key individually, plus the whole map under
+//!   [`LEGACY_STATE_KEY`] and the `post` result under [`LEGACY_ACTION_KEY`]).
+//! - [`Flow::from_legacy`] converts a whole [`AsyncFlow`] whose transitions
+//!   are statically declared (via [`AsyncFlow::connect`]) into a `Flow` of
+//!   adapter nodes, wiring each node's state snapshot into its successors.
+//!
+//! Conversion is only feasible for flows the DAG scheduler can express: no
+//! cycles and no fan-out (a V1 node with several declared successors picks one
+//! at runtime from `post`, which a static DAG cannot model). Such flows keep
+//! running on the V1 interpreter, [`AsyncFlow::run_async`].
+//!
+//! V1 code keeps compiling against [`v1`], which exports these types under
+//! their V1 names — `LegacyAsyncNode` as `AsyncNode` — so porting an agent is
+//! an import change: `use agentflow_core::legacy::v1::{AsyncFlow, AsyncNode,
+//! SharedState, AgentFlowError};`.
+
+use crate::async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult};
+use crate::error::AgentFlowError;
+use crate::flow::{Flow, GraphNode, NodeType};
+use crate::value::FlowValue;
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// Output key carrying the full `SharedState` snapshot of an adapted node.
+///
+/// Successor adapters created by [`Flow::from_legacy`] map this key in, so the
+/// state keeps flowing through the DAG exactly as it did through `AsyncFlow`.
+pub const LEGACY_STATE_KEY: &str = "shared_state";
+
+/// Output key carrying the value returned by the legacy node's `post_async`
+/// (`Json(String)` for `Some(action)`, `Json(null)` for `None`).
+pub const LEGACY_ACTION_KEY: &str = "action";
+
+/// Default step budget for [`AsyncFlow::run_async`], guarding against a
+/// `post_async` that keeps routing back into a cycle.
+const DEFAULT_MAX_STEPS: usize = 1000;
+
+/// Id of an [`AsyncFlow`] start node whose `get_node_id` returns `None`.
+pub const DEFAULT_START_ID: &str = "start";
+
+/// The V1 API under its V1 names, for code written against
+/// `agentflow_core::{AsyncFlow, AsyncNode, SharedState}` before V2 took the
+/// `AsyncNode` name.
+pub mod v1 {
+  pub use super::{AsyncFlow, LegacyAsyncNode as AsyncNode, SharedState};
+  pub use crate::error::AgentFlowError;
+
+  /// The V1 result alias.
+  pub type Result<T, E = AgentFlowError> = std::result::Result<T, E>;
+}
+
+/// The V1 mutable key/value state shared by every node of an `AsyncFlow`.
+///
+/// Cloning is cheap and yields a handle onto the *same* map, matching the V1
+/// semantics where every node observed every other node's writes.
+#[derive(Debug, Clone, Default)]
+pub struct SharedState {
+  inner: Arc<RwLock<HashMap<String, Value>>>,
+}
+
+impl SharedState {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Build a state pre-populated with `entries`.
+  pub fn from_map(entries: HashMap<String, Value>) -> Self {
+    Self {
+      inner: Arc::new(RwLock::new(entries)),
+    }
+  }
+
+  /// Clone of the value stored under `key`, if any.
+  pub fn get(&self, key: &str) -> Option<Value> {
+    self.read().get(key).cloned()
+  }
+
+  /// Store `value` under `key`, returning the previous value.
+  pub fn insert(&self, key: String, value: Value) -> Option<Value> {
+    self.write().insert(key, value)
+  }
+
+  pub fn remove(&self, key: &str) -> Option<Value> {
+    self.write().remove(key)
+  }
+
+  pub fn contains_key(&self, key: &str) -> bool {
+    self.read().contains_key(key)
+  }
+
+  pub fn keys(&self) -> Vec<String> {
+    self.read().keys().cloned().collect()
+  }
+
+  pub fn len(&self) -> usize {
+    self.read().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.read().is_empty()
+  }
+
+  /// Point-in-time copy of every entry.
+  pub fn snapshot(&self) -> HashMap<String, Value> {
+    self.read().clone()
+  }
+
+  /// Copy every entry of `other` into this state, overwriting existing keys.
+  pub fn merge(&self, other: &Map<String, Value>) {
+    let mut guard = self.write();
+    for (key, value) in other {
+      guard.insert(key.clone(), value.clone());
+    }
+  }
+
+  // A poisoned lock only means another node panicked mid-write; the map itself
+  // is still a valid `HashMap`, so recover the guard rather than propagate the
+  // panic into every later node.
+  fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Value>> {
+    self
+      .inner
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Value>> {
+    self
+      .inner
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+/// The V1 node lifecycle: read from shared state, compute, write back.
+///
+/// `post_async` returns the *action* — in V1 the id of the node to run next,
+/// or `None` to follow the single declared successor (or stop).
+#[async_trait]
+pub trait LegacyAsyncNode: Send + Sync {
+  /// Read what the node needs out of the shared state.
+  async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError>;
+
+  /// Do the work. Receives `prep_async`'s result; must not touch shared state.
+  async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError>;
+
+  /// Write results back into the shared state and pick the next action.
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError>;
+
+  /// Optional stable id for diagnostics (V1 nodes reported it for logging).
+  fn get_node_id(&self) -> Option<String> {
+    None
+  }
+
+  /// Run the full `prep → exec → post` lifecycle against `shared`.
+  async fn run_async(&self, shared: &SharedState) -> Result<Option<String>, AgentFlowError> {
+    let prep_result = self.prep_async(shared).await?;
+    let exec_result = self.exec_async(prep_result.clone()).await?;
+    self.post_async(shared, prep_result, exec_result).await
+  }
+}
+
+/// Exposes a [`LegacyAsyncNode`] as a V2 [`AsyncNode`].
+///
+/// Before `prep_async`, every input is written into a fresh [`SharedState`]:
+/// plain inputs first (a `FlowValue::Json` is stored as its JSON value, `File` /
+/// `Url` as their tagged serialization), then the object under
+/// [`LEGACY_STATE_KEY`] — the upstream snapshot — on top, since it already
+/// reflects every earlier write. After `post_async`, each state entry becomes
+/// an output of the same name alongside the [`LEGACY_STATE_KEY`] snapshot and
+/// the [`LEGACY_ACTION_KEY`] action. A state entry literally named like one of
+/// those two reserved keys is shadowed by them.
+#[derive(Clone)]
+pub struct SharedStateAdapterNode {
+  node: Arc<dyn LegacyAsyncNode>,
+}
+
+impl std::fmt::Debug for SharedStateAdapterNode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SharedStateAdapterNode")
+      .field("node_id", &self.node.get_node_id())
+      .finish_non_exhaustive()
+  }
+}
+
+impl SharedStateAdapterNode {
+  pub fn new(node: Arc<dyn LegacyAsyncNode>) -> Self {
+    Self { node }
+  }
+
+  /// The wrapped legacy node.
+  pub fn inner(&self) -> &Arc<dyn LegacyAsyncNode> {
+    &self.node
+  }
+
+  /// Materialize V2 node inputs as a V1 shared state.
+  pub fn state_from_inputs(inputs: &AsyncNodeInputs) -> Result<SharedState, AgentFlowError> {
+    let state = SharedState::new();
+    for (key, value) in inputs {
+      if key == LEGACY_STATE_KEY {
+        continue;
+      }
+      state.insert(key.clone(), flow_value_to_state_value(value)?);
+    }
+    match inputs.get(LEGACY_STATE_KEY) {
+      None => {}
+      Some(FlowValue::Json(Value::Object(snapshot))) => state.merge(snapshot),
+      Some(_) => {
+        return Err(AgentFlowError::NodeInputError {
+          message: format!("Input '{LEGACY_STATE_KEY}' must be a JSON object state snapshot"),
+        });
+      }
+    }
+    Ok(state)
+  }
+
+  /// Turn the post-run shared state into V2 node outputs.
+  pub fn outputs_from_state(
+    state: &SharedState,
+    action: Option<String>,
+  ) -> HashMap<String, FlowValue> {
+    let snapshot = state.snapshot();
+    let mut outputs: HashMap<String, FlowValue> = snapshot
+      .iter()
+      .map(|(key, value)| (key.clone(), FlowValue::Json(value.clone())))
+      .collect();
+    let snapshot: Map<String, Value> = snapshot.into_iter().collect();
+    outputs.insert(
+      LEGACY_STATE_KEY.to_string(),
+      FlowValue::Json(Value::Object(snapshot)),
+    );
+    outputs.insert(
+      LEGACY_ACTION_KEY.to_string(),
+      FlowValue::Json(action.map(Value::String).unwrap_or(Value::Null)),
+    );
+    outputs
+  }
+}
+
+#[async_trait]
+impl AsyncNode for SharedStateAdapterNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let state = Self::state_from_inputs(inputs)?;
+    let action = self.node.run_async(&state).await?;
+    Ok(Self::outputs_from_state(&state, action))
+  }
+}
+
+fn flow_value_to_state_value(value: &FlowValue) -> Result<Value, AgentFlowError> {
+  match value {
+    FlowValue::Json(json) => Ok(json.clone()),
+    other => Ok(serde_json::to_value(other)?),
+  }
+}
+
+/// A V1 flow: a start node, further legacy nodes keyed by id, and optionally
+/// the transitions between them.
+///
+/// [`AsyncFlow::run_async`] interprets the flow the V1 way — `post_async`'s
+/// action names the next node, and `None` stops, or follows the single
+/// successor declared with [`AsyncFlow::connect`]. [`Flow::from_legacy`] uses
+/// the declared transitions as DAG edges.
+#[derive(Clone)]
+pub struct AsyncFlow {
+  start: String,
+  nodes: BTreeMap<String, Arc<dyn LegacyAsyncNode>>,
+  successors: BTreeMap<String, Vec<String>>,
+  max_steps: usize,
+}
+
+impl std::fmt::Debug for AsyncFlow {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("AsyncFlow")
+      .field("start", &self.start)
+      .field("nodes", &self.nodes.keys().collect::<Vec<_>>())
+      .field("successors", &self.successors)
+      .field("max_steps", &self.max_steps)
+      .finish()
+  }
+}
+
+impl AsyncFlow {
+  /// Create a flow that starts at `start_node`, registered under its
+  /// `get_node_id` or, without one, [`DEFAULT_START_ID`].
+  pub fn new(start_node: Box<dyn LegacyAsyncNode>) -> Self {
+    let start = start_node
+      .get_node_id()
+      .unwrap_or_else(|| DEFAULT_START_ID.to_string());
+    Self {
+      nodes: BTreeMap::from([(start.clone(), Arc::from(start_node))]),
+      start,
+      successors: BTreeMap::new(),
+      max_steps: DEFAULT_MAX_STEPS,
+    }
+  }
+
+  /// Register `node` under `id`, the action its predecessor's `post_async`
+  /// returns to route to it.
+  pub fn add_node(&mut self, id: String, node: Box<dyn LegacyAsyncNode>) {
+    self.nodes.insert(id, Arc::from(node));
+  }
+
+  /// Builder form of [`AsyncFlow::add_node`].
+  pub fn with_node(mut self, id: impl Into<String>, node: Box<dyn LegacyAsyncNode>) -> Self {
+    self.add_node(id.into(), node);
+    self
+  }
+
+  /// Declare that `to` may run after `from`.
+  pub fn connect(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+    let to = to.into();
+    let successors = self.successors.entry(from.into()).or_default();
+    if !successors.contains(&to) {
+      successors.push(to);
+    }
+    self
+  }
+
+  /// Cap the number of node runs in [`AsyncFlow::run_async`].
+  pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+    self.max_steps = max_steps.max(1);
+    self
+  }
+
+  pub fn start(&self) -> &str {
+    &self.start
+  }
+
+  pub fn nodes(&self) -> &BTreeMap<String, Arc<dyn LegacyAsyncNode>> {
+    &self.nodes
+  }
+
+  pub fn successors(&self, id: &str) -> &[String] {
+    self.successors.get(id).map(Vec::as_slice).unwrap_or(&[])
+  }
+
+  /// Run the flow with V1 semantics against `shared`. Returns the ids of the
+  /// nodes that ran, in order, as a JSON array.
+  pub async fn run_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
+    let mut visited = Vec::new();
+    let mut current = Some(self.start.clone());
+    while let Some(node_id) = current {
+      if visited.len() >= self.max_steps {
+        return Err(AgentFlowError::FlowExecutionFailed {
+          message: format!(
+            "legacy flow exceeded {} steps (last node '{}'); check post_async routing for a cycle",
+            self.max_steps, node_id
+          ),
+        });
+      }
+      let node = self
+        .nodes
+        .get(&node_id)
+        .ok_or_else(|| AgentFlowError::UnknownTransition {
+          action: node_id.clone(),
+        })?;
+      let action = node.run_async(shared).await?;
+      visited.push(node_id.clone());
+      current = match action {
+        Some(next) => Some(next),
+        None => match self.successors(&node_id) {
+          [] => None,
+          [only] => Some(only.clone()),
+          _ => {
+            return Err(AgentFlowError::FlowExecutionFailed {
+              message: format!(
+                "legacy node '{}' has several successors but post_async returned no action",
+                node_id
+              ),
+            });
+          }
+        },
+      };
+    }
+    Ok(Value::from(visited))
+  }
+}
+
+impl Flow {
+  /// Convert a V1 [`AsyncFlow`] into a V2 `Flow` of [`SharedStateAdapterNode`]s.
+  ///
+  /// Each declared transition `from → to` becomes a dependency of `to` on
+  /// `from`, and `to` receives `from`'s [`LEGACY_STATE_KEY`] snapshot through
+  /// its `input_mapping`. The state seed a V1 caller put into `SharedState`
+  /// before `run_async` is passed as the flow's initial inputs instead (e.g.
+  /// `execute_from_inputs`); pass plain keys, not a [`LEGACY_STATE_KEY`] object,
+  /// since initial inputs reach every node.
+  ///
+  /// Fails with `FlowDefinitionError` when the flow cannot be expressed as a
+  /// DAG: the start node or a transition endpoint is not registered, a node
+  /// has several successors (runtime branching), the transitions loop back on
+  /// themselves, or a registered node is unreachable from the start.
+  pub fn from_legacy(legacy: AsyncFlow) -> Result<Flow, AgentFlowError> {
+    let definition_error = |message: String| AgentFlowError::FlowDefinitionError { message };
+
+    for (from, targets) in &legacy.successors {
+      if !legacy.nodes.contains_key(from) {
+        return Err(definition_error(format!(
+          "legacy transition source '{from}' is not registered"
+        )));
+      }
+      if targets.len() > 1 {
+        return Err(definition_error(format!(
+          "legacy node '{}' branches to [{}]; runtime branching cannot be converted to a static DAG — run it with AsyncFlow::run_async",
+          from,
+          targets.join(", ")
+        )));
+      }
+    }
+
+    // With at most one successor per node, the flow is a single chain from
+    // the start node; walk it to catch cycles and dangling targets.
+    let mut chain: Vec<String> = Vec::new();
+    let mut current = Some(legacy.start.clone());
+    while let Some(id) = current {
+      if !legacy.nodes.contains_key(&id) {
+        return Err(definition_error(match chain.last() {
+          Some(from) => {
+            format!("legacy transition '{from}' -> '{id}' targets an unregistered node")
+          }
+          None => format!("legacy flow start node '{id}' is not registered"),
+        }));
+      }
+      if chain.contains(&id) {
+        return Err(definition_error(format!(
+          "legacy transitions loop back to '{id}'; cycles cannot be converted to a static DAG"
+        )));
+      }
+      current = legacy.successors(&id).first().cloned();
+      chain.push(id);
+    }
+    if let Some(orphan) = legacy.nodes.keys().find(|id| !chain.contains(id)) {
+      return Err(definition_error(format!(
+        "legacy node '{}' is unreachable from start node '{}'",
+        orphan, legacy.start
+      )));
+    }
+
+    let mut flow = Flow::default();
+    let mut previous: Option<&String> = None;
+    for id in &chain {
+      let (dependencies, input_mapping) = match previous {
+        None => (Vec::new(), HashMap::new()),
+        Some(prev) => (
+          vec![prev.clone()],
+          HashMap::from([(
+            LEGACY_STATE_KEY.to_string(),
+            (prev.clone(), LEGACY_STATE_KEY.to_string()),
+          )]),
+        ),
+      };
+      flow.add_node(GraphNode {
+        id: id.clone(),
+        node_type: NodeType::Standard(Arc::new(SharedStateAdapterNode::new(
+          legacy.nodes[id].clone(),
+        ))),
+        dependencies,
+        input_mapping: Some(input_mapping),
+        run_if: None,
+        initial_inputs: HashMap::new(),
+      });
+      previous = Some(id);
+    }
+    Ok(flow)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// Adds `step` to the `counter` state key and appends its name to `trail`.
+  struct CounterNode {
+    name: &'static str,
+    step: i64,
+  }
+
+  #[async_trait]
+  impl LegacyAsyncNode for CounterNode {
+    async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
+      Ok(shared.get("counter").unwrap_or(json!(0)))
+    }
+
+    async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
+      Ok(json!(prep_result.as_i64().unwrap_or(0) + self.step))
+    }
+
+    async fn post_async(
+      &self,
+      shared: &SharedState,
+      _prep_result: Value,
+      exec_result: Value,
+    ) -> Result<Option<String>, AgentFlowError> {
+      shared.insert("counter".to_string(), exec_result);
+      let mut trail = shared.get("trail").unwrap_or(json!([]));
+      trail.as_array_mut().unwrap().push(json!(self.name));
+      shared.insert("trail".to_string(), trail);
+      Ok(None)
+    }
+
+    fn get_node_id(&self) -> Option<String> {
+      Some(self.name.to_string())
+    }
+  }
+
+  #[tokio::test]
+  async fn adapter_round_trips_inputs_through_shared_state() {
+    let adapter = SharedStateAdapterNode::new(Arc::new(CounterNode { name: "a", step: 2 }));
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("topic".to_string(), FlowValue::Json(json!("rust")));
+    inputs.insert(
+      LEGACY_STATE_KEY.to_string(),
+      FlowValue::Json(json!({"counter": 5})),
+    );
+
+    let outputs = adapter.execute(&inputs).await.unwrap();
+
+    assert_eq!(outputs["counter"], FlowValue::Json(json!(7)));
+    assert_eq!(outputs["topic"], FlowValue::Json(json!("rust")));
+    assert_eq!(outputs[LEGACY_ACTION_KEY], FlowValue::Json(Value::Null));
+    assert_eq!(
+      outputs[LEGACY_STATE_KEY],
+      FlowValue::Json(json!({"counter": 7, "topic": "rust", "trail": ["a"]}))
+    );
+  }
+
+  #[tokio::test]
+  async fn upstream_snapshot_overrides_plain_inputs() {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("counter".to_string(), FlowValue::Json(json!(1)));
+    inputs.insert(
+      LEGACY_STATE_KEY.to_string(),
+      FlowValue::Json(json!({"counter": 10})),
+    );
+    let state = SharedStateAdapterNode::state_from_inputs(&inputs).unwrap();
+    assert_eq!(state.get("counter"), Some(json!(10)));
+  }
+
+  #[tokio::test]
+  async fn legacy_interpreter_follows_declared_successors() {
+    let flow = AsyncFlow::new(Box::new(CounterNode { name: "a", step: 1 }))
+      .with_node(
+        "b",
+        Box::new(CounterNode {
+          name: "b",
+          step: 10,
+        }),
+      )
+      .connect("a", "b");
+    let shared = SharedState::new();
+
+    let visited = flow.run_async(&shared).await.unwrap();
+
+    assert_eq!(visited, json!(["a", "b"]));
+    assert_eq!(shared.get("counter"), Some(json!(11)));
+  }
+
+  #[test]
+  fn from_legacy_wires_state_snapshot_between_nodes() {
+    let legacy = AsyncFlow::new(Box::new(CounterNode { name: "a", step: 1 }))
+      .with_node("b", Box::new(CounterNode { name: "b", step: 1 }))
+      .connect("a", "b");
+
+    let flow = Flow::from_legacy(legacy).unwrap();
+
+    let b = &flow.nodes()["b"];
+    assert_eq!(b.dependencies, vec!["a".to_string()]);
+    assert_eq!(
+      b.input_mapping.as_ref().unwrap()[LEGACY_STATE_KEY],
+      ("a".to_string(), LEGACY_STATE_KEY.to_string())
+    );
+    assert!(flow.nodes()["a"].dependencies.is_empty());
+  }
+
+  #[test]
+  fn from_legacy_rejects_runtime_branching() {
+    let legacy = AsyncFlow::new(Box::new(CounterNode { name: "a", step: 1 }))
+      .with_node("b", Box::new(CounterNode { name: "b", step: 1 }))
+      .with_node("c", Box::new(CounterNode { name: "c", step: 1 }))
+      .connect("a", "b")
+      .connect("a", "c");
+
+    let Err(err) = Flow::from_legacy(legacy) else {
+      panic!("conversion should fail");
+    };
+    assert!(err.to_string().contains("runtime branching"), "{err}");
+  }
+
+  #[test]
+  fn from_legacy_rejects_cycles() {
+    let legacy = AsyncFlow::new(Box::new(CounterNode { name: "a", step: 1 }))
+      .with_node("b", Box::new(CounterNode { name: "b", step: 1 }))
+      .connect("a", "b")
+      .connect("b", "a");
+
+    let Err(err) = Flow::from_legacy(legacy) else {
+      panic!("conversion should fail");
+    };
+    assert!(err.to_string().contains("loop back"), "{err}");
+  }
+
+  #[test]
+  fn from_legacy_rejects_unreachable_nodes() {
+    let legacy = AsyncFlow::new(Box::new(CounterNode { name: "a", step: 1 }))
+      .with_node("orphan", Box::new(CounterNode { name: "o", step: 1 }));
+
+    let Err(err) = Flow::from_legacy(legacy) else {
+      panic!("conversion should fail");
+    };
+    assert!(err.to_string().contains("unreachable"), "{err}");
+  }
+}
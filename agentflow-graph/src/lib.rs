@@ -18,10 +18,13 @@ pub use agentflow_value::{self as value, FlowValue};
 
 pub mod async_node;
 pub mod checkpoint;
+pub mod diff;
 pub mod error;
 pub mod events;
 pub mod expr;
 pub mod flow;
+pub mod inspect;
+pub mod legacy;
 pub mod node;
 pub mod runner;
 pub mod state_size;
@@ -30,6 +33,9 @@ pub mod state_size;
 // `agentflow_graph::{AsyncNode, Flow, ...}` (mirroring the `agentflow_core::*`
 // re-export they may be migrating from).
 pub use async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult};
+pub use diff::{FlowDiff, NodeChange, ValueChange};
 pub use error::AgentFlowError;
 pub use flow::{Flow, GraphNode, NodeType};
-pub use runner::FlowRunner;
+pub use inspect::{ExecutionPlan, FlowValidation, PlanStatus, PlannedNode};
+pub use legacy::{AsyncFlow, LegacyAsyncNode, SharedState, SharedStateAdapterNode};
+pub use runner::{FlowCancellationToken, FlowRunner};
@@ -9,6 +9,8 @@
 //! production implementation (P-A: burns the `agents -> core` edge).
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::async_node::{AsyncNodeInputs, AsyncNodeResult};
 use crate::error::AgentFlowError;
@@ -26,4 +28,52 @@ pub trait FlowRunner: Send + Sync {
     flow: &Flow,
     inputs: AsyncNodeInputs,
   ) -> Result<HashMap<String, AsyncNodeResult>, AgentFlowError>;
+
+  /// [`Self::run`], stopping before the next node once `token` is
+  /// cancelled. A runner that cannot stop a run early ignores the token.
+  async fn run_with_cancellation(
+    &self,
+    flow: &Flow,
+    inputs: AsyncNodeInputs,
+    token: FlowCancellationToken,
+  ) -> Result<HashMap<String, AsyncNodeResult>, AgentFlowError> {
+    let _ = token;
+    self.run(flow, inputs).await
+  }
 }
+
+/// Process-local cancellation signal for Flow execution.
+#[derive(Debug, Clone)]
+pub struct FlowCancellationToken {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl FlowCancellationToken {
+  pub fn new() -> Self {
+    Self {
+      cancelled: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+}
+
+impl Default for FlowCancellationToken {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PartialEq for FlowCancellationToken {
+  fn eq(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.cancelled, &other.cancelled) || self.is_cancelled() == other.is_cancelled()
+  }
+}
+
+impl Eq for FlowCancellationToken {}
@@ -0,0 +1,94 @@
+//! V1 agent code compiles against `legacy::v1` with only its imports changed.
+//!
+//! The nodes and the flow below are written the way the V1 paper research
+//! analyzer was: prep/exec/post nodes that route by returning the next
+//! node's id from `post_async`, a flow built from a boxed start node, and
+//! results read back out of the shared state.
+
+use agentflow_graph::legacy::v1::{AgentFlowError, AsyncFlow, AsyncNode, Result, SharedState};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+struct ParserNode;
+
+#[async_trait]
+impl AsyncNode for ParserNode {
+  async fn prep_async(&self, _shared: &SharedState) -> Result<Value> {
+    Ok(json!({"path": "paper.pdf"}))
+  }
+
+  async fn exec_async(&self, prep_result: Value) -> Result<Value> {
+    Ok(json!({"content": format!("text of {}", prep_result["path"].as_str().unwrap_or_default())}))
+  }
+
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>> {
+    shared.insert("pdf_content".to_string(), exec_result["content"].clone());
+    Ok(Some("summarizer".to_string()))
+  }
+
+  fn get_node_id(&self) -> Option<String> {
+    Some("pdf_parser".to_string())
+  }
+}
+
+struct SummaryNode;
+
+#[async_trait]
+impl AsyncNode for SummaryNode {
+  async fn prep_async(&self, shared: &SharedState) -> Result<Value, AgentFlowError> {
+    shared
+      .get("pdf_content")
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: "pdf_content missing".to_string(),
+      })
+  }
+
+  async fn exec_async(&self, prep_result: Value) -> Result<Value, AgentFlowError> {
+    Ok(json!(format!(
+      "summary of {}",
+      prep_result.as_str().unwrap_or_default()
+    )))
+  }
+
+  async fn post_async(
+    &self,
+    shared: &SharedState,
+    _prep_result: Value,
+    exec_result: Value,
+  ) -> Result<Option<String>, AgentFlowError> {
+    shared.insert("summary".to_string(), exec_result);
+    Ok(None)
+  }
+}
+
+#[tokio::test]
+async fn v1_flow_routes_by_post_action() {
+  let mut flow = AsyncFlow::new(Box::new(ParserNode));
+  flow.add_node("summarizer".to_string(), Box::new(SummaryNode));
+
+  let shared_state = SharedState::new();
+  let execution_result = flow.run_async(&shared_state).await.unwrap();
+
+  assert_eq!(execution_result, json!(["pdf_parser", "summarizer"]));
+  assert_eq!(
+    shared_state.get("summary"),
+    Some(json!("summary of text of paper.pdf"))
+  );
+}
+
+#[tokio::test]
+async fn unknown_action_fails_the_run() {
+  let flow = AsyncFlow::new(Box::new(ParserNode));
+
+  let err = flow.run_async(&SharedState::new()).await.unwrap_err();
+
+  assert!(
+    matches!(err, AgentFlowError::UnknownTransition { ref action } if action == "summarizer"),
+    "{err:?}"
+  );
+}
@@ -1,17 +1,25 @@
 use crate::{
   LLMError, Result, StreamingResponse,
   config::ModelConfig,
+  context_overflow::{
+    self, ContextReport, MESSAGE_OVERHEAD_TOKENS, OverflowPolicy, SUMMARY_MAX_TOKENS,
+  },
   multimodal::MultimodalMessage,
-  providers::ProviderRequest,
+  prompts::{PromptRef, PromptRegistry},
+  providers::{LLMProvider, ProviderRequest},
   registry::ModelRegistry,
   thinking::ThinkingConfig,
+  tokenizer::counter_for_model,
   tool_calling::{LLMResponse, ToolChoice, ToolSpec},
   trace_context::{LlmTraceContext, scope as trace_scope},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
+use zeroize::Zeroizing;
 
 #[cfg(feature = "logging")]
 use tracing::{debug, error, info, warn};
@@ -73,6 +81,17 @@ pub struct LLMClient {
   /// on a model whose registry entry doesn't have `supports_thinking: true`
   /// produces [`LLMError::UnsupportedFeature`] at request-build time.
   pub thinking: Option<ThinkingConfig>,
+  /// What to do when the messages do not fit the model's context window.
+  /// `None` sends them as they are. See [`crate::context_overflow`].
+  pub overflow_policy: Option<OverflowPolicy>,
+  /// Text the assistant's answer starts with (Moonshot partial mode).
+  pub prefill: Option<String>,
+  /// The template `prompt` was rendered from, reported on the response
+  /// and in the request logs. See [`crate::prompts`].
+  pub prompt_template: Option<PromptRef>,
+  /// Why `LLMClientBuilder::prompt_template` failed, as
+  /// `(prompt, message)`; returned by every `execute*`.
+  prompt_template_error: Option<(String, String)>,
   pub enable_logging: bool,
   pub additional_params: HashMap<String, Value>,
   /// Optional W3C trace context to propagate to the underlying HTTP call.
@@ -83,6 +102,8 @@ pub struct LLMClient {
   /// happens to be active (set by a higher layer such as the agent
   /// runtime), or none at all.
   pub trace_context: Option<LlmTraceContext>,
+  /// Overrides the provider's configured API key for this request only.
+  pub api_key: Option<Zeroizing<String>>,
 }
 
 impl LLMClient {
@@ -101,9 +122,23 @@ impl LLMClient {
       tool_choice: None,
       response_format: None,
       thinking: None,
+      overflow_policy: None,
+      prefill: None,
+      prompt_template: None,
+      prompt_template_error: None,
       enable_logging: true,
       additional_params: HashMap::new(),
       trace_context: None,
+      api_key: None,
+    }
+  }
+
+  /// The vendor's shared provider, or a one-off client when this request
+  /// carries its own API key.
+  fn provider(&self, registry: &ModelRegistry, vendor: &str) -> Result<Arc<dyn LLMProvider>> {
+    match &self.api_key {
+      Some(api_key) => registry.provider_with_api_key(vendor, api_key),
+      None => registry.get_provider(vendor),
     }
   }
 
@@ -136,9 +171,10 @@ impl LLMClient {
       false,                // requires streaming (this is non-streaming)
       self.tools.is_some(), // uses tools
     )?;
-    let provider = registry.get_provider(&model_config.vendor)?;
+    let provider = self.provider(registry, &model_config.vendor)?;
 
-    let request = self.build_request(&model_config, false)?;
+    let (fitted, _) = self.fit_context(&model_config).await?;
+    let request = self.build_request(&model_config, false, fitted.as_deref())?;
     let provider = provider.clone();
     let result = match self.trace_context.clone() {
       Some(ctx) => trace_scope(ctx, async move { provider.execute(&request).await }).await,
@@ -155,6 +191,15 @@ impl LLMClient {
     final_result
   }
 
+  /// `name@version` of the prompt template, `-` without one
+  #[cfg(feature = "logging")]
+  fn prompt_template_label(&self) -> String {
+    self
+      .prompt_template
+      .as_ref()
+      .map_or_else(|| "-".to_string(), PromptRef::to_string)
+  }
+
   /// Log request start information
   fn log_request_start(&self) {
     #[cfg(feature = "logging")]
@@ -166,6 +211,9 @@ impl LLMClient {
         self.temperature,
         self.response_format
       );
+      if let Some(prompt) = &self.prompt_template {
+        info!("Prompt template: {}", prompt);
+      }
 
       // Q3.6.2: do NOT log the full prompt at DEBUG. Prompts routinely
       // contain PII, retrieved documents, system prompts with internal
@@ -204,8 +252,9 @@ impl LLMClient {
       match result {
         Ok(response) => {
           info!(
-            "LLM request completed: model={}, duration={}ms, response_len={}",
+            "LLM request completed: model={}, prompt_template={}, duration={}ms, response_len={}",
             self.model_name,
+            self.prompt_template_label(),
             duration.as_millis(),
             response.len()
           );
@@ -233,8 +282,9 @@ impl LLMClient {
         }
         Err(e) => {
           error!(
-            "LLM request failed: model={}, duration={}ms, error={}",
+            "LLM request failed: model={}, prompt_template={}, duration={}ms, error={}",
             self.model_name,
+            self.prompt_template_label(),
             duration.as_millis(),
             e
           );
@@ -277,9 +327,10 @@ impl LLMClient {
       .unwrap_or(false);
 
     model_config.validate_request(true, has_images, false, false, false, self.tools.is_some())?;
-    let provider = registry.get_provider(&model_config.vendor)?;
+    let provider = self.provider(registry, &model_config.vendor)?;
 
-    let request = self.build_request(&model_config, false)?;
+    let (fitted, context) = self.fit_context(&model_config).await?;
+    let request = self.build_request(&model_config, false, fitted.as_deref())?;
     let provider = provider.clone();
     let provider_response = match self.trace_context.clone() {
       Some(ctx) => trace_scope(ctx, async move { provider.execute(&request).await }).await?,
@@ -295,6 +346,9 @@ impl LLMClient {
       usage: provider_response.usage.clone(),
       raw_metadata: provider_response.metadata.clone(),
       thinking: provider_response.thinking.clone(),
+      thinking_blocks: provider_response.thinking_blocks.clone(),
+      context,
+      prompt: self.prompt_template.clone(),
     };
 
     if self.enable_logging {
@@ -329,9 +383,10 @@ impl LLMClient {
       self.tools.is_some(), // uses tools
     )?;
 
-    let provider = registry.get_provider(&model_config.vendor)?;
+    let provider = self.provider(registry, &model_config.vendor)?;
 
-    let request = self.build_request(&model_config, true)?;
+    let (fitted, _) = self.fit_context(&model_config).await?;
+    let request = self.build_request(&model_config, true, fitted.as_deref())?;
 
     let provider = provider.clone();
     let result = match self.trace_context.clone() {
@@ -365,7 +420,110 @@ impl LLMClient {
     result
   }
 
-  fn build_request(&self, model_config: &ModelConfig, streaming: bool) -> Result<ProviderRequest> {
+  /// Applies the overflow policy: the messages to send instead of the
+  /// client's own when the policy changed them, and what it did. Neither
+  /// without a policy or a known context window.
+  async fn fit_context(
+    &self,
+    model_config: &ModelConfig,
+  ) -> Result<(Option<Vec<MultimodalMessage>>, Option<ContextReport>)> {
+    let (Some(policy), Some(context_window)) =
+      (&self.overflow_policy, model_config.context_window())
+    else {
+      return Ok((None, None));
+    };
+
+    let prompt_message;
+    let messages = match &self.multimodal_messages {
+      Some(messages) => messages.as_slice(),
+      None => {
+        prompt_message = [MultimodalMessage::user().add_text(&self.prompt).build()];
+        &prompt_message[..]
+      }
+    };
+    let counter = counter_for_model(model_config.model_id.as_deref().unwrap_or(&self.model_name));
+    let tokens: Vec<u32> = messages
+      .iter()
+      .map(|message| context_overflow::message_tokens(message, counter.as_ref()))
+      .collect();
+    let tool_tokens = match &self.tools {
+      Some(tools) => counter.count_tokens(&serde_json::to_string(tools)?),
+      None => 0,
+    };
+    let before = tool_tokens + tokens.iter().sum::<u32>();
+    let budget =
+      context_window.saturating_sub(self.max_tokens.or(model_config.max_tokens).unwrap_or(0));
+    let mut report = ContextReport {
+      context_window,
+      context_tokens_before: before,
+      context_tokens_after: before,
+      ..ContextReport::default()
+    };
+    if before <= budget {
+      return Ok((None, Some(report)));
+    }
+
+    let overflow = || LLMError::ContextOverflow {
+      model: self.model_name.clone(),
+      tokens: before,
+      budget,
+    };
+    let (from_middle, summary_model) = match policy {
+      OverflowPolicy::Error => return Err(overflow()),
+      OverflowPolicy::TruncateOldest => (false, None),
+      OverflowPolicy::TruncateMiddle => (true, None),
+      OverflowPolicy::SummarizeHistory { model } => (false, Some(model)),
+    };
+    // Keep room for the digest standing in for the evicted turns
+    let reserved =
+      tool_tokens + summary_model.map_or(0, |_| SUMMARY_MAX_TOKENS + MESSAGE_OVERHEAD_TOKENS);
+    let evicted = context_overflow::plan_evictions(
+      messages,
+      &tokens,
+      budget.saturating_sub(reserved),
+      from_middle,
+    )
+    .ok_or_else(overflow)?;
+
+    let mut fitted = context_overflow::without(messages, &evicted);
+    if let Some(model) = summary_model {
+      let summary = LLMClientBuilder::new(model)
+        .prompt(&context_overflow::summary_prompt(messages, &evicted))
+        .max_tokens(SUMMARY_MAX_TOKENS)
+        .enable_logging(self.enable_logging)
+        .trace_context(self.trace_context.clone())
+        .execute();
+      let summary = Box::pin(summary).await?;
+      // Everything before the first evicted message is kept
+      fitted.insert(evicted[0], context_overflow::summary_message(&summary));
+      report.summary = Some(summary);
+    }
+
+    report.context_tokens_after = tool_tokens
+      + fitted
+        .iter()
+        .map(|message| context_overflow::message_tokens(message, counter.as_ref()))
+        .sum::<u32>();
+    report.policy_fired = true;
+    report.evicted = evicted;
+    Ok((Some(fitted), Some(report)))
+  }
+
+  /// `fitted` replaces the client's messages when the overflow policy
+  /// changed them.
+  fn build_request(
+    &self,
+    model_config: &ModelConfig,
+    streaming: bool,
+    fitted: Option<&[MultimodalMessage]>,
+  ) -> Result<ProviderRequest> {
+    if let Some((prompt, message)) = &self.prompt_template_error {
+      return Err(LLMError::PromptTemplateError {
+        prompt: prompt.clone(),
+        message: message.clone(),
+      });
+    }
+
     let mut params = HashMap::new();
 
     // Apply model defaults
@@ -464,13 +622,41 @@ impl LLMClient {
     }
 
     // Build messages based on input type
-    let messages = if let Some(ref multimodal_messages) = self.multimodal_messages {
-      // Use multimodal messages directly
-      self.build_multimodal_messages(multimodal_messages, model_config)?
-    } else {
-      // Use traditional prompt
-      vec![self.build_message_content(model_config)?]
-    };
+    let mut messages =
+      if let Some(multimodal_messages) = fitted.or(self.multimodal_messages.as_deref()) {
+        // Use multimodal messages directly
+        self.build_multimodal_messages(multimodal_messages, model_config)?
+      } else {
+        // Use traditional prompt
+        vec![self.build_message_content(model_config)?]
+      };
+
+    // Partial mode and builtin tools are Moonshot's; other vendors would
+    // reject the `partial` flag or the tool type, or ignore them.
+    let moonshot = model_config.vendor.eq_ignore_ascii_case("moonshot");
+    if let Some(prefill) = &self.prefill {
+      if !moonshot {
+        return Err(LLMError::UnsupportedOperation {
+          message: format!(
+            "assistant prefill needs a Moonshot model; '{}' is served by '{}'",
+            self.model_name, model_config.vendor
+          ),
+        });
+      }
+      messages.push(serde_json::json!({
+        "role": "assistant",
+        "content": prefill,
+        "partial": true
+      }));
+    }
+    if !moonshot && let Some(builtin) = self.tools.iter().flatten().find(|tool| tool.is_builtin()) {
+      return Err(LLMError::UnsupportedOperation {
+        message: format!(
+          "builtin tool '{}' needs a Moonshot model; '{}' is served by '{}'",
+          builtin.name, self.model_name, model_config.vendor
+        ),
+      });
+    }
 
     // Fail-fast: caller asked for thinking but the model isn't configured
     // for it. Better here than after the HTTP round trip — silent provider-
@@ -524,9 +710,32 @@ impl LLMClient {
       self.tools.is_some(), // uses tools
     )?;
 
+    // Replayed thinking blocks and tool turns are in Anthropic's shape;
+    // other vendors have no use for the blocks and no way to take the turns.
+    let anthropic = model_config.vendor.eq_ignore_ascii_case("anthropic");
+    if !anthropic && multimodal_messages.iter().any(|msg| msg.has_tool_turns()) {
+      return Err(crate::LLMError::UnsupportedFeature {
+        model: self.model_name.clone(),
+        feature: "tool_use / tool_result message content".to_string(),
+      });
+    }
+
     for msg in multimodal_messages {
       let capabilities = model_config.get_capabilities();
 
+      if anthropic && (msg.has_thinking() || msg.has_tool_turns()) {
+        // Content blocks go out as they are, whatever the capabilities say
+        messages.push(msg.to_openai_format());
+        continue;
+      }
+      let msg = if msg.has_thinking() {
+        let mut msg = msg.clone();
+        msg.content.retain(|content| !content.is_thinking());
+        Cow::Owned(msg)
+      } else {
+        Cow::Borrowed(msg)
+      };
+
       if capabilities.is_multimodal() {
         // Use full multimodal format for multimodal models
         messages.push(msg.to_openai_format());
@@ -537,6 +746,14 @@ impl LLMClient {
             message: format!("Model {} does not support image input", self.model_name),
           });
         }
+        if msg.has_files() {
+          return Err(crate::LLMError::InvalidModelConfig {
+            message: format!(
+              "Model {} does not support uploaded file input",
+              self.model_name
+            ),
+          });
+        }
         messages.push(serde_json::json!({
           "role": msg.role,
           "content": msg.to_text_format()
@@ -592,13 +809,54 @@ impl LLMClientBuilder {
   pub fn prompt(mut self, prompt: &str) -> Self {
     self.client.prompt = prompt.to_string();
     self.client.multimodal_messages = None; // Clear multimodal if using prompt
+    self.client.prompt_template = None;
+    self.client.prompt_template_error = None;
     self
   }
 
+  /// Use a template from [`PromptRegistry::global`] as the prompt:
+  /// `name@version` pins a version, a bare `name` takes the latest. The
+  /// template's model hints fill in a temperature or max tokens not set
+  /// before this call. A template that is not registered or does not
+  /// render with `vars` fails the request with
+  /// [`LLMError::PromptTemplateError`].
+  pub fn prompt_template<I, K, V>(self, spec: &str, vars: I) -> Self
+  where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+  {
+    let vars = vars
+      .into_iter()
+      .map(|(name, value)| (name.into(), value.into()))
+      .collect();
+    match PromptRegistry::global().render(spec, &vars) {
+      Ok(rendered) => {
+        let mut builder = self.prompt(&rendered.text);
+        let client = &mut builder.client;
+        client.temperature = client.temperature.or(rendered.model_hints.temperature);
+        client.max_tokens = client.max_tokens.or(rendered.model_hints.max_tokens);
+        client.prompt_template = Some(rendered.prompt);
+        builder
+      }
+      Err(err) => {
+        let mut builder = self.prompt("");
+        let message = match err {
+          LLMError::PromptTemplateError { message, .. } => message,
+          other => other.to_string(),
+        };
+        builder.client.prompt_template_error = Some((spec.to_string(), message));
+        builder
+      }
+    }
+  }
+
   /// Set multimodal messages (replaces any existing prompt or messages)
   pub fn multimodal_messages(mut self, messages: Vec<MultimodalMessage>) -> Self {
     self.client.multimodal_messages = Some(messages);
     self.client.prompt = String::new(); // Clear prompt if using multimodal
+    self.client.prompt_template = None;
+    self.client.prompt_template_error = None;
     self
   }
 
@@ -656,10 +914,22 @@ impl LLMClientBuilder {
   /// Provider adapters serialise these to their native format (OpenAI tools
   /// array, Anthropic tools block, Google function_declarations).
   pub fn tools(mut self, tools: Vec<ToolSpec>) -> Self {
-    self.client.tools = Some(tools);
+    self.set_tools(tools);
     self
   }
 
+  /// Replaces the function tools, keeping any [`Self::builtin_tool`]s.
+  fn set_tools(&mut self, tools: Vec<ToolSpec>) {
+    let builtins = self
+      .client
+      .tools
+      .take()
+      .into_iter()
+      .flatten()
+      .filter(ToolSpec::is_builtin);
+    self.client.tools = Some(tools.into_iter().chain(builtins).collect());
+  }
+
   /// Set the tool selection strategy. Has no effect when `tools` is unset.
   pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
     self.client.tool_choice = Some(choice);
@@ -674,7 +944,7 @@ impl LLMClientBuilder {
     let parsed: std::result::Result<Vec<ToolSpec>, String> =
       tools.iter().map(ToolSpec::from_openai_value).collect();
     let parsed = parsed.map_err(|message| LLMError::ConfigurationError { message })?;
-    self.client.tools = Some(parsed);
+    self.set_tools(parsed);
     Ok(self)
   }
 
@@ -705,6 +975,60 @@ impl LLMClientBuilder {
     self
   }
 
+  /// Enable thinking with an explicit token budget: Anthropic's
+  /// `budget_tokens` (at least 1024), sent as is. Shorthand for
+  /// `.thinking(ThinkingConfig::Budget(tokens))`; other providers bucket
+  /// the budget into their own knob (see [`ThinkingConfig::Budget`]).
+  ///
+  /// The budget counts against `max_tokens`; when `max_tokens` is not
+  /// above it, the Anthropic adapter adds the budget on top.
+  pub fn thinking_budget(self, tokens: u32) -> Self {
+    self.thinking(ThinkingConfig::Budget(tokens))
+  }
+
+  /// What to do when the messages do not fit the model's context window:
+  /// its registry `context_window` less `max_tokens`. Without a policy
+  /// (or a known window) the request goes out as it is, and an oversized
+  /// one comes back as the provider's error. `execute_full` reports the
+  /// outcome in [`LLMResponse::context`].
+  ///
+  /// ```ignore
+  /// AgentFlow::model("gpt-4o")
+  ///   .multimodal_messages(history)
+  ///   .overflow_policy(OverflowPolicy::SummarizeHistory { model: "gpt-4o-mini".into() })
+  ///   .execute_full()
+  ///   .await?;
+  /// ```
+  pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+    self.client.overflow_policy = Some(policy);
+    self
+  }
+
+  /// Start the assistant's answer with `text` and let the model continue
+  /// it: Moonshot's partial mode, sent as a trailing assistant message
+  /// with `partial: true`. The response, streamed or not, includes `text`
+  /// followed by the continuation. Other vendors return
+  /// [`LLMError::UnsupportedOperation`].
+  pub fn prefill_assistant<S: Into<String>>(mut self, text: S) -> Self {
+    self.client.prefill = Some(text.into());
+    self
+  }
+
+  /// Enable a tool the provider runs itself, e.g. Moonshot's
+  /// `"$web_search"`, next to any [`Self::tools`]. The model calls it like
+  /// a function; answer the call with its `arguments` unchanged as the tool
+  /// result and Moonshot runs the search. The tokens the results will
+  /// take are in the response metadata's `web_search_tokens`. Moonshot
+  /// only.
+  pub fn builtin_tool<S: Into<String>>(mut self, name: S) -> Self {
+    self
+      .client
+      .tools
+      .get_or_insert_with(Vec::new)
+      .push(ToolSpec::builtin(name));
+    self
+  }
+
   pub fn json_mode(mut self) -> Self {
     self.client.response_format = Some(ResponseFormat::JsonObject);
     self
@@ -731,6 +1055,13 @@ impl LLMClientBuilder {
     self
   }
 
+  /// Authenticate this request with `api_key` rather than the provider's
+  /// configured key.
+  pub fn api_key(mut self, api_key: &str) -> Self {
+    self.client.api_key = Some(Zeroizing::new(api_key.to_string()));
+    self
+  }
+
   pub fn param<T: Into<Value>>(mut self, key: &str, value: T) -> Self {
     self
       .client
@@ -23,6 +23,11 @@ pub struct StreamChunk {
   /// when present (those fields are sent once at the start of a block).
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub tool_call_deltas: Vec<ToolCallDelta>,
+  /// Reasoning text streamed in this chunk, when the provider streams its
+  /// thinking (Anthropic `thinking_delta`). Kept out of `content` so the
+  /// answer text stays clean.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub thinking_delta: Option<String>,
 }
 
 /// Incremental tool_call payload streamed by a provider. Multiple deltas
@@ -0,0 +1,265 @@
+//! Where each field of the effective model configuration comes from.
+//!
+//! The default source is chosen whole (see
+//! [`LLMConfig::resolve_default_source`]), so a user `models.yml`
+//! replaces the built-in `default_models.yml` rather than layering on
+//! top of it. [`LLMConfig::explain`] therefore compares every field of
+//! the effective config with the built-in defaults: a field either is
+//! the built-in value, repeats it in the file, or is set by the file
+//! alone. Built-in models the file leaves out are listed too, since
+//! they are not available while the file is in effect.
+
+use super::{LLMConfig, LLMConfigSource, LLMConfigSourceKind};
+use crate::{LLMError, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// Origin of one field, or of a whole model / provider entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldOrigin {
+  /// No file is in effect; the value is the built-in default.
+  BuiltIn,
+  /// Set in the config file, with the value the built-in defaults have.
+  SameAsBuiltIn,
+  /// Set in the config file, and absent from or different in the
+  /// built-in defaults.
+  ConfigFile,
+}
+
+impl FieldOrigin {
+  /// Short label for annotated output.
+  pub fn label(self) -> &'static str {
+    match self {
+      Self::BuiltIn => "built-in",
+      Self::SameAsBuiltIn => "file, same as built-in",
+      Self::ConfigFile => "file",
+    }
+  }
+}
+
+/// One set field and its origin. Unset (`null`) fields are left out.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldExplanation {
+  pub field: String,
+  pub value: Value,
+  pub origin: FieldOrigin,
+}
+
+/// A model or provider entry with its fields.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntryExplanation {
+  pub name: String,
+  /// `SameAsBuiltIn` when the built-in defaults have an entry of this
+  /// name, whatever its fields; `ConfigFile` when only the file has it.
+  pub origin: FieldOrigin,
+  pub fields: Vec<FieldExplanation>,
+}
+
+/// The effective configuration, annotated. Returned by
+/// [`LLMConfig::explain`] and [`crate::ModelRegistry::explain`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigExplanation {
+  pub source_kind: LLMConfigSourceKind,
+  /// The file in effect; `None` for the built-in defaults.
+  pub source_path: Option<PathBuf>,
+  /// Sorted by name.
+  pub models: Vec<EntryExplanation>,
+  /// Sorted by name.
+  pub providers: Vec<EntryExplanation>,
+  pub defaults: Vec<FieldExplanation>,
+  /// Built-in models missing from the file in effect, sorted.
+  pub unavailable_builtin_models: Vec<String>,
+}
+
+impl LLMConfig {
+  /// Annotate this config, loaded from `source`, with the origin of
+  /// every field, compared with the built-in defaults.
+  pub fn explain(&self, source: &LLMConfigSource) -> Result<ConfigExplanation> {
+    let builtin = Self::builtin()?;
+    let from_file = source.kind != LLMConfigSourceKind::BuiltInDefault;
+    let effective = to_object(self)?;
+    let defaults = to_object(&builtin)?;
+    let section = |name: &str| {
+      explain_entries(
+        effective.get(name).and_then(Value::as_object),
+        defaults.get(name).and_then(Value::as_object),
+        from_file,
+      )
+    };
+
+    let mut unavailable_builtin_models: Vec<String> = if from_file {
+      builtin
+        .models
+        .keys()
+        .filter(|name| !self.models.contains_key(*name))
+        .cloned()
+        .collect()
+    } else {
+      Vec::new()
+    };
+    unavailable_builtin_models.sort();
+
+    Ok(ConfigExplanation {
+      source_kind: source.kind,
+      source_path: source.path.clone(),
+      models: section("models"),
+      providers: section("providers"),
+      defaults: explain_fields(
+        effective.get("defaults").and_then(Value::as_object),
+        defaults.get("defaults").and_then(Value::as_object),
+        from_file,
+      ),
+      unavailable_builtin_models,
+    })
+  }
+}
+
+fn to_object(config: &LLMConfig) -> Result<Map<String, Value>> {
+  match serde_json::to_value(config) {
+    Ok(Value::Object(object)) => Ok(object),
+    Ok(_) => unreachable!("LLMConfig serializes to an object"),
+    Err(e) => Err(LLMError::InternalError {
+      message: format!("Failed to serialize model config: {e}"),
+    }),
+  }
+}
+
+fn explain_entries(
+  effective: Option<&Map<String, Value>>,
+  builtin: Option<&Map<String, Value>>,
+  from_file: bool,
+) -> Vec<EntryExplanation> {
+  let mut entries: Vec<EntryExplanation> = effective
+    .into_iter()
+    .flatten()
+    .map(|(name, value)| {
+      let builtin_entry = builtin.and_then(|builtin| builtin.get(name));
+      EntryExplanation {
+        name: name.clone(),
+        origin: origin(from_file, builtin_entry.is_some()),
+        fields: explain_fields(
+          value.as_object(),
+          builtin_entry.and_then(Value::as_object),
+          from_file,
+        ),
+      }
+    })
+    .collect();
+  entries.sort_by(|a, b| a.name.cmp(&b.name));
+  entries
+}
+
+fn explain_fields(
+  effective: Option<&Map<String, Value>>,
+  builtin: Option<&Map<String, Value>>,
+  from_file: bool,
+) -> Vec<FieldExplanation> {
+  let mut fields: Vec<FieldExplanation> = effective
+    .into_iter()
+    .flatten()
+    .filter(|(_, value)| !value.is_null())
+    .map(|(field, value)| {
+      let same = builtin.and_then(|builtin| builtin.get(field)) == Some(value);
+      FieldExplanation {
+        field: field.clone(),
+        value: value.clone(),
+        origin: origin(from_file, same),
+      }
+    })
+    .collect();
+  fields.sort_by(|a, b| a.field.cmp(&b.field));
+  fields
+}
+
+fn origin(from_file: bool, in_builtin: bool) -> FieldOrigin {
+  match (from_file, in_builtin) {
+    (false, _) => FieldOrigin::BuiltIn,
+    (true, true) => FieldOrigin::SameAsBuiltIn,
+    (true, false) => FieldOrigin::ConfigFile,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn file_source() -> LLMConfigSource {
+    LLMConfigSource {
+      kind: LLMConfigSourceKind::UserModelsYml,
+      path: Some(PathBuf::from("/home/u/.agentflow/models.yml")),
+      warnings: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn file_fields_are_compared_with_the_builtin_defaults() {
+    let builtin = LLMConfig::builtin().unwrap();
+    let gpt = serde_yaml::to_string(&builtin.models["gpt-4o"]).unwrap();
+    let yaml = format!(
+      "models:\n  gpt-4o:\n{}\n  local-llama:\n    vendor: openai\n    base_url: http://localhost:8080/v1\nproviders:\n  openai:\n    api_key_env: MY_OPENAI_KEY\n    base_url: https://api.openai.com/v1\n",
+      gpt
+        .lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+    );
+    let config = LLMConfig::from_yaml(&yaml).unwrap();
+    let explanation = config.explain(&file_source()).unwrap();
+
+    let gpt = &explanation.models[0];
+    assert_eq!(gpt.name, "gpt-4o");
+    assert_eq!(gpt.origin, FieldOrigin::SameAsBuiltIn);
+    assert!(
+      gpt
+        .fields
+        .iter()
+        .all(|field| field.origin == FieldOrigin::SameAsBuiltIn)
+    );
+    let llama = &explanation.models[1];
+    assert_eq!(llama.origin, FieldOrigin::ConfigFile);
+    assert_eq!(
+      llama
+        .fields
+        .iter()
+        .map(|field| field.field.as_str())
+        .collect::<Vec<_>>(),
+      ["base_url", "vendor"]
+    );
+
+    let openai = &explanation.providers[0].fields;
+    let origin_of = |name: &str| openai.iter().find(|f| f.field == name).unwrap().origin;
+    assert_eq!(origin_of("api_key_env"), FieldOrigin::ConfigFile);
+    assert_eq!(origin_of("base_url"), FieldOrigin::SameAsBuiltIn);
+
+    assert!(
+      explanation
+        .unavailable_builtin_models
+        .contains(&"gpt-4o-mini".to_string())
+    );
+    assert!(
+      !explanation
+        .unavailable_builtin_models
+        .contains(&"gpt-4o".to_string())
+    );
+  }
+
+  #[test]
+  fn builtin_source_marks_everything_builtin() {
+    let source = LLMConfigSource {
+      kind: LLMConfigSourceKind::BuiltInDefault,
+      path: None,
+      warnings: Vec::new(),
+    };
+    let explanation = LLMConfig::builtin().unwrap().explain(&source).unwrap();
+    assert!(explanation.unavailable_builtin_models.is_empty());
+    assert!(explanation.models.iter().all(|model| {
+      model.origin == FieldOrigin::BuiltIn
+        && model
+          .fields
+          .iter()
+          .all(|field| field.origin == FieldOrigin::BuiltIn)
+    }));
+  }
+}
@@ -1,9 +1,12 @@
+pub mod explain;
 pub mod model_config;
 pub mod validation;
 pub mod vendor_configs;
 
+pub use explain::{ConfigExplanation, EntryExplanation, FieldExplanation, FieldOrigin};
 pub use model_config::{
-  LLMConfig, LLMConfigSource, LLMConfigSourceKind, MODELS_CONFIG_ENV, ModelConfig, ProviderConfig,
+  LLMConfig, LLMConfigSource, LLMConfigSourceKind, MODELS_CONFIG_ENV, ModelConfig, ModelPricing,
+  ProviderConfig,
 };
 pub use validation::validate_config;
 pub use vendor_configs::{
@@ -248,6 +248,30 @@ impl ModelConfig {
     matches!(self.granular_type(), ModelType::Tts)
   }
 
+  /// Context window in tokens: the entry's `context_window` key, else
+  /// the explicit `capabilities.max_context_tokens`.
+  pub fn context_window(&self) -> Option<u32> {
+    self
+      .additional_params
+      .get("context_window")
+      .and_then(serde_json::Value::as_u64)
+      .and_then(|tokens| u32::try_from(tokens).ok())
+      .or_else(|| {
+        self
+          .capabilities
+          .as_ref()
+          .and_then(|capabilities| capabilities.max_context_tokens)
+      })
+  }
+
+  /// The entry's `pricing` key, when it parses as [`ModelPricing`].
+  pub fn pricing(&self) -> Option<ModelPricing> {
+    self
+      .additional_params
+      .get("pricing")
+      .and_then(|pricing| serde_json::from_value(pricing.clone()).ok())
+  }
+
   /// Check if this model supports the given input type
   pub fn supports_input_type(&self, input_type: &InputType) -> bool {
     self.get_capabilities().supports_input(input_type)
@@ -327,6 +351,23 @@ impl ModelConfig {
   }
 }
 
+/// Price of a model per million tokens, as written under a model
+/// entry's `pricing` key. It is only displayed (`agentflow llm models
+/// --detailed`), never used to meter requests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+  /// Price per million input tokens.
+  pub input: f64,
+  /// Price per million output tokens.
+  pub output: f64,
+  #[serde(default = "default_currency")]
+  pub currency: String,
+}
+
+fn default_currency() -> String {
+  "USD".to_string()
+}
+
 /// Configuration for a provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
@@ -408,6 +449,10 @@ pub enum LLMConfigSourceKind {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GlobalDefaults {
+  /// Model the CLI uses when a command gets no `--model`; set by
+  /// `agentflow config init --interactive`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub model: Option<String>,
   pub timeout_seconds: Option<u64>,
   pub max_retries: Option<u32>,
   pub retry_delay_ms: Option<u64>,
@@ -504,11 +549,16 @@ impl LLMConfig {
     let source = Self::resolve_default_source()?;
     let config = match source.path.as_ref() {
       Some(path) => Self::from_file(path).await?,
-      None => Self::from_yaml(include_str!("../../templates/default_models.yml"))?,
+      None => Self::builtin()?,
     };
     Ok((config, source))
   }
 
+  /// The model defaults bundled with the crate.
+  pub fn builtin() -> Result<Self> {
+    Self::from_yaml(include_str!("../../templates/default_models.yml"))
+  }
+
   /// Get a model configuration by name
   pub fn get_model(&self, model_name: &str) -> Result<&ModelConfig> {
     self
@@ -548,6 +598,8 @@ impl LLMConfig {
       "dashscope" => vec!["DASHSCOPE_API_KEY"],
       "deepseek" => vec!["DEEPSEEK_API_KEY"],
       "minimax" => vec!["MINIMAX_API_KEY"],
+      "groq" => vec!["GROQ_API_KEY"],
+      "ollama" => vec!["OLLAMA_API_KEY"],
       _ => vec![],
     };
 
@@ -557,6 +609,11 @@ impl LLMConfig {
       }
     }
 
+    // A local Ollama takes no key, but the OpenAI client sends one
+    if provider_name.eq_ignore_ascii_case("ollama") {
+      return Ok("ollama".to_string());
+    }
+
     Err(LLMError::MissingApiKey {
       provider: provider_name.to_string(),
     })
@@ -622,6 +679,8 @@ impl LLMConfig {
         "zhipu",
         "deepseek",
         "minimax",
+        "groq",
+        "ollama",
         "mock",
       ]
       .contains(&model_config.vendor.as_str())
@@ -716,6 +775,36 @@ mod tests {
   use std::env;
   use tempfile::TempDir;
 
+  #[test]
+  fn context_window_and_pricing_come_from_extra_keys() {
+    let config = LLMConfig::from_yaml(
+      r#"
+models:
+  priced:
+    vendor: openai
+    context_window: 128000
+    pricing: { input: 2.5, output: 10 }
+  plain:
+    vendor: openai
+    pricing: "cheap"
+"#,
+    )
+    .unwrap();
+    let priced = &config.models["priced"];
+    assert_eq!(priced.context_window(), Some(128_000));
+    assert_eq!(
+      priced.pricing(),
+      Some(ModelPricing {
+        input: 2.5,
+        output: 10.0,
+        currency: "USD".to_string()
+      })
+    );
+    let plain = &config.models["plain"];
+    assert_eq!(plain.context_window(), None);
+    assert_eq!(plain.pricing(), None);
+  }
+
   #[test]
   fn test_model_config_parsing() {
     let yaml = r#"
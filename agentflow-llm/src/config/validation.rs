@@ -73,6 +73,8 @@ impl ConfigValidator {
       "zhipu",
       "deepseek",
       "minimax",
+      "groq",
+      "ollama",
     ];
     if !supported_vendors.contains(&model_config.vendor.as_str()) {
       return Err(LLMError::UnsupportedProvider {
@@ -0,0 +1,344 @@
+//! Fitting a conversation into the model's context window.
+//!
+//! A request whose messages exceed the model's `context_window` comes back
+//! from the provider as an opaque 400. An [`OverflowPolicy`], set with
+//! `LLMClientBuilder::overflow_policy`, checks the assembled messages
+//! before they are sent, keeping `max_tokens` free for the answer. When
+//! they do not fit, the policy either fails with
+//! [`LLMError::ContextOverflow`](crate::LLMError::ContextOverflow) or
+//! evicts whole messages.
+//!
+//! ## Eviction rules
+//!
+//! - System messages and the last user turn (with everything after it)
+//!   are never evicted.
+//! - A message carrying tool results goes with the message that made the
+//!   calls, so a provider never sees one without the other.
+//! - The conversation after the system prompt still opens with a user
+//!   turn, which Anthropic requires.
+//!
+//! [`ContextReport`] on the response says what happened.
+//!
+//! ## Counting
+//!
+//! Counts are estimates. Text is counted with the model's tokenizer (see
+//! [`crate::tokenizer`]). Each image or file is a flat
+//! [`ATTACHMENT_TOKENS`], and each message adds
+//! [`MESSAGE_OVERHEAD_TOKENS`] for its role markers. Tool definitions
+//! count as their JSON.
+
+use crate::multimodal::{MessageContent, MultimodalMessage};
+use crate::tokenizer::TokenCounter;
+
+/// Tokens charged per message on top of its content, for role markers.
+pub const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Tokens charged per image or uploaded file. Providers bill these by
+/// resolution or page count; this is a middle-of-the-road estimate.
+pub const ATTACHMENT_TOKENS: u32 = 1_000;
+
+/// `max_tokens` of the call writing a [`OverflowPolicy::SummarizeHistory`]
+/// digest, and the room kept for the digest in the fitted request.
+pub const SUMMARY_MAX_TOKENS: u32 = 1_024;
+
+/// Opens the message that replaces the evicted turns with their digest.
+pub const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:\n";
+
+/// What to do when a request's messages do not fit the model's context
+/// window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverflowPolicy {
+  /// Fail with `LLMError::ContextOverflow` before anything is sent.
+  Error,
+  /// Evict the oldest messages first.
+  TruncateOldest,
+  /// Evict from the middle of the history outward, keeping the opening
+  /// turns that set up the task and the most recent ones.
+  TruncateMiddle,
+  /// Evict the oldest messages and put a digest of them, written by
+  /// `model`, in their place.
+  SummarizeHistory { model: String },
+}
+
+/// How a request was fitted into the context window.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextReport {
+  /// The model's context window.
+  pub context_window: u32,
+  /// Estimated tokens of the request as given.
+  pub context_tokens_before: u32,
+  /// Estimated tokens of the request as sent.
+  pub context_tokens_after: u32,
+  /// Whether the policy had to change the request.
+  pub policy_fired: bool,
+  /// Indices of the evicted messages, into the messages as given.
+  pub evicted: Vec<usize>,
+  /// The digest that replaced them under
+  /// [`OverflowPolicy::SummarizeHistory`].
+  pub summary: Option<String>,
+}
+
+/// Estimated tokens of one message.
+pub fn message_tokens(message: &MultimodalMessage, counter: &dyn TokenCounter) -> u32 {
+  let content: u32 = message
+    .content
+    .iter()
+    .map(|content| match content {
+      MessageContent::Text { text } => counter.count_tokens(text),
+      MessageContent::ImageUrl { .. }
+      | MessageContent::ImageData { .. }
+      | MessageContent::FileData { .. } => ATTACHMENT_TOKENS,
+      MessageContent::Thinking { thinking, .. } => counter.count_tokens(thinking),
+      MessageContent::RedactedThinking { data } => counter.count_tokens(data),
+      MessageContent::ToolUse { name, input, .. } => {
+        counter.count_tokens(name) + counter.count_tokens(&input.to_string())
+      }
+      MessageContent::ToolResult { content, .. } => counter.count_tokens(content),
+    })
+    .sum();
+  content + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// The messages to evict, in order, so that the rest of `tokens` (one
+/// count per message) fits `budget`: empty when everything fits, `None`
+/// when it cannot fit even with every evictable message gone.
+/// `from_middle` picks [`OverflowPolicy::TruncateMiddle`]'s order over
+/// [`OverflowPolicy::TruncateOldest`]'s.
+pub fn plan_evictions(
+  messages: &[MultimodalMessage],
+  tokens: &[u32],
+  budget: u32,
+  from_middle: bool,
+) -> Option<Vec<usize>> {
+  let mut excess = tokens.iter().sum::<u32>().saturating_sub(budget);
+  let mut units = eviction_units(messages);
+  let mut evicted = Vec::new();
+  while excess > 0 {
+    if units.is_empty() {
+      return None;
+    }
+    let unit = units.remove(if from_middle { units.len() / 2 } else { 0 });
+    excess = excess.saturating_sub(unit.iter().map(|&index| tokens[index]).sum());
+    evicted.extend(unit);
+  }
+  if evicted.is_empty() {
+    return Some(evicted);
+  }
+
+  // Keep the conversation opening with a user turn
+  loop {
+    let first_kept = messages
+      .iter()
+      .enumerate()
+      .find(|(index, message)| message.role != "system" && !evicted.contains(index));
+    match first_kept {
+      Some((index, message)) if message.role != "user" => {
+        let Some(at) = units.iter().position(|unit| unit.contains(&index)) else {
+          break;
+        };
+        evicted.extend(units.remove(at));
+      }
+      _ => break,
+    }
+  }
+  evicted.sort_unstable();
+  Some(evicted)
+}
+
+/// The messages that may be evicted, grouped into what must go together:
+/// a message with tool results joins the one before it.
+fn eviction_units(messages: &[MultimodalMessage]) -> Vec<Vec<usize>> {
+  let last_user = messages.iter().rposition(|message| message.role == "user");
+  let mut units: Vec<Vec<usize>> = Vec::new();
+  for (index, message) in messages.iter().enumerate() {
+    let answers_tool_call = message
+      .content
+      .iter()
+      .any(|content| matches!(content, MessageContent::ToolResult { .. }));
+    match units.last_mut() {
+      Some(unit) if answers_tool_call => unit.push(index),
+      _ => units.push(vec![index]),
+    }
+  }
+  units.retain(|unit| {
+    unit
+      .iter()
+      .all(|&index| messages[index].role != "system" && last_user.is_none_or(|last| index < last))
+  });
+  units
+}
+
+/// `messages` without the `evicted` indices.
+pub(crate) fn without(messages: &[MultimodalMessage], evicted: &[usize]) -> Vec<MultimodalMessage> {
+  messages
+    .iter()
+    .enumerate()
+    .filter(|(index, _)| !evicted.contains(index))
+    .map(|(_, message)| message.clone())
+    .collect()
+}
+
+/// The prompt asking for a digest of the `evicted` messages.
+pub(crate) fn summary_prompt(messages: &[MultimodalMessage], evicted: &[usize]) -> String {
+  let transcript = evicted
+    .iter()
+    .map(|&index| {
+      let message = &messages[index];
+      format!("{}: {}", message.role, message.to_text_format())
+    })
+    .collect::<Vec<_>>()
+    .join("\n\n");
+  format!(
+    "Summarize the conversation below so it can stand in for it. Keep the facts, \
+     decisions, names, numbers and open questions later turns may rely on. \
+     Answer with the summary only.\n\n{transcript}"
+  )
+}
+
+/// The message standing in for the evicted turns.
+pub(crate) fn summary_message(summary: &str) -> MultimodalMessage {
+  MultimodalMessage::user()
+    .add_text(format!("{SUMMARY_PREFIX}{summary}"))
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::tokenizer::HeuristicCounter;
+
+  /// A system prompt, ten question/answer pairs of 100 tokens each with
+  /// a tool call and its result after the third, then the current
+  /// question.
+  fn long_conversation() -> Vec<MultimodalMessage> {
+    let text = |tag: &str| format!("{tag} {}", "x".repeat(399 - tag.len()));
+    let mut messages = vec![MultimodalMessage::system().add_text("Be brief.").build()];
+    for turn in 0..10 {
+      messages.push(
+        MultimodalMessage::user()
+          .add_text(text(&format!("q{turn}")))
+          .build(),
+      );
+      messages.push(
+        MultimodalMessage::assistant()
+          .add_text(text(&format!("a{turn}")))
+          .build(),
+      );
+      if turn == 2 {
+        messages.push(
+          MultimodalMessage::assistant()
+            .add_content(MessageContent::ToolUse {
+              id: "call_1".to_string(),
+              name: "search".to_string(),
+              input: serde_json::json!({ "q": "x" }),
+            })
+            .build(),
+        );
+        messages.push(
+          MultimodalMessage::user()
+            .add_tool_result("call_1".to_string(), text("result"))
+            .build(),
+        );
+      }
+    }
+    messages.push(MultimodalMessage::user().add_text("And now?").build());
+    messages
+  }
+
+  fn tokens(messages: &[MultimodalMessage]) -> Vec<u32> {
+    messages
+      .iter()
+      .map(|message| message_tokens(message, &HeuristicCounter))
+      .collect()
+  }
+
+  #[test]
+  fn messages_are_counted_with_overhead_and_flat_attachments() {
+    let message = MultimodalMessage::user()
+      .add_text("12345678")
+      .add_image_url("https://example.com/a.png")
+      .build();
+    assert_eq!(
+      message_tokens(&message, &HeuristicCounter),
+      2 + ATTACHMENT_TOKENS + MESSAGE_OVERHEAD_TOKENS
+    );
+  }
+
+  #[test]
+  fn nothing_is_evicted_when_the_conversation_fits() {
+    let messages = long_conversation();
+    let tokens = tokens(&messages);
+    let total = tokens.iter().sum();
+    assert_eq!(
+      plan_evictions(&messages, &tokens, total, false),
+      Some(vec![])
+    );
+  }
+
+  #[test]
+  fn truncate_oldest_drops_the_earliest_turns() {
+    let messages = long_conversation();
+    let tokens = tokens(&messages);
+    let total: u32 = tokens.iter().sum();
+
+    // Room for all but ~250 tokens: three 104-token messages go
+    let evicted = plan_evictions(&messages, &tokens, total - 250, false).unwrap();
+    // q0, a0, q1 would leave a1 opening the conversation, so it goes too
+    assert_eq!(evicted, vec![1, 2, 3, 4]);
+    assert_eq!(without(&messages, &evicted)[1].role, "user");
+  }
+
+  #[test]
+  fn truncate_middle_keeps_the_opening_and_the_latest_turns() {
+    let messages = long_conversation();
+    let tokens = tokens(&messages);
+    let total: u32 = tokens.iter().sum();
+
+    let evicted = plan_evictions(&messages, &tokens, total - 300, true).unwrap();
+    assert_eq!(evicted, vec![11, 12, 13]);
+    let kept = without(&messages, &evicted);
+    assert!(kept[1].get_text().starts_with("q0"));
+    assert!(kept[kept.len() - 2].get_text().starts_with("a9"));
+  }
+
+  #[test]
+  fn a_tool_call_and_its_result_go_together() {
+    let messages = long_conversation();
+    let tokens = tokens(&messages);
+    let total: u32 = tokens.iter().sum();
+
+    // Six messages leave the tool call opening the conversation, and it
+    // takes its result along
+    let evicted = plan_evictions(&messages, &tokens, total - 600, false).unwrap();
+    assert_eq!(evicted, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    assert!(messages[7].has_tool_turns() && messages[8].has_tool_turns());
+  }
+
+  #[test]
+  fn the_system_prompt_and_last_user_turn_are_never_evicted() {
+    let messages = long_conversation();
+    let tokens = tokens(&messages);
+    let last = messages.len() - 1;
+
+    // Everything else goes before this fails
+    let evicted = plan_evictions(&messages, &tokens, tokens[0] + tokens[last], false).unwrap();
+    assert_eq!(evicted, (1..last).collect::<Vec<_>>());
+    assert_eq!(
+      plan_evictions(&messages, &tokens, tokens[0] + tokens[last] - 1, true),
+      None
+    );
+  }
+
+  #[test]
+  fn the_summary_prompt_carries_the_evicted_turns() {
+    let messages = long_conversation();
+    let prompt = summary_prompt(&messages, &[1, 2]);
+    assert!(prompt.contains("\n\nuser: q0 "));
+    assert!(prompt.contains("\n\nassistant: a0 "));
+    assert!(!prompt.contains("q1"));
+    assert_eq!(
+      summary_message("They met.").get_text(),
+      format!("{SUMMARY_PREFIX}They met.")
+    );
+  }
+}
@@ -1,38 +1,35 @@
 //! Configuration updater for adding discovered models to default_models.yml
 
-use super::{DiscoveredModel, ModelFetcher};
+use super::{DiscoveredModel, ModelFetcher, ModelSource, VendorConfig};
 use crate::{
   LLMError, Result,
   config::{LLMConfig, ModelConfig},
 };
 use std::collections::HashMap;
 use std::path::Path;
-use tracing::info;
+use std::sync::Arc;
+use tracing::{info, warn};
 
 /// Updates configuration files with discovered models
 pub struct ConfigUpdater {
-  fetcher: ModelFetcher,
+  source: Arc<dyn ModelSource>,
 }
 
 impl ConfigUpdater {
   /// Create a new ConfigUpdater instance
   pub fn new() -> Result<Self> {
     let fetcher = ModelFetcher::new()?;
-    Ok(Self { fetcher })
+    Ok(Self::with_source(Arc::new(fetcher)))
+  }
+
+  /// Create an updater that reads model lists from `source`.
+  pub fn with_source(source: Arc<dyn ModelSource>) -> Self {
+    Self { source }
   }
 
   /// Fetch models from all vendors and update the default configuration
   pub async fn update_default_models(&self, config_path: &str) -> Result<UpdateResult> {
     info!("Fetching models from all supported vendors...");
-    let discovered_models = self.fetcher.fetch_all_models().await;
-
-    if discovered_models.is_empty() {
-      return Err(LLMError::ConfigurationError {
-        message: "No models could be fetched from any vendor".to_string(),
-      });
-    }
-
-    info!("Fetched models from {} vendors", discovered_models.len());
 
     // Load existing configuration
     let mut config = if Path::new(config_path).exists() {
@@ -42,14 +39,11 @@ impl ConfigUpdater {
       LLMConfig::default()
     };
 
-    let mut stats = UpdateResult::new();
-
-    // Add discovered models to configuration
-    for (vendor, models) in discovered_models {
-      info!("Processing {} models from {}", models.len(), vendor);
-      let vendor_stats = self.add_vendor_models(&mut config, &vendor, &models).await;
-      stats.merge(vendor_stats);
-    }
+    let vendors: Vec<String> = VendorConfig::vendors_with_model_list()
+      .into_iter()
+      .map(|vendor| vendor.name)
+      .collect();
+    let stats = self.merge_discovered(&mut config, &vendors).await?;
 
     // Write updated configuration back to file
     self.write_config(&config, config_path).await?;
@@ -62,6 +56,46 @@ impl ConfigUpdater {
     Ok(stats)
   }
 
+  /// Fetch each of `vendors` and merge its models into `config`.
+  ///
+  /// A vendor that fails to answer is recorded in
+  /// [`UpdateResult::failed_vendors`] and the others still merge; it is
+  /// an error only when every vendor fails.
+  pub async fn merge_discovered(
+    &self,
+    config: &mut LLMConfig,
+    vendors: &[String],
+  ) -> Result<UpdateResult> {
+    let mut stats = UpdateResult::new();
+    let mut fetched_any = false;
+    for vendor in vendors {
+      match self.source.list_models(vendor).await {
+        Ok(models) => {
+          info!("Processing {} models from {}", models.len(), vendor);
+          fetched_any = true;
+          stats.merge(self.add_vendor_models(config, vendor, &models).await);
+        }
+        Err(e) => {
+          warn!("Failed to fetch models from {}: {}", vendor, e);
+          stats.failed_vendors.push(format!("{}: {}", vendor, e));
+        }
+      }
+    }
+
+    if !fetched_any {
+      return Err(LLMError::ConfigurationError {
+        message: match stats.failed_vendors.as_slice() {
+          [] => "No vendors to fetch models from".to_string(),
+          failed => format!(
+            "No models could be fetched from any vendor ({})",
+            failed.join("; ")
+          ),
+        },
+      });
+    }
+    Ok(stats)
+  }
+
   /// Add models from a specific vendor to the configuration
   async fn add_vendor_models(
     &self,
@@ -75,7 +109,11 @@ impl ConfigUpdater {
       let model_key = self.generate_model_key(&model.id, vendor);
       let model_config = self.create_model_config(model, vendor);
 
-      if config.models.contains_key(&model_key) {
+      if let Some(existing) = config.models.get(&model_key) {
+        if same_config(existing, &model_config) {
+          stats.unchanged_models += 1;
+          continue;
+        }
         // Update existing model
         config.models.insert(model_key.clone(), model_config);
         stats.updated_models += 1;
@@ -325,7 +363,7 @@ impl ConfigUpdater {
   }
 
   /// Write configuration to YAML file
-  async fn write_config(&self, config: &LLMConfig, path: &str) -> Result<()> {
+  pub async fn write_config(&self, config: &LLMConfig, path: &str) -> Result<()> {
     let yaml_content = serde_yaml::to_string(config).map_err(|e| LLMError::ConfigurationError {
       message: format!("Failed to serialize config to YAML: {}", e),
     })?;
@@ -347,13 +385,24 @@ impl ConfigUpdater {
   }
 }
 
+/// Whether two model entries would serialize identically.
+fn same_config(a: &ModelConfig, b: &ModelConfig) -> bool {
+  matches!(
+    (serde_json::to_value(a), serde_json::to_value(b)),
+    (Ok(a), Ok(b)) if a == b
+  )
+}
+
 /// Result of configuration update operation
 #[derive(Debug, Clone)]
 pub struct UpdateResult {
   pub added_models: usize,
   pub updated_models: usize,
+  /// Discovered models already configured exactly as discovery would.
+  pub unchanged_models: usize,
   pub added_model_names: Vec<String>,
   pub updated_model_names: Vec<String>,
+  /// `"<vendor>: <error>"` for each vendor whose list could not be fetched.
   pub failed_vendors: Vec<String>,
 }
 
@@ -362,6 +411,7 @@ impl UpdateResult {
     Self {
       added_models: 0,
       updated_models: 0,
+      unchanged_models: 0,
       added_model_names: Vec::new(),
       updated_model_names: Vec::new(),
       failed_vendors: Vec::new(),
@@ -371,6 +421,7 @@ impl UpdateResult {
   fn merge(&mut self, other: UpdateResult) {
     self.added_models += other.added_models;
     self.updated_models += other.updated_models;
+    self.unchanged_models += other.unchanged_models;
     self.added_model_names.extend(other.added_model_names);
     self.updated_model_names.extend(other.updated_model_names);
     self.failed_vendors.extend(other.failed_vendors);
@@ -477,6 +528,74 @@ mod tests {
     assert!(!updater.is_qwen_multimodal("qwen-turbo"));
   }
 
+  struct StaticSource;
+
+  #[async_trait::async_trait]
+  impl ModelSource for StaticSource {
+    async fn list_models(&self, vendor: &str) -> Result<Vec<DiscoveredModel>> {
+      if vendor != "moonshot" {
+        return Err(LLMError::MissingApiKey {
+          provider: vendor.to_string(),
+        });
+      }
+      Ok(
+        ["moonshot-v1-8k", "moonshot-v1-128k"]
+          .into_iter()
+          .map(|id| DiscoveredModel {
+            id: id.to_string(),
+            vendor: vendor.to_string(),
+            display_name: None,
+            owned_by: None,
+            created: None,
+            object: None,
+          })
+          .collect(),
+      )
+    }
+  }
+
+  #[tokio::test]
+  async fn merge_counts_only_real_changes_and_records_failed_vendors() {
+    let updater = ConfigUpdater::with_source(Arc::new(StaticSource));
+    let mut config = LLMConfig::default();
+    let vendors = vec!["moonshot".to_string(), "step".to_string()];
+
+    let first = updater
+      .merge_discovered(&mut config, &vendors)
+      .await
+      .unwrap();
+    assert_eq!(first.added_models, 2);
+    assert_eq!(first.failed_vendors.len(), 1);
+    assert!(first.failed_vendors[0].starts_with("step: "));
+
+    let second = updater
+      .merge_discovered(&mut config, &vendors)
+      .await
+      .unwrap();
+    assert_eq!(
+      (
+        second.added_models,
+        second.updated_models,
+        second.unchanged_models
+      ),
+      (0, 0, 2)
+    );
+
+    config.models.get_mut("moonshot-v1-8k").unwrap().max_tokens = Some(1);
+    let third = updater
+      .merge_discovered(&mut config, &vendors)
+      .await
+      .unwrap();
+    assert_eq!(third.updated_model_names, vec!["moonshot-v1-8k"]);
+
+    assert!(
+      updater
+        .merge_discovered(&mut config, &["step".to_string()])
+        .await
+        .is_err()
+    );
+  }
+
   #[test]
   fn test_update_result_report() {
     let mut result = UpdateResult::new();
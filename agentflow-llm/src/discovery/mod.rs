@@ -6,6 +6,7 @@
 //! - Update configuration with discovered models
 
 use crate::{LLMError, Result};
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -19,6 +20,13 @@ pub use config_updater::{ConfigUpdater, UpdateResult};
 pub use model_fetcher::ModelFetcher;
 pub use model_validator::ModelValidator;
 
+/// Where discovery gets a vendor's model list. [`ModelFetcher`] asks the
+/// vendor's API; tests substitute a fixed list.
+#[async_trait]
+pub trait ModelSource: Send + Sync {
+  async fn list_models(&self, vendor: &str) -> Result<Vec<DiscoveredModel>>;
+}
+
 /// Represents a model from a vendor's API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveredModel {
@@ -1,7 +1,8 @@
 //! Model fetcher implementation for retrieving model lists from vendors
 
-use super::{DiscoveredModel, ModelListResponse, VendorConfig, create_http_client};
+use super::{DiscoveredModel, ModelListResponse, ModelSource, VendorConfig, create_http_client};
 use crate::{LLMError, Result};
+use async_trait::async_trait;
 use reqwest::Client;
 use std::collections::HashMap;
 use std::env;
@@ -60,7 +61,26 @@ impl ModelFetcher {
     }
 
     let api_key = self.get_api_key(&vendor.api_key_env)?;
-    let response = self.make_request(vendor, &api_key).await?;
+    self.fetch_models_with_key(vendor, &api_key).await
+  }
+
+  /// Fetch models from a specific vendor with `api_key` rather than the
+  /// key in the vendor's environment variable, e.g. to check a key before
+  /// it is saved.
+  pub async fn fetch_models_with_key(
+    &self,
+    vendor: &VendorConfig,
+    api_key: &str,
+  ) -> Result<Vec<DiscoveredModel>> {
+    if !vendor.supports_model_list {
+      return Err(LLMError::UnsupportedOperation {
+        message: format!(
+          "Vendor {} does not support model list fetching",
+          vendor.name
+        ),
+      });
+    }
+    let response = self.make_request(vendor, api_key).await?;
 
     let models = self.parse_response(response, &vendor.name).await?;
     debug!("Parsed {} models from {}", models.len(), vendor.name);
@@ -176,6 +196,13 @@ impl ModelFetcher {
   }
 }
 
+#[async_trait]
+impl ModelSource for ModelFetcher {
+  async fn list_models(&self, vendor: &str) -> Result<Vec<DiscoveredModel>> {
+    self.fetch_models_by_vendor_name(vendor).await
+  }
+}
+
 // `Default` is intentionally not implemented: `Self::new()` is fallible
 // (returns `Result<Self>`) because building the underlying `reqwest::Client`
 // can fail on platforms with broken TLS / proxy configuration. Callers go
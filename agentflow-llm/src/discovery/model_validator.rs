@@ -1,8 +1,10 @@
 //! Model validation functionality for verifying user-specified models
 
-use super::{ModelFetcher, VendorConfig};
+use super::{ModelFetcher, ModelSource, VendorConfig};
 use crate::{Result, config::LLMConfig};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use tracing::{info, warn};
 
 /// Result of model validation
@@ -23,23 +25,52 @@ pub struct InvalidModel {
 
 /// Validator for checking if user-specified models exist in vendor APIs
 pub struct ModelValidator {
-  fetcher: ModelFetcher,
+  source: Arc<dyn ModelSource>,
+  /// Model ids (or the fetch error) per vendor, so a config with many
+  /// models from one vendor fetches its list once.
+  listed: Mutex<HashMap<String, std::result::Result<HashSet<String>, String>>>,
 }
 
 impl ModelValidator {
   /// Create a new ModelValidator instance
   pub fn new() -> Result<Self> {
     let fetcher = ModelFetcher::new()?;
-    Ok(Self { fetcher })
+    Ok(Self::with_source(Arc::new(fetcher)))
+  }
+
+  /// Create a validator that reads model lists from `source`.
+  pub fn with_source(source: Arc<dyn ModelSource>) -> Self {
+    Self {
+      source,
+      listed: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// The vendor's model ids, fetched once per validator; errors are
+  /// kept as their rendered message.
+  async fn vendor_model_ids(&self, vendor: &str) -> std::result::Result<HashSet<String>, String> {
+    let mut listed = self.listed.lock().await;
+    if !listed.contains_key(vendor) {
+      let ids = self
+        .source
+        .list_models(vendor)
+        .await
+        .map(|models| models.into_iter().map(|model| model.id).collect())
+        .map_err(|e| e.to_string());
+      listed.insert(vendor.to_string(), ids);
+    }
+    listed[vendor].clone()
   }
 
   /// Validate all models in a configuration
   pub async fn validate_config(&self, config: &LLMConfig) -> ValidationResult {
     let mut valid_models = Vec::new();
     let mut invalid_models = Vec::new();
-    let mut unavailable_vendors = HashSet::new();
+    let mut unavailable_vendors = BTreeSet::new();
 
-    for (model_name, model_config) in &config.models {
+    let mut models: Vec<_> = config.models.iter().collect();
+    models.sort_by(|a, b| a.0.cmp(b.0));
+    for (model_name, model_config) in models {
       info!(
         "Validating model: {} (vendor: {})",
         model_name, model_config.vendor
@@ -81,7 +112,7 @@ impl ModelValidator {
     &self,
     model_name: &str,
     model_config: &crate::config::ModelConfig,
-  ) -> Result<bool> {
+  ) -> std::result::Result<bool, String> {
     let vendor_config = VendorConfig::get_by_name(&model_config.vendor);
 
     // If vendor doesn't support model listing, assume model is valid.
@@ -100,20 +131,23 @@ impl ModelValidator {
     let model_id_to_check = model_config.model_id.as_ref().map_or(model_name, |v| v);
 
     // Check if model exists in vendor's API
-    self
-      .fetcher
-      .model_exists(&model_config.vendor, model_id_to_check)
-      .await
+    Ok(
+      self
+        .vendor_model_ids(&model_config.vendor)
+        .await?
+        .contains(model_id_to_check),
+    )
   }
 
   /// Validate a specific model by name and vendor
   pub async fn validate_model(&self, model_name: &str, vendor: &str) -> Result<bool> {
-    self.fetcher.model_exists(vendor, model_name).await
+    let models = self.source.list_models(vendor).await?;
+    Ok(models.iter().any(|model| model.id == model_name))
   }
 
   /// Get available models for a vendor (for suggesting alternatives)
   pub async fn get_available_models(&self, vendor: &str) -> Result<Vec<String>> {
-    let models = self.fetcher.fetch_models_by_vendor_name(vendor).await?;
+    let models = self.source.list_models(vendor).await?;
     Ok(models.into_iter().map(|m| m.id).collect())
   }
 
@@ -82,6 +82,25 @@ pub enum LLMError {
      or use a model that does (e.g. claude-3-7-sonnet, o3-mini, gemini-2.5-pro, deepseek-reasoner)."
   )]
   UnsupportedFeature { model: String, feature: String },
+
+  /// The request's messages do not fit the model's context window, less
+  /// `max_tokens`. Raised before sending under
+  /// [`crate::OverflowPolicy::Error`], or when a truncating policy has
+  /// nothing left to evict.
+  #[error(
+    "Request for model '{model}' needs ~{tokens} tokens of context but only {budget} fit. \
+     Shorten the conversation or set an overflow policy that truncates or summarizes history."
+  )]
+  ContextOverflow {
+    model: String,
+    tokens: u32,
+    budget: u32,
+  },
+
+  /// A prompt template could not be found, parsed or rendered. `prompt`
+  /// is the `name@version` asked for. See [`crate::prompts`].
+  #[error("Prompt template '{prompt}': {message}")]
+  PromptTemplateError { prompt: String, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, LLMError>;
@@ -144,6 +163,7 @@ fn env_var_hint(provider: &str) -> &'static str {
     "glm" | "bigmodel" | "zhipu" => "GLM_API_KEY (or BIGMODEL_API_KEY, ZHIPU_API_KEY)",
     "deepseek" => "DEEPSEEK_API_KEY",
     "minimax" => "MINIMAX_API_KEY",
+    "groq" => "GROQ_API_KEY",
     _ => "the provider's *_API_KEY env var",
   }
 }
@@ -85,11 +85,13 @@
 
 pub mod client;
 pub mod config;
+pub mod context_overflow;
 pub mod discovery;
 pub mod error;
 pub mod modality_dispatch;
 pub mod model_types;
 pub mod multimodal;
+pub mod prompts;
 pub mod providers;
 pub mod registry;
 pub mod thinking;
@@ -100,20 +102,24 @@ pub mod trace_context;
 // Re-export main API components
 pub use client::{LLMClient, ResponseFormat, StreamingResponse, prompt_fingerprint};
 pub use config::{
-  LLMConfig, LLMConfigSource, LLMConfigSourceKind, LoadingBenchmark, MODELS_CONFIG_ENV,
-  ModelConfig, PerformanceComparison, VendorConfigManager,
+  ConfigExplanation, EntryExplanation, FieldExplanation, FieldOrigin, LLMConfig, LLMConfigSource,
+  LLMConfigSourceKind, LoadingBenchmark, MODELS_CONFIG_ENV, ModelConfig, ModelPricing,
+  PerformanceComparison, VendorConfigManager,
 };
+pub use context_overflow::{ContextReport, OverflowPolicy};
 pub use discovery::{ConfigUpdater, ModelFetcher, ModelValidator};
 pub use error::{LLMError, Result};
 pub use model_types::{InputType, ModelCapabilities, ModelType, OutputType};
-pub use multimodal::{ImageData, ImageUrl, MessageContent, MultimodalMessage};
+pub use multimodal::{FileData, ImageData, ImageUrl, MessageContent, MultimodalMessage};
+pub use prompts::{ModelHints, PromptRef, PromptRegistry, PromptTemplate, RenderedPrompt};
+pub use providers::google_files::{FileHandle, FileState, GoogleFilesClient};
 pub use providers::modality::{
   AsrProvider, AsrRequest, AsrResponse, GeneratedImage, Image2ImageProvider, Image2ImageRequest,
   ImageEditProvider, ImageEditRequest, ImageGenerationResponse, Text2ImageProvider,
-  Text2ImageRequest, TtsProvider, TtsRequest, TtsResponse,
+  Text2ImageRequest, TtsProvider, TtsRequest, TtsResponse, TtsVoice,
 };
 pub use registry::ModelRegistry;
-pub use thinking::{ThinkingConfig, ThinkingKind};
+pub use thinking::{ThinkingBlock, ThinkingConfig, ThinkingKind};
 pub use tokenizer::{
   HeuristicCounter, TiktokenCounter, TokenCounter, TokenCounterError, count_tokens_for_model,
   counter_for_model,
@@ -183,7 +189,8 @@ impl AgentFlow {
   /// 3. `~/.agentflow/models.yaml` (legacy user-specific)
   /// 4. Built-in defaults (bundled in crate)
   ///
-  /// Also loads environment variables from ~/.agentflow/.env if available
+  /// Also loads environment variables from ~/.agentflow/.env if available,
+  /// and the prompt templates in ~/.agentflow/prompts
   pub async fn init() -> Result<()> {
     // Load environment variables from ~/.agentflow/.env
     if let Some(home_dir) = dirs::home_dir() {
@@ -193,6 +200,8 @@ impl AgentFlow {
       }
     }
 
+    PromptRegistry::global().load_user_prompts()?;
+
     let source = LLMConfig::resolve_default_source()?;
     for warning in &source.warnings {
       eprintln!("Warning: {warning}");
@@ -455,6 +464,12 @@ impl AgentFlow {
   pub async fn image_edit(model_name: &str) -> Result<Box<dyn ImageEditProvider>> {
     modality_dispatch::image_edit_provider(model_name).await
   }
+
+  /// Build a [`GoogleFilesClient`] sharing `model_name`'s Gemini
+  /// credentials, for uploading files its requests reference.
+  pub async fn google_files(model_name: &str) -> Result<GoogleFilesClient> {
+    modality_dispatch::google_files_client(model_name).await
+  }
 }
 
 #[cfg(test)]
@@ -8,12 +8,17 @@
 //!
 //! Today only StepFun implements the 5 modality traits — other vendors
 //! return [`LLMError::UnsupportedProvider`]. P-LLM.5 adds OpenAI
-//! Whisper as the second `AsrProvider` implementation.
+//! Whisper as the second `AsrProvider` implementation, and DashScope's
+//! image-synthesis API is the second `Text2ImageProvider`. A text-to-image
+//! model the registry does not list is served by the vendor its name
+//! points at (see [`text2image_vendor`]).
 
 use crate::{
   LLMError, Result,
   model_types::ModelType,
   providers::{
+    dashscope_image::DashScopeImageClient,
+    google_files::GoogleFilesClient,
     modality::{
       AsrProvider, Image2ImageProvider, ImageEditProvider, Text2ImageProvider, TtsProvider,
     },
@@ -110,14 +115,46 @@ pub async fn tts_provider(model_name: &str) -> Result<Box<dyn TtsProvider>> {
   }
 }
 
-/// Build a [`Text2ImageProvider`] for the named text-to-image model.
+/// The vendor serving a text-to-image model, going by its name:
+/// DashScope for `wanx*`, `wan2.*` and `qwen-image*`, StepFun for
+/// `step-*`.
+pub fn text2image_vendor(model_name: &str) -> Option<&'static str> {
+  if ["wanx", "wan2.", "qwen-image"]
+    .iter()
+    .any(|prefix| model_name.starts_with(prefix))
+  {
+    Some("dashscope")
+  } else if model_name.starts_with("step-") {
+    Some("stepfun")
+  } else {
+    None
+  }
+}
+
+/// Build a [`Text2ImageProvider`] for the named text-to-image model. A
+/// model missing from the registry goes to [`text2image_vendor`]'s
+/// vendor at its default endpoint.
 pub async fn text2image_provider(model_name: &str) -> Result<Box<dyn Text2ImageProvider>> {
-  let resolved = resolve(model_name, ModelType::Text2Image).await?;
+  let resolved = match (
+    resolve(model_name, ModelType::Text2Image).await,
+    text2image_vendor(model_name),
+  ) {
+    (Err(LLMError::ModelNotFound { .. }), Some(vendor)) => ResolvedModel {
+      vendor: vendor.to_string(),
+      base_url: None,
+      api_key: resolve_api_key(vendor).await?,
+    },
+    (resolved, _) => resolved?,
+  };
   match resolved.vendor.as_str() {
     "stepfun" | "step" => Ok(Box::new(StepFunSpecializedClient::new(
       &resolved.api_key,
       resolved.base_url,
     )?)),
+    "dashscope" => Ok(Box::new(DashScopeImageClient::new(
+      &resolved.api_key,
+      resolved.base_url,
+    )?)),
     _ => unsupported_vendor(&resolved.vendor, "text-to-image"),
   }
 }
@@ -146,6 +183,21 @@ pub async fn image_edit_provider(model_name: &str) -> Result<Box<dyn ImageEditPr
   }
 }
 
+/// Build a [`GoogleFilesClient`] with the credentials and endpoint of the
+/// named Gemini model, so files uploaded through it can be referenced in
+/// that model's requests. Any model type is accepted; a model of another
+/// vendor returns `UnsupportedProvider`.
+pub async fn google_files_client(model_name: &str) -> Result<GoogleFilesClient> {
+  let model_config = ModelRegistry::global().get_model(model_name)?;
+  match model_config.vendor.as_str() {
+    "google" | "gemini" => GoogleFilesClient::new(
+      &resolve_api_key(&model_config.vendor).await?,
+      model_config.base_url.clone(),
+    ),
+    vendor => unsupported_vendor(vendor, "file upload"),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -176,6 +228,15 @@ mod tests {
     assert!(msg.contains("type 'asr'"), "expected type missing: {msg}");
   }
 
+  #[test]
+  fn text2image_vendor_goes_by_model_prefix() {
+    assert_eq!(text2image_vendor("qwen-image-plus"), Some("dashscope"));
+    assert_eq!(text2image_vendor("wan2.2-t2i-flash"), Some("dashscope"));
+    assert_eq!(text2image_vendor("wanx2.1-t2i-turbo"), Some("dashscope"));
+    assert_eq!(text2image_vendor("step-2x-large"), Some("stepfun"));
+    assert_eq!(text2image_vendor("qwen-vl-plus"), None);
+  }
+
   #[test]
   fn unsupported_vendor_message_names_modality() {
     let err = unsupported_vendor::<()>("openai", "ASR").unwrap_err();
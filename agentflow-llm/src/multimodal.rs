@@ -1,6 +1,9 @@
 //! # Multimodal Support for AgentFlow LLM
 //!
 //! This module provides support for multimodal inputs (text + images) to LLMs.
+//! Gemini models also take files uploaded through its Files API (see
+//! [`GoogleFilesClient`](crate::providers::google_files::GoogleFilesClient))
+//! as `file_data` parts referencing the file's URI.
 //!
 //! ## Example Usage
 //!
@@ -18,6 +21,7 @@
 //!   .execute().await?;
 //! ```
 
+use crate::thinking::ThinkingBlock;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -32,6 +36,29 @@ pub enum MessageContent {
   ImageUrl { image_url: ImageUrl },
   /// Base64 encoded image
   ImageData { image_data: ImageData },
+  /// A file uploaded to the provider, by URI (Gemini's `file_data`)
+  FileData { file_data: FileData },
+  /// Reasoning from an earlier assistant turn, replayed verbatim (Anthropic)
+  Thinking {
+    thinking: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+  },
+  /// Encrypted reasoning from an earlier assistant turn (Anthropic)
+  RedactedThinking { data: String },
+  /// A tool call from an earlier assistant turn (Anthropic)
+  ToolUse {
+    id: String,
+    name: String,
+    input: Value,
+  },
+  /// The result of a tool call, in a user turn (Anthropic)
+  ToolResult {
+    tool_use_id: String,
+    content: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    is_error: bool,
+  },
 }
 
 /// Image URL configuration
@@ -51,6 +78,13 @@ pub struct ImageData {
   pub detail: Option<String>, // "low", "high", "auto"
 }
 
+/// An uploaded file reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileData {
+  pub file_uri: String,
+  pub mime_type: String, // "application/pdf", "video/mp4", etc.
+}
+
 impl MessageContent {
   /// Create text content
   pub fn text<S: Into<String>>(text: S) -> Self {
@@ -99,6 +133,16 @@ impl MessageContent {
     }
   }
 
+  /// Create a reference to an uploaded file
+  pub fn file_uri<S: Into<String>>(file_uri: S, mime_type: S) -> Self {
+    Self::FileData {
+      file_data: FileData {
+        file_uri: file_uri.into(),
+        mime_type: mime_type.into(),
+      },
+    }
+  }
+
   /// Check if this content is text
   pub fn is_text(&self) -> bool {
     matches!(self, MessageContent::Text { .. })
@@ -112,6 +156,27 @@ impl MessageContent {
     )
   }
 
+  /// Check if this content is an uploaded file
+  pub fn is_file(&self) -> bool {
+    matches!(self, MessageContent::FileData { .. })
+  }
+
+  /// Check if this content is replayed reasoning
+  pub fn is_thinking(&self) -> bool {
+    matches!(
+      self,
+      MessageContent::Thinking { .. } | MessageContent::RedactedThinking { .. }
+    )
+  }
+
+  /// Check if this content is a tool call or a tool result
+  pub fn is_tool_turn(&self) -> bool {
+    matches!(
+      self,
+      MessageContent::ToolUse { .. } | MessageContent::ToolResult { .. }
+    )
+  }
+
   /// Get text content if this is text
   pub fn as_text(&self) -> Option<&String> {
     match self {
@@ -166,6 +231,21 @@ impl MultimodalMessage {
     self.content.iter().any(|c| c.is_image())
   }
 
+  /// Check if message references uploaded files
+  pub fn has_files(&self) -> bool {
+    self.content.iter().any(|c| c.is_file())
+  }
+
+  /// Check if message replays reasoning blocks
+  pub fn has_thinking(&self) -> bool {
+    self.content.iter().any(|c| c.is_thinking())
+  }
+
+  /// Check if message carries tool calls or tool results
+  pub fn has_tool_turns(&self) -> bool {
+    self.content.iter().any(|c| c.is_tool_turn())
+  }
+
   /// Get all text content concatenated
   pub fn get_text(&self) -> String {
     self
@@ -198,7 +278,14 @@ impl MultimodalMessage {
           MessageContent::Text { text } => text.clone(),
           MessageContent::ImageUrl { .. } => "[Image from URL]".to_string(),
           MessageContent::ImageData { .. } => "[Image Data]".to_string(),
+          MessageContent::FileData { .. } => "[Uploaded File]".to_string(),
+          MessageContent::Thinking { .. } | MessageContent::RedactedThinking { .. } => {
+            String::new()
+          }
+          MessageContent::ToolUse { name, .. } => format!("[Tool Call: {}]", name),
+          MessageContent::ToolResult { content, .. } => content.clone(),
         })
+        .filter(|text| !text.is_empty())
         .collect::<Vec<_>>()
         .join(" ")
     }
@@ -254,6 +341,24 @@ impl MultimodalMessageBuilder {
     self
   }
 
+  /// Add a reference to an uploaded file
+  pub fn add_file_uri<S: Into<String>>(mut self, file_uri: S, mime_type: S) -> Self {
+    self
+      .content
+      .push(MessageContent::file_uri(file_uri, mime_type));
+    self
+  }
+
+  /// Add the result of the tool call `tool_use_id`
+  pub fn add_tool_result<S: Into<String>>(mut self, tool_use_id: S, content: S) -> Self {
+    self.content.push(MessageContent::ToolResult {
+      tool_use_id: tool_use_id.into(),
+      content: content.into(),
+      is_error: false,
+    });
+    self
+  }
+
   /// Add arbitrary content
   pub fn add_content(mut self, content: MessageContent) -> Self {
     self.content.push(content);
@@ -309,6 +414,21 @@ impl MultimodalMessage {
   }
 }
 
+impl From<ThinkingBlock> for MessageContent {
+  fn from(block: ThinkingBlock) -> Self {
+    match block {
+      ThinkingBlock::Thinking {
+        thinking,
+        signature,
+      } => MessageContent::Thinking {
+        thinking,
+        signature,
+      },
+      ThinkingBlock::RedactedThinking { data } => MessageContent::RedactedThinking { data },
+    }
+  }
+}
+
 /// Conversion from simple string to text-only multimodal message
 impl From<String> for MultimodalMessage {
   fn from(text: String) -> Self {
@@ -375,4 +495,26 @@ mod tests {
     assert!(json["content"].is_array());
     assert_eq!(json["content"].as_array().unwrap().len(), 2);
   }
+
+  #[test]
+  fn test_file_uri_serializes_as_file_data() {
+    let msg = MultimodalMessage::user()
+      .add_text("Summarize this paper")
+      .add_file_uri("https://example.com/files/abc", "application/pdf")
+      .build();
+
+    assert!(msg.has_files());
+    assert!(!msg.has_images());
+    assert_eq!(
+      msg.to_openai_format()["content"][1],
+      serde_json::json!({
+        "type": "file_data",
+        "file_data": {
+          "file_uri": "https://example.com/files/abc",
+          "mime_type": "application/pdf",
+        }
+      })
+    );
+    assert_eq!(msg.to_text_format(), "Summarize this paper [Uploaded File]");
+  }
 }
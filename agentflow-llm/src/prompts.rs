@@ -0,0 +1,509 @@
+//! Named, versioned prompt templates.
+//!
+//! A [`PromptTemplate`] is a prompt body with `{{variable}}` placeholders,
+//! the variables it declares, and hints for the model it was tuned on. It
+//! is written as YAML, one template per file:
+//!
+//! ```yaml
+//! name: summarize_paper
+//! version: 2
+//! description: Three-paragraph summary for the reading list
+//! variables: [title, abstract]
+//! model_hints:
+//!   models: [gpt-4o, claude-3-5-sonnet]
+//!   temperature: 0.2
+//!   max_tokens: 800
+//! template: |
+//!   Summarize the paper "{{title}}" in three paragraphs.
+//!
+//!   {{abstract}}
+//! ```
+//!
+//! A [`PromptRegistry`] holds every version of every template and looks
+//! one up by `name@version`, or by bare `name` for the latest version.
+//! [`AgentFlow::init`](crate::AgentFlow::init) loads the files in
+//! `~/.agentflow/prompts` into [`PromptRegistry::global`]; templates
+//! shipped with a crate go in with
+//! `register_yaml(include_str!("prompts/summarize_paper.yml"))`.
+//!
+//! Rendering is strict: every declared variable must be given and nothing
+//! else may be. A template is checked when it is registered, so a
+//! placeholder that is not declared, or a declared variable the body never
+//! uses, fails at load time rather than at the first request. Text between
+//! double braces that is not a variable name (`{{"key": 1}}`) is left as
+//! it is.
+//!
+//! `LLMClientBuilder::prompt_template("summarize_paper@2", vars)` renders
+//! from the global registry and reports the [`PromptRef`] on
+//! [`LLMResponse::prompt`](crate::LLMResponse::prompt) and in the request
+//! logs, so runs of two versions can be compared.
+
+use crate::{LLMError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Which template, and which version of it, a prompt was rendered from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PromptRef {
+  pub name: String,
+  pub version: u32,
+}
+
+impl fmt::Display for PromptRef {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}@{}", self.name, self.version)
+  }
+}
+
+/// Settings the template was tuned with. The client builder applies
+/// `temperature` and `max_tokens` when the caller has not set them;
+/// `models` is informational.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelHints {
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub models: Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub temperature: Option<f32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_tokens: Option<u32>,
+}
+
+/// One version of a named prompt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+  pub name: String,
+  /// Starts at 1; a higher version is newer.
+  pub version: u32,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  /// The names the body's `{{placeholders}}` use.
+  #[serde(default)]
+  pub variables: Vec<String>,
+  #[serde(default)]
+  pub model_hints: ModelHints,
+  pub template: String,
+}
+
+/// A template with its variables filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedPrompt {
+  pub text: String,
+  pub prompt: PromptRef,
+  pub model_hints: ModelHints,
+}
+
+impl PromptTemplate {
+  /// Parses and [validates](Self::validate) one template.
+  pub fn from_yaml(yaml: &str) -> Result<Self> {
+    let template: Self = serde_yaml::from_str(yaml).map_err(|e| LLMError::ConfigurationError {
+      message: format!("Invalid prompt template: {e}"),
+    })?;
+    template.validate()?;
+    Ok(template)
+  }
+
+  pub fn prompt_ref(&self) -> PromptRef {
+    PromptRef {
+      name: self.name.clone(),
+      version: self.version,
+    }
+  }
+
+  /// Checks the name and version, and that the placeholders in the body
+  /// are exactly the declared variables.
+  pub fn validate(&self) -> Result<()> {
+    if !is_identifier(&self.name) {
+      return Err(self.error(format!(
+        "'{}' is not a valid name: use letters, digits, '_', '-' and '.'",
+        self.name
+      )));
+    }
+    if self.version == 0 {
+      return Err(self.error("versions start at 1".to_string()));
+    }
+    let declared: BTreeSet<&str> = self.variables.iter().map(String::as_str).collect();
+    if declared.len() != self.variables.len() {
+      return Err(self.error("a variable is declared twice".to_string()));
+    }
+    let used: BTreeSet<&str> = placeholders(&self.template).collect();
+    if let Some(undeclared) = used.difference(&declared).next() {
+      return Err(self.error(format!(
+        "the template uses '{{{{{undeclared}}}}}' but does not declare it"
+      )));
+    }
+    if let Some(unused) = declared.difference(&used).next() {
+      return Err(self.error(format!(
+        "'{unused}' is declared but the template never uses it"
+      )));
+    }
+    Ok(())
+  }
+
+  /// Fills in the placeholders. Every declared variable must be in `vars`,
+  /// and `vars` may hold nothing else.
+  pub fn render(&self, vars: &HashMap<String, String>) -> Result<RenderedPrompt> {
+    let missing: Vec<&str> = self
+      .variables
+      .iter()
+      .filter(|name| !vars.contains_key(*name))
+      .map(String::as_str)
+      .collect();
+    if !missing.is_empty() {
+      return Err(self.error(format!("missing variables: {}", missing.join(", "))));
+    }
+    let mut unknown: Vec<&str> = vars
+      .keys()
+      .filter(|name| !self.variables.contains(name))
+      .map(String::as_str)
+      .collect();
+    if !unknown.is_empty() {
+      unknown.sort_unstable();
+      return Err(self.error(format!("undeclared variables: {}", unknown.join(", "))));
+    }
+
+    let mut text = String::with_capacity(self.template.len());
+    let mut rest = self.template.as_str();
+    while let Some((before, name, after)) = next_placeholder(rest) {
+      text.push_str(before);
+      text.push_str(&vars[name]);
+      rest = after;
+    }
+    text.push_str(rest);
+
+    Ok(RenderedPrompt {
+      text,
+      prompt: self.prompt_ref(),
+      model_hints: self.model_hints.clone(),
+    })
+  }
+
+  fn error(&self, message: String) -> LLMError {
+    LLMError::PromptTemplateError {
+      prompt: self.prompt_ref().to_string(),
+      message,
+    }
+  }
+}
+
+/// Every version of every registered template.
+#[derive(Debug, Default)]
+pub struct PromptRegistry {
+  prompts: RwLock<HashMap<String, BTreeMap<u32, Arc<PromptTemplate>>>>,
+}
+
+impl PromptRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The registry `LLMClientBuilder::prompt_template` renders from.
+  pub fn global() -> &'static PromptRegistry {
+    static INSTANCE: OnceLock<PromptRegistry> = OnceLock::new();
+    INSTANCE.get_or_init(PromptRegistry::new)
+  }
+
+  /// `~/.agentflow/prompts`
+  pub fn user_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".agentflow").join("prompts"))
+  }
+
+  /// Adds `template` after [validating](PromptTemplate::validate) it,
+  /// replacing a template with the same name and version.
+  pub fn register(&self, template: PromptTemplate) -> Result<PromptRef> {
+    template.validate()?;
+    let prompt = template.prompt_ref();
+    self
+      .prompts
+      .write()
+      .map_err(|e| lock_error(&e))?
+      .entry(template.name.clone())
+      .or_default()
+      .insert(template.version, Arc::new(template));
+    Ok(prompt)
+  }
+
+  /// Parses and adds one template, e.g. one embedded with `include_str!`.
+  pub fn register_yaml(&self, yaml: &str) -> Result<PromptRef> {
+    self.register(PromptTemplate::from_yaml(yaml)?)
+  }
+
+  /// Adds every `*.yml` and `*.yaml` file in `dir`, in file name order.
+  /// Returns what was loaded. A file that does not parse or validate
+  /// fails the whole load, naming the file.
+  pub fn load_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<PromptRef>> {
+    let dir = dir.as_ref();
+    let read_error = |e: std::io::Error| LLMError::ConfigurationError {
+      message: format!("Failed to read prompt directory {}: {e}", dir.display()),
+    };
+    let mut paths = std::fs::read_dir(dir)
+      .map_err(read_error)?
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<std::io::Result<Vec<_>>>()
+      .map_err(read_error)?;
+    paths.retain(|path| {
+      path.is_file()
+        && path
+          .extension()
+          .is_some_and(|ext| ext == "yml" || ext == "yaml")
+    });
+    paths.sort();
+
+    paths
+      .iter()
+      .map(|path| {
+        let yaml = std::fs::read_to_string(path).map_err(|e| LLMError::ConfigurationError {
+          message: format!("Failed to read prompt file {}: {e}", path.display()),
+        })?;
+        self
+          .register_yaml(&yaml)
+          .map_err(|e| LLMError::ConfigurationError {
+            message: format!("{}: {e}", path.display()),
+          })
+      })
+      .collect()
+  }
+
+  /// Loads [`Self::user_dir`] when it exists.
+  pub fn load_user_prompts(&self) -> Result<Vec<PromptRef>> {
+    match Self::user_dir() {
+      Some(dir) if dir.is_dir() => self.load_dir(dir),
+      _ => Ok(Vec::new()),
+    }
+  }
+
+  /// Looks up `name@version`, or the latest version for a bare `name` or
+  /// `name@latest`.
+  pub fn get(&self, spec: &str) -> Result<Arc<PromptTemplate>> {
+    let not_found = |message: String| LLMError::PromptTemplateError {
+      prompt: spec.to_string(),
+      message,
+    };
+    let (name, version) = match spec.split_once('@') {
+      None => (spec, None),
+      Some((name, "latest")) => (name, None),
+      Some((name, version)) => {
+        let version = version
+          .parse::<u32>()
+          .map_err(|_| not_found(format!("'{version}' is not a version number")))?;
+        (name, Some(version))
+      }
+    };
+
+    let prompts = self.prompts.read().map_err(|e| lock_error(&e))?;
+    let versions = prompts
+      .get(name)
+      .ok_or_else(|| not_found("no prompt template with this name is registered".to_string()))?;
+    let template = match version {
+      Some(version) => versions.get(&version),
+      None => versions.values().next_back(),
+    };
+    template.cloned().ok_or_else(|| {
+      let known: Vec<String> = versions.keys().map(u32::to_string).collect();
+      not_found(format!("registered versions are {}", known.join(", ")))
+    })
+  }
+
+  /// The registered versions of `name`, oldest first.
+  pub fn versions(&self, name: &str) -> Vec<u32> {
+    self
+      .prompts
+      .read()
+      .map(|prompts| {
+        prompts
+          .get(name)
+          .map(|versions| versions.keys().copied().collect())
+          .unwrap_or_default()
+      })
+      .unwrap_or_default()
+  }
+
+  /// [`Self::get`] then [`PromptTemplate::render`].
+  pub fn render(&self, spec: &str, vars: &HashMap<String, String>) -> Result<RenderedPrompt> {
+    self.get(spec)?.render(vars)
+  }
+}
+
+fn lock_error<T>(e: &std::sync::PoisonError<T>) -> LLMError {
+  LLMError::InternalError {
+    message: format!("Prompt registry lock poisoned: {e}"),
+  }
+}
+
+fn is_identifier(text: &str) -> bool {
+  !text.is_empty()
+    && text
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn is_variable(text: &str) -> bool {
+  !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Splits `text` around its first `{{variable}}`: the text before, the
+/// variable name, and the text after.
+fn next_placeholder(text: &str) -> Option<(&str, &str, &str)> {
+  let mut from = 0;
+  while let Some(open) = text[from..].find("{{").map(|i| from + i) {
+    let inner_start = open + 2;
+    let close = text[inner_start..].find("}}")? + inner_start;
+    let name = text[inner_start..close].trim();
+    if is_variable(name) {
+      return Some((&text[..open], name, &text[close + 2..]));
+    }
+    from = inner_start;
+  }
+  None
+}
+
+fn placeholders(mut text: &str) -> impl Iterator<Item = &str> {
+  std::iter::from_fn(move || {
+    let (_, name, rest) = next_placeholder(text)?;
+    text = rest;
+    Some(name)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn summarize(version: u32, body: &str) -> String {
+    format!(
+      "name: summarize_paper\nversion: {version}\nvariables: [title, abstract]\n\
+       model_hints:\n  temperature: 0.2\ntemplate: '{body}'\n"
+    )
+  }
+
+  fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+      .iter()
+      .map(|(k, v)| (k.to_string(), v.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn bare_name_resolves_to_the_latest_version() {
+    let registry = PromptRegistry::new();
+    registry
+      .register_yaml(&summarize(1, "Summarize {{title}}: {{abstract}}"))
+      .unwrap();
+    registry
+      .register_yaml(&summarize(3, "v3 {{title}} / {{ abstract }}"))
+      .unwrap();
+    registry
+      .register_yaml(&summarize(2, "v2 {{title}} / {{abstract}}"))
+      .unwrap();
+
+    assert_eq!(registry.versions("summarize_paper"), vec![1, 2, 3]);
+    assert_eq!(registry.get("summarize_paper").unwrap().version, 3);
+    assert_eq!(registry.get("summarize_paper@latest").unwrap().version, 3);
+    assert_eq!(registry.get("summarize_paper@2").unwrap().version, 2);
+
+    let rendered = registry
+      .render(
+        "summarize_paper@1",
+        &vars(&[("title", "Attention"), ("abstract", "Transformers.")]),
+      )
+      .unwrap();
+    assert_eq!(rendered.text, "Summarize Attention: Transformers.");
+    assert_eq!(rendered.prompt.to_string(), "summarize_paper@1");
+    assert_eq!(rendered.model_hints.temperature, Some(0.2));
+
+    let err = registry.get("summarize_paper@7").unwrap_err();
+    assert!(
+      err.to_string().contains("registered versions are 1, 2, 3"),
+      "{err}"
+    );
+    assert!(registry.get("summarize_paper@two").is_err());
+    assert!(registry.get("translate").is_err());
+  }
+
+  #[test]
+  fn render_requires_exactly_the_declared_variables() {
+    let template =
+      PromptTemplate::from_yaml(&summarize(2, "{{title}} {\"keep\": 1} {{ {{abstract}}")).unwrap();
+
+    let err = template.render(&vars(&[("title", "T")])).unwrap_err();
+    match &err {
+      LLMError::PromptTemplateError { prompt, message } => {
+        assert_eq!(prompt, "summarize_paper@2");
+        assert_eq!(message, "missing variables: abstract");
+      }
+      other => panic!("expected PromptTemplateError, got {other:?}"),
+    }
+
+    let err = template
+      .render(&vars(&[("title", "T"), ("abstract", "A"), ("tone", "dry")]))
+      .unwrap_err();
+    assert!(
+      err.to_string().contains("undeclared variables: tone"),
+      "{err}"
+    );
+
+    // Braces around anything but a variable name are left alone
+    let rendered = template
+      .render(&vars(&[("title", "T"), ("abstract", "A")]))
+      .unwrap();
+    assert_eq!(rendered.text, "T {\"keep\": 1} {{ A");
+  }
+
+  #[test]
+  fn templates_are_checked_when_registered() {
+    let undeclared = "name: p\nversion: 1\nvariables: [a]\ntemplate: \"{{a}} {{b}}\"";
+    let err = PromptTemplate::from_yaml(undeclared).unwrap_err();
+    assert!(err.to_string().contains("does not declare it"), "{err}");
+
+    let unused = "name: p\nversion: 1\nvariables: [a, b]\ntemplate: \"{{a}}\"";
+    let err = PromptTemplate::from_yaml(unused).unwrap_err();
+    assert!(err.to_string().contains("never uses it"), "{err}");
+
+    let zero = "name: p\nversion: 0\ntemplate: hi";
+    assert!(PromptTemplate::from_yaml(zero).is_err());
+  }
+
+  #[test]
+  fn registry_loads_a_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+      dir.path().join("summarize_v1.yml"),
+      summarize(1, "{{title}} {{abstract}}"),
+    )
+    .unwrap();
+    std::fs::write(
+      dir.path().join("summarize_v2.yaml"),
+      summarize(2, "{{title}}\n{{abstract}}"),
+    )
+    .unwrap();
+    std::fs::write(
+      dir.path().join("greet.yml"),
+      "name: greet\nversion: 1\nvariables: [who]\ntemplate: Hello {{who}}",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("README.md"), "not a prompt").unwrap();
+
+    let registry = PromptRegistry::new();
+    let loaded = registry.load_dir(dir.path()).unwrap();
+    let loaded: Vec<String> = loaded.iter().map(PromptRef::to_string).collect();
+    assert_eq!(
+      loaded,
+      vec!["greet@1", "summarize_paper@1", "summarize_paper@2"]
+    );
+    assert_eq!(registry.get("summarize_paper").unwrap().version, 2);
+    assert_eq!(
+      registry
+        .render("greet", &vars(&[("who", "Ada")]))
+        .unwrap()
+        .text,
+      "Hello Ada"
+    );
+
+    // A broken file names itself
+    std::fs::write(dir.path().join("zz_broken.yml"), "name: [").unwrap();
+    let err = PromptRegistry::new().load_dir(dir.path()).unwrap_err();
+    assert!(err.to_string().contains("zz_broken.yml"), "{err}");
+  }
+}
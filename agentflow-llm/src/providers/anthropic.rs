@@ -2,7 +2,7 @@ use crate::{
   LLMError, Result,
   client::streaming::{StreamChunk, StreamingResponse, ToolCallDelta},
   providers::{ContentType, LLMProvider, ProviderRequest, ProviderResponse},
-  thinking::ThinkingConfig,
+  thinking::{ThinkingBlock, ThinkingConfig},
   tool_calling::{StopReason, ToolCallRequest, ToolChoice, ToolSpec},
 };
 use async_trait::async_trait;
@@ -79,12 +79,18 @@ impl AnthropicProvider {
           Some("system") => {
             system_message = content.as_str().map(|s| s.to_string());
           }
-          Some("user") | Some("assistant") => {
+          Some("user") => {
             anthropic_messages.push(json!({
               "role": role,
               "content": content
             }));
           }
+          Some("assistant") => {
+            anthropic_messages.push(json!({
+              "role": role,
+              "content": thinking_blocks_first(content)
+            }));
+          }
           _ => {}
         }
       }
@@ -131,6 +137,19 @@ impl AnthropicProvider {
     if let Some(thinking) = &request.thinking
       && let Some(block) = thinking_config_to_anthropic_value(thinking)
     {
+      // The thinking budget counts against `max_tokens`, which must stay
+      // above it, and thinking rejects changes to `temperature` / `top_k`
+      // (which model defaults would otherwise send).
+      if let Some(budget) = block["budget_tokens"].as_u64() {
+        let max_tokens = body["max_tokens"].as_u64().unwrap_or(4096);
+        if max_tokens <= budget {
+          body["max_tokens"] = json!(budget + max_tokens);
+        }
+        if let Some(fields) = body.as_object_mut() {
+          fields.remove("temperature");
+          fields.remove("top_k");
+        }
+      }
       body["thinking"] = block;
     }
 
@@ -138,6 +157,23 @@ impl AnthropicProvider {
   }
 }
 
+/// An assistant turn's content with its replayed `thinking` and
+/// `redacted_thinking` blocks moved to the front, where Anthropic requires
+/// them, and otherwise unchanged: their signatures must round-trip intact.
+fn thinking_blocks_first(content: &Value) -> Value {
+  let Some(parts) = content.as_array() else {
+    return content.clone();
+  };
+  let (mut ordered, rest): (Vec<Value>, Vec<Value>) = parts.iter().cloned().partition(|part| {
+    matches!(
+      part.get("type").and_then(Value::as_str),
+      Some("thinking" | "redacted_thinking")
+    )
+  });
+  ordered.extend(rest);
+  Value::Array(ordered)
+}
+
 /// Encode a [`ThinkingConfig`] as Anthropic's `thinking` request block.
 ///
 /// Anthropic only accepts `{ type: "enabled", budget_tokens: N }` or
@@ -192,7 +228,9 @@ pub(crate) fn parse_anthropic_tool_use_blocks(
         name: name.clone(),
         arguments: input.clone(),
       }),
-      AnthropicContent::Text { .. } | AnthropicContent::Thinking { .. } => None,
+      AnthropicContent::Text { .. }
+      | AnthropicContent::Thinking { .. }
+      | AnthropicContent::RedactedThinking { .. } => None,
     })
     .collect()
 }
@@ -206,7 +244,9 @@ pub(crate) fn parse_anthropic_thinking_blocks(content: &[AnthropicContent]) -> O
     .iter()
     .filter_map(|block| match block {
       AnthropicContent::Thinking { thinking, .. } => Some(thinking.as_str()),
-      AnthropicContent::Text { .. } | AnthropicContent::ToolUse { .. } => None,
+      AnthropicContent::Text { .. }
+      | AnthropicContent::ToolUse { .. }
+      | AnthropicContent::RedactedThinking { .. } => None,
     })
     .collect::<Vec<_>>()
     .join("");
@@ -217,6 +257,27 @@ pub(crate) fn parse_anthropic_thinking_blocks(content: &[AnthropicContent]) -> O
   }
 }
 
+/// The `thinking` and `redacted_thinking` blocks of an Anthropic response,
+/// verbatim, for replaying the turn.
+pub(crate) fn anthropic_thinking_blocks(content: &[AnthropicContent]) -> Vec<ThinkingBlock> {
+  content
+    .iter()
+    .filter_map(|block| match block {
+      AnthropicContent::Thinking {
+        thinking,
+        signature,
+      } => Some(ThinkingBlock::Thinking {
+        thinking: thinking.clone(),
+        signature: signature.clone(),
+      }),
+      AnthropicContent::RedactedThinking { data } => {
+        Some(ThinkingBlock::RedactedThinking { data: data.clone() })
+      }
+      AnthropicContent::Text { .. } | AnthropicContent::ToolUse { .. } => None,
+    })
+    .collect()
+}
+
 #[async_trait]
 impl LLMProvider for AnthropicProvider {
   fn name(&self) -> &str {
@@ -259,7 +320,9 @@ impl LLMProvider for AnthropicProvider {
       .iter()
       .filter_map(|block| match block {
         AnthropicContent::Text { text } => Some(text.as_str()),
-        AnthropicContent::ToolUse { .. } | AnthropicContent::Thinking { .. } => None,
+        AnthropicContent::ToolUse { .. }
+        | AnthropicContent::Thinking { .. }
+        | AnthropicContent::RedactedThinking { .. } => None,
       })
       .collect::<Vec<_>>()
       .join("");
@@ -284,6 +347,7 @@ impl LLMProvider for AnthropicProvider {
     // alongside `text` blocks when extended thinking is enabled. Surface
     // their concatenated text on the typed channel.
     let thinking = parse_anthropic_thinking_blocks(&anthropic_response.content);
+    let thinking_blocks = anthropic_thinking_blocks(&anthropic_response.content);
 
     Ok(ProviderResponse {
       content,
@@ -292,6 +356,7 @@ impl LLMProvider for AnthropicProvider {
       tool_calls,
       stop_reason,
       thinking,
+      thinking_blocks,
     })
   }
 
@@ -400,14 +465,18 @@ pub(crate) enum AnthropicContent {
   /// Extended-thinking block emitted by Claude 3.7+ when `thinking: {
   /// type: "enabled" }` is set on the request. Carries the model's chain
   /// of thought; the `signature` is opaque and used by Anthropic for
-  /// integrity verification on multi-turn replays. We don't act on the
-  /// signature today but accept the field so deserialisation succeeds.
+  /// integrity verification on multi-turn replays, so it is kept verbatim
+  /// in [`ThinkingBlock`].
   #[serde(rename = "thinking")]
   Thinking {
     thinking: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     signature: Option<String>,
   },
+  /// Thinking flagged by Anthropic's safety systems, returned encrypted.
+  /// It has no readable text but must be replayed like a `thinking` block.
+  #[serde(rename = "redacted_thinking")]
+  RedactedThinking { data: String },
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -452,6 +521,19 @@ impl AnthropicStreamingResponse {
     }
   }
 
+  /// A chunk carrying reasoning, not answer text.
+  fn thinking_chunk(event: &Value, thinking_delta: Option<String>) -> StreamChunk {
+    StreamChunk {
+      content: String::new(),
+      is_final: false,
+      metadata: Some(event.clone()),
+      usage: None,
+      content_type: Some("thinking".to_string()),
+      tool_call_deltas: Vec::new(),
+      thinking_delta,
+    }
+  }
+
   fn parse_sse_event(line: &str) -> Option<StreamChunk> {
     if line.starts_with("event: ") {
       return None; // Event type line, not data
@@ -489,8 +571,16 @@ impl AnthropicStreamingResponse {
                 name,
                 arguments_delta: None,
               }],
+              thinking_delta: None,
             });
           }
+          // A redacted block arrives whole here; pass it on in `metadata`
+          // for callers rebuilding the turn.
+          if let Some(block) = event.get("content_block")
+            && block.get("type").and_then(|t| t.as_str()) == Some("redacted_thinking")
+          {
+            return Some(Self::thinking_chunk(&event, None));
+          }
         }
         "content_block_delta" => {
           if let Some(delta) = event.get("delta") {
@@ -513,8 +603,18 @@ impl AnthropicStreamingResponse {
                   name: None,
                   arguments_delta: Some(partial.to_string()),
                 }],
+                thinking_delta: None,
               });
             }
+            // Extended thinking streams its text as `thinking_delta` and
+            // closes each block with a `signature_delta`.
+            if delta_type == "thinking_delta" {
+              let thinking = delta.get("thinking").and_then(|t| t.as_str());
+              return Some(Self::thinking_chunk(&event, thinking.map(String::from)));
+            }
+            if delta_type == "signature_delta" {
+              return Some(Self::thinking_chunk(&event, None));
+            }
             // Existing path: text_delta on a text block.
             if let Some(text) = delta.get("text")
               && let Some(text_str) = text.as_str()
@@ -526,6 +626,7 @@ impl AnthropicStreamingResponse {
                 usage: None,
                 content_type: Some("text".to_string()),
                 tool_call_deltas: Vec::new(),
+                thinking_delta: None,
               });
             }
           }
@@ -542,6 +643,7 @@ impl AnthropicStreamingResponse {
             usage: None,
             content_type: Some("text".to_string()),
             tool_call_deltas: Vec::new(),
+            thinking_delta: None,
           });
         }
         _ => {}
@@ -887,6 +989,156 @@ mod tests {
     assert!(parse_anthropic_thinking_blocks(&content).is_none());
   }
 
+  #[test]
+  fn parse_mixed_thinking_redacted_and_text_response() {
+    let raw = json!({
+      "id": "msg_x",
+      "type": "message",
+      "role": "assistant",
+      "model": "claude-sonnet-4-20250514",
+      "content": [
+        {"type": "thinking", "thinking": "Need the weather.", "signature": "sig_1"},
+        {"type": "redacted_thinking", "data": "EmwKAhgBEgy3va3pzix"},
+        {"type": "text", "text": "Checking."},
+        {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}}
+      ],
+      "stop_reason": "tool_use",
+      "stop_sequence": null,
+      "usage": {"input_tokens": 20, "output_tokens": 40}
+    });
+    let parsed: AnthropicResponse = serde_json::from_value(raw).unwrap();
+
+    assert_eq!(
+      parse_anthropic_thinking_blocks(&parsed.content).as_deref(),
+      Some("Need the weather.")
+    );
+    assert_eq!(
+      anthropic_thinking_blocks(&parsed.content),
+      vec![
+        ThinkingBlock::Thinking {
+          thinking: "Need the weather.".to_string(),
+          signature: Some("sig_1".to_string()),
+        },
+        ThinkingBlock::RedactedThinking {
+          data: "EmwKAhgBEgy3va3pzix".to_string(),
+        },
+      ]
+    );
+    assert_eq!(parse_anthropic_tool_use_blocks(&parsed.content).len(), 1);
+  }
+
+  /// An explicit budget goes out as `budget_tokens`; `max_tokens` is
+  /// raised above it and the model's default temperature is not sent.
+  #[test]
+  fn build_request_body_makes_room_for_the_thinking_budget() {
+    let provider = AnthropicProvider::new("test-key", None).unwrap();
+    let mut request = ProviderRequest::new(
+      "claude-sonnet-4-20250514",
+      vec![json!({"role": "user", "content": "plan a trip"})],
+      false,
+    );
+    request
+      .parameters
+      .insert("max_tokens".to_string(), json!(8192));
+    request
+      .parameters
+      .insert("temperature".to_string(), json!(0.6));
+    request.parameters.insert("top_p".to_string(), json!(0.95));
+    request.thinking = Some(ThinkingConfig::Budget(10_000));
+
+    let body = provider.build_request_body(&request);
+    assert_eq!(
+      body["thinking"],
+      json!({"type": "enabled", "budget_tokens": 10_000})
+    );
+    assert_eq!(body["max_tokens"], 18_192);
+    assert!(body.get("temperature").is_none());
+    assert_eq!(body["top_p"], 0.95);
+
+    request
+      .parameters
+      .insert("max_tokens".to_string(), json!(32_000));
+    let body = provider.build_request_body(&request);
+    assert_eq!(body["max_tokens"], 32_000);
+  }
+
+  /// A turn that called tools is replayed with its thinking blocks first
+  /// and their signatures intact.
+  #[test]
+  fn build_request_body_replays_thinking_before_tool_use() {
+    let provider = AnthropicProvider::new("test-key", None).unwrap();
+    let response = crate::LLMResponse {
+      content: "Checking.".to_string(),
+      tool_calls: vec![ToolCallRequest {
+        id: "toolu_1".to_string(),
+        name: "get_weather".to_string(),
+        arguments: json!({"city": "Oslo"}),
+      }],
+      stop_reason: Some(StopReason::ToolCalls),
+      usage: None,
+      raw_metadata: None,
+      thinking: Some("Need the weather.".to_string()),
+      thinking_blocks: vec![
+        ThinkingBlock::Thinking {
+          thinking: "Need the weather.".to_string(),
+          signature: Some("sig_1".to_string()),
+        },
+        ThinkingBlock::RedactedThinking {
+          data: "EmwKAhgBEgy3va3pzix".to_string(),
+        },
+      ],
+      context: None,
+      prompt: None,
+    };
+    let mut assistant = response.assistant_message();
+    // However the caller assembled the turn, thinking goes first.
+    assistant.content.rotate_left(2);
+    let results = crate::MultimodalMessage::user()
+      .add_tool_result("toolu_1", "4°C, cloudy")
+      .build();
+    let request = ProviderRequest::new(
+      "claude-sonnet-4-20250514",
+      vec![
+        json!({"role": "user", "content": "Weather in Oslo?"}),
+        assistant.to_openai_format(),
+        results.to_openai_format(),
+      ],
+      false,
+    );
+
+    let body = provider.build_request_body(&request);
+    let messages = body["messages"].as_array().unwrap();
+    assert_eq!(
+      messages[1]["content"],
+      json!([
+        {"type": "thinking", "thinking": "Need the weather.", "signature": "sig_1"},
+        {"type": "redacted_thinking", "data": "EmwKAhgBEgy3va3pzix"},
+        {"type": "text", "text": "Checking."},
+        {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}}
+      ])
+    );
+    assert_eq!(
+      messages[2]["content"],
+      json!([{"type": "tool_result", "tool_use_id": "toolu_1", "content": "4°C, cloudy"}])
+    );
+  }
+
+  #[test]
+  fn streaming_thinking_delta_is_kept_out_of_content() {
+    let chunk = AnthropicStreamingResponse::parse_sse_event(
+      "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"Let me see\"}}",
+    ).unwrap();
+    assert_eq!(chunk.content, "");
+    assert_eq!(chunk.thinking_delta.as_deref(), Some("Let me see"));
+    assert_eq!(chunk.content_type.as_deref(), Some("thinking"));
+
+    let chunk = AnthropicStreamingResponse::parse_sse_event(
+      "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"signature_delta\",\"signature\":\"sig_1\"}}",
+    ).unwrap();
+    assert!(chunk.thinking_delta.is_none());
+    assert_eq!(chunk.metadata.unwrap()["delta"]["signature"], "sig_1");
+  }
+
   #[test]
   fn streaming_text_delta_remains_unaffected() {
     let chunk = AnthropicStreamingResponse::parse_sse_event(
@@ -0,0 +1,524 @@
+//! Per-vendor deviations from the OpenAI chat completions API.
+//!
+//! DashScope, GLM, DeepSeek, MiniMax, Groq and Ollama speak "OpenAI
+//! compatible" and are served by [`OpenAIProvider`](super::OpenAIProvider),
+//! but each differs in small ways: parameters it rejects or names
+//! differently, where its error body keeps the code and message, and
+//! finish reasons of its own. A [`CompatProfile`] records those
+//! differences for one vendor. The provider applies it to every request
+//! body it builds, every error response and every finish reason, so a
+//! vendor quirk is one line in its profile rather than a branch in the
+//! provider.
+//!
+//! Moonshot and StepFun have providers of their own and use their
+//! profiles only to extract errors.
+
+use crate::{LLMError, tool_calling::StopReason};
+use serde_json::Value;
+
+/// Keys every chat completions body keeps, whatever the profile allows.
+const CORE_KEYS: [&str; 3] = ["model", "messages", "stream"];
+
+/// How one vendor deviates from the OpenAI chat completions API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompatProfile {
+  /// The vendor name, reported by the provider and in error messages.
+  pub vendor: String,
+  /// The API root used when the provider config sets no `base_url`.
+  pub base_url: Option<String>,
+  /// Body keys the vendor accepts besides `model`, `messages` and
+  /// `stream`, under their OpenAI names; others are dropped before
+  /// sending. `None` sends everything.
+  pub allowed_params: Option<Vec<String>>,
+  /// `(openai_name, vendor_name)` pairs, applied after filtering.
+  pub param_renames: Vec<(String, String)>,
+  /// JSON pointers into an error body where the vendor puts its error
+  /// code, tried in order.
+  pub error_code_pointers: Vec<String>,
+  /// JSON pointers to the error message, tried in order.
+  pub error_message_pointers: Vec<String>,
+  /// Vendor finish reasons that do not mean what they would to OpenAI.
+  /// Anything else goes through [`StopReason::from_openai_finish_reason`].
+  pub finish_reasons: Vec<(String, StopReason)>,
+}
+
+impl CompatProfile {
+  /// A profile that sends everything and reads OpenAI-shaped errors.
+  pub fn new(vendor: impl Into<String>) -> Self {
+    Self {
+      vendor: vendor.into(),
+      base_url: None,
+      allowed_params: None,
+      param_renames: Vec::new(),
+      error_code_pointers: vec!["/error/code".to_string(), "/error/type".to_string()],
+      error_message_pointers: vec!["/error/message".to_string()],
+      finish_reasons: Vec::new(),
+    }
+  }
+
+  pub fn allow_params<I, S>(mut self, params: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.allowed_params = Some(params.into_iter().map(Into::into).collect());
+    self
+  }
+
+  pub fn rename_param(mut self, openai_name: &str, vendor_name: &str) -> Self {
+    self
+      .param_renames
+      .push((openai_name.to_string(), vendor_name.to_string()));
+    self
+  }
+
+  /// Replaces the error pointers.
+  pub fn error_pointers(mut self, code: &[&str], message: &[&str]) -> Self {
+    self.error_code_pointers = code.iter().map(|p| p.to_string()).collect();
+    self.error_message_pointers = message.iter().map(|p| p.to_string()).collect();
+    self
+  }
+
+  pub fn base_url(mut self, base_url: &str) -> Self {
+    self.base_url = Some(base_url.to_string());
+    self
+  }
+
+  pub fn finish_reason(mut self, raw: &str, reason: StopReason) -> Self {
+    self.finish_reasons.push((raw.to_string(), reason));
+    self
+  }
+
+  pub fn openai() -> Self {
+    Self::new("openai").base_url("https://api.openai.com/v1")
+  }
+
+  /// DashScope compatible mode. Errors come OpenAI-shaped from the
+  /// compatible endpoint and flat (`code`, `message`) from the native one.
+  pub fn dashscope() -> Self {
+    Self::new("dashscope")
+      .base_url("https://dashscope.aliyuncs.com/compatible-mode/v1")
+      .allow_params([
+        "temperature",
+        "top_p",
+        "top_k",
+        "max_tokens",
+        "stop",
+        "seed",
+        "presence_penalty",
+        "response_format",
+        "tools",
+        "tool_choice",
+        "parallel_tool_calls",
+        "stream_options",
+        "enable_search",
+        "enable_thinking",
+        "thinking_budget",
+      ])
+      .rename_param("max_completion_tokens", "max_tokens")
+      .error_pointers(&["/error/code", "/code"], &["/error/message", "/message"])
+  }
+
+  /// BigModel GLM. Rejects the OpenAI penalties and `n`, and stops with
+  /// `sensitive` when its content filter fires.
+  pub fn glm() -> Self {
+    Self::new("glm")
+      .base_url("https://open.bigmodel.cn/api/paas/v4")
+      .allow_params([
+        "temperature",
+        "top_p",
+        "max_tokens",
+        "stop",
+        "do_sample",
+        "request_id",
+        "user_id",
+        "response_format",
+        "tools",
+        "tool_choice",
+        "thinking",
+      ])
+      .rename_param("max_completion_tokens", "max_tokens")
+      .rename_param("user", "user_id")
+      .finish_reason("sensitive", StopReason::ContentFilter)
+  }
+
+  /// DeepSeek. Takes `max_tokens` only and stops with
+  /// `insufficient_system_resource` when it sheds load mid-answer.
+  pub fn deepseek() -> Self {
+    Self::new("deepseek")
+      .base_url("https://api.deepseek.com/v1")
+      .rename_param("max_completion_tokens", "max_tokens")
+      .finish_reason(
+        "insufficient_system_resource",
+        StopReason::Other("insufficient_system_resource".to_string()),
+      )
+  }
+
+  /// MiniMax. Errors are reported in `base_resp`.
+  pub fn minimax() -> Self {
+    Self::new("minimax")
+      .base_url("https://api.minimaxi.com/v1")
+      .rename_param("max_completion_tokens", "max_tokens")
+      .error_pointers(
+        &["/base_resp/status_code", "/error/code"],
+        &["/base_resp/status_msg", "/error/message"],
+      )
+  }
+
+  /// Groq. Rejects `logprobs`, `logit_bias` and `n`.
+  pub fn groq() -> Self {
+    Self::new("groq")
+      .base_url("https://api.groq.com/openai/v1")
+      .allow_params([
+        "temperature",
+        "top_p",
+        "max_tokens",
+        "max_completion_tokens",
+        "stop",
+        "seed",
+        "presence_penalty",
+        "frequency_penalty",
+        "response_format",
+        "tools",
+        "tool_choice",
+        "parallel_tool_calls",
+        "stream_options",
+        "user",
+        "reasoning_effort",
+        "reasoning_format",
+        "service_tier",
+      ])
+  }
+
+  /// Ollama's `/v1` endpoint. No `tool_choice`, and errors are a bare
+  /// `{"error": "..."}` string.
+  pub fn ollama() -> Self {
+    Self::new("ollama")
+      .base_url("http://localhost:11434/v1")
+      .allow_params([
+        "temperature",
+        "top_p",
+        "max_tokens",
+        "stop",
+        "seed",
+        "presence_penalty",
+        "frequency_penalty",
+        "response_format",
+        "tools",
+        "stream_options",
+        "reasoning_effort",
+      ])
+      .rename_param("max_completion_tokens", "max_tokens")
+      .error_pointers(&["/error/code"], &["/error/message", "/error"])
+  }
+
+  /// Moonshot labels its errors with `type` only.
+  pub fn moonshot() -> Self {
+    Self::new("moonshot")
+      .base_url("https://api.moonshot.cn/v1")
+      .error_pointers(&["/error/type"], &["/error/message"])
+  }
+
+  pub fn stepfun() -> Self {
+    Self::new("stepfun").base_url("https://api.stepfun.com/v1")
+  }
+
+  /// The profile for a vendor name as written in `models.yml`, or `None`
+  /// for vendors that are not OpenAI compatible.
+  pub fn for_vendor(vendor: &str) -> Option<Self> {
+    match vendor.to_lowercase().as_str() {
+      "openai" => Some(Self::openai()),
+      "dashscope" => Some(Self::dashscope()),
+      "glm" | "bigmodel" | "zhipu" => Some(Self::glm()),
+      "deepseek" => Some(Self::deepseek()),
+      "minimax" => Some(Self::minimax()),
+      "groq" => Some(Self::groq()),
+      "ollama" => Some(Self::ollama()),
+      "moonshot" => Some(Self::moonshot()),
+      "stepfun" | "step" => Some(Self::stepfun()),
+      _ => None,
+    }
+  }
+
+  /// Drops the keys the vendor does not accept, then renames.
+  pub fn adapt_request(&self, body: &mut Value) {
+    let Some(object) = body.as_object_mut() else {
+      return;
+    };
+    if let Some(allowed) = &self.allowed_params {
+      let is_allowed = |key: &str| {
+        CORE_KEYS.contains(&key)
+          || allowed.iter().any(|name| name == key)
+          || self
+            .param_renames
+            .iter()
+            .any(|(from, to)| from == key && allowed.contains(to))
+      };
+      let dropped: Vec<String> = object
+        .keys()
+        .filter(|key| !is_allowed(key))
+        .cloned()
+        .collect();
+      for key in &dropped {
+        object.remove(key);
+      }
+      if !dropped.is_empty() {
+        tracing::debug!(
+          vendor = %self.vendor,
+          "dropped parameters the vendor does not accept: {}",
+          dropped.join(", ")
+        );
+      }
+    }
+    for (from, to) in &self.param_renames {
+      // An explicit vendor-named value wins over a renamed one
+      if let Some(value) = object.remove(from)
+        && !object.contains_key(to)
+      {
+        object.insert(to.clone(), value);
+      }
+    }
+  }
+
+  /// Maps a vendor finish reason onto a [`StopReason`].
+  pub fn stop_reason(&self, raw: &str) -> StopReason {
+    self
+      .finish_reasons
+      .iter()
+      .find(|(vendor_reason, _)| vendor_reason == raw)
+      .map(|(_, reason)| reason.clone())
+      .unwrap_or_else(|| StopReason::from_openai_finish_reason(raw))
+  }
+
+  /// The vendor's error code and message from an error body, when it
+  /// parses and has a message.
+  pub fn extract_error(&self, body: &str) -> Option<(Option<String>, String)> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let lookup = |pointers: &[String]| {
+      pointers
+        .iter()
+        .filter_map(|pointer| value.pointer(pointer))
+        .find_map(|found| match found {
+          Value::String(text) if !text.is_empty() => Some(text.clone()),
+          Value::Number(number) => Some(number.to_string()),
+          _ => None,
+        })
+    };
+    let message = lookup(&self.error_message_pointers)?;
+    Some((lookup(&self.error_code_pointers), message))
+  }
+
+  /// An error response as [`LLMError::HttpError`]: the vendor's code and
+  /// message when [`Self::extract_error`] finds them, the raw body
+  /// otherwise.
+  pub fn http_error(&self, status_code: u16, body: &str) -> LLMError {
+    let message = match self.extract_error(body) {
+      Some((Some(code), message)) => format!("{} error {code}: {message}", self.vendor),
+      Some((None, message)) => format!("{} error: {message}", self.vendor),
+      None => body.to_string(),
+    };
+    LLMError::HttpError {
+      status_code,
+      message,
+    }
+  }
+
+  /// Reads a failed response into [`Self::http_error`].
+  pub(crate) async fn error_from_response(&self, response: reqwest::Response) -> LLMError {
+    let status_code = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    self.http_error(status_code, &body)
+  }
+}
+
+impl Default for CompatProfile {
+  fn default() -> Self {
+    Self::openai()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn body() -> Value {
+    json!({
+      "model": "m",
+      "messages": [],
+      "stream": false,
+      "temperature": 0.3,
+      "max_completion_tokens": 256,
+      "frequency_penalty": 0.5,
+      "logprobs": true,
+      "n": 2,
+      "tool_choice": "auto",
+      "user": "u-1"
+    })
+  }
+
+  fn keys(body: &Value) -> Vec<&str> {
+    let mut keys: Vec<&str> = body
+      .as_object()
+      .unwrap()
+      .keys()
+      .map(String::as_str)
+      .collect();
+    keys.sort_unstable();
+    keys
+  }
+
+  #[test]
+  fn parameters_are_stripped_and_renamed_per_vendor() {
+    let cases: [(CompatProfile, &[&str]); 5] = [
+      (
+        CompatProfile::openai(),
+        &[
+          "frequency_penalty",
+          "logprobs",
+          "max_completion_tokens",
+          "messages",
+          "model",
+          "n",
+          "stream",
+          "temperature",
+          "tool_choice",
+          "user",
+        ],
+      ),
+      (
+        CompatProfile::glm(),
+        &[
+          "max_tokens",
+          "messages",
+          "model",
+          "stream",
+          "temperature",
+          "tool_choice",
+          "user_id",
+        ],
+      ),
+      (
+        CompatProfile::groq(),
+        &[
+          "frequency_penalty",
+          "max_completion_tokens",
+          "messages",
+          "model",
+          "stream",
+          "temperature",
+          "tool_choice",
+          "user",
+        ],
+      ),
+      (
+        CompatProfile::ollama(),
+        &[
+          "frequency_penalty",
+          "max_tokens",
+          "messages",
+          "model",
+          "stream",
+          "temperature",
+        ],
+      ),
+      (
+        CompatProfile::dashscope(),
+        &[
+          "max_tokens",
+          "messages",
+          "model",
+          "stream",
+          "temperature",
+          "tool_choice",
+        ],
+      ),
+    ];
+    for (profile, expected) in cases {
+      let mut body = body();
+      profile.adapt_request(&mut body);
+      assert_eq!(keys(&body), expected, "{}", profile.vendor);
+    }
+
+    // The renamed value keeps its content, and an explicit one wins
+    let mut body = json!({"model": "m", "max_completion_tokens": 256});
+    CompatProfile::deepseek().adapt_request(&mut body);
+    assert_eq!(body, json!({"model": "m", "max_tokens": 256}));
+    let mut body = json!({"max_completion_tokens": 256, "max_tokens": 64});
+    CompatProfile::deepseek().adapt_request(&mut body);
+    assert_eq!(body, json!({"max_tokens": 64}));
+  }
+
+  #[test]
+  fn errors_carry_the_vendor_code_and_message() {
+    let cases = [
+      (
+        CompatProfile::openai(),
+        r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+        "openai error invalid_api_key: Incorrect API key provided",
+      ),
+      (
+        CompatProfile::dashscope(),
+        r#"{"code":"InvalidParameter","message":"Range of input length should be [1, 30720]","request_id":"r-1"}"#,
+        "dashscope error InvalidParameter: Range of input length should be [1, 30720]",
+      ),
+      (
+        CompatProfile::minimax(),
+        r#"{"base_resp":{"status_code":1004,"status_msg":"authorized error"}}"#,
+        "minimax error 1004: authorized error",
+      ),
+      (
+        CompatProfile::ollama(),
+        r#"{"error":"model \"llama9\" not found, try pulling it first"}"#,
+        "ollama error: model \"llama9\" not found, try pulling it first",
+      ),
+      (
+        CompatProfile::moonshot(),
+        r#"{"error":{"message":"Invalid Authentication","type":"invalid_authentication_error"}}"#,
+        "moonshot error invalid_authentication_error: Invalid Authentication",
+      ),
+      (
+        CompatProfile::glm(),
+        r#"{"error":{"code":"1261","message":"Prompt exceeds max length"}}"#,
+        "glm error 1261: Prompt exceeds max length",
+      ),
+    ];
+    for (profile, body, expected) in cases {
+      match profile.http_error(400, body) {
+        LLMError::HttpError {
+          status_code,
+          message,
+        } => {
+          assert_eq!(status_code, 400);
+          assert_eq!(message, expected);
+        }
+        other => panic!("expected HttpError, got {other:?}"),
+      }
+    }
+
+    // A body the profile cannot read is passed through
+    let err = CompatProfile::minimax().http_error(502, "<html>Bad Gateway</html>");
+    assert!(
+      err.to_string().contains("<html>Bad Gateway</html>"),
+      "{err}"
+    );
+  }
+
+  #[test]
+  fn vendor_finish_reasons_are_mapped() {
+    assert_eq!(
+      CompatProfile::glm().stop_reason("sensitive"),
+      StopReason::ContentFilter
+    );
+    assert_eq!(CompatProfile::glm().stop_reason("stop"), StopReason::Stop);
+    assert_eq!(
+      CompatProfile::openai().stop_reason("sensitive"),
+      StopReason::Other("sensitive".to_string())
+    );
+    assert_eq!(
+      CompatProfile::for_vendor("zhipu").unwrap().vendor,
+      "glm".to_string()
+    );
+    assert!(CompatProfile::for_vendor("anthropic").is_none());
+  }
+}
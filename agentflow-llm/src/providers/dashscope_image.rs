@@ -0,0 +1,510 @@
+//! DashScope (Alibaba Cloud Model Studio) text-to-image provider.
+//!
+//! Implements [`Text2ImageProvider`] over DashScope's asynchronous
+//! image-synthesis API (the `wanx*` / `wan2.*` and `qwen-image*` models):
+//!
+//! 1. `POST {base_url}/services/aigc/text2image/image-synthesis` with
+//!    `X-DashScope-Async: enable` submits a task and returns its id.
+//! 2. `GET {base_url}/tasks/{task_id}` is polled until the task is
+//!    `SUCCEEDED`, `FAILED`, `CANCELED` or `UNKNOWN`, or until the task
+//!    timeout (default 5 min) runs out.
+//!
+//! DashScope error codes, on a rejected request or a failed task, map to
+//! the matching [`LLMError`]: `Throttling*` to `RateLimitExceeded`,
+//! `Arrearage` to `QuotaExceeded`, `InvalidApiKey` to
+//! `AuthenticationError`, the rest (`DataInspectionFailed`,
+//! `InvalidParameter`, …) to `ModelExecutionError` with the code in the
+//! message.
+//!
+//! DashScope only returns image URLs. When the request asks for
+//! `b64_json` the images are downloaded and encoded here, so callers
+//! get the payload the [`GeneratedImage`] contract promises. Sizes use
+//! DashScope's `1024*1024` form; `1024x1024` is translated. `steps` /
+//! `cfg_scale` have no DashScope equivalent and are ignored.
+//!
+//! A model `base_url` pointing at the OpenAI-compatible chat endpoint
+//! (`…/compatible-mode/v1`) is mapped to the native `…/api/v1`.
+
+use crate::{
+  LLMError, Result,
+  providers::modality::{
+    GeneratedImage, ImageGenerationResponse, Text2ImageProvider, Text2ImageRequest,
+  },
+};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose};
+use reqwest::Client;
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+
+const DEFAULT_BASE_URL: &str = "https://dashscope.aliyuncs.com/api/v1";
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_TASK_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub struct DashScopeImageClient {
+  client: Client,
+  api_key: String,
+  base_url: String,
+  poll_interval: Duration,
+  task_timeout: Duration,
+}
+
+impl std::fmt::Debug for DashScopeImageClient {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("DashScopeImageClient")
+      .field("base_url", &self.base_url)
+      .field("api_key", &"<redacted>")
+      .finish()
+  }
+}
+
+impl DashScopeImageClient {
+  pub fn new(api_key: &str, base_url: Option<String>) -> Result<Self> {
+    Self::with_client(super::default_http_client()?, api_key, base_url)
+  }
+
+  /// Construct with a caller-supplied [`reqwest::Client`]. Mirrors
+  /// `OpenAIProvider::with_client`.
+  pub fn with_client(client: Client, api_key: &str, base_url: Option<String>) -> Result<Self> {
+    if api_key.is_empty() {
+      return Err(LLMError::MissingApiKey {
+        provider: "dashscope".to_string(),
+      });
+    }
+    let base_url = base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+    let base_url = base_url.trim_end_matches('/');
+    let base_url = match base_url.strip_suffix("/compatible-mode/v1") {
+      Some(host) => format!("{host}/api/v1"),
+      None => base_url.to_string(),
+    };
+    Ok(Self {
+      client,
+      api_key: api_key.to_string(),
+      base_url,
+      poll_interval: DEFAULT_POLL_INTERVAL,
+      task_timeout: DEFAULT_TASK_TIMEOUT,
+    })
+  }
+
+  /// How long to wait between task status checks (default 2 s).
+  pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+    self.poll_interval = poll_interval;
+    self
+  }
+
+  /// How long to wait for a submitted task to finish (default 5 min).
+  /// A task still pending or running after that is a `TimeoutError`.
+  pub fn with_task_timeout(mut self, task_timeout: Duration) -> Self {
+    self.task_timeout = task_timeout;
+    self
+  }
+
+  async fn submit(&self, request: &Text2ImageRequest) -> Result<String> {
+    let url = format!("{}/services/aigc/text2image/image-synthesis", self.base_url);
+    let response = self
+      .client
+      .post(&url)
+      .bearer_auth(&self.api_key)
+      .header("X-DashScope-Async", "enable")
+      .json(&submit_body(request))
+      .send()
+      .await?;
+    let body = checked_json(response).await?;
+    body["output"]["task_id"]
+      .as_str()
+      .map(str::to_string)
+      .ok_or_else(|| LLMError::ResponseParsingError {
+        message: format!("DashScope image-synthesis response has no task_id: {body}"),
+      })
+  }
+
+  async fn wait_for(&self, task_id: &str) -> Result<Value> {
+    let url = format!("{}/tasks/{}", self.base_url, task_id);
+    let started = Instant::now();
+    loop {
+      let response = self
+        .client
+        .get(&url)
+        .bearer_auth(&self.api_key)
+        .send()
+        .await?;
+      let body = checked_json(response).await?;
+      match body["output"]["task_status"].as_str() {
+        Some("PENDING") | Some("RUNNING") if started.elapsed() >= self.task_timeout => {
+          return Err(LLMError::TimeoutError {
+            timeout_ms: self.task_timeout.as_millis() as u64,
+          });
+        }
+        Some("PENDING") | Some("RUNNING") => tokio::time::sleep(self.poll_interval).await,
+        _ => return Ok(body),
+      }
+    }
+  }
+
+  async fn download_b64(&self, url: &str) -> Result<String> {
+    let response = self.client.get(url).send().await?;
+    if !response.status().is_success() {
+      return Err(LLMError::HttpError {
+        status_code: response.status().as_u16(),
+        message: format!("failed to download generated image from {url}"),
+      });
+    }
+    Ok(general_purpose::STANDARD.encode(response.bytes().await?))
+  }
+}
+
+#[async_trait]
+impl Text2ImageProvider for DashScopeImageClient {
+  fn name(&self) -> &str {
+    "dashscope"
+  }
+
+  async fn generate(&self, request: Text2ImageRequest) -> Result<ImageGenerationResponse> {
+    let task_id = self.submit(&request).await?;
+    let task = self.wait_for(&task_id).await?;
+    let mut response = parse_task(&task, request.seed)?;
+    if request.response_format.as_deref() == Some("b64_json") {
+      for image in &mut response.images {
+        if let Some(url) = image.url.take() {
+          image.b64_json = Some(self.download_b64(&url).await?);
+        }
+      }
+    }
+    Ok(response)
+  }
+}
+
+fn submit_body(request: &Text2ImageRequest) -> Value {
+  let mut parameters = serde_json::Map::new();
+  if let Some(size) = &request.size {
+    parameters.insert("size".into(), json!(size.replace('x', "*")));
+  }
+  if let Some(n) = request.n {
+    parameters.insert("n".into(), json!(n));
+  }
+  if let Some(seed) = request.seed {
+    parameters.insert("seed".into(), json!(seed));
+  }
+  json!({
+    "model": request.model,
+    "input": { "prompt": request.prompt },
+    "parameters": parameters,
+  })
+}
+
+async fn checked_json(response: reqwest::Response) -> Result<Value> {
+  let status = response.status();
+  let text = response.text().await.unwrap_or_default();
+  if !status.is_success() {
+    // DashScope explains a rejected request with a `code`
+    let body: Value = serde_json::from_str(&text).unwrap_or_default();
+    return Err(match body["code"].as_str() {
+      Some(code) => dashscope_error(
+        code,
+        format!("{code}: {}", body["message"].as_str().unwrap_or_default()),
+      ),
+      None => LLMError::HttpError {
+        status_code: status.as_u16(),
+        message: text,
+      },
+    });
+  }
+  serde_json::from_str(&text).map_err(|e| LLMError::ResponseParsingError {
+    message: format!("DashScope returned invalid JSON ({e}): {text}"),
+  })
+}
+
+/// The error for a DashScope error `code`, described by `message`
+fn dashscope_error(code: &str, message: String) -> LLMError {
+  let provider = "dashscope".to_string();
+  match code {
+    code if code.starts_with("Throttling") => LLMError::RateLimitExceeded { provider, message },
+    "Arrearage" => LLMError::QuotaExceeded { provider, message },
+    "InvalidApiKey" => LLMError::AuthenticationError { provider, message },
+    _ => LLMError::ModelExecutionError { message },
+  }
+}
+
+/// Turns a finished task into the modality response. A failed task is
+/// an error; a succeeded task keeps its per-image failures (e.g.
+/// `DataInspectionFailed`) as entries with no payload and the code as
+/// `finish_reason`.
+fn parse_task(task: &Value, seed: Option<i32>) -> Result<ImageGenerationResponse> {
+  let output = &task["output"];
+  let status = output["task_status"].as_str().unwrap_or("UNKNOWN");
+  if status != "SUCCEEDED" {
+    let code = output["code"].as_str().unwrap_or_default();
+    return Err(dashscope_error(
+      code,
+      format!(
+        "DashScope image task {} {}: {} {}",
+        output["task_id"].as_str().unwrap_or("?"),
+        status.to_lowercase(),
+        code,
+        output["message"].as_str().unwrap_or_default()
+      )
+      .trim_end()
+      .to_string(),
+    ));
+  }
+  let images = output["results"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .map(|result| GeneratedImage {
+      url: result["url"].as_str().map(str::to_string),
+      b64_json: None,
+      seed,
+      finish_reason: Some(
+        result["code"]
+          .as_str()
+          .map_or_else(|| "success".to_string(), str::to_string),
+      ),
+    })
+    .collect();
+  Ok(ImageGenerationResponse {
+    created: chrono::Utc::now().timestamp().max(0) as u64,
+    images,
+    metadata: Some(json!({
+      "task_id": output["task_id"],
+      "request_id": task["request_id"],
+      "usage": task["usage"],
+    })),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::{Arc, Mutex};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::{TcpListener, TcpStream};
+
+  /// A stub DashScope answering each request, one per connection, with
+  /// the next of `responses`. Returns its base URL and the requests it
+  /// received.
+  async fn stub_server(responses: Vec<(u16, Value)>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&requests);
+    tokio::spawn(async move {
+      for (status, body) in responses {
+        let Ok((mut stream, _)) = listener.accept().await else {
+          return;
+        };
+        let request = read_request(&mut stream).await;
+        received.lock().unwrap().push(request);
+        let body = body.to_string();
+        let response = format!(
+          "HTTP/1.1 {status} Stub\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+          body.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+      }
+    });
+    (base_url, requests)
+  }
+
+  /// The request's head and body, read up to its `content-length`
+  async fn read_request(stream: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+      let n = stream.read(&mut buf).await.unwrap_or(0);
+      if n == 0 {
+        break;
+      }
+      request.extend_from_slice(&buf[..n]);
+      let text = String::from_utf8_lossy(&request);
+      if let Some(end) = text.find("\r\n\r\n") {
+        let length = text[..end]
+          .lines()
+          .filter_map(|line| line.split_once(':'))
+          .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+          .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+          .unwrap_or(0);
+        if request.len() >= end + 4 + length {
+          break;
+        }
+      }
+    }
+    String::from_utf8_lossy(&request).into_owned()
+  }
+
+  fn stub_client(base_url: String) -> DashScopeImageClient {
+    let client = Client::builder().no_proxy().build().unwrap();
+    DashScopeImageClient::with_client(client, "sk-test", Some(base_url))
+      .unwrap()
+      .with_poll_interval(Duration::from_millis(5))
+  }
+
+  fn task(status: &str) -> (u16, Value) {
+    (
+      200,
+      json!({ "output": { "task_id": "task-1", "task_status": status } }),
+    )
+  }
+
+  fn request() -> Text2ImageRequest {
+    Text2ImageRequest {
+      model: "wanx2.1-t2i-turbo".into(),
+      prompt: "a lighthouse".into(),
+      size: Some("1024x768".into()),
+      n: Some(1),
+      response_format: Some("url".into()),
+      seed: Some(7),
+      steps: Some(20),
+      cfg_scale: Some(7.5),
+    }
+  }
+
+  #[test]
+  fn submit_body_uses_dashscope_field_names() {
+    assert_eq!(
+      submit_body(&request()),
+      json!({
+        "model": "wanx2.1-t2i-turbo",
+        "input": { "prompt": "a lighthouse" },
+        "parameters": { "size": "1024*768", "n": 1, "seed": 7 },
+      })
+    );
+  }
+
+  #[test]
+  fn succeeded_task_keeps_per_image_failures() {
+    let task = json!({
+      "request_id": "req-1",
+      "output": {
+        "task_id": "task-1",
+        "task_status": "SUCCEEDED",
+        "results": [
+          { "url": "https://example.com/1.png" },
+          { "code": "DataInspectionFailed", "message": "blocked" },
+        ],
+      },
+      "usage": { "image_count": 1 },
+    });
+    let response = parse_task(&task, Some(7)).unwrap();
+    assert_eq!(response.images.len(), 2);
+    assert_eq!(
+      response.images[0].url.as_deref(),
+      Some("https://example.com/1.png")
+    );
+    assert_eq!(response.images[0].seed, Some(7));
+    assert_eq!(
+      response.images[1].finish_reason.as_deref(),
+      Some("DataInspectionFailed")
+    );
+    assert_eq!(response.metadata.unwrap()["task_id"], "task-1");
+  }
+
+  #[test]
+  fn failed_task_is_an_error() {
+    let task = json!({ "output": {
+      "task_id": "task-2",
+      "task_status": "FAILED",
+      "code": "InvalidParameter",
+      "message": "size is not supported",
+    } });
+    let err = parse_task(&task, None).unwrap_err().to_string();
+    assert!(
+      err.contains("task-2 failed: InvalidParameter size is not supported"),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  async fn task_is_submitted_polled_and_completed() {
+    let (base_url, requests) = stub_server(vec![
+      task("PENDING"),
+      task("PENDING"),
+      task("RUNNING"),
+      (
+        200,
+        json!({
+          "request_id": "req-1",
+          "output": {
+            "task_id": "task-1",
+            "task_status": "SUCCEEDED",
+            "results": [{ "url": "https://example.com/poster.png" }],
+          },
+        }),
+      ),
+    ])
+    .await;
+
+    let response = stub_client(base_url).generate(request()).await.unwrap();
+    assert_eq!(
+      response.images[0].url.as_deref(),
+      Some("https://example.com/poster.png")
+    );
+    assert_eq!(response.metadata.unwrap()["request_id"], "req-1");
+
+    let requests = requests.lock().unwrap();
+    let lines: Vec<&str> = requests
+      .iter()
+      .map(|request| request.lines().next().unwrap_or_default())
+      .collect();
+    assert_eq!(
+      lines,
+      [
+        "POST /services/aigc/text2image/image-synthesis HTTP/1.1",
+        "GET /tasks/task-1 HTTP/1.1",
+        "GET /tasks/task-1 HTTP/1.1",
+        "GET /tasks/task-1 HTTP/1.1",
+      ]
+    );
+    assert!(requests[0].contains("x-dashscope-async: enable"));
+    assert!(requests[0].contains(r#""size":"1024*768""#));
+  }
+
+  #[tokio::test]
+  async fn failed_task_and_rejected_submit_map_to_errors() {
+    let (base_url, _) = stub_server(vec![
+      task("RUNNING"),
+      (
+        200,
+        json!({ "output": {
+          "task_id": "task-1",
+          "task_status": "FAILED",
+          "code": "DataInspectionFailed",
+          "message": "Input data may contain inappropriate content.",
+        } }),
+      ),
+    ])
+    .await;
+    let err = stub_client(base_url).generate(request()).await.unwrap_err();
+    assert!(
+      matches!(&err, LLMError::ModelExecutionError { message } if message.contains("task-1 failed: DataInspectionFailed")),
+      "{err}"
+    );
+
+    let (base_url, _) = stub_server(vec![(
+      429,
+      json!({ "code": "Throttling.RateQuota", "message": "Requests rate limit exceeded" }),
+    )])
+    .await;
+    let err = stub_client(base_url).generate(request()).await.unwrap_err();
+    assert!(
+      matches!(&err, LLMError::RateLimitExceeded { message, .. } if message == "Throttling.RateQuota: Requests rate limit exceeded"),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  async fn task_still_running_after_the_timeout_is_an_error() {
+    let mut responses = vec![task("PENDING")];
+    responses.extend(std::iter::repeat_with(|| task("RUNNING")).take(100));
+    let (base_url, _) = stub_server(responses).await;
+    let err = stub_client(base_url)
+      .with_task_timeout(Duration::from_millis(30))
+      .generate(request())
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(err, LLMError::TimeoutError { timeout_ms: 30 }),
+      "{err}"
+    );
+  }
+}
@@ -168,10 +168,12 @@ impl GoogleProvider {
 /// Convert an OpenAI-shaped `content` field (string, or an array of typed
 /// parts) into a Gemini `parts` array.
 ///
-/// Supported part types: `text` and `image_url`. An `image_url` value can be
-/// either a string or an object `{ "url": "..." }`. Data URLs of the form
-/// `data:<mime>;base64,<payload>` are decoded into Gemini's `inline_data`
-/// shape; remote `http(s)` URLs are passed through as `file_data` references.
+/// Supported part types: `text`, `image_url` and `file_data`. An `image_url`
+/// value can be either a string or an object `{ "url": "..." }`. Data URLs of
+/// the form `data:<mime>;base64,<payload>` are decoded into Gemini's
+/// `inline_data` shape; remote `http(s)` URLs are passed through as
+/// `file_data` references. A `file_data` part (`{ "file_uri", "mime_type" }`,
+/// e.g. a file uploaded through the Files API) keeps its own MIME type.
 /// Unknown part shapes are dropped — multimodal flows should not crash on a
 /// single unrecognised part.
 pub(crate) fn openai_content_to_gemini_parts(content: &Value) -> Vec<Value> {
@@ -226,6 +228,26 @@ pub(crate) fn openai_content_to_gemini_parts(content: &Value) -> Vec<Value> {
           }));
         }
       }
+      "file_data" => {
+        let file = obj.get("file_data").and_then(Value::as_object);
+        let uri = file
+          .and_then(|file| file.get("file_uri"))
+          .and_then(Value::as_str)
+          .unwrap_or("");
+        if uri.is_empty() {
+          continue;
+        }
+        let mime_type = file
+          .and_then(|file| file.get("mime_type"))
+          .and_then(Value::as_str)
+          .unwrap_or("application/octet-stream");
+        parts.push(json!({
+          "file_data": {
+            "mime_type": mime_type,
+            "file_uri": uri,
+          }
+        }));
+      }
       _ => {}
     }
   }
@@ -426,6 +448,7 @@ impl LLMProvider for GoogleProvider {
       // capturing that is part of a future patch. For now we surface
       // None so the response shape matches other providers.
       thinking: None,
+      thinking_blocks: Vec::new(),
     })
   }
 
@@ -599,6 +622,7 @@ impl GoogleStreamingResponse {
           }),
           content_type: Some("text".to_string()),
           tool_call_deltas: Vec::new(),
+          thinking_delta: None,
         });
       }
 
@@ -615,6 +639,7 @@ impl GoogleStreamingResponse {
           }),
           content_type: Some("text".to_string()),
           tool_call_deltas: Vec::new(),
+          thinking_delta: None,
         });
       }
     }
@@ -948,6 +973,33 @@ mod tests {
     );
   }
 
+  #[test]
+  fn build_request_body_serialises_uploaded_files_as_file_data() {
+    let provider = GoogleProvider::new("test-key", None).unwrap();
+    let message = crate::MultimodalMessage::user()
+      .add_text("What happens in this talk?")
+      .add_file_uri(
+        "https://generativelanguage.googleapis.com/v1beta/files/abc123",
+        "video/mp4",
+      )
+      .build();
+    let request = ProviderRequest::new("gemini-2.5-pro", vec![message.to_openai_format()], false);
+
+    let body = provider.build_request_body(&request);
+    assert_eq!(
+      body["contents"][0]["parts"],
+      json!([
+        {"text": "What happens in this talk?"},
+        {
+          "file_data": {
+            "mime_type": "video/mp4",
+            "file_uri": "https://generativelanguage.googleapis.com/v1beta/files/abc123"
+          }
+        }
+      ])
+    );
+  }
+
   #[test]
   fn openai_content_to_gemini_parts_drops_unknown_part_kinds() {
     let content = json!([
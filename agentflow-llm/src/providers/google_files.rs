@@ -0,0 +1,568 @@
+//! Google Gemini Files API client.
+//!
+//! Large documents, videos and audio cannot be sent inline to
+//! `generateContent`; Gemini takes them as uploaded files referenced by
+//! URI (a `file_data` part, see [`MessageContent::file_uri`]).
+//! [`GoogleFilesClient`] uploads them with the resumable protocol:
+//!
+//! 1. `POST {base_url}/upload/v1beta/files` with
+//!    `X-Goog-Upload-Command: start` declares the size and MIME type and
+//!    returns the upload URL in the `x-goog-upload-url` header.
+//! 2. `POST <upload url>` with `X-Goog-Upload-Command: upload, finalize`
+//!    sends the bytes and returns the file, usually still `PROCESSING`.
+//! 3. `GET {base_url}/v1beta/files/{id}` is polled until the file is
+//!    `ACTIVE` (usable) or `FAILED`, or until the processing timeout
+//!    (default 5 min) runs out.
+//!
+//! Gemini deletes uploaded files after 48 hours; [`GoogleFilesClient::delete`]
+//! removes one sooner.
+
+use crate::{LLMError, Result, multimodal::MessageContent};
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::{Body, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_PROCESSING_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Processing state of an uploaded file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileState {
+  #[default]
+  StateUnspecified,
+  Processing,
+  Active,
+  Failed,
+}
+
+/// A file uploaded to the Files API, as Gemini describes it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHandle {
+  /// The resource name, `files/<id>`.
+  pub name: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub display_name: Option<String>,
+  pub mime_type: String,
+  /// What a `file_data` part references.
+  #[serde(default)]
+  pub uri: String,
+  #[serde(default)]
+  pub state: FileState,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expiration_time: Option<String>,
+  /// Why processing failed, for a `FAILED` file.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<Value>,
+}
+
+impl FileHandle {
+  pub fn is_active(&self) -> bool {
+    self.state == FileState::Active
+  }
+
+  /// The message part referencing this file.
+  pub fn to_content(&self) -> MessageContent {
+    MessageContent::file_uri(self.uri.as_str(), self.mime_type.as_str())
+  }
+}
+
+pub struct GoogleFilesClient {
+  client: Client,
+  api_key: String,
+  base_url: String,
+  poll_interval: Duration,
+  processing_timeout: Duration,
+}
+
+impl std::fmt::Debug for GoogleFilesClient {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("GoogleFilesClient")
+      .field("base_url", &self.base_url)
+      .field("api_key", &"<redacted>")
+      .finish()
+  }
+}
+
+impl GoogleFilesClient {
+  pub fn new(api_key: &str, base_url: Option<String>) -> Result<Self> {
+    Self::with_client(super::default_http_client()?, api_key, base_url)
+  }
+
+  /// Construct with a caller-supplied [`reqwest::Client`]. Mirrors
+  /// `GoogleProvider::with_client`.
+  pub fn with_client(client: Client, api_key: &str, base_url: Option<String>) -> Result<Self> {
+    if api_key.is_empty() {
+      return Err(LLMError::MissingApiKey {
+        provider: "google".to_string(),
+      });
+    }
+    let base_url = base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+    Ok(Self {
+      client,
+      api_key: api_key.to_string(),
+      base_url: base_url.trim_end_matches('/').to_string(),
+      poll_interval: DEFAULT_POLL_INTERVAL,
+      processing_timeout: DEFAULT_PROCESSING_TIMEOUT,
+    })
+  }
+
+  /// How long to wait between state checks (default 2 s).
+  pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+    self.poll_interval = poll_interval;
+    self
+  }
+
+  /// How long to wait for an upload to become `ACTIVE` (default 5 min).
+  /// A file still processing after that is a `TimeoutError`.
+  pub fn with_processing_timeout(mut self, processing_timeout: Duration) -> Self {
+    self.processing_timeout = processing_timeout;
+    self
+  }
+
+  /// Uploads the file at `path`, with the MIME type its extension
+  /// implies, and waits until it is `ACTIVE`. The file is streamed from
+  /// disk rather than read into memory first.
+  pub async fn upload(&self, path: impl AsRef<Path>) -> Result<FileHandle> {
+    let path = path.as_ref();
+    let read_error = |e: std::io::Error| LLMError::ConfigurationError {
+      message: format!("Failed to read {} for upload: {e}", path.display()),
+    };
+    let file = tokio::fs::File::open(path).await.map_err(read_error)?;
+    let size = file.metadata().await.map_err(read_error)?.len();
+    let display_name = path
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned());
+    self
+      .send_upload(
+        Body::from(file),
+        size,
+        mime_type_for_path(path),
+        display_name.as_deref(),
+      )
+      .await
+  }
+
+  /// Uploads `bytes` as a file of `mime_type` and waits until it is
+  /// `ACTIVE`.
+  pub async fn upload_bytes(
+    &self,
+    bytes: Vec<u8>,
+    mime_type: &str,
+    display_name: Option<&str>,
+  ) -> Result<FileHandle> {
+    let size = bytes.len() as u64;
+    self
+      .send_upload(Body::from(bytes), size, mime_type, display_name)
+      .await
+  }
+
+  /// Declares an upload of `size` bytes, sends `body` and waits until the
+  /// file is `ACTIVE`.
+  async fn send_upload(
+    &self,
+    body: Body,
+    size: u64,
+    mime_type: &str,
+    display_name: Option<&str>,
+  ) -> Result<FileHandle> {
+    let upload_url = self.start_upload(size, mime_type, display_name).await?;
+    let response = self
+      .client
+      .post(&upload_url)
+      .headers(self.build_headers()?)
+      .header(CONTENT_LENGTH, size)
+      .header("X-Goog-Upload-Offset", "0")
+      .header("X-Goog-Upload-Command", "upload, finalize")
+      .body(body)
+      .send()
+      .await?;
+    let body = checked_json(response).await?;
+    let file = parse_file(&body["file"])?;
+    self.wait_until_active(file).await
+  }
+
+  /// Declares the upload and returns the URL the bytes go to.
+  async fn start_upload(
+    &self,
+    size: u64,
+    mime_type: &str,
+    display_name: Option<&str>,
+  ) -> Result<String> {
+    let url = format!("{}/upload/v1beta/files", self.base_url);
+    let mut file = json!({});
+    if let Some(display_name) = display_name {
+      file["display_name"] = json!(display_name);
+    }
+    let response = self
+      .client
+      .post(&url)
+      .headers(self.build_headers()?)
+      .header("X-Goog-Upload-Protocol", "resumable")
+      .header("X-Goog-Upload-Command", "start")
+      .header("X-Goog-Upload-Header-Content-Length", size.to_string())
+      .header("X-Goog-Upload-Header-Content-Type", mime_type)
+      .json(&json!({ "file": file }))
+      .send()
+      .await?;
+    if !response.status().is_success() {
+      return Err(http_error(response).await);
+    }
+    response
+      .headers()
+      .get("x-goog-upload-url")
+      .and_then(|value| value.to_str().ok())
+      .map(str::to_string)
+      .ok_or_else(|| LLMError::ResponseParsingError {
+        message: "Gemini upload start response has no x-goog-upload-url header".to_string(),
+      })
+  }
+
+  /// The file named `name` (`files/<id>`, or just `<id>`) as it is now.
+  pub async fn get(&self, name: &str) -> Result<FileHandle> {
+    let response = self
+      .client
+      .get(self.file_url(name))
+      .headers(self.build_headers()?)
+      .send()
+      .await?;
+    parse_file(&checked_json(response).await?)
+  }
+
+  /// The processing state of the file named `name`.
+  pub async fn get_state(&self, name: &str) -> Result<FileState> {
+    Ok(self.get(name).await?.state)
+  }
+
+  /// Polls `file` until it is `ACTIVE`. A `FAILED` file is a
+  /// `ModelExecutionError`.
+  pub async fn wait_until_active(&self, mut file: FileHandle) -> Result<FileHandle> {
+    let started = Instant::now();
+    loop {
+      match file.state {
+        FileState::Active => return Ok(file),
+        FileState::Failed => {
+          return Err(LLMError::ModelExecutionError {
+            message: format!(
+              "Gemini could not process uploaded file {}: {}",
+              file.name,
+              file
+                .error
+                .as_ref()
+                .and_then(|error| error["message"].as_str())
+                .unwrap_or("no reason given")
+            ),
+          });
+        }
+        _ if started.elapsed() >= self.processing_timeout => {
+          return Err(LLMError::TimeoutError {
+            timeout_ms: self.processing_timeout.as_millis() as u64,
+          });
+        }
+        _ => {
+          tokio::time::sleep(self.poll_interval).await;
+          file = self.get(&file.name).await?;
+        }
+      }
+    }
+  }
+
+  /// Deletes the file named `name`.
+  pub async fn delete(&self, name: &str) -> Result<()> {
+    let response = self
+      .client
+      .delete(self.file_url(name))
+      .headers(self.build_headers()?)
+      .send()
+      .await?;
+    if !response.status().is_success() {
+      return Err(http_error(response).await);
+    }
+    Ok(())
+  }
+
+  fn file_url(&self, name: &str) -> String {
+    let id = name.strip_prefix("files/").unwrap_or(name);
+    format!("{}/v1beta/files/{}", self.base_url, id)
+  }
+
+  /// The API key travels in `x-goog-api-key`, as for `generateContent`,
+  /// so it never appears in a URL.
+  fn build_headers(&self) -> Result<reqwest::header::HeaderMap> {
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      "x-goog-api-key",
+      HeaderValue::from_str(&self.api_key).map_err(|err| LLMError::ConfigurationError {
+        message: format!("Google API key contains non-ASCII bytes: {err}"),
+      })?,
+    );
+    crate::trace_context::inject_into_headers(&mut headers);
+    Ok(headers)
+  }
+}
+
+async fn http_error(response: reqwest::Response) -> LLMError {
+  LLMError::HttpError {
+    status_code: response.status().as_u16(),
+    message: response.text().await.unwrap_or_default(),
+  }
+}
+
+async fn checked_json(response: reqwest::Response) -> Result<Value> {
+  if !response.status().is_success() {
+    return Err(http_error(response).await);
+  }
+  let text = response.text().await.unwrap_or_default();
+  serde_json::from_str(&text).map_err(|e| LLMError::ResponseParsingError {
+    message: format!("Gemini Files API returned invalid JSON ({e}): {text}"),
+  })
+}
+
+fn parse_file(file: &Value) -> Result<FileHandle> {
+  serde_json::from_value(file.clone()).map_err(|e| LLMError::ResponseParsingError {
+    message: format!("Gemini Files API returned an unexpected file ({e}): {file}"),
+  })
+}
+
+/// The MIME type Gemini expects for a file, going by its extension;
+/// `application/octet-stream` when unknown.
+pub fn mime_type_for_path(path: &Path) -> &'static str {
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(str::to_ascii_lowercase)
+    .unwrap_or_default();
+  match extension.as_str() {
+    "pdf" => "application/pdf",
+    "txt" => "text/plain",
+    "md" => "text/markdown",
+    "html" | "htm" => "text/html",
+    "csv" => "text/csv",
+    "xml" => "text/xml",
+    "png" => "image/png",
+    "jpg" | "jpeg" => "image/jpeg",
+    "webp" => "image/webp",
+    "heic" => "image/heic",
+    "heif" => "image/heif",
+    "mp4" => "video/mp4",
+    "mpeg" | "mpg" => "video/mpeg",
+    "mov" => "video/quicktime",
+    "avi" => "video/x-msvideo",
+    "flv" => "video/x-flv",
+    "webm" => "video/webm",
+    "wmv" => "video/x-ms-wmv",
+    "3gp" => "video/3gpp",
+    "mp3" => "audio/mp3",
+    "wav" => "audio/wav",
+    "aac" => "audio/aac",
+    "ogg" => "audio/ogg",
+    "flac" => "audio/flac",
+    "aiff" | "aif" => "audio/aiff",
+    _ => "application/octet-stream",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::{Arc, Mutex};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::{TcpListener, TcpStream};
+
+  /// One stub answer: status, extra headers and JSON body.
+  type StubResponse = (u16, Vec<(&'static str, String)>, Value);
+
+  /// A stub Gemini answering each request, one per connection, with the
+  /// next of `responses`. `{base}` in a header value is replaced with the
+  /// stub's own URL. Returns that URL and the requests it received.
+  async fn stub_server(responses: Vec<StubResponse>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&requests);
+    let base = base_url.clone();
+    tokio::spawn(async move {
+      for (status, headers, body) in responses {
+        let Ok((mut stream, _)) = listener.accept().await else {
+          return;
+        };
+        let request = read_request(&mut stream).await;
+        received.lock().unwrap().push(request);
+        let body = body.to_string();
+        let headers: String = headers
+          .iter()
+          .map(|(name, value)| format!("{name}: {}\r\n", value.replace("{base}", &base)))
+          .collect();
+        let response = format!(
+          "HTTP/1.1 {status} Stub\r\ncontent-type: application/json\r\n{headers}content-length: {}\r\nconnection: close\r\n\r\n{body}",
+          body.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+      }
+    });
+    (base_url, requests)
+  }
+
+  /// The request's head and body, read up to its `content-length`
+  async fn read_request(stream: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+      let n = stream.read(&mut buf).await.unwrap_or(0);
+      if n == 0 {
+        break;
+      }
+      request.extend_from_slice(&buf[..n]);
+      let text = String::from_utf8_lossy(&request);
+      if let Some(end) = text.find("\r\n\r\n") {
+        let length = text[..end]
+          .lines()
+          .filter_map(|line| line.split_once(':'))
+          .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+          .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+          .unwrap_or(0);
+        if request.len() >= end + 4 + length {
+          break;
+        }
+      }
+    }
+    String::from_utf8_lossy(&request).into_owned()
+  }
+
+  fn stub_client(base_url: String) -> GoogleFilesClient {
+    let client = Client::builder().no_proxy().build().unwrap();
+    GoogleFilesClient::with_client(client, "test-key", Some(base_url))
+      .unwrap()
+      .with_poll_interval(Duration::from_millis(5))
+  }
+
+  fn file(state: &str) -> Value {
+    json!({
+      "name": "files/abc123",
+      "displayName": "talk.mp4",
+      "mimeType": "video/mp4",
+      "uri": "https://generativelanguage.googleapis.com/v1beta/files/abc123",
+      "state": state,
+    })
+  }
+
+  #[tokio::test]
+  async fn upload_is_resumable_and_waits_until_active() {
+    let (base_url, requests) = stub_server(vec![
+      (
+        200,
+        vec![("x-goog-upload-url", "{base}/upload/session-1".to_string())],
+        json!({}),
+      ),
+      (200, vec![], json!({ "file": file("PROCESSING") })),
+      (200, vec![], file("PROCESSING")),
+      (200, vec![], file("ACTIVE")),
+    ])
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("talk.mp4");
+    std::fs::write(&path, b"not really a video").unwrap();
+
+    let handle = stub_client(base_url).upload(&path).await.unwrap();
+    assert!(handle.is_active());
+    assert_eq!(handle.name, "files/abc123");
+    assert_eq!(handle.mime_type, "video/mp4");
+
+    let requests = requests.lock().unwrap();
+    let lines: Vec<&str> = requests
+      .iter()
+      .map(|request| request.lines().next().unwrap_or_default())
+      .collect();
+    assert_eq!(
+      lines,
+      [
+        "POST /upload/v1beta/files HTTP/1.1",
+        "POST /upload/session-1 HTTP/1.1",
+        "GET /v1beta/files/abc123 HTTP/1.1",
+        "GET /v1beta/files/abc123 HTTP/1.1",
+      ]
+    );
+    let start = requests[0].to_ascii_lowercase();
+    assert!(start.contains("x-goog-upload-protocol: resumable"));
+    assert!(start.contains("x-goog-upload-command: start"));
+    assert!(start.contains("x-goog-upload-header-content-length: 18"));
+    assert!(start.contains("x-goog-upload-header-content-type: video/mp4"));
+    assert!(start.contains("x-goog-api-key: test-key"));
+    assert!(requests[0].contains(r#"{"file":{"display_name":"talk.mp4"}}"#));
+    let upload = requests[1].to_ascii_lowercase();
+    assert!(upload.contains("x-goog-upload-command: upload, finalize"));
+    assert!(upload.contains("x-goog-upload-offset: 0"));
+    assert!(requests[1].ends_with("not really a video"));
+  }
+
+  #[tokio::test]
+  async fn failed_processing_and_slow_processing_are_errors() {
+    let (base_url, _) = stub_server(vec![(
+      200,
+      vec![],
+      json!({
+        "name": "files/abc123",
+        "mimeType": "video/mp4",
+        "state": "FAILED",
+        "error": { "code": 400, "message": "unsupported codec" },
+      }),
+    )])
+    .await;
+    let client = stub_client(base_url);
+    assert_eq!(
+      client.get_state("files/abc123").await.unwrap(),
+      FileState::Failed
+    );
+
+    let (base_url, _) = stub_server(vec![(200, vec![], file("PROCESSING")); 100]).await;
+    let err = stub_client(base_url)
+      .with_processing_timeout(Duration::from_millis(30))
+      .wait_until_active(serde_json::from_value(file("PROCESSING")).unwrap())
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(err, LLMError::TimeoutError { timeout_ms: 30 }),
+      "{err}"
+    );
+
+    let failed: FileHandle = serde_json::from_value(json!({
+      "name": "files/x",
+      "mimeType": "application/pdf",
+      "state": "FAILED",
+      "error": { "message": "unsupported codec" },
+    }))
+    .unwrap();
+    let err = stub_client("http://127.0.0.1:9".to_string())
+      .wait_until_active(failed)
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("unsupported codec"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn delete_addresses_the_file_by_id() {
+    let (base_url, requests) = stub_server(vec![(200, vec![], json!({}))]).await;
+    stub_client(base_url).delete("files/abc123").await.unwrap();
+    assert!(requests.lock().unwrap()[0].starts_with("DELETE /v1beta/files/abc123 HTTP/1.1"));
+  }
+
+  #[test]
+  fn mime_types_follow_the_extension() {
+    assert_eq!(
+      mime_type_for_path(Path::new("paper.PDF")),
+      "application/pdf"
+    );
+    assert_eq!(mime_type_for_path(Path::new("clip.mov")), "video/quicktime");
+    assert_eq!(
+      mime_type_for_path(Path::new("archive.tar.zst")),
+      "application/octet-stream"
+    );
+  }
+}
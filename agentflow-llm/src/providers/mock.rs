@@ -171,6 +171,7 @@ impl StreamingResponse for MockStreamingResponse {
         usage: None,
         content_type: Some("text".to_string()),
         tool_call_deltas: Vec::new(),
+        thinking_delta: None,
       }))
     }
   }
@@ -226,6 +227,7 @@ impl LLMProvider for MockProvider {
       tool_calls,
       stop_reason,
       thinking: None,
+      thinking_blocks: Vec::new(),
     })
   }
 
@@ -1,6 +1,6 @@
 use crate::{
   LLMError, Result, StreamingResponse,
-  thinking::ThinkingConfig,
+  thinking::{ThinkingBlock, ThinkingConfig},
   tool_calling::{StopReason, ToolCallRequest, ToolChoice, ToolSpec},
 };
 use async_trait::async_trait;
@@ -9,7 +9,10 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 pub mod anthropic;
+pub mod compat;
+pub mod dashscope_image;
 pub mod google;
+pub mod google_files;
 pub mod mock;
 pub mod modality;
 pub mod moonshot;
@@ -18,7 +21,9 @@ pub mod openai_asr;
 pub mod stepfun;
 
 pub use anthropic::AnthropicProvider;
+pub use compat::CompatProfile;
 pub use google::GoogleProvider;
+pub use google_files::{FileHandle, FileState, GoogleFilesClient};
 pub use mock::MockProvider;
 pub use moonshot::MoonshotProvider;
 pub use openai::OpenAIProvider;
@@ -242,6 +247,10 @@ pub struct ProviderResponse {
   /// when thinking wasn't requested.
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub thinking: Option<String>,
+  /// The reasoning blocks behind `thinking`, verbatim with their
+  /// signatures, for replaying the turn. Only Anthropic returns them.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub thinking_blocks: Vec<ThinkingBlock>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -289,12 +298,17 @@ pub fn create_provider(
     "google" | "gemini" => Ok(Box::new(GoogleProvider::new(api_key, base_url)?)),
     "moonshot" => Ok(Box::new(MoonshotProvider::new(api_key, base_url)?)),
     "stepfun" | "step" => Ok(Box::new(StepFunProvider::new(api_key, base_url)?)), // Use dedicated StepFun provider
-    "dashscope" => Ok(Box::new(OpenAIProvider::new(api_key, base_url)?)), // Dashscope is OpenAI-compatible
-    "glm" | "bigmodel" | "zhipu" => Ok(Box::new(OpenAIProvider::new(api_key, base_url)?)), // BigModel GLM is OpenAI-compatible
-    "deepseek" => Ok(Box::new(OpenAIProvider::new(api_key, base_url)?)), // DeepSeek is OpenAI-compatible
-    "minimax" => Ok(Box::new(OpenAIProvider::new(api_key, base_url)?)), // MiniMax is OpenAI-compatible (host: api.minimaxi.com)
-    _ => Err(LLMError::UnsupportedProvider {
-      provider: provider_name.to_string(),
-    }),
+    // DashScope, GLM, DeepSeek, MiniMax, Groq and Ollama are OpenAI-compatible
+    vendor => match CompatProfile::for_vendor(vendor) {
+      Some(profile) => {
+        let base_url = base_url.or_else(|| profile.base_url.clone());
+        Ok(Box::new(
+          OpenAIProvider::new(api_key, base_url)?.with_profile(profile),
+        ))
+      }
+      None => Err(LLMError::UnsupportedProvider {
+        provider: provider_name.to_string(),
+      }),
+    },
   }
 }
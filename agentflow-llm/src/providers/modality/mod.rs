@@ -36,7 +36,10 @@ pub use asr::{AsrProvider, AsrRequest, AsrResponse};
 pub use image_edit::{ImageEditProvider, ImageEditRequest};
 pub use image_to_image::{Image2ImageProvider, Image2ImageRequest};
 pub use text_to_image::{Text2ImageProvider, Text2ImageRequest};
-pub use tts::{TtsProvider, TtsRequest, TtsResponse};
+pub use tts::{
+  DEFAULT_TTS_CHUNK_CHARS, TtsProvider, TtsRequest, TtsResponse, TtsVoice, merge_wav,
+  split_tts_text,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -76,4 +79,8 @@ pub struct GeneratedImage {
   /// Seed used to generate this image, when known.
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub seed: Option<i32>,
+  /// Vendor finish reason, e.g. `"success"` or `"content_filtered"`. A
+  /// filtered entry usually carries no image payload.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub finish_reason: Option<String>,
 }
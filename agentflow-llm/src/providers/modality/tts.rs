@@ -1,6 +1,10 @@
 //! Text-to-Speech (text → audio) provider trait.
+//!
+//! Also holds the long-text helpers: [`split_tts_text`] cuts a script
+//! into chunks under a provider's per-call cap, and [`merge_wav`] joins
+//! the WAV audio synthesised for each chunk into one file.
 
-use crate::Result;
+use crate::{LLMError, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +46,19 @@ pub struct TtsResponse {
   pub mime_type: String,
 }
 
+/// A voice a TTS provider can synthesise with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsVoice {
+  /// Identifier to pass as [`TtsRequest::voice`].
+  pub id: String,
+  /// Display name, when the provider has one.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  /// Free-form details, e.g. how a custom voice was created.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+}
+
 /// Provider trait for TTS endpoints.
 #[async_trait]
 pub trait TtsProvider: Send + Sync {
@@ -50,4 +67,251 @@ pub trait TtsProvider: Send + Sync {
 
   /// Synthesise `request` into audio bytes.
   async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse>;
+
+  /// Voices the provider reports for this account. Providers without a
+  /// listing endpoint return `UnsupportedOperation`.
+  async fn list_voices(&self) -> Result<Vec<TtsVoice>> {
+    Err(LLMError::UnsupportedOperation {
+      message: format!("provider '{}' cannot list TTS voices", self.name()),
+    })
+  }
+}
+
+/// Per-call input cap of StepFun TTS, in characters. A safe default
+/// chunk size for other providers too.
+pub const DEFAULT_TTS_CHUNK_CHARS: usize = 1000;
+
+/// Characters after which a chunk may end without cutting a sentence.
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', ';', '\n', '。', '！', '？', '；'];
+
+/// Split `text` into chunks of at most `max_chars` characters, in order.
+///
+/// Chunks end at sentence boundaries where possible. A sentence longer
+/// than `max_chars` is cut at its last whitespace before the limit, or
+/// at the limit itself when it has none (e.g. CJK text). Chunks are
+/// trimmed and empty ones dropped, so blank input yields no chunks.
+pub fn split_tts_text(text: &str, max_chars: usize) -> Vec<String> {
+  let max_chars = max_chars.max(1);
+  let mut chunks = Vec::new();
+  let mut current = String::new();
+  let mut current_chars = 0;
+  for sentence in sentences(text) {
+    let sentence_chars = sentence.chars().count();
+    if current_chars + sentence_chars > max_chars {
+      push_chunk(&mut chunks, &mut current);
+      current_chars = 0;
+    }
+    if sentence_chars <= max_chars {
+      current.push_str(sentence);
+      current_chars += sentence_chars;
+      continue;
+    }
+    let mut rest = sentence;
+    while rest.chars().count() > max_chars {
+      let limit = rest
+        .char_indices()
+        .nth(max_chars)
+        .map_or(rest.len(), |(index, _)| index);
+      let cut = rest[..limit]
+        .rfind(char::is_whitespace)
+        .filter(|&index| !rest[..index].trim().is_empty())
+        .unwrap_or(limit);
+      chunks.push(rest[..cut].trim().to_string());
+      rest = rest[cut..].trim_start();
+    }
+    current.push_str(rest);
+    current_chars = rest.chars().count();
+  }
+  push_chunk(&mut chunks, &mut current);
+  chunks
+}
+
+/// `text` cut after each sentence end, keeping the trailing whitespace
+/// with the sentence it follows.
+fn sentences(text: &str) -> Vec<&str> {
+  let mut sentences = Vec::new();
+  let mut start = 0;
+  let mut chars = text.char_indices().peekable();
+  while let Some((_, ch)) = chars.next() {
+    if !SENTENCE_ENDS.contains(&ch) {
+      continue;
+    }
+    while let Some(&(_, next)) = chars.peek() {
+      if next.is_whitespace() || SENTENCE_ENDS.contains(&next) {
+        chars.next();
+      } else {
+        break;
+      }
+    }
+    let end = chars.peek().map_or(text.len(), |&(index, _)| index);
+    sentences.push(&text[start..end]);
+    start = end;
+  }
+  if start < text.len() {
+    sentences.push(&text[start..]);
+  }
+  sentences
+}
+
+fn push_chunk(chunks: &mut Vec<String>, current: &mut String) {
+  let chunk = current.trim();
+  if !chunk.is_empty() {
+    chunks.push(chunk.to_string());
+  }
+  current.clear();
+}
+
+/// Join WAV files into one, in order.
+///
+/// Every part must be a RIFF/WAVE file with the same `fmt ` chunk
+/// (sample rate, channels, bit depth). The result holds that `fmt `
+/// chunk and the concatenated `data` chunks; other chunks (`LIST`,
+/// `fact`, …) are dropped. A `data` size of `0xFFFFFFFF`, which
+/// streaming encoders write, is read as "to the end of the file".
+pub fn merge_wav(parts: &[Vec<u8>]) -> Result<Vec<u8>> {
+  let mut format: Option<&[u8]> = None;
+  let mut data = Vec::new();
+  for (index, part) in parts.iter().enumerate() {
+    let (part_format, part_data) =
+      wav_chunks(part).map_err(|message| LLMError::ResponseParsingError {
+        message: format!("audio part {} is not a WAV file: {message}", index + 1),
+      })?;
+    match format {
+      None => format = Some(part_format),
+      Some(first) if first != part_format => {
+        return Err(LLMError::ResponseParsingError {
+          message: format!(
+            "audio part {} has a different WAV format from part 1",
+            index + 1
+          ),
+        });
+      }
+      Some(_) => {}
+    }
+    data.extend_from_slice(part_data);
+  }
+  let Some(format) = format else {
+    return Err(LLMError::InternalError {
+      message: "merge_wav needs at least one part".to_string(),
+    });
+  };
+  let riff_size = 4 + (8 + format.len() + format.len() % 2) + (8 + data.len());
+  let riff_size = u32::try_from(riff_size).map_err(|_| LLMError::InternalError {
+    message: "merged WAV is larger than 4 GiB".to_string(),
+  })?;
+  let mut wav = Vec::with_capacity(riff_size as usize + 8);
+  wav.extend_from_slice(b"RIFF");
+  wav.extend_from_slice(&riff_size.to_le_bytes());
+  wav.extend_from_slice(b"WAVE");
+  wav.extend_from_slice(b"fmt ");
+  wav.extend_from_slice(&(format.len() as u32).to_le_bytes());
+  wav.extend_from_slice(format);
+  if format.len() % 2 == 1 {
+    wav.push(0);
+  }
+  wav.extend_from_slice(b"data");
+  wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+  wav.extend_from_slice(&data);
+  Ok(wav)
+}
+
+/// The `fmt ` and `data` chunk bodies of a WAV file.
+fn wav_chunks(wav: &[u8]) -> std::result::Result<(&[u8], &[u8]), &'static str> {
+  if wav.len() < 12 || &wav[..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+    return Err("missing RIFF/WAVE header");
+  }
+  let mut format = None;
+  let mut offset = 12;
+  while offset + 8 <= wav.len() {
+    let id = &wav[offset..offset + 4];
+    let size = u32::from_le_bytes([
+      wav[offset + 4],
+      wav[offset + 5],
+      wav[offset + 6],
+      wav[offset + 7],
+    ]);
+    let body = offset + 8;
+    if id == b"data" {
+      let end = if size == u32::MAX {
+        wav.len()
+      } else {
+        body + size as usize
+      };
+      if end > wav.len() {
+        return Err("data chunk runs past the end of the file");
+      }
+      return format
+        .map(|format| (format, &wav[body..end]))
+        .ok_or("data chunk comes before the fmt chunk");
+    }
+    let end = body + size as usize;
+    if end > wav.len() {
+      return Err("chunk runs past the end of the file");
+    }
+    if id == b"fmt " {
+      format = Some(&wav[body..end]);
+    }
+    offset = end + (size as usize % 2);
+  }
+  Err("no data chunk")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn wav(format: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut wav = b"RIFF\0\0\0\0WAVE".to_vec();
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&(format.len() as u32).to_le_bytes());
+    wav.extend_from_slice(format);
+    wav.extend_from_slice(b"LIST\x02\0\0\0ab");
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    wav.extend_from_slice(data);
+    wav
+  }
+
+  #[test]
+  fn split_prefers_sentence_boundaries() {
+    let chunks = split_tts_text("One two. Three four five! Six?  Seven eight nine ten", 24);
+    assert_eq!(
+      chunks,
+      ["One two.", "Three four five! Six?", "Seven eight nine ten"]
+    );
+    assert!(split_tts_text("  \n ", 20).is_empty());
+  }
+
+  #[test]
+  fn split_cuts_long_sentences_at_whitespace_or_the_limit() {
+    assert_eq!(
+      split_tts_text("alpha beta gamma delta", 11),
+      ["alpha beta", "gamma delta"]
+    );
+    assert_eq!(
+      split_tts_text("一二三四五六七", 3),
+      ["一二三", "四五六", "七"]
+    );
+  }
+
+  #[test]
+  fn merge_wav_concatenates_data_chunks() {
+    let format = [1u8, 0, 1, 0, 0x80, 0x3e, 0, 0, 0, 0x7d, 0, 0, 2, 0, 16, 0];
+    let merged = merge_wav(&[wav(&format, b"abcd"), wav(&format, b"ef")]).unwrap();
+    assert_eq!(wav_chunks(&merged).unwrap(), (&format[..], &b"abcdef"[..]));
+    assert_eq!(
+      u32::from_le_bytes(merged[4..8].try_into().unwrap()) as usize,
+      merged.len() - 8
+    );
+
+    let mut other = format;
+    other[4] = 0x44;
+    let err = merge_wav(&[wav(&format, b"ab"), wav(&other, b"cd")]).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("part 2 has a different WAV format")
+    );
+    assert!(merge_wav(&[b"ID3...".to_vec()]).is_err());
+  }
 }
@@ -2,16 +2,16 @@ use crate::{
   LLMError, Result,
   client::streaming::{StreamChunk, StreamingResponse, TokenUsage},
   providers::{
-    ContentType, LLMProvider, ProviderRequest, ProviderResponse,
+    CompatProfile, ContentType, LLMProvider, ProviderRequest, ProviderResponse,
     openai::{parse_openai_tool_calls, tool_choice_to_openai_value, tool_spec_to_openai_value},
   },
-  tool_calling::StopReason,
+  tool_calling::{StopReason, ToolSpec},
 };
 use async_trait::async_trait;
 use futures::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::{Map, Value, json};
 use std::pin::Pin;
 use tokio_stream::Stream;
 
@@ -74,9 +74,10 @@ impl MoonshotProvider {
       body[key] = value.clone();
     }
 
-    // Moonshot speaks the OpenAI tools wire format directly.
+    // Moonshot speaks the OpenAI tools wire format directly, plus its
+    // builtin functions.
     if let Some(tools) = &request.tools {
-      body["tools"] = Value::Array(tools.iter().map(tool_spec_to_openai_value).collect());
+      body["tools"] = Value::Array(tools.iter().map(moonshot_tool_value).collect());
     }
     if let Some(choice) = &request.tool_choice {
       body["tool_choice"] = tool_choice_to_openai_value(choice);
@@ -86,6 +87,46 @@ impl MoonshotProvider {
   }
 }
 
+/// A function tool in the OpenAI shape, or a builtin such as `$web_search`
+/// as Moonshot's `builtin_function`.
+fn moonshot_tool_value(spec: &ToolSpec) -> Value {
+  if spec.is_builtin() {
+    json!({
+      "type": "builtin_function",
+      "function": { "name": spec.name },
+    })
+  } else {
+    tool_spec_to_openai_value(spec)
+  }
+}
+
+/// The text of a trailing `partial: true` assistant message. Moonshot
+/// answers with the continuation only, so the adapter puts it back in
+/// front.
+fn partial_prefill(request: &ProviderRequest) -> Option<String> {
+  let last = request.messages.last()?;
+  (last["role"] == "assistant" && last["partial"] == true)
+    .then(|| last["content"].as_str().map(str::to_string))
+    .flatten()
+    .filter(|prefill| !prefill.is_empty())
+}
+
+/// Tokens the `$web_search` results will add to the next request, which
+/// Moonshot reports as `usage.total_tokens` in each call's arguments.
+/// `None` when the model did not search.
+fn web_search_tokens(tool_calls: &Value) -> Option<u64> {
+  let tokens: Vec<u64> = tool_calls
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter(|call| call["function"]["name"] == "$web_search")
+    .filter_map(|call| call["function"]["arguments"].as_str())
+    .filter_map(|arguments| serde_json::from_str::<Value>(arguments).ok())
+    .filter_map(|arguments| arguments["usage"]["total_tokens"].as_u64())
+    .collect();
+  (!tokens.is_empty()).then(|| tokens.iter().sum())
+}
+
 #[async_trait]
 impl LLMProvider for MoonshotProvider {
   fn name(&self) -> &str {
@@ -111,12 +152,11 @@ impl LLMProvider for MoonshotProvider {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(
+        CompatProfile::moonshot()
+          .error_from_response(response)
+          .await,
+      );
     }
 
     let moonshot_response: MoonshotResponse = response.json().await?;
@@ -129,7 +169,10 @@ impl LLMProvider for MoonshotProvider {
       .clone();
 
     // Convert to ContentType - Moonshot currently only returns text
-    let content = ContentType::Text(content_text);
+    let content = ContentType::Text(match partial_prefill(request) {
+      Some(prefill) => prefill + &content_text,
+      None => content_text,
+    });
 
     let usage = moonshot_response
       .usage
@@ -149,13 +192,24 @@ impl LLMProvider for MoonshotProvider {
       .and_then(|c| c.finish_reason.as_deref())
       .map(StopReason::from_openai_finish_reason);
 
+    // Every usage field Moonshot reports (`cached_tokens` and the like)
+    // stays in `metadata.usage`.
+    let mut metadata = serde_json::to_value(&moonshot_response)?;
+    if let Some(tokens) = first_choice
+      .and_then(|c| c.message.tool_calls.as_ref())
+      .and_then(web_search_tokens)
+    {
+      metadata["web_search_tokens"] = json!(tokens);
+    }
+
     Ok(ProviderResponse {
       content,
       usage,
-      metadata: Some(serde_json::to_value(&moonshot_response)?),
+      metadata: Some(metadata),
       tool_calls,
       stop_reason,
       thinking: None,
+      thinking_blocks: Vec::new(),
     })
   }
 
@@ -181,15 +235,17 @@ impl LLMProvider for MoonshotProvider {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(
+        CompatProfile::moonshot()
+          .error_from_response(response)
+          .await,
+      );
     }
 
-    Ok(Box::new(MoonshotStreamingResponse::new(response)))
+    Ok(Box::new(MoonshotStreamingResponse::new(
+      response,
+      partial_prefill(request),
+    )))
   }
 
   async fn validate_config(&self) -> Result<()> {
@@ -257,6 +313,9 @@ struct MoonshotUsage {
   prompt_tokens: u32,
   completion_tokens: u32,
   total_tokens: u32,
+  /// Moonshot's extra billing fields, such as `cached_tokens`.
+  #[serde(flatten)]
+  extra: Map<String, Value>,
 }
 
 // Streaming response structures
@@ -287,12 +346,15 @@ pub struct MoonshotStreamingResponse {
   stream: Pin<Box<dyn Stream<Item = Result<String>> + Send>>,
   buffer: Option<String>,
   finished: bool,
+  /// The partial-mode prefill, emitted as the first chunk so the stream
+  /// reads as the whole answer.
+  prefill: Option<String>,
 }
 
 // Q2.5.4: `unsafe impl Send + Sync` removed (trait no longer needs Sync).
 
 impl MoonshotStreamingResponse {
-  fn new(response: reqwest::Response) -> Self {
+  fn new(response: reqwest::Response, prefill: Option<String>) -> Self {
     let byte_stream = response.bytes_stream();
     let string_stream = byte_stream.map(|chunk_result| {
       chunk_result
@@ -306,6 +368,7 @@ impl MoonshotStreamingResponse {
       stream: Box::pin(string_stream),
       buffer: Some(String::new()),
       finished: false,
+      prefill,
     }
   }
 
@@ -324,6 +387,7 @@ impl MoonshotStreamingResponse {
         usage: None,
         content_type: Some("text".to_string()),
         tool_call_deltas: Vec::new(),
+        thinking_delta: None,
       });
     }
 
@@ -342,6 +406,7 @@ impl MoonshotStreamingResponse {
         }),
         content_type: Some("text".to_string()),
         tool_call_deltas: Vec::new(),
+        thinking_delta: None,
       });
     }
 
@@ -355,6 +420,17 @@ impl StreamingResponse for MoonshotStreamingResponse {
     if self.finished {
       return Ok(None);
     }
+    if let Some(prefill) = self.prefill.take() {
+      return Ok(Some(StreamChunk {
+        content: prefill,
+        is_final: false,
+        metadata: Some(json!({ "partial_prefill": true })),
+        usage: None,
+        content_type: Some("text".to_string()),
+        tool_call_deltas: Vec::new(),
+        thinking_delta: None,
+      }));
+    }
 
     loop {
       // Try to get the next chunk from the stream
@@ -465,6 +541,55 @@ mod tests {
     assert_eq!(body["tool_choice"], "auto");
   }
 
+  #[test]
+  fn build_request_body_sends_builtin_tools_as_builtin_functions() {
+    use crate::tool_calling::ToolSpec;
+    let provider = MoonshotProvider::new("test-key", None).unwrap();
+    let mut request = ProviderRequest::new("kimi-k2", vec![], false);
+    request.tools = Some(vec![
+      ToolSpec::new("ping", "Ping a host", json!({"type": "object"})),
+      ToolSpec::builtin("$web_search"),
+    ]);
+    let body = provider.build_request_body(&request);
+    assert_eq!(body["tools"][0]["type"], "function");
+    assert_eq!(
+      body["tools"][1],
+      json!({"type": "builtin_function", "function": {"name": "$web_search"}})
+    );
+  }
+
+  #[test]
+  fn partial_prefill_reads_a_trailing_partial_assistant_message() {
+    let mut request = ProviderRequest::new(
+      "kimi-k2",
+      vec![
+        json!({"role": "user", "content": "Name a colour"}),
+        json!({"role": "assistant", "content": "{\"colour\": \"", "partial": true}),
+      ],
+      false,
+    );
+    assert_eq!(
+      partial_prefill(&request).as_deref(),
+      Some("{\"colour\": \"")
+    );
+
+    request.messages[1]["partial"] = json!(false);
+    assert_eq!(partial_prefill(&request), None);
+  }
+
+  #[test]
+  fn web_search_tokens_sum_the_search_calls() {
+    let calls = json!([
+      {"id": "1", "type": "builtin_function", "function": {
+        "name": "$web_search",
+        "arguments": "{\"search_result\":{\"search_id\":\"s1\"},\"usage\":{\"total_tokens\":1200}}"
+      }},
+      {"id": "2", "type": "function", "function": {"name": "ping", "arguments": "{}"}}
+    ]);
+    assert_eq!(web_search_tokens(&calls), Some(1200));
+    assert_eq!(web_search_tokens(&json!([])), None);
+  }
+
   #[test]
   fn test_supported_models() {
     let provider = MoonshotProvider::new("test-key", None).unwrap();
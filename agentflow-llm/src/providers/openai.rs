@@ -1,7 +1,7 @@
 use crate::{
   LLMError, Result,
   client::streaming::{StreamChunk, StreamingResponse, TokenUsage, ToolCallDelta},
-  providers::{ContentType, LLMProvider, ProviderRequest, ProviderResponse},
+  providers::{CompatProfile, ContentType, LLMProvider, ProviderRequest, ProviderResponse},
   thinking::ThinkingConfig,
   tool_calling::{StopReason, ToolCallRequest, ToolChoice, ToolSpec},
 };
@@ -17,6 +17,7 @@ pub struct OpenAIProvider {
   client: Client,
   api_key: String,
   base_url: String,
+  profile: CompatProfile,
 }
 
 impl OpenAIProvider {
@@ -43,9 +44,21 @@ impl OpenAIProvider {
       client,
       api_key: api_key.to_string(),
       base_url,
+      profile: CompatProfile::openai(),
     })
   }
 
+  /// Serve an OpenAI-compatible vendor: `profile` adapts every request
+  /// body, error and finish reason (see [`CompatProfile`]).
+  pub fn with_profile(mut self, profile: CompatProfile) -> Self {
+    self.profile = profile;
+    self
+  }
+
+  pub fn profile(&self) -> &CompatProfile {
+    &self.profile
+  }
+
   fn build_headers(&self) -> Result<reqwest::header::HeaderMap> {
     use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
 
@@ -90,6 +103,7 @@ impl OpenAIProvider {
       body["reasoning_effort"] = Value::String(effort.to_string());
     }
 
+    self.profile.adapt_request(&mut body);
     body
   }
 }
@@ -170,7 +184,7 @@ pub(crate) fn parse_openai_tool_calls(value: &Value) -> Vec<ToolCallRequest> {
 #[async_trait]
 impl LLMProvider for OpenAIProvider {
   fn name(&self) -> &str {
-    "openai"
+    &self.profile.vendor
   }
 
   async fn execute(&self, request: &ProviderRequest) -> Result<ProviderResponse> {
@@ -192,12 +206,7 @@ impl LLMProvider for OpenAIProvider {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(self.profile.error_from_response(response).await);
     }
 
     let openai_response: OpenAIResponse = response.json().await?;
@@ -242,7 +251,7 @@ impl LLMProvider for OpenAIProvider {
     let stop_reason = if tool_calls.is_empty() {
       first_choice
         .and_then(|c| c.finish_reason.as_deref())
-        .map(StopReason::from_openai_finish_reason)
+        .map(|reason| self.profile.stop_reason(reason))
     } else {
       Some(StopReason::ToolCalls)
     };
@@ -261,6 +270,7 @@ impl LLMProvider for OpenAIProvider {
       tool_calls,
       stop_reason,
       thinking,
+      thinking_blocks: Vec::new(),
     })
   }
 
@@ -286,12 +296,7 @@ impl LLMProvider for OpenAIProvider {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(self.profile.error_from_response(response).await);
     }
 
     Ok(Box::new(OpenAIStreamingResponse::new(response)))
@@ -310,8 +315,11 @@ impl LLMProvider for OpenAIProvider {
 
     if !response.status().is_success() {
       return Err(LLMError::AuthenticationError {
-        provider: "openai".to_string(),
-        message: "Failed to authenticate with OpenAI API".to_string(),
+        provider: self.profile.vendor.clone(),
+        message: format!(
+          "Failed to authenticate with the {} API",
+          self.profile.vendor
+        ),
       });
     }
 
@@ -464,6 +472,7 @@ impl OpenAIStreamingResponse {
         usage: None,
         content_type: Some("text".to_string()),
         tool_call_deltas: Vec::new(),
+        thinking_delta: None,
       });
     }
 
@@ -515,6 +524,7 @@ impl OpenAIStreamingResponse {
         }),
         content_type: Some("text".to_string()),
         tool_call_deltas,
+        thinking_delta: None,
       });
     }
 
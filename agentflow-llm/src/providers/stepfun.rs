@@ -2,7 +2,7 @@ use crate::{
   LLMError, Result,
   client::streaming::{StreamChunk, StreamingResponse, TokenUsage},
   providers::{
-    ContentType, LLMProvider, ProviderRequest, ProviderResponse,
+    CompatProfile, ContentType, LLMProvider, ProviderRequest, ProviderResponse,
     openai::{parse_openai_tool_calls, tool_choice_to_openai_value, tool_spec_to_openai_value},
   },
   tool_calling::StopReason,
@@ -151,12 +151,7 @@ impl StepFunProvider {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let stepfun_response: StepFunResponse = response.json().await?;
@@ -215,6 +210,7 @@ impl StepFunProvider {
       tool_calls,
       stop_reason,
       thinking: None,
+      thinking_blocks: Vec::new(),
     })
   }
 
@@ -232,12 +228,7 @@ impl StepFunProvider {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     Ok(Box::new(StepFunStreamingResponse::new(response)))
@@ -484,6 +475,7 @@ impl StepFunStreamingResponse {
         usage: None,
         content_type: Some("text".to_string()),
         tool_call_deltas: Vec::new(),
+        thinking_delta: None,
       });
     }
 
@@ -508,6 +500,7 @@ impl StepFunStreamingResponse {
         }),
         content_type: Some("text".to_string()),
         tool_call_deltas: Vec::new(),
+        thinking_delta: None,
       });
     }
 
@@ -791,12 +784,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let result: ImageGenerationResponse = response.json().await?;
@@ -824,12 +812,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let result: ImageGenerationResponse = response.json().await?;
@@ -889,12 +872,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let result: ImageGenerationResponse = response.json().await?;
@@ -919,12 +897,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let audio_data = response.bytes().await?;
@@ -949,12 +922,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let result: VoiceCloningResponse = response.json().await?;
@@ -998,12 +966,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     let result: VoiceListResponse = response.json().await?;
@@ -1033,12 +996,7 @@ impl StepFunSpecializedClient {
       .await?;
 
     if !response.status().is_success() {
-      let status_code = response.status().as_u16();
-      let error_text = response.text().await.unwrap_or_default();
-      return Err(LLMError::HttpError {
-        status_code,
-        message: error_text,
-      });
+      return Err(CompatProfile::stepfun().error_from_response(response).await);
     }
 
     // Handle different response formats
@@ -1211,7 +1169,7 @@ use crate::providers::modality::{
   Image2ImageRequest as ModalityImage2ImageRequest, ImageEditProvider,
   ImageEditRequest as ModalityImageEditRequest,
   ImageGenerationResponse as ModalityImageGenerationResponse, Text2ImageProvider,
-  Text2ImageRequest as ModalityText2ImageRequest, TtsProvider, TtsRequest, TtsResponse,
+  Text2ImageRequest as ModalityText2ImageRequest, TtsProvider, TtsRequest, TtsResponse, TtsVoice,
 };
 
 fn into_modality_image_response(
@@ -1227,6 +1185,7 @@ fn into_modality_image_response(
       url: data.url,
       b64_json: data.b64_json.or(data.image),
       seed: Some(data.seed),
+      finish_reason: Some(data.finish_reason),
     })
     .collect();
   ModalityImageGenerationResponse {
@@ -1291,6 +1250,25 @@ impl TtsProvider for StepFunSpecializedClient {
     let audio = self.text_to_speech(stepfun_request).await?;
     Ok(TtsResponse { audio, mime_type })
   }
+
+  /// The account's custom (cloned) voices, all pages. StepFun's preset
+  /// voices are not listed by the API.
+  async fn list_voices(&self) -> Result<Vec<TtsVoice>> {
+    let mut voices = Vec::new();
+    let mut after = None;
+    loop {
+      let page = StepFunSpecializedClient::list_voices(self, Some(100), None, None, after).await?;
+      voices.extend(page.data.into_iter().map(|voice| TtsVoice {
+        description: Some(format!("custom voice from file {}", voice.file_id)),
+        id: voice.id,
+        name: None,
+      }));
+      if !page.has_more || page.last_id.is_none() {
+        return Ok(voices);
+      }
+      after = page.last_id;
+    }
+  }
 }
 
 #[async_trait]
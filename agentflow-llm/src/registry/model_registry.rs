@@ -1,6 +1,6 @@
 use crate::{
   LLMError, Result,
-  config::{LLMConfig, ModelConfig},
+  config::{ConfigExplanation, LLMConfig, ModelConfig},
   providers::{LLMProvider, create_provider},
 };
 use std::collections::{HashMap, HashSet};
@@ -135,6 +135,30 @@ impl ModelRegistry {
     })
   }
 
+  /// A provider client authenticated with `api_key` instead of the key
+  /// configured for `provider_name` (per-node credentials). Uses the
+  /// provider's configured `base_url`.
+  pub fn provider_with_api_key(
+    &self,
+    provider_name: &str,
+    api_key: &str,
+  ) -> Result<Arc<dyn LLMProvider>> {
+    let base_url = {
+      let config_guard = self.config.read().map_err(|e| LLMError::InternalError {
+        message: format!("Configuration lock poisoned: {}", e),
+      })?;
+      config_guard
+        .as_ref()
+        .and_then(|config| config.get_provider(provider_name))
+        .and_then(|provider| provider.base_url.clone())
+    };
+    Ok(Arc::from(create_provider(
+      provider_name,
+      api_key,
+      base_url,
+    )?))
+  }
+
   /// List all available model names
   pub fn list_models(&self) -> Vec<String> {
     // Note: Returns empty vec if lock is poisoned to maintain backward compatibility
@@ -186,6 +210,14 @@ impl ModelRegistry {
       })
   }
 
+  /// The loaded configuration annotated with each field's origin (see
+  /// [`LLMConfig::explain`]). Assumes it was loaded from the default
+  /// source, as [`crate::AgentFlow::init`] does.
+  pub async fn explain(&self) -> Result<ConfigExplanation> {
+    let config = self.get_config().await?;
+    config.explain(&LLMConfig::resolve_default_source()?)
+  }
+
   /// Get model information for debugging/inspection.
   ///
   /// Read-only by design: must not require a live provider, because
@@ -168,6 +168,36 @@ impl ThinkingKind {
   }
 }
 
+/// A reasoning block exactly as the provider returned it.
+///
+/// Anthropic returns `thinking` blocks, each with an opaque `signature`,
+/// and `redacted_thinking` blocks whose reasoning was flagged by its safety
+/// systems and comes back encrypted. When an assistant turn called tools,
+/// its blocks must be sent back unmodified, ahead of the `tool_use` blocks,
+/// with the tool results; [`crate::LLMResponse::assistant_message`] does so.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThinkingBlock {
+  Thinking {
+    thinking: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+  },
+  RedactedThinking {
+    data: String,
+  },
+}
+
+impl ThinkingBlock {
+  /// The reasoning text; `None` for a redacted block.
+  pub fn text(&self) -> Option<&str> {
+    match self {
+      ThinkingBlock::Thinking { thinking, .. } => Some(thinking),
+      ThinkingBlock::RedactedThinking { .. } => None,
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
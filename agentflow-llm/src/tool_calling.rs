@@ -9,6 +9,10 @@
 //! - StepFun / Moonshot: OpenAI-compatible passthrough
 //! - Mock: programmatic injection (used by ReAct/Plan-Execute fallback tests)
 
+use crate::context_overflow::ContextReport;
+use crate::multimodal::{MessageContent, MultimodalMessage};
+use crate::prompts::PromptRef;
+use crate::thinking::ThinkingBlock;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
@@ -36,6 +40,27 @@ impl ToolSpec {
     }
   }
 
+  /// A tool the provider runs itself, such as Moonshot's `$web_search`.
+  /// Builtin names start with `$`, which is added when missing; function
+  /// names cannot contain it, so the two never collide.
+  pub fn builtin<N: Into<String>>(name: N) -> Self {
+    let name = name.into();
+    Self {
+      name: if name.starts_with('$') {
+        name
+      } else {
+        format!("${name}")
+      },
+      description: String::new(),
+      parameters: Value::Null,
+    }
+  }
+
+  /// Whether this is a provider builtin (see [`Self::builtin`]).
+  pub fn is_builtin(&self) -> bool {
+    self.name.starts_with('$')
+  }
+
   /// Construct a `ToolSpec` from a JSON value in OpenAI tool format:
   /// `{ "type": "function", "function": { "name", "description", "parameters" } }`
   /// or directly `{ "name", "description", "parameters" }`.
@@ -175,6 +200,15 @@ pub struct LLMResponse {
   /// `None` when the model didn't emit reasoning text or thinking wasn't
   /// requested. See [`crate::ThinkingConfig`] for how to ask for it.
   pub thinking: Option<String>,
+  /// The reasoning blocks behind `thinking`, verbatim with their
+  /// signatures (Anthropic only). [`Self::assistant_message`] replays them.
+  pub thinking_blocks: Vec<ThinkingBlock>,
+  /// How the request was fitted into the context window. `None` without
+  /// an overflow policy or when the model's `context_window` is unknown.
+  pub context: Option<ContextReport>,
+  /// The template the prompt was rendered from, when the request was
+  /// built with `prompt_template`.
+  pub prompt: Option<PromptRef>,
 }
 
 impl LLMResponse {
@@ -182,6 +216,29 @@ impl LLMResponse {
   pub fn has_tool_calls(&self) -> bool {
     !self.tool_calls.is_empty()
   }
+
+  /// This response as the assistant turn of a conversation: thinking
+  /// blocks, text, then tool calls. Anthropic requires a turn that called
+  /// tools to come back this way, thinking blocks unmodified, in the next
+  /// request alongside a user turn with the results (see
+  /// [`MultimodalMessageBuilder::add_tool_result`](crate::multimodal::MultimodalMessageBuilder::add_tool_result)).
+  pub fn assistant_message(&self) -> MultimodalMessage {
+    let mut message = MultimodalMessage::assistant();
+    for block in &self.thinking_blocks {
+      message = message.add_content(block.clone().into());
+    }
+    if !self.content.is_empty() {
+      message = message.add_text(&self.content);
+    }
+    for call in &self.tool_calls {
+      message = message.add_content(MessageContent::ToolUse {
+        id: call.id.clone(),
+        name: call.name.clone(),
+        input: call.arguments.clone(),
+      });
+    }
+    message.build()
+  }
 }
 
 #[cfg(test)]
@@ -259,6 +316,9 @@ mod tests {
       usage: None,
       raw_metadata: None,
       thinking: None,
+      thinking_blocks: Vec::new(),
+      context: None,
+      prompt: None,
     };
     assert!(resp.has_tool_calls());
   }
@@ -0,0 +1,171 @@
+//! `.overflow_policy(...)` end to end through the mock provider.
+//!
+//! The message selection itself is unit-tested in `context_overflow.rs`;
+//! this file checks that each policy is applied before the request goes
+//! out and reported on the response.
+
+use agentflow_llm::{AgentFlow, LLMError, ModelRegistry, MultimodalMessage, OverflowPolicy};
+
+/// A 2000-token window with 100 kept for the answer: 1900 for the
+/// request.
+const REGISTRY: &str = r#"
+models:
+  mock-small:
+    vendor: mock
+    type: text
+    context_window: 2000
+    max_tokens: 100
+"#;
+
+const BUDGET: u32 = 1900;
+
+async fn load_registry() {
+  ModelRegistry::global()
+    .load_config_from_yaml(REGISTRY)
+    .await
+    .expect("hermetic mock registry loads");
+}
+
+/// A system prompt, ten question/answer pairs of ~104 tokens each, then
+/// the current question: ~2100 tokens.
+fn long_conversation() -> Vec<MultimodalMessage> {
+  let text = |tag: String| format!("{tag} {}", "x".repeat(399 - tag.len()));
+  let mut messages = vec![MultimodalMessage::system().add_text("Be brief.").build()];
+  for turn in 0..10 {
+    messages.push(
+      MultimodalMessage::user()
+        .add_text(text(format!("q{turn}")))
+        .build(),
+    );
+    messages.push(
+      MultimodalMessage::assistant()
+        .add_text(text(format!("a{turn}")))
+        .build(),
+    );
+  }
+  messages.push(MultimodalMessage::user().add_text("And now?").build());
+  messages
+}
+
+#[tokio::test]
+async fn without_a_policy_the_request_goes_out_unchecked() {
+  load_registry().await;
+  let response = AgentFlow::model("mock-small")
+    .multimodal_messages(long_conversation())
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+  assert_eq!(response.context, None);
+}
+
+#[tokio::test]
+async fn error_policy_fails_before_sending() {
+  load_registry().await;
+  let err = AgentFlow::model("mock-small")
+    .multimodal_messages(long_conversation())
+    .overflow_policy(OverflowPolicy::Error)
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap_err();
+  match err {
+    LLMError::ContextOverflow { tokens, budget, .. } => {
+      assert_eq!(budget, BUDGET);
+      assert!(tokens > BUDGET, "{tokens}");
+    }
+    other => panic!("expected ContextOverflow, got {other:?}"),
+  }
+
+  // A request that fits is reported without the policy firing
+  let response = AgentFlow::model("mock-small")
+    .prompt("Hello")
+    .overflow_policy(OverflowPolicy::Error)
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+  let report = response.context.unwrap();
+  assert!(!report.policy_fired);
+  assert_eq!(report.context_tokens_before, report.context_tokens_after);
+}
+
+#[tokio::test]
+async fn truncate_oldest_evicts_the_first_turns() {
+  load_registry().await;
+  let response = AgentFlow::model("mock-small")
+    .multimodal_messages(long_conversation())
+    .overflow_policy(OverflowPolicy::TruncateOldest)
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+
+  let report = response.context.unwrap();
+  assert!(report.policy_fired);
+  assert_eq!(report.context_window, 2000);
+  assert!(report.context_tokens_before > BUDGET);
+  assert!(report.context_tokens_after <= BUDGET);
+  // ~190 tokens over: the first question and answer go
+  assert_eq!(report.evicted, vec![1, 2]);
+  assert_eq!(report.summary, None);
+}
+
+#[tokio::test]
+async fn truncate_middle_keeps_both_ends() {
+  load_registry().await;
+  let messages = long_conversation();
+  let last = messages.len() - 1;
+  let response = AgentFlow::model("mock-small")
+    .multimodal_messages(messages)
+    .overflow_policy(OverflowPolicy::TruncateMiddle)
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+
+  let report = response.context.unwrap();
+  assert!(report.policy_fired);
+  assert!(report.context_tokens_after <= BUDGET);
+  assert_eq!(report.evicted, vec![10, 11]);
+  assert!(!report.evicted.contains(&1) && !report.evicted.contains(&(last - 1)));
+}
+
+#[tokio::test]
+async fn summarize_history_replaces_evicted_turns_with_a_digest() {
+  load_registry().await;
+  let response = AgentFlow::model("mock-small")
+    .multimodal_messages(long_conversation())
+    .overflow_policy(OverflowPolicy::SummarizeHistory {
+      model: "mock-small".to_string(),
+    })
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+
+  let report = response.context.unwrap();
+  assert!(report.policy_fired);
+  assert!(report.context_tokens_after <= BUDGET);
+  // Room is kept for the digest, so more goes than under TruncateOldest
+  assert_eq!(report.evicted, (1..=12).collect::<Vec<_>>());
+  // The mock echoes the start of the prompt it was given
+  let summary = report.summary.unwrap();
+  assert!(
+    summary.starts_with("Mock response for: 'Summarize the conversation"),
+    "{summary}"
+  );
+}
+
+#[tokio::test]
+async fn an_oversized_last_turn_cannot_be_truncated() {
+  load_registry().await;
+  let err = AgentFlow::model("mock-small")
+    .prompt(&"x".repeat(10_000))
+    .overflow_policy(OverflowPolicy::TruncateOldest)
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap_err();
+  assert!(matches!(err, LLMError::ContextOverflow { .. }), "{err:?}");
+}
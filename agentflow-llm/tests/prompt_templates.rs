@@ -0,0 +1,118 @@
+//! `.prompt_template(...)` end to end through the mock provider.
+//!
+//! Lookup and rendering are unit-tested in `prompts.rs`; this file checks
+//! that the builder renders from the global registry and that the template
+//! is reported on the response.
+
+use agentflow_llm::{AgentFlow, LLMError, ModelRegistry, PromptRef, PromptRegistry};
+
+const REGISTRY: &str = r#"
+models:
+  mock-model:
+    vendor: mock
+    type: text
+"#;
+
+const SUMMARIZE_V1: &str = r#"
+name: summarize_paper
+version: 1
+variables: [title]
+template: "Summarize {{title}}."
+"#;
+
+const SUMMARIZE_V2: &str = r#"
+name: summarize_paper
+version: 2
+variables: [title, abstract]
+model_hints:
+  temperature: 0.1
+  max_tokens: 300
+template: "Summarize {{title}} from its abstract: {{abstract}}"
+"#;
+
+async fn setup() {
+  ModelRegistry::global()
+    .load_config_from_yaml(REGISTRY)
+    .await
+    .expect("hermetic mock registry loads");
+  let prompts = PromptRegistry::global();
+  prompts.register_yaml(SUMMARIZE_V1).unwrap();
+  prompts.register_yaml(SUMMARIZE_V2).unwrap();
+}
+
+fn summarize_ref(version: u32) -> Option<PromptRef> {
+  Some(PromptRef {
+    name: "summarize_paper".to_string(),
+    version,
+  })
+}
+
+#[tokio::test]
+async fn pinned_and_latest_versions_are_rendered_and_reported() {
+  setup().await;
+  let response = AgentFlow::model("mock-model")
+    .prompt_template("summarize_paper@1", [("title", "Attention")])
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+  assert_eq!(response.prompt, summarize_ref(1));
+  // The mock echoes the start of the prompt it was given
+  assert!(
+    response
+      .content
+      .starts_with("Mock response for: 'Summarize Attention."),
+    "{}",
+    response.content
+  );
+
+  let response = AgentFlow::model("mock-model")
+    .prompt_template(
+      "summarize_paper",
+      [("title", "Attention"), ("abstract", "Transformers")],
+    )
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+  assert_eq!(response.prompt, summarize_ref(2));
+
+  // A plain prompt afterwards drops the template
+  let response = AgentFlow::model("mock-model")
+    .prompt_template("summarize_paper@1", [("title", "Attention")])
+    .prompt("Something else")
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap();
+  assert_eq!(response.prompt, None);
+}
+
+#[tokio::test]
+async fn a_missing_variable_fails_the_request() {
+  setup().await;
+  let err = AgentFlow::model("mock-model")
+    .prompt_template("summarize_paper@2", [("title", "Attention")])
+    .enable_logging(false)
+    .execute_full()
+    .await
+    .unwrap_err();
+  match err {
+    LLMError::PromptTemplateError { prompt, message } => {
+      assert_eq!(prompt, "summarize_paper@2");
+      assert_eq!(message, "missing variables: abstract");
+    }
+    other => panic!("expected PromptTemplateError, got {other:?}"),
+  }
+
+  let err = AgentFlow::model("mock-model")
+    .prompt_template("summarize_paper@9", [("title", "Attention")])
+    .enable_logging(false)
+    .execute()
+    .await
+    .unwrap_err();
+  assert!(
+    matches!(err, LLMError::PromptTemplateError { .. }),
+    "{err:?}"
+  );
+}
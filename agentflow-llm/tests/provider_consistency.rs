@@ -42,8 +42,8 @@ use std::time::Duration;
 use agentflow_llm::LLMError;
 use agentflow_llm::client::StreamingResponse;
 use agentflow_llm::providers::{
-  AnthropicProvider, ContentType, GoogleProvider, LLMProvider, MoonshotProvider, OpenAIProvider,
-  ProviderRequest, StepFunProvider,
+  AnthropicProvider, CompatProfile, ContentType, GoogleProvider, LLMProvider, MoonshotProvider,
+  OpenAIProvider, ProviderRequest, StepFunProvider,
 };
 use agentflow_llm::tool_calling::{StopReason, ToolCallRequest, ToolChoice, ToolSpec};
 use serde_json::json;
@@ -2133,3 +2133,197 @@ async fn cross_provider_tool_choice_specific_tool_is_honored_by_every_provider()
     );
   }
 }
+
+// -----------------------------------------------------------------------------
+// Moonshot partial mode and builtin tools
+//
+// Not a cross-provider contract: only Moonshot continues a trailing
+// `partial: true` assistant message and runs `$web_search` itself. The
+// client refuses both for other vendors, so the tests drive the provider
+// with the messages and tools the client builds.
+// -----------------------------------------------------------------------------
+
+const MOONSHOT_PREFILL: &str = "{\"colour\": \"";
+
+const MOONSHOT_CONTINUATION: &str = r#"{"id":"chatcmpl-test","object":"chat.completion","created":0,"model":"kimi-k2","choices":[{"index":0,"message":{"role":"assistant","content":"teal\"}"},"finish_reason":"stop"}],"usage":{"prompt_tokens":12,"completion_tokens":3,"total_tokens":15,"cached_tokens":8}}"#;
+
+const MOONSHOT_WEB_SEARCH_CALL: &str = r#"{"id":"chatcmpl-test","object":"chat.completion","created":0,"model":"kimi-k2","choices":[{"index":0,"message":{"role":"assistant","content":"","tool_calls":[{"id":"search_1","type":"builtin_function","function":{"name":"$web_search","arguments":"{\"search_result\":{\"search_id\":\"s-1\"},\"usage\":{\"total_tokens\":2048}}"}}]},"finish_reason":"tool_calls"}],"usage":{"prompt_tokens":20,"completion_tokens":4,"total_tokens":24}}"#;
+
+fn moonshot_prefill_request(stream: bool) -> ProviderRequest {
+  ProviderRequest::new(
+    "kimi-k2",
+    vec![
+      json!({"role": "user", "content": "Name a colour as JSON"}),
+      json!({"role": "assistant", "content": MOONSHOT_PREFILL, "partial": true}),
+    ],
+    stream,
+  )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn moonshot_partial_mode_sends_the_prefill_and_returns_the_whole_answer() {
+  let (base_url, captured) = spawn_mock_server(200, MOONSHOT_CONTINUATION.to_string()).await;
+  let provider =
+    MoonshotProvider::with_client(no_proxy_client(), "test-key", Some(base_url)).expect("provider");
+  let response = provider
+    .execute(&moonshot_prefill_request(false))
+    .await
+    .expect("ok");
+
+  let body = captured_body(captured.lock().await.as_deref().expect("request captured"));
+  assert_eq!(
+    body["messages"][1],
+    json!({"role": "assistant", "content": MOONSHOT_PREFILL, "partial": true})
+  );
+  assert_text(&response.content, "{\"colour\": \"teal\"}");
+  // Usage fields beyond the OpenAI three survive into the metadata
+  let metadata = response.metadata.expect("metadata");
+  assert_eq!(metadata["usage"]["cached_tokens"], 8);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn moonshot_web_search_is_sent_as_a_builtin_function() {
+  let (base_url, captured) = spawn_mock_server(200, MOONSHOT_WEB_SEARCH_CALL.to_string()).await;
+  let provider =
+    MoonshotProvider::with_client(no_proxy_client(), "test-key", Some(base_url)).expect("provider");
+  let mut request = provider_request("kimi-k2");
+  request.tools = Some(vec![ToolSpec::builtin("web_search")]);
+  let response = provider.execute(&request).await.expect("ok");
+
+  let body = captured_body(captured.lock().await.as_deref().expect("request captured"));
+  assert_eq!(
+    body["tools"],
+    json!([{"type": "builtin_function", "function": {"name": "$web_search"}}])
+  );
+  // The call comes back for the caller to echo its arguments as the result
+  assert_eq!(response.stop_reason, Some(StopReason::ToolCalls));
+  assert_eq!(response.tool_calls[0].name, "$web_search");
+  let metadata = response.metadata.expect("metadata");
+  assert_eq!(metadata["web_search_tokens"], 2048);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn moonshot_streaming_partial_mode_emits_the_prefill_first() {
+  let base_url = spawn_streaming_mock_server(openai_compat_stream_events("kimi-k2")).await;
+  let provider =
+    MoonshotProvider::with_client(no_proxy_client(), "test-key", Some(base_url)).expect("provider");
+  let mut stream = provider
+    .execute_streaming(&moonshot_prefill_request(true))
+    .await
+    .expect("stream");
+
+  let first = stream
+    .next_chunk()
+    .await
+    .expect("chunk")
+    .expect("prefill chunk");
+  assert_eq!(first.content, MOONSHOT_PREFILL);
+  assert!(!first.is_final);
+  let mut text = first.content;
+  while let Some(chunk) = stream.next_chunk().await.expect("chunk") {
+    text.push_str(&chunk.content);
+  }
+  assert_eq!(text, format!("{MOONSHOT_PREFILL}Hello world"));
+}
+
+// -----------------------------------------------------------------------------
+// OpenAI-compatible vendor profiles
+//
+// DashScope, GLM, DeepSeek, MiniMax, Groq and Ollama go through
+// `OpenAIProvider` with a `CompatProfile`. On the wire the profile drops
+// the parameters a vendor rejects, renames the ones it calls differently,
+// reads the vendor's error body and maps its finish reasons.
+// -----------------------------------------------------------------------------
+
+const GLM_CONTENT_FILTERED: &str = r#"{"id":"chatcmpl-test","object":"chat.completion","created":0,"model":"glm-4","choices":[{"index":0,"message":{"role":"assistant","content":""},"finish_reason":"sensitive"}],"usage":{"prompt_tokens":5,"completion_tokens":0,"total_tokens":5}}"#;
+
+fn compat_provider(profile: CompatProfile, base_url: String) -> OpenAIProvider {
+  OpenAIProvider::with_client(no_proxy_client(), "test-key", Some(base_url))
+    .expect("provider")
+    .with_profile(profile)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compat_profiles_adapt_parameters_on_the_wire() {
+  // (profile, parameters sent, parameters expected besides model/messages/stream)
+  let cases = [
+    (
+      CompatProfile::glm(),
+      json!({"temperature": 0.2, "n": 2, "max_completion_tokens": 64}),
+      json!({"temperature": 0.2, "max_tokens": 64}),
+    ),
+    (
+      CompatProfile::groq(),
+      json!({"temperature": 0.2, "logprobs": true, "max_completion_tokens": 64}),
+      json!({"temperature": 0.2, "max_completion_tokens": 64}),
+    ),
+    (
+      CompatProfile::ollama(),
+      json!({"temperature": 0.2, "tool_choice": "auto", "max_completion_tokens": 64}),
+      json!({"temperature": 0.2, "max_tokens": 64}),
+    ),
+  ];
+  for (profile, sent, expected) in cases {
+    let vendor = profile.vendor.clone();
+    let (base_url, captured) = spawn_mock_server(200, OPENAI_SUCCESS.to_string()).await;
+    let provider = compat_provider(profile, base_url);
+    assert_eq!(provider.name(), vendor);
+    let mut request = provider_request("m");
+    request.parameters = serde_json::from_value(sent).unwrap();
+    provider.execute(&request).await.expect("ok");
+
+    let mut body = captured_body(captured.lock().await.as_deref().expect("request captured"));
+    for key in ["model", "messages", "stream"] {
+      body.as_object_mut().unwrap().remove(key);
+    }
+    assert_eq!(body, expected, "{vendor}");
+  }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compat_profiles_report_the_vendor_error() {
+  let cases = [
+    (
+      CompatProfile::dashscope(),
+      r#"{"code":"InvalidApiKey","message":"Invalid API-key provided.","request_id":"r-1"}"#,
+      "dashscope error InvalidApiKey: Invalid API-key provided.",
+    ),
+    (
+      CompatProfile::minimax(),
+      r#"{"base_resp":{"status_code":1004,"status_msg":"login fail"}}"#,
+      "minimax error 1004: login fail",
+    ),
+    (
+      CompatProfile::ollama(),
+      r#"{"error":"unauthorized"}"#,
+      "ollama error: unauthorized",
+    ),
+  ];
+  for (profile, body, expected) in cases {
+    let (base_url, _) = spawn_mock_server(401, body.to_string()).await;
+    let err = compat_provider(profile, base_url)
+      .execute(&provider_request("m"))
+      .await
+      .expect_err("401");
+    match err {
+      LLMError::HttpError {
+        status_code,
+        message,
+      } => {
+        assert_eq!(status_code, 401);
+        assert_eq!(message, expected);
+      }
+      other => panic!("expected HttpError, got {other:?}"),
+    }
+  }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compat_profile_maps_vendor_finish_reasons() {
+  let (base_url, _) = spawn_mock_server(200, GLM_CONTENT_FILTERED.to_string()).await;
+  let response = compat_provider(CompatProfile::glm(), base_url)
+    .execute(&provider_request("glm-4"))
+    .await
+    .expect("ok");
+  assert_eq!(response.stop_reason, Some(StopReason::ContentFilter));
+}
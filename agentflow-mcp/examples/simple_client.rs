@@ -133,8 +133,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                   );
                 } else {
                   println!(
-                    "Binary content: {} bytes",
-                    content.as_blob().map(|b| b.len()).unwrap_or(0)
+                    "Binary content ({}): {} bytes",
+                    content.mime_type().unwrap_or("unknown type"),
+                    content.as_bytes().map(|b| b.len()).unwrap_or(0)
                   );
                 }
               }
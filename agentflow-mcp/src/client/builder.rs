@@ -113,6 +113,13 @@ impl ClientBuilder {
     self
   }
 
+  /// Configure HTTP transport posting to `url`
+  #[cfg(feature = "http")]
+  pub fn with_http(mut self, url: impl Into<String>) -> Self {
+    self.transport = Some(Box::new(crate::transport::HttpTransport::new(url)));
+    self
+  }
+
   /// Configure stdio transport with environment variables.
   pub fn with_stdio_env(mut self, command: Vec<String>, env: HashMap<String, String>) -> Self {
     self.transport = Some(Box::new(StdioTransport::new(command).with_env(env)));
@@ -4,7 +4,9 @@
 
 use crate::error::{JsonRpcErrorCode, MCPError, MCPResult, ResultExt};
 use crate::protocol::types::{JsonRpcRequest, JsonRpcResponse};
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 use super::MCPClient;
 
@@ -24,42 +26,140 @@ pub struct Resource {
   pub mime_type: Option<String>,
 }
 
-/// Resource content
+/// One item of a resource read: text, or binary data as base64
+///
+/// Serializes as the MCP wire form, `{uri, mimeType?, text}` or
+/// `{uri, mimeType?, blob}`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub struct ResourceContent {
-  /// Resource URI
-  pub uri: String,
-  /// MIME type
-  #[serde(skip_serializing_if = "Option::is_none", rename = "mimeType")]
-  pub mime_type: Option<String>,
+#[serde(untagged)]
+pub enum ResourceContent {
   /// Text content
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub text: Option<String>,
-  /// Blob content (base64)
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub blob: Option<String>,
+  Text {
+    /// Resource URI
+    uri: String,
+    /// MIME type
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "mimeType")]
+    mime_type: Option<String>,
+    /// The text
+    text: String,
+  },
+  /// Binary content (images, PDFs, ...)
+  Blob {
+    /// Resource URI
+    uri: String,
+    /// MIME type
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "mimeType")]
+    mime_type: Option<String>,
+    /// The data, base64-encoded
+    blob: String,
+  },
 }
 
 impl ResourceContent {
+  /// Create text content
+  pub fn text(uri: impl Into<String>, text: impl Into<String>) -> Self {
+    Self::Text {
+      uri: uri.into(),
+      mime_type: None,
+      text: text.into(),
+    }
+  }
+
+  /// Create binary content from its base64 encoding
+  pub fn blob(uri: impl Into<String>, blob: impl Into<String>) -> Self {
+    Self::Blob {
+      uri: uri.into(),
+      mime_type: None,
+      blob: blob.into(),
+    }
+  }
+
+  /// Set the MIME type
+  pub fn with_mime_type(mut self, mime: impl Into<String>) -> Self {
+    match &mut self {
+      Self::Text { mime_type, .. } | Self::Blob { mime_type, .. } => *mime_type = Some(mime.into()),
+    }
+    self
+  }
+
+  /// Resource URI
+  pub fn uri(&self) -> &str {
+    match self {
+      Self::Text { uri, .. } | Self::Blob { uri, .. } => uri,
+    }
+  }
+
+  /// MIME type, if the server gave one
+  pub fn mime_type(&self) -> Option<&str> {
+    match self {
+      Self::Text { mime_type, .. } | Self::Blob { mime_type, .. } => mime_type.as_deref(),
+    }
+  }
+
   /// Get content as text
   pub fn as_text(&self) -> Option<&str> {
-    self.text.as_deref()
+    match self {
+      Self::Text { text, .. } => Some(text),
+      Self::Blob { .. } => None,
+    }
   }
 
-  /// Get content as blob
+  /// Get content as blob (base64)
   pub fn as_blob(&self) -> Option<&str> {
-    self.blob.as_deref()
+    match self {
+      Self::Blob { blob, .. } => Some(blob),
+      Self::Text { .. } => None,
+    }
   }
 
   /// Check if resource has text content
   pub fn is_text(&self) -> bool {
-    self.text.is_some()
+    matches!(self, Self::Text { .. })
   }
 
   /// Check if resource has blob content
   pub fn is_blob(&self) -> bool {
-    self.blob.is_some()
+    matches!(self, Self::Blob { .. })
+  }
+
+  /// Check if the content is binary, i.e. a blob
+  pub fn is_binary(&self) -> bool {
+    self.is_blob()
+  }
+
+  /// The content's bytes: the decoded blob, or the text as UTF-8
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if a blob is not valid base64.
+  pub fn as_bytes(&self) -> MCPResult<Vec<u8>> {
+    match self {
+      Self::Text { text, .. } => Ok(text.as_bytes().to_vec()),
+      Self::Blob { uri, blob, .. } => BASE64_STANDARD.decode(blob.trim()).map_err(|e| {
+        MCPError::resource(
+          format!("Resource '{}' has an invalid base64 blob: {}", uri, e),
+          Some(uri.clone()),
+        )
+      }),
+    }
+  }
+
+  /// Write the content's bytes (see [`as_bytes`](Self::as_bytes)) to `path`
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if a blob is not valid base64 or the file cannot be
+  /// written.
+  pub async fn save_to(&self, path: impl AsRef<Path>) -> MCPResult<()> {
+    let path = path.as_ref();
+    let bytes = self.as_bytes()?;
+    tokio::fs::write(path, bytes).await.map_err(|e| {
+      MCPError::from(e).context(format!(
+        "Failed to save resource '{}' to {}",
+        self.uri(),
+        path.display()
+      ))
+    })
   }
 }
 
@@ -67,7 +167,7 @@ impl ResourceContent {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadResourceResult {
-  /// Resource contents
+  /// Resource contents, in the order the server returned them
   pub contents: Vec<ResourceContent>,
 }
 
@@ -81,6 +181,31 @@ impl ReadResourceResult {
   pub fn text_contents(&self) -> Vec<&str> {
     self.contents.iter().filter_map(|c| c.as_text()).collect()
   }
+
+  /// Check if any content item is binary
+  pub fn has_binary(&self) -> bool {
+    self.contents.iter().any(ResourceContent::is_binary)
+  }
+
+  /// All contents as one string, text items joined by newlines in order
+  ///
+  /// # Errors
+  ///
+  /// Returns an error naming the first binary item, rather than decoding
+  /// it as text.
+  pub fn into_string(self) -> MCPResult<String> {
+    if let Some(binary) = self.contents.iter().find(|c| c.is_binary()) {
+      return Err(MCPError::resource(
+        format!(
+          "Resource '{}' is binary ({}); use read_resource and as_bytes or save_to instead",
+          binary.uri(),
+          binary.mime_type().unwrap_or("unknown type")
+        ),
+        Some(binary.uri().to_string()),
+      ));
+    }
+    Ok(self.text_contents().join("\n"))
+  }
 }
 
 /// Resource access methods for MCPClient
@@ -260,6 +385,34 @@ impl MCPClient {
     Ok(read_result)
   }
 
+  /// Read a text resource into one string
+  ///
+  /// Multiple contents are joined by newlines in the order the server
+  /// returned them.
+  ///
+  /// # Errors
+  ///
+  /// Returns the errors of [`read_resource`](Self::read_resource), and a
+  /// resource error if any content is binary.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # use agentflow_mcp::client::ClientBuilder;
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// # let mut client = ClientBuilder::new()
+  /// #   .with_stdio(vec!["node".to_string(), "server.js".to_string()])
+  /// #   .build().await?;
+  /// # client.connect().await?;
+  /// let text = client.read_resource_as_string("file:///path/to/file.txt").await?;
+  /// println!("Content: {}", text);
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn read_resource_as_string(&mut self, uri: impl Into<String>) -> MCPResult<String> {
+    self.read_resource(uri).await?.into_string()
+  }
+
   /// Subscribe to resource updates
   ///
   /// # Arguments
@@ -426,48 +579,52 @@ mod tests {
 
   #[test]
   fn test_resource_content_text() {
-    let content = ResourceContent {
-      uri: "file:///test.txt".to_string(),
-      mime_type: Some("text/plain".to_string()),
-      text: Some("Hello, world!".to_string()),
-      blob: None,
-    };
+    let content =
+      ResourceContent::text("file:///test.txt", "Hello, world!").with_mime_type("text/plain");
 
     assert!(content.is_text());
-    assert!(!content.is_blob());
+    assert!(!content.is_binary());
     assert_eq!(content.as_text(), Some("Hello, world!"));
+    assert_eq!(content.mime_type(), Some("text/plain"));
+    assert_eq!(content.as_bytes().unwrap(), b"Hello, world!");
   }
 
   #[test]
   fn test_resource_content_blob() {
-    let content = ResourceContent {
-      uri: "file:///image.png".to_string(),
-      mime_type: Some("image/png".to_string()),
-      text: None,
-      blob: Some("base64data".to_string()),
-    };
+    let content: ResourceContent = serde_json::from_value(serde_json::json!({
+      "uri": "file:///image.png",
+      "mimeType": "image/png",
+      "blob": "iVBORw0KGgo="
+    }))
+    .unwrap();
 
     assert!(!content.is_text());
-    assert!(content.is_blob());
-    assert_eq!(content.as_blob(), Some("base64data"));
+    assert!(content.is_binary());
+    assert_eq!(content.uri(), "file:///image.png");
+    assert_eq!(content.as_blob(), Some("iVBORw0KGgo="));
+    assert_eq!(content.as_bytes().unwrap(), b"\x89PNG\r\n\x1a\n");
+
+    let invalid = ResourceContent::blob("file:///bad.bin", "not base64!");
+    let err = invalid.as_bytes().unwrap_err();
+    assert!(err.to_string().contains("invalid base64"), "{err}");
+  }
+
+  #[test]
+  fn test_resource_content_wire_format() {
+    let content =
+      ResourceContent::blob("file:///a.pdf", "JVBERg==").with_mime_type("application/pdf");
+    assert_eq!(
+      serde_json::to_value(&content).unwrap(),
+      serde_json::json!({ "uri": "file:///a.pdf", "mimeType": "application/pdf", "blob": "JVBERg==" })
+    );
   }
 
   #[test]
   fn test_read_resource_result() {
     let result = ReadResourceResult {
       contents: vec![
-        ResourceContent {
-          uri: "file:///test1.txt".to_string(),
-          mime_type: None,
-          text: Some("First".to_string()),
-          blob: None,
-        },
-        ResourceContent {
-          uri: "file:///test2.txt".to_string(),
-          mime_type: None,
-          text: Some("Second".to_string()),
-          blob: None,
-        },
+        ResourceContent::text("file:///test1.txt", "First"),
+        ResourceContent::text("file:///test2.txt", "Second"),
       ],
     };
 
@@ -475,5 +632,25 @@ mod tests {
     assert_eq!(result.text_contents().len(), 2);
     assert_eq!(result.text_contents()[0], "First");
     assert_eq!(result.text_contents()[1], "Second");
+    assert!(!result.has_binary());
+    assert_eq!(result.into_string().unwrap(), "First\nSecond");
+  }
+
+  #[test]
+  fn test_binary_result_is_not_a_string() {
+    let result = ReadResourceResult {
+      contents: vec![
+        ResourceContent::text("file:///notes.txt", "caption"),
+        ResourceContent::blob("file:///chart.png", "iVBORw0KGgo=").with_mime_type("image/png"),
+      ],
+    };
+
+    let err = result.into_string().unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("'file:///chart.png' is binary (image/png)"),
+      "{err}"
+    );
   }
 }
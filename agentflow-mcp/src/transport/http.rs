@@ -0,0 +1,305 @@
+//! HTTP transport for remote MCP servers
+//!
+//! Each JSON-RPC message is POSTed to a single endpoint (the "streamable
+//! HTTP" shape). The server may answer with a plain JSON body or with a
+//! `text/event-stream` body; for the latter the first `data:` event carrying
+//! the request's `id` is taken as the response. An `Mcp-Session-Id` header
+//! returned by the server is echoed on every later request.
+//!
+//! Server-initiated messages are not supported: `receive_message` always
+//! returns `None`.
+
+use crate::error::{MCPError, MCPResult};
+use crate::transport::traits::{Transport, TransportConfig, TransportType};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Session header defined by the MCP streamable HTTP transport.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// HTTP transport posting JSON-RPC messages to one MCP endpoint
+///
+/// # Example
+///
+/// ```no_run
+/// use agentflow_mcp::transport::{HttpTransport, Transport};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut transport = HttpTransport::new("http://localhost:8080/mcp");
+/// transport.connect().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HttpTransport {
+  /// Endpoint every message is POSTed to
+  url: String,
+  /// Extra headers sent with every request (e.g. `Authorization`)
+  headers: HashMap<String, String>,
+  /// Session id assigned by the server, if any
+  session_id: Mutex<Option<String>>,
+  client: reqwest::Client,
+  connected: bool,
+  /// Timeout for each HTTP round-trip
+  timeout: Duration,
+}
+
+impl HttpTransport {
+  /// Default timeout for each request (30 seconds)
+  pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+  /// Create a transport for the given endpoint URL
+  pub fn new(url: impl Into<String>) -> Self {
+    Self {
+      url: url.into(),
+      headers: HashMap::new(),
+      session_id: Mutex::new(None),
+      client: reqwest::Client::new(),
+      connected: false,
+      timeout: Duration::from_millis(Self::DEFAULT_TIMEOUT_MS),
+    }
+  }
+
+  /// Send an extra header with every request
+  pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.headers.insert(name.into(), value.into());
+    self
+  }
+
+  /// Use a preconfigured HTTP client (proxy settings, TLS roots, ...)
+  pub fn with_client(mut self, client: reqwest::Client) -> Self {
+    self.client = client;
+    self
+  }
+
+  /// Endpoint URL this transport posts to
+  pub fn url(&self) -> &str {
+    &self.url
+  }
+
+  async fn post(&self, message: &Value) -> MCPResult<reqwest::Response> {
+    if !self.connected {
+      return Err(MCPError::connection("HTTP transport is not connected"));
+    }
+
+    let mut request = self
+      .client
+      .post(&self.url)
+      .timeout(self.timeout)
+      .header(
+        reqwest::header::ACCEPT,
+        "application/json, text/event-stream",
+      )
+      .json(message);
+    for (name, value) in &self.headers {
+      request = request.header(name, value);
+    }
+    if let Some(session_id) = self.session_id.lock().ok().and_then(|id| id.clone()) {
+      request = request.header(SESSION_HEADER, session_id);
+    }
+
+    let response = request.send().await.map_err(|e| {
+      if e.is_timeout() {
+        MCPError::timeout(
+          format!("HTTP request to {} timed out", self.url),
+          Some(self.timeout.as_millis() as u64),
+        )
+      } else {
+        MCPError::transport(format!("HTTP request to {} failed: {}", self.url, e))
+      }
+    })?;
+
+    if let Some(session_id) = response
+      .headers()
+      .get(SESSION_HEADER)
+      .and_then(|value| value.to_str().ok())
+      && let Ok(mut slot) = self.session_id.lock()
+    {
+      *slot = Some(session_id.to_string());
+    }
+
+    let status = response.status();
+    if !status.is_success() {
+      let body = response.text().await.unwrap_or_default();
+      return Err(MCPError::transport(format!(
+        "MCP server at {} returned HTTP {}: {}",
+        self.url, status, body
+      )));
+    }
+    Ok(response)
+  }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+  async fn connect(&mut self) -> MCPResult<()> {
+    reqwest::Url::parse(&self.url)
+      .map_err(|e| MCPError::configuration(format!("Invalid MCP URL '{}': {}", self.url, e)))?;
+    self.connected = true;
+    Ok(())
+  }
+
+  async fn send_message(&self, request: Value) -> MCPResult<Value> {
+    let response = self.post(&request).await?;
+    let is_event_stream = response
+      .headers()
+      .get(reqwest::header::CONTENT_TYPE)
+      .and_then(|value| value.to_str().ok())
+      .is_some_and(|value| value.starts_with("text/event-stream"));
+    let body = response
+      .text()
+      .await
+      .map_err(|e| MCPError::transport(format!("Failed to read MCP response: {}", e)))?;
+
+    if is_event_stream {
+      return response_from_event_stream(&body, request.get("id"));
+    }
+    Ok(serde_json::from_str(&body)?)
+  }
+
+  async fn send_notification(&self, notification: Value) -> MCPResult<()> {
+    self.post(&notification).await?;
+    Ok(())
+  }
+
+  async fn receive_message(&self) -> MCPResult<Option<Value>> {
+    Ok(None)
+  }
+
+  async fn disconnect(&mut self) -> MCPResult<()> {
+    if let Some(session_id) = self.session_id.lock().ok().and_then(|mut id| id.take()) {
+      // Best effort: servers that track sessions accept a DELETE to end one.
+      let _ = self
+        .client
+        .delete(&self.url)
+        .timeout(self.timeout)
+        .header(SESSION_HEADER, session_id)
+        .send()
+        .await;
+    }
+    self.connected = false;
+    Ok(())
+  }
+
+  fn is_connected(&self) -> bool {
+    self.connected
+  }
+
+  fn transport_type(&self) -> TransportType {
+    TransportType::Http
+  }
+}
+
+impl TransportConfig for HttpTransport {
+  fn timeout_ms(&self) -> Option<u64> {
+    Some(self.timeout.as_millis() as u64)
+  }
+
+  fn set_timeout_ms(&mut self, timeout: u64) {
+    self.timeout = Duration::from_millis(timeout);
+  }
+
+  fn set_max_message_size(&mut self, _size: usize) {}
+}
+
+/// Picks the JSON-RPC response matching `id` out of an SSE body.
+fn response_from_event_stream(body: &str, id: Option<&Value>) -> MCPResult<Value> {
+  body
+    .lines()
+    .filter_map(|line| line.strip_prefix("data:"))
+    .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+    .find(|message| id.is_none() || message.get("id") == id)
+    .ok_or_else(|| MCPError::transport("MCP event stream ended without a response"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use wiremock::matchers::{header, method};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  fn transport(server: &MockServer) -> HttpTransport {
+    HttpTransport::new(format!("{}/mcp", server.uri()))
+      .with_client(reqwest::Client::builder().no_proxy().build().unwrap())
+  }
+
+  #[tokio::test]
+  async fn posts_json_and_echoes_the_session_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(header(SESSION_HEADER, "abc"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "jsonrpc": "2.0", "id": 2, "result": { "tools": [] }
+      })))
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .respond_with(
+        ResponseTemplate::new(200)
+          .insert_header(SESSION_HEADER, "abc")
+          .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": {} })),
+      )
+      .mount(&server)
+      .await;
+    let mut transport = transport(&server);
+    transport.connect().await.unwrap();
+
+    let first = transport
+      .send_message(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }))
+      .await
+      .unwrap();
+    let second = transport
+      .send_message(json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }))
+      .await
+      .unwrap();
+
+    assert_eq!(first["id"], json!(1));
+    assert_eq!(second["result"], json!({ "tools": [] }));
+  }
+
+  #[tokio::test]
+  async fn reads_the_matching_response_from_an_event_stream() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(
+        "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+         event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"ok\":true}}\n\n",
+        "text/event-stream",
+      ))
+      .mount(&server)
+      .await;
+    let mut transport = transport(&server);
+    transport.connect().await.unwrap();
+
+    let response = transport
+      .send_message(json!({ "jsonrpc": "2.0", "id": 7, "method": "tools/call" }))
+      .await
+      .unwrap();
+
+    assert_eq!(response["result"], json!({ "ok": true }));
+  }
+
+  #[tokio::test]
+  async fn http_errors_and_disconnected_sends_fail() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+      .mount(&server)
+      .await;
+    let mut transport = transport(&server);
+
+    assert!(matches!(
+      transport.send_message(json!({ "id": 1 })).await,
+      Err(MCPError::Connection { .. })
+    ));
+    transport.connect().await.unwrap();
+    let err = transport
+      .send_message(json!({ "id": 1 }))
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("HTTP 500"), "{err}");
+  }
+}
@@ -6,7 +6,7 @@
 //! # Transports
 //!
 //! - **Stdio**: Communicates with local processes via stdin/stdout
-//! - **HTTP**: Communicates with remote servers via HTTP (future)
+//! - **HTTP**: Posts JSON-RPC messages to a remote endpoint (`http` feature)
 //! - **HTTP+SSE**: HTTP with Server-Sent Events for bidirectional communication (future)
 //!
 //! # Example
@@ -26,11 +26,15 @@
 //! # }
 //! ```
 
+#[cfg(feature = "http")]
+pub mod http;
 pub mod mock;
 pub mod stdio;
 pub mod traits;
 
 // Re-export commonly used types
+#[cfg(feature = "http")]
+pub use http::HttpTransport;
 pub use mock::MockTransport;
 pub use stdio::StdioTransport;
 pub use traits::{Transport, TransportConfig, TransportType};
@@ -9,6 +9,7 @@ use crate::transport::traits::{Transport, TransportConfig, TransportType};
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
@@ -77,6 +78,8 @@ pub struct StdioTransport {
   /// to a third-party MCP server. Operators who genuinely need full
   /// parent inheritance can flip this with `with_inherit_parent_env(true)`.
   inherit_parent_env: bool,
+  /// Working directory of the spawned process; `None` keeps the parent's.
+  working_dir: Option<PathBuf>,
   /// Spawned child process — set on connect, taken on disconnect.
   /// Not accessed by send_message / receive_message, so no Mutex.
   process: Option<Child>,
@@ -150,6 +153,7 @@ impl StdioTransport {
       command,
       env: HashMap::new(),
       inherit_parent_env: false,
+      working_dir: None,
       process: None,
       writer: Arc::new(AsyncMutex::new(None)),
       inflight: Arc::new(std::sync::Mutex::new(HashMap::new())),
@@ -178,6 +182,12 @@ impl StdioTransport {
     self
   }
 
+  /// Run the MCP server process in `dir`.
+  pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.working_dir = Some(dir.into());
+    self
+  }
+
   /// Set the I/O timeout
   ///
   /// # Arguments
@@ -375,6 +385,9 @@ impl Transport for StdioTransport {
     if !self.env.is_empty() {
       cmd.envs(&self.env);
     }
+    if let Some(dir) = &self.working_dir {
+      cmd.current_dir(dir);
+    }
 
     let mut child = cmd
       .stdin(std::process::Stdio::piped())
@@ -772,6 +785,29 @@ mod tests {
     );
   }
 
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn spawn_runs_in_the_working_dir() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut transport = StdioTransport::new(vec![
+      "sh".to_string(),
+      "-c".to_string(),
+      "pwd > cwd.txt".to_string(),
+    ])
+    .with_timeout(Duration::from_secs(5))
+    .with_working_dir(dir.path());
+
+    let _ = transport.connect().await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let _ = transport.disconnect().await;
+
+    let body = std::fs::read_to_string(dir.path().join("cwd.txt")).unwrap_or_default();
+    assert_eq!(
+      std::fs::canonicalize(body.trim()).ok(),
+      std::fs::canonicalize(dir.path()).ok()
+    );
+  }
+
   // ============================================================================
   // Connection Tests
   // ============================================================================
@@ -206,11 +206,58 @@ async fn test_read_resource() {
 
   // Verify
   let content = result.first_content().unwrap();
-  assert_eq!(content.uri, "file:///test.txt");
+  assert_eq!(content.uri(), "file:///test.txt");
   assert_eq!(content.as_text(), Some("Hello, world!"));
   assert!(content.is_text());
 }
 
+#[tokio::test]
+async fn test_read_binary_resource() {
+  // A 1x1 transparent PNG
+  const PNG_BASE64: &str =
+    "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAQAAAC1HAwCAAAAC0lEQVR42mNkYAAAAAYAAjCB0C8AAAAASUVORK5CYII=";
+
+  // Setup mock transport: the image, then its caption
+  let mut transport = MockTransport::new();
+  transport.add_response(MockTransport::standard_initialize_response());
+  let contents = vec![
+    json!({ "uri": "file:///pixel.png", "mimeType": "image/png", "blob": PNG_BASE64 }),
+    json!({ "uri": "file:///pixel.txt", "mimeType": "text/plain", "text": "A single pixel" }),
+  ];
+  transport.add_response(MockTransport::resource_read_response(contents.clone()));
+  transport.add_response(MockTransport::resource_read_response(contents));
+
+  let mut client = ClientBuilder::new()
+    .with_transport(transport)
+    .build()
+    .await
+    .unwrap();
+  client.connect().await.unwrap();
+
+  // Contents come back in order
+  let result = client.read_resource("file:///pixel.png").await.unwrap();
+  assert_eq!(result.contents.len(), 2);
+  let image = &result.contents[0];
+  assert!(image.is_binary());
+  assert_eq!(image.mime_type(), Some("image/png"));
+  assert_eq!(result.contents[1].as_text(), Some("A single pixel"));
+
+  // The blob decodes to the PNG and is written out as such
+  let bytes = image.as_bytes().unwrap();
+  assert!(bytes.starts_with(b"\x89PNG\r\n\x1a\n"));
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("pixel.png");
+  image.save_to(&path).await.unwrap();
+  assert_eq!(std::fs::read(&path).unwrap(), bytes);
+
+  // Reading it as a string fails instead of mangling the image
+  let err = client
+    .read_resource_as_string("file:///pixel.png")
+    .await
+    .unwrap_err();
+  assert!(err.to_string().contains("is binary (image/png)"), "{err}");
+}
+
 #[tokio::test]
 async fn test_list_prompts() {
   // Setup mock transport
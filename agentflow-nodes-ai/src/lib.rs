@@ -14,10 +14,16 @@ pub use nodes::image_edit::ImageEditNode;
 pub use nodes::image_to_image::ImageToImageNode;
 pub use nodes::image_understand::ImageUnderstandNode;
 pub use nodes::llm::LlmNode;
+pub use nodes::llm_tool_agent::{AgentToolSource, LlmToolAgentNode};
+pub use nodes::text_splitter::{SplitStrategy, TextSplitterNode};
 pub use nodes::text_to_image::TextToImageNode;
 pub use nodes::tts::TTSNode;
 
+#[cfg(feature = "rag")]
+pub use nodes::embedding::EmbeddingNode;
 #[cfg(feature = "mcp")]
 pub use nodes::mcp::MCPNode;
+#[cfg(feature = "mcp")]
+pub use nodes::mcp_tool::{McpServerSpec, McpToolNode};
 #[cfg(feature = "rag")]
 pub use nodes::rag::RAGNode;
@@ -6,7 +6,8 @@ use agentflow_core::{
   value::FlowValue,
 };
 use agentflow_llm::{AgentFlow, AsrRequest};
-use agentflow_nodes::common::utils::{flow_value_to_string, load_bytes_from_source};
+use agentflow_nodes::common::template::{RenderOptions, render};
+use agentflow_nodes::common::utils::load_bytes_from_source;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -28,7 +29,6 @@ pub struct ASRNode {
   pub audio_source: String,
   pub response_format: ASRResponseFormat,
   pub output_key: String,
-  pub input_keys: Vec<String>,
 }
 
 impl ASRNode {
@@ -39,7 +39,6 @@ impl ASRNode {
       audio_source: audio_source.to_string(),
       response_format: Default::default(),
       output_key: format!("{}_output", name),
-      input_keys: vec![],
     }
   }
 }
@@ -55,13 +54,7 @@ impl AsyncNode for ASRNode {
         message: format!("Failed to initialize AgentFlow LLM service: {}", e),
       })?;
 
-    let mut resolved_source = self.audio_source.clone();
-    for key in &self.input_keys {
-      if let Some(value) = inputs.get(key) {
-        let placeholder = format!("{{{{{}}}}}", key);
-        resolved_source = resolved_source.replace(&placeholder, &flow_value_to_string(value));
-      }
-    }
+    let resolved_source = render(&self.audio_source, inputs, &RenderOptions::lenient())?;
 
     let audio_data = load_bytes_from_source(&resolved_source, inputs).await?;
 
@@ -137,7 +130,6 @@ mod tests {
       audio_source: "test.wav".to_string(),
       response_format: ASRResponseFormat::Text,
       output_key: "transcript_output".to_string(),
-      input_keys: vec!["test.wav".to_string()],
     };
 
     let mut inputs = AsyncNodeInputs::new();
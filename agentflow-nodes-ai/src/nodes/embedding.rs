@@ -0,0 +1,281 @@
+//! Embedding node - turns texts into vectors through an `agentflow-rag`
+//! [`EmbeddingProvider`].
+//!
+//! The `texts` input (overridable with `texts_key`) is a string or an array
+//! of strings; an optional `ids` input names each text. Texts are sent in
+//! batches of at most `batch_size`, and the node outputs:
+//!
+//! - `vectors`: one number array per text, in input order;
+//! - `records`: `[{id, text, vector}]`, the corpus shape `vector_search`
+//!   consumes;
+//! - `dimension` and `model`.
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_rag::embeddings::{EmbeddingProvider, OpenAIEmbedding};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+#[derive(Clone)]
+pub struct EmbeddingNode {
+  pub name: String,
+  pub model: String,
+  pub texts_key: String,
+  pub batch_size: usize,
+  provider: Option<Arc<dyn EmbeddingProvider>>,
+}
+
+impl fmt::Debug for EmbeddingNode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EmbeddingNode")
+      .field("name", &self.name)
+      .field("model", &self.model)
+      .field("texts_key", &self.texts_key)
+      .field("batch_size", &self.batch_size)
+      .finish_non_exhaustive()
+  }
+}
+
+impl EmbeddingNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      model: DEFAULT_EMBEDDING_MODEL.to_string(),
+      texts_key: "texts".to_string(),
+      batch_size: DEFAULT_BATCH_SIZE,
+      provider: None,
+    }
+  }
+
+  pub fn with_model(mut self, model: &str) -> Self {
+    self.model = model.to_string();
+    self
+  }
+
+  pub fn with_texts_key(mut self, key: &str) -> Self {
+    self.texts_key = key.to_string();
+    self
+  }
+
+  /// Maximum texts per provider call (at least 1).
+  pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  /// Use `provider` instead of an OpenAI client for `model`.
+  pub fn with_provider(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+    self.provider = Some(provider);
+    self
+  }
+
+  fn texts(&self, inputs: &AsyncNodeInputs) -> Result<Vec<String>, AgentFlowError> {
+    match inputs.get(&self.texts_key) {
+      Some(FlowValue::Json(Value::String(text))) => Ok(vec![text.clone()]),
+      Some(FlowValue::Json(Value::Array(items))) => items
+        .iter()
+        .map(|item| match item {
+          Value::String(text) => Ok(text.clone()),
+          _ => Err(AgentFlowError::NodeInputError {
+            message: format!("Input '{}' must contain only strings", self.texts_key),
+          }),
+        })
+        .collect(),
+      Some(_) => Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "Input '{}' must be a string or an array of strings",
+          self.texts_key
+        ),
+      }),
+      None => Err(AgentFlowError::NodeInputError {
+        message: format!("Missing required input '{}'", self.texts_key),
+      }),
+    }
+  }
+
+  fn ids(&self, inputs: &AsyncNodeInputs, count: usize) -> Result<Vec<String>, AgentFlowError> {
+    let Some(FlowValue::Json(Value::Array(ids))) = inputs.get("ids") else {
+      return Ok((0..count).map(|i| i.to_string()).collect());
+    };
+    if ids.len() != count {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "'ids' has {} entries but there are {} texts",
+          ids.len(),
+          count
+        ),
+      });
+    }
+    Ok(
+      ids
+        .iter()
+        .map(|id| match id {
+          Value::String(id) => id.clone(),
+          other => other.to_string(),
+        })
+        .collect(),
+    )
+  }
+
+  /// Embeds `texts` in `batch_size` chunks, preserving order.
+  pub async fn embed_all(
+    &self,
+    provider: &dyn EmbeddingProvider,
+    texts: &[String],
+  ) -> Result<Vec<Vec<f32>>, AgentFlowError> {
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(self.batch_size.max(1)) {
+      let embedded = provider
+        .embed_batch(batch.iter().map(String::as_str).collect())
+        .await
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!("Embedding node '{}': embedding failed: {}", self.name, e),
+        })?;
+      if embedded.len() != batch.len() {
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Embedding node '{}': provider returned {} vectors for {} texts",
+            self.name,
+            embedded.len(),
+            batch.len()
+          ),
+        });
+      }
+      vectors.extend(embedded);
+    }
+    Ok(vectors)
+  }
+}
+
+#[async_trait]
+impl AsyncNode for EmbeddingNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let texts = self.texts(inputs)?;
+    let ids = self.ids(inputs, texts.len())?;
+
+    let provider: Arc<dyn EmbeddingProvider> = match &self.provider {
+      Some(provider) => provider.clone(),
+      None => Arc::new(OpenAIEmbedding::new(&self.model).map_err(|e| {
+        AgentFlowError::ConfigurationError {
+          message: format!(
+            "Embedding node '{}': failed to create embedding client for '{}': {}",
+            self.name, self.model, e
+          ),
+        }
+      })?),
+    };
+
+    println!(
+      "🧮 Embedding {} text(s) with '{}' (batches of {})",
+      texts.len(),
+      provider.model_name(),
+      self.batch_size
+    );
+    let vectors = self.embed_all(provider.as_ref(), &texts).await?;
+
+    let records: Vec<Value> = ids
+      .iter()
+      .zip(&texts)
+      .zip(&vectors)
+      .map(|((id, text), vector)| json!({ "id": id, "text": text, "vector": vector }))
+      .collect();
+    let dimension = vectors.first().map_or(provider.dimension(), Vec::len);
+
+    let mut outputs = HashMap::new();
+    outputs.insert("vectors".to_string(), FlowValue::Json(json!(vectors)));
+    outputs.insert(
+      "records".to_string(),
+      FlowValue::Json(Value::Array(records)),
+    );
+    outputs.insert("dimension".to_string(), FlowValue::Json(json!(dimension)));
+    outputs.insert(
+      "model".to_string(),
+      FlowValue::Json(Value::String(provider.model_name().to_string())),
+    );
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+
+  /// Records each batch and embeds a text as `[len, 1.0]`.
+  #[derive(Default)]
+  struct RecordingProvider {
+    batches: Mutex<Vec<usize>>,
+  }
+
+  #[async_trait]
+  impl EmbeddingProvider for RecordingProvider {
+    async fn embed_text(&self, text: &str) -> agentflow_rag::Result<Vec<f32>> {
+      Ok(vec![text.len() as f32, 1.0])
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> agentflow_rag::Result<Vec<Vec<f32>>> {
+      self.batches.lock().unwrap().push(texts.len());
+      Ok(texts.iter().map(|t| vec![t.len() as f32, 1.0]).collect())
+    }
+
+    fn dimension(&self) -> usize {
+      2
+    }
+
+    fn model_name(&self) -> &str {
+      "recording"
+    }
+  }
+
+  #[tokio::test]
+  async fn embeds_in_batches_and_keeps_input_order() {
+    let provider = Arc::new(RecordingProvider::default());
+    let node = EmbeddingNode::new("embed")
+      .with_batch_size(2)
+      .with_provider(provider.clone());
+
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "texts".to_string(),
+      FlowValue::Json(json!(["a", "bb", "ccc", "dddd", "eeeee"])),
+    );
+    inputs.insert(
+      "ids".to_string(),
+      FlowValue::Json(json!(["x1", "x2", "x3", "x4", "x5"])),
+    );
+    let outputs = node.execute(&inputs).await.unwrap();
+
+    assert_eq!(*provider.batches.lock().unwrap(), vec![2, 2, 1]);
+    let Some(FlowValue::Json(vectors)) = outputs.get("vectors") else {
+      panic!("missing vectors");
+    };
+    assert_eq!(vectors[4], json!([5.0, 1.0]));
+    let Some(FlowValue::Json(records)) = outputs.get("records") else {
+      panic!("missing records");
+    };
+    assert_eq!(
+      records[2],
+      json!({ "id": "x3", "text": "ccc", "vector": [3.0, 1.0] })
+    );
+    assert!(matches!(outputs.get("dimension"), Some(FlowValue::Json(d)) if d == &json!(2)));
+  }
+
+  #[tokio::test]
+  async fn rejects_mismatched_ids() {
+    let node = EmbeddingNode::new("embed").with_provider(Arc::new(RecordingProvider::default()));
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("texts".to_string(), FlowValue::Json(json!(["a", "b"])));
+    inputs.insert("ids".to_string(), FlowValue::Json(json!(["only-one"])));
+    let err = node.execute(&inputs).await.unwrap_err();
+    assert!(err.to_string().contains("'ids'"), "{err}");
+  }
+}
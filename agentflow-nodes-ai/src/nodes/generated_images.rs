@@ -0,0 +1,314 @@
+//! Output handling shared by the image-generation nodes
+//! ([`TextToImageNode`](super::text_to_image::TextToImageNode) and
+//! [`ImageEditNode`](super::image_edit::ImageEditNode)).
+//!
+//! Both receive a provider-neutral [`ImageGenerationResponse`] and turn it
+//! into the same output shape: optional files on disk plus per-image
+//! `image_paths` / `seeds` / `finish_reasons` lists. Entries the vendor
+//! reports as `content_filtered` either fail the node or, with
+//! `allow_filtered`, become structured `warnings`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use agentflow_core::{async_node::AsyncNodeInputs, error::AgentFlowError, value::FlowValue};
+use agentflow_llm::providers::modality::{GeneratedImage, ImageGenerationResponse};
+use agentflow_nodes::common::utils::load_bytes_from_source;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde_json::{Value, json};
+
+/// Finish reason vendors use for images withheld by their safety filter.
+pub(crate) const CONTENT_FILTERED: &str = "content_filtered";
+
+/// A provider injected in place of the modality dispatcher's choice.
+///
+/// Wraps the trait object so nodes can keep `#[derive(Debug, Clone)]`.
+pub(crate) struct ProviderOverride<P: ?Sized>(pub Arc<P>);
+
+impl<P: ?Sized> Clone for ProviderOverride<P> {
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+impl<P: ?Sized> fmt::Debug for ProviderOverride<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("ProviderOverride(..)")
+  }
+}
+
+/// How one node turns a response into outputs.
+pub(crate) struct ImageOutputOptions<'a> {
+  pub node: &'a str,
+  /// Key holding the first delivered image as a data URI or URL.
+  pub output_key: &'a str,
+  /// File pattern with `{name}`, `{index}`, `{seed}` and `{ext}`
+  /// placeholders. `None` keeps images in memory only.
+  pub output_path: Option<&'a str>,
+  pub allow_filtered: bool,
+}
+
+/// Renders `pattern` for image `index`; `{seed}` renders as `none` for an
+/// image the vendor reported no seed for. When the response holds several
+/// images and the pattern has no `{index}`, and no `{seed}` with a seed to
+/// put in it, the index is appended to the file stem so the files don't
+/// overwrite each other.
+pub(crate) fn render_output_path(
+  pattern: &str,
+  name: &str,
+  index: usize,
+  total: usize,
+  seed: Option<i32>,
+  ext: &str,
+) -> PathBuf {
+  let distinct = pattern.contains("{index}") || (pattern.contains("{seed}") && seed.is_some());
+  let seed = seed
+    .map(|s| s.to_string())
+    .unwrap_or_else(|| "none".to_string());
+  let rendered = pattern
+    .replace("{name}", name)
+    .replace("{index}", &index.to_string())
+    .replace("{seed}", &seed)
+    .replace("{ext}", ext);
+  let path = PathBuf::from(rendered);
+  if total > 1 && !distinct {
+    with_index_suffix(&path, index)
+  } else {
+    path
+  }
+}
+
+/// `path` with `_{index}` appended to its file stem.
+fn with_index_suffix(path: &Path, index: usize) -> PathBuf {
+  let stem = path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  let file_name = match path.extension() {
+    Some(extension) => format!("{stem}_{index}.{}", extension.to_string_lossy()),
+    None => format!("{stem}_{index}"),
+  };
+  path.with_file_name(file_name)
+}
+
+async fn image_bytes(
+  node: &str,
+  index: usize,
+  image: &GeneratedImage,
+) -> Result<Vec<u8>, AgentFlowError> {
+  if let Some(b64) = &image.b64_json {
+    return STANDARD
+      .decode(b64)
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Node '{node}': image {index} has invalid base64 data: {e}"),
+      });
+  }
+  match &image.url {
+    Some(url) => load_bytes_from_source(url, &AsyncNodeInputs::new()).await,
+    None => Err(AgentFlowError::AsyncExecutionError {
+      message: format!("Node '{node}': image {index} has no image data"),
+    }),
+  }
+}
+
+/// Builds the node outputs for `response`, writing files when
+/// `options.output_path` is set.
+pub(crate) async fn collect_outputs(
+  options: &ImageOutputOptions<'_>,
+  response: &ImageGenerationResponse,
+) -> Result<HashMap<String, FlowValue>, AgentFlowError> {
+  if response.images.is_empty() {
+    return Err(AgentFlowError::AsyncExecutionError {
+      message: format!("Node '{}': provider returned no images", options.node),
+    });
+  }
+
+  let total = response.images.len();
+  let mut primary = None;
+  let mut image_paths = Vec::new();
+  let mut seeds = Vec::new();
+  let mut finish_reasons = Vec::new();
+  let mut warnings = Vec::new();
+  let mut written = HashSet::new();
+
+  for (index, image) in response.images.iter().enumerate() {
+    let finish_reason = image.finish_reason.as_deref().unwrap_or("success");
+    seeds.push(image.seed.map_or(Value::Null, Value::from));
+    finish_reasons.push(Value::String(finish_reason.to_string()));
+
+    if finish_reason == CONTENT_FILTERED {
+      if !options.allow_filtered {
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Node '{}': image {} was withheld by the provider ({CONTENT_FILTERED}); \
+             set allow_filtered: true to continue without it",
+            options.node, index
+          ),
+        });
+      }
+      eprintln!(
+        "⚠️  Node '{}': image {} was {CONTENT_FILTERED}",
+        options.node, index
+      );
+      warnings.push(json!({
+        "index": index,
+        "finish_reason": finish_reason,
+        "message": "image withheld by the provider's content filter",
+      }));
+      continue;
+    }
+
+    let Some(pattern) = options.output_path else {
+      if primary.is_none() {
+        primary = match (&image.b64_json, &image.url) {
+          (Some(b64), _) => {
+            let mime = STANDARD
+              .decode(b64)
+              .ok()
+              .and_then(|bytes| image::guess_format(&bytes).ok())
+              .map_or("image/png", |format| format.to_mime_type());
+            Some(format!("data:{mime};base64,{b64}"))
+          }
+          (None, Some(url)) => Some(url.clone()),
+          (None, None) => None,
+        };
+      }
+      continue;
+    };
+
+    let bytes = image_bytes(options.node, index, image).await?;
+    let ext = image::guess_format(&bytes)
+      .ok()
+      .and_then(|format| format.extensions_str().first().copied())
+      .unwrap_or("png");
+    let mut path = render_output_path(pattern, options.node, index, total, image.seed, ext);
+    // Two images with the same seed render the same `{seed}` path.
+    if written.contains(&path) {
+      path = with_index_suffix(&path, index);
+    }
+    if !written.insert(path.clone()) {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Node '{}': image {} would overwrite {:?}; add {{index}} to output_path",
+          options.node, index, path
+        ),
+      });
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+      tokio::fs::create_dir_all(parent)
+        .await
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Node '{}': failed to create {:?}: {}",
+            options.node, parent, e
+          ),
+        })?;
+    }
+    tokio::fs::write(&path, &bytes)
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Node '{}': failed to write {:?}: {}", options.node, path, e),
+      })?;
+    println!("💾 Saved image {} to {}", index, path.display());
+
+    if primary.is_none() {
+      primary = Some(match &image.url {
+        Some(url) if image.b64_json.is_none() => url.clone(),
+        _ => format!(
+          "data:{};base64,{}",
+          image::guess_format(&bytes).map_or("image/png", |format| format.to_mime_type()),
+          STANDARD.encode(&bytes)
+        ),
+      });
+    }
+    image_paths.push(Value::String(path.to_string_lossy().into_owned()));
+  }
+
+  let mut outputs = HashMap::new();
+  outputs.insert(
+    options.output_key.to_string(),
+    FlowValue::Json(primary.map_or(Value::Null, Value::String)),
+  );
+  outputs.insert(
+    "image_paths".to_string(),
+    FlowValue::Json(Value::Array(image_paths)),
+  );
+  outputs.insert("seeds".to_string(), FlowValue::Json(Value::Array(seeds)));
+  outputs.insert(
+    "finish_reasons".to_string(),
+    FlowValue::Json(Value::Array(finish_reasons)),
+  );
+  if !warnings.is_empty() {
+    outputs.insert(
+      "warnings".to_string(),
+      FlowValue::Json(Value::Array(warnings)),
+    );
+  }
+  Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn output_path_placeholders_and_index_suffix() {
+    assert_eq!(
+      render_output_path("out/{name}-{seed}.{ext}", "cover", 0, 1, Some(42), "png"),
+      PathBuf::from("out/cover-42.png")
+    );
+    assert_eq!(
+      render_output_path("out/{name}.{ext}", "cover", 1, 2, None, "jpg"),
+      PathBuf::from("out/cover_1.jpg")
+    );
+    assert_eq!(
+      render_output_path("out/{name}_{index}.png", "cover", 1, 2, None, "jpg"),
+      PathBuf::from("out/cover_1.png")
+    );
+    assert_eq!(
+      render_output_path("out/{name}-{seed}.{ext}", "cover", 1, 2, Some(7), "png"),
+      PathBuf::from("out/cover-7.png")
+    );
+    // Without a seed, `{seed}` cannot tell the images apart.
+    assert_eq!(
+      render_output_path("out/{name}-{seed}.{ext}", "cover", 1, 2, None, "png"),
+      PathBuf::from("out/cover-none_1.png")
+    );
+  }
+
+  #[tokio::test]
+  async fn images_sharing_a_seed_are_not_overwritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let pattern = format!("{}/{{name}}-{{seed}}.{{ext}}", dir.path().display());
+    let image = GeneratedImage {
+      url: None,
+      b64_json: Some(STANDARD.encode(b"not a png")),
+      seed: Some(42),
+      finish_reason: None,
+    };
+    let response = ImageGenerationResponse {
+      created: 0,
+      images: vec![image.clone(), image],
+      metadata: None,
+    };
+    let options = ImageOutputOptions {
+      node: "cover",
+      output_key: "cover_image",
+      output_path: Some(&pattern),
+      allow_filtered: false,
+    };
+
+    let outputs = collect_outputs(&options, &response).await.unwrap();
+
+    let Some(FlowValue::Json(Value::Array(paths))) = outputs.get("image_paths") else {
+      panic!("no image_paths in {outputs:?}");
+    };
+    let paths: Vec<&str> = paths.iter().filter_map(Value::as_str).collect();
+    assert_eq!(paths.len(), 2);
+    assert!(paths[0].ends_with("cover-42.png"), "{paths:?}");
+    assert!(paths[1].ends_with("cover-42_1.png"), "{paths:?}");
+    assert!(Path::new(paths[1]).exists());
+  }
+}
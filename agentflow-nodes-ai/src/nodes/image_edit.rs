@@ -3,14 +3,18 @@
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
-  value::FlowValue,
 };
-use agentflow_llm::{AgentFlow, providers::modality::ImageEditRequest as ModalityImageEditRequest};
-use agentflow_nodes::common::utils::{flow_value_to_string, load_bytes_from_source};
+use agentflow_llm::{
+  AgentFlow,
+  providers::modality::{ImageEditProvider, ImageEditRequest as ModalityImageEditRequest},
+};
+use agentflow_nodes::common::template::{RenderOptions, render};
+use agentflow_nodes::common::utils::load_bytes_from_source;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::generated_images::{ImageOutputOptions, ProviderOverride, collect_outputs};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageEditNode {
@@ -24,7 +28,13 @@ pub struct ImageEditNode {
   pub steps: Option<u32>,
   pub cfg_scale: Option<f32>,
   pub output_key: String,
-  pub input_keys: Vec<String>,
+  /// File pattern for saving edited images (`{name}`, `{index}`, `{seed}`,
+  /// `{ext}`); the saved paths are output as `image_paths`.
+  pub output_path: Option<String>,
+  /// Report `content_filtered` images as `warnings` instead of failing.
+  pub allow_filtered: bool,
+  #[serde(skip)]
+  provider: Option<ProviderOverride<dyn ImageEditProvider>>,
 }
 
 impl ImageEditNode {
@@ -40,9 +50,52 @@ impl ImageEditNode {
       steps: None,
       cfg_scale: None,
       output_key: format!("{}_output", name),
-      input_keys: vec![],
+      output_path: None,
+      allow_filtered: false,
+      provider: None,
     }
   }
+
+  pub fn with_size(mut self, size: &str) -> Self {
+    self.size = Some(size.to_string());
+    self
+  }
+
+  pub fn with_response_format(mut self, format: &str) -> Self {
+    self.response_format = Some(format.to_string());
+    self
+  }
+
+  pub fn with_seed(mut self, seed: i32) -> Self {
+    self.seed = Some(seed);
+    self
+  }
+
+  pub fn with_steps(mut self, steps: u32) -> Self {
+    self.steps = Some(steps);
+    self
+  }
+
+  pub fn with_cfg_scale(mut self, scale: f32) -> Self {
+    self.cfg_scale = Some(scale);
+    self
+  }
+
+  pub fn with_output_path(mut self, pattern: &str) -> Self {
+    self.output_path = Some(pattern.to_string());
+    self
+  }
+
+  pub fn with_allow_filtered(mut self, allow: bool) -> Self {
+    self.allow_filtered = allow;
+    self
+  }
+
+  /// Use `provider` instead of resolving one from the model registry.
+  pub fn with_provider(mut self, provider: Arc<dyn ImageEditProvider>) -> Self {
+    self.provider = Some(ProviderOverride(provider));
+    self
+  }
 }
 
 #[async_trait]
@@ -50,26 +103,23 @@ impl AsyncNode for ImageEditNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
     println!("🎨 Executing ImageEditNode: {}", self.name);
 
-    let mut resolved_prompt = self.prompt.clone();
-    for key in &self.input_keys {
-      if let Some(value) = inputs.get(key) {
-        let placeholder = format!("{{{{{}}}}}", key);
-        resolved_prompt = resolved_prompt.replace(&placeholder, &flow_value_to_string(value));
-      }
-    }
+    let resolved_prompt = render(&self.prompt, inputs, &RenderOptions::lenient())?;
 
     let image_data = load_bytes_from_source(&self.image_source, inputs).await?;
 
     // P-LLM.3: route through the modality dispatcher.
-    let provider =
-      AgentFlow::image_edit(&self.model)
+    let provider: Arc<dyn ImageEditProvider> = match &self.provider {
+      Some(ProviderOverride(provider)) => provider.clone(),
+      None => AgentFlow::image_edit(&self.model)
         .await
         .map_err(|e| AgentFlowError::ConfigurationError {
           message: format!(
             "Failed to resolve image-edit provider for '{}': {}",
             self.model, e
           ),
-        })?;
+        })?
+        .into(),
+    };
 
     let request = ModalityImageEditRequest {
       model: self.model.clone(),
@@ -92,37 +142,91 @@ impl AsyncNode for ImageEditNode {
           message: format!("Image edit failed: {}", e),
         })?;
 
-    let first_image =
-      response
-        .images
-        .first()
-        .ok_or_else(|| AgentFlowError::AsyncExecutionError {
-          message: "No images returned in response".to_string(),
-        })?;
-    let output_data = if let Some(b64) = &first_image.b64_json {
-      format!("data:image/png;base64,{}", b64)
-    } else if let Some(url) = &first_image.url {
-      url.clone()
-    } else {
-      return Err(AgentFlowError::AsyncExecutionError {
-        message: "No image data in response".to_string(),
-      });
-    };
-
     println!("✅ ImageEditNode execution successful.");
-    let mut outputs = HashMap::new();
-    outputs.insert(
-      self.output_key.clone(),
-      FlowValue::Json(Value::String(output_data)),
-    );
-
-    Ok(outputs)
+    collect_outputs(
+      &ImageOutputOptions {
+        node: &self.name,
+        output_key: &self.output_key,
+        output_path: self.output_path.as_deref(),
+        allow_filtered: self.allow_filtered,
+      },
+      &response,
+    )
+    .await
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use agentflow_core::value::FlowValue;
+  use serde_json::Value;
+
+  #[tokio::test]
+  async fn sends_multipart_edit_and_saves_result() {
+    use agentflow_llm::providers::stepfun::StepFunSpecializedClient;
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use serde_json::json;
+    use wiremock::matchers::{header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mut png = Vec::new();
+    image::RgbImage::new(2, 2)
+      .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+      .unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/images/edits"))
+      .and(header_regex("content-type", "^multipart/form-data"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "created": 1,
+        "data": [{ "finish_reason": "success", "seed": 7, "b64_json": STANDARD.encode(&png) }],
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let client = StepFunSpecializedClient::new("test-key", Some(server.uri())).unwrap();
+    let node = ImageEditNode::new("sky", "step-1x-edit", "add a {{ colour }} sky", "photo")
+      .with_seed(7)
+      .with_steps(28)
+      .with_output_path(&format!("{}/edited.{{ext}}", dir.path().display()))
+      .with_provider(Arc::new(client));
+
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "photo".to_string(),
+      FlowValue::Json(Value::String(format!(
+        "data:image/png;base64,{}",
+        STANDARD.encode(&png)
+      ))),
+    );
+    inputs.insert(
+      "colour".to_string(),
+      FlowValue::Json(Value::String("blue".to_string())),
+    );
+    let outputs = node.execute(&inputs).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body = String::from_utf8_lossy(&requests[0].body);
+    for field in [
+      "name=\"model\"\r\n\r\nstep-1x-edit",
+      "name=\"prompt\"\r\n\r\nadd a blue sky",
+      "name=\"seed\"\r\n\r\n7",
+      "name=\"steps\"\r\n\r\n28",
+      "name=\"image\"; filename=\"photo\"",
+    ] {
+      assert!(body.contains(field), "missing {field:?} in {body}");
+    }
+
+    let saved = dir.path().join("edited.png");
+    assert!(saved.exists());
+    assert!(matches!(
+      outputs.get("seeds"),
+      Some(FlowValue::Json(seeds)) if seeds == &json!([7])
+    ));
+  }
 
   #[tokio::test]
   #[ignore] // Requires STEPFUN_API_KEY environment variable
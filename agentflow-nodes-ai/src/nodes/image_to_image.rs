@@ -8,7 +8,8 @@ use agentflow_core::{
 use agentflow_llm::{
   AgentFlow, providers::modality::Image2ImageRequest as ModalityImage2ImageRequest,
 };
-use agentflow_nodes::common::utils::{flow_value_to_string, load_data_uri_from_source};
+use agentflow_nodes::common::template::{RenderOptions, render};
+use agentflow_nodes::common::utils::load_data_uri_from_source;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -28,7 +29,6 @@ pub struct ImageToImageNode {
   pub steps: Option<u32>,
   pub cfg_scale: Option<f32>,
   pub output_key: String,
-  pub input_keys: Vec<String>,
 }
 
 impl ImageToImageNode {
@@ -46,7 +46,6 @@ impl ImageToImageNode {
       steps: None,
       cfg_scale: None,
       output_key: format!("{}_output", name),
-      input_keys: vec![],
     }
   }
 }
@@ -56,13 +55,7 @@ impl AsyncNode for ImageToImageNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
     println!("🎨 Executing ImageToImageNode: {}", self.name);
 
-    let mut resolved_prompt = self.prompt.clone();
-    for key in &self.input_keys {
-      if let Some(value) = inputs.get(key) {
-        let placeholder = format!("{{{{{}}}}}", key);
-        resolved_prompt = resolved_prompt.replace(&placeholder, &flow_value_to_string(value));
-      }
-    }
+    let resolved_prompt = render(&self.prompt, inputs, &RenderOptions::lenient())?;
 
     let source_url = load_data_uri_from_source(&self.source_image, inputs).await?;
 
@@ -1,28 +1,74 @@
-//! ImageUnderstand Node - Specialized node for multimodal image understanding using vision models
+//! ImageUnderstand Node - multimodal image understanding using vision models.
+//!
+//! Sends a `{{ key }}`-rendered prompt plus one or more images as a single
+//! user turn and outputs the model's `response` and token `usage`. Images
+//! come from the configured sources, each of which may name an input (a
+//! path, `http(s)`/`data:` URL, `File`/`Url` flow value, or a list of those)
+//! or be a literal path / URL.
+//!
+//! Local files and `data:` URIs are sent inline as base64, with the MIME type
+//! sniffed from the image bytes. An image larger than `max_image_bytes` is
+//! re-encoded as JPEG and, if that is still too large, downscaled until it
+//! fits. Remote URLs are passed to the provider untouched.
+//!
+//! Local files, images and uploads alike, are only read from under the
+//! node's `base_dir`: relative paths resolve against it, and a path that
+//! lands outside it once symlinks are resolved is refused. Without a base
+//! directory no local file is read, so an input cannot send an arbitrary
+//! file to the provider.
+//!
+//! Documents, video and audio too large to inline go in `files` and are
+//! sent to Gemini as `file_data` parts. A local path or `File` flow value is
+//! uploaded through the Gemini Files API with the model's credentials and
+//! deleted once the model has answered; a `Url` flow value, an `http(s)`
+//! URI or a `FileHandle` object (`uri` and `mimeType`) from an earlier
+//! upload is referenced as is. With `files` set, `images` may be empty.
 
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
   value::FlowValue,
 };
-use agentflow_llm::{AgentFlow, multimodal::MultimodalMessage};
-use agentflow_nodes::common::utils::{flow_value_to_string, load_data_uri_from_source};
+use agentflow_llm::{
+  AgentFlow, GoogleFilesClient, multimodal::MultimodalMessage,
+  providers::google_files::mime_type_for_path,
+};
+use agentflow_nodes::common::template::{RenderOptions, render};
 use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Inline image budget applied when none is configured (4 MiB).
+pub const DEFAULT_MAX_IMAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// JPEG quality used when an oversized image is re-encoded.
+const JPEG_QUALITY: u8 = 85;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageUnderstandNode {
   pub name: String,
   pub model: String,
   pub text_prompt: String,
-  pub image_source: String,
+  /// Image sources, sent in this order.
+  pub images: Vec<String>,
+  /// Document / video / audio sources, sent after the images as Gemini
+  /// `file_data` parts.
+  #[serde(default)]
+  pub files: Vec<String>,
   pub system_message: Option<String>,
   pub temperature: Option<f32>,
   pub max_tokens: Option<u32>,
+  /// Largest inline image, in bytes, before it is re-encoded / downscaled.
+  pub max_image_bytes: usize,
+  /// The directory local files are read from; `None` reads none.
+  #[serde(default)]
+  pub base_dir: Option<PathBuf>,
+  /// Legacy output key (`<name>_output`) that mirrors `response`.
   pub output_key: String,
-  pub input_keys: Vec<String>,
 }
 
 impl ImageUnderstandNode {
@@ -31,14 +77,404 @@ impl ImageUnderstandNode {
       name: name.to_string(),
       model: model.to_string(),
       text_prompt: text_prompt.to_string(),
-      image_source: image_source.to_string(),
+      images: vec![image_source.to_string()],
+      files: Vec::new(),
       system_message: None,
       temperature: None,
       max_tokens: None,
+      max_image_bytes: DEFAULT_MAX_IMAGE_BYTES,
+      base_dir: None,
       output_key: format!("{}_output", name),
-      input_keys: vec![],
     }
   }
+
+  /// Replace the image sources.
+  pub fn with_images(mut self, images: Vec<String>) -> Self {
+    self.images = images;
+    self
+  }
+
+  /// Replace the file sources.
+  pub fn with_files(mut self, files: Vec<String>) -> Self {
+    self.files = files;
+    self
+  }
+
+  pub fn with_system_message(mut self, system_message: &str) -> Self {
+    self.system_message = Some(system_message.to_string());
+    self
+  }
+
+  pub fn with_temperature(mut self, temperature: f32) -> Self {
+    self.temperature = Some(temperature);
+    self
+  }
+
+  pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+    self.max_tokens = Some(max_tokens);
+    self
+  }
+
+  pub fn with_max_image_bytes(mut self, max_image_bytes: usize) -> Self {
+    self.max_image_bytes = max_image_bytes;
+    self
+  }
+
+  /// Allow local images and uploads from under `base_dir`, which relative
+  /// paths resolve against.
+  pub fn with_base_dir(mut self, base_dir: impl Into<PathBuf>) -> Self {
+    self.base_dir = Some(base_dir.into());
+    self
+  }
+
+  /// `source` resolved against the base directory and canonicalized,
+  /// refused when it lands outside it or no base directory is set.
+  fn local_path(&self, source: &Path) -> Result<PathBuf, AgentFlowError> {
+    let Some(base_dir) = &self.base_dir else {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "ImageUnderstandNode '{}' has no base_dir, so it cannot read the local file '{}'",
+          self.name,
+          source.display()
+        ),
+      });
+    };
+    let base = base_dir
+      .canonicalize()
+      .map_err(|e| AgentFlowError::ConfigurationError {
+        message: format!(
+          "Base directory '{}' is not usable: {}",
+          base_dir.display(),
+          e
+        ),
+      })?;
+    let path = base
+      .join(source)
+      .canonicalize()
+      .map_err(|e| AgentFlowError::NodeInputError {
+        message: format!("Failed to read {}: {}", source.display(), e),
+      })?;
+    if !path.starts_with(&base) {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "Refusing '{}': it escapes the base directory '{}'",
+          source.display(),
+          base_dir.display()
+        ),
+      });
+    }
+    Ok(path)
+  }
+
+  /// Resolves every configured source into an image URL (remote or `data:`).
+  async fn image_urls(&self, inputs: &AsyncNodeInputs) -> Result<Vec<String>, AgentFlowError> {
+    let mut urls = Vec::new();
+    for source in &self.images {
+      match inputs.get(source) {
+        Some(FlowValue::Json(Value::Array(items))) => {
+          for item in items {
+            let Value::String(item) = item else {
+              return Err(AgentFlowError::NodeInputError {
+                message: format!("Image input '{}' must be a list of strings", source),
+              });
+            };
+            urls.push(self.image_url(item).await?);
+          }
+        }
+        Some(FlowValue::Json(Value::String(item))) => urls.push(self.image_url(item).await?),
+        Some(FlowValue::File { path, .. }) => {
+          urls.push(self.image_url(&path.to_string_lossy()).await?)
+        }
+        Some(FlowValue::Url { url, .. }) => urls.push(url.clone()),
+        Some(FlowValue::Json(other)) => {
+          return Err(AgentFlowError::NodeInputError {
+            message: format!(
+              "Image input '{}' must be a path, URL or data URI, got {}",
+              source, other
+            ),
+          });
+        }
+        None => urls.push(self.image_url(source).await?),
+      }
+    }
+    Ok(urls)
+  }
+
+  async fn image_url(&self, source: &str) -> Result<String, AgentFlowError> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+      return Ok(source.to_string());
+    }
+    let (bytes, mime_hint) = match source.strip_prefix("data:") {
+      Some(data) => {
+        let (mime, encoded) =
+          data
+            .split_once(";base64,")
+            .ok_or_else(|| AgentFlowError::NodeInputError {
+              message: "Image data URI must be base64 encoded".to_string(),
+            })?;
+        let bytes = STANDARD
+          .decode(encoded)
+          .map_err(|e| AgentFlowError::NodeInputError {
+            message: format!("Invalid base64 in image data URI: {}", e),
+          })?;
+        (bytes, Some(mime.to_string()))
+      }
+      None => {
+        let path = self.local_path(Path::new(source))?;
+        let bytes = tokio::fs::read(&path)
+          .await
+          .map_err(|e| AgentFlowError::NodeInputError {
+            message: format!("Failed to read image at {}: {}", source, e),
+          })?;
+        let mime = mime_guess::from_path(&path)
+          .first()
+          .map(|mime| mime.to_string());
+        (bytes, mime)
+      }
+    };
+    prepare_image_data_uri(&bytes, mime_hint.as_deref(), self.max_image_bytes)
+  }
+
+  /// Resolves every configured file source into a reference or an upload.
+  fn file_sources(&self, inputs: &AsyncNodeInputs) -> Result<Vec<FileSource>, AgentFlowError> {
+    let mut sources = Vec::new();
+    for source in &self.files {
+      match inputs.get(source) {
+        Some(FlowValue::Json(Value::Array(items))) => {
+          for item in items {
+            sources.push(file_source_from_json(source, item)?);
+          }
+        }
+        Some(FlowValue::Json(value)) => sources.push(file_source_from_json(source, value)?),
+        Some(FlowValue::File { path, .. }) => sources.push(FileSource::Upload(path.clone())),
+        Some(FlowValue::Url { url, mime_type }) => {
+          sources.push(FileSource::Reference(FileRef::new(url, mime_type.clone())))
+        }
+        None => sources.push(FileSource::from_literal(source)),
+      }
+    }
+    Ok(sources)
+  }
+}
+
+/// A file part of the request: its URI and MIME type.
+#[derive(Debug, Clone, PartialEq)]
+struct FileRef {
+  uri: String,
+  mime_type: String,
+}
+
+impl FileRef {
+  /// Without a MIME type one is guessed from the URI's extension.
+  fn new(uri: &str, mime_type: Option<String>) -> Self {
+    let mime_type = mime_type.unwrap_or_else(|| {
+      mime_guess::from_path(uri.split(['?', '#']).next().unwrap_or(uri))
+        .first()
+        .map_or_else(|| "application/octet-stream".to_string(), |m| m.to_string())
+    });
+    Self {
+      uri: uri.to_string(),
+      mime_type,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FileSource {
+  /// Already reachable by URI.
+  Reference(FileRef),
+  /// A local file, uploaded before the call.
+  Upload(PathBuf),
+}
+
+impl FileSource {
+  fn from_literal(source: &str) -> Self {
+    if source.starts_with("http://") || source.starts_with("https://") {
+      Self::Reference(FileRef::new(source, None))
+    } else {
+      Self::Upload(PathBuf::from(source))
+    }
+  }
+}
+
+/// A string is a path or URI; an object is a `FileHandle` (`uri` and
+/// `mimeType`, or `file_uri` and `mime_type`).
+fn file_source_from_json(input: &str, value: &Value) -> Result<FileSource, AgentFlowError> {
+  match value {
+    Value::String(source) => Ok(FileSource::from_literal(source)),
+    Value::Object(handle) => {
+      let field = |keys: [&str; 2]| {
+        keys
+          .iter()
+          .find_map(|key| handle.get(*key).and_then(Value::as_str))
+          .filter(|value| !value.is_empty())
+      };
+      let uri = field(["uri", "file_uri"]).ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!("File input '{}' has no 'uri'", input),
+      })?;
+      Ok(FileSource::Reference(FileRef::new(
+        uri,
+        field(["mimeType", "mime_type"]).map(str::to_string),
+      )))
+    }
+    other => Err(AgentFlowError::NodeInputError {
+      message: format!(
+        "File input '{}' must be a path, URI or uploaded file, got {}",
+        input, other
+      ),
+    }),
+  }
+}
+
+/// MIME type from the image's magic bytes, else the caller's hint.
+pub(crate) fn sniff_mime(bytes: &[u8], hint: Option<&str>) -> String {
+  match image::guess_format(bytes) {
+    Ok(format) => format.to_mime_type().to_string(),
+    Err(_) => hint.unwrap_or("application/octet-stream").to_string(),
+  }
+}
+
+/// Base64 `data:` URI for `bytes`, re-encoded as JPEG (and downscaled as
+/// needed) when larger than `max_bytes`.
+pub(crate) fn prepare_image_data_uri(
+  bytes: &[u8],
+  mime_hint: Option<&str>,
+  max_bytes: usize,
+) -> Result<String, AgentFlowError> {
+  if bytes.len() <= max_bytes {
+    return Ok(format!(
+      "data:{};base64,{}",
+      sniff_mime(bytes, mime_hint),
+      STANDARD.encode(bytes)
+    ));
+  }
+  let jpeg = shrink_to_jpeg(bytes, max_bytes)?;
+  Ok(format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg)))
+}
+
+/// Re-encodes as JPEG, then shrinks the dimensions in steps until the
+/// encoding fits `max_bytes`.
+fn shrink_to_jpeg(bytes: &[u8], max_bytes: usize) -> Result<Vec<u8>, AgentFlowError> {
+  let failed = |message: String| AgentFlowError::NodeInputError { message };
+  let mut image = image::load_from_memory(bytes).map_err(|e| {
+    failed(format!(
+      "Image exceeds the size limit and cannot be decoded: {}",
+      e
+    ))
+  })?;
+  // JPEG has no alpha channel.
+  image = image::DynamicImage::ImageRgb8(image.to_rgb8());
+  loop {
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY)
+      .encode_image(&image)
+      .map_err(|e| failed(format!("Failed to re-encode image as JPEG: {}", e)))?;
+    if encoded.len() <= max_bytes {
+      println!(
+        "🗜️  Re-encoded image to {}x{} JPEG ({} bytes)",
+        image.width(),
+        image.height(),
+        encoded.len()
+      );
+      return Ok(encoded);
+    }
+    if image.width() <= 16 || image.height() <= 16 {
+      return Err(failed(format!(
+        "Image cannot be shrunk below {} bytes",
+        max_bytes
+      )));
+    }
+    // Bytes scale roughly with area; aim a little under the budget.
+    let scale = ((max_bytes as f64 / encoded.len() as f64).sqrt() * 0.9).clamp(0.1, 0.9);
+    let width = ((image.width() as f64 * scale) as u32).max(16);
+    let height = ((image.height() as f64 * scale) as u32).max(16);
+    image = image.resize(width, height, FilterType::Triangle);
+  }
+}
+
+/// The user turn: the prompt text followed by each image, then each file,
+/// in order.
+fn build_user_message(prompt: &str, image_urls: &[String], files: &[FileRef]) -> MultimodalMessage {
+  let message = image_urls.iter().fold(
+    MultimodalMessage::user().add_text(prompt),
+    |message, url| message.add_image_url(url.as_str()),
+  );
+  files
+    .iter()
+    .fold(message, |message, file| {
+      message.add_file_uri(file.uri.as_str(), file.mime_type.as_str())
+    })
+    .build()
+}
+
+/// Files this node uploaded, deleted once the model has answered.
+#[derive(Default)]
+struct Uploads {
+  client: Option<GoogleFilesClient>,
+  names: Vec<String>,
+}
+
+impl Uploads {
+  /// Best effort: Gemini expires uploads after 48 hours anyway.
+  async fn delete(&self) {
+    let Some(client) = &self.client else {
+      return;
+    };
+    for name in &self.names {
+      if let Err(e) = client.delete(name).await {
+        println!("⚠️  Failed to delete uploaded file {}: {}", name, e);
+      }
+    }
+  }
+}
+
+/// Uploads the local files among `sources`, returning the parts to send
+/// in order and the uploads to delete afterwards.
+async fn upload_files(
+  model: &str,
+  sources: Vec<FileSource>,
+) -> Result<(Vec<FileRef>, Uploads), AgentFlowError> {
+  let mut uploads = Uploads::default();
+  let mut files = Vec::new();
+  for source in sources {
+    let path = match source {
+      FileSource::Reference(file) => {
+        files.push(file);
+        continue;
+      }
+      FileSource::Upload(path) => path,
+    };
+    let client = match &uploads.client {
+      Some(client) => client,
+      None => uploads
+        .client
+        .insert(AgentFlow::google_files(model).await.map_err(|e| {
+          AgentFlowError::ConfigurationError {
+            message: format!("Cannot upload files for model '{}': {}", model, e),
+          }
+        })?),
+    };
+    println!("📤 Uploading {} to the Gemini Files API", path.display());
+    let handle = match client.upload(&path).await {
+      Ok(handle) => handle,
+      Err(e) => {
+        uploads.delete().await;
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: format!("Failed to upload {}: {}", path.display(), e),
+        });
+      }
+    };
+    let mime_type = match handle.mime_type.as_str() {
+      "" => mime_type_for_path(&path).to_string(),
+      mime => mime.to_string(),
+    };
+    files.push(FileRef {
+      uri: handle.uri,
+      mime_type,
+    });
+    uploads.names.push(handle.name);
+  }
+  Ok((files, uploads))
 }
 
 #[async_trait]
@@ -46,29 +482,34 @@ impl AsyncNode for ImageUnderstandNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
     println!("🔍 Executing ImageUnderstandNode: {}", self.name);
 
+    let prompt = render(&self.text_prompt, inputs, &RenderOptions::lenient())?;
+    let image_urls = self.image_urls(inputs).await?;
+    let file_sources = self
+      .file_sources(inputs)?
+      .into_iter()
+      .map(|source| match source {
+        FileSource::Upload(path) => self.local_path(&path).map(FileSource::Upload),
+        reference => Ok(reference),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    if image_urls.is_empty() && file_sources.is_empty() {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!("ImageUnderstandNode '{}' has no images or files", self.name),
+      });
+    }
+
     AgentFlow::init()
       .await
       .map_err(|e| AgentFlowError::ConfigurationError {
         message: format!("Failed to initialize AgentFlow LLM service: {}", e),
       })?;
 
-    let mut resolved_prompt = self.text_prompt.clone();
-    for key in &self.input_keys {
-      if let Some(value) = inputs.get(key) {
-        let placeholder = format!("{{{{{}}}}}", key);
-        resolved_prompt = resolved_prompt.replace(&placeholder, &flow_value_to_string(value));
-      }
-    }
-
-    let image_data_uri = load_data_uri_from_source(&self.image_source, inputs).await?;
-
-    let message = MultimodalMessage::user()
-      .add_text(resolved_prompt)
-      .add_image_url(image_data_uri)
-      .build();
-
-    let mut request = AgentFlow::model(&self.model).multimodal_prompt(message);
-
+    let (files, uploads) = upload_files(&self.model, file_sources).await?;
+    let mut request = AgentFlow::model(&self.model).multimodal_prompt(build_user_message(
+      &prompt,
+      &image_urls,
+      &files,
+    ));
     if let Some(system_message) = &self.system_message {
       request = request.system(system_message);
     }
@@ -79,19 +520,25 @@ impl AsyncNode for ImageUnderstandNode {
       request = request.max_tokens(max_tokens);
     }
 
-    let response = request
-      .execute()
-      .await
-      .map_err(|e| AgentFlowError::AsyncExecutionError {
-        message: format!("LLM execution failed: {}", e),
-      })?;
+    let response = request.execute_full().await;
+    uploads.delete().await;
+    let response = response.map_err(|e| AgentFlowError::AsyncExecutionError {
+      message: format!("LLM execution failed: {}", e),
+    })?;
 
     println!("✅ ImageUnderstandNode execution successful.");
+    let usage = response.usage.as_ref().map_or(Value::Null, |usage| {
+      json!({
+        "prompt_tokens": usage.prompt_tokens,
+        "completion_tokens": usage.completion_tokens,
+        "total_tokens": usage.total_tokens,
+      })
+    });
+    let text = FlowValue::Json(Value::String(response.content));
     let mut outputs = HashMap::new();
-    outputs.insert(
-      self.output_key.clone(),
-      FlowValue::Json(Value::String(response)),
-    );
+    outputs.insert(self.output_key.clone(), text.clone());
+    outputs.insert("response".to_string(), text);
+    outputs.insert("usage".to_string(), FlowValue::Json(usage));
 
     Ok(outputs)
   }
@@ -100,6 +547,230 @@ impl AsyncNode for ImageUnderstandNode {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use image::{ImageBuffer, ImageFormat, Rgb};
+
+  fn noisy_png(width: u32, height: u32) -> Vec<u8> {
+    // A cheap hash per pixel: noise compresses badly, so the PNG is large.
+    let image = ImageBuffer::from_fn(width, height, |x, y| {
+      let h = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)).wrapping_mul(2_246_822_519);
+      Rgb([(h >> 24) as u8, (h >> 16) as u8, (h >> 8) as u8])
+    });
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+      .write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png)
+      .unwrap();
+    bytes
+  }
+
+  fn decode_data_uri(uri: &str) -> (String, Vec<u8>) {
+    let (mime, data) = uri
+      .strip_prefix("data:")
+      .and_then(|rest| rest.split_once(";base64,"))
+      .unwrap();
+    (mime.to_string(), STANDARD.decode(data).unwrap())
+  }
+
+  #[tokio::test]
+  async fn two_images_build_one_user_message_in_order() {
+    let dir =
+      std::env::temp_dir().join(format!("agentflow-image-understand-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // Misnamed on purpose: the MIME type comes from the bytes.
+    let chart = dir.join("chart.bin");
+    std::fs::write(&chart, noisy_png(4, 4)).unwrap();
+
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "chart".to_string(),
+      FlowValue::Json(json!(chart.to_string_lossy())),
+    );
+    let node = ImageUnderstandNode::new(
+      "vision",
+      "step-1o-turbo-vision",
+      "Compare {{ topic }}",
+      "chart",
+    )
+    .with_images(vec![
+      "chart".to_string(),
+      "https://example.com/b.jpg".to_string(),
+    ])
+    .with_base_dir(&dir);
+
+    let urls = node.image_urls(&inputs).await.unwrap();
+    inputs.insert("topic".to_string(), FlowValue::Json(json!("the charts")));
+    let message = build_user_message(
+      &render(&node.text_prompt, &inputs, &RenderOptions::lenient()).unwrap(),
+      &urls,
+      &[],
+    );
+
+    assert!(
+      urls[0].starts_with("data:image/png;base64,"),
+      "{}",
+      &urls[0][..40]
+    );
+    assert_eq!(urls[1], "https://example.com/b.jpg");
+    let content = serde_json::to_value(&message.content).unwrap();
+    assert_eq!(content.as_array().unwrap().len(), 3);
+    assert_eq!(
+      content[0],
+      json!({ "type": "text", "text": "Compare the charts" })
+    );
+    assert_eq!(content[1]["image_url"]["url"], json!(urls[0]));
+    assert_eq!(
+      content[2]["image_url"]["url"],
+      json!("https://example.com/b.jpg")
+    );
+    std::fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn files_are_referenced_or_uploaded_and_sent_after_the_images() {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "uploaded".to_string(),
+      FlowValue::Json(json!({
+        "name": "files/abc123",
+        "uri": "https://generativelanguage.googleapis.com/v1beta/files/abc123",
+        "mimeType": "video/mp4",
+        "state": "ACTIVE",
+      })),
+    );
+    inputs.insert(
+      "report".to_string(),
+      FlowValue::File {
+        path: PathBuf::from("/tmp/report.pdf"),
+        mime_type: Some("application/pdf".to_string()),
+      },
+    );
+    let node = ImageUnderstandNode::new("analyze", "gemini-2.5-flash", "Summarize", "chart")
+      .with_images(vec![])
+      .with_files(vec![
+        "uploaded".to_string(),
+        "report".to_string(),
+        "https://example.com/paper.pdf".to_string(),
+      ]);
+
+    let sources = node.file_sources(&inputs).unwrap();
+    let uploaded = FileRef {
+      uri: "https://generativelanguage.googleapis.com/v1beta/files/abc123".to_string(),
+      mime_type: "video/mp4".to_string(),
+    };
+    let paper = FileRef::new("https://example.com/paper.pdf", None);
+    assert_eq!(
+      sources,
+      vec![
+        FileSource::Reference(uploaded.clone()),
+        FileSource::Upload(PathBuf::from("/tmp/report.pdf")),
+        FileSource::Reference(paper.clone()),
+      ]
+    );
+    assert_eq!(paper.mime_type, "application/pdf");
+
+    let message = build_user_message(
+      "Summarize",
+      &["https://example.com/a.png".to_string()],
+      &[uploaded, paper],
+    );
+    let content = serde_json::to_value(&message.content).unwrap();
+    assert_eq!(content[1]["type"], json!("image_url"));
+    assert_eq!(
+      content[2],
+      json!({
+        "type": "file_data",
+        "file_data": {
+          "file_uri": "https://generativelanguage.googleapis.com/v1beta/files/abc123",
+          "mime_type": "video/mp4",
+        },
+      })
+    );
+    assert_eq!(
+      content[3]["file_data"]["mime_type"],
+      json!("application/pdf")
+    );
+
+    inputs.insert(
+      "bad".to_string(),
+      FlowValue::Json(json!({ "mimeType": "video/mp4" })),
+    );
+    let err = node
+      .clone()
+      .with_files(vec!["bad".to_string()])
+      .file_sources(&inputs)
+      .unwrap_err();
+    assert!(err.to_string().contains("has no 'uri'"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn local_reads_stay_inside_the_base_dir() {
+    let base = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    std::fs::write(base.path().join("chart.png"), noisy_png(4, 4)).unwrap();
+    std::fs::write(outside.path().join("secret.png"), noisy_png(4, 4)).unwrap();
+    let secret = outside
+      .path()
+      .join("secret.png")
+      .to_string_lossy()
+      .into_owned();
+    let node = ImageUnderstandNode::new("vision", "step-1o-turbo-vision", "Describe", "chart.png");
+
+    let err = node.image_url("chart.png").await.unwrap_err();
+    assert!(err.to_string().contains("has no base_dir"), "{err}");
+
+    let node = node.with_base_dir(base.path());
+    assert!(
+      node
+        .image_url("chart.png")
+        .await
+        .unwrap()
+        .starts_with("data:image/png")
+    );
+    let relative = format!(
+      "../{}/secret.png",
+      outside.path().file_name().unwrap().to_string_lossy()
+    );
+    for escape in [secret.as_str(), relative.as_str()] {
+      let err = node.image_url(escape).await.unwrap_err();
+      assert!(
+        err.to_string().contains("escapes the base directory"),
+        "{err}"
+      );
+    }
+    #[cfg(unix)]
+    {
+      std::os::unix::fs::symlink(&secret, base.path().join("link.png")).unwrap();
+      let err = node.image_url("link.png").await.unwrap_err();
+      assert!(
+        err.to_string().contains("escapes the base directory"),
+        "{err}"
+      );
+    }
+  }
+
+  #[test]
+  fn oversized_images_are_reencoded_and_downscaled() {
+    let png = noisy_png(1200, 900);
+    let limit = 100 * 1024;
+    assert!(png.len() > limit);
+
+    let uri = prepare_image_data_uri(&png, Some("image/png"), limit).unwrap();
+
+    let (mime, jpeg) = decode_data_uri(&uri);
+    assert_eq!(mime, "image/jpeg");
+    assert!(jpeg.len() <= limit, "{} bytes", jpeg.len());
+    let shrunk = image::load_from_memory(&jpeg).unwrap();
+    assert!(shrunk.width() < 1200 && shrunk.height() < 900);
+    // Aspect ratio survives the resize.
+    let ratio = shrunk.width() as f64 / shrunk.height() as f64;
+    assert!((ratio - 4.0 / 3.0).abs() < 0.05, "{ratio}");
+  }
+
+  #[test]
+  fn small_images_pass_through_unchanged() {
+    let png = noisy_png(8, 8);
+    let uri = prepare_image_data_uri(&png, None, DEFAULT_MAX_IMAGE_BYTES).unwrap();
+    assert_eq!(decode_data_uri(&uri), ("image/png".to_string(), png));
+  }
 
   #[tokio::test]
   async fn test_image_understand_node_integration() {
@@ -137,5 +808,6 @@ mod tests {
     } else {
       panic!("Output was not a FlowValue::Json(Value::String(...))");
     }
+    assert_eq!(outputs.get("response"), Some(output_value));
   }
 }
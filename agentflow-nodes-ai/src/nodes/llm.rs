@@ -1,22 +1,236 @@
+//! LLM node — a single chat completion against a registered model.
+//!
+//! Beyond the plain `prompt` → `output` call the node can stream the
+//! completion (chunks are collected internally and reported to an optional
+//! [`LlmStreamObserver`]), attach images from its inputs as a multimodal
+//! user message, and ask the provider for JSON output, which is then parsed
+//! into `response_json`. Every run also reports the `model` that answered and
+//! the token `usage` the provider returned.
+//!
+//! A streamed completion also reports its progress to the running flow as
+//! `NodeProgress` events, `{"event": "llm.stream", "chunks",
+//! "received_chars", "completion_tokens"}`, every
+//! [`STREAM_PROGRESS_EVERY`] chunks and once at the end (`completion_tokens`
+//! is `null` until the provider reports usage).
+
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
+  node_context,
+  secret::SecretValue,
   value::FlowValue,
 };
-use agentflow_llm::AgentFlow;
+use agentflow_llm::{
+  AgentFlow, LLMError, LLMResponse, client::LLMClientBuilder, multimodal::MultimodalMessage,
+  providers::TokenUsage,
+};
+use agentflow_nodes::common::template::{RenderOptions, render};
+use agentflow_nodes::common::utils::load_data_uri_from_source;
 use async_trait::async_trait;
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Schema name sent with [`LlmNode::with_response_schema`] requests.
+const RESPONSE_SCHEMA_NAME: &str = "response";
+
+/// How many streamed chunks pass between two `llm.stream` progress events.
+pub const STREAM_PROGRESS_EVERY: usize = 8;
+
+/// One streamed chunk, as reported to an [`LlmStreamObserver`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmStreamProgress<'a> {
+  /// Zero-based position of the chunk in the stream.
+  pub index: usize,
+  /// The text delta carried by this chunk.
+  pub delta: &'a str,
+  /// Characters received so far, including this chunk.
+  pub received_chars: usize,
+  /// Whether the provider flagged this as the last chunk.
+  pub is_final: bool,
+}
+
+/// Receives a progress event for every chunk of a streamed completion.
+pub trait LlmStreamObserver: Send + Sync {
+  fn on_chunk(&self, progress: &LlmStreamProgress<'_>);
+}
+
+/// Structured output requested from the provider.
+#[derive(Debug, Clone, PartialEq)]
+enum ResponseFormat {
+  Json,
+  Schema(Value),
+}
+
+#[derive(Clone, Default)]
+pub struct LlmNode {
+  streaming: bool,
+  images: Vec<String>,
+  response_format: Option<ResponseFormat>,
+  observer: Option<Arc<dyn LlmStreamObserver>>,
+  api_key: Option<SecretValue>,
+}
+
+impl fmt::Debug for LlmNode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LlmNode")
+      .field("streaming", &self.streaming)
+      .field("images", &self.images)
+      .field("response_format", &self.response_format)
+      .field("observer", &self.observer.is_some())
+      .field("api_key", &self.api_key)
+      .finish()
+  }
+}
+
+impl LlmNode {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Request a streamed completion. The chunks are still joined into a single
+  /// `output`; attach an observer with [`LlmNode::with_observer`] to follow
+  /// them as they arrive.
+  pub fn with_streaming(mut self, streaming: bool) -> Self {
+    self.streaming = streaming;
+    self
+  }
+
+  /// Input keys holding images to send with the prompt. Each value may be a
+  /// file path, an `http(s)` or `data:` URL, a `File`/`Url` flow value, or a
+  /// list of those. A name that is not an input key is used as the source
+  /// itself.
+  pub fn with_images(mut self, images: Vec<String>) -> Self {
+    self.images = images;
+    self
+  }
+
+  /// Ask for a JSON object response and parse it into `response_json`.
+  pub fn with_json_mode(mut self) -> Self {
+    self.response_format = Some(ResponseFormat::Json);
+    self
+  }
+
+  /// Constrain the response to a JSON schema and parse it into
+  /// `response_json`.
+  pub fn with_response_schema(mut self, schema: Value) -> Self {
+    self.response_format = Some(ResponseFormat::Schema(schema));
+    self
+  }
+
+  pub fn with_observer(mut self, observer: Arc<dyn LlmStreamObserver>) -> Self {
+    self.observer = Some(observer);
+    self
+  }
+
+  /// Authenticate with this key instead of the provider's configured one.
+  pub fn with_api_key(mut self, api_key: SecretValue) -> Self {
+    self.api_key = Some(api_key);
+    self
+  }
+
+  /// Resolves the configured image keys into URLs (remote or `data:`), in
+  /// the order they were configured.
+  async fn image_urls(&self, inputs: &AsyncNodeInputs) -> Result<Vec<String>, AgentFlowError> {
+    let no_inputs = AsyncNodeInputs::new();
+    let mut urls = Vec::new();
+    for key in &self.images {
+      match inputs.get(key) {
+        Some(FlowValue::Json(Value::Array(items))) => {
+          for item in items {
+            let Value::String(source) = item else {
+              return Err(AgentFlowError::NodeInputError {
+                message: format!("Image input '{}' must be a list of strings", key),
+              });
+            };
+            urls.push(load_data_uri_from_source(source, &no_inputs).await?);
+          }
+        }
+        Some(FlowValue::Json(Value::String(source))) => {
+          urls.push(load_data_uri_from_source(source, &no_inputs).await?);
+        }
+        _ => urls.push(load_data_uri_from_source(key, inputs).await?),
+      }
+    }
+    Ok(urls)
+  }
+
+  async fn complete(&self, request: LLMClientBuilder) -> Result<LLMResponse, AgentFlowError> {
+    let failed = |e: LLMError| AgentFlowError::AsyncExecutionError {
+      message: format!("LLM execution failed: {}", e),
+    };
+    if !self.streaming {
+      return request.execute_full().await.map_err(failed);
+    }
+
+    let mut stream = request.execute_streaming().await.map_err(failed)?;
+    let mut content = String::new();
+    let mut thinking = String::new();
+    let mut usage = None;
+    let mut metadata = None;
+    let mut index = 0;
+    while let Some(chunk) = stream.next_chunk().await.map_err(failed)? {
+      content.push_str(&chunk.content);
+      if let Some(delta) = &chunk.thinking_delta {
+        thinking.push_str(delta);
+      }
+      if let Some(observer) = &self.observer {
+        observer.on_chunk(&LlmStreamProgress {
+          index,
+          delta: &chunk.content,
+          received_chars: content.chars().count(),
+          is_final: chunk.is_final,
+        });
+      }
+      index += 1;
+      usage = chunk.usage.or(usage);
+      metadata = chunk.metadata.or(metadata);
+      if chunk.is_final {
+        break;
+      }
+      if index % STREAM_PROGRESS_EVERY == 0 {
+        emit_stream_progress(index, &content, usage.as_ref());
+      }
+    }
+    emit_stream_progress(index, &content, usage.as_ref());
+    Ok(LLMResponse {
+      content,
+      tool_calls: Vec::new(),
+      stop_reason: None,
+      usage: usage.map(|usage| TokenUsage {
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        total_tokens: usage.total_tokens,
+      }),
+      raw_metadata: metadata,
+      thinking: (!thinking.is_empty()).then_some(thinking),
+      thinking_blocks: Vec::new(),
+      context: None,
+      prompt: None,
+    })
+  }
+}
 
-#[derive(Debug, Clone, Default)]
-pub struct LlmNode;
+fn emit_stream_progress(
+  chunks: usize,
+  content: &str,
+  usage: Option<&agentflow_llm::client::TokenUsage>,
+) {
+  node_context::emit_progress(json!({
+    "event": "llm.stream",
+    "chunks": chunks,
+    "received_chars": content.chars().count(),
+    "completion_tokens": usage.and_then(|usage| usage.completion_tokens),
+  }));
+}
 
 #[async_trait]
 impl AsyncNode for LlmNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
-    let prompt = get_string_input(inputs, "prompt")?;
-    let model = get_optional_string_input(inputs, "model")?;
-    let system = get_optional_string_input(inputs, "system")?;
+    let (prompt, system) = render_prompts(inputs)?;
+    let model = get_optional_string_input(inputs, "model")?.unwrap_or_default();
+    let image_urls = self.image_urls(inputs).await?;
 
     AgentFlow::init()
       .await
@@ -24,37 +238,110 @@ impl AsyncNode for LlmNode {
         message: format!("Failed to initialize AgentFlow LLM service: {}", e),
       })?;
 
-    let mut request = AgentFlow::model(model.unwrap_or_default()).prompt(prompt);
+    let mut request = AgentFlow::model(model);
+    request = if image_urls.is_empty() {
+      request.prompt(&prompt)
+    } else {
+      request.multimodal_prompt(build_user_message(&prompt, &image_urls))
+    };
 
-    if let Some(sys) = system {
+    if let Some(sys) = &system {
       request = request.system(sys);
     }
+    if let Some(api_key) = &self.api_key {
+      request = request.api_key(api_key.expose_secret());
+    }
     if let Some(temp) = get_optional_f64_input(inputs, "temperature")? {
       request = request.temperature(temp as f32);
     }
     if let Some(max_tokens) = get_optional_u64_input(inputs, "max_tokens")? {
       request = request.max_tokens(max_tokens as u32);
     }
+    request = match &self.response_format {
+      Some(ResponseFormat::Json) => request.json_mode(),
+      Some(ResponseFormat::Schema(schema)) => {
+        request.json_schema(RESPONSE_SCHEMA_NAME, schema.clone())
+      }
+      None => request,
+    };
 
     println!("🤖 Executing LLM request...");
-    let response = request
-      .execute()
-      .await
-      .map_err(|e| AgentFlowError::AsyncExecutionError {
-        message: format!("LLM execution failed: {}", e),
-      })?;
+    let response = self.complete(request).await?;
     println!("✅ LLM Response received.");
 
+    let answered_by = response
+      .raw_metadata
+      .as_ref()
+      .and_then(|metadata| metadata.get("model"))
+      .and_then(Value::as_str)
+      .unwrap_or(model)
+      .to_string();
+    let usage = response.usage.as_ref().map_or(Value::Null, |usage| {
+      json!({
+        "prompt_tokens": usage.prompt_tokens,
+        "completion_tokens": usage.completion_tokens,
+        "total_tokens": usage.total_tokens,
+      })
+    });
+
     let mut outputs = HashMap::new();
+    if self.response_format.is_some() {
+      outputs.insert(
+        "response_json".to_string(),
+        FlowValue::Json(parse_json_response(&response.content)?),
+      );
+    }
     outputs.insert(
       "output".to_string(),
-      FlowValue::Json(Value::String(response)),
+      FlowValue::Json(Value::String(response.content)),
+    );
+    outputs.insert(
+      "model".to_string(),
+      FlowValue::Json(Value::String(answered_by)),
     );
+    outputs.insert("usage".to_string(), FlowValue::Json(usage));
 
     Ok(outputs)
   }
 }
 
+/// The user turn: the prompt text followed by each image, in order.
+fn build_user_message(prompt: &str, image_urls: &[String]) -> MultimodalMessage {
+  image_urls
+    .iter()
+    .fold(
+      MultimodalMessage::user().add_text(prompt),
+      |message, url| message.add_image_url(url.as_str()),
+    )
+    .build()
+}
+
+/// The `prompt` and optional `system` inputs with their placeholders filled
+/// from the other inputs. Unknown names are left as written so literal
+/// braces in a prompt survive.
+fn render_prompts(inputs: &AsyncNodeInputs) -> Result<(String, Option<String>), AgentFlowError> {
+  let options = RenderOptions::lenient();
+  let prompt = render(get_string_input(inputs, "prompt")?, inputs, &options)?;
+  let system = get_optional_string_input(inputs, "system")?
+    .map(|system| render(system, inputs, &options))
+    .transpose()?;
+  Ok((prompt, system))
+}
+
+/// Parses a JSON-mode response, tolerating a surrounding Markdown code fence.
+fn parse_json_response(content: &str) -> Result<Value, AgentFlowError> {
+  let trimmed = content.trim();
+  let body = trimmed
+    .strip_prefix("```json")
+    .or_else(|| trimmed.strip_prefix("```"))
+    .and_then(|inner| inner.strip_suffix("```"))
+    .unwrap_or(trimmed)
+    .trim();
+  serde_json::from_str(body).map_err(|e| AgentFlowError::AsyncExecutionError {
+    message: format!("LLM response is not valid JSON: {}", e),
+  })
+}
+
 fn get_string_input<'a>(inputs: &'a AsyncNodeInputs, key: &str) -> Result<&'a str, AgentFlowError> {
   inputs
     .get(key)
@@ -122,7 +409,7 @@ mod tests {
 
   #[tokio::test]
   async fn test_llm_node_async_execution() {
-    let node = LlmNode;
+    let node = LlmNode::new();
     let mut inputs = AsyncNodeInputs::new();
     inputs.insert(
       "prompt".to_string(),
@@ -144,4 +431,139 @@ mod tests {
       );
     }
   }
+
+  #[test]
+  fn placeholders_resolve_from_inputs_and_keep_unknown_names() {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("topic".to_string(), FlowValue::Json(json!("graphs")));
+    inputs.insert("count".to_string(), FlowValue::Json(json!(3)));
+
+    inputs.insert(
+      "prompt".to_string(),
+      FlowValue::Json(json!(
+        "List {{count}} facts about {{ topic }} as {{ format }}."
+      )),
+    );
+    inputs.insert(
+      "system".to_string(),
+      FlowValue::Json(json!("You write about {{topic}}.")),
+    );
+
+    let (prompt, system) = render_prompts(&inputs).unwrap();
+
+    assert_eq!(prompt, "List 3 facts about graphs as {{ format }}.");
+    assert_eq!(system.as_deref(), Some("You write about graphs."));
+  }
+
+  #[tokio::test]
+  async fn prompts_and_http_urls_render_placeholders_identically() {
+    use agentflow_nodes::nodes::http::HttpNode;
+    use agentflow_tools::SandboxPolicy;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(200))
+      .mount(&server)
+      .await;
+    let template = format!(
+      "{}/users/{{{{ user.name }}}}/{{{{user.tags[1]}}}}?page={{{{ page }}}}&first={{{{ user.tags.0 }}}}",
+      server.uri()
+    );
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "user".to_string(),
+      FlowValue::Json(json!({ "name": "ada", "tags": ["math", "engines"] })),
+    );
+    inputs.insert("page".to_string(), FlowValue::Json(json!(2)));
+    inputs.insert("prompt".to_string(), FlowValue::Json(json!(template)));
+    inputs.insert("url".to_string(), FlowValue::Json(json!(template)));
+
+    let (prompt, _) = render_prompts(&inputs).unwrap();
+    let policy = Arc::new(SandboxPolicy {
+      allow_loopback_network_access: true,
+      ..SandboxPolicy::default()
+    });
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    HttpNode::new(policy)
+      .with_client(client)
+      .execute(&inputs)
+      .await
+      .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let requested = &requests[0].url;
+    assert_eq!(
+      prompt,
+      format!("{}/users/ada/engines?page=2&first=math", server.uri())
+    );
+    assert_eq!(
+      format!(
+        "{}{}?{}",
+        server.uri(),
+        requested.path(),
+        requested.query().unwrap_or_default()
+      ),
+      prompt
+    );
+  }
+
+  #[tokio::test]
+  async fn images_resolve_paths_urls_and_lists_in_order() {
+    let dir = std::env::temp_dir().join(format!("agentflow-llm-node-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let png = dir.join("pixel.png");
+    std::fs::write(&png, [0x89, b'P', b'N', b'G']).unwrap();
+
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "photo".to_string(),
+      FlowValue::Json(json!(png.to_string_lossy())),
+    );
+    inputs.insert(
+      "gallery".to_string(),
+      FlowValue::Json(json!([
+        "https://example.com/a.jpg",
+        "data:image/gif;base64,R0lG"
+      ])),
+    );
+    let node = LlmNode::new().with_images(vec!["photo".to_string(), "gallery".to_string()]);
+
+    let urls = node.image_urls(&inputs).await.unwrap();
+    let message = build_user_message("Describe these", &urls);
+
+    assert_eq!(
+      urls,
+      vec![
+        "data:image/png;base64,iVBORw==".to_string(),
+        "https://example.com/a.jpg".to_string(),
+        "data:image/gif;base64,R0lG".to_string(),
+      ]
+    );
+    assert!(message.has_images());
+    let content = serde_json::to_value(&message.content).unwrap();
+    assert_eq!(
+      content[0],
+      json!({ "type": "text", "text": "Describe these" })
+    );
+    assert_eq!(
+      content[2]["image_url"]["url"],
+      json!("https://example.com/a.jpg")
+    );
+    std::fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn json_responses_parse_with_or_without_code_fences() {
+    assert_eq!(
+      parse_json_response("```json\n{\"ok\": true}\n```").unwrap(),
+      json!({ "ok": true })
+    );
+    assert_eq!(parse_json_response(" [1, 2] ").unwrap(), json!([1, 2]));
+    assert!(matches!(
+      parse_json_response("not json"),
+      Err(AgentFlowError::AsyncExecutionError { .. })
+    ));
+  }
 }
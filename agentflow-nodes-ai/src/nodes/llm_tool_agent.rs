@@ -0,0 +1,331 @@
+//! Tool-use agent loop node.
+//!
+//! `LlmToolAgentNode` runs the usual "model decides → call tool → feed the
+//! result back → repeat" loop inside a single workflow node. Tools come from
+//! an [`AgentToolSource`]: a local `agentflow_tools::ToolRegistry` or, with
+//! the `mcp` feature, a connected `MCPClient`. Each tool call the model makes
+//! is executed, recorded in `tool_trace`, and appended to the conversation;
+//! the first reply without tool calls becomes `final_answer`.
+//!
+//! Tool results go back to the model as user turns prefixed with
+//! `[Tool Result: <name>]`, matching the ReAct runtime in `agentflow-agents`,
+//! because `agentflow-llm` messages carry no tool role.
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_llm::{AgentFlow, ToolCallRequest, ToolSpec, multimodal::MultimodalMessage};
+#[cfg(feature = "mcp")]
+use agentflow_mcp::client::{Content, MCPClient};
+use agentflow_tools::ToolRegistry;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Iteration budget used when none is configured.
+pub const DEFAULT_MAX_ITERATIONS: usize = 8;
+
+/// Schema name sent with [`LlmToolAgentNode::with_final_answer_schema`].
+const FINAL_ANSWER_SCHEMA_NAME: &str = "final_answer";
+
+/// Where the agent's tools come from.
+#[derive(Clone)]
+pub enum AgentToolSource {
+  /// Tools registered in a local registry; calls go through its policy and
+  /// schema checks.
+  Registry(Arc<ToolRegistry>),
+  /// Every tool exposed by a connected MCP server, under its remote name.
+  #[cfg(feature = "mcp")]
+  Mcp(Arc<tokio::sync::Mutex<MCPClient>>),
+}
+
+/// Result of one tool call, as fed back to the model.
+struct ToolCallOutcome {
+  content: String,
+  is_error: bool,
+}
+
+impl AgentToolSource {
+  async fn tool_specs(&self) -> Result<Vec<ToolSpec>, AgentFlowError> {
+    match self {
+      Self::Registry(registry) => Ok(
+        registry
+          .list()
+          .into_iter()
+          .map(|tool| ToolSpec::new(tool.name(), tool.description(), tool.parameters_schema()))
+          .collect(),
+      ),
+      #[cfg(feature = "mcp")]
+      Self::Mcp(client) => {
+        let tools = client.lock().await.list_tools().await.map_err(|e| {
+          AgentFlowError::AsyncExecutionError {
+            message: format!("Failed to list MCP tools: {}", e),
+          }
+        })?;
+        Ok(
+          tools
+            .into_iter()
+            .map(|tool| {
+              ToolSpec::new(
+                tool.name,
+                tool.description.unwrap_or_default(),
+                tool.input_schema,
+              )
+            })
+            .collect(),
+        )
+      }
+    }
+  }
+
+  /// Runs one call. Tool failures are reported back to the model rather
+  /// than failing the node, so it can recover or pick another tool.
+  async fn call(&self, call: &ToolCallRequest) -> ToolCallOutcome {
+    match self {
+      Self::Registry(registry) => {
+        match registry.execute(&call.name, call.arguments.clone()).await {
+          Ok(output) => ToolCallOutcome {
+            content: output.content,
+            is_error: output.is_error,
+          },
+          Err(e) => ToolCallOutcome {
+            content: e.to_string(),
+            is_error: true,
+          },
+        }
+      }
+      #[cfg(feature = "mcp")]
+      Self::Mcp(client) => {
+        let result = client
+          .lock()
+          .await
+          .call_tool(call.name.as_str(), call.arguments.clone())
+          .await;
+        match result {
+          Ok(result) => ToolCallOutcome {
+            content: result
+              .content
+              .iter()
+              .map(|content| match content {
+                Content::Text { text } => text.clone(),
+                Content::Image { mime_type, .. } => format!("[image: {}]", mime_type),
+                Content::Resource { uri, .. } => format!("[resource: {}]", uri),
+              })
+              .collect::<Vec<_>>()
+              .join("\n"),
+            is_error: result.is_error(),
+          },
+          Err(e) => ToolCallOutcome {
+            content: e.to_string(),
+            is_error: true,
+          },
+        }
+      }
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct LlmToolAgentNode {
+  model: String,
+  system_prompt: Option<String>,
+  tools: AgentToolSource,
+  max_iterations: usize,
+  final_answer_schema: Option<Value>,
+}
+
+impl LlmToolAgentNode {
+  pub fn new(model: &str, tools: AgentToolSource) -> Self {
+    Self {
+      model: model.to_string(),
+      system_prompt: None,
+      tools,
+      max_iterations: DEFAULT_MAX_ITERATIONS,
+      final_answer_schema: None,
+    }
+  }
+
+  pub fn with_system_prompt(mut self, system_prompt: &str) -> Self {
+    self.system_prompt = Some(system_prompt.to_string());
+    self
+  }
+
+  /// Maximum number of model calls. Running out before the model stops
+  /// calling tools fails the node with the partial trace attached.
+  pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+    self.max_iterations = max_iterations.max(1);
+    self
+  }
+
+  /// Constrain the final reply to a JSON schema; `final_answer` is then the
+  /// parsed value instead of a string.
+  pub fn with_final_answer_schema(mut self, schema: Value) -> Self {
+    self.final_answer_schema = Some(schema);
+    self
+  }
+
+  fn final_answer(&self, content: String) -> Result<Value, AgentFlowError> {
+    if self.final_answer_schema.is_none() {
+      return Ok(Value::String(content));
+    }
+    serde_json::from_str(content.trim()).map_err(|e| AgentFlowError::AsyncExecutionError {
+      message: format!("Final answer is not valid JSON: {}", e),
+    })
+  }
+}
+
+#[async_trait]
+impl AsyncNode for LlmToolAgentNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let prompt = match inputs.get("prompt") {
+      Some(FlowValue::Json(Value::String(prompt))) => prompt.clone(),
+      _ => {
+        return Err(AgentFlowError::NodeInputError {
+          message: "Required string input 'prompt' is missing or has wrong type".to_string(),
+        });
+      }
+    };
+
+    AgentFlow::init()
+      .await
+      .map_err(|e| AgentFlowError::ConfigurationError {
+        message: format!("Failed to initialize AgentFlow LLM service: {}", e),
+      })?;
+
+    let specs = self.tools.tool_specs().await?;
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = &self.system_prompt {
+      messages.push(MultimodalMessage::system().add_text(system_prompt).build());
+    }
+    messages.push(MultimodalMessage::user().add_text(&prompt).build());
+
+    let mut tool_trace = Vec::new();
+    for iteration in 1..=self.max_iterations {
+      let mut request = AgentFlow::model(&self.model).multimodal_messages(messages.clone());
+      if !specs.is_empty() {
+        request = request.tools(specs.clone());
+      }
+      if let Some(schema) = &self.final_answer_schema {
+        request = request.json_schema(FINAL_ANSWER_SCHEMA_NAME, schema.clone());
+      }
+
+      println!("🤖 Tool agent iteration {}...", iteration);
+      let response =
+        request
+          .execute_full()
+          .await
+          .map_err(|e| AgentFlowError::AsyncExecutionError {
+            message: format!("LLM execution failed: {}", e),
+          })?;
+
+      if !response.has_tool_calls() {
+        println!("✅ Tool agent finished after {} iteration(s).", iteration);
+        let mut outputs = HashMap::new();
+        outputs.insert(
+          "final_answer".to_string(),
+          FlowValue::Json(self.final_answer(response.content)?),
+        );
+        outputs.insert(
+          "tool_trace".to_string(),
+          FlowValue::Json(Value::Array(tool_trace)),
+        );
+        outputs.insert("iterations".to_string(), FlowValue::Json(json!(iteration)));
+        return Ok(outputs);
+      }
+
+      messages.push(assistant_turn(&response.content, &response.tool_calls));
+      for call in &response.tool_calls {
+        println!("🔧 Calling tool '{}'", call.name);
+        let outcome = self.tools.call(call).await;
+        messages.push(
+          MultimodalMessage::user()
+            .add_text(format!("[Tool Result: {}]\n{}", call.name, outcome.content))
+            .build(),
+        );
+        tool_trace.push(json!({
+          "iteration": iteration,
+          "call": call,
+          "result": { "content": outcome.content, "is_error": outcome.is_error },
+        }));
+      }
+    }
+
+    Err(AgentFlowError::NodePartialExecutionFailed {
+      message: format!(
+        "Tool agent exhausted its budget of {} iteration(s) without a final answer",
+        self.max_iterations
+      ),
+      partial_outputs: HashMap::from([
+        (
+          "tool_trace".to_string(),
+          FlowValue::Json(Value::Array(tool_trace)),
+        ),
+        (
+          "iterations".to_string(),
+          FlowValue::Json(json!(self.max_iterations)),
+        ),
+      ]),
+    })
+  }
+}
+
+/// The assistant turn that requested `calls`, kept in the history so the
+/// model sees what it already asked for.
+fn assistant_turn(content: &str, calls: &[ToolCallRequest]) -> MultimodalMessage {
+  let mut text = content.trim().to_string();
+  for call in calls {
+    if !text.is_empty() {
+      text.push('\n');
+    }
+    text.push_str(&format!("[Tool Call: {}] {}", call.name, call.arguments));
+  }
+  MultimodalMessage::assistant().add_text(text).build()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn assistant_turn_lists_each_requested_call() {
+    let calls = vec![
+      ToolCallRequest {
+        id: "call_0".to_string(),
+        name: "lookup".to_string(),
+        arguments: json!({ "city": "Paris" }),
+      },
+      ToolCallRequest {
+        id: "call_1".to_string(),
+        name: "convert".to_string(),
+        arguments: json!({ "celsius": 21 }),
+      },
+    ];
+
+    let turn = assistant_turn("Let me check.", &calls);
+
+    assert_eq!(turn.role, "assistant");
+    assert_eq!(
+      turn.get_text(),
+      "Let me check.\n[Tool Call: lookup] {\"city\":\"Paris\"}\n[Tool Call: convert] {\"celsius\":21}"
+    );
+  }
+
+  #[test]
+  fn final_answer_is_parsed_only_with_a_schema() {
+    let node = LlmToolAgentNode::new("mock", AgentToolSource::Registry(Arc::default()));
+    assert_eq!(
+      node.final_answer("{\"a\": 1}".to_string()).unwrap(),
+      json!("{\"a\": 1}")
+    );
+
+    let node = node.with_final_answer_schema(json!({ "type": "object" }));
+    assert_eq!(
+      node.final_answer(" {\"a\": 1} ".to_string()).unwrap(),
+      json!({ "a": 1 })
+    );
+    assert!(node.final_answer("done".to_string()).is_err());
+  }
+}
@@ -23,6 +23,8 @@ use agentflow_core::{
   value::FlowValue,
 };
 use agentflow_mcp::client::ClientBuilder;
+use agentflow_mcp::transport::StdioTransport;
+use agentflow_nodes::common::process_env::ProcessEnv;
 use async_trait::async_trait;
 use serde_json::{Value, json};
 use std::collections::HashMap;
@@ -48,6 +50,9 @@ pub struct MCPNode {
 
   /// Whether to cache the client connection (future feature)
   pub cache_connection: bool,
+
+  /// Variables and working directory of the spawned server
+  pub process_env: ProcessEnv,
 }
 
 impl Default for MCPNode {
@@ -59,6 +64,7 @@ impl Default for MCPNode {
       timeout_ms: Some(30_000),
       max_retries: Some(3),
       cache_connection: false,
+      process_env: ProcessEnv::default(),
     }
   }
 }
@@ -90,6 +96,12 @@ impl MCPNode {
     self.max_retries = Some(max_retries);
     self
   }
+
+  /// Set the server's environment variables and working directory
+  pub fn with_process_env(mut self, process_env: ProcessEnv) -> Self {
+    self.process_env = process_env;
+    self
+  }
 }
 
 #[async_trait]
@@ -117,7 +129,16 @@ impl AsyncNode for MCPNode {
     println!("🔌 Connecting to MCP server: {:?}", server_command);
 
     // 2. Build MCP client with configuration
-    let mut client_builder = ClientBuilder::new().with_stdio(server_command);
+    let vars = self
+      .process_env
+      .exposed_vars()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect();
+    let mut transport = StdioTransport::new(server_command).with_env(vars);
+    if let Some(cwd) = &self.process_env.cwd {
+      transport = transport.with_working_dir(cwd.clone());
+    }
+    let mut client_builder = ClientBuilder::new().with_transport(transport);
 
     if let Some(timeout_ms) = self
       .timeout_ms
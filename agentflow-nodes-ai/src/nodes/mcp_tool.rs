@@ -0,0 +1,543 @@
+//! Config-first MCP tool invocation node
+//!
+//! `McpToolNode` calls one tool on an MCP server reached over stdio (a spawned
+//! command) or HTTP (a URL). Unlike [`MCPNode`](super::mcp::MCPNode), which
+//! spawns a fresh server for every run, connections are kept in a
+//! process-wide pool keyed by [`McpServerSpec`], so a workflow that calls the
+//! same server from several nodes (or in a loop) initializes it once.
+//!
+//! # Example Usage
+//!
+//! ```yaml
+//! nodes:
+//!   - id: search
+//!     type: mcp_tool
+//!     parameters:
+//!       url: "http://localhost:8080/mcp"
+//!       tool_name: search
+//!       arguments:
+//!         query: "papers about {{ topic }}"
+//!         limit: "{{ limit }}"
+//! ```
+//!
+//! String arguments are rendered against the node inputs: a value that is
+//! exactly one `{{ name }}` placeholder is replaced by the input's JSON value
+//! (so numbers and objects keep their type); anything else has its
+//! placeholders substituted as text.
+//!
+//! Outputs: `text` (the joined text content), `json` (only when `text`
+//! parses as JSON) and `images` (paths of image content written to
+//! `output_dir`).
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_mcp::client::{CallToolResult, ClientBuilder, Content, MCPClient};
+use agentflow_mcp::transport::StdioTransport;
+use agentflow_nodes::common::process_env::{EnvValue, ProcessEnv};
+use agentflow_nodes::common::template::{RenderOptions, lookup, render, whole_placeholder};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, OnceCell};
+
+/// Per-request timeout used when none is configured.
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// How to reach an MCP server; also the key of the connection pool.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum McpServerSpec {
+  /// Spawn `command` and talk JSON-RPC over its stdin/stdout.
+  Stdio {
+    command: Vec<String>,
+    /// Variables and working directory of the spawned server.
+    env: ProcessEnv,
+  },
+  /// POST JSON-RPC messages to `url`.
+  Http { url: String },
+}
+
+impl McpServerSpec {
+  /// Stdio server without extra environment variables.
+  pub fn stdio(command: Vec<String>) -> Self {
+    Self::Stdio {
+      command,
+      env: ProcessEnv::default(),
+    }
+  }
+
+  /// HTTP server at `url`.
+  pub fn http(url: impl Into<String>) -> Self {
+    Self::Http { url: url.into() }
+  }
+
+  async fn connect(&self, timeout_ms: u64) -> Result<MCPClient, AgentFlowError> {
+    let builder = match self {
+      Self::Stdio { command, env } => {
+        let vars = env
+          .exposed_vars()
+          .map(|(name, value)| (name.to_string(), value.to_string()))
+          .collect();
+        let mut transport = StdioTransport::new(command.clone()).with_env(vars);
+        if let Some(cwd) = &env.cwd {
+          transport = transport.with_working_dir(cwd.clone());
+        }
+        ClientBuilder::new().with_transport(transport)
+      }
+      Self::Http { url } => ClientBuilder::new().with_http(url.clone()),
+    };
+    let mut client = builder
+      .with_timeout(Duration::from_millis(timeout_ms))
+      .build()
+      .await
+      .map_err(|e| AgentFlowError::ConfigurationError {
+        message: format!("Failed to build MCP client: {}", e),
+      })?;
+    client
+      .connect()
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to connect to MCP server {}: {}", self, e),
+      })?;
+    Ok(client)
+  }
+}
+
+impl std::fmt::Display for McpServerSpec {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Stdio { command, .. } => write!(f, "{:?}", command),
+      Self::Http { url } => f.write_str(url),
+    }
+  }
+}
+
+type SharedClient = Arc<Mutex<MCPClient>>;
+
+/// One pooled server: connected at most once, by whichever node gets there
+/// first. Nodes racing for the same server wait on its cell, not on the pool.
+type PooledClient = Arc<OnceCell<SharedClient>>;
+
+/// Connected clients shared by every `McpToolNode` with `reuse_connection`.
+/// The pool lock only guards the map; connecting happens outside it, so a
+/// slow server does not hold up nodes that talk to other servers.
+static CONNECTIONS: LazyLock<Mutex<HashMap<McpServerSpec, PooledClient>>> =
+  LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Put an already connected client into the pool under `spec`, replacing any
+/// existing entry. Useful to pre-warm a server or to supply a client built
+/// on a custom transport.
+pub async fn register_connection(spec: McpServerSpec, client: MCPClient) {
+  let cell = OnceCell::new_with(Some(Arc::new(Mutex::new(client))));
+  CONNECTIONS.lock().await.insert(spec, Arc::new(cell));
+}
+
+/// Drop the pooled connection for `spec`, disconnecting it if no node is
+/// still using it.
+pub async fn close_connection(spec: &McpServerSpec) {
+  let Some(cell) = CONNECTIONS.lock().await.remove(spec) else {
+    return;
+  };
+  if let Ok(cell) = Arc::try_unwrap(cell)
+    && let Some(client) = cell.into_inner()
+    && let Ok(client) = Arc::try_unwrap(client)
+  {
+    client.into_inner().disconnect().await.ok();
+  }
+}
+
+/// Calls one MCP tool with arguments rendered from the node inputs.
+#[derive(Debug, Clone)]
+pub struct McpToolNode {
+  pub server: McpServerSpec,
+  pub tool_name: String,
+  /// Argument template; see the module docs for how it is rendered.
+  pub arguments: Value,
+  pub timeout_ms: u64,
+  /// Keep the connection in the process-wide pool (default: true).
+  pub reuse_connection: bool,
+  /// Directory image content is written to (default:
+  /// `<temp>/agentflow-mcp-images`).
+  pub output_dir: Option<PathBuf>,
+}
+
+impl McpToolNode {
+  pub fn new(server: McpServerSpec, tool_name: impl Into<String>) -> Self {
+    Self {
+      server,
+      tool_name: tool_name.into(),
+      arguments: json!({}),
+      timeout_ms: DEFAULT_TIMEOUT_MS,
+      reuse_connection: true,
+      output_dir: None,
+    }
+  }
+
+  pub fn with_arguments(mut self, arguments: Value) -> Self {
+    self.arguments = arguments;
+    self
+  }
+
+  pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+    self.timeout_ms = timeout_ms;
+    self
+  }
+
+  pub fn with_reuse_connection(mut self, reuse_connection: bool) -> Self {
+    self.reuse_connection = reuse_connection;
+    self
+  }
+
+  pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+    self.output_dir = Some(output_dir.into());
+    self
+  }
+
+  /// Adds a node's `env:` / `cwd:` to a stdio server, overriding the `env`
+  /// parameter where both set a variable. An HTTP server has no process to
+  /// apply them to, so that is an error.
+  pub fn with_process_env(mut self, process_env: ProcessEnv) -> Result<Self, AgentFlowError> {
+    if process_env.is_empty() {
+      return Ok(self);
+    }
+    let McpServerSpec::Stdio { env, .. } = &mut self.server else {
+      return Err(AgentFlowError::ConfigurationError {
+        message: "mcp_tool 'env' and 'cwd' need a 'server_command' to apply to".to_string(),
+      });
+    };
+    env.vars.extend(process_env.vars);
+    if process_env.cwd.is_some() {
+      env.cwd = process_env.cwd;
+    }
+    Ok(self)
+  }
+
+  /// Builds the node from workflow parameters: exactly one of
+  /// `server_command` (with optional `env`) or `url`, plus `tool_name`,
+  /// `arguments`, `timeout_ms`, `reuse_connection` and `output_dir`.
+  pub fn from_params(params: &Value) -> Result<Self, AgentFlowError> {
+    let invalid = |message: String| AgentFlowError::ConfigurationError { message };
+    let server = match (params.get("server_command"), params.get("url")) {
+      (Some(command), None) => {
+        let command: Vec<String> = serde_json::from_value(command.clone()).map_err(|e| {
+          invalid(format!(
+            "mcp_tool 'server_command' must be a list of strings: {}",
+            e
+          ))
+        })?;
+        if command.is_empty() {
+          return Err(invalid(
+            "mcp_tool 'server_command' must not be empty".to_string(),
+          ));
+        }
+        let vars: BTreeMap<String, String> = match params.get("env") {
+          Some(env) => serde_json::from_value(env.clone())
+            .map_err(|e| invalid(format!("mcp_tool 'env' must map names to strings: {}", e)))?,
+          None => BTreeMap::new(),
+        };
+        McpServerSpec::Stdio {
+          command,
+          env: ProcessEnv {
+            vars: vars
+              .into_iter()
+              .map(|(name, value)| (name, EnvValue::Plain(value)))
+              .collect(),
+            cwd: None,
+          },
+        }
+      }
+      (None, Some(Value::String(url))) => McpServerSpec::http(url.clone()),
+      (None, Some(_)) => return Err(invalid("mcp_tool 'url' must be a string".to_string())),
+      _ => {
+        return Err(invalid(
+          "mcp_tool requires exactly one of 'server_command' or 'url'".to_string(),
+        ));
+      }
+    };
+
+    let tool_name = params
+      .get("tool_name")
+      .and_then(Value::as_str)
+      .filter(|name| !name.is_empty())
+      .ok_or_else(|| invalid("mcp_tool requires a 'tool_name' string".to_string()))?;
+
+    let mut node = Self::new(server, tool_name);
+    if let Some(arguments) = params.get("arguments") {
+      node = node.with_arguments(arguments.clone());
+    }
+    if let Some(timeout_ms) = params.get("timeout_ms").and_then(Value::as_u64) {
+      node = node.with_timeout_ms(timeout_ms);
+    }
+    if let Some(reuse) = params.get("reuse_connection").and_then(Value::as_bool) {
+      node = node.with_reuse_connection(reuse);
+    }
+    if let Some(output_dir) = params.get("output_dir").and_then(Value::as_str) {
+      node = node.with_output_dir(output_dir);
+    }
+    Ok(node)
+  }
+
+  async fn call(&self, arguments: Value) -> Result<CallToolResult, AgentFlowError> {
+    let call_failed = |e: agentflow_mcp::MCPError| AgentFlowError::AsyncExecutionError {
+      message: format!("MCP tool '{}' call failed: {}", self.tool_name, e),
+    };
+
+    if !self.reuse_connection {
+      let mut client = self.server.connect(self.timeout_ms).await?;
+      let result = client.call_tool(self.tool_name.as_str(), arguments).await;
+      client
+        .disconnect()
+        .await
+        .map_err(|e| {
+          eprintln!("⚠️  Warning: Failed to disconnect MCP client: {}", e);
+        })
+        .ok();
+      return result.map_err(call_failed);
+    }
+
+    let cell = CONNECTIONS
+      .lock()
+      .await
+      .entry(self.server.clone())
+      .or_default()
+      .clone();
+    // A failed connect leaves the cell empty, so the next run retries.
+    let client = cell
+      .get_or_try_init(|| async {
+        println!("🔌 Connecting to MCP server {}", self.server);
+        let client = self.server.connect(self.timeout_ms).await?;
+        Ok::<_, AgentFlowError>(Arc::new(Mutex::new(client)))
+      })
+      .await?
+      .clone();
+    let result = client
+      .lock()
+      .await
+      .call_tool(self.tool_name.as_str(), arguments)
+      .await;
+    if result.is_err() {
+      // The server may have gone away; reconnect on the next run instead of
+      // failing every later call on a dead connection.
+      let mut pool = CONNECTIONS.lock().await;
+      if pool
+        .get(&self.server)
+        .and_then(|pooled| pooled.get())
+        .is_some_and(|pooled| Arc::ptr_eq(pooled, &client))
+      {
+        pool.remove(&self.server);
+      }
+    }
+    result.map_err(call_failed)
+  }
+
+  async fn save_image(
+    &self,
+    index: usize,
+    data: &str,
+    mime_type: &str,
+  ) -> Result<String, AgentFlowError> {
+    if data.starts_with("http://") || data.starts_with("https://") {
+      return Ok(data.to_string());
+    }
+    let bytes = STANDARD
+      .decode(data)
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "MCP tool '{}' returned invalid image data: {}",
+          self.tool_name, e
+        ),
+      })?;
+    let dir = self
+      .output_dir
+      .clone()
+      .unwrap_or_else(|| std::env::temp_dir().join("agentflow-mcp-images"));
+    tokio::fs::create_dir_all(&dir)
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to create image directory {:?}: {}", dir, e),
+      })?;
+    let extension = mime_type
+      .split('/')
+      .nth(1)
+      .map(|subtype| subtype.split(['+', ';']).next().unwrap_or(subtype))
+      .filter(|subtype| !subtype.is_empty())
+      .unwrap_or("bin");
+    let stamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_nanos())
+      .unwrap_or_default();
+    let path = dir.join(format!(
+      "{}-{}-{}.{}",
+      self.tool_name, stamp, index, extension
+    ));
+    tokio::fs::write(&path, bytes)
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to write image {:?}: {}", path, e),
+      })?;
+    Ok(path.to_string_lossy().to_string())
+  }
+}
+
+#[async_trait]
+impl AsyncNode for McpToolNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let arguments = render_arguments(&self.arguments, inputs)?;
+    println!(
+      "🔧 Calling MCP tool '{}' on {}",
+      self.tool_name, self.server
+    );
+    let result = self.call(arguments).await?;
+
+    let mut texts = Vec::new();
+    let mut images = Vec::new();
+    for content in &result.content {
+      match content {
+        Content::Text { text } => texts.push(text.clone()),
+        Content::Resource {
+          text: Some(text), ..
+        } => texts.push(text.clone()),
+        Content::Resource { .. } => {}
+        Content::Image { data, mime_type } => {
+          images.push(Value::String(
+            self.save_image(images.len(), data, mime_type).await?,
+          ));
+        }
+      }
+    }
+    let text = texts.join("\n");
+
+    if result.is_error() {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!("MCP tool '{}' reported an error: {}", self.tool_name, text),
+      });
+    }
+    println!("✅ MCP tool '{}' completed", self.tool_name);
+
+    let mut outputs = HashMap::new();
+    if let Ok(parsed) = serde_json::from_str::<Value>(text.trim()) {
+      outputs.insert("json".to_string(), FlowValue::Json(parsed));
+    }
+    outputs.insert("text".to_string(), FlowValue::Json(Value::String(text)));
+    outputs.insert("images".to_string(), FlowValue::Json(Value::Array(images)));
+    Ok(outputs)
+  }
+}
+
+/// Renders an argument template against the node inputs. A string that is
+/// a single placeholder takes the input's JSON value, keeping its type.
+fn render_arguments(template: &Value, inputs: &AsyncNodeInputs) -> Result<Value, AgentFlowError> {
+  Ok(match template {
+    Value::String(text) => match whole_placeholder(text).and_then(|path| lookup(path, inputs)) {
+      Some(value) => value,
+      None => Value::String(render(text, inputs, &RenderOptions::lenient())?),
+    },
+    Value::Array(items) => Value::Array(
+      items
+        .iter()
+        .map(|item| render_arguments(item, inputs))
+        .collect::<Result<_, _>>()?,
+    ),
+    Value::Object(fields) => Value::Object(
+      fields
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), render_arguments(value, inputs)?)))
+        .collect::<Result<_, AgentFlowError>>()?,
+    ),
+    other => other.clone(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn whole_placeholders_keep_the_input_type() {
+    let inputs = AsyncNodeInputs::from([
+      ("limit".to_string(), FlowValue::Json(json!(5))),
+      ("topic".to_string(), FlowValue::Json(json!("rust"))),
+    ]);
+
+    let rendered = render_arguments(
+      &json!({
+        "limit": "{{ limit }}",
+        "query": "papers about {{topic}}",
+        "tags": ["{{ topic }}", "{{ missing }}"],
+        "exact": true
+      }),
+      &inputs,
+    )
+    .unwrap();
+
+    assert_eq!(
+      rendered,
+      json!({
+        "limit": 5,
+        "query": "papers about rust",
+        "tags": ["rust", "{{ missing }}"],
+        "exact": true
+      })
+    );
+  }
+
+  #[test]
+  fn from_params_requires_exactly_one_transport() {
+    let node = McpToolNode::from_params(&json!({
+      "server_command": ["mcp-server", "--stdio"],
+      "env": { "TOKEN": "x" },
+      "tool_name": "echo",
+      "reuse_connection": false
+    }))
+    .unwrap();
+    assert_eq!(
+      node.server,
+      McpServerSpec::Stdio {
+        command: vec!["mcp-server".to_string(), "--stdio".to_string()],
+        env: ProcessEnv {
+          vars: BTreeMap::from([("TOKEN".to_string(), EnvValue::from("x"))]),
+          cwd: None,
+        },
+      }
+    );
+    assert!(!node.reuse_connection);
+
+    // A node's `env:` / `cwd:` block overrides the `env` parameter.
+    let node = node
+      .with_process_env(ProcessEnv {
+        vars: BTreeMap::from([("TOKEN".to_string(), EnvValue::from("y"))]),
+        cwd: Some(PathBuf::from("/srv/mcp")),
+      })
+      .unwrap();
+    let McpServerSpec::Stdio { env, .. } = &node.server else {
+      panic!("expected a stdio server");
+    };
+    assert_eq!(env.exposed_vars().collect::<Vec<_>>(), vec![("TOKEN", "y")]);
+    assert_eq!(env.cwd, Some(PathBuf::from("/srv/mcp")));
+
+    let node = McpToolNode::from_params(&json!({
+      "url": "http://localhost:8080/mcp",
+      "tool_name": "echo"
+    }))
+    .unwrap();
+    assert_eq!(
+      node.server,
+      McpServerSpec::http("http://localhost:8080/mcp")
+    );
+    assert_eq!(node.timeout_ms, DEFAULT_TIMEOUT_MS);
+
+    for params in [
+      json!({ "tool_name": "echo" }),
+      json!({ "server_command": ["a"], "url": "http://x", "tool_name": "echo" }),
+      json!({ "url": "http://x" }),
+    ] {
+      assert!(McpToolNode::from_params(&params).is_err(), "{params}");
+    }
+  }
+}
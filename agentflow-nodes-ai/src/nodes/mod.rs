@@ -6,7 +6,10 @@
 //! `agentflow-llm` / `agentflow-rag` / `agentflow-mcp`.
 
 pub mod llm;
+pub mod llm_tool_agent;
+pub mod text_splitter;
 
+mod generated_images;
 pub mod image_edit;
 pub mod image_to_image;
 pub mod image_understand;
@@ -17,6 +20,10 @@ pub mod tts;
 
 #[cfg(feature = "mcp")]
 pub mod mcp;
+#[cfg(feature = "mcp")]
+pub mod mcp_tool;
 
+#[cfg(feature = "rag")]
+pub mod embedding;
 #[cfg(feature = "rag")]
 pub mod rag;
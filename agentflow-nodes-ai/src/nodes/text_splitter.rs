@@ -0,0 +1,651 @@
+//! Text splitter node.
+//!
+//! `TextSplitterNode` cuts the `text` input into chunks for embedding,
+//! map-style summarisation or per-chunk LLM calls. Four strategies:
+//!
+//! - `by_tokens`: packs words (and individual CJK characters, which carry
+//!   no spaces to split on) up to `chunk_size` tokens, counted with
+//!   [`agentflow_llm::counter_for_model`] for the configured `model`.
+//!   Pieces are counted one at a time, so the total is an upper-bound-ish
+//!   estimate of what the whole chunk encodes to.
+//! - `by_chars`: fixed windows of `chunk_size` characters.
+//! - `by_sentences`: packs whole sentences up to `chunk_size` characters.
+//! - `by_markdown_sections`: one chunk per heading section, with sections
+//!   larger than `chunk_size` characters split between blocks. Fenced code
+//!   blocks are never split, even when that makes a chunk oversized.
+//!
+//! `chunk_overlap` (same unit as `chunk_size`) repeats the tail of each
+//! chunk at the start of the next; packing strategies only overlap whole
+//! pieces. Chunks shorter than `min_chunk_size` characters are folded into
+//! the previous chunk.
+//!
+//! Outputs `chunks` (array of strings) and `chunk_metadata`: per chunk its
+//! `index`, character offsets `start` / `end` into the input, `char_count`,
+//! and — for markdown — `section` and the heading path `section_titles`.
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_llm::{TokenCounter, counter_for_model};
+use agentflow_nodes::common::utils::flow_value_to_string;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Model whose tokenizer `by_tokens` uses when none is configured.
+pub const DEFAULT_TOKEN_MODEL: &str = "gpt-4o";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitStrategy {
+  ByTokens,
+  #[default]
+  ByChars,
+  BySentences,
+  ByMarkdownSections,
+}
+
+impl FromStr for SplitStrategy {
+  type Err = AgentFlowError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "by_tokens" => Ok(Self::ByTokens),
+      "by_chars" => Ok(Self::ByChars),
+      "by_sentences" => Ok(Self::BySentences),
+      "by_markdown_sections" => Ok(Self::ByMarkdownSections),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Unknown split strategy '{}' (expected by_tokens, by_chars, by_sentences or by_markdown_sections)",
+          other
+        ),
+      }),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextSplitterNode {
+  pub name: String,
+  pub strategy: SplitStrategy,
+  /// Tokens for `by_tokens`, characters otherwise.
+  pub chunk_size: usize,
+  pub chunk_overlap: usize,
+  pub min_chunk_size: usize,
+  /// Model whose tokenizer `by_tokens` counts with.
+  pub model: String,
+  pub text_key: String,
+}
+
+/// A chunk as a byte range of the input plus its heading path.
+#[derive(Debug, Clone)]
+struct Span {
+  start: usize,
+  end: usize,
+  titles: Vec<String>,
+}
+
+/// An unsplittable unit for the packing strategies.
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+  start: usize,
+  end: usize,
+  size: usize,
+}
+
+impl TextSplitterNode {
+  pub fn new(name: &str, strategy: SplitStrategy) -> Self {
+    Self {
+      name: name.to_string(),
+      strategy,
+      chunk_size: 1000,
+      chunk_overlap: 0,
+      min_chunk_size: 0,
+      model: DEFAULT_TOKEN_MODEL.to_string(),
+      text_key: "text".to_string(),
+    }
+  }
+
+  pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+    self.chunk_size = chunk_size;
+    self
+  }
+
+  pub fn with_chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+    self.chunk_overlap = chunk_overlap;
+    self
+  }
+
+  pub fn with_min_chunk_size(mut self, min_chunk_size: usize) -> Self {
+    self.min_chunk_size = min_chunk_size;
+    self
+  }
+
+  pub fn with_model(mut self, model: &str) -> Self {
+    self.model = model.to_string();
+    self
+  }
+
+  pub fn with_text_key(mut self, key: &str) -> Self {
+    self.text_key = key.to_string();
+    self
+  }
+
+  fn validate(&self) -> Result<(), AgentFlowError> {
+    if self.chunk_size == 0 {
+      return Err(AgentFlowError::ConfigurationError {
+        message: format!("Text splitter '{}': chunk_size must be > 0", self.name),
+      });
+    }
+    if self.chunk_overlap >= self.chunk_size {
+      return Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Text splitter '{}': chunk_overlap ({}) must be smaller than chunk_size ({})",
+          self.name, self.chunk_overlap, self.chunk_size
+        ),
+      });
+    }
+    Ok(())
+  }
+
+  /// Split `text` into chunks and their metadata.
+  pub fn split(&self, text: &str) -> Result<(Vec<String>, Vec<Value>), AgentFlowError> {
+    self.validate()?;
+    let spans = match self.strategy {
+      SplitStrategy::ByChars => self.split_chars(text),
+      SplitStrategy::ByTokens => {
+        let counter = counter_for_model(&self.model);
+        let pieces = word_pieces(text, counter.as_ref());
+        self.pack(&pieces, Vec::new())
+      }
+      SplitStrategy::BySentences => self.pack(&sentence_pieces(text), Vec::new()),
+      SplitStrategy::ByMarkdownSections => self.split_markdown(text),
+    };
+    let spans = merge_small(text, spans, self.min_chunk_size);
+
+    let mut chunks = Vec::with_capacity(spans.len());
+    let mut metadata = Vec::with_capacity(spans.len());
+    for (index, span) in spans.iter().enumerate() {
+      let chunk = &text[span.start..span.end];
+      let start = text[..span.start].chars().count();
+      let char_count = chunk.chars().count();
+      let mut meta = json!({
+        "index": index,
+        "start": start,
+        "end": start + char_count,
+        "char_count": char_count,
+      });
+      if self.strategy == SplitStrategy::ByMarkdownSections {
+        meta["section"] = json!(span.titles.last());
+        meta["section_titles"] = json!(span.titles);
+      }
+      chunks.push(chunk.to_string());
+      metadata.push(meta);
+    }
+    Ok((chunks, metadata))
+  }
+
+  fn split_chars(&self, text: &str) -> Vec<Span> {
+    let mut boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    boundaries.push(text.len());
+    let char_len = boundaries.len() - 1;
+    let step = self.chunk_size - self.chunk_overlap;
+    let mut spans = Vec::new();
+    let mut start = 0;
+    while start < char_len {
+      let end = (start + self.chunk_size).min(char_len);
+      spans.push(Span {
+        start: boundaries[start],
+        end: boundaries[end],
+        titles: Vec::new(),
+      });
+      if end == char_len {
+        break;
+      }
+      start += step;
+    }
+    spans
+  }
+
+  /// Greedily packs consecutive pieces up to `chunk_size`, starting each
+  /// following chunk with the trailing pieces that fit in `chunk_overlap`.
+  /// A piece larger than `chunk_size` becomes a chunk of its own.
+  fn pack(&self, pieces: &[Piece], titles: Vec<String>) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    while start < pieces.len() {
+      let mut end = start;
+      let mut size = 0;
+      while end < pieces.len() && (end == start || size + pieces[end].size <= self.chunk_size) {
+        size += pieces[end].size;
+        end += 1;
+      }
+      spans.push(Span {
+        start: pieces[start].start,
+        end: pieces[end - 1].end,
+        titles: titles.clone(),
+      });
+      if end == pieces.len() {
+        break;
+      }
+      let mut next = end;
+      let mut overlap = 0;
+      while next - 1 > start && overlap + pieces[next - 1].size <= self.chunk_overlap {
+        overlap += pieces[next - 1].size;
+        next -= 1;
+      }
+      start = next;
+    }
+    spans
+  }
+
+  fn split_markdown(&self, text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    for section in markdown_sections(text) {
+      let size = text[section.start..section.end].chars().count();
+      if size <= self.chunk_size {
+        spans.push(section);
+      } else {
+        let blocks = markdown_blocks(text, section.start, section.end);
+        spans.extend(self.pack(&blocks, section.titles));
+      }
+    }
+    spans
+  }
+}
+
+fn is_cjk(c: char) -> bool {
+  matches!(c as u32,
+    0x3040..=0x30FF // Hiragana, Katakana
+    | 0x3400..=0x4DBF // CJK Extension A
+    | 0x4E00..=0x9FFF // CJK Unified Ideographs
+    | 0xAC00..=0xD7AF // Hangul syllables
+    | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+    | 0x3000..=0x303F // CJK punctuation
+    | 0xFF00..=0xFFEF // Full-width forms
+  )
+}
+
+/// Words with their trailing whitespace; every CJK character is a word.
+fn word_pieces(text: &str, counter: &dyn TokenCounter) -> Vec<Piece> {
+  let mut pieces = Vec::new();
+  let mut start = 0;
+  let mut chars = text.char_indices().peekable();
+  while let Some((_, c)) = chars.next() {
+    let next = chars.peek().map(|(j, c)| (*j, *c));
+    let ends_piece = match next {
+      None => true,
+      Some((_, n)) => {
+        is_cjk(c) && !n.is_whitespace()
+          || is_cjk(n) && !c.is_whitespace()
+          || c.is_whitespace() && !n.is_whitespace()
+      }
+    };
+    if ends_piece {
+      let end = next.map_or(text.len(), |(j, _)| j);
+      let segment = &text[start..end];
+      pieces.push(Piece {
+        start,
+        end,
+        size: counter.count_tokens(segment) as usize,
+      });
+      start = end;
+    }
+  }
+  pieces
+}
+
+/// Sentences with their trailing whitespace, sized in characters. A sentence
+/// ends after `.`, `!` or `?` followed by whitespace, after CJK full stops,
+/// or at a blank line.
+fn sentence_pieces(text: &str) -> Vec<Piece> {
+  let mut pieces = Vec::new();
+  let mut start = 0;
+  let mut chars = text.char_indices().peekable();
+  while let Some((_, c)) = chars.next() {
+    let next = chars.peek().map(|(_, n)| *n);
+    let terminal = match c {
+      '。' | '！' | '？' => true,
+      '.' | '!' | '?' => next.is_none_or(char::is_whitespace),
+      '\n' => next == Some('\n'),
+      _ => false,
+    };
+    if terminal || next.is_none() {
+      // Keep the whitespace after the terminator with this sentence.
+      while let Some((_, n)) = chars.peek() {
+        if n.is_whitespace() {
+          chars.next();
+        } else {
+          break;
+        }
+      }
+      let end = chars.peek().map_or(text.len(), |(j, _)| *j);
+      pieces.push(Piece {
+        start,
+        end,
+        size: text[start..end].chars().count(),
+      });
+      start = end;
+    }
+  }
+  pieces
+}
+
+/// Byte ranges of each line, including its newline.
+fn lines_with_offsets(text: &str, from: usize, to: usize) -> Vec<(usize, usize)> {
+  let mut lines = Vec::new();
+  let mut start = from;
+  while start < to {
+    let end = text[start..to].find('\n').map_or(to, |i| start + i + 1);
+    lines.push((start, end));
+    start = end;
+  }
+  lines
+}
+
+fn fence_marker(line: &str) -> Option<&'static str> {
+  let trimmed = line.trim_start();
+  if trimmed.starts_with("```") {
+    Some("```")
+  } else if trimmed.starts_with("~~~") {
+    Some("~~~")
+  } else {
+    None
+  }
+}
+
+/// `(level, title)` for an ATX heading line.
+fn heading(line: &str) -> Option<(usize, String)> {
+  let trimmed = line.trim_end();
+  let level = trimmed.chars().take_while(|c| *c == '#').count();
+  if !(1..=6).contains(&level) {
+    return None;
+  }
+  let rest = &trimmed[level..];
+  if !rest.is_empty() && !rest.starts_with(' ') {
+    return None;
+  }
+  Some((level, rest.trim().trim_end_matches('#').trim().to_string()))
+}
+
+/// One span per heading section (text before the first heading is its own
+/// untitled section). Headings inside code fences are ignored.
+fn markdown_sections(text: &str) -> Vec<Span> {
+  let mut sections = Vec::new();
+  let mut path: Vec<(usize, String)> = Vec::new();
+  let mut current_start = 0;
+  let mut current_titles = Vec::new();
+  let mut fence: Option<&str> = None;
+  for (start, end) in lines_with_offsets(text, 0, text.len()) {
+    let line = &text[start..end];
+    if let Some(marker) = fence {
+      if fence_marker(line) == Some(marker) {
+        fence = None;
+      }
+      continue;
+    }
+    if let Some(marker) = fence_marker(line) {
+      fence = Some(marker);
+      continue;
+    }
+    if let Some((level, title)) = heading(line) {
+      if start > current_start && !text[current_start..start].trim().is_empty() {
+        sections.push(Span {
+          start: current_start,
+          end: start,
+          titles: current_titles.clone(),
+        });
+      }
+      path.retain(|(l, _)| *l < level);
+      path.push((level, title));
+      current_titles = path.iter().map(|(_, t)| t.clone()).collect();
+      current_start = start;
+    }
+  }
+  if current_start < text.len() && !text[current_start..].trim().is_empty() {
+    sections.push(Span {
+      start: current_start,
+      end: text.len(),
+      titles: current_titles,
+    });
+  }
+  sections
+}
+
+/// Blank-line separated blocks of a section, sized in characters; a fenced
+/// code block (with any blank lines inside it) is one block.
+fn markdown_blocks(text: &str, from: usize, to: usize) -> Vec<Piece> {
+  let mut blocks = Vec::new();
+  let mut block_start = from;
+  let mut fence: Option<&str> = None;
+  for (start, end) in lines_with_offsets(text, from, to) {
+    let line = &text[start..end];
+    match fence {
+      Some(marker) => {
+        if fence_marker(line) == Some(marker) {
+          fence = None;
+        }
+      }
+      None => {
+        if let Some(marker) = fence_marker(line) {
+          fence = Some(marker);
+        } else if line.trim().is_empty() && !text[block_start..start].trim().is_empty() {
+          blocks.push(Piece {
+            start: block_start,
+            end,
+            size: text[block_start..end].chars().count(),
+          });
+          block_start = end;
+        }
+      }
+    }
+  }
+  if block_start < to {
+    blocks.push(Piece {
+      start: block_start,
+      end: to,
+      size: text[block_start..to].chars().count(),
+    });
+  }
+  blocks
+}
+
+/// Folds chunks shorter than `min` characters into their predecessor.
+fn merge_small(text: &str, spans: Vec<Span>, min: usize) -> Vec<Span> {
+  if min == 0 {
+    return spans;
+  }
+  let mut merged: Vec<Span> = Vec::with_capacity(spans.len());
+  for span in spans {
+    match merged.last_mut() {
+      Some(previous) if text[span.start..span.end].chars().count() < min => {
+        previous.end = previous.end.max(span.end);
+      }
+      _ => merged.push(span),
+    }
+  }
+  merged
+}
+
+#[async_trait]
+impl AsyncNode for TextSplitterNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let text = inputs
+      .get(&self.text_key)
+      .map(flow_value_to_string)
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!(
+          "Text splitter '{}' requires a '{}' input",
+          self.name, self.text_key
+        ),
+      })?;
+
+    let (chunks, metadata) = self.split(&text)?;
+    println!(
+      "✂️  Text splitter '{}': {} chunk(s)",
+      self.name,
+      chunks.len()
+    );
+
+    let mut outputs = HashMap::new();
+    outputs.insert("chunks".to_string(), FlowValue::Json(json!(chunks)));
+    outputs.insert(
+      "chunk_metadata".to_string(),
+      FlowValue::Json(Value::Array(metadata)),
+    );
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn char_windows_overlap_exactly() {
+    let node = TextSplitterNode::new("s", SplitStrategy::ByChars)
+      .with_chunk_size(4)
+      .with_chunk_overlap(2);
+
+    let (chunks, metadata) = node.split("abcdefghij").unwrap();
+
+    assert_eq!(chunks, vec!["abcd", "cdef", "efgh", "ghij"]);
+    assert_eq!(metadata[1]["start"], json!(2));
+    assert_eq!(metadata[1]["end"], json!(6));
+  }
+
+  #[test]
+  fn sentences_are_packed_with_whole_sentence_overlap() {
+    let node = TextSplitterNode::new("s", SplitStrategy::BySentences)
+      .with_chunk_size(24)
+      .with_chunk_overlap(12);
+
+    let (chunks, _) = node.split("One two. Three four. Five six. Seven.").unwrap();
+
+    assert_eq!(
+      chunks,
+      vec![
+        "One two. Three four. ",
+        "Three four. Five six. ",
+        "Five six. Seven."
+      ]
+    );
+  }
+
+  #[test]
+  fn cjk_text_splits_on_characters_with_char_offsets() {
+    let text = "自然语言处理。机器学习很有趣！深度学习";
+    let by_chars = TextSplitterNode::new("s", SplitStrategy::ByChars).with_chunk_size(7);
+    let (chunks, metadata) = by_chars.split(text).unwrap();
+    assert_eq!(
+      chunks,
+      vec!["自然语言处理。", "机器学习很有趣", "！深度学习"]
+    );
+    assert_eq!(metadata[2]["start"], json!(14));
+    assert_eq!(metadata[2]["end"], json!(19));
+
+    let by_sentences = TextSplitterNode::new("s", SplitStrategy::BySentences).with_chunk_size(8);
+    let (chunks, _) = by_sentences.split(text).unwrap();
+    assert_eq!(
+      chunks,
+      vec!["自然语言处理。", "机器学习很有趣！", "深度学习"]
+    );
+
+    let by_tokens = TextSplitterNode::new("s", SplitStrategy::ByTokens)
+      .with_chunk_size(6)
+      .with_chunk_overlap(2);
+    let (chunks, metadata) = by_tokens.split(text).unwrap();
+    assert!(chunks.len() > 1);
+    assert!(chunks[0].starts_with('自'));
+    assert_eq!(metadata.last().unwrap()["end"], json!(19));
+    let counter = counter_for_model(DEFAULT_TOKEN_MODEL);
+    // Each chunk re-opens with the last piece(s) of the one before it.
+    for (pair, meta) in chunks.windows(2).zip(metadata.windows(2)) {
+      assert!(
+        meta[1]["start"].as_u64() < meta[0]["end"].as_u64(),
+        "{pair:?}"
+      );
+      let shared = (meta[0]["end"].as_u64().unwrap() - meta[1]["start"].as_u64().unwrap()) as usize;
+      let tail: String = pair[0]
+        .chars()
+        .skip(pair[0].chars().count() - shared)
+        .collect();
+      assert!(pair[1].starts_with(&tail), "{pair:?}");
+    }
+    for chunk in &chunks {
+      let pieces: usize = chunk
+        .chars()
+        .map(|c| counter.count_tokens(&c.to_string()) as usize)
+        .sum();
+      assert!(pieces <= 6 || chunk.chars().count() == 1, "{chunk}");
+    }
+  }
+
+  #[test]
+  fn markdown_sections_follow_heading_hierarchy_and_keep_fences_whole() {
+    let doc = "\
+Intro text.
+
+# Guide
+
+Overview.
+
+## Install
+
+Run this:
+
+```sh
+# not a heading
+
+cargo install agentflow
+```
+
+## Usage
+
+### Flags
+
+Use --help.
+";
+    let node = TextSplitterNode::new("s", SplitStrategy::ByMarkdownSections).with_chunk_size(30);
+
+    let (chunks, metadata) = node.split(doc).unwrap();
+
+    let titles: Vec<&Value> = metadata.iter().map(|m| &m["section_titles"]).collect();
+    assert_eq!(
+      titles,
+      vec![
+        &json!([]),
+        &json!(["Guide"]),
+        &json!(["Guide", "Install"]),
+        &json!(["Guide", "Install"]),
+        &json!(["Guide", "Usage"]),
+        &json!(["Guide", "Usage", "Flags"]),
+      ]
+    );
+    assert_eq!(metadata[5]["section"], json!("Flags"));
+    // The oversized Install section split between blocks, never inside the fence.
+    assert_eq!(chunks[2], "## Install\n\nRun this:\n\n");
+    assert!(chunks[3].starts_with("```sh\n# not a heading\n\ncargo install"));
+    assert!(chunks[3].ends_with("```\n\n"));
+    assert_eq!(chunks.concat(), doc);
+  }
+
+  #[test]
+  fn small_trailing_chunks_merge_and_bad_sizes_are_rejected() {
+    let node = TextSplitterNode::new("s", SplitStrategy::ByChars)
+      .with_chunk_size(4)
+      .with_min_chunk_size(3);
+    let (chunks, _) = node.split("abcdefghij").unwrap();
+    assert_eq!(chunks, vec!["abcd", "efghij"]);
+
+    let err = TextSplitterNode::new("s", SplitStrategy::ByChars)
+      .with_chunk_size(4)
+      .with_chunk_overlap(4)
+      .split("abc")
+      .unwrap_err();
+    assert!(err.to_string().contains("chunk_overlap"), "{err}");
+    assert!("by_words".parse::<SplitStrategy>().is_err());
+  }
+}
@@ -4,12 +4,19 @@ use agentflow_core::{
   value::FlowValue,
 };
 use agentflow_llm::{
-  AgentFlow, providers::modality::Text2ImageRequest as ModalityText2ImageRequest,
+  AgentFlow,
+  providers::modality::{
+    ImageGenerationResponse, Text2ImageProvider, Text2ImageRequest as ModalityText2ImageRequest,
+  },
 };
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::generated_images::{ImageOutputOptions, ProviderOverride, collect_outputs};
+use agentflow_nodes::common::template::{RenderOptions, render};
 
 // ... (rest of the file is the same until the AsyncNode implementation)
 
@@ -20,7 +27,6 @@ pub struct TextToImageNode {
   pub model: String,
   pub prompt_template: String,
   pub negative_prompt: Option<String>,
-  pub input_keys: Vec<String>,
   pub output_key: String,
 
   // Image generation specific parameters
@@ -30,7 +36,14 @@ pub struct TextToImageNode {
   pub cfg_scale: Option<f32>, // Classifier-free guidance scale
   pub style_reference: Option<StyleReference>,
   pub n: Option<u32>,    // Number of images to generate (default 1)
-  pub seed: Option<u64>, // For reproducible generation
+  pub seed: Option<i32>, // For reproducible generation
+
+  // Output handling
+  /// Where to save images; see [`TextToImageNode::with_output_path`].
+  pub output_path: Option<String>,
+  /// Report `content_filtered` images as `warnings` instead of failing.
+  pub allow_filtered: bool,
+  provider: Option<ProviderOverride<dyn Text2ImageProvider>>,
 
   // Workflow control
   pub dependencies: Vec<String>,
@@ -61,7 +74,6 @@ impl TextToImageNode {
       model: model.to_string(),
       prompt_template: String::new(),
       negative_prompt: None,
-      input_keys: Vec::new(),
       output_key: format!("{}_image", name),
       size: None,
       response_format: ImageResponseFormat::default(),
@@ -70,6 +82,9 @@ impl TextToImageNode {
       style_reference: None,
       n: None,
       seed: None,
+      output_path: None,
+      allow_filtered: false,
+      provider: None,
       dependencies: Vec::new(),
       condition: None,
       timeout_ms: None,
@@ -116,13 +131,27 @@ impl TextToImageNode {
     self
   }
 
-  pub fn with_seed(mut self, seed: u64) -> Self {
+  pub fn with_seed(mut self, seed: i32) -> Self {
     self.seed = Some(seed);
     self
   }
 
-  pub fn with_input_keys(mut self, keys: Vec<String>) -> Self {
-    self.input_keys = keys;
+  /// Save every generated image to a file rendered from `pattern`, which
+  /// may use `{name}`, `{index}`, `{seed}` and `{ext}`; the saved paths
+  /// are output as `image_paths`.
+  pub fn with_output_path(mut self, pattern: &str) -> Self {
+    self.output_path = Some(pattern.to_string());
+    self
+  }
+
+  pub fn with_allow_filtered(mut self, allow: bool) -> Self {
+    self.allow_filtered = allow;
+    self
+  }
+
+  /// Use `provider` instead of resolving one from the model registry.
+  pub fn with_provider(mut self, provider: Arc<dyn Text2ImageProvider>) -> Self {
+    self.provider = Some(ProviderOverride(provider));
     self
   }
 
@@ -138,16 +167,7 @@ impl TextToImageNode {
 
   /// Resolve template variables in the prompt using inputs
   fn resolve_prompt(&self, inputs: &AsyncNodeInputs) -> Result<String, AgentFlowError> {
-    let mut resolved = self.prompt_template.clone();
-    for (key, value) in inputs {
-      let placeholder = format!("{{{{{}}}}}", key);
-      if resolved.contains(&placeholder)
-        && let FlowValue::Json(Value::String(s)) = value
-      {
-        resolved = resolved.replace(&placeholder, s);
-      }
-    }
-    Ok(resolved)
+    render(&self.prompt_template, inputs, &RenderOptions::lenient())
   }
 
   /// Create configuration for image generation.
@@ -169,18 +189,9 @@ impl TextToImageNode {
     );
 
     if let Some(ref neg_prompt) = self.negative_prompt {
-      let mut resolved_negative = neg_prompt.clone();
-      for (key, value) in inputs {
-        let placeholder = format!("{{{{{}}}}}", key);
-        if resolved_negative.contains(&placeholder)
-          && let FlowValue::Json(Value::String(s)) = value
-        {
-          resolved_negative = resolved_negative.replace(&placeholder, s);
-        }
-      }
       config.insert(
         "negative_prompt".to_string(),
-        Value::String(resolved_negative),
+        Value::String(render(neg_prompt, inputs, &RenderOptions::lenient())?),
       );
     }
 
@@ -256,7 +267,7 @@ impl TextToImageNode {
   async fn execute_real_image_generation(
     &self,
     config: &serde_json::Map<String, Value>,
-  ) -> Result<String, AgentFlowError> {
+  ) -> Result<ImageGenerationResponse, AgentFlowError> {
     let prompt = config
       .get("prompt")
       .and_then(|v| v.as_str())
@@ -277,15 +288,18 @@ impl TextToImageNode {
     println!("   Prompt: {}", prompt);
     println!("   Size: {}", size);
 
-    let provider =
-      AgentFlow::text2image_for(&model)
+    let provider: Arc<dyn Text2ImageProvider> = match &self.provider {
+      Some(ProviderOverride(provider)) => provider.clone(),
+      None => AgentFlow::text2image_for(&model)
         .await
         .map_err(|e| AgentFlowError::ConfigurationError {
           message: format!(
             "Failed to resolve text-to-image provider for '{}': {}",
             model, e
           ),
-        })?;
+        })?
+        .into(),
+    };
 
     let response_format = match &self.response_format {
       ImageResponseFormat::Base64Json => "b64_json",
@@ -298,7 +312,7 @@ impl TextToImageNode {
       size: Some(size.to_string()),
       n: self.n,
       response_format: Some(response_format.to_string()),
-      seed: self.seed.map(|s| s as i32),
+      seed: self.seed,
       steps: self.steps,
       cfg_scale: self.cfg_scale,
     };
@@ -311,42 +325,14 @@ impl TextToImageNode {
           message: format!("Text-to-image generation failed: {}", e),
         })?;
 
-    let first_image =
-      image_response
-        .images
-        .first()
-        .ok_or_else(|| AgentFlowError::AsyncExecutionError {
-          message: "No images returned from text-to-image provider".to_string(),
-        })?;
-
-    let result = match response_format {
-      "b64_json" => first_image
-        .b64_json
-        .as_ref()
-        .map(|b| format!("data:image/png;base64,{}", b))
-        .ok_or_else(|| AgentFlowError::AsyncExecutionError {
-          message: "No base64 image data returned from provider".to_string(),
-        })?,
-      "url" => first_image
-        .url
-        .clone()
-        .ok_or_else(|| AgentFlowError::AsyncExecutionError {
-          message: "No image URL returned from provider".to_string(),
-        })?,
-      other => {
-        return Err(AgentFlowError::AsyncExecutionError {
-          message: format!("Unsupported response format: {}", other),
-        });
-      }
-    };
-
     println!(
-      "✅ Image Generation via '{}': size {} format {}",
+      "✅ Image Generation via '{}': size {} format {}, {} image(s)",
       provider.name(),
       size,
-      response_format
+      response_format,
+      image_response.images.len()
     );
-    Ok(result)
+    Ok(image_response)
   }
 
   // Q1.3.3: the `execute_mock_image_generation` fallback was removed
@@ -419,13 +405,16 @@ impl AsyncNode for TextToImageNode {
         })?
     };
 
-    let mut outputs = HashMap::new();
-    outputs.insert(
-      self.output_key.clone(),
-      FlowValue::Json(Value::String(response)),
-    );
-
-    Ok(outputs)
+    collect_outputs(
+      &ImageOutputOptions {
+        node: &self.name,
+        output_key: &self.output_key,
+        output_path: self.output_path.as_deref(),
+        allow_filtered: self.allow_filtered,
+      },
+      &response,
+    )
+    .await
   }
 }
 
@@ -462,6 +451,111 @@ impl TextToImageNode {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use agentflow_llm::providers::stepfun::StepFunSpecializedClient;
+  use base64::{Engine as _, engine::general_purpose::STANDARD};
+  use serde_json::json;
+  use wiremock::matchers::{body_partial_json, header, method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  fn tiny_png_b64() -> String {
+    let mut bytes = Vec::new();
+    image::RgbImage::new(2, 2)
+      .write_to(
+        &mut std::io::Cursor::new(&mut bytes),
+        image::ImageFormat::Png,
+      )
+      .unwrap();
+    STANDARD.encode(bytes)
+  }
+
+  async fn stub_generation_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/images/generations"))
+      .and(header("authorization", "Bearer test-key"))
+      .and(body_partial_json(json!({
+        "model": "step-1x-medium",
+        "prompt": "A red fox in snow",
+        "size": "512x512",
+        "n": 2,
+        "response_format": "b64_json",
+        "seed": 42,
+        "steps": 30,
+      })))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "created": 1,
+        "data": [
+          { "finish_reason": "success", "seed": 42, "b64_json": tiny_png_b64() },
+          { "finish_reason": "content_filtered", "seed": 43 },
+        ],
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+    server
+  }
+
+  fn stub_node(server: &MockServer) -> TextToImageNode {
+    let client = StepFunSpecializedClient::new("test-key", Some(server.uri())).unwrap();
+    TextToImageNode::new("fox", "step-1x-medium")
+      .with_prompt("A {{ animal }} in snow")
+      .with_size("512x512")
+      .with_count(2)
+      .with_seed(42)
+      .with_steps(30)
+      .with_provider(Arc::new(client))
+  }
+
+  fn animal_inputs() -> AsyncNodeInputs {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "animal".to_string(),
+      FlowValue::Json(Value::String("red fox".to_string())),
+    );
+    inputs
+  }
+
+  #[tokio::test]
+  async fn saves_images_and_reports_filtered_entries_as_warnings() {
+    let server = stub_generation_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let pattern = format!("{}/{{name}}_{{seed}}.{{ext}}", dir.path().display());
+    let node = stub_node(&server)
+      .with_output_path(&pattern)
+      .with_allow_filtered(true);
+
+    let outputs = node.execute(&animal_inputs()).await.unwrap();
+
+    let json_of = |key: &str| match outputs.get(key) {
+      Some(FlowValue::Json(value)) => value.clone(),
+      other => panic!("missing {key}: {other:?}"),
+    };
+    let saved = dir.path().join("fox_42.png");
+    assert_eq!(json_of("image_paths"), json!([saved.to_string_lossy()]));
+    assert!(std::fs::read(&saved).unwrap().starts_with(b"\x89PNG"));
+    assert_eq!(json_of("seeds"), json!([42, 43]));
+    assert_eq!(
+      json_of("finish_reasons"),
+      json!(["success", "content_filtered"])
+    );
+    assert_eq!(json_of("warnings")[0]["index"], json!(1));
+    assert!(
+      json_of("fox_image")
+        .as_str()
+        .unwrap()
+        .starts_with("data:image/png;base64,")
+    );
+  }
+
+  #[tokio::test]
+  async fn filtered_images_fail_unless_allowed() {
+    let server = stub_generation_server().await;
+    let err = stub_node(&server)
+      .execute(&animal_inputs())
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("content_filtered"), "{err}");
+  }
 
   /// Q1.3.3: without a configured API key, the real image generation
   /// path must surface a real error — no more silent 1x1 PNG fallback.
@@ -11,6 +11,7 @@ use agentflow_core::{
   value::FlowValue,
 };
 use agentflow_llm::{AgentFlow, TtsRequest};
+use agentflow_nodes::common::template::{RenderOptions, render};
 use async_trait::async_trait;
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use serde::{Deserialize, Serialize};
@@ -46,7 +47,6 @@ pub struct TTSNode {
   pub response_format: AudioResponseFormat,
   pub speed: Option<f32>,
   pub output_key: String,
-  pub input_keys: Vec<String>,
 }
 
 impl TTSNode {
@@ -59,16 +59,6 @@ impl TTSNode {
       response_format: Default::default(),
       speed: None,
       output_key: format!("{}_output", name),
-      input_keys: vec![],
-    }
-  }
-
-  fn flow_value_to_string(value: &FlowValue) -> String {
-    match value {
-      FlowValue::Json(Value::String(s)) => s.clone(),
-      FlowValue::Json(v) => v.to_string().trim_matches('"').to_string(),
-      FlowValue::File { path, .. } => path.to_string_lossy().to_string(),
-      FlowValue::Url { url, .. } => url.clone(),
     }
   }
 }
@@ -84,13 +74,7 @@ impl AsyncNode for TTSNode {
         message: format!("Failed to initialize AgentFlow LLM service: {}", e),
       })?;
 
-    let mut resolved_input = self.input_template.clone();
-    for key in &self.input_keys {
-      if let Some(value) = inputs.get(key) {
-        let placeholder = format!("{{{{{}}}}}", key);
-        resolved_input = resolved_input.replace(&placeholder, &Self::flow_value_to_string(value));
-      }
-    }
+    let resolved_input = render(&self.input_template, inputs, &RenderOptions::lenient())?;
 
     // P-LLM.3: route through the modality dispatcher. The registry
     // entry for `self.model` decides which vendor handles the call
@@ -156,7 +140,6 @@ mod tests {
       response_format: AudioResponseFormat::Mp3,
       speed: Some(1.0),
       output_key: "audio_output".to_string(),
-      input_keys: vec![],
     };
 
     let inputs = AsyncNodeInputs::new();
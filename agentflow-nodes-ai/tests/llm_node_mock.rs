@@ -0,0 +1,231 @@
+//! `LlmNode` against the in-memory mock provider: streaming, usage/model
+//! outputs, image attachments and schema-parsed `response_json`.
+//!
+//! The model registry and the mock response queue are process-global, so
+//! every test seeds them under `serial_gate()` and re-runs
+//! `AgentFlow::init_with_config` before executing the node.
+
+use agentflow_core::events::{EventListener, WorkflowEvent};
+use agentflow_core::node_context::NodeContext;
+use agentflow_core::{async_node::AsyncNode, value::FlowValue};
+use agentflow_llm::AgentFlow;
+use agentflow_nodes_ai::LlmNode;
+use agentflow_nodes_ai::nodes::llm::{LlmStreamObserver, LlmStreamProgress};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+const TEXT_MODEL: &str = "mock-llm-node";
+const VISION_MODEL: &str = "mock-llm-node-vision";
+
+fn serial_gate() -> &'static tokio::sync::Mutex<()> {
+  static GATE: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+  GATE.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+async fn reset_mock_with_responses(responses: &[&str]) {
+  let config_path = std::env::temp_dir().join(format!(
+    "agentflow-nodes-ai-llm-mock-{}.yml",
+    std::process::id()
+  ));
+  std::fs::write(
+    &config_path,
+    format!(
+      r#"
+models:
+  {TEXT_MODEL}:
+    vendor: mock
+    type: text
+    model_id: {TEXT_MODEL}
+  {VISION_MODEL}:
+    vendor: mock
+    type: chat
+    accepts: [text, image]
+    model_id: {VISION_MODEL}
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#
+    ),
+  )
+  .unwrap();
+  let config_path = config_path.to_str().unwrap().to_string();
+
+  // SAFETY: callers hold `serial_gate()`, so no other test touches these
+  // variables concurrently.
+  unsafe {
+    std::env::set_var(
+      "AGENTFLOW_MOCK_RESPONSES",
+      serde_json::to_string(responses).unwrap(),
+    );
+    // `LlmNode::execute` re-runs `AgentFlow::init()`, which reads this.
+    std::env::set_var("AGENTFLOW_MODELS_CONFIG", &config_path);
+  }
+  AgentFlow::init_with_config(&config_path).await.unwrap();
+}
+
+fn inputs(pairs: &[(&str, Value)]) -> HashMap<String, FlowValue> {
+  pairs
+    .iter()
+    .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+    .collect()
+}
+
+#[derive(Default)]
+struct RecordingObserver {
+  chunks: Mutex<Vec<(usize, String, bool)>>,
+}
+
+impl LlmStreamObserver for RecordingObserver {
+  fn on_chunk(&self, progress: &LlmStreamProgress<'_>) {
+    self.chunks.lock().unwrap().push((
+      progress.index,
+      progress.delta.to_string(),
+      progress.is_final,
+    ));
+  }
+}
+
+#[tokio::test]
+async fn reports_model_and_usage_alongside_output() {
+  let _guard = serial_gate().lock().await;
+  reset_mock_with_responses(&["four words right here"]).await;
+
+  let outputs = LlmNode::new()
+    .execute(&inputs(&[
+      ("prompt", json!("Say something about {{ topic }}")),
+      ("topic", json!("rust")),
+      ("model", json!(TEXT_MODEL)),
+    ]))
+    .await
+    .unwrap();
+
+  assert_eq!(
+    outputs["output"],
+    FlowValue::Json(json!("four words right here"))
+  );
+  assert_eq!(outputs["model"], FlowValue::Json(json!(TEXT_MODEL)));
+  assert_eq!(
+    outputs["usage"],
+    FlowValue::Json(json!({
+      "prompt_tokens": 50,
+      "completion_tokens": 4,
+      "total_tokens": 54,
+    }))
+  );
+  assert!(!outputs.contains_key("response_json"));
+}
+
+#[tokio::test]
+async fn streaming_collects_chunks_and_notifies_the_observer() {
+  let _guard = serial_gate().lock().await;
+  reset_mock_with_responses(&["streamed answer"]).await;
+  let observer = Arc::new(RecordingObserver::default());
+
+  let outputs = LlmNode::new()
+    .with_streaming(true)
+    .with_observer(observer.clone())
+    .execute(&inputs(&[
+      ("prompt", json!("Stream please")),
+      ("model", json!(TEXT_MODEL)),
+    ]))
+    .await
+    .unwrap();
+
+  assert_eq!(outputs["output"], FlowValue::Json(json!("streamed answer")));
+  assert_eq!(outputs["model"], FlowValue::Json(json!(TEXT_MODEL)));
+  assert_eq!(
+    *observer.chunks.lock().unwrap(),
+    vec![(0, "streamed answer".to_string(), true)]
+  );
+}
+
+#[tokio::test]
+async fn streaming_reports_progress_to_the_running_flow() {
+  struct ProgressRecorder(Mutex<Vec<Value>>);
+  impl EventListener for ProgressRecorder {
+    fn on_event(&self, event: &WorkflowEvent) {
+      if let WorkflowEvent::NodeProgress { detail, .. } = event {
+        self.0.lock().unwrap().push(detail.clone());
+      }
+    }
+  }
+
+  let _guard = serial_gate().lock().await;
+  reset_mock_with_responses(&["streamed answer"]).await;
+  let recorder = Arc::new(ProgressRecorder(Mutex::new(Vec::new())));
+  let context = NodeContext::new("wf", "ask", Some(recorder.clone()), None);
+
+  let node = LlmNode::new().with_streaming(true);
+  let inputs = inputs(&[
+    ("prompt", json!("Stream please")),
+    ("model", json!(TEXT_MODEL)),
+  ]);
+  context.scope(node.execute(&inputs)).await.unwrap();
+
+  let details = recorder.0.lock().unwrap();
+  let last = details.last().expect("a final progress event");
+  assert_eq!(last["event"], "llm.stream");
+  assert_eq!(last["chunks"], 1);
+  assert_eq!(last["received_chars"], "streamed answer".len());
+}
+
+#[tokio::test]
+async fn images_are_sent_to_a_multimodal_model() {
+  let _guard = serial_gate().lock().await;
+  reset_mock_with_responses(&["a red square"]).await;
+
+  let outputs = LlmNode::new()
+    .with_images(vec!["photo".to_string()])
+    .execute(&inputs(&[
+      ("prompt", json!("What is in the picture?")),
+      ("photo", json!("https://example.com/square.png")),
+      ("model", json!(VISION_MODEL)),
+    ]))
+    .await
+    .unwrap();
+
+  assert_eq!(outputs["output"], FlowValue::Json(json!("a red square")));
+
+  // A text-only model rejects the same request before calling the provider.
+  reset_mock_with_responses(&["unused"]).await;
+  let err = LlmNode::new()
+    .with_images(vec!["photo".to_string()])
+    .execute(&inputs(&[
+      ("prompt", json!("What is in the picture?")),
+      ("photo", json!("https://example.com/square.png")),
+      ("model", json!(TEXT_MODEL)),
+    ]))
+    .await
+    .unwrap_err();
+  assert!(err.to_string().contains("LLM execution failed"), "{err}");
+}
+
+#[tokio::test]
+async fn response_schema_output_is_parsed_into_response_json() {
+  let _guard = serial_gate().lock().await;
+  reset_mock_with_responses(&[r#"{"title": "AgentFlow", "stars": 5}"#]).await;
+  let schema = json!({
+    "type": "object",
+    "properties": {
+      "title": { "type": "string" },
+      "stars": { "type": "integer" }
+    },
+    "required": ["title", "stars"]
+  });
+  let node = LlmNode::new().with_response_schema(schema);
+  let request = inputs(&[
+    ("prompt", json!("Rate the project")),
+    ("model", json!(TEXT_MODEL)),
+  ]);
+
+  let outputs = node.execute(&request).await.unwrap();
+  assert_eq!(
+    outputs["response_json"],
+    FlowValue::Json(json!({ "title": "AgentFlow", "stars": 5 }))
+  );
+
+  reset_mock_with_responses(&["not json"]).await;
+  let err = node.execute(&request).await.unwrap_err();
+  assert!(err.to_string().contains("not valid JSON"), "{err}");
+}
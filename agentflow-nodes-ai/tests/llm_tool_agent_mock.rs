@@ -0,0 +1,292 @@
+//! `LlmToolAgentNode` driven by the mock provider's scripted tool calls,
+//! against a local `ToolRegistry` and an in-process MCP server.
+//!
+//! The mock provider reads its response and tool-call queues from the
+//! environment when the registry is rebuilt, so every test seeds them under
+//! `serial_gate()` and re-runs `AgentFlow::init_with_config` first.
+
+use agentflow_core::{async_node::AsyncNode, error::AgentFlowError, value::FlowValue};
+use agentflow_llm::AgentFlow;
+use agentflow_nodes_ai::{AgentToolSource, LlmToolAgentNode};
+use agentflow_tools::{Tool, ToolError, ToolOutput, ToolRegistry};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+const MODEL: &str = "mock-tool-agent";
+
+fn serial_gate() -> &'static tokio::sync::Mutex<()> {
+  static GATE: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+  GATE.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+/// Seeds one text response and one batch of tool calls per model turn.
+async fn script_turns(turns: &[(&str, Value)]) {
+  let config_path = std::env::temp_dir().join(format!(
+    "agentflow-nodes-ai-tool-agent-mock-{}.yml",
+    std::process::id()
+  ));
+  std::fs::write(
+    &config_path,
+    format!(
+      r#"
+models:
+  {MODEL}:
+    vendor: mock
+    type: chat
+    model_id: {MODEL}
+providers:
+  mock:
+    api_key_env: MOCK_API_KEY
+"#
+    ),
+  )
+  .unwrap();
+  let config_path = config_path.to_str().unwrap().to_string();
+  let responses: Vec<&str> = turns.iter().map(|(text, _)| *text).collect();
+  let tool_calls: Vec<&Value> = turns.iter().map(|(_, calls)| calls).collect();
+
+  // SAFETY: callers hold `serial_gate()`, so no other test touches these
+  // variables concurrently.
+  unsafe {
+    std::env::set_var(
+      "AGENTFLOW_MOCK_RESPONSES",
+      serde_json::to_string(&responses).unwrap(),
+    );
+    std::env::set_var(
+      "AGENTFLOW_MOCK_TOOL_CALLS",
+      serde_json::to_string(&tool_calls).unwrap(),
+    );
+    std::env::set_var("AGENTFLOW_MODELS_CONFIG", &config_path);
+  }
+  AgentFlow::init_with_config(&config_path).await.unwrap();
+}
+
+fn call(id: &str, name: &str, arguments: Value) -> Value {
+  json!([{ "id": id, "name": name, "arguments": arguments }])
+}
+
+fn prompt(text: &str) -> HashMap<String, FlowValue> {
+  HashMap::from([("prompt".to_string(), FlowValue::Json(json!(text)))])
+}
+
+/// Answers `weather` and `convert` the same way the in-process MCP server
+/// below does.
+fn answer(name: &str, arguments: &Value) -> String {
+  match name {
+    "weather" => format!("{}: 21C", arguments["city"].as_str().unwrap_or("?")),
+    "convert" => format!(
+      "{}F",
+      arguments["celsius"].as_f64().unwrap_or(0.0) * 9.0 / 5.0 + 32.0
+    ),
+    other => format!("unknown tool {other}"),
+  }
+}
+
+struct ScriptedTool {
+  name: &'static str,
+}
+
+#[async_trait]
+impl Tool for ScriptedTool {
+  fn name(&self) -> &str {
+    self.name
+  }
+
+  fn description(&self) -> &str {
+    "scripted test tool"
+  }
+
+  fn parameters_schema(&self) -> Value {
+    json!({ "type": "object" })
+  }
+
+  async fn execute(&self, params: Value) -> Result<ToolOutput, ToolError> {
+    Ok(ToolOutput::success(answer(self.name, &params)))
+  }
+}
+
+fn registry() -> AgentToolSource {
+  let mut registry = ToolRegistry::new();
+  registry.register(Arc::new(ScriptedTool { name: "weather" }));
+  registry.register(Arc::new(ScriptedTool { name: "convert" }));
+  AgentToolSource::Registry(Arc::new(registry))
+}
+
+fn two_tool_turns() -> Vec<(&'static str, Value)> {
+  vec![
+    ("", call("call_0", "weather", json!({ "city": "Paris" }))),
+    ("", call("call_1", "convert", json!({ "celsius": 21 }))),
+    ("It is 69.8F in Paris.", json!([])),
+  ]
+}
+
+fn assert_two_tool_trace(outputs: &HashMap<String, FlowValue>) {
+  assert_eq!(
+    outputs["final_answer"],
+    FlowValue::Json(json!("It is 69.8F in Paris."))
+  );
+  assert_eq!(outputs["iterations"], FlowValue::Json(json!(3)));
+  let FlowValue::Json(Value::Array(trace)) = &outputs["tool_trace"] else {
+    panic!("tool_trace should be an array");
+  };
+  assert_eq!(trace.len(), 2);
+  assert_eq!(trace[0]["call"]["name"], json!("weather"));
+  assert_eq!(trace[0]["result"]["content"], json!("Paris: 21C"));
+  assert_eq!(trace[1]["call"]["name"], json!("convert"));
+  assert_eq!(trace[1]["result"]["content"], json!("69.8F"));
+  assert_eq!(trace[1]["result"]["is_error"], json!(false));
+}
+
+#[tokio::test]
+async fn registry_tools_run_a_two_call_loop() {
+  let _guard = serial_gate().lock().await;
+  script_turns(&two_tool_turns()).await;
+
+  let outputs = LlmToolAgentNode::new(MODEL, registry())
+    .with_system_prompt("Answer using the tools.")
+    .execute(&prompt("How warm is Paris in Fahrenheit?"))
+    .await
+    .unwrap();
+
+  assert_two_tool_trace(&outputs);
+}
+
+#[tokio::test]
+async fn exhausted_budget_fails_with_the_partial_trace() {
+  let _guard = serial_gate().lock().await;
+  script_turns(&two_tool_turns()).await;
+
+  let err = LlmToolAgentNode::new(MODEL, registry())
+    .with_max_iterations(2)
+    .execute(&prompt("How warm is Paris in Fahrenheit?"))
+    .await
+    .unwrap_err();
+
+  let AgentFlowError::NodePartialExecutionFailed {
+    message,
+    partial_outputs,
+  } = err
+  else {
+    panic!("expected a partial execution failure, got {err:?}");
+  };
+  assert!(message.contains("2 iteration"), "{message}");
+  let FlowValue::Json(Value::Array(trace)) = &partial_outputs["tool_trace"] else {
+    panic!("partial tool_trace should be an array");
+  };
+  assert_eq!(trace.len(), 2);
+}
+
+#[tokio::test]
+async fn unknown_tools_are_reported_back_to_the_model() {
+  let _guard = serial_gate().lock().await;
+  script_turns(&[
+    ("", call("call_0", "missing", json!({}))),
+    ("I could not find that tool.", json!([])),
+  ])
+  .await;
+
+  let outputs = LlmToolAgentNode::new(MODEL, registry())
+    .execute(&prompt("Use a tool that does not exist"))
+    .await
+    .unwrap();
+
+  let FlowValue::Json(Value::Array(trace)) = &outputs["tool_trace"] else {
+    panic!("tool_trace should be an array");
+  };
+  assert_eq!(trace[0]["result"]["is_error"], json!(true));
+  assert_eq!(
+    outputs["final_answer"],
+    FlowValue::Json(json!("I could not find that tool."))
+  );
+}
+
+#[cfg(feature = "mcp")]
+mod mcp {
+  use super::*;
+  use agentflow_mcp::MCPResult;
+  use agentflow_mcp::client::ClientBuilder;
+  use agentflow_mcp::transport::{Transport, TransportType};
+
+  /// A JSON-RPC MCP server answering in-process, exposing the same two tools
+  /// as the registry above.
+  struct InProcessServer {
+    connected: bool,
+  }
+
+  #[async_trait]
+  impl Transport for InProcessServer {
+    async fn connect(&mut self) -> MCPResult<()> {
+      self.connected = true;
+      Ok(())
+    }
+
+    async fn send_message(&self, request: Value) -> MCPResult<Value> {
+      let result = match request["method"].as_str().unwrap_or_default() {
+        "initialize" => json!({
+          "protocolVersion": "2024-11-05",
+          "capabilities": { "tools": {} },
+          "serverInfo": { "name": "in-process", "version": "0.1.0" }
+        }),
+        "tools/list" => json!({
+          "tools": [
+            { "name": "weather", "description": "Current temperature", "inputSchema": { "type": "object" } },
+            { "name": "convert", "description": "Celsius to Fahrenheit", "inputSchema": { "type": "object" } }
+          ]
+        }),
+        "tools/call" => {
+          let params = &request["params"];
+          let text = answer(
+            params["name"].as_str().unwrap_or_default(),
+            &params["arguments"],
+          );
+          json!({ "content": [{ "type": "text", "text": text }] })
+        }
+        other => json!({ "unexpected": other }),
+      };
+      Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+    }
+
+    async fn send_notification(&self, _notification: Value) -> MCPResult<()> {
+      Ok(())
+    }
+
+    async fn receive_message(&self) -> MCPResult<Option<Value>> {
+      Ok(None)
+    }
+
+    async fn disconnect(&mut self) -> MCPResult<()> {
+      self.connected = false;
+      Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+      self.connected
+    }
+
+    fn transport_type(&self) -> TransportType {
+      TransportType::Stdio
+    }
+  }
+
+  #[tokio::test]
+  async fn mcp_tools_run_a_two_call_loop() {
+    let _guard = serial_gate().lock().await;
+    script_turns(&two_tool_turns()).await;
+    let mut client = ClientBuilder::new()
+      .with_transport(InProcessServer { connected: false })
+      .build()
+      .await
+      .unwrap();
+    client.connect().await.unwrap();
+    let source = AgentToolSource::Mcp(Arc::new(tokio::sync::Mutex::new(client)));
+
+    let outputs = LlmToolAgentNode::new(MODEL, source)
+      .execute(&prompt("How warm is Paris in Fahrenheit?"))
+      .await
+      .unwrap();
+
+    assert_two_tool_trace(&outputs);
+  }
+}
@@ -0,0 +1,179 @@
+//! `McpToolNode` built from workflow parameters, calling an in-process MCP
+//! server registered in the connection pool under the node's server spec.
+//!
+//! The spec names a command that does not exist, so every call going through
+//! the pool (rather than spawning the server) is what makes these pass.
+
+#![cfg(feature = "mcp")]
+
+use agentflow_core::{async_node::AsyncNode, value::FlowValue};
+use agentflow_mcp::MCPResult;
+use agentflow_mcp::client::ClientBuilder;
+use agentflow_mcp::transport::{Transport, TransportType};
+use agentflow_nodes_ai::nodes::mcp_tool::register_connection;
+use agentflow_nodes_ai::{McpServerSpec, McpToolNode};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const PNG_BYTES: &[u8] = b"\x89PNG\r\n\x1a\nnot-really-a-png";
+
+/// Answers `echo` with its arguments as JSON text, `draw` with one image and
+/// `fail` with an error result.
+struct InProcessServer {
+  connected: bool,
+  calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Transport for InProcessServer {
+  async fn connect(&mut self) -> MCPResult<()> {
+    self.connected = true;
+    Ok(())
+  }
+
+  async fn send_message(&self, request: Value) -> MCPResult<Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+      "initialize" => json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "in-process", "version": "0.1.0" }
+      }),
+      "tools/call" => {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let params = &request["params"];
+        match params["name"].as_str().unwrap_or_default() {
+          "echo" => json!({
+            "content": [{ "type": "text", "text": params["arguments"].to_string() }]
+          }),
+          "draw" => json!({
+            "content": [
+              { "type": "text", "text": "a drawing" },
+              { "type": "image", "data": STANDARD.encode(PNG_BYTES), "mimeType": "image/png" }
+            ]
+          }),
+          _ => json!({
+            "content": [{ "type": "text", "text": "no such tool" }],
+            "isError": true
+          }),
+        }
+      }
+      other => json!({ "unexpected": other }),
+    };
+    Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+  }
+
+  async fn send_notification(&self, _notification: Value) -> MCPResult<()> {
+    Ok(())
+  }
+
+  async fn receive_message(&self) -> MCPResult<Option<Value>> {
+    Ok(None)
+  }
+
+  async fn disconnect(&mut self) -> MCPResult<()> {
+    self.connected = false;
+    Ok(())
+  }
+
+  fn is_connected(&self) -> bool {
+    self.connected
+  }
+
+  fn transport_type(&self) -> TransportType {
+    TransportType::Stdio
+  }
+}
+
+/// Registers an in-process server under a stdio spec unique to `name` and
+/// returns that spec's `server_command` plus the server's call counter.
+async fn pooled_server(name: &str) -> (Value, Arc<AtomicUsize>) {
+  let command = vec![format!("agentflow-test-missing-mcp-server-{name}")];
+  let calls = Arc::new(AtomicUsize::new(0));
+  let mut client = ClientBuilder::new()
+    .with_transport(InProcessServer {
+      connected: false,
+      calls: calls.clone(),
+    })
+    .build()
+    .await
+    .unwrap();
+  client.connect().await.unwrap();
+  register_connection(McpServerSpec::stdio(command.clone()), client).await;
+  (json!(command), calls)
+}
+
+fn inputs(pairs: &[(&str, Value)]) -> HashMap<String, FlowValue> {
+  pairs
+    .iter()
+    .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+    .collect()
+}
+
+#[tokio::test]
+async fn renders_arguments_and_reuses_the_pooled_connection() {
+  let (server_command, calls) = pooled_server("echo").await;
+  let node = McpToolNode::from_params(&json!({
+    "server_command": server_command,
+    "tool_name": "echo",
+    "arguments": { "query": "papers about {{ topic }}", "limit": "{{ limit }}" }
+  }))
+  .unwrap();
+  let request = inputs(&[("topic", json!("rust")), ("limit", json!(3))]);
+
+  let outputs = node.execute(&request).await.unwrap();
+  node.execute(&request).await.unwrap();
+
+  assert_eq!(
+    outputs["json"],
+    FlowValue::Json(json!({ "query": "papers about rust", "limit": 3 }))
+  );
+  assert!(matches!(
+    &outputs["text"],
+    FlowValue::Json(Value::String(_))
+  ));
+  assert_eq!(outputs["images"], FlowValue::Json(json!([])));
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn image_content_is_saved_to_the_output_dir() {
+  let (server_command, _) = pooled_server("draw").await;
+  let output_dir =
+    std::env::temp_dir().join(format!("agentflow-mcp-tool-images-{}", std::process::id()));
+  let node = McpToolNode::from_params(&json!({
+    "server_command": server_command,
+    "tool_name": "draw",
+    "output_dir": output_dir.to_str().unwrap()
+  }))
+  .unwrap();
+
+  let outputs = node.execute(&HashMap::new()).await.unwrap();
+
+  assert_eq!(outputs["text"], FlowValue::Json(json!("a drawing")));
+  assert!(!outputs.contains_key("json"));
+  let FlowValue::Json(Value::Array(images)) = &outputs["images"] else {
+    panic!("images should be an array");
+  };
+  let path = images[0].as_str().unwrap();
+  assert!(path.ends_with(".png"), "{path}");
+  assert!(path.starts_with(output_dir.to_str().unwrap()));
+  assert_eq!(std::fs::read(path).unwrap(), PNG_BYTES);
+}
+
+#[tokio::test]
+async fn tool_error_results_fail_the_node() {
+  let (server_command, _) = pooled_server("fail").await;
+  let node = McpToolNode::from_params(&json!({
+    "server_command": server_command,
+    "tool_name": "explode"
+  }))
+  .unwrap();
+
+  let err = node.execute(&HashMap::new()).await.unwrap_err();
+
+  assert!(err.to_string().contains("no such tool"), "{err}");
+}
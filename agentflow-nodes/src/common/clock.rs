@@ -0,0 +1,23 @@
+//! Wall-clock abstraction for the timing nodes.
+//!
+//! `schedule_gate` and `throttle` read the current time through [`Clock`]
+//! so tests can pin it; sleeping still goes through `tokio::time`, which
+//! tests can pause.
+
+use std::fmt::Debug;
+
+use chrono::{DateTime, Utc};
+
+pub trait Clock: Debug + Send + Sync {
+  fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> DateTime<Utc> {
+    Utc::now()
+  }
+}
@@ -1,2 +1,5 @@
+pub mod clock;
+pub mod process_env;
+pub mod template;
 pub mod tera_helpers;
 pub mod utils;
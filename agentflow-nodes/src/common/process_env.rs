@@ -0,0 +1,180 @@
+//! Environment variables and working directory for a process a node spawns.
+//!
+//! A workflow sets them per node with the `env:` and `cwd:` blocks; the
+//! factory resolves them into a [`ProcessEnv`] that the `command`, `mcp` and
+//! `mcp_tool` nodes apply to their child. An `env` value is either text, with
+//! `${NAME}` expanded from the host environment, or a `$secret` /
+//! `$secret_file` reference, which becomes a [`SecretValue`]: it is masked in
+//! `Debug` output and in every persisted step output, so a child echoing its
+//! environment does not leak it. `${NAME}` expansions are plain text and are
+//! not masked; credentials belong in `$secret` references.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use agentflow_core::{SecretValue, error::AgentFlowError, secret::is_secret_reference};
+use serde_json::Value;
+
+use crate::common::utils::expand_env_vars;
+
+/// One environment variable's value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EnvValue {
+  Plain(String),
+  Secret(SecretValue),
+}
+
+impl EnvValue {
+  /// Resolves a configured value: `${NAME}` is expanded in a string, and a
+  /// `$secret` / `$secret_file` reference is read into a secret. Numbers and
+  /// booleans are written out.
+  pub fn resolve(value: &Value) -> Result<Self, AgentFlowError> {
+    match value {
+      Value::String(text) => Ok(Self::Plain(expand_env_vars(text)?)),
+      Value::Number(number) => Ok(Self::Plain(number.to_string())),
+      Value::Bool(flag) => Ok(Self::Plain(flag.to_string())),
+      value if is_secret_reference(value) => Ok(Self::Secret(SecretValue::resolve(value)?)),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "expected a string or a {{\"$secret\": ..}} / {{\"$secret_file\": ..}} reference, got {other}"
+        ),
+      }),
+    }
+  }
+
+  /// The value handed to the child. Keep the borrow short and never log it.
+  pub fn expose(&self) -> &str {
+    match self {
+      Self::Plain(text) => text,
+      Self::Secret(secret) => secret.expose_secret(),
+    }
+  }
+
+  pub fn is_secret(&self) -> bool {
+    matches!(self, Self::Secret(_))
+  }
+}
+
+impl From<&str> for EnvValue {
+  fn from(text: &str) -> Self {
+    Self::Plain(text.to_string())
+  }
+}
+
+impl From<String> for EnvValue {
+  fn from(text: String) -> Self {
+    Self::Plain(text)
+  }
+}
+
+impl From<SecretValue> for EnvValue {
+  fn from(secret: SecretValue) -> Self {
+    Self::Secret(secret)
+  }
+}
+
+/// The `env:` and `cwd:` of a node, resolved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ProcessEnv {
+  /// Set on the child on top of whatever the node passes through itself.
+  pub vars: BTreeMap<String, EnvValue>,
+  /// The child's working directory; `None` keeps the current one.
+  pub cwd: Option<PathBuf>,
+}
+
+impl ProcessEnv {
+  /// Resolves every `env` value (see [`EnvValue::resolve`]) and expands
+  /// `${NAME}` in `cwd`.
+  pub fn resolve(env: &BTreeMap<String, Value>, cwd: Option<&str>) -> Result<Self, AgentFlowError> {
+    let vars = env
+      .iter()
+      .map(|(name, value)| {
+        if name.is_empty() || name.contains(['=', '\0']) {
+          return Err(AgentFlowError::ConfigurationError {
+            message: format!("'{name}' is not a valid environment variable name"),
+          });
+        }
+        let value = EnvValue::resolve(value).map_err(|err| AgentFlowError::ConfigurationError {
+          message: format!("env '{name}': {err}"),
+        })?;
+        Ok((name.clone(), value))
+      })
+      .collect::<Result<_, AgentFlowError>>()?;
+    let cwd = cwd
+      .map(|cwd| expand_env_vars(cwd).map(PathBuf::from))
+      .transpose()?;
+    Ok(Self { vars, cwd })
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.vars.is_empty() && self.cwd.is_none()
+  }
+
+  /// The variables with their values, for a spawn API taking strings.
+  pub fn exposed_vars(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .vars
+      .iter()
+      .map(|(name, value)| (name.as_str(), value.expose()))
+  }
+
+  /// Sets the variables and working directory on `command`.
+  pub fn apply(&self, command: &mut tokio::process::Command) {
+    command.envs(self.exposed_vars());
+    if let Some(cwd) = &self.cwd {
+      command.current_dir(cwd);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn values_expand_variables_and_resolve_secrets() {
+    // SAFETY: the names are unique to this test.
+    unsafe {
+      std::env::set_var("AGENTFLOW_PROCESS_ENV_TEST_HOME", "/srv/app");
+      std::env::set_var("AGENTFLOW_PROCESS_ENV_TEST_TOKEN", "tok-process-env-test");
+    }
+    let env = BTreeMap::from([
+      (
+        "DATA_DIR".to_string(),
+        json!("${AGENTFLOW_PROCESS_ENV_TEST_HOME}/data"),
+      ),
+      ("RETRIES".to_string(), json!(3)),
+      (
+        "TOKEN".to_string(),
+        json!({ "$secret": "AGENTFLOW_PROCESS_ENV_TEST_TOKEN" }),
+      ),
+    ]);
+
+    let resolved =
+      ProcessEnv::resolve(&env, Some("${AGENTFLOW_PROCESS_ENV_TEST_HOME}/work")).unwrap();
+
+    assert_eq!(
+      resolved.exposed_vars().collect::<Vec<_>>(),
+      vec![
+        ("DATA_DIR", "/srv/app/data"),
+        ("RETRIES", "3"),
+        ("TOKEN", "tok-process-env-test"),
+      ]
+    );
+    assert!(resolved.vars["TOKEN"].is_secret());
+    assert_eq!(resolved.cwd, Some(PathBuf::from("/srv/app/work")));
+    assert!(!format!("{resolved:?}").contains("tok-process-env-test"));
+  }
+
+  #[test]
+  fn bad_names_and_values_are_rejected() {
+    let err =
+      ProcessEnv::resolve(&BTreeMap::from([("A=B".to_string(), json!("x"))]), None).unwrap_err();
+    assert!(err.to_string().contains("not a valid"), "{err}");
+
+    let err =
+      ProcessEnv::resolve(&BTreeMap::from([("LIST".to_string(), json!(["a"]))]), None).unwrap_err();
+    assert!(err.to_string().contains("env 'LIST'"), "{err}");
+  }
+}
@@ -0,0 +1,420 @@
+//! `{{ name }}` placeholder rendering shared by the built-in nodes.
+//!
+//! A placeholder names a node input, optionally followed by a path into its
+//! JSON value: `{{ user.name }}`, `{{ items.0.title }}` and
+//! `{{ items[0].title }}` all work. An input whose name itself contains dots
+//! wins over a path. Two namespaces sit behind the inputs:
+//!
+//! - `{{ flow.workflow_id }}`, `{{ flow.node_id }}` and `{{ flow.run_dir }}`
+//!   read the running node's [`NodeContext`];
+//! - `{{ env.NAME }}` reads environment variable `NAME`, but only when the
+//!   caller opts in with [`RenderOptions::with_env`].
+//!
+//! Write `\{{` for a literal `{{`. Substituted values can be escaped for the
+//! text they land in (see [`Escape`]); the template text itself never is.
+//!
+//! `TemplateNode` keeps Tera for loops and filters, and its plain
+//! placeholders render the same as this module's.
+
+use crate::common::utils::flow_value_to_string;
+use agentflow_core::{
+  async_node::AsyncNodeInputs, error::AgentFlowError, node_context::NodeContext, value::FlowValue,
+};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// What a placeholder without a value renders as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingVariable {
+  /// Fail with a [`AgentFlowError::NodeInputError`] naming the placeholder.
+  Error,
+  /// Leave the placeholder as written, so literal braces survive.
+  Keep,
+  /// Render nothing.
+  Empty,
+}
+
+/// How substituted values are escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+  None,
+  /// Percent-encode, for a value placed in a URL path or query.
+  Url,
+  /// JSON string escaping without the surrounding quotes, for a value
+  /// placed inside a JSON string literal.
+  Json,
+  /// `&`, `<`, `>`, `"` and `'` as character references, for a value
+  /// placed in HTML text or a quoted attribute.
+  Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+  pub missing: MissingVariable,
+  pub escape: Escape,
+  /// Whether `{{ env.NAME }}` may read the process environment.
+  pub env: bool,
+}
+
+impl RenderOptions {
+  /// Missing variables are errors; the mode for URLs, commands and paths.
+  pub const fn strict() -> Self {
+    Self {
+      missing: MissingVariable::Error,
+      escape: Escape::None,
+      env: false,
+    }
+  }
+
+  /// Missing variables are left as written; the mode for prompts and
+  /// other prose.
+  pub const fn lenient() -> Self {
+    Self {
+      missing: MissingVariable::Keep,
+      escape: Escape::None,
+      env: false,
+    }
+  }
+
+  pub const fn with_missing(mut self, missing: MissingVariable) -> Self {
+    self.missing = missing;
+    self
+  }
+
+  pub const fn with_escape(mut self, escape: Escape) -> Self {
+    self.escape = escape;
+    self
+  }
+
+  pub const fn with_env(mut self, env: bool) -> Self {
+    self.env = env;
+    self
+  }
+}
+
+/// Replaces every `{{ path }}` in `template` with the value it names.
+pub fn render(
+  template: &str,
+  inputs: &AsyncNodeInputs,
+  options: &RenderOptions,
+) -> Result<String, AgentFlowError> {
+  let mut rendered = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    if rest[..start].ends_with('\\') {
+      rendered.push_str(&rest[..start - 1]);
+      rendered.push_str("{{");
+      rest = &rest[start + 2..];
+      continue;
+    }
+    let Some(len) = rest[start + 2..].find("}}") else {
+      break;
+    };
+    let end = start + 2 + len + 2;
+    let path = rest[start + 2..start + 2 + len].trim();
+    rendered.push_str(&rest[..start]);
+    match resolve(path, inputs, options) {
+      Some(value) => rendered.push_str(&escape(&value, options.escape)),
+      None => match options.missing {
+        MissingVariable::Error => {
+          return Err(AgentFlowError::NodeInputError {
+            message: format!("Placeholder '{{{{{path}}}}}' has no matching input"),
+          });
+        }
+        MissingVariable::Keep => rendered.push_str(&rest[start..end]),
+        MissingVariable::Empty => {}
+      },
+    }
+    rest = &rest[end..];
+  }
+  rendered.push_str(rest);
+  Ok(rendered)
+}
+
+/// Renders a URL template strictly, percent-encoding every value placed in
+/// the path, query or fragment. The scheme and authority are rendered as
+/// written, so `https://{{ host }}/items/{{ id }}` and a leading
+/// `{{ base_url }}/items/{{ id }}` both keep their base URL intact.
+pub fn render_url(template: &str, inputs: &AsyncNodeInputs) -> Result<String, AgentFlowError> {
+  let split = origin_end(template);
+  let mut rendered = render(&template[..split], inputs, &RenderOptions::strict())?;
+  rendered.push_str(&render(
+    &template[split..],
+    inputs,
+    &RenderOptions::strict().with_escape(Escape::Url),
+  )?);
+  Ok(rendered)
+}
+
+/// Byte offset where the scheme and authority of a URL template end.
+fn origin_end(template: &str) -> usize {
+  if let Some(scheme_end) = template.find("://") {
+    let authority = scheme_end + 3;
+    return template[authority..]
+      .find(['/', '?', '#'])
+      .map_or(template.len(), |offset| authority + offset);
+  }
+  if template.starts_with("{{")
+    && let Some(close) = template.find("}}")
+  {
+    return close + 2;
+  }
+  0
+}
+
+/// The JSON value `path` names in `inputs`, for callers that substitute a
+/// whole value rather than its string form. Files and URLs come back as
+/// their path / URL string.
+pub fn lookup(path: &str, inputs: &AsyncNodeInputs) -> Option<Value> {
+  if let Some(value) = inputs.get(path) {
+    return Some(flow_value_to_json(value));
+  }
+  let (root, segments) = split_path(path)?;
+  let FlowValue::Json(json) = inputs.get(root)? else {
+    return None;
+  };
+  let mut current = json;
+  for segment in &segments {
+    current = match current {
+      Value::Object(map) => map.get(*segment)?,
+      Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+      _ => return None,
+    };
+  }
+  Some(current.clone())
+}
+
+/// `Some(path)` when `text` is nothing but a single `{{ path }}`.
+pub fn whole_placeholder(text: &str) -> Option<&str> {
+  let path = text.trim().strip_prefix("{{")?.strip_suffix("}}")?.trim();
+  (!path.is_empty() && !path.contains("{{") && !path.contains("}}")).then_some(path)
+}
+
+fn resolve(path: &str, inputs: &AsyncNodeInputs, options: &RenderOptions) -> Option<String> {
+  if let Some(value) = lookup(path, inputs) {
+    return Some(match value {
+      Value::String(text) => text,
+      other => other.to_string(),
+    });
+  }
+  if !inputs.contains_key("flow")
+    && let Some(field) = path.strip_prefix("flow.")
+  {
+    let context = NodeContext::current()?;
+    return match field {
+      "workflow_id" => Some(context.workflow_id),
+      "node_id" => Some(context.node_id),
+      "run_dir" => context
+        .run_dir()
+        .map(|dir| dir.to_string_lossy().into_owned()),
+      _ => None,
+    };
+  }
+  if options.env
+    && !inputs.contains_key("env")
+    && let Some(name) = path.strip_prefix("env.")
+  {
+    return std::env::var(name).ok();
+  }
+  None
+}
+
+/// Splits `a.b[0].c` into `a` and `[b, 0, c]`; `None` for a bare name.
+fn split_path(path: &str) -> Option<(&str, Vec<&str>)> {
+  let root_end = path.find(['.', '['])?;
+  let root = &path[..root_end];
+  let mut segments = Vec::new();
+  for part in path[root_end..].split('.') {
+    let (key, mut indices) = part
+      .split_once('[')
+      .map_or((part, ""), |(key, rest)| (key, rest));
+    if !key.is_empty() {
+      segments.push(key);
+    }
+    while !indices.is_empty() {
+      let (index, rest) = indices.split_once(']')?;
+      segments.push(index.trim());
+      indices = rest.strip_prefix('[').unwrap_or(rest);
+    }
+  }
+  (!root.is_empty()).then_some((root, segments))
+}
+
+fn flow_value_to_json(value: &FlowValue) -> Value {
+  match value {
+    FlowValue::Json(value) => value.clone(),
+    other => Value::String(flow_value_to_string(other)),
+  }
+}
+
+fn escape(value: &str, escape: Escape) -> Cow<'_, str> {
+  match escape {
+    Escape::None => Cow::Borrowed(value),
+    Escape::Url => urlencoding::encode(value),
+    Escape::Json => {
+      let quoted = Value::String(value.to_string()).to_string();
+      Cow::Owned(quoted[1..quoted.len() - 1].to_string())
+    }
+    Escape::Html if !value.contains(['&', '<', '>', '"', '\'']) => Cow::Borrowed(value),
+    Escape::Html => {
+      let mut escaped = String::with_capacity(value.len() + 16);
+      for c in value.chars() {
+        match c {
+          '&' => escaped.push_str("&amp;"),
+          '<' => escaped.push_str("&lt;"),
+          '>' => escaped.push_str("&gt;"),
+          '"' => escaped.push_str("&quot;"),
+          '\'' => escaped.push_str("&#39;"),
+          c => escaped.push(c),
+        }
+      }
+      Cow::Owned(escaped)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn inputs() -> AsyncNodeInputs {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "user".to_string(),
+      FlowValue::Json(json!({ "name": "Ada", "tags": ["math", "engines"] })),
+    );
+    inputs.insert("count".to_string(), FlowValue::Json(json!(3)));
+    inputs.insert("a.b".to_string(), FlowValue::Json(json!("dotted key")));
+    inputs
+  }
+
+  #[test]
+  fn paths_walk_objects_and_arrays() {
+    let rendered = render(
+      "{{user.name}} likes {{ user.tags.0 }} and {{ user.tags[1] }} x{{ count }}; {{a.b}}",
+      &inputs(),
+      &RenderOptions::strict(),
+    )
+    .unwrap();
+
+    assert_eq!(rendered, "Ada likes math and engines x3; dotted key");
+    assert_eq!(
+      lookup("user.tags", &inputs()),
+      Some(json!(["math", "engines"]))
+    );
+    assert_eq!(lookup("user.tags.7", &inputs()), None);
+  }
+
+  #[test]
+  fn missing_variable_modes() {
+    let template = "Hi {{ user.email }}!";
+
+    let err = render(template, &inputs(), &RenderOptions::strict()).unwrap_err();
+    assert!(
+      err.to_string().contains("'{{user.email}}'"),
+      "error should name the variable: {err}"
+    );
+    assert_eq!(
+      render(template, &inputs(), &RenderOptions::lenient()).unwrap(),
+      "Hi {{ user.email }}!"
+    );
+    assert_eq!(
+      render(
+        template,
+        &inputs(),
+        &RenderOptions::strict().with_missing(MissingVariable::Empty)
+      )
+      .unwrap(),
+      "Hi !"
+    );
+  }
+
+  #[test]
+  fn url_templates_encode_values_but_not_the_origin() {
+    let mut inputs = inputs();
+    inputs.insert("id".to_string(), FlowValue::Json(json!("a/b?c")));
+    inputs.insert(
+      "base".to_string(),
+      FlowValue::Json(json!("https://api.example.com/v1")),
+    );
+    inputs.insert("host".to_string(), FlowValue::Json(json!("example.com")));
+
+    assert_eq!(
+      render_url("https://{{ host }}/items/{{ id }}?q={{ id }}", &inputs).unwrap(),
+      "https://example.com/items/a%2Fb%3Fc?q=a%2Fb%3Fc"
+    );
+    assert_eq!(
+      render_url("{{ base }}/items/{{ id }}", &inputs).unwrap(),
+      "https://api.example.com/v1/items/a%2Fb%3Fc"
+    );
+    assert!(render_url("https://x/{{ missing }}", &inputs).is_err());
+  }
+
+  #[test]
+  fn escaping_and_env_opt_in() {
+    let mut inputs = inputs();
+    inputs.insert("q".to_string(), FlowValue::Json(json!("a b&\"c\"")));
+
+    assert_eq!(
+      render("\\{{ q }} {{ q }}", &inputs, &RenderOptions::strict()).unwrap(),
+      "{{ q }} a b&\"c\""
+    );
+    assert_eq!(
+      render(
+        "?q={{ q }}",
+        &inputs,
+        &RenderOptions::strict().with_escape(Escape::Url)
+      )
+      .unwrap(),
+      "?q=a%20b%26%22c%22"
+    );
+    assert_eq!(
+      render(
+        r#"{"q": "{{ q }}"}"#,
+        &inputs,
+        &RenderOptions::strict().with_escape(Escape::Json)
+      )
+      .unwrap(),
+      r#"{"q": "a b&\"c\""}"#
+    );
+    assert_eq!(
+      render(
+        "<a title=\"{{ q }}\">{{ q }}</a>",
+        &inputs,
+        &RenderOptions::strict().with_escape(Escape::Html)
+      )
+      .unwrap(),
+      "<a title=\"a b&amp;&quot;c&quot;\">a b&amp;&quot;c&quot;</a>"
+    );
+
+    let path = std::env::var("PATH").unwrap();
+    assert!(render("{{ env.PATH }}", &inputs, &RenderOptions::strict()).is_err());
+    assert_eq!(
+      render(
+        "{{ env.PATH }}",
+        &inputs,
+        &RenderOptions::strict().with_env(true)
+      )
+      .unwrap(),
+      path
+    );
+  }
+
+  #[tokio::test]
+  async fn flow_context_fills_the_flow_namespace() {
+    let context = NodeContext::new("wf", "fetch", None, None).with_run_dir("/tmp/runs/r1");
+    let rendered = context
+      .scope(async {
+        render(
+          "{{ flow.workflow_id }}/{{ flow.node_id }} in {{ flow.run_dir }}",
+          &inputs(),
+          &RenderOptions::strict(),
+        )
+      })
+      .await
+      .unwrap();
+
+    assert_eq!(rendered, "wf/fetch in /tmp/runs/r1");
+  }
+}
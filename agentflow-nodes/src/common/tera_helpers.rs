@@ -1,8 +1,81 @@
-use agentflow_core::value::FlowValue;
+use agentflow_core::{async_node::AsyncNodeInputs, value::FlowValue};
 use serde_json::{Map as JsonMap, Value as JsonValue};
 use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use tera::{Result as TeraResult, Tera, Value as TeraValue};
 
+/// Global Tera instance with custom filters and functions, shared by
+/// `template` and `document_render` nodes
+static TERA_INSTANCE: OnceLock<Mutex<Tera>> = OnceLock::new();
+
+fn get_tera() -> &'static Mutex<Tera> {
+  TERA_INSTANCE.get_or_init(|| {
+    let mut tera = Tera::default();
+    register_custom_filters(&mut tera);
+    register_custom_functions(&mut tera);
+    Mutex::new(tera)
+  })
+}
+
+/// Lenient rendering fills one missing top-level variable per attempt; the
+/// bound keeps a pathological template from looping forever.
+const MAX_LENIENT_FILLS: usize = 64;
+
+/// A context holding every input under its own name.
+pub fn inputs_context(inputs: &AsyncNodeInputs) -> tera::Context {
+  let mut context = tera::Context::new();
+  for (key, value) in inputs {
+    context.insert(key, &flow_value_to_tera_value(value));
+  }
+  context
+}
+
+/// Renders `template` against `context` on the shared Tera instance. When
+/// `strict` is off, top-level variables missing from the context render as
+/// an empty string. An error is formatted as `" at line L, column C:
+/// <reason>"`, or `": <reason>"` without a position.
+pub fn render_template(
+  template: &str,
+  context: &mut tera::Context,
+  strict: bool,
+) -> Result<String, String> {
+  // Q3.8.2: avoid the `.lock().unwrap()` panic on a poisoned mutex.
+  // A poisoned Tera mutex means a previous render panicked mid-flight;
+  // the engine's internal state may be inconsistent. Rather than
+  // crashing the workflow, surface the poison as an
+  // `AsyncExecutionError` so the caller's retry / error-handling
+  // logic gets a chance. We recover the guard via `into_inner` so
+  // subsequent renders proceed (Tera::render_str takes `&mut self`
+  // only because Tera caches parsed templates internally; the cache
+  // is best-effort and a stale entry is acceptable).
+  let mut tera_guard = get_tera().lock().unwrap_or_else(|poisoned| {
+    eprintln!(
+      "[agentflow-nodes:template] Tera template engine mutex was poisoned by a prior panic; recovering and continuing"
+    );
+    poisoned.into_inner()
+  });
+
+  let mut filled = 0;
+  loop {
+    match tera_guard.render_str(template, context) {
+      Ok(rendered) => return Ok(rendered),
+      Err(err) => {
+        if !strict
+          && filled < MAX_LENIENT_FILLS
+          && let Some(name) = missing_variable(&err)
+          && !name.contains(['.', '['])
+          && !context.contains_key(&name)
+        {
+          context.insert(name, "");
+          filled += 1;
+          continue;
+        }
+        return Err(describe_render_error(template, &err));
+      }
+    }
+  }
+}
+
 /// Convert FlowValue to Tera-compatible value
 pub fn flow_value_to_tera_value(value: &FlowValue) -> TeraValue {
   match value {
@@ -71,6 +144,22 @@ pub fn register_custom_filters(tera: &mut Tera) {
     },
   );
 
+  // `{{ value | json }}` / `{{ value | json(pretty=true) }}` — the short
+  // spelling template authors reach for first.
+  tera.register_filter(
+    "json",
+    |value: &TeraValue, args: &HashMap<String, TeraValue>| -> TeraResult<TeraValue> {
+      let pretty = matches!(args.get("pretty"), Some(TeraValue::Bool(true)));
+      let json_str = if pretty {
+        serde_json::to_string_pretty(value)
+      } else {
+        serde_json::to_string(value)
+      }
+      .unwrap_or_else(|_| format!("{:?}", value));
+      Ok(TeraValue::String(json_str))
+    },
+  );
+
   // Filter to convert to JSON string
   tera.register_filter(
     "to_json",
@@ -102,6 +191,103 @@ pub fn register_custom_functions(tera: &mut Tera) {
   );
 }
 
+/// Tera's top-level error is usually just "Failed to render '__tera_one_off'";
+/// the useful part lives in the `source()` chain.
+fn error_chain(err: &tera::Error) -> Vec<String> {
+  let mut messages = vec![err.to_string()];
+  let mut source = std::error::Error::source(err);
+  while let Some(inner) = source {
+    messages.push(inner.to_string());
+    source = inner.source();
+  }
+  messages
+}
+
+/// The variable named by a "Variable `x` not found in context" error, if any.
+fn missing_variable(err: &tera::Error) -> Option<String> {
+  error_chain(err).iter().find_map(|message| {
+    let rest = message.split("Variable `").nth(1)?;
+    let (name, tail) = rest.split_once('`')?;
+    tail
+      .trim_start()
+      .starts_with("not found")
+      .then(|| name.to_string())
+  })
+}
+
+/// Formats a render error as `" at line L, column C: <reason>"`.
+///
+/// Parse errors carry a pest position (`--> L:C`). Runtime errors don't, so
+/// for a missing variable we point at its first use inside a tag instead.
+fn describe_render_error(template: &str, err: &tera::Error) -> String {
+  let chain = error_chain(err);
+  let reason = chain
+    .iter()
+    .rev()
+    .find(|message| !message.starts_with("Failed to "))
+    .or(chain.last())
+    .cloned()
+    .unwrap_or_default();
+
+  let position = chain
+    .iter()
+    .find_map(|message| parse_error_position(message))
+    .or_else(|| {
+      missing_variable(err).and_then(|name| {
+        let root = name.split(['.', '[']).next().unwrap_or(&name).to_string();
+        variable_position(template, &name).or_else(|| variable_position(template, &root))
+      })
+    });
+
+  // Pest messages are multi-line with an ASCII-art pointer; keep the
+  // last line, which states what was expected.
+  let reason = if reason.contains("-->") {
+    reason
+      .lines()
+      .rev()
+      .find(|line| !line.trim().is_empty())
+      .map(|line| line.trim_start_matches(['=', ' ']).to_string())
+      .unwrap_or(reason)
+  } else {
+    reason
+  };
+
+  match position {
+    Some((line, column)) => format!(" at line {line}, column {column}: {reason}"),
+    None => format!(": {reason}"),
+  }
+}
+
+fn parse_error_position(message: &str) -> Option<(usize, usize)> {
+  let rest = message.split("--> ").nth(1)?;
+  let location = rest.split_whitespace().next()?;
+  let (line, column) = location.split_once(':')?;
+  Some((line.parse().ok()?, column.parse().ok()?))
+}
+
+/// The 1-based line/column of the first `name` that sits inside a
+/// `{{ ... }}` or `{% ... %}` tag and isn't part of a longer identifier.
+fn variable_position(template: &str, name: &str) -> Option<(usize, usize)> {
+  let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+  for (offset, _) in template.match_indices(name) {
+    let before = &template[..offset];
+    let after = &template[offset + name.len()..];
+    if before.chars().next_back().is_some_and(is_ident)
+      || after.chars().next().is_some_and(is_ident)
+    {
+      continue;
+    }
+    let opened = before.rfind("{{").max(before.rfind("{%"));
+    let closed = before.rfind("}}").max(before.rfind("%}"));
+    if opened.is_some() && opened > closed {
+      let line = before.matches('\n').count() + 1;
+      let column = before.rsplit('\n').next().unwrap_or(before).chars().count() + 1;
+      return Some((line, column));
+    }
+  }
+  None
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -172,6 +358,7 @@ mod tests {
     assert!(tera.get_filter("flow_path").is_ok());
     assert!(tera.get_filter("json_pretty").is_ok());
     assert!(tera.get_filter("to_json").is_ok());
+    assert!(tera.get_filter("json").is_ok());
   }
 
   #[test]
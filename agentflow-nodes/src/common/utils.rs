@@ -111,3 +111,77 @@ pub async fn load_bytes_from_source(
       message: format!("Failed to read file at {}: {}", source, e),
     })
 }
+
+/// Strict [`template::render`](crate::common::template::render): replace
+/// `{{ key }}` / `{{ key.path }}` with the input value it names, failing on
+/// a placeholder without one.
+pub fn resolve_placeholders(
+  template: &str,
+  inputs: &agentflow_core::async_node::AsyncNodeInputs,
+) -> Result<String, AgentFlowError> {
+  crate::common::template::render(
+    template,
+    inputs,
+    &crate::common::template::RenderOptions::strict(),
+  )
+}
+
+/// A `GET` of the `url` input (with `{{key}}` placeholders, percent-encoded
+/// after the URL's origin) carrying the optional `headers` input object,
+/// whose values may use placeholders too.
+/// `node` names the caller in the missing-URL error (e.g. `Poll node 'p'`).
+pub fn get_request_from_inputs(
+  node: &str,
+  inputs: &agentflow_core::async_node::AsyncNodeInputs,
+) -> Result<agentflow_tools::builtin::HttpRequest, AgentFlowError> {
+  let url = match inputs.get("url") {
+    Some(FlowValue::Json(Value::String(url))) => crate::common::template::render_url(url, inputs)?,
+    _ => {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!("{} requires a string 'url' input", node),
+      });
+    }
+  };
+  let headers = match inputs.get("headers") {
+    Some(FlowValue::Json(Value::Object(headers))) => headers
+      .iter()
+      .map(|(name, value)| {
+        let value = match value {
+          Value::String(value) => value.clone(),
+          other => other.to_string(),
+        };
+        Ok((name.clone(), resolve_placeholders(&value, inputs)?))
+      })
+      .collect::<Result<_, AgentFlowError>>()?,
+    _ => Vec::new(),
+  };
+  Ok(agentflow_tools::builtin::HttpRequest {
+    method: "GET".to_string(),
+    url,
+    headers,
+    body: None,
+    timeout: None,
+  })
+}
+
+/// Replace `${NAME}` with the value of environment variable `NAME`; an
+/// unset variable is a configuration error. Text without `${` is returned
+/// unchanged, so literal `$` signs are fine.
+pub fn expand_env_vars(text: &str) -> Result<String, AgentFlowError> {
+  let mut expanded = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find("${") {
+    let Some(len) = rest[start + 2..].find('}') else {
+      break;
+    };
+    let name = rest[start + 2..start + 2 + len].trim();
+    let value = std::env::var(name).map_err(|_| AgentFlowError::ConfigurationError {
+      message: format!("Environment variable '{name}' referenced as '${{{name}}}' is not set"),
+    })?;
+    expanded.push_str(&rest[..start]);
+    expanded.push_str(&value);
+    rest = &rest[start + 2 + len + 1..];
+  }
+  expanded.push_str(rest);
+  Ok(expanded)
+}
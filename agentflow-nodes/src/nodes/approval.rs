@@ -0,0 +1,633 @@
+//! Approval node - pauses a flow until a human signs off.
+//!
+//! On execution the node announces the pending payload (the `content`
+//! input, else all inputs as an object) with a
+//! [`WorkflowEvent::NodeProgress`](agentflow_core::events::WorkflowEvent)
+//! event `{"event": "approval.pending", "prompt", "content", "channel", ..}`,
+//! then waits for a decision on its channel:
+//!
+//! - `file`: deletes any `sentinel_path` left from an earlier run, then
+//!   polls until the file exists, reads the decision and deletes the file
+//!   so a rerun can't reuse it;
+//! - `http` (`http` feature): binds a
+//!   [`WebhookWaitNode`](crate::nodes::webhook_wait::WebhookWaitNode)
+//!   listener and announces its `url`, which carries a random per-run
+//!   `token`; the first request to that URL decides;
+//! - `stdin`: prompts on the terminal; only available when stdin is
+//!   interactive (e.g. `agentflow workflow run` in a shell). Lines come
+//!   from one process-wide reader thread, so a wait that times out leaves
+//!   no blocked reader behind.
+//!
+//! A decision is JSON - `{"approved": bool, "feedback": "..", "content": ..}`
+//! (or `"decision": "approve" | "reject"`) - or plain text whose first word
+//! is `approve` / `yes` / `y` or `reject` / `no` / `n`, the rest being
+//! feedback. A decision's `content` replaces the payload, so reviewers can
+//! edit it.
+//!
+//! Without a decision within `timeout`, `on_timeout` applies: `approve`,
+//! `reject` or `error` (`TimeoutExceeded`, the default). Cancelling the
+//! flow stops the wait with `TaskCancelled`.
+//!
+//! Outputs `approved`, `feedback`, `content` and `decided_by` (`file`,
+//! `http`, `stdin` or `timeout`).
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  node_context,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use tokio::sync::{Mutex, mpsc};
+
+use crate::common::utils::flow_value_to_string;
+
+pub const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(3_600);
+pub const DEFAULT_SENTINEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where the decision comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalChannel {
+  File {
+    sentinel_path: PathBuf,
+  },
+  #[cfg(feature = "http")]
+  Http {
+    host: String,
+    port: u16,
+    path: String,
+  },
+  Stdin,
+}
+
+impl ApprovalChannel {
+  fn name(&self) -> &'static str {
+    match self {
+      Self::File { .. } => "file",
+      #[cfg(feature = "http")]
+      Self::Http { .. } => "http",
+      Self::Stdin => "stdin",
+    }
+  }
+}
+
+/// What a timeout means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPolicy {
+  Approve,
+  Reject,
+  Error,
+}
+
+impl TimeoutPolicy {
+  pub fn parse(policy: &str) -> Result<Self, AgentFlowError> {
+    match policy.trim().to_ascii_lowercase().as_str() {
+      "approve" => Ok(Self::Approve),
+      "reject" => Ok(Self::Reject),
+      "error" => Ok(Self::Error),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Unknown on_timeout policy '{}' (expected approve, reject or error)",
+          other
+        ),
+      }),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApprovalNode {
+  pub name: String,
+  pub channel: ApprovalChannel,
+  pub prompt: Option<String>,
+  pub timeout: Duration,
+  pub on_timeout: TimeoutPolicy,
+  pub poll_interval: Duration,
+}
+
+/// A reviewer's answer.
+#[derive(Debug, Clone, PartialEq)]
+struct Decision {
+  approved: bool,
+  feedback: String,
+  content: Option<Value>,
+}
+
+impl ApprovalNode {
+  pub fn new(name: &str, channel: ApprovalChannel) -> Self {
+    Self {
+      name: name.to_string(),
+      channel,
+      prompt: None,
+      timeout: DEFAULT_APPROVAL_TIMEOUT,
+      on_timeout: TimeoutPolicy::Error,
+      poll_interval: DEFAULT_SENTINEL_POLL_INTERVAL,
+    }
+  }
+
+  /// Waits for a decision written to `sentinel_path`.
+  pub fn file(name: &str, sentinel_path: impl Into<PathBuf>) -> Self {
+    Self::new(
+      name,
+      ApprovalChannel::File {
+        sentinel_path: sentinel_path.into(),
+      },
+    )
+  }
+
+  pub fn with_prompt(mut self, prompt: &str) -> Self {
+    self.prompt = Some(prompt.to_string());
+    self
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn with_on_timeout(mut self, policy: TimeoutPolicy) -> Self {
+    self.on_timeout = policy;
+    self
+  }
+
+  /// How often the `file` channel checks for the sentinel.
+  pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+    self.poll_interval = interval;
+    self
+  }
+
+  fn payload(inputs: &AsyncNodeInputs) -> Value {
+    let to_json = |value: &FlowValue| match value {
+      FlowValue::Json(value) => value.clone(),
+      other => Value::String(flow_value_to_string(other)),
+    };
+    match inputs.get("content") {
+      Some(content) => to_json(content),
+      None => Value::Object(
+        inputs
+          .iter()
+          .map(|(key, value)| (key.clone(), to_json(value)))
+          .collect::<Map<_, _>>(),
+      ),
+    }
+  }
+
+  fn prompt_text(&self) -> String {
+    self
+      .prompt
+      .clone()
+      .unwrap_or_else(|| format!("Approve the output of '{}'?", self.name))
+  }
+
+  fn announce(&self, content: &Value, extra: &[(&str, Value)]) {
+    let mut detail = json!({
+      "event": "approval.pending",
+      "prompt": self.prompt_text(),
+      "content": content,
+      "channel": self.channel.name(),
+    });
+    for (key, value) in extra {
+      detail[*key] = value.clone();
+    }
+    node_context::emit_progress(detail);
+  }
+
+  async fn wait_for_file(&self, sentinel_path: &PathBuf) -> Result<Decision, AgentFlowError> {
+    // A decision left over from an earlier run must not approve this one.
+    match tokio::fs::remove_file(sentinel_path).await {
+      Ok(()) => eprintln!(
+        "⚠️  Approval node '{}': removed stale {}",
+        self.name,
+        sentinel_path.display()
+      ),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+      Err(err) => {
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Approval node '{}': failed to clear stale {}: {}",
+            self.name,
+            sentinel_path.display(),
+            err
+          ),
+        });
+      }
+    }
+    loop {
+      match tokio::fs::read_to_string(sentinel_path).await {
+        Ok(text) => {
+          let decision = parse_decision(&text).ok_or_else(|| AgentFlowError::NodeInputError {
+            message: format!(
+              "Approval node '{}': {} holds no recognizable decision",
+              self.name,
+              sentinel_path.display()
+            ),
+          });
+          let _ = tokio::fs::remove_file(sentinel_path).await;
+          return decision;
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+          tokio::time::sleep(self.poll_interval).await;
+        }
+        Err(err) => {
+          return Err(AgentFlowError::AsyncExecutionError {
+            message: format!(
+              "Approval node '{}': failed to read {}: {}",
+              self.name,
+              sentinel_path.display(),
+              err
+            ),
+          });
+        }
+      }
+    }
+  }
+
+  async fn wait_for_stdin(&self, content: &Value) -> Result<Decision, AgentFlowError> {
+    let pretty = serde_json::to_string_pretty(content).unwrap_or_else(|_| content.to_string());
+    let mut lines = stdin_lines().lock().await;
+    // Lines typed while nothing was waiting don't answer this prompt.
+    while lines.try_recv().is_ok() {}
+    eprintln!("\n✋ {}\n{}\n", self.prompt_text(), pretty);
+    loop {
+      eprint!("Approve? [y/n] (optionally followed by feedback): ");
+      match lines.recv().await {
+        Some(Ok(line)) if !line.is_empty() => {
+          if let Some(decision) = parse_decision(&line) {
+            return Ok(decision);
+          }
+        }
+        Some(Err(e)) => {
+          return Err(AgentFlowError::AsyncExecutionError {
+            message: format!("Approval node '{}': failed to read stdin: {}", self.name, e),
+          });
+        }
+        Some(Ok(_)) | None => {
+          return Err(AgentFlowError::NodeInputError {
+            message: format!(
+              "Approval node '{}': stdin closed before a decision",
+              self.name
+            ),
+          });
+        }
+      }
+    }
+  }
+
+  /// Waits on the configured channel; `None` on timeout.
+  async fn decide(
+    &self,
+    content: &Value,
+  ) -> Result<Option<(Decision, &'static str)>, AgentFlowError> {
+    let channel = self.channel.name();
+    let wait = async {
+      match &self.channel {
+        ApprovalChannel::File { sentinel_path } => {
+          self.announce(
+            content,
+            &[("sentinel_path", json!(sentinel_path.to_string_lossy()))],
+          );
+          println!(
+            "✋ Approval node '{}' waiting for {}",
+            self.name,
+            sentinel_path.display()
+          );
+          self.wait_for_file(sentinel_path).await
+        }
+        #[cfg(feature = "http")]
+        ApprovalChannel::Http { host, port, path } => {
+          let pending = crate::nodes::webhook_wait::WebhookWaitNode::new(&self.name)
+            .with_host(host)
+            .with_port(*port)
+            .with_path(path)
+            .listen()
+            .await?;
+          self.announce(
+            content,
+            &[("url", json!(pending.url)), ("token", json!(pending.token))],
+          );
+          println!(
+            "✋ Approval node '{}' waiting for a callback on {}",
+            self.name, pending.url
+          );
+          let callback = pending.receive().await?;
+          let body = match (callback.get("json"), callback.get("body")) {
+            (Some(FlowValue::Json(json)), _) => json.to_string(),
+            (None, Some(FlowValue::Json(Value::String(body)))) => body.clone(),
+            _ => String::new(),
+          };
+          parse_decision(&body).ok_or_else(|| AgentFlowError::NodeInputError {
+            message: format!(
+              "Approval node '{}': callback body holds no recognizable decision",
+              self.name
+            ),
+          })
+        }
+        ApprovalChannel::Stdin => {
+          if !std::io::stdin().is_terminal() {
+            return Err(AgentFlowError::ConfigurationError {
+              message: format!(
+                "Approval node '{}': the stdin channel needs an interactive terminal; \
+                 use the file or http channel for unattended runs",
+                self.name
+              ),
+            });
+          }
+          self.announce(content, &[]);
+          self.wait_for_stdin(content).await
+        }
+      }
+    };
+
+    tokio::select! {
+      decision = wait => decision.map(|decision| Some((decision, channel))),
+      _ = tokio::time::sleep(self.timeout) => Ok(None),
+      _ = node_context::cancelled() => Err(AgentFlowError::TaskCancelled),
+    }
+  }
+}
+
+/// Parses a JSON or plain-text decision.
+type StdinLines = Mutex<mpsc::UnboundedReceiver<std::io::Result<String>>>;
+
+/// Lines read by a single process-wide stdin thread; an empty string
+/// marks end of input. A blocking `read_line` can't be cancelled, so
+/// sharing one reader keeps a timed-out wait from leaking a thread.
+fn stdin_lines() -> &'static StdinLines {
+  static LINES: OnceLock<StdinLines> = OnceLock::new();
+  LINES.get_or_init(|| {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+      loop {
+        let mut line = String::new();
+        let read = std::io::stdin().read_line(&mut line);
+        let done = !matches!(read, Ok(read) if read > 0);
+        if sender.send(read.map(|_| line)).is_err() || done {
+          break;
+        }
+      }
+    });
+    Mutex::new(receiver)
+  })
+}
+
+fn parse_decision(text: &str) -> Option<Decision> {
+  let text = text.trim();
+  if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(text) {
+    let approved = match (object.get("approved"), object.get("decision")) {
+      (Some(Value::Bool(approved)), _) => *approved,
+      (_, Some(Value::String(decision))) => parse_verdict(decision)?,
+      _ => return None,
+    };
+    return Some(Decision {
+      approved,
+      feedback: object
+        .get("feedback")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string(),
+      content: object.get("content").cloned(),
+    });
+  }
+  let (verdict, feedback) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+  Some(Decision {
+    approved: parse_verdict(verdict)?,
+    feedback: feedback.trim().to_string(),
+    content: None,
+  })
+}
+
+fn parse_verdict(word: &str) -> Option<bool> {
+  match word
+    .trim_end_matches([':', ',', '.'])
+    .to_ascii_lowercase()
+    .as_str()
+  {
+    "approve" | "approved" | "yes" | "y" | "ok" => Some(true),
+    "reject" | "rejected" | "no" | "n" => Some(false),
+    _ => None,
+  }
+}
+
+#[async_trait]
+impl AsyncNode for ApprovalNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let content = Self::payload(inputs);
+    let (decision, decided_by) = match self.decide(&content).await? {
+      Some(decided) => decided,
+      None => {
+        let timed_out = |approved: bool| Decision {
+          approved,
+          feedback: format!(
+            "no decision within {}ms; on_timeout applied",
+            self.timeout.as_millis()
+          ),
+          content: None,
+        };
+        let decision = match self.on_timeout {
+          TimeoutPolicy::Approve => timed_out(true),
+          TimeoutPolicy::Reject => timed_out(false),
+          TimeoutPolicy::Error => {
+            return Err(AgentFlowError::TimeoutExceeded {
+              duration_ms: self.timeout.as_millis() as u64,
+            });
+          }
+        };
+        (decision, "timeout")
+      }
+    };
+
+    println!(
+      "{} Approval node '{}' {} (via {})",
+      if decision.approved { "✅" } else { "🚫" },
+      self.name,
+      if decision.approved {
+        "approved"
+      } else {
+        "rejected"
+      },
+      decided_by
+    );
+    let mut outputs = HashMap::new();
+    outputs.insert(
+      "approved".to_string(),
+      FlowValue::Json(json!(decision.approved)),
+    );
+    outputs.insert(
+      "feedback".to_string(),
+      FlowValue::Json(json!(decision.feedback)),
+    );
+    outputs.insert(
+      "content".to_string(),
+      FlowValue::Json(decision.content.unwrap_or(content)),
+    );
+    outputs.insert("decided_by".to_string(), FlowValue::Json(json!(decided_by)));
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_core::events::{EventListener, WorkflowEvent};
+  use agentflow_core::node_context::NodeContext;
+  use std::sync::{Arc, Mutex};
+
+  #[derive(Default)]
+  struct Recorder(Mutex<Vec<Value>>);
+
+  impl EventListener for Recorder {
+    fn on_event(&self, event: &WorkflowEvent) {
+      if let WorkflowEvent::NodeProgress { detail, .. } = event {
+        self.0.lock().unwrap().push(detail.clone());
+      }
+    }
+  }
+
+  fn draft() -> AsyncNodeInputs {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "content".to_string(),
+      FlowValue::Json(json!({ "to": "ops@example.com", "subject": "Deploy v2" })),
+    );
+    inputs
+  }
+
+  fn file_node(dir: &tempfile::TempDir) -> (ApprovalNode, PathBuf) {
+    let sentinel = dir.path().join("approve.json");
+    let node = ApprovalNode::file("review", &sentinel)
+      .with_prompt("Send the deploy email?")
+      .with_poll_interval(Duration::from_millis(10))
+      .with_timeout(Duration::from_secs(10));
+    (node, sentinel)
+  }
+
+  #[tokio::test]
+  async fn file_sentinel_approves_with_an_edited_payload() {
+    let dir = tempfile::tempdir().unwrap();
+    let (node, sentinel) = file_node(&dir);
+    let writer = {
+      let sentinel = sentinel.clone();
+      tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let decision = json!({
+          "approved": true,
+          "feedback": "ship it",
+          "content": { "to": "ops@example.com", "subject": "Deploy v2.0.1" },
+        });
+        std::fs::write(sentinel, decision.to_string()).unwrap();
+      })
+    };
+
+    let recorder = Arc::new(Recorder::default());
+    let outputs = NodeContext::new("run", "review", Some(recorder.clone()), None)
+      .scope(node.execute(&draft()))
+      .await
+      .unwrap();
+    writer.await.unwrap();
+
+    assert_eq!(outputs["approved"], FlowValue::Json(json!(true)));
+    assert_eq!(outputs["feedback"], FlowValue::Json(json!("ship it")));
+    assert_eq!(outputs["decided_by"], FlowValue::Json(json!("file")));
+    assert_eq!(
+      outputs["content"],
+      FlowValue::Json(json!({ "to": "ops@example.com", "subject": "Deploy v2.0.1" }))
+    );
+    assert!(!sentinel.exists(), "the sentinel is consumed");
+
+    let events = recorder.0.lock().unwrap();
+    assert_eq!(events[0]["event"], json!("approval.pending"));
+    assert_eq!(events[0]["prompt"], json!("Send the deploy email?"));
+    assert_eq!(events[0]["content"]["subject"], json!("Deploy v2"));
+  }
+
+  #[tokio::test]
+  async fn plain_text_sentinel_rejects_with_feedback() {
+    let dir = tempfile::tempdir().unwrap();
+    let (node, sentinel) = file_node(&dir);
+    let writer = tokio::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(50)).await;
+      std::fs::write(&sentinel, "reject: wrong recipient\n").unwrap();
+    });
+
+    let outputs = node.execute(&draft()).await.unwrap();
+    writer.await.unwrap();
+    assert_eq!(outputs["approved"], FlowValue::Json(json!(false)));
+    assert_eq!(
+      outputs["feedback"],
+      FlowValue::Json(json!("wrong recipient"))
+    );
+    assert_eq!(
+      outputs["content"],
+      FlowValue::Json(json!({ "to": "ops@example.com", "subject": "Deploy v2" }))
+    );
+  }
+
+  #[tokio::test]
+  async fn a_stale_sentinel_does_not_decide() {
+    let dir = tempfile::tempdir().unwrap();
+    let (node, sentinel) = file_node(&dir);
+    std::fs::write(&sentinel, "approve").unwrap();
+
+    let outputs = node
+      .with_timeout(Duration::from_millis(50))
+      .with_on_timeout(TimeoutPolicy::Reject)
+      .execute(&draft())
+      .await
+      .unwrap();
+    assert_eq!(outputs["decided_by"], FlowValue::Json(json!("timeout")));
+    assert!(!sentinel.exists());
+  }
+
+  #[tokio::test]
+  async fn timeout_policies_approve_reject_or_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let (node, _) = file_node(&dir);
+    let node = node.with_timeout(Duration::from_millis(30));
+
+    for (policy, approved) in [
+      (TimeoutPolicy::Approve, true),
+      (TimeoutPolicy::Reject, false),
+    ] {
+      let outputs = node
+        .clone()
+        .with_on_timeout(policy)
+        .execute(&draft())
+        .await
+        .unwrap();
+      assert_eq!(outputs["approved"], FlowValue::Json(json!(approved)));
+      assert_eq!(outputs["decided_by"], FlowValue::Json(json!("timeout")));
+    }
+
+    let err = node
+      .with_on_timeout(TimeoutPolicy::Error)
+      .execute(&draft())
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::TimeoutExceeded { duration_ms: 30 }),
+      "{err}"
+    );
+  }
+
+  #[test]
+  fn decisions_parse_from_json_and_plain_text() {
+    assert_eq!(
+      parse_decision(r#"{"decision": "approve"}"#).map(|d| d.approved),
+      Some(true)
+    );
+    assert_eq!(parse_decision("y").map(|d| d.approved), Some(true));
+    assert_eq!(
+      parse_decision("No, too risky").unwrap().feedback,
+      "too risky"
+    );
+    assert!(parse_decision("maybe later").is_none());
+    assert!(TimeoutPolicy::parse("ignore").is_err());
+  }
+}
@@ -1,4 +1,4 @@
-use crate::common::utils::flow_value_to_string;
+use crate::common::template::{RenderOptions, render};
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
@@ -7,11 +7,14 @@ use agentflow_core::{
 use async_trait::async_trait;
 use flate2::read::GzDecoder;
 use regex::Regex;
+use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
+use std::time::Duration;
 use tar::Archive;
 
 // Compile-time-known regex patterns. Built lazily on first use, panic on
@@ -28,8 +31,81 @@ use tar::Archive;
   reason = "compile-time regex literals; covered by unit tests in module"
 )]
 static ARXIV_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
-  Regex::new(r"arxiv\.org/(?:abs|pdf)/(\d{4}\.\d{4,5})(?:v(\d+))?")
-    .expect("ARXIV_URL_RE pattern is malformed — this is a bug in agentflow-nodes")
+  Regex::new(
+    r"(?:arxiv\.org|ar5iv\.org|ar5iv\.labs\.arxiv\.org)/(?:abs|pdf|html)/([a-z\-]+(?:\.[A-Z]{2})?/\d{7}|\d{4}\.\d{4,5})(?:v(\d+))?",
+  )
+  .expect("ARXIV_URL_RE pattern is malformed — this is a bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static ARXIV_BARE_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(?i:arxiv:)?([a-z\-]+(?:\.[A-Z]{2})?/\d{7}|\d{4}\.\d{4,5})(?:v(\d+))?(?:\.pdf)?$")
+    .expect("ARXIV_BARE_ID_RE pattern is malformed — this is a bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static ATOM_ENTRY_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"(?s)<entry>(.*?)</entry>")
+    .expect("ATOM_ENTRY_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static ATOM_AUTHOR_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"(?s)<author>\s*<name>(.*?)</name>")
+    .expect("ATOM_AUTHOR_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static ATOM_CATEGORY_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r#"<category[^>]*\sterm="([^"]+)""#)
+    .expect("ATOM_CATEGORY_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static ATOM_PRIMARY_CATEGORY_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r#"<arxiv:primary_category[^>]*\sterm="([^"]+)""#)
+    .expect("ATOM_PRIMARY_CATEGORY_RE is malformed — bug in agentflow-nodes")
+});
+/// A numbered heading line in PDF-extracted text: `2`, `2.1`, or `3.` followed
+/// by a capitalised title without a trailing full stop.
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static TEXT_HEADING_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(\d{1,2}(?:\.\d{1,2}){0,2})\.?\s+([A-Z][A-Za-z0-9 ,:;&()'/\-]{1,80})$")
+    .expect("TEXT_HEADING_RE is malformed — bug in agentflow-nodes")
+});
+/// Headings of the LaTeXML (arXiv HTML / ar5iv) document model, in document
+/// order together with the paragraphs between them.
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time selector literal; covered by unit tests in module"
+)]
+static HTML_BLOCK_SELECTOR: LazyLock<Selector> = LazyLock::new(|| {
+  Selector::parse(
+    "h1.ltx_title_document, h6.ltx_title_abstract, .ltx_abstract p, \
+     h2.ltx_title_section, h3.ltx_title_subsection, h4.ltx_title_subsubsection, \
+     h2.ltx_title_appendix, h2.ltx_title_bibliography, .ltx_para p, li.ltx_bibitem",
+  )
+  .expect("HTML_BLOCK_SELECTOR is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time selector literal; covered by unit tests in module"
+)]
+static HTML_TAG_SELECTOR: LazyLock<Selector> = LazyLock::new(|| {
+  Selector::parse(".ltx_tag").expect("HTML_TAG_SELECTOR is malformed — bug in agentflow-nodes")
 });
 #[allow(
   clippy::expect_used,
@@ -69,20 +145,114 @@ static LATEX_TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
     .expect("LATEX_TAG_RE is malformed — bug in agentflow-nodes")
 });
 
+/// Fetches an arXiv paper: metadata from the export API, full text from the
+/// HTML rendering (falling back to ar5iv, then the PDF), and the text split
+/// into numbered sections.
+///
+/// Outputs `paper_id`, `version`, `metadata`, `full_text`, `sections`,
+/// `text_source` (`html` / `pdf`) and `cached`, plus the LaTeX outputs when
+/// `fetch_source` is set. Results are cached on disk per paper id and version
+/// under `cache_dir` (default `~/.agentflow/cache/arxiv`). A pinned version
+/// never changes, so its entry is kept; the entry for an unpinned id is
+/// refetched once it is older than `latest_ttl_secs` (default one day), so
+/// a newly published version is picked up.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArxivNode {
   pub name: String,
   pub url: String,
   pub fetch_source: Option<bool>,
   pub simplify_latex: Option<bool>,
+  /// Download and section the paper's text. Defaults to `true`.
+  #[serde(default)]
+  pub full_text: Option<bool>,
+  /// Read and write the on-disk cache. Defaults to `true`.
+  #[serde(default)]
+  pub cache: Option<bool>,
+  #[serde(default)]
+  pub cache_dir: Option<PathBuf>,
+  /// Maximum age of a cached unpinned (`-latest`) entry, in seconds.
+  #[serde(default)]
+  pub latest_ttl_secs: Option<u64>,
+  #[serde(default)]
+  pub endpoints: ArxivEndpoints,
+  #[serde(skip)]
+  client: Option<reqwest::Client>,
+}
+
+/// Base URLs the node talks to. Overridable for mirrors and tests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArxivEndpoints {
+  /// Atom export API, e.g. `http://export.arxiv.org/api/query`.
+  pub api: String,
+  /// HTML renderings tried in order; `{id}` is appended after a `/`.
+  pub html: Vec<String>,
+  pub pdf: String,
+}
+
+impl Default for ArxivEndpoints {
+  fn default() -> Self {
+    Self {
+      api: "http://export.arxiv.org/api/query".to_string(),
+      html: vec![
+        "https://arxiv.org/html".to_string(),
+        "https://ar5iv.labs.arxiv.org/html".to_string(),
+      ],
+      pdf: "https://arxiv.org/pdf".to_string(),
+    }
+  }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub struct ArxivPaper {
   pub paper_id: String,
   pub version: Option<u32>,
 }
 
+impl ArxivPaper {
+  /// `2312.07104v2`, or the bare id when no version is pinned.
+  pub fn versioned_id(&self) -> String {
+    match self.version {
+      Some(version) => format!("{}v{}", self.paper_id, version),
+      None => self.paper_id.clone(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArxivMetadata {
+  pub title: String,
+  pub authors: Vec<String>,
+  #[serde(rename = "abstract")]
+  pub abstract_text: String,
+  pub categories: Vec<String>,
+  pub primary_category: Option<String>,
+  pub published: Option<String>,
+  pub updated: Option<String>,
+  /// Latest version reported by the API.
+  pub version: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArxivSection {
+  /// `2`, `2.1`, ...; `None` for unnumbered parts such as the abstract.
+  pub number: Option<String>,
+  pub title: String,
+  /// 1 for sections, 2 for subsections, and so on.
+  pub level: usize,
+  pub content: String,
+}
+
+/// What the cache stores for one paper version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPaper {
+  paper_id: String,
+  version: Option<u32>,
+  metadata: ArxivMetadata,
+  full_text: Option<String>,
+  sections: Vec<ArxivSection>,
+  text_source: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct LatexSource {
   pub main_content: String,
@@ -95,15 +265,27 @@ impl AsyncNode for ArxivNode {
     let resolved_url = self.resolve_arxiv_url(inputs)?;
     let paper_info = self.fetch_arxiv_paper(&resolved_url).await?;
 
+    let (record, cached) = match self.load_cached(&paper_info).await {
+      Some(record) => {
+        println!("📦 Using cached arXiv paper {}", paper_info.versioned_id());
+        (record, true)
+      }
+      None => {
+        let record = self.fetch_record(&paper_info).await?;
+        self.store_cached(&paper_info, &record).await;
+        (record, false)
+      }
+    };
+
     let mut outputs = HashMap::new();
-    let source_url = format!("https://arxiv.org/abs/{}", paper_info.paper_id);
-    let version = paper_info.version.unwrap_or(1);
+    let source_url = format!("https://arxiv.org/abs/{}", record.paper_id);
+    let version = record.version.unwrap_or(1);
 
     outputs.insert(
       "paper_id".to_string(),
-      FlowValue::Json(Value::String(paper_info.paper_id.clone())),
+      FlowValue::Json(Value::String(record.paper_id.clone())),
     );
-    if let Some(version) = paper_info.version {
+    if let Some(version) = record.version {
       outputs.insert(
         "version".to_string(),
         FlowValue::Json(Value::String(version.to_string())),
@@ -117,6 +299,29 @@ impl AsyncNode for ArxivNode {
       "original_url".to_string(),
       FlowValue::Json(Value::String(resolved_url.clone())),
     );
+    outputs.insert(
+      "metadata".to_string(),
+      FlowValue::Json(json!(record.metadata)),
+    );
+    outputs.insert("cached".to_string(), FlowValue::Json(json!(cached)));
+    if let Some(full_text) = &record.full_text {
+      outputs.insert(
+        "full_text".to_string(),
+        FlowValue::Json(Value::String(full_text.clone())),
+      );
+      outputs.insert(
+        "sections".to_string(),
+        FlowValue::Json(json!(record.sections)),
+      );
+      outputs.insert(
+        "text_source".to_string(),
+        FlowValue::Json(json!(record.text_source)),
+      );
+    }
+    let paper_info = ArxivPaper {
+      paper_id: record.paper_id.clone(),
+      version: record.version,
+    };
 
     if self.fetch_source.unwrap_or(false) {
       match self
@@ -168,37 +373,77 @@ impl AsyncNode for ArxivNode {
 }
 
 impl ArxivNode {
-  fn resolve_arxiv_url(&self, inputs: &AsyncNodeInputs) -> Result<String, AgentFlowError> {
-    let mut url = self.url.clone();
-    for (key, value) in inputs {
-      // Support both {{ key }} and {{key}}
-      let placeholder_with_spaces = format!("{{{{ {} }}}}", key);
-      let placeholder_without_spaces = format!("{{{{{}}}}}", key);
-      let value_str = flow_value_to_string(value);
-
-      if url.contains(&placeholder_with_spaces) {
-        url = url.replace(&placeholder_with_spaces, &value_str);
-      }
-      if url.contains(&placeholder_without_spaces) {
-        url = url.replace(&placeholder_without_spaces, &value_str);
-      }
+  pub fn new(name: &str, url: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      url: url.to_string(),
+      fetch_source: None,
+      simplify_latex: None,
+      full_text: None,
+      cache: None,
+      cache_dir: None,
+      latest_ttl_secs: None,
+      endpoints: ArxivEndpoints::default(),
+      client: None,
     }
-    Ok(url)
+  }
+
+  pub fn with_fetch_source(mut self, fetch_source: bool) -> Self {
+    self.fetch_source = Some(fetch_source);
+    self
+  }
+
+  pub fn with_simplify_latex(mut self, simplify_latex: bool) -> Self {
+    self.simplify_latex = Some(simplify_latex);
+    self
+  }
+
+  pub fn with_full_text(mut self, full_text: bool) -> Self {
+    self.full_text = Some(full_text);
+    self
+  }
+
+  pub fn with_cache(mut self, cache: bool) -> Self {
+    self.cache = Some(cache);
+    self
+  }
+
+  pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+    self.cache_dir = Some(cache_dir.into());
+    self
+  }
+
+  pub fn with_latest_ttl(mut self, ttl: Duration) -> Self {
+    self.latest_ttl_secs = Some(ttl.as_secs());
+    self
+  }
+
+  pub fn with_endpoints(mut self, endpoints: ArxivEndpoints) -> Self {
+    self.endpoints = endpoints;
+    self
+  }
+
+  pub fn with_client(mut self, client: reqwest::Client) -> Self {
+    self.client = Some(client);
+    self
+  }
+
+  fn client(&self) -> reqwest::Client {
+    self.client.clone().unwrap_or_default()
+  }
+
+  /// Strict: a missing input fails here, naming the placeholder, instead
+  /// of sending `{{ paper }}` to arXiv as an id.
+  fn resolve_arxiv_url(&self, inputs: &AsyncNodeInputs) -> Result<String, AgentFlowError> {
+    render(&self.url, inputs, &RenderOptions::strict())
   }
 
   async fn fetch_arxiv_paper(&self, url: &str) -> Result<ArxivPaper, AgentFlowError> {
-    // Check if it's a valid arXiv URL
-    if let Some(caps) = ARXIV_URL_RE.captures(url) {
-      let paper_id = caps.get(1).map(|m| m.as_str().to_string()).ok_or_else(|| {
-        AgentFlowError::NodeInputError {
-          message: "Could not parse paper ID from arXiv URL".to_string(),
-        }
-      })?;
-      let version = caps.get(2).and_then(|m| m.as_str().parse::<u32>().ok());
-      return Ok(ArxivPaper { paper_id, version });
+    if let Some(paper) = parse_arxiv_id(url) {
+      return Ok(paper);
     }
 
-    // If not a URL, treat as search query
+    // If not a URL or id, treat as search query
     self.search_arxiv(url).await
   }
 
@@ -206,16 +451,15 @@ impl ArxivNode {
     // Use arXiv API to search for papers
     let encoded_query = urlencoding::encode(query);
     let api_url = format!(
-      "http://export.arxiv.org/api/query?search_query=all:{}&start=0&max_results=1",
-      encoded_query
+      "{}?search_query=all:{}&start=0&max_results=1",
+      self.endpoints.api, encoded_query
     );
 
-    let response =
-      reqwest::get(&api_url)
-        .await
-        .map_err(|e| AgentFlowError::AsyncExecutionError {
-          message: format!("Failed to search arXiv: {}", e),
-        })?;
+    let response = self.client().get(&api_url).send().await.map_err(|e| {
+      AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to search arXiv: {}", e),
+      }
+    })?;
 
     let body = response
       .text()
@@ -242,6 +486,196 @@ impl ArxivNode {
     Ok(ArxivPaper { paper_id, version })
   }
 
+  fn cache_path(&self, paper: &ArxivPaper) -> Option<PathBuf> {
+    if !self.cache.unwrap_or(true) {
+      return None;
+    }
+    let dir = self.cache_dir.clone().or_else(|| {
+      dirs::home_dir().map(|home| home.join(".agentflow").join("cache").join("arxiv"))
+    })?;
+    Some(cache_file(&dir, paper))
+  }
+
+  async fn load_cached(&self, paper: &ArxivPaper) -> Option<CachedPaper> {
+    let path = self.cache_path(paper)?;
+    if paper.version.is_none() {
+      let ttl = Duration::from_secs(self.latest_ttl_secs.unwrap_or(DEFAULT_LATEST_TTL_SECS));
+      let age = tokio::fs::metadata(&path)
+        .await
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .unwrap_or_default();
+      if age > ttl {
+        return None;
+      }
+    }
+    let content = tokio::fs::read_to_string(&path).await.ok()?;
+    let record: CachedPaper = serde_json::from_str(&content)
+      .inspect_err(|e| {
+        eprintln!(
+          "⚠️  Ignoring unreadable arXiv cache entry {}: {}",
+          path.display(),
+          e
+        )
+      })
+      .ok()?;
+    // A cached metadata-only entry doesn't satisfy a full-text request.
+    if self.full_text.unwrap_or(true) && record.full_text.is_none() {
+      return None;
+    }
+    Some(record)
+  }
+
+  /// Caching is best-effort: a read-only or full disk costs a refetch next
+  /// time, not this run.
+  async fn store_cached(&self, paper: &ArxivPaper, record: &CachedPaper) {
+    let mut targets: Vec<PathBuf> = self.cache_path(paper).into_iter().collect();
+    // An unpinned request resolves to the latest version; file it under that
+    // version too so a later pinned request hits the cache.
+    if paper.version.is_none()
+      && record.version.is_some()
+      && let Some(path) = self.cache_path(&ArxivPaper {
+        paper_id: record.paper_id.clone(),
+        version: record.version,
+      })
+    {
+      targets.push(path);
+    }
+    let Ok(content) = serde_json::to_string_pretty(record) else {
+      return;
+    };
+    for path in targets {
+      if let Some(parent) = path.parent()
+        && let Err(e) = tokio::fs::create_dir_all(parent).await
+      {
+        eprintln!(
+          "⚠️  Could not create arXiv cache dir {}: {}",
+          parent.display(),
+          e
+        );
+        return;
+      }
+      if let Err(e) = tokio::fs::write(&path, &content).await {
+        eprintln!(
+          "⚠️  Could not write arXiv cache entry {}: {}",
+          path.display(),
+          e
+        );
+      }
+    }
+  }
+
+  async fn fetch_record(&self, paper: &ArxivPaper) -> Result<CachedPaper, AgentFlowError> {
+    let metadata = self.fetch_metadata(paper).await?;
+    let resolved = ArxivPaper {
+      paper_id: paper.paper_id.clone(),
+      version: paper.version.or(metadata.version),
+    };
+
+    let mut record = CachedPaper {
+      paper_id: resolved.paper_id.clone(),
+      version: resolved.version,
+      metadata,
+      full_text: None,
+      sections: Vec::new(),
+      text_source: None,
+    };
+    if self.full_text.unwrap_or(true) {
+      let (full_text, sections, source) = self.fetch_full_text(&resolved).await?;
+      record.full_text = Some(full_text);
+      record.sections = sections;
+      record.text_source = Some(source.to_string());
+    }
+    Ok(record)
+  }
+
+  async fn fetch_metadata(&self, paper: &ArxivPaper) -> Result<ArxivMetadata, AgentFlowError> {
+    let api_url = format!(
+      "{}?id_list={}",
+      self.endpoints.api,
+      urlencoding::encode(&paper.versioned_id())
+    );
+    let response = self
+      .client()
+      .get(&api_url)
+      .send()
+      .await
+      .and_then(|response| response.error_for_status())
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Failed to fetch arXiv metadata for {}: {}",
+          paper.paper_id, e
+        ),
+      })?;
+    let body = response
+      .text()
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to read arXiv metadata response: {}", e),
+      })?;
+    parse_atom_metadata(&body).ok_or_else(|| AgentFlowError::NodeInputError {
+      message: format!("arXiv has no paper with id {}", paper.versioned_id()),
+    })
+  }
+
+  /// HTML first (it carries real section structure), then the PDF.
+  async fn fetch_full_text(
+    &self,
+    paper: &ArxivPaper,
+  ) -> Result<(String, Vec<ArxivSection>, &'static str), AgentFlowError> {
+    let id = paper.versioned_id();
+    let client = self.client();
+    for base in &self.endpoints.html {
+      let url = format!("{}/{}", base.trim_end_matches('/'), id);
+      let html = match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => response.text().await.ok(),
+        Ok(response) => {
+          println!("⚠️  No HTML rendering at {} ({})", url, response.status());
+          None
+        }
+        Err(e) => {
+          println!("⚠️  Could not fetch {}: {}", url, e);
+          None
+        }
+      };
+      if let Some(html) = html {
+        let (full_text, sections) = extract_html_text(&html);
+        if !sections.is_empty() {
+          return Ok((full_text, sections, "html"));
+        }
+      }
+    }
+
+    let url = format!("{}/{}", self.endpoints.pdf.trim_end_matches('/'), id);
+    let bytes = client
+      .get(&url)
+      .send()
+      .await
+      .and_then(|response| response.error_for_status())
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to download PDF for arXiv paper {}: {}", id, e),
+      })?
+      .bytes()
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to read PDF for arXiv paper {}: {}", id, e),
+      })?;
+    // pdf-extract is CPU-bound and can panic on malformed input; keep both
+    // off the async worker.
+    let text = tokio::task::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes))
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("PDF text extraction for {} panicked: {}", id, e),
+      })?
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to extract text from PDF for {}: {}", id, e),
+      })?;
+    let sections = split_text_sections(&text);
+    Ok((text, sections, "pdf"))
+  }
+
   async fn download_and_extract_latex(
     &self,
     paper_id: &str,
@@ -252,11 +686,15 @@ impl ArxivNode {
       v_id = paper_id,
       v_num = version
     );
-    let response = reqwest::get(&url)
-      .await
-      .map_err(|e| AgentFlowError::AsyncExecutionError {
-        message: e.to_string(),
-      })?;
+    let response =
+      self
+        .client()
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: e.to_string(),
+        })?;
     let compressed_bytes =
       response
         .bytes()
@@ -344,9 +782,204 @@ fn contains_document_marker(content: &str) -> bool {
   content.contains(r"\begin{document}")
 }
 
+/// Normalises every way users write an arXiv reference — `2312.07104`,
+/// `2312.07104v2`, `arXiv:2312.07104`, old-style `hep-th/9901001`, and the
+/// `abs` / `pdf` / `html` / ar5iv URLs — to an id and optional version.
+pub fn parse_arxiv_id(input: &str) -> Option<ArxivPaper> {
+  let input = input.trim();
+  let caps = ARXIV_URL_RE
+    .captures(input)
+    .or_else(|| ARXIV_BARE_ID_RE.captures(input))?;
+  let paper_id = caps.get(1)?.as_str().to_string();
+  let version = caps.get(2).and_then(|m| m.as_str().parse::<u32>().ok());
+  Some(ArxivPaper { paper_id, version })
+}
+
+/// How long an unpinned paper's cache entry is trusted by default.
+const DEFAULT_LATEST_TTL_SECS: u64 = 24 * 60 * 60;
+
+fn cache_file(dir: &Path, paper: &ArxivPaper) -> PathBuf {
+  // Old-style ids contain a `/`.
+  let stem = paper.paper_id.replace('/', "_");
+  let name = match paper.version {
+    Some(version) => format!("{stem}v{version}.json"),
+    None => format!("{stem}-latest.json"),
+  };
+  dir.join(name)
+}
+
+fn xml_text(entry: &str, tag: &str) -> Option<String> {
+  let open = format!("<{tag}>");
+  let close = format!("</{tag}>");
+  let start = entry.find(&open)? + open.len();
+  let end = start + entry[start..].find(&close)?;
+  Some(collapse_whitespace(&xml_unescape(&entry[start..end])))
+}
+
+fn xml_unescape(text: &str) -> String {
+  text
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&apos;", "'")
+    .replace("&#39;", "'")
+    .replace("&amp;", "&")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses the first `<entry>` of an export-API Atom feed.
+fn parse_atom_metadata(feed: &str) -> Option<ArxivMetadata> {
+  let entry = ATOM_ENTRY_RE.captures(feed)?.get(1)?.as_str();
+  let id = xml_text(entry, "id")?;
+  // The API answers unknown ids with an `<entry>` whose id is the API error
+  // page rather than an empty feed.
+  let paper = parse_arxiv_id(&id)?;
+  Some(ArxivMetadata {
+    title: xml_text(entry, "title").unwrap_or_default(),
+    authors: ATOM_AUTHOR_RE
+      .captures_iter(entry)
+      .filter_map(|caps| caps.get(1))
+      .map(|m| collapse_whitespace(&xml_unescape(m.as_str())))
+      .collect(),
+    abstract_text: xml_text(entry, "summary").unwrap_or_default(),
+    categories: ATOM_CATEGORY_RE
+      .captures_iter(entry)
+      .filter_map(|caps| caps.get(1))
+      .map(|m| m.as_str().to_string())
+      .collect(),
+    primary_category: ATOM_PRIMARY_CATEGORY_RE
+      .captures(entry)
+      .and_then(|caps| caps.get(1))
+      .map(|m| m.as_str().to_string()),
+    published: xml_text(entry, "published"),
+    updated: xml_text(entry, "updated"),
+    version: paper.version,
+  })
+}
+
+/// Walks a LaTeXML rendering in document order, starting a section at each
+/// heading and collecting the paragraphs that follow it.
+fn extract_html_text(html: &str) -> (String, Vec<ArxivSection>) {
+  let document = Html::parse_document(html);
+  let mut sections: Vec<ArxivSection> = Vec::new();
+  let mut full_text = String::new();
+
+  for element in document.select(&HTML_BLOCK_SELECTOR) {
+    let text = collapse_whitespace(&element.text().collect::<String>());
+    if text.is_empty() {
+      continue;
+    }
+    let name = element.value().name();
+    let is_heading = matches!(name, "h1" | "h2" | "h3" | "h4" | "h6");
+    if !full_text.is_empty() {
+      full_text.push_str(if is_heading { "\n\n" } else { "\n" });
+    }
+    full_text.push_str(&text);
+
+    if name == "h1" {
+      continue;
+    }
+    if is_heading {
+      let number = element
+        .select(&HTML_TAG_SELECTOR)
+        .next()
+        .map(|tag| collapse_whitespace(&tag.text().collect::<String>()))
+        .filter(|tag| !tag.is_empty());
+      let title = match &number {
+        Some(number) => text
+          .strip_prefix(number.as_str())
+          .unwrap_or(&text)
+          .trim()
+          .to_string(),
+        None => text.clone(),
+      };
+      let number = number.map(|n| n.trim_end_matches('.').to_string());
+      let level = match name {
+        "h3" => 2,
+        "h4" => 3,
+        _ => 1,
+      };
+      sections.push(ArxivSection {
+        number,
+        title,
+        level,
+        content: String::new(),
+      });
+    } else if let Some(section) = sections.last_mut() {
+      if !section.content.is_empty() {
+        section.content.push('\n');
+      }
+      section.content.push_str(&text);
+    }
+  }
+
+  (full_text, sections)
+}
+
+/// Heuristic sectioning for text without markup (the PDF fallback): numbered
+/// headings on their own line, plus the usual unnumbered front and back
+/// matter.
+fn split_text_sections(text: &str) -> Vec<ArxivSection> {
+  const UNNUMBERED: &[&str] = &[
+    "Abstract",
+    "Acknowledgments",
+    "Acknowledgements",
+    "References",
+    "Bibliography",
+    "Appendix",
+  ];
+  let mut sections: Vec<ArxivSection> = Vec::new();
+  for line in text.lines() {
+    let trimmed = line.trim();
+    let heading = if let Some(caps) = TEXT_HEADING_RE.captures(trimmed) {
+      caps.get(1).zip(caps.get(2)).map(|(number, title)| {
+        let number = number.as_str().to_string();
+        (
+          Some(number.clone()),
+          title.as_str().trim().to_string(),
+          number.split('.').count(),
+        )
+      })
+    } else if UNNUMBERED.iter().any(|h| h.eq_ignore_ascii_case(trimmed)) {
+      Some((None, trimmed.to_string(), 1))
+    } else {
+      None
+    };
+
+    match heading {
+      Some((number, title, level)) => sections.push(ArxivSection {
+        number,
+        title,
+        level,
+        content: String::new(),
+      }),
+      None if !trimmed.is_empty() => {
+        if let Some(section) = sections.last_mut() {
+          if !section.content.is_empty() {
+            section.content.push('\n');
+          }
+          section.content.push_str(trimmed);
+        }
+      }
+      None => {}
+    }
+  }
+  sections
+}
+
 #[cfg(test)]
 mod tests {
-  use super::contains_document_marker;
+  use super::*;
+  use wiremock::matchers::{method, path, query_param};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  const API_FIXTURE: &str = include_str!("../../tests/fixtures/arxiv/api_2312.07104.xml");
+  const HTML_FIXTURE: &str = include_str!("../../tests/fixtures/arxiv/html_2312.07104v2.html");
+  const PDF_TEXT_FIXTURE: &str =
+    include_str!("../../tests/fixtures/arxiv/pdf_text_2312.07104v2.txt");
 
   /// Q3.8.6 regression: pre-fix the search literal was
   /// `r"\\begin{document}"` (two backslashes) and therefore never
@@ -384,4 +1017,270 @@ Even with neighbouring text \alpha + \beta, the marker still matches.
       "supporting .tex without \\begin{{document}} must not be picked as main"
     );
   }
+
+  fn paper(id: &str, version: Option<u32>) -> Option<ArxivPaper> {
+    Some(ArxivPaper {
+      paper_id: id.to_string(),
+      version,
+    })
+  }
+
+  #[test]
+  fn normalizes_every_supported_id_format() {
+    let cases = [
+      (
+        "https://arxiv.org/abs/2312.07104",
+        paper("2312.07104", None),
+      ),
+      (
+        "https://arxiv.org/abs/2312.07104v2",
+        paper("2312.07104", Some(2)),
+      ),
+      (
+        "https://arxiv.org/pdf/2312.07104.pdf",
+        paper("2312.07104", None),
+      ),
+      (
+        "https://arxiv.org/pdf/2312.07104v1",
+        paper("2312.07104", Some(1)),
+      ),
+      (
+        "https://arxiv.org/html/2312.07104v2",
+        paper("2312.07104", Some(2)),
+      ),
+      (
+        "https://ar5iv.labs.arxiv.org/html/2312.07104",
+        paper("2312.07104", None),
+      ),
+      ("2312.07104", paper("2312.07104", None)),
+      (" 2312.07104v2 ", paper("2312.07104", Some(2))),
+      ("arXiv:2312.07104", paper("2312.07104", None)),
+      ("1501.0001", paper("1501.0001", None)),
+      ("hep-th/9901001v3", paper("hep-th/9901001", Some(3))),
+      (
+        "https://arxiv.org/abs/math.GT/0309136",
+        paper("math.GT/0309136", None),
+      ),
+      ("attention is all you need", None),
+      ("2312.071", None),
+    ];
+    for (input, expected) in cases {
+      assert_eq!(parse_arxiv_id(input), expected, "{input}");
+    }
+  }
+
+  #[test]
+  fn parses_atom_metadata_fixture() {
+    let metadata = parse_atom_metadata(API_FIXTURE).unwrap();
+    assert_eq!(
+      metadata.title,
+      "SGLang: Efficient Execution of Structured Language Model Programs"
+    );
+    assert_eq!(
+      metadata.authors,
+      ["Lianmin Zheng", "Liangsheng Yin", "Zhiqiang Xie"]
+    );
+    assert!(
+      metadata
+        .abstract_text
+        .starts_with("Large language models (LLMs)")
+    );
+    assert!(metadata.abstract_text.ends_with("programs & more."));
+    assert_eq!(metadata.categories, ["cs.AI", "cs.PL"]);
+    assert_eq!(metadata.primary_category.as_deref(), Some("cs.AI"));
+    assert_eq!(metadata.published.as_deref(), Some("2023-12-12T09:38:10Z"));
+    assert_eq!(metadata.version, Some(2));
+
+    assert!(parse_atom_metadata("<feed></feed>").is_none());
+  }
+
+  #[test]
+  fn splits_html_rendering_into_numbered_sections() {
+    let (full_text, sections) = extract_html_text(HTML_FIXTURE);
+
+    let headings: Vec<(Option<&str>, &str, usize)> = sections
+      .iter()
+      .map(|s| (s.number.as_deref(), s.title.as_str(), s.level))
+      .collect();
+    assert_eq!(
+      headings,
+      [
+        (None, "Abstract", 1),
+        (Some("1"), "Introduction", 1),
+        (Some("2"), "Programming Model", 1),
+        (Some("2.1"), "Primitives", 2),
+        (Some("3"), "Conclusion", 1),
+        (None, "References", 1),
+      ]
+    );
+    assert_eq!(
+      sections[1].content,
+      "Recent advancements in LLM capabilities have expanded their utility.\nWe introduce SGLang, a frontend language."
+    );
+    assert!(full_text.starts_with("SGLang: Efficient Execution"));
+    assert!(full_text.contains("\n\n1 Introduction\nRecent advancements"));
+    assert!(!full_text.contains("MathJax"));
+  }
+
+  #[test]
+  fn splits_pdf_text_on_heading_lines() {
+    let sections = split_text_sections(PDF_TEXT_FIXTURE);
+
+    let headings: Vec<(Option<&str>, &str)> = sections
+      .iter()
+      .map(|s| (s.number.as_deref(), s.title.as_str()))
+      .collect();
+    assert_eq!(
+      headings,
+      [
+        (None, "Abstract"),
+        (Some("1"), "Introduction"),
+        (Some("2"), "Programming Model"),
+        (Some("2.1"), "Primitives"),
+        (Some("3"), "Conclusion"),
+        (None, "References"),
+      ]
+    );
+    assert_eq!(sections[3].level, 2);
+    // Sentences that start with a number stay in the body.
+    assert!(sections[1].content.contains("by 2.5 times on 3 benchmarks"));
+  }
+
+  #[test]
+  fn a_missing_url_input_is_an_error() {
+    let node = ArxivNode::new("paper", "{{ paper }}");
+    let err = node.resolve_arxiv_url(&AsyncNodeInputs::new()).unwrap_err();
+    assert!(err.to_string().contains("'{{paper}}'"), "{err}");
+  }
+
+  fn fixture_node(server: &MockServer, cache_dir: &Path) -> ArxivNode {
+    ArxivNode::new("paper", "{{ paper }}")
+      .with_cache_dir(cache_dir)
+      .with_endpoints(ArxivEndpoints {
+        api: format!("{}/api/query", server.uri()),
+        html: vec![
+          format!("{}/missing", server.uri()),
+          format!("{}/html", server.uri()),
+        ],
+        pdf: format!("{}/pdf", server.uri()),
+      })
+      .with_client(reqwest::Client::builder().no_proxy().build().unwrap())
+  }
+
+  #[tokio::test]
+  async fn fetches_metadata_and_sections_then_serves_from_cache() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/api/query"))
+      .and(query_param("id_list", "2312.07104"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(API_FIXTURE))
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/missing/2312.07104v2"))
+      .respond_with(ResponseTemplate::new(404))
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/html/2312.07104v2"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(HTML_FIXTURE))
+      .expect(1)
+      .mount(&server)
+      .await;
+    let cache_dir = tempfile::tempdir().unwrap();
+    let node = fixture_node(&server, cache_dir.path());
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "paper".to_string(),
+      FlowValue::Json(json!("https://arxiv.org/abs/2312.07104")),
+    );
+
+    let first = node.execute(&inputs).await.unwrap();
+    assert_eq!(first["paper_id"], FlowValue::Json(json!("2312.07104")));
+    assert_eq!(first["version"], FlowValue::Json(json!("2")));
+    assert_eq!(first["cached"], FlowValue::Json(json!(false)));
+    assert_eq!(first["text_source"], FlowValue::Json(json!("html")));
+    let FlowValue::Json(metadata) = &first["metadata"] else {
+      panic!("metadata should be JSON");
+    };
+    assert_eq!(metadata["authors"][0], json!("Lianmin Zheng"));
+    assert_eq!(
+      metadata["abstract"],
+      json!(parse_atom_metadata(API_FIXTURE).unwrap().abstract_text)
+    );
+    let FlowValue::Json(Value::Array(sections)) = &first["sections"] else {
+      panic!("sections should be an array");
+    };
+    assert_eq!(sections[1]["title"], json!("Introduction"));
+    assert_eq!(sections[1]["number"], json!("1"));
+
+    // Both the unpinned and the resolved version are cached.
+    assert!(cache_dir.path().join("2312.07104-latest.json").exists());
+    assert!(cache_dir.path().join("2312.07104v2.json").exists());
+
+    let second = node.execute(&inputs).await.unwrap();
+    assert_eq!(second["cached"], FlowValue::Json(json!(true)));
+    assert_eq!(second["sections"], first["sections"]);
+
+    inputs.insert("paper".to_string(), FlowValue::Json(json!("2312.07104v2")));
+    let pinned = node.execute(&inputs).await.unwrap();
+    assert_eq!(pinned["cached"], FlowValue::Json(json!(true)));
+    // `expect(1)` on each mock verifies neither repeat run hit the network.
+  }
+
+  #[tokio::test]
+  async fn expired_latest_entry_is_refetched_but_pinned_entry_is_kept() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/api/query"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(API_FIXTURE))
+      .expect(2)
+      .mount(&server)
+      .await;
+    let cache_dir = tempfile::tempdir().unwrap();
+    let node = fixture_node(&server, cache_dir.path())
+      .with_full_text(false)
+      .with_latest_ttl(Duration::ZERO);
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("paper".to_string(), FlowValue::Json(json!("2312.07104")));
+
+    let first = node.execute(&inputs).await.unwrap();
+    assert_eq!(first["cached"], FlowValue::Json(json!(false)));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let second = node.execute(&inputs).await.unwrap();
+    assert_eq!(second["cached"], FlowValue::Json(json!(false)));
+
+    inputs.insert("paper".to_string(), FlowValue::Json(json!("2312.07104v2")));
+    let pinned = node.execute(&inputs).await.unwrap();
+    assert_eq!(pinned["cached"], FlowValue::Json(json!(true)));
+  }
+
+  #[tokio::test]
+  async fn metadata_only_mode_skips_full_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/api/query"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(API_FIXTURE))
+      .mount(&server)
+      .await;
+    let cache_dir = tempfile::tempdir().unwrap();
+    let node = fixture_node(&server, cache_dir.path())
+      .with_full_text(false)
+      .with_cache(false);
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("paper".to_string(), FlowValue::Json(json!("2312.07104")));
+
+    let outputs = node.execute(&inputs).await.unwrap();
+    assert!(outputs.contains_key("metadata"));
+    assert!(!outputs.contains_key("full_text"));
+    assert!(!outputs.contains_key("sections"));
+    assert!(
+      std::fs::read_dir(cache_dir.path())
+        .unwrap()
+        .next()
+        .is_none()
+    );
+  }
 }
@@ -1,14 +1,63 @@
+//! Batch node: run a child node once per item, in rate-limited batches.
+//!
+//! Items are read from the array input named by `items_key` and split into
+//! chunks of `batch_size`. Within a chunk at most `max_concurrent` child runs
+//! are in flight; `batch_delay` is slept between chunks so a provider's rate
+//! limit can be respected. Each child run receives `item` and `index` inputs
+//! and is retried up to `max_retries` times before the item is recorded in
+//! `failed_items` — one bad item never fails the whole batch.
+//!
+//! The value taken from a child run is its `result_key` output (default
+//! `output`), or the whole output map when that key is absent. Successful
+//! values are combined in item order according to [`BatchAggregation`] and
+//! exposed as `results`.
+
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
   value::FlowValue,
 };
 use async_trait::async_trait;
-use serde_json::Value;
+use serde_json::{Map, Value, json};
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
+/// How successful child results are combined into the `results` output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BatchAggregation {
+  /// A JSON array with one entry per successful item.
+  #[default]
+  CollectArray,
+  /// Each result as text, joined with `separator`.
+  ConcatText { separator: String },
+  /// Shallow merge of object results; later items win on key conflicts.
+  MergeObjects,
+}
+
+impl FromStr for BatchAggregation {
+  type Err = AgentFlowError;
+
+  fn from_str(mode: &str) -> Result<Self, Self::Err> {
+    match mode {
+      "collect_array" => Ok(Self::CollectArray),
+      "concat_text" => Ok(Self::ConcatText {
+        separator: "\n".to_string(),
+      }),
+      "merge_objects" => Ok(Self::MergeObjects),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Unknown batch aggregation '{}' (expected collect_array, concat_text or merge_objects)",
+          other
+        ),
+      }),
+    }
+  }
+}
+
 /// Batch node for parallel processing of multiple items
 #[derive(Clone)]
 pub struct BatchNode {
@@ -16,6 +65,15 @@ pub struct BatchNode {
   pub items_key: String, // Key in shared state containing array of items to process
   pub batch_size: usize,
   pub max_concurrent: usize,
+  /// Pause between consecutive batches.
+  pub batch_delay: Option<Duration>,
+  /// Extra attempts per item after the first failure.
+  pub max_retries: u32,
+  /// Pause before each retry of a failed item.
+  pub retry_delay: Duration,
+  pub aggregation: BatchAggregation,
+  /// Child output collected per item (default `output`).
+  pub result_key: String,
   /// The inner node executed once per batch item. Skipped in [`Debug`]
   /// output because `AsyncNode` is a trait object (no `Debug` bound).
   pub child_node: Option<Arc<dyn AsyncNode>>,
@@ -28,6 +86,11 @@ impl std::fmt::Debug for BatchNode {
       .field("items_key", &self.items_key)
       .field("batch_size", &self.batch_size)
       .field("max_concurrent", &self.max_concurrent)
+      .field("batch_delay", &self.batch_delay)
+      .field("max_retries", &self.max_retries)
+      .field("retry_delay", &self.retry_delay)
+      .field("aggregation", &self.aggregation)
+      .field("result_key", &self.result_key)
       .field(
         "child_node",
         &self.child_node.as_ref().map(|_| "<AsyncNode>"),
@@ -36,6 +99,13 @@ impl std::fmt::Debug for BatchNode {
   }
 }
 
+/// Outcome of running the child for one item, retries included.
+struct ItemOutcome {
+  index: usize,
+  attempts: u32,
+  result: Result<Value, AgentFlowError>,
+}
+
 impl BatchNode {
   pub fn new(name: &str, items_key: &str) -> Self {
     Self {
@@ -43,17 +113,22 @@ impl BatchNode {
       items_key: items_key.to_string(),
       batch_size: 10,
       max_concurrent: 4,
+      batch_delay: None,
+      max_retries: 0,
+      retry_delay: Duration::from_millis(0),
+      aggregation: BatchAggregation::default(),
+      result_key: "output".to_string(),
       child_node: None,
     }
   }
 
   pub fn with_batch_size(mut self, size: usize) -> Self {
-    self.batch_size = size;
+    self.batch_size = size.max(1);
     self
   }
 
   pub fn with_max_concurrent(mut self, max: usize) -> Self {
-    self.max_concurrent = max;
+    self.max_concurrent = max.max(1);
     self
   }
 
@@ -62,70 +137,159 @@ impl BatchNode {
     self
   }
 
-  async fn process_batch(&self, items: &[FlowValue]) -> Result<Vec<FlowValue>, AgentFlowError> {
-    let mut results = Vec::new();
+  pub fn with_batch_delay(mut self, delay: Duration) -> Self {
+    self.batch_delay = Some(delay);
+    self
+  }
+
+  pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+    self.max_retries = max_retries;
+    self.retry_delay = retry_delay;
+    self
+  }
+
+  pub fn with_aggregation(mut self, aggregation: BatchAggregation) -> Self {
+    self.aggregation = aggregation;
+    self
+  }
+
+  pub fn with_result_key(mut self, result_key: &str) -> Self {
+    self.result_key = result_key.to_string();
+    self
+  }
+
+  async fn process_batch(
+    &self,
+    child_node: &Arc<dyn AsyncNode>,
+    offset: usize,
+    items: &[Value],
+  ) -> Result<Vec<ItemOutcome>, AgentFlowError> {
+    let permits = Arc::new(Semaphore::new(self.max_concurrent));
+    let mut tasks = JoinSet::new();
+
+    for (position, item) in items.iter().enumerate() {
+      let permits = Arc::clone(&permits);
+      let child = Arc::clone(child_node);
+      let item = item.clone();
+      let index = offset + position;
+      let max_retries = self.max_retries;
+      let retry_delay = self.retry_delay;
+      let result_key = self.result_key.clone();
 
-    if let Some(child_node) = &self.child_node {
-      let mut tasks = JoinSet::new();
+      tasks.spawn(async move {
+        let _permit = match permits.acquire_owned().await {
+          Ok(permit) => permit,
+          Err(e) => {
+            return ItemOutcome {
+              index,
+              attempts: 0,
+              result: Err(AgentFlowError::AsyncExecutionError {
+                message: format!("Batch concurrency limiter closed: {}", e),
+              }),
+            };
+          }
+        };
+        let mut inputs = AsyncNodeInputs::new();
+        inputs.insert("item".to_string(), FlowValue::Json(item));
+        inputs.insert("index".to_string(), FlowValue::Json(json!(index)));
 
-      for item in items.iter() {
-        if tasks.len() >= self.max_concurrent
-          && let Some(task_result) = tasks.join_next().await
-        {
-          match task_result {
-            Ok(result) => results.push(result?),
+        let mut attempts = 0;
+        loop {
+          attempts += 1;
+          match child.execute(&inputs).await {
+            Ok(outputs) => {
+              return ItemOutcome {
+                index,
+                attempts,
+                result: Ok(child_result(outputs, &result_key)),
+              };
+            }
+            Err(e) if attempts > max_retries => {
+              return ItemOutcome {
+                index,
+                attempts,
+                result: Err(e),
+              };
+            }
             Err(e) => {
-              return Err(AgentFlowError::AsyncExecutionError {
-                message: format!("Batch processing task failed: {}", e),
-              });
+              eprintln!(
+                "⚠️  Batch item {} failed (attempt {}): {}; retrying",
+                index, attempts, e
+              );
+              if !retry_delay.is_zero() {
+                tokio::time::sleep(retry_delay).await;
+              }
             }
           }
         }
+      });
+    }
 
-        let mut inputs = AsyncNodeInputs::new();
-        inputs.insert("item".to_string(), item.clone());
-
-        let child_clone = Arc::clone(child_node);
-
-        tasks.spawn(async move { child_clone.execute(&inputs).await });
-      }
+    let mut outcomes = Vec::with_capacity(items.len());
+    while let Some(task_result) = tasks.join_next().await {
+      outcomes.push(
+        task_result.map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!("Batch processing task failed: {}", e),
+        })?,
+      );
+    }
+    Ok(outcomes)
+  }
 
-      while let Some(task_result) = tasks.join_next().await {
-        match task_result {
-          Ok(result) => results.push(result?),
-          Err(e) => {
-            return Err(AgentFlowError::AsyncExecutionError {
-              message: format!("Batch processing task failed: {}", e),
-            });
+  fn aggregate(&self, values: Vec<Value>) -> Result<Value, AgentFlowError> {
+    match &self.aggregation {
+      BatchAggregation::CollectArray => Ok(Value::Array(values)),
+      BatchAggregation::ConcatText { separator } => Ok(Value::String(
+        values
+          .iter()
+          .map(|value| match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+          })
+          .collect::<Vec<_>>()
+          .join(separator),
+      )),
+      BatchAggregation::MergeObjects => {
+        let mut merged = Map::new();
+        for value in values {
+          match value {
+            Value::Object(fields) => merged.extend(fields),
+            other => {
+              return Err(AgentFlowError::NodeExecutionFailed {
+                message: format!(
+                  "Batch node '{}' cannot merge non-object result {} (aggregation: merge_objects)",
+                  self.name, other
+                ),
+              });
+            }
           }
         }
+        Ok(Value::Object(merged))
       }
-    } else {
-      results = items
-        .iter()
-        .map(|i| {
-          let mut res = HashMap::new();
-          res.insert("output".to_string(), i.clone());
-          res
-        })
-        .collect();
     }
+  }
+}
 
-    // Both branches that populate `results` (lines 95-101 and 104-111) insert
-    // the "output" key explicitly, so `h.get("output")` is statically present.
-    // Use `cloned()` + `unwrap_or` to fall through to JSON null on the
-    // unreachable miss path instead of panicking — keeps Q5.1 happy without
-    // adding a new fail mode at the call site.
-    let flattened_results = results
-      .into_iter()
-      .map(|h| {
-        h.get("output")
-          .cloned()
-          .unwrap_or(FlowValue::Json(Value::Null))
-      })
-      .collect();
+/// The value collected for one item: the `result_key` output when present,
+/// otherwise every output as a JSON object.
+fn child_result(mut outputs: HashMap<String, FlowValue>, result_key: &str) -> Value {
+  match outputs.remove(result_key) {
+    Some(value) => flow_value_to_json(value),
+    None => Value::Object(
+      outputs
+        .into_iter()
+        .map(|(key, value)| (key, flow_value_to_json(value)))
+        .collect(),
+    ),
+  }
+}
 
-    Ok(flattened_results)
+// The custom FlowValue Serialize impl would wrap non-JSON values in the
+// `{type, value}` trace envelope; downstream nodes expect the plain data.
+fn flow_value_to_json(value: FlowValue) -> Value {
+  match value {
+    FlowValue::Json(inner) => inner,
+    other => serde_json::to_value(other).unwrap_or(Value::Null),
   }
 }
 
@@ -133,10 +297,7 @@ impl BatchNode {
 impl AsyncNode for BatchNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
     let items = match inputs.get(&self.items_key) {
-      Some(FlowValue::Json(Value::Array(arr))) => arr
-        .iter()
-        .map(|v| FlowValue::Json(v.clone()))
-        .collect::<Vec<_>>(),
+      Some(FlowValue::Json(Value::Array(arr))) => arr.clone(),
       _ => {
         return Err(AgentFlowError::NodeInputError {
           message: format!("Input '{}' is missing or not an array", self.items_key),
@@ -150,31 +311,61 @@ impl AsyncNode for BatchNode {
     println!("   Batch size: {}", self.batch_size);
     println!("   Max concurrent: {}", self.max_concurrent);
 
-    let mut all_results = Vec::new();
-    for batch in items.chunks(self.batch_size) {
-      let batch_results = self.process_batch(batch).await?;
-      all_results.extend(batch_results);
+    let Some(child_node) = &self.child_node else {
+      // Without a child the node passes its items through unchanged.
+      let mut outputs = HashMap::new();
+      outputs.insert(
+        "results".to_string(),
+        FlowValue::Json(self.aggregate(items)?),
+      );
+      outputs.insert(
+        "failed_items".to_string(),
+        FlowValue::Json(Value::Array(vec![])),
+      );
+      return Ok(outputs);
+    };
+
+    let mut outcomes = Vec::with_capacity(items.len());
+    for (batch_index, batch) in items.chunks(self.batch_size.max(1)).enumerate() {
+      if batch_index > 0
+        && let Some(delay) = self.batch_delay
+      {
+        tokio::time::sleep(delay).await;
+      }
+      let offset = batch_index * self.batch_size.max(1);
+      outcomes.extend(self.process_batch(child_node, offset, batch).await?);
+    }
+    outcomes.sort_by_key(|outcome| outcome.index);
+
+    let mut values = Vec::new();
+    let mut failed_items = Vec::new();
+    for outcome in outcomes {
+      match outcome.result {
+        Ok(value) => values.push(value),
+        Err(e) => failed_items.push(json!({
+          "index": outcome.index,
+          "item": items[outcome.index],
+          "error": e.to_string(),
+          "attempts": outcome.attempts,
+        })),
+      }
     }
 
     println!(
-      "✅ Batch processing complete. {} results",
-      all_results.len()
+      "✅ Batch processing complete. {} succeeded, {} failed",
+      values.len(),
+      failed_items.len()
     );
 
-    // Flatten the Vec<FlowValue> into a plain JSON array — the custom
-    // FlowValue Serialize impl would otherwise wrap each entry in
-    // `{type, value}`, which is the trace envelope shape, not the data
-    // shape downstream nodes consume.
-    let array: Vec<Value> = all_results
-      .into_iter()
-      .map(|v| match v {
-        FlowValue::Json(inner) => inner,
-        other => serde_json::to_value(other).unwrap_or(Value::Null),
-      })
-      .collect();
     let mut outputs = HashMap::new();
-    outputs.insert("results".to_string(), FlowValue::Json(Value::Array(array)));
-
+    outputs.insert(
+      "results".to_string(),
+      FlowValue::Json(self.aggregate(values)?),
+    );
+    outputs.insert(
+      "failed_items".to_string(),
+      FlowValue::Json(Value::Array(failed_items)),
+    );
     Ok(outputs)
   }
 }
@@ -182,7 +373,7 @@ impl AsyncNode for BatchNode {
 #[cfg(test)]
 mod tests {
   use super::*;
-  use serde_json::json;
+  use std::sync::atomic::{AtomicUsize, Ordering};
 
   struct MockChildNode;
 
@@ -196,6 +387,54 @@ mod tests {
     }
   }
 
+  /// Records the highest number of simultaneous executions.
+  #[derive(Default)]
+  struct ConcurrencyProbe {
+    running: AtomicUsize,
+    peak: AtomicUsize,
+  }
+
+  #[async_trait]
+  impl AsyncNode for ConcurrencyProbe {
+    async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+      self.peak.fetch_max(running, Ordering::SeqCst);
+      tokio::time::sleep(Duration::from_millis(20)).await;
+      self.running.fetch_sub(1, Ordering::SeqCst);
+      Ok(HashMap::from([(
+        "output".to_string(),
+        inputs["item"].clone(),
+      )]))
+    }
+  }
+
+  /// Fails the first `failures` attempts for item 1, succeeds otherwise.
+  struct FlakyChild {
+    failures: usize,
+    attempts: AtomicUsize,
+  }
+
+  #[async_trait]
+  impl AsyncNode for FlakyChild {
+    async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+      if inputs["index"] == FlowValue::Json(json!(1))
+        && self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures
+      {
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: "flaky".to_string(),
+        });
+      }
+      Ok(HashMap::from([(
+        "output".to_string(),
+        inputs["item"].clone(),
+      )]))
+    }
+  }
+
+  fn items(values: Value) -> AsyncNodeInputs {
+    AsyncNodeInputs::from([("my_items".to_string(), FlowValue::Json(values))])
+  }
+
   #[tokio::test]
   async fn test_batch_node() {
     let child_node = Arc::new(MockChildNode);
@@ -214,4 +453,90 @@ mod tests {
       assert_eq!(arr[2], json!(3));
     }
   }
+
+  #[tokio::test]
+  async fn max_concurrent_caps_in_flight_children() {
+    let probe = Arc::new(ConcurrencyProbe::default());
+    let node = BatchNode::new("limited", "my_items")
+      .with_batch_size(6)
+      .with_max_concurrent(2)
+      .with_child_node(probe.clone());
+
+    let outputs = node
+      .execute(&items(json!([1, 2, 3, 4, 5, 6])))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      outputs["results"],
+      FlowValue::Json(json!([1, 2, 3, 4, 5, 6]))
+    );
+    assert_eq!(probe.peak.load(Ordering::SeqCst), 2);
+  }
+
+  #[tokio::test]
+  async fn failing_items_are_retried_then_reported() {
+    let recovers = BatchNode::new("retry", "my_items")
+      .with_retries(2, Duration::from_millis(1))
+      .with_child_node(Arc::new(FlakyChild {
+        failures: 2,
+        attempts: AtomicUsize::new(0),
+      }));
+    let outputs = recovers
+      .execute(&items(json!(["a", "b", "c"])))
+      .await
+      .unwrap();
+    assert_eq!(outputs["results"], FlowValue::Json(json!(["a", "b", "c"])));
+    assert_eq!(outputs["failed_items"], FlowValue::Json(json!([])));
+
+    let gives_up = BatchNode::new("retry", "my_items")
+      .with_retries(1, Duration::from_millis(1))
+      .with_child_node(Arc::new(FlakyChild {
+        failures: 5,
+        attempts: AtomicUsize::new(0),
+      }));
+    let outputs = gives_up
+      .execute(&items(json!(["a", "b", "c"])))
+      .await
+      .unwrap();
+    assert_eq!(outputs["results"], FlowValue::Json(json!(["a", "c"])));
+    let FlowValue::Json(Value::Array(failed)) = &outputs["failed_items"] else {
+      panic!("failed_items should be an array");
+    };
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["index"], json!(1));
+    assert_eq!(failed[0]["item"], json!("b"));
+    assert_eq!(failed[0]["attempts"], json!(2));
+  }
+
+  #[tokio::test]
+  async fn aggregation_modes_shape_the_results() {
+    let concat = BatchNode::new("concat", "my_items")
+      .with_batch_size(2)
+      .with_aggregation(BatchAggregation::ConcatText {
+        separator: ", ".to_string(),
+      })
+      .with_child_node(Arc::new(MockChildNode));
+    let outputs = concat.execute(&items(json!(["x", "y", 3]))).await.unwrap();
+    assert_eq!(outputs["results"], FlowValue::Json(json!("x, y, 3")));
+
+    let merge = BatchNode::new("merge", "my_items")
+      .with_aggregation(BatchAggregation::MergeObjects)
+      .with_child_node(Arc::new(MockChildNode));
+    let outputs = merge
+      .execute(&items(json!([{ "a": 1, "b": 1 }, { "b": 2 }])))
+      .await
+      .unwrap();
+    assert_eq!(
+      outputs["results"],
+      FlowValue::Json(json!({ "a": 1, "b": 2 }))
+    );
+    assert!(merge.execute(&items(json!([{ "a": 1 }, 2]))).await.is_err());
+
+    assert_eq!(
+      "collect_array".parse::<BatchAggregation>().unwrap(),
+      BatchAggregation::CollectArray
+    );
+    assert!("sum".parse::<BatchAggregation>().is_err());
+  }
 }
@@ -0,0 +1,637 @@
+//! Command execution node.
+//!
+//! `CommandNode` runs one program (ffmpeg, pandoc, git, ...) with arguments
+//! rendered from the node inputs via `{{ key }}` placeholders, and reports
+//! `stdout`, `stderr`, `exit_code` and `success`. A non-zero exit is an
+//! ordinary result (`success: false`) unless [`CommandNode::with_fail_on_error`]
+//! is set.
+//!
+//! Safety controls:
+//!
+//! - The program is spawned directly; nothing is interpreted by a shell
+//!   unless `allow_shell` is set, in which case `program` is the script given
+//!   to `sh -c` and `args` become its positional parameters (`$1`, ...).
+//!   Placeholders in a script are never spliced into its text: each becomes
+//!   a quoted reference to an `AGENTFLOW_VALUE_<n>` environment variable
+//!   holding the value, so an input cannot inject shell syntax.
+//! - When an allowlist of programs is configured, anything else is refused
+//!   before spawn. Both the program and the allowlist entries are resolved
+//!   to canonical paths (bare names through `PATH`) and compared as such,
+//!   and the resolved path is what gets spawned. With `allow_shell` the
+//!   program actually run is `sh`, so `sh` itself must be allowed.
+//! - The child starts from an empty environment: only `PATH` and the
+//!   variables named in `env_allowlist` are inherited, plus the node's own
+//!   `env:` block ([`ProcessEnv`]), whose secret values never reach the run
+//!   directory.
+//! - The run is killed after `timeout`, and at most `max_output_bytes` of
+//!   each stream are kept.
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::common::process_env::ProcessEnv;
+use crate::common::template::{RenderOptions, render};
+use crate::common::utils::{flow_value_to_string, resolve_placeholders};
+
+/// Timeout applied when none is configured.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Per-stream capture limit applied when none is configured (1 MiB).
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct CommandNode {
+  pub name: String,
+  /// Program to run, or the `sh -c` script when `allow_shell` is set.
+  pub program: String,
+  pub args: Vec<String>,
+  pub allow_shell: bool,
+  /// Input whose value is written to the child's stdin.
+  pub stdin_key: Option<String>,
+  /// Working directory, with `{{ key }}` placeholders; overrides the
+  /// `cwd` of `process_env`.
+  pub working_dir: Option<String>,
+  /// Parent environment variables passed through (besides `PATH`).
+  pub env_allowlist: Vec<String>,
+  /// The node's `env:` / `cwd:` block, set after the pass-through.
+  pub process_env: ProcessEnv,
+  pub timeout: Duration,
+  pub max_output_bytes: usize,
+  /// Programs permitted to run; `None` allows any.
+  pub allowed_programs: Option<Vec<String>>,
+  /// Turn a non-zero exit into a node error.
+  pub fail_on_error: bool,
+}
+
+/// One captured output stream.
+struct Captured {
+  text: String,
+  truncated: bool,
+}
+
+impl CommandNode {
+  pub fn new(name: &str, program: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      program: program.to_string(),
+      args: Vec::new(),
+      allow_shell: false,
+      stdin_key: None,
+      working_dir: None,
+      env_allowlist: Vec::new(),
+      process_env: ProcessEnv::default(),
+      timeout: DEFAULT_TIMEOUT,
+      max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+      allowed_programs: None,
+      fail_on_error: false,
+    }
+  }
+
+  pub fn with_args(mut self, args: Vec<String>) -> Self {
+    self.args = args;
+    self
+  }
+
+  pub fn with_allow_shell(mut self, allow_shell: bool) -> Self {
+    self.allow_shell = allow_shell;
+    self
+  }
+
+  pub fn with_stdin_key(mut self, key: &str) -> Self {
+    self.stdin_key = Some(key.to_string());
+    self
+  }
+
+  pub fn with_working_dir(mut self, dir: &str) -> Self {
+    self.working_dir = Some(dir.to_string());
+    self
+  }
+
+  pub fn with_env_allowlist(mut self, names: Vec<String>) -> Self {
+    self.env_allowlist = names;
+    self
+  }
+
+  pub fn with_process_env(mut self, process_env: ProcessEnv) -> Self {
+    self.process_env = process_env;
+    self
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn with_max_output_bytes(mut self, max: usize) -> Self {
+    self.max_output_bytes = max;
+    self
+  }
+
+  pub fn with_allowed_programs(mut self, programs: Vec<String>) -> Self {
+    self.allowed_programs = Some(programs);
+    self
+  }
+
+  pub fn with_fail_on_error(mut self, fail_on_error: bool) -> Self {
+    self.fail_on_error = fail_on_error;
+    self
+  }
+
+  /// Binary that will actually be exec'd, checked against the allowlist.
+  fn executable<'a>(&self, program: &'a str) -> &'a str {
+    if self.allow_shell { "sh" } else { program }
+  }
+
+  /// With an allowlist configured, the canonical path of `executable` once
+  /// it is allowed; `None` when any program may run.
+  fn check_allowed(&self, executable: &str) -> Result<Option<PathBuf>, AgentFlowError> {
+    let Some(allowed) = &self.allowed_programs else {
+      return Ok(None);
+    };
+    let refused = || AgentFlowError::ConfigurationError {
+      message: format!(
+        "Command node '{}': program '{}' is not in the allowed programs list",
+        self.name, executable
+      ),
+    };
+    // Paths, not names: `/tmp/evil/git` must not pass for an allowed `git`.
+    let resolved = resolve_executable(executable).ok_or_else(refused)?;
+    if allowed
+      .iter()
+      .filter_map(|entry| resolve_executable(entry))
+      .any(|entry| entry == resolved)
+    {
+      Ok(Some(resolved))
+    } else {
+      Err(refused())
+    }
+  }
+
+  fn build_command(
+    &self,
+    program: &str,
+    executable: Option<&Path>,
+    args: &[String],
+    inputs: &AsyncNodeInputs,
+  ) -> Result<tokio::process::Command, AgentFlowError> {
+    let mut script_values = Vec::new();
+    let mut command = if self.allow_shell {
+      let (script, values) = shell_script(program, inputs)?;
+      script_values = values;
+      let mut command = tokio::process::Command::new(executable.unwrap_or(Path::new("sh")));
+      command.arg("-c").arg(script).arg(&self.name).args(args);
+      command
+    } else {
+      let resolved = resolve_placeholders(program, inputs)?;
+      let mut command = match executable {
+        Some(path) => tokio::process::Command::new(path),
+        None => tokio::process::Command::new(resolved),
+      };
+      command.args(args);
+      command
+    };
+
+    command.env_clear();
+    for name in std::iter::once("PATH").chain(self.env_allowlist.iter().map(String::as_str)) {
+      if let Some(value) = std::env::var_os(name) {
+        command.env(name, value);
+      }
+    }
+    self.process_env.apply(&mut command);
+    command.envs(script_values);
+    if let Some(dir) = &self.working_dir {
+      command.current_dir(resolve_placeholders(dir, inputs)?);
+    }
+    command
+      .stdin(if self.stdin_key.is_some() {
+        Stdio::piped()
+      } else {
+        Stdio::null()
+      })
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .kill_on_drop(true);
+    Ok(command)
+  }
+}
+
+/// Canonical path of `program`: as given when it contains a `/`, otherwise
+/// the first match on `PATH`. `None` when nothing executable is found.
+fn resolve_executable(program: &str) -> Option<PathBuf> {
+  let path = Path::new(program);
+  if path.components().count() > 1 || path.is_absolute() {
+    return path.canonicalize().ok().filter(|path| path.is_file());
+  }
+  std::env::split_paths(&std::env::var_os("PATH")?)
+    .map(|dir| dir.join(program))
+    .find(|candidate| candidate.is_file())
+    .and_then(|candidate| candidate.canonicalize().ok())
+}
+
+/// Rewrites each `{{ path }}` in a shell script into a quoted reference to
+/// an `AGENTFLOW_VALUE_<n>` variable and returns those variables' values.
+/// The quoting follows the script's own quoting at the placeholder, so the
+/// value is always a single, literal word.
+fn shell_script(
+  template: &str,
+  inputs: &AsyncNodeInputs,
+) -> Result<(String, Vec<(String, String)>), AgentFlowError> {
+  #[derive(Clone, Copy, PartialEq)]
+  enum Quote {
+    None,
+    Single,
+    Double,
+  }
+
+  let mut script = String::with_capacity(template.len());
+  let mut values = Vec::new();
+  let mut quote = Quote::None;
+  let mut rest = template;
+  while let Some(c) = rest.chars().next() {
+    if rest.starts_with("\\{{") {
+      script.push_str("{{");
+      rest = &rest[3..];
+      continue;
+    }
+    if rest.starts_with("{{")
+      && let Some(len) = rest[2..].find("}}")
+    {
+      let path = rest[2..2 + len].trim();
+      let value = render(&format!("{{{{{path}}}}}"), inputs, &RenderOptions::strict())?;
+      let name = format!("AGENTFLOW_VALUE_{}", values.len() + 1);
+      match quote {
+        Quote::None => script.push_str(&format!("\"${{{name}}}\"")),
+        Quote::Double => script.push_str(&format!("${{{name}}}")),
+        Quote::Single => script.push_str(&format!("'\"${{{name}}}\"'")),
+      }
+      values.push((name, value));
+      rest = &rest[2 + len + 2..];
+      continue;
+    }
+    match (quote, c) {
+      (Quote::None, '\'') => quote = Quote::Single,
+      (Quote::Single, '\'') => quote = Quote::None,
+      (Quote::None, '"') => quote = Quote::Double,
+      (Quote::Double, '"') => quote = Quote::None,
+      (Quote::None | Quote::Double, '\\') => {
+        // Copy the escaped character as is.
+        let escaped_len = rest[1..].chars().next().map_or(0, char::len_utf8);
+        script.push_str(&rest[..1 + escaped_len]);
+        rest = &rest[1 + escaped_len..];
+        continue;
+      }
+      _ => {}
+    }
+    script.push(c);
+    rest = &rest[c.len_utf8()..];
+  }
+  Ok((script, values))
+}
+
+/// Reads a whole stream, keeping at most `limit` bytes. The rest is still
+/// drained so a chatty child never blocks on a full pipe.
+async fn capture<R: AsyncRead + Unpin>(mut reader: R, limit: usize) -> std::io::Result<Captured> {
+  let mut kept = Vec::new();
+  let mut chunk = [0u8; 8192];
+  let mut truncated = false;
+  loop {
+    let read = reader.read(&mut chunk).await?;
+    if read == 0 {
+      break;
+    }
+    let room = limit.saturating_sub(kept.len());
+    if read > room {
+      truncated = true;
+    }
+    kept.extend_from_slice(&chunk[..read.min(room)]);
+  }
+  Ok(Captured {
+    text: String::from_utf8_lossy(&kept).to_string(),
+    truncated,
+  })
+}
+
+#[async_trait]
+impl AsyncNode for CommandNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    // A script keeps its placeholders; `build_command` passes their values
+    // through the environment.
+    let program = if self.allow_shell {
+      self.program.clone()
+    } else {
+      resolve_placeholders(&self.program, inputs)?
+    };
+    let args = self
+      .args
+      .iter()
+      .map(|arg| resolve_placeholders(arg, inputs))
+      .collect::<Result<Vec<_>, _>>()?;
+    let executable = self.check_allowed(self.executable(&program))?;
+
+    let stdin = match &self.stdin_key {
+      Some(key) => Some(inputs.get(key).map(flow_value_to_string).ok_or_else(|| {
+        AgentFlowError::NodeInputError {
+          message: format!(
+            "Command node '{}': stdin input '{}' is missing",
+            self.name, key
+          ),
+        }
+      })?),
+      None => None,
+    };
+
+    println!("🖥️  Command node '{}': running {}", self.name, program);
+    let mut child = self
+      .build_command(&program, executable.as_deref(), &args, inputs)?
+      .spawn()
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Command node '{}': failed to start '{}': {}",
+          self.name, program, e
+        ),
+      })?;
+
+    let stdin_pipe = child.stdin.take();
+    let (Some(stdout_pipe), Some(stderr_pipe)) = (child.stdout.take(), child.stderr.take()) else {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Command node '{}': child output was not captured",
+          self.name
+        ),
+      });
+    };
+    let limit = self.max_output_bytes;
+    let run = async {
+      let feed = async {
+        if let (Some(mut pipe), Some(data)) = (stdin_pipe, stdin) {
+          // The child may exit without reading everything; that is its call.
+          let _ = pipe.write_all(data.as_bytes()).await;
+        }
+      };
+      let (_, stdout, stderr, status) = tokio::join!(
+        feed,
+        capture(stdout_pipe, limit),
+        capture(stderr_pipe, limit),
+        child.wait()
+      );
+      Ok::<_, std::io::Error>((stdout?, stderr?, status?))
+    };
+
+    let (stdout, stderr, status) = match tokio::time::timeout(self.timeout, run).await {
+      Ok(result) => result.map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Command node '{}': I/O error: {}", self.name, e),
+      })?,
+      Err(_) => {
+        // Dropping the `run` future dropped the child, and `kill_on_drop`
+        // has already sent it SIGKILL.
+        eprintln!(
+          "⏱️  Command node '{}' killed after {:?}",
+          self.name, self.timeout
+        );
+        return Err(AgentFlowError::TimeoutExceeded {
+          duration_ms: self.timeout.as_millis() as u64,
+        });
+      }
+    };
+
+    let exit_code = status.code();
+    let success = status.success();
+    if !success && self.fail_on_error {
+      return Err(AgentFlowError::NodeExecutionFailed {
+        message: format!(
+          "Command node '{}' exited with {}: {}",
+          self.name,
+          exit_code.map_or("a signal".to_string(), |code| format!("code {code}")),
+          stderr.text.trim()
+        ),
+      });
+    }
+
+    let mut outputs = HashMap::new();
+    outputs.insert("stdout".to_string(), FlowValue::Json(json!(stdout.text)));
+    outputs.insert("stderr".to_string(), FlowValue::Json(json!(stderr.text)));
+    outputs.insert(
+      "exit_code".to_string(),
+      FlowValue::Json(exit_code.map_or(Value::Null, |code| json!(code))),
+    );
+    outputs.insert("success".to_string(), FlowValue::Json(json!(success)));
+    if stdout.truncated || stderr.truncated {
+      outputs.insert("truncated".to_string(), FlowValue::Json(json!(true)));
+    }
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::common::process_env::EnvValue;
+
+  fn inputs(pairs: &[(&str, Value)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn renders_args_and_reports_stdout() {
+    let node = CommandNode::new("echo", "echo").with_args(vec!["hello {{ who }}".to_string()]);
+
+    let outputs = node
+      .execute(&inputs(&[("who", json!("world"))]))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["stdout"], FlowValue::Json(json!("hello world\n")));
+    assert_eq!(outputs["exit_code"], FlowValue::Json(json!(0)));
+    assert_eq!(outputs["success"], FlowValue::Json(json!(true)));
+  }
+
+  #[tokio::test]
+  async fn stdin_is_piped_from_an_input() {
+    let node = CommandNode::new("upper", "tr")
+      .with_args(vec!["a-z".to_string(), "A-Z".to_string()])
+      .with_stdin_key("text");
+
+    let outputs = node
+      .execute(&inputs(&[("text", json!("piped text"))]))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["stdout"], FlowValue::Json(json!("PIPED TEXT")));
+  }
+
+  #[tokio::test]
+  async fn non_zero_exit_is_reported_or_fails_when_asked() {
+    let node = CommandNode::new("fail", "echo oops >&2; exit 3").with_allow_shell(true);
+
+    let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+    assert_eq!(outputs["exit_code"], FlowValue::Json(json!(3)));
+    assert_eq!(outputs["success"], FlowValue::Json(json!(false)));
+    assert_eq!(outputs["stderr"], FlowValue::Json(json!("oops\n")));
+
+    let err = node
+      .with_fail_on_error(true)
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("code 3"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn shell_placeholders_cannot_inject_commands() {
+    let payload = "x\"; echo INJECTED; '\"$(echo SUBST)";
+    let node = CommandNode::new(
+      "quoted",
+      "printf '%s|' {{ v }} \"in {{ v }}\" 'raw {{ v }}' \\{{ v }}",
+    )
+    .with_allow_shell(true);
+
+    let outputs = node
+      .execute(&inputs(&[("v", json!(payload))]))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      outputs["stdout"],
+      FlowValue::Json(json!(format!(
+        "{payload}|in {payload}|raw {payload}|{{{{|v|}}}}|"
+      )))
+    );
+  }
+
+  #[test]
+  fn shell_script_maps_placeholders_to_environment() {
+    let (script, values) = shell_script(
+      "cp {{ src }} \"{{ dst }}\"",
+      &inputs(&[("src", json!("a b")), ("dst", json!("c"))]),
+    )
+    .unwrap();
+
+    assert_eq!(
+      script,
+      "cp \"${AGENTFLOW_VALUE_1}\" \"${AGENTFLOW_VALUE_2}\""
+    );
+    assert_eq!(
+      values,
+      vec![
+        ("AGENTFLOW_VALUE_1".to_string(), "a b".to_string()),
+        ("AGENTFLOW_VALUE_2".to_string(), "c".to_string()),
+      ]
+    );
+    assert!(shell_script("echo {{ missing }}", &AsyncNodeInputs::new()).is_err());
+  }
+
+  #[tokio::test]
+  async fn process_env_reaches_the_child() {
+    let dir = std::env::temp_dir().join(format!("agentflow-command-env-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let process_env = ProcessEnv {
+      vars: std::collections::BTreeMap::from([(
+        "GREETING".to_string(),
+        EnvValue::from("hi from env"),
+      )]),
+      cwd: Some(dir.clone()),
+    };
+    let node = CommandNode::new("env", "echo \"$GREETING\" && pwd")
+      .with_allow_shell(true)
+      .with_process_env(process_env);
+
+    let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+
+    let FlowValue::Json(Value::String(stdout)) = &outputs["stdout"] else {
+      panic!("stdout should be a string");
+    };
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("hi from env"));
+    assert_eq!(
+      std::fs::canonicalize(lines.next().unwrap()).unwrap(),
+      std::fs::canonicalize(&dir).unwrap()
+    );
+    std::fs::remove_dir_all(&dir).ok();
+  }
+
+  #[tokio::test]
+  async fn timeout_kills_the_child() {
+    let node = CommandNode::new("sleepy", "sleep")
+      .with_args(vec!["5".to_string()])
+      .with_timeout(Duration::from_millis(100));
+
+    let started = std::time::Instant::now();
+    let err = node.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+
+    assert!(matches!(
+      err,
+      AgentFlowError::TimeoutExceeded { duration_ms: 100 }
+    ));
+    assert!(started.elapsed() < Duration::from_secs(2));
+  }
+
+  #[tokio::test]
+  async fn allowlist_and_output_limit_are_enforced() {
+    let refused = CommandNode::new("rm", "rm")
+      .with_allowed_programs(vec!["echo".to_string()])
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(refused.to_string().contains("not in the allowed programs"));
+
+    // With allow_shell the binary run is `sh`, which this list omits.
+    let refused = CommandNode::new("shell", "echo hi")
+      .with_allow_shell(true)
+      .with_allowed_programs(vec!["echo".to_string()])
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(refused.to_string().contains("'sh'"), "{refused}");
+
+    let outputs = CommandNode::new("long", "/bin/echo")
+      .with_args(vec!["0123456789".to_string()])
+      .with_allowed_programs(vec!["echo".to_string()])
+      .with_max_output_bytes(4)
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap();
+    assert_eq!(outputs["stdout"], FlowValue::Json(json!("0123")));
+    assert_eq!(outputs["truncated"], FlowValue::Json(json!(true)));
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn allowlist_compares_resolved_paths_not_file_names() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let impostor = dir.path().join("echo");
+    std::fs::write(&impostor, "#!/bin/sh\necho impostor\n").unwrap();
+    std::fs::set_permissions(&impostor, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let refused = CommandNode::new("fake", impostor.to_str().unwrap())
+      .with_allowed_programs(vec!["echo".to_string()])
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(
+      refused.to_string().contains("not in the allowed programs"),
+      "{refused}"
+    );
+
+    let outputs = CommandNode::new("fake", impostor.to_str().unwrap())
+      .with_allowed_programs(vec![impostor.to_str().unwrap().to_string()])
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap();
+    assert_eq!(outputs["stdout"], FlowValue::Json(json!("impostor\n")));
+  }
+}
@@ -1,20 +1,64 @@
+//! Conditional node.
+//!
+//! `ConditionalNode` evaluates a [`ConditionType`] against the node's own
+//! inputs. The typed checks (`Exists`, `Equals`, `GreaterThan`, ...) read
+//! one named input and treat a missing input as `false`; `Expression`
+//! uses the same language as Flow's `run_if` (`&&` / `||` / `!`, `==` /
+//! `!=` / `<` / `>=` ..., `contains()`, `len()`, ...), so `score >= 10`
+//! reads the `score` input and a missing input is an error. Numeric
+//! strings compare as numbers against numbers.
+//!
+//! Two shapes:
+//!
+//! - a single condition, whose branch is `"true"` or `"false"` and whose
+//!   `selected_value` is `true_value` / `false_value` (defaulting to the
+//!   boolean itself);
+//! - an ordered list of `cases` (`{label, when, value}`); the first case
+//!   whose `when` holds wins, else the `default` branch applies.
+//!
+//! Outputs `condition_result` (whether the condition / some case held),
+//! `branch` and `selected_value`, plus `selected_value` again under the
+//! legacy `output` key. Expressions are parsed once, on first use;
+//! [`ConditionalNode::from_params`] compiles them up front, so a malformed
+//! one is a configuration error.
+
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
+  expr,
   value::FlowValue,
 };
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Branch label used when no case matches and no default label is given.
+pub const DEFAULT_BRANCH: &str = "default";
+
+/// One labelled branch of a multi-case conditional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalCase {
+  pub label: String,
+  pub when: String,
+  pub value: Option<Value>,
+}
 
 /// Conditional node for flow control based on conditions
 #[derive(Debug, Clone)]
 pub struct ConditionalNode {
   pub name: String,
-  pub condition: String, // JavaScript-like expression or template
+  /// Input name for the typed checks, or the expression source for
+  /// `ConditionType::Expression`; unused when `cases` is non-empty.
+  pub condition: String,
   pub true_value: Option<Value>,
   pub false_value: Option<Value>,
   pub condition_type: ConditionType,
+  pub cases: Vec<ConditionalCase>,
+  pub default_label: String,
+  pub default_value: Option<Value>,
+  /// Parsed expressions: the condition, or one per case.
+  compiled: OnceLock<Vec<expr::CompiledExpr>>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,11 +73,13 @@ pub enum ConditionType {
   LessThan(f64),
   /// Contains check (for strings/arrays)
   Contains(String),
-  /// Custom expression (future: could support more complex logic)
+  /// `condition` is a `run_if`-style expression
   Expression,
 }
 
 impl ConditionalNode {
+  /// Existence check on the `condition` input; pick another check with
+  /// [`ConditionalNode::with_condition_type`].
   pub fn new(name: &str, condition: &str) -> Self {
     Self {
       name: name.to_string(),
@@ -41,11 +87,26 @@ impl ConditionalNode {
       true_value: None,
       false_value: None,
       condition_type: ConditionType::Exists,
+      cases: Vec::new(),
+      default_label: DEFAULT_BRANCH.to_string(),
+      default_value: None,
+      compiled: OnceLock::new(),
     }
   }
 
+  /// Evaluates `condition` as a `run_if`-style expression.
+  pub fn expression(name: &str, condition: &str) -> Self {
+    Self::new(name, condition).with_condition_type(ConditionType::Expression)
+  }
+
+  /// A multi-case node; add branches with [`ConditionalNode::with_case`].
+  pub fn cases(name: &str) -> Self {
+    Self::expression(name, "")
+  }
+
   pub fn with_condition_type(mut self, condition_type: ConditionType) -> Self {
     self.condition_type = condition_type;
+    self.compiled = OnceLock::new();
     self
   }
 
@@ -59,10 +120,29 @@ impl ConditionalNode {
     self
   }
 
+  pub fn with_case(mut self, label: &str, when: &str, value: Option<Value>) -> Self {
+    self.cases.push(ConditionalCase {
+      label: label.to_string(),
+      when: when.to_string(),
+      value,
+    });
+    self.compiled = OnceLock::new();
+    self
+  }
+
+  /// Branch taken when no case matches.
+  pub fn with_default(mut self, label: &str, value: Option<Value>) -> Self {
+    self.default_label = label.to_string();
+    self.default_value = value;
+    self
+  }
+
+  /// `variable == expected` (string comparison).
   pub fn equals(name: &str, variable: &str, expected: &str) -> Self {
     Self::new(name, variable).with_condition_type(ConditionType::Equals(expected.to_string()))
   }
 
+  #[deprecated(note = "use `ConditionalNode::new`, which checks existence by default")]
   pub fn exists(name: &str, variable: &str) -> Self {
     Self::new(name, variable).with_condition_type(ConditionType::Exists)
   }
@@ -71,86 +151,192 @@ impl ConditionalNode {
     Self::new(name, variable).with_condition_type(ConditionType::GreaterThan(threshold))
   }
 
-  pub fn contains(name: &str, variable: &str, substring: &str) -> Self {
-    Self::new(name, variable).with_condition_type(ConditionType::Contains(substring.to_string()))
+  pub fn less_than(name: &str, variable: &str, threshold: f64) -> Self {
+    Self::new(name, variable).with_condition_type(ConditionType::LessThan(threshold))
   }
 
-  /// Evaluate the condition against inputs
-  fn evaluate_condition(&self, inputs: &AsyncNodeInputs) -> Result<bool, AgentFlowError> {
-    let var_name = self.condition.as_str();
-
-    match &self.condition_type {
-      ConditionType::Exists => Ok(inputs.contains_key(var_name)),
-      ConditionType::Equals(expected) => {
-        if let Some(value) = inputs.get(var_name) {
-          let actual = match value {
-            FlowValue::Json(Value::String(s)) => s.clone(),
-            FlowValue::Json(v) => v.to_string(),
-            _ => "".to_string(),
+  /// Substring (strings) or element (arrays) check.
+  pub fn contains(name: &str, variable: &str, needle: &str) -> Self {
+    Self::new(name, variable).with_condition_type(ConditionType::Contains(needle.to_string()))
+  }
+
+  /// Builds the node from a `type: conditional` parameters block:
+  /// `condition` (+ `true_value` / `false_value`) or `cases`
+  /// (+ `default: {label, value}`), validating every expression.
+  pub fn from_params(name: &str, params: &Value) -> Result<Self, AgentFlowError> {
+    let config_error = |message: String| AgentFlowError::ConfigurationError { message };
+    let condition = params.get("condition").and_then(Value::as_str);
+    let cases = params.get("cases");
+
+    let mut node = match (condition, cases) {
+      (Some(condition), None) => {
+        let mut node = Self::expression(name, condition);
+        node.true_value = params.get("true_value").cloned();
+        node.false_value = params.get("false_value").cloned();
+        node
+      }
+      (None, Some(cases)) => {
+        let cases = cases
+          .as_array()
+          .filter(|cases| !cases.is_empty())
+          .ok_or_else(|| config_error("'cases' must be a non-empty list".to_string()))?;
+        let mut node = Self::cases(name);
+        for (index, case) in cases.iter().enumerate() {
+          let field = |key: &str| case.get(key).and_then(Value::as_str);
+          let (Some(label), Some(when)) = (field("label"), field("when")) else {
+            return Err(config_error(format!(
+              "case {} needs string 'label' and 'when' fields",
+              index
+            )));
           };
-          Ok(actual == *expected)
-        } else {
-          Ok(false)
+          node = node.with_case(label, when, case.get("value").cloned());
         }
+        node
       }
-      ConditionType::GreaterThan(threshold) => {
-        if let Some(value) = inputs.get(var_name) {
-          if let Some(number) = flow_value_as_f64(value) {
-            Ok(number > *threshold)
-          } else {
-            Ok(false)
-          }
-        } else {
-          Ok(false)
-        }
+      (Some(_), Some(_)) => {
+        return Err(config_error(
+          "set either 'condition' or 'cases', not both".to_string(),
+        ));
       }
-      ConditionType::LessThan(threshold) => {
-        if let Some(value) = inputs.get(var_name) {
-          if let Some(number) = flow_value_as_f64(value) {
-            Ok(number < *threshold)
-          } else {
-            Ok(false)
-          }
-        } else {
-          Ok(false)
-        }
+      (None, None) => {
+        return Err(config_error(
+          "requires a 'condition' expression or a 'cases' list".to_string(),
+        ));
       }
-      ConditionType::Contains(substring) => {
-        if let Some(value) = inputs.get(var_name) {
-          match value {
-            FlowValue::Json(Value::String(s)) => Ok(s.contains(substring)),
-            FlowValue::Json(Value::Array(arr)) => Ok(arr.iter().any(|item| {
-              if let Value::String(s) = item {
-                s == substring
-              } else {
-                false
-              }
-            })),
-            _ => Ok(false),
-          }
-        } else {
-          Ok(false)
+    };
+
+    if let Some(default) = params.get("default") {
+      let label = default
+        .get("label")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_BRANCH);
+      node = node.with_default(label, default.get("value").cloned());
+    }
+    node.validate()?;
+    Ok(node)
+  }
+
+  /// Compiles every expression, reporting the first that doesn't parse.
+  pub fn validate(&self) -> Result<(), AgentFlowError> {
+    self.compiled().map(|_| ())
+  }
+
+  /// The parsed expressions, compiled on first use.
+  fn compiled(&self) -> Result<&[expr::CompiledExpr], AgentFlowError> {
+    if let Some(compiled) = self.compiled.get() {
+      return Ok(compiled);
+    }
+    let check = |what: String, source: &str| {
+      expr::CompiledExpr::new(source).map_err(|err| AgentFlowError::ConfigurationError {
+        message: format!("{} '{}' is invalid: {}", what, source, err),
+      })
+    };
+    let compiled = if !self.cases.is_empty() {
+      self
+        .cases
+        .iter()
+        .map(|case| check(format!("case '{}'", case.label), &case.when))
+        .collect::<Result<Vec<_>, _>>()?
+    } else if matches!(self.condition_type, ConditionType::Expression) {
+      vec![check("condition".to_string(), &self.condition)?]
+    } else {
+      Vec::new()
+    };
+    Ok(self.compiled.get_or_init(|| compiled))
+  }
+
+  fn evaluate(
+    &self,
+    compiled: &expr::CompiledExpr,
+    inputs: &AsyncNodeInputs,
+  ) -> Result<bool, AgentFlowError> {
+    compiled
+      .evaluate_bool(&HashMap::new(), inputs)
+      .map_err(|err| AgentFlowError::NodeInputError {
+        message: format!(
+          "Conditional node '{}': cannot evaluate '{}': {}",
+          self.name,
+          compiled.source(),
+          err
+        ),
+      })
+  }
+
+  /// Evaluates the single condition; typed checks read a missing input
+  /// as `false`.
+  fn evaluate_condition(&self, inputs: &AsyncNodeInputs) -> Result<bool, AgentFlowError> {
+    let value = inputs.get(self.condition.as_str());
+    let result = match &self.condition_type {
+      ConditionType::Exists => value.is_some(),
+      ConditionType::Equals(expected) => value.is_some_and(|value| match value {
+        FlowValue::Json(Value::String(actual)) => actual == expected,
+        FlowValue::Json(actual) => {
+          serde_json::from_str::<Value>(expected).is_ok_and(|expected| *actual == expected)
         }
-      }
+        _ => false,
+      }),
+      ConditionType::GreaterThan(threshold) => value
+        .and_then(flow_value_as_f64)
+        .is_some_and(|number| number > *threshold),
+      ConditionType::LessThan(threshold) => value
+        .and_then(flow_value_as_f64)
+        .is_some_and(|number| number < *threshold),
+      ConditionType::Contains(needle) => value.is_some_and(|value| match value {
+        FlowValue::Json(Value::String(text)) => text.contains(needle.as_str()),
+        FlowValue::Json(Value::Array(items)) => items
+          .iter()
+          .any(|item| item.as_str() == Some(needle.as_str())),
+        _ => false,
+      }),
       ConditionType::Expression => {
-        // For now, just check if the condition string evaluates to true
-        // Future: could implement proper expression parsing
-        if let Some(FlowValue::Json(Value::Bool(b))) = inputs.get(var_name) {
-          Ok(*b)
-        } else {
-          Ok(false)
-        }
+        let compiled = self.compiled()?;
+        let Some(compiled) = compiled.first() else {
+          return Ok(false);
+        };
+        self.evaluate(compiled, inputs)?
+      }
+    };
+    Ok(result)
+  }
+
+  /// `(condition_result, branch, selected_value)` for `inputs`.
+  fn select(&self, inputs: &AsyncNodeInputs) -> Result<(bool, String, Value), AgentFlowError> {
+    if self.cases.is_empty() {
+      let result = self.evaluate_condition(inputs)?;
+      let value = if result {
+        self.true_value.clone()
+      } else {
+        self.false_value.clone()
+      };
+      return Ok((
+        result,
+        result.to_string(),
+        value.unwrap_or(Value::Bool(result)),
+      ));
+    }
+    for (case, compiled) in self.cases.iter().zip(self.compiled()?) {
+      if self.evaluate(compiled, inputs)? {
+        return Ok((
+          true,
+          case.label.clone(),
+          case.value.clone().unwrap_or(Value::Null),
+        ));
       }
     }
+    Ok((
+      false,
+      self.default_label.clone(),
+      self.default_value.clone().unwrap_or(Value::Null),
+    ))
   }
 }
 
-/// Extract a numeric value from a [`FlowValue`] for comparison conditions.
-/// Returns `None` for non-numeric variants so the condition reads as
-/// "not numeric, doesn't satisfy the comparison" rather than panicking.
+/// Numeric value of an input for the comparison checks; numeric strings
+/// count, anything else doesn't satisfy the comparison.
 fn flow_value_as_f64(value: &FlowValue) -> Option<f64> {
   match value {
-    FlowValue::Json(Value::Number(n)) => n.as_f64(),
+    FlowValue::Json(Value::Number(number)) => number.as_f64(),
+    FlowValue::Json(Value::String(text)) => text.trim().parse().ok(),
     _ => None,
   }
 }
@@ -158,32 +344,23 @@ fn flow_value_as_f64(value: &FlowValue) -> Option<f64> {
 #[async_trait]
 impl AsyncNode for ConditionalNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
-    let condition_result = self.evaluate_condition(inputs)?;
-
-    println!("🔧 Conditional Node '{}' prepared:", self.name);
-    println!("   Condition: {}", self.condition);
-    println!("   Type: {:?}", self.condition_type);
-    println!("   Result: {}", condition_result);
-
-    let result = if condition_result {
-      self.true_value.clone().unwrap_or(Value::Bool(true))
-    } else {
-      self.false_value.clone().unwrap_or(Value::Bool(false))
-    };
-
-    println!("✅ Conditional result: {}", result);
+    let (condition_result, branch, selected_value) = self.select(inputs)?;
+    println!("🔀 Conditional node '{}' → branch '{}'", self.name, branch);
 
     let mut outputs = HashMap::new();
-    outputs.insert("output".to_string(), FlowValue::Json(result));
     outputs.insert(
-      "branch".to_string(),
-      FlowValue::Json(Value::String(if condition_result {
-        "true".to_string()
-      } else {
-        "false".to_string()
-      })),
+      "condition_result".to_string(),
+      FlowValue::Json(Value::Bool(condition_result)),
+    );
+    outputs.insert("branch".to_string(), FlowValue::Json(Value::String(branch)));
+    outputs.insert(
+      "output".to_string(),
+      FlowValue::Json(selected_value.clone()),
+    );
+    outputs.insert(
+      "selected_value".to_string(),
+      FlowValue::Json(selected_value),
     );
-
     Ok(outputs)
   }
 }
@@ -193,50 +370,147 @@ mod tests {
   use super::*;
   use serde_json::json;
 
+  fn inputs(pairs: &[(&str, Value)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+      .collect()
+  }
+
+  fn output<'a>(outputs: &'a HashMap<String, FlowValue>, key: &str) -> &'a Value {
+    match outputs.get(key) {
+      Some(FlowValue::Json(value)) => value,
+      other => panic!("missing {key}: {other:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn compound_expression_selects_true_or_false_value() {
+    let node = ConditionalNode::from_params(
+      "route",
+      &json!({
+        "condition": "(score >= 10 && contains(tags, 'urgent')) || !enabled",
+        "true_value": "escalate",
+        "false_value": "queue",
+      }),
+    )
+    .unwrap();
+
+    let hit = node
+      .execute(&inputs(&[
+        ("score", json!(12)),
+        ("tags", json!(["urgent", "billing"])),
+        ("enabled", json!(true)),
+      ]))
+      .await
+      .unwrap();
+    assert_eq!(output(&hit, "condition_result"), &json!(true));
+    assert_eq!(output(&hit, "branch"), &json!("true"));
+    assert_eq!(output(&hit, "selected_value"), &json!("escalate"));
+
+    let miss = node
+      .execute(&inputs(&[
+        ("score", json!(12)),
+        ("tags", json!(["billing"])),
+        ("enabled", json!(true)),
+      ]))
+      .await
+      .unwrap();
+    assert_eq!(output(&miss, "branch"), &json!("false"));
+    assert_eq!(output(&miss, "selected_value"), &json!("queue"));
+  }
+
   #[tokio::test]
-  async fn test_conditional_node_exists() {
-    let node = ConditionalNode::exists("test", "my_var");
-    let mut inputs = AsyncNodeInputs::new();
-    inputs.insert("my_var".to_string(), FlowValue::Json(json!("some_value")));
+  async fn numeric_strings_compare_as_numbers() {
+    let node = ConditionalNode::greater_than("gt", "score", 9.5);
+    let outputs = node
+      .execute(&inputs(&[("score", json!("10"))]))
+      .await
+      .unwrap();
+    assert_eq!(output(&outputs, "condition_result"), &json!(true));
+    assert_eq!(output(&outputs, "selected_value"), &json!(true));
 
-    let result = node.execute(&inputs).await.unwrap();
-    let output = result.get("output").unwrap();
-    assert_eq!(output, &FlowValue::Json(json!(true)));
+    let outputs = ConditionalNode::equals("eq", "count", "3")
+      .execute(&inputs(&[("count", json!(3))]))
+      .await
+      .unwrap();
+    assert_eq!(output(&outputs, "condition_result"), &json!(true));
   }
 
   #[tokio::test]
-  async fn test_conditional_node_equals() {
-    let node = ConditionalNode::equals("test", "my_var", "expected_value");
-    let mut inputs = AsyncNodeInputs::new();
-    inputs.insert(
-      "my_var".to_string(),
-      FlowValue::Json(json!("expected_value")),
+  async fn first_matching_case_wins_else_default() {
+    let node = ConditionalNode::from_params(
+      "grade",
+      &json!({
+        "cases": [
+          { "label": "high", "when": "score >= 80", "value": "A" },
+          { "label": "medium", "when": "score >= 50", "value": "B" },
+        ],
+        "default": { "label": "low", "value": "C" },
+      }),
+    )
+    .unwrap();
+
+    for (score, branch, value, matched) in [
+      (json!(91), "high", "A", true),
+      (json!("65"), "medium", "B", true),
+      (json!(10), "low", "C", false),
+    ] {
+      let outputs = node.execute(&inputs(&[("score", score)])).await.unwrap();
+      assert_eq!(output(&outputs, "branch"), &json!(branch));
+      assert_eq!(output(&outputs, "selected_value"), &json!(value));
+      assert_eq!(output(&outputs, "condition_result"), &json!(matched));
+    }
+  }
+
+  #[test]
+  fn invalid_expressions_are_rejected_when_built() {
+    let err = ConditionalNode::from_params("c", &json!({ "condition": "score >= " })).unwrap_err();
+    assert!(
+      err.to_string().contains("condition 'score >= ' is invalid"),
+      "{err}"
     );
 
-    let result = node.execute(&inputs).await.unwrap();
-    let output = result.get("output").unwrap();
-    assert_eq!(output, &FlowValue::Json(json!(true)));
+    let err = ConditionalNode::from_params(
+      "c",
+      &json!({ "cases": [{ "label": "x", "when": "lenn(items) > 0" }] }),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("case 'x'"), "{err}");
+
+    assert!(ConditionalNode::from_params("c", &json!({})).is_err());
   }
 
   #[tokio::test]
-  async fn test_conditional_node_greater_than() {
-    let node = ConditionalNode::greater_than("test", "my_var", 10.0);
-    let mut inputs = AsyncNodeInputs::new();
-    inputs.insert("my_var".to_string(), FlowValue::Json(json!(15.0)));
+  #[allow(deprecated)]
+  async fn typed_checks_read_missing_inputs_as_false() {
+    let present = inputs(&[("user", json!("alice"))]);
+    for node in [
+      ConditionalNode::new("ex", "user"),
+      ConditionalNode::exists("ex", "user"),
+    ] {
+      let outputs = node.execute(&present).await.unwrap();
+      assert_eq!(output(&outputs, "output"), &json!(true));
+      let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+      assert_eq!(output(&outputs, "branch"), &json!("false"));
+    }
 
-    let result = node.execute(&inputs).await.unwrap();
-    let output = result.get("output").unwrap();
-    assert_eq!(output, &FlowValue::Json(json!(true)));
+    let outputs = ConditionalNode::contains("c", "body", "needle")
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap();
+    assert_eq!(output(&outputs, "condition_result"), &json!(false));
   }
 
   #[tokio::test]
-  async fn test_conditional_node_contains() {
-    let node = ConditionalNode::contains("test", "my_var", "sub");
-    let mut inputs = AsyncNodeInputs::new();
-    inputs.insert("my_var".to_string(), FlowValue::Json(json!("substring")));
-
-    let result = node.execute(&inputs).await.unwrap();
-    let output = result.get("output").unwrap();
-    assert_eq!(output, &FlowValue::Json(json!(true)));
+  async fn missing_input_is_an_input_error() {
+    let err = ConditionalNode::expression("c", "contains(body, 'needle')")
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::NodeInputError { .. }),
+      "{err}"
+    );
   }
 }
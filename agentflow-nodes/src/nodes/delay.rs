@@ -0,0 +1,127 @@
+//! Delay node - sleeps for a fixed or jittered duration.
+//!
+//! The node waits `duration` plus a uniformly random extra of up to
+//! `jitter`, then passes its inputs through unchanged, adding `delayed_ms`.
+//! Cancelling the flow ends the sleep with `TaskCancelled`; a flow deadline
+//! bounds it like any other node.
+
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  node_context,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use rand::Rng;
+use serde_json::json;
+
+#[derive(Debug, Clone)]
+pub struct DelayNode {
+  pub name: String,
+  pub duration: Duration,
+  pub jitter: Duration,
+}
+
+impl DelayNode {
+  pub fn new(name: &str, duration: Duration) -> Self {
+    Self {
+      name: name.to_string(),
+      duration,
+      jitter: Duration::ZERO,
+    }
+  }
+
+  /// Adds up to `jitter` (uniformly distributed) to every sleep.
+  pub fn with_jitter(mut self, jitter: Duration) -> Self {
+    self.jitter = jitter;
+    self
+  }
+
+  /// The next sleep: within `[duration, duration + jitter]`.
+  pub fn sample_duration(&self) -> Duration {
+    if self.jitter.is_zero() {
+      return self.duration;
+    }
+    let extra = rand::thread_rng().gen_range(Duration::ZERO..=self.jitter);
+    self.duration + extra
+  }
+}
+
+/// Sleeps for `duration` unless the flow is cancelled first.
+pub(crate) async fn cancellable_sleep(duration: Duration) -> Result<(), AgentFlowError> {
+  tokio::select! {
+    _ = tokio::time::sleep(duration) => Ok(()),
+    _ = node_context::cancelled() => Err(AgentFlowError::TaskCancelled),
+  }
+}
+
+#[async_trait]
+impl AsyncNode for DelayNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let duration = self.sample_duration();
+    println!(
+      "⏳ Delay node '{}' sleeping {}ms",
+      self.name,
+      duration.as_millis()
+    );
+    cancellable_sleep(duration).await?;
+
+    let mut outputs = inputs.clone();
+    outputs.insert(
+      "delayed_ms".to_string(),
+      FlowValue::Json(json!(duration.as_millis() as u64)),
+    );
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_core::node_context::NodeContext;
+  use agentflow_core::scheduler::FlowCancellationToken;
+
+  #[test]
+  fn jitter_stays_within_bounds() {
+    let node =
+      DelayNode::new("wait", Duration::from_millis(100)).with_jitter(Duration::from_millis(50));
+    for _ in 0..500 {
+      let sample = node.sample_duration();
+      assert!(
+        sample >= Duration::from_millis(100) && sample <= Duration::from_millis(150),
+        "{sample:?}"
+      );
+    }
+    let fixed = DelayNode::new("wait", Duration::from_millis(100));
+    assert_eq!(fixed.sample_duration(), Duration::from_millis(100));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn sleeps_then_passes_inputs_through() {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("topic".to_string(), FlowValue::Json(json!("rust")));
+    let started = tokio::time::Instant::now();
+
+    let outputs = DelayNode::new("wait", Duration::from_secs(5))
+      .execute(&inputs)
+      .await
+      .unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(5));
+    assert_eq!(outputs["topic"], FlowValue::Json(json!("rust")));
+    assert_eq!(outputs["delayed_ms"], FlowValue::Json(json!(5000)));
+  }
+
+  #[tokio::test]
+  async fn cancellation_ends_the_sleep() {
+    let token = FlowCancellationToken::new();
+    token.cancel();
+    let err = NodeContext::new("run", "wait", None, Some(token))
+      .scope(DelayNode::new("wait", Duration::from_secs(3_600)).execute(&AsyncNodeInputs::new()))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, AgentFlowError::TaskCancelled), "{err}");
+  }
+}
@@ -0,0 +1,801 @@
+//! Document rendering node - a markdown report template rendered to
+//! markdown, standalone HTML and optionally PDF files.
+//!
+//! The `template` is Tera markdown rendered against the node inputs exactly
+//! like `template` nodes (`{{ paper.title }}`, loops, filters). The result
+//! is then:
+//!
+//! - `markdown`: kept as-is;
+//! - `html`: converted with pulldown-cmark (tables, footnotes, strikethrough,
+//!   task lists) into a self-contained page with a bundled stylesheet.
+//!   Headings get slug `id`s, `table_of_contents` adds a `<nav class="toc">`,
+//!   and relative image paths are inlined as `data:` URIs (resolved against
+//!   `base_dir`, defaulting to `output_dir`) unless `embed_images` is off.
+//!   Absolute paths, `..` segments and symlinks leading outside that
+//!   directory are left as links and reported under `warnings`;
+//! - `pdf`: the HTML file converted by an external command - `pdf_command`
+//!   (argv with `{{ input }}` / `{{ output }}` placeholders) or, failing
+//!   that, `weasyprint` or a headless Chromium found on `PATH`. The command
+//!   runs through [`CommandNode`], so a host command allowlist applies.
+//!   Chromium keeps its sandbox; where it can't start one (e.g. as root in
+//!   a container) pass a `pdf_command` with `--no-sandbox` explicitly.
+//!
+//! With `output_dir` each requested format is written to
+//! `<output_dir>/<file_stem>.<ext>`. Outputs are `markdown`, `html` (when
+//! HTML or PDF was requested), `paths` (`{format: path}`), one
+//! `<format>_path` per written file, and `warnings` for images that
+//! couldn't be embedded.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd, html};
+use serde_json::{Map, Value, json};
+
+use crate::common::tera_helpers::{inputs_context, render_template};
+use crate::nodes::command::CommandNode;
+
+/// Stylesheet inlined into every HTML document unless `stylesheet` is set.
+pub const DEFAULT_STYLESHEET: &str = r#"
+:root { color-scheme: light; }
+body { margin: 0; background: #fff; color: #1f2328;
+  font: 16px/1.6 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; }
+main.document { max-width: 860px; margin: 0 auto; padding: 2.5rem 1.5rem 4rem; }
+h1, h2, h3, h4 { line-height: 1.25; margin: 1.8em 0 0.6em; }
+h1 { font-size: 2em; border-bottom: 1px solid #d1d9e0; padding-bottom: 0.3em; }
+h2 { font-size: 1.5em; border-bottom: 1px solid #d1d9e0; padding-bottom: 0.3em; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+code, pre { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; }
+code { background: #eff1f3; padding: 0.15em 0.35em; border-radius: 4px; }
+pre { background: #f6f8fa; padding: 1em; border-radius: 6px; overflow: auto; }
+pre code { background: none; padding: 0; }
+blockquote { margin: 0; padding: 0 1em; color: #59636e; border-left: 0.25em solid #d1d9e0; }
+table { border-collapse: collapse; margin: 1em 0; display: block; overflow: auto; }
+th, td { border: 1px solid #d1d9e0; padding: 6px 13px; }
+tr:nth-child(2n) { background: #f6f8fa; }
+img { max-width: 100%; }
+nav.toc { max-width: 860px; margin: 0 auto; padding: 1.5rem 1.5rem 0; }
+nav.toc ul { list-style: none; padding-left: 0; margin: 0; }
+nav.toc li.toc-level-2 { padding-left: 1.2em; }
+nav.toc li.toc-level-3 { padding-left: 2.4em; }
+@media print { main.document, nav.toc { max-width: none; padding: 0; } }
+"#;
+
+pub const DEFAULT_PDF_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Browsers tried (in order) when no `pdf_command` is set and `weasyprint`
+/// is not installed.
+const CHROMIUM_BINARIES: &[&str] = &["chromium", "chromium-browser", "google-chrome"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentFormat {
+  Markdown,
+  Html,
+  Pdf,
+}
+
+impl DocumentFormat {
+  pub fn parse(name: &str) -> Result<Self, AgentFlowError> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "markdown" | "md" => Ok(Self::Markdown),
+      "html" => Ok(Self::Html),
+      "pdf" => Ok(Self::Pdf),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Unknown document format '{}' (expected markdown, html or pdf)",
+          other
+        ),
+      }),
+    }
+  }
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Markdown => "markdown",
+      Self::Html => "html",
+      Self::Pdf => "pdf",
+    }
+  }
+
+  fn extension(self) -> &'static str {
+    match self {
+      Self::Markdown => "md",
+      Self::Html => "html",
+      Self::Pdf => "pdf",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentRenderNode {
+  pub name: String,
+  pub template: String,
+  pub formats: Vec<DocumentFormat>,
+  pub output_dir: Option<PathBuf>,
+  /// File name without extension; defaults to the node name.
+  pub file_stem: Option<String>,
+  /// HTML `<title>`; defaults to the first level-1 heading.
+  pub title: Option<String>,
+  pub table_of_contents: bool,
+  pub embed_images: bool,
+  /// Directory relative image paths are resolved against.
+  pub base_dir: Option<PathBuf>,
+  /// Replaces [`DEFAULT_STYLESHEET`].
+  pub stylesheet: Option<String>,
+  pub pdf_command: Option<Vec<String>>,
+  pub pdf_timeout: Duration,
+  /// Programs the PDF hook may run; `None` allows any.
+  pub allowed_programs: Option<Vec<String>>,
+}
+
+/// One heading, as collected for ids and the table of contents.
+struct Heading {
+  level: usize,
+  id: String,
+  text: String,
+}
+
+/// Body HTML plus what the conversion learned along the way.
+struct RenderedHtml {
+  body: String,
+  headings: Vec<Heading>,
+  warnings: Vec<String>,
+}
+
+impl DocumentRenderNode {
+  pub fn new(name: &str, template: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      template: template.to_string(),
+      formats: vec![DocumentFormat::Markdown],
+      output_dir: None,
+      file_stem: None,
+      title: None,
+      table_of_contents: false,
+      embed_images: true,
+      base_dir: None,
+      stylesheet: None,
+      pdf_command: None,
+      pdf_timeout: DEFAULT_PDF_TIMEOUT,
+      allowed_programs: None,
+    }
+  }
+
+  pub fn with_formats(mut self, formats: Vec<DocumentFormat>) -> Self {
+    self.formats = formats;
+    self
+  }
+
+  pub fn with_output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.output_dir = Some(dir.into());
+    self
+  }
+
+  pub fn with_file_stem(mut self, stem: &str) -> Self {
+    self.file_stem = Some(stem.to_string());
+    self
+  }
+
+  pub fn with_title(mut self, title: &str) -> Self {
+    self.title = Some(title.to_string());
+    self
+  }
+
+  pub fn with_table_of_contents(mut self, enabled: bool) -> Self {
+    self.table_of_contents = enabled;
+    self
+  }
+
+  pub fn with_embed_images(mut self, enabled: bool) -> Self {
+    self.embed_images = enabled;
+    self
+  }
+
+  pub fn with_base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.base_dir = Some(dir.into());
+    self
+  }
+
+  pub fn with_stylesheet(mut self, css: &str) -> Self {
+    self.stylesheet = Some(css.to_string());
+    self
+  }
+
+  /// Argv for the HTML → PDF conversion, e.g.
+  /// `["weasyprint", "{{ input }}", "{{ output }}"]`.
+  pub fn with_pdf_command(mut self, argv: Vec<String>) -> Self {
+    self.pdf_command = Some(argv);
+    self
+  }
+
+  pub fn with_pdf_timeout(mut self, timeout: Duration) -> Self {
+    self.pdf_timeout = timeout;
+    self
+  }
+
+  pub fn with_allowed_programs(mut self, programs: Option<Vec<String>>) -> Self {
+    self.allowed_programs = programs;
+    self
+  }
+
+  fn wants(&self, format: DocumentFormat) -> bool {
+    self.formats.contains(&format)
+  }
+
+  fn render_template(&self, inputs: &AsyncNodeInputs) -> Result<String, AgentFlowError> {
+    render_template(&self.template, &mut inputs_context(inputs), true).map_err(|reason| {
+      AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Document node '{}': template rendering failed{}",
+          self.name, reason
+        ),
+      }
+    })
+  }
+
+  fn image_dir(&self) -> PathBuf {
+    self
+      .base_dir
+      .clone()
+      .or_else(|| self.output_dir.clone())
+      .unwrap_or_else(|| PathBuf::from("."))
+  }
+
+  async fn markdown_to_html(&self, markdown: &str) -> RenderedHtml {
+    let options = Options::ENABLE_TABLES
+      | Options::ENABLE_FOOTNOTES
+      | Options::ENABLE_STRIKETHROUGH
+      | Options::ENABLE_TASKLISTS
+      | Options::ENABLE_HEADING_ATTRIBUTES;
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options).collect();
+    let mut headings = Vec::new();
+    let mut warnings = Vec::new();
+    let mut used_ids = HashSet::new();
+    let image_dir = self.image_dir();
+
+    for index in 0..events.len() {
+      match &events[index] {
+        Event::Start(Tag::Heading { level, id, .. }) => {
+          let level = heading_level(*level);
+          let text = heading_text(&events[index + 1..]);
+          let id = match id {
+            Some(id) => id.to_string(),
+            None => unique_slug(&text, &mut used_ids),
+          };
+          used_ids.insert(id.clone());
+          if let Event::Start(Tag::Heading { id: slot, .. }) = &mut events[index] {
+            *slot = Some(CowStr::from(id.clone()));
+          }
+          headings.push(Heading { level, id, text });
+        }
+        Event::Start(Tag::Image { dest_url, .. }) if self.embed_images => {
+          if !is_local_reference(dest_url) {
+            continue;
+          }
+          let path = match resolve_image(&image_dir, dest_url).await {
+            Ok(path) => path,
+            Err(reason) => {
+              warnings.push(format!("image '{}' not embedded: {}", dest_url, reason));
+              continue;
+            }
+          };
+          match tokio::fs::read(&path).await {
+            Ok(bytes) => {
+              let mime = mime_guess::from_path(&path).first_or_octet_stream();
+              let data_uri = format!("data:{};base64,{}", mime, STANDARD.encode(bytes));
+              if let Event::Start(Tag::Image { dest_url, .. }) = &mut events[index] {
+                *dest_url = CowStr::from(data_uri);
+              }
+            }
+            Err(err) => warnings.push(format!(
+              "image '{}' not embedded: {} ({})",
+              dest_url,
+              err,
+              path.display()
+            )),
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let mut body = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut body, events.into_iter());
+    RenderedHtml {
+      body,
+      headings,
+      warnings,
+    }
+  }
+
+  fn standalone_html(&self, rendered: &RenderedHtml) -> String {
+    let title = self
+      .title
+      .clone()
+      .or_else(|| {
+        rendered
+          .headings
+          .iter()
+          .find(|heading| heading.level == 1)
+          .map(|heading| heading.text.clone())
+      })
+      .unwrap_or_else(|| self.name.clone());
+    let stylesheet = self.stylesheet.as_deref().unwrap_or(DEFAULT_STYLESHEET);
+
+    let mut page = String::new();
+    page.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    page.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    page.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+    page.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", stylesheet));
+    if self.table_of_contents && !rendered.headings.is_empty() {
+      page.push_str("<nav class=\"toc\">\n<ul>\n");
+      for heading in rendered
+        .headings
+        .iter()
+        .filter(|heading| heading.level <= 3)
+      {
+        page.push_str(&format!(
+          "<li class=\"toc-level-{}\"><a href=\"#{}\">{}</a></li>\n",
+          heading.level,
+          escape_html(&heading.id),
+          escape_html(&heading.text)
+        ));
+      }
+      page.push_str("</ul>\n</nav>\n");
+    }
+    page.push_str("<main class=\"document\">\n");
+    page.push_str(&rendered.body);
+    page.push_str("</main>\n</body>\n</html>\n");
+    page
+  }
+
+  /// The argv used for PDF conversion: the configured one, else the first
+  /// known converter on `PATH`.
+  fn pdf_argv(&self) -> Result<Vec<String>, AgentFlowError> {
+    if let Some(argv) = self.pdf_command.clone().filter(|argv| !argv.is_empty()) {
+      return Ok(argv);
+    }
+    if find_on_path("weasyprint").is_some() {
+      return Ok(
+        ["weasyprint", "{{ input }}", "{{ output }}"]
+          .map(str::to_string)
+          .to_vec(),
+      );
+    }
+    if let Some(browser) = CHROMIUM_BINARIES
+      .iter()
+      .find(|binary| find_on_path(binary).is_some())
+    {
+      return Ok(vec![
+        browser.to_string(),
+        "--headless".to_string(),
+        "--disable-gpu".to_string(),
+        "--print-to-pdf={{ output }}".to_string(),
+        "file://{{ input }}".to_string(),
+      ]);
+    }
+    Err(AgentFlowError::ConfigurationError {
+      message: format!(
+        "Document node '{}': PDF output needs weasyprint or a headless Chromium \
+         ({}) on PATH, or an explicit pdf_command",
+        self.name,
+        CHROMIUM_BINARIES.join(" / ")
+      ),
+    })
+  }
+
+  async fn write_pdf(&self, html_path: &Path, pdf_path: &Path) -> Result<(), AgentFlowError> {
+    let argv = self.pdf_argv()?;
+    let absolute = |path: &Path| {
+      std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+    };
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "input".to_string(),
+      FlowValue::Json(json!(absolute(html_path))),
+    );
+    inputs.insert(
+      "output".to_string(),
+      FlowValue::Json(json!(absolute(pdf_path))),
+    );
+
+    let mut command = CommandNode::new(&format!("{}.pdf", self.name), &argv[0])
+      .with_args(argv[1..].to_vec())
+      .with_env_allowlist(vec!["HOME".to_string()])
+      .with_timeout(self.pdf_timeout)
+      .with_fail_on_error(true);
+    if let Some(allowed) = &self.allowed_programs {
+      command = command.with_allowed_programs(allowed.clone());
+    }
+    command.execute(&inputs).await.map_err(|err| match err {
+      AgentFlowError::AsyncExecutionError { message } if message.contains("failed to start") => {
+        AgentFlowError::ConfigurationError {
+          message: format!(
+            "Document node '{}': PDF converter '{}' is unavailable: {}",
+            self.name, argv[0], message
+          ),
+        }
+      }
+      other => other,
+    })?;
+
+    if !tokio::fs::try_exists(pdf_path).await.unwrap_or(false) {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Document node '{}': '{}' finished without writing {}",
+          self.name,
+          argv[0],
+          pdf_path.display()
+        ),
+      });
+    }
+    Ok(())
+  }
+
+  async fn write_file(&self, path: &Path, contents: &str) -> Result<(), AgentFlowError> {
+    tokio::fs::write(path, contents)
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Document node '{}': failed to write {}: {}",
+          self.name,
+          path.display(),
+          e
+        ),
+      })
+  }
+}
+
+#[async_trait]
+impl AsyncNode for DocumentRenderNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    if self.wants(DocumentFormat::Pdf) && self.output_dir.is_none() {
+      return Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Document node '{}': pdf output requires output_dir",
+          self.name
+        ),
+      });
+    }
+
+    println!("📄 Rendering document for node '{}'", self.name);
+    let markdown = self.render_template(inputs)?;
+    let needs_html = self.wants(DocumentFormat::Html) || self.wants(DocumentFormat::Pdf);
+    let rendered = if needs_html {
+      Some(self.markdown_to_html(&markdown).await)
+    } else {
+      None
+    };
+    let page = rendered
+      .as_ref()
+      .map(|rendered| self.standalone_html(rendered));
+    let warnings = rendered
+      .map(|rendered| rendered.warnings)
+      .unwrap_or_default();
+    for warning in &warnings {
+      eprintln!("⚠️  Document node '{}': {}", self.name, warning);
+    }
+
+    let mut paths = Map::new();
+    if let Some(dir) = &self.output_dir {
+      tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Document node '{}': failed to create {}: {}",
+            self.name,
+            dir.display(),
+            e
+          ),
+        })?;
+      let stem = self.file_stem.as_deref().unwrap_or(&self.name);
+      let path_for = |format: DocumentFormat| dir.join(format!("{stem}.{}", format.extension()));
+
+      if self.wants(DocumentFormat::Markdown) {
+        let path = path_for(DocumentFormat::Markdown);
+        self.write_file(&path, &markdown).await?;
+        paths.insert("markdown".to_string(), json!(path.to_string_lossy()));
+      }
+      if let Some(page) = &page {
+        // PDF converters read the HTML file, so it is written either way.
+        let html_path = path_for(DocumentFormat::Html);
+        self.write_file(&html_path, page).await?;
+        paths.insert("html".to_string(), json!(html_path.to_string_lossy()));
+        if self.wants(DocumentFormat::Pdf) {
+          let pdf_path = path_for(DocumentFormat::Pdf);
+          self.write_pdf(&html_path, &pdf_path).await?;
+          paths.insert("pdf".to_string(), json!(pdf_path.to_string_lossy()));
+        }
+      }
+      for (format, path) in &paths {
+        println!(
+          "💾 Wrote {} to {}",
+          format,
+          path.as_str().unwrap_or_default()
+        );
+      }
+    }
+
+    let mut outputs = HashMap::new();
+    outputs.insert("markdown".to_string(), FlowValue::Json(json!(markdown)));
+    if let Some(page) = page {
+      outputs.insert("html".to_string(), FlowValue::Json(json!(page)));
+    }
+    for (format, path) in &paths {
+      outputs.insert(format!("{format}_path"), FlowValue::Json(path.clone()));
+    }
+    outputs.insert("paths".to_string(), FlowValue::Json(Value::Object(paths)));
+    if !warnings.is_empty() {
+      outputs.insert("warnings".to_string(), FlowValue::Json(json!(warnings)));
+    }
+    Ok(outputs)
+  }
+}
+
+fn heading_level(level: HeadingLevel) -> usize {
+  match level {
+    HeadingLevel::H1 => 1,
+    HeadingLevel::H2 => 2,
+    HeadingLevel::H3 => 3,
+    HeadingLevel::H4 => 4,
+    HeadingLevel::H5 => 5,
+    HeadingLevel::H6 => 6,
+  }
+}
+
+/// Plain text of the heading whose events start at `events[0]`.
+fn heading_text(events: &[Event]) -> String {
+  let mut text = String::new();
+  for event in events {
+    match event {
+      Event::End(TagEnd::Heading(_)) => break,
+      Event::Text(part) | Event::Code(part) => text.push_str(part),
+      _ => {}
+    }
+  }
+  text.trim().to_string()
+}
+
+/// GitHub-style anchor: lower-cased alphanumerics joined by `-`, with a
+/// numeric suffix when the slug is already taken.
+fn unique_slug(text: &str, used: &mut HashSet<String>) -> String {
+  let mut slug = String::new();
+  for c in text.chars().flat_map(char::to_lowercase) {
+    if c.is_alphanumeric() {
+      slug.push(c);
+    } else if (c == ' ' || c == '-' || c == '_') && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+  let slug = match slug.trim_matches('-') {
+    "" => "section".to_string(),
+    trimmed => trimmed.to_string(),
+  };
+  let mut candidate = slug.clone();
+  let mut suffix = 1;
+  while used.contains(&candidate) {
+    candidate = format!("{slug}-{suffix}");
+    suffix += 1;
+  }
+  used.insert(candidate.clone());
+  candidate
+}
+
+/// Whether an image reference points at a local file (not a URL or
+/// `data:` URI).
+fn is_local_reference(dest: &str) -> bool {
+  !(dest.is_empty() || dest.contains("://") || dest.starts_with("//") || dest.starts_with("data:"))
+}
+
+/// The file an image reference names under `image_dir`: relative, with no
+/// `..` segments, and still inside `image_dir` once symlinks are resolved.
+async fn resolve_image(image_dir: &Path, dest: &str) -> Result<PathBuf, String> {
+  let relative = Path::new(dest);
+  if relative
+    .components()
+    .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+  {
+    return Err("only relative paths inside the image directory are embedded".to_string());
+  }
+  let root = tokio::fs::canonicalize(image_dir)
+    .await
+    .map_err(|err| format!("{} ({})", err, image_dir.display()))?;
+  let path = tokio::fs::canonicalize(root.join(relative))
+    .await
+    .map_err(|err| format!("{} ({})", err, root.join(relative).display()))?;
+  if !path.starts_with(&root) {
+    return Err(format!("{} is outside {}", path.display(), root.display()));
+  }
+  Ok(path)
+}
+
+fn find_on_path(program: &str) -> Option<PathBuf> {
+  std::env::var_os("PATH").and_then(|path| {
+    std::env::split_paths(&path)
+      .map(|dir| dir.join(program))
+      .find(|candidate| candidate.is_file())
+  })
+}
+
+fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const REPORT: &str = r#"# {{ title }}
+
+Prepared for {{ team }}.
+
+## Results
+
+| Metric | Value |
+| ------ | ----- |
+{% for row in metrics %}| {{ row.name }} | {{ row.value }} |
+{% endfor %}
+![Latency plot](figures/latency.png)
+
+## Results
+
+Second section with a duplicate heading.
+"#;
+
+  fn inputs() -> AsyncNodeInputs {
+    [
+      ("title", json!("Quarterly Report")),
+      ("team", json!("Platform")),
+      (
+        "metrics",
+        json!([{ "name": "p50", "value": "12ms" }, { "name": "p99", "value": "80ms" }]),
+      ),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), FlowValue::Json(value)))
+    .collect()
+  }
+
+  fn string_output(outputs: &HashMap<String, FlowValue>, key: &str) -> String {
+    match outputs.get(key) {
+      Some(FlowValue::Json(Value::String(text))) => text.clone(),
+      other => panic!("missing {key}: {other:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn renders_a_report_to_markdown_and_standalone_html() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("figures")).unwrap();
+    std::fs::write(dir.path().join("figures/latency.png"), b"\x89PNG fake").unwrap();
+
+    let node = DocumentRenderNode::new("report", REPORT)
+      .with_formats(vec![DocumentFormat::Markdown, DocumentFormat::Html])
+      .with_output_dir(dir.path())
+      .with_table_of_contents(true);
+    let outputs = node.execute(&inputs()).await.unwrap();
+
+    let markdown = string_output(&outputs, "markdown");
+    assert!(markdown.contains("| p99 | 80ms |"), "{markdown}");
+    assert_eq!(
+      std::fs::read_to_string(dir.path().join("report.md")).unwrap(),
+      markdown
+    );
+
+    let html = string_output(&outputs, "html");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Quarterly Report</title>"));
+    assert!(html.contains("<style>"));
+    assert!(html.contains(r#"<h1 id="quarterly-report">Quarterly Report</h1>"#));
+    assert!(html.contains(r#"<h2 id="results">Results</h2>"#));
+    assert!(html.contains(r#"<h2 id="results-1">Results</h2>"#));
+    assert!(html.contains("<table>"));
+    assert!(html.contains("<th>Metric</th>"));
+    assert!(html.contains("<td>80ms</td>"));
+    let expected_image = format!(
+      r#"<img src="data:image/png;base64,{}" alt="Latency plot" />"#,
+      STANDARD.encode(b"\x89PNG fake")
+    );
+    assert!(html.contains(&expected_image), "{html}");
+
+    let toc_start = html.find(r#"<nav class="toc">"#).unwrap();
+    let main_start = html.find(r#"<main class="document">"#).unwrap();
+    assert!(toc_start < main_start);
+    assert!(html.contains(r##"<li class="toc-level-2"><a href="#results-1">Results</a></li>"##));
+
+    let html_path = string_output(&outputs, "html_path");
+    assert_eq!(std::fs::read_to_string(html_path).unwrap(), html);
+    assert!(!outputs.contains_key("warnings"));
+  }
+
+  #[tokio::test]
+  async fn template_errors_point_at_the_missing_variable() {
+    let err = DocumentRenderNode::new("doc", "# Report\n\nBy {{ author.name }}\n")
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("Document node 'doc': template rendering failed at line 3, column 7"),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  async fn missing_images_are_reported_not_fatal() {
+    let node = DocumentRenderNode::new("doc", "# Plot\n\n![x](nowhere/plot.png)\n")
+      .with_formats(vec![DocumentFormat::Html])
+      .with_base_dir(std::env::temp_dir());
+    let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+
+    assert!(string_output(&outputs, "html").contains(r#"<img src="nowhere/plot.png""#));
+    assert!(matches!(
+      outputs.get("warnings"),
+      Some(FlowValue::Json(Value::Array(warnings))) if warnings.len() == 1
+    ));
+  }
+
+  #[tokio::test]
+  async fn images_outside_the_base_dir_are_not_embedded() {
+    let root = tempfile::tempdir().unwrap();
+    let base = root.path().join("report");
+    std::fs::create_dir(&base).unwrap();
+    std::fs::write(root.path().join("secret.png"), b"secret").unwrap();
+    let absolute = root.path().join("secret.png");
+
+    let markdown = format!("![a](../secret.png)\n\n![b]({})\n", absolute.display());
+    let node = DocumentRenderNode::new("doc", &markdown)
+      .with_formats(vec![DocumentFormat::Html])
+      .with_base_dir(&base);
+    let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+
+    let html = string_output(&outputs, "html");
+    assert!(!html.contains(&STANDARD.encode(b"secret")), "{html}");
+    assert!(matches!(
+      outputs.get("warnings"),
+      Some(FlowValue::Json(Value::Array(warnings))) if warnings.len() == 2
+    ));
+  }
+
+  #[tokio::test]
+  async fn unavailable_pdf_converter_is_a_clear_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let node = DocumentRenderNode::new("doc", "# Title\n")
+      .with_formats(vec![DocumentFormat::Pdf])
+      .with_output_dir(dir.path())
+      .with_pdf_command(vec![
+        "agentflow-no-such-converter".to_string(),
+        "{{ input }}".to_string(),
+        "{{ output }}".to_string(),
+      ]);
+    let err = node.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(
+      matches!(&err, AgentFlowError::ConfigurationError { message }
+        if message.contains("PDF converter 'agentflow-no-such-converter' is unavailable")),
+      "{err}"
+    );
+  }
+}
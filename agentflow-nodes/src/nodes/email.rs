@@ -0,0 +1,636 @@
+//! Email node - sends a templated message over SMTP, with attachments.
+//!
+//! `subject`, `text` and `html` are templates whose `{{name}}` placeholders
+//! are filled from the node's inputs; values substituted into `html` are
+//! HTML-escaped. With both bodies the message is `multipart/alternative`. Recipients come from the `to` / `cc` / `bcc`
+//! inputs, each an address (`Name <addr@example.com>` or a bare address),
+//! a comma-separated list of them, or an array.
+//!
+//! The `attachments` input is a file path, a `FlowValue::File`, an object,
+//! or an array of those. Objects are `{path}` or `{filename, content}` /
+//! `{filename, base64}` for inline data, plus an optional `content_type`;
+//! otherwise the type is guessed from the file name. The attachments'
+//! combined size is capped at `max_attachment_bytes`.
+//!
+//! The SMTP password is a [`SecretValue`]. In dry-run mode nothing is sent:
+//! the full MIME message is written to `<run dir>/<node>_email_<id>.eml`
+//! (the system temp dir outside a flow) and its path output as `eml_path`.
+//! Outputs are `message_id`, `recipients` (every envelope address),
+//! `accepted_recipients` and `dry_run`. The SMTP server has accepted
+//! `accepted_recipients`: after a send that is every recipient, since a
+//! rejected `RCPT TO` fails the send, and after a dry run it is empty.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use agentflow_core::{
+  SecretValue,
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  node_context,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use lettre::message::{Attachment, Mailbox, Message, MultiPart, SinglePart, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde_json::{Value, json};
+
+use crate::common::template::{self, Escape, RenderOptions};
+use crate::common::utils::{flow_value_to_string, resolve_placeholders};
+
+/// Default cap on the combined size of a message's attachments.
+pub const DEFAULT_MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// How the SMTP connection is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+  /// Plain connection upgraded with `STARTTLS` (port 587 by default).
+  Starttls,
+  /// TLS from the first byte (port 465 by default).
+  Tls,
+  /// Unencrypted (port 25 by default); for local relays and test servers.
+  None,
+}
+
+impl SmtpTls {
+  pub fn parse(value: &str) -> Result<Self, String> {
+    match value {
+      "starttls" => Ok(Self::Starttls),
+      "tls" => Ok(Self::Tls),
+      "none" => Ok(Self::None),
+      other => Err(format!(
+        "unknown tls mode '{other}' (expected starttls, tls or none)"
+      )),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+  pub host: String,
+  /// Defaults to the port of the `tls` mode.
+  pub port: Option<u16>,
+  pub tls: SmtpTls,
+  pub username: Option<String>,
+  pub password: Option<SecretValue>,
+  pub timeout: Duration,
+}
+
+impl SmtpSettings {
+  pub fn new(host: &str) -> Self {
+    Self {
+      host: host.to_string(),
+      port: None,
+      tls: SmtpTls::Starttls,
+      username: None,
+      password: None,
+      timeout: Duration::from_secs(30),
+    }
+  }
+
+  pub fn with_port(mut self, port: u16) -> Self {
+    self.port = Some(port);
+    self
+  }
+
+  pub fn with_tls(mut self, tls: SmtpTls) -> Self {
+    self.tls = tls;
+    self
+  }
+
+  pub fn with_credentials(mut self, username: &str, password: impl Into<SecretValue>) -> Self {
+    self.username = Some(username.to_string());
+    self.password = Some(password.into());
+    self
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  fn transport(
+    &self,
+  ) -> Result<AsyncSmtpTransport<Tokio1Executor>, lettre::transport::smtp::Error> {
+    let mut builder = match self.tls {
+      SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?,
+      SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?,
+      SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+    };
+    if let Some(port) = self.port {
+      builder = builder.port(port);
+    }
+    if let (Some(username), Some(password)) = (&self.username, &self.password) {
+      builder = builder.credentials(Credentials::new(
+        username.clone(),
+        password.expose_secret().to_string(),
+      ));
+    }
+    Ok(builder.timeout(Some(self.timeout)).build())
+  }
+}
+
+/// Parses one recipient value: an address, a comma-separated list of them,
+/// or an array of either.
+pub fn parse_recipients(value: &Value) -> Result<Vec<Mailbox>, String> {
+  match value {
+    Value::Null => Ok(Vec::new()),
+    Value::String(list) => list
+      .split(',')
+      .map(str::trim)
+      .filter(|address| !address.is_empty())
+      .map(|address| {
+        address
+          .parse::<Mailbox>()
+          .map_err(|_| format!("'{address}' is not a valid email address"))
+      })
+      .collect(),
+    Value::Array(items) => items.iter().try_fold(Vec::new(), |mut all, item| {
+      all.extend(parse_recipients(item)?);
+      Ok(all)
+    }),
+    other => Err(format!(
+      "recipients must be a string or an array, got {other}"
+    )),
+  }
+}
+
+/// An attachment ready to encode.
+struct AttachmentData {
+  filename: String,
+  content_type: String,
+  body: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailNode {
+  pub name: String,
+  pub smtp: SmtpSettings,
+  pub from: String,
+  pub subject: String,
+  pub text: Option<String>,
+  pub html: Option<String>,
+  pub max_attachment_bytes: u64,
+  pub dry_run: bool,
+}
+
+impl EmailNode {
+  pub fn new(name: &str, smtp: SmtpSettings, from: &str, subject: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      smtp,
+      from: from.to_string(),
+      subject: subject.to_string(),
+      text: None,
+      html: None,
+      max_attachment_bytes: DEFAULT_MAX_ATTACHMENT_BYTES,
+      dry_run: false,
+    }
+  }
+
+  pub fn with_text(mut self, text: &str) -> Self {
+    self.text = Some(text.to_string());
+    self
+  }
+
+  pub fn with_html(mut self, html: &str) -> Self {
+    self.html = Some(html.to_string());
+    self
+  }
+
+  pub fn with_max_attachment_bytes(mut self, max_attachment_bytes: u64) -> Self {
+    self.max_attachment_bytes = max_attachment_bytes;
+    self
+  }
+
+  /// Renders the message to the run directory instead of sending it.
+  pub fn dry_run(mut self) -> Self {
+    self.dry_run = true;
+    self
+  }
+
+  fn input_error(&self, message: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::NodeInputError {
+      message: format!("Email node '{}': {}", self.name, message),
+    }
+  }
+
+  fn error(&self, message: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::AsyncExecutionError {
+      message: format!("Email node '{}': {}", self.name, message),
+    }
+  }
+
+  fn recipients(
+    &self,
+    inputs: &AsyncNodeInputs,
+    key: &str,
+  ) -> Result<Vec<Mailbox>, AgentFlowError> {
+    let value = match inputs.get(key) {
+      None => return Ok(Vec::new()),
+      Some(FlowValue::Json(value)) => value.clone(),
+      Some(other) => json!(flow_value_to_string(other)),
+    };
+    parse_recipients(&value).map_err(|err| self.input_error(format!("{key}: {err}")))
+  }
+
+  async fn attachments(
+    &self,
+    inputs: &AsyncNodeInputs,
+  ) -> Result<Vec<AttachmentData>, AgentFlowError> {
+    let entries = match inputs.get("attachments") {
+      None | Some(FlowValue::Json(Value::Null)) => return Ok(Vec::new()),
+      Some(FlowValue::File { path, mime_type }) => {
+        vec![json!({ "path": path, "content_type": mime_type })]
+      }
+      Some(FlowValue::Url { url, .. }) => {
+        return Err(self.input_error(format!(
+          "attachment {url} is a URL; download it to a file first"
+        )));
+      }
+      Some(FlowValue::Json(Value::Array(entries))) => entries.clone(),
+      Some(FlowValue::Json(entry)) => vec![entry.clone()],
+    };
+
+    let mut attachments = Vec::with_capacity(entries.len());
+    let mut total = 0u64;
+    for entry in entries {
+      let entry = match entry {
+        Value::String(path) => json!({ "path": path }),
+        entry @ Value::Object(_) => entry,
+        other => return Err(self.input_error(format!("invalid attachment {other}"))),
+      };
+      let field = |key: &str| entry.get(key).and_then(Value::as_str);
+      let (filename, body) = match (field("path"), field("content"), field("base64")) {
+        (Some(path), _, _) => {
+          let path = PathBuf::from(path);
+          let size = tokio::fs::metadata(&path)
+            .await
+            .map_err(|err| self.input_error(format!("attachment {}: {err}", path.display())))?
+            .len();
+          self.check_size(total + size)?;
+          let body = tokio::fs::read(&path)
+            .await
+            .map_err(|err| self.input_error(format!("attachment {}: {err}", path.display())))?;
+          let filename = field("filename").map(str::to_string).unwrap_or_else(|| {
+            path
+              .file_name()
+              .map(|name| name.to_string_lossy().into_owned())
+              .unwrap_or_else(|| "attachment".to_string())
+          });
+          (filename, body)
+        }
+        (None, content, base64) => {
+          let Some(filename) = field("filename") else {
+            return Err(self.input_error("inline attachments need a 'filename'"));
+          };
+          let body = match (content, base64) {
+            (Some(content), _) => content.as_bytes().to_vec(),
+            (None, Some(base64)) => STANDARD.decode(base64.trim()).map_err(|err| {
+              self.input_error(format!("attachment {filename}: invalid base64: {err}"))
+            })?,
+            (None, None) => {
+              return Err(self.input_error(format!(
+                "attachment {filename} needs a 'path', 'content' or 'base64'"
+              )));
+            }
+          };
+          (filename.to_string(), body)
+        }
+      };
+      total += body.len() as u64;
+      self.check_size(total)?;
+      let content_type = field("content_type")
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+          mime_guess::from_path(&filename)
+            .first_or_octet_stream()
+            .to_string()
+        });
+      attachments.push(AttachmentData {
+        filename,
+        content_type,
+        body,
+      });
+    }
+    Ok(attachments)
+  }
+
+  fn check_size(&self, total: u64) -> Result<(), AgentFlowError> {
+    if total > self.max_attachment_bytes {
+      return Err(self.input_error(format!(
+        "attachments exceed the {} byte limit",
+        self.max_attachment_bytes
+      )));
+    }
+    Ok(())
+  }
+
+  /// Builds the message from `inputs`.
+  pub async fn build_message(&self, inputs: &AsyncNodeInputs) -> Result<Message, AgentFlowError> {
+    let render =
+      |template: &str| resolve_placeholders(template, inputs).map_err(|err| self.input_error(err));
+    let from = self.from.parse::<Mailbox>().map_err(|_| {
+      self.input_error(format!(
+        "from: '{}' is not a valid email address",
+        self.from
+      ))
+    })?;
+    let mut builder = Message::builder()
+      .from(from)
+      .subject(render(&self.subject)?)
+      .message_id(None);
+    let mut recipient_count = 0;
+    for key in ["to", "cc", "bcc"] {
+      for mailbox in self.recipients(inputs, key)? {
+        recipient_count += 1;
+        builder = match key {
+          "to" => builder.to(mailbox),
+          "cc" => builder.cc(mailbox),
+          _ => builder.bcc(mailbox),
+        };
+      }
+    }
+    if recipient_count == 0 {
+      return Err(self.input_error("needs at least one 'to', 'cc' or 'bcc' recipient"));
+    }
+
+    let text = self.text.as_deref().map(render).transpose()?;
+    let html = self
+      .html
+      .as_deref()
+      .map(|html| {
+        template::render(
+          html,
+          inputs,
+          &RenderOptions::strict().with_escape(Escape::Html),
+        )
+        .map_err(|err| self.input_error(err))
+      })
+      .transpose()?;
+    let body = match (text, html) {
+      (Some(text), Some(html)) => MultiPart::alternative_plain_html(text, html),
+      (None, Some(html)) => MultiPart::mixed().singlepart(SinglePart::html(html)),
+      (text, None) => MultiPart::mixed().singlepart(SinglePart::plain(text.unwrap_or_default())),
+    };
+    let attachments = self.attachments(inputs).await?;
+    let message = if attachments.is_empty() {
+      builder.multipart(body)
+    } else {
+      let mut mixed = MultiPart::mixed().multipart(body);
+      for attachment in attachments {
+        let content_type = ContentType::parse(&attachment.content_type).map_err(|err| {
+          self.input_error(format!(
+            "attachment {}: invalid content_type '{}': {err}",
+            attachment.filename, attachment.content_type
+          ))
+        })?;
+        mixed = mixed
+          .singlepart(Attachment::new(attachment.filename).body(attachment.body, content_type));
+      }
+      builder.multipart(mixed)
+    };
+    message.map_err(|err| self.input_error(format!("cannot build message: {err}")))
+  }
+}
+
+#[async_trait]
+impl AsyncNode for EmailNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let message = self.build_message(inputs).await?;
+    let message_id = message
+      .headers()
+      .get_raw("Message-ID")
+      .unwrap_or_default()
+      .to_string();
+    let recipients: Vec<String> = message
+      .envelope()
+      .to()
+      .iter()
+      .map(ToString::to_string)
+      .collect();
+
+    let mut outputs = HashMap::from([
+      ("message_id".to_string(), FlowValue::Json(json!(message_id))),
+      ("recipients".to_string(), FlowValue::Json(json!(recipients))),
+      ("dry_run".to_string(), FlowValue::Json(json!(self.dry_run))),
+    ]);
+
+    if self.dry_run {
+      let dir = node_context::run_dir().unwrap_or_else(std::env::temp_dir);
+      let id = uuid::Uuid::new_v4().simple().to_string();
+      let path = dir.join(format!("{}_email_{}.eml", self.name, &id[..8]));
+      tokio::fs::create_dir_all(&dir)
+        .await
+        .and(tokio::fs::write(&path, message.formatted()).await)
+        .map_err(|err| self.error(format!("cannot write {}: {err}", path.display())))?;
+      println!(
+        "✉️  Email node '{}' (dry run) wrote {}",
+        self.name,
+        path.display()
+      );
+      outputs.insert(
+        "eml_path".to_string(),
+        FlowValue::Json(json!(path.display().to_string())),
+      );
+      outputs.insert(
+        "accepted_recipients".to_string(),
+        FlowValue::Json(json!([])),
+      );
+      return Ok(outputs);
+    }
+
+    let transport = self
+      .smtp
+      .transport()
+      .map_err(|err| self.error(format!("invalid SMTP settings: {err}")))?;
+    transport
+      .send(message)
+      .await
+      .map_err(|err| self.error(format!("SMTP send via {} failed: {err}", self.smtp.host)))?;
+    println!(
+      "✉️  Email node '{}' sent {} to {} recipient(s)",
+      self.name,
+      message_id,
+      recipients.len()
+    );
+    outputs.insert(
+      "accepted_recipients".to_string(),
+      FlowValue::Json(json!(recipients)),
+    );
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_core::node_context::NodeContext;
+
+  fn node() -> EmailNode {
+    EmailNode::new(
+      "notify",
+      SmtpSettings::new("smtp.example.com").with_credentials("bot", "hunter2"),
+      "Reports Bot <bot@example.com>",
+      "Weekly report for {{ client }}",
+    )
+    .with_text("Hi {{ client }}, the report is attached.")
+    .with_html("<p>Hi <b>{{ client }}</b>, the report is attached.</p>")
+    .dry_run()
+  }
+
+  fn inputs(pairs: &[(&str, Value)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+      .collect()
+  }
+
+  fn output(outputs: &HashMap<String, FlowValue>, key: &str) -> Value {
+    match &outputs[key] {
+      FlowValue::Json(value) => value.clone(),
+      other => panic!("{key} is not JSON: {other:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn dry_run_writes_a_multipart_message_to_the_run_dir() {
+    let run_dir = tempfile::tempdir().unwrap();
+    let report = run_dir.path().join("report.csv");
+    std::fs::write(&report, "region,total\nemea,42\n").unwrap();
+
+    let outputs = NodeContext::new("run", "notify", None, None)
+      .with_run_dir(run_dir.path())
+      .scope(node().execute(&inputs(&[
+        ("client", json!("ACME")),
+        ("to", json!("Ada <ada@example.com>, grace@example.com")),
+        ("bcc", json!(["audit@example.com"])),
+        (
+          "attachments",
+          json!([
+            report.display().to_string(),
+            { "filename": "logo.png", "base64": STANDARD.encode([0x89, b'P', b'N', b'G', 0]) }
+          ]),
+        ),
+      ])))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      output(&outputs, "recipients"),
+      json!(["ada@example.com", "grace@example.com", "audit@example.com"])
+    );
+    assert_eq!(output(&outputs, "accepted_recipients"), json!([]));
+    let eml_path = PathBuf::from(output(&outputs, "eml_path").as_str().unwrap());
+    assert_eq!(eml_path.parent(), Some(run_dir.path()));
+    let eml = std::fs::read_to_string(&eml_path).unwrap();
+
+    let message_id = output(&outputs, "message_id");
+    assert!(
+      eml.contains(&format!("Message-ID: {}", message_id.as_str().unwrap())),
+      "{eml}"
+    );
+    assert!(eml.contains("Subject: Weekly report for ACME"), "{eml}");
+    assert!(
+      eml.contains("To: Ada <ada@example.com>, grace@example.com"),
+      "{eml}"
+    );
+    assert!(
+      !eml.contains("audit@example.com"),
+      "Bcc must stay off the headers: {eml}"
+    );
+    // mixed( alternative(plain, html), csv, png )
+    let mixed = eml
+      .find("Content-Type: multipart/mixed")
+      .expect("multipart/mixed");
+    let alternative = eml
+      .find("Content-Type: multipart/alternative")
+      .expect("alternative");
+    let plain = eml.find("Content-Type: text/plain").expect("text/plain");
+    let html = eml.find("Content-Type: text/html").expect("text/html");
+    let csv = eml
+      .find("Content-Disposition: attachment; filename=\"report.csv\"")
+      .expect("csv attachment");
+    let png = eml
+      .find("Content-Disposition: attachment; filename=\"logo.png\"")
+      .expect("png attachment");
+    assert!(mixed < alternative && alternative < plain && plain < html && html < csv && csv < png);
+    assert!(eml.contains("Content-Type: text/csv"), "{eml}");
+    assert!(eml.contains("Content-Type: image/png"), "{eml}");
+  }
+
+  #[tokio::test]
+  async fn html_bodies_escape_substituted_values() {
+    let message = node()
+      .build_message(&inputs(&[
+        ("client", json!("<i>&")),
+        ("to", json!("ada@example.com")),
+      ]))
+      .await
+      .unwrap();
+    let eml = String::from_utf8(message.formatted()).unwrap();
+
+    assert!(
+      eml.contains("<p>Hi <b>&lt;i&gt;&amp;</b>, the report"),
+      "{eml}"
+    );
+    assert!(
+      eml.contains("Hi <i>&, the report"),
+      "the plain-text body stays unescaped: {eml}"
+    );
+  }
+
+  #[tokio::test]
+  async fn recipients_are_validated() {
+    let parsed = parse_recipients(&json!([
+      "Ada <ada@example.com>",
+      "b@example.com, c@example.com"
+    ]))
+    .unwrap();
+    assert_eq!(parsed.len(), 3);
+    assert_eq!(parsed[0].name.as_deref(), Some("Ada"));
+
+    let err = parse_recipients(&json!("ada@example.com, not-an-address")).unwrap_err();
+    assert_eq!(err, "'not-an-address' is not a valid email address");
+    assert!(parse_recipients(&json!(42)).is_err());
+
+    let err = node()
+      .execute(&inputs(&[
+        ("client", json!("ACME")),
+        ("to", json!("nobody")),
+      ]))
+      .await
+      .unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("to: 'nobody' is not a valid email address"),
+      "{err}"
+    );
+    let err = node()
+      .execute(&inputs(&[("client", json!("ACME"))]))
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("at least one"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn attachments_over_the_size_cap_are_rejected() {
+    let node = node().with_max_attachment_bytes(8);
+    let err = node
+      .execute(&inputs(&[
+        ("client", json!("ACME")),
+        ("to", json!("ada@example.com")),
+        (
+          "attachments",
+          json!({ "filename": "notes.txt", "content": "more than eight bytes" }),
+        ),
+      ]))
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("exceed the 8 byte limit"), "{err}");
+  }
+}
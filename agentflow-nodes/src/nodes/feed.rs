@@ -0,0 +1,454 @@
+//! Feed node - fetches an RSS / Atom / JSON Feed and outputs its entries.
+//!
+//! The `url` input (with `{{key}}` placeholders and optional `headers`) is
+//! fetched through the sandboxed
+//! [`HttpTool`](agentflow_tools::builtin::HttpTool) and parsed with
+//! `feed-rs`. Each entry becomes `{id, title, link, published, summary}`:
+//! `published` falls back to the entry's `updated` date (RFC 3339, or
+//! `null`) and `summary` to its content, with markup stripped.
+//!
+//! - `since` (RFC 3339) drops entries dated before it; undated entries are
+//!   kept.
+//! - `state_file` enables de-duplication across runs: ids already recorded
+//!   there are skipped, and the ids of emitted entries are added (the file
+//!   keeps the newest [`MAX_REMEMBERED_IDS`]).
+//! - `max_entries` caps how many entries are emitted, newest first as the
+//!   feed orders them.
+//!
+//! Outputs `entries`, `entry_count`, `skipped` (filtered or already seen)
+//! and `feed` (`{title, link, updated}`).
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_tools::SandboxPolicy;
+use agentflow_tools::builtin::HttpTool;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::common::utils::get_request_from_inputs;
+
+/// How many entry ids a `state_file` remembers.
+pub const MAX_REMEMBERED_IDS: usize = 5_000;
+
+#[derive(Clone)]
+pub struct FeedNode {
+  pub name: String,
+  pub since: Option<DateTime<Utc>>,
+  pub state_file: Option<PathBuf>,
+  pub max_entries: Option<usize>,
+  policy: Arc<SandboxPolicy>,
+  client: Option<reqwest::Client>,
+}
+
+impl std::fmt::Debug for FeedNode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("FeedNode")
+      .field("name", &self.name)
+      .field("since", &self.since)
+      .field("state_file", &self.state_file)
+      .field("max_entries", &self.max_entries)
+      .finish()
+  }
+}
+
+/// On-disk de-duplication state, oldest id first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FeedState {
+  seen: Vec<String>,
+}
+
+impl FeedNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      since: None,
+      state_file: None,
+      max_entries: None,
+      policy: Arc::new(SandboxPolicy::default()),
+      client: None,
+    }
+  }
+
+  pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+    self.since = Some(since);
+    self
+  }
+
+  /// Parses an RFC 3339 `since` timestamp.
+  pub fn with_since_rfc3339(self, since: &str) -> Result<Self, AgentFlowError> {
+    let since =
+      DateTime::parse_from_rfc3339(since).map_err(|err| AgentFlowError::ConfigurationError {
+        message: format!("Feed node: invalid 'since' timestamp '{}': {}", since, err),
+      })?;
+    Ok(self.with_since(since.with_timezone(&Utc)))
+  }
+
+  pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Self {
+    self.state_file = Some(path.into());
+    self
+  }
+
+  pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+    self.max_entries = Some(max_entries);
+    self
+  }
+
+  pub fn with_policy(mut self, policy: Arc<SandboxPolicy>) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Use a pre-built reqwest client (e.g. `.no_proxy()` for loopback tests).
+  pub fn with_client(mut self, client: reqwest::Client) -> Self {
+    self.client = Some(client);
+    self
+  }
+
+  fn tool(&self) -> Result<HttpTool, AgentFlowError> {
+    match &self.client {
+      Some(client) => Ok(HttpTool::with_client(client.clone(), self.policy.clone())),
+      None => {
+        HttpTool::new(self.policy.clone()).map_err(|err| AgentFlowError::AsyncExecutionError {
+          message: format!("FeedNode failed to build HTTP client: {err}"),
+        })
+      }
+    }
+  }
+
+  async fn load_state(&self) -> Result<FeedState, AgentFlowError> {
+    let Some(path) = &self.state_file else {
+      return Ok(FeedState::default());
+    };
+    match tokio::fs::read_to_string(path).await {
+      Ok(text) => serde_json::from_str(&text).map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Feed node '{}': state file {} is corrupt: {}",
+          self.name,
+          path.display(),
+          e
+        ),
+      }),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(FeedState::default()),
+      Err(err) => Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Feed node '{}': failed to read state file {}: {}",
+          self.name,
+          path.display(),
+          err
+        ),
+      }),
+    }
+  }
+
+  async fn save_state(&self, state: &FeedState) -> Result<(), AgentFlowError> {
+    let Some(path) = &self.state_file else {
+      return Ok(());
+    };
+    let write_error = |e: std::io::Error| AgentFlowError::AsyncExecutionError {
+      message: format!(
+        "Feed node '{}': failed to write state file {}: {}",
+        self.name,
+        path.display(),
+        e
+      ),
+    };
+    if let Some(parent) = path
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+    {
+      tokio::fs::create_dir_all(parent)
+        .await
+        .map_err(write_error)?;
+    }
+    let text = serde_json::to_string_pretty(state)
+      .map_err(|e| AgentFlowError::SerializationError(e.to_string()))?;
+    tokio::fs::write(path, text).await.map_err(write_error)
+  }
+}
+
+/// Parses feed bytes into `(feed metadata, entries)` without any filtering.
+pub fn parse_feed(bytes: &[u8]) -> Result<(Value, Vec<Value>), AgentFlowError> {
+  let feed = feed_rs::parser::parse(bytes).map_err(|err| AgentFlowError::AsyncExecutionError {
+    message: format!("Failed to parse feed: {}", err),
+  })?;
+  let first_link = |links: &[feed_rs::model::Link]| {
+    links
+      .iter()
+      .find(|link| link.rel.as_deref().is_none_or(|rel| rel == "alternate"))
+      .or(links.first())
+      .map(|link| link.href.clone())
+  };
+  let metadata = json!({
+    "title": feed.title.as_ref().map(|title| plain_text(&title.content)),
+    "link": first_link(&feed.links),
+    "updated": feed.updated.map(|date| date.to_rfc3339()),
+  });
+  let entries = feed
+    .entries
+    .iter()
+    .map(|entry| {
+      let summary = entry
+        .summary
+        .as_ref()
+        .map(|summary| summary.content.clone())
+        .or_else(|| {
+          entry
+            .content
+            .as_ref()
+            .and_then(|content| content.body.clone())
+        });
+      json!({
+        "id": entry.id,
+        "title": entry.title.as_ref().map(|title| plain_text(&title.content)),
+        "link": first_link(&entry.links),
+        "published": entry.published.or(entry.updated).map(|date| date.to_rfc3339()),
+        "summary": summary.map(|summary| plain_text(&summary)),
+      })
+    })
+    .collect();
+  Ok((metadata, entries))
+}
+
+/// Text of an HTML fragment with whitespace collapsed.
+fn plain_text(fragment: &str) -> String {
+  let text: String = scraper::Html::parse_fragment(fragment)
+    .root_element()
+    .text()
+    .collect();
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[async_trait]
+impl AsyncNode for FeedNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let request = get_request_from_inputs(&format!("Feed node '{}'", self.name), inputs)?;
+    let url = request.url.clone();
+    let response =
+      self
+        .tool()?
+        .send(request)
+        .await
+        .map_err(|err| AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Feed node '{}': failed to fetch {}: {}",
+            self.name, url, err
+          ),
+        })?;
+    if !(200..300).contains(&response.status) {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Feed node '{}': {} returned HTTP {}",
+          self.name, url, response.status
+        ),
+      });
+    }
+    let (metadata, parsed) =
+      parse_feed(response.body.as_bytes()).map_err(|err| AgentFlowError::AsyncExecutionError {
+        message: format!("Feed node '{}': {}: {}", self.name, url, err),
+      })?;
+
+    let mut state = self.load_state().await?;
+    let seen: HashSet<String> = state.seen.iter().cloned().collect();
+    let total = parsed.len();
+    let entries: Vec<Value> = parsed
+      .into_iter()
+      .filter(|entry| {
+        let published = entry["published"]
+          .as_str()
+          .and_then(|date| DateTime::parse_from_rfc3339(date).ok());
+        match (self.since, published) {
+          (Some(since), Some(published)) => published >= since,
+          _ => true,
+        }
+      })
+      .filter(|entry| entry["id"].as_str().is_none_or(|id| !seen.contains(id)))
+      .take(self.max_entries.unwrap_or(usize::MAX))
+      .collect();
+
+    if self.state_file.is_some() {
+      state.seen.extend(
+        entries
+          .iter()
+          .filter_map(|entry| entry["id"].as_str().map(str::to_string)),
+      );
+      let overflow = state.seen.len().saturating_sub(MAX_REMEMBERED_IDS);
+      state.seen.drain(..overflow);
+      self.save_state(&state).await?;
+    }
+
+    println!(
+      "📰 Feed node '{}': {} new of {} entries",
+      self.name,
+      entries.len(),
+      total
+    );
+    let mut outputs = HashMap::new();
+    outputs.insert(
+      "entry_count".to_string(),
+      FlowValue::Json(json!(entries.len())),
+    );
+    outputs.insert(
+      "skipped".to_string(),
+      FlowValue::Json(json!(total - entries.len())),
+    );
+    outputs.insert(
+      "entries".to_string(),
+      FlowValue::Json(Value::Array(entries)),
+    );
+    outputs.insert("feed".to_string(), FlowValue::Json(metadata));
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use wiremock::matchers::{method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Release Notes</title>
+    <link>https://example.com/releases</link>
+    <description>Product updates</description>
+    <item>
+      <title>v2.1 shipped</title>
+      <link>https://example.com/releases/2.1</link>
+      <guid>release-2.1</guid>
+      <pubDate>Tue, 10 Mar 2026 09:00:00 GMT</pubDate>
+      <description><![CDATA[<p>Faster <b>startup</b> and a new CLI.</p>]]></description>
+    </item>
+    <item>
+      <title>v2.0 shipped</title>
+      <link>https://example.com/releases/2.0</link>
+      <guid>release-2.0</guid>
+      <pubDate>Mon, 02 Feb 2026 09:00:00 GMT</pubDate>
+      <description>Major release.</description>
+    </item>
+    <item>
+      <title>v1.9 shipped</title>
+      <link>https://example.com/releases/1.9</link>
+      <guid>release-1.9</guid>
+      <pubDate>Thu, 01 Jan 2026 09:00:00 GMT</pubDate>
+      <description>Bug fixes.</description>
+    </item>
+  </channel>
+</rss>"#;
+
+  const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Engineering Blog</title>
+  <link href="https://blog.example.com/"/>
+  <updated>2026-04-01T12:00:00Z</updated>
+  <id>urn:uuid:blog</id>
+  <entry>
+    <title>Scaling the scheduler</title>
+    <link href="https://blog.example.com/scheduler"/>
+    <id>urn:uuid:post-1</id>
+    <updated>2026-04-01T12:00:00Z</updated>
+    <summary>How we sharded the queue.</summary>
+  </entry>
+</feed>"#;
+
+  async fn serve(body: &str) -> (MockServer, AsyncNodeInputs) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/feed.xml"))
+      .respond_with(
+        ResponseTemplate::new(200).set_body_raw(body.to_string(), "application/rss+xml"),
+      )
+      .mount(&server)
+      .await;
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "url".to_string(),
+      FlowValue::Json(json!(format!("{}/feed.xml", server.uri()))),
+    );
+    (server, inputs)
+  }
+
+  fn loopback(node: FeedNode) -> FeedNode {
+    let policy = Arc::new(SandboxPolicy {
+      allow_loopback_network_access: true,
+      ..SandboxPolicy::default()
+    });
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    node.with_policy(policy).with_client(client)
+  }
+
+  fn entries(outputs: &HashMap<String, FlowValue>) -> Vec<Value> {
+    match outputs.get("entries") {
+      Some(FlowValue::Json(Value::Array(entries))) => entries.clone(),
+      other => panic!("entries should be an array: {other:?}"),
+    }
+  }
+
+  #[test]
+  fn parses_rss_and_atom_entries() {
+    let (feed, rss) = parse_feed(RSS.as_bytes()).unwrap();
+    assert_eq!(feed["title"], json!("Release Notes"));
+    assert_eq!(rss.len(), 3);
+    assert_eq!(
+      rss[0],
+      json!({
+        "id": "release-2.1",
+        "title": "v2.1 shipped",
+        "link": "https://example.com/releases/2.1",
+        "published": "2026-03-10T09:00:00+00:00",
+        "summary": "Faster startup and a new CLI.",
+      })
+    );
+
+    let (feed, atom) = parse_feed(ATOM.as_bytes()).unwrap();
+    assert_eq!(feed["link"], json!("https://blog.example.com/"));
+    assert_eq!(atom[0]["id"], json!("urn:uuid:post-1"));
+    // Atom entries without <published> fall back to <updated>.
+    assert_eq!(atom[0]["published"], json!("2026-04-01T12:00:00+00:00"));
+    assert_eq!(atom[0]["summary"], json!("How we sharded the queue."));
+  }
+
+  #[tokio::test]
+  async fn since_filter_and_state_file_skip_old_and_seen_entries() {
+    let (_server, inputs) = serve(RSS).await;
+    let dir = tempfile::tempdir().unwrap();
+    let state = dir.path().join("state/releases.json");
+    let node = loopback(
+      FeedNode::new("releases")
+        .with_since_rfc3339("2026-02-01T00:00:00Z")
+        .unwrap()
+        .with_state_file(&state),
+    );
+
+    let first = node.execute(&inputs).await.unwrap();
+    let ids: Vec<_> = entries(&first).iter().map(|e| e["id"].clone()).collect();
+    assert_eq!(ids, vec![json!("release-2.1"), json!("release-2.0")]);
+    assert_eq!(first["skipped"], FlowValue::Json(json!(1)));
+
+    let second = node.execute(&inputs).await.unwrap();
+    assert!(entries(&second).is_empty());
+    assert_eq!(second["skipped"], FlowValue::Json(json!(3)));
+
+    let saved: FeedState = serde_json::from_str(&std::fs::read_to_string(state).unwrap()).unwrap();
+    assert_eq!(saved.seen, vec!["release-2.1", "release-2.0"]);
+  }
+
+  #[tokio::test]
+  async fn non_feed_bodies_are_errors() {
+    let (_server, inputs) = serve("<html><body>not a feed</body></html>").await;
+    let err = loopback(FeedNode::new("bad"))
+      .execute(&inputs)
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("Failed to parse feed"), "{err}");
+  }
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::path::{Component, Path};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 use agentflow_core::{
@@ -9,25 +9,79 @@ use agentflow_core::{
 };
 use agentflow_tools::SandboxPolicy;
 use async_trait::async_trait;
-use serde_json::json;
+use serde_json::{Map, Value, json};
 
-/// Workflow node that reads or writes files on the local filesystem.
+/// Operations accepted by [`FileNode`]'s `operation` input.
+pub const FILE_OPERATIONS: &[&str] = &["read", "write", "append", "list", "delete", "exists"];
+
+/// Formats accepted by [`FileNode`]'s `parse` input (only valid with `read`).
+pub const FILE_PARSE_FORMATS: &[&str] = &["json", "yaml", "csv", "lines"];
+
+/// Check an `operation` / `parse` pair before anything touches the disk.
+///
+/// The workflow factory calls this at build time for statically configured
+/// nodes; [`FileNode`] repeats it at run time for mapped inputs.
+pub fn validate_file_options(operation: &str, parse: Option<&str>) -> Result<(), String> {
+  if !FILE_OPERATIONS.contains(&operation) {
+    return Err(format!(
+      "unsupported file operation '{}', expected one of: {}",
+      operation,
+      FILE_OPERATIONS.join(", ")
+    ));
+  }
+  match parse {
+    None => Ok(()),
+    Some(format) if !FILE_PARSE_FORMATS.contains(&format) => Err(format!(
+      "unsupported parse format '{}', expected one of: {}",
+      format,
+      FILE_PARSE_FORMATS.join(", ")
+    )),
+    Some(format) if operation != "read" => Err(format!(
+      "parse '{}' only applies to the 'read' operation, not '{}'",
+      format, operation
+    )),
+    Some(_) => Ok(()),
+  }
+}
+
+/// Workflow node that reads, writes, lists, and deletes files on the local
+/// filesystem.
 ///
 /// `FileNode` defers to [`agentflow_tools::SandboxPolicy`] for path
 /// validation (Q1.3.1). The default policy is permissive — workflows
 /// that ran before the audit keep working — but the parent-dir traversal
 /// guard and symlink/hardlink check always fire regardless of policy. To
 /// pin reads/writes to a specific subtree (production deployments), wire
-/// in a stricter policy via [`FileNode::with_policy`].
+/// in a stricter policy via [`FileNode::with_policy`] or jail the node
+/// under one directory with [`FileNode::with_base_dir`].
+///
+/// # Inputs
+///
+/// - `operation` — one of [`FILE_OPERATIONS`].
+/// - `path` — the file (or, for `list`, the directory). Relative paths
+///   resolve against the base directory when one is set.
+/// - `content` — text for `write` / `append`; non-string JSON is written as
+///   pretty-printed JSON.
+/// - `pattern` — glob for `list`, relative to `path` (default `*`).
+/// - `parse` — one of [`FILE_PARSE_FORMATS`]; `read` then emits structured
+///   JSON as `content` instead of raw text.
+/// - `max_bytes` — read size cap, further bounded by the policy's
+///   `max_file_read_bytes`.
+/// - `base_dir` — allowed base directory when the node has none configured.
+/// - `recursive` — let `delete` remove a non-empty directory.
 #[derive(Debug, Clone)]
 pub struct FileNode {
   policy: Arc<SandboxPolicy>,
+  base_dir: Option<PathBuf>,
 }
 
 impl FileNode {
   /// Build a `FileNode` with the supplied sandbox policy.
   pub fn new(policy: Arc<SandboxPolicy>) -> Self {
-    Self { policy }
+    Self {
+      policy,
+      base_dir: None,
+    }
   }
 
   /// Override the policy on an existing node.
@@ -35,6 +89,15 @@ impl FileNode {
     self.policy = policy;
     self
   }
+
+  /// Confine every path to `base_dir`: relative paths resolve against it
+  /// and anything that lands outside it (after resolving symlinks in the
+  /// existing part of the path) is rejected. Takes precedence over a
+  /// `base_dir` input.
+  pub fn with_base_dir(mut self, base_dir: impl Into<PathBuf>) -> Self {
+    self.base_dir = Some(base_dir.into());
+    self
+  }
 }
 
 impl Default for FileNode {
@@ -42,9 +105,7 @@ impl Default for FileNode {
     // Permissive baseline keeps backwards-compatibility with existing
     // workflows; the traversal + symlink guards still run because they
     // live below the policy check in `validate_path`.
-    Self {
-      policy: Arc::new(SandboxPolicy::permissive()),
-    }
+    Self::new(Arc::new(SandboxPolicy::permissive()))
   }
 }
 
@@ -52,64 +113,40 @@ impl Default for FileNode {
 impl AsyncNode for FileNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
     let operation = get_string_input(inputs, "operation")?;
-    let path_str = get_string_input(inputs, "path")?;
-    let path = Path::new(path_str);
+    let parse = get_optional_string_input(inputs, "parse")?;
+    validate_file_options(operation, parse)
+      .map_err(|message| AgentFlowError::NodeInputError { message })?;
 
-    self.validate_path(path)?;
+    let path_str = get_string_input(inputs, "path")?;
+    let base_dir = match &self.base_dir {
+      Some(base_dir) => Some(base_dir.clone()),
+      None => get_optional_string_input(inputs, "base_dir")?.map(PathBuf::from),
+    };
+    let path = self.resolve_path(Path::new(path_str), base_dir.as_deref())?;
 
     match operation {
       "read" => {
-        // Re-validate before opening: between the initial check and now
-        // a symlink could have appeared. `tokio::fs::symlink_metadata`
-        // returns the entry's own metadata (not the link target) so we
-        // can refuse to follow.
-        let meta = tokio::fs::symlink_metadata(path).await.map_err(|e| {
-          AgentFlowError::AsyncExecutionError {
-            message: format!("Failed to stat '{}': {}", path.display(), e),
-          }
-        })?;
-        if meta.file_type().is_symlink() {
-          return Err(AgentFlowError::AsyncExecutionError {
-            message: format!(
-              "Refusing to read '{}': path is a symlink and could escape the policy",
-              path.display()
-            ),
-          });
-        }
-        if meta.file_type().is_file()
-          && meta.nlink_or_zero() > 1
-          && !self.policy.allow_hardlinked_files
-        {
-          return Err(AgentFlowError::AsyncExecutionError {
-            message: format!(
-              "Refusing to read '{}': file has multiple hardlinks ({}); set policy.allow_hardlinked_files = true if intentional",
-              path.display(),
-              meta.nlink_or_zero()
-            ),
-          });
-        }
-        if meta.len() > self.policy.max_file_read_bytes {
-          return Err(AgentFlowError::AsyncExecutionError {
-            message: format!(
-              "Refusing to read '{}': size {} bytes exceeds policy.max_file_read_bytes {}",
-              path.display(),
-              meta.len(),
-              self.policy.max_file_read_bytes
-            ),
-          });
-        }
-
-        let content = tokio::fs::read_to_string(path).await.map_err(|e| {
-          AgentFlowError::AsyncExecutionError {
-            message: format!("Failed to read file '{}': {}", path.display(), e),
-          }
-        })?;
+        let content = self
+          .read_text(&path, get_optional_u64_input(inputs, "max_bytes")?)
+          .await?;
+        let content = match parse {
+          Some(format) => parse_content(&content, format, &path)?,
+          None => json!(content),
+        };
         let mut outputs = HashMap::new();
-        outputs.insert("content".to_string(), FlowValue::Json(json!(content)));
+        outputs.insert("content".to_string(), FlowValue::Json(content));
         Ok(outputs)
       }
-      "write" => {
-        let content = get_string_input(inputs, "content")?;
+      "write" | "append" => {
+        let content = match inputs.get("content") {
+          Some(FlowValue::Json(Value::String(s))) => s.clone(),
+          Some(FlowValue::Json(value)) => serde_json::to_string_pretty(value)?,
+          _ => {
+            return Err(AgentFlowError::NodeInputError {
+              message: "Required input 'content' is missing or has wrong type".to_string(),
+            });
+          }
+        };
         if let Some(parent) = path.parent()
           && !parent.as_os_str().is_empty()
         {
@@ -124,18 +161,89 @@ impl AsyncNode for FileNode {
             }
           })?;
         }
-        tokio::fs::write(path, content)
-          .await
-          .map_err(|e| AgentFlowError::AsyncExecutionError {
-            message: format!("Failed to write file '{}': {}", path.display(), e),
+        if operation == "append" {
+          use tokio::io::AsyncWriteExt;
+          let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(|e| AgentFlowError::AsyncExecutionError {
+              message: format!("Failed to open '{}' for append: {}", path.display(), e),
+            })?;
+          // tokio's File hands writes to a blocking task; flush so the
+          // data is on disk before the node reports success.
+          let written = match file.write_all(content.as_bytes()).await {
+            Ok(()) => file.flush().await,
+            Err(e) => Err(e),
+          };
+          written.map_err(|e| AgentFlowError::AsyncExecutionError {
+            message: format!("Failed to append to '{}': {}", path.display(), e),
+          })?;
+        } else {
+          tokio::fs::write(&path, content).await.map_err(|e| {
+            AgentFlowError::AsyncExecutionError {
+              message: format!("Failed to write file '{}': {}", path.display(), e),
+            }
           })?;
+        }
         let mut outputs = HashMap::new();
         outputs.insert("path".to_string(), FlowValue::Json(json!(path_str)));
         Ok(outputs)
       }
-      _ => Err(AgentFlowError::NodeInputError {
-        message: format!("Unsupported file operation: {}", operation),
-      }),
+      "list" => {
+        let pattern = get_optional_string_input(inputs, "pattern")?.unwrap_or("*");
+        let entries = self.list(&path, pattern)?;
+        let mut outputs = HashMap::new();
+        outputs.insert("count".to_string(), FlowValue::Json(json!(entries.len())));
+        outputs.insert(
+          "entries".to_string(),
+          FlowValue::Json(Value::Array(entries)),
+        );
+        Ok(outputs)
+      }
+      "delete" => {
+        let recursive = matches!(
+          inputs.get("recursive"),
+          Some(FlowValue::Json(Value::Bool(true)))
+        );
+        let deleted = match tokio::fs::symlink_metadata(&path).await {
+          Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+          Err(e) => {
+            return Err(AgentFlowError::AsyncExecutionError {
+              message: format!("Failed to stat '{}': {}", path.display(), e),
+            });
+          }
+          Ok(meta) => {
+            let removed = if meta.is_dir() && recursive {
+              tokio::fs::remove_dir_all(&path).await
+            } else if meta.is_dir() {
+              tokio::fs::remove_dir(&path).await
+            } else {
+              tokio::fs::remove_file(&path).await
+            };
+            removed.map_err(|e| AgentFlowError::AsyncExecutionError {
+              message: format!("Failed to delete '{}': {}", path.display(), e),
+            })?;
+            true
+          }
+        };
+        let mut outputs = HashMap::new();
+        outputs.insert("deleted".to_string(), FlowValue::Json(json!(deleted)));
+        outputs.insert("path".to_string(), FlowValue::Json(json!(path_str)));
+        Ok(outputs)
+      }
+      "exists" => {
+        let meta = tokio::fs::symlink_metadata(&path).await.ok();
+        let mut outputs = HashMap::new();
+        outputs.insert("exists".to_string(), FlowValue::Json(json!(meta.is_some())));
+        outputs.insert(
+          "is_dir".to_string(),
+          FlowValue::Json(json!(meta.is_some_and(|meta| meta.is_dir()))),
+        );
+        Ok(outputs)
+      }
+      _ => unreachable!("validate_file_options rejects unknown operations"),
     }
   }
 }
@@ -161,6 +269,203 @@ impl FileNode {
     }
     Ok(())
   }
+
+  /// Join `path` onto the base directory (if any), run [`validate_path`],
+  /// and make sure the result — with symlinks in its existing prefix
+  /// resolved — is still inside the base directory.
+  ///
+  /// [`validate_path`]: FileNode::validate_path
+  fn resolve_path(&self, path: &Path, base_dir: Option<&Path>) -> Result<PathBuf, AgentFlowError> {
+    let resolved = match base_dir {
+      Some(base_dir) if path.is_relative() => base_dir.join(path),
+      _ => path.to_path_buf(),
+    };
+    self.validate_path(&resolved)?;
+
+    if let Some(base_dir) = base_dir {
+      let base = base_dir
+        .canonicalize()
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Base directory '{}' is not usable: {}",
+            base_dir.display(),
+            e
+          ),
+        })?;
+      if !canonicalize_existing_prefix(&resolved).starts_with(&base) {
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Refusing path '{}': it escapes the base directory '{}'",
+            path.display(),
+            base_dir.display()
+          ),
+        });
+      }
+    }
+    Ok(resolved)
+  }
+
+  async fn read_text(&self, path: &Path, max_bytes: Option<u64>) -> Result<String, AgentFlowError> {
+    // Re-validate before opening: between the initial check and now
+    // a symlink could have appeared. `tokio::fs::symlink_metadata`
+    // returns the entry's own metadata (not the link target) so we
+    // can refuse to follow.
+    let meta =
+      tokio::fs::symlink_metadata(path)
+        .await
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!("Failed to stat '{}': {}", path.display(), e),
+        })?;
+    if meta.file_type().is_symlink() {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Refusing to read '{}': path is a symlink and could escape the policy",
+          path.display()
+        ),
+      });
+    }
+    if meta.file_type().is_file() && meta.nlink_or_zero() > 1 && !self.policy.allow_hardlinked_files
+    {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Refusing to read '{}': file has multiple hardlinks ({}); set policy.allow_hardlinked_files = true if intentional",
+          path.display(),
+          meta.nlink_or_zero()
+        ),
+      });
+    }
+    let limit = max_bytes
+      .map(|max| max.min(self.policy.max_file_read_bytes))
+      .unwrap_or(self.policy.max_file_read_bytes);
+    if meta.len() > limit {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Refusing to read '{}': size {} bytes exceeds the {} byte limit",
+          path.display(),
+          meta.len(),
+          limit
+        ),
+      });
+    }
+
+    tokio::fs::read_to_string(path)
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to read file '{}': {}", path.display(), e),
+      })
+  }
+
+  /// Glob `pattern` under `dir`, returning one metadata object per match
+  /// with `path` relative to `dir`, sorted by path. Matches that resolve
+  /// outside `dir` (symlinks, or anything under a symlinked directory,
+  /// pointing elsewhere) are left out, as `read` and `write` refuse them.
+  fn list(&self, dir: &Path, pattern: &str) -> Result<Vec<Value>, AgentFlowError> {
+    let pattern_path = Path::new(pattern);
+    if pattern_path.is_absolute()
+      || pattern_path
+        .components()
+        .any(|c| matches!(c, Component::ParentDir))
+    {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "List pattern '{}' must be relative and must not contain '..'",
+          pattern
+        ),
+      });
+    }
+    let canonical_dir = dir
+      .canonicalize()
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to resolve '{}': {}", dir.display(), e),
+      })?;
+    let full_pattern = dir.join(pattern_path);
+    let matches =
+      glob::glob(&full_pattern.to_string_lossy()).map_err(|e| AgentFlowError::NodeInputError {
+        message: format!("Invalid glob pattern '{}': {}", pattern, e),
+      })?;
+
+    let mut entries = Vec::new();
+    for entry in matches {
+      let entry = entry.map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to list '{}': {}", dir.display(), e),
+      })?;
+      let Ok(relative) = entry.strip_prefix(dir) else {
+        continue;
+      };
+      match entry.canonicalize() {
+        Ok(resolved) if resolved.starts_with(&canonical_dir) => {}
+        _ => continue,
+      }
+      let meta =
+        std::fs::symlink_metadata(&entry).map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!("Failed to stat '{}': {}", entry.display(), e),
+        })?;
+      let modified = meta
+        .modified()
+        .ok()
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
+      entries.push(json!({
+        "path": relative.to_string_lossy(),
+        "size": meta.len(),
+        "is_dir": meta.is_dir(),
+        "is_symlink": meta.file_type().is_symlink(),
+        "modified": modified,
+      }));
+    }
+    entries.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+    Ok(entries)
+  }
+}
+
+/// Canonicalize the longest existing prefix of `path` and re-append the
+/// rest, so not-yet-created files can still be checked against a base dir.
+fn canonicalize_existing_prefix(path: &Path) -> PathBuf {
+  let mut existing = path;
+  let mut tail = Vec::new();
+  loop {
+    if let Ok(canonical) = existing.canonicalize() {
+      return tail
+        .iter()
+        .rev()
+        .fold(canonical, |acc, part| acc.join(part));
+    }
+    match (existing.parent(), existing.file_name()) {
+      (Some(parent), Some(name)) => {
+        tail.push(name.to_os_string());
+        existing = parent;
+      }
+      _ => return path.to_path_buf(),
+    }
+  }
+}
+
+fn parse_content(content: &str, format: &str, path: &Path) -> Result<Value, AgentFlowError> {
+  let parse_error = |e: &dyn std::fmt::Display| AgentFlowError::AsyncExecutionError {
+    message: format!("Failed to parse '{}' as {}: {}", path.display(), format, e),
+  };
+  match format {
+    "json" => serde_json::from_str(content).map_err(|e| parse_error(&e)),
+    "yaml" => serde_yaml::from_str(content).map_err(|e| parse_error(&e)),
+    "lines" => Ok(json!(content.lines().collect::<Vec<_>>())),
+    "csv" => {
+      let mut reader = csv::Reader::from_reader(content.as_bytes());
+      let headers = reader.headers().map_err(|e| parse_error(&e))?.clone();
+      let mut rows = Vec::new();
+      for record in reader.records() {
+        let record = record.map_err(|e| parse_error(&e))?;
+        let row: Map<String, Value> = headers
+          .iter()
+          .zip(record.iter())
+          .map(|(header, field)| (header.to_string(), json!(field)))
+          .collect();
+        rows.push(Value::Object(row));
+      }
+      Ok(Value::Array(rows))
+    }
+    other => Err(AgentFlowError::NodeInputError {
+      message: format!("unsupported parse format '{}'", other),
+    }),
+  }
 }
 
 trait MetadataExt {
@@ -199,6 +504,35 @@ fn get_string_input<'a>(inputs: &'a AsyncNodeInputs, key: &str) -> Result<&'a st
     })
 }
 
+fn get_optional_string_input<'a>(
+  inputs: &'a AsyncNodeInputs,
+  key: &str,
+) -> Result<Option<&'a str>, AgentFlowError> {
+  match inputs.get(key) {
+    None | Some(FlowValue::Json(Value::Null)) => Ok(None),
+    Some(FlowValue::Json(Value::String(s))) => Ok(Some(s.as_str())),
+    _ => Err(AgentFlowError::NodeInputError {
+      message: format!("Input '{}' has wrong type, expected a string", key),
+    }),
+  }
+}
+
+fn get_optional_u64_input(
+  inputs: &AsyncNodeInputs,
+  key: &str,
+) -> Result<Option<u64>, AgentFlowError> {
+  match inputs.get(key) {
+    None | Some(FlowValue::Json(Value::Null)) => Ok(None),
+    Some(FlowValue::Json(value)) if value.as_u64().is_some() => Ok(value.as_u64()),
+    _ => Err(AgentFlowError::NodeInputError {
+      message: format!(
+        "Input '{}' has wrong type, expected a non-negative integer",
+        key
+      ),
+    }),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -312,4 +646,240 @@ mod tests {
     let err = node.execute(&inputs).await.unwrap_err();
     assert!(err.to_string().contains("symlink"));
   }
+
+  fn inputs(pairs: Value) -> AsyncNodeInputs {
+    pairs
+      .as_object()
+      .unwrap()
+      .iter()
+      .map(|(k, v)| (k.clone(), FlowValue::Json(v.clone())))
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn list_globs_relative_paths_with_metadata() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("notes/deep")).unwrap();
+    std::fs::write(dir.path().join("notes/a.md"), "a").unwrap();
+    std::fs::write(dir.path().join("notes/deep/b.md"), "bb").unwrap();
+    std::fs::write(dir.path().join("notes/skip.txt"), "x").unwrap();
+
+    let outputs = FileNode::default()
+      .with_base_dir(dir.path())
+      .execute(&inputs(json!({
+        "operation": "list",
+        "path": "notes",
+        "pattern": "**/*.md",
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["count"], FlowValue::Json(json!(2)));
+    let FlowValue::Json(Value::Array(entries)) = &outputs["entries"] else {
+      panic!("entries should be an array");
+    };
+    let paths: Vec<&str> = entries
+      .iter()
+      .map(|entry| entry["path"].as_str().unwrap())
+      .collect();
+    assert_eq!(paths, vec!["a.md", "deep/b.md"]);
+    assert_eq!(entries[1]["size"], json!(2));
+    assert_eq!(entries[1]["is_dir"], json!(false));
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn list_skips_symlinks_that_leave_the_directory() {
+    let base = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let notes = base.path().join("notes");
+    std::fs::create_dir(&notes).unwrap();
+    std::fs::write(outside.path().join("secret.md"), "secret").unwrap();
+    std::fs::write(notes.join("own.md"), "own").unwrap();
+    std::os::unix::fs::symlink(outside.path(), notes.join("door")).unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret.md"), notes.join("link.md")).unwrap();
+    std::os::unix::fs::symlink(notes.join("own.md"), notes.join("alias.md")).unwrap();
+
+    let outputs = FileNode::default()
+      .with_base_dir(base.path())
+      .execute(&inputs(json!({
+        "operation": "list",
+        "path": "notes",
+        "pattern": "**/*.md",
+      })))
+      .await
+      .unwrap();
+
+    let FlowValue::Json(Value::Array(entries)) = &outputs["entries"] else {
+      panic!("entries should be an array");
+    };
+    let paths: Vec<&str> = entries
+      .iter()
+      .map(|entry| entry["path"].as_str().unwrap())
+      .collect();
+    assert_eq!(paths, vec!["alias.md", "own.md"]);
+    assert_eq!(entries[0]["is_symlink"], json!(true));
+  }
+
+  #[tokio::test]
+  async fn read_parses_csv_into_array_of_objects() {
+    let dir = tempdir().unwrap();
+    std::fs::write(
+      dir.path().join("people.csv"),
+      "name,city\nAda,London\n\"Hopper, Grace\",Arlington\n",
+    )
+    .unwrap();
+
+    let outputs = FileNode::default()
+      .with_base_dir(dir.path())
+      .execute(&inputs(json!({
+        "operation": "read",
+        "path": "people.csv",
+        "parse": "csv",
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      outputs["content"],
+      FlowValue::Json(json!([
+        { "name": "Ada", "city": "London" },
+        { "name": "Hopper, Grace", "city": "Arlington" },
+      ]))
+    );
+  }
+
+  #[tokio::test]
+  async fn read_parses_yaml_and_lines() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("config.yaml"), "retries: 3\nname: demo\n").unwrap();
+    let node = FileNode::default().with_base_dir(dir.path());
+
+    let yaml = node
+      .execute(&inputs(json!({
+        "operation": "read",
+        "path": "config.yaml",
+        "parse": "yaml",
+      })))
+      .await
+      .unwrap();
+    let lines = node
+      .execute(&inputs(json!({
+        "operation": "read",
+        "path": "config.yaml",
+        "parse": "lines",
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      yaml["content"],
+      FlowValue::Json(json!({ "retries": 3, "name": "demo" }))
+    );
+    assert_eq!(
+      lines["content"],
+      FlowValue::Json(json!(["retries: 3", "name: demo"]))
+    );
+  }
+
+  #[tokio::test]
+  async fn base_dir_rejects_absolute_escape_and_symlinked_dirs() {
+    let base = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    let node = FileNode::default().with_base_dir(base.path());
+
+    let err = node
+      .execute(&inputs(json!({
+        "operation": "read",
+        "path": outside.path().join("secret.txt").to_str().unwrap(),
+      })))
+      .await
+      .unwrap_err();
+    assert!(
+      err.to_string().contains("escapes the base directory"),
+      "{err}"
+    );
+
+    #[cfg(unix)]
+    {
+      std::os::unix::fs::symlink(outside.path(), base.path().join("door")).unwrap();
+      let err = node
+        .execute(&inputs(json!({
+          "operation": "write",
+          "path": "door/planted.txt",
+          "content": "x",
+        })))
+        .await
+        .unwrap_err();
+      assert!(
+        err.to_string().contains("escapes the base directory"),
+        "{err}"
+      );
+      assert!(!outside.path().join("planted.txt").exists());
+    }
+  }
+
+  #[tokio::test]
+  async fn append_exists_and_delete() {
+    let dir = tempdir().unwrap();
+    let node = FileNode::default().with_base_dir(dir.path());
+    for chunk in ["one\n", "two\n"] {
+      node
+        .execute(&inputs(json!({
+          "operation": "append",
+          "path": "log.txt",
+          "content": chunk,
+        })))
+        .await
+        .unwrap();
+    }
+    assert_eq!(
+      std::fs::read_to_string(dir.path().join("log.txt")).unwrap(),
+      "one\ntwo\n"
+    );
+
+    let exists = node
+      .execute(&inputs(json!({ "operation": "exists", "path": "log.txt" })))
+      .await
+      .unwrap();
+    assert_eq!(exists["exists"], FlowValue::Json(json!(true)));
+
+    let deleted = node
+      .execute(&inputs(json!({ "operation": "delete", "path": "log.txt" })))
+      .await
+      .unwrap();
+    assert_eq!(deleted["deleted"], FlowValue::Json(json!(true)));
+    assert!(!dir.path().join("log.txt").exists());
+  }
+
+  #[tokio::test]
+  async fn max_bytes_caps_reads() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("big.txt"), "0123456789").unwrap();
+
+    let err = FileNode::default()
+      .with_base_dir(dir.path())
+      .execute(&inputs(json!({
+        "operation": "read",
+        "path": "big.txt",
+        "max_bytes": 4,
+      })))
+      .await
+      .unwrap_err();
+
+    assert!(
+      err.to_string().contains("exceeds the 4 byte limit"),
+      "{err}"
+    );
+  }
+
+  #[test]
+  fn parse_is_only_valid_for_read() {
+    assert!(validate_file_options("read", Some("csv")).is_ok());
+    assert!(validate_file_options("list", None).is_ok());
+    assert!(validate_file_options("write", Some("json")).is_err());
+    assert!(validate_file_options("read", Some("xml")).is_err());
+    assert!(validate_file_options("move", None).is_err());
+  }
 }
@@ -0,0 +1,694 @@
+//! Git node - clones repositories and reads their files and history.
+//!
+//! Operations (all run `git2` on a blocking thread):
+//!
+//! - `clone` clones the `url` input into the `target_dir` input, or a fresh
+//!   directory under the system temp dir (removed when the node is
+//!   dropped, at the end of the flow), optionally `depth`-limited. A
+//!   `ref` input (branch, tag or commit) is checked out detached; refs a
+//!   shallow clone did not bring along are fetched explicitly. Outputs
+//!   `path`, `head` and `ref`.
+//! - `read_files` reads the files under the `path` input that match
+//!   `patterns` (globs relative to the repository root; `.git` is never
+//!   read). Symlinks, and files that resolve outside the root, are listed
+//!   in `skipped` and never followed. Files over `max_file_bytes`, or that look binary (a NUL byte
+//!   or invalid UTF-8), are listed in `skipped` rather than read; reading
+//!   stops at `max_total_bytes`, setting `truncated`. Outputs `files`
+//!   (`{path, size, content}`), `skipped` (`{path, reason}`) and `count`.
+//! - `log` lists up to `max_commits` commits reachable from the `ref`
+//!   input (default `HEAD`) of the repository at `path`, newest first.
+//!   Outputs `commits` (`{id, short_id, author, email, time, summary,
+//!   message}`) and `count`.
+//!
+//! A relative `target_dir` or `path` is taken relative to `working_dir`
+//! (the node's `cwd:`) when one is set, else to the process's directory.
+//!
+//! Private remotes authenticate through the SSH agent for SSH URLs, or
+//! with `token` (a [`SecretValue`], sent as the HTTPS password for
+//! `username`) for HTTPS URLs.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use agentflow_core::{
+  SecretValue,
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use git2::{
+  Cred, CredentialType, FetchOptions, Oid, RemoteCallbacks, Repository, Sort,
+  build::{CheckoutBuilder, RepoBuilder},
+};
+use serde_json::{Value, json};
+
+use crate::common::utils::flow_value_to_string;
+
+/// How many bytes git inspects when deciding whether a file is binary.
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitOperation {
+  Clone,
+  ReadFiles,
+  Log,
+}
+
+impl GitOperation {
+  pub fn parse(value: &str) -> Result<Self, String> {
+    match value {
+      "clone" => Ok(Self::Clone),
+      "read_files" => Ok(Self::ReadFiles),
+      "log" => Ok(Self::Log),
+      other => Err(format!(
+        "unknown operation '{other}' (expected clone, read_files or log)"
+      )),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct GitNode {
+  pub name: String,
+  pub operation: GitOperation,
+  /// `clone` history depth; `None` clones everything.
+  pub depth: Option<u32>,
+  /// `read_files` globs, relative to the repository root.
+  pub patterns: Vec<String>,
+  pub max_file_bytes: u64,
+  pub max_total_bytes: u64,
+  pub max_commits: usize,
+  /// HTTPS user name sent with `token`.
+  pub username: String,
+  pub token: Option<SecretValue>,
+  /// Base of relative `target_dir` / `path` inputs.
+  pub working_dir: Option<PathBuf>,
+  /// Clones made without a `target_dir`; deleted when the last clone of
+  /// the node is dropped.
+  temp_clones: Arc<Mutex<Vec<tempfile::TempDir>>>,
+}
+
+impl GitNode {
+  pub fn new(name: &str, operation: GitOperation) -> Self {
+    Self {
+      name: name.to_string(),
+      operation,
+      depth: None,
+      patterns: vec!["**/*".to_string()],
+      max_file_bytes: 256 * 1024,
+      max_total_bytes: 8 * 1024 * 1024,
+      max_commits: 20,
+      username: "x-access-token".to_string(),
+      token: None,
+      working_dir: None,
+      temp_clones: Arc::default(),
+    }
+  }
+
+  pub fn with_depth(mut self, depth: u32) -> Self {
+    self.depth = Some(depth);
+    self
+  }
+
+  pub fn with_patterns(mut self, patterns: Vec<String>) -> Self {
+    self.patterns = patterns;
+    self
+  }
+
+  pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+    self.max_file_bytes = max_file_bytes;
+    self
+  }
+
+  pub fn with_max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+    self.max_total_bytes = max_total_bytes;
+    self
+  }
+
+  pub fn with_max_commits(mut self, max_commits: usize) -> Self {
+    self.max_commits = max_commits;
+    self
+  }
+
+  /// HTTPS credentials; `token` is typically a `$secret` reference.
+  pub fn with_token(mut self, username: &str, token: impl Into<SecretValue>) -> Self {
+    self.username = username.to_string();
+    self.token = Some(token.into());
+    self
+  }
+
+  pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.working_dir = Some(dir.into());
+    self
+  }
+
+  /// `path` under `working_dir` unless it is absolute.
+  fn resolve_path(&self, path: impl Into<PathBuf>) -> PathBuf {
+    let path = path.into();
+    match &self.working_dir {
+      Some(dir) if path.is_relative() => dir.join(path),
+      _ => path,
+    }
+  }
+
+  fn error(&self, message: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::AsyncExecutionError {
+      message: format!("Git node '{}': {}", self.name, message),
+    }
+  }
+
+  fn required_input(&self, inputs: &AsyncNodeInputs, key: &str) -> Result<String, AgentFlowError> {
+    inputs
+      .get(key)
+      .map(flow_value_to_string)
+      .filter(|value| !value.is_empty())
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!("Git node '{}' requires a '{}' input", self.name, key),
+      })
+  }
+
+  /// Fetch options with credentials and the configured depth.
+  fn fetch_options(&self) -> FetchOptions<'_> {
+    let mut attempts = 0;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |_url, username_from_url, allowed| {
+      // libgit2 calls back again after a rejected credential; give up
+      // rather than loop forever.
+      attempts += 1;
+      if attempts > 3 {
+        return Err(git2::Error::from_str("authentication failed"));
+      }
+      if allowed.contains(CredentialType::SSH_KEY) {
+        return Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+      }
+      if allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+        && let Some(token) = &self.token
+      {
+        return Cred::userpass_plaintext(&self.username, token.expose_secret());
+      }
+      Cred::default()
+    });
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+    if let Some(depth) = self.depth {
+      options.depth(depth as i32);
+    }
+    options
+  }
+
+  /// Clones `url` into `target` and checks out `reference`; returns HEAD.
+  fn clone_repo(
+    &self,
+    url: &str,
+    target: &Path,
+    reference: Option<&str>,
+  ) -> Result<Oid, git2::Error> {
+    let repo = RepoBuilder::new()
+      .fetch_options(self.fetch_options())
+      .clone(url, target)?;
+    if let Some(reference) = reference {
+      let object = match resolve_ref(&repo, reference) {
+        Some(object) => object,
+        None => {
+          // Not in a shallow / single-branch clone: ask for it directly.
+          repo
+            .find_remote("origin")?
+            .fetch(&[reference], Some(&mut self.fetch_options()), None)?;
+          repo.revparse_single("FETCH_HEAD")?
+        }
+      };
+      let commit = object.peel_to_commit()?;
+      repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))?;
+      repo.set_head_detached(commit.id())?;
+    }
+    Ok(repo.head()?.peel_to_commit()?.id())
+  }
+
+  fn read_files(&self, root: &Path) -> AsyncNodeResult {
+    let canonical_root = root
+      .canonicalize()
+      .map_err(|err| self.error(format!("cannot open {}: {err}", root.display())))?;
+    let display_path = |relative: &Path| {
+      relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+    };
+    let (mut files, mut skipped) = (Vec::new(), Vec::new());
+    let mut paths = BTreeMap::new();
+    for pattern in &self.patterns {
+      let pattern = root.join(pattern.trim_start_matches('/'));
+      let matches = glob::glob(&pattern.to_string_lossy())
+        .map_err(|err| self.error(format!("invalid pattern '{}': {err}", pattern.display())))?;
+      for path in matches.flatten() {
+        let Ok(relative) = path.strip_prefix(root) else {
+          continue;
+        };
+        let in_git_dir = relative
+          .components()
+          .any(|component| component == Component::Normal(".git".as_ref()));
+        if in_git_dir || paths.contains_key(relative) {
+          continue;
+        }
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+          continue;
+        };
+        let reason = if metadata.file_type().is_symlink() {
+          "symlink"
+        } else if !metadata.is_file() {
+          continue;
+        } else if !path
+          .canonicalize()
+          .is_ok_and(|path| path.starts_with(&canonical_root))
+        {
+          // Reached through a symlinked directory.
+          "outside_root"
+        } else {
+          paths.insert(relative.to_path_buf(), metadata.len());
+          continue;
+        };
+        skipped.push(json!({"path": display_path(relative), "reason": reason}));
+      }
+    }
+
+    let (mut total, mut truncated) = (0u64, false);
+    for (relative, size) in paths {
+      let display = display_path(&relative);
+      let path = root.join(&relative);
+      if size > self.max_file_bytes {
+        skipped.push(json!({"path": display, "reason": "too_large", "size": size}));
+        continue;
+      }
+      if total + size > self.max_total_bytes {
+        truncated = true;
+        break;
+      }
+      let bytes =
+        std::fs::read(&path).map_err(|err| self.error(format!("cannot read {display}: {err}")))?;
+      let looks_binary = bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0);
+      let content = match String::from_utf8(bytes) {
+        Ok(content) if !looks_binary => content,
+        _ => {
+          skipped.push(json!({"path": display, "reason": "binary", "size": size}));
+          continue;
+        }
+      };
+      total += size;
+      files.push(json!({"path": display, "size": size, "content": content}));
+    }
+
+    Ok(
+      [
+        ("count", json!(files.len())),
+        ("files", Value::Array(files)),
+        ("skipped", Value::Array(skipped)),
+        ("truncated", json!(truncated)),
+        ("total_bytes", json!(total)),
+      ]
+      .into_iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value)))
+      .collect(),
+    )
+  }
+
+  fn log(&self, root: &Path, reference: &str) -> Result<Vec<Value>, git2::Error> {
+    let repo = Repository::open(root)?;
+    let start = repo.revparse_single(reference)?.peel_to_commit()?.id();
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    walk.push(start)?;
+    walk
+      .take(self.max_commits)
+      .map(|oid| commit_json(&repo, oid?))
+      .collect()
+  }
+}
+
+/// `reference` as a remote branch, tag or any revision `revparse` accepts.
+fn resolve_ref<'r>(repo: &'r Repository, reference: &str) -> Option<git2::Object<'r>> {
+  [
+    format!("refs/remotes/origin/{reference}"),
+    format!("refs/tags/{reference}"),
+    reference.to_string(),
+  ]
+  .iter()
+  .find_map(|spec| repo.revparse_single(spec).ok())
+}
+
+fn commit_json(repo: &Repository, oid: Oid) -> Result<Value, git2::Error> {
+  let commit = repo.find_commit(oid)?;
+  let author = commit.author();
+  let time = author.when();
+  let time = FixedOffset::east_opt(time.offset_minutes() * 60)
+    .and_then(|offset| {
+      DateTime::from_timestamp(time.seconds(), 0).map(|time| time.with_timezone(&offset))
+    })
+    .map(|time| time.to_rfc3339());
+  let short_id = commit.as_object().short_id()?;
+  Ok(json!({
+    "id": oid.to_string(),
+    "short_id": short_id.as_str(),
+    "author": author.name(),
+    "email": author.email(),
+    "time": time,
+    "summary": commit.summary(),
+    "message": commit.message(),
+  }))
+}
+
+#[async_trait]
+impl AsyncNode for GitNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let node = self.clone();
+    let reference = inputs
+      .get("ref")
+      .map(flow_value_to_string)
+      .filter(|reference| !reference.is_empty());
+    let result = match self.operation {
+      GitOperation::Clone => {
+        let url = self.required_input(inputs, "url")?;
+        let target = match inputs.get("target_dir").map(flow_value_to_string) {
+          Some(target) if !target.is_empty() => self.resolve_path(target),
+          _ => {
+            let dir = tempfile::Builder::new()
+              .prefix("agentflow-git-")
+              .tempdir()
+              .map_err(|err| self.error(format!("cannot create a temp dir: {err}")))?;
+            let target = dir.path().to_path_buf();
+            self
+              .temp_clones
+              .lock()
+              .unwrap_or_else(|e| e.into_inner())
+              .push(dir);
+            target
+          }
+        };
+        println!(
+          "📥 Git node '{}' cloning {} into {}",
+          self.name,
+          url,
+          target.display()
+        );
+        tokio::task::spawn_blocking(move || {
+          let head = node
+            .clone_repo(&url, &target, reference.as_deref())
+            .map_err(|err| node.error(format!("clone of {url} failed: {}", err.message())))?;
+          Ok(
+            [
+              ("path", json!(target.display().to_string())),
+              ("head", json!(head.to_string())),
+              ("ref", json!(reference.as_deref().unwrap_or("HEAD"))),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), FlowValue::Json(value)))
+            .collect(),
+          )
+        })
+        .await
+      }
+      GitOperation::ReadFiles => {
+        let root = self.resolve_path(self.required_input(inputs, "path")?);
+        tokio::task::spawn_blocking(move || node.read_files(&root)).await
+      }
+      GitOperation::Log => {
+        let root = self.resolve_path(self.required_input(inputs, "path")?);
+        tokio::task::spawn_blocking(move || {
+          let reference = reference.as_deref().unwrap_or("HEAD");
+          let commits = node
+            .log(&root, reference)
+            .map_err(|err| node.error(format!("log of {reference} failed: {}", err.message())))?;
+          Ok(
+            [
+              ("count".to_string(), FlowValue::Json(json!(commits.len()))),
+              (
+                "commits".to_string(),
+                FlowValue::Json(Value::Array(commits)),
+              ),
+            ]
+            .into_iter()
+            .collect(),
+          )
+        })
+        .await
+      }
+    };
+    result.map_err(|err| self.error(format!("worker thread failed: {err}")))?
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use git2::Signature;
+
+  /// A repository with two commits on the default branch, a `feature`
+  /// branch one commit ahead and a `v1` tag on the first commit.
+  fn fixture() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(dir.path()).unwrap();
+    let signature = Signature::now("Ada Lovelace", "ada@example.com").unwrap();
+    let commit = |files: &[(&str, &[u8])], message: &str, update_ref: Option<&str>| {
+      for (path, content) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+      }
+      let mut index = repo.index().unwrap();
+      index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+      index.write().unwrap();
+      let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+      let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+      let parents: Vec<_> = parent.iter().collect();
+      repo
+        .commit(update_ref, &signature, &signature, message, &tree, &parents)
+        .unwrap()
+    };
+
+    let first = commit(
+      &[
+        ("README.md", b"# Fixture\n"),
+        ("src/main.rs", b"fn main() {}\n"),
+        ("logo.png", b"\x89PNG\r\n\x1a\n\0\0\0"),
+        ("big.txt", &[b'x'; 2048]),
+      ],
+      "Initial commit",
+      Some("HEAD"),
+    );
+    repo
+      .tag_lightweight("v1", &repo.find_object(first, None).unwrap(), false)
+      .unwrap();
+    let second = commit(
+      &[("src/lib.rs", b"pub fn lib() {}\n")],
+      "Add lib",
+      Some("HEAD"),
+    );
+    repo
+      .branch("feature", &repo.find_commit(second).unwrap(), false)
+      .unwrap();
+    commit(
+      &[("feature.txt", b"wip\n")],
+      "Start feature",
+      Some("refs/heads/feature"),
+    );
+    dir
+  }
+
+  fn output(outputs: &std::collections::HashMap<String, FlowValue>, key: &str) -> Value {
+    match &outputs[key] {
+      FlowValue::Json(value) => value.clone(),
+      other => panic!("{key} is not JSON: {other:?}"),
+    }
+  }
+
+  fn inputs(pairs: &[(&str, String)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(json!(value))))
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn clones_and_checks_out_branches_and_tags() {
+    let origin = fixture();
+    let url = origin.path().display().to_string();
+    let target = tempfile::tempdir().unwrap();
+
+    let node = GitNode::new("checkout", GitOperation::Clone);
+    let feature_dir = target.path().join("feature");
+    let outputs = node
+      .execute(&inputs(&[
+        ("url", url.clone()),
+        ("ref", "feature".to_string()),
+        ("target_dir", feature_dir.display().to_string()),
+      ]))
+      .await
+      .unwrap();
+    assert_eq!(
+      output(&outputs, "path"),
+      json!(feature_dir.display().to_string())
+    );
+    assert!(feature_dir.join("feature.txt").exists());
+    let feature_head = Repository::open(origin.path())
+      .unwrap()
+      .revparse_single("feature")
+      .unwrap()
+      .id();
+    assert_eq!(output(&outputs, "head"), json!(feature_head.to_string()));
+
+    let tag_dir = target.path().join("tag");
+    node
+      .execute(&inputs(&[
+        ("url", url.clone()),
+        ("ref", "v1".to_string()),
+        ("target_dir", tag_dir.display().to_string()),
+      ]))
+      .await
+      .unwrap();
+    assert!(tag_dir.join("README.md").exists());
+    assert!(!tag_dir.join("src/lib.rs").exists());
+
+    let err = node
+      .execute(&inputs(&[(
+        "url",
+        target.path().join("missing").display().to_string(),
+      )]))
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("clone of"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn temp_clones_are_removed_with_the_node() {
+    let origin = fixture();
+    let node = GitNode::new("checkout", GitOperation::Clone);
+    let outputs = node
+      .execute(&inputs(&[("url", origin.path().display().to_string())]))
+      .await
+      .unwrap();
+    let path = PathBuf::from(output(&outputs, "path").as_str().unwrap());
+    assert!(path.join("README.md").exists());
+
+    drop(node);
+    assert!(!path.exists());
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn symlinks_out_of_the_repository_are_not_followed() {
+    let repo = fixture();
+    let outside = tempfile::tempdir().unwrap();
+    std::fs::write(outside.path().join("secret.txt"), "hunter2").unwrap();
+    std::os::unix::fs::symlink(
+      outside.path().join("secret.txt"),
+      repo.path().join("leak.txt"),
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(outside.path(), repo.path().join("linked")).unwrap();
+
+    let outputs = GitNode::new("sources", GitOperation::ReadFiles)
+      .with_patterns(vec!["*.txt".into(), "linked/*".into()])
+      .execute(&inputs(&[("path", repo.path().display().to_string())]))
+      .await
+      .unwrap();
+
+    let files = output(&outputs, "files");
+    assert_eq!(files.as_array().unwrap().len(), 1);
+    assert_eq!(files[0]["path"], json!("big.txt"));
+    assert_eq!(
+      output(&outputs, "skipped"),
+      json!([
+        {"path": "leak.txt", "reason": "symlink"},
+        {"path": "linked/secret.txt", "reason": "outside_root"},
+      ])
+    );
+  }
+
+  #[tokio::test]
+  async fn reads_matching_text_files_and_skips_binary_and_oversized_ones() {
+    let repo = fixture();
+    let node = GitNode::new("sources", GitOperation::ReadFiles)
+      .with_patterns(vec![
+        "**/*.rs".into(),
+        "*.md".into(),
+        "*.png".into(),
+        "big.txt".into(),
+      ])
+      .with_max_file_bytes(1024);
+
+    let outputs = node
+      .execute(&inputs(&[("path", repo.path().display().to_string())]))
+      .await
+      .unwrap();
+
+    let files = output(&outputs, "files");
+    let paths: Vec<_> = files
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|file| file["path"].as_str().unwrap())
+      .collect();
+    assert_eq!(paths, ["README.md", "src/lib.rs", "src/main.rs"]);
+    assert_eq!(files[2]["content"], json!("fn main() {}\n"));
+    assert_eq!(
+      output(&outputs, "skipped"),
+      json!([
+        {"path": "big.txt", "reason": "too_large", "size": 2048},
+        {"path": "logo.png", "reason": "binary", "size": 11},
+      ])
+    );
+
+    let capped = GitNode::new("sources", GitOperation::ReadFiles).with_max_total_bytes(20);
+    let outputs = capped
+      .execute(&inputs(&[("path", repo.path().display().to_string())]))
+      .await
+      .unwrap();
+    assert_eq!(output(&outputs, "truncated"), json!(true));
+    assert!(output(&outputs, "total_bytes").as_u64().unwrap() <= 20);
+  }
+
+  #[tokio::test]
+  async fn logs_recent_commits_newest_first() {
+    let repo = fixture();
+    let node = GitNode::new("history", GitOperation::Log).with_max_commits(2);
+
+    let outputs = node
+      .execute(&inputs(&[
+        ("path", repo.path().display().to_string()),
+        ("ref", "feature".to_string()),
+      ]))
+      .await
+      .unwrap();
+
+    let commits = output(&outputs, "commits");
+    assert_eq!(output(&outputs, "count"), json!(2));
+    assert_eq!(commits[0]["summary"], json!("Start feature"));
+    assert_eq!(commits[1]["summary"], json!("Add lib"));
+    assert_eq!(commits[0]["author"], json!("Ada Lovelace"));
+    assert_eq!(commits[0]["email"], json!("ada@example.com"));
+    assert!(commits[0]["time"].as_str().is_some());
+
+    // A relative path is taken under the working directory.
+    let (parent, name) = (
+      repo.path().parent().unwrap(),
+      repo.path().file_name().unwrap(),
+    );
+    let outputs = node
+      .with_working_dir(parent)
+      .execute(&inputs(&[
+        ("path", name.to_string_lossy().to_string()),
+        ("ref", "feature".to_string()),
+      ]))
+      .await
+      .unwrap();
+    assert_eq!(
+      output(&outputs, "commits")[0]["summary"],
+      json!("Start feature")
+    );
+  }
+}
@@ -1,15 +1,24 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
+  secret::SecretValue,
   value::FlowValue,
 };
-use agentflow_tools::builtin::HttpTool;
-use agentflow_tools::{SandboxPolicy, Tool};
+use agentflow_tools::SandboxPolicy;
+use agentflow_tools::builtin::{HttpRequest, HttpResponse, HttpTool};
 use async_trait::async_trait;
-use serde_json::{Value, json};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde_json::{Map, Value, json};
+
+use crate::common::template::render_url;
+use crate::common::utils::resolve_placeholders;
+
+/// Delay before the first retry of a 5xx response; doubles on each attempt.
+const DEFAULT_RETRY_DELAY_MS: u64 = 200;
 
 /// Workflow node that performs an HTTP request.
 ///
@@ -21,9 +30,91 @@ use serde_json::{Value, json};
 /// C2). The default policy is permissive on domain allow-listing but
 /// strict on private/loopback IP classes; pin via [`HttpNode::with_policy`]
 /// to lock down further.
+///
+/// # Inputs
+///
+/// - `url` (required) and `body` may contain `{{input_key}}` placeholders,
+///   replaced with the string form of the named node input. Values placed
+///   in the URL's path, query or fragment are percent-encoded.
+/// - `method` — defaults to `GET`.
+/// - `query` — object of query parameters appended to the URL.
+/// - `headers` — object of extra request headers.
+/// - Body, first match wins: `json` (any JSON value, sent as
+///   `application/json`), `form` (object, sent URL-encoded), or `body`
+///   (raw string).
+/// - `auth` — `{ "type": "bearer", "token": .. }` or
+///   `{ "type": "basic", "username": .., "password": .. }`. Credentials
+///   known at construction time belong in [`HttpNode::with_auth`] /
+///   [`HttpNode::with_secret_header`] instead, which keep them in a
+///   [`SecretValue`]; an `auth` input takes precedence over `with_auth`.
+/// - `timeout_ms` — per-request timeout.
+/// - `retries` — extra attempts after a 5xx response (default 0), with
+///   `retry_delay_ms` (default 200) doubling between attempts.
+///
+/// # Outputs
+///
+/// `status`, `headers` (object, lower-cased names), `body_text`, `body`
+/// (alias of `body_text` kept for existing workflows), and `json` when the
+/// response content type is JSON and the body parses.
 #[derive(Clone)]
 pub struct HttpNode {
   policy: Arc<SandboxPolicy>,
+  client: Option<reqwest::Client>,
+  auth: Option<HttpAuth>,
+  secret_headers: Vec<(String, SecretValue)>,
+}
+
+/// Credentials resolved when the node is built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpAuth {
+  Bearer {
+    token: SecretValue,
+  },
+  Basic {
+    username: String,
+    password: SecretValue,
+  },
+}
+
+impl HttpAuth {
+  /// Parses an `auth` block whose `token` / `password` may be a literal or
+  /// a `$secret` / `$secret_file` reference.
+  pub fn from_config(auth: &Map<String, Value>) -> Result<Self, AgentFlowError> {
+    let field = |name: &str| {
+      auth
+        .get(name)
+        .ok_or_else(|| AgentFlowError::ConfigurationError {
+          message: format!("HTTP 'auth' is missing field '{name}'"),
+        })
+    };
+    match auth.get("type").and_then(Value::as_str).unwrap_or("bearer") {
+      "bearer" => Ok(Self::Bearer {
+        token: SecretValue::resolve(field("token")?)?,
+      }),
+      "basic" => Ok(Self::Basic {
+        username: field("username")?
+          .as_str()
+          .ok_or_else(|| AgentFlowError::ConfigurationError {
+            message: "HTTP 'auth.username' must be a string".to_string(),
+          })?
+          .to_string(),
+        password: SecretValue::resolve(field("password")?)?,
+      }),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!("Unsupported auth type '{other}', expected 'bearer' or 'basic'"),
+      }),
+    }
+  }
+
+  fn header_value(&self) -> String {
+    match self {
+      Self::Bearer { token } => format!("Bearer {}", token.expose_secret()),
+      Self::Basic { username, password } => {
+        let credentials = format!("{}:{}", username, password.expose_secret());
+        format!("Basic {}", STANDARD.encode(credentials))
+      }
+    }
+  }
 }
 
 impl std::fmt::Debug for HttpNode {
@@ -34,73 +125,245 @@ impl std::fmt::Debug for HttpNode {
 
 impl HttpNode {
   pub fn new(policy: Arc<SandboxPolicy>) -> Self {
-    Self { policy }
+    Self {
+      policy,
+      client: None,
+      auth: None,
+      secret_headers: Vec::new(),
+    }
+  }
+
+  pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+    self.auth = Some(auth);
+    self
+  }
+
+  /// A request header whose value is a secret (API keys outside the
+  /// `authorization` header). Overrides a same-named `headers` input.
+  pub fn with_secret_header(mut self, name: &str, value: SecretValue) -> Self {
+    self.secret_headers.push((name.to_ascii_lowercase(), value));
+    self
   }
 
   pub fn with_policy(mut self, policy: Arc<SandboxPolicy>) -> Self {
     self.policy = policy;
     self
   }
+
+  /// Use a pre-built reqwest client (shared pool, custom TLS, or `.no_proxy()`
+  /// for loopback tests) instead of building one per execution.
+  pub fn with_client(mut self, client: reqwest::Client) -> Self {
+    self.client = Some(client);
+    self
+  }
+
+  fn tool(&self) -> Result<HttpTool, AgentFlowError> {
+    match &self.client {
+      Some(client) => Ok(HttpTool::with_client(client.clone(), self.policy.clone())),
+      None => {
+        HttpTool::new(self.policy.clone()).map_err(|err| AgentFlowError::AsyncExecutionError {
+          message: format!("HttpNode failed to build HTTP client: {err}"),
+        })
+      }
+    }
+  }
 }
 
 impl Default for HttpNode {
   fn default() -> Self {
     // The default policy denies private IPs / cloud metadata / loopback
     // but allows arbitrary public domains (allowed_domains empty == permissive).
-    Self {
-      policy: Arc::new(SandboxPolicy::default()),
-    }
+    Self::new(Arc::new(SandboxPolicy::default()))
   }
 }
 
 #[async_trait]
 impl AsyncNode for HttpNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
-    let url = get_string_input(inputs, "url")?;
-    let method = get_optional_string_input(inputs, "method")?.unwrap_or("GET");
-    let headers = get_optional_map_input(inputs, "headers")?;
-    let body = get_optional_string_input(inputs, "body")?;
-
-    // Build the params object expected by HttpTool. `usize::MAX` for
-    // max_response_chars preserves the legacy node behavior of returning
-    // the full response body; HttpTool's 8 KB default only matters for
-    // its agent-tool usage where a long body is noisy in transcripts.
-    let mut params = json!({
-        "url": url,
-        "method": method,
-    });
-    if let Some(b) = body {
-      params["body"] = json!(b);
+    let mut request = build_request(inputs)?;
+    for (name, value) in &self.secret_headers {
+      request
+        .headers
+        .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+      request
+        .headers
+        .push((name.clone(), value.expose_secret().to_string()));
     }
-    if let Some(h) = headers {
-      params["headers"] = json!(h);
+    if let Some(auth) = &self.auth {
+      set_default_header(&mut request.headers, "authorization", &auth.header_value());
     }
+    let retries = get_optional_u64_input(inputs, "retries")?.unwrap_or(0);
+    let mut delay = Duration::from_millis(
+      get_optional_u64_input(inputs, "retry_delay_ms")?.unwrap_or(DEFAULT_RETRY_DELAY_MS),
+    );
+    let tool = self.tool()?;
 
-    let tool = HttpTool::new(self.policy.clone())
-      .map_err(|err| AgentFlowError::AsyncExecutionError {
-        message: format!("HttpNode failed to build HTTP client: {err}"),
-      })?
-      .with_max_response_chars(usize::MAX);
+    let mut attempt = 0;
+    let response = loop {
+      let response =
+        tool
+          .send(request.clone())
+          .await
+          .map_err(|err| AgentFlowError::AsyncExecutionError {
+            message: err.to_string(),
+          })?;
+      if response.status < 500 || attempt >= retries {
+        break response;
+      }
+      attempt += 1;
+      eprintln!(
+        "⚠️  HTTP {} from {}, retrying ({}/{})",
+        response.status, request.url, attempt, retries
+      );
+      tokio::time::sleep(delay).await;
+      delay *= 2;
+    };
 
-    let output = tool
-      .execute(params)
-      .await
-      .map_err(|err| AgentFlowError::AsyncExecutionError {
-        message: err.to_string(),
-      })?;
-
-    // HttpTool emits a single-text-part `ToolOutput`. We don't have the
-    // HTTP status code at this layer because the tool flattens it into
-    // the body for prompt-engineering ergonomics, so the node returns
-    // `body` (with the legacy field name preserved) and signals success
-    // via `output.is_error`.
-    let mut outputs = HashMap::new();
-    outputs.insert(
-      "status".to_string(),
-      FlowValue::Json(json!(if output.is_error { 500 } else { 200 })),
+    Ok(response_outputs(response))
+  }
+}
+
+fn build_request(inputs: &AsyncNodeInputs) -> Result<HttpRequest, AgentFlowError> {
+  let url = render_url(get_string_input(inputs, "url")?, inputs)?;
+  let url = match get_optional_object_input(inputs, "query")? {
+    Some(query) if !query.is_empty() => append_query(&url, query),
+    _ => url,
+  };
+  let method = get_optional_string_input(inputs, "method")?
+    .unwrap_or("GET")
+    .to_uppercase();
+
+  let mut headers: Vec<(String, String)> = get_optional_object_input(inputs, "headers")?
+    .map(|map| {
+      map
+        .iter()
+        .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+        .collect()
+    })
+    .unwrap_or_default();
+
+  let body = if let Some(FlowValue::Json(value)) = inputs.get("json") {
+    set_default_header(&mut headers, "content-type", "application/json");
+    Some(value.to_string())
+  } else if let Some(form) = get_optional_object_input(inputs, "form")? {
+    set_default_header(
+      &mut headers,
+      "content-type",
+      "application/x-www-form-urlencoded",
     );
-    outputs.insert("body".to_string(), FlowValue::Json(json!(output.content)));
-    Ok(outputs)
+    Some(encode_pairs(form))
+  } else {
+    get_optional_string_input(inputs, "body")?
+      .map(|body| resolve_placeholders(body, inputs))
+      .transpose()?
+  };
+
+  if let Some(auth) = get_optional_object_input(inputs, "auth")? {
+    headers.push(("authorization".to_string(), authorization_header(auth)?));
+  }
+
+  Ok(HttpRequest {
+    method,
+    url,
+    headers,
+    body,
+    timeout: get_optional_u64_input(inputs, "timeout_ms")?.map(Duration::from_millis),
+  })
+}
+
+fn response_outputs(response: HttpResponse) -> HashMap<String, FlowValue> {
+  let is_json = response
+    .header("content-type")
+    .is_some_and(|content_type| content_type.to_ascii_lowercase().contains("json"));
+  let parsed = is_json
+    .then(|| serde_json::from_str::<Value>(&response.body).ok())
+    .flatten();
+
+  let mut headers = Map::new();
+  for (name, value) in &response.headers {
+    headers.entry(name.clone()).or_insert_with(|| json!(value));
+  }
+
+  let mut outputs = HashMap::new();
+  outputs.insert(
+    "status".to_string(),
+    FlowValue::Json(json!(response.status)),
+  );
+  outputs.insert(
+    "headers".to_string(),
+    FlowValue::Json(Value::Object(headers)),
+  );
+  if let Some(parsed) = parsed {
+    outputs.insert("json".to_string(), FlowValue::Json(parsed));
+  }
+  outputs.insert(
+    "body".to_string(),
+    FlowValue::Json(json!(response.body.clone())),
+  );
+  outputs.insert(
+    "body_text".to_string(),
+    FlowValue::Json(json!(response.body)),
+  );
+  outputs
+}
+
+fn append_query(url: &str, query: &Map<String, Value>) -> String {
+  let separator = if url.contains('?') { '&' } else { '?' };
+  format!("{url}{separator}{}", encode_pairs(query))
+}
+
+/// `application/x-www-form-urlencoded` encoding of a flat JSON object;
+/// arrays repeat the key, non-string scalars use their JSON text.
+fn encode_pairs(pairs: &Map<String, Value>) -> String {
+  let mut encoded = Vec::new();
+  for (key, value) in pairs {
+    let values = match value {
+      Value::Array(items) => items.iter().collect(),
+      Value::Null => Vec::new(),
+      other => vec![other],
+    };
+    for value in values {
+      let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+      };
+      encoded.push(format!(
+        "{}={}",
+        urlencoding::encode(key),
+        urlencoding::encode(&text)
+      ));
+    }
+  }
+  encoded.join("&")
+}
+
+fn set_default_header(headers: &mut Vec<(String, String)>, name: &str, value: &str) {
+  if !headers
+    .iter()
+    .any(|(key, _)| key.eq_ignore_ascii_case(name))
+  {
+    headers.push((name.to_string(), value.to_string()));
+  }
+}
+
+fn authorization_header(auth: &Map<String, Value>) -> Result<String, AgentFlowError> {
+  let field = |name: &str| {
+    auth
+      .get(name)
+      .and_then(Value::as_str)
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!("Input 'auth' is missing string field '{name}'"),
+      })
+  };
+  match auth.get("type").and_then(Value::as_str).unwrap_or("bearer") {
+    "bearer" => Ok(format!("Bearer {}", field("token")?)),
+    "basic" => {
+      let credentials = format!("{}:{}", field("username")?, field("password")?);
+      Ok(format!("Basic {}", STANDARD.encode(credentials)))
+    }
+    other => Err(AgentFlowError::NodeInputError {
+      message: format!("Unsupported auth type '{other}', expected 'bearer' or 'basic'"),
+    }),
   }
 }
 
@@ -134,27 +397,35 @@ fn get_optional_string_input<'a>(
   }
 }
 
-fn get_optional_map_input(
-  inputs: &AsyncNodeInputs,
+fn get_optional_object_input<'a>(
+  inputs: &'a AsyncNodeInputs,
   key: &str,
-) -> Result<Option<HashMap<String, String>>, AgentFlowError> {
+) -> Result<Option<&'a Map<String, Value>>, AgentFlowError> {
   match inputs.get(key) {
-    None => Ok(None),
-    Some(FlowValue::Json(Value::Object(map))) => {
-      let mut result = HashMap::new();
-      for (k, v) in map {
-        if let Value::String(s) = v {
-          result.insert(k.clone(), s.clone());
-        }
-      }
-      Ok(Some(result))
-    }
+    None | Some(FlowValue::Json(Value::Null)) => Ok(None),
+    Some(FlowValue::Json(Value::Object(map))) => Ok(Some(map)),
     _ => Err(AgentFlowError::NodeInputError {
       message: format!("Input '{}' has wrong type, expected a map", key),
     }),
   }
 }
 
+fn get_optional_u64_input(
+  inputs: &AsyncNodeInputs,
+  key: &str,
+) -> Result<Option<u64>, AgentFlowError> {
+  match inputs.get(key) {
+    None | Some(FlowValue::Json(Value::Null)) => Ok(None),
+    Some(FlowValue::Json(value)) if value.as_u64().is_some() => Ok(value.as_u64()),
+    _ => Err(AgentFlowError::NodeInputError {
+      message: format!(
+        "Input '{}' has wrong type, expected a non-negative integer",
+        key
+      ),
+    }),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -223,4 +494,267 @@ mod tests {
       "loopback should be allowed under explicit policy, but got: {message}"
     );
   }
+
+  fn loopback_node() -> HttpNode {
+    let policy = Arc::new(SandboxPolicy {
+      allow_loopback_network_access: true,
+      ..SandboxPolicy::default()
+    });
+    // `.no_proxy()` keeps a system proxy from swallowing loopback traffic
+    // (Q1.2.3).
+    let client = reqwest::Client::builder()
+      .redirect(reqwest::redirect::Policy::none())
+      .no_proxy()
+      .build()
+      .unwrap();
+    HttpNode::new(policy).with_client(client)
+  }
+
+  fn inputs(pairs: Value) -> AsyncNodeInputs {
+    pairs
+      .as_object()
+      .unwrap()
+      .iter()
+      .map(|(k, v)| (k.clone(), FlowValue::Json(v.clone())))
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn get_with_query_params_and_placeholders() {
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/users/42"))
+      .and(query_param("page", "2"))
+      .and(query_param("q", "a b"))
+      .and(header("authorization", "Bearer secret"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "ada" })))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let outputs = loopback_node()
+      .execute(&inputs(json!({
+        "url": format!("{}/users/{{{{user_id}}}}", server.uri()),
+        "user_id": 42,
+        "query": { "page": 2, "q": "a b" },
+        "auth": { "type": "bearer", "token": "secret" },
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["status"], FlowValue::Json(json!(200)));
+    assert_eq!(outputs["json"], FlowValue::Json(json!({ "name": "ada" })));
+    let FlowValue::Json(Value::Object(headers)) = &outputs["headers"] else {
+      panic!("headers should be an object");
+    };
+    assert!(headers["content-type"].as_str().unwrap().contains("json"));
+  }
+
+  #[test]
+  fn url_placeholders_are_percent_encoded() {
+    let request = build_request(&inputs(json!({
+      "url": "https://example.com/files/{{name}}?tag={{tag}}",
+      "name": "../etc/passwd",
+      "tag": "a&b=c",
+    })))
+    .unwrap();
+
+    assert_eq!(
+      request.url,
+      "https://example.com/files/..%2Fetc%2Fpasswd?tag=a%26b%3Dc"
+    );
+  }
+
+  #[tokio::test]
+  async fn configured_secrets_are_sent_but_never_debug_printed() {
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // SAFETY: the variable name is unique to this test.
+    unsafe { std::env::set_var("AGENTFLOW_HTTP_TEST_TOKEN", "tok-http-9876") };
+    let auth = HttpAuth::from_config(
+      json!({ "type": "bearer", "token": { "$secret": "AGENTFLOW_HTTP_TEST_TOKEN" } })
+        .as_object()
+        .unwrap(),
+    )
+    .unwrap();
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(header("authorization", "Bearer tok-http-9876"))
+      .and(header("x-api-key", "key-http-5432"))
+      .respond_with(ResponseTemplate::new(204))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let node = loopback_node()
+      .with_auth(auth.clone())
+      .with_secret_header("X-Api-Key", SecretValue::new("key-http-5432"));
+    let outputs = node
+      .execute(&inputs(json!({
+        "url": server.uri(),
+        "headers": { "x-api-key": "overridden" },
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["status"], FlowValue::Json(json!(204)));
+    let debug = format!("{:?}", auth);
+    assert!(!debug.contains("tok-http-9876"), "{debug}");
+  }
+
+  #[tokio::test]
+  async fn post_json_body_sets_content_type() {
+    use wiremock::matchers::{body_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(header("content-type", "application/json"))
+      .and(header("authorization", "Basic dXNlcjpwYXNz"))
+      .and(body_json(json!({ "title": "hello", "tags": ["a"] })))
+      .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "id": 7 })))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let outputs = loopback_node()
+      .execute(&inputs(json!({
+        "url": server.uri(),
+        "method": "post",
+        "json": { "title": "hello", "tags": ["a"] },
+        "auth": { "type": "basic", "username": "user", "password": "pass" },
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["status"], FlowValue::Json(json!(201)));
+    assert_eq!(outputs["json"], FlowValue::Json(json!({ "id": 7 })));
+  }
+
+  #[tokio::test]
+  async fn post_form_body_is_url_encoded() {
+    use wiremock::matchers::{body_string, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(header("content-type", "application/x-www-form-urlencoded"))
+      .and(body_string("text=a%26b"))
+      .respond_with(ResponseTemplate::new(200))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let outputs = loopback_node()
+      .execute(&inputs(json!({
+        "url": server.uri(),
+        "method": "POST",
+        "form": { "text": "a&b" },
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["status"], FlowValue::Json(json!(200)));
+  }
+
+  #[tokio::test]
+  async fn retries_server_errors_until_success() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(500))
+      .up_to_n_times(2)
+      .expect(2)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(200).set_body_string("recovered"))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let outputs = loopback_node()
+      .execute(&inputs(json!({
+        "url": server.uri(),
+        "retries": 2,
+        "retry_delay_ms": 1,
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["status"], FlowValue::Json(json!(200)));
+    assert_eq!(outputs["body_text"], FlowValue::Json(json!("recovered")));
+  }
+
+  #[tokio::test]
+  async fn server_error_is_returned_once_retries_are_spent() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(503))
+      .expect(2)
+      .mount(&server)
+      .await;
+
+    let outputs = loopback_node()
+      .execute(&inputs(json!({
+        "url": server.uri(),
+        "retries": 1,
+        "retry_delay_ms": 1,
+      })))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["status"], FlowValue::Json(json!(503)));
+  }
+
+  #[tokio::test]
+  async fn non_json_response_has_text_but_no_json_output() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(
+        ResponseTemplate::new(200)
+          .insert_header("content-type", "text/html")
+          .set_body_string("<p>{\"not\": \"json\"}</p>"),
+      )
+      .mount(&server)
+      .await;
+
+    let outputs = loopback_node()
+      .execute(&inputs(json!({ "url": server.uri() })))
+      .await
+      .unwrap();
+
+    assert!(!outputs.contains_key("json"));
+    assert_eq!(
+      outputs["body_text"],
+      FlowValue::Json(json!("<p>{\"not\": \"json\"}</p>"))
+    );
+    assert_eq!(outputs["body"], outputs["body_text"]);
+  }
+
+  #[tokio::test]
+  async fn unknown_placeholder_is_an_input_error() {
+    let err = HttpNode::default()
+      .execute(&inputs(
+        json!({ "url": "https://example.test/{{missing}}" }),
+      ))
+      .await
+      .unwrap_err();
+
+    assert!(matches!(err, AgentFlowError::NodeInputError { .. }));
+    assert!(err.to_string().contains("'{{missing}}'"), "{err}");
+  }
 }
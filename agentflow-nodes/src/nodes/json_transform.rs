@@ -0,0 +1,586 @@
+//! JSON transform node.
+//!
+//! `JsonTransformNode` reshapes one JSON input through an ordered pipeline of
+//! operations, so wiring an LLM or HTTP response into the next node no
+//! longer needs a custom Rust node. The pipeline starts from the
+//! `input_key` input (default `input`; `null` when absent) and each
+//! operation replaces the current value:
+//!
+//! - `select: <jsonpath>` — the match (RFC 9535 JSONPath, e.g.
+//!   `$.choices[0].message.content`); several matches become an array.
+//!   The result's shape follows the match count, not the path: a wildcard
+//!   or filter that happens to match one element yields that element, not
+//!   a one-element array.
+//! - `select_all: <jsonpath>` — every match as an array, however many there
+//!   are (`[]` when none).
+//! - `map_fields: {name: <jsonpath>, ...}` — a new object, one field per path.
+//! - `merge: [input, ...]` — shallow-merges the named inputs' objects into
+//!   the current object (later keys win) or appends them to the current array.
+//! - `to_array` / `from_array` — object values ↔ array elements; with
+//!   `{key: field}`, the object key is written to / read from that field.
+//! - `stringify` (`{pretty: true}` for indented output) / `parse`.
+//!
+//! A path that matches nothing yields `null` under `on_missing: null` (the
+//! default) and fails the node under `on_missing: error`. Expressions are
+//! compiled by [`JsonTransformNode::from_params`], so a malformed one is a
+//! configuration error rather than a run-time failure.
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::common::utils::flow_value_to_string;
+
+/// What a path that matches nothing evaluates to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingPathMode {
+  #[default]
+  Null,
+  Error,
+}
+
+impl FromStr for MissingPathMode {
+  type Err = AgentFlowError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "null" => Ok(Self::Null),
+      "error" => Ok(Self::Error),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!(
+          "Unknown on_missing mode '{}' (expected null or error)",
+          other
+        ),
+      }),
+    }
+  }
+}
+
+/// A compiled JSONPath plus its source text for error messages.
+#[derive(Debug, Clone)]
+pub struct PathExpr {
+  source: String,
+  path: JsonPath,
+}
+
+impl PathExpr {
+  pub fn parse(source: &str) -> Result<Self, AgentFlowError> {
+    let path = JsonPath::parse(source).map_err(|e| AgentFlowError::ConfigurationError {
+      message: format!("Invalid JSONPath '{}': {}", source, e),
+    })?;
+    Ok(Self {
+      source: source.to_string(),
+      path,
+    })
+  }
+
+  /// `None` when nothing matches; a lone match is returned as-is, even for
+  /// a path that could match several values.
+  fn query(&self, value: &Value) -> Option<Value> {
+    let matches = self.path.query(value).all();
+    match matches.as_slice() {
+      [] => None,
+      [single] => Some((*single).clone()),
+      many => Some(Value::Array(many.iter().map(|v| (*v).clone()).collect())),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum TransformOp {
+  Select(PathExpr),
+  SelectAll(PathExpr),
+  MapFields(Vec<(String, PathExpr)>),
+  Merge(Vec<String>),
+  ToArray { key: Option<String> },
+  FromArray { key: Option<String> },
+  Stringify { pretty: bool },
+  Parse,
+}
+
+impl TransformOp {
+  /// Parses one pipeline entry: a bare operation name, or a single-key
+  /// object mapping the operation name to its argument.
+  pub fn from_value(value: &Value) -> Result<Self, AgentFlowError> {
+    let invalid = |message: String| AgentFlowError::ConfigurationError { message };
+    let entry = match value {
+      Value::String(name) => Some((name.as_str(), &Value::Null)),
+      Value::Object(fields) if fields.len() == 1 => {
+        fields.iter().next().map(|(name, arg)| (name.as_str(), arg))
+      }
+      _ => None,
+    };
+    let Some((name, arg)) = entry else {
+      return Err(invalid(format!(
+        "json_transform operation must be a name or a single-key object, got {}",
+        value
+      )));
+    };
+    let key_option = |arg: &Value| match arg {
+      Value::Null => Ok(None),
+      Value::Object(options) => match options.get("key") {
+        None => Ok(None),
+        Some(Value::String(key)) => Ok(Some(key.clone())),
+        Some(_) => Err(invalid(format!("'{}' option 'key' must be a string", name))),
+      },
+      _ => Err(invalid(format!(
+        "'{}' takes an optional {{key: field}}",
+        name
+      ))),
+    };
+
+    match name {
+      "select" => match arg {
+        Value::String(path) => Ok(Self::Select(PathExpr::parse(path)?)),
+        _ => Err(invalid("'select' requires a JSONPath string".to_string())),
+      },
+      "select_all" => match arg {
+        Value::String(path) => Ok(Self::SelectAll(PathExpr::parse(path)?)),
+        _ => Err(invalid(
+          "'select_all' requires a JSONPath string".to_string(),
+        )),
+      },
+      "map_fields" => match arg {
+        Value::Object(fields) if !fields.is_empty() => fields
+          .iter()
+          .map(|(field, path)| match path {
+            Value::String(path) => Ok((field.clone(), PathExpr::parse(path)?)),
+            _ => Err(invalid(format!(
+              "'map_fields.{}' must be a JSONPath string",
+              field
+            ))),
+          })
+          .collect::<Result<Vec<_>, _>>()
+          .map(Self::MapFields),
+        _ => Err(invalid(
+          "'map_fields' requires an object of field: JSONPath".to_string(),
+        )),
+      },
+      "merge" => match arg {
+        Value::Array(names) if !names.is_empty() => names
+          .iter()
+          .map(|name| {
+            name
+              .as_str()
+              .map(str::to_string)
+              .ok_or_else(|| invalid("'merge' input names must be strings".to_string()))
+          })
+          .collect::<Result<Vec<_>, _>>()
+          .map(Self::Merge),
+        _ => Err(invalid(
+          "'merge' requires a non-empty list of input names".to_string(),
+        )),
+      },
+      "to_array" => Ok(Self::ToArray {
+        key: key_option(arg)?,
+      }),
+      "from_array" => Ok(Self::FromArray {
+        key: key_option(arg)?,
+      }),
+      "stringify" => Ok(Self::Stringify {
+        pretty: arg.get("pretty").and_then(Value::as_bool).unwrap_or(false),
+      }),
+      "parse" => Ok(Self::Parse),
+      other => Err(invalid(format!(
+        "Unknown json_transform operation '{}' (expected select, select_all, map_fields, merge, to_array, from_array, stringify or parse)",
+        other
+      ))),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonTransformNode {
+  pub name: String,
+  pub input_key: String,
+  pub output_key: String,
+  pub on_missing: MissingPathMode,
+  pub operations: Vec<TransformOp>,
+}
+
+impl JsonTransformNode {
+  pub fn new(name: &str, operations: Vec<TransformOp>) -> Self {
+    Self {
+      name: name.to_string(),
+      input_key: "input".to_string(),
+      output_key: "output".to_string(),
+      on_missing: MissingPathMode::default(),
+      operations,
+    }
+  }
+
+  pub fn with_input_key(mut self, key: &str) -> Self {
+    self.input_key = key.to_string();
+    self
+  }
+
+  pub fn with_output_key(mut self, key: &str) -> Self {
+    self.output_key = key.to_string();
+    self
+  }
+
+  pub fn with_on_missing(mut self, mode: MissingPathMode) -> Self {
+    self.on_missing = mode;
+    self
+  }
+
+  /// Build from a `type: json_transform` parameters object
+  /// (`operations`, `input_key`, `output_key`, `on_missing`).
+  pub fn from_params(name: &str, params: &Value) -> Result<Self, AgentFlowError> {
+    let operations = match params.get("operations") {
+      Some(Value::Array(entries)) if !entries.is_empty() => entries
+        .iter()
+        .map(TransformOp::from_value)
+        .collect::<Result<Vec<_>, _>>()?,
+      _ => {
+        return Err(AgentFlowError::ConfigurationError {
+          message: "json_transform requires a non-empty 'operations' list".to_string(),
+        });
+      }
+    };
+    let mut node = Self::new(name, operations);
+    if let Some(key) = params.get("input_key").and_then(Value::as_str) {
+      node = node.with_input_key(key);
+    }
+    if let Some(key) = params.get("output_key").and_then(Value::as_str) {
+      node = node.with_output_key(key);
+    }
+    if let Some(mode) = params.get("on_missing").and_then(Value::as_str) {
+      node = node.with_on_missing(mode.parse()?);
+    }
+    Ok(node)
+  }
+
+  fn lookup(&self, expr: &PathExpr, value: &Value) -> Result<Value, AgentFlowError> {
+    match (expr.query(value), self.on_missing) {
+      (Some(found), _) => Ok(found),
+      (None, MissingPathMode::Null) => Ok(Value::Null),
+      (None, MissingPathMode::Error) => Err(AgentFlowError::NodeExecutionFailed {
+        message: format!(
+          "json_transform '{}': path '{}' matched nothing",
+          self.name, expr.source
+        ),
+      }),
+    }
+  }
+
+  fn apply(
+    &self,
+    op: &TransformOp,
+    current: Value,
+    inputs: &AsyncNodeInputs,
+  ) -> Result<Value, AgentFlowError> {
+    let failed = |message: String| AgentFlowError::NodeExecutionFailed {
+      message: format!("json_transform '{}': {}", self.name, message),
+    };
+    match op {
+      TransformOp::Select(expr) => self.lookup(expr, &current),
+      TransformOp::SelectAll(expr) => Ok(Value::Array(
+        expr
+          .path
+          .query(&current)
+          .all()
+          .into_iter()
+          .cloned()
+          .collect(),
+      )),
+      TransformOp::MapFields(fields) => {
+        let mut record = Map::new();
+        for (field, expr) in fields {
+          record.insert(field.clone(), self.lookup(expr, &current)?);
+        }
+        Ok(Value::Object(record))
+      }
+      TransformOp::Merge(names) => {
+        let mut merged = current;
+        for name in names {
+          let other = match inputs.get(name) {
+            Some(value) => input_json(value),
+            None => return Err(failed(format!("merge input '{}' is missing", name))),
+          };
+          merged = match (merged, other) {
+            (Value::Null, other) => other,
+            (Value::Object(mut base), Value::Object(extra)) => {
+              base.extend(extra);
+              Value::Object(base)
+            }
+            (Value::Array(mut base), Value::Array(extra)) => {
+              base.extend(extra);
+              Value::Array(base)
+            }
+            (Value::Array(mut base), other) => {
+              base.push(other);
+              Value::Array(base)
+            }
+            (base, other) => {
+              return Err(failed(format!(
+                "cannot merge {} into {}",
+                kind(&other),
+                kind(&base)
+              )));
+            }
+          };
+        }
+        Ok(merged)
+      }
+      TransformOp::ToArray { key } => Ok(match current {
+        Value::Null => Value::Array(Vec::new()),
+        Value::Array(items) => Value::Array(items),
+        Value::Object(fields) => Value::Array(
+          fields
+            .into_iter()
+            .map(|(name, value)| match (key, value) {
+              (Some(key), Value::Object(mut item)) => {
+                item.insert(key.clone(), Value::String(name));
+                Value::Object(item)
+              }
+              (Some(key), value) => {
+                serde_json::json!({ key.as_str(): name, "value": value })
+              }
+              (None, value) => value,
+            })
+            .collect(),
+        ),
+        scalar => Value::Array(vec![scalar]),
+      }),
+      TransformOp::FromArray { key } => {
+        let Value::Array(items) = current else {
+          return Err(failed(format!(
+            "from_array expects an array, got {}",
+            kind(&current)
+          )));
+        };
+        let mut record = Map::new();
+        for (index, item) in items.into_iter().enumerate() {
+          let name = match key {
+            Some(key) => match item.get(key) {
+              Some(Value::String(name)) => name.clone(),
+              Some(name @ (Value::Number(_) | Value::Bool(_))) => name.to_string(),
+              _ => {
+                return Err(failed(format!(
+                  "from_array element {} has no string '{}' field",
+                  index, key
+                )));
+              }
+            },
+            None => index.to_string(),
+          };
+          record.insert(name, item);
+        }
+        Ok(Value::Object(record))
+      }
+      TransformOp::Stringify { pretty } => {
+        let text = if *pretty {
+          serde_json::to_string_pretty(&current)
+        } else {
+          serde_json::to_string(&current)
+        };
+        text
+          .map(Value::String)
+          .map_err(|e| failed(format!("stringify failed: {}", e)))
+      }
+      TransformOp::Parse => match current {
+        Value::String(text) => {
+          serde_json::from_str(text.trim()).map_err(|e| failed(format!("parse failed: {}", e)))
+        }
+        other => Ok(other),
+      },
+    }
+  }
+
+  /// Run the pipeline over `input` (the value of `input_key`).
+  pub fn transform(&self, input: Value, inputs: &AsyncNodeInputs) -> Result<Value, AgentFlowError> {
+    self
+      .operations
+      .iter()
+      .try_fold(input, |current, op| self.apply(op, current, inputs))
+  }
+}
+
+fn input_json(value: &FlowValue) -> Value {
+  match value {
+    FlowValue::Json(json) => json.clone(),
+    // File / URL references transform as their path or URL text.
+    other => Value::String(flow_value_to_string(other)),
+  }
+}
+
+fn kind(value: &Value) -> &'static str {
+  match value {
+    Value::Null => "null",
+    Value::Bool(_) => "a boolean",
+    Value::Number(_) => "a number",
+    Value::String(_) => "a string",
+    Value::Array(_) => "an array",
+    Value::Object(_) => "an object",
+  }
+}
+
+#[async_trait]
+impl AsyncNode for JsonTransformNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let input = inputs
+      .get(&self.input_key)
+      .map(input_json)
+      .unwrap_or(Value::Null);
+    let output = self.transform(input, inputs)?;
+    Ok(HashMap::from([(
+      self.output_key.clone(),
+      FlowValue::Json(output),
+    )]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn node(params: Value) -> JsonTransformNode {
+    JsonTransformNode::from_params("reshape", &params).unwrap()
+  }
+
+  fn inputs(pairs: &[(&str, Value)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn flattens_an_llm_style_response_into_a_record() {
+    let response = json!({
+      "id": "chatcmpl-1",
+      "model": "gpt-4o",
+      "choices": [{
+        "message": {
+          "role": "assistant",
+          "content": "{\"title\": \"Attention\", \"tags\": [\"nlp\", \"ml\"]}"
+        }
+      }],
+      "usage": { "total_tokens": 42 }
+    });
+    let transform = node(json!({
+      "input_key": "response",
+      "output_key": "record",
+      "operations": [
+        { "map_fields": {
+          "content": "$.choices[0].message.content",
+          "model": "$.model",
+          "tokens": "$.usage.total_tokens"
+        } },
+        { "merge": ["source"] }
+      ]
+    }));
+    let content = node(json!({
+      "operations": [{ "select": "$.content" }, "parse", { "select": "$.tags[*]" }]
+    }));
+
+    let outputs = transform
+      .execute(&inputs(&[
+        ("response", response),
+        ("source", json!({ "source": "openai" })),
+      ]))
+      .await
+      .unwrap();
+    let FlowValue::Json(record) = &outputs["record"] else {
+      panic!("record should be JSON");
+    };
+
+    assert_eq!(record["model"], json!("gpt-4o"));
+    assert_eq!(record["tokens"], json!(42));
+    assert_eq!(record["source"], json!("openai"));
+    let tags = content
+      .transform(record.clone(), &AsyncNodeInputs::new())
+      .unwrap();
+    assert_eq!(tags, json!(["nlp", "ml"]));
+  }
+
+  #[test]
+  fn missing_paths_are_null_or_errors() {
+    let value = json!({ "present": 1 });
+    let lenient =
+      node(json!({ "operations": [{ "map_fields": { "a": "$.present", "b": "$.absent" } }] }));
+    assert_eq!(
+      lenient
+        .transform(value.clone(), &AsyncNodeInputs::new())
+        .unwrap(),
+      json!({ "a": 1, "b": null })
+    );
+
+    let strict =
+      node(json!({ "on_missing": "error", "operations": [{ "select": "$.absent.deeper" }] }));
+    let err = strict
+      .transform(value, &AsyncNodeInputs::new())
+      .unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("'$.absent.deeper' matched nothing"),
+      "{err}"
+    );
+  }
+
+  #[test]
+  fn select_collapses_a_single_match_but_select_all_keeps_the_array() {
+    let value = json!({ "items": [{ "id": 1, "tag": "a" }, { "id": 2, "tag": "b" }] });
+    let select = node(json!({ "operations": [{ "select": "$.items[?@.tag == 'a'].id" }] }));
+    assert_eq!(
+      select
+        .transform(value.clone(), &AsyncNodeInputs::new())
+        .unwrap(),
+      json!(1)
+    );
+
+    let select_all = node(json!({ "operations": [{ "select_all": "$.items[?@.tag == 'a'].id" }] }));
+    assert_eq!(
+      select_all
+        .transform(value.clone(), &AsyncNodeInputs::new())
+        .unwrap(),
+      json!([1])
+    );
+    let none = node(json!({ "operations": [{ "select_all": "$.items[?@.tag == 'z'].id" }] }));
+    assert_eq!(
+      none.transform(value, &AsyncNodeInputs::new()).unwrap(),
+      json!([])
+    );
+  }
+
+  #[test]
+  fn array_object_round_trip_and_stringify() {
+    let people = node(json!({
+      "operations": [{ "from_array": { "key": "id" } }, { "to_array": { "key": "id" } }, "stringify"]
+    }));
+    let text = people
+      .transform(
+        json!([{ "id": "a", "n": 1 }, { "id": "b", "n": 2 }]),
+        &AsyncNodeInputs::new(),
+      )
+      .unwrap();
+    assert_eq!(text, json!(r#"[{"id":"a","n":1},{"id":"b","n":2}]"#));
+  }
+
+  #[test]
+  fn invalid_pipelines_fail_at_construction() {
+    for params in [
+      json!({ "operations": [{ "select": "$.[bad" }] }),
+      json!({ "operations": [{ "map_fields": { "x": "no-dollar" } }] }),
+      json!({ "operations": ["flatten"] }),
+      json!({ "operations": [] }),
+      json!({ "on_missing": "skip", "operations": ["parse"] }),
+    ] {
+      let err = JsonTransformNode::from_params("bad", &params).unwrap_err();
+      assert!(
+        matches!(err, AgentFlowError::ConfigurationError { .. }),
+        "{params}: {err}"
+      );
+    }
+  }
+}
@@ -1,3 +1,12 @@
+//! Markdown → mind map rendering.
+//!
+//! The heading / list tree is turned into markmap's JSON data format in Rust
+//! and embedded in a standalone HTML page, so rendering needs neither a
+//! renderer service nor, with [`MarkMapConfig::assets_dir`], a CDN. A static
+//! SVG of the same tree can be produced alongside for contexts that can't run
+//! JavaScript (PDF reports, image previews).
+
+use crate::common::template::{RenderOptions, render};
 use crate::common::utils::flow_value_to_string;
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
@@ -5,20 +14,92 @@ use agentflow_core::{
   value::FlowValue,
 };
 use async_trait::async_trait;
+use regex::Regex;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static HEADING_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(#{1,6})\s+(.*?)\s*#*\s*$")
+    .expect("HEADING_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static LIST_ITEM_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(\s*)(?:[-*+]|\d{1,9}[.)])\s+(.*)$")
+    .expect("LIST_ITEM_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static INLINE_CODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"`([^`]+)`").expect("INLINE_CODE_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static BOLD_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"\*\*(.+?)\*\*").expect("BOLD_RE is malformed — bug in agentflow-nodes")
+});
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"\[([^\]]+)\]\(((?:https?://|/|#)[^)\s]*)\)")
+    .expect("LINK_RE is malformed — bug in agentflow-nodes")
+});
+
+/// d3's `schemeCategory10`, markmap's default palette.
+const DEFAULT_COLOR_SCHEME: &[&str] = &[
+  "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+  "#bcbd22", "#17becf",
+];
+
+const CDN_SCRIPTS: &str = r#"<script src="https://cdn.jsdelivr.net/npm/d3@7"></script>
+<script src="https://cdn.jsdelivr.net/npm/markmap-view@0.17"></script>"#;
+
+/// Files [`MarkMapConfig::assets_dir`] must contain, inlined in this order.
+pub const MARKMAP_ASSET_FILES: &[&str] = &["d3.min.js", "markmap-view.min.js"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarkMapConfig {
+  /// Remote renderer. When set, the HTML comes from `{api_url}/api/render`
+  /// instead of the local template.
   pub api_url: Option<String>,
   pub title: Option<String>,
+  /// `light` or `dark`.
   pub theme: Option<String>,
   pub color_freeze_level: Option<u8>,
+  /// Levels expanded on load; `-1` expands everything.
   pub initial_expand_level: Option<i8>,
   pub max_width: Option<u32>,
   pub timeout_seconds: Option<u64>,
+  /// Branch colours, cycled. Defaults to d3's category10.
+  #[serde(default)]
+  pub color_scheme: Option<Vec<String>>,
+  /// Nodes deeper than this (the root is depth 1) are dropped.
+  #[serde(default)]
+  pub max_depth: Option<usize>,
+  /// Directory holding [`MARKMAP_ASSET_FILES`]; when set they are inlined
+  /// into the HTML so it opens offline.
+  #[serde(default)]
+  pub assets_dir: Option<PathBuf>,
+  /// Also emit a static `svg` output.
+  #[serde(default)]
+  pub render_svg: Option<bool>,
 }
 
 impl Default for MarkMapConfig {
@@ -30,9 +111,8 @@ impl Default for MarkMapConfig {
     // endpoint stays up, doesn't rate-limit, doesn't log payloads, or
     // doesn't disappear when the developer stops paying the CF bill.
     // The default now reads `AGENTFLOW_MARKMAP_API_URL` if set,
-    // otherwise leaves the field `None` so the node fails fast with a
-    // clear "configure api_url" message instead of silently exfiltrating
-    // markdown to a third party.
+    // otherwise leaves the field `None` and the node renders locally,
+    // so markdown never leaves the process unless an operator opts in.
     Self {
       api_url: std::env::var("AGENTFLOW_MARKMAP_API_URL").ok(),
       title: Some("Mind Map".to_string()),
@@ -41,10 +121,488 @@ impl Default for MarkMapConfig {
       initial_expand_level: Some(-1),
       max_width: Some(200),
       timeout_seconds: Some(30),
+      color_scheme: None,
+      max_depth: None,
+      assets_dir: None,
+      render_svg: None,
+    }
+  }
+}
+
+impl MarkMapConfig {
+  fn colors(&self) -> Vec<String> {
+    match &self.color_scheme {
+      Some(colors) if !colors.is_empty() => colors.clone(),
+      _ => DEFAULT_COLOR_SCHEME
+        .iter()
+        .map(ToString::to_string)
+        .collect(),
+    }
+  }
+
+  fn is_dark(&self) -> bool {
+    self.theme.as_deref() == Some("dark")
+  }
+
+  /// Options in the JSON shape markmap's `deriveOptions` understands.
+  fn view_options(&self) -> Value {
+    let mut options = json!({ "color": self.colors() });
+    if let Some(level) = self.color_freeze_level {
+      options["colorFreezeLevel"] = json!(level);
+    }
+    if let Some(level) = self.initial_expand_level {
+      options["initialExpandLevel"] = json!(level);
+    }
+    if let Some(width) = self.max_width {
+      options["maxWidth"] = json!(width);
+    }
+    options
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MindNodeKind {
+  Root,
+  Heading,
+  ListItem,
+  Paragraph,
+}
+
+/// One node of the mind map tree; `content` is the raw markdown text.
+#[derive(Debug, Clone, PartialEq)]
+struct MindNode {
+  content: String,
+  kind: MindNodeKind,
+  children: Vec<MindNode>,
+}
+
+impl MindNode {
+  /// Builds the tree markmap would: headings nest by level, list items by
+  /// indentation under the nearest heading, and loose paragraphs become leaf
+  /// nodes. A document with a single top-level heading uses it as the root;
+  /// otherwise `title` is.
+  fn parse(markdown: &str, title: &str) -> Self {
+    struct Entry {
+      content: String,
+      kind: MindNodeKind,
+      children: Vec<usize>,
+    }
+    fn build(arena: &mut [Entry], index: usize) -> MindNode {
+      let children = arena[index].children.clone();
+      MindNode {
+        content: std::mem::take(&mut arena[index].content),
+        kind: arena[index].kind,
+        children: children
+          .into_iter()
+          .map(|child| build(arena, child))
+          .collect(),
+      }
+    }
+
+    let mut arena = vec![Entry {
+      content: title.to_string(),
+      kind: MindNodeKind::Root,
+      children: Vec::new(),
+    }];
+    // (rank, arena index): headings rank by level, list items by indentation
+    // above every heading, so popping to a lower rank finds the parent.
+    let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+    let mut last_item: Option<usize> = None;
+    let mut in_fence = false;
+
+    let push = |arena: &mut Vec<Entry>, parent: usize, content: &str, kind| {
+      arena.push(Entry {
+        content: content.trim().to_string(),
+        kind,
+        children: Vec::new(),
+      });
+      let index = arena.len() - 1;
+      arena[parent].children.push(index);
+      index
+    };
+
+    for line in markdown.lines() {
+      let trimmed = line.trim();
+      if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        in_fence = !in_fence;
+        continue;
+      }
+      if in_fence || trimmed.is_empty() {
+        continue;
+      }
+
+      if let Some(caps) = HEADING_RE.captures(trimmed)
+        && !line.starts_with([' ', '\t'])
+      {
+        let rank = caps[1].len();
+        while stack.last().is_some_and(|(top, _)| *top >= rank) {
+          stack.pop();
+        }
+        let parent = stack.last().map_or(0, |(_, index)| *index);
+        let index = push(&mut arena, parent, &caps[2], MindNodeKind::Heading);
+        stack.push((rank, index));
+        last_item = None;
+      } else if let Some(caps) = LIST_ITEM_RE.captures(line) {
+        let indent: usize = caps[1].chars().map(|c| if c == '\t' { 4 } else { 1 }).sum();
+        let rank = 100 + indent;
+        while stack.last().is_some_and(|(top, _)| *top >= rank) {
+          stack.pop();
+        }
+        let parent = stack.last().map_or(0, |(_, index)| *index);
+        let index = push(&mut arena, parent, &caps[2], MindNodeKind::ListItem);
+        stack.push((rank, index));
+        last_item = Some(index);
+      } else if let Some(item) = last_item.filter(|_| line.starts_with([' ', '\t'])) {
+        // Indented continuation of the previous list item.
+        arena[item].content.push(' ');
+        arena[item].content.push_str(trimmed);
+      } else {
+        while stack.last().is_some_and(|(top, _)| *top >= 100) {
+          stack.pop();
+        }
+        let parent = stack.last().map_or(0, |(_, index)| *index);
+        push(&mut arena, parent, trimmed, MindNodeKind::Paragraph);
+        last_item = None;
+      }
+    }
+
+    let root = build(&mut arena, 0);
+    match root.children.as_slice() {
+      [only] if only.kind == MindNodeKind::Heading => {
+        let mut promoted = only.clone();
+        promoted.kind = MindNodeKind::Root;
+        promoted
+      }
+      _ => root,
+    }
+  }
+
+  /// Drops everything below `max_depth` and returns how many nodes went.
+  fn truncate(&mut self, max_depth: usize) -> usize {
+    fn count(node: &MindNode) -> usize {
+      1 + node.children.iter().map(count).sum::<usize>()
+    }
+    fn walk(node: &mut MindNode, depth: usize, max_depth: usize) -> usize {
+      if depth >= max_depth {
+        let dropped = node.children.iter().map(count).sum();
+        node.children.clear();
+        return dropped;
+      }
+      node
+        .children
+        .iter_mut()
+        .map(|child| walk(child, depth + 1, max_depth))
+        .sum()
+    }
+    walk(self, 1, max_depth.max(1))
+  }
+
+  /// markmap's `{ content, children }` data, with content as inline HTML.
+  fn to_data(&self) -> Value {
+    json!({
+      "content": inline_html(&self.content),
+      "children": self.children.iter().map(MindNode::to_data).collect::<Vec<_>>(),
+    })
+  }
+
+  /// Canonical markdown for the tree: contiguous heading levels, `-` bullets
+  /// with two-space indentation.
+  fn to_markdown(&self) -> String {
+    fn walk(node: &MindNode, depth: usize, list_base: Option<usize>, lines: &mut Vec<String>) {
+      let heading = matches!(node.kind, MindNodeKind::Root | MindNodeKind::Heading)
+        && list_base.is_none()
+        && depth <= 6;
+      let mut child_base = list_base;
+      match node.kind {
+        _ if heading => {
+          lines.push(String::new());
+          lines.push(format!("{} {}", "#".repeat(depth), node.content));
+        }
+        MindNodeKind::Paragraph if list_base.is_none() => {
+          lines.push(String::new());
+          lines.push(node.content.clone());
+          lines.push(String::new());
+        }
+        _ => {
+          let base = *child_base.get_or_insert(depth);
+          if lines.last().is_some_and(|line| line.starts_with('#')) {
+            lines.push(String::new());
+          }
+          lines.push(format!("{}- {}", "  ".repeat(depth - base), node.content));
+        }
+      }
+      for child in &node.children {
+        walk(child, depth + 1, child_base, lines);
+      }
+    }
+
+    let mut lines = Vec::new();
+    walk(self, 1, None, &mut lines);
+    let mut out = String::new();
+    let mut previous_blank = true;
+    for line in lines {
+      let blank = line.is_empty();
+      if !(blank && previous_blank) {
+        out.push_str(&line);
+        out.push('\n');
+      }
+      previous_blank = blank;
     }
+    out.trim_end().to_string() + "\n"
   }
 }
 
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Escapes the text, then renders the inline markdown markmap users lean on:
+/// code spans, bold, and http(s) / relative links.
+fn inline_html(text: &str) -> String {
+  let escaped = escape_html(text);
+  let coded = INLINE_CODE_RE.replace_all(&escaped, "<code>$1</code>");
+  let bold = BOLD_RE.replace_all(&coded, "<strong>$1</strong>");
+  LINK_RE
+    .replace_all(&bold, r#"<a href="$2">$1</a>"#)
+    .into_owned()
+}
+
+/// Strips the inline markdown back out for the plain-text SVG labels.
+fn plain_text(text: &str) -> String {
+  let linked = LINK_RE.replace_all(text, "$1");
+  let bold = BOLD_RE.replace_all(&linked, "$1");
+  INLINE_CODE_RE.replace_all(&bold, "$1").into_owned()
+}
+
+/// JSON is valid JavaScript, but a `</script>` inside a string would still
+/// end the script element.
+fn script_json(value: &Value) -> String {
+  value.to_string().replace("</", "<\\/")
+}
+
+fn render_html(data: &Value, config: &MarkMapConfig) -> Result<String, AgentFlowError> {
+  let scripts = match &config.assets_dir {
+    None => CDN_SCRIPTS.to_string(),
+    Some(dir) => {
+      let mut inlined = String::new();
+      for file in MARKMAP_ASSET_FILES {
+        let path = dir.join(file);
+        let source =
+          std::fs::read_to_string(&path).map_err(|e| AgentFlowError::ConfigurationError {
+            message: format!(
+              "MarkMap assets_dir is missing {} ({}); it must contain {}",
+              path.display(),
+              e,
+              MARKMAP_ASSET_FILES.join(" and ")
+            ),
+          })?;
+        let _ = writeln!(
+          inlined,
+          "<script>\n{}\n</script>",
+          source.replace("</script", "<\\/script")
+        );
+      }
+      inlined
+    }
+  };
+  let title = config.title.as_deref().unwrap_or("Mind Map");
+  let (body_class, background) = if config.is_dark() {
+    ("markmap-dark", "#1e1e1e")
+  } else {
+    ("", "#ffffff")
+  };
+
+  Ok(format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{title}</title>
+<style>
+html, body {{ margin: 0; padding: 0; height: 100%; background: {background}; }}
+#mindmap {{ display: block; width: 100vw; height: 100vh; }}
+</style>
+{scripts}
+</head>
+<body class="{body_class}">
+<svg id="mindmap"></svg>
+<script>
+(() => {{
+  const data = {data};
+  const options = {options};
+  const {{ Markmap, deriveOptions }} = window.markmap;
+  Markmap.create('#mindmap', deriveOptions(options), data);
+}})();
+</script>
+</body>
+</html>
+"#,
+    title = escape_html(title),
+    data = script_json(data),
+    options = script_json(&config.view_options()),
+  ))
+}
+
+/// A left-to-right tidy tree: leaves take consecutive rows, parents centre on
+/// their children, and every depth gets a column as wide as its widest label.
+fn render_svg(root: &MindNode, config: &MarkMapConfig) -> String {
+  const ROW_HEIGHT: f64 = 30.0;
+  const CHAR_WIDTH: f64 = 7.0;
+  const COLUMN_GAP: f64 = 40.0;
+  const MARGIN: f64 = 20.0;
+
+  struct Placed {
+    label: String,
+    depth: usize,
+    y: f64,
+    width: f64,
+    color: String,
+    parent: Option<usize>,
+  }
+
+  let colors = config.colors();
+  let expand = config
+    .initial_expand_level
+    .filter(|level| *level >= 0)
+    .map(|level| level.max(1) as usize);
+  let freeze = usize::from(config.color_freeze_level.unwrap_or(6));
+  let max_chars = (f64::from(config.max_width.unwrap_or(200)) / CHAR_WIDTH).max(4.0) as usize;
+
+  fn place(
+    node: &MindNode,
+    depth: usize,
+    parent: Option<usize>,
+    inherited: Option<&str>,
+    state: &mut (Vec<Placed>, usize, usize),
+    colors: &[String],
+    limits: (Option<usize>, usize, usize),
+  ) -> f64 {
+    let (expand, freeze, max_chars) = limits;
+    let mut label = plain_text(&node.content);
+    if label.chars().count() > max_chars {
+      label = label.chars().take(max_chars - 1).collect::<String>() + "…";
+    }
+    let color = match inherited {
+      Some(color) if depth > freeze => color.to_string(),
+      _ => {
+        let color = colors[state.2 % colors.len()].clone();
+        state.2 += 1;
+        color
+      }
+    };
+    let width = label.chars().count() as f64 * CHAR_WIDTH + 8.0;
+    state.0.push(Placed {
+      label,
+      depth,
+      y: 0.0,
+      width,
+      color: color.clone(),
+      parent,
+    });
+    let index = state.0.len() - 1;
+
+    let visible = expand.is_none_or(|level| depth < level);
+    let y = if visible && !node.children.is_empty() {
+      let ys: Vec<f64> = node
+        .children
+        .iter()
+        .map(|child| {
+          place(
+            child,
+            depth + 1,
+            Some(index),
+            Some(&color),
+            state,
+            colors,
+            limits,
+          )
+        })
+        .collect();
+      (ys[0] + ys[ys.len() - 1]) / 2.0
+    } else {
+      let y = MARGIN + state.1 as f64 * ROW_HEIGHT + ROW_HEIGHT / 2.0;
+      state.1 += 1;
+      y
+    };
+    state.0[index].y = y;
+    y
+  }
+
+  let mut state = (Vec::new(), 0usize, 0usize);
+  place(
+    root,
+    1,
+    None,
+    None,
+    &mut state,
+    &colors,
+    (expand, freeze, max_chars),
+  );
+  let (placed, rows, _) = state;
+
+  let depth_count = placed.iter().map(|p| p.depth).max().unwrap_or(1);
+  let mut column_x = vec![MARGIN; depth_count + 1];
+  for depth in 1..depth_count {
+    let widest = placed
+      .iter()
+      .filter(|p| p.depth == depth)
+      .map(|p| p.width)
+      .fold(0.0, f64::max);
+    column_x[depth + 1] = column_x[depth] + widest + COLUMN_GAP;
+  }
+  let width = placed
+    .iter()
+    .map(|p| column_x[p.depth] + p.width)
+    .fold(0.0, f64::max)
+    + MARGIN;
+  let height = rows.max(1) as f64 * ROW_HEIGHT + 2.0 * MARGIN;
+  let (background, text) = if config.is_dark() {
+    ("#1e1e1e", "#dddddd")
+  } else {
+    ("#ffffff", "#333333")
+  };
+
+  let mut svg = format!(
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}" font-family="sans-serif" font-size="13">
+<rect width="100%" height="100%" fill="{background}"/>
+"#
+  );
+  for node in &placed {
+    let x = column_x[node.depth];
+    if let Some(parent) = node.parent.map(|index| &placed[index]) {
+      let (px, py) = (column_x[parent.depth] + parent.width, parent.y + 4.0);
+      let mid = (px + x) / 2.0;
+      let _ = writeln!(
+        svg,
+        r#"<path d="M{px:.1},{py:.1} C{mid:.1},{py:.1} {mid:.1},{y:.1} {x:.1},{y:.1}" fill="none" stroke="{color}" stroke-width="1.5"/>"#,
+        y = node.y + 4.0,
+        color = node.color,
+      );
+    }
+    let _ = writeln!(
+      svg,
+      r#"<line x1="{x:.1}" y1="{y:.1}" x2="{x2:.1}" y2="{y:.1}" stroke="{color}" stroke-width="2"/>"#,
+      y = node.y + 4.0,
+      x2 = x + node.width,
+      color = node.color,
+    );
+    let _ = writeln!(
+      svg,
+      r#"<text x="{x:.1}" y="{y:.1}" fill="{text}">{label}</text>"#,
+      x = x + 4.0,
+      y = node.y,
+      label = escape_html(&node.label),
+    );
+  }
+  svg.push_str("</svg>\n");
+  svg
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarkMapNode {
   pub name: String,
@@ -80,23 +638,7 @@ impl MarkMapNode {
     }
 
     // Otherwise, resolve placeholders in the configured markdown
-    let mut resolved = self.markdown.clone();
-
-    for (key, value) in inputs {
-      // Support both {{ key }} and {{key}}
-      let placeholder_with_spaces = format!("{{{{ {} }}}}", key);
-      let placeholder_without_spaces = format!("{{{{{}}}}}", key);
-      let value_str = flow_value_to_string(value);
-
-      if resolved.contains(&placeholder_with_spaces) {
-        resolved = resolved.replace(&placeholder_with_spaces, &value_str);
-      }
-      if resolved.contains(&placeholder_without_spaces) {
-        resolved = resolved.replace(&placeholder_without_spaces, &value_str);
-      }
-    }
-
-    Ok(resolved)
+    render(&self.markdown, inputs, &RenderOptions::lenient())
   }
 
   fn build_request_payload(&self, markdown: &str) -> Value {
@@ -126,35 +668,13 @@ impl MarkMapNode {
     payload
   }
 
-  async fn save_html_to_file(&self, html: &str) -> Result<(), AgentFlowError> {
-    if let Some(file_path) = &self.save_to_file {
-      tokio::fs::write(file_path, html)
-        .await
-        .map_err(|e| AgentFlowError::AsyncExecutionError {
-          message: format!("Failed to save HTML to file {}: {}", file_path, e),
-        })?;
-    }
-    Ok(())
-  }
-}
-
-#[async_trait]
-impl AsyncNode for MarkMapNode {
-  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
-    let resolved_markdown = self.resolve_markdown(inputs)?;
-    let payload = self.build_request_payload(&resolved_markdown);
-
-    let default_config = MarkMapConfig::default();
-    let config = self.config.as_ref().unwrap_or(&default_config);
-    let api_url = config
-      .api_url
-      .as_ref()
-      .ok_or_else(|| AgentFlowError::ConfigurationError {
-        message:
-          "MarkMap api_url not configured. Set `AGENTFLOW_MARKMAP_API_URL=https://your-own-renderer` or pass it explicitly via MarkMapConfig.api_url. (Q3.8.1: the previous hardcoded personal Cloudflare Worker default was removed for security.)"
-            .to_string(),
-      })?;
-
+  async fn render_remote(
+    &self,
+    api_url: &str,
+    markdown: &str,
+    config: &MarkMapConfig,
+  ) -> Result<String, AgentFlowError> {
+    let payload = self.build_request_payload(markdown);
     let timeout_duration = std::time::Duration::from_secs(config.timeout_seconds.unwrap_or(30));
     let client = Client::builder()
       .timeout(timeout_duration)
@@ -181,19 +701,69 @@ impl AsyncNode for MarkMapNode {
       });
     }
 
-    let response_text = response
+    response
       .text()
       .await
       .map_err(|e| AgentFlowError::AsyncExecutionError {
         message: format!("Failed to read response body: {}", e),
-      })?;
+      })
+  }
+
+  async fn save_html_to_file(&self, html: &str) -> Result<(), AgentFlowError> {
+    if let Some(file_path) = &self.save_to_file {
+      tokio::fs::write(file_path, html)
+        .await
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!("Failed to save HTML to file {}: {}", file_path, e),
+        })?;
+    }
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl AsyncNode for MarkMapNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let resolved_markdown = self.resolve_markdown(inputs)?;
+    let default_config = MarkMapConfig::default();
+    let config = self.config.as_ref().unwrap_or(&default_config);
+
+    let title = config.title.as_deref().unwrap_or("Mind Map");
+    let mut tree = MindNode::parse(&resolved_markdown, title);
+    let truncated = config
+      .max_depth
+      .map_or(0, |max_depth| tree.truncate(max_depth));
+    if truncated > 0 {
+      println!(
+        "✂️  MarkMap '{}' dropped {} node(s) below max_depth",
+        self.name, truncated
+      );
+    }
+    let markdown = tree.to_markdown();
+    let data = tree.to_data();
 
-    self.save_html_to_file(&response_text).await?;
+    let html = match &config.api_url {
+      Some(api_url) => self.render_remote(api_url, &markdown, config).await?,
+      None => render_html(&data, config)?,
+    };
+    self.save_html_to_file(&html).await?;
 
     let mut outputs = HashMap::new();
+    if config.render_svg.unwrap_or(false) {
+      outputs.insert(
+        "svg".to_string(),
+        FlowValue::Json(Value::String(render_svg(&tree, config))),
+      );
+    }
+    outputs.insert("html".to_string(), FlowValue::Json(Value::String(html)));
+    outputs.insert(
+      "markdown".to_string(),
+      FlowValue::Json(Value::String(markdown)),
+    );
+    outputs.insert("data".to_string(), FlowValue::Json(data));
     outputs.insert(
-      "html".to_string(),
-      FlowValue::Json(Value::String(response_text)),
+      "truncated_nodes".to_string(),
+      FlowValue::Json(json!(truncated)),
     );
 
     Ok(outputs)
@@ -205,29 +775,199 @@ mod tests {
   use super::*;
   use serde_json::json;
 
-  // Q3.8.1: with no default api_url (per security audit), execution must
-  // surface a configuration error rather than silently hitting a
-  // previously-hardcoded personal Cloudflare Worker. This replaces the
-  // prior live-network test, which was both flaky and a smoke test for
-  // the personal endpoint.
-  #[tokio::test]
-  async fn test_markmap_node_requires_api_url_configuration() {
-    // Ensure the env var isn't accidentally set in CI.
-    // SAFETY: test-scoped env mutation; var has no semantic meaning to other tests.
-    unsafe {
-      std::env::remove_var("AGENTFLOW_MARKMAP_API_URL");
+  const PAPER_FIXTURE: &str = include_str!("../../tests/fixtures/markmap/paper.md");
+
+  fn local_config() -> MarkMapConfig {
+    MarkMapConfig {
+      api_url: None,
+      ..MarkMapConfig::default()
     }
+  }
 
-    let node = MarkMapNode::new("test_map", "# {{title}}\n## Item 1\n## Item 2");
+  async fn render(markdown: &str, config: MarkMapConfig) -> HashMap<String, FlowValue> {
+    let node = MarkMapNode::new("test_map", "").with_config(config);
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("markdown".to_string(), FlowValue::Json(json!(markdown)));
+    node.execute(&inputs).await.unwrap()
+  }
+
+  fn string(outputs: &HashMap<String, FlowValue>, key: &str) -> String {
+    let FlowValue::Json(Value::String(value)) = &outputs[key] else {
+      panic!("{key} should be a string");
+    };
+    value.clone()
+  }
+
+  // Q3.8.1: with no api_url configured nothing may leave the process; the
+  // node renders locally instead of calling a hardcoded endpoint.
+  #[tokio::test]
+  async fn test_markmap_node_renders_locally_without_api_url() {
+    let node =
+      MarkMapNode::new("test_map", "# {{title}}\n## Item 1\n## Item 2").with_config(local_config());
     let mut inputs = AsyncNodeInputs::new();
     inputs.insert("title".to_string(), FlowValue::Json(json!("My Test Map")));
 
-    let result = node.execute(&inputs).await;
-    let err = result.expect_err("must fail without configured api_url");
-    let msg = format!("{err}");
-    assert!(
-      msg.contains("api_url not configured"),
-      "error must instruct operator to configure api_url, got: {msg}"
+    let outputs = node.execute(&inputs).await.unwrap();
+    assert_eq!(
+      outputs["data"],
+      FlowValue::Json(json!({
+        "content": "My Test Map",
+        "children": [
+          { "content": "Item 1", "children": [] },
+          { "content": "Item 2", "children": [] },
+        ],
+      }))
     );
   }
+
+  #[tokio::test]
+  async fn html_embeds_the_tree_for_the_fixture() {
+    let outputs = render(PAPER_FIXTURE, local_config()).await;
+    let html = string(&outputs, "html");
+
+    let FlowValue::Json(data) = &outputs["data"] else {
+      panic!("data should be JSON");
+    };
+    assert_eq!(data["content"], json!("SGLang"));
+    let sections: Vec<&Value> = data["children"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|child| &child["content"])
+      .collect();
+    assert_eq!(
+      sections,
+      [
+        &json!("Introduction"),
+        &json!("Programming Model"),
+        &json!("Conclusion")
+      ]
+    );
+    let intro = &data["children"][0]["children"];
+    assert_eq!(
+      intro[0]["children"][1]["content"],
+      json!("Structured outputs")
+    );
+    assert_eq!(
+      intro[1]["content"],
+      json!("<strong>Contributions</strong>: frontend language &amp; runtime")
+    );
+    let primitives = &data["children"][1]["children"][1];
+    assert_eq!(
+      primitives["children"][1]["content"],
+      json!("<code>select</code> continues on the next line")
+    );
+
+    assert!(html.contains(&format!("const data = {};", script_json(data))));
+    assert!(html.contains("Markmap.create('#mindmap'"));
+    assert!(html.contains("cdn.jsdelivr.net/npm/markmap-view"));
+    assert!(!html.contains("not a heading"));
+    assert_eq!(outputs["truncated_nodes"], FlowValue::Json(json!(0)));
+  }
+
+  #[tokio::test]
+  async fn markdown_output_is_normalized() {
+    let outputs = render(
+      "# Root\n\n### Skipped level\n* item\n    * nested\n\nLoose text\n",
+      local_config(),
+    )
+    .await;
+
+    assert_eq!(
+      string(&outputs, "markdown"),
+      "# Root\n\n## Skipped level\n\n- item\n  - nested\n\nLoose text\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn deep_nesting_is_truncated_at_max_depth() {
+    let markdown = "# Root\n## A\n- a1\n  - a2\n    - a3\n      - a4\n## B\n";
+    let outputs = render(
+      markdown,
+      MarkMapConfig {
+        max_depth: Some(3),
+        ..local_config()
+      },
+    )
+    .await;
+
+    let FlowValue::Json(data) = &outputs["data"] else {
+      panic!("data should be JSON");
+    };
+    assert_eq!(data["children"][0]["children"][0]["content"], json!("a1"));
+    assert_eq!(data["children"][0]["children"][0]["children"], json!([]));
+    assert_eq!(outputs["truncated_nodes"], FlowValue::Json(json!(3)));
+    assert!(!string(&outputs, "html").contains("a2"));
+    assert_eq!(
+      string(&outputs, "markdown"),
+      "# Root\n\n## A\n\n- a1\n\n## B\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn assets_dir_inlines_scripts_instead_of_the_cdn() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("d3.min.js"), "window.d3 = {};").unwrap();
+    std::fs::write(
+      dir.path().join("markmap-view.min.js"),
+      "window.markmap = {};",
+    )
+    .unwrap();
+
+    let outputs = render(
+      "# Offline",
+      MarkMapConfig {
+        assets_dir: Some(dir.path().to_path_buf()),
+        color_scheme: Some(vec!["#123456".to_string()]),
+        initial_expand_level: Some(2),
+        ..local_config()
+      },
+    )
+    .await;
+
+    let html = string(&outputs, "html");
+    assert!(!html.contains("cdn.jsdelivr.net"));
+    assert!(html.contains("<script>\nwindow.d3 = {};\n</script>"));
+    assert!(html.contains(r##""color":["#123456"]"##));
+    assert!(html.contains(r#""initialExpandLevel":2"#));
+
+    let missing = MarkMapNode::new("missing", "# x").with_config(MarkMapConfig {
+      assets_dir: Some(dir.path().join("nope")),
+      ..local_config()
+    });
+    let err = missing.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(err.to_string().contains("d3.min.js"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn svg_renders_visible_levels_only() {
+    let outputs = render(
+      PAPER_FIXTURE,
+      MarkMapConfig {
+        render_svg: Some(true),
+        initial_expand_level: Some(2),
+        theme: Some("dark".to_string()),
+        ..local_config()
+      },
+    )
+    .await;
+
+    let svg = string(&outputs, "svg");
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains(">SGLang</text>"));
+    assert!(svg.contains(">Programming Model</text>"));
+    assert!(!svg.contains("Structured outputs"));
+    assert!(svg.contains("#1e1e1e"));
+    assert_eq!(svg.matches("<path ").count(), 3);
+
+    let without = render(PAPER_FIXTURE, local_config()).await;
+    assert!(!without.contains_key("svg"));
+  }
+
+  #[test]
+  fn script_content_cannot_close_the_script_element() {
+    let data = MindNode::parse("# </script><script>alert(1)</script>", "t").to_data();
+    let html = render_html(&data, &local_config()).unwrap();
+    assert_eq!(html.matches("</script>").count(), 3);
+  }
 }
@@ -7,6 +7,18 @@
 #[cfg(feature = "http")]
 pub mod http;
 
+// Waiting on external systems: inbound callbacks and outbound polling.
+#[cfg(feature = "http")]
+pub mod poll_until;
+#[cfg(feature = "http")]
+pub mod webhook_wait;
+
+// Content ingestion: RSS / Atom feeds and readable web pages.
+#[cfg(feature = "http")]
+pub mod feed;
+#[cfg(feature = "http")]
+pub mod web_extract;
+
 #[cfg(feature = "file")]
 pub mod file;
 
@@ -19,6 +31,48 @@ pub mod batch;
 #[cfg(feature = "conditional")]
 pub mod conditional;
 
+#[cfg(feature = "sql")]
+pub mod sql;
+
+#[cfg(feature = "s3")]
+pub mod object_store;
+
+#[cfg(feature = "git")]
+pub mod git;
+
+#[cfg(feature = "email")]
+pub mod email;
+
+// Host process execution (tool tier — std/tokio only, no feature gate).
+pub mod command;
+
+// Data reshaping (tool tier — no feature gate).
+pub mod json_transform;
+
+// Regex / code-block / JSON extraction from free text (tool tier — no
+// feature gate).
+pub mod text_extract;
+
+// CSV / XLSX ingestion and CSV output (tool tier — XLSX input needs the
+// `xlsx` feature).
+pub mod tabular;
+
+// In-process cosine-similarity search (tool tier — no feature gate).
+pub mod vector_search;
+
+// Markdown report rendering to HTML / PDF files (tool tier — no feature gate).
+pub mod document_render;
+
+// Human sign-off via file sentinel, webhook callback or stdin (tool tier —
+// the http channel needs the `http` feature).
+pub mod approval;
+
+// Timing: fixed / jittered delays, schedule windows and per-group throttling
+// (tool tier — no feature gate).
+pub mod delay;
+pub mod schedule_gate;
+pub mod throttle;
+
 // Specialized content processing nodes (tool tier — no capability deps).
 pub mod arxiv;
 pub mod markmap;
@@ -0,0 +1,394 @@
+//! Poll-until node - re-fetches a URL until a JSONPath condition holds.
+//!
+//! Each attempt GETs the `url` input (with `{{key}}` placeholders and
+//! optional `headers`) through the sandboxed
+//! [`HttpTool`](agentflow_tools::builtin::HttpTool), parses the body as
+//! JSON and queries it with the `until` JSONPath. The condition is met when
+//! some match equals `equals`, or - without `equals` - when some match is
+//! neither `null` nor `false`; a filter such as `$[?@.status == 'done']`
+//! therefore works on its own. Non-JSON or failing responses just count as
+//! "not yet".
+//!
+//! Between attempts the node sleeps `interval_ms`, multiplied by `backoff`
+//! after each miss and capped at `max_interval_ms`. It gives up with
+//! `TimeoutExceeded` once `timeout_ms` has elapsed, with
+//! `AsyncExecutionError` after `max_attempts` misses, and with
+//! `TaskCancelled` as soon as the flow is cancelled.
+//!
+//! Outputs `json` (the satisfying body), `matched` (the JSONPath matches),
+//! `status`, `attempts` and `elapsed_ms`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  node_context,
+  value::FlowValue,
+};
+use agentflow_tools::SandboxPolicy;
+use agentflow_tools::builtin::{HttpRequest, HttpTool};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use serde_json_path::JsonPath;
+
+use crate::common::utils::get_request_from_inputs;
+
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 1_000;
+pub const DEFAULT_MAX_POLL_INTERVAL_MS: u64 = 30_000;
+pub const DEFAULT_POLL_TIMEOUT_MS: u64 = 300_000;
+
+#[derive(Clone)]
+pub struct PollUntilNode {
+  pub name: String,
+  until_source: String,
+  until: JsonPath,
+  pub equals: Option<Value>,
+  pub interval: Duration,
+  pub backoff: f64,
+  pub max_interval: Duration,
+  pub timeout: Duration,
+  pub max_attempts: Option<u32>,
+  policy: Arc<SandboxPolicy>,
+  client: Option<reqwest::Client>,
+}
+
+impl std::fmt::Debug for PollUntilNode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PollUntilNode")
+      .field("name", &self.name)
+      .field("until", &self.until_source)
+      .field("equals", &self.equals)
+      .field("interval", &self.interval)
+      .field("backoff", &self.backoff)
+      .field("timeout", &self.timeout)
+      .field("max_attempts", &self.max_attempts)
+      .finish_non_exhaustive()
+  }
+}
+
+impl PollUntilNode {
+  /// A poller whose condition is the `until` JSONPath; rejects a path that
+  /// doesn't parse.
+  pub fn new(name: &str, until: &str) -> Result<Self, AgentFlowError> {
+    let path = JsonPath::parse(until).map_err(|e| AgentFlowError::ConfigurationError {
+      message: format!("Invalid JSONPath '{}': {}", until, e),
+    })?;
+    Ok(Self {
+      name: name.to_string(),
+      until_source: until.to_string(),
+      until: path,
+      equals: None,
+      interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+      backoff: 1.0,
+      max_interval: Duration::from_millis(DEFAULT_MAX_POLL_INTERVAL_MS),
+      timeout: Duration::from_millis(DEFAULT_POLL_TIMEOUT_MS),
+      max_attempts: None,
+      // Same default as `HttpNode`: public hosts only.
+      policy: Arc::new(SandboxPolicy::default()),
+      client: None,
+    })
+  }
+
+  pub fn with_equals(mut self, expected: Value) -> Self {
+    self.equals = Some(expected);
+    self
+  }
+
+  pub fn with_interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  /// Factor applied to the interval after each miss (at least 1.0).
+  pub fn with_backoff(mut self, backoff: f64) -> Self {
+    self.backoff = backoff.max(1.0);
+    self
+  }
+
+  pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+    self.max_interval = max_interval;
+    self
+  }
+
+  /// Total polling budget.
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+    self.max_attempts = Some(max_attempts.max(1));
+    self
+  }
+
+  pub fn with_policy(mut self, policy: Arc<SandboxPolicy>) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Use a pre-built reqwest client (e.g. `.no_proxy()` for loopback tests).
+  pub fn with_client(mut self, client: reqwest::Client) -> Self {
+    self.client = Some(client);
+    self
+  }
+
+  fn tool(&self) -> Result<HttpTool, AgentFlowError> {
+    match &self.client {
+      Some(client) => Ok(HttpTool::with_client(client.clone(), self.policy.clone())),
+      None => {
+        HttpTool::new(self.policy.clone()).map_err(|err| AgentFlowError::AsyncExecutionError {
+          message: format!("PollUntilNode failed to build HTTP client: {err}"),
+        })
+      }
+    }
+  }
+
+  fn request(&self, inputs: &AsyncNodeInputs) -> Result<HttpRequest, AgentFlowError> {
+    get_request_from_inputs(&format!("Poll node '{}'", self.name), inputs)
+  }
+
+  /// JSONPath matches when the condition holds for `body`, else `None`.
+  fn satisfied(&self, body: &Value) -> Option<Vec<Value>> {
+    let matches = self.until.query(body).all();
+    let hit = matches.iter().any(|value| match &self.equals {
+      Some(expected) => *value == expected,
+      None => !matches!(value, Value::Null | Value::Bool(false)),
+    });
+    hit.then(|| matches.into_iter().cloned().collect())
+  }
+
+  /// Sleeps `delay`, or fails early when the flow is cancelled.
+  async fn wait(&self, delay: Duration) -> Result<(), AgentFlowError> {
+    tokio::select! {
+      _ = tokio::time::sleep(delay) => Ok(()),
+      _ = node_context::cancelled() => Err(AgentFlowError::TaskCancelled),
+    }
+  }
+}
+
+#[async_trait]
+impl AsyncNode for PollUntilNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let request = self.request(inputs)?;
+    let tool = self.tool()?;
+    let started = Instant::now();
+    let mut delay = self.interval;
+    let mut attempts = 0u32;
+    let mut last_status = None;
+
+    loop {
+      if node_context::is_cancelled() {
+        return Err(AgentFlowError::TaskCancelled);
+      }
+      attempts += 1;
+      let response = tokio::select! {
+        response = tool.send(request.clone()) => response,
+        _ = node_context::cancelled() => return Err(AgentFlowError::TaskCancelled),
+      };
+      match response {
+        Ok(response) => {
+          last_status = Some(response.status);
+          let body = serde_json::from_str::<Value>(&response.body).ok();
+          if let Some(body) = body.filter(|_| (200..300).contains(&response.status))
+            && let Some(matched) = self.satisfied(&body)
+          {
+            println!(
+              "✅ Poll node '{}': condition met after {} attempt(s)",
+              self.name, attempts
+            );
+            let mut outputs = HashMap::new();
+            outputs.insert("json".to_string(), FlowValue::Json(body));
+            outputs.insert(
+              "matched".to_string(),
+              FlowValue::Json(Value::Array(matched)),
+            );
+            outputs.insert(
+              "status".to_string(),
+              FlowValue::Json(json!(response.status)),
+            );
+            outputs.insert("attempts".to_string(), FlowValue::Json(json!(attempts)));
+            outputs.insert(
+              "elapsed_ms".to_string(),
+              FlowValue::Json(json!(started.elapsed().as_millis() as u64)),
+            );
+            return Ok(outputs);
+          }
+        }
+        Err(err) => eprintln!(
+          "⚠️  Poll node '{}': attempt {} failed: {}",
+          self.name, attempts, err
+        ),
+      }
+      node_context::emit_progress(json!({
+        "attempt": attempts,
+        "status": last_status,
+        "satisfied": false,
+      }));
+
+      if self.max_attempts.is_some_and(|max| attempts >= max) {
+        return Err(AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Poll node '{}': '{}' not satisfied after {} attempt(s) (last status: {})",
+            self.name,
+            self.until_source,
+            attempts,
+            last_status.map_or("none".to_string(), |s| s.to_string())
+          ),
+        });
+      }
+      let remaining = self.timeout.saturating_sub(started.elapsed());
+      if remaining.is_zero() {
+        return Err(AgentFlowError::TimeoutExceeded {
+          duration_ms: self.timeout.as_millis() as u64,
+        });
+      }
+      self.wait(delay.min(remaining)).await?;
+      // `mul_f64` panics on overflow; a huge or non-finite product just
+      // means "wait the maximum".
+      delay = Duration::try_from_secs_f64(delay.as_secs_f64() * self.backoff)
+        .map_or(self.max_interval, |next| next.min(self.max_interval));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_core::node_context::NodeContext;
+  use agentflow_core::scheduler::FlowCancellationToken;
+  use wiremock::matchers::{method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  fn loopback(node: PollUntilNode) -> PollUntilNode {
+    let policy = Arc::new(SandboxPolicy {
+      allow_loopback_network_access: true,
+      ..SandboxPolicy::default()
+    });
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    node.with_policy(policy).with_client(client)
+  }
+
+  fn url_input(url: String) -> AsyncNodeInputs {
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("url".to_string(), FlowValue::Json(json!(url)));
+    inputs
+  }
+
+  #[tokio::test]
+  async fn polls_until_the_status_field_flips() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/jobs/7"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "running" })))
+      .up_to_n_times(2)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/jobs/7"))
+      .respond_with(
+        ResponseTemplate::new(200).set_body_json(json!({ "status": "done", "result": 42 })),
+      )
+      .mount(&server)
+      .await;
+
+    let node = loopback(
+      PollUntilNode::new("poll", "$.status")
+        .unwrap()
+        .with_equals(json!("done"))
+        .with_interval(Duration::from_millis(10))
+        .with_backoff(2.0),
+    );
+    let outputs = node
+      .execute(&url_input(format!("{}/jobs/7", server.uri())))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["attempts"], FlowValue::Json(json!(3)));
+    assert_eq!(outputs["matched"], FlowValue::Json(json!(["done"])));
+    assert_eq!(
+      outputs["json"],
+      FlowValue::Json(json!({ "status": "done", "result": 42 }))
+    );
+  }
+
+  #[tokio::test]
+  async fn gives_up_when_the_budget_expires() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ready": false })))
+      .mount(&server)
+      .await;
+
+    let node = loopback(
+      PollUntilNode::new("poll", "$.ready")
+        .unwrap()
+        .with_interval(Duration::from_millis(20))
+        .with_timeout(Duration::from_millis(100)),
+    );
+    let err = node.execute(&url_input(server.uri())).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::TimeoutExceeded { duration_ms: 100 }),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  async fn huge_backoff_is_capped_instead_of_overflowing() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ready": false })))
+      .mount(&server)
+      .await;
+
+    let node = loopback(
+      PollUntilNode::new("poll", "$.ready")
+        .unwrap()
+        .with_interval(Duration::from_millis(10))
+        .with_backoff(f64::MAX)
+        .with_max_interval(Duration::from_millis(10))
+        .with_max_attempts(3),
+    );
+    let err = node.execute(&url_input(server.uri())).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::AsyncExecutionError { .. }),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  async fn stops_when_the_flow_is_cancelled() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ready": false })))
+      .mount(&server)
+      .await;
+
+    let node = loopback(
+      PollUntilNode::new("poll", "$.ready")
+        .unwrap()
+        .with_interval(Duration::from_secs(60)),
+    );
+    let token = FlowCancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(100)).await;
+      canceller.cancel();
+    });
+
+    let inputs = url_input(server.uri());
+    let err = NodeContext::new("run", "poll", None, Some(token))
+      .scope(node.execute(&inputs))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, AgentFlowError::TaskCancelled), "{err}");
+  }
+
+  #[test]
+  fn rejects_an_invalid_path() {
+    let err = PollUntilNode::new("poll", "$.status[").unwrap_err();
+    assert!(err.to_string().contains("Invalid JSONPath"), "{err}");
+  }
+}
@@ -0,0 +1,504 @@
+//! Schedule gate node - only lets a flow proceed inside configured windows.
+//!
+//! Windows are either `[days] HH:MM-HH:MM` ranges (`"Mon-Fri 09:00-17:00"`,
+//! `"22:00-06:00"` wraps past midnight) or five-field cron expressions
+//! (`"*/15 9-17 * * 1-5"`, where every matching minute is open). Outside
+//! every window the [`GatePolicy`] decides: wait until the next window
+//! opens, skip the node (so `run_if: "{{ nodes.gate.outputs.open }}"`
+//! downstream sees null), or fail.
+//!
+//! Inputs pass through unchanged; the node adds `open`, `waited_ms` and,
+//! after a wait, `next_open`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  node_context,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use chrono::{Datelike, FixedOffset, NaiveDateTime, Offset, Timelike, Utc};
+use serde_json::json;
+
+use crate::common::clock::{Clock, SystemClock};
+use crate::nodes::delay::cancellable_sleep;
+
+/// How far ahead [`ScheduleGateNode::next_open`] looks for a window.
+const LOOKAHEAD_MINUTES: i64 = 366 * 24 * 60;
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// What to do when the gate is closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatePolicy {
+  Wait,
+  Skip,
+  Error,
+}
+
+impl GatePolicy {
+  pub fn parse(value: &str) -> Option<Self> {
+    match value {
+      "wait" => Some(Self::Wait),
+      "skip" => Some(Self::Skip),
+      "error" => Some(Self::Error),
+      _ => None,
+    }
+  }
+}
+
+/// One open window, evaluated in the gate's local time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleWindow {
+  /// `start..end` in minutes since midnight on `days` (Monday first); an
+  /// `end` before `start` runs into the next day.
+  Daily {
+    days: [bool; 7],
+    start: u32,
+    end: u32,
+  },
+  Cron(CronSpec),
+}
+
+/// A parsed five-field cron expression: one bit per allowed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSpec {
+  minutes: u64,
+  hours: u64,
+  days_of_month: u64,
+  months: u64,
+  /// Sunday is bit 0.
+  days_of_week: u64,
+  /// Cron ORs day-of-month and day-of-week when both are restricted.
+  any_day: bool,
+}
+
+impl ScheduleWindow {
+  pub fn parse(spec: &str) -> Result<Self, String> {
+    let spec = spec.trim();
+    if spec.contains(':') {
+      parse_daily(spec)
+    } else {
+      parse_cron(spec).map(Self::Cron)
+    }
+  }
+
+  /// Whether the minute containing `at` is inside this window.
+  pub fn contains(&self, at: NaiveDateTime) -> bool {
+    match self {
+      Self::Daily { days, start, end } => {
+        let minute = at.hour() * 60 + at.minute();
+        let weekday = at.weekday().num_days_from_monday() as usize;
+        if start < end {
+          days[weekday] && (*start..*end).contains(&minute)
+        } else {
+          (days[weekday] && minute >= *start) || (days[(weekday + 6) % 7] && minute < *end)
+        }
+      }
+      Self::Cron(cron) => cron.matches(at),
+    }
+  }
+}
+
+impl CronSpec {
+  fn matches(&self, at: NaiveDateTime) -> bool {
+    let bit = |set: u64, value: u32| set & (1 << value) != 0;
+    let day_of_month = bit(self.days_of_month, at.day());
+    let day_of_week = bit(self.days_of_week, at.weekday().num_days_from_sunday());
+    let day = if self.any_day {
+      day_of_month && day_of_week
+    } else {
+      day_of_month || day_of_week
+    };
+    bit(self.minutes, at.minute())
+      && bit(self.hours, at.hour())
+      && bit(self.months, at.month())
+      && day
+  }
+}
+
+fn parse_daily(spec: &str) -> Result<ScheduleWindow, String> {
+  let (days, range) = match spec.rsplit_once(char::is_whitespace) {
+    Some((days, range)) => (parse_days(days.trim())?, range),
+    None => ([true; 7], spec),
+  };
+  let (start, end) = range
+    .split_once('-')
+    .ok_or_else(|| format!("window '{spec}' must be 'HH:MM-HH:MM'"))?;
+  let (start, end) = (parse_clock_time(start)?, parse_clock_time(end)?);
+  if start == end {
+    return Err(format!("window '{spec}' is empty"));
+  }
+  Ok(ScheduleWindow::Daily { days, start, end })
+}
+
+fn parse_days(spec: &str) -> Result<[bool; 7], String> {
+  let index = |name: &str| {
+    WEEKDAYS
+      .iter()
+      .position(|day| {
+        name
+          .get(..3)
+          .is_some_and(|prefix| day.eq_ignore_ascii_case(prefix))
+      })
+      .ok_or_else(|| format!("unknown weekday '{name}'"))
+  };
+  let mut days = [false; 7];
+  for part in spec.split(',') {
+    match part.trim().split_once('-') {
+      Some((from, to)) => {
+        let (from, to) = (index(from.trim())?, index(to.trim())?);
+        let mut day = from;
+        loop {
+          days[day] = true;
+          if day == to {
+            break;
+          }
+          day = (day + 1) % 7;
+        }
+      }
+      None => days[index(part.trim())?] = true,
+    }
+  }
+  Ok(days)
+}
+
+/// `HH:MM` as minutes since midnight; `24:00` is the end of the day.
+fn parse_clock_time(text: &str) -> Result<u32, String> {
+  let text = text.trim();
+  let invalid = || format!("'{text}' is not an HH:MM time");
+  let (hour, minute) = text.split_once(':').ok_or_else(invalid)?;
+  let hour: u32 = hour.parse().map_err(|_| invalid())?;
+  let minute: u32 = minute.parse().map_err(|_| invalid())?;
+  if minute >= 60 || hour > 24 || (hour == 24 && minute != 0) {
+    return Err(invalid());
+  }
+  Ok(hour * 60 + minute)
+}
+
+fn parse_cron(spec: &str) -> Result<CronSpec, String> {
+  let fields: Vec<_> = spec.split_whitespace().collect();
+  let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+    return Err(format!(
+      "'{spec}' is neither an 'HH:MM-HH:MM' window nor a five-field cron expression"
+    ));
+  };
+  let mut days_of_week = parse_cron_field(day_of_week, 0, 7)?;
+  // Both 0 and 7 mean Sunday.
+  if days_of_week & (1 << 7) != 0 {
+    days_of_week = (days_of_week | 1) & !(1 << 7);
+  }
+  Ok(CronSpec {
+    minutes: parse_cron_field(minute, 0, 59)?,
+    hours: parse_cron_field(hour, 0, 23)?,
+    days_of_month: parse_cron_field(day_of_month, 1, 31)?,
+    months: parse_cron_field(month, 1, 12)?,
+    days_of_week,
+    any_day: day_of_month == "*" || day_of_week == "*",
+  })
+}
+
+/// `*`, `N`, `N-M` and lists of them, each optionally stepped with `/S`.
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+  let invalid = || format!("invalid cron field '{field}' (allowed {min}-{max})");
+  let number = |text: &str| -> Result<u32, String> {
+    let value: u32 = text.parse().map_err(|_| invalid())?;
+    if (min..=max).contains(&value) {
+      Ok(value)
+    } else {
+      Err(invalid())
+    }
+  };
+  let mut set = 0u64;
+  for part in field.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+      None => (part, 1),
+    };
+    if step == 0 {
+      return Err(invalid());
+    }
+    let (from, to) = match range {
+      "*" => (min, max),
+      _ => match range.split_once('-') {
+        Some((from, to)) => (number(from)?, number(to)?),
+        None if step > 1 => (number(range)?, max),
+        None => (number(range)?, number(range)?),
+      },
+    };
+    if from > to {
+      return Err(invalid());
+    }
+    for value in (from..=to).step_by(step as usize) {
+      set |= 1 << value;
+    }
+  }
+  Ok(set)
+}
+
+/// `Z` / `UTC`, or an offset such as `+02:00` / `-05:30`.
+pub fn parse_utc_offset(text: &str) -> Result<FixedOffset, String> {
+  match text.trim() {
+    "Z" | "UTC" => Ok(Utc.fix()),
+    offset => offset
+      .parse()
+      .map_err(|_| format!("utc_offset '{offset}' must be Z, UTC or look like +02:00")),
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduleGateNode {
+  pub name: String,
+  pub windows: Vec<ScheduleWindow>,
+  pub policy: GatePolicy,
+  /// Under [`GatePolicy::Wait`], fail instead of waiting longer than this.
+  pub max_wait: Option<Duration>,
+  pub utc_offset: FixedOffset,
+  clock: Arc<dyn Clock>,
+}
+
+impl ScheduleGateNode {
+  pub fn new(name: &str, windows: Vec<ScheduleWindow>) -> Self {
+    Self {
+      name: name.to_string(),
+      windows,
+      policy: GatePolicy::Wait,
+      max_wait: None,
+      utc_offset: Utc.fix(),
+      clock: Arc::new(SystemClock),
+    }
+  }
+
+  pub fn with_policy(mut self, policy: GatePolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+    self.max_wait = Some(max_wait);
+    self
+  }
+
+  /// Evaluates windows in this fixed offset from UTC instead of UTC.
+  pub fn with_utc_offset(mut self, utc_offset: FixedOffset) -> Self {
+    self.utc_offset = utc_offset;
+    self
+  }
+
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  pub fn is_open(&self, at: NaiveDateTime) -> bool {
+    self.windows.iter().any(|window| window.contains(at))
+  }
+
+  /// The start of the first open minute strictly after `at`, if one
+  /// falls within the next year.
+  pub fn next_open(&self, at: NaiveDateTime) -> Option<NaiveDateTime> {
+    let minute = at.with_second(0)?.with_nanosecond(0)?;
+    (1..=LOOKAHEAD_MINUTES)
+      .map(|offset| minute + chrono::Duration::minutes(offset))
+      .find(|candidate| self.is_open(*candidate))
+  }
+
+  fn outputs(inputs: &AsyncNodeInputs, waited: Duration) -> AsyncNodeResult {
+    let mut outputs = inputs.clone();
+    outputs.insert("open".to_string(), FlowValue::Json(json!(true)));
+    outputs.insert(
+      "waited_ms".to_string(),
+      FlowValue::Json(json!(waited.as_millis() as u64)),
+    );
+    Ok(outputs)
+  }
+}
+
+#[async_trait]
+impl AsyncNode for ScheduleGateNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let now = self
+      .clock
+      .now()
+      .with_timezone(&self.utc_offset)
+      .naive_local();
+    if self.is_open(now) {
+      return Self::outputs(inputs, Duration::ZERO);
+    }
+
+    let next_open = self.next_open(now);
+    match self.policy {
+      GatePolicy::Skip => {
+        println!("⏭️  Schedule gate '{}' closed, skipping", self.name);
+        return Err(AgentFlowError::NodeSkipped);
+      }
+      GatePolicy::Error => {
+        return Err(AgentFlowError::NodeExecutionFailed {
+          message: format!(
+            "schedule gate '{}' is closed at {now}{}",
+            self.name,
+            next_open
+              .map(|at| format!(" (next window opens {at})"))
+              .unwrap_or_default()
+          ),
+        });
+      }
+      GatePolicy::Wait => {}
+    }
+
+    let next_open = next_open.ok_or_else(|| AgentFlowError::NodeExecutionFailed {
+      message: format!(
+        "schedule gate '{}' has no open window within a year of {now}",
+        self.name
+      ),
+    })?;
+    let wait = (next_open - now).to_std().unwrap_or_default();
+    if let Some(max_wait) = self.max_wait
+      && wait > max_wait
+    {
+      return Err(AgentFlowError::TimeoutExceeded {
+        duration_ms: max_wait.as_millis() as u64,
+      });
+    }
+    let next_open = next_open
+      .and_local_timezone(self.utc_offset)
+      .single()
+      .map(|at| at.with_timezone(&Utc).to_rfc3339())
+      .unwrap_or_else(|| next_open.to_string());
+
+    println!(
+      "⏳ Schedule gate '{}' closed, waiting {}s until {next_open}",
+      self.name,
+      wait.as_secs()
+    );
+    node_context::emit_progress(json!({
+      "event": "schedule_gate.waiting",
+      "next_open": next_open,
+      "wait_ms": wait.as_millis() as u64,
+    }));
+    cancellable_sleep(wait).await?;
+
+    let mut outputs = Self::outputs(inputs, wait)?;
+    outputs.insert("next_open".to_string(), FlowValue::Json(json!(next_open)));
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::{DateTime, TimeZone};
+
+  #[derive(Debug)]
+  struct FixedClock(DateTime<Utc>);
+
+  impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+      self.0
+    }
+  }
+
+  /// Wednesday 2026-10-14 at `hour:minute` UTC.
+  fn wednesday(hour: u32, minute: u32) -> Arc<dyn Clock> {
+    Arc::new(FixedClock(
+      Utc.with_ymd_and_hms(2026, 10, 14, hour, minute, 0).unwrap(),
+    ))
+  }
+
+  fn gate(windows: &[&str], clock: Arc<dyn Clock>) -> ScheduleGateNode {
+    let windows = windows
+      .iter()
+      .map(|spec| ScheduleWindow::parse(spec).unwrap())
+      .collect();
+    ScheduleGateNode::new("gate", windows).with_clock(clock)
+  }
+
+  fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    Utc
+      .with_ymd_and_hms(2026, 10, day, hour, minute, 0)
+      .unwrap()
+      .naive_utc()
+  }
+
+  #[test]
+  fn parses_day_windows_and_cron_expressions() {
+    let business = ScheduleWindow::parse("Mon-Fri 09:00-17:00").unwrap();
+    assert!(business.contains(at(14, 9, 0)));
+    assert!(!business.contains(at(14, 17, 0)));
+    assert!(!business.contains(at(17, 12, 0)), "Saturday");
+
+    let overnight = ScheduleWindow::parse("Fri 22:00-06:00").unwrap();
+    assert!(overnight.contains(at(16, 23, 0)));
+    assert!(overnight.contains(at(17, 5, 59)), "Saturday morning");
+    assert!(!overnight.contains(at(15, 23, 0)), "Thursday");
+
+    let quarter_hours = ScheduleWindow::parse("*/15 9-17 * * 1-5").unwrap();
+    assert!(quarter_hours.contains(at(14, 9, 30)));
+    assert!(!quarter_hours.contains(at(14, 9, 31)));
+    assert!(!quarter_hours.contains(at(18, 9, 30)), "Sunday");
+
+    let first_or_sunday = ScheduleWindow::parse("0 0 1 * 7").unwrap();
+    assert!(first_or_sunday.contains(at(1, 0, 0)));
+    assert!(first_or_sunday.contains(at(18, 0, 0)));
+
+    for bad in [
+      "Funday 09:00-10:00",
+      "09:00-09:00",
+      "25:00-26:00",
+      "* * *",
+      "61 * * * *",
+    ] {
+      assert!(ScheduleWindow::parse(bad).is_err(), "{bad}");
+    }
+  }
+
+  #[tokio::test]
+  async fn skip_policy_skips_outside_the_window() {
+    let closed = gate(&["Mon-Fri 09:00-17:00"], wednesday(20, 0)).with_policy(GatePolicy::Skip);
+    let err = closed.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(matches!(err, AgentFlowError::NodeSkipped), "{err}");
+
+    let open = gate(&["Mon-Fri 09:00-17:00"], wednesday(10, 0)).with_policy(GatePolicy::Skip);
+    let outputs = open.execute(&AsyncNodeInputs::new()).await.unwrap();
+    assert_eq!(outputs["open"], FlowValue::Json(json!(true)));
+    assert_eq!(outputs["waited_ms"], FlowValue::Json(json!(0)));
+
+    let failing = gate(&["09:00-17:00"], wednesday(20, 0)).with_policy(GatePolicy::Error);
+    let err = failing.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(err.to_string().contains("next window opens"), "{err}");
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn wait_policy_sleeps_until_the_next_window() {
+    let node = gate(&["Mon-Fri 09:00-17:00"], wednesday(8, 30));
+    let started = tokio::time::Instant::now();
+    let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(30 * 60));
+    assert_eq!(outputs["waited_ms"], FlowValue::Json(json!(30 * 60 * 1000)));
+    assert_eq!(
+      outputs["next_open"],
+      FlowValue::Json(json!("2026-10-14T09:00:00+00:00"))
+    );
+
+    // 09:00 at UTC+02:00 is 07:00 UTC.
+    let offset = gate(&["09:00-17:00"], wednesday(6, 0))
+      .with_utc_offset(FixedOffset::east_opt(2 * 3600).unwrap());
+    let outputs = offset.execute(&AsyncNodeInputs::new()).await.unwrap();
+    assert_eq!(
+      outputs["next_open"],
+      FlowValue::Json(json!("2026-10-14T07:00:00+00:00"))
+    );
+
+    let bounded =
+      gate(&["Mon-Fri 09:00-17:00"], wednesday(20, 0)).with_max_wait(Duration::from_secs(3600));
+    let err = bounded.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::TimeoutExceeded { .. }),
+      "{err}"
+    );
+  }
+}
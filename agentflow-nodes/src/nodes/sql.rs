@@ -0,0 +1,772 @@
+//! SQL node - parameterized queries against SQLite or Postgres (`sql`
+//! feature).
+//!
+//! `connection` is a `postgres://` / `postgresql://` URL, a `sqlite:` URL
+//! (`sqlite::memory:`, `sqlite:name?mode=memory`, `sqlite://data/app.db`)
+//! or a bare SQLite file path; `${VAR}` references are expanded from the
+//! environment so passwords stay out of workflow files (or the whole string
+//! can come from a `$secret` reference, see [`agentflow_core::secret`]). It
+//! is held as a [`SecretValue`] and masked in persisted run outputs. Pools are shared
+//! process-wide per connection string, so in-memory SQLite databases live
+//! as long as the process and nodes naming the same one see the same data.
+//!
+//! Values only reach the database as bound parameters: every `:name` in
+//! `sql` (outside string literals, quoted identifiers, comments and `::`
+//! casts) is bound from the `params` input object's entry of that name,
+//! else from the node input of that name. JSON nulls, booleans, integers,
+//! floats and strings bind as such; arrays and objects bind as JSON. On
+//! Postgres a null parameter is written as a `NULL` literal instead, so its
+//! type is inferred from context rather than forced to `text`.
+//!
+//! - `query` mode outputs `rows` (array of `{column: value}` objects, at
+//!   most `max_rows`), `row_count`, `columns` and `truncated`;
+//! - `execute` mode outputs `affected_rows`.
+//!
+//! The whole call (pool checkout plus statement) is bounded by `timeout`,
+//! which is also set on the connection before each statement -
+//! `statement_timeout` on Postgres, `busy_timeout` on SQLite - so the
+//! server stops work the client has given up on.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  secret::SecretValue,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use futures::{Stream, TryStreamExt};
+use serde_json::{Map, Value, json};
+use sqlx::postgres::{PgPool, PgPoolOptions, PgRow, PgValueRef, Postgres};
+use sqlx::sqlite::{
+  Sqlite, SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow, SqliteValueRef,
+};
+use sqlx::{Column, Database, Decode, Encode, Row, Type, TypeInfo, ValueRef};
+
+use crate::common::utils::{expand_env_vars, flow_value_to_string};
+
+pub const DEFAULT_SQL_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_MAX_ROWS: usize = 1_000;
+const MAX_POOL_CONNECTIONS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlMode {
+  /// Fetch rows.
+  Query,
+  /// Run a statement and report the affected row count.
+  Execute,
+}
+
+impl SqlMode {
+  pub fn parse(mode: &str) -> Result<Self, AgentFlowError> {
+    match mode.trim().to_ascii_lowercase().as_str() {
+      "query" => Ok(Self::Query),
+      "execute" => Ok(Self::Execute),
+      other => Err(AgentFlowError::ConfigurationError {
+        message: format!("Unknown SQL mode '{}' (expected query or execute)", other),
+      }),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SqlNode {
+  pub name: String,
+  pub connection: SecretValue,
+  pub sql: String,
+  pub mode: SqlMode,
+  pub timeout: Duration,
+  pub max_rows: usize,
+}
+
+#[derive(Clone)]
+enum SqlPool {
+  Sqlite(SqlitePool),
+  Postgres(PgPool),
+}
+
+/// Positional placeholder syntax of the target database.
+#[derive(Clone, Copy)]
+enum Placeholder {
+  /// `?1`, `?2`, ...
+  Sqlite,
+  /// `$1`, `$2`, ...
+  Postgres,
+}
+
+/// Rows gathered in `query` mode.
+struct FetchedRows {
+  rows: Vec<Value>,
+  columns: Vec<String>,
+  truncated: bool,
+}
+
+enum SqlOutcome {
+  Rows(FetchedRows),
+  Affected(u64),
+}
+
+static POOLS: OnceLock<Mutex<HashMap<String, SqlPool>>> = OnceLock::new();
+
+fn pools() -> &'static Mutex<HashMap<String, SqlPool>> {
+  POOLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+impl SqlNode {
+  /// `connection` usually embeds credentials, so it is held as a
+  /// [`SecretValue`]; a `&str` converts.
+  pub fn new(name: &str, connection: impl Into<SecretValue>, sql: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      connection: connection.into(),
+      sql: sql.to_string(),
+      mode: SqlMode::Query,
+      timeout: DEFAULT_SQL_TIMEOUT,
+      max_rows: DEFAULT_MAX_ROWS,
+    }
+  }
+
+  pub fn with_mode(mut self, mode: SqlMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Rows beyond the cap are dropped and `truncated` is set.
+  pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+    self.max_rows = max_rows;
+    self
+  }
+
+  fn error(&self, err: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::AsyncExecutionError {
+      message: format!("SQL node '{}': {}", self.name, err),
+    }
+  }
+
+  async fn pool(&self, connection: &str) -> Result<SqlPool, AgentFlowError> {
+    let cached = pools()
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .get(connection)
+      .cloned();
+    if let Some(pool) = cached {
+      return Ok(pool);
+    }
+
+    let pool = if connection.starts_with("postgres://") || connection.starts_with("postgresql://") {
+      let pool = PgPoolOptions::new()
+        .max_connections(MAX_POOL_CONNECTIONS)
+        .connect(connection)
+        .await
+        .map_err(|e| self.error(format!("failed to connect to Postgres: {}", e)))?;
+      SqlPool::Postgres(pool)
+    } else {
+      let options = if connection.starts_with("sqlite:") {
+        SqliteConnectOptions::from_str(connection)
+          .map_err(|e| self.error(format!("invalid SQLite connection string: {}", e)))?
+      } else {
+        SqliteConnectOptions::new().filename(connection)
+      }
+      .create_if_missing(true);
+      // An in-memory database disappears with its last connection, so keep
+      // exactly one open for the life of the process.
+      let in_memory = connection.contains(":memory:") || connection.contains("mode=memory");
+      let pool_options = if in_memory {
+        SqlitePoolOptions::new()
+          .max_connections(1)
+          .idle_timeout(None)
+          .max_lifetime(None)
+      } else {
+        SqlitePoolOptions::new().max_connections(MAX_POOL_CONNECTIONS)
+      };
+      let pool = pool_options
+        .connect_with(options)
+        .await
+        .map_err(|e| self.error(format!("failed to open SQLite database: {}", e)))?;
+      SqlPool::Sqlite(pool)
+    };
+
+    Ok(
+      pools()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(connection.to_string())
+        .or_insert(pool)
+        .clone(),
+    )
+  }
+
+  /// The timeout in whole milliseconds, as the session settings take it.
+  fn timeout_ms(&self) -> u64 {
+    u64::try_from(self.timeout.as_millis())
+      .unwrap_or(u64::MAX)
+      .clamp(1, i32::MAX as u64)
+  }
+
+  fn bound_values(
+    &self,
+    names: &[String],
+    inputs: &AsyncNodeInputs,
+  ) -> Result<Vec<Value>, AgentFlowError> {
+    let params = match inputs.get("params") {
+      Some(FlowValue::Json(Value::Object(params))) => Some(params),
+      _ => None,
+    };
+    names
+      .iter()
+      .map(
+        |name| match (params.and_then(|params| params.get(name)), inputs.get(name)) {
+          (Some(value), _) | (None, Some(FlowValue::Json(value))) => Ok(value.clone()),
+          (None, Some(other)) => Ok(Value::String(flow_value_to_string(other))),
+          (None, None) => Err(AgentFlowError::NodeInputError {
+            message: format!(
+              "SQL node '{}': no input bound to parameter ':{}'",
+              self.name, name
+            ),
+          }),
+        },
+      )
+      .collect()
+  }
+
+  async fn run(
+    &self,
+    pool: SqlPool,
+    inputs: &AsyncNodeInputs,
+  ) -> Result<SqlOutcome, AgentFlowError> {
+    match pool {
+      SqlPool::Sqlite(pool) => {
+        let (sql, names) = rewrite_named_params(&self.sql, Placeholder::Sqlite, &[]);
+        let values = self.bound_values(&names, inputs)?;
+        let mut connection = pool.acquire().await.map_err(|e| self.error(e))?;
+        sqlx::query(&format!("PRAGMA busy_timeout = {}", self.timeout_ms()))
+          .execute(&mut *connection)
+          .await
+          .map_err(|e| self.error(e))?;
+        let query = bind_values(sqlx::query::<Sqlite>(&sql), &values);
+        match self.mode {
+          SqlMode::Query => {
+            self
+              .collect_rows(query.fetch(&mut *connection), sqlite_row)
+              .await
+          }
+          SqlMode::Execute => query
+            .execute(&mut *connection)
+            .await
+            .map(|done| SqlOutcome::Affected(done.rows_affected()))
+            .map_err(|e| self.error(e)),
+        }
+      }
+      SqlPool::Postgres(pool) => {
+        // A bound `None::<String>` would be typed `text` and fail against
+        // integer or timestamp columns, so nulls become `NULL` literals.
+        let (_, names) = rewrite_named_params(&self.sql, Placeholder::Postgres, &[]);
+        let nulls: Vec<String> = names
+          .iter()
+          .zip(self.bound_values(&names, inputs)?)
+          .filter(|(_, value)| value.is_null())
+          .map(|(name, _)| name.clone())
+          .collect();
+        let (sql, names) = rewrite_named_params(&self.sql, Placeholder::Postgres, &nulls);
+        let values = self.bound_values(&names, inputs)?;
+        let mut connection = pool.acquire().await.map_err(|e| self.error(e))?;
+        sqlx::query(&format!("SET statement_timeout = {}", self.timeout_ms()))
+          .execute(&mut *connection)
+          .await
+          .map_err(|e| self.error(e))?;
+        let query = bind_values(sqlx::query::<Postgres>(&sql), &values);
+        match self.mode {
+          SqlMode::Query => {
+            self
+              .collect_rows(query.fetch(&mut *connection), postgres_row)
+              .await
+          }
+          SqlMode::Execute => query
+            .execute(&mut *connection)
+            .await
+            .map(|done| SqlOutcome::Affected(done.rows_affected()))
+            .map_err(|e| self.error(e)),
+        }
+      }
+    }
+  }
+
+  async fn collect_rows<R: Row>(
+    &self,
+    mut stream: impl Stream<Item = Result<R, sqlx::Error>> + Unpin,
+    convert: fn(&R) -> Result<Map<String, Value>, sqlx::Error>,
+  ) -> Result<SqlOutcome, AgentFlowError> {
+    let mut fetched = FetchedRows {
+      rows: Vec::new(),
+      columns: Vec::new(),
+      truncated: false,
+    };
+    while let Some(row) = stream.try_next().await.map_err(|e| self.error(e))? {
+      if fetched.rows.len() == self.max_rows {
+        fetched.truncated = true;
+        break;
+      }
+      if fetched.columns.is_empty() {
+        fetched.columns = row
+          .columns()
+          .iter()
+          .map(|column| column.name().to_string())
+          .collect();
+      }
+      fetched
+        .rows
+        .push(Value::Object(convert(&row).map_err(|e| self.error(e))?));
+    }
+    Ok(SqlOutcome::Rows(fetched))
+  }
+}
+
+#[async_trait]
+impl AsyncNode for SqlNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    // Values pulled in by `${VAR}` are masked like the literal itself.
+    let connection = SecretValue::new(expand_env_vars(self.connection.expose_secret())?);
+    let work = async {
+      let pool = self.pool(connection.expose_secret()).await?;
+      self.run(pool, inputs).await
+    };
+    let outcome = tokio::time::timeout(self.timeout, work)
+      .await
+      .map_err(|_| AgentFlowError::TimeoutExceeded {
+        duration_ms: self.timeout.as_millis() as u64,
+      })??;
+
+    let mut outputs = HashMap::new();
+    match outcome {
+      SqlOutcome::Rows(fetched) => {
+        println!(
+          "🗄️  SQL node '{}' fetched {} row(s){}",
+          self.name,
+          fetched.rows.len(),
+          if fetched.truncated {
+            " (truncated)"
+          } else {
+            ""
+          }
+        );
+        outputs.insert(
+          "row_count".to_string(),
+          FlowValue::Json(json!(fetched.rows.len())),
+        );
+        outputs.insert(
+          "rows".to_string(),
+          FlowValue::Json(Value::Array(fetched.rows)),
+        );
+        outputs.insert(
+          "columns".to_string(),
+          FlowValue::Json(json!(fetched.columns)),
+        );
+        outputs.insert(
+          "truncated".to_string(),
+          FlowValue::Json(json!(fetched.truncated)),
+        );
+      }
+      SqlOutcome::Affected(count) => {
+        println!("🗄️  SQL node '{}' affected {} row(s)", self.name, count);
+        outputs.insert("affected_rows".to_string(), FlowValue::Json(json!(count)));
+      }
+    }
+    Ok(outputs)
+  }
+}
+
+fn bind_values<'q, DB>(
+  mut query: sqlx::query::Query<'q, DB, <DB as Database>::Arguments<'q>>,
+  values: &[Value],
+) -> sqlx::query::Query<'q, DB, <DB as Database>::Arguments<'q>>
+where
+  DB: Database,
+  Option<String>: Encode<'q, DB> + Type<DB>,
+  bool: Encode<'q, DB> + Type<DB>,
+  i64: Encode<'q, DB> + Type<DB>,
+  f64: Encode<'q, DB> + Type<DB>,
+  String: Encode<'q, DB> + Type<DB>,
+  sqlx::types::Json<Value>: Encode<'q, DB> + Type<DB>,
+{
+  for value in values {
+    query = match value {
+      Value::Null => query.bind(None::<String>),
+      Value::Bool(flag) => query.bind(*flag),
+      Value::Number(number) => match number.as_i64() {
+        Some(int) => query.bind(int),
+        None => query.bind(number.as_f64().unwrap_or(f64::NAN)),
+      },
+      Value::String(text) => query.bind(text.clone()),
+      structured => query.bind(sqlx::types::Json(structured.clone())),
+    };
+  }
+  query
+}
+
+fn decode<'r, T, DB>(raw: <DB as Database>::ValueRef<'r>) -> Result<T, sqlx::Error>
+where
+  DB: Database,
+  T: Decode<'r, DB>,
+{
+  T::decode(raw).map_err(sqlx::Error::Decode)
+}
+
+fn float(value: f64) -> Value {
+  serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn sqlite_row(row: &SqliteRow) -> Result<Map<String, Value>, sqlx::Error> {
+  let mut object = Map::new();
+  for column in row.columns() {
+    let raw: SqliteValueRef = row.try_get_raw(column.ordinal())?;
+    let value = if raw.is_null() {
+      Value::Null
+    } else {
+      // SQLite is dynamically typed: decode by the value's storage class,
+      // using the declared column type only to recover booleans.
+      let storage = raw.type_info().name().to_string();
+      match storage.as_str() {
+        "INTEGER" if column.type_info().name() == "BOOLEAN" => {
+          json!(decode::<i64, Sqlite>(raw)? != 0)
+        }
+        "INTEGER" => json!(decode::<i64, Sqlite>(raw)?),
+        "REAL" => float(decode::<f64, Sqlite>(raw)?),
+        "BLOB" => json!(STANDARD.encode(decode::<Vec<u8>, Sqlite>(raw)?)),
+        _ => json!(decode::<String, Sqlite>(raw)?),
+      }
+    };
+    object.insert(column.name().to_string(), value);
+  }
+  Ok(object)
+}
+
+fn postgres_row(row: &PgRow) -> Result<Map<String, Value>, sqlx::Error> {
+  let mut object = Map::new();
+  for column in row.columns() {
+    let raw: PgValueRef = row.try_get_raw(column.ordinal())?;
+    let value = if raw.is_null() {
+      Value::Null
+    } else {
+      let type_name = raw.type_info().name().to_string();
+      match type_name.as_str() {
+        "BOOL" => json!(decode::<bool, Postgres>(raw)?),
+        "INT2" => json!(decode::<i16, Postgres>(raw)?),
+        "INT4" => json!(decode::<i32, Postgres>(raw)?),
+        "INT8" => json!(decode::<i64, Postgres>(raw)?),
+        "FLOAT4" => float(f64::from(decode::<f32, Postgres>(raw)?)),
+        "FLOAT8" => float(decode::<f64, Postgres>(raw)?),
+        "JSON" | "JSONB" => decode::<sqlx::types::Json<Value>, Postgres>(raw)?.0,
+        "UUID" => json!(decode::<uuid::Uuid, Postgres>(raw)?.to_string()),
+        "TIMESTAMPTZ" => {
+          json!(decode::<chrono::DateTime<chrono::Utc>, Postgres>(raw)?.to_rfc3339())
+        }
+        "TIMESTAMP" => json!(decode::<chrono::NaiveDateTime, Postgres>(raw)?.to_string()),
+        "DATE" => json!(decode::<chrono::NaiveDate, Postgres>(raw)?.to_string()),
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" | "CHAR" | "CITEXT" => {
+          json!(decode::<String, Postgres>(raw)?)
+        }
+        "BYTEA" => json!(STANDARD.encode(decode::<Vec<u8>, Postgres>(raw)?)),
+        other => {
+          return Err(sqlx::Error::Decode(
+            format!(
+              "column '{}' has unsupported type {}; cast it in the query (e.g. `{}::text`)",
+              column.name(),
+              other,
+              column.name()
+            )
+            .into(),
+          ));
+        }
+      }
+    };
+    object.insert(column.name().to_string(), value);
+  }
+  Ok(object)
+}
+
+/// Rewrites `:name` parameters to positional placeholders, returning the
+/// new SQL and the parameter names in position order (a repeated name
+/// reuses its position). Names in `nulls` are written as `NULL` and take no
+/// position. String literals, quoted identifiers, comments and `::` casts
+/// are copied verbatim.
+fn rewrite_named_params(sql: &str, style: Placeholder, nulls: &[String]) -> (String, Vec<String>) {
+  let chars: Vec<char> = sql.chars().collect();
+  let mut rewritten = String::with_capacity(sql.len());
+  let mut names: Vec<String> = Vec::new();
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i];
+    let next = chars.get(i + 1).copied();
+    let copy_until = |start: usize, end: &str| -> usize {
+      let end: Vec<char> = end.chars().collect();
+      let mut j = start;
+      while j < chars.len() {
+        if chars[j..].starts_with(&end) {
+          return j + end.len();
+        }
+        j += 1;
+      }
+      chars.len()
+    };
+    let skip_to = match (c, next) {
+      ('\'', _) => Some(copy_until(i + 1, "'")),
+      ('"', _) => Some(copy_until(i + 1, "\"")),
+      ('-', Some('-')) => Some(copy_until(i + 2, "\n")),
+      ('/', Some('*')) => Some(copy_until(i + 2, "*/")),
+      (':', Some(':')) => Some(i + 2),
+      _ => None,
+    };
+    if let Some(end) = skip_to {
+      rewritten.extend(&chars[i..end]);
+      i = end;
+      continue;
+    }
+    if c == ':' && next.is_some_and(|n| n.is_ascii_alphabetic() || n == '_') {
+      let start = i + 1;
+      let mut end = start;
+      while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+        end += 1;
+      }
+      let name: String = chars[start..end].iter().collect();
+      i = end;
+      if nulls.contains(&name) {
+        rewritten.push_str("NULL");
+        continue;
+      }
+      let position = match names.iter().position(|known| *known == name) {
+        Some(index) => index + 1,
+        None => {
+          names.push(name);
+          names.len()
+        }
+      };
+      match style {
+        Placeholder::Sqlite => rewritten.push_str(&format!("?{position}")),
+        Placeholder::Postgres => rewritten.push_str(&format!("${position}")),
+      }
+      continue;
+    }
+    rewritten.push(c);
+    i += 1;
+  }
+  (rewritten, names)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn inputs(pairs: &[(&str, Value)]) -> AsyncNodeInputs {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), FlowValue::Json(value.clone())))
+      .collect()
+  }
+
+  fn output<'a>(outputs: &'a HashMap<String, FlowValue>, key: &str) -> &'a Value {
+    match outputs.get(key) {
+      Some(FlowValue::Json(value)) => value,
+      other => panic!("missing {key}: {other:?}"),
+    }
+  }
+
+  async fn execute(connection: &str, sql: &str, pairs: &[(&str, Value)]) -> u64 {
+    let outputs = SqlNode::new("setup", connection, sql)
+      .with_mode(SqlMode::Execute)
+      .execute(&inputs(pairs))
+      .await
+      .unwrap();
+    output(&outputs, "affected_rows").as_u64().unwrap()
+  }
+
+  #[tokio::test]
+  async fn binds_named_parameters_and_maps_column_types() {
+    let db = "sqlite:sql-node-binding?mode=memory";
+    execute(
+      db,
+      "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price REAL, note TEXT, active BOOLEAN)",
+      &[],
+    )
+    .await;
+    let insert =
+      "INSERT INTO items (name, price, note, active) VALUES (:name, :price, :note, :active)";
+    for (name, price, note, active) in [
+      ("widget", json!(2.5), json!("it's: fine"), true),
+      ("gadget", json!(10), Value::Null, false),
+    ] {
+      let affected = execute(
+        db,
+        insert,
+        &[
+          ("name", json!(name)),
+          ("price", price),
+          ("note", note),
+          ("active", json!(active)),
+        ],
+      )
+      .await;
+      assert_eq!(affected, 1);
+    }
+
+    // The literal ':name' and the cast-like '::' are not parameters.
+    let node = SqlNode::new(
+      "query",
+      db,
+      "SELECT id, name, price, note, active, ':name' AS literal FROM items \
+       WHERE price >= :min_price OR name = :name ORDER BY id",
+    );
+    let outputs = node
+      .execute(&inputs(&[
+        ("min_price", json!(5)),
+        ("name", json!("widget")),
+      ]))
+      .await
+      .unwrap();
+
+    assert_eq!(output(&outputs, "row_count"), &json!(2));
+    assert_eq!(output(&outputs, "truncated"), &json!(false));
+    assert_eq!(
+      output(&outputs, "columns"),
+      &json!(["id", "name", "price", "note", "active", "literal"])
+    );
+    assert_eq!(
+      output(&outputs, "rows"),
+      &json!([
+        { "id": 1, "name": "widget", "price": 2.5, "note": "it's: fine", "active": true, "literal": ":name" },
+        { "id": 2, "name": "gadget", "price": 10.0, "note": null, "active": false, "literal": ":name" },
+      ])
+    );
+  }
+
+  #[tokio::test]
+  async fn caps_rows_at_max_rows() {
+    let node = SqlNode::new(
+      "numbers",
+      "sqlite::memory:",
+      "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < :count) \
+       SELECT x FROM n",
+    )
+    .with_max_rows(5);
+    let outputs = node
+      .execute(&inputs(&[("count", json!(50))]))
+      .await
+      .unwrap();
+
+    assert_eq!(output(&outputs, "row_count"), &json!(5));
+    assert_eq!(output(&outputs, "truncated"), &json!(true));
+    assert_eq!(output(&outputs, "rows")[4], json!({ "x": 5 }));
+  }
+
+  #[tokio::test]
+  async fn missing_parameters_and_environment_variables_are_reported() {
+    let err = SqlNode::new("q", "sqlite::memory:", "SELECT :absent AS value")
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(&err, AgentFlowError::NodeInputError { message } if message.contains("':absent'")),
+      "{err}"
+    );
+
+    let err = SqlNode::new(
+      "q",
+      "postgres://app:${AGENTFLOW_SQL_TEST_UNSET}@db/app",
+      "SELECT 1",
+    )
+    .execute(&AsyncNodeInputs::new())
+    .await
+    .unwrap_err();
+    assert!(
+      matches!(&err, AgentFlowError::ConfigurationError { message }
+        if message.contains("AGENTFLOW_SQL_TEST_UNSET")),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  #[ignore = "needs a Postgres server: AGENTFLOW_TEST_POSTGRES_URL=postgres://... cargo test -p agentflow-nodes --features sql -- --ignored"]
+  async fn postgres_binds_typed_nulls_and_enforces_the_statement_timeout() {
+    let db = std::env::var("AGENTFLOW_TEST_POSTGRES_URL").unwrap();
+    execute(&db, "DROP TABLE IF EXISTS agentflow_sql_node_test", &[]).await;
+    execute(
+      &db,
+      "CREATE TABLE agentflow_sql_node_test (id SERIAL PRIMARY KEY, score INTEGER, seen TIMESTAMPTZ)",
+      &[],
+    )
+    .await;
+    let insert = "INSERT INTO agentflow_sql_node_test (score, seen) VALUES (:score, :seen)";
+    assert_eq!(
+      execute(
+        &db,
+        insert,
+        &[("score", Value::Null), ("seen", Value::Null)]
+      )
+      .await,
+      1
+    );
+    assert_eq!(
+      execute(&db, insert, &[("score", json!(7)), ("seen", Value::Null)]).await,
+      1
+    );
+
+    let outputs = SqlNode::new(
+      "q",
+      db.as_str(),
+      "SELECT id, score FROM agentflow_sql_node_test WHERE seen IS NULL ORDER BY id",
+    )
+    .execute(&AsyncNodeInputs::new())
+    .await
+    .unwrap();
+    assert_eq!(
+      output(&outputs, "rows"),
+      &json!([{ "id": 1, "score": null }, { "id": 2, "score": 7 }])
+    );
+
+    // Server and client deadlines race; either way the backend stops too.
+    let err = SqlNode::new("slow", db.as_str(), "SELECT pg_sleep(5)::text AS slept")
+      .with_timeout(Duration::from_millis(200))
+      .execute(&AsyncNodeInputs::new())
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(
+        err,
+        AgentFlowError::AsyncExecutionError { .. } | AgentFlowError::TimeoutExceeded { .. }
+      ),
+      "{err}"
+    );
+    execute(&db, "DROP TABLE agentflow_sql_node_test", &[]).await;
+  }
+
+  #[test]
+  fn named_parameters_become_positional_placeholders() {
+    let (sql, names) = rewrite_named_params(
+      "SELECT a::text, ':skip', \"col:x\" FROM t -- :comment\nWHERE a = :a AND b = :b OR a > :a",
+      Placeholder::Postgres,
+      &[],
+    );
+    assert_eq!(
+      sql,
+      "SELECT a::text, ':skip', \"col:x\" FROM t -- :comment\nWHERE a = $1 AND b = $2 OR a > $1"
+    );
+    assert_eq!(names, vec!["a", "b"]);
+
+    let (sql, names) = rewrite_named_params(
+      "UPDATE t SET a = :a, b = :b WHERE c = :c",
+      Placeholder::Postgres,
+      &["b".to_string()],
+    );
+    assert_eq!(sql, "UPDATE t SET a = $1, b = NULL WHERE c = $2");
+    assert_eq!(names, vec!["a", "c"]);
+  }
+}
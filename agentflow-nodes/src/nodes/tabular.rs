@@ -0,0 +1,773 @@
+//! Tabular nodes - CSV / XLSX ingestion and CSV output.
+//!
+//! [`TabularReadNode`] reads the `content` input (inline CSV) or the file
+//! named by the `path` input. The format comes from `with_format`, or else
+//! from the path's extension. XLSX workbooks need the `xlsx` feature. Each
+//! row becomes a JSON object keyed by the header row, or by `column_1`,
+//! `column_2`, ... without one. Untyped CSV cells stay strings; XLSX cells
+//! keep their native type.
+//!
+//! - Column type hints (`int` / `float` / `bool` / `date` / `string`)
+//!   coerce cells. An empty cell in a typed column becomes `null`.
+//! - `filter` is a `run_if`-style expression evaluated per row, with the
+//!   row's columns as inputs (`amount > 100 && region == "EU"`).
+//! - `max_rows` caps the rows emitted after filtering.
+//!
+//! Malformed rows (wrong field count, undecodable bytes, failed coercions,
+//! a `filter` that cannot be evaluated on them) are collected into
+//! `skipped_rows` as `{line, error}` instead of failing the file, unless
+//! the node is `strict`. Repeated header names get a `_2`, `_3`, ...
+//! suffix so no column shadows another. Outputs `rows`, `columns`,
+//! `row_count`, `skipped_rows` and `truncated`.
+//!
+//! [`TabularWriteNode`] writes the `rows` input (an array of objects) as
+//! CSV to the `path` input. Without a path it outputs the CSV as `content`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  expr,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate};
+use serde_json::{Map, Value, json};
+
+use crate::common::utils::flow_value_to_string;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabularFormat {
+  Csv,
+  Xlsx,
+}
+
+impl TabularFormat {
+  pub fn parse(value: &str) -> Result<Self, String> {
+    match value {
+      "csv" => Ok(Self::Csv),
+      "xlsx" => Ok(Self::Xlsx),
+      other => Err(format!("unknown format '{other}' (expected csv or xlsx)")),
+    }
+  }
+
+  fn from_path(path: &Path) -> Self {
+    let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+      Some("xlsx" | "xlsm" | "xls" | "ods") => Self::Xlsx,
+      _ => Self::Csv,
+    }
+  }
+}
+
+/// A column type hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+  String,
+  Int,
+  Float,
+  Bool,
+  /// ISO `YYYY-MM-DD`, or an RFC 3339 timestamp.
+  Date,
+}
+
+impl ColumnType {
+  pub fn parse(value: &str) -> Result<Self, String> {
+    match value {
+      "string" => Ok(Self::String),
+      "int" => Ok(Self::Int),
+      "float" => Ok(Self::Float),
+      "bool" => Ok(Self::Bool),
+      "date" => Ok(Self::Date),
+      other => Err(format!(
+        "unknown column type '{other}' (expected string, int, float, bool or date)"
+      )),
+    }
+  }
+
+  fn name(self) -> &'static str {
+    match self {
+      Self::String => "string",
+      Self::Int => "int",
+      Self::Float => "float",
+      Self::Bool => "bool",
+      Self::Date => "date",
+    }
+  }
+
+  fn coerce(self, value: Value) -> Result<Value, String> {
+    let text = match &value {
+      Value::Null => return Ok(Value::Null),
+      Value::String(text) if text.trim().is_empty() && self != Self::String => {
+        return Ok(Value::Null);
+      }
+      Value::String(text) => text.trim().to_string(),
+      other => other.to_string(),
+    };
+    let mismatch = || format!("'{text}' is not a valid {}", self.name());
+    match self {
+      Self::String if value.is_string() => Ok(value),
+      Self::String => Ok(Value::String(text)),
+      Self::Int => match value.as_f64() {
+        Some(number) if number.fract() == 0.0 => Ok(json!(number as i64)),
+        _ => text.parse::<i64>().map(Value::from).map_err(|_| mismatch()),
+      },
+      Self::Float => value
+        .as_f64()
+        .or_else(|| text.parse::<f64>().ok())
+        .filter(|number| number.is_finite())
+        .map(Value::from)
+        .ok_or_else(mismatch),
+      Self::Bool => match text.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" => Ok(Value::Bool(true)),
+        "false" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+        _ => Err(mismatch()),
+      },
+      Self::Date => {
+        if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+          Ok(json!(date.format("%Y-%m-%d").to_string()))
+        } else if let Ok(timestamp) = DateTime::parse_from_rfc3339(&text) {
+          Ok(json!(timestamp.to_rfc3339()))
+        } else {
+          Err(mismatch())
+        }
+      }
+    }
+  }
+}
+
+/// One source row: its 1-based line (or sheet row) and its cells.
+struct RawRecord {
+  line: u64,
+  cells: Result<Vec<Value>, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TabularReadNode {
+  pub name: String,
+  /// `None` infers the format from the `path` extension.
+  pub format: Option<TabularFormat>,
+  pub delimiter: u8,
+  pub has_headers: bool,
+  pub column_types: BTreeMap<String, ColumnType>,
+  pub max_rows: Option<usize>,
+  pub filter: Option<String>,
+  /// XLSX sheet to read; the first sheet by default.
+  pub sheet: Option<String>,
+  /// Fail on the first malformed row instead of skipping it.
+  pub strict: bool,
+}
+
+impl TabularReadNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      format: None,
+      delimiter: b',',
+      has_headers: true,
+      column_types: BTreeMap::new(),
+      max_rows: None,
+      filter: None,
+      sheet: None,
+      strict: false,
+    }
+  }
+
+  pub fn with_format(mut self, format: TabularFormat) -> Self {
+    self.format = Some(format);
+    self
+  }
+
+  pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+    self.delimiter = delimiter;
+    self
+  }
+
+  pub fn with_headers(mut self, has_headers: bool) -> Self {
+    self.has_headers = has_headers;
+    self
+  }
+
+  pub fn with_column_type(mut self, column: &str, column_type: ColumnType) -> Self {
+    self.column_types.insert(column.to_string(), column_type);
+    self
+  }
+
+  pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+    self.max_rows = Some(max_rows);
+    self
+  }
+
+  /// Keeps only rows for which `filter` is truthy; rejects an expression
+  /// that doesn't parse.
+  pub fn with_filter(mut self, filter: &str) -> Result<Self, AgentFlowError> {
+    let filter = expr::normalize_expression(filter);
+    expr::compile(filter).map_err(|err| AgentFlowError::ConfigurationError {
+      message: format!("filter '{}' is invalid: {}", filter, err),
+    })?;
+    self.filter = Some(filter.to_string());
+    Ok(self)
+  }
+
+  pub fn with_sheet(mut self, sheet: &str) -> Self {
+    self.sheet = Some(sheet.to_string());
+    self
+  }
+
+  pub fn strict(mut self) -> Self {
+    self.strict = true;
+    self
+  }
+
+  fn error(&self, message: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::NodeInputError {
+      message: format!("Tabular read node '{}': {}", self.name, message),
+    }
+  }
+
+  fn csv_records(&self, content: &[u8]) -> Vec<RawRecord> {
+    let mut reader = csv::ReaderBuilder::new()
+      .delimiter(self.delimiter)
+      .has_headers(false)
+      .flexible(true)
+      .from_reader(content);
+    reader
+      .records()
+      .map(|record| match record {
+        Ok(record) => RawRecord {
+          line: record.position().map_or(0, |position| position.line()),
+          cells: Ok(record.iter().map(|cell| json!(cell)).collect()),
+        },
+        Err(err) => RawRecord {
+          line: err.position().map_or(0, |position| position.line()),
+          cells: Err(err.to_string()),
+        },
+      })
+      .collect()
+  }
+
+  #[cfg(feature = "xlsx")]
+  fn xlsx_records(&self, content: Vec<u8>) -> Result<Vec<RawRecord>, AgentFlowError> {
+    use calamine::{Data, Reader};
+
+    let mut workbook = calamine::open_workbook_auto_from_rs(std::io::Cursor::new(content))
+      .map_err(|err| self.error(format!("not a readable workbook: {err}")))?;
+    let sheet = match &self.sheet {
+      Some(sheet) => sheet.clone(),
+      None => workbook
+        .sheet_names()
+        .first()
+        .cloned()
+        .ok_or_else(|| self.error("workbook has no sheets"))?,
+    };
+    let range = workbook
+      .worksheet_range(&sheet)
+      .map_err(|err| self.error(format!("sheet '{sheet}': {err}")))?;
+    let first_row = range.start().map_or(0, |(row, _)| row as u64);
+
+    Ok(
+      range
+        .rows()
+        .enumerate()
+        .map(|(index, row)| RawRecord {
+          line: first_row + index as u64 + 1,
+          cells: row
+            .iter()
+            .map(|cell| match cell {
+              Data::Int(value) => Ok(json!(value)),
+              Data::Float(value) => Ok(json!(value)),
+              Data::String(value) | Data::DateTimeIso(value) | Data::DurationIso(value) => {
+                Ok(json!(value))
+              }
+              Data::Bool(value) => Ok(json!(value)),
+              Data::DateTime(value) => Ok(match value.as_datetime() {
+                Some(at) if at.time() == chrono::NaiveTime::MIN => json!(at.date().to_string()),
+                Some(at) => json!(at.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                None => json!(value.as_f64()),
+              }),
+              Data::Error(err) => Err(format!("cell error {err}")),
+              Data::Empty => Ok(Value::Null),
+            })
+            .collect(),
+        })
+        .collect(),
+    )
+  }
+
+  #[cfg(not(feature = "xlsx"))]
+  fn xlsx_records(&self, _content: Vec<u8>) -> Result<Vec<RawRecord>, AgentFlowError> {
+    Err(self.error("XLSX input needs agentflow-nodes built with the `xlsx` feature"))
+  }
+
+  async fn load(&self, inputs: &AsyncNodeInputs) -> Result<Vec<RawRecord>, AgentFlowError> {
+    if let Some(content) = inputs.get("content") {
+      if self.format == Some(TabularFormat::Xlsx) {
+        return Err(self.error("XLSX input must come from 'path', not inline 'content'"));
+      }
+      return Ok(self.csv_records(flow_value_to_string(content).as_bytes()));
+    }
+    let path = inputs
+      .get("path")
+      .map(flow_value_to_string)
+      .ok_or_else(|| self.error("requires a 'path' or 'content' input"))?;
+    let bytes = tokio::fs::read(&path)
+      .await
+      .map_err(|err| self.error(format!("failed to read {path}: {err}")))?;
+    match self
+      .format
+      .unwrap_or_else(|| TabularFormat::from_path(Path::new(&path)))
+    {
+      TabularFormat::Csv => Ok(self.csv_records(&bytes)),
+      TabularFormat::Xlsx => self.xlsx_records(bytes),
+    }
+  }
+
+  fn matches_filter(&self, row: &Map<String, Value>) -> Result<bool, String> {
+    let Some(filter) = &self.filter else {
+      return Ok(true);
+    };
+    let inputs: HashMap<String, FlowValue> = row
+      .iter()
+      .map(|(column, value)| (column.clone(), FlowValue::Json(value.clone())))
+      .collect();
+    expr::evaluate_bool(filter, &HashMap::new(), &inputs)
+      .map_err(|err| format!("cannot evaluate filter '{filter}': {err}"))
+  }
+
+  fn header_columns(&self, header: RawRecord) -> Result<Vec<String>, AgentFlowError> {
+    let cells = header
+      .cells
+      .map_err(|err| self.error(format!("header row is malformed: {err}")))?;
+    let mut columns: Vec<String> = Vec::with_capacity(cells.len());
+    for (index, cell) in cells.iter().enumerate() {
+      let name = match cell {
+        Value::String(name) if !name.trim().is_empty() => name.trim().to_string(),
+        Value::String(_) | Value::Null => format!("column_{}", index + 1),
+        other => other.to_string(),
+      };
+      let mut unique = name.clone();
+      let mut suffix = 2;
+      while columns.contains(&unique) {
+        unique = format!("{name}_{suffix}");
+        suffix += 1;
+      }
+      columns.push(unique);
+    }
+    Ok(columns)
+  }
+}
+
+#[async_trait]
+impl AsyncNode for TabularReadNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let mut records = self.load(inputs).await?.into_iter().peekable();
+
+    let columns: Vec<String> = if self.has_headers {
+      match records.next() {
+        Some(header) => self.header_columns(header)?,
+        None => Vec::new(),
+      }
+    } else {
+      let width = records
+        .peek()
+        .and_then(|first| first.cells.as_ref().ok())
+        .map_or(0, Vec::len);
+      (1..=width).map(|index| format!("column_{index}")).collect()
+    };
+    if let Some(unknown) = self
+      .column_types
+      .keys()
+      .find(|column| !columns.contains(column))
+    {
+      return Err(self.error(format!(
+        "column_types names '{unknown}', which is not a column (columns: {})",
+        columns.join(", ")
+      )));
+    }
+
+    let mut rows = Vec::new();
+    let mut skipped_rows = Vec::new();
+    let mut truncated = false;
+    for record in records {
+      let row = record.cells.and_then(|cells| {
+        if cells.len() != columns.len() {
+          return Err(format!(
+            "expected {} fields, found {}",
+            columns.len(),
+            cells.len()
+          ));
+        }
+        columns
+          .iter()
+          .zip(cells)
+          .map(|(column, cell)| {
+            let value = match self.column_types.get(column) {
+              Some(column_type) => column_type
+                .coerce(cell)
+                .map_err(|err| format!("column '{column}': {err}"))?,
+              None => cell,
+            };
+            Ok((column.clone(), value))
+          })
+          .collect::<Result<Map<_, _>, String>>()
+      });
+      let row = row.and_then(|row| Ok(self.matches_filter(&row)?.then_some(row)));
+      let row = match row {
+        Ok(Some(row)) => row,
+        Ok(None) => continue,
+        Err(err) if self.strict => {
+          return Err(self.error(format!("line {}: {}", record.line, err)));
+        }
+        Err(err) => {
+          skipped_rows.push(json!({ "line": record.line, "error": err }));
+          continue;
+        }
+      };
+      if self.max_rows.is_some_and(|max_rows| rows.len() >= max_rows) {
+        truncated = true;
+        break;
+      }
+      rows.push(Value::Object(row));
+    }
+
+    if !skipped_rows.is_empty() {
+      println!(
+        "⚠️  Tabular read node '{}' skipped {} malformed row(s)",
+        self.name,
+        skipped_rows.len()
+      );
+    }
+    Ok(HashMap::from([
+      ("row_count".to_string(), FlowValue::Json(json!(rows.len()))),
+      ("rows".to_string(), FlowValue::Json(Value::Array(rows))),
+      ("columns".to_string(), FlowValue::Json(json!(columns))),
+      (
+        "skipped_rows".to_string(),
+        FlowValue::Json(Value::Array(skipped_rows)),
+      ),
+      ("truncated".to_string(), FlowValue::Json(json!(truncated))),
+    ]))
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct TabularWriteNode {
+  pub name: String,
+  pub delimiter: u8,
+  pub has_headers: bool,
+  /// Column order; by default every key of the rows, in first-seen order
+  /// (keys within a row iterate alphabetically).
+  pub columns: Option<Vec<String>>,
+}
+
+impl TabularWriteNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      delimiter: b',',
+      has_headers: true,
+      columns: None,
+    }
+  }
+
+  pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+    self.delimiter = delimiter;
+    self
+  }
+
+  pub fn with_headers(mut self, has_headers: bool) -> Self {
+    self.has_headers = has_headers;
+    self
+  }
+
+  pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+    self.columns = Some(columns);
+    self
+  }
+
+  fn error(&self, message: impl std::fmt::Display) -> AgentFlowError {
+    AgentFlowError::NodeInputError {
+      message: format!("Tabular write node '{}': {}", self.name, message),
+    }
+  }
+
+  /// `rows` as CSV, with the columns written.
+  pub fn to_csv(&self, rows: &[Value]) -> Result<(String, Vec<String>), AgentFlowError> {
+    let objects = rows
+      .iter()
+      .enumerate()
+      .map(|(index, row)| {
+        row
+          .as_object()
+          .ok_or_else(|| self.error(format!("rows[{index}] is not an object")))
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let columns = self.columns.clone().unwrap_or_else(|| {
+      let mut columns: Vec<String> = Vec::new();
+      for key in objects.iter().flat_map(|object| object.keys()) {
+        if !columns.contains(key) {
+          columns.push(key.clone());
+        }
+      }
+      columns
+    });
+
+    let mut writer = csv::WriterBuilder::new()
+      .delimiter(self.delimiter)
+      .from_writer(Vec::new());
+    let write_error = |err: csv::Error| self.error(format!("failed to encode CSV: {err}"));
+    if self.has_headers {
+      writer.write_record(&columns).map_err(write_error)?;
+    }
+    for object in objects {
+      let record = columns.iter().map(|column| match object.get(column) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+      });
+      writer.write_record(record).map_err(write_error)?;
+    }
+    let bytes = writer
+      .into_inner()
+      .map_err(|err| self.error(format!("failed to encode CSV: {err}")))?;
+    let content = String::from_utf8(bytes).map_err(|err| self.error(err))?;
+    Ok((content, columns))
+  }
+}
+
+#[async_trait]
+impl AsyncNode for TabularWriteNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let rows = match inputs.get("rows") {
+      Some(FlowValue::Json(Value::Array(rows))) => rows,
+      Some(_) => return Err(self.error("'rows' must be an array of objects")),
+      None => return Err(self.error("requires a 'rows' input")),
+    };
+    let (content, columns) = self.to_csv(rows)?;
+
+    let mut outputs = HashMap::from([
+      ("row_count".to_string(), FlowValue::Json(json!(rows.len()))),
+      ("columns".to_string(), FlowValue::Json(json!(columns))),
+    ]);
+    match inputs.get("path").map(flow_value_to_string) {
+      Some(path) => {
+        let write_error =
+          |err: std::io::Error| self.error(format!("failed to write {path}: {err}"));
+        if let Some(parent) = Path::new(&path)
+          .parent()
+          .filter(|parent| !parent.as_os_str().is_empty())
+        {
+          tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(write_error)?;
+        }
+        tokio::fs::write(&path, content)
+          .await
+          .map_err(write_error)?;
+        outputs.insert("path".to_string(), FlowValue::Json(json!(path)));
+      }
+      None => {
+        outputs.insert("content".to_string(), FlowValue::Json(json!(content)));
+      }
+    }
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn inline(content: &str) -> AsyncNodeInputs {
+    HashMap::from([("content".to_string(), FlowValue::Json(json!(content)))])
+  }
+
+  fn output(outputs: &HashMap<String, FlowValue>, key: &str) -> Value {
+    match &outputs[key] {
+      FlowValue::Json(value) => value.clone(),
+      other => panic!("{key} is not JSON: {other:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn reads_quoted_and_escaped_fields() {
+    let csv = "name;quote;notes\n\"Lovelace, Ada\";\"She said \"\"hi\"\"\";\"line one\nline two\"\nTuring;;\n";
+    let outputs = TabularReadNode::new("people")
+      .with_delimiter(b';')
+      .execute(&inline(csv))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      output(&outputs, "columns"),
+      json!(["name", "quote", "notes"])
+    );
+    assert_eq!(
+      output(&outputs, "rows"),
+      json!([
+        { "name": "Lovelace, Ada", "quote": "She said \"hi\"", "notes": "line one\nline two" },
+        { "name": "Turing", "quote": "", "notes": "" },
+      ])
+    );
+    assert_eq!(output(&outputs, "row_count"), json!(2));
+  }
+
+  #[tokio::test]
+  async fn collects_malformed_rows_and_coercion_failures() {
+    let csv = "id,amount,active,joined\n1,9.5,yes,2026-01-02\ntwo,3,no,2026-02-03\n3,1\n4,,false,yesterday\n5,7,1,\n";
+    let node = TabularReadNode::new("ledger")
+      .with_column_type("id", ColumnType::Int)
+      .with_column_type("amount", ColumnType::Float)
+      .with_column_type("active", ColumnType::Bool)
+      .with_column_type("joined", ColumnType::Date);
+    let outputs = node.clone().execute(&inline(csv)).await.unwrap();
+
+    assert_eq!(
+      output(&outputs, "rows"),
+      json!([
+        { "id": 1, "amount": 9.5, "active": true, "joined": "2026-01-02" },
+        { "id": 5, "amount": 7.0, "active": true, "joined": null },
+      ])
+    );
+    assert_eq!(
+      output(&outputs, "skipped_rows"),
+      json!([
+        { "line": 3, "error": "column 'id': 'two' is not a valid int" },
+        { "line": 4, "error": "expected 4 fields, found 2" },
+        { "line": 5, "error": "column 'joined': 'yesterday' is not a valid date" },
+      ])
+    );
+
+    let err = node.strict().execute(&inline(csv)).await.unwrap_err();
+    assert!(err.to_string().contains("line 3: column 'id'"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn filter_expression_and_row_limit_select_rows() {
+    let csv = "region,amount\nEU,120\nUS,300\nEU,80\nEU,500\nEU,150\n";
+    let outputs = TabularReadNode::new("sales")
+      .with_column_type("amount", ColumnType::Int)
+      .with_filter("{{ region == \"EU\" && amount >= 100 }}")
+      .unwrap()
+      .with_max_rows(2)
+      .execute(&inline(csv))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      output(&outputs, "rows"),
+      json!([{ "region": "EU", "amount": 120 }, { "region": "EU", "amount": 500 }])
+    );
+    assert_eq!(output(&outputs, "truncated"), json!(true));
+
+    assert!(
+      TabularReadNode::new("sales")
+        .with_filter("amount >")
+        .is_err()
+    );
+    let unevaluable = TabularReadNode::new("sales")
+      .with_filter("missing > 1")
+      .unwrap();
+    let outputs = unevaluable.clone().execute(&inline(csv)).await.unwrap();
+    assert_eq!(output(&outputs, "row_count"), json!(0));
+    let skipped = output(&outputs, "skipped_rows");
+    assert_eq!(skipped.as_array().unwrap().len(), 5);
+    assert_eq!(skipped[0]["line"], json!(2));
+    assert!(
+      skipped[0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("cannot evaluate filter"),
+      "{skipped}"
+    );
+    let err = unevaluable
+      .strict()
+      .execute(&inline(csv))
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("cannot evaluate filter"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn repeated_header_names_get_suffixes() {
+    let outputs = TabularReadNode::new("dupes")
+      .execute(&inline("name,name,name_2,\nA,B,C,D\n"))
+      .await
+      .unwrap();
+    assert_eq!(
+      output(&outputs, "columns"),
+      json!(["name", "name_2", "name_2_2", "column_4"])
+    );
+    assert_eq!(
+      output(&outputs, "rows"),
+      json!([{ "name": "A", "name_2": "B", "name_2_2": "C", "column_4": "D" }])
+    );
+  }
+
+  #[tokio::test]
+  async fn headerless_input_gets_positional_columns() {
+    let outputs = TabularReadNode::new("raw")
+      .with_headers(false)
+      .execute(&inline("a,b\nc,d\n"))
+      .await
+      .unwrap();
+    assert_eq!(output(&outputs, "columns"), json!(["column_1", "column_2"]));
+    assert_eq!(output(&outputs, "row_count"), json!(2));
+  }
+
+  #[tokio::test]
+  async fn writes_rows_that_read_back_unchanged() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out").join("people.csv");
+    let rows = json!([
+      { "name": "Lovelace, Ada", "quote": "She said \"hi\"", "age": 36 },
+      { "name": "Turing", "tags": ["math"] },
+    ]);
+    let inputs = HashMap::from([
+      ("rows".to_string(), FlowValue::Json(rows)),
+      (
+        "path".to_string(),
+        FlowValue::Json(json!(path.display().to_string())),
+      ),
+    ]);
+    let outputs = TabularWriteNode::new("export")
+      .execute(&inputs)
+      .await
+      .unwrap();
+    assert_eq!(
+      output(&outputs, "columns"),
+      json!(["age", "name", "quote", "tags"])
+    );
+
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+      written,
+      "age,name,quote,tags\n36,\"Lovelace, Ada\",\"She said \"\"hi\"\"\",\n,Turing,,\"[\"\"math\"\"]\"\n"
+    );
+    let read_back = TabularReadNode::new("import")
+      .execute(&inline(&written))
+      .await
+      .unwrap();
+    assert_eq!(
+      output(&read_back, "rows")[0]["quote"],
+      json!("She said \"hi\"")
+    );
+
+    let err = TabularWriteNode::new("export")
+      .execute(&HashMap::from([(
+        "rows".to_string(),
+        FlowValue::Json(json!([1])),
+      )]))
+      .await
+      .unwrap_err();
+    assert!(
+      err.to_string().contains("rows[0] is not an object"),
+      "{err}"
+    );
+  }
+}
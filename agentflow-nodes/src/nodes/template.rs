@@ -1,6 +1,4 @@
-use crate::common::tera_helpers::{
-  flow_value_to_tera_value, register_custom_filters, register_custom_functions,
-};
+use crate::common::tera_helpers::{flow_value_to_tera_value, render_template};
 use agentflow_core::{
   async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
   error::AgentFlowError,
@@ -9,27 +7,44 @@ use agentflow_core::{
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::{Mutex, OnceLock};
-use tera::Tera;
-
-/// Global Tera instance with custom filters and functions
-static TERA_INSTANCE: OnceLock<Mutex<Tera>> = OnceLock::new();
-
-fn get_tera() -> &'static Mutex<Tera> {
-  TERA_INSTANCE.get_or_init(|| {
-    let mut tera = Tera::default();
-    register_custom_filters(&mut tera);
-    register_custom_functions(&mut tera);
-    Mutex::new(tera)
-  })
+
+/// Output formats understood by [`TemplateNode`].
+pub const TEMPLATE_OUTPUT_FORMATS: &[&str] = &["text", "json", "yaml"];
+
+/// Check an `output_format` before the node runs.
+///
+/// The workflow factory calls this at build time; [`TemplateNode`] repeats
+/// it when it renders, so a format set through [`TemplateNode::with_format`]
+/// cannot silently fall back to `text`.
+pub fn validate_output_format(format: &str) -> Result<(), String> {
+  if TEMPLATE_OUTPUT_FORMATS.contains(&format) {
+    Ok(())
+  } else {
+    Err(format!(
+      "unsupported output format '{}', expected one of: {}",
+      format,
+      TEMPLATE_OUTPUT_FORMATS.join(", ")
+    ))
+  }
 }
 
+/// Renders a Tera template against the node's inputs.
+///
+/// Every input is exposed to the template under its own name, so nested
+/// values read naturally (`{{ paper.sections.0.title }}`), and loops,
+/// conditionals and filters (`upper`, `truncate`, `json`, ...) work on the
+/// structured value rather than a stringified copy.
 pub struct TemplateNode {
   pub name: String,
   pub template: String,
   pub output_key: String,
   pub output_format: String,
+  /// Static defaults placed in the context before the inputs. Kept for
+  /// older callers; new code should pass values as node inputs.
   pub variables: HashMap<String, String>,
+  /// When `false`, top-level variables missing from the context render as
+  /// an empty string instead of failing the node.
+  pub strict: bool,
 }
 
 impl TemplateNode {
@@ -40,6 +55,7 @@ impl TemplateNode {
       output_key: "output".to_string(),
       output_format: "text".to_string(),
       variables: HashMap::new(),
+      strict: true,
     }
   }
 
@@ -53,98 +69,107 @@ impl TemplateNode {
     self
   }
 
+  pub fn with_strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+
+  #[deprecated(note = "pass template values as node inputs instead")]
   pub fn with_variables(mut self, variables: HashMap<String, String>) -> Self {
     self.variables = variables;
     self
   }
 
+  #[deprecated(note = "pass template values as node inputs instead")]
   pub fn with_variable(mut self, key: &str, value: &str) -> Self {
     self.variables.insert(key.to_string(), value.to_string());
     self
   }
+
+  fn render(&self, context: &mut tera::Context) -> Result<String, AgentFlowError> {
+    render_template(&self.template, context, self.strict).map_err(|reason| {
+      AgentFlowError::AsyncExecutionError {
+        message: format!("Template rendering failed{}", reason),
+      }
+    })
+  }
+
+  fn structured_outputs(&self, value: Value) -> AsyncNodeResult {
+    let mut outputs = HashMap::new();
+    match value {
+      // Objects unpack into one output per field so downstream nodes can map
+      // `node.field` directly.
+      Value::Object(map) => {
+        for (key, value) in map {
+          outputs.insert(key, FlowValue::Json(value));
+        }
+      }
+      other => {
+        outputs.insert(self.output_key.clone(), FlowValue::Json(other));
+      }
+    }
+    Ok(outputs)
+  }
 }
 
 #[async_trait]
 impl AsyncNode for TemplateNode {
   async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
-    let tera = get_tera();
+    validate_output_format(&self.output_format).map_err(|message| {
+      AgentFlowError::ConfigurationError {
+        message: format!("Template '{}': {}", self.name, message),
+      }
+    })?;
     let mut context = tera::Context::new();
 
-    // First, add node-defined variables to context
+    // Static defaults first so the flow's inputs win on a name clash.
     for (key, value) in &self.variables {
       context.insert(key, value);
     }
-
-    // Then, add inputs from the flow (can override variables)
     for (key, value) in inputs {
       let tera_value = flow_value_to_tera_value(value);
       context.insert(key, &tera_value);
     }
 
     println!("📝 Rendering template for node '{}'", self.name);
-
-    // Q3.8.2: avoid the `.lock().unwrap()` panic on a poisoned mutex.
-    // A poisoned Tera mutex means a previous render panicked mid-flight;
-    // the engine's internal state may be inconsistent. Rather than
-    // crashing the workflow, surface the poison as an
-    // `AsyncExecutionError` so the caller's retry / error-handling
-    // logic gets a chance. We recover the guard via `into_inner` so
-    // subsequent renders proceed (Tera::render_str takes `&mut self`
-    // only because Tera caches parsed templates internally; the cache
-    // is best-effort and a stale entry is acceptable).
-    let rendered = {
-      let mut tera_guard = tera.lock().unwrap_or_else(|poisoned| {
-        eprintln!(
-          "[agentflow-nodes:template] Tera template engine mutex was poisoned by a prior panic; recovering and continuing"
-        );
-        poisoned.into_inner()
-      });
-      tera_guard
-        .render_str(&self.template, &context)
-        .map_err(|e| AgentFlowError::AsyncExecutionError {
-          message: format!("Template rendering failed: {}", e),
-        })?
-    };
+    let rendered = self.render(&mut context)?;
 
     // Parse result based on output format
     match self.output_format.as_str() {
       "json" => {
-        println!(
-          "📝 Attempting to parse rendered template as JSON: {}",
-          &rendered
-        );
-        match serde_json::from_str::<Value>(&rendered) {
-          Ok(Value::Object(map)) => {
-            // When output format is JSON and it's an object, unpack fields into separate outputs
-            println!(
-              "✅ Template rendered successfully (JSON object unpacked with {} fields)",
-              map.len()
-            );
-            let mut outputs = HashMap::new();
-            for (key, value) in map {
-              outputs.insert(key, FlowValue::Json(value));
-            }
-            Ok(outputs)
+        // An explicit format is a contract: a template that renders invalid
+        // JSON fails here instead of surfacing as a "wrong type" error in
+        // some downstream node.
+        let parsed = serde_json::from_str::<Value>(&rendered).map_err(|e| {
+          AgentFlowError::AsyncExecutionError {
+            message: format!(
+              "Template '{}' rendered invalid JSON at line {}, column {}: {}\n    Rendered content: {}",
+              self.name,
+              e.line(),
+              e.column(),
+              e,
+              rendered
+            ),
           }
-          Ok(json) => {
-            // Non-object JSON, keep as single output
-            println!("✅ Template rendered successfully (non-object JSON)");
-            let mut outputs = HashMap::new();
-            outputs.insert(self.output_key.clone(), FlowValue::Json(json));
-            Ok(outputs)
-          }
-          Err(e) => {
-            // Invalid JSON, treat as string
-            println!("⚠️  Template rendered but JSON parsing failed: {}", e);
-            println!("    Rendered content: {}", &rendered);
-            let mut outputs = HashMap::new();
-            outputs.insert(
-              self.output_key.clone(),
-              FlowValue::Json(Value::String(rendered)),
-            );
-            Ok(outputs)
+        })?;
+        println!("✅ Template rendered successfully (JSON)");
+        self.structured_outputs(parsed)
+      }
+      "yaml" => {
+        let parsed = serde_yaml::from_str::<Value>(&rendered).map_err(|e| {
+          let location = e
+            .location()
+            .map(|loc| format!(" at line {}, column {}", loc.line(), loc.column()))
+            .unwrap_or_default();
+          AgentFlowError::AsyncExecutionError {
+            message: format!(
+              "Template '{}' rendered invalid YAML{}: {}\n    Rendered content: {}",
+              self.name, location, e, rendered
+            ),
           }
-        }
+        })?;
+        println!("✅ Template rendered successfully (YAML)");
+        self.structured_outputs(parsed)
       }
       _ => {
         // F-A6-7: opportunistic JSON auto-detect for the default
@@ -157,12 +182,9 @@ impl AsyncNode for TemplateNode {
         // prose templates that happen to start with `{` (rare but
         // possible) stay safe.
         //
-        // The explicit `output_format: "json"` path above remains
-        // the strict-mode override: it logs the rendered content
-        // and warns on parse failure, which is what you want when
-        // the workflow author intends JSON and a bug would
-        // otherwise hide as a downstream "wrong type" error far
-        // from the producing template.
+        // The explicit `json` / `yaml` formats above are the strict
+        // override: a parse failure there is an error, which is what
+        // you want when the workflow author intends structured output.
         let trimmed = rendered.trim_start();
         // Capture the first char once so the log line below can reuse it
         // without a second `chars().next().unwrap()` (Q5.1).
@@ -212,6 +234,7 @@ mod tests {
   }
 
   #[tokio::test]
+  #[allow(deprecated)]
   async fn test_template_node_with_variables() {
     let node = TemplateNode::new("var_test", "{{greeting}} {{name}}{{punctuation}}")
       .with_variable("greeting", "Hi")
@@ -395,4 +418,121 @@ No tasks found.
       panic!("Expected string output");
     }
   }
+
+  #[tokio::test]
+  async fn test_nested_access_loop_and_filters() {
+    let node = TemplateNode::new(
+      "paper",
+      "{{ paper.sections.0.title | upper }}\n{% for s in paper.sections %}- {{ s.title | truncate(length=5) }}\n{% endfor %}{{ paper.tags | json }}",
+    );
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "paper".to_string(),
+      FlowValue::Json(json!({
+        "sections": [{"title": "Intro"}, {"title": "Methodology"}],
+        "tags": ["ml", "nlp"]
+      })),
+    );
+
+    let result = node.execute(&inputs).await.unwrap();
+    assert_eq!(
+      result["output"],
+      FlowValue::Json(json!("INTRO\n- Intro\n- Metho…\n[\"ml\",\"nlp\"]"))
+    );
+  }
+
+  #[tokio::test]
+  async fn test_yaml_output_format_unpacks_objects() {
+    let node =
+      TemplateNode::new("yaml_test", "title: {{ title }}\ncount: {{ n }}\n").with_format("yaml");
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("title".to_string(), FlowValue::Json(json!("Report")));
+    inputs.insert("n".to_string(), FlowValue::Json(json!(3)));
+
+    let result = node.execute(&inputs).await.unwrap();
+    assert_eq!(result["title"], FlowValue::Json(json!("Report")));
+    assert_eq!(result["count"], FlowValue::Json(json!(3)));
+  }
+
+  #[tokio::test]
+  async fn test_unknown_output_format_is_an_error() {
+    assert!(validate_output_format("yaml").is_ok());
+    let message = validate_output_format("markdown").unwrap_err();
+    assert!(message.contains("text, json, yaml"), "{message}");
+
+    let node = TemplateNode::new("fmt", "hello").with_format("markdown");
+    let err = node.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::ConfigurationError { .. }),
+      "{err:?}"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_json_output_format_rejects_invalid_json() {
+    let node = TemplateNode::new("bad_json", r#"{"name": {{ name }}}"#).with_format("json");
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("name".to_string(), FlowValue::Json(json!("unquoted")));
+
+    let err = node.execute(&inputs).await.unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("invalid JSON at line 1"), "{message}");
+    assert!(message.contains("bad_json"), "{message}");
+  }
+
+  #[tokio::test]
+  async fn test_missing_variable_is_an_error_with_location() {
+    let node = TemplateNode::new("strict", "Hello\n  {{ user.name }} and {{ missing }}");
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("user".to_string(), FlowValue::Json(json!({"name": "Ada"})));
+
+    let err = node.execute(&inputs).await.unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("line 2, column 26"), "{message}");
+    assert!(message.contains("missing"), "{message}");
+  }
+
+  #[tokio::test]
+  async fn test_lenient_mode_renders_missing_variables_empty() {
+    let node = TemplateNode::new("lenient", "[{{ a }}|{{ b }}|{{ c }}]").with_strict(false);
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("b".to_string(), FlowValue::Json(json!("B")));
+
+    let result = node.execute(&inputs).await.unwrap();
+    assert_eq!(result["output"], FlowValue::Json(json!("[|B|]")));
+  }
+
+  #[tokio::test]
+  async fn test_syntax_error_reports_line_and_column() {
+    let node = TemplateNode::new("syntax", "ok\n{% if %}x{% endif %}");
+
+    let err = node.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("at line 2, column"), "{message}");
+  }
+
+  #[tokio::test]
+  async fn plain_placeholders_match_the_shared_renderer() {
+    use crate::common::template::{RenderOptions, render};
+
+    let template = "{{ user.name }} ({{ user.tags.1 }}) x{{count}}";
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "user".to_string(),
+      FlowValue::Json(json!({ "name": "Ada", "tags": ["math", "engines"] })),
+    );
+    inputs.insert("count".to_string(), FlowValue::Json(json!(3)));
+
+    let outputs = TemplateNode::new("t", template)
+      .execute(&inputs)
+      .await
+      .unwrap();
+
+    assert_eq!(
+      outputs["output"],
+      FlowValue::Json(json!(
+        render(template, &inputs, &RenderOptions::strict()).unwrap()
+      ))
+    );
+  }
 }
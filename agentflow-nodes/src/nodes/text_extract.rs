@@ -0,0 +1,781 @@
+//! Text extract node.
+//!
+//! `TextExtractNode` post-processes free text, typically an LLM answer,
+//! through an ordered pipeline of operations. The pipeline starts from the
+//! `input_key` input (default `text`) and each operation works on the
+//! current text:
+//!
+//! - `regex_capture: <pattern>` (or `{pattern, all}`) — the first match, or
+//!   with `all: true` every match as an array. A match with named groups
+//!   becomes an object of them, one with a single unnamed group that
+//!   group's text, and one without groups the whole match.
+//! - `extract_code_blocks` (or `{language, all}`) — fenced code blocks as
+//!   `{language, code}` objects, optionally only those whose info string
+//!   names one of `language` (a string or list, case-insensitive). `all`
+//!   defaults to `true`; `false` keeps just the first block.
+//! - `extract_json` (or `{all}`) — the first balanced JSON object or array,
+//!   parsed; trailing commas are repaired. `all: true` collects every one.
+//! - `strip_markdown` — markdown to plain text: fences, headings, quotes,
+//!   list markers, rules, emphasis and link syntax are removed.
+//! - `replace: {pattern, with}` — regex replacement (`$1` / `$name` refer
+//!   to groups; `with` defaults to empty).
+//!
+//! The three extraction operations set `extracted` and `match_count` and
+//! remove what they matched from the text, so later operations see the
+//! residual. A search that finds nothing yields `null` under `on_missing:
+//! null` (the default) and fails the node under `on_missing: error`.
+//! Outputs are `extracted` (from the last extraction), `match_count` and
+//! the final `text`.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{Map, Value, json};
+
+use crate::common::utils::flow_value_to_string;
+use crate::nodes::json_transform::MissingPathMode;
+
+#[derive(Debug, Clone)]
+pub enum ExtractOp {
+  RegexCapture { regex: Regex, all: bool },
+  CodeBlocks { languages: Vec<String>, all: bool },
+  Json { all: bool },
+  StripMarkdown,
+  Replace { regex: Regex, replacement: String },
+}
+
+impl ExtractOp {
+  /// Parses one pipeline entry: a bare operation name, or a single-key
+  /// object mapping the operation name to its argument.
+  pub fn from_value(value: &Value) -> Result<Self, AgentFlowError> {
+    let invalid = |message: String| AgentFlowError::ConfigurationError { message };
+    let single_field = match value {
+      Value::Object(fields) if fields.len() == 1 => fields.iter().next(),
+      _ => None,
+    };
+    let (name, arg) = match (value, single_field) {
+      (Value::String(name), _) => (name.as_str(), &Value::Null),
+      (_, Some((name, arg))) => (name.as_str(), arg),
+      (other, None) => {
+        return Err(invalid(format!(
+          "text_extract operation must be a name or a single-key object, got {}",
+          other
+        )));
+      }
+    };
+    let regex = |pattern: Option<&Value>| match pattern {
+      Some(Value::String(pattern)) => Regex::new(pattern).map_err(|e| {
+        invalid(format!(
+          "'{}' pattern '{}' is invalid: {}",
+          name, pattern, e
+        ))
+      }),
+      _ => Err(invalid(format!("'{}' requires a 'pattern' string", name))),
+    };
+    let all = |default: bool| match arg.get("all") {
+      None => Ok(default),
+      Some(Value::Bool(all)) => Ok(*all),
+      Some(_) => Err(invalid(format!(
+        "'{}' option 'all' must be a boolean",
+        name
+      ))),
+    };
+
+    match name {
+      "regex_capture" => match arg {
+        Value::String(_) => Ok(Self::RegexCapture {
+          regex: regex(Some(arg))?,
+          all: false,
+        }),
+        _ => Ok(Self::RegexCapture {
+          regex: regex(arg.get("pattern"))?,
+          all: all(false)?,
+        }),
+      },
+      "extract_code_blocks" => {
+        let languages = match arg.get("language") {
+          None => Vec::new(),
+          Some(Value::String(language)) => vec![language.to_lowercase()],
+          Some(Value::Array(languages)) => languages
+            .iter()
+            .map(|language| {
+              language
+                .as_str()
+                .map(str::to_lowercase)
+                .ok_or_else(|| invalid("'extract_code_blocks' languages must be strings".into()))
+            })
+            .collect::<Result<_, _>>()?,
+          Some(_) => {
+            return Err(invalid(
+              "'extract_code_blocks' option 'language' must be a string or list".to_string(),
+            ));
+          }
+        };
+        Ok(Self::CodeBlocks {
+          languages,
+          all: all(true)?,
+        })
+      }
+      "extract_json" => Ok(Self::Json { all: all(false)? }),
+      "strip_markdown" => Ok(Self::StripMarkdown),
+      "replace" => {
+        let replacement = match arg.get("with") {
+          None => String::new(),
+          Some(Value::String(with)) => with.clone(),
+          Some(_) => {
+            return Err(invalid(
+              "'replace' option 'with' must be a string".to_string(),
+            ));
+          }
+        };
+        Ok(Self::Replace {
+          regex: regex(arg.get("pattern"))?,
+          replacement,
+        })
+      }
+      other => Err(invalid(format!(
+        "Unknown text_extract operation '{}' (expected regex_capture, extract_code_blocks, extract_json, strip_markdown or replace)",
+        other
+      ))),
+    }
+  }
+}
+
+/// The running state of a pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+  pub text: String,
+  pub extracted: Value,
+  pub match_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextExtractNode {
+  pub name: String,
+  pub input_key: String,
+  pub on_missing: MissingPathMode,
+  pub operations: Vec<ExtractOp>,
+}
+
+impl TextExtractNode {
+  pub fn new(name: &str, operations: Vec<ExtractOp>) -> Self {
+    Self {
+      name: name.to_string(),
+      input_key: "text".to_string(),
+      on_missing: MissingPathMode::default(),
+      operations,
+    }
+  }
+
+  pub fn with_input_key(mut self, key: &str) -> Self {
+    self.input_key = key.to_string();
+    self
+  }
+
+  pub fn with_on_missing(mut self, mode: MissingPathMode) -> Self {
+    self.on_missing = mode;
+    self
+  }
+
+  /// Build from a `type: text_extract` parameters object
+  /// (`operations`, `input_key`, `on_missing`).
+  pub fn from_params(name: &str, params: &Value) -> Result<Self, AgentFlowError> {
+    let operations = match params.get("operations") {
+      Some(Value::Array(entries)) if !entries.is_empty() => entries
+        .iter()
+        .map(ExtractOp::from_value)
+        .collect::<Result<Vec<_>, _>>()?,
+      _ => {
+        return Err(AgentFlowError::ConfigurationError {
+          message: "text_extract requires a non-empty 'operations' list".to_string(),
+        });
+      }
+    };
+    let mut node = Self::new(name, operations);
+    if let Some(key) = params.get("input_key").and_then(Value::as_str) {
+      node = node.with_input_key(key);
+    }
+    if let Some(mode) = params.get("on_missing").and_then(Value::as_str) {
+      node = node.with_on_missing(mode.parse()?);
+    }
+    Ok(node)
+  }
+
+  /// Run the pipeline over `text`.
+  pub fn extract(&self, text: &str) -> Result<Extraction, AgentFlowError> {
+    let mut state = Extraction {
+      text: text.to_string(),
+      extracted: Value::Null,
+      match_count: 0,
+    };
+    for op in &self.operations {
+      let (values, spans, all) = match op {
+        ExtractOp::StripMarkdown => {
+          state.text = strip_markdown(&state.text);
+          continue;
+        }
+        ExtractOp::Replace { regex, replacement } => {
+          state.text = regex
+            .replace_all(&state.text, replacement.as_str())
+            .into_owned();
+          continue;
+        }
+        ExtractOp::RegexCapture { regex, all } => {
+          let (values, spans) = regex_matches(regex, &state.text, *all);
+          (values, spans, *all)
+        }
+        ExtractOp::CodeBlocks { languages, all } => {
+          let (values, spans) = code_blocks(&state.text, languages, *all);
+          (values, spans, *all)
+        }
+        ExtractOp::Json { all } => {
+          let (values, spans) = json_values(&state.text, *all);
+          (values, spans, *all)
+        }
+      };
+
+      if values.is_empty() && self.on_missing == MissingPathMode::Error {
+        return Err(AgentFlowError::NodeExecutionFailed {
+          message: format!(
+            "text_extract '{}': {} found nothing",
+            self.name,
+            op_name(op)
+          ),
+        });
+      }
+      state.match_count = values.len();
+      state.extracted = if all {
+        Value::Array(values)
+      } else {
+        values.into_iter().next().unwrap_or(Value::Null)
+      };
+      state.text = remove_spans(&state.text, &spans);
+    }
+    Ok(state)
+  }
+}
+
+fn op_name(op: &ExtractOp) -> &'static str {
+  match op {
+    ExtractOp::RegexCapture { .. } => "regex_capture",
+    ExtractOp::CodeBlocks { .. } => "extract_code_blocks",
+    ExtractOp::Json { .. } => "extract_json",
+    ExtractOp::StripMarkdown => "strip_markdown",
+    ExtractOp::Replace { .. } => "replace",
+  }
+}
+
+fn regex_matches(regex: &Regex, text: &str, all: bool) -> (Vec<Value>, Vec<Range<usize>>) {
+  let names: Vec<_> = regex.capture_names().flatten().collect();
+  let mut values = Vec::new();
+  let mut spans = Vec::new();
+  for captures in regex
+    .captures_iter(text)
+    .take(if all { usize::MAX } else { 1 })
+  {
+    let Some(whole) = captures.get(0) else {
+      continue;
+    };
+    let group = |index: usize| {
+      captures
+        .get(index)
+        .map_or(Value::Null, |group| json!(group.as_str()))
+    };
+    values.push(if !names.is_empty() {
+      Value::Object(
+        names
+          .iter()
+          .map(|name| {
+            let value = captures
+              .name(name)
+              .map_or(Value::Null, |group| json!(group.as_str()));
+            (name.to_string(), value)
+          })
+          .collect::<Map<_, _>>(),
+      )
+    } else {
+      match captures.len() {
+        1 => json!(whole.as_str()),
+        2 => group(1),
+        groups => Value::Array((1..groups).map(group).collect()),
+      }
+    });
+    spans.push(whole.range());
+  }
+  (values, spans)
+}
+
+/// Opening fence of a code block: its character, length and info string.
+fn fence(line: &str) -> Option<(char, usize, &str)> {
+  let trimmed = line.trim_start();
+  if line.len() - trimmed.len() > 3 {
+    return None;
+  }
+  let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+  let length = trimmed.chars().take_while(|c| *c == marker).count();
+  (length >= 3).then(|| (marker, length, trimmed[length..].trim()))
+}
+
+fn closes(line: &str, marker: char, length: usize) -> bool {
+  let trimmed = line.trim();
+  trimmed.len() >= length && trimmed.chars().all(|c| c == marker)
+}
+
+/// Fenced blocks as `(language, code, span)`; an unclosed block runs to
+/// the end of the text.
+fn fenced_blocks(text: &str) -> Vec<(String, String, Range<usize>)> {
+  let mut blocks = Vec::new();
+  let mut open: Option<(char, usize, String, usize, String)> = None;
+  let mut offset = 0;
+  for line in text.split_inclusive('\n') {
+    let start = offset;
+    offset += line.len();
+    match &mut open {
+      None => {
+        if let Some((marker, length, info)) = fence(line) {
+          let language = info.split_whitespace().next().unwrap_or("").to_string();
+          open = Some((marker, length, language, start, String::new()));
+        }
+      }
+      Some((marker, length, _, _, code)) => {
+        if closes(line, *marker, *length) {
+          if let Some((_, _, language, block_start, code)) = open.take() {
+            blocks.push((language, code, block_start..offset));
+          }
+        } else {
+          code.push_str(line);
+        }
+      }
+    }
+  }
+  if let Some((_, _, language, block_start, code)) = open {
+    blocks.push((language, code, block_start..text.len()));
+  }
+  blocks
+}
+
+fn code_blocks(text: &str, languages: &[String], all: bool) -> (Vec<Value>, Vec<Range<usize>>) {
+  fenced_blocks(text)
+    .into_iter()
+    .filter(|(language, _, _)| languages.is_empty() || languages.contains(&language.to_lowercase()))
+    .take(if all { usize::MAX } else { 1 })
+    .map(|(language, code, span)| {
+      let code = code.strip_suffix('\n').unwrap_or(&code).to_string();
+      (json!({ "language": language, "code": code }), span)
+    })
+    .unzip()
+}
+
+/// Every balanced `{...}` / `[...]` in `text`, sorted by start, and the
+/// offsets of commas directly before a closer, ascending; both in one
+/// pass. Quotes only delimit strings inside brackets, so stray quotes in
+/// the surrounding prose don't hide what follows; a mismatched closer
+/// abandons every bracket still open.
+fn scan_brackets(text: &str) -> (Vec<Range<usize>>, Vec<usize>) {
+  let (mut spans, mut commas) = (Vec::new(), Vec::new());
+  let mut open: Vec<(usize, u8)> = Vec::new();
+  let mut pending_comma = None;
+  let (mut in_string, mut escaped) = (false, false);
+  for (index, byte) in text.bytes().enumerate() {
+    if in_string {
+      match byte {
+        _ if escaped => escaped = false,
+        b'\\' => escaped = true,
+        b'"' => in_string = false,
+        _ => {}
+      }
+      continue;
+    }
+    match byte {
+      b'"' if !open.is_empty() => {
+        in_string = true;
+        pending_comma = None;
+      }
+      b',' if !open.is_empty() => pending_comma = Some(index),
+      b'{' | b'[' => {
+        open.push((index, if byte == b'{' { b'}' } else { b']' }));
+        pending_comma = None;
+      }
+      b'}' | b']' => {
+        commas.extend(pending_comma.take());
+        match open.pop() {
+          Some((start, closer)) if closer == byte => spans.push(start..index + 1),
+          Some(_) => open.clear(),
+          None => {}
+        }
+      }
+      _ if byte.is_ascii_whitespace() => {}
+      _ => pending_comma = None,
+    }
+  }
+  spans.sort_unstable_by_key(|span| span.start);
+  (spans, commas)
+}
+
+/// Drops commas directly before a closing `}` / `]`, outside strings.
+fn remove_trailing_commas(candidate: &str) -> String {
+  let mut repaired = String::with_capacity(candidate.len());
+  let (mut in_string, mut escaped) = (false, false);
+  for (index, c) in candidate.char_indices() {
+    if in_string {
+      match c {
+        _ if escaped => escaped = false,
+        '\\' => escaped = true,
+        '"' => in_string = false,
+        _ => {}
+      }
+    } else if c == '"' {
+      in_string = true;
+    } else if c == ',' && candidate[index + 1..].trim_start().starts_with(['}', ']']) {
+      continue;
+    }
+    repaired.push(c);
+  }
+  repaired
+}
+
+/// JSON values embedded in `text`, outermost first. Brackets are matched
+/// once up front, and the trailing-comma repair only runs on candidates
+/// that have one, so long or unbalanced text stays linear in practice.
+fn json_values(text: &str, all: bool) -> (Vec<Value>, Vec<Range<usize>>) {
+  let (mut values, mut spans) = (Vec::new(), Vec::new());
+  let (candidates, commas) = scan_brackets(text);
+  let mut next = 0;
+  for span in candidates {
+    if !all && !values.is_empty() {
+      break;
+    }
+    if span.start < next {
+      continue;
+    }
+    let candidate = &text[span.clone()];
+    let has_trailing_comma = commas
+      .get(commas.partition_point(|comma| *comma < span.start))
+      .is_some_and(|comma| *comma < span.end);
+    let parsed = serde_json::from_str::<Value>(candidate).or_else(|err| {
+      if has_trailing_comma {
+        serde_json::from_str(&remove_trailing_commas(candidate))
+      } else {
+        Err(err)
+      }
+    });
+    if let Ok(value) = parsed {
+      values.push(value);
+      next = span.end;
+      spans.push(span);
+    }
+  }
+  (values, spans)
+}
+
+/// `text` without `spans` (ascending, non-overlapping), with the blank
+/// lines they leave behind collapsed.
+fn remove_spans(text: &str, spans: &[Range<usize>]) -> String {
+  if spans.is_empty() {
+    return text.to_string();
+  }
+  let mut residual = String::with_capacity(text.len());
+  let mut last = 0;
+  for span in spans {
+    residual.push_str(&text[last..span.start]);
+    last = span.end;
+  }
+  residual.push_str(&text[last..]);
+  BLANK_LINES
+    .replace_all(residual.trim(), "\n\n")
+    .into_owned()
+}
+
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static BLANK_LINES: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"\n[ \t]*\n\s*\n").expect("BLANK_LINES is malformed — bug in agentflow-nodes")
+});
+
+/// Inline markdown rewrites, applied in order.
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static INLINE_MARKDOWN: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+  [
+    (r"!\[([^\]]*)\]\([^)]*\)", "$1"),
+    (r"\[([^\]]+)\]\([^)]*\)", "$1"),
+    (r"`([^`]+)`", "$1"),
+    (r"\*\*([^*]+)\*\*", "$1"),
+    (r"__([^_]+)__", "$1"),
+    (r"~~([^~]+)~~", "$1"),
+    (r"\*([^*\s][^*]*)\*", "$1"),
+    (r"\b_([^_]+)_\b", "$1"),
+  ]
+  .into_iter()
+  .map(|(pattern, replacement)| {
+    let regex = Regex::new(pattern).expect("INLINE_MARKDOWN is malformed — bug in agentflow-nodes");
+    (regex, replacement)
+  })
+  .collect()
+});
+
+#[allow(
+  clippy::expect_used,
+  reason = "compile-time regex literals; covered by unit tests in module"
+)]
+static LINE_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(\s*>\s?)*\s*(#{1,6}\s+|[-*+]\s+|\d+[.)]\s+)?")
+    .expect("LINE_PREFIX is malformed — bug in agentflow-nodes")
+});
+
+fn is_rule(line: &str) -> bool {
+  let marks: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+  marks.len() >= 3
+    && ['-', '*', '_']
+      .iter()
+      .any(|mark| marks.chars().all(|c| c == *mark))
+}
+
+fn strip_markdown(text: &str) -> String {
+  let mut lines = Vec::new();
+  let mut fence_open: Option<(char, usize)> = None;
+  for line in text.lines() {
+    if let Some((marker, length)) = fence_open {
+      if closes(line, marker, length) {
+        fence_open = None;
+      } else {
+        lines.push(line.to_string());
+      }
+      continue;
+    }
+    if let Some((marker, length, _)) = fence(line) {
+      fence_open = Some((marker, length));
+      continue;
+    }
+    if is_rule(line) {
+      continue;
+    }
+    let heading = LINE_PREFIX
+      .captures(line)
+      .and_then(|captures| captures.get(2))
+      .is_some_and(|prefix| prefix.as_str().starts_with('#'));
+    let line = LINE_PREFIX.replace(line, "");
+    let mut line = line.trim_end();
+    if heading {
+      // An ATX closing sequence is a run of `#` after a space; `C#`
+      // keeps its hash.
+      let unclosed = line.trim_end_matches('#');
+      if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        line = unclosed.trim_end();
+      }
+    }
+    let line = INLINE_MARKDOWN
+      .iter()
+      .fold(line.to_string(), |line, (regex, replacement)| {
+        regex.replace_all(&line, *replacement).into_owned()
+      });
+    lines.push(line);
+  }
+  BLANK_LINES
+    .replace_all(lines.join("\n").trim(), "\n\n")
+    .into_owned()
+}
+
+#[async_trait]
+impl AsyncNode for TextExtractNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let text = inputs
+      .get(&self.input_key)
+      .map(flow_value_to_string)
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!(
+          "text_extract '{}' requires a '{}' input",
+          self.name, self.input_key
+        ),
+      })?;
+    let extraction = self.extract(&text)?;
+    Ok(HashMap::from([
+      (
+        "extracted".to_string(),
+        FlowValue::Json(extraction.extracted),
+      ),
+      (
+        "match_count".to_string(),
+        FlowValue::Json(json!(extraction.match_count)),
+      ),
+      ("text".to_string(), FlowValue::Json(json!(extraction.text))),
+    ]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn node(operations: Value) -> TextExtractNode {
+    TextExtractNode::from_params("post", &json!({ "operations": operations })).unwrap()
+  }
+
+  #[test]
+  fn extracts_nested_json_from_a_chatty_answer() {
+    let answer = "Sure! I looked into it {briefly}, and here is the result:\n\n\
+      ```json\n\
+      {\n  \"user\": {\"name\": \"Ada\", \"langs\": [\"en\", \"fr\",],},\n  \"note\": \"braces } in [strings] are fine\",\n  \"ok\": true\n}\n\
+      ```\n\n\
+      Let me know if you need anything else.";
+
+    let extraction = node(json!(["extract_json"])).extract(answer).unwrap();
+
+    assert_eq!(
+      extraction.extracted,
+      json!({
+        "user": { "name": "Ada", "langs": ["en", "fr"] },
+        "note": "braces } in [strings] are fine",
+        "ok": true
+      })
+    );
+    assert_eq!(extraction.match_count, 1);
+    assert!(!extraction.text.contains("Ada"), "{}", extraction.text);
+    assert!(extraction.text.contains("{briefly}"), "{}", extraction.text);
+
+    let none = node(json!(["extract_json"]))
+      .extract("No data today.")
+      .unwrap();
+    assert_eq!(none.extracted, Value::Null);
+    assert_eq!(none.match_count, 0);
+    let strict = TextExtractNode::from_params(
+      "post",
+      &json!({ "on_missing": "error", "operations": ["extract_json"] }),
+    )
+    .unwrap();
+    let err = strict.extract("No data today.").unwrap_err();
+    assert!(
+      err.to_string().contains("extract_json found nothing"),
+      "{err}"
+    );
+  }
+
+  #[test]
+  fn captures_every_match_with_named_groups() {
+    let text = "Scores: alice=91, bob=78, carol=85.";
+
+    let named = node(json!([{
+      "regex_capture": { "pattern": r"(?P<name>[a-z]+)=(?P<score>\d+)", "all": true }
+    }]))
+    .extract(text)
+    .unwrap();
+    assert_eq!(
+      named.extracted,
+      json!([
+        { "name": "alice", "score": "91" },
+        { "name": "bob", "score": "78" },
+        { "name": "carol", "score": "85" }
+      ])
+    );
+    assert_eq!(named.match_count, 3);
+    assert_eq!(named.text, "Scores: , , .");
+
+    let first = node(json!([{ "regex_capture": r"(\d+)" }]))
+      .extract(text)
+      .unwrap();
+    assert_eq!(first.extracted, json!("91"));
+    assert_eq!(first.match_count, 1);
+  }
+
+  #[test]
+  fn filters_code_blocks_by_language_and_keeps_the_residual() {
+    let answer = "Here is the script:\n\n```python\nprint('hi')\n```\n\n\
+      And the config:\n\n~~~toml\nname = \"demo\"\n~~~\n\nDone.";
+
+    let extraction = node(json!([{ "extract_code_blocks": { "language": ["Python"] } }]))
+      .extract(answer)
+      .unwrap();
+
+    assert_eq!(
+      extraction.extracted,
+      json!([{ "language": "python", "code": "print('hi')" }])
+    );
+    assert!(
+      extraction
+        .text
+        .starts_with("Here is the script:\n\nAnd the config:")
+    );
+    assert!(extraction.text.contains("~~~toml"), "{}", extraction.text);
+
+    let all = node(json!(["extract_code_blocks"]))
+      .extract(answer)
+      .unwrap();
+    assert_eq!(all.match_count, 2);
+    assert_eq!(all.text, "Here is the script:\n\nAnd the config:\n\nDone.");
+  }
+
+  #[tokio::test]
+  async fn chains_cleanup_operations_into_a_pipeline() {
+    let markdown = "# Summary\n\n> **Bold** claim with a [link](https://example.com).\n\n\
+      - first *point*\n- second `point`\n\n---\n\nSee ![chart](c.png) v1.2.3.";
+    let pipeline = node(json!([
+      "strip_markdown",
+      { "replace": { "pattern": r"v(\d+)\.(\d+)\.\d+", "with": "v$1.$2" } },
+      { "regex_capture": r"v\d+\.\d+" }
+    ]));
+
+    let outputs = pipeline
+      .execute(&HashMap::from([(
+        "text".to_string(),
+        FlowValue::Json(json!(markdown)),
+      )]))
+      .await
+      .unwrap();
+
+    assert_eq!(outputs["extracted"], FlowValue::Json(json!("v1.2")));
+    assert_eq!(
+      outputs["text"],
+      FlowValue::Json(json!(
+        "Summary\n\nBold claim with a link.\n\nfirst point\nsecond point\n\nSee chart ."
+      ))
+    );
+  }
+
+  #[test]
+  fn closing_hashes_are_only_stripped_from_headings() {
+    assert_eq!(
+      strip_markdown("## Learn C# ##\n# F#\nI like C#\n- C# #"),
+      "Learn C#\nF#\nI like C#\nC# #"
+    );
+  }
+
+  #[test]
+  fn json_scan_is_linear_in_unbalanced_text() {
+    let noise = "{[".repeat(50_000);
+    let text = format!("{noise} then {{\"ok\": [1, 2,]}}");
+    let started = std::time::Instant::now();
+    let (values, spans) = json_values(&text, true);
+    assert_eq!(values, [json!({"ok": [1, 2]})]);
+    assert_eq!(spans.len(), 1);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+  }
+
+  #[test]
+  fn invalid_pipelines_fail_at_construction() {
+    for params in [
+      json!({ "operations": [{ "regex_capture": "(unclosed" }] }),
+      json!({ "operations": [{ "replace": { "with": "x" } }] }),
+      json!({ "operations": [{ "extract_code_blocks": { "language": 3 } }] }),
+      json!({ "operations": ["summarize"] }),
+      json!({ "operations": [] }),
+    ] {
+      let err = TextExtractNode::from_params("bad", &params).unwrap_err();
+      assert!(
+        matches!(err, AgentFlowError::ConfigurationError { .. }),
+        "{params}: {err}"
+      );
+    }
+  }
+}
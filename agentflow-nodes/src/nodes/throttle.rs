@@ -0,0 +1,327 @@
+//! Throttle node - enforces a minimum interval between executions.
+//!
+//! Every throttle node sharing a `group` is serialized: an execution waits
+//! until `min_interval` has passed since the group's previous one, then
+//! records its own start time. By default that timestamp is persisted to
+//! `~/.agentflow/throttle/<group>.json`, so spacing holds across runs and
+//! processes as well as between nodes of one run; `in_memory()` keeps it
+//! per process. Processes sharing a state directory serialize on an
+//! advisory lock of `<group>.lock`. Group names are sanitized into file
+//! names, with a hash suffix whenever sanitizing changed them so distinct
+//! groups never share a file. The wait is cancellable and bounded by flow
+//! deadlines.
+//!
+//! Inputs pass through unchanged; the node adds `waited_ms` and `group`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::common::clock::{Clock, SystemClock};
+use crate::nodes::delay::cancellable_sleep;
+
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+type GroupSlot = Arc<tokio::sync::Mutex<Option<DateTime<Utc>>>>;
+
+/// One slot per group (and state directory), holding the in-memory
+/// timestamp and serializing executions within this process.
+static GROUPS: LazyLock<Mutex<HashMap<String, GroupSlot>>> =
+  LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ThrottleState {
+  last_run: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThrottleNode {
+  pub name: String,
+  pub group: String,
+  pub min_interval: Duration,
+  /// Where `<group>.json` lives; `None` keeps the timestamp in memory.
+  pub state_dir: Option<PathBuf>,
+  clock: Arc<dyn Clock>,
+}
+
+impl ThrottleNode {
+  pub fn new(name: &str, group: &str, min_interval: Duration) -> Self {
+    Self {
+      name: name.to_string(),
+      group: group.to_string(),
+      min_interval,
+      state_dir: dirs::home_dir().map(|home| home.join(".agentflow").join("throttle")),
+      clock: Arc::new(SystemClock),
+    }
+  }
+
+  pub fn with_state_dir(mut self, state_dir: impl Into<PathBuf>) -> Self {
+    self.state_dir = Some(state_dir.into());
+    self
+  }
+
+  /// Keeps the group timestamp in this process only.
+  pub fn in_memory(mut self) -> Self {
+    self.state_dir = None;
+    self
+  }
+
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  fn state_path(&self) -> Option<PathBuf> {
+    let mut file_name: String = self
+      .group
+      .chars()
+      .map(|c| {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+          c
+        } else {
+          '_'
+        }
+      })
+      .collect();
+    if file_name != self.group {
+      // `a/b` and `a_b` sanitize alike; the hash of the raw name keeps
+      // their files apart.
+      file_name = format!("{file_name}-{:016x}", fnv1a(self.group.as_bytes()));
+    }
+    self
+      .state_dir
+      .as_ref()
+      .map(|dir| dir.join(format!("{file_name}.json")))
+  }
+
+  fn slot(&self) -> GroupSlot {
+    let key = match self.state_path() {
+      Some(path) => path.display().to_string(),
+      None => format!("memory:{}", self.group),
+    };
+    let mut groups = GROUPS.lock().unwrap_or_else(|e| e.into_inner());
+    groups.entry(key).or_default().clone()
+  }
+
+  /// Takes the cross-process lock on the group's state, polling so the
+  /// wait stays cancellable. `None` for in-memory groups.
+  async fn lock_state(&self) -> Result<Option<std::fs::File>, AgentFlowError> {
+    let Some(path) = self.state_path() else {
+      return Ok(None);
+    };
+    let lock_path = path.with_extension("lock");
+    let io_error = |e: std::io::Error| AgentFlowError::PersistenceError {
+      message: format!("failed to lock throttle state {}: {e}", lock_path.display()),
+    };
+    if let Some(parent) = lock_path.parent() {
+      tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+    }
+    let file = tokio::fs::OpenOptions::new()
+      .create(true)
+      .truncate(false)
+      .write(true)
+      .open(&lock_path)
+      .await
+      .map_err(io_error)?
+      .into_std()
+      .await;
+    loop {
+      match file.try_lock() {
+        Ok(()) => return Ok(Some(file)),
+        Err(std::fs::TryLockError::WouldBlock) => cancellable_sleep(LOCK_POLL_INTERVAL).await?,
+        Err(std::fs::TryLockError::Error(e)) => return Err(io_error(e)),
+      }
+    }
+  }
+
+  async fn load_last_run(&self) -> Option<DateTime<Utc>> {
+    let text = tokio::fs::read_to_string(self.state_path()?).await.ok()?;
+    match serde_json::from_str::<ThrottleState>(&text) {
+      Ok(state) => Some(state.last_run),
+      Err(e) => {
+        eprintln!(
+          "⚠️  Throttle '{}' ignoring unreadable state for group '{}': {}",
+          self.name, self.group, e
+        );
+        None
+      }
+    }
+  }
+
+  async fn store_last_run(&self, last_run: DateTime<Utc>) -> Result<(), AgentFlowError> {
+    let Some(path) = self.state_path() else {
+      return Ok(());
+    };
+    let io_error = |e: std::io::Error| AgentFlowError::PersistenceError {
+      message: format!("failed to write throttle state {}: {e}", path.display()),
+    };
+    if let Some(parent) = path.parent() {
+      tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+    }
+    let state = serde_json::to_vec(&ThrottleState { last_run })?;
+    tokio::fs::write(&path, state).await.map_err(io_error)
+  }
+}
+
+#[async_trait]
+impl AsyncNode for ThrottleNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let slot = self.slot();
+    let mut last_run = slot.lock().await;
+    // Released on drop, after the new timestamp is stored.
+    let _state_lock = self.lock_state().await?;
+    let previous = match self.state_dir {
+      Some(_) => self.load_last_run().await.max(*last_run),
+      None => *last_run,
+    };
+
+    let now = self.clock.now();
+    let next_allowed = match previous {
+      Some(previous) => Some(
+        chrono::TimeDelta::from_std(self.min_interval)
+          .ok()
+          .and_then(|interval| previous.checked_add_signed(interval))
+          .ok_or_else(|| AgentFlowError::ConfigurationError {
+            message: format!(
+              "Throttle '{}': min_interval of {}ms is out of range",
+              self.name,
+              self.min_interval.as_millis()
+            ),
+          })?,
+      ),
+      None => None,
+    };
+    let wait = next_allowed
+      .and_then(|next_allowed| (next_allowed - now).to_std().ok())
+      .unwrap_or_default();
+    if !wait.is_zero() {
+      println!(
+        "⏳ Throttle '{}' waiting {}ms for group '{}'",
+        self.name,
+        wait.as_millis(),
+        self.group
+      );
+      cancellable_sleep(wait).await?;
+    }
+
+    let ran_at = now + wait;
+    *last_run = Some(ran_at);
+    self.store_last_run(ran_at).await?;
+
+    let mut outputs = inputs.clone();
+    outputs.insert(
+      "waited_ms".to_string(),
+      FlowValue::Json(json!(wait.as_millis() as u64)),
+    );
+    outputs.insert("group".to_string(), FlowValue::Json(json!(self.group)));
+    Ok(outputs)
+  }
+}
+
+/// Stable across processes and releases, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+    (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  #[derive(Debug)]
+  struct FixedClock(DateTime<Utc>);
+
+  impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+      self.0
+    }
+  }
+
+  #[tokio::test]
+  async fn spaces_successive_executions_in_a_group() {
+    let interval = Duration::from_millis(60);
+    let first = ThrottleNode::new("first", "throttle-test-spacing", interval).in_memory();
+    let second = ThrottleNode::new("second", "throttle-test-spacing", interval).in_memory();
+    let inputs = AsyncNodeInputs::new();
+
+    let started = tokio::time::Instant::now();
+    let outputs = first.execute(&inputs).await.unwrap();
+    assert_eq!(outputs["waited_ms"], FlowValue::Json(json!(0)));
+
+    // Concurrent executions queue behind each other.
+    let (a, b) = tokio::join!(first.execute(&inputs), second.execute(&inputs));
+    a.unwrap();
+    b.unwrap();
+    assert!(
+      started.elapsed() >= Duration::from_millis(110),
+      "{:?}",
+      started.elapsed()
+    );
+
+    let other = ThrottleNode::new("other", "throttle-test-other", interval).in_memory();
+    let outputs = other.execute(&inputs).await.unwrap();
+    assert_eq!(outputs["waited_ms"], FlowValue::Json(json!(0)));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn persists_the_last_run_across_processes() {
+    let dir = tempfile::tempdir().unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+    let previous = now - chrono::Duration::minutes(10);
+    std::fs::write(
+      dir.path().join("api.json"),
+      serde_json::to_vec(&ThrottleState { last_run: previous }).unwrap(),
+    )
+    .unwrap();
+
+    let node = ThrottleNode::new("limit", "api", Duration::from_secs(30 * 60))
+      .with_state_dir(dir.path())
+      .with_clock(Arc::new(FixedClock(now)));
+    let outputs = node.execute(&AsyncNodeInputs::new()).await.unwrap();
+    assert_eq!(outputs["waited_ms"], FlowValue::Json(json!(20 * 60 * 1000)));
+    assert_eq!(outputs["group"], FlowValue::Json(json!("api")));
+
+    let state: ThrottleState =
+      serde_json::from_slice(&std::fs::read(dir.path().join("api.json")).unwrap()).unwrap();
+    assert_eq!(state.last_run, now + chrono::Duration::minutes(20));
+  }
+
+  #[test]
+  fn sanitized_group_names_do_not_collide() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = |group: &str| {
+      ThrottleNode::new("t", group, Duration::ZERO)
+        .with_state_dir(dir.path())
+        .state_path()
+        .unwrap()
+    };
+    assert_eq!(path("api"), dir.path().join("api.json"));
+    assert_ne!(path("a/b"), path("a_b"));
+    assert_ne!(path("a/b"), path("a b"));
+  }
+
+  #[tokio::test]
+  async fn an_oversized_interval_is_an_error_not_a_panic() {
+    let dir = tempfile::tempdir().unwrap();
+    let node = ThrottleNode::new("huge", "huge", Duration::MAX).with_state_dir(dir.path());
+    node.execute(&AsyncNodeInputs::new()).await.unwrap();
+    let err = node.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::ConfigurationError { .. }),
+      "{err}"
+    );
+  }
+}
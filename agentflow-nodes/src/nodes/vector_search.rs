@@ -0,0 +1,719 @@
+//! Vector similarity search node.
+//!
+//! `VectorSearchNode` ranks a corpus of embedded records against a query
+//! vector by cosine similarity, entirely in-process. The corpus is one of:
+//!
+//! - the `corpus_key` input (default `corpus`): an array of
+//!   `{id, text, vector}` objects, e.g. the `records` output of the
+//!   `embedding` node;
+//! - `corpus_path`: a JSONL file with one such object per line;
+//! - `index_path`: a [`VectorIndex`] file. When the file exists it is
+//!   memory-mapped; when it doesn't, the index is built from one of the
+//!   sources above and saved there, so later runs skip re-embedding. The
+//!   file records a hash of the corpus it was built from: when a corpus is
+//!   supplied and its hash differs, the index is rebuilt and replaced. With
+//!   no corpus supplied, an existing index is used as-is.
+//!
+//! Outputs `matches` (`[{rank, id, text, score}]`, best first), plus the
+//! parallel `ids` and `scores` lists.
+//!
+//! ## Index file format
+//!
+//! ```text
+//! magic "AFVECIX2" | dimension u32 | reserved u32 | count u64 | meta_len u64
+//! corpus_hash u64 | metadata JSON ([{id, text}], meta_len bytes) | zero padding to 4 bytes
+//! count × dimension little-endian f32
+//! ```
+//!
+//! `AFVECIX1` files, which have no `corpus_hash` (a 32-byte header), still
+//! load; they count as stale whenever a corpus is supplied.
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use memmap2::Mmap;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const INDEX_MAGIC: &[u8; 8] = b"AFVECIX2";
+const INDEX_MAGIC_V1: &[u8; 8] = b"AFVECIX1";
+const HEADER_LEN: usize = 40;
+const HEADER_LEN_V1: usize = 32;
+
+/// One embedded corpus entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorRecord {
+  pub id: String,
+  pub text: String,
+  pub vector: Vec<f32>,
+}
+
+impl VectorRecord {
+  /// Parses `{id, text, vector}`; a missing `id` falls back to `position`.
+  pub fn from_json(value: &Value, position: usize) -> Result<Self, AgentFlowError> {
+    let object = value
+      .as_object()
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!("Corpus entry {} is not an object", position),
+      })?;
+    let id = match object.get("id") {
+      Some(Value::String(id)) => id.clone(),
+      Some(Value::Number(id)) => id.to_string(),
+      _ => position.to_string(),
+    };
+    let text = object
+      .get("text")
+      .and_then(Value::as_str)
+      .unwrap_or_default()
+      .to_string();
+    let vector = object
+      .get("vector")
+      .ok_or_else(|| AgentFlowError::NodeInputError {
+        message: format!("Corpus entry '{}' has no 'vector'", id),
+      })
+      .and_then(|v| parse_vector(v, &format!("corpus entry '{}'", id)))?;
+    Ok(Self { id, text, vector })
+  }
+}
+
+/// A scored corpus entry returned by [`VectorIndex::search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+  pub id: String,
+  pub text: String,
+  pub score: f32,
+}
+
+#[derive(Debug)]
+enum VectorData {
+  Owned(Vec<f32>),
+  /// Row-major little-endian `f32`s starting at `offset` in the mapping.
+  Mapped {
+    map: Mmap,
+    offset: usize,
+  },
+}
+
+/// A flat (brute-force) cosine-similarity index.
+#[derive(Debug)]
+pub struct VectorIndex {
+  dimension: usize,
+  ids: Vec<String>,
+  texts: Vec<String>,
+  data: VectorData,
+  /// [`corpus_hash`] of the records the index was built from; `None` for
+  /// an index file that predates it.
+  corpus_hash: Option<u64>,
+}
+
+impl VectorIndex {
+  /// Builds an in-memory index; every vector must have the same dimension.
+  pub fn from_records(records: Vec<VectorRecord>) -> Result<Self, AgentFlowError> {
+    let hash = corpus_hash(&records);
+    let dimension = records.first().map_or(0, |record| record.vector.len());
+    let mut ids = Vec::with_capacity(records.len());
+    let mut texts = Vec::with_capacity(records.len());
+    let mut data = Vec::with_capacity(records.len() * dimension);
+    for record in records {
+      if record.vector.len() != dimension {
+        return Err(AgentFlowError::NodeInputError {
+          message: format!(
+            "Corpus entry '{}' has dimension {}, expected {}",
+            record.id,
+            record.vector.len(),
+            dimension
+          ),
+        });
+      }
+      ids.push(record.id);
+      texts.push(record.text);
+      data.extend(record.vector);
+    }
+    Ok(Self {
+      dimension,
+      ids,
+      texts,
+      data: VectorData::Owned(data),
+      corpus_hash: Some(hash),
+    })
+  }
+
+  pub fn len(&self) -> usize {
+    self.ids.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.ids.is_empty()
+  }
+
+  pub fn dimension(&self) -> usize {
+    self.dimension
+  }
+
+  /// Hash of the corpus the index was built from, see [`corpus_hash`].
+  pub fn corpus_hash(&self) -> Option<u64> {
+    self.corpus_hash
+  }
+
+  /// Whether the vectors are read from a memory-mapped file.
+  pub fn is_mapped(&self) -> bool {
+    matches!(self.data, VectorData::Mapped { .. })
+  }
+
+  /// Copies out row `index`.
+  pub fn vector(&self, index: usize) -> Vec<f32> {
+    let mut row = Vec::with_capacity(self.dimension);
+    self.read_row(index, &mut row);
+    row
+  }
+
+  fn read_row(&self, index: usize, row: &mut Vec<f32>) {
+    row.clear();
+    let start = index * self.dimension;
+    match &self.data {
+      VectorData::Owned(data) => row.extend_from_slice(&data[start..start + self.dimension]),
+      VectorData::Mapped { map, offset } => {
+        let bytes = &map[offset + start * 4..offset + (start + self.dimension) * 4];
+        row.extend(
+          bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        );
+      }
+    }
+  }
+
+  /// The `top_k` entries most similar to `query`, best first. Ties keep
+  /// corpus order; entries scoring below `min_score` are dropped.
+  pub fn search(
+    &self,
+    query: &[f32],
+    top_k: usize,
+    min_score: Option<f32>,
+  ) -> Result<Vec<SearchMatch>, AgentFlowError> {
+    if !self.is_empty() && query.len() != self.dimension {
+      return Err(AgentFlowError::NodeInputError {
+        message: format!(
+          "Query vector has dimension {}, index has {}",
+          query.len(),
+          self.dimension
+        ),
+      });
+    }
+    let mut row = Vec::with_capacity(self.dimension);
+    let mut scored: Vec<(usize, f32)> = (0..self.len())
+      .map(|index| {
+        self.read_row(index, &mut row);
+        (index, cosine_similarity(query, &row))
+      })
+      .filter(|(_, score)| min_score.is_none_or(|min| *score >= min))
+      .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.truncate(top_k);
+    Ok(
+      scored
+        .into_iter()
+        .map(|(index, score)| SearchMatch {
+          id: self.ids[index].clone(),
+          text: self.texts[index].clone(),
+          score,
+        })
+        .collect(),
+    )
+  }
+
+  /// Writes the index to `path` (via a temporary file, then rename).
+  pub fn save(&self, path: &Path) -> Result<(), AgentFlowError> {
+    let io_error = |e: std::io::Error| AgentFlowError::AsyncExecutionError {
+      message: format!("Failed to write vector index {:?}: {}", path, e),
+    };
+    let metadata: Vec<Value> = self
+      .ids
+      .iter()
+      .zip(&self.texts)
+      .map(|(id, text)| json!({ "id": id, "text": text }))
+      .collect();
+    let metadata =
+      serde_json::to_vec(&metadata).map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!("Failed to encode vector index metadata: {}", e),
+      })?;
+
+    let mut bytes =
+      Vec::with_capacity(HEADER_LEN + metadata.len() + self.len() * self.dimension * 4);
+    bytes.extend_from_slice(INDEX_MAGIC);
+    bytes.extend_from_slice(&(self.dimension as u32).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&(self.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&(metadata.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&self.corpus_hash.unwrap_or_default().to_le_bytes());
+    bytes.extend_from_slice(&metadata);
+    bytes.resize(bytes.len().next_multiple_of(4), 0);
+    let mut row = Vec::with_capacity(self.dimension);
+    for index in 0..self.len() {
+      self.read_row(index, &mut row);
+      for value in &row {
+        bytes.extend_from_slice(&value.to_le_bytes());
+      }
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+      std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp).map_err(io_error)?;
+    file.write_all(&bytes).map_err(io_error)?;
+    file.sync_all().map_err(io_error)?;
+    std::fs::rename(&tmp, path).map_err(io_error)
+  }
+
+  /// Opens an index written by [`VectorIndex::save`], memory-mapping the
+  /// vectors.
+  pub fn load(path: &Path) -> Result<Self, AgentFlowError> {
+    let invalid = |reason: &str| AgentFlowError::NodeInputError {
+      message: format!("{:?} is not a valid vector index: {}", path, reason),
+    };
+    let file = File::open(path).map_err(|e| AgentFlowError::NodeInputError {
+      message: format!("Failed to open vector index {:?}: {}", path, e),
+    })?;
+    // SAFETY: the mapping is read-only and the index file is only ever
+    // replaced by rename, never rewritten in place.
+    let map = unsafe { Mmap::map(&file) }.map_err(|e| AgentFlowError::NodeInputError {
+      message: format!("Failed to map vector index {:?}: {}", path, e),
+    })?;
+    let header_len = match map.get(..8) {
+      Some(magic) if magic == INDEX_MAGIC => HEADER_LEN,
+      Some(magic) if magic == INDEX_MAGIC_V1 => HEADER_LEN_V1,
+      _ => return Err(invalid("bad header")),
+    };
+    let u64_at = |at: usize| {
+      map
+        .get(at..at + 8)
+        .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(|| invalid("bad header"))
+    };
+    let dimension = map
+      .get(8..12)
+      .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+      .map(u32::from_le_bytes)
+      .ok_or_else(|| invalid("bad header"))? as usize;
+    let count = u64_at(16)? as usize;
+    let meta_len = u64_at(24)? as usize;
+    let corpus_hash = match header_len {
+      HEADER_LEN => Some(u64_at(32)?),
+      _ => None,
+    };
+
+    let meta_end = header_len
+      .checked_add(meta_len)
+      .filter(|end| *end <= map.len())
+      .ok_or_else(|| invalid("truncated metadata"))?;
+    let offset = meta_end.next_multiple_of(4);
+    let expected = count
+      .checked_mul(dimension)
+      .and_then(|n| n.checked_mul(4))
+      .and_then(|n| n.checked_add(offset));
+    if expected != Some(map.len()) {
+      return Err(invalid("vector data size does not match the header"));
+    }
+
+    let metadata: Vec<Value> =
+      serde_json::from_slice(&map[header_len..meta_end]).map_err(|_| invalid("bad metadata"))?;
+    if metadata.len() != count {
+      return Err(invalid("metadata count does not match the header"));
+    }
+    let field = |entry: &Value, key: &str| {
+      entry
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+    };
+    let ids = metadata.iter().map(|entry| field(entry, "id")).collect();
+    let texts = metadata.iter().map(|entry| field(entry, "text")).collect();
+
+    Ok(Self {
+      dimension,
+      ids,
+      texts,
+      data: VectorData::Mapped { map, offset },
+      corpus_hash,
+    })
+  }
+}
+
+/// A stable (FNV-1a) hash of the records' ids, texts and vectors, stored
+/// in a saved index to tell whether it still matches its corpus.
+pub fn corpus_hash(records: &[VectorRecord]) -> u64 {
+  let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+  let mut feed = |bytes: &[u8]| {
+    for byte in bytes {
+      hash ^= u64::from(*byte);
+      hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+  };
+  for record in records {
+    feed(&(record.id.len() as u64).to_le_bytes());
+    feed(record.id.as_bytes());
+    feed(&(record.text.len() as u64).to_le_bytes());
+    feed(record.text.as_bytes());
+    feed(&(record.vector.len() as u64).to_le_bytes());
+    for value in &record.vector {
+      feed(&value.to_le_bytes());
+    }
+  }
+  hash
+}
+
+/// Cosine similarity; `0.0` when either vector has zero length.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+  let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+  for (x, y) in a.iter().zip(b) {
+    dot += x * y;
+    norm_a += x * x;
+    norm_b += y * y;
+  }
+  if norm_a == 0.0 || norm_b == 0.0 {
+    0.0
+  } else {
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+  }
+}
+
+/// Reads `{id, text, vector}` records from a JSONL file (blank lines skipped).
+pub fn load_jsonl_corpus(path: &Path) -> Result<Vec<VectorRecord>, AgentFlowError> {
+  let file = File::open(path).map_err(|e| AgentFlowError::NodeInputError {
+    message: format!("Failed to open corpus {:?}: {}", path, e),
+  })?;
+  let mut records = Vec::new();
+  for (line_no, line) in BufReader::new(file).lines().enumerate() {
+    let line = line.map_err(|e| AgentFlowError::NodeInputError {
+      message: format!("Failed to read corpus {:?}: {}", path, e),
+    })?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let value: Value = serde_json::from_str(&line).map_err(|e| AgentFlowError::NodeInputError {
+      message: format!("{:?} line {}: invalid JSON: {}", path, line_no + 1, e),
+    })?;
+    records.push(VectorRecord::from_json(&value, records.len())?);
+  }
+  Ok(records)
+}
+
+/// A number array, or an array holding exactly one (the `vectors` output of
+/// an embedding node for a single text).
+fn parse_vector(value: &Value, what: &str) -> Result<Vec<f32>, AgentFlowError> {
+  let not_a_vector = || AgentFlowError::NodeInputError {
+    message: format!("{} must be an array of numbers", what),
+  };
+  let items = match value.as_array().map(Vec::as_slice) {
+    Some([Value::Array(inner)]) => inner.as_slice(),
+    Some(items) => items,
+    None => return Err(not_a_vector()),
+  };
+  items
+    .iter()
+    .map(|item| item.as_f64().map(|n| n as f32).ok_or_else(not_a_vector))
+    .collect()
+}
+
+/// Cosine-similarity top-k search over an embedded corpus.
+#[derive(Debug, Clone)]
+pub struct VectorSearchNode {
+  pub name: String,
+  pub query_key: String,
+  pub corpus_key: String,
+  pub corpus_path: Option<PathBuf>,
+  pub index_path: Option<PathBuf>,
+  pub top_k: usize,
+  pub min_score: Option<f32>,
+}
+
+impl VectorSearchNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      query_key: "query".to_string(),
+      corpus_key: "corpus".to_string(),
+      corpus_path: None,
+      index_path: None,
+      top_k: 5,
+      min_score: None,
+    }
+  }
+
+  pub fn with_query_key(mut self, key: &str) -> Self {
+    self.query_key = key.to_string();
+    self
+  }
+
+  pub fn with_corpus_key(mut self, key: &str) -> Self {
+    self.corpus_key = key.to_string();
+    self
+  }
+
+  pub fn with_corpus_path(mut self, path: impl Into<PathBuf>) -> Self {
+    self.corpus_path = Some(path.into());
+    self
+  }
+
+  pub fn with_index_path(mut self, path: impl Into<PathBuf>) -> Self {
+    self.index_path = Some(path.into());
+    self
+  }
+
+  pub fn with_top_k(mut self, top_k: usize) -> Self {
+    self.top_k = top_k;
+    self
+  }
+
+  pub fn with_min_score(mut self, min_score: f32) -> Self {
+    self.min_score = Some(min_score);
+    self
+  }
+
+  /// The supplied corpus, or `None` when there is none: no input and no
+  /// `corpus_path` file on disk.
+  fn corpus_records(
+    &self,
+    inputs: &AsyncNodeInputs,
+  ) -> Result<Option<Vec<VectorRecord>>, AgentFlowError> {
+    if let Some(value) = inputs.get(&self.corpus_key) {
+      let FlowValue::Json(Value::Array(entries)) = value else {
+        return Err(AgentFlowError::NodeInputError {
+          message: format!("Input '{}' must be an array of records", self.corpus_key),
+        });
+      };
+      return entries
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| VectorRecord::from_json(entry, position))
+        .collect::<Result<_, _>>()
+        .map(Some);
+    }
+    match &self.corpus_path {
+      Some(path) if path.exists() => load_jsonl_corpus(path).map(Some),
+      _ => Ok(None),
+    }
+  }
+
+  fn open_index(&self, inputs: &AsyncNodeInputs) -> Result<VectorIndex, AgentFlowError> {
+    let records = self.corpus_records(inputs)?;
+    if let Some(path) = self.index_path.as_deref().filter(|path| path.exists()) {
+      let index = VectorIndex::load(path)?;
+      let current = records
+        .as_deref()
+        .is_none_or(|records| index.corpus_hash() == Some(corpus_hash(records)));
+      if current {
+        println!(
+          "📦 Loaded vector index {} ({} entries)",
+          path.display(),
+          index.len()
+        );
+        return Ok(index);
+      }
+      println!(
+        "♻️  Vector index {} is stale for the supplied corpus; rebuilding",
+        path.display()
+      );
+    }
+    let records = match records {
+      Some(records) => records,
+      None => match &self.corpus_path {
+        // Surfaces the missing file rather than a generic message.
+        Some(path) => load_jsonl_corpus(path)?,
+        None => {
+          return Err(AgentFlowError::NodeInputError {
+            message: format!(
+              "VectorSearch node '{}' needs a '{}' input, corpus_path, or an existing index_path",
+              self.name, self.corpus_key
+            ),
+          });
+        }
+      },
+    };
+    let index = VectorIndex::from_records(records)?;
+    if let Some(path) = &self.index_path {
+      index.save(path)?;
+      println!(
+        "💾 Saved vector index {} ({} entries)",
+        path.display(),
+        index.len()
+      );
+    }
+    Ok(index)
+  }
+}
+
+#[async_trait]
+impl AsyncNode for VectorSearchNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let query = match inputs.get(&self.query_key) {
+      Some(FlowValue::Json(value)) => parse_vector(value, &format!("Input '{}'", self.query_key))?,
+      _ => {
+        return Err(AgentFlowError::NodeInputError {
+          message: format!("Missing query vector input '{}'", self.query_key),
+        });
+      }
+    };
+
+    let index = self.open_index(inputs)?;
+    let matches = index.search(&query, self.top_k, self.min_score)?;
+    println!(
+      "🔎 VectorSearch node '{}': {} of {} entries matched",
+      self.name,
+      matches.len(),
+      index.len()
+    );
+
+    let listed: Vec<Value> = matches
+      .iter()
+      .enumerate()
+      .map(|(rank, m)| json!({ "rank": rank + 1, "id": m.id, "text": m.text, "score": m.score }))
+      .collect();
+    let ids = matches
+      .iter()
+      .map(|m| Value::String(m.id.clone()))
+      .collect();
+    let scores = matches.iter().map(|m| json!(m.score)).collect();
+
+    let mut outputs = HashMap::new();
+    outputs.insert("matches".to_string(), FlowValue::Json(Value::Array(listed)));
+    outputs.insert("ids".to_string(), FlowValue::Json(Value::Array(ids)));
+    outputs.insert("scores".to_string(), FlowValue::Json(Value::Array(scores)));
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record(id: &str, vector: &[f32]) -> VectorRecord {
+    VectorRecord {
+      id: id.to_string(),
+      text: format!("text {id}"),
+      vector: vector.to_vec(),
+    }
+  }
+
+  fn corpus() -> Vec<VectorRecord> {
+    vec![
+      record("east", &[1.0, 0.0]),
+      record("north", &[0.0, 1.0]),
+      record("north-east", &[1.0, 1.0]),
+      record("west", &[-1.0, 0.0]),
+    ]
+  }
+
+  #[test]
+  fn search_orders_by_cosine_similarity() {
+    let index = VectorIndex::from_records(corpus()).unwrap();
+    let matches = index.search(&[1.0, 0.2], 3, None).unwrap();
+    let ids: Vec<_> = matches.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["east", "north-east", "north"]);
+    assert!(matches[0].score > matches[1].score && matches[1].score > matches[2].score);
+
+    let positive = index.search(&[1.0, 0.2], 10, Some(0.0)).unwrap();
+    assert_eq!(positive.len(), 3, "west scores below zero");
+
+    let err = index.search(&[1.0, 0.0, 0.0], 3, None).unwrap_err();
+    assert!(err.to_string().contains("dimension"), "{err}");
+  }
+
+  #[test]
+  fn index_round_trips_through_a_mapped_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested/corpus.afvec");
+    VectorIndex::from_records(corpus())
+      .unwrap()
+      .save(&path)
+      .unwrap();
+
+    let loaded = VectorIndex::load(&path).unwrap();
+    assert!(loaded.is_mapped());
+    assert_eq!((loaded.len(), loaded.dimension()), (4, 2));
+    assert_eq!(loaded.vector(2), vec![1.0, 1.0]);
+    let best = &loaded.search(&[0.0, 2.0], 1, None).unwrap()[0];
+    assert_eq!(
+      (best.id.as_str(), best.text.as_str()),
+      ("north", "text north")
+    );
+
+    std::fs::write(&path, b"not an index").unwrap();
+    assert!(VectorIndex::load(&path).is_err());
+  }
+
+  #[tokio::test]
+  async fn node_builds_index_once_then_reuses_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let corpus_path = dir.path().join("corpus.jsonl");
+    std::fs::write(
+      &corpus_path,
+      "{\"id\": 1, \"text\": \"cats\", \"vector\": [0.9, 0.1]}\n\n\
+       {\"id\": 2, \"text\": \"dogs\", \"vector\": [0.1, 0.9]}\n",
+    )
+    .unwrap();
+    let index_path = dir.path().join("corpus.afvec");
+    let node = VectorSearchNode::new("search")
+      .with_corpus_path(&corpus_path)
+      .with_index_path(&index_path)
+      .with_top_k(1);
+
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert("query".to_string(), FlowValue::Json(json!([[0.2, 0.8]])));
+    let outputs = node.execute(&inputs).await.unwrap();
+    assert!(index_path.exists());
+    assert!(matches!(outputs.get("ids"), Some(FlowValue::Json(ids)) if ids == &json!(["2"])));
+
+    // The saved index answers even once the source corpus is gone.
+    std::fs::remove_file(&corpus_path).unwrap();
+    let outputs = node.execute(&inputs).await.unwrap();
+    let Some(FlowValue::Json(matches)) = outputs.get("matches") else {
+      panic!("missing matches");
+    };
+    assert_eq!(matches[0]["text"], json!("dogs"));
+    assert_eq!(matches[0]["rank"], json!(1));
+  }
+
+  #[tokio::test]
+  async fn node_rebuilds_an_index_built_from_another_corpus() {
+    let dir = tempfile::tempdir().unwrap();
+    let index_path = dir.path().join("corpus.afvec");
+    let node = VectorSearchNode::new("search")
+      .with_index_path(&index_path)
+      .with_top_k(1);
+    let run = |corpus: Value| {
+      let mut inputs = AsyncNodeInputs::new();
+      inputs.insert("query".to_string(), FlowValue::Json(json!([1.0, 0.0])));
+      inputs.insert("corpus".to_string(), FlowValue::Json(corpus));
+      inputs
+    };
+
+    let first = node
+      .execute(&run(
+        json!([{ "id": "old", "text": "old", "vector": [1.0, 0.0] }]),
+      ))
+      .await
+      .unwrap();
+    assert_eq!(first["ids"], FlowValue::Json(json!(["old"])));
+    let built = VectorIndex::load(&index_path).unwrap().corpus_hash();
+    assert!(built.is_some());
+
+    let second = node
+      .execute(&run(
+        json!([{ "id": "new", "text": "new", "vector": [1.0, 0.0] }]),
+      ))
+      .await
+      .unwrap();
+    assert_eq!(second["ids"], FlowValue::Json(json!(["new"])));
+    assert_ne!(VectorIndex::load(&index_path).unwrap().corpus_hash(), built);
+  }
+}
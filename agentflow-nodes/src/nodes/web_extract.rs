@@ -0,0 +1,633 @@
+//! Web extraction node - fetches a page and extracts its readable content.
+//!
+//! The `url` input (with `{{key}}` placeholders and optional `headers`) is
+//! fetched through the sandboxed
+//! [`HttpTool`](agentflow_tools::builtin::HttpTool). Responses whose
+//! `content-type` isn't HTML, or whose declared `content-length` exceeds
+//! `max_bytes`, fail the node from their headers alone; other bodies are
+//! streamed and abandoned as soon as they pass `max_bytes`.
+//!
+//! Main content is picked with a readability-style heuristic: every
+//! paragraph outside navigation, headers, footers, sidebars and
+//! comment/share blocks scores its parent (and half that to its
+//! grandparent) by length and comma count; the best container, discounted
+//! by its link density, wins, falling back to `<body>`. Its text is output
+//! as blank-line separated blocks.
+//!
+//! Outputs `url`, `title` (`og:title`, `<title>` or the first `<h1>`),
+//! `text`, `excerpt`, `word_count` and `metadata` (`description`, `author`,
+//! `published`, `site_name`, `canonical_url`, `image`, `lang` - only those
+//! the page declares).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  value::FlowValue,
+};
+use agentflow_tools::SandboxPolicy;
+use agentflow_tools::builtin::HttpTool;
+use async_trait::async_trait;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde_json::{Map, Value, json};
+
+use crate::common::utils::get_request_from_inputs;
+
+pub const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+const DEFAULT_CONTENT_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
+
+/// Elements whose subtree never contributes content.
+const SKIPPED_TAGS: &[&str] = &[
+  "script", "style", "noscript", "template", "svg", "nav", "header", "footer", "aside", "form",
+  "iframe", "button",
+];
+/// `class` / `id` fragments marking boilerplate containers.
+const UNLIKELY_MARKERS: &[&str] = &[
+  "comment",
+  "sidebar",
+  "footer",
+  "navbar",
+  "menu",
+  "share",
+  "social",
+  "related",
+  "advert",
+  "promo",
+  "cookie",
+  "banner",
+  "breadcrumb",
+];
+/// Elements that start a new text block.
+const BLOCK_TAGS: &[&str] = &[
+  "p",
+  "div",
+  "section",
+  "article",
+  "main",
+  "blockquote",
+  "pre",
+  "ul",
+  "ol",
+  "li",
+  "table",
+  "tr",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "figure",
+  "figcaption",
+  "dl",
+  "dt",
+  "dd",
+  "br",
+  "hr",
+];
+/// Paragraphs shorter than this don't score their containers.
+const MIN_PARAGRAPH_CHARS: usize = 25;
+const EXCERPT_CHARS: usize = 300;
+
+#[derive(Clone)]
+pub struct WebExtractNode {
+  pub name: String,
+  pub max_bytes: usize,
+  pub allowed_content_types: Vec<String>,
+  policy: Arc<SandboxPolicy>,
+  client: Option<reqwest::Client>,
+}
+
+impl std::fmt::Debug for WebExtractNode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("WebExtractNode")
+      .field("name", &self.name)
+      .field("max_bytes", &self.max_bytes)
+      .field("allowed_content_types", &self.allowed_content_types)
+      .finish()
+  }
+}
+
+impl WebExtractNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      max_bytes: DEFAULT_MAX_BYTES,
+      allowed_content_types: DEFAULT_CONTENT_TYPES
+        .iter()
+        .map(|t| t.to_string())
+        .collect(),
+      policy: Arc::new(SandboxPolicy::default()),
+      client: None,
+    }
+  }
+
+  pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+    self.max_bytes = max_bytes;
+    self
+  }
+
+  /// MIME types (without parameters) the response may declare.
+  pub fn with_allowed_content_types(mut self, types: Vec<String>) -> Self {
+    self.allowed_content_types = types;
+    self
+  }
+
+  pub fn with_policy(mut self, policy: Arc<SandboxPolicy>) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Use a pre-built reqwest client (e.g. `.no_proxy()` for loopback tests).
+  pub fn with_client(mut self, client: reqwest::Client) -> Self {
+    self.client = Some(client);
+    self
+  }
+
+  fn tool(&self) -> Result<HttpTool, AgentFlowError> {
+    match &self.client {
+      Some(client) => Ok(HttpTool::with_client(client.clone(), self.policy.clone())),
+      None => {
+        HttpTool::new(self.policy.clone()).map_err(|err| AgentFlowError::AsyncExecutionError {
+          message: format!("WebExtractNode failed to build HTTP client: {err}"),
+        })
+      }
+    }
+  }
+
+  fn guard_error(&self, url: &str, reason: String) -> AgentFlowError {
+    AgentFlowError::NodeInputError {
+      message: format!("Web extract node '{}': {}: {}", self.name, url, reason),
+    }
+  }
+}
+
+/// The readable content of an HTML document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedPage {
+  pub title: Option<String>,
+  pub text: String,
+  pub excerpt: String,
+  pub metadata: Map<String, Value>,
+}
+
+/// Extracts title, main text and metadata from an HTML document.
+pub fn extract_page(html: &str) -> ExtractedPage {
+  let document = Html::parse_document(html);
+  let metadata = page_metadata(&document);
+
+  let text_of = |selector: &str| {
+    select(&document, selector)
+      .into_iter()
+      .next()
+      .map(|element| collapse(&element.text().collect::<String>()))
+      .filter(|text| !text.is_empty())
+  };
+  let title = meta_content(&document, "property", "og:title")
+    .or_else(|| text_of("title"))
+    .or_else(|| text_of("h1"));
+
+  let blocks = main_content(&document).map(text_blocks).unwrap_or_default();
+  let text = blocks.join("\n\n");
+  let excerpt = metadata
+    .get("description")
+    .and_then(Value::as_str)
+    .map(str::to_string)
+    .or_else(|| {
+      blocks
+        .iter()
+        .find(|block| block.len() >= MIN_PARAGRAPH_CHARS)
+        .cloned()
+    })
+    .unwrap_or_default();
+  let excerpt = match excerpt.char_indices().nth(EXCERPT_CHARS) {
+    Some((cut, _)) => format!("{}…", excerpt[..cut].trim_end()),
+    None => excerpt,
+  };
+
+  ExtractedPage {
+    title,
+    text,
+    excerpt,
+    metadata,
+  }
+}
+
+/// Elements of `document` matching `selector`; one that doesn't parse
+/// matches nothing.
+fn select<'a>(document: &'a Html, selector: &str) -> Vec<ElementRef<'a>> {
+  Selector::parse(selector)
+    .map(|selector| document.select(&selector).collect())
+    .unwrap_or_default()
+}
+
+/// [`select`] within `root`.
+fn select_in<'a>(root: ElementRef<'a>, selector: &str) -> Vec<ElementRef<'a>> {
+  Selector::parse(selector)
+    .map(|selector| root.select(&selector).collect())
+    .unwrap_or_default()
+}
+
+fn collapse(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn meta_content(document: &Html, attribute: &str, value: &str) -> Option<String> {
+  select(document, &format!("meta[{}=\"{}\"]", attribute, value))
+    .into_iter()
+    .filter_map(|meta| meta.value().attr("content"))
+    .map(collapse)
+    .find(|content| !content.is_empty())
+}
+
+fn page_metadata(document: &Html) -> Map<String, Value> {
+  let mut metadata = Map::new();
+  let mut put = |key: &str, value: Option<String>| {
+    if let Some(value) = value {
+      metadata.insert(key.to_string(), Value::String(value));
+    }
+  };
+  put(
+    "description",
+    meta_content(document, "name", "description")
+      .or_else(|| meta_content(document, "property", "og:description")),
+  );
+  put(
+    "author",
+    meta_content(document, "name", "author")
+      .or_else(|| meta_content(document, "property", "article:author")),
+  );
+  put(
+    "published",
+    meta_content(document, "property", "article:published_time")
+      .or_else(|| meta_content(document, "name", "date"))
+      .or_else(|| {
+        select(document, "time[datetime]")
+          .into_iter()
+          .next()
+          .and_then(|time| time.value().attr("datetime").map(str::to_string))
+      }),
+  );
+  put(
+    "site_name",
+    meta_content(document, "property", "og:site_name"),
+  );
+  put(
+    "canonical_url",
+    select(document, "link[rel=\"canonical\"]")
+      .into_iter()
+      .next()
+      .and_then(|link| link.value().attr("href").map(str::to_string)),
+  );
+  put("image", meta_content(document, "property", "og:image"));
+  put(
+    "lang",
+    select(document, "html")
+      .into_iter()
+      .next()
+      .and_then(|html| html.value().attr("lang").map(str::to_string)),
+  );
+  metadata
+}
+
+/// Whether `element` is boilerplate by tag, `class` or `id`.
+fn is_unlikely(element: ElementRef) -> bool {
+  let value = element.value();
+  if SKIPPED_TAGS.contains(&value.name()) || value.attr("role") == Some("navigation") {
+    return true;
+  }
+  let markers = format!(
+    "{} {}",
+    value.attr("class").unwrap_or_default(),
+    value.attr("id").unwrap_or_default()
+  )
+  .to_ascii_lowercase();
+  UNLIKELY_MARKERS
+    .iter()
+    .any(|marker| markers.contains(marker))
+}
+
+fn in_unlikely_subtree(element: ElementRef) -> bool {
+  std::iter::once(element)
+    .chain(element.ancestors().filter_map(ElementRef::wrap))
+    .any(is_unlikely)
+}
+
+fn main_content(document: &Html) -> Option<ElementRef<'_>> {
+  let mut scores: HashMap<ego_tree::NodeId, (ElementRef, f64)> = HashMap::new();
+  for paragraph in select(document, "p, pre, blockquote, td") {
+    if in_unlikely_subtree(paragraph) {
+      continue;
+    }
+    let text = collapse(&paragraph.text().collect::<String>());
+    if text.len() < MIN_PARAGRAPH_CHARS {
+      continue;
+    }
+    let score = 1.0 + text.matches(',').count() as f64 + (text.len() as f64 / 100.0).min(3.0);
+    let mut ancestors = paragraph.ancestors().filter_map(ElementRef::wrap);
+    for weight in [1.0, 0.5] {
+      let Some(ancestor) = ancestors.next() else {
+        break;
+      };
+      scores.entry(ancestor.id()).or_insert((ancestor, 0.0)).1 += score * weight;
+    }
+  }
+
+  scores
+    .into_values()
+    .map(|(element, score)| (element, score * (1.0 - link_density(element))))
+    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    .map(|(element, _)| element)
+    .or_else(|| select(document, "body").into_iter().next())
+}
+
+fn link_density(element: ElementRef) -> f64 {
+  let total = collapse(&element.text().collect::<String>()).len();
+  if total == 0 {
+    return 0.0;
+  }
+  let links: usize = select_in(element, "a")
+    .into_iter()
+    .map(|link| collapse(&link.text().collect::<String>()).len())
+    .sum();
+  links as f64 / total as f64
+}
+
+/// Text of `root` split into blocks at block-level elements.
+fn text_blocks(root: ElementRef) -> Vec<String> {
+  fn walk(node: ego_tree::NodeRef<Node>, current: &mut String, blocks: &mut Vec<String>) {
+    match node.value() {
+      Node::Text(text) => current.push_str(text),
+      Node::Element(element) => {
+        if ElementRef::wrap(node).is_some_and(is_unlikely) {
+          return;
+        }
+        let block = BLOCK_TAGS.contains(&element.name());
+        if block {
+          flush(current, blocks);
+        }
+        for child in node.children() {
+          walk(child, current, blocks);
+        }
+        if block {
+          flush(current, blocks);
+        }
+      }
+      _ => {}
+    }
+  }
+  fn flush(current: &mut String, blocks: &mut Vec<String>) {
+    let text = collapse(current);
+    if !text.is_empty() {
+      blocks.push(text);
+    }
+    current.clear();
+  }
+
+  let mut blocks = Vec::new();
+  let mut current = String::new();
+  for child in root.children() {
+    walk(child, &mut current, &mut blocks);
+  }
+  flush(&mut current, &mut blocks);
+  blocks
+}
+
+#[async_trait]
+impl AsyncNode for WebExtractNode {
+  async fn execute(&self, inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let request = get_request_from_inputs(&format!("Web extract node '{}'", self.name), inputs)?;
+    let url = request.url.clone();
+    let mut response = self.tool()?.send_response(request).await.map_err(|err| {
+      AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Web extract node '{}': failed to fetch {}: {}",
+          self.name, url, err
+        ),
+      }
+    })?;
+    if !response.status().is_success() {
+      return Err(AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Web extract node '{}': {} returned HTTP {}",
+          self.name,
+          url,
+          response.status().as_u16()
+        ),
+      });
+    }
+
+    let header = |name: &str| {
+      response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    };
+    let content_type = header("content-type")
+      .map(|value| {
+        value
+          .split(';')
+          .next()
+          .unwrap_or_default()
+          .trim()
+          .to_ascii_lowercase()
+      })
+      .unwrap_or_default();
+    if !content_type.is_empty()
+      && !self
+        .allowed_content_types
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(&content_type))
+    {
+      return Err(self.guard_error(&url, format!("unsupported content type '{}'", content_type)));
+    }
+    let too_large = |size: usize| {
+      self.guard_error(
+        &url,
+        format!(
+          "response of {} bytes exceeds max_bytes ({})",
+          size, self.max_bytes
+        ),
+      )
+    };
+    if let Some(declared) = header("content-length")
+      .and_then(|length| length.trim().parse::<usize>().ok())
+      .filter(|declared| *declared > self.max_bytes)
+    {
+      return Err(too_large(declared));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) =
+      response
+        .chunk()
+        .await
+        .map_err(|err| AgentFlowError::AsyncExecutionError {
+          message: format!(
+            "Web extract node '{}': failed to read {}: {}",
+            self.name, url, err
+          ),
+        })?
+    {
+      if body.len() + chunk.len() > self.max_bytes {
+        return Err(too_large(body.len() + chunk.len()));
+      }
+      body.extend_from_slice(&chunk);
+    }
+
+    let page = extract_page(&String::from_utf8_lossy(&body));
+    let word_count = page.text.split_whitespace().count();
+    println!(
+      "🧾 Web extract node '{}': {} words from {}",
+      self.name, word_count, url
+    );
+
+    let mut outputs = HashMap::new();
+    outputs.insert("url".to_string(), FlowValue::Json(json!(url)));
+    outputs.insert("title".to_string(), FlowValue::Json(json!(page.title)));
+    outputs.insert("text".to_string(), FlowValue::Json(json!(page.text)));
+    outputs.insert("excerpt".to_string(), FlowValue::Json(json!(page.excerpt)));
+    outputs.insert("word_count".to_string(), FlowValue::Json(json!(word_count)));
+    outputs.insert(
+      "metadata".to_string(),
+      FlowValue::Json(Value::Object(page.metadata)),
+    );
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use wiremock::matchers::{method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  const ARTICLE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <title>Scaling the scheduler | Example Blog</title>
+  <meta property="og:title" content="Scaling the scheduler">
+  <meta name="description" content="How we sharded the work queue.">
+  <meta name="author" content="Dana Lee">
+  <meta property="article:published_time" content="2026-04-01T12:00:00Z">
+  <meta property="og:site_name" content="Example Blog">
+  <link rel="canonical" href="https://blog.example.com/scheduler">
+  <script>window.analytics = true;</script>
+</head>
+<body>
+  <header><a href="/">Home</a> <a href="/about">About</a></header>
+  <nav class="menu"><ul><li><a href="/a">A long navigation link, with commas, that is not content</a></li></ul></nav>
+  <div id="page">
+    <article class="post">
+      <h1>Scaling the scheduler</h1>
+      <p>Last quarter the scheduler hit its limits, so we split the work queue into shards.</p>
+      <p>Each shard owns a slice of the key space, which keeps hot tenants isolated, predictable, and cheap.</p>
+      <ul><li>Fewer lock conflicts</li><li>Lower tail latency</li></ul>
+      <p>Rebalancing happens online, without pausing workers, and takes under a minute.</p>
+    </article>
+    <aside class="sidebar"><p>Subscribe to our newsletter for more posts like this one, every week.</p></aside>
+    <div class="comments"><p>Great post, thanks for sharing, very helpful indeed!</p></div>
+  </div>
+  <footer><p>Copyright 2026 Example Inc. All rights reserved, worldwide.</p></footer>
+</body>
+</html>"#;
+
+  fn loopback(node: WebExtractNode) -> WebExtractNode {
+    let policy = Arc::new(SandboxPolicy {
+      allow_loopback_network_access: true,
+      ..SandboxPolicy::default()
+    });
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    node.with_policy(policy).with_client(client)
+  }
+
+  async fn serve(body: &str, content_type: &str) -> (MockServer, AsyncNodeInputs) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/post"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(body.to_string(), content_type))
+      .mount(&server)
+      .await;
+    let mut inputs = AsyncNodeInputs::new();
+    inputs.insert(
+      "url".to_string(),
+      FlowValue::Json(json!(format!("{}/post", server.uri()))),
+    );
+    (server, inputs)
+  }
+
+  #[test]
+  fn extracts_the_article_and_metadata() {
+    let page = extract_page(ARTICLE);
+
+    assert_eq!(page.title.as_deref(), Some("Scaling the scheduler"));
+    assert_eq!(
+      page.text,
+      "Scaling the scheduler\n\n\
+       Last quarter the scheduler hit its limits, so we split the work queue into shards.\n\n\
+       Each shard owns a slice of the key space, which keeps hot tenants isolated, predictable, and cheap.\n\n\
+       Fewer lock conflicts\n\nLower tail latency\n\n\
+       Rebalancing happens online, without pausing workers, and takes under a minute."
+    );
+    assert_eq!(page.excerpt, "How we sharded the work queue.");
+    assert_eq!(
+      Value::Object(page.metadata),
+      json!({
+        "description": "How we sharded the work queue.",
+        "author": "Dana Lee",
+        "published": "2026-04-01T12:00:00Z",
+        "site_name": "Example Blog",
+        "canonical_url": "https://blog.example.com/scheduler",
+        "lang": "en",
+      })
+    );
+  }
+
+  #[test]
+  fn falls_back_to_body_text_without_paragraphs() {
+    let page = extract_page(
+      "<html><head><title>Status</title></head><body><h1>All systems normal</h1><script>x()</script></body></html>",
+    );
+    assert_eq!(page.title.as_deref(), Some("Status"));
+    assert_eq!(page.text, "All systems normal");
+  }
+
+  #[tokio::test]
+  async fn fetches_and_extracts_over_http() {
+    let (_server, inputs) = serve(ARTICLE, "text/html; charset=utf-8").await;
+    let outputs = loopback(WebExtractNode::new("read"))
+      .execute(&inputs)
+      .await
+      .unwrap();
+    assert_eq!(
+      outputs["title"],
+      FlowValue::Json(json!("Scaling the scheduler"))
+    );
+    assert_eq!(outputs["word_count"], FlowValue::Json(json!(52)));
+  }
+
+  #[tokio::test]
+  async fn rejects_non_html_and_oversized_responses() {
+    let (_server, inputs) = serve("%PDF-1.7", "application/pdf").await;
+    let err = loopback(WebExtractNode::new("read"))
+      .execute(&inputs)
+      .await
+      .unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("unsupported content type 'application/pdf'"),
+      "{err}"
+    );
+
+    let (_server, inputs) = serve(ARTICLE, "text/html").await;
+    let err = loopback(WebExtractNode::new("read").with_max_bytes(512))
+      .execute(&inputs)
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("exceeds max_bytes (512)"), "{err}");
+  }
+}
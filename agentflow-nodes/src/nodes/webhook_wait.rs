@@ -0,0 +1,350 @@
+//! Webhook-wait node - blocks until an external system calls back.
+//!
+//! On execution the node binds an ephemeral HTTP listener on `host:port`
+//! (port `0` picks a free one), reports the callback URL through a
+//! [`WorkflowEvent::NodeProgress`](agentflow_core::events::WorkflowEvent)
+//! event (`{"event": "webhook.listening", "url": ..}`) so the caller can
+//! hand it to the external system, then waits for the first request on
+//! `path` (any method). Each listener appends a random per-run token to
+//! `path` (`/webhook/<token>`), so only a caller that was handed the URL
+//! can resolve the wait; requests without it get a 404. The listener is
+//! shut down as soon as that request arrives, `timeout_ms` passes
+//! (`TimeoutExceeded`) or the flow is cancelled (`TaskCancelled`).
+//!
+//! Outputs `url`, `method`, `headers` (lower-cased names), `query`,
+//! `body` (text) and `json` when the body parses as JSON.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use agentflow_core::{
+  async_node::{AsyncNode, AsyncNodeInputs, AsyncNodeResult},
+  error::AgentFlowError,
+  node_context,
+  value::FlowValue,
+};
+use async_trait::async_trait;
+use axum::{
+  Json, Router,
+  body::Bytes,
+  extract::{Query, State},
+  http::{HeaderMap, Method},
+  routing::any,
+};
+use serde_json::{Map, Value, json};
+use tokio::sync::{mpsc, oneshot};
+
+pub const DEFAULT_WEBHOOK_PATH: &str = "/webhook";
+pub const DEFAULT_WEBHOOK_TIMEOUT_MS: u64 = 300_000;
+
+#[derive(Debug, Clone)]
+pub struct WebhookWaitNode {
+  pub name: String,
+  pub host: String,
+  pub port: u16,
+  pub path: String,
+  pub timeout: Duration,
+}
+
+/// One received callback, as handed from the handler to the node.
+struct Callback {
+  method: Method,
+  headers: HeaderMap,
+  query: HashMap<String, String>,
+  body: Bytes,
+}
+
+impl WebhookWaitNode {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      host: "127.0.0.1".to_string(),
+      port: 0,
+      path: DEFAULT_WEBHOOK_PATH.to_string(),
+      timeout: Duration::from_millis(DEFAULT_WEBHOOK_TIMEOUT_MS),
+    }
+  }
+
+  /// Interface to bind; the callback URL uses it verbatim.
+  pub fn with_host(mut self, host: &str) -> Self {
+    self.host = host.to_string();
+    self
+  }
+
+  pub fn with_port(mut self, port: u16) -> Self {
+    self.port = port;
+    self
+  }
+
+  /// Route to listen on; a leading `/` is added when missing.
+  pub fn with_path(mut self, path: &str) -> Self {
+    self.path = if path.starts_with('/') {
+      path.to_string()
+    } else {
+      format!("/{path}")
+    };
+    self
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  fn outputs(url: String, callback: Callback) -> HashMap<String, FlowValue> {
+    let mut headers = Map::new();
+    for (name, value) in &callback.headers {
+      headers
+        .entry(name.as_str().to_string())
+        .or_insert_with(|| json!(String::from_utf8_lossy(value.as_bytes())));
+    }
+    let body = String::from_utf8_lossy(&callback.body).into_owned();
+
+    let mut outputs = HashMap::new();
+    outputs.insert("url".to_string(), FlowValue::Json(json!(url)));
+    outputs.insert(
+      "method".to_string(),
+      FlowValue::Json(json!(callback.method.as_str())),
+    );
+    outputs.insert(
+      "headers".to_string(),
+      FlowValue::Json(Value::Object(headers)),
+    );
+    outputs.insert("query".to_string(), FlowValue::Json(json!(callback.query)));
+    if let Ok(parsed) = serde_json::from_str::<Value>(&body) {
+      outputs.insert("json".to_string(), FlowValue::Json(parsed));
+    }
+    outputs.insert("body".to_string(), FlowValue::Json(json!(body)));
+    outputs
+  }
+}
+
+async fn receive(
+  State(sender): State<mpsc::Sender<Callback>>,
+  method: Method,
+  headers: HeaderMap,
+  Query(query): Query<HashMap<String, String>>,
+  body: Bytes,
+) -> Json<Value> {
+  // Only the first callback is consumed; later ones still get a 200 so
+  // the caller doesn't retry against a listener that is shutting down.
+  let _ = sender.try_send(Callback {
+    method,
+    headers,
+    query,
+    body,
+  });
+  Json(json!({ "received": true }))
+}
+
+/// A bound callback listener, as returned by [`WebhookWaitNode::listen`].
+/// Dropping it shuts the listener down.
+pub struct PendingWebhook {
+  /// Callback URL, including the per-run token.
+  pub url: String,
+  pub token: String,
+  callbacks: mpsc::Receiver<Callback>,
+  shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl PendingWebhook {
+  /// Waits for the first callback and returns the node outputs for it.
+  pub async fn receive(mut self) -> Result<HashMap<String, FlowValue>, AgentFlowError> {
+    let callback =
+      self
+        .callbacks
+        .recv()
+        .await
+        .ok_or_else(|| AgentFlowError::AsyncExecutionError {
+          message: format!("Webhook listener on {} stopped unexpectedly", self.url),
+        })?;
+    Ok(WebhookWaitNode::outputs(self.url.clone(), callback))
+  }
+}
+
+impl Drop for PendingWebhook {
+  fn drop(&mut self) {
+    if let Some(shutdown) = self.shutdown.take() {
+      let _ = shutdown.send(());
+    }
+  }
+}
+
+impl WebhookWaitNode {
+  /// Binds the listener without waiting, for nodes that need the callback
+  /// URL before they block (e.g. `approval`).
+  pub async fn listen(&self) -> Result<PendingWebhook, AgentFlowError> {
+    let listener = tokio::net::TcpListener::bind((self.host.as_str(), self.port))
+      .await
+      .map_err(|e| AgentFlowError::AsyncExecutionError {
+        message: format!(
+          "Webhook node '{}': failed to bind {}:{}: {}",
+          self.name, self.host, self.port, e
+        ),
+      })?;
+    let address: SocketAddr =
+      listener
+        .local_addr()
+        .map_err(|e| AgentFlowError::AsyncExecutionError {
+          message: format!("Webhook node '{}': {}", self.name, e),
+        })?;
+    // 122 random bits; the route only exists under this token.
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let route = format!("{}/{}", self.path.trim_end_matches('/'), token);
+    let url = format!("http://{}:{}{}", self.host, address.port(), route);
+
+    let (sender, callbacks) = mpsc::channel(1);
+    let app = Router::new().route(&route, any(receive)).with_state(sender);
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+      axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+          let _ = shutdown_signal.await;
+        })
+        .await
+    });
+    Ok(PendingWebhook {
+      url,
+      token,
+      callbacks,
+      shutdown: Some(shutdown),
+    })
+  }
+}
+
+#[async_trait]
+impl AsyncNode for WebhookWaitNode {
+  async fn execute(&self, _inputs: &AsyncNodeInputs) -> AsyncNodeResult {
+    let pending = self.listen().await?;
+    let url = pending.url.clone();
+    println!("🪝 Webhook node '{}' listening on {}", self.name, url);
+    node_context::emit_progress(json!({ "event": "webhook.listening", "url": url }));
+
+    let outputs = tokio::select! {
+      outputs = pending.receive() => outputs,
+      _ = tokio::time::sleep(self.timeout) => Err(AgentFlowError::TimeoutExceeded {
+        duration_ms: self.timeout.as_millis() as u64,
+      }),
+      _ = node_context::cancelled() => Err(AgentFlowError::TaskCancelled),
+    }?;
+    if let Some(FlowValue::Json(Value::String(method))) = outputs.get("method") {
+      println!(
+        "📨 Webhook node '{}' received {} {}",
+        self.name, method, self.path
+      );
+    }
+    Ok(outputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use agentflow_core::events::{EventListener, WorkflowEvent};
+  use agentflow_core::node_context::NodeContext;
+  use agentflow_core::scheduler::FlowCancellationToken;
+  use std::sync::Arc;
+
+  /// Forwards the URL from the `webhook.listening` event.
+  struct UrlListener(mpsc::UnboundedSender<String>);
+
+  impl EventListener for UrlListener {
+    fn on_event(&self, event: &WorkflowEvent) {
+      if let WorkflowEvent::NodeProgress { detail, .. } = event
+        && let Some(url) = detail["url"].as_str()
+      {
+        let _ = self.0.send(url.to_string());
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn outputs_the_first_callback_posted_to_the_announced_url() {
+    let (urls, mut announced) = mpsc::unbounded_channel();
+    let poster = tokio::spawn(async move {
+      let url = announced.recv().await.unwrap();
+      reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .post(format!("{url}?job=7"))
+        .header("x-signature", "abc")
+        .json(&json!({ "status": "approved" }))
+        .send()
+        .await
+        .unwrap()
+        .status()
+    });
+
+    let node = WebhookWaitNode::new("wait")
+      .with_path("hooks/done")
+      .with_timeout(Duration::from_secs(10));
+    let context = NodeContext::new("run", "wait", Some(Arc::new(UrlListener(urls))), None);
+    let outputs = context
+      .scope(node.execute(&AsyncNodeInputs::new()))
+      .await
+      .unwrap();
+
+    assert_eq!(poster.await.unwrap(), 200);
+    assert_eq!(outputs["method"], FlowValue::Json(json!("POST")));
+    assert_eq!(outputs["query"], FlowValue::Json(json!({ "job": "7" })));
+    assert_eq!(
+      outputs["json"],
+      FlowValue::Json(json!({ "status": "approved" }))
+    );
+    let FlowValue::Json(headers) = &outputs["headers"] else {
+      panic!("headers should be JSON");
+    };
+    assert_eq!(headers["x-signature"], json!("abc"));
+    let FlowValue::Json(Value::String(url)) = &outputs["url"] else {
+      panic!("url should be a string");
+    };
+    assert!(url.contains("/hooks/done/"), "{url}");
+  }
+
+  #[tokio::test]
+  async fn ignores_callbacks_without_the_run_token() {
+    let pending = WebhookWaitNode::new("wait")
+      .with_path("hooks/done")
+      .listen()
+      .await
+      .unwrap();
+    let bare = pending
+      .url
+      .strip_suffix(&format!("/{}", pending.token))
+      .unwrap()
+      .to_string();
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+
+    let status = client.post(&bare).send().await.unwrap().status();
+    assert_eq!(status, 404);
+    let outcome = tokio::time::timeout(Duration::from_millis(100), pending.receive()).await;
+    assert!(
+      outcome.is_err(),
+      "a tokenless callback must not resolve the wait"
+    );
+  }
+
+  #[tokio::test]
+  async fn times_out_without_a_callback() {
+    let node = WebhookWaitNode::new("wait").with_timeout(Duration::from_millis(50));
+    let err = node.execute(&AsyncNodeInputs::new()).await.unwrap_err();
+    assert!(
+      matches!(err, AgentFlowError::TimeoutExceeded { duration_ms: 50 }),
+      "{err}"
+    );
+  }
+
+  #[tokio::test]
+  async fn stops_waiting_when_the_flow_is_cancelled() {
+    let token = FlowCancellationToken::new();
+    token.cancel();
+    let node = WebhookWaitNode::new("wait").with_timeout(Duration::from_secs(60));
+    let err = NodeContext::new("run", "wait", None, Some(token))
+      .scope(node.execute(&AsyncNodeInputs::new()))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, AgentFlowError::TaskCancelled), "{err}");
+  }
+}
@@ -22,17 +22,42 @@
 // and they pin the module-path contract — which is exactly the
 // audit invariant we care about.
 
-use agentflow_nodes::nodes::{arxiv::ArxivNode, markmap::MarkMapNode};
+use agentflow_nodes::nodes::{
+  approval::ApprovalNode,
+  arxiv::ArxivNode,
+  command::CommandNode,
+  delay::DelayNode,
+  document_render::DocumentRenderNode,
+  json_transform::JsonTransformNode,
+  markmap::MarkMapNode,
+  schedule_gate::ScheduleGateNode,
+  tabular::{TabularReadNode, TabularWriteNode},
+  text_extract::TextExtractNode,
+  throttle::ThrottleNode,
+  vector_search::VectorSearchNode,
+};
 
-/// The tool-tier specialized content nodes (`arxiv` / `markmap`) ship
-/// unconditionally under the default feature set. (The per-modality AI nodes —
-/// `asr` / `tts` / `text_to_image` / `image_*` — moved to `agentflow-nodes-ai`
-/// in the P-A nodes split; their analogous pin lives there.)
+/// The tool-tier specialized nodes (`arxiv` / `markmap` / `command` /
+/// `json_transform` / `vector_search` / `document_render` / `approval` / `delay` / `schedule_gate` / `throttle` / `tabular` / `text_extract`) ship unconditionally under the default feature set.
+/// (The per-modality AI nodes — `asr` / `tts` / `text_to_image` / `image_*` —
+/// moved to `agentflow-nodes-ai` in the P-A nodes split; their analogous pin
+/// lives there.)
 #[test]
 fn tool_tier_content_nodes_are_unconditional_under_default_features() {
   let sizes = [
     std::mem::size_of::<ArxivNode>(),
     std::mem::size_of::<MarkMapNode>(),
+    std::mem::size_of::<CommandNode>(),
+    std::mem::size_of::<JsonTransformNode>(),
+    std::mem::size_of::<VectorSearchNode>(),
+    std::mem::size_of::<DocumentRenderNode>(),
+    std::mem::size_of::<ApprovalNode>(),
+    std::mem::size_of::<DelayNode>(),
+    std::mem::size_of::<ScheduleGateNode>(),
+    std::mem::size_of::<ThrottleNode>(),
+    std::mem::size_of::<TabularReadNode>(),
+    std::mem::size_of::<TabularWriteNode>(),
+    std::mem::size_of::<TextExtractNode>(),
   ];
   assert!(
     sizes.iter().all(|s| *s > 0),
@@ -60,11 +85,16 @@ fn cargo_toml_feature_matrix_matches_audit_pinned_shape() {
   // [\"llm\", \"http\", \"file\", \"template\"]; mcp, rag,
   // batch, conditional are opt-in") in the same PR.
   for required in [
-    "http = [\"reqwest\"]",
+    "http = [\"reqwest\", \"axum\", \"feed-rs\"]",
     "file = []",
     "template = [\"handlebars\"]",
     "batch = []",
     "conditional = []",
+    "sql = [\"sqlx\", \"futures\"]",
+    "xlsx = [\"calamine\"]",
+    "s3 = [\"http\", \"hmac\", \"sha2\"]",
+    "git = [\"git2\", \"tempfile\"]",
+    "email = [\"lettre\"]",
     "default = [\"http\", \"file\", \"template\"]",
   ] {
     assert!(
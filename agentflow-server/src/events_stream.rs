@@ -566,6 +566,16 @@ fn workflow_event_payload(event: &agentflow_core::events::WorkflowEvent) -> serd
       "node_id": node_id,
       "output": output,
     }),
+    W::NodeProgress {
+      workflow_id,
+      node_id,
+      detail,
+      ..
+    } => serde_json::json!({
+      "workflow_id": workflow_id,
+      "node_id": node_id,
+      "detail": detail,
+    }),
     W::NodeFailed {
       workflow_id,
       node_id,
@@ -522,6 +522,8 @@ pub fn mock_node(
       "value".to_string(),
       serde_yaml::to_value(value).unwrap_or(serde_yaml::Value::Null),
     )]),
+    env: HashMap::new(),
+    cwd: None,
   }
 }
 
@@ -529,6 +531,7 @@ pub fn mock_flow(name: impl Into<String>, nodes: Vec<NodeDefinitionV2>) -> FlowD
   FlowDefinitionV2 {
     name: name.into(),
     inputs: HashMap::new(),
+    outputs: HashMap::new(),
     nodes,
   }
 }
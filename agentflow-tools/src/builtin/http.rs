@@ -1,7 +1,7 @@
-use std::{net::IpAddr, sync::Arc};
+use std::{net::IpAddr, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use reqwest::{Client, StatusCode, Url, header::LOCATION, redirect::Policy};
+use reqwest::{Client, Response, StatusCode, Url, header::LOCATION, redirect::Policy};
 use serde_json::{Value, json};
 
 use crate::{
@@ -21,6 +21,40 @@ const CLOUD_METADATA_IPS: &[IpAddr] = &[
   IpAddr::V4(std::net::Ipv4Addr::new(100, 100, 100, 200)),
 ];
 
+/// A fully specified request for [`HttpTool::send`].
+#[derive(Debug, Clone, Default)]
+pub struct HttpRequest {
+  /// HTTP method; case-insensitive, defaults to `GET` when empty.
+  pub method: String,
+  pub url: String,
+  pub headers: Vec<(String, String)>,
+  /// Sent only for methods that carry a body (POST / PUT / PATCH).
+  pub body: Option<String>,
+  /// Per-request timeout overriding the client's 30-second default.
+  pub timeout: Option<Duration>,
+}
+
+/// The final (post-redirect) response returned by [`HttpTool::send`].
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+  pub status: u16,
+  /// Response headers with lower-cased names, in wire order.
+  pub headers: Vec<(String, String)>,
+  /// Full, untruncated body text.
+  pub body: String,
+}
+
+impl HttpResponse {
+  /// First value of the named header (case-insensitive).
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+}
+
 /// Make HTTP GET / POST requests with domain sandbox enforcement.
 pub struct HttpTool {
   client: Client,
@@ -70,12 +104,6 @@ impl HttpTool {
     Self::new(Arc::new(SandboxPolicy::default()))
   }
 
-  fn extract_host(url: &str) -> Option<String> {
-    url::Url::parse(url)
-      .ok()
-      .and_then(|u| u.host_str().map(String::from))
-  }
-
   async fn validate_url_allowed(&self, url: &Url) -> Result<(), ToolError> {
     match url.scheme() {
       "http" | "https" => {}
@@ -122,85 +150,57 @@ impl HttpTool {
 
     Ok(())
   }
-}
-
-#[async_trait]
-impl Tool for HttpTool {
-  fn name(&self) -> &str {
-    "http"
-  }
 
-  fn description(&self) -> &str {
-    "Make HTTP GET or POST requests to fetch web content or call REST APIs. \
-        Returns the response body as text (truncated to 8 000 characters)."
-  }
-
-  fn parameters_schema(&self) -> Value {
-    json!({
-        "type": "object",
-        "properties": {
-            "url": {
-                "type": "string",
-                "description": "Full URL to request"
-            },
-            "method": {
-                "type": "string",
-                "enum": ["GET", "POST"],
-                "description": "HTTP method (default: GET)"
-            },
-            "body": {
-                "type": "string",
-                "description": "Request body string (for POST)"
-            },
-            "headers": {
-                "type": "object",
-                "description": "Optional key-value map of additional request headers"
-            }
-        },
-        "required": ["url"]
+  /// Send one request under the sandbox policy and return the structured
+  /// response. Every redirect hop is re-validated before it is followed, so
+  /// callers that need the status code and headers (e.g. `HttpNode`) get the
+  /// same SSRF defenses as the agent-facing [`Tool::execute`].
+  pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse, ToolError> {
+    let response = self.send_response(request).await?;
+    let status = response.status().as_u16();
+    let headers = response
+      .headers()
+      .iter()
+      .map(|(name, value)| {
+        (
+          name.as_str().to_string(),
+          String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        )
+      })
+      .collect();
+    let body = response.text().await.map_err(|e| ToolError::HttpError {
+      message: e.to_string(),
+    })?;
+    Ok(HttpResponse {
+      status,
+      headers,
+      body,
     })
   }
 
-  fn metadata(&self) -> ToolMetadata {
-    ToolMetadata::builtin_named(self.name())
-  }
-
-  fn idempotency(&self, params: &Value) -> ToolIdempotency {
-    match params["method"]
-      .as_str()
-      .unwrap_or("GET")
-      .to_uppercase()
-      .as_str()
-    {
-      // RFC 7231 idempotent / safe methods.
-      "GET" | "HEAD" | "PUT" | "DELETE" => ToolIdempotency::Idempotent,
-      "POST" | "PATCH" => ToolIdempotency::NonIdempotent,
-      _ => ToolIdempotency::Unknown,
-    }
-  }
-
-  async fn execute(&self, params: Value) -> Result<ToolOutput, ToolError> {
-    let url = params["url"]
-      .as_str()
-      .ok_or_else(|| ToolError::InvalidParams {
-        message: "Missing required parameter 'url'".to_string(),
-      })?;
-
-    let mut current_url = Url::parse(url).map_err(|error| ToolError::InvalidParams {
-      message: format!("Invalid URL '{}': {}", url, error),
+  /// Like [`HttpTool::send`], but returns the final response before its
+  /// body is read, so callers can check the headers and stream the body
+  /// under their own size cap.
+  pub async fn send_response(&self, request: HttpRequest) -> Result<Response, ToolError> {
+    let mut current_url = Url::parse(&request.url).map_err(|error| ToolError::InvalidParams {
+      message: format!("Invalid URL '{}': {}", request.url, error),
     })?;
+    if current_url.host_str().is_none() {
+      return Err(ToolError::InvalidParams {
+        message: format!("Cannot parse host from URL: {}", request.url),
+      });
+    }
 
-    let host = Self::extract_host(url).ok_or_else(|| ToolError::InvalidParams {
-      message: format!("Cannot parse host from URL: {}", url),
-    })?;
-    drop(host);
-
-    let method = params["method"].as_str().unwrap_or("GET");
+    let method = if request.method.is_empty() {
+      "GET".to_string()
+    } else {
+      request.method.to_uppercase()
+    };
 
     for redirect_count in 0..=MAX_REDIRECTS {
       self.validate_url_allowed(&current_url).await?;
 
-      let mut builder = match method.to_uppercase().as_str() {
+      let mut builder = match method.as_str() {
         "GET" => self.client.get(current_url.clone()),
         "POST" => self.client.post(current_url.clone()),
         "PUT" => self.client.put(current_url.clone()),
@@ -217,21 +217,19 @@ impl Tool for HttpTool {
         }
       };
 
-      // Attach custom headers
-      if let Some(headers) = params["headers"].as_object() {
-        for (k, v) in headers {
-          if let Some(v_str) = v.as_str() {
-            builder = builder.header(k.as_str(), v_str);
-          }
-        }
+      for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
       }
 
       // Attach body for methods that can carry one (POST/PUT/PATCH).
-      let method_upper = method.to_uppercase();
-      if matches!(method_upper.as_str(), "POST" | "PUT" | "PATCH")
-        && let Some(body) = params["body"].as_str()
+      if matches!(method.as_str(), "POST" | "PUT" | "PATCH")
+        && let Some(body) = &request.body
       {
-        builder = builder.body(body.to_string());
+        builder = builder.body(body.clone());
+      }
+
+      if let Some(timeout) = request.timeout {
+        builder = builder.timeout(timeout);
       }
 
       let response = builder.send().await.map_err(|e| ToolError::HttpError {
@@ -267,27 +265,7 @@ impl Tool for HttpTool {
         continue;
       }
 
-      let status = response.status();
-      let body = response.text().await.map_err(|e| ToolError::HttpError {
-        message: e.to_string(),
-      })?;
-
-      // Truncate very long responses
-      let content = if body.len() > self.max_response_chars {
-        format!(
-          "{}... [truncated — total {} chars]",
-          &body[..self.max_response_chars],
-          body.len()
-        )
-      } else {
-        body
-      };
-
-      return if status.is_success() {
-        Ok(ToolOutput::success(content))
-      } else {
-        Ok(ToolOutput::error(format!("HTTP {}: {}", status, content)))
-      };
+      return Ok(response);
     }
 
     Err(ToolError::HttpError {
@@ -296,6 +274,110 @@ impl Tool for HttpTool {
   }
 }
 
+#[async_trait]
+impl Tool for HttpTool {
+  fn name(&self) -> &str {
+    "http"
+  }
+
+  fn description(&self) -> &str {
+    "Make HTTP GET or POST requests to fetch web content or call REST APIs. \
+        Returns the response body as text (truncated to 8 000 characters)."
+  }
+
+  fn parameters_schema(&self) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "url": {
+                "type": "string",
+                "description": "Full URL to request"
+            },
+            "method": {
+                "type": "string",
+                "enum": ["GET", "POST"],
+                "description": "HTTP method (default: GET)"
+            },
+            "body": {
+                "type": "string",
+                "description": "Request body string (for POST)"
+            },
+            "headers": {
+                "type": "object",
+                "description": "Optional key-value map of additional request headers"
+            }
+        },
+        "required": ["url"]
+    })
+  }
+
+  fn metadata(&self) -> ToolMetadata {
+    ToolMetadata::builtin_named(self.name())
+  }
+
+  fn idempotency(&self, params: &Value) -> ToolIdempotency {
+    match params["method"]
+      .as_str()
+      .unwrap_or("GET")
+      .to_uppercase()
+      .as_str()
+    {
+      // RFC 7231 idempotent / safe methods.
+      "GET" | "HEAD" | "PUT" | "DELETE" => ToolIdempotency::Idempotent,
+      "POST" | "PATCH" => ToolIdempotency::NonIdempotent,
+      _ => ToolIdempotency::Unknown,
+    }
+  }
+
+  async fn execute(&self, params: Value) -> Result<ToolOutput, ToolError> {
+    let url = params["url"]
+      .as_str()
+      .ok_or_else(|| ToolError::InvalidParams {
+        message: "Missing required parameter 'url'".to_string(),
+      })?;
+
+    let headers = params["headers"]
+      .as_object()
+      .map(|headers| {
+        headers
+          .iter()
+          .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+          .collect()
+      })
+      .unwrap_or_default();
+
+    let response = self
+      .send(HttpRequest {
+        method: params["method"].as_str().unwrap_or("GET").to_string(),
+        url: url.to_string(),
+        headers,
+        body: params["body"].as_str().map(str::to_string),
+        timeout: None,
+      })
+      .await?;
+
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK);
+    let body = response.body;
+
+    // Truncate very long responses
+    let content = if body.len() > self.max_response_chars {
+      format!(
+        "{}... [truncated — total {} chars]",
+        &body[..self.max_response_chars],
+        body.len()
+      )
+    } else {
+      body
+    };
+
+    if status.is_success() {
+      Ok(ToolOutput::success(content))
+    } else {
+      Ok(ToolOutput::error(format!("HTTP {}: {}", status, content)))
+    }
+  }
+}
+
 fn is_redirect(status: StatusCode) -> bool {
   matches!(
     status,
@@ -4,6 +4,6 @@ pub mod script;
 pub mod shell;
 
 pub use file::FileTool;
-pub use http::HttpTool;
+pub use http::{HttpRequest, HttpResponse, HttpTool};
 pub use script::ScriptTool;
 pub use shell::ShellTool;